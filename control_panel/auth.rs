@@ -0,0 +1,162 @@
+use std::path::Path;
+
+use actix_web::dev::ServiceRequest;
+use actix_web::http::{header, Method};
+
+/// Environment variable with token required in `Authorization: Bearer <token>` header of API
+/// requests. Only reading requests are allowed if the variable isn't set
+pub(crate) static API_TOKEN_ENV: &str = "CONTROL_PANEL_API_TOKEN";
+
+pub(crate) fn api_token() -> Option<String> {
+    match std::env::var(API_TOKEN_ENV) {
+        Ok(token) if !token.is_empty() => Some(token),
+        _ => {
+            log::warn!("{API_TOKEN_ENV} isn't set, so only reading requests to ControlPanel API are allowed");
+            None
+        }
+    }
+}
+
+/// Paths of health probes which are allowed without token, because Kubernetes and load balancers
+/// usually don't add it
+static HEALTH_PROBE_PATHS: [&str; 2] = ["/health/live", "/health/ready"];
+
+/// Requests of webui files are allowed without token, because browser can't add it.
+/// Without configured token API is read only, so nobody can change state of engine
+pub(crate) fn is_authorized(req: &ServiceRequest, token: Option<&str>, webui_dir: &Path) -> bool {
+    let token = match token {
+        Some(token) => token,
+        None => return req.method() == Method::GET,
+    };
+
+    if req.method() == Method::GET
+        && (HEALTH_PROBE_PATHS.contains(&req.path()) || is_webui_file(req.path(), webui_dir))
+    {
+        return true;
+    }
+
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.strip_prefix("Bearer "))
+        .map(|x| constant_time_eq(x.as_bytes(), token.as_bytes()))
+        .unwrap_or(false)
+}
+
+fn is_webui_file(path: &str, webui_dir: &Path) -> bool {
+    let relative_path = path.trim_start_matches('/');
+    relative_path.is_empty()
+        || (!relative_path.contains("..") && webui_dir.join(relative_path).is_file())
+}
+
+/// Comparison time doesn't depend on position of the first mismatched byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use std::path::PathBuf;
+
+    const TOKEN: &str = "secret";
+
+    fn webui_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("webui")
+    }
+
+    fn is_request_authorized(request: TestRequest) -> bool {
+        is_authorized(&request.to_srv_request(), Some(TOKEN), &webui_dir())
+    }
+
+    fn api_request(authorization: &str) -> TestRequest {
+        TestRequest::get()
+            .uri("/orders")
+            .insert_header((header::AUTHORIZATION, authorization))
+    }
+
+    #[test]
+    fn request_with_token_is_authorized() {
+        assert!(is_request_authorized(api_request("Bearer secret")));
+    }
+
+    #[test]
+    fn request_without_token_is_rejected() {
+        assert!(!is_request_authorized(TestRequest::get().uri("/orders")));
+        assert!(!is_request_authorized(
+            TestRequest::post().uri("/orders/cancel_all")
+        ));
+    }
+
+    #[test]
+    fn request_with_wrong_token_is_rejected() {
+        assert!(!is_request_authorized(api_request("Bearer wrong")));
+        assert!(!is_request_authorized(api_request("Bearer secret2")));
+        assert!(!is_request_authorized(api_request("Bearer ")));
+    }
+
+    #[test]
+    fn request_with_wrong_scheme_is_rejected() {
+        assert!(!is_request_authorized(api_request("Basic secret")));
+        assert!(!is_request_authorized(api_request("bearer secret")));
+        assert!(!is_request_authorized(api_request("secret")));
+    }
+
+    #[test]
+    fn webui_files_and_health_probes_are_allowed_without_token() {
+        assert!(is_request_authorized(TestRequest::get().uri("/")));
+        assert!(is_request_authorized(TestRequest::get().uri("/index.html")));
+        assert!(is_request_authorized(
+            TestRequest::get().uri("/health/live")
+        ));
+
+        // only reading is allowed without token
+        assert!(!is_request_authorized(
+            TestRequest::post().uri("/index.html")
+        ));
+        assert!(!is_request_authorized(
+            TestRequest::get().uri("/missing.html")
+        ));
+    }
+
+    #[test]
+    fn files_outside_of_webui_dir_are_rejected() {
+        assert!(webui_dir().join("../Cargo.toml").is_file());
+
+        assert!(!is_request_authorized(
+            TestRequest::get().uri("/../Cargo.toml")
+        ));
+        assert!(!is_request_authorized(
+            TestRequest::get().uri("/webui/../../Cargo.toml")
+        ));
+    }
+
+    #[test]
+    fn api_is_read_only_if_token_is_not_set() {
+        std::env::remove_var(API_TOKEN_ENV);
+        assert_eq!(api_token(), None);
+
+        std::env::set_var(API_TOKEN_ENV, "");
+        assert_eq!(api_token(), None);
+
+        std::env::set_var(API_TOKEN_ENV, TOKEN);
+        assert_eq!(api_token().as_deref(), Some(TOKEN));
+        std::env::remove_var(API_TOKEN_ENV);
+
+        let is_authorized_without_token =
+            |request: TestRequest| is_authorized(&request.to_srv_request(), None, &webui_dir());
+        assert!(is_authorized_without_token(
+            TestRequest::get().uri("/orders")
+        ));
+        assert!(!is_authorized_without_token(
+            TestRequest::post().uri("/orders/cancel_all")
+        ));
+        assert!(!is_authorized_without_token(
+            TestRequest::post().uri("/kill-switch")
+        ));
+        assert!(!is_authorized_without_token(
+            TestRequest::post().uri("/strategy/test/pause")
+        ));
+    }
+}
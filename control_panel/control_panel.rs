@@ -1,14 +1,15 @@
 use actix_server::ServerHandle;
 use anyhow::Result;
-use futures::{executor, future::BoxFuture, FutureExt};
+use futures::{executor, future, future::BoxFuture, FutureExt};
 use jsonrpc_core_client::{transports::ipc, RpcError};
 use mmb_rpc::rest_api::{MmbRpcClient, IPC_ADDRESS};
 use mmb_utils::logger::print_info;
 use parking_lot::Mutex;
 use std::{sync::mpsc, sync::Arc, time::Duration};
 
-use super::endpoints;
-use actix_web::{dev::Server, App, HttpResponse, HttpServer};
+use super::{auth, endpoints};
+use actix_web::dev::{Server, Service, ServiceResponse};
+use actix_web::{App, HttpResponse, HttpServer};
 use tokio::sync::oneshot;
 
 use actix_web::web::Data;
@@ -66,16 +67,59 @@ impl ControlPanel {
         *self.server_stopper_tx.lock() = Some(server_stopper_tx);
 
         let client = self.client.clone();
+        let api_token = auth::api_token();
 
         let server = HttpServer::new(move || {
             let mut webui_dir = std::env::current_dir().expect("Unable get current directory");
             webui_dir.push(r"webui");
 
+            let api_token = api_token.clone();
+            let auth_webui_dir = webui_dir.clone();
+
             App::new()
+                .wrap_fn(move |req, srv| {
+                    if auth::is_authorized(&req, api_token.as_deref(), &auth_webui_dir) {
+                        srv.call(req)
+                            .map(|res| res.map(ServiceResponse::map_into_left_body))
+                            .left_future()
+                    } else {
+                        let message = match api_token {
+                            Some(_) => "Invalid API token",
+                            None => "API token isn't configured, only reading requests are allowed",
+                        };
+                        let response = HttpResponse::Unauthorized().body(message);
+                        future::ok(req.into_response(response).map_into_right_body()).right_future()
+                    }
+                })
                 .app_data(Data::new(client.clone()))
                 .service(endpoints::health)
+                .service(endpoints::health_live)
+                .service(endpoints::health_ready)
                 .service(endpoints::stop)
                 .service(endpoints::stats)
+                .service(endpoints::open_orders)
+                .service(endpoints::orders)
+                .service(endpoints::cancel_order)
+                .service(endpoints::portfolio)
+                .service(endpoints::positions)
+                .service(endpoints::unmatched_events)
+                .service(endpoints::cold_start)
+                .service(endpoints::confirm_cold_start)
+                .service(endpoints::loss_limit)
+                .service(endpoints::acknowledge_loss_limit)
+                .service(endpoints::rebalancing)
+                .service(endpoints::approve_rebalancing)
+                .service(endpoints::reject_rebalancing)
+                .service(endpoints::strategies)
+                .service(endpoints::start_strategy)
+                .service(endpoints::stop_strategy)
+                .service(endpoints::pause_strategy)
+                .service(endpoints::resume_strategy)
+                .service(endpoints::signals)
+                .service(endpoints::push_signals)
+                .service(endpoints::kill_switch)
+                .service(endpoints::kill_switch_with_flatten)
+                .service(endpoints::kill_switch_hyphenated)
                 .service(endpoints::get_config)
                 .service(endpoints::set_config)
                 .service(
@@ -149,6 +193,15 @@ fn handle_rpc_error(error: RpcError) -> HttpResponse {
 pub async fn send_request(
     client: DataWebMmbRpcClient,
     action: impl Fn(&MmbRpcClient) -> BoxFuture<Result<String, RpcError>>,
+) -> HttpResponse {
+    send_request_with_response(client, action, |response| HttpResponse::Ok().body(response)).await
+}
+
+/// The same as [`send_request`], but HTTP response is built from engine response by `to_http_response`
+pub async fn send_request_with_response(
+    client: DataWebMmbRpcClient,
+    action: impl Fn(&MmbRpcClient) -> BoxFuture<Result<String, RpcError>>,
+    to_http_response: impl Fn(String) -> HttpResponse,
 ) -> HttpResponse {
     let mut try_counter = 1;
 
@@ -165,7 +218,7 @@ pub async fn send_request(
 
         if let Some(client) = &*client.lock().await {
             match (action)(client).await {
-                Ok(response) => return HttpResponse::Ok().body(response),
+                Ok(response) => return to_http_response(response),
                 Err(err) => {
                     if try_counter > 2 {
                         return handle_rpc_error(err);
@@ -1,7 +1,7 @@
 use actix_web::{get, post, web, HttpResponse, Responder};
 use futures::FutureExt;
 
-use crate::control_panel::{send_request, DataWebMmbRpcClient};
+use crate::control_panel::{send_request, send_request_with_response, DataWebMmbRpcClient};
 
 // New endpoints have to be added as a service for actix server and webui control page. Look at super::control_panel::start() and webui/README.md
 
@@ -10,6 +10,40 @@ pub(super) async fn health(client: DataWebMmbRpcClient) -> impl Responder {
     send_request(client, |client| client.health().boxed()).await
 }
 
+#[get("/health/live")]
+pub(super) async fn health_live(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request_with_response(
+        client,
+        |client| client.health_live().boxed(),
+        health_report_response,
+    )
+    .await
+}
+
+#[get("/health/ready")]
+pub(super) async fn health_ready(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request_with_response(
+        client,
+        |client| client.health_ready().boxed(),
+        health_report_response,
+    )
+    .await
+}
+
+/// Unhealthy report is returned with status 503, so Kubernetes probes and load balancers can
+/// check only status code
+fn health_report_response(report: String) -> HttpResponse {
+    let is_healthy = serde_json::from_str::<serde_json::Value>(&report)
+        .ok()
+        .and_then(|x| x["is_healthy"].as_bool())
+        .unwrap_or(false);
+    let mut response = match is_healthy {
+        true => HttpResponse::Ok(),
+        false => HttpResponse::ServiceUnavailable(),
+    };
+    response.content_type("application/json").body(report)
+}
+
 #[post("/stop")]
 pub(super) async fn stop(client: DataWebMmbRpcClient) -> impl Responder {
     send_request(client, |client| client.stop().boxed()).await
@@ -41,3 +75,179 @@ pub(super) async fn set_config(body: web::Bytes, client: DataWebMmbRpcClient) ->
 pub(super) async fn stats(client: DataWebMmbRpcClient) -> impl Responder {
     send_request(client, |client| client.stats().boxed()).await
 }
+
+#[get("/open_orders")]
+pub(super) async fn open_orders(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.open_orders().boxed()).await
+}
+
+#[get("/orders")]
+pub(super) async fn orders(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.open_orders().boxed()).await
+}
+
+#[post("/orders/{client_order_id}/cancel")]
+pub(super) async fn cancel_order(
+    client_order_id: web::Path<String>,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let client_order_id = client_order_id.into_inner();
+    send_request(client, move |client| {
+        client.cancel_order(client_order_id.clone()).boxed()
+    })
+    .await
+}
+
+#[get("/portfolio")]
+pub(super) async fn portfolio(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.portfolio().boxed()).await
+}
+
+#[get("/positions")]
+pub(super) async fn positions(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.positions().boxed()).await
+}
+
+#[get("/unmatched_events")]
+pub(super) async fn unmatched_events(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.unmatched_events().boxed()).await
+}
+
+#[get("/cold_start")]
+pub(super) async fn cold_start(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.cold_start().boxed()).await
+}
+
+#[post("/cold_start/confirm")]
+pub(super) async fn confirm_cold_start(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.confirm_cold_start().boxed()).await
+}
+
+#[get("/loss_limit")]
+pub(super) async fn loss_limit(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.loss_limit().boxed()).await
+}
+
+#[post("/loss_limit/acknowledge")]
+pub(super) async fn acknowledge_loss_limit(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.acknowledge_loss_limit().boxed()).await
+}
+
+#[get("/rebalancing")]
+pub(super) async fn rebalancing(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.rebalancing().boxed()).await
+}
+
+#[post("/rebalancing/{decision_id}/approve")]
+pub(super) async fn approve_rebalancing(
+    decision_id: web::Path<u64>,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let decision_id = decision_id.into_inner();
+    send_request(client, move |client| {
+        client.approve_rebalancing(decision_id).boxed()
+    })
+    .await
+}
+
+#[post("/rebalancing/{decision_id}/reject")]
+pub(super) async fn reject_rebalancing(
+    decision_id: web::Path<u64>,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let decision_id = decision_id.into_inner();
+    send_request(client, move |client| {
+        client.reject_rebalancing(decision_id).boxed()
+    })
+    .await
+}
+
+#[get("/strategies")]
+pub(super) async fn strategies(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.strategies().boxed()).await
+}
+
+#[post("/strategies/{name}/start")]
+pub(super) async fn start_strategy(
+    name: web::Path<String>,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let name = name.into_inner();
+    send_request(client, move |client| {
+        client.start_strategy(name.clone()).boxed()
+    })
+    .await
+}
+
+#[post("/strategies/{name}/stop")]
+pub(super) async fn stop_strategy(
+    name: web::Path<String>,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let name = name.into_inner();
+    send_request(client, move |client| {
+        client.stop_strategy(name.clone()).boxed()
+    })
+    .await
+}
+
+#[post("/strategy/{name}/pause")]
+pub(super) async fn pause_strategy(
+    name: web::Path<String>,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let name = name.into_inner();
+    send_request(client, move |client| {
+        client.pause_strategy(name.clone()).boxed()
+    })
+    .await
+}
+
+#[post("/strategy/{name}/resume")]
+pub(super) async fn resume_strategy(
+    name: web::Path<String>,
+    client: DataWebMmbRpcClient,
+) -> impl Responder {
+    let name = name.into_inner();
+    send_request(client, move |client| {
+        client.resume_strategy(name.clone()).boxed()
+    })
+    .await
+}
+
+#[get("/signals")]
+pub(super) async fn signals(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.signals().boxed()).await
+}
+
+#[post("/signals")]
+pub(super) async fn push_signals(body: web::Bytes, client: DataWebMmbRpcClient) -> impl Responder {
+    let messages = match String::from_utf8(body.to_vec()) {
+        Ok(messages) => messages,
+        Err(err) => {
+            return HttpResponse::BadRequest().body(format!(
+                "Failed to convert input signals({body:?}) to utf8 string: {err}",
+            ))
+        }
+    };
+
+    send_request(client, move |client| {
+        client.push_signals(messages.clone()).boxed()
+    })
+    .await
+}
+
+#[post("/kill_switch")]
+pub(super) async fn kill_switch(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.kill_switch(false).boxed()).await
+}
+
+#[post("/kill_switch/flatten")]
+pub(super) async fn kill_switch_with_flatten(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.kill_switch(true).boxed()).await
+}
+
+#[post("/kill-switch")]
+pub(super) async fn kill_switch_hyphenated(client: DataWebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.kill_switch(false).boxed()).await
+}
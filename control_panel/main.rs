@@ -27,6 +27,7 @@ use mmb_utils::{
 };
 use tokio::signal;
 
+mod auth;
 mod control_panel;
 mod endpoints;
 
@@ -0,0 +1,118 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use chrono::Utc;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_domain::events::ExchangeEvent;
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::snapshot::{Price, SortedOrderData};
+use mmb_domain::order_book::event::{EventType, OrderBookEvent};
+use mmb_domain::order_book::order_book_data::OrderBookData;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tokio::sync::broadcast;
+
+const BOOK_DEPTH: u32 = 100;
+const UPDATES_COUNT: u32 = 1_000;
+
+fn order_book_event(
+    event_type: EventType,
+    asks: SortedOrderData,
+    bids: SortedOrderData,
+) -> OrderBookEvent {
+    OrderBookEvent::new(
+        Utc::now(),
+        ExchangeAccountId::new("Binance", 0),
+        CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        String::new(),
+        event_type,
+        Arc::new(OrderBookData::new(asks, bids)),
+    )
+}
+
+fn ask_price(level: u32) -> Price {
+    dec!(20000) + Decimal::from(level) * dec!(0.5)
+}
+
+fn bid_price(level: u32) -> Price {
+    dec!(19999.5) - Decimal::from(level) * dec!(0.5)
+}
+
+fn snapshot_event() -> OrderBookEvent {
+    order_book_event(
+        EventType::Snapshot,
+        (0..BOOK_DEPTH).map(|x| (ask_price(x), dec!(1))).collect(),
+        (0..BOOK_DEPTH).map(|x| (bid_price(x), dec!(1))).collect(),
+    )
+}
+
+/// Deltas changing, removing and restoring levels on both sides without crossing the book
+fn update_events() -> Vec<OrderBookEvent> {
+    (0..UPDATES_COUNT)
+        .map(|i| {
+            let level = i * 7 % BOOK_DEPTH;
+            let amount = match i % 3 {
+                0 => dec!(0),
+                1 => dec!(2.5),
+                _ => dec!(1),
+            };
+            order_book_event(
+                EventType::Update,
+                BTreeMap::from([(ask_price(level), amount)]),
+                BTreeMap::from([(bid_price(BOOK_DEPTH - 1 - level), amount)]),
+            )
+        })
+        .collect()
+}
+
+/// Every event is received by all subscribers of exchange events
+fn broadcast_fan_out(c: &mut Criterion) {
+    let mut group = c.benchmark_group("exchange_events_fan_out");
+    let event = ExchangeEvent::OrderBookEvent(update_events().swap_remove(0));
+    for receivers_count in [1, 4, 16] {
+        let (sender, _) = broadcast::channel(20_000);
+        let mut receivers = (0..receivers_count)
+            .map(|_| sender.subscribe())
+            .collect::<Vec<_>>();
+
+        group.throughput(Throughput::Elements(receivers_count as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(receivers_count),
+            &receivers_count,
+            |b, _| {
+                b.iter(|| {
+                    let _ = sender.send(event.clone());
+                    for receiver in &mut receivers {
+                        let _ = black_box(receiver.try_recv());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn book_delta_application(c: &mut Criterion) {
+    let mut group = c.benchmark_group("order_book");
+    let updates = update_events();
+    let mut local_snapshots_service = LocalSnapshotsService::new(HashMap::new());
+    let _ = local_snapshots_service.update(&snapshot_event());
+
+    group.throughput(Throughput::Elements(updates.len() as u64));
+    group.bench_function("apply_deltas", |b| {
+        b.iter(|| {
+            for update in &updates {
+                let _ = black_box(local_snapshots_service.update(update));
+            }
+        })
+    });
+    group.bench_function("apply_snapshot", |b| {
+        let snapshot = snapshot_event();
+        b.iter(|| black_box(local_snapshots_service.update(&snapshot)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, broadcast_fan_out, book_delta_application);
+criterion_main!(benches);
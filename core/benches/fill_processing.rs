@@ -0,0 +1,430 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::any::Any;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration as StdDuration, Instant};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use criterion::{criterion_group, BatchSize, Criterion};
+use dashmap::DashMap;
+use mmb_core::connectivity::WebSocketRole;
+use mmb_core::database::events::recorder::EventRecorder;
+use mmb_core::exchanges::exchange_blocker::ExchangeBlocker;
+use mmb_core::exchanges::general::exchange::{Exchange, RequestResult};
+use mmb_core::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    WebSocketOptions,
+};
+use mmb_core::exchanges::general::handlers::handle_order_filled::{FillAmount, FillEvent};
+use mmb_core::exchanges::general::order::cancel::CancelOrderResult;
+use mmb_core::exchanges::general::order::create::CreateOrderResult;
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::{
+    RequestTimeoutArguments, RequestsTimeoutManagerFactory,
+};
+use mmb_core::exchanges::timeouts::timeout_manager::TimeoutManager;
+use mmb_core::exchanges::traits::{
+    ExchangeClient, ExchangeError, HandleMetricsCb, HandleOrderFilledCb, HandleTradeCb,
+    OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb, Support,
+};
+use mmb_core::infrastructure::init_lifetime_manager;
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{
+    AllowedEventSourceType, EventSourceType, ExchangeBalancesAndPositions, ExchangeEvent,
+};
+use mmb_domain::exchanges::commission::{Commission, CommissionForType};
+use mmb_domain::exchanges::symbol::{BeforeAfter, Precision, Symbol};
+use mmb_domain::market::{
+    CurrencyCode, CurrencyId, CurrencyPair, ExchangeAccountId, SpecificCurrencyPair,
+};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order::pool::{OrderRef, OrdersPool};
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, ExchangeOrderId, OrderInfo, OrderOptions, OrderRole, OrderSide,
+    OrderSnapshot, Price,
+};
+use mmb_domain::position::{ActivePosition, ClosedPosition};
+use mmb_utils::{hashmap, DateTime};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::json;
+use tokio::runtime::Runtime;
+use tokio::sync::broadcast;
+use url::Url;
+
+/// Client without exchange connection, fills are injected directly into `Exchange`
+struct BenchClient;
+
+#[async_trait]
+impl ExchangeClient for BenchClient {
+    async fn create_order(&self, _order: &OrderRef) -> CreateOrderResult {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    async fn cancel_order(
+        &self,
+        _order: &OrderRef,
+        _exchange_order_id: &ExchangeOrderId,
+    ) -> CancelOrderResult {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    async fn cancel_all_orders(&self, _currency_pair: CurrencyPair) -> Result<()> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    async fn get_open_orders_by_currency_pair(
+        &self,
+        _currency_pair: CurrencyPair,
+    ) -> Result<Vec<OrderInfo>> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    async fn get_order_info(&self, _order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    async fn close_position(
+        &self,
+        _position: &ActivePosition,
+        _price: Option<Price>,
+    ) -> Result<ClosedPosition> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    async fn get_active_positions(&self) -> Result<Vec<ActivePosition>> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    async fn get_balance_and_positions(&self) -> Result<ExchangeBalancesAndPositions> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    async fn get_my_trades(
+        &self,
+        _symbol: &Symbol,
+        _last_date_time: Option<DateTime>,
+    ) -> RequestResult<Vec<OrderTrade>> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    async fn get_server_time(&self) -> Option<Result<i64>> {
+        unimplemented!("isn't used in benchmarks")
+    }
+}
+
+#[async_trait]
+impl Support for BenchClient {
+    fn as_any(&self) -> &(dyn Any + Sync + Send + 'static) {
+        self
+    }
+
+    fn on_websocket_message(&self, _msg: &str) -> Result<()> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    fn on_connected(&self) -> Result<()> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    fn on_disconnected(&self) -> Result<()> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    fn set_send_websocket_message_callback(&mut self, _callback: SendWebsocketMessageCb) {}
+
+    fn set_order_created_callback(&mut self, _callback: OrderCreatedCb) {}
+
+    fn set_order_cancelled_callback(&mut self, _callback: OrderCancelledCb) {}
+
+    fn set_handle_order_filled_callback(&mut self, _callback: HandleOrderFilledCb) {}
+
+    fn set_handle_trade_callback(&mut self, _callback: HandleTradeCb) {}
+
+    fn set_handle_metrics_callback(&mut self, _callback: HandleMetricsCb) {}
+
+    fn set_traded_specific_currencies(&self, _currencies: Vec<SpecificCurrencyPair>) {}
+
+    fn is_websocket_enabled(&self, _role: WebSocketRole) -> bool {
+        false
+    }
+
+    async fn create_ws_url(&self, _role: WebSocketRole) -> Result<Url> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    fn get_specific_currency_pair(&self, _currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        unimplemented!("isn't used in benchmarks")
+    }
+
+    fn should_log_message(&self, _message: &str) -> bool {
+        false
+    }
+
+    fn get_balance_reservation_currency_code(
+        &self,
+        symbol: Arc<Symbol>,
+        side: OrderSide,
+    ) -> CurrencyCode {
+        symbol.get_trade_code(side, BeforeAfter::Before)
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        unimplemented!("isn't used in benchmarks")
+    }
+}
+
+fn currency_pair() -> CurrencyPair {
+    CurrencyPair::from_codes("btc".into(), "usdt".into())
+}
+
+fn create_exchange(runtime: &Runtime) -> (Arc<Exchange>, broadcast::Receiver<ExchangeEvent>) {
+    let lifetime_manager = init_lifetime_manager();
+    let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+    let (events_sender, events_receiver) = broadcast::channel(1_000);
+
+    let request_timeout_manager = RequestsTimeoutManagerFactory::from_requests_per_period(
+        RequestTimeoutArguments::from_requests_per_minute(1200),
+        exchange_account_id,
+    );
+    let event_recorder = runtime
+        .block_on(EventRecorder::start(None, None, Default::default()))
+        .expect("Failed to start EventRecorder");
+    let exchange_blocker = ExchangeBlocker::new(vec![exchange_account_id]);
+
+    let exchange = Exchange::new(
+        exchange_account_id,
+        Box::new(BenchClient),
+        OrdersPool::new(),
+        ExchangeFeatures::new(
+            OpenOrdersType::AllCurrencyPair,
+            RestFillsFeatures::default(),
+            OrderFeatures::default(),
+            OrderTradeOption::default(),
+            WebSocketOptions::default(),
+            false,
+            AllowedEventSourceType::default(),
+            AllowedEventSourceType::default(),
+            AllowedEventSourceType::default(),
+        ),
+        RequestTimeoutArguments::from_requests_per_minute(1200),
+        events_sender,
+        lifetime_manager,
+        TimeoutManager::new(hashmap![exchange_account_id => request_timeout_manager]),
+        Arc::downgrade(&exchange_blocker),
+        Commission::new(
+            CommissionForType::new(dec!(0.1), dec!(40)),
+            CommissionForType::new(dec!(0.2), dec!(40)),
+        ),
+        event_recorder,
+    );
+
+    let symbol = Arc::new(Symbol::new(
+        false,
+        "btc".into(),
+        "btc".into(),
+        "usdt".into(),
+        "usdt".into(),
+        None,
+        None,
+        None,
+        None,
+        None,
+        "btc".into(),
+        None,
+        Precision::ByTick { tick: dec!(0.01) },
+        Precision::ByTick { tick: dec!(0.0001) },
+    ));
+    exchange
+        .leverage_by_currency_pair
+        .insert(symbol.currency_pair(), dec!(1));
+    exchange.currencies.lock().push(symbol.base_currency_code());
+    exchange
+        .currencies
+        .lock()
+        .push(symbol.quote_currency_code());
+    exchange.symbols.insert(symbol.currency_pair(), symbol);
+
+    (exchange, events_receiver)
+}
+
+/// Adds created order with amount 1 to exchange
+fn add_order(exchange: &Exchange, order_number: u64) -> ExchangeOrderId {
+    let exchange_order_id = ExchangeOrderId::new(order_number.to_string().into());
+    let mut order = OrderSnapshot::with_params(
+        ClientOrderId::unique_id(),
+        OrderOptions::limit(dec!(20000)),
+        Some(OrderRole::Maker),
+        exchange.exchange_account_id,
+        currency_pair(),
+        dec!(1),
+        OrderSide::Buy,
+        None,
+        "Benchmark",
+    );
+    order.props.exchange_order_id = Some(exchange_order_id.clone());
+    let order_ref = exchange.orders.add_snapshot_initial(&order);
+    let _ = exchange
+        .orders
+        .cache_by_exchange_id
+        .insert(exchange_order_id.clone(), order_ref);
+
+    exchange_order_id
+}
+
+fn fill_event(exchange_order_id: ExchangeOrderId, trade_number: u64, amount: Amount) -> FillEvent {
+    FillEvent {
+        source_type: EventSourceType::WebSocket,
+        trade_id: Some(json!(trade_number).into()),
+        client_order_id: None,
+        exchange_order_id,
+        fill_price: dec!(20000),
+        fill_amount: FillAmount::Incremental {
+            fill_amount: amount,
+            total_filled_amount: None,
+        },
+        order_role: Some(OrderRole::Maker),
+        commission_currency_code: None,
+        commission_rate: None,
+        commission_amount: None,
+        fill_type: OrderFillType::UserTrade,
+        special_order_data: None,
+        fill_date: None,
+    }
+}
+
+fn fill_processing(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to create tokio runtime");
+    let _runtime_guard = runtime.enter();
+    let (exchange, mut events_receiver) = create_exchange(&runtime);
+
+    let mut order_number = 0;
+    c.bench_function("handle_order_filled", |b| {
+        b.iter_batched(
+            || {
+                order_number += 1;
+                // fill events are drained, so broadcast channel doesn't lag
+                while events_receiver.try_recv().is_ok() {}
+                fill_event(add_order(&exchange, order_number), order_number, dec!(1))
+            },
+            |mut fill_event| exchange.handle_order_filled(&mut fill_event),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+/// Counts heap allocations of the process, so allocations of fill handling can be measured
+struct CountingAllocator;
+
+static ALLOCATIONS_COUNT: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS_COUNT.fetch_add(1, Ordering::Relaxed);
+        let _ = ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS_COUNT.fetch_add(1, Ordering::Relaxed);
+        let _ = ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const LOAD_MESSAGES_PER_SECOND: u64 = 50_000;
+const FILLS_PER_ORDER: u64 = 5;
+
+/// Feeds fills of 5 partial fills per order at 50k messages per second for 1 second and prints
+/// heap allocations of their handling with and without object pools
+fn simulated_load() {
+    let runtime = Runtime::new().expect("Failed to create tokio runtime");
+    let _runtime_guard = runtime.enter();
+
+    let mut allocations_per_message = Vec::new();
+    for is_pooled in [false, true] {
+        let (exchange, mut events_receiver) = create_exchange(&runtime);
+        if !is_pooled {
+            exchange.order_snapshots_pool.set_capacity(0);
+            exchange.order_fills_pool.set_capacity(0);
+        }
+
+        let fill_amount = dec!(1) / Decimal::from(FILLS_PER_ORDER);
+        let fill_events = (0..LOAD_MESSAGES_PER_SECOND / FILLS_PER_ORDER)
+            .flat_map(|order_number| {
+                let exchange_order_id = add_order(&exchange, order_number);
+                (0..FILLS_PER_ORDER).map(move |fill_number| {
+                    let trade_number = order_number * FILLS_PER_ORDER + fill_number;
+                    fill_event(exchange_order_id.clone(), trade_number, fill_amount)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let interval = StdDuration::from_secs(1) / LOAD_MESSAGES_PER_SECOND as u32;
+        let allocations_before = ALLOCATIONS_COUNT.load(Ordering::Relaxed);
+        let bytes_before = ALLOCATED_BYTES.load(Ordering::Relaxed);
+        let started = Instant::now();
+        for (message_number, mut fill_event) in fill_events.into_iter().enumerate() {
+            let scheduled = started + interval * message_number as u32;
+            if let Some(ahead) = scheduled.checked_duration_since(Instant::now()) {
+                std::thread::sleep(ahead);
+            }
+
+            exchange.handle_order_filled(&mut fill_event);
+            // consumer handles events as soon as they are received
+            while events_receiver.try_recv().is_ok() {}
+        }
+        let elapsed = started.elapsed();
+        let allocations = ALLOCATIONS_COUNT.load(Ordering::Relaxed) - allocations_before;
+        let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes_before;
+
+        let messages_count = LOAD_MESSAGES_PER_SECOND as f64;
+        let per_message = allocations as f64 / messages_count;
+        allocations_per_message.push(per_message);
+        println!(
+            "simulated_load pooled={is_pooled}: {:.0} msg/s, {per_message:.2} allocations/msg, {:.0} bytes/msg, snapshots {:?}, fill buffers {:?}",
+            messages_count / elapsed.as_secs_f64(),
+            bytes as f64 / messages_count,
+            exchange.order_snapshots_pool.stats(),
+            exchange.order_fills_pool.stats(),
+        );
+    }
+
+    println!(
+        "simulated_load: allocations reduced by {:.1}%",
+        (1. - allocations_per_message[1] / allocations_per_message[0]) * 100.
+    );
+}
+
+criterion_group!(benches, fill_processing);
+
+fn main() {
+    simulated_load();
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
@@ -0,0 +1,86 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use mmb_core::connectivity::json::parse_frame;
+use serde::Deserialize;
+use serde_json::Value;
+
+const LEVELS_COUNT: usize = 20;
+
+#[derive(Deserialize)]
+struct DepthFrame<'a> {
+    #[serde(borrow)]
+    stream: &'a str,
+    #[serde(borrow)]
+    data: DepthData<'a>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DepthData<'a> {
+    last_update_id: u64,
+    #[serde(borrow)]
+    bids: Vec<(&'a str, &'a str)>,
+    #[serde(borrow)]
+    asks: Vec<(&'a str, &'a str)>,
+}
+
+/// Partial order book frame of Binance combined stream
+fn depth_frame() -> String {
+    let levels = |start: f64, step: f64| {
+        (0..LEVELS_COUNT)
+            .map(|i| {
+                format!(
+                    r#"["{:.2}","{:.8}"]"#,
+                    start + step * i as f64,
+                    0.1 + i as f64
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    format!(
+        r#"{{"stream":"btcusdt@depth20@100ms","data":{{"lastUpdateId":160,"bids":[{}],"asks":[{}]}}}}"#,
+        levels(20000.0, -0.01),
+        levels(20000.01, 0.01)
+    )
+}
+
+/// Current way of Binance connector with dynamic value against borrowed deserialization. Backend
+/// of `parse_frame` is `simd-json` if feature `simd-json` is enabled, e.g.
+/// `cargo bench -p mmb_core --features simd-json --bench json_parsing`
+fn depth(c: &mut Criterion) {
+    let frame = depth_frame();
+
+    let mut group = c.benchmark_group("binance_depth_frame");
+    group.throughput(Throughput::Bytes(frame.len() as u64));
+    group.bench_function("serde_json_value", |b| {
+        b.iter(|| {
+            let value: Value = serde_json::from_str(black_box(&frame)).expect("in bench");
+            black_box(value["data"]["bids"][0][0].as_str().map(str::len))
+        })
+    });
+    group.bench_function("serde_json_borrowed", |b| {
+        b.iter(|| {
+            let depth: DepthFrame = serde_json::from_str(black_box(&frame)).expect("in bench");
+            black_box((
+                depth.stream.len(),
+                depth.data.last_update_id,
+                depth.data.bids[0].0.len(),
+            ))
+        })
+    });
+    let mut buffer = Vec::new();
+    group.bench_function("parse_frame", |b| {
+        b.iter(|| {
+            let depth: DepthFrame = parse_frame(black_box(&frame), &mut buffer).expect("in bench");
+            black_box((
+                depth.stream.len(),
+                depth.data.last_update_id,
+                depth.data.asks[0].0.len(),
+            ))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, depth);
+criterion_main!(benches);
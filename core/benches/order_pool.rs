@@ -0,0 +1,113 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::pool::OrdersPool;
+use mmb_domain::order::snapshot::{ClientOrderId, OrderHeader, OrderSide, UserOrder};
+use rust_decimal_macros::dec;
+
+const ORDERS_PER_THREAD: usize = 1_000;
+const THREADS_COUNTS: [usize; 3] = [1, 4, 8];
+
+fn order_headers(threads_count: usize) -> Vec<Vec<OrderHeader>> {
+    (0..threads_count)
+        .map(|_| {
+            (0..ORDERS_PER_THREAD)
+                .map(|_| {
+                    OrderHeader::with_user_order(
+                        ClientOrderId::unique_id(),
+                        ExchangeAccountId::new("Binance", 0),
+                        CurrencyPair::from_codes("btc".into(), "usdt".into()),
+                        OrderSide::Buy,
+                        dec!(1),
+                        UserOrder::limit(dec!(20000)),
+                        None,
+                        None,
+                        "Benchmark".to_owned(),
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Every thread inserts its own orders into the same pool
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("orders_pool_insert");
+    for threads_count in THREADS_COUNTS {
+        group.throughput(Throughput::Elements(
+            (threads_count * ORDERS_PER_THREAD) as u64,
+        ));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads_count),
+            &threads_count,
+            |b, &threads_count| {
+                b.iter_custom(|iters| {
+                    let mut elapsed = Duration::ZERO;
+                    for _ in 0..iters {
+                        let pool = OrdersPool::new();
+                        let headers = order_headers(threads_count);
+                        let now = Utc::now();
+
+                        let start = Instant::now();
+                        thread::scope(|s| {
+                            for headers in &headers {
+                                let pool = &pool;
+                                s.spawn(move || {
+                                    for header in headers {
+                                        let _ = pool.add_simple_initial(header, now, None);
+                                    }
+                                });
+                            }
+                        });
+                        elapsed += start.elapsed();
+                    }
+                    elapsed
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Every thread looks up orders of all threads by client order id
+fn lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("orders_pool_lookup");
+    for threads_count in THREADS_COUNTS {
+        let pool = OrdersPool::new();
+        let now = Utc::now();
+        let client_order_ids: Vec<ClientOrderId> = order_headers(threads_count)
+            .iter()
+            .flatten()
+            .map(|header| pool.add_simple_initial(header, now, None).client_order_id())
+            .collect();
+
+        group.throughput(Throughput::Elements(
+            (threads_count * client_order_ids.len()) as u64,
+        ));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads_count),
+            &threads_count,
+            |b, &threads_count| {
+                b.iter(|| {
+                    thread::scope(|s| {
+                        for _ in 0..threads_count {
+                            s.spawn(|| {
+                                for client_order_id in &client_order_ids {
+                                    let order = pool.cache_by_client_id.get(client_order_id);
+                                    black_box(order.map(|x| x.status()));
+                                }
+                            });
+                        }
+                    });
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, insert, lookup);
+criterion_main!(benches);
@@ -0,0 +1,155 @@
+use std::collections::{HashMap, HashSet};
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mmb_core::misc::clock::SystemClock;
+use mmb_core::misc::trading_calendar::TradingCalendar;
+use mmb_core::statistic_service::{
+    ExchangeAccountStatistic, MarketAccountIdStatistic, StatisticService,
+};
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId, MarketAccountId};
+use mmb_domain::order::snapshot::{Amount, ClientOrderId};
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+const FILLS_PER_THREAD: usize = 1_000;
+const THREADS_COUNTS: [usize; 3] = [1, 4, 8];
+
+/// Every thread fills orders of its own trade place, as with many traded pairs
+fn market_account_ids(threads_count: usize) -> Vec<MarketAccountId> {
+    (0..threads_count)
+        .map(|i| {
+            MarketAccountId::new(
+                ExchangeAccountId::new("Binance", 0),
+                CurrencyPair::from_codes(format!("alt{i}").as_str().into(), "usdt".into()),
+            )
+        })
+        .collect()
+}
+
+/// Previous layout of statistics where every event takes write lock of the whole map, kept for
+/// comparison
+#[derive(Default)]
+struct GlobalLockStatistic {
+    market_account_id_stats: RwLock<HashMap<MarketAccountId, MarketAccountIdStatistic>>,
+    exchange_account_stats: RwLock<HashMap<ExchangeAccountId, ExchangeAccountStatistic>>,
+    partially_filled_orders: Mutex<HashSet<ClientOrderId>>,
+}
+
+impl GlobalLockStatistic {
+    fn register_completely_filled_order(
+        &self,
+        market_account_id: MarketAccountId,
+        client_order_id: &ClientOrderId,
+        filled_amount: Amount,
+        commission: Amount,
+    ) {
+        self.market_account_id_stats
+            .write()
+            .entry(market_account_id)
+            .or_default()
+            .fully_filled_orders_count += 1;
+
+        if self.partially_filled_orders.lock().remove(client_order_id) {
+            self.market_account_id_stats
+                .write()
+                .entry(market_account_id)
+                .or_default()
+                .partially_filled_orders_count -= 1;
+        }
+
+        self.market_account_id_stats
+            .write()
+            .entry(market_account_id)
+            .or_default()
+            .summary_filled_amount += filled_amount;
+
+        self.market_account_id_stats
+            .write()
+            .entry(market_account_id)
+            .or_default()
+            .summary_commission += commission;
+    }
+
+    fn register_fill(&self, exchange_account_id: ExchangeAccountId) {
+        let mut exchange_account_stats = self.exchange_account_stats.write();
+        let stats = exchange_account_stats
+            .entry(exchange_account_id)
+            .or_default();
+        stats.fills_count += 1;
+        stats.cancel_fill_ratio =
+            Some(Decimal::from(stats.canceled_orders_count) / Decimal::from(stats.fills_count));
+    }
+}
+
+fn fills(c: &mut Criterion) {
+    let client_order_id = ClientOrderId::unique_id();
+
+    let mut group = c.benchmark_group("statistic_fills");
+    for threads_count in THREADS_COUNTS {
+        let market_account_ids = market_account_ids(threads_count);
+        group.throughput(Throughput::Elements(
+            (threads_count * FILLS_PER_THREAD) as u64,
+        ));
+
+        group.bench_with_input(
+            BenchmarkId::new("sharded", threads_count),
+            &market_account_ids,
+            |b, market_account_ids| {
+                let statistics =
+                    StatisticService::new(TradingCalendar::new(None), SystemClock::shared());
+                b.iter(|| {
+                    thread::scope(|s| {
+                        for &market_account_id in market_account_ids {
+                            let statistics = &statistics;
+                            let client_order_id = &client_order_id;
+                            s.spawn(move || {
+                                for _ in 0..FILLS_PER_THREAD {
+                                    statistics.register_fill(market_account_id.exchange_account_id);
+                                    statistics.register_completely_filled_order(
+                                        market_account_id,
+                                        client_order_id,
+                                        dec!(1),
+                                        dec!(0.001),
+                                    );
+                                }
+                            });
+                        }
+                    });
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("global_lock", threads_count),
+            &market_account_ids,
+            |b, market_account_ids| {
+                let statistics = GlobalLockStatistic::default();
+                b.iter(|| {
+                    thread::scope(|s| {
+                        for &market_account_id in market_account_ids {
+                            let statistics = &statistics;
+                            let client_order_id = &client_order_id;
+                            s.spawn(move || {
+                                for _ in 0..FILLS_PER_THREAD {
+                                    statistics.register_fill(market_account_id.exchange_account_id);
+                                    statistics.register_completely_filled_order(
+                                        market_account_id,
+                                        client_order_id,
+                                        dec!(1),
+                                        dec!(0.001),
+                                    );
+                                }
+                            });
+                        }
+                    });
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fills);
+criterion_main!(benches);
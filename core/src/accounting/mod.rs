@@ -0,0 +1 @@
+pub mod performance_fee;
@@ -0,0 +1,316 @@
+use std::fmt::Write as _;
+
+use chrono::{Datelike, Duration, NaiveDate, TimeZone, Utc};
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// Calendar period over which performance fee is charged. Periods are aligned by UTC time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeePeriod {
+    Daily,
+    /// Week starts on Monday
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl FeePeriod {
+    /// Start of the period containing the specified time
+    pub fn start_of(&self, time: DateTime) -> DateTime {
+        let date = time.naive_utc().date();
+        let start = match self {
+            FeePeriod::Daily => date,
+            FeePeriod::Weekly => {
+                date - Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+            FeePeriod::Monthly => first_day_of_month(date.year(), date.month()),
+            FeePeriod::Quarterly => first_day_of_month(date.year(), (date.month() - 1) / 3 * 3 + 1),
+            FeePeriod::Yearly => first_day_of_month(date.year(), 1),
+        };
+
+        to_date_time(start)
+    }
+
+    /// Start of the period following the one that starts at `start`
+    pub fn next_start(&self, start: DateTime) -> DateTime {
+        let date = start.naive_utc().date();
+        let add_months = |months: u32| {
+            let month_index = date.month0() + months;
+            first_day_of_month(
+                date.year() + (month_index / 12) as i32,
+                month_index % 12 + 1,
+            )
+        };
+
+        let next = match self {
+            FeePeriod::Daily => date + Duration::days(1),
+            FeePeriod::Weekly => date + Duration::days(7),
+            FeePeriod::Monthly => add_months(1),
+            FeePeriod::Quarterly => add_months(3),
+            FeePeriod::Yearly => add_months(12),
+        };
+
+        to_date_time(next)
+    }
+}
+
+fn first_day_of_month(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, 1).expect("first day of month should always exist")
+}
+
+fn to_date_time(date: NaiveDate) -> DateTime {
+    let naive = date
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight should always exist");
+    Utc.from_utc_datetime(&naive)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PerformanceFeeSettings {
+    /// Share of profit above high-water mark charged as fee, e.g. `0.2` for 20%
+    pub fee_rate: Decimal,
+    pub period: FeePeriod,
+    /// Initial high-water mark. The first equity of the series is used if it isn't set
+    pub high_water_mark: Option<Decimal>,
+}
+
+/// Fee charged for a single period
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeePeriodStatement {
+    pub start: DateTime,
+    pub end: DateTime,
+    /// Equity at the start of period after fees charged before
+    pub start_equity: Decimal,
+    /// Equity at the end of period before fee of this period
+    pub end_equity: Decimal,
+    /// High-water mark before fee of this period
+    pub high_water_mark: Decimal,
+    /// Profit above high-water mark
+    pub fee_base: Decimal,
+    pub fee: Decimal,
+}
+
+/// Performance fees of a strategy or sub-portfolio over its equity series
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PerformanceFeeStatement {
+    pub portfolio: String,
+    pub fee_rate: Decimal,
+    pub period: FeePeriod,
+    pub periods: Vec<FeePeriodStatement>,
+    pub total_fee: Decimal,
+    /// High-water mark after the last charged fee
+    pub high_water_mark: Decimal,
+}
+
+impl PerformanceFeeStatement {
+    pub fn periods_csv(&self) -> String {
+        let mut csv = "start,end,start_equity,end_equity,high_water_mark,fee_base,fee\n".to_owned();
+        for period in &self.periods {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{}",
+                period.start.to_rfc3339(),
+                period.end.to_rfc3339(),
+                period.start_equity,
+                period.end_equity,
+                period.high_water_mark,
+                period.fee_base,
+                period.fee
+            );
+        }
+        csv
+    }
+}
+
+/// Calculates performance fees over equity series ordered by time. Fee of a period is charged on
+/// profit above the high-water mark at the end of period, and the fee is deducted from equity
+/// of the following periods. Deposits and withdrawals should be excluded from the series
+pub fn calculate_performance_fee(
+    portfolio: &str,
+    settings: &PerformanceFeeSettings,
+    equity_series: &[(DateTime, Decimal)],
+) -> PerformanceFeeStatement {
+    let mut statement = PerformanceFeeStatement {
+        portfolio: portfolio.to_owned(),
+        fee_rate: settings.fee_rate,
+        period: settings.period,
+        periods: Vec::new(),
+        total_fee: dec!(0),
+        high_water_mark: dec!(0),
+    };
+
+    let (first_time, first_equity) = match equity_series.first() {
+        Some(first) => *first,
+        None => {
+            statement.high_water_mark = settings.high_water_mark.unwrap_or_default();
+            return statement;
+        }
+    };
+
+    let mut high_water_mark = settings.high_water_mark.unwrap_or(first_equity);
+    let mut charged_fee = dec!(0);
+    let mut start = settings.period.start_of(first_time);
+    let mut start_equity = first_equity;
+    let mut points = equity_series.iter().peekable();
+
+    while points.peek().is_some() {
+        let end = settings.period.next_start(start);
+
+        let mut end_equity = None;
+        while let Some((_, equity)) = points.next_if(|(time, _)| *time < end) {
+            end_equity = Some(*equity - charged_fee);
+        }
+
+        let end_equity = match end_equity {
+            Some(end_equity) => end_equity,
+            // there are no points in period, so equity is unchanged till the next period
+            None => {
+                start = end;
+                continue;
+            }
+        };
+
+        let fee_base = (end_equity - high_water_mark).max(dec!(0));
+        let fee = fee_base * settings.fee_rate;
+        statement.periods.push(FeePeriodStatement {
+            start,
+            end,
+            start_equity,
+            end_equity,
+            high_water_mark,
+            fee_base,
+            fee,
+        });
+
+        charged_fee += fee;
+        high_water_mark = high_water_mark.max(end_equity - fee);
+        start = end;
+        start_equity = end_equity - fee;
+    }
+
+    statement.total_fee = charged_fee;
+    statement.high_water_mark = high_water_mark;
+    statement
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn time(month: u32, day: u32) -> DateTime {
+        Utc.with_ymd_and_hms(2022, month, day, 12, 0, 0)
+            .single()
+            .expect("in test")
+    }
+
+    fn midnight(year: i32, month: u32, day: u32) -> DateTime {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0)
+            .single()
+            .expect("in test")
+    }
+
+    fn settings(high_water_mark: Option<Decimal>) -> PerformanceFeeSettings {
+        PerformanceFeeSettings {
+            fee_rate: dec!(0.2),
+            period: FeePeriod::Monthly,
+            high_water_mark,
+        }
+    }
+
+    #[rstest]
+    #[case::daily(
+        FeePeriod::Daily,
+        time(11, 17),
+        midnight(2022, 11, 17),
+        midnight(2022, 11, 18)
+    )]
+    #[case::weekly(
+        FeePeriod::Weekly,
+        time(11, 17),
+        midnight(2022, 11, 14),
+        midnight(2022, 11, 21)
+    )]
+    #[case::monthly(
+        FeePeriod::Monthly,
+        time(12, 17),
+        midnight(2022, 12, 1),
+        midnight(2023, 1, 1)
+    )]
+    #[case::quarterly(
+        FeePeriod::Quarterly,
+        time(11, 17),
+        midnight(2022, 10, 1),
+        midnight(2023, 1, 1)
+    )]
+    #[case::yearly(
+        FeePeriod::Yearly,
+        time(11, 17),
+        midnight(2022, 1, 1),
+        midnight(2023, 1, 1)
+    )]
+    fn period_boundaries(
+        #[case] period: FeePeriod,
+        #[case] time: DateTime,
+        #[case] expected_start: DateTime,
+        #[case] expected_next_start: DateTime,
+    ) {
+        let start = period.start_of(time);
+
+        assert_eq!(start, expected_start);
+        assert_eq!(period.next_start(start), expected_next_start);
+    }
+
+    #[test]
+    fn fee_is_charged_above_high_water_mark_only() {
+        let equity_series = [
+            (time(1, 1), dec!(1000)),
+            (time(1, 31), dec!(1100)),
+            // loss: no fee, high-water mark stays
+            (time(2, 15), dec!(1050)),
+            // recovered below high-water mark: no fee
+            (time(3, 10), dec!(1070)),
+            // above high-water mark 1080 (1100 - 20 fee)
+            (time(4, 10), dec!(1180)),
+        ];
+
+        let statement = calculate_performance_fee("test", &settings(None), &equity_series);
+
+        let fees = statement.periods.iter().map(|x| x.fee).collect::<Vec<_>>();
+        assert_eq!(fees, vec![dec!(20), dec!(0), dec!(0), dec!(16)]);
+        assert_eq!(statement.total_fee, dec!(36));
+        assert_eq!(statement.high_water_mark, dec!(1144));
+
+        let april = &statement.periods[3];
+        assert_eq!(april.start_equity, dec!(1050));
+        assert_eq!(april.end_equity, dec!(1160));
+        assert_eq!(april.high_water_mark, dec!(1080));
+        assert_eq!(april.fee_base, dec!(80));
+    }
+
+    #[test]
+    fn periods_without_data_are_skipped() {
+        let equity_series = [(time(1, 10), dec!(1000)), (time(3, 10), dec!(1100))];
+
+        let statement =
+            calculate_performance_fee("test", &settings(Some(dec!(1000))), &equity_series);
+
+        assert_eq!(statement.periods.len(), 2);
+        assert_eq!(statement.periods[1].start, midnight(2022, 3, 1));
+        assert_eq!(statement.periods[1].start_equity, dec!(1000));
+        assert_eq!(statement.periods[1].fee, dec!(20));
+    }
+
+    #[test]
+    fn empty_series() {
+        let statement = calculate_performance_fee("test", &settings(Some(dec!(500))), &[]);
+
+        assert!(statement.periods.is_empty());
+        assert_eq!(statement.total_fee, dec!(0));
+        assert_eq!(statement.high_water_mark, dec!(500));
+    }
+}
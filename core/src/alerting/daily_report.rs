@@ -0,0 +1,412 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{NaiveTime, TimeZone};
+use dashmap::DashMap;
+use mmb_domain::market::{CurrencyCode, ExchangeAccountId, MarketAccountId};
+use mmb_domain::order::snapshot::{Amount, Price};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+
+use crate::alerting::email::EmailNotifier;
+use crate::alerting::{Alert, AlertService};
+use crate::balance::manager::balance_manager::BalanceManager;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::misc::clock::Clock;
+use crate::risk::loss_limit::{equity, value_in};
+use crate::settings::{AlertingSettings, DailyReportSettings};
+use crate::statistic_service::{MarketAccountIdStatistic, StatisticService};
+
+/// Limit of incidents listed in report, all of them are counted
+const MAX_LISTED_INCIDENTS: usize = 20;
+
+/// State of trading at report time used as reference for the next report
+#[derive(Debug, Clone, Default)]
+struct ReportBaseline {
+    time: DateTime,
+    equities: HashMap<ExchangeAccountId, Decimal>,
+    stats: HashMap<MarketAccountId, MarketAccountIdStatistic>,
+}
+
+/// Trading of market within report period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketSummary {
+    pub market_account_id: MarketAccountId,
+    pub filled_orders_count: u64,
+    /// In base currency
+    pub filled_amount: Amount,
+    pub commission: Amount,
+    /// Filled amount valued in valuation currency, `None` if price of base currency is unknown
+    pub volume: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyReport {
+    pub period_start: DateTime,
+    pub period_end: DateTime,
+    pub valuation_currency_code: CurrencyCode,
+    /// Change of equity of exchange accounts which are valued at both bounds of period
+    pub pnl: Vec<(ExchangeAccountId, Decimal)>,
+    /// Markets with fills within period sorted by volume descending
+    pub markets: Vec<MarketSummary>,
+    pub top_pairs_count: usize,
+    pub incidents: Vec<Alert>,
+}
+
+impl DailyReport {
+    fn new(
+        settings: &DailyReportSettings,
+        previous: &ReportBaseline,
+        current: &ReportBaseline,
+        base_price: impl Fn(MarketAccountId) -> Option<Price>,
+        incidents: Vec<Alert>,
+    ) -> Self {
+        let mut pnl: Vec<_> = current
+            .equities
+            .iter()
+            .filter_map(|(exchange_account_id, equity)| {
+                let previous_equity = previous.equities.get(exchange_account_id)?;
+                Some((*exchange_account_id, equity - previous_equity))
+            })
+            .collect();
+        pnl.sort_by_key(|(exchange_account_id, _)| exchange_account_id.to_string());
+
+        let mut markets: Vec<_> = current
+            .stats
+            .iter()
+            .filter_map(|(market_account_id, stats)| {
+                let previous_stats = previous
+                    .stats
+                    .get(market_account_id)
+                    .cloned()
+                    .unwrap_or_default();
+                let filled_orders_count =
+                    stats.fully_filled_orders_count - previous_stats.fully_filled_orders_count;
+                if filled_orders_count == 0 {
+                    return None;
+                }
+
+                let filled_amount =
+                    stats.summary_filled_amount - previous_stats.summary_filled_amount;
+                Some(MarketSummary {
+                    market_account_id: *market_account_id,
+                    filled_orders_count,
+                    filled_amount,
+                    commission: stats.summary_commission - previous_stats.summary_commission,
+                    volume: base_price(*market_account_id).map(|price| filled_amount * price),
+                })
+            })
+            .collect();
+        markets.sort_by_key(|x| (Reverse(x.volume), x.market_account_id.to_string()));
+
+        DailyReport {
+            period_start: previous.time,
+            period_end: current.time,
+            valuation_currency_code: settings.valuation_currency_code,
+            pnl,
+            markets,
+            top_pairs_count: settings.top_pairs_count,
+            incidents,
+        }
+    }
+
+    pub fn subject(&self) -> String {
+        format!("Trading report {}", self.period_end.format("%Y-%m-%d"))
+    }
+}
+
+impl Display for DailyReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let currency = self.valuation_currency_code;
+        writeln!(
+            f,
+            "Trading report for {} - {}",
+            self.period_start.format("%Y-%m-%d %H:%M UTC"),
+            self.period_end.format("%Y-%m-%d %H:%M UTC")
+        )?;
+
+        writeln!(f, "\nPnL:")?;
+        for (exchange_account_id, pnl) in &self.pnl {
+            writeln!(f, "  {exchange_account_id}: {pnl} {currency}")?;
+        }
+        let total_pnl: Decimal = self.pnl.iter().map(|(_, pnl)| pnl).sum();
+        writeln!(f, "  Total: {total_pnl} {currency}")?;
+
+        let filled_orders_count: u64 = self.markets.iter().map(|x| x.filled_orders_count).sum();
+        let volume: Decimal = self.markets.iter().filter_map(|x| x.volume).sum();
+        writeln!(
+            f,
+            "\nVolume: {volume} {currency} over {filled_orders_count} filled orders"
+        )?;
+
+        writeln!(f, "\nTop pairs by volume:")?;
+        for market in self.markets.iter().take(self.top_pairs_count) {
+            let volume = match market.volume {
+                Some(volume) => format!("{volume} {currency}"),
+                None => "isn't valued".to_owned(),
+            };
+            writeln!(
+                f,
+                "  {}: filled {} (volume {volume}), fees {}, filled orders {}",
+                market.market_account_id,
+                market.filled_amount,
+                market.commission,
+                market.filled_orders_count
+            )?;
+        }
+
+        writeln!(f, "\nIncidents: {}", self.incidents.len())?;
+        for incident in self.incidents.iter().rev().take(MAX_LISTED_INCIDENTS) {
+            writeln!(
+                f,
+                "  {} {incident}",
+                incident.time.format("%Y-%m-%d %H:%M:%S")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The first moment of `time` of day after `now`
+pub(crate) fn next_report_time(now: DateTime, time: NaiveTime) -> DateTime {
+    let today = chrono::Utc.from_utc_datetime(&now.date_naive().and_time(time));
+    match today > now {
+        true => today,
+        false => today + chrono::Duration::days(1),
+    }
+}
+
+/// Emails summary of trading every day at configured time: PnL of exchange accounts, volumes and
+/// fees of markets and incidents alerted since the previous report
+pub struct DailyReportService {
+    settings: DailyReportSettings,
+    email: EmailNotifier,
+    alert_service: Arc<AlertService>,
+    statistics: Arc<StatisticService>,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    balance_manager: Arc<Mutex<BalanceManager>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl DailyReportService {
+    /// Returns `None` if daily report isn't configured
+    pub fn new(
+        settings: &AlertingSettings,
+        alert_service: Arc<AlertService>,
+        statistics: Arc<StatisticService>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        balance_manager: Arc<Mutex<BalanceManager>>,
+        clock: Arc<dyn Clock>,
+    ) -> Option<Arc<Self>> {
+        let report_settings = settings.daily_report.clone()?;
+        let email = match settings.email.clone() {
+            Some(email) => EmailNotifier::new(email),
+            None => {
+                log::error!("Daily report isn't sent because email settings aren't specified");
+                return None;
+            }
+        };
+
+        Some(Arc::new(DailyReportService {
+            settings: report_settings,
+            email,
+            alert_service,
+            statistics,
+            exchanges,
+            balance_manager,
+            clock,
+        }))
+    }
+
+    pub fn start(self: &Arc<Self>) {
+        spawn_future(
+            "Send daily reports",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            self.clone().send_reports(),
+        );
+    }
+
+    async fn send_reports(self: Arc<Self>) -> Result<()> {
+        let mut baseline = self.baseline();
+        loop {
+            let now = self.clock.now();
+            let report_time = next_report_time(now, self.settings.time);
+            let delay = (report_time - now).to_std().unwrap_or_default();
+            self.clock.sleep(delay).await;
+
+            let current = self.baseline();
+            let report = DailyReport::new(
+                &self.settings,
+                &baseline,
+                &current,
+                |market_account_id| self.base_price(market_account_id),
+                self.alert_service.take_incidents(),
+            );
+            baseline = current;
+
+            log::info!("{report}");
+            if let Err(err) = self
+                .email
+                .send_email(&report.subject(), report.to_string())
+                .await
+            {
+                log::error!("Failed to send daily report: {err:?}");
+            }
+        }
+    }
+
+    fn baseline(&self) -> ReportBaseline {
+        let balances_by_exchange_id = self
+            .balance_manager
+            .lock()
+            .get_balances()
+            .balances_by_exchange_id
+            .unwrap_or_default();
+
+        let equities = balances_by_exchange_id
+            .iter()
+            .filter_map(|(exchange_account_id, balances)| {
+                let exchange = self.exchanges.get(exchange_account_id)?;
+                let equity = equity(&exchange, balances, self.settings.valuation_currency_code)?;
+                Some((*exchange_account_id, equity))
+            })
+            .collect();
+
+        ReportBaseline {
+            time: self.clock.now(),
+            equities,
+            stats: self
+                .statistics
+                .market_account_id_stats()
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn base_price(&self, market_account_id: MarketAccountId) -> Option<Price> {
+        let exchange = self.exchanges.get(&market_account_id.exchange_account_id)?;
+        let base_currency_code = exchange
+            .symbols
+            .get(&market_account_id.currency_pair)?
+            .base_currency_code;
+        value_in(
+            &exchange,
+            base_currency_code,
+            self.settings.valuation_currency_code,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerting::{AlertKind, AlertSeverity};
+    use mmb_domain::market::CurrencyPair;
+    use rust_decimal_macros::dec;
+
+    fn time(hour: u32, minute: u32) -> DateTime {
+        chrono::Utc.ymd(2022, 11, 17).and_hms(hour, minute, 0)
+    }
+
+    fn settings() -> DailyReportSettings {
+        DailyReportSettings {
+            time: NaiveTime::from_hms_opt(9, 0, 0).expect("valid time"),
+            valuation_currency_code: "usdt".into(),
+            top_pairs_count: 1,
+        }
+    }
+
+    fn stats(filled_orders_count: u64, filled_amount: Amount) -> MarketAccountIdStatistic {
+        MarketAccountIdStatistic {
+            fully_filled_orders_count: filled_orders_count,
+            summary_filled_amount: filled_amount,
+            summary_commission: filled_amount / dec!(1000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn report_is_sent_at_the_next_occurrence_of_time() {
+        let report_time = settings().time;
+        assert_eq!(next_report_time(time(8, 59), report_time), time(9, 0));
+        assert_eq!(
+            next_report_time(time(9, 0), report_time),
+            time(9, 0) + chrono::Duration::days(1)
+        );
+    }
+
+    #[test]
+    fn report_contains_changes_since_previous_one() {
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        let btc = MarketAccountId::new(
+            exchange_account_id,
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        );
+        let eth = MarketAccountId::new(
+            exchange_account_id,
+            CurrencyPair::from_codes("eth".into(), "usdt".into()),
+        );
+        let idle = MarketAccountId::new(
+            exchange_account_id,
+            CurrencyPair::from_codes("ltc".into(), "usdt".into()),
+        );
+
+        let previous = ReportBaseline {
+            time: time(9, 0) - chrono::Duration::days(1),
+            equities: HashMap::from([(exchange_account_id, dec!(1000))]),
+            stats: HashMap::from([(btc, stats(2, dec!(1))), (idle, stats(1, dec!(3)))]),
+        };
+        let current = ReportBaseline {
+            time: time(9, 0),
+            equities: HashMap::from([
+                (exchange_account_id, dec!(1012.5)),
+                (ExchangeAccountId::new("Bitmex", 0), dec!(500)),
+            ]),
+            stats: HashMap::from([
+                (btc, stats(5, dec!(1.5))),
+                (eth, stats(4, dec!(2))),
+                (idle, stats(1, dec!(3))),
+            ]),
+        };
+        let incident = Alert {
+            severity: AlertSeverity::Critical,
+            kind: AlertKind::KillSwitch,
+            source: String::new(),
+            message: "Kill switch is triggered".to_owned(),
+            time: time(3, 0),
+            suppressed: 0,
+        };
+        let base_price = |market_account_id| match market_account_id {
+            x if x == btc => Some(dec!(20000)),
+            x if x == eth => Some(dec!(1500)),
+            _ => None,
+        };
+
+        let report = DailyReport::new(&settings(), &previous, &current, base_price, vec![incident]);
+
+        assert_eq!(report.pnl, vec![(exchange_account_id, dec!(12.5))]);
+        let markets: Vec<_> = report
+            .markets
+            .iter()
+            .map(|x| (x.market_account_id, x.filled_orders_count, x.volume))
+            .collect();
+        assert_eq!(
+            markets,
+            vec![(btc, 3, Some(dec!(10000.0))), (eth, 4, Some(dec!(3000)))]
+        );
+
+        let text = report.to_string();
+        assert!(text.contains("Total: 12.5 usdt"), "{text}");
+        assert!(text.contains("Volume: 13000.0 usdt over 7 filled orders"));
+        assert!(text.contains("Binance_0|btc/usdt: filled 0.5"));
+        assert!(!text.contains("eth/usdt"));
+        assert!(text.contains("Incidents: 1"));
+    }
+}
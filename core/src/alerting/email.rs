@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::alerting::{Alert, Notifier};
+use crate::settings::{EmailSettings, SmtpEncryption};
+
+/// Sends alerts and reports by email through SMTP relay
+pub struct EmailNotifier {
+    settings: EmailSettings,
+}
+
+impl EmailNotifier {
+    pub fn new(settings: EmailSettings) -> Self {
+        EmailNotifier { settings }
+    }
+
+    pub async fn send_email(&self, subject: &str, body: String) -> Result<()> {
+        let settings = &self.settings;
+
+        let mut message = Message::builder()
+            .from(
+                settings
+                    .from
+                    .parse()
+                    .with_context(|| format!("parsing sender address {}", settings.from))?,
+            )
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN);
+        for to in &settings.to {
+            message = message.to(to
+                .parse()
+                .with_context(|| format!("parsing recipient address {to}"))?);
+        }
+        let message = message.body(body).context("building email")?;
+
+        let host = settings.smtp_host.as_str();
+        let mut transport = match settings.encryption {
+            SmtpEncryption::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                .with_context(|| format!("creating SMTP transport for {host}"))?,
+            SmtpEncryption::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+                .with_context(|| format!("creating SMTP transport for {host}"))?,
+            SmtpEncryption::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        }
+        .port(settings.smtp_port);
+        if let (Some(username), Some(password)) = (&settings.username, &settings.password) {
+            transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        let _ = transport
+            .build()
+            .send(message)
+            .await
+            .with_context(|| format!("sending email via {host}"))?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        "Email"
+    }
+
+    async fn notify(&self, alert: &Alert, text: &str) -> Result<()> {
+        let subject = format!("[{}] {:?} alert", alert.severity, alert.kind);
+        self.send_email(&subject, text.to_owned()).await
+    }
+}
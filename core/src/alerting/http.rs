@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+
+pub(super) type HttpClient = Client<HttpsConnector<HttpConnector>>;
+
+/// Client for notifiers. Plain http is allowed, because webhooks of internal tooling often don't
+/// use TLS
+pub(super) fn create_client() -> HttpClient {
+    let https = HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    Client::builder().build::<_, Body>(https)
+}
+
+/// Posts JSON body and fails on unsuccessful status. `target` names receiver in errors instead of
+/// uri, because uri can contain secrets
+pub(super) async fn post_json(
+    client: &HttpClient,
+    target: &str,
+    uri: &str,
+    headers: &HashMap<String, String>,
+    body: String,
+) -> Result<()> {
+    let mut builder = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(hyper::header::CONTENT_TYPE, "application/json");
+    for (name, value) in headers {
+        builder = builder.header(name, value);
+    }
+    let request = builder
+        .body(Body::from(body))
+        .with_context(|| format!("building {target} request"))?;
+
+    let response = client
+        .request(request)
+        .await
+        .with_context(|| format!("sending {target} request"))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let content = hyper::body::to_bytes(response.into_body())
+            .await
+            .with_context(|| format!("reading {target} response"))?;
+        bail!(
+            "{target} responded with {status}: {}",
+            String::from_utf8_lossy(&content)
+        );
+    }
+
+    Ok(())
+}
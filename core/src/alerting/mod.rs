@@ -0,0 +1,531 @@
+pub mod daily_report;
+pub mod email;
+mod http;
+pub mod slack;
+pub mod telegram;
+pub mod template;
+pub mod webhook;
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::future::join_all;
+use mmb_domain::events::{ConnectivityState, ExchangeEvent};
+use mmb_domain::market::ExchangeAccountId;
+use mmb_domain::order::event::OrderEventType;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+
+use crate::alerting::email::EmailNotifier;
+use crate::alerting::slack::SlackNotifier;
+use crate::alerting::telegram::TelegramNotifier;
+use crate::alerting::webhook::WebhookNotifier;
+use crate::infrastructure::spawn_future;
+use crate::misc::clock::Clock;
+use crate::settings::AlertingSettings;
+
+const DISCONNECTIONS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Limit of incidents kept for daily report, the oldest ones are dropped
+const MAX_INCIDENTS: usize = 1000;
+
+/// Alert service of running engine used by components which don't have access to `EngineContext`
+static ALERT_SERVICE: Mutex<Option<Weak<AlertService>>> = parking_lot::const_mutex(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Display for AlertSeverity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertSeverity::Info => write!(f, "INFO"),
+            AlertSeverity::Warning => write!(f, "WARNING"),
+            AlertSeverity::Critical => write!(f, "CRITICAL"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum AlertKind {
+    /// Order rejected by pre-trade risk checks, breached loss or exposure limit
+    RiskRule,
+    KillSwitch,
+    WebsocketDisconnected,
+    /// Balances or open orders of engine differ from exchange ones
+    ReconciliationMismatch,
+    /// Panic inside future spawned by `spawn_future`
+    Panic,
+    /// Critical task run by `Supervisor` keeps crashing after restarts
+    TaskCrashLoop,
+    /// Order is creating or canceling for too long and its state can't be repaired automatically
+    StuckOrder,
+    /// Exchange declared maintenance or finished it
+    ExchangeMaintenance,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub severity: AlertSeverity,
+    pub kind: AlertKind,
+    /// Origin of alert, e.g. exchange account id. Alerts are rate limited per kind and source
+    pub source: String,
+    pub message: String,
+    pub time: DateTime,
+    /// Count of alerts of the same kind and source suppressed by rate limiting since the
+    /// previous sent one
+    pub suppressed: u32,
+}
+
+impl Display for Alert {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {:?}: {}", self.severity, self.kind, self.message)?;
+        if self.suppressed > 0 {
+            write!(f, " ({} similar alerts were suppressed)", self.suppressed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Backend delivering alerts to operators
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// `text` is alert rendered by template of its kind
+    async fn notify(&self, alert: &Alert, text: &str) -> Result<()>;
+}
+
+/// Sends alert via alert service of running engine. Nothing is sent if alerting isn't configured
+pub fn alert(severity: AlertSeverity, kind: AlertKind, source: &str, message: impl Into<String>) {
+    let alert_service = ALERT_SERVICE.lock().as_ref().and_then(Weak::upgrade);
+    if let Some(alert_service) = alert_service {
+        alert_service.send(severity, kind, source, message);
+    }
+}
+
+/// Allows only one alert of the same kind and source within interval
+struct RateLimiter {
+    interval: chrono::Duration,
+    /// Time of the last sent alert and count of suppressed ones after it
+    last_sent: HashMap<(AlertKind, String), (DateTime, u32)>,
+}
+
+impl RateLimiter {
+    fn new(interval: chrono::Duration) -> Self {
+        RateLimiter {
+            interval,
+            last_sent: HashMap::new(),
+        }
+    }
+
+    /// Returns count of alerts suppressed since the previous sent one if alert can be sent now
+    fn check(&mut self, kind: AlertKind, source: &str, time: DateTime) -> Option<u32> {
+        match self.last_sent.get_mut(&(kind, source.to_owned())) {
+            Some((last_time, suppressed)) if time - *last_time < self.interval => {
+                *suppressed += 1;
+                None
+            }
+            Some(entry) => Some(std::mem::replace(entry, (time, 0)).1),
+            None => {
+                let _ = self.last_sent.insert((kind, source.to_owned()), (time, 0));
+                Some(0)
+            }
+        }
+    }
+}
+
+/// Websocket disconnections of exchange accounts which can be alerted when they last too long
+#[derive(Default)]
+struct DisconnectionTracker {
+    /// Start of disconnection and flag that it has been alerted already
+    disconnected: HashMap<ExchangeAccountId, (DateTime, bool)>,
+}
+
+impl DisconnectionTracker {
+    /// Returns time of alerted disconnection if connection is restored after it
+    fn on_state(
+        &mut self,
+        exchange_account_id: ExchangeAccountId,
+        state: ConnectivityState,
+        time: DateTime,
+    ) -> Option<DateTime> {
+        match state {
+            ConnectivityState::Disconnected => {
+                let _ = self
+                    .disconnected
+                    .entry(exchange_account_id)
+                    .or_insert((time, false));
+                None
+            }
+            ConnectivityState::Connected | ConnectivityState::Degraded => {
+                match self.disconnected.remove(&exchange_account_id) {
+                    Some((since, true)) => Some(since),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Disconnections lasting longer than `timeout` which haven't been alerted yet
+    fn check(
+        &mut self,
+        now: DateTime,
+        timeout: chrono::Duration,
+    ) -> Vec<(ExchangeAccountId, DateTime)> {
+        self.disconnected
+            .iter_mut()
+            .filter(|(_, (since, is_alerted))| !*is_alerted && now - *since >= timeout)
+            .map(|(exchange_account_id, (since, is_alerted))| {
+                *is_alerted = true;
+                (*exchange_account_id, *since)
+            })
+            .collect()
+    }
+}
+
+/// Pushes alerts about risk rule triggers, kill switch activation, long websocket disconnections,
+/// reconciliation mismatches and panics to configured notifiers. Alerts with severity lower than
+/// configured one are dropped and alerts of the same kind and source are rate limited
+pub struct AlertService {
+    settings: RwLock<AlertingSettings>,
+    /// Notifiers are replaced when settings are updated, so alerts being delivered use old ones
+    notifiers: RwLock<Arc<Vec<Arc<dyn Notifier>>>>,
+    /// Notifiers passed by code embedding engine, they are kept when settings are updated
+    custom_notifiers: Vec<Arc<dyn Notifier>>,
+    rate_limiter: Mutex<RateLimiter>,
+    /// Sent alerts with severity not lower than warning since the last `take_incidents` call
+    incidents: Mutex<Vec<Alert>>,
+    alerts_sender: mpsc::UnboundedSender<Alert>,
+    alerts_receiver: Mutex<Option<mpsc::UnboundedReceiver<Alert>>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl AlertService {
+    pub fn new(settings: AlertingSettings, clock: Arc<dyn Clock>) -> Arc<Self> {
+        Self::with_custom_notifiers(settings, clock, Vec::new())
+    }
+
+    /// Alerts are sent to custom notifiers in addition to ones configured by settings
+    pub fn with_custom_notifiers(
+        settings: AlertingSettings,
+        clock: Arc<dyn Clock>,
+        custom_notifiers: Vec<Arc<dyn Notifier>>,
+    ) -> Arc<Self> {
+        let (alerts_sender, alerts_receiver) = mpsc::unbounded_channel();
+        let notifiers = create_notifiers(&settings, &custom_notifiers);
+        let rate_limiter =
+            RateLimiter::new(chrono::Duration::seconds(settings.rate_limit_secs as i64));
+
+        let alert_service = Arc::new(AlertService {
+            settings: RwLock::new(settings),
+            notifiers: RwLock::new(Arc::new(notifiers)),
+            custom_notifiers,
+            rate_limiter: Mutex::new(rate_limiter),
+            incidents: Default::default(),
+            alerts_sender,
+            alerts_receiver: Mutex::new(Some(alerts_receiver)),
+            clock,
+        });
+        *ALERT_SERVICE.lock() = Some(Arc::downgrade(&alert_service));
+
+        alert_service
+    }
+
+    /// Queues alert for sending. Alerts sent before `start` are delivered after it
+    pub fn send(
+        &self,
+        severity: AlertSeverity,
+        kind: AlertKind,
+        source: &str,
+        message: impl Into<String>,
+    ) {
+        if severity < self.settings.read().min_severity {
+            return;
+        }
+
+        let time = self.clock.now();
+        let suppressed = match self.rate_limiter.lock().check(kind, source, time) {
+            Some(suppressed) => suppressed,
+            None => return,
+        };
+
+        let alert = Alert {
+            severity,
+            kind,
+            source: source.to_owned(),
+            message: message.into(),
+            time,
+            suppressed,
+        };
+        if severity >= AlertSeverity::Warning {
+            let mut incidents = self.incidents.lock();
+            if incidents.len() == MAX_INCIDENTS {
+                let _ = incidents.remove(0);
+            }
+            incidents.push(alert.clone());
+        }
+        if self.alerts_sender.send(alert).is_err() {
+            log::warn!("Alert isn't sent because alerts delivery is stopped");
+        }
+    }
+
+    /// Replaces notifiers, templates and thresholds without restart. Alerts queued before the
+    /// call are rendered and sent by new settings
+    pub fn update_settings(&self, settings: AlertingSettings) {
+        let notifiers = create_notifiers(&settings, &self.custom_notifiers);
+        self.rate_limiter.lock().interval =
+            chrono::Duration::seconds(settings.rate_limit_secs as i64);
+        *self.notifiers.write() = Arc::new(notifiers);
+        *self.settings.write() = settings;
+    }
+
+    /// Alerts with severity not lower than warning sent since the previous call
+    pub fn take_incidents(&self) -> Vec<Alert> {
+        std::mem::take(&mut *self.incidents.lock())
+    }
+
+    pub fn start(self: &Arc<Self>, events: broadcast::Receiver<ExchangeEvent>) {
+        let alerts_receiver = match self.alerts_receiver.lock().take() {
+            Some(alerts_receiver) => alerts_receiver,
+            None => {
+                log::warn!("Alert service is started already");
+                return;
+            }
+        };
+
+        spawn_future(
+            "Deliver alerts",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            self.clone().deliver_alerts(alerts_receiver),
+        );
+        spawn_future(
+            "Watch engine events for alerts",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            self.clone().watch_events(events),
+        );
+    }
+
+    async fn deliver_alerts(
+        self: Arc<Self>,
+        mut alerts: mpsc::UnboundedReceiver<Alert>,
+    ) -> Result<()> {
+        while let Some(alert) = alerts.recv().await {
+            let template = self.settings.read().templates.get(&alert.kind).cloned();
+            let text = match template {
+                Some(template) => template::render(&template, &alert, str::to_owned),
+                None => alert.to_string(),
+            };
+            log::info!("Alert: {text}");
+
+            let notifiers = self.notifiers.read().clone();
+            let notifications = notifiers.iter().map(|notifier| async {
+                if let Err(err) = notifier.notify(&alert, &text).await {
+                    log::error!("Failed to send alert to {}: {err:?}", notifier.name());
+                }
+            });
+            join_all(notifications).await;
+        }
+
+        Ok(())
+    }
+
+    async fn watch_events(
+        self: Arc<Self>,
+        mut events: broadcast::Receiver<ExchangeEvent>,
+    ) -> Result<()> {
+        let mut disconnections = DisconnectionTracker::default();
+        let mut check_interval = tokio::time::interval(DISCONNECTIONS_CHECK_INTERVAL);
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => self.handle_event(&event, &mut disconnections),
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Alert service skipped {skipped} engine events");
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                _ = check_interval.tick() => {
+                    let disconnect_timeout =
+                        chrono::Duration::seconds(self.settings.read().disconnect_alert_secs as i64);
+                    for (exchange_account_id, since) in disconnections.check(self.clock.now(), disconnect_timeout) {
+                        self.send(
+                            AlertSeverity::Critical,
+                            AlertKind::WebsocketDisconnected,
+                            &exchange_account_id.to_string(),
+                            format!("Websocket of {exchange_account_id} is disconnected since {since}"),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_event(&self, event: &ExchangeEvent, disconnections: &mut DisconnectionTracker) {
+        match event {
+            ExchangeEvent::OrderEvent(event) => {
+                if !matches!(event.event_type, OrderEventType::RejectedByRisk) {
+                    return;
+                }
+
+                let order = &event.order;
+                let exchange_account_id = order.exchange_account_id();
+                let reason = order.fn_ref(|x| x.internal_props.last_creation_error_message.clone());
+                self.send(
+                    AlertSeverity::Warning,
+                    AlertKind::RiskRule,
+                    &exchange_account_id.to_string(),
+                    format!(
+                        "Order {} on {exchange_account_id} {} is rejected by risk manager: {reason}",
+                        order.client_order_id(),
+                        order.currency_pair()
+                    ),
+                );
+            }
+            ExchangeEvent::TradingHalted(event) => {
+                let flattened = match event.is_positions_flattened {
+                    true => " Positions are flattened.",
+                    false => "",
+                };
+                self.send(
+                    AlertSeverity::Critical,
+                    AlertKind::KillSwitch,
+                    "",
+                    format!(
+                        "Kill switch is triggered: {}. Open orders are canceled.{flattened} Trading is halted until restart",
+                        event.reason
+                    ),
+                );
+            }
+            ExchangeEvent::Connectivity(event) => {
+                let exchange_account_id = event.exchange_account_id;
+                if let Some(since) =
+                    disconnections.on_state(exchange_account_id, event.state, event.time)
+                {
+                    self.send(
+                        AlertSeverity::Info,
+                        AlertKind::WebsocketDisconnected,
+                        &exchange_account_id.to_string(),
+                        format!(
+                            "Websocket of {exchange_account_id} is connected again after disconnection since {since}"
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn create_notifiers(
+    settings: &AlertingSettings,
+    custom_notifiers: &[Arc<dyn Notifier>],
+) -> Vec<Arc<dyn Notifier>> {
+    let mut notifiers: Vec<Arc<dyn Notifier>> = Vec::new();
+    if let Some(telegram) = &settings.telegram {
+        notifiers.push(Arc::new(TelegramNotifier::new(telegram.clone())));
+    }
+    if let Some(slack) = &settings.slack {
+        notifiers.push(Arc::new(SlackNotifier::new(slack.clone())));
+    }
+    for webhook in &settings.webhooks {
+        notifiers.push(Arc::new(WebhookNotifier::new(webhook.clone())));
+    }
+    if let Some(email) = &settings.email {
+        notifiers.push(Arc::new(EmailNotifier::new(email.clone())));
+    }
+    notifiers.extend(custom_notifiers.iter().cloned());
+    notifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn time(secs: i64) -> DateTime {
+        chrono::Utc
+            .timestamp_opt(1_000_000 + secs, 0)
+            .single()
+            .expect("valid time")
+    }
+
+    #[test]
+    fn alerts_of_same_source_are_rate_limited() {
+        let mut rate_limiter = RateLimiter::new(chrono::Duration::seconds(60));
+
+        assert_eq!(rate_limiter.check(AlertKind::Panic, "a", time(0)), Some(0));
+        assert_eq!(rate_limiter.check(AlertKind::Panic, "a", time(10)), None);
+        assert_eq!(rate_limiter.check(AlertKind::Panic, "a", time(20)), None);
+        assert_eq!(rate_limiter.check(AlertKind::Panic, "b", time(20)), Some(0));
+        assert_eq!(
+            rate_limiter.check(AlertKind::RiskRule, "a", time(20)),
+            Some(0)
+        );
+        assert_eq!(rate_limiter.check(AlertKind::Panic, "a", time(60)), Some(2));
+        assert_eq!(rate_limiter.check(AlertKind::Panic, "a", time(70)), None);
+    }
+
+    struct TestNotifier;
+
+    #[async_trait]
+    impl Notifier for TestNotifier {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        async fn notify(&self, _: &Alert, _: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn custom_notifiers_are_added_to_configured_ones() {
+        let custom: Vec<Arc<dyn Notifier>> = vec![Arc::new(TestNotifier)];
+
+        let notifiers = create_notifiers(&AlertingSettings::default(), &custom);
+
+        let names: Vec<_> = notifiers.iter().map(|x| x.name()).collect();
+        assert_eq!(names, vec!["test"]);
+    }
+
+    #[test]
+    fn only_long_disconnection_is_alerted_once() {
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        let timeout = chrono::Duration::seconds(30);
+        let mut tracker = DisconnectionTracker::default();
+
+        let state = ConnectivityState::Disconnected;
+        assert_eq!(tracker.on_state(exchange_account_id, state, time(0)), None);
+        assert_eq!(tracker.check(time(10), timeout), vec![]);
+        let state = ConnectivityState::Connected;
+        assert_eq!(tracker.on_state(exchange_account_id, state, time(15)), None);
+
+        let state = ConnectivityState::Disconnected;
+        assert_eq!(tracker.on_state(exchange_account_id, state, time(20)), None);
+        // reconnection attempts don't restart disconnection
+        assert_eq!(tracker.on_state(exchange_account_id, state, time(35)), None);
+        assert_eq!(
+            tracker.check(time(50), timeout),
+            vec![(exchange_account_id, time(20))]
+        );
+        assert_eq!(tracker.check(time(60), timeout), vec![]);
+
+        let state = ConnectivityState::Connected;
+        assert_eq!(
+            tracker.on_state(exchange_account_id, state, time(70)),
+            Some(time(20))
+        );
+    }
+}
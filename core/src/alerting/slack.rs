@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::alerting::http::{create_client, post_json, HttpClient};
+use crate::alerting::{Alert, Notifier};
+use crate::settings::SlackSettings;
+
+/// Sends alerts to Slack channel by incoming webhook
+pub struct SlackNotifier {
+    settings: SlackSettings,
+    client: HttpClient,
+}
+
+impl SlackNotifier {
+    pub fn new(settings: SlackSettings) -> Self {
+        SlackNotifier {
+            settings,
+            client: create_client(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &str {
+        "Slack"
+    }
+
+    async fn notify(&self, _: &Alert, text: &str) -> Result<()> {
+        let body = json!({ "text": text });
+        post_json(
+            &self.client,
+            self.name(),
+            &self.settings.webhook_url,
+            &HashMap::new(),
+            body.to_string(),
+        )
+        .await
+    }
+}
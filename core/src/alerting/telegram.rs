@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::alerting::http::{create_client, post_json, HttpClient};
+use crate::alerting::{Alert, Notifier};
+use crate::settings::TelegramSettings;
+
+/// Sends alerts to Telegram chat by Bot API
+pub struct TelegramNotifier {
+    settings: TelegramSettings,
+    client: HttpClient,
+}
+
+impl TelegramNotifier {
+    pub fn new(settings: TelegramSettings) -> Self {
+        TelegramNotifier {
+            settings,
+            client: create_client(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &str {
+        "Telegram"
+    }
+
+    async fn notify(&self, _: &Alert, text: &str) -> Result<()> {
+        let body = json!({
+            "chat_id": self.settings.chat_id,
+            "text": text,
+            "disable_web_page_preview": true,
+        });
+        let uri = format!(
+            "{}/bot{}/sendMessage",
+            self.settings.api_url, self.settings.bot_token
+        );
+        post_json(
+            &self.client,
+            self.name(),
+            &uri,
+            &HashMap::new(),
+            body.to_string(),
+        )
+        .await
+    }
+}
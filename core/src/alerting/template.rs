@@ -0,0 +1,102 @@
+use crate::alerting::Alert;
+
+/// Replaces placeholders `{severity}`, `{kind}`, `{source}`, `{message}`, `{time}` and
+/// `{suppressed}` by values of alert passed through `escape`. Unknown placeholders are kept as is
+pub fn render(template: &str, alert: &Alert, escape: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(template.len() + alert.message.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = match rest.find('}') {
+            Some(end) => end,
+            None => break,
+        };
+        match placeholder_value(&rest[1..end], alert) {
+            Some(value) => {
+                result.push_str(&escape(&value));
+                rest = &rest[end + 1..];
+            }
+            // brace isn't a placeholder start, e.g. brace of JSON object
+            None => {
+                result.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+
+    result
+}
+
+fn placeholder_value(name: &str, alert: &Alert) -> Option<String> {
+    let value = match name {
+        "severity" => alert.severity.to_string(),
+        "kind" => format!("{:?}", alert.kind),
+        "source" => alert.source.clone(),
+        "message" => alert.message.clone(),
+        "time" => alert.time.to_rfc3339(),
+        "suppressed" => alert.suppressed.to_string(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Escapes value for insertion inside JSON string literal
+pub fn escape_json(value: &str) -> String {
+    let quoted = serde_json::Value::from(value).to_string();
+    quoted[1..quoted.len() - 1].to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerting::{AlertKind, AlertSeverity};
+    use chrono::TimeZone;
+
+    fn alert() -> Alert {
+        Alert {
+            severity: AlertSeverity::Critical,
+            kind: AlertKind::KillSwitch,
+            source: "Binance_0".to_owned(),
+            message: r#"Kill switch is triggered: "manual" {source}"#.to_owned(),
+            time: chrono::Utc
+                .timestamp_opt(1_000_000, 0)
+                .single()
+                .expect("valid time"),
+            suppressed: 2,
+        }
+    }
+
+    #[test]
+    fn placeholders_are_replaced_once() {
+        let text = render(
+            "{severity} {kind} on {source}: {message} ({suppressed}) {unknown} {",
+            &alert(),
+            str::to_owned,
+        );
+
+        assert_eq!(
+            text,
+            r#"CRITICAL KillSwitch on Binance_0: Kill switch is triggered: "manual" {source} (2) {unknown} {"#
+        );
+    }
+
+    #[test]
+    fn json_template_gets_escaped_values() {
+        let body = render(
+            r#"{"title": "{kind}", "text": "{message}", "at": "{time}"}"#,
+            &alert(),
+            escape_json,
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&body).expect("valid json");
+        assert_eq!(json["title"], "KillSwitch");
+        assert_eq!(
+            json["text"],
+            r#"Kill switch is triggered: "manual" {source}"#
+        );
+        assert_eq!(json["at"], "1970-01-12T13:46:40+00:00");
+    }
+}
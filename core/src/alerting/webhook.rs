@@ -0,0 +1,118 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::alerting::http::{create_client, post_json, HttpClient};
+use crate::alerting::template::{escape_json, render};
+use crate::alerting::{Alert, Notifier};
+use crate::settings::WebhookSettings;
+
+/// Posts alerts as JSON to arbitrary endpoint, e.g. incident management tool. Body is rendered by
+/// template of alert kind or contains all alert fields and rendered text if there is no template
+pub struct WebhookNotifier {
+    settings: WebhookSettings,
+    client: HttpClient,
+}
+
+impl WebhookNotifier {
+    pub fn new(settings: WebhookSettings) -> Self {
+        WebhookNotifier {
+            settings,
+            client: create_client(),
+        }
+    }
+
+    fn body(&self, alert: &Alert, text: &str) -> Result<String> {
+        let body = match self.settings.body_templates.get(&alert.kind) {
+            Some(template) => render(template, alert, escape_json),
+            None => json!({
+                "severity": alert.severity,
+                "kind": alert.kind,
+                "source": alert.source,
+                "message": alert.message,
+                "time": alert.time,
+                "suppressed": alert.suppressed,
+                "text": text,
+            })
+            .to_string(),
+        };
+
+        // invalid template is found on the first alert of its kind, so it's reported as error
+        let _: serde_json::Value = serde_json::from_str(&body)
+            .with_context(|| format!("body template of {:?} isn't valid JSON", alert.kind))?;
+        Ok(body)
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        "Webhook"
+    }
+
+    async fn notify(&self, alert: &Alert, text: &str) -> Result<()> {
+        let body = self.body(alert, text)?;
+        post_json(
+            &self.client,
+            self.name(),
+            &self.settings.url,
+            &self.settings.headers,
+            body,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerting::{AlertKind, AlertSeverity};
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+
+    fn notifier() -> WebhookNotifier {
+        WebhookNotifier::new(WebhookSettings {
+            url: "http://localhost/alerts".to_owned(),
+            headers: HashMap::new(),
+            body_templates: HashMap::from([(
+                AlertKind::Panic,
+                r#"{"summary": "{message}", "priority": "P1"}"#.to_owned(),
+            )]),
+        })
+    }
+
+    fn alert(kind: AlertKind) -> Alert {
+        Alert {
+            severity: AlertSeverity::Critical,
+            kind,
+            source: String::new(),
+            message: "Future 'x' panicked".to_owned(),
+            time: chrono::Utc
+                .timestamp_opt(1_000_000, 0)
+                .single()
+                .expect("valid time"),
+            suppressed: 0,
+        }
+    }
+
+    #[test]
+    fn body_is_rendered_by_template_of_alert_kind() {
+        let notifier = notifier();
+
+        let body = notifier
+            .body(&alert(AlertKind::Panic), "text")
+            .expect("valid body");
+        assert_eq!(
+            body,
+            r#"{"summary": "Future 'x' panicked", "priority": "P1"}"#
+        );
+
+        let body = notifier
+            .body(&alert(AlertKind::KillSwitch), "text")
+            .expect("valid body");
+        let json: serde_json::Value = serde_json::from_str(&body).expect("valid json");
+        assert_eq!(json["kind"], "KillSwitch");
+        assert_eq!(json["severity"], "Critical");
+        assert_eq!(json["text"], "text");
+    }
+}
@@ -1,16 +1,20 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+use crate::alerting::{alert, AlertKind, AlertSeverity};
 use crate::balance::balance_reservation_manager::BalanceReservationManager;
 use crate::balance::changes::balance_changes_service::BalanceChangesService;
 use crate::balance::manager::balance_reservation::BalanceReservation;
 use crate::balance::manager::balances::Balances;
 use crate::balance::manager::position_change::PositionChange;
+use crate::balance::manager::reconciliation::find_balance_discrepancies;
 use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+use crate::exchanges::general::exchange::Exchange;
 use crate::explanation::Explanation;
 use crate::misc::reserve_parameters::ReserveParameters;
 use crate::misc::service_value_tree::ServiceValueTree;
 use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use crate::settings::BalanceReconciliationSettings;
 use mmb_domain::events::ExchangeBalancesAndPositions;
 use mmb_domain::exchanges::symbol::{BeforeAfter, Symbol};
 use mmb_domain::market::{CurrencyCode, CurrencyPair, ExchangeAccountId, MarketAccountId};
@@ -34,6 +38,7 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
 use crate::database::events::recorder::EventRecorder;
+use crate::database::journal::{Journal, JournalRecord};
 #[cfg(test)]
 use crate::MOCK_MUTEX;
 use mmb_database::impl_event;
@@ -52,6 +57,7 @@ pub struct BalanceManager {
     position_differs_times_in_row_by_exchange_id:
         HashMap<ExchangeAccountId, HashMap<CurrencyPair, u32>>,
     event_recorder: Option<Arc<EventRecorder>>,
+    journal: Option<Arc<Journal>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -77,9 +83,16 @@ impl BalanceManager {
             balance_changes_service: None,
             position_differs_times_in_row_by_exchange_id: Default::default(),
             event_recorder,
+            journal: None,
         }))
     }
 
+    /// Balance state is written to journal after every change. Copies of balance manager
+    /// created by `custom_clone` don't write to journal
+    pub fn set_journal(&mut self, journal: Arc<Journal>) {
+        self.journal = Some(journal);
+    }
+
     pub fn restore_balance_state(
         &mut self,
         balances: &Balances,
@@ -173,6 +186,12 @@ impl BalanceManager {
     }
 
     fn save_balances(&mut self) {
+        if let Some(journal) = &self.journal {
+            if let Err(err) = journal.append(JournalRecord::Balances(self.get_balances())) {
+                log::error!("Failed to write balances to journal: {err:?}");
+            }
+        }
+
         match &self.event_recorder {
             None => {}
             Some(event_recorder) => {
@@ -846,6 +865,24 @@ impl BalanceManager {
             .get_exchange_balance(exchange_account_id, symbol, currency_code, None)
     }
 
+    /// Sum of exchange balances of exchange account converted into another currency by `convert`,
+    /// e.g. by `CrossRateService::convert_to_reporting`. `None` if balances aren't received yet or
+    /// some of them can't be converted
+    pub fn get_exchange_balances_value(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        convert: impl Fn(Amount, CurrencyCode) -> Option<Amount>,
+    ) -> Option<Amount> {
+        self.balance_reservation_manager
+            .virtual_balance_holder
+            .get_raw_exchange_balances()
+            .get(&exchange_account_id)?
+            .iter()
+            .filter(|(_, balance)| !balance.is_zero())
+            .map(|(&currency_code, &balance)| convert(balance, currency_code))
+            .sum()
+    }
+
     pub fn get_all_virtual_balance_diffs(&self) -> &ServiceValueTree {
         self.balance_reservation_manager
             .virtual_balance_holder
@@ -984,19 +1021,122 @@ impl BalanceManager {
         self.balance_changes_service = Some(service);
     }
 
+    fn exchanges_with_balances(&self) -> Vec<Arc<Exchange>> {
+        self.balance_reservation_manager
+            .exchanges_by_id()
+            .values()
+            .filter(|exchange| !exchange.is_watch_only())
+            .cloned()
+            .collect_vec()
+    }
+
+    /// Balances expected by engine: last balances received from exchange with changes by fills
+    /// which happened after that
+    pub fn get_local_balances(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> Result<HashMap<CurrencyCode, Amount>> {
+        let mut balances = self
+            .calculate_whole_balances()?
+            .remove(&exchange_account_id)
+            .unwrap_or_default();
+
+        let balance_diffs = self
+            .balance_reservation_manager
+            .virtual_balance_holder
+            .get_virtual_balance_diffs()
+            .get_as_balances();
+        for (balance_request, diff) in balance_diffs {
+            if balance_request.exchange_account_id == exchange_account_id {
+                *balances.entry(balance_request.currency_code).or_default() += diff;
+            }
+        }
+
+        Ok(balances)
+    }
+
+    /// Requests balances from exchanges and compares them with local ones. Local balances are
+    /// replaced by exchange ones if there are no discrepancies beyond tolerance or if
+    /// `snap_to_exchange` is set
+    pub async fn reconcile_balances_for_exchanges(
+        this: Arc<Mutex<Self>>,
+        settings: BalanceReconciliationSettings,
+        cancellation_token: CancellationToken,
+    ) {
+        log::trace!("Balance reconciliation started");
+
+        let exchanges = this.lock().exchanges_with_balances();
+
+        let reconcile_actions = exchanges.iter().map(|exchange| {
+            let this = this.clone();
+            let cancellation_token = cancellation_token.clone();
+            let settings = &settings;
+
+            async move {
+                let run = async move {
+                    let exchange_account_id = exchange.exchange_account_id;
+                    let balances_and_positions = exchange
+                        .get_balance(cancellation_token.clone())
+                        .await
+                        .with_context(|| format!("failed get_balance for {exchange_account_id}"))?;
+
+                    let tolerance_percent = match exchange.is_under_maintenance() {
+                        true => settings
+                            .maintenance_tolerance_percent
+                            .unwrap_or(settings.tolerance_percent),
+                        false => settings.tolerance_percent,
+                    };
+
+                    let mut this = this.lock();
+                    let discrepancies = find_balance_discrepancies(
+                        exchange_account_id,
+                        &this.get_local_balances(exchange_account_id)?,
+                        &balances_and_positions,
+                        tolerance_percent,
+                    );
+
+                    for discrepancy in &discrepancies {
+                        log::error!("Balance discrepancy is found on {discrepancy}");
+                    }
+                    if !discrepancies.is_empty() {
+                        alert(
+                            AlertSeverity::Warning,
+                            AlertKind::ReconciliationMismatch,
+                            &exchange_account_id.to_string(),
+                            format!(
+                                "Balance discrepancies are found on {}",
+                                discrepancies.iter().join("; ")
+                            ),
+                        );
+                    }
+
+                    if !discrepancies.is_empty() && !settings.snap_to_exchange {
+                        return Ok(());
+                    }
+
+                    this.update_exchange_balance(exchange_account_id, &balances_and_positions)
+                        .context("failed to update exchange balance")
+                };
+
+                match run.await {
+                    Ok(()) => nothing_to_do(),
+                    Err(err) => log::error!("{err:?}"),
+                }
+            }
+        });
+
+        join_all(reconcile_actions).await;
+
+        log::trace!("Balance reconciliation finished")
+    }
+
     pub async fn update_balances_for_exchanges(
         this: Arc<Mutex<Self>>,
         cancellation_token: CancellationToken,
     ) {
         log::trace!("Balance update started");
 
-        let exchanges = this
-            .lock()
-            .balance_reservation_manager
-            .exchanges_by_id()
-            .values()
-            .cloned()
-            .collect_vec();
+        let exchanges = this.lock().exchanges_with_balances();
 
         let update_actions = exchanges.iter().map(|exchange| {
             let this = this.clone();
@@ -1061,6 +1201,15 @@ impl BalanceManager {
         self.balance_reservation_manager
             .get_position(exchange_account_id, currency_pair, side)
     }
+
+    /// Signed net position: positive for long and negative for short. It's position by buy side
+    pub fn net_position(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+    ) -> Decimal {
+        self.get_position(exchange_account_id, currency_pair, OrderSide::Buy)
+    }
 }
 
 impl_mock_initializer!(MockBalanceManager);
@@ -5,6 +5,7 @@ pub mod balance_request;
 pub(crate) mod balance_reservation;
 pub(crate) mod balances;
 pub(crate) mod position_change;
+pub mod reconciliation;
 
 #[cfg(test)]
 pub mod tests;
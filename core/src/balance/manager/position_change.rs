@@ -1,10 +1,10 @@
 use mmb_domain::order::snapshot::ClientOrderFillId;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use mmb_utils::DateTime;
 use rust_decimal::Decimal;
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PositionChange {
     pub(crate) client_order_fill_id: ClientOrderFillId,
     pub(crate) change_time: DateTime,
@@ -0,0 +1,123 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use mmb_domain::events::ExchangeBalancesAndPositions;
+use mmb_domain::market::{CurrencyCode, ExchangeAccountId};
+use mmb_domain::order::snapshot::Amount;
+use rust_decimal::Decimal;
+
+/// Difference between balance expected by engine and balance received from exchange
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceDiscrepancy {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    pub local: Amount,
+    pub exchange: Amount,
+}
+
+impl Display for BalanceDiscrepancy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: local balance {}, exchange balance {}",
+            self.exchange_account_id, self.currency_code, self.local, self.exchange
+        )
+    }
+}
+
+/// Compares local balances with exchange ones. Currency missing on one side is considered as zero
+pub(crate) fn find_balance_discrepancies(
+    exchange_account_id: ExchangeAccountId,
+    local_balances: &HashMap<CurrencyCode, Amount>,
+    exchange_balances: &ExchangeBalancesAndPositions,
+    tolerance_percent: Decimal,
+) -> Vec<BalanceDiscrepancy> {
+    let exchange_balances: HashMap<_, _> = exchange_balances
+        .balances
+        .iter()
+        .map(|x| (x.currency_code, x.balance))
+        .collect();
+
+    let currency_codes: HashSet<_> = local_balances
+        .keys()
+        .chain(exchange_balances.keys())
+        .collect();
+
+    let mut discrepancies = currency_codes
+        .into_iter()
+        .filter_map(|currency_code| {
+            let local = local_balances
+                .get(currency_code)
+                .copied()
+                .unwrap_or_default();
+            let exchange = exchange_balances
+                .get(currency_code)
+                .copied()
+                .unwrap_or_default();
+
+            let tolerance = exchange.abs() * tolerance_percent / Decimal::ONE_HUNDRED;
+            ((local - exchange).abs() > tolerance).then_some(BalanceDiscrepancy {
+                exchange_account_id,
+                currency_code: *currency_code,
+                local,
+                exchange,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    discrepancies.sort_by(|a, b| a.currency_code.as_str().cmp(b.currency_code.as_str()));
+    discrepancies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::events::ExchangeBalance;
+    use mmb_utils::hashmap;
+    use rust_decimal_macros::dec;
+
+    fn exchange_balances(balances: &[(&str, Amount)]) -> ExchangeBalancesAndPositions {
+        ExchangeBalancesAndPositions {
+            balances: balances
+                .iter()
+                .map(|(currency_code, balance)| ExchangeBalance {
+                    currency_code: (*currency_code).into(),
+                    balance: *balance,
+                })
+                .collect(),
+            positions: None,
+        }
+    }
+
+    #[test]
+    fn discrepancies_beyond_tolerance() {
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        let local = hashmap![
+            "BTC".into() => dec!(1.005),
+            "ETH".into() => dec!(10),
+            "BNB".into() => dec!(1)
+        ];
+        let exchange = exchange_balances(&[("BTC", dec!(1)), ("ETH", dec!(9))]);
+
+        let discrepancies =
+            find_balance_discrepancies(exchange_account_id, &local, &exchange, dec!(1));
+
+        assert_eq!(
+            discrepancies,
+            vec![
+                BalanceDiscrepancy {
+                    exchange_account_id,
+                    currency_code: "BNB".into(),
+                    local: dec!(1),
+                    exchange: dec!(0),
+                },
+                BalanceDiscrepancy {
+                    exchange_account_id,
+                    currency_code: "ETH".into(),
+                    local: dec!(10),
+                    exchange: dec!(9),
+                },
+            ]
+        );
+    }
+}
@@ -1,20 +1,27 @@
 use crate::lifecycle::launcher::InitSettings;
 use crate::settings::AppSettings;
 use anyhow::{anyhow, bail, Context, Result};
+use itertools::Itertools;
 use mmb_utils::hashmap;
-use mmb_utils::infrastructure::WithExpect;
 use serde::de::DeserializeOwned;
+use std::env;
 use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
 use std::{collections::HashMap, io::Write};
 use std::{fmt::Debug, fs::File};
-use toml_edit::{value, ArrayOfTables, Document, Table};
+use toml_edit::{value, ArrayOfTables, Document, Item, Table, TableLike};
 
 pub static EXCHANGE_ACCOUNT_ID: &str = "exchange_account_id";
 pub static API_KEY: &str = "api_key";
 pub static SECRET_KEY: &str = "secret_key";
+pub static API_PASSPHRASE: &str = "api_passphrase";
+pub static IS_WATCH_ONLY: &str = "is_watch_only";
 pub static CONFIG_PATH: &str = "config.toml";
 pub static CREDENTIALS_PATH: &str = "credentials.toml";
 
+pub static PROFILE_ARG: &str = "--profile";
+pub static PROFILE_ENV: &str = "MMB_PROFILE";
+
 pub fn try_load_settings<TSettings>(
     config_path: &str,
     credentials_path: &str,
@@ -22,12 +29,126 @@ pub fn try_load_settings<TSettings>(
 where
     TSettings: Clone + Debug + DeserializeOwned,
 {
+    let profile = selected_profile();
+    let settings = read_settings(config_path, profile.as_deref())?;
+    let credentials = read_credentials(&settings, credentials_path, profile.as_deref())?;
+
+    parse_settings(&settings, &credentials)
+}
+
+/// Profile selected by `--profile <name>` argument or `MMB_PROFILE` variable, e.g. `paper` or
+/// `prod`. Only base settings are used if profile isn't selected
+pub fn selected_profile() -> Option<String> {
+    let args = env::args().skip(1).collect_vec();
+    let from_args = args
+        .iter()
+        .position(|x| x == PROFILE_ARG)
+        .and_then(|i| args.get(i + 1).cloned())
+        .or_else(|| {
+            args.iter()
+                .find_map(|x| x.strip_prefix("--profile=").map(str::to_owned))
+        });
+
+    from_args
+        .or_else(|| env::var(PROFILE_ENV).ok())
+        .filter(|x| !x.is_empty())
+}
+
+/// Path of profile file placed next to base one, e.g. `config.paper.toml` for `config.toml`
+pub fn profile_path(path: &str, profile: &str) -> PathBuf {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{stem}.{profile}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.{profile}"),
+    };
+    path.with_file_name(file_name)
+}
+
+/// Files from which settings are loaded: base settings file, settings file of selected profile
+/// and used credentials file. Missing optional files aren't listed
+pub fn settings_files(config_path: &str, credentials_path: &str) -> Vec<PathBuf> {
+    let profile = selected_profile();
+    let mut files = vec![PathBuf::from(config_path)];
+    if let Some(profile) = &profile {
+        files.push(profile_path(config_path, profile));
+    }
+    files.push(credentials_file(credentials_path, profile.as_deref()));
+
+    files.retain(|x| x.exists());
+    files
+}
+
+/// Settings of profile file are applied over base settings file. Profile file is required if
+/// profile is selected
+fn read_settings(config_path: &str, profile: Option<&str>) -> Result<String> {
     let settings = read_to_string(config_path)
         .with_context(|| format!("Unable load settings file: {}", config_path))?;
-    let credentials = read_to_string(credentials_path)
-        .with_context(|| format!("Unable load credentials file: {}", credentials_path))?;
+    let profile = match profile {
+        Some(profile) => profile,
+        None => return Ok(settings),
+    };
 
-    parse_settings(&settings, &credentials)
+    let profile_path = profile_path(config_path, profile);
+    let profile_settings = read_to_string(&profile_path).with_context(|| {
+        format!(
+            "Unable load settings file of profile '{profile}': {}",
+            profile_path.display()
+        )
+    })?;
+
+    let mut settings: Document = settings.parse().context("Unable parse settings")?;
+    let profile_settings: Document = profile_settings
+        .parse()
+        .with_context(|| format!("Unable parse settings of profile '{profile}'"))?;
+    apply_profile(settings.as_table_mut(), profile_settings.as_table());
+
+    log::info!("Settings of profile '{profile}' are applied");
+    Ok(settings.to_string())
+}
+
+/// Tables are merged key by key. Other values, including arrays and arrays of tables like
+/// `core.exchanges`, are replaced by profile values entirely
+fn apply_profile(base: &mut dyn TableLike, profile: &dyn TableLike) {
+    for (key, item) in profile.iter() {
+        match (
+            base.get_mut(key).and_then(Item::as_table_like_mut),
+            item.as_table_like(),
+        ) {
+            (Some(base), Some(profile)) => apply_profile(base, profile),
+            _ => {
+                let _ = base.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+/// Credentials file of profile, e.g. `credentials.paper.toml`, is used if it exists
+fn credentials_file(credentials_path: &str, profile: Option<&str>) -> PathBuf {
+    profile
+        .map(|x| profile_path(credentials_path, x))
+        .filter(|x| x.exists())
+        .unwrap_or_else(|| PathBuf::from(credentials_path))
+}
+
+/// Credentials file isn't needed if credentials are received from secrets provider
+fn read_credentials(
+    settings: &str,
+    credentials_path: &str,
+    profile: Option<&str>,
+) -> Result<String> {
+    let settings: Document = settings.parse().context("Unable parse settings")?;
+    if uses_secrets_provider(&settings) {
+        return Ok(String::new());
+    }
+
+    let credentials_path = credentials_file(credentials_path, profile);
+    read_to_string(&credentials_path).with_context(|| {
+        format!(
+            "Unable load credentials file: {}",
+            credentials_path.display()
+        )
+    })
 }
 
 pub fn load_pretty_settings<StrategySettings>(
@@ -44,10 +165,11 @@ where
             config_path,
             credentials_path,
         } => {
-            let settings = read_to_string(&config_path)
-                .with_expect(|| format!("Unable load settings file: {}", config_path));
-            let credentials = read_to_string(&credentials_path)
-                .with_expect(|| format!("Unable load credentials file: {}", credentials_path));
+            let profile = selected_profile();
+            let settings =
+                read_settings(&config_path, profile.as_deref()).expect("Failed to read settings");
+            let credentials = read_credentials(&settings, &credentials_path, profile.as_deref())
+                .expect("Failed to read credentials");
 
             let settings =
                 parse_toml_settings(&settings, &credentials).expect("Failed to parse toml file");
@@ -65,8 +187,21 @@ where
 {
     let settings =
         parse_toml_settings(settings, credentials).context("Unable parse toml settings")?;
-    toml_edit::de::from_document::<AppSettings<TSettings>>(settings)
-        .context("Unable parse combined settings")
+    let settings = toml_edit::de::from_document::<AppSettings<TSettings>>(settings)
+        .context("Unable parse combined settings")?;
+
+    for exchange in &settings.core.exchanges {
+        if let Some(order_rate_limits) = &exchange.order_rate_limits {
+            order_rate_limits.validate().with_context(|| {
+                format!(
+                    "Invalid order rate limits of {}",
+                    exchange.exchange_account_id
+                )
+            })?;
+        }
+    }
+
+    Ok(settings)
 }
 
 pub fn save_settings(settings: &str, config_path: &str, credentials_path: &str) -> Result<()> {
@@ -78,14 +213,30 @@ pub fn save_settings(settings: &str, config_path: &str, credentials_path: &str)
     let exchanges = get_exchanges_mut(&mut serialized_settings)
         .ok_or_else(|| anyhow!("Unable to get core.exchanges array from gotten settings"))?;
     for exchange_settings in exchanges.iter_mut() {
+        if is_watch_only(exchange_settings) {
+            let _ = exchange_settings.remove(API_KEY);
+            let _ = exchange_settings.remove(SECRET_KEY);
+            let _ = exchange_settings.remove(API_PASSPHRASE);
+            continue;
+        }
+
         let (exchange_account_id, api_key, secret_key) = get_credentials_data(exchange_settings)
             .ok_or_else(|| anyhow!("Unable to get credentials data for exchange"))?;
 
-        let creds = hashmap![
+        let mut creds = hashmap![
             API_KEY => api_key,
             SECRET_KEY => secret_key
         ];
 
+        // passphrase is needed only for some exchanges
+        if let Some(api_passphrase) = exchange_settings
+            .remove(API_PASSPHRASE)
+            .and_then(|x| x.as_str().map(str::to_owned))
+            .filter(|x| !x.is_empty())
+        {
+            let _ = creds.insert(API_PASSPHRASE, api_passphrase);
+        }
+
         credentials_per_exchange.insert(exchange_account_id, creds);
 
         // Remove credentials from main config
@@ -105,11 +256,12 @@ pub fn save_settings(settings: &str, config_path: &str, credentials_path: &str)
 
 fn parse_toml_settings(settings: &str, credentials: &str) -> Result<Document> {
     let mut settings: Document = settings.parse().context("Unable parse settings")?;
+    let is_secrets_provider_used = uses_secrets_provider(&settings);
 
     let exchanges = get_exchanges_mut(&mut settings)
         .context("Unable to get 'core.exchanges' array from gotten settings")?;
 
-    if !exchanges.is_empty() {
+    if !exchanges.is_empty() && !is_secrets_provider_used {
         let credentials: Document = credentials.parse()?;
         let credentials = credentials.as_table();
 
@@ -124,6 +276,13 @@ fn parse_toml_settings(settings: &str, credentials: &str) -> Result<Document> {
             )
                 })?;
 
+            if is_watch_only(exchange) {
+                // watch-only accounts don't need credentials to receive market data
+                exchange.insert(API_KEY, value(""));
+                exchange.insert(SECRET_KEY, value(""));
+                continue;
+            }
+
             let api_key = credentials
                 .get(exchange_account_id)
                 .and_then(|v| v.get(API_KEY))
@@ -143,14 +302,45 @@ fn parse_toml_settings(settings: &str, credentials: &str) -> Result<Document> {
                 bail!("Unable to parse settings: api or secret key is empty")
             }
 
+            // passphrase is needed only for some exchanges
+            let api_passphrase = credentials
+                .get(exchange_account_id)
+                .and_then(|v| v.get(API_PASSPHRASE))
+                .and_then(|v| v.as_str());
+
             exchange.insert(API_KEY, value(api_key));
             exchange.insert(SECRET_KEY, value(secret_key));
+            if let Some(api_passphrase) = api_passphrase {
+                exchange.insert(API_PASSPHRASE, value(api_passphrase));
+            }
+        }
+    }
+
+    if is_secrets_provider_used {
+        // credentials are set after loading by `secrets::resolve_credentials`
+        for exchange in exchanges.iter_mut() {
+            exchange.insert(API_KEY, value(""));
+            exchange.insert(SECRET_KEY, value(""));
         }
     }
 
     Ok(settings)
 }
 
+fn uses_secrets_provider(settings: &Document) -> bool {
+    settings
+        .get("core")
+        .and_then(|x| x.get("secrets"))
+        .is_some()
+}
+
+fn is_watch_only(exchange_settings: &Table) -> bool {
+    exchange_settings
+        .get(IS_WATCH_ONLY)
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
 fn get_credentials_data(exchange_settings: &Table) -> Option<(String, String, String)> {
     let exchange_account_id = exchange_settings
         .get(EXCHANGE_ACCOUNT_ID)?
@@ -171,3 +361,155 @@ fn get_exchanges_mut(serialized: &mut Document) -> Option<&mut ArrayOfTables> {
         .get_mut("exchanges")?
         .as_array_of_tables_mut()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Deserialize)]
+    struct TestStrategySettings {}
+
+    const SETTINGS: &str = r#"
+[strategy]
+
+[[core.exchanges]]
+exchange_account_id = "Binance_0"
+is_margin_trading = false
+request_trades = false
+subscribe_to_market_data = true
+websocket_channels = ["depth"]
+
+[[core.exchanges]]
+exchange_account_id = "Bitmex_0"
+is_margin_trading = false
+request_trades = false
+subscribe_to_market_data = true
+is_watch_only = true
+websocket_channels = []
+"#;
+
+    #[test]
+    fn watch_only_exchange_does_not_require_credentials() {
+        let credentials = r#"
+[Binance_0]
+api_key = "key"
+secret_key = "secret"
+"#;
+
+        let settings =
+            parse_settings::<TestStrategySettings>(SETTINGS, credentials).expect("in test");
+
+        let exchanges = &settings.core.exchanges;
+        assert!(!exchanges[0].is_watch_only);
+        assert_eq!(exchanges[0].api_key, "key");
+        assert!(exchanges[1].is_watch_only);
+        assert_eq!(exchanges[1].api_key, "");
+    }
+
+    #[test]
+    fn api_passphrase_is_loaded_from_credentials() {
+        let credentials = r#"
+[Binance_0]
+api_key = "key"
+secret_key = "secret"
+api_passphrase = "passphrase"
+"#;
+
+        let settings =
+            parse_settings::<TestStrategySettings>(SETTINGS, credentials).expect("in test");
+
+        assert_eq!(settings.core.exchanges[0].api_passphrase, "passphrase");
+        assert_eq!(settings.core.exchanges[1].api_passphrase, "");
+    }
+
+    #[test]
+    fn credentials_are_not_required_with_secrets_provider() {
+        let settings = format!("{SETTINGS}\n[core.secrets.provider]\ntype = \"env\"\n");
+
+        let settings = parse_settings::<TestStrategySettings>(&settings, "").expect("in test");
+
+        assert!(settings.core.secrets.is_some());
+        assert_eq!(settings.core.exchanges[0].api_key, "");
+    }
+
+    #[test]
+    fn profile_overrides_base_settings() {
+        let mut settings: Document = r#"
+[strategy]
+spread = 1
+max_amount = 2
+
+[core.risk]
+max_position_amount = 10
+
+[[core.exchanges]]
+exchange_account_id = "Binance_0"
+"#
+        .parse()
+        .expect("in test");
+        let profile: Document = r#"
+[strategy]
+spread = 3
+
+[[core.exchanges]]
+exchange_account_id = "Simulated_0"
+"#
+        .parse()
+        .expect("in test");
+
+        apply_profile(settings.as_table_mut(), profile.as_table());
+
+        assert_eq!(settings["strategy"]["spread"].as_integer(), Some(3));
+        assert_eq!(settings["strategy"]["max_amount"].as_integer(), Some(2));
+        assert_eq!(
+            settings["core"]["risk"]["max_position_amount"].as_integer(),
+            Some(10)
+        );
+        let exchanges = get_exchanges_mut(&mut settings).expect("in test");
+        assert_eq!(exchanges.len(), 1);
+        assert_eq!(
+            exchanges
+                .get(0)
+                .and_then(|x| x[EXCHANGE_ACCOUNT_ID].as_str()),
+            Some("Simulated_0")
+        );
+    }
+
+    #[test]
+    fn profile_file_is_placed_next_to_base_one() {
+        assert_eq!(
+            profile_path("configs/config.toml", "paper"),
+            PathBuf::from("configs/config.paper.toml")
+        );
+    }
+
+    #[test]
+    fn order_rate_limit_without_refill_is_rejected() {
+        let settings = |refill_per_second| {
+            format!(
+                "{SETTINGS}\n[core.exchanges.order_rate_limits.create]\ncapacity = 5\nrefill_per_second = {refill_per_second}\n"
+            )
+        };
+        let credentials = r#"
+[Binance_0]
+api_key = "key"
+secret_key = "secret"
+"#;
+
+        let settings_with_refill = settings("0.5");
+        let result = parse_settings::<TestStrategySettings>(&settings_with_refill, credentials);
+        assert!(result.is_ok());
+
+        let settings_without_refill = settings("0");
+        let result = parse_settings::<TestStrategySettings>(&settings_without_refill, credentials);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn trading_exchange_requires_credentials() {
+        let result = parse_settings::<TestStrategySettings>(SETTINGS, "");
+
+        assert!(result.is_err());
+    }
+}
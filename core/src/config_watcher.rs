@@ -0,0 +1,474 @@
+use std::fmt::Debug;
+use std::fs;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::logger::print_info;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::config::{settings_files, try_load_settings};
+use crate::infrastructure::spawn_by_timer;
+use crate::lifecycle::launcher::check_exposure_markets;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::secrets::ExchangeCredentials;
+use crate::settings::{
+    AlertingSettings, AppSettings, ConfigReloadSettings, CoreSettings, ExchangeSettings,
+    RiskSettings,
+};
+
+/// Result of applying changed settings file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConfigReloadReport {
+    /// Sections of settings applied without restart
+    pub applied: Vec<String>,
+    /// Changes which aren't applied, because they require restart or reconnection
+    pub rejected: Vec<String>,
+}
+
+impl ConfigReloadReport {
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty() && self.rejected.is_empty()
+    }
+
+    fn print(&self) {
+        print_info("Changed settings file is reloaded");
+        for section in &self.applied {
+            print_info(format_args!("\tApplied: {section}"));
+        }
+        for change in &self.rejected {
+            print_info(format_args!("\tRejected: {change}"));
+        }
+    }
+}
+
+/// Sections of core settings which can be changed without restart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReloadableSection {
+    Risk,
+    Alerting,
+}
+
+impl ReloadableSection {
+    fn name(&self) -> &'static str {
+        match self {
+            ReloadableSection::Risk => "core.risk",
+            ReloadableSection::Alerting => "core.alerting",
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct CoreChanges {
+    reloadable: Vec<ReloadableSection>,
+    rejected: Vec<String>,
+}
+
+/// Watches settings and credentials files loaded at startup and applies their changes without
+/// restart: strategy settings are sent to `TradingEngine::subscribe_strategy_settings`
+/// receivers, risk limits and alerting settings are replaced in running services. Other changes,
+/// including credentials of exchange accounts, are rejected until restart
+pub struct ConfigWatcher<StrategySettings: Clone> {
+    config_path: String,
+    credentials_path: String,
+    engine_context: Weak<EngineContext>,
+    /// Settings loaded at startup with sections applied after it
+    current: Mutex<AppSettings<StrategySettings>>,
+    /// Modification times of settings and credentials files
+    modified: Mutex<Option<Vec<SystemTime>>>,
+    strategy_settings: Arc<watch::Sender<StrategySettings>>,
+}
+
+impl<StrategySettings> ConfigWatcher<StrategySettings>
+where
+    StrategySettings: Clone + Debug + DeserializeOwned + Serialize + Send + Sync + 'static,
+{
+    pub(crate) fn start(
+        settings: &ConfigReloadSettings,
+        config_path: String,
+        credentials_path: String,
+        current: AppSettings<StrategySettings>,
+        engine_context: &Arc<EngineContext>,
+        strategy_settings: Arc<watch::Sender<StrategySettings>>,
+    ) -> Arc<Self> {
+        let watcher = Arc::new(ConfigWatcher {
+            modified: Mutex::new(modified_times(&config_path, &credentials_path).ok()),
+            config_path,
+            credentials_path,
+            engine_context: Arc::downgrade(engine_context),
+            current: Mutex::new(current),
+            strategy_settings,
+        });
+
+        let interval = Duration::from_secs(settings.check_interval_secs.max(1));
+        spawn_by_timer(
+            "Watch settings file",
+            interval,
+            interval,
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            {
+                let watcher = watcher.clone();
+                move || {
+                    watcher.reload_if_changed();
+                    async {}
+                }
+            },
+        );
+
+        log::info!(
+            "Settings file {} is watched for changes",
+            watcher.config_path
+        );
+        watcher
+    }
+
+    fn reload_if_changed(&self) {
+        let modified = match modified_times(&self.config_path, &self.credentials_path) {
+            Ok(modified) => modified,
+            Err(err) => {
+                log::warn!("Failed to check settings file: {err:?}");
+                return;
+            }
+        };
+        // changed files aren't read again until the next change even if they are invalid
+        if self.modified.lock().replace(modified.clone()) == Some(modified) {
+            return;
+        }
+
+        let mut settings = match try_load_settings::<StrategySettings>(
+            &self.config_path,
+            &self.credentials_path,
+        ) {
+            Ok(settings) => settings,
+            Err(err) => {
+                log::error!("Failed to reload settings, previous ones are used: {err:?}");
+                return;
+            }
+        };
+
+        if settings.core.secrets.is_some() {
+            // credentials of secrets provider aren't in files, they are rotated by
+            // `CredentialsRotation`
+            keep_credentials(&self.current.lock().core, &mut settings.core);
+        }
+
+        let engine_context = match self.engine_context.upgrade() {
+            Some(engine_context) => engine_context,
+            None => return,
+        };
+
+        let report = self.apply(&engine_context, settings);
+        if !report.is_empty() {
+            log::info!("Settings are reloaded: {report:?}");
+            report.print();
+        }
+    }
+
+    /// Applies reloadable changes of settings and reports all found changes
+    pub(crate) fn apply(
+        &self,
+        engine_context: &EngineContext,
+        settings: AppSettings<StrategySettings>,
+    ) -> ConfigReloadReport {
+        let mut current = self.current.lock();
+        let mut report = ConfigReloadReport::default();
+
+        if serde_json::to_value(&current.strategy).ok()
+            != serde_json::to_value(&settings.strategy).ok()
+        {
+            current.strategy = settings.strategy.clone();
+            let _ = self.strategy_settings.send_replace(settings.strategy);
+            report.applied.push("strategy".to_owned());
+        }
+
+        let changes = core_changes(&current.core, &settings.core);
+        report.rejected = changes.rejected;
+        for section in changes.reloadable {
+            let result = match section {
+                ReloadableSection::Risk => apply_risk(engine_context, &settings.core.risk),
+                ReloadableSection::Alerting => {
+                    apply_alerting(engine_context, &settings.core.alerting)
+                }
+            };
+
+            match result {
+                Ok(()) => {
+                    update_section(&mut current.core, &settings.core, section);
+                    report.applied.push(section.name().to_owned());
+                }
+                Err(err) => report.rejected.push(format!("{}: {err:#}", section.name())),
+            }
+        }
+
+        report
+    }
+}
+
+fn apply_risk(engine_context: &EngineContext, settings: &Option<RiskSettings>) -> Result<()> {
+    let settings = settings.as_ref().context("Risk settings are removed")?;
+    check_exposure_markets(settings, &engine_context.exchanges)?;
+    let risk_manager = engine_context
+        .exchanges
+        .iter()
+        .find_map(|x| x.risk_manager())
+        .context("Risk manager isn't started")?;
+
+    risk_manager.update_settings(settings.clone());
+    let balance_manager = engine_context.balance_manager.lock();
+    risk_manager.update_exposures(|exchange_account_id, currency_pair| {
+        balance_manager.net_position(exchange_account_id, currency_pair)
+    });
+
+    Ok(())
+}
+
+fn apply_alerting(
+    engine_context: &EngineContext,
+    settings: &Option<AlertingSettings>,
+) -> Result<()> {
+    let settings = settings.as_ref().context("Alerting settings are removed")?;
+    engine_context
+        .alert_service
+        .as_ref()
+        .context("Alert service isn't started")?
+        .update_settings(settings.clone());
+    Ok(())
+}
+
+/// Daily report isn't reloadable, so its settings stay the same as at startup
+fn update_section(current: &mut CoreSettings, new: &CoreSettings, section: ReloadableSection) {
+    match section {
+        ReloadableSection::Risk => current.risk = new.risk.clone(),
+        ReloadableSection::Alerting => {
+            let daily_report = current
+                .alerting
+                .as_ref()
+                .and_then(|x| x.daily_report.clone());
+            current.alerting = new
+                .alerting
+                .clone()
+                .map(|x| AlertingSettings { daily_report, ..x });
+        }
+    }
+}
+
+/// Splits changes of core settings into sections which can be applied without restart and
+/// descriptions of rejected changes
+fn core_changes(old: &CoreSettings, new: &CoreSettings) -> CoreChanges {
+    let mut changes = CoreChanges::default();
+    let (old_sections, new_sections) = match (sections(old), sections(new)) {
+        (Ok(old_sections), Ok(new_sections)) => (old_sections, new_sections),
+        (Err(err), _) | (_, Err(err)) => {
+            changes.rejected.push(format!("core: {err:#}"));
+            return changes;
+        }
+    };
+
+    let names = old_sections
+        .keys()
+        .chain(new_sections.keys())
+        .unique()
+        .sorted();
+    for name in names {
+        if old_sections.get(name) == new_sections.get(name) {
+            continue;
+        }
+
+        match name.as_str() {
+            "risk" => match (&old.risk, &new.risk) {
+                (Some(_), Some(_)) => changes.reloadable.push(ReloadableSection::Risk),
+                _ => changes.rejected.push(presence_changed(name)),
+            },
+            "alerting" => match (&old.alerting, &new.alerting) {
+                (Some(old), Some(new)) => {
+                    if old.daily_report != new.daily_report {
+                        changes
+                            .rejected
+                            .push("core.alerting.daily_report: restart is required".to_owned());
+                    }
+                    let without_daily_report = |x: &AlertingSettings| AlertingSettings {
+                        daily_report: None,
+                        ..x.clone()
+                    };
+                    if without_daily_report(old) != without_daily_report(new) {
+                        changes.reloadable.push(ReloadableSection::Alerting);
+                    }
+                }
+                _ => changes.rejected.push(presence_changed(name)),
+            },
+            "exchanges" => changes
+                .rejected
+                .extend(exchange_changes(&old.exchanges, &new.exchanges)),
+            _ => changes
+                .rejected
+                .push(format!("core.{name}: restart is required")),
+        }
+    }
+
+    changes
+}
+
+fn sections(settings: &CoreSettings) -> Result<serde_json::Map<String, serde_json::Value>> {
+    match serde_json::to_value(settings).context("Unable to compare settings")? {
+        serde_json::Value::Object(sections) => Ok(sections),
+        _ => unreachable!("CoreSettings is serialized as object"),
+    }
+}
+
+fn presence_changed(name: &str) -> String {
+    format!("core.{name}: section can't be added or removed without restart")
+}
+
+fn exchange_changes(old: &[ExchangeSettings], new: &[ExchangeSettings]) -> Vec<String> {
+    let account_ids = |exchanges: &[ExchangeSettings]| {
+        exchanges
+            .iter()
+            .map(|x| x.exchange_account_id.to_string())
+            .sorted()
+            .collect_vec()
+    };
+    if account_ids(old) != account_ids(new) {
+        return vec![
+            "core.exchanges: exchange accounts can't be added or removed without restart"
+                .to_owned(),
+        ];
+    }
+
+    let without_credentials = |x: &ExchangeSettings| ExchangeSettings {
+        api_key: String::new(),
+        secret_key: String::new(),
+        api_passphrase: String::new(),
+        ..x.clone()
+    };
+
+    let mut rejected = Vec::new();
+    for old in old {
+        let new = match new
+            .iter()
+            .find(|x| x.exchange_account_id == old.exchange_account_id)
+        {
+            Some(new) if new != old => new,
+            _ => continue,
+        };
+
+        let exchange_account_id = old.exchange_account_id;
+        if old.api_key != new.api_key
+            || old.secret_key != new.secret_key
+            || old.api_passphrase != new.api_passphrase
+        {
+            rejected.push(format!(
+                "core.exchanges.{exchange_account_id}: credentials are changed, reconnection is required"
+            ));
+        }
+        if without_credentials(old) != without_credentials(new) {
+            rejected.push(format!(
+                "core.exchanges.{exchange_account_id}: restart is required"
+            ));
+        }
+    }
+
+    rejected
+}
+
+fn keep_credentials(current: &CoreSettings, new: &mut CoreSettings) {
+    for exchange_settings in new.exchanges.iter_mut() {
+        if let Some(current) = current
+            .exchanges
+            .iter()
+            .find(|x| x.exchange_account_id == exchange_settings.exchange_account_id)
+        {
+            ExchangeCredentials::from_settings(current).apply_to(exchange_settings);
+        }
+    }
+}
+
+/// Files of selected profile are watched too
+fn modified_times(config_path: &str, credentials_path: &str) -> Result<Vec<SystemTime>> {
+    settings_files(config_path, credentials_path)
+        .iter()
+        .map(|path| {
+            fs::metadata(path)
+                .and_then(|x| x.modified())
+                .with_context(|| format!("getting modification time of {}", path.display()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{CandlesSettings, SlackSettings};
+    use rust_decimal_macros::dec;
+
+    fn alerting() -> AlertingSettings {
+        serde_json::from_str("{}").expect("valid alerting settings")
+    }
+
+    fn core_settings() -> CoreSettings {
+        CoreSettings {
+            risk: Some(RiskSettings::default()),
+            alerting: Some(alerting()),
+            exchanges: vec![ExchangeSettings::new_short(
+                "Binance_0".parse().expect("valid id"),
+                "key".to_owned(),
+                "secret".to_owned(),
+                false,
+            )],
+            ..CoreSettings::default()
+        }
+    }
+
+    #[test]
+    fn risk_limits_and_alerting_targets_are_reloadable() {
+        let old = core_settings();
+        let mut new = old.clone();
+        new.risk = Some(RiskSettings {
+            max_order_notional: Some(dec!(1000)),
+            ..RiskSettings::default()
+        });
+        new.alerting = Some(AlertingSettings {
+            slack: Some(SlackSettings {
+                webhook_url: "https://hooks.slack.com/services/1".to_owned(),
+            }),
+            ..alerting()
+        });
+
+        assert_eq!(
+            core_changes(&old, &new),
+            CoreChanges {
+                reloadable: vec![ReloadableSection::Alerting, ReloadableSection::Risk],
+                rejected: vec![],
+            }
+        );
+        assert_eq!(core_changes(&old, &old), CoreChanges::default());
+    }
+
+    #[test]
+    fn changes_requiring_restart_or_reconnection_are_rejected() {
+        let old = core_settings();
+        let mut new = old.clone();
+        new.exchanges[0].api_key = "rotated key".to_owned();
+        new.exchanges[0].request_trades = true;
+        new.risk = None;
+        new.candles = Some(CandlesSettings::default());
+
+        assert_eq!(
+            core_changes(&old, &new),
+            CoreChanges {
+                reloadable: vec![],
+                rejected: vec![
+                    "core.candles: restart is required".to_owned(),
+                    "core.exchanges.Binance_0: credentials are changed, reconnection is required"
+                        .to_owned(),
+                    "core.exchanges.Binance_0: restart is required".to_owned(),
+                    "core.risk: section can't be added or removed without restart".to_owned(),
+                ],
+            }
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+/// Delay before first reconnection attempt
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(100);
+
+/// Delays between reconnection attempts don't grow above this value
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Delays between reconnection attempts which are doubled after every failed attempt
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    initial_delay: Duration,
+    max_delay: Duration,
+    next_delay: Duration,
+}
+
+impl ReconnectBackoff {
+    pub fn new(initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            initial_delay,
+            max_delay,
+            next_delay: initial_delay,
+        }
+    }
+
+    /// Returns delay before next attempt and doubles the following one
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next_delay;
+        self.next_delay = (delay * 2).min(self.max_delay);
+        delay
+    }
+
+    /// Should be called after successful connection
+    pub fn reset(&mut self) {
+        self.next_delay = self.initial_delay;
+    }
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        Self::new(INITIAL_RECONNECT_DELAY, MAX_RECONNECT_DELAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_grows_up_to_max() {
+        let mut backoff = ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(5));
+
+        let delays = (0..5)
+            .map(|_| backoff.next_delay().as_secs())
+            .collect::<Vec<_>>();
+
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+    }
+
+    #[test]
+    fn delay_is_reset_after_connection() {
+        let mut backoff = ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        backoff.next_delay();
+        backoff.next_delay();
+
+        backoff.reset();
+
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+}
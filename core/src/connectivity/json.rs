@@ -0,0 +1,60 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Deserializes websocket frame. Deserialized value can borrow strings from the frame, so market
+/// data can be parsed without allocations. Frame is parsed by `simd-json` if feature `simd-json`
+/// is enabled. It parses in place, so the frame is copied into `buffer` which should outlive the
+/// value. `buffer` isn't used by `serde_json`. `simd-json` is faster only if build enables SIMD
+/// instructions of target CPU, e.g. with `-C target-cpu=native`
+pub fn parse_frame<'a, T: Deserialize<'a>>(frame: &'a str, buffer: &'a mut Vec<u8>) -> Result<T> {
+    #[cfg(feature = "simd-json")]
+    {
+        buffer.clear();
+        buffer.extend_from_slice(frame.as_bytes());
+        simd_json::serde::from_slice(buffer).context("Unable to parse websocket frame")
+    }
+
+    #[cfg(not(feature = "simd-json"))]
+    {
+        let _ = buffer;
+        serde_json::from_str(frame).context("Unable to parse websocket frame")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Level<'a>(&'a str, &'a str);
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Depth<'a> {
+        #[serde(borrow)]
+        stream: &'a str,
+        #[serde(borrow)]
+        bids: Vec<Level<'a>>,
+    }
+
+    #[test]
+    fn frame_is_parsed_with_borrowed_strings() {
+        let frame = r#"{"stream":"btcusdt@depth20","bids":[["20000.1","0.5"]],"lastUpdateId":1}"#;
+        let mut buffer = Vec::new();
+
+        let depth: Depth = parse_frame(frame, &mut buffer).expect("in test");
+
+        assert_eq!(
+            depth,
+            Depth {
+                stream: "btcusdt@depth20",
+                bids: vec![Level("20000.1", "0.5")],
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_frame_is_error() {
+        let mut buffer = Vec::new();
+        assert!(parse_frame::<Depth>(r#"{"stream":"#, &mut buffer).is_err());
+    }
+}
@@ -1,7 +1,10 @@
+use anyhow::{anyhow, Context};
 use std::fmt::{Display, Formatter};
 use thiserror::Error;
 use url::Url;
 
+mod backoff;
+pub mod json;
 mod websocket;
 mod websocket_connection;
 
@@ -21,6 +24,8 @@ pub enum ConnectivityError {
 
 pub type Result<T> = std::result::Result<T, ConnectivityError>;
 
+/// Exchange clients use main connection for market data and secondary one for private order flow.
+/// Clients of exchanges without separate private streams may use only main connection
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum WebSocketRole {
     Main,
@@ -45,6 +50,47 @@ impl WebSocketParams {
     pub fn new(url: Url) -> Self {
         WebSocketParams { url }
     }
+
+    /// Replaces scheme, host and port of url keeping path and query specific for exchange,
+    /// e.g. to connect to endpoint in another region
+    pub fn with_host(mut self, host: &str) -> anyhow::Result<Self> {
+        let host =
+            Url::parse(host).with_context(|| format!("Unable to parse websocket host {host}"))?;
+
+        self.url
+            .set_scheme(host.scheme())
+            .map_err(|_| anyhow!("Unable to set scheme of websocket host {host}"))?;
+        self.url
+            .set_host(host.host_str())
+            .with_context(|| format!("Unable to set websocket host {host}"))?;
+        self.url
+            .set_port(host.port())
+            .map_err(|_| anyhow!("Unable to set port of websocket host {host}"))?;
+
+        Ok(self)
+    }
 }
 
-pub use websocket::{websocket_open, WsSender};
+pub use backoff::ReconnectBackoff;
+pub use websocket::{websocket_open, WsReceivers, WsSender};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_host_of_websocket_url() {
+        let params = WebSocketParams::new(
+            Url::parse("wss://stream.binance.com:9443/ws/listen_key").expect("in test"),
+        );
+
+        let params = params
+            .with_host("wss://ws-region.example.com")
+            .expect("in test");
+
+        assert_eq!(
+            params.url.as_str(),
+            "wss://ws-region.example.com/ws/listen_key"
+        );
+    }
+}
@@ -1,9 +1,6 @@
 use super::websocket_connection::open_connection;
 use super::{ConnectivityError, Result, WebSocketParams, WebSocketRole};
-use crate::infrastructure::spawn_future;
-use futures::FutureExt;
 use mmb_domain::market::ExchangeAccountId;
-use mmb_utils::infrastructure::SpawnFutureFlags;
 use tokio::sync::mpsc;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_util::sync::{CancellationToken, DropGuard as CancellationTokenDropGuard};
@@ -36,11 +33,28 @@ impl WsSender {
     }
 }
 
+/// Receive ends of websocket connections. Connections are read independently, so heavy market
+/// data traffic of main connection can't delay messages of secondary one
+pub struct WsReceivers {
+    pub main: mpsc::UnboundedReceiver<String>,
+    pub secondary: Option<mpsc::UnboundedReceiver<String>>,
+}
+
+impl WsReceivers {
+    pub fn into_vec(self) -> Vec<(WebSocketRole, mpsc::UnboundedReceiver<String>)> {
+        let mut receivers = vec![(WebSocketRole::Main, self.main)];
+        if let Some(secondary) = self.secondary {
+            receivers.push((WebSocketRole::Secondary, secondary));
+        }
+        receivers
+    }
+}
+
 pub async fn websocket_open(
     exchange_account_id: ExchangeAccountId,
     main: WebSocketParams,
     secondary: Option<WebSocketParams>,
-) -> Result<(WsSender, mpsc::UnboundedReceiver<String>)> {
+) -> Result<(WsSender, WsReceivers)> {
     log::trace!("Websocket '{}' connecting", exchange_account_id);
     let ret = if let Some(secondary) = secondary {
         connect_both_parallel(main, secondary, exchange_account_id).await?
@@ -55,7 +69,7 @@ async fn connect_both_parallel(
     main: WebSocketParams,
     secondary: WebSocketParams,
     exchange_account_id: ExchangeAccountId,
-) -> Result<(WsSender, mpsc::UnboundedReceiver<String>)> {
+) -> Result<(WsSender, WsReceivers)> {
     let cancel = CancellationToken::new();
     let (main, secondary) = tokio::join!(
         open_connection(
@@ -75,48 +89,16 @@ async fn connect_both_parallel(
     match (main, secondary) {
         (Err(e), _) | (_, Err(e)) => Err(e),
         (Ok(main), Ok(secondary)) => {
-            let chan = mpsc::unbounded_channel();
             let sender = WsSender {
                 main_sender: main.0,
                 secondary_sender: Some(secondary.0),
                 _cancel: cancel.drop_guard(),
             };
-            spawn_future(
-                "spawn combined_channel_reader",
-                SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
-                async move {
-                    combined_channel_reader(main.1, secondary.1, chan.0).await;
-                    Ok(())
-                }
-                .boxed(),
-            );
-            Ok((sender, chan.1))
-        }
-    }
-}
-
-/// Forward input from two channels to single output
-async fn combined_channel_reader(
-    mut main: mpsc::UnboundedReceiver<String>,
-    mut secondary: mpsc::UnboundedReceiver<String>,
-    tx: mpsc::UnboundedSender<String>,
-) {
-    loop {
-        // init message from main or secondary channel
-        // finish processing when one of the channels closed
-        let message = tokio::select! {
-            m = main.recv() => m,
-            m = secondary.recv() => m
-        };
-
-        let message = match message {
-            None => break, // loop
-            Some(m) => m,
-        };
-
-        if tx.send(message).is_err() {
-            // can't forward message, no receiver
-            break;
+            let receivers = WsReceivers {
+                main: main.1,
+                secondary: Some(secondary.1),
+            };
+            Ok((sender, receivers))
         }
     }
 }
@@ -125,7 +107,7 @@ async fn combined_channel_reader(
 async fn connect_only_main(
     params: WebSocketParams,
     exchange_account_id: ExchangeAccountId,
-) -> Result<(WsSender, mpsc::UnboundedReceiver<String>)> {
+) -> Result<(WsSender, WsReceivers)> {
     let cancel = CancellationToken::new();
     let (tx, rx) = open_connection(
         exchange_account_id,
@@ -139,5 +121,9 @@ async fn connect_only_main(
         secondary_sender: None,
         _cancel: cancel.drop_guard(),
     };
-    Ok((sender, rx))
+    let receivers = WsReceivers {
+        main: rx,
+        secondary: None,
+    };
+    Ok((sender, receivers))
 }
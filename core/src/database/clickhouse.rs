@@ -0,0 +1,546 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use mmb_domain::events::{ExchangeEvent, TradesEvent};
+use mmb_domain::market::{CurrencyCode, CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::event::OrderEvent;
+use mmb_domain::order::history::{order_history_records, OrderFillRecord};
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, ExchangeOrderId, OrderFillRole, OrderSide, Price,
+};
+use mmb_domain::order_book::event::{EventType, OrderBookEvent};
+use mmb_domain::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+
+use crate::infrastructure::spawn_future;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::settings::ClickHouseSettings;
+
+type HttpClient = Client<HttpsConnector<HttpConnector>>;
+
+const ORDER_BOOKS_TABLE: &str = "order_books";
+const TRADES_TABLE: &str = "trades";
+const FILLS_TABLE: &str = "fills";
+
+#[derive(Debug, Serialize)]
+struct OrderBookRow {
+    time: DateTime,
+    exchange_account_id: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    is_snapshot: bool,
+    ask_prices: Vec<Price>,
+    ask_amounts: Vec<Amount>,
+    bid_prices: Vec<Price>,
+    bid_amounts: Vec<Amount>,
+}
+
+#[derive(Debug, Serialize)]
+struct TradeRow {
+    time: DateTime,
+    receipt_time: DateTime,
+    exchange_account_id: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    trade_id: String,
+    price: Price,
+    amount: Amount,
+    side: OrderSide,
+}
+
+#[derive(Debug, Serialize)]
+struct FillRow {
+    time: DateTime,
+    client_order_id: ClientOrderId,
+    exchange_order_id: Option<ExchangeOrderId>,
+    exchange_account_id: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    side: OrderSide,
+    strategy_name: String,
+    trade_id: Option<String>,
+    price: Price,
+    amount: Amount,
+    role: OrderFillRole,
+    commission_currency_code: CurrencyCode,
+    commission_amount: Amount,
+}
+
+/// Rows of table in `JSONEachRow` format
+#[derive(Debug)]
+struct TableBatch {
+    table: &'static str,
+    rows: String,
+    rows_count: usize,
+}
+
+impl TableBatch {
+    fn new(table: &'static str) -> Self {
+        TableBatch {
+            table,
+            rows: String::new(),
+            rows_count: 0,
+        }
+    }
+
+    fn push(&mut self, row: &impl Serialize) -> Result<()> {
+        let json = serde_json::to_string(row)
+            .with_context(|| format!("serializing row of ClickHouse table {}", self.table))?;
+        self.rows.push_str(&json);
+        self.rows.push('\n');
+        self.rows_count += 1;
+        Ok(())
+    }
+
+    fn take(&mut self) -> Option<TableBatch> {
+        match self.rows_count {
+            0 => None,
+            _ => Some(std::mem::replace(self, TableBatch::new(self.table))),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ClickHouseSinkStats {
+    pub inserted_rows: u64,
+    /// Rows which weren't inserted because of ClickHouse errors or full queue of batches
+    pub lost_rows: u64,
+}
+
+/// Writes order book events, public trades and order fills to ClickHouse tables `order_books`,
+/// `trades` and `fills` by batched inserts. Insertion is done in background, so slow ClickHouse
+/// doesn't delay handling of events
+pub struct ClickHouseSink {
+    settings: ClickHouseSettings,
+    client: HttpClient,
+    inserted_rows: AtomicU64,
+    lost_rows: AtomicU64,
+}
+
+impl ClickHouseSink {
+    pub fn start(
+        settings: ClickHouseSettings,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Arc<Self> {
+        let https = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        let (batches_tx, batches_rx) = mpsc::channel(settings.max_pending_batches.max(1));
+
+        let sink = Arc::new(ClickHouseSink {
+            settings,
+            client: Client::builder().build::<_, Body>(https),
+            inserted_rows: AtomicU64::new(0),
+            lost_rows: AtomicU64::new(0),
+        });
+
+        spawn_future(
+            "Collect rows for ClickHouse",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            sink.clone().collect_rows(events_receiver, batches_tx),
+        );
+        spawn_future(
+            "Insert rows to ClickHouse",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            sink.clone().insert_batches(batches_rx),
+        );
+
+        sink
+    }
+
+    pub fn stats(&self) -> ClickHouseSinkStats {
+        ClickHouseSinkStats {
+            inserted_rows: self.inserted_rows.load(Ordering::Relaxed),
+            lost_rows: self.lost_rows.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn collect_rows(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+        batches_tx: mpsc::Sender<TableBatch>,
+    ) -> Result<()> {
+        let mut batches = [
+            TableBatch::new(ORDER_BOOKS_TABLE),
+            TableBatch::new(TRADES_TABLE),
+            TableBatch::new(FILLS_TABLE),
+        ];
+        // order book updates contain only changed levels, so they are applied to local order
+        // books and the best levels of them are written
+        let mut local_snapshots = LocalSnapshotsService::default();
+        let mut flush_timer = tokio::time::interval(Duration::from_millis(
+            self.settings.flush_interval_millis.max(1),
+        ));
+
+        // rows are collected even during graceful shutdown until events channel is closed
+        loop {
+            tokio::select! {
+                event = events_receiver.recv() => match event {
+                    Ok(event) => {
+                        if let Err(err) = self.add_rows(&event, &mut local_snapshots, &mut batches) {
+                            log::error!("Failed to prepare rows for ClickHouse: {err:?}");
+                        }
+                        for batch in &mut batches {
+                            if batch.rows_count >= self.settings.batch_size {
+                                self.send_batch(batch, &batches_tx);
+                            }
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::error!("ClickHouseSink skipped {skipped} events")
+                    }
+                    Err(RecvError::Closed) => {
+                        for batch in &mut batches {
+                            self.send_batch(batch, &batches_tx);
+                        }
+                        return Ok(());
+                    }
+                },
+                _ = flush_timer.tick() => {
+                    for batch in &mut batches {
+                        self.send_batch(batch, &batches_tx);
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_rows(
+        &self,
+        event: &ExchangeEvent,
+        local_snapshots: &mut LocalSnapshotsService,
+        batches: &mut [TableBatch; 3],
+    ) -> Result<()> {
+        let [order_books, trades, fills] = batches;
+        match event {
+            ExchangeEvent::OrderBookEvent(event) => match local_snapshots.update(event) {
+                Some(market_account_id) => {
+                    let snapshot =
+                        local_snapshots.get_snapshot_expected(market_account_id.market_id());
+                    order_books.push(&order_book_row(event, snapshot, self.settings.book_depth))
+                }
+                // levels which aren't changed by update are unknown until snapshot
+                None => Ok(()),
+            },
+            ExchangeEvent::Trades(event) => trade_rows(event).try_for_each(|x| trades.push(&x)),
+            ExchangeEvent::OrderEvent(event) => match fill_row(event) {
+                Some(row) => fills.push(&row),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn send_batch(&self, batch: &mut TableBatch, batches_tx: &mpsc::Sender<TableBatch>) {
+        let Some(batch) = batch.take() else {
+            return;
+        };
+
+        if let Err(mpsc::error::TrySendError::Full(batch)) = batches_tx.try_send(batch) {
+            log::error!(
+                "Queue of ClickHouse batches is full, {} rows of table {} are dropped",
+                batch.rows_count,
+                batch.table
+            );
+            let _ = self
+                .lost_rows
+                .fetch_add(batch.rows_count as u64, Ordering::Relaxed);
+        }
+    }
+
+    async fn insert_batches(
+        self: Arc<Self>,
+        mut batches_rx: mpsc::Receiver<TableBatch>,
+    ) -> Result<()> {
+        while let Some(batch) = batches_rx.recv().await {
+            let rows_count = batch.rows_count as u64;
+            let table = batch.table;
+            match self.insert(batch).await {
+                Ok(()) => {
+                    let _ = self.inserted_rows.fetch_add(rows_count, Ordering::Relaxed);
+                }
+                Err(err) => {
+                    log::error!(
+                        "Failed to insert {rows_count} rows to ClickHouse table {table}: {err:?}"
+                    );
+                    let _ = self.lost_rows.fetch_add(rows_count, Ordering::Relaxed);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn insert(&self, batch: TableBatch) -> Result<()> {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri(insert_uri(&self.settings, batch.table));
+        if let Some(user) = &self.settings.user {
+            builder = builder.header("X-ClickHouse-User", user);
+        }
+        if let Some(password) = &self.settings.password {
+            builder = builder.header("X-ClickHouse-Key", password);
+        }
+        let request = builder
+            .body(Body::from(batch.rows))
+            .context("building ClickHouse request")?;
+
+        let response = self
+            .client
+            .request(request)
+            .await
+            .context("sending ClickHouse request")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let content = hyper::body::to_bytes(response.into_body())
+                .await
+                .context("reading ClickHouse response")?;
+            bail!(
+                "ClickHouse responded with {status}: {}",
+                String::from_utf8_lossy(&content)
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn insert_uri(settings: &ClickHouseSettings, table: &str) -> String {
+    let query = format!(
+        "INSERT INTO {}.{table} FORMAT JSONEachRow",
+        settings.database
+    );
+    let params = form_urlencoded::Serializer::new(String::new())
+        .append_pair("query", &query)
+        .append_pair("date_time_input_format", "best_effort")
+        .finish();
+    format!("{}/?{params}", settings.url.trim_end_matches('/'))
+}
+
+/// Row with the best levels of local order book after applying of event to it
+fn order_book_row(
+    event: &OrderBookEvent,
+    snapshot: &LocalOrderBookSnapshot,
+    depth: usize,
+) -> OrderBookRow {
+    let (ask_prices, ask_amounts) = levels(snapshot.get_asks_price_levels(), depth);
+    let (bid_prices, bid_amounts) = levels(snapshot.get_bids_price_levels(), depth);
+    OrderBookRow {
+        time: event.creation_time,
+        exchange_account_id: event.exchange_account_id,
+        currency_pair: event.currency_pair,
+        is_snapshot: matches!(event.event_type, EventType::Snapshot),
+        ask_prices,
+        ask_amounts,
+        bid_prices,
+        bid_amounts,
+    }
+}
+
+/// The best levels of order book side, levels should be ordered from the best price
+fn levels<'a>(
+    levels: impl Iterator<Item = (&'a Price, &'a Amount)>,
+    depth: usize,
+) -> (Vec<Price>, Vec<Amount>) {
+    levels
+        .take(depth)
+        .map(|(price, amount)| (*price, *amount))
+        .unzip()
+}
+
+fn trade_rows(event: &TradesEvent) -> impl Iterator<Item = TradeRow> + '_ {
+    event.trades.iter().map(|trade| TradeRow {
+        time: trade.transaction_time,
+        receipt_time: event.receipt_time,
+        exchange_account_id: event.exchange_account_id,
+        currency_pair: event.currency_pair,
+        trade_id: trade.trade_id.to_string(),
+        price: trade.price,
+        amount: trade.quantity,
+        side: trade.side,
+    })
+}
+
+fn fill_row(event: &OrderEvent) -> Option<FillRow> {
+    let (_, fill) = order_history_records(event);
+    let OrderFillRecord {
+        client_order_id,
+        exchange_order_id,
+        exchange_account_id,
+        currency_pair,
+        side,
+        strategy_name,
+        fill,
+    } = fill?;
+
+    Some(FillRow {
+        time: fill.receive_time(),
+        client_order_id,
+        exchange_order_id,
+        exchange_account_id,
+        currency_pair,
+        side,
+        strategy_name,
+        trade_id: fill.trade_id().map(|x| x.to_string()),
+        price: fill.price(),
+        amount: fill.amount(),
+        role: fill.role(),
+        commission_currency_code: fill.commission_currency_code(),
+        commission_amount: fill.commission_amount(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use mmb_domain::events::{Trade, TradeId};
+    use mmb_domain::order_book::order_book_data::OrderBookData;
+    use rust_decimal_macros::dec;
+
+    fn settings() -> ClickHouseSettings {
+        ClickHouseSettings {
+            url: "http://localhost:8123/".to_owned(),
+            database: "mmb".to_owned(),
+            user: None,
+            password: None,
+            batch_size: 2,
+            flush_interval_millis: 1000,
+            max_pending_batches: 1,
+            book_depth: 2,
+        }
+    }
+
+    fn order_book_event(
+        event_type: EventType,
+        asks: &[(Price, Amount)],
+        bids: &[(Price, Amount)],
+    ) -> OrderBookEvent {
+        OrderBookEvent::new(
+            Utc::now(),
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            String::new(),
+            event_type,
+            Arc::new(OrderBookData::new(
+                asks.iter().copied().collect(),
+                bids.iter().copied().collect(),
+            )),
+        )
+    }
+
+    fn order_book_rows(events: &[OrderBookEvent]) -> Vec<OrderBookRow> {
+        let mut local_snapshots = LocalSnapshotsService::default();
+        events
+            .iter()
+            .filter_map(|event| {
+                let market_id = local_snapshots.update(event)?.market_id();
+                let snapshot = local_snapshots.get_snapshot_expected(market_id);
+                Some(order_book_row(event, snapshot, settings().book_depth))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn order_book_row_contains_best_levels() {
+        let event = order_book_event(
+            EventType::Snapshot,
+            &[
+                (dec!(103), dec!(1)),
+                (dec!(101), dec!(2)),
+                (dec!(102), dec!(3)),
+            ],
+            &[
+                (dec!(98), dec!(4)),
+                (dec!(100), dec!(5)),
+                (dec!(99), dec!(6)),
+            ],
+        );
+
+        let rows = order_book_rows(&[event]);
+
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert!(row.is_snapshot);
+        assert_eq!(row.ask_prices, vec![dec!(101), dec!(102)]);
+        assert_eq!(row.ask_amounts, vec![dec!(2), dec!(3)]);
+        assert_eq!(row.bid_prices, vec![dec!(100), dec!(99)]);
+        assert_eq!(row.bid_amounts, vec![dec!(5), dec!(6)]);
+    }
+
+    #[test]
+    fn order_book_update_row_contains_best_levels_of_local_order_book() {
+        let snapshot = order_book_event(
+            EventType::Snapshot,
+            &[(dec!(101), dec!(1)), (dec!(102), dec!(2))],
+            &[(dec!(100), dec!(3)), (dec!(99), dec!(4))],
+        );
+        // update changes only levels out of depth and removes the best ask
+        let update = order_book_event(
+            EventType::Update,
+            &[(dec!(101), dec!(0)), (dec!(103), dec!(5))],
+            &[(dec!(98), dec!(6))],
+        );
+        let update_before_snapshot = update.clone();
+
+        let rows = order_book_rows(&[update_before_snapshot, snapshot, update]);
+
+        assert_eq!(rows.len(), 2);
+        let row = &rows[1];
+        assert!(!row.is_snapshot);
+        assert_eq!(row.ask_prices, vec![dec!(102), dec!(103)]);
+        assert_eq!(row.ask_amounts, vec![dec!(2), dec!(5)]);
+        assert_eq!(row.bid_prices, vec![dec!(100), dec!(99)]);
+        assert_eq!(row.bid_amounts, vec![dec!(3), dec!(4)]);
+    }
+
+    #[test]
+    fn trades_are_batched_as_json_lines() {
+        let trade = |id: u64| Trade {
+            trade_id: TradeId::Number(id),
+            price: dec!(100),
+            quantity: dec!(1),
+            side: OrderSide::Sell,
+            transaction_time: Utc::now(),
+        };
+        let event = TradesEvent {
+            exchange_account_id: ExchangeAccountId::new("Binance", 0),
+            currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            trades: vec![trade(1), trade(2)],
+            receipt_time: Utc::now(),
+        };
+
+        let mut batch = TableBatch::new(TRADES_TABLE);
+        trade_rows(&event)
+            .try_for_each(|x| batch.push(&x))
+            .expect("in test");
+        let taken = batch.take().expect("in test");
+
+        assert_eq!(taken.rows_count, 2);
+        let lines: Vec<serde_json::Value> = taken
+            .rows
+            .lines()
+            .map(|x| serde_json::from_str(x).expect("in test"))
+            .collect();
+        assert_eq!(lines[1]["trade_id"], "2");
+        assert_eq!(lines[1]["exchange_account_id"], "Binance_0");
+        assert!(batch.take().is_none());
+    }
+
+    #[test]
+    fn insert_uri_contains_encoded_query() {
+        assert_eq!(
+            insert_uri(&settings(), TRADES_TABLE),
+            "http://localhost:8123/?query=INSERT+INTO+mmb.trades+FORMAT+JSONEachRow&date_time_input_format=best_effort"
+        );
+    }
+}
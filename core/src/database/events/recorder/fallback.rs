@@ -1,14 +1,15 @@
 use crate::database::events::recorder::save_batch;
+use crate::database::serialization::{SerializationFormat, VersionedSerializer};
 use crate::exchanges::timeouts::timeout_manager;
 use anyhow::{Context, Result};
 use itertools::Itertools;
 use mmb_database::postgres_db::events::InsertEvent;
-use mmb_database::postgres_db::PgPool;
+use mmb_database::storage::EventStorage;
 use mmb_utils::{nothing_to_do, DateTime};
 use serde::{Deserialize, Serialize};
 use std::ffi::OsString;
 use std::fs::{create_dir_all, DirEntry, File};
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::{env, fs};
@@ -17,6 +18,7 @@ use tokio::task::spawn_blocking;
 const BUFFER_SIZE: usize = 16384;
 const EVENTS_FILE_PREFIX: &str = "events_";
 const NOT_FINISHED_FILED_PREFIX: &str = "writing_yet_";
+const POSTPONED_EVENTS_SCHEMA_VERSION: u32 = 1;
 
 fn get_postponed_events_dir(
     postponed_events_dir_from_settings: Option<PathBuf>,
@@ -63,17 +65,26 @@ impl PostponedEventsFileFormat {
     }
 }
 
+fn postponed_events_serializer(
+    format: SerializationFormat,
+) -> VersionedSerializer<PostponedEventsFileFormat> {
+    VersionedSerializer::new(format, POSTPONED_EVENTS_SCHEMA_VERSION)
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct EventRecorderFallback {
     postponed_events_dir: Arc<Path>,
+    format: SerializationFormat,
 }
 
 impl EventRecorderFallback {
     /// EventRecorder's fallback handlers
     /// postponed_events_dir: postponed events director from settings if exists
-    pub fn new(postponed_events_dir: Option<PathBuf>) -> Result<Self> {
+    /// format: format for writing postponed events files. Files are read with any supported format
+    pub fn new(postponed_events_dir: Option<PathBuf>, format: SerializationFormat) -> Result<Self> {
         Ok(Self {
             postponed_events_dir: init_postponed_events_dir(postponed_events_dir)?,
+            format,
         })
     }
 
@@ -83,6 +94,7 @@ impl EventRecorderFallback {
         not_written_events: Vec<InsertEvent>,
     ) -> Result<()> {
         let postponed_events_dir = self.postponed_events_dir.clone();
+        let format = self.format;
         spawn_blocking(move || -> Result<()> {
             let now = timeout_manager::now();
 
@@ -94,12 +106,19 @@ impl EventRecorderFallback {
             let mut buf_writer = BufWriter::with_capacity(BUFFER_SIZE, file);
 
             let file_format = PostponedEventsFileFormat::new(table_name, not_written_events);
-            serde_json::to_writer(&mut buf_writer, &file_format).with_context(|| {
-                format!(
-                    "failed saving postponed events to file `{}`",
-                    not_finished_file_path.display()
-                )
-            })?;
+            let bytes = postponed_events_serializer(format)
+                .serialize(&file_format)
+                .context("failed serialization of postponed events")?;
+            buf_writer
+                .write_all(&bytes)
+                .and_then(|()| buf_writer.flush())
+                .with_context(|| {
+                    format!(
+                        "failed saving postponed events to file `{}`",
+                        not_finished_file_path.display()
+                    )
+                })?;
+            drop(buf_writer);
 
             let finished_file_path = postponed_events_dir.join(&file_names.finished);
             fs::rename(not_finished_file_path, finished_file_path).with_context(|| {
@@ -131,7 +150,7 @@ impl EventRecorderFallback {
 
     pub(crate) async fn try_restore_to_db_postponed_events(
         &self,
-        pool: &PgPool,
+        storage: &dyn EventStorage,
         file_names: &[OsString],
     ) {
         for file_name in file_names {
@@ -148,7 +167,7 @@ impl EventRecorderFallback {
                 }
             };
 
-            match save_batch(pool, &table_name, events, self).await {
+            match save_batch(storage, &table_name, events, self).await {
                 Err(err) => log::error!("failed resaving batch of events to db: {err}"),
                 Ok(()) => tokio::fs::remove_file(file_path)
                     .await
@@ -210,9 +229,15 @@ async fn load_from_file(path: PathBuf) -> Result<PostponedEventsFileFormat> {
     spawn_blocking(move || -> Result<_> {
         let file = File::open(&path)
             .with_context(|| format!("can't open postponed events file {}", path.display()))?;
-        let reader = BufReader::with_capacity(BUFFER_SIZE, file);
-        serde_json::from_reader(reader)
-            .with_context(|| format!("can't read postponed events file {}", path.display()))
+        let mut reader = BufReader::with_capacity(BUFFER_SIZE, file);
+        let mut bytes = Vec::new();
+        let _ = reader
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("can't read postponed events file {}", path.display()))?;
+
+        postponed_events_serializer(SerializationFormat::default())
+            .deserialize(&bytes)
+            .with_context(|| format!("can't parse postponed events file {}", path.display()))
     })
     .await?
 }
@@ -222,6 +247,7 @@ mod tests {
     use crate::database::events::recorder::fallback::{
         load_from_file, EventRecorderFallback, PostponedEventsFileFormat,
     };
+    use crate::database::serialization::SerializationFormat;
     use bb8_postgres::bb8::PooledConnection;
     use bb8_postgres::PostgresConnectionManager;
     use chrono::Utc;
@@ -272,7 +298,8 @@ mod tests {
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn save_files_on_fallback() {
         // arrange
-        let fallback = EventRecorderFallback::new(None).expect("in test");
+        let fallback =
+            EventRecorderFallback::new(None, SerializationFormat::MessagePack).expect("in test");
         defer! {
             fs::remove_dir_all(fallback.clone().postponed_events_dir).expect("clear postponed events dir");
         };
@@ -324,7 +351,8 @@ mod tests {
     async fn restore_postponed_events_in_db() {
         let pool_mutex = init_test().await;
 
-        let fallback = EventRecorderFallback::new(None).expect("in test");
+        let fallback =
+            EventRecorderFallback::new(None, SerializationFormat::Json).expect("in test");
         defer! {
             fs::remove_dir_all(fallback.clone().postponed_events_dir).expect("clear postponed events dir");
         };
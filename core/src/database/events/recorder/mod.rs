@@ -1,12 +1,11 @@
 mod fallback;
 
 use crate::database::events::recorder::fallback::EventRecorderFallback;
+use crate::database::serialization::SerializationFormat;
 use crate::infrastructure::spawn_future;
 use anyhow::{bail, Context, Result};
-use mmb_database::postgres_db::events::{
-    save_events_batch, save_events_one_by_one, Event, InsertEvent, TableName,
-};
-use mmb_database::postgres_db::PgPool;
+use mmb_database::postgres_db::events::{Event, InsertEvent, TableName};
+use mmb_database::storage::EventStorage;
 use mmb_utils::infrastructure::SpawnFutureFlags;
 use mmb_utils::logger::print_info;
 use parking_lot::Mutex;
@@ -30,6 +29,7 @@ pub struct DbSettings {
 }
 
 pub struct EventRecorder {
+    storage: Option<Arc<dyn EventStorage>>,
     data_tx: mpsc::Sender<(TableName, InsertEvent)>,
     shutdown_signal_tx: mpsc::UnboundedSender<()>,
     shutdown_rx: Mutex<Option<oneshot::Receiver<Result<()>>>>,
@@ -37,29 +37,31 @@ pub struct EventRecorder {
 
 impl EventRecorder {
     pub async fn start(
-        pool: Option<PgPool>,
+        storage: Option<Arc<dyn EventStorage>>,
         postponed_events_dir: Option<PathBuf>,
+        postponed_events_format: SerializationFormat,
     ) -> Result<Arc<EventRecorder>> {
         let (data_tx, data_rx) = mpsc::channel(20_000);
         let (shutdown_signal_tx, shutdown_signal_rx) = mpsc::unbounded_channel();
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
 
-        match pool {
+        match storage.clone() {
             None => {
                 let _ = shutdown_tx.send(Ok(()));
                 print_info(
                     "EventRecorder is not started because `database_url` is not set in settings",
                 );
             }
-            Some(pool) => {
-                let fallback = EventRecorderFallback::new(postponed_events_dir)
-                    .context("failed creation EventRecorderFallback")?;
+            Some(storage) => {
+                let fallback =
+                    EventRecorderFallback::new(postponed_events_dir, postponed_events_format)
+                        .context("failed creation EventRecorderFallback")?;
 
                 let _ = spawn_future(
                     "start db event recorder",
                     SpawnFutureFlags::DENY_CANCELLATION | SpawnFutureFlags::STOP_BY_TOKEN,
                     start_db_event_recorder(
-                        pool.clone(),
+                        storage.clone(),
                         data_rx,
                         shutdown_signal_rx,
                         shutdown_tx,
@@ -69,13 +71,14 @@ impl EventRecorder {
                 let _ = spawn_future(
                     "start postponed events restoring",
                     SpawnFutureFlags::DENY_CANCELLATION | SpawnFutureFlags::STOP_BY_TOKEN,
-                    start_postponed_events_restoring(pool, fallback),
+                    start_postponed_events_restoring(storage, fallback),
                 );
                 print_info("EventRecorder started");
             }
         }
 
         Ok(Arc::new(Self {
+            storage,
             data_tx,
             shutdown_signal_tx,
             shutdown_rx: Mutex::new(Some(shutdown_rx)),
@@ -100,6 +103,21 @@ impl EventRecorder {
         Ok(())
     }
 
+    /// Storage of recorded events, e.g. for querying of order history. It isn't set if database
+    /// isn't configured
+    pub fn storage(&self) -> Option<Arc<dyn EventStorage>> {
+        self.storage.clone()
+    }
+
+    /// Count of events waiting to be saved to database
+    pub fn backlog(&self) -> usize {
+        if self.data_tx.is_closed() {
+            return 0;
+        }
+
+        self.data_tx.max_capacity() - self.data_tx.capacity()
+    }
+
     pub async fn flush_and_stop(&self) -> Result<()> {
         let _ = self.shutdown_signal_tx.send(());
         let receiver = self.shutdown_rx.lock().take();
@@ -113,7 +131,7 @@ impl EventRecorder {
 }
 
 async fn start_postponed_events_restoring(
-    pool: PgPool,
+    storage: Arc<dyn EventStorage>,
     fallback: EventRecorderFallback,
 ) -> Result<()> {
     let mut interval = tokio::time::interval(RESTORING_EVENTS_TIMEOUT);
@@ -130,19 +148,19 @@ async fn start_postponed_events_restoring(
             continue;
         }
 
-        if !pool.is_connection_health().await {
+        if !storage.is_connection_health().await {
             continue;
         }
 
         file_names.sort();
         fallback
-            .try_restore_to_db_postponed_events(&pool, &file_names)
+            .try_restore_to_db_postponed_events(storage.as_ref(), &file_names)
             .await;
     }
 }
 
 async fn start_db_event_recorder(
-    pool: PgPool,
+    storage: Arc<dyn EventStorage>,
     mut data_rx: mpsc::Receiver<(TableName, InsertEvent)>,
     mut shutdown_signal_rx: mpsc::UnboundedReceiver<()>,
     shutdown_tx: oneshot::Sender<Result<()>>,
@@ -180,7 +198,7 @@ async fn start_db_event_recorder(
                             events.len() >= BATCH_SIZE_TO_SAVE {
 
                             let events = mem::replace(events, create_batch_size_vec());
-                            save_batch(&pool, table_name, events, &fallback).await.context("from `start_db_event_recorder` in `save_batch`")?;
+                            save_batch(storage.as_ref(), table_name, events, &fallback).await.context("from `start_db_event_recorder` in `save_batch`")?;
 
                             *last_time_to_save = Instant::now();
                         }
@@ -192,7 +210,7 @@ async fn start_db_event_recorder(
                 for (table_name, EventsByTableName { ref mut events, ref mut last_time_to_save }) in &mut events_map {
                     if last_time_to_save.elapsed() < SAVING_TIMEOUT {
                         let events = mem::replace(events, create_batch_size_vec());
-                        save_batch(&pool, table_name, events, &fallback).await.context("from `start_db_event_recorder` in `save_batch`")?;
+                        save_batch(storage.as_ref(), table_name, events, &fallback).await.context("from `start_db_event_recorder` in `save_batch`")?;
 
                         *last_time_to_save = Instant::now();
                     }
@@ -202,7 +220,7 @@ async fn start_db_event_recorder(
     }
 
     async fn flush_all_events(
-        pool: &PgPool,
+        storage: &dyn EventStorage,
         mut data_rx: mpsc::Receiver<(TableName, InsertEvent)>,
         mut events_map: HashMap<TableName, EventsByTableName>,
         fallback: EventRecorderFallback,
@@ -212,7 +230,7 @@ async fn start_db_event_recorder(
         }
 
         for (table_name, EventsByTableName { events, .. }) in events_map {
-            save_batch(pool, table_name, events, &fallback)
+            save_batch(storage, table_name, events, &fallback)
                 .await
                 .context("from `flush_all_events` in `save_batch`")?;
         }
@@ -220,7 +238,7 @@ async fn start_db_event_recorder(
         Ok(())
     }
 
-    let flush_result = flush_all_events(&pool, data_rx, events_map, fallback).await;
+    let flush_result = flush_all_events(storage.as_ref(), data_rx, events_map, fallback).await;
 
     let _ = shutdown_tx.send(flush_result);
 
@@ -228,18 +246,18 @@ async fn start_db_event_recorder(
 }
 
 async fn save_batch(
-    pool: &PgPool,
+    storage: &dyn EventStorage,
     table_name: &'_ str,
     events: Vec<InsertEvent>,
     fallback: &EventRecorderFallback,
 ) -> Result<()> {
-    match save_events_batch(pool, table_name, &events).await {
+    match storage.save_events_batch(table_name, &events).await {
         Ok(()) => return Ok(()),
         Err(err) => log::error!("Failed to save batch of events with error: {err:?}"),
     }
 
     let (saving_result, not_written_events) =
-        save_events_one_by_one(pool, table_name, events).await;
+        storage.save_events_one_by_one(table_name, events).await;
     match saving_result {
         Ok(()) => if !not_written_events.is_empty() {},
         Err(err) => {
@@ -272,6 +290,7 @@ mod tests {
     use mmb_database::impl_event;
     use mmb_database::postgres_db::tests::{get_database_url, PgPoolMutex};
     use serde::{Deserialize, Serialize};
+    use std::sync::Arc;
     use std::time::{Duration, Instant};
     use tokio::time::sleep;
 
@@ -330,9 +349,13 @@ mod tests {
     async fn save_1_event() {
         let pool_mutex = init_test().await;
 
-        let event_recorder = EventRecorder::start(Some(pool_mutex.pool.clone()), None)
-            .await
-            .expect("in test");
+        let event_recorder = EventRecorder::start(
+            Some(Arc::new(pool_mutex.pool.clone())),
+            None,
+            Default::default(),
+        )
+        .await
+        .expect("in test");
 
         let connection = pool_mutex.pool.get_connection_expected().await;
 
@@ -358,7 +381,9 @@ mod tests {
         let person = test_person();
 
         // act
-        let event_recorder = EventRecorder::start(None, None).await.expect("in test");
+        let event_recorder = EventRecorder::start(None, None, Default::default())
+            .await
+            .expect("in test");
 
         event_recorder.save(person).expect("in test");
 
@@ -381,9 +406,13 @@ mod tests {
         let person = test_person();
 
         // act
-        let event_recorder = EventRecorder::start(Some(pool_mutex.pool.clone()), None)
-            .await
-            .expect("in test");
+        let event_recorder = EventRecorder::start(
+            Some(Arc::new(pool_mutex.pool.clone())),
+            None,
+            Default::default(),
+        )
+        .await
+        .expect("in test");
         let connection = pool_mutex.pool.get_connection_expected().await;
 
         let timer = Instant::now();
@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use anyhow::{anyhow, bail, Context, Result};
+use dashmap::DashMap;
+use mmb_domain::market::ExchangeAccountId;
+use mmb_domain::order::history::OrderChangeType;
+use mmb_domain::order::snapshot::{ClientOrderId, OrderSnapshot};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::balance::manager::balance_manager::BalanceManager;
+use crate::balance::manager::balances::Balances;
+use crate::exchanges::general::exchange::Exchange;
+
+/// Critical engine event written to journal before its side effects take place
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum JournalRecord {
+    /// Order is going to be sent to exchange
+    OrderIntent(OrderSnapshot),
+    /// State of order after acknowledgement, fill or cancellation
+    OrderChanged {
+        change_type: OrderChangeType,
+        order: OrderSnapshot,
+    },
+    /// Not finished order rewritten by compaction of journal
+    OrderRecovered(OrderSnapshot),
+    /// State of balance manager after its change
+    Balances(Balances),
+}
+
+/// State of engine rebuilt from journal
+#[derive(Debug, Default)]
+pub struct RecoveredState {
+    /// Orders which weren't finished at the moment of the last record about them
+    pub orders: Vec<OrderSnapshot>,
+    pub balances: Option<Balances>,
+}
+
+/// Append-only journal. Records are written and synced to disk by separate thread in batches,
+/// so writers don't wait for disk. Journal is compacted to not finished orders and the latest
+/// balances at startup and after every `compaction_records` records
+pub struct Journal {
+    sender: Option<Sender<PendingRecord>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl Journal {
+    /// Replays existing journal and starts new one containing only recovered state
+    pub fn open(path: &Path, compaction_records: usize) -> Result<(Arc<Journal>, RecoveredState)> {
+        let mut state = JournalState::default();
+        if path.exists() {
+            replay(path, &mut state)?;
+        }
+
+        let writer = JournalWriter::open(path, state.clone(), compaction_records.max(1))?;
+        let (sender, receiver) = mpsc::channel();
+        let writer = thread::Builder::new()
+            .name("journal_writer".to_owned())
+            .spawn(move || writer.run(receiver))
+            .context("starting journal writer")?;
+
+        let journal = Arc::new(Journal {
+            sender: Some(sender),
+            writer: Some(writer),
+        });
+
+        Ok((journal, state.into_recovered()))
+    }
+
+    /// Queues record for writing without waiting for disk
+    pub fn append(&self, record: JournalRecord) -> Result<()> {
+        self.send(PendingRecord {
+            record,
+            synced: None,
+        })
+    }
+
+    /// Waits until record is synced to disk
+    pub async fn append_durable(&self, record: JournalRecord) -> Result<()> {
+        let (synced, synced_receiver) = oneshot::channel();
+        self.send(PendingRecord {
+            record,
+            synced: Some(synced),
+        })?;
+
+        match synced_receiver.await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(err)) => bail!("{err}"),
+            Err(_) => bail!("Journal writer is stopped"),
+        }
+    }
+
+    fn send(&self, record: PendingRecord) -> Result<()> {
+        match &self.sender {
+            Some(sender) => sender
+                .send(record)
+                .map_err(|_| anyhow!("Journal writer is stopped")),
+            None => bail!("Journal is closed"),
+        }
+    }
+}
+
+impl Drop for Journal {
+    /// Queued records are written before journal is dropped
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                log::error!("Journal writer panicked");
+            }
+        }
+    }
+}
+
+struct PendingRecord {
+    record: JournalRecord,
+    synced: Option<oneshot::Sender<Result<(), String>>>,
+}
+
+/// Not finished orders and the latest balances by all records of journal
+#[derive(Debug, Default, Clone)]
+struct JournalState {
+    orders: HashMap<ClientOrderId, OrderSnapshot>,
+    balances: Option<Balances>,
+}
+
+impl JournalState {
+    fn apply(&mut self, record: &JournalRecord) {
+        match record {
+            JournalRecord::OrderIntent(order)
+            | JournalRecord::OrderChanged { order, .. }
+            | JournalRecord::OrderRecovered(order) => {
+                let client_order_id = &order.header.client_order_id;
+                match order.props.is_finished() {
+                    true => {
+                        let _ = self.orders.remove(client_order_id);
+                    }
+                    false => {
+                        let _ = self.orders.insert(client_order_id.clone(), order.clone());
+                    }
+                }
+            }
+            JournalRecord::Balances(balances) => self.balances = Some(balances.clone()),
+        }
+    }
+
+    fn into_recovered(self) -> RecoveredState {
+        let mut orders = self.orders.into_values().collect::<Vec<_>>();
+        orders.sort_by_key(|x| x.props.init_time);
+
+        RecoveredState {
+            orders,
+            balances: self.balances,
+        }
+    }
+}
+
+struct JournalWriter {
+    path: PathBuf,
+    file: File,
+    state: JournalState,
+    records_count: usize,
+    compaction_records: usize,
+}
+
+impl JournalWriter {
+    fn open(path: &Path, state: JournalState, compaction_records: usize) -> Result<Self> {
+        let (file, records_count) = compact(path, &state)?;
+
+        Ok(JournalWriter {
+            path: path.to_path_buf(),
+            file,
+            state,
+            records_count,
+            compaction_records,
+        })
+    }
+
+    /// Writes records until all senders are dropped. Records queued during writing of
+    /// previous batch are written by the next batch with single sync
+    fn run(mut self, receiver: Receiver<PendingRecord>) {
+        while let Ok(first) = receiver.recv() {
+            let (records, synced): (Vec<_>, Vec<_>) = iter::once(first)
+                .chain(receiver.try_iter())
+                .map(|x| (x.record, x.synced))
+                .unzip();
+
+            let result = self.write_batch(&records).map_err(|err| {
+                log::error!(
+                    "Failed to write {} records to journal: {err:?}",
+                    records.len()
+                );
+                format!("{err:?}")
+            });
+
+            for synced in synced.into_iter().flatten() {
+                let _ = synced.send(result.clone());
+            }
+        }
+    }
+
+    fn write_batch(&mut self, records: &[JournalRecord]) -> Result<()> {
+        // every balances record contains full state, so only the latest one of batch is needed
+        let last_balances = records
+            .iter()
+            .rposition(|x| matches!(x, JournalRecord::Balances(_)));
+
+        for (index, record) in records.iter().enumerate() {
+            if matches!(record, JournalRecord::Balances(_)) && Some(index) != last_balances {
+                continue;
+            }
+
+            write_record(&mut self.file, record)?;
+            self.state.apply(record);
+            self.records_count += 1;
+        }
+
+        self.file
+            .sync_data()
+            .with_context(|| format!("syncing journal file {}", self.path.display()))?;
+
+        if self.records_count >= self.compaction_records {
+            let (file, records_count) = compact(&self.path, &self.state)?;
+            self.file = file;
+            self.records_count = records_count;
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces journal by file containing only the state. Returns the file opened for appending
+/// and count of records in it
+fn compact(path: &Path, state: &JournalState) -> Result<(File, usize)> {
+    let recovered = state.clone().into_recovered();
+
+    let tmp_path = path.with_extension("tmp");
+    let mut records_count = 0;
+    {
+        let mut tmp_file = File::create(&tmp_path)
+            .with_context(|| format!("creating journal file {}", tmp_path.display()))?;
+        for order in recovered.orders {
+            write_record(&mut tmp_file, &JournalRecord::OrderRecovered(order))?;
+            records_count += 1;
+        }
+        if let Some(balances) = recovered.balances {
+            write_record(&mut tmp_file, &JournalRecord::Balances(balances))?;
+            records_count += 1;
+        }
+        tmp_file.sync_all().context("syncing compacted journal")?;
+    }
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("replacing journal file {}", path.display()))?;
+
+    let file = OpenOptions::new()
+        .append(true)
+        .open(path)
+        .with_context(|| format!("opening journal file {}", path.display()))?;
+
+    Ok((file, records_count))
+}
+
+fn write_record(file: &mut File, record: &JournalRecord) -> Result<()> {
+    let mut line = serde_json::to_vec(record).context("serializing journal record")?;
+    line.push(b'\n');
+    // whole line is written at once, so crash can damage only the last record
+    file.write_all(&line).context("writing journal record")
+}
+
+fn replay(path: &Path, state: &mut JournalState) -> Result<()> {
+    let file =
+        File::open(path).with_context(|| format!("opening journal file {}", path.display()))?;
+
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.context("reading journal")?;
+        if line.is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<JournalRecord>(&line) {
+            Ok(record) => state.apply(&record),
+            Err(err) => log::warn!("Skipped damaged record {} of journal: {err}", index + 1),
+        }
+    }
+
+    Ok(())
+}
+
+/// Restores not finished orders to orders pools of exchanges and balance state to balance manager.
+/// Recovered orders are synchronized with exchanges by open orders reconciliation after connection
+pub(crate) fn restore_recovered_state(
+    recovered: RecoveredState,
+    exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+    balance_manager: &Mutex<BalanceManager>,
+) {
+    for order in recovered.orders {
+        let exchange = match exchanges.get(&order.header.exchange_account_id) {
+            Some(exchange) => exchange,
+            None => {
+                log::warn!(
+                    "Order {} from journal is skipped because exchange account {} isn't configured",
+                    order.header.client_order_id,
+                    order.header.exchange_account_id
+                );
+                continue;
+            }
+        };
+
+        let order_ref = exchange.orders.add_snapshot_initial(&order);
+        if let Some(exchange_order_id) = order.props.exchange_order_id {
+            let _ = exchange
+                .orders
+                .cache_by_exchange_id
+                .insert(exchange_order_id, order_ref);
+        }
+
+        log::info!(
+            "Order {} is recovered from journal",
+            order.header.client_order_id
+        );
+    }
+
+    if let Some(balances) = recovered.balances {
+        balance_manager
+            .lock()
+            .restore_balance_state(&balances, false);
+        log::info!("Balance state is recovered from journal");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::snapshot::{OrderOptions, OrderSide, OrderStatus};
+    use rust_decimal_macros::dec;
+    use std::env;
+    use uuid::Uuid;
+
+    fn order(status: OrderStatus) -> OrderSnapshot {
+        let mut order = OrderSnapshot::with_params(
+            ClientOrderId::unique_id(),
+            OrderOptions::limit(dec!(10)),
+            None,
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+            dec!(1),
+            OrderSide::Buy,
+            None,
+            "StrategyInUnitTests",
+        );
+        order.set_status(status, chrono::Utc::now());
+        order
+    }
+
+    #[test]
+    fn replay_keeps_last_state_of_not_finished_orders() {
+        let path = env::temp_dir().join(format!("journal_{}.log", Uuid::new_v4()));
+
+        let open_order = order(OrderStatus::Creating);
+        let finished_order = order(OrderStatus::Creating);
+        {
+            let (journal, recovered) = Journal::open(&path, 100).expect("in test");
+            assert!(recovered.orders.is_empty());
+
+            journal
+                .append(JournalRecord::OrderIntent(open_order.clone()))
+                .expect("in test");
+            journal
+                .append(JournalRecord::OrderIntent(finished_order.clone()))
+                .expect("in test");
+
+            let mut created_order = open_order.clone();
+            created_order.set_status(OrderStatus::Created, chrono::Utc::now());
+            journal
+                .append(JournalRecord::OrderChanged {
+                    change_type: OrderChangeType::CreateOrderSucceeded,
+                    order: created_order,
+                })
+                .expect("in test");
+
+            let mut canceled_order = finished_order.clone();
+            canceled_order.set_status(OrderStatus::Canceled, chrono::Utc::now());
+            journal
+                .append(JournalRecord::OrderChanged {
+                    change_type: OrderChangeType::CancelOrderSucceeded,
+                    order: canceled_order,
+                })
+                .expect("in test");
+        }
+
+        // record damaged by crash during writing
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .expect("in test");
+        file.write_all(b"{\"OrderIntent\":{\"hea").expect("in test");
+        drop(file);
+
+        let (_, recovered) = Journal::open(&path, 100).expect("in test");
+        assert_eq!(recovered.orders.len(), 1);
+        assert_eq!(
+            recovered.orders[0].header.client_order_id,
+            open_order.header.client_order_id
+        );
+        assert_eq!(recovered.orders[0].props.status, OrderStatus::Created);
+
+        // journal is compacted to recovered state
+        let (_, recovered_again) = Journal::open(&path, 100).expect("in test");
+        assert_eq!(recovered_again.orders.len(), 1);
+
+        let _ = fs::remove_file(path);
+    }
+
+    fn records_count(path: &Path) -> usize {
+        fs::read_to_string(path).expect("in test").lines().count()
+    }
+
+    #[test]
+    fn journal_is_compacted_after_specified_records_count() {
+        let path = env::temp_dir().join(format!("journal_{}.log", Uuid::new_v4()));
+
+        let open_order = order(OrderStatus::Created);
+        {
+            let (journal, _) = Journal::open(&path, 3).expect("in test");
+            journal
+                .append(JournalRecord::OrderIntent(open_order.clone()))
+                .expect("in test");
+            for status in [OrderStatus::Creating, OrderStatus::Canceled] {
+                journal
+                    .append(JournalRecord::OrderIntent(order(status)))
+                    .expect("in test");
+            }
+        }
+
+        // canceled order is removed by compaction
+        assert_eq!(records_count(&path), 2);
+        let (_, recovered) = Journal::open(&path, 3).expect("in test");
+        let recovered_ids = recovered
+            .orders
+            .iter()
+            .map(|x| x.header.client_order_id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(recovered_ids.len(), 2);
+        assert!(recovered_ids.contains(&open_order.header.client_order_id));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn durable_record_is_synced_before_append_returns() {
+        let path = env::temp_dir().join(format!("journal_{}.log", Uuid::new_v4()));
+        let (journal, _) = Journal::open(&path, 100).expect("in test");
+
+        journal
+            .append_durable(JournalRecord::OrderIntent(order(OrderStatus::Creating)))
+            .await
+            .expect("in test");
+
+        assert_eq!(records_count(&path), 1);
+
+        drop(journal);
+        let _ = fs::remove_file(path);
+    }
+}
@@ -1 +1,6 @@
+pub mod clickhouse;
 pub mod events;
+pub mod journal;
+pub mod order_archive;
+pub mod order_history;
+pub mod serialization;
@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use mmb_database::impl_event;
+use mmb_database::postgres_db::events::Event;
+use mmb_database::storage::{EventStorage, EventsFilter};
+use mmb_domain::market::ExchangeAccountId;
+use mmb_domain::order::snapshot::{ClientOrderId, OrderSnapshot};
+use mmb_utils::DateTime;
+use serde::{Deserialize, Serialize};
+
+/// Final state of finished order evicted from orders pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedOrder {
+    pub client_order_id: ClientOrderId,
+    pub exchange_account_id: ExchangeAccountId,
+    pub archive_time: DateTime,
+    pub order: OrderSnapshot,
+}
+
+impl_event!(ArchivedOrder, "archived_orders");
+
+/// Loads the latest archived state of order. `None` if order wasn't archived
+pub async fn load_archived_order(
+    storage: &dyn EventStorage,
+    exchange_account_id: ExchangeAccountId,
+    client_order_id: &ClientOrderId,
+) -> Result<Option<OrderSnapshot>> {
+    let exchange_account_id = exchange_account_id.to_string();
+    let json_fields = [
+        ("client_order_id", client_order_id.as_str()),
+        ("exchange_account_id", exchange_account_id.as_str()),
+    ];
+    let filter = EventsFilter {
+        json_fields: &json_fields,
+        ..Default::default()
+    };
+
+    let Some(json) = storage
+        .load_events(ArchivedOrder::TABLE_NAME, &filter)
+        .await?
+        .pop()
+    else {
+        return Ok(None);
+    };
+
+    let archived_order: ArchivedOrder =
+        serde_json::from_value(json).context("Unable to parse archived order")?;
+    Ok(Some(archived_order.order))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use mmb_database::postgres_db::events::InsertEvent;
+    use mmb_database::sqlite_db::SqliteStorage;
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::pool::OrdersPool;
+    use mmb_domain::order::snapshot::{OrderHeader, OrderSide, OrderStatus, UserOrder};
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn archived_order_is_loaded_by_client_order_id() {
+        let storage = SqliteStorage::create("sqlite::memory:", 1)
+            .await
+            .expect("in test");
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        let header = OrderHeader::with_user_order(
+            "archived".into(),
+            exchange_account_id,
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            OrderSide::Buy,
+            dec!(1),
+            UserOrder::limit(dec!(100)),
+            None,
+            None,
+            "grid".to_owned(),
+        );
+        let order = OrdersPool::new().add_simple_initial(&header, Utc::now(), None);
+        order.fn_mut(|x| x.set_status(OrderStatus::Completed, Utc::now()));
+
+        let archived_order = ArchivedOrder {
+            client_order_id: order.client_order_id(),
+            exchange_account_id,
+            archive_time: Utc::now(),
+            order: order.deep_clone(),
+        };
+        let event = InsertEvent {
+            version: 1,
+            json: archived_order.get_json().expect("in test"),
+        };
+        storage
+            .save_events_batch(ArchivedOrder::TABLE_NAME, &[event])
+            .await
+            .expect("in test");
+
+        let loaded = load_archived_order(&storage, exchange_account_id, &"archived".into())
+            .await
+            .expect("in test")
+            .expect("order should be archived");
+        assert_eq!(loaded.header.client_order_id, "archived".into());
+        assert_eq!(loaded.status(), OrderStatus::Completed);
+
+        let missing = load_archived_order(&storage, exchange_account_id, &"missing".into())
+            .await
+            .expect("in test");
+        assert!(missing.is_none());
+    }
+}
@@ -0,0 +1,273 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use mmb_database::order_history::{
+    query_order_fills, query_order_state_transitions, HistoryPage, HistoryQuery,
+};
+use mmb_database::storage::EventStorage;
+use mmb_domain::events::ExchangeEvent;
+use mmb_domain::order::history::{order_history_records, OrderFillRecord, OrderStateTransition};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use serde::de::DeserializeOwned;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::database::events::recorder::EventRecorder;
+use crate::infrastructure::spawn_future;
+
+/// Writes every order state transition and fill to database, so trade history survives restarts.
+/// Records are saved in batches by `EventRecorder`
+pub struct OrderHistoryRecorder {
+    event_recorder: Arc<EventRecorder>,
+}
+
+impl OrderHistoryRecorder {
+    pub fn start(
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        event_recorder: Arc<EventRecorder>,
+    ) -> Arc<Self> {
+        let order_history_recorder = Arc::new(Self { event_recorder });
+
+        spawn_future(
+            "Start order history recorder",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            order_history_recorder.clone().run(events_receiver),
+        );
+
+        order_history_recorder
+    }
+
+    async fn run(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Result<()> {
+        // events are recorded even during graceful shutdown until events channel is closed
+        loop {
+            match events_receiver.recv().await {
+                Ok(event) => self.handle_event(&event),
+                Err(RecvError::Lagged(skipped)) => {
+                    log::error!("OrderHistoryRecorder skipped {skipped} events, order history is incomplete")
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
+    fn handle_event(&self, event: &ExchangeEvent) {
+        let order_event = match event {
+            ExchangeEvent::OrderEvent(order_event) => order_event,
+            _ => return,
+        };
+
+        let (transition, fill) = order_history_records(order_event);
+        if let Err(err) = self.event_recorder.save(transition) {
+            log::error!("Failed to save order state transition: {err:?}");
+        }
+
+        if let Some(fill) = fill {
+            if let Err(err) = self.event_recorder.save(fill) {
+                log::error!("Failed to save order fill: {err:?}");
+            }
+        }
+    }
+}
+
+/// Page of order history records, see `HistoryPage`
+#[derive(Debug, Clone)]
+pub struct OrderHistoryPage<T> {
+    pub records: Vec<T>,
+    pub next_cursor: Option<i64>,
+}
+
+impl<T: DeserializeOwned> OrderHistoryPage<T> {
+    fn parse(page: HistoryPage) -> Result<Self> {
+        let records = page
+            .records
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<serde_json::Result<_>>()
+            .context("Unable to parse order history record")?;
+
+        Ok(OrderHistoryPage {
+            records,
+            next_cursor: page.next_cursor,
+        })
+    }
+}
+
+/// Loads page of order state transitions for reconciliation and reports. Storage is returned by
+/// `EventRecorder::storage`
+pub async fn query_orders(
+    storage: &dyn EventStorage,
+    query: &HistoryQuery,
+) -> Result<OrderHistoryPage<OrderStateTransition>> {
+    OrderHistoryPage::parse(query_order_state_transitions(storage, query).await?)
+}
+
+/// Loads page of order fills for reconciliation and reports. Storage is returned by
+/// `EventRecorder::storage`
+pub async fn query_fills(
+    storage: &dyn EventStorage,
+    query: &HistoryQuery,
+) -> Result<OrderHistoryPage<OrderFillRecord>> {
+    OrderHistoryPage::parse(query_order_fills(storage, query).await?)
+}
+
+/// CSV with header row. Pages can be exported one by one and joined without headers of
+/// the following pages
+pub fn orders_to_csv(orders: &[OrderStateTransition]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        "time",
+        "client_order_id",
+        "exchange_order_id",
+        "exchange_account_id",
+        "currency_pair",
+        "side",
+        "order_type",
+        "price",
+        "amount",
+        "filled_amount",
+        "change_type",
+        "status",
+        "strategy_name",
+    ])?;
+    for order in orders {
+        writer.write_record([
+            order.time.to_rfc3339(),
+            order.client_order_id.to_string(),
+            optional(order.exchange_order_id.as_ref()),
+            order.exchange_account_id.to_string(),
+            order.currency_pair.to_string(),
+            order.side.as_str().to_owned(),
+            format!("{:?}", order.order_type),
+            optional(order.price.as_ref()),
+            order.amount.to_string(),
+            order.filled_amount.to_string(),
+            format!("{:?}", order.change_type),
+            format!("{:?}", order.status),
+            order.strategy_name.clone(),
+        ])?;
+    }
+
+    into_string(writer)
+}
+
+/// CSV with header row, see `orders_to_csv`
+pub fn fills_to_csv(fills: &[OrderFillRecord]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        "time",
+        "client_order_id",
+        "exchange_order_id",
+        "exchange_account_id",
+        "currency_pair",
+        "side",
+        "trade_id",
+        "price",
+        "amount",
+        "role",
+        "commission_currency_code",
+        "commission_amount",
+        "strategy_name",
+    ])?;
+    for record in fills {
+        let fill = &record.fill;
+        writer.write_record([
+            fill.receive_time().to_rfc3339(),
+            record.client_order_id.to_string(),
+            optional(record.exchange_order_id.as_ref()),
+            record.exchange_account_id.to_string(),
+            record.currency_pair.to_string(),
+            fill.side().unwrap_or(record.side).as_str().to_owned(),
+            optional(fill.trade_id()),
+            fill.price().to_string(),
+            fill.amount().to_string(),
+            format!("{:?}", fill.role()),
+            fill.commission_currency_code().to_string(),
+            fill.commission_amount().to_string(),
+            record.strategy_name.clone(),
+        ])?;
+    }
+
+    into_string(writer)
+}
+
+fn optional(value: Option<&impl ToString>) -> String {
+    value.map(ToString::to_string).unwrap_or_default()
+}
+
+fn into_string(writer: csv::Writer<Vec<u8>>) -> Result<String> {
+    let bytes = writer.into_inner().context("Unable to write CSV")?;
+    String::from_utf8(bytes).context("CSV isn't valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use mmb_database::postgres_db::events::{Event, InsertEvent};
+    use mmb_database::sqlite_db::SqliteStorage;
+    use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+    use mmb_domain::order::history::OrderChangeType;
+    use mmb_domain::order::snapshot::{OrderSide, OrderStatus, OrderType};
+    use rust_decimal_macros::dec;
+
+    fn transition(client_order_id: &str, strategy_name: &str) -> OrderStateTransition {
+        OrderStateTransition {
+            client_order_id: client_order_id.into(),
+            exchange_order_id: None,
+            exchange_account_id: ExchangeAccountId::new("Binance", 0),
+            currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            price: Some(dec!(100)),
+            amount: dec!(2),
+            filled_amount: dec!(0),
+            change_type: OrderChangeType::CreateOrderSucceeded,
+            status: OrderStatus::Created,
+            strategy_name: strategy_name.to_owned(),
+            time: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn orders_are_queried_by_pages_and_exported_to_csv() {
+        let storage = SqliteStorage::create("sqlite::memory:", 1)
+            .await
+            .expect("in test");
+        let events: Vec<_> = [("1", "grid"), ("2", "other"), ("3", "grid"), ("4", "grid")]
+            .into_iter()
+            .map(|(id, strategy_name)| InsertEvent {
+                version: 1,
+                json: transition(id, strategy_name).get_json().expect("in test"),
+            })
+            .collect();
+        storage
+            .save_events_batch(OrderStateTransition::TABLE_NAME, &events)
+            .await
+            .expect("in test");
+
+        let mut query = HistoryQuery {
+            exchange_account_id: Some("Binance_0".to_owned()),
+            currency_pair: Some("btc/usdt".to_owned()),
+            strategy_name: Some("grid".to_owned()),
+            page_size: 2,
+            ..Default::default()
+        };
+        let first_page = query_orders(&storage, &query).await.expect("in test");
+        assert_eq!(first_page.records.len(), 2);
+        assert!(first_page.next_cursor.is_some());
+
+        query.cursor = first_page.next_cursor;
+        let last_page = query_orders(&storage, &query).await.expect("in test");
+        assert_eq!(last_page.records.len(), 1);
+        assert_eq!(last_page.next_cursor, None);
+        assert_eq!(last_page.records[0].client_order_id, "4".into());
+
+        let csv = orders_to_csv(&first_page.records).expect("in test");
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("time,client_order_id,exchange_order_id"));
+        assert!(lines[2].contains(",3,,Binance_0,btc/usdt,Buy,Limit,100,2,0,"));
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+/// Every persisted payload starts with this marker, so files written before headers were introduced
+/// (plain JSON without any header) can be detected and read too
+const HEADER_MAGIC: &[u8; 4] = b"MMBS";
+const HEADER_LEN: usize = HEADER_MAGIC.len() + 1 + 4;
+
+/// Schema version of payloads that were written without header
+const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum SerializationFormat {
+    #[default]
+    Json,
+    /// Compact binary format. Unlike bincode/postcard it is self-describing, so dynamic data like
+    /// `serde_json::Value` and `typetag` based extension data can be deserialized back
+    MessagePack,
+}
+
+impl SerializationFormat {
+    fn to_byte(self) -> u8 {
+        match self {
+            SerializationFormat::Json => 1,
+            SerializationFormat::MessagePack => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self> {
+        match byte {
+            1 => Ok(SerializationFormat::Json),
+            2 => Ok(SerializationFormat::MessagePack),
+            _ => bail!("Unknown serialization format id {byte} in payload header"),
+        }
+    }
+
+    pub fn serializer(self) -> Box<dyn PayloadSerializer> {
+        match self {
+            SerializationFormat::Json => Box::new(JsonSerializer),
+            SerializationFormat::MessagePack => Box::new(MessagePackSerializer),
+        }
+    }
+}
+
+/// Serialization backend for persisted data.
+/// Payloads are converted through `serde_json::Value` so that schema migrations can be applied
+/// the same way regardless of the format they were stored with
+pub trait PayloadSerializer: Send + Sync {
+    fn format(&self) -> SerializationFormat;
+
+    fn serialize(&self, value: &JsonValue) -> Result<Vec<u8>>;
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<JsonValue>;
+}
+
+pub struct JsonSerializer;
+
+impl PayloadSerializer for JsonSerializer {
+    fn format(&self) -> SerializationFormat {
+        SerializationFormat::Json
+    }
+
+    fn serialize(&self, value: &JsonValue) -> Result<Vec<u8>> {
+        serde_json::to_vec(value).context("failed serialization to json")
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<JsonValue> {
+        serde_json::from_slice(bytes).context("failed deserialization from json")
+    }
+}
+
+pub struct MessagePackSerializer;
+
+impl PayloadSerializer for MessagePackSerializer {
+    fn format(&self) -> SerializationFormat {
+        SerializationFormat::MessagePack
+    }
+
+    fn serialize(&self, value: &JsonValue) -> Result<Vec<u8>> {
+        // named fields keep payloads readable by migration hooks after structs are changed
+        rmp_serde::to_vec_named(value).context("failed serialization to message pack")
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<JsonValue> {
+        rmp_serde::from_slice(bytes).context("failed deserialization from message pack")
+    }
+}
+
+/// Upgrades payload of the specified schema version to the next schema version
+pub type MigrationHook = fn(JsonValue) -> Result<JsonValue>;
+
+/// Writes and reads payloads of type `T` with a header that contains serialization format and
+/// schema version. Payloads of older schema versions are upgraded by registered migration hooks
+pub struct VersionedSerializer<T> {
+    serializer: Box<dyn PayloadSerializer>,
+    schema_version: u32,
+    migrations: BTreeMap<u32, MigrationHook>,
+    _payload: PhantomData<fn(T) -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> VersionedSerializer<T> {
+    pub fn new(format: SerializationFormat, schema_version: u32) -> Self {
+        Self {
+            serializer: format.serializer(),
+            schema_version,
+            migrations: Default::default(),
+            _payload: PhantomData,
+        }
+    }
+
+    /// Register hook for upgrading payload from `from_version` to `from_version + 1`
+    pub fn with_migration(mut self, from_version: u32, hook: MigrationHook) -> Self {
+        let _ = self.migrations.insert(from_version, hook);
+        self
+    }
+
+    pub fn format(&self) -> SerializationFormat {
+        self.serializer.format()
+    }
+
+    pub fn serialize(&self, value: &T) -> Result<Vec<u8>> {
+        let value = serde_json::to_value(value).context("failed converting payload to value")?;
+        let body = self.serializer.serialize(&value)?;
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + body.len());
+        bytes.extend_from_slice(HEADER_MAGIC);
+        bytes.push(self.format().to_byte());
+        bytes.extend_from_slice(&self.schema_version.to_le_bytes());
+        bytes.extend_from_slice(&body);
+
+        Ok(bytes)
+    }
+
+    /// Payload can be read with any supported format, not only the one selected for writing
+    pub fn deserialize(&self, bytes: &[u8]) -> Result<T> {
+        let (format, version, body) = match bytes.strip_prefix(HEADER_MAGIC) {
+            Some(rest) if rest.len() >= HEADER_LEN - HEADER_MAGIC.len() => {
+                let format = SerializationFormat::from_byte(rest[0])?;
+                let mut version = [0u8; 4];
+                version.copy_from_slice(&rest[1..5]);
+                (format, u32::from_le_bytes(version), &rest[5..])
+            }
+            Some(_) => bail!("Payload header is truncated"),
+            None => (SerializationFormat::Json, LEGACY_SCHEMA_VERSION, bytes),
+        };
+
+        let value = match format == self.format() {
+            true => self.serializer.deserialize(body)?,
+            false => format.serializer().deserialize(body)?,
+        };
+        let value = self.migrate(value, version)?;
+
+        serde_json::from_value(value).context("failed converting value to payload")
+    }
+
+    fn migrate(&self, mut value: JsonValue, from_version: u32) -> Result<JsonValue> {
+        if from_version > self.schema_version {
+            bail!(
+                "Payload schema version {from_version} is newer than supported version {}",
+                self.schema_version
+            );
+        }
+
+        for version in from_version..self.schema_version {
+            let hook = self.migrations.get(&version).with_context(|| {
+                format!("There is no migration hook from schema version {version}")
+            })?;
+            value = hook(value)
+                .with_context(|| format!("failed migration from schema version {version}"))?;
+        }
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct Payload {
+        name: String,
+        values: Vec<u32>,
+    }
+
+    fn payload() -> Payload {
+        Payload {
+            name: "test".to_owned(),
+            values: vec![1, 2, 3],
+        }
+    }
+
+    #[rstest]
+    #[case(SerializationFormat::Json)]
+    #[case(SerializationFormat::MessagePack)]
+    fn roundtrip(#[case] format: SerializationFormat) {
+        let serializer = VersionedSerializer::<Payload>::new(format, 1);
+
+        let bytes = serializer.serialize(&payload()).expect("in test");
+        let restored = serializer.deserialize(&bytes).expect("in test");
+
+        assert_eq!(restored, payload());
+    }
+
+    #[test]
+    fn read_payload_written_with_other_format() {
+        let json = VersionedSerializer::<Payload>::new(SerializationFormat::Json, 1);
+        let message_pack = VersionedSerializer::<Payload>::new(SerializationFormat::MessagePack, 1);
+
+        let bytes = json.serialize(&payload()).expect("in test");
+        let restored = message_pack.deserialize(&bytes).expect("in test");
+
+        assert_eq!(restored, payload());
+    }
+
+    #[test]
+    fn message_pack_is_more_compact() {
+        let json = VersionedSerializer::<Payload>::new(SerializationFormat::Json, 1);
+        let message_pack = VersionedSerializer::<Payload>::new(SerializationFormat::MessagePack, 1);
+
+        let json_len = json.serialize(&payload()).expect("in test").len();
+        let message_pack_len = message_pack.serialize(&payload()).expect("in test").len();
+
+        assert!(message_pack_len < json_len);
+    }
+
+    #[test]
+    fn read_legacy_payload_without_header() {
+        let serializer = VersionedSerializer::<Payload>::new(SerializationFormat::MessagePack, 1);
+
+        let bytes = serde_json::to_vec(&payload()).expect("in test");
+        let restored = serializer.deserialize(&bytes).expect("in test");
+
+        assert_eq!(restored, payload());
+    }
+
+    #[test]
+    fn apply_migration_hooks() {
+        fn rename_field(mut value: JsonValue) -> Result<JsonValue> {
+            let object = value.as_object_mut().context("expected object")?;
+            let title = object.remove("title").context("expected title")?;
+            let _ = object.insert("name".to_owned(), title);
+            Ok(value)
+        }
+
+        fn add_values(mut value: JsonValue) -> Result<JsonValue> {
+            let object = value.as_object_mut().context("expected object")?;
+            let _ = object.insert("values".to_owned(), json!([1, 2, 3]));
+            Ok(value)
+        }
+
+        let serializer = VersionedSerializer::<Payload>::new(SerializationFormat::Json, 3)
+            .with_migration(1, rename_field)
+            .with_migration(2, add_values);
+
+        let legacy = serde_json::to_vec(&json!({ "title": "test" })).expect("in test");
+        let restored = serializer.deserialize(&legacy).expect("in test");
+
+        assert_eq!(restored, payload());
+    }
+
+    #[test]
+    fn fail_on_missing_migration_hook() {
+        let serializer = VersionedSerializer::<Payload>::new(SerializationFormat::Json, 2);
+
+        let legacy = serde_json::to_vec(&payload()).expect("in test");
+
+        assert!(serializer.deserialize(&legacy).is_err());
+    }
+}
@@ -2,7 +2,6 @@ use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 
 use anyhow::{anyhow, bail, Result};
-use chrono::Utc;
 use itertools::Itertools;
 use mmb_utils::infrastructure::{SpawnFutureFlags, WithExpect};
 use mmb_utils::{nothing_to_do, DateTime};
@@ -17,8 +16,11 @@ use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::general::request_type::RequestType;
 use crate::explanation::{Explanation, WithExplanation};
 use crate::lifecycle::trading_engine::{EngineContext, Service};
+use crate::misc::clock::Clock;
 use crate::misc::reserve_parameters::ReserveParameters;
 use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::order_book::sanity::check_order_book;
+use crate::runtimes::{spawn_future_in, RuntimeKind};
 use crate::{
     disposition_execution::trade_limit::is_enough_amount_and_cost, infrastructure::spawn_future,
 };
@@ -29,7 +31,7 @@ use crate::{
     statistic_service::StatisticService,
 };
 use chrono::Duration;
-use mmb_domain::events::ExchangeEvent;
+use mmb_domain::events::{ExchangeEvent, MarketDataSuspectEvent, MarketDataSuspectReason};
 use mmb_domain::exchanges::symbol::Symbol;
 use mmb_domain::market::CurrencyPair;
 use mmb_domain::market::{ExchangeAccountId, MarketAccountId};
@@ -128,6 +130,9 @@ struct DispositionExecutor {
     work_finished_sender: Option<oneshot::Sender<Result<()>>>,
     cancellation_token: CancellationToken,
     statistics: Arc<StatisticService>,
+    clock: Arc<dyn Clock>,
+    /// Why order book of traded market is considered invalid now
+    market_data_suspect: Option<MarketDataSuspectReason>,
 }
 
 impl DispositionExecutor {
@@ -143,10 +148,12 @@ impl DispositionExecutor {
         cancellation_token: CancellationToken,
         statistics: Arc<StatisticService>,
     ) -> Self {
-        let symbol = engine_ctx
+        let exchange = engine_ctx
             .exchanges
             .get(&exchange_account_id)
             .expect("Target exchange should exists")
+            .clone();
+        let symbol = exchange
             .get_symbol(currency_pair)
             .expect("Currency pair symbol should exists for target trading place");
 
@@ -156,11 +163,13 @@ impl DispositionExecutor {
             local_snapshots_service,
             exchange_account_id,
             symbol,
-            orders_state: OrdersState::new(),
+            orders_state: OrdersState::new(strategy.price_slots_count()),
             strategy,
             work_finished_sender: Some(work_finished_sender),
             cancellation_token,
             statistics,
+            clock: exchange.clock().clone(),
+            market_data_suspect: None,
         }
     }
 
@@ -185,7 +194,7 @@ impl DispositionExecutor {
         event: &ExchangeEvent,
         last_trading_context: &mut Option<TradingContext>,
     ) -> Result<()> {
-        let now = now();
+        let now = self.clock.now();
         let need_recalculate_trading_context = self.prepare_estimate_trading_context(event, now);
 
         match event {
@@ -200,13 +209,24 @@ impl DispositionExecutor {
 
                 match order_event.event_type {
                     OrderEventType::CreateOrderSucceeded => nothing_to_do(),
-                    OrderEventType::CreateOrderFailed => {
+                    OrderEventType::CreateOrderFailed
+                    | OrderEventType::RejectedByRisk
+                    | OrderEventType::RejectedByBalance
+                    | OrderEventType::RejectedByValidation => {
                         let client_order_id = order.client_order_id();
-                        log::trace!("Started handling event CreateOrderFailed {client_order_id} in DispositionExecutor");
-                        let Some(price_slot) = self.get_price_slot(order) else { return Ok(()); };
+                        log::trace!(
+                            "Started handling event {:?} {client_order_id} in DispositionExecutor",
+                            order_event.event_type
+                        );
+                        let Some(price_slot) = self.get_price_slot(order) else {
+                            return Ok(());
+                        };
 
                         self.finish_order(order, price_slot)?;
-                        log::trace!("Finished handling event CreateOrderFailed {client_order_id} in DispositionExecutor");
+                        log::trace!(
+                            "Finished handling event {:?} {client_order_id} in DispositionExecutor",
+                            order_event.event_type
+                        );
                     }
                     OrderEventType::OrderFilled { ref cloned_order } => {
                         log::trace!(
@@ -219,6 +239,7 @@ impl DispositionExecutor {
                                 self.strategy.configuration_descriptor(),
                                 cloned_order,
                             );
+                            self.update_exposures(order);
 
                             if cloned_order.status() == OrderStatus::Completed {
                                 return Ok(());
@@ -266,11 +287,17 @@ impl DispositionExecutor {
 
                         // TODO save state to Database
                     }
+                    // order is already finished by CancelOrderSucceeded
+                    OrderEventType::OrderExpired => nothing_to_do(),
                 }
             }
             _ => nothing_to_do(),
         };
 
+        if need_recalculate_trading_context && self.is_market_data_suspect(now) {
+            return Ok(());
+        }
+
         let mut new_trading_context = estimate_trading_context(
             need_recalculate_trading_context,
             event,
@@ -375,6 +402,22 @@ impl DispositionExecutor {
             return Ok(());
         }
 
+        let market_account_id =
+            MarketAccountId::new(self.exchange_account_id, self.symbol.currency_pair());
+        if !self
+            .engine_ctx
+            .trading_sessions
+            .is_session_open(market_account_id, now)
+        {
+            self.start_cancelling_all_orders(
+                "trading session is closed",
+                &mut composite_order.borrow_mut(),
+                explanation,
+            );
+
+            return Ok(());
+        }
+
         // TODO close position if needed
 
         let new_estimating = match new_estimating {
@@ -558,7 +601,8 @@ impl DispositionExecutor {
 
             Ok(())
         };
-        spawn_future(
+        spawn_future_in(
+            RuntimeKind::OrderManagement,
             "Start wait_cancel_order from DispositionExecutor::cancel_order()",
             SpawnFutureFlags::empty(),
             action,
@@ -745,7 +789,8 @@ impl DispositionExecutor {
                 Ok(())
             };
 
-            spawn_future(
+            spawn_future_in(
+                RuntimeKind::OrderManagement,
                 "create_order in blocking try_create_order",
                 SpawnFutureFlags::empty(),
                 action,
@@ -816,6 +861,21 @@ impl DispositionExecutor {
         None
     }
 
+    fn update_exposures(&self, order: &OrderRef) {
+        let risk_manager = self
+            .engine_ctx
+            .exchanges
+            .get(&order.exchange_account_id())
+            .and_then(|x| x.risk_manager());
+
+        if let Some(risk_manager) = risk_manager {
+            let balance_manager = self.engine_ctx.balance_manager.lock();
+            risk_manager.update_exposures(|exchange_account_id, currency_pair| {
+                balance_manager.net_position(exchange_account_id, currency_pair)
+            });
+        }
+    }
+
     fn finish_order(&self, order: &OrderRef, price_slot: &PriceSlot) -> Result<()> {
         let client_order_id = order.client_order_id();
         log::trace!("Started DispositionExecutor::finish_order {client_order_id}");
@@ -881,6 +941,49 @@ impl DispositionExecutor {
             .clone()
     }
 
+    /// Checks order book of traded market, so orders aren't placed by invalid data.
+    /// `MarketDataSuspect` event is sent when the book becomes suspect
+    fn is_market_data_suspect(&mut self, now: DateTime) -> bool {
+        let settings = match &self.engine_ctx.core_settings.market_data_sanity {
+            None => return false,
+            Some(settings) => settings,
+        };
+
+        let market_account_id =
+            MarketAccountId::new(self.exchange_account_id, self.symbol.currency_pair());
+        let market_id = market_account_id.market_id();
+        let snapshot = match self.local_snapshots_service.get_snapshot(market_id) {
+            None => return false,
+            Some(snapshot) => snapshot,
+        };
+        let is_crossed = self.local_snapshots_service.is_crossed(market_id);
+        let reason = check_order_book(snapshot, is_crossed, now, settings);
+
+        let previous_reason = std::mem::replace(&mut self.market_data_suspect, reason);
+        match reason {
+            Some(reason) if previous_reason != Some(reason) => {
+                log::warn!(
+                    "Orders aren't placed on {market_account_id} because market data is suspect: {reason:?}"
+                );
+                self.engine_ctx.send_event(ExchangeEvent::MarketDataSuspect(
+                    MarketDataSuspectEvent {
+                        exchange_account_id: self.exchange_account_id,
+                        currency_pair: self.symbol.currency_pair(),
+                        reason,
+                        last_update_time: snapshot.last_update_time,
+                        time: now,
+                    },
+                ));
+            }
+            None if previous_reason.is_some() => {
+                log::info!("Market data of {market_account_id} is valid again");
+            }
+            _ => {}
+        }
+
+        reason.is_some()
+    }
+
     fn prepare_estimate_trading_context(&self, event: &ExchangeEvent, now: DateTime) -> bool {
         let event_time = match event {
             ExchangeEvent::OrderBookEvent(order_book_event) => order_book_event.creation_time,
@@ -959,10 +1062,6 @@ fn get_cancelling_orders<'a>(
     cancelling_orders
 }
 
-fn now() -> DateTime {
-    Utc::now()
-}
-
 #[inline(always)]
 fn log_trace(msg: impl AsRef<str>, explanation: &mut Explanation) -> Result<()> {
     let msg = msg.as_ref();
@@ -324,14 +324,14 @@ struct OrdersStateBySide {
 }
 
 impl OrdersStateBySide {
-    pub fn new(_side: OrderSide) -> Self {
+    pub fn new(_side: OrderSide, slots_count: usize) -> Self {
         OrdersStateBySide {
             _side,
-            // TODO create list of PriceSlots by config
-            slots: vec![PriceSlot::new(
-                PriceSlotId::new("PriceSlotId".into(), 0),
-                _side,
-            )],
+            slots: (0..slots_count)
+                .map(|level_index| {
+                    PriceSlot::new(PriceSlotId::new("PriceSlotId".into(), level_index), _side)
+                })
+                .collect(),
         }
     }
 
@@ -357,10 +357,10 @@ struct OrdersState {
 }
 
 impl OrdersState {
-    pub fn new() -> Self {
+    pub fn new(slots_count: usize) -> Self {
         OrdersState {
             by_side: enum_map! {
-                side => OrdersStateBySide::new(side),
+                side => OrdersStateBySide::new(side, slots_count),
             },
         }
     }
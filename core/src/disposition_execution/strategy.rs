@@ -30,4 +30,9 @@ pub trait DispositionStrategy: Send + Sync + 'static {
     ) -> Result<()>;
 
     fn configuration_descriptor(&self) -> ConfigurationDescriptor;
+
+    /// Count of price slots by each side. Trading context should contain estimation for every slot
+    fn price_slots_count(&self) -> usize {
+        1
+    }
 }
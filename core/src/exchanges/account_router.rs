@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{ensure, Context, Result};
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_domain::market::{ExchangeAccountId, MarketId};
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::OrderHeader;
+use mmb_utils::cancellation_token::CancellationToken;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+
+use crate::exchanges::exchange_blocker::ExchangeBlocker;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
+use crate::settings::{AccountRoutingSettings, RoutingPolicy};
+
+/// Chooses exchange account for orders when several accounts of the same exchange trade the
+/// currency pair, so request limits are spread between accounts and strategies can be segregated
+pub struct AccountRouter {
+    settings: AccountRoutingSettings,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    timeout_manager: Arc<TimeoutManager>,
+    exchange_blocker: Arc<ExchangeBlocker>,
+    /// Index of account for the next order of market by round-robin policy
+    next_indexes: Mutex<HashMap<MarketId, usize>>,
+}
+
+impl AccountRouter {
+    pub fn new(
+        settings: AccountRoutingSettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        timeout_manager: Arc<TimeoutManager>,
+        exchange_blocker: Arc<ExchangeBlocker>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            settings,
+            exchanges,
+            timeout_manager,
+            exchange_blocker,
+            next_indexes: Default::default(),
+        })
+    }
+
+    /// Creates order on exchange account chosen by routing policy. Exchange account id of header
+    /// is used only to specify exchange
+    pub async fn create_order(
+        &self,
+        order_header: &OrderHeader,
+        cancellation_token: CancellationToken,
+    ) -> Result<OrderRef> {
+        let exchange_account_id =
+            self.route(order_header.market_id(), &order_header.strategy_name)?;
+        let exchange = self
+            .exchanges
+            .get(&exchange_account_id)
+            .map(|x| x.clone())
+            .with_context(|| format!("Exchange {exchange_account_id} isn't found"))?;
+
+        let order_header = OrderHeader {
+            exchange_account_id,
+            ..order_header.clone()
+        };
+        exchange
+            .create_order(&order_header, None, cancellation_token)
+            .await
+    }
+
+    pub fn route(&self, market_id: MarketId, strategy_name: &str) -> Result<ExchangeAccountId> {
+        let exchange_account_id =
+            self.choose(market_id, strategy_name, self.candidates(market_id))?;
+        log::trace!(
+            "Order of strategy {strategy_name} on {market_id} is routed to {exchange_account_id}"
+        );
+        Ok(exchange_account_id)
+    }
+
+    /// Accounts of exchange which can trade currency pair now, ordered by account number
+    fn candidates(&self, market_id: MarketId) -> Vec<ExchangeAccountId> {
+        self.exchanges
+            .iter()
+            .filter(|x| {
+                x.exchange_account_id.exchange_id == market_id.exchange_id
+                    && x.symbols.contains_key(&market_id.currency_pair)
+                    && !x.is_watch_only()
+                    && !x.is_trading_halted()
+                    && !self.exchange_blocker.is_blocked(x.exchange_account_id)
+            })
+            .map(|x| x.exchange_account_id)
+            .sorted_by_key(|x| x.account_number)
+            .collect()
+    }
+
+    fn choose(
+        &self,
+        market_id: MarketId,
+        strategy_name: &str,
+        candidates: Vec<ExchangeAccountId>,
+    ) -> Result<ExchangeAccountId> {
+        if self.settings.policy == RoutingPolicy::PerStrategy {
+            let account_number = self
+                .settings
+                .strategy_accounts
+                .get(strategy_name)
+                .with_context(|| {
+                    format!("There is no account designated for strategy {strategy_name}")
+                })?;
+            let exchange_account_id =
+                ExchangeAccountId::new(market_id.exchange_id, *account_number);
+            ensure!(
+                candidates.contains(&exchange_account_id),
+                "Account {exchange_account_id} designated for strategy {strategy_name} can't trade {} now",
+                market_id.currency_pair
+            );
+            return Ok(exchange_account_id);
+        }
+
+        ensure!(
+            !candidates.is_empty(),
+            "There are no accounts which can trade {market_id} now"
+        );
+
+        let exchange_account_id = match self.settings.policy {
+            RoutingPolicy::LowestUtilization => candidates
+                .into_iter()
+                .min_by_key(|x| self.utilization(*x))
+                .expect("candidates shouldn't be empty"),
+            RoutingPolicy::RoundRobin | RoutingPolicy::PerStrategy => {
+                let mut next_indexes = self.next_indexes.lock();
+                let next_index = next_indexes.entry(market_id).or_default();
+                let exchange_account_id = candidates[*next_index % candidates.len()];
+                *next_index = next_index.wrapping_add(1);
+                exchange_account_id
+            }
+        };
+
+        Ok(exchange_account_id)
+    }
+
+    fn utilization(&self, exchange_account_id: ExchangeAccountId) -> Decimal {
+        self.timeout_manager.utilization(exchange_account_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::request_type::RequestType;
+    use crate::exchanges::timeouts::requests_timeout_manager_factory::{
+        RequestTimeoutArguments, RequestsTimeoutManagerFactory,
+    };
+    use crate::infrastructure::init_lifetime_manager;
+    use mmb_domain::market::CurrencyPair;
+    use mmb_utils::hashmap;
+
+    fn account(account_number: u8) -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", account_number)
+    }
+
+    fn market_id() -> MarketId {
+        MarketId::new(
+            "Binance".into(),
+            CurrencyPair::from_codes("BTC".into(), "USDT".into()),
+        )
+    }
+
+    fn router(settings: AccountRoutingSettings) -> Arc<AccountRouter> {
+        let _ = init_lifetime_manager();
+        let timeout_managers = (0..3)
+            .map(|x| {
+                let timeout_manager = RequestsTimeoutManagerFactory::from_requests_per_period(
+                    RequestTimeoutArguments::from_requests_per_minute(10),
+                    account(x),
+                );
+                (account(x), timeout_manager)
+            })
+            .collect();
+
+        AccountRouter::new(
+            settings,
+            DashMap::new(),
+            TimeoutManager::new(timeout_managers),
+            ExchangeBlocker::new((0..3).map(account).collect()),
+        )
+    }
+
+    fn candidates() -> Vec<ExchangeAccountId> {
+        (0..3).map(account).collect()
+    }
+
+    #[tokio::test]
+    async fn round_robin_cycles_through_accounts() {
+        let router = router(AccountRoutingSettings::default());
+
+        let routed = (0..4)
+            .map(|_| {
+                router
+                    .choose(market_id(), "strategy", candidates())
+                    .expect("in test")
+            })
+            .collect_vec();
+
+        assert_eq!(routed, vec![account(0), account(1), account(2), account(0)]);
+    }
+
+    #[tokio::test]
+    async fn least_utilized_account_is_chosen() {
+        let router = router(AccountRoutingSettings {
+            policy: RoutingPolicy::LowestUtilization,
+            ..Default::default()
+        });
+        for exchange_account_id in [account(0), account(0), account(1)] {
+            let _ = router
+                .timeout_manager
+                .try_reserve_instant(exchange_account_id, RequestType::CreateOrder);
+        }
+
+        let routed = router
+            .choose(market_id(), "strategy", candidates())
+            .expect("in test");
+
+        assert_eq!(routed, account(2));
+    }
+
+    #[tokio::test]
+    async fn strategy_trades_only_on_designated_account() {
+        let router = router(AccountRoutingSettings {
+            policy: RoutingPolicy::PerStrategy,
+            strategy_accounts: hashmap!["maker".to_owned() => 1],
+        });
+
+        let routed = router
+            .choose(market_id(), "maker", candidates())
+            .expect("in test");
+        assert_eq!(routed, account(1));
+
+        assert!(router
+            .choose(market_id(), "maker", vec![account(0), account(2)])
+            .is_err());
+        assert!(router.choose(market_id(), "taker", candidates()).is_err());
+    }
+}
@@ -12,3 +12,8 @@ impl_block_reason!(CREATE_ORDER_INSUFFICIENT_FUNDS);
 impl_block_reason!(REST_RATE_LIMIT);
 impl_block_reason!(GRACEFUL_SHUTDOWN);
 impl_block_reason!(EXCHANGE_UNAVAILABLE);
+impl_block_reason!(EXCHANGE_MAINTENANCE);
+impl_block_reason!(HIGH_ORDER_LATENCY);
+impl_block_reason!(COLD_START_CONFIRMATION);
+impl_block_reason!(KILL_SWITCH);
+impl_block_reason!(LOSS_LIMIT_ACKNOWLEDGEMENT);
@@ -0,0 +1,690 @@
+use crate::connectivity::WebSocketRole;
+use crate::exchanges::general::exchange::{BoxExchangeClient, Exchange, RequestResult};
+use crate::exchanges::general::order::cancel::CancelOrderResult;
+use crate::exchanges::general::order::create::CreateOrderResult;
+use crate::exchanges::general::order::get_order_trades::OrderTrade;
+use crate::exchanges::general::order::group::CreateOrderGroupResult;
+use crate::exchanges::traits::{
+    ExchangeClient, ExchangeError, HandleMetricsCb, HandleOrderFilledCb, HandleSequenceGapCb,
+    HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb, Support,
+};
+use crate::infrastructure::spawn_future_ok;
+use crate::secrets::ExchangeCredentials;
+use crate::settings::{ExchangeSettings, FaultInjectionSettings, FaultRates};
+use anyhow::{anyhow, ensure, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use futures::Future;
+use mmb_domain::events::ExchangeBalancesAndPositions;
+use mmb_domain::exchanges::symbol::Symbol;
+use mmb_domain::market::{
+    CurrencyCode, CurrencyId, CurrencyPair, ExchangeAccountId, SpecificCurrencyPair,
+};
+use mmb_domain::order::group::{OrderGroupId, OrderGroupType};
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{
+    ExchangeOrderId, OrderHeader, OrderInfo, OrderInfoExtensionData, OrderSide, Price,
+};
+use mmb_domain::position::{ActivePosition, ClosedPosition};
+use mmb_domain::transfer::{
+    AccountTransfer, ExchangeFundsMovementId, ExchangeTransferId, FundsMovement, TransferStatus,
+    Withdrawal,
+};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// Resolution of random points compared with fault rates
+const POINTS_COUNT: i64 = 1_000_000;
+const POINTS_SCALE: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultTarget {
+    Rest,
+    WebSocket,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// REST request is executed, but its response is lost. Websocket message isn't delivered
+    Drop,
+    /// Response or message is delivered later
+    Delay(Duration),
+    /// REST request is sent twice. Websocket message is delivered twice
+    Duplicate,
+    /// REST request is executed, but its response can't be parsed. Websocket message is truncated
+    Corrupt,
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fault::Drop => write!(f, "drop"),
+            Fault::Delay(delay) => write!(f, "delay {}ms", delay.as_millis()),
+            Fault::Duplicate => write!(f, "duplicate"),
+            Fault::Corrupt => write!(f, "corrupt"),
+        }
+    }
+}
+
+/// Sequence of faults generated from seed. Every request or message takes next random point from
+/// the sequence, so the same order of calls reproduces the same faults
+pub struct FaultSchedule {
+    settings: FaultInjectionSettings,
+    rng: Mutex<StdRng>,
+}
+
+impl FaultSchedule {
+    pub fn new(settings: FaultInjectionSettings) -> Result<Self> {
+        validate_rates(&settings.rest, "rest")?;
+        validate_rates(&settings.websocket, "websocket")?;
+
+        Ok(Self {
+            rng: Mutex::new(StdRng::seed_from_u64(settings.seed)),
+            settings,
+        })
+    }
+
+    pub fn next_fault(&self, target: FaultTarget) -> Option<Fault> {
+        let rates = match target {
+            FaultTarget::Rest => &self.settings.rest,
+            FaultTarget::WebSocket => &self.settings.websocket,
+        };
+
+        let mut rng = self.rng.lock();
+        let point = Decimal::new(rng.gen_range(0..POINTS_COUNT), POINTS_SCALE);
+
+        let mut threshold = rates.drop;
+        if point < threshold {
+            return Some(Fault::Drop);
+        }
+
+        threshold += rates.delay;
+        if point < threshold {
+            let delay_ms = rng.gen_range(0..=self.settings.max_delay_ms);
+            return Some(Fault::Delay(Duration::from_millis(delay_ms)));
+        }
+
+        threshold += rates.duplicate;
+        if point < threshold {
+            return Some(Fault::Duplicate);
+        }
+
+        threshold += rates.corrupt;
+        if point < threshold {
+            return Some(Fault::Corrupt);
+        }
+
+        None
+    }
+}
+
+fn validate_rates(rates: &FaultRates, target: &str) -> Result<()> {
+    let all_rates = [rates.drop, rates.delay, rates.duplicate, rates.corrupt];
+    ensure!(
+        all_rates
+            .iter()
+            .all(|rate| *rate >= dec!(0) && *rate <= dec!(1)),
+        "Fault rates for {target} should be in range [0, 1]: {rates:?}"
+    );
+    ensure!(
+        all_rates.iter().sum::<Decimal>() <= dec!(1),
+        "Sum of fault rates for {target} should not exceed 1: {rates:?}"
+    );
+
+    Ok(())
+}
+
+/// Wraps exchange client if fault injection is configured in settings
+pub fn with_fault_injection(
+    client: BoxExchangeClient,
+    settings: &ExchangeSettings,
+) -> Result<BoxExchangeClient> {
+    match &settings.fault_injection {
+        None => Ok(client),
+        Some(fault_injection) => {
+            log::warn!(
+                "Fault injection is enabled for {}: {fault_injection:?}",
+                settings.exchange_account_id
+            );
+            let schedule = FaultSchedule::new(fault_injection.clone())?;
+            Ok(Box::new(FaultInjectionClient::new(client, schedule)))
+        }
+    }
+}
+
+/// Exchange client which passes REST responses and websocket messages of inner client through
+/// fault schedule
+pub struct FaultInjectionClient {
+    inner: Arc<BoxExchangeClient>,
+    schedule: FaultSchedule,
+}
+
+impl FaultInjectionClient {
+    pub fn new(inner: BoxExchangeClient, schedule: FaultSchedule) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            schedule,
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut BoxExchangeClient {
+        Arc::get_mut(&mut self.inner)
+            .expect("Callbacks of exchange client should be set before any message is delivered")
+    }
+}
+
+/// Response of REST request which can be replaced with injected error
+trait FaultyResponse {
+    fn with_error(self, error: ExchangeError) -> Self;
+}
+
+impl FaultyResponse for CreateOrderResult {
+    fn with_error(self, error: ExchangeError) -> Self {
+        CreateOrderResult::failed(error, self.source_type)
+    }
+}
+
+impl FaultyResponse for CancelOrderResult {
+    fn with_error(self, error: ExchangeError) -> Self {
+        CancelOrderResult::failed(error, self.source_type)
+    }
+}
+
+impl<T> FaultyResponse for RequestResult<T> {
+    fn with_error(self, error: ExchangeError) -> Self {
+        RequestResult::Error(error)
+    }
+}
+
+impl<T> FaultyResponse for Result<T, ExchangeError> {
+    fn with_error(self, error: ExchangeError) -> Self {
+        Err(error)
+    }
+}
+
+impl<T> FaultyResponse for Result<T> {
+    fn with_error(self, error: ExchangeError) -> Self {
+        Err(error.into())
+    }
+}
+
+async fn send_with_fault<T, F>(
+    schedule: &FaultSchedule,
+    request_name: &str,
+    send: impl Fn() -> F,
+) -> T
+where
+    T: FaultyResponse,
+    F: Future<Output = T>,
+{
+    let fault = match schedule.next_fault(FaultTarget::Rest) {
+        None => return send().await,
+        Some(fault) => fault,
+    };
+
+    log::warn!("Injected fault '{fault}' into request {request_name}");
+
+    match fault {
+        Fault::Drop => send().await.with_error(ExchangeError::send(anyhow!(
+            "Response to {request_name} is dropped by fault injection"
+        ))),
+        Fault::Delay(delay) => {
+            let response = send().await;
+            tokio::time::sleep(delay).await;
+            response
+        }
+        Fault::Duplicate => {
+            let response = send().await;
+            let _ = send().await;
+            response
+        }
+        Fault::Corrupt => send().await.with_error(ExchangeError::parsing(format!(
+            "Response to {request_name} is corrupted by fault injection"
+        ))),
+    }
+}
+
+fn corrupt_message(msg: &str) -> &str {
+    let mut end = msg.len() / 2;
+    while !msg.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    &msg[..end]
+}
+
+#[async_trait]
+impl ExchangeClient for FaultInjectionClient {
+    async fn create_order(&self, order: &OrderRef) -> CreateOrderResult {
+        send_with_fault(&self.schedule, "create_order", || {
+            self.inner.create_order(order)
+        })
+        .await
+    }
+
+    async fn cancel_order(
+        &self,
+        order: &OrderRef,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> CancelOrderResult {
+        send_with_fault(&self.schedule, "cancel_order", || {
+            self.inner.cancel_order(order, exchange_order_id)
+        })
+        .await
+    }
+
+    async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        send_with_fault(&self.schedule, "cancel_all_orders", || {
+            self.inner.cancel_all_orders(currency_pair)
+        })
+        .await
+    }
+
+    async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
+        send_with_fault(&self.schedule, "get_open_orders", || {
+            self.inner.get_open_orders()
+        })
+        .await
+    }
+
+    async fn get_open_orders_by_currency_pair(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<Vec<OrderInfo>> {
+        send_with_fault(&self.schedule, "get_open_orders_by_currency_pair", || {
+            self.inner.get_open_orders_by_currency_pair(currency_pair)
+        })
+        .await
+    }
+
+    async fn get_order_info(&self, order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
+        send_with_fault(&self.schedule, "get_order_info", || {
+            self.inner.get_order_info(order)
+        })
+        .await
+    }
+
+    async fn close_position(
+        &self,
+        position: &ActivePosition,
+        price: Option<Price>,
+    ) -> Result<ClosedPosition> {
+        self.inner.close_position(position, price).await
+    }
+
+    async fn get_active_positions(&self) -> Result<Vec<ActivePosition>> {
+        send_with_fault(&self.schedule, "get_active_positions", || {
+            self.inner.get_active_positions()
+        })
+        .await
+    }
+
+    async fn get_balance_and_positions(&self) -> Result<ExchangeBalancesAndPositions> {
+        send_with_fault(&self.schedule, "get_balance_and_positions", || {
+            self.inner.get_balance_and_positions()
+        })
+        .await
+    }
+
+    async fn get_my_trades(
+        &self,
+        symbol: &Symbol,
+        from_datetime: Option<DateTime>,
+    ) -> RequestResult<Vec<OrderTrade>> {
+        send_with_fault(&self.schedule, "get_my_trades", || {
+            self.inner.get_my_trades(symbol, from_datetime)
+        })
+        .await
+    }
+
+    async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>> {
+        self.inner.build_all_symbols().await
+    }
+
+    async fn get_server_time(&self) -> Option<Result<i64>> {
+        self.inner.get_server_time().await
+    }
+
+    fn supports_native_order_group(
+        &self,
+        group_type: OrderGroupType,
+        legs: &[OrderHeader],
+    ) -> bool {
+        self.inner.supports_native_order_group(group_type, legs)
+    }
+
+    async fn create_order_group(
+        &self,
+        group_id: OrderGroupId,
+        group_type: OrderGroupType,
+        legs: &[OrderRef],
+    ) -> Result<CreateOrderGroupResult, ExchangeError> {
+        send_with_fault(&self.schedule, "create_order_group", || {
+            self.inner.create_order_group(group_id, group_type, legs)
+        })
+        .await
+    }
+
+    async fn transfer_between_accounts(
+        &self,
+        transfer: &AccountTransfer,
+        from_sub_account: Option<&str>,
+        to_sub_account: Option<&str>,
+    ) -> Result<ExchangeTransferId, ExchangeError> {
+        send_with_fault(&self.schedule, "transfer_between_accounts", || {
+            self.inner
+                .transfer_between_accounts(transfer, from_sub_account, to_sub_account)
+        })
+        .await
+    }
+
+    async fn get_transfer_status(
+        &self,
+        transfer_id: &ExchangeTransferId,
+    ) -> Result<TransferStatus, ExchangeError> {
+        send_with_fault(&self.schedule, "get_transfer_status", || {
+            self.inner.get_transfer_status(transfer_id)
+        })
+        .await
+    }
+
+    async fn withdraw(
+        &self,
+        withdrawal: &Withdrawal,
+    ) -> Result<ExchangeFundsMovementId, ExchangeError> {
+        send_with_fault(&self.schedule, "withdraw", || {
+            self.inner.withdraw(withdrawal)
+        })
+        .await
+    }
+
+    async fn get_funds_movements(
+        &self,
+        from_datetime: DateTime,
+    ) -> Result<Vec<FundsMovement>, ExchangeError> {
+        send_with_fault(&self.schedule, "get_funds_movements", || {
+            self.inner.get_funds_movements(from_datetime)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl Support for FaultInjectionClient {
+    fn as_any(&self) -> &(dyn Any + Send + Sync + 'static) {
+        // downcasting to connector type should still work
+        self.inner.as_any()
+    }
+
+    async fn initialized(&self, exchange: Arc<Exchange>) {
+        self.inner.initialized(exchange).await
+    }
+
+    fn on_websocket_message(&self, msg: &str) -> Result<()> {
+        let fault = match self.schedule.next_fault(FaultTarget::WebSocket) {
+            None => return self.inner.on_websocket_message(msg),
+            Some(fault) => fault,
+        };
+
+        log::warn!("Injected fault '{fault}' into websocket message {msg}");
+
+        match fault {
+            Fault::Drop => Ok(()),
+            Fault::Delay(delay) => {
+                let inner = self.inner.clone();
+                let msg = msg.to_owned();
+                spawn_future_ok(
+                    "Delayed websocket message",
+                    SpawnFutureFlags::STOP_BY_TOKEN,
+                    async move {
+                        tokio::time::sleep(delay).await;
+                        if let Err(error) = inner.on_websocket_message(&msg) {
+                            log::warn!(
+                                "Error occurred while delayed websocket message processing: {error:?}. For message: {msg}"
+                            );
+                        }
+                    },
+                );
+                Ok(())
+            }
+            Fault::Duplicate => {
+                self.inner.on_websocket_message(msg)?;
+                self.inner.on_websocket_message(msg)
+            }
+            Fault::Corrupt => self.inner.on_websocket_message(corrupt_message(msg)),
+        }
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        self.inner.on_connecting()
+    }
+
+    fn on_connected(&self) -> Result<()> {
+        self.inner.on_connected()
+    }
+
+    fn on_disconnected(&self) -> Result<()> {
+        self.inner.on_disconnected()
+    }
+
+    fn set_send_websocket_message_callback(&mut self, callback: SendWebsocketMessageCb) {
+        self.inner_mut()
+            .set_send_websocket_message_callback(callback)
+    }
+
+    fn set_order_created_callback(&mut self, callback: OrderCreatedCb) {
+        self.inner_mut().set_order_created_callback(callback)
+    }
+
+    fn set_order_cancelled_callback(&mut self, callback: OrderCancelledCb) {
+        self.inner_mut().set_order_cancelled_callback(callback)
+    }
+
+    fn set_handle_order_filled_callback(&mut self, callback: HandleOrderFilledCb) {
+        self.inner_mut().set_handle_order_filled_callback(callback)
+    }
+
+    fn set_handle_trade_callback(&mut self, callback: HandleTradeCb) {
+        self.inner_mut().set_handle_trade_callback(callback)
+    }
+
+    fn set_handle_metrics_callback(&mut self, callback: HandleMetricsCb) {
+        self.inner_mut().set_handle_metrics_callback(callback)
+    }
+
+    fn set_handle_sequence_gap_callback(&mut self, callback: HandleSequenceGapCb) {
+        self.inner_mut().set_handle_sequence_gap_callback(callback)
+    }
+
+    fn set_traded_specific_currencies(&self, currencies: Vec<SpecificCurrencyPair>) {
+        self.inner.set_traded_specific_currencies(currencies)
+    }
+
+    fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
+        self.inner.is_websocket_enabled(role)
+    }
+
+    async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url> {
+        self.inner.create_ws_url(role).await
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        self.inner.get_specific_currency_pair(currency_pair)
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        self.inner.get_supported_currencies()
+    }
+
+    fn should_log_message(&self, message: &str) -> bool {
+        self.inner.should_log_message(message)
+    }
+
+    fn log_unknown_message(&self, exchange_account_id: ExchangeAccountId, message: &str) {
+        self.inner.log_unknown_message(exchange_account_id, message)
+    }
+
+    fn get_balance_reservation_currency_code(
+        &self,
+        symbol: Arc<Symbol>,
+        side: OrderSide,
+    ) -> CurrencyCode {
+        self.inner
+            .get_balance_reservation_currency_code(symbol, side)
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        self.inner.get_settings()
+    }
+
+    fn update_credentials(&self, credentials: &ExchangeCredentials) -> Result<()> {
+        self.inner.update_credentials(credentials)
+    }
+
+    fn get_initial_extension_data(&self) -> Option<Box<dyn OrderInfoExtensionData>> {
+        self.inner.get_initial_extension_data()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::market::ExchangeErrorType;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn settings(rest: FaultRates) -> FaultInjectionSettings {
+        FaultInjectionSettings {
+            seed: 42,
+            max_delay_ms: 10,
+            rest,
+            websocket: FaultRates::default(),
+        }
+    }
+
+    fn rates(drop: Decimal, delay: Decimal, duplicate: Decimal, corrupt: Decimal) -> FaultRates {
+        FaultRates {
+            drop,
+            delay,
+            duplicate,
+            corrupt,
+        }
+    }
+
+    fn faults(schedule: &FaultSchedule, count: usize) -> Vec<Option<Fault>> {
+        (0..count)
+            .map(|_| schedule.next_fault(FaultTarget::Rest))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_produces_same_faults() {
+        let rest = rates(dec!(0.1), dec!(0.1), dec!(0.1), dec!(0.1));
+        let first = FaultSchedule::new(settings(rest.clone())).expect("in test");
+        let second = FaultSchedule::new(settings(rest)).expect("in test");
+
+        let first_faults = faults(&first, 1000);
+
+        assert_eq!(first_faults, faults(&second, 1000));
+        assert!(first_faults.contains(&Some(Fault::Drop)));
+        assert!(first_faults.contains(&Some(Fault::Duplicate)));
+        assert!(first_faults.contains(&Some(Fault::Corrupt)));
+        assert!(first_faults.contains(&None));
+    }
+
+    #[test]
+    fn no_faults_for_zero_rates() {
+        let schedule = FaultSchedule::new(settings(FaultRates::default())).expect("in test");
+
+        assert!(faults(&schedule, 1000).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn always_fault_for_full_rate() {
+        let rest = rates(dec!(0), dec!(1), dec!(0), dec!(0));
+        let schedule = FaultSchedule::new(settings(rest)).expect("in test");
+
+        for fault in faults(&schedule, 1000) {
+            match fault {
+                Some(Fault::Delay(delay)) => assert!(delay <= Duration::from_millis(10)),
+                _ => panic!("Unexpected fault {fault:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_rates_are_rejected() {
+        let too_big_sum = rates(dec!(0.5), dec!(0.5), dec!(0.1), dec!(0));
+        assert!(FaultSchedule::new(settings(too_big_sum)).is_err());
+
+        let negative = rates(dec!(-0.1), dec!(0), dec!(0), dec!(0));
+        assert!(FaultSchedule::new(settings(negative)).is_err());
+    }
+
+    async fn send_request(schedule: &FaultSchedule, requests_count: &AtomicUsize) -> Result<u32> {
+        send_with_fault(schedule, "test_request", || async {
+            requests_count.fetch_add(1, Ordering::SeqCst);
+            Ok(1)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn dropped_response_is_send_error() {
+        let rest = rates(dec!(1), dec!(0), dec!(0), dec!(0));
+        let schedule = FaultSchedule::new(settings(rest)).expect("in test");
+        let requests_count = AtomicUsize::new(0);
+
+        let result: Result<u32, ExchangeError> = send_with_fault(&schedule, "test_request", || {
+            requests_count.fetch_add(1, Ordering::SeqCst);
+            async { Ok(1) }
+        })
+        .await;
+
+        let error = result.expect_err("in test");
+        assert_eq!(error.error_type, ExchangeErrorType::SendError);
+        // request reaches exchange, only response is lost
+        assert_eq!(requests_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn corrupted_response_is_parsing_error() {
+        let rest = rates(dec!(0), dec!(0), dec!(0), dec!(1));
+        let schedule = FaultSchedule::new(settings(rest)).expect("in test");
+
+        let result: RequestResult<u32> = send_with_fault(&schedule, "test_request", || async {
+            RequestResult::Success(1)
+        })
+        .await;
+
+        let error = result.get_error().expect("in test");
+        assert_eq!(error.error_type, ExchangeErrorType::ParsingError);
+    }
+
+    #[tokio::test]
+    async fn duplicated_request_is_sent_twice() {
+        let rest = rates(dec!(0), dec!(0), dec!(1), dec!(0));
+        let schedule = FaultSchedule::new(settings(rest)).expect("in test");
+        let requests_count = AtomicUsize::new(0);
+
+        let result = send_request(&schedule, &requests_count).await;
+
+        assert_eq!(result.expect("in test"), 1);
+        assert_eq!(requests_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn corrupted_message_is_truncated_on_char_boundary() {
+        assert_eq!(corrupt_message("{\"id\":1}"), "{\"id");
+        assert_eq!(corrupt_message("ёёё"), "ё");
+    }
+}
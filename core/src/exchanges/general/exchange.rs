@@ -1,23 +1,36 @@
 use super::polling_timeout_manager::PollingTimeoutManager;
 use crate::balance::manager::balance_manager::BalanceManager;
 use crate::connectivity::{
-    websocket_open, ConnectivityError, WebSocketParams, WebSocketRole, WsSender,
+    websocket_open, ConnectivityError, ReconnectBackoff, WebSocketParams, WebSocketRole,
+    WsReceivers, WsSender,
 };
 use crate::database::events::recorder::EventRecorder;
+use crate::database::journal::{Journal, JournalRecord};
 use crate::exchanges::block_reasons::WEBSOCKET_DISCONNECTED;
 use crate::exchanges::exchange_blocker::{BlockType, ExchangeBlocker};
 use crate::exchanges::general::features::ExchangeFeatures;
 use crate::exchanges::general::order::cancel::CancelOrderResult;
 use crate::exchanges::general::order::create::CreateOrderResult;
+use crate::exchanges::general::order::group::OrderGroup;
+use crate::exchanges::general::order::latency::OrderLatencies;
 use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::general::system_status::MaintenanceState;
+use crate::exchanges::stream_watchdog::StreamWatchdog;
 use crate::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
 use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
 use crate::exchanges::traits::{ExchangeClient, ExchangeError};
 use crate::infrastructure::spawn_future;
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
-use crate::misc::time::time_manager;
-use crate::orders::buffered_fills::buffered_canceled_orders_manager::BufferedCanceledOrdersManager;
-use crate::orders::buffered_fills::buffered_fills_manager::BufferedFillsManager;
+use crate::misc::clock::Clock;
+use crate::orders::dead_letter_queue::DeadLetterQueue;
+use crate::risk::kill_switch::KillSwitch;
+use crate::risk::risk_manager::RiskManager;
+use crate::runtimes::{spawn_future_in, RuntimeKind};
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use crate::services::cross_rates::CrossRateService;
+use crate::services::index_price::IndexPriceService;
+use crate::services::volatility::VolatilityService;
+use crate::settings::{ExternalOrdersSettings, FeeSettings, StreamWatchdogSettings};
 use anyhow::{bail, Context, Result};
 use dashmap::DashMap;
 use function_name::named;
@@ -25,32 +38,39 @@ use futures::future::join_all;
 use itertools::Itertools;
 use mmb_database::impl_event;
 use mmb_domain::events::{
-    BalanceUpdateEvent, ExchangeBalancesAndPositions, ExchangeEvent, LiquidationPriceEvent,
-    MetricsEvent, MetricsEventInfo, MetricsEventInfoBase, MetricsEventType, MetricsTime, Trade,
+    BalanceUpdateEvent, ConnectivityEvent, ConnectivityState, ExchangeBalancesAndPositions,
+    ExchangeEvent, LiquidationPriceEvent, MetricsEvent, MetricsEventInfo, MetricsEventInfoBase,
+    MetricsEventType, MetricsTime, StreamStaleEvent, Trade,
 };
-use mmb_domain::exchanges::commission::Commission;
+use mmb_domain::exchanges::commission::{Commission, FeeSchedule};
 use mmb_domain::exchanges::symbol::Symbol;
 use mmb_domain::market::{
     CurrencyCode, CurrencyPair, ExchangeAccountId, MarketId, SpecificCurrencyPair,
 };
 use mmb_domain::order::event::OrderEvent;
 use mmb_domain::order::event::OrderEventType;
+use mmb_domain::order::fill::OrderFill;
+use mmb_domain::order::group::OrderGroupId;
+use mmb_domain::order::history::OrderChangeType;
 use mmb_domain::order::pool::OrderRef;
 use mmb_domain::order::pool::OrdersPool;
 use mmb_domain::order::snapshot::OrderSide;
 use mmb_domain::order::snapshot::{Amount, Price};
-use mmb_domain::order::snapshot::{ClientOrderId, ExchangeOrderId};
+use mmb_domain::order::snapshot::{ClientOrderId, ExchangeOrderId, OrderSnapshot};
 use mmb_domain::position::{ActivePosition, ClosedPosition, DerivativePosition};
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::infrastructure::{SpawnFutureFlags, WithExpect};
+use mmb_utils::object_pool::{ObjectPool, SharedPool};
 use mmb_utils::send_expected::SendExpectedByRef;
 use mmb_utils::{nothing_to_do, DateTime};
-use parking_lot::Mutex;
+use once_cell::sync::OnceCell;
+use parking_lot::{Mutex, RwLock};
 use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
 use serde::Serialize;
 use std::fmt::Debug;
 use std::ops::DerefMut;
-use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
 use std::time::Duration;
 use tokio::sync::{broadcast, oneshot};
@@ -95,11 +115,16 @@ pub struct Exchange {
     pub currencies: Mutex<Vec<CurrencyCode>>,
     pub leverage_by_currency_pair: DashMap<CurrencyPair, Decimal>,
     pub order_book_top: DashMap<CurrencyPair, OrderBookTop>,
+    pub(super) order_book_update_times: DashMap<CurrencyPair, DateTime>,
     pub exchange_client: BoxExchangeClient,
     pub(super) features: ExchangeFeatures,
-    pub(super) events_channel: broadcast::Sender<ExchangeEvent>,
+    pub(crate) events_channel: broadcast::Sender<ExchangeEvent>,
     pub(super) lifetime_manager: Arc<AppLifetimeManager>,
-    pub(super) commission: Commission,
+    pub(super) commission: RwLock<Commission>,
+    pub(super) fee_schedule: Mutex<Option<FeeSchedule>>,
+    pub(super) external_orders: Mutex<Option<ExternalOrdersSettings>>,
+    pub(super) maintenance: Mutex<MaintenanceState>,
+    pub(super) order_latencies: Mutex<OrderLatencies>,
     pub(super) wait_cancel_order: DashMap<ClientOrderId, broadcast::Sender<()>>,
     pub(super) wait_finish_order: DashMap<ClientOrderId, broadcast::Sender<OrderRef>>,
     pub(super) polling_trades_counts: DashMap<ExchangeAccountId, u32>,
@@ -110,8 +135,23 @@ pub struct Exchange {
     pub(super) last_trades: DashMap<MarketId, Trade>,
     pub(super) timeout_manager: Arc<TimeoutManager>,
     pub(crate) balance_manager: Mutex<Option<Weak<Mutex<BalanceManager>>>>,
-    pub(super) buffered_fills_manager: Mutex<BufferedFillsManager>,
-    pub(super) buffered_canceled_orders_manager: Mutex<BufferedCanceledOrdersManager>,
+    pub(super) risk_manager: Mutex<Option<Arc<RiskManager>>>,
+    pub(super) volatility_service: Mutex<Option<Arc<VolatilityService>>>,
+    /// Weak because index price service holds exchanges itself
+    pub(super) index_price_service: Mutex<Option<Weak<IndexPriceService>>>,
+    /// Weak because cross rate service holds exchanges itself
+    pub(super) cross_rate_service: Mutex<Option<Weak<CrossRateService>>>,
+    pub(super) kill_switch: Mutex<Option<Arc<KillSwitch>>>,
+    pub(super) order_groups: DashMap<OrderGroupId, Arc<OrderGroup>>,
+    pub(super) order_group_by_leg: DashMap<ClientOrderId, OrderGroupId>,
+    /// Orders which balance was reserved on creation by exchange instead of disposition executor
+    pub(super) balance_reserved_orders: DashMap<ClientOrderId, ConfigurationDescriptor>,
+    pub(super) journal: Mutex<Option<Arc<Journal>>>,
+    pub(super) dead_letter_queue: Mutex<DeadLetterQueue>,
+    /// Snapshots of orders sent in fill events, they are reused after consumers dropped them
+    pub order_snapshots_pool: SharedPool<OrderSnapshot>,
+    /// Buffers for copies of order fills during fill handling
+    pub order_fills_pool: ObjectPool<Vec<OrderFill>>,
     // It allows to send and receive notification about event in websocket channel
     // Websocket event is main source detecting order creation result
     // Rest response using only for unsuccessful operations as error
@@ -130,19 +170,39 @@ pub struct Exchange {
             Option<oneshot::Receiver<CancelOrderResult>>,
         ),
     >,
-    exchange_blocker: Weak<ExchangeBlocker>,
+    pub(super) exchange_blocker: Weak<ExchangeBlocker>,
     ws_sender: Mutex<Option<WsSender>>,
     auto_reconnect: AtomicBool,
+    reconnect_backoff: Mutex<ReconnectBackoff>,
+    connectivity_state: Mutex<Option<ConnectivityState>>,
+    /// Channels subscribed at runtime, they are resubscribed after every reconnection
+    websocket_subscriptions: Mutex<Vec<WebSocketSubscription>>,
+    /// Incremented on every connection, so closing of replaced connection isn't handled as
+    /// disconnection
+    connection_generation: AtomicU64,
+    stream_watchdog: OnceCell<StreamWatchdog>,
 
     // Temporary fix before integration ExchangeBlocker to wait_order_finish/wait_cancel_order fallbacks #641
     timeout: Duration,
     // Equal 0 by default in case if we cannot get exchange server time
     server_time_latency: AtomicI64,
     pub event_recorder: Arc<EventRecorder>,
+    pub(super) clock: Arc<dyn Clock>,
 }
 
+/// Enough for snapshots of fill events which are waiting for consumers at high load
+const ORDER_SNAPSHOTS_POOL_CAPACITY: usize = 1024;
+const ORDER_FILLS_POOL_CAPACITY: usize = 16;
+
 pub type BoxExchangeClient = Box<dyn ExchangeClient + Send + Sync + 'static>;
 
+#[derive(Debug, Clone)]
+struct WebSocketSubscription {
+    role: WebSocketRole,
+    channel: String,
+    message: String,
+}
+
 impl Exchange {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -158,7 +218,8 @@ impl Exchange {
         commission: Commission,
         event_recorder: Arc<EventRecorder>,
     ) -> Arc<Self> {
-        let polling_timeout_manager = PollingTimeoutManager::new(timeout_arguments);
+        let clock = timeout_manager.clock().clone();
+        let polling_timeout_manager = PollingTimeoutManager::new(timeout_arguments, clock.clone());
 
         Arc::new_cyclic(move |e| {
             Self::setup_exchange_client(e.clone(), exchange_client.as_mut());
@@ -175,10 +236,15 @@ impl Exchange {
                 features,
                 events_channel,
                 timeout_manager,
-                commission,
+                commission: RwLock::new(commission),
+                fee_schedule: Mutex::new(None),
+                external_orders: Mutex::new(None),
+                maintenance: Default::default(),
+                order_latencies: Default::default(),
                 symbols: Default::default(),
                 currencies: Default::default(),
                 order_book_top: Default::default(),
+                order_book_update_times: Default::default(),
                 wait_cancel_order: DashMap::new(),
                 wait_finish_order: DashMap::new(),
                 polling_trades_counts: DashMap::new(),
@@ -189,13 +255,29 @@ impl Exchange {
                 last_trades_update_time: DashMap::new(),
                 last_trades: DashMap::new(),
                 balance_manager: Mutex::new(None),
-                buffered_fills_manager: Default::default(),
+                risk_manager: Mutex::new(None),
+                volatility_service: Mutex::new(None),
+                index_price_service: Mutex::new(None),
+                cross_rate_service: Mutex::new(None),
+                kill_switch: Mutex::new(None),
+                order_groups: DashMap::new(),
+                order_group_by_leg: DashMap::new(),
+                balance_reserved_orders: DashMap::new(),
+                journal: Mutex::new(None),
+                dead_letter_queue: Default::default(),
+                order_snapshots_pool: SharedPool::new(ORDER_SNAPSHOTS_POOL_CAPACITY),
+                order_fills_pool: ObjectPool::new(ORDER_FILLS_POOL_CAPACITY),
                 exchange_blocker,
-                buffered_canceled_orders_manager: Default::default(),
                 auto_reconnect: AtomicBool::new(false),
+                reconnect_backoff: Default::default(),
+                connectivity_state: Mutex::new(None),
+                websocket_subscriptions: Default::default(),
+                connection_generation: AtomicU64::new(0),
+                stream_watchdog: OnceCell::new(),
                 timeout,
                 server_time_latency: Default::default(),
                 event_recorder,
+                clock,
             }
         })
     }
@@ -255,6 +337,14 @@ impl Exchange {
             }
         }));
 
+        exchange_client.set_handle_sequence_gap_callback(Box::new({
+            let exchange_weak = exchange_weak.clone();
+            move |gap| match exchange_weak.upgrade() {
+                Some(exchange) => exchange.handle_sequence_gap(gap),
+                None => log::info!("Unable to upgrade weak reference to Exchange instance"),
+            }
+        }));
+
         exchange_client.set_handle_metrics_callback(Box::new(move |event_info| match exchange_weak
             .upgrade()
         {
@@ -267,6 +357,9 @@ impl Exchange {
 
     fn on_websocket_message(&self, msg: &str) {
         self.maybe_log_websocket_message(msg);
+        if let Some(stream_watchdog) = self.stream_watchdog.get() {
+            stream_watchdog.message_received(self.clock.now());
+        }
 
         if let Err(error) = self.exchange_client.on_websocket_message(msg) {
             log::warn!(
@@ -299,13 +392,26 @@ impl Exchange {
             exchange_blocker.unblock(self.exchange_account_id, WEBSOCKET_DISCONNECTED);
         }
 
-        let callback_outcome = self.exchange_client.on_connected();
-        if let Err(error) = callback_outcome {
-            log::warn!(
-                "Error occurred while websocket message processing: {:?}",
-                error
-            );
+        if let Some(stream_watchdog) = self.stream_watchdog.get() {
+            stream_watchdog.restart(self.clock.now());
         }
+
+        // exchange client authenticates and subscribes to its channels on connection
+        let callback_outcome = self
+            .exchange_client
+            .on_connected()
+            .and_then(|()| self.resubscribe_websocket());
+        let state = match callback_outcome {
+            Ok(()) => ConnectivityState::Connected,
+            Err(error) => {
+                log::warn!(
+                    "Error occurred while websocket authentication or subscription on {}: {error:?}",
+                    self.exchange_account_id
+                );
+                ConnectivityState::Degraded
+            }
+        };
+        self.set_connectivity_state(state);
     }
 
     fn on_disconnected(self: &Arc<Self>) {
@@ -330,6 +436,7 @@ impl Exchange {
                 BlockType::Manual,
             );
         }
+        self.set_connectivity_state(ConnectivityState::Disconnected);
 
         // auto reconnect
         if !self.auto_reconnect.load(Ordering::SeqCst) {
@@ -337,9 +444,19 @@ impl Exchange {
         }
         let id = self.exchange_account_id;
         let action = format!("Exchange account id {} reconnect", id);
+        let delay = self.reconnect_backoff.lock().next_delay();
+        log::info!("Exchange account id {id} reconnects in {delay:?}");
         let self_weak = Arc::downgrade(self);
         let future = async move {
+            sleep(delay).await;
             if let Some(self_strong) = self_weak.upgrade() {
+                // connection could be closed or reopened manually during delay
+                if !self_strong.auto_reconnect.load(Ordering::SeqCst)
+                    || self_strong.connectivity_state() != Some(ConnectivityState::Disconnected)
+                {
+                    return Ok(());
+                }
+
                 if let Err(e) = self_strong.connect_ws().await {
                     log::error!("Exchange account id {} failed to reconnect: {:?}", id, e)
                 }
@@ -349,6 +466,81 @@ impl Exchange {
         spawn_future(&action, SpawnFutureFlags::STOP_BY_TOKEN, future);
     }
 
+    fn set_connectivity_state(&self, state: ConnectivityState) {
+        let previous_state = self.connectivity_state.lock().replace(state);
+        if previous_state == Some(state) {
+            return;
+        }
+
+        let event = ConnectivityEvent {
+            exchange_account_id: self.exchange_account_id,
+            state,
+            time: self.clock.now(),
+        };
+        if self
+            .events_channel
+            .send(ExchangeEvent::Connectivity(event))
+            .is_err()
+        {
+            log::trace!(
+                "Connectivity event of {} has no receivers",
+                self.exchange_account_id
+            );
+        }
+    }
+
+    /// Websocket connectivity state. `None` if connection has never been opened
+    pub fn connectivity_state(&self) -> Option<ConnectivityState> {
+        *self.connectivity_state.lock()
+    }
+
+    /// Sends subscription message and remembers it, so the channel is resubscribed after every
+    /// reconnection. Subscription is remembered even if message can't be sent now.
+    /// Channels subscribed by exchange client in `on_connected` don't need it
+    pub fn subscribe_websocket(
+        &self,
+        role: WebSocketRole,
+        channel: &str,
+        message: String,
+    ) -> Result<()> {
+        {
+            let mut subscriptions = self.websocket_subscriptions.lock();
+            subscriptions.retain(|x| x.role != role || x.channel != channel);
+            subscriptions.push(WebSocketSubscription {
+                role,
+                channel: channel.to_owned(),
+                message: message.clone(),
+            });
+        }
+
+        self.forward_websocket_message(role, message)
+    }
+
+    pub fn unsubscribe_websocket(
+        &self,
+        role: WebSocketRole,
+        channel: &str,
+        message: String,
+    ) -> Result<()> {
+        self.websocket_subscriptions
+            .lock()
+            .retain(|x| x.role != role || x.channel != channel);
+
+        self.forward_websocket_message(role, message)
+    }
+
+    fn resubscribe_websocket(&self) -> Result<()> {
+        let subscriptions = self.websocket_subscriptions.lock().clone();
+        for subscription in subscriptions {
+            self.forward_websocket_message(subscription.role, subscription.message)
+                .with_context(|| {
+                    format!("Unable to resubscribe to channel {}", subscription.channel)
+                })?;
+        }
+
+        Ok(())
+    }
+
     fn maybe_log_websocket_message(&self, msg: &str) {
         if self.exchange_client.should_log_message(msg) {
             log::info!("Websocket message from {}: {msg}", self.exchange_account_id);
@@ -359,6 +551,55 @@ impl Exchange {
         *self.balance_manager.lock() = Some(Arc::downgrade(&balance_manager));
     }
 
+    pub fn setup_journal(&self, journal: Arc<Journal>) {
+        *self.journal.lock() = Some(journal);
+    }
+
+    pub(crate) fn journal(&self) -> Option<Arc<Journal>> {
+        self.journal.lock().clone()
+    }
+
+    pub fn setup_risk_manager(&self, risk_manager: Arc<RiskManager>) {
+        *self.risk_manager.lock() = Some(risk_manager);
+    }
+
+    pub fn risk_manager(&self) -> Option<Arc<RiskManager>> {
+        self.risk_manager.lock().clone()
+    }
+
+    /// Volatility of markets is used by risk manager for dynamic position limits
+    pub fn setup_volatility_service(&self, volatility_service: Arc<VolatilityService>) {
+        *self.volatility_service.lock() = Some(volatility_service);
+    }
+
+    /// Cross rates are used for conversion of commissions without direct market
+    pub fn setup_cross_rate_service(&self, cross_rate_service: &Arc<CrossRateService>) {
+        *self.cross_rate_service.lock() = Some(Arc::downgrade(cross_rate_service));
+    }
+
+    pub(crate) fn cross_rate_service(&self) -> Option<Arc<CrossRateService>> {
+        self.cross_rate_service
+            .lock()
+            .as_ref()
+            .and_then(Weak::upgrade)
+    }
+
+    /// Index prices are used instead of own mid prices for marking of balances
+    pub fn setup_index_price_service(&self, index_price_service: &Arc<IndexPriceService>) {
+        *self.index_price_service.lock() = Some(Arc::downgrade(index_price_service));
+    }
+
+    pub fn setup_kill_switch(&self, kill_switch: Arc<KillSwitch>) {
+        *self.kill_switch.lock() = Some(kill_switch);
+    }
+
+    pub fn is_trading_halted(&self) -> bool {
+        self.kill_switch
+            .lock()
+            .as_ref()
+            .is_some_and(|x| x.is_triggered())
+    }
+
     pub async fn reconnect_ws(self: &Arc<Self>) -> Result<()> {
         self.disconnect_ws().await;
         self.connect_ws().await
@@ -375,15 +616,37 @@ impl Exchange {
         self.on_connecting();
         // do connect
         match self.connect_internal().await {
-            Ok(reader) => {
+            Ok(receivers) => {
                 // enable auto reconnect after first success
                 self.auto_reconnect.store(true, Ordering::SeqCst);
-                spawn_future(
-                    &format!("Exchange account id {} reader", self.exchange_account_id),
-                    SpawnFutureFlags::STOP_BY_TOKEN,
-                    Self::reader_future(Arc::downgrade(self), reader),
-                );
+                self.reconnect_backoff.lock().reset();
+
+                // connections are closed together, so disconnection is handled only once
+                let is_disconnected = Arc::new(AtomicBool::new(false));
+                let generation = self.connection_generation.fetch_add(1, Ordering::SeqCst) + 1;
+                for (role, reader) in receivers.into_vec() {
+                    let runtime_kind = match role {
+                        WebSocketRole::Main => RuntimeKind::MarketData,
+                        WebSocketRole::Secondary => RuntimeKind::OrderManagement,
+                    };
+                    spawn_future_in(
+                        runtime_kind,
+                        &format!(
+                            "Exchange account id {} {role} reader",
+                            self.exchange_account_id
+                        ),
+                        SpawnFutureFlags::STOP_BY_TOKEN,
+                        Self::reader_future(
+                            Arc::downgrade(self),
+                            reader,
+                            is_disconnected.clone(),
+                            generation,
+                        ),
+                    );
+                }
                 self.on_connected();
+                // orders could be changed on exchange while websocket was disconnected
+                self.spawn_open_orders_reconciliation();
                 Ok(())
             }
             Err(e) => {
@@ -393,10 +656,12 @@ impl Exchange {
         }
     }
 
-    /// Read websocket messages and forward to upstream callbacks
+    /// Read websocket messages of one connection and forward to upstream callbacks
     async fn reader_future(
         instance: Weak<Self>,
         mut reader: tokio::sync::mpsc::UnboundedReceiver<String>,
+        is_disconnected: Arc<AtomicBool>,
+        generation: u64,
     ) -> Result<()> {
         while let Some(msg) = reader.recv().await {
             match instance.upgrade() {
@@ -409,17 +674,125 @@ impl Exchange {
         }
 
         // channel exhausted, so, disconnected
+        if is_disconnected.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
         if let Some(strong) = instance.upgrade() {
+            // connection was replaced by newer one
+            if strong.connection_generation.load(Ordering::SeqCst) != generation {
+                return Ok(());
+            }
+
             strong.on_disconnected()
         }
 
         Ok(())
     }
 
+    /// Starts periodical check of data streams silence. Websocket is reconnected if any stream is
+    /// silent for too long
+    pub fn start_stream_watchdog(self: &Arc<Self>, settings: &StreamWatchdogSettings) {
+        let stream_watchdog = StreamWatchdog::new(settings, self.clock.now());
+        let check_interval = stream_watchdog.check_interval();
+        if self.stream_watchdog.set(stream_watchdog).is_err() {
+            log::warn!(
+                "Stream watchdog of {} is already started",
+                self.exchange_account_id
+            );
+            return;
+        }
+
+        let clock = self.clock.clone();
+        let self_weak = Arc::downgrade(self);
+        let action = async move {
+            loop {
+                clock.sleep(check_interval).await;
+                match self_weak.upgrade() {
+                    Some(exchange) => exchange.check_stale_streams().await,
+                    None => return Ok(()),
+                }
+            }
+        };
+        spawn_future(
+            &format!(
+                "Exchange account id {} stream watchdog",
+                self.exchange_account_id
+            ),
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action,
+        );
+    }
+
+    pub(crate) fn order_book_updated(&self, currency_pair: CurrencyPair) {
+        let now = self.clock.now();
+        let _ = self.order_book_update_times.insert(currency_pair, now);
+        if let Some(stream_watchdog) = self.stream_watchdog.get() {
+            stream_watchdog.stream_updated(currency_pair, now);
+        }
+    }
+
+    /// Time of the last order book update of currency pair
+    pub fn order_book_update_time(&self, currency_pair: CurrencyPair) -> Option<DateTime> {
+        self.order_book_update_times
+            .get(&currency_pair)
+            .map(|x| *x.value())
+    }
+
+    async fn check_stale_streams(self: &Arc<Self>) {
+        // silence is expected while connection is restored
+        if !matches!(
+            self.connectivity_state(),
+            Some(ConnectivityState::Connected | ConnectivityState::Degraded)
+        ) {
+            return;
+        }
+
+        let Some(stream_watchdog) = self.stream_watchdog.get() else {
+            return;
+        };
+        let now = self.clock.now();
+        let stale_streams = stream_watchdog.find_stale_streams(now);
+        if stale_streams.is_empty() {
+            return;
+        }
+
+        for stale_stream in stale_streams {
+            log::warn!(
+                "Stream {:?} of {} is silent since {}, reconnecting",
+                stale_stream.currency_pair,
+                self.exchange_account_id,
+                stale_stream.last_update_time
+            );
+
+            let event = StreamStaleEvent {
+                exchange_account_id: self.exchange_account_id,
+                currency_pair: stale_stream.currency_pair,
+                last_update_time: stale_stream.last_update_time,
+                time: now,
+            };
+            if self
+                .events_channel
+                .send(ExchangeEvent::StreamStale(event))
+                .is_err()
+            {
+                log::trace!(
+                    "StreamStale event of {} has no receivers",
+                    self.exchange_account_id
+                );
+            }
+        }
+
+        if let Err(error) = self.reconnect_ws().await {
+            log::error!(
+                "Failed to reconnect {} after stale stream detection: {error:?}",
+                self.exchange_account_id
+            );
+        }
+    }
+
     /// Actual connect function, all internal work here.
-    async fn connect_internal(
-        self: &Arc<Self>,
-    ) -> Result<tokio::sync::mpsc::UnboundedReceiver<String>, ConnectivityError> {
+    async fn connect_internal(self: &Arc<Self>) -> Result<WsReceivers, ConnectivityError> {
         log::info!("Websocket: Connecting on {}", self.exchange_account_id);
 
         if !self
@@ -459,9 +832,9 @@ impl Exchange {
             );
             None
         };
-        let (tx, rx) = websocket_open(self.exchange_account_id, main, secondary).await?;
+        let (tx, receivers) = websocket_open(self.exchange_account_id, main, secondary).await?;
         self.ws_sender.lock().replace(tx);
-        Ok(rx)
+        Ok(receivers)
     }
 
     fn forward_websocket_message(&self, role: WebSocketRole, msg: String) -> Result<()> {
@@ -490,7 +863,17 @@ impl Exchange {
         role: WebSocketRole,
     ) -> Result<WebSocketParams> {
         let ws_url = self.exchange_client.create_ws_url(role).await?;
-        Ok(WebSocketParams::new(ws_url))
+        let params = WebSocketParams::new(ws_url);
+
+        let settings = self.exchange_client.get_settings();
+        let host = match role {
+            WebSocketRole::Main => &settings.market_data_websocket_host,
+            WebSocketRole::Secondary => &settings.order_entry_websocket_host,
+        };
+        match host {
+            Some(host) => params.with_host(host),
+            None => Ok(params),
+        }
     }
 
     pub(crate) fn add_event_on_order_change(
@@ -506,6 +889,23 @@ impl Exchange {
             let _ = self.orders.not_finished.remove(&order.client_order_id());
         }
 
+        if let Some(risk_manager) = self.risk_manager.lock().as_ref() {
+            risk_manager.register_order_event(self.exchange_account_id, &event_type);
+        }
+
+        if let Some(journal) = self.journal() {
+            let record = JournalRecord::OrderChanged {
+                change_type: OrderChangeType::from(&event_type),
+                order: order.deep_clone(),
+            };
+            if let Err(err) = journal.append(record) {
+                log::error!(
+                    "Failed to write change of order {} to journal: {err:?}",
+                    order.client_order_id()
+                );
+            }
+        }
+
         let event = ExchangeEvent::OrderEvent(OrderEvent::new(order.clone(), event_type));
         self.events_channel
             .send(event)
@@ -775,7 +1175,7 @@ impl Exchange {
         }
 
         let event = LiquidationPriceEvent::new(
-            time_manager::now(),
+            self.clock.now(),
             self.exchange_account_id,
             currency_pair,
             liquidation_price,
@@ -795,6 +1195,104 @@ impl Exchange {
         self.timeout
     }
 
+    /// Clock of timeout manager used for order timestamps and timeouts of this exchange
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Fees of exchange account by order role
+    pub fn commission(&self) -> Commission {
+        self.commission.read().clone()
+    }
+
+    /// Uses fees of tier reached by trading volume until exchange reports actual fees. Fees are
+    /// requested from exchange now and then every refresh interval if it is specified
+    pub async fn setup_fees(self: &Arc<Self>, settings: &FeeSettings) {
+        *self.commission.write() = settings.schedule.commission(settings.trading_volume);
+        *self.fee_schedule.lock() = Some(settings.schedule.clone());
+        self.refresh_commission().await;
+
+        let refresh_interval = match settings.refresh_interval_secs {
+            Some(refresh_interval_secs) => Duration::from_secs(refresh_interval_secs),
+            None => return,
+        };
+
+        let clock = self.clock.clone();
+        let self_weak = Arc::downgrade(self);
+        let action = async move {
+            loop {
+                clock.sleep(refresh_interval).await;
+                match self_weak.upgrade() {
+                    Some(exchange) => exchange.refresh_commission().await,
+                    None => return Ok(()),
+                }
+            }
+        };
+        spawn_future(
+            &format!(
+                "Exchange account id {} fees refreshing",
+                self.exchange_account_id
+            ),
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action,
+        );
+    }
+
+    /// Replaces fees by fees reported by exchange with exchange token discount of fee schedule.
+    /// Current fees are kept if exchange client can't request them
+    pub async fn refresh_commission(&self) {
+        let commission = match self.exchange_client.get_commission().await {
+            None => return,
+            Some(Ok(commission)) => commission,
+            Some(Err(err)) => {
+                log::warn!(
+                    "Unable to request fees of {}: {err}",
+                    self.exchange_account_id
+                );
+                return;
+            }
+        };
+
+        let commission = match &*self.fee_schedule.lock() {
+            Some(fee_schedule) => fee_schedule.apply_discount(commission),
+            None => commission,
+        };
+        log::info!(
+            "Fees of {} are refreshed: maker {}%, taker {}%",
+            self.exchange_account_id,
+            commission.maker.fee,
+            commission.taker.fee
+        );
+        *self.commission.write() = commission;
+    }
+
+    /// Mid price of order book top. `None` if any side of order book is empty or unknown
+    pub fn mid_price(&self, currency_pair: CurrencyPair) -> Option<Price> {
+        let top = self.order_book_top.get(&currency_pair)?;
+        let ask = top.ask.as_ref()?;
+        let bid = top.bid.as_ref()?;
+        Some((ask.price + bid.price) / dec!(2))
+    }
+
+    /// Fair price of currency pair: index price if index is configured for it, otherwise mid price
+    /// of order book top
+    pub fn mark_price(&self, currency_pair: CurrencyPair) -> Option<Price> {
+        let index_price_service = self
+            .index_price_service
+            .lock()
+            .as_ref()
+            .and_then(Weak::upgrade);
+        match index_price_service {
+            Some(service) if service.has_index(currency_pair) => service.index_price(currency_pair),
+            _ => self.mid_price(currency_pair),
+        }
+    }
+
+    /// Watch-only accounts only supply market data and can't trade
+    pub fn is_watch_only(&self) -> bool {
+        self.exchange_client.get_settings().is_watch_only
+    }
+
     pub fn get_symbol(&self, currency_pair: CurrencyPair) -> Result<Arc<Symbol>> {
         self.symbols
             .get(&currency_pair)
@@ -2,6 +2,7 @@ use std::sync::{Arc, Weak};
 
 use crate::database::events::recorder::EventRecorder;
 use crate::exchanges::exchange_blocker::ExchangeBlocker;
+use crate::exchanges::fault_injection::with_fault_injection;
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
 use crate::lifecycle::launcher::EngineBuildConfig;
 use crate::settings::ExchangeSettings;
@@ -16,6 +17,7 @@ use crate::{
 use mmb_domain::events::ExchangeEvent;
 use mmb_domain::exchanges::commission::Commission;
 use mmb_domain::order::pool::OrdersPool;
+use mmb_utils::infrastructure::WithExpect;
 use tokio::sync::broadcast;
 
 pub fn create_timeout_manager(
@@ -40,7 +42,22 @@ pub fn create_timeout_manager(
         })
         .collect();
 
-    TimeoutManager::new(request_timeout_managers)
+    let order_rate_limits = core_settings
+        .exchanges
+        .iter()
+        .filter_map(|exchange_settings| {
+            exchange_settings
+                .order_rate_limits
+                .clone()
+                .map(|limits| (exchange_settings.exchange_account_id, limits))
+        })
+        .collect();
+
+    TimeoutManager::with_order_rate_limits(
+        request_timeout_managers,
+        order_rate_limits,
+        build_settings.clock.clone(),
+    )
 }
 
 pub async fn create_exchange(
@@ -64,10 +81,12 @@ pub async fn create_exchange(
         timeout_manager.clone(),
         orders.clone(),
     );
+    let client = with_fault_injection(exchange_client.client, user_settings)
+        .with_expect(|| format!("Invalid fault injection settings for {exchange_account_id}"));
 
     let exchange = Exchange::new(
         exchange_account_id,
-        exchange_client.client,
+        client,
         orders,
         exchange_client.features,
         exchange_client_builder.get_timeout_arguments(),
@@ -79,8 +98,26 @@ pub async fn create_exchange(
         event_recorder,
     );
 
+    if let Some(stream_watchdog) = &user_settings.stream_watchdog {
+        exchange.start_stream_watchdog(stream_watchdog);
+    }
+    if let Some(stuck_orders) = &user_settings.stuck_orders {
+        exchange.start_stuck_orders_watchdog(stuck_orders);
+    }
+    if let Some(external_orders) = &user_settings.external_orders {
+        exchange.setup_external_orders(external_orders);
+    }
+    if let Some(system_status) = &user_settings.system_status {
+        exchange.start_system_status_polling(system_status);
+    }
+    exchange.start_dead_letters_rematching();
+
     exchange.build_symbols(&user_settings.currency_pairs).await;
     exchange.exchange_client.initialized(exchange.clone()).await;
 
+    if let Some(fees) = &user_settings.fees {
+        exchange.setup_fees(fees).await;
+    }
+
     exchange
 }
@@ -1,5 +1,7 @@
+use anyhow::{bail, Result};
 use dashmap::DashMap;
 use itertools::Itertools;
+use mmb_domain::events::{ExchangeEvent, SymbolStatusEvent};
 use mmb_domain::market::CurrencyCode;
 use mmb_utils::infrastructure::WithExpect;
 use rust_decimal_macros::dec;
@@ -7,7 +9,7 @@ use std::collections::HashSet;
 use std::sync::Arc;
 
 use crate::settings::CurrencyPairSetting;
-use mmb_domain::exchanges::symbol::Symbol;
+use mmb_domain::exchanges::symbol::{Symbol, SymbolStatus};
 use mmb_domain::market::{CurrencyId, ExchangeAccountId};
 
 use super::exchange::Exchange;
@@ -38,6 +40,57 @@ impl Exchange {
         ));
     }
 
+    /// Replaces trading rules of traded symbols by actual ones. Symbols which disappeared from
+    /// exchange are kept with `Delisted` status, so orders can't be created for them
+    pub async fn refresh_symbols(&self) -> Result<()> {
+        let exchange_symbols = self.exchange_client.build_all_symbols().await?;
+
+        let traded_symbols = self.symbols.iter().map(|x| x.value().clone()).collect_vec();
+        for symbol in traded_symbols {
+            let currency_pair = symbol.currency_pair();
+            let refreshed_symbol = exchange_symbols
+                .iter()
+                .find(|x| x.currency_pair() == currency_pair)
+                .cloned()
+                .unwrap_or_else(|| {
+                    Arc::new(Symbol {
+                        status: SymbolStatus::Delisted,
+                        ..(*symbol).clone()
+                    })
+                });
+
+            if refreshed_symbol.status != symbol.status {
+                log::warn!(
+                    "Symbol {currency_pair} on {} changed status from {:?} to {:?}",
+                    self.exchange_account_id,
+                    symbol.status,
+                    refreshed_symbol.status
+                );
+
+                let event = SymbolStatusEvent {
+                    exchange_account_id: self.exchange_account_id,
+                    currency_pair,
+                    status: refreshed_symbol.status,
+                    time: self.clock.now(),
+                };
+                if self
+                    .events_channel
+                    .send(ExchangeEvent::SymbolStatus(event))
+                    .is_err()
+                {
+                    log::trace!(
+                        "SymbolStatus event of {} has no receivers",
+                        self.exchange_account_id
+                    );
+                }
+            }
+
+            self.symbols.insert(currency_pair, refreshed_symbol);
+        }
+
+        Ok(())
+    }
+
     async fn request_symbols_with_retries(&self) -> Vec<Arc<Symbol>> {
         const MAX_RETRIES: u8 = 5;
         for retry in 0..=MAX_RETRIES {
@@ -120,7 +173,16 @@ fn get_matched_currency_pair(
     exchange_symbols: &[Arc<Symbol>],
     exchange_account_id: ExchangeAccountId,
 ) -> Option<Arc<Symbol>> {
-    // currency pair symbol and currency pairs from settings should match 1 to 1
+    match_symbol(currency_pair_setting, exchange_symbols)
+        .map_err(|err| log::error!("{err} on exchange {exchange_account_id}"))
+        .ok()
+}
+
+/// Finds exchange symbol of currency pair from settings. They should match 1 to 1
+pub(crate) fn match_symbol(
+    currency_pair_setting: &CurrencyPairSetting,
+    exchange_symbols: &[Arc<Symbol>],
+) -> Result<Arc<Symbol>> {
     let filtered_symbol = exchange_symbols
         .iter()
         .filter(|symbol| match currency_pair_setting {
@@ -136,10 +198,8 @@ fn get_matched_currency_pair(
         .collect_vec();
 
     match filtered_symbol.as_slice() {
-        [] => log::error!("Unsupported symbol {currency_pair_setting:?} on exchange {exchange_account_id}"),
-        [symbol] => return Some(symbol.clone()),
-        _ => log::error!("Found more then 1 symbol for currency pair {currency_pair_setting:?} on exchange {exchange_account_id}. Found symbols: {filtered_symbol:?}"),
-    };
-
-    None
+        [] => bail!("Unsupported symbol {currency_pair_setting:?}"),
+        [symbol] => Ok(symbol.clone()),
+        _ => bail!("Found more then 1 symbol for currency pair {currency_pair_setting:?}. Found symbols: {filtered_symbol:?}"),
+    }
 }
@@ -1,7 +1,6 @@
 use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::general::handlers::should_ignore_event;
 use crate::exchanges::traits::ExchangeError;
-use chrono::Utc;
 use function_name::named;
 use mmb_domain::events::EventSourceType;
 use mmb_domain::market::ExchangeErrorType;
@@ -78,7 +77,7 @@ impl Exchange {
                     // TODO Some metrics
                 }
 
-                order.fn_mut(|x| x.set_status(OrderStatus::FailedToCancel, Utc::now()));
+                order.fn_mut(|x| x.set_status(OrderStatus::FailedToCancel, self.clock.now()));
 
                 self.add_event_on_order_change(order, OrderEventType::CancelOrderFailed)
                     .with_expect(|| format!("Failed to add event CancelOrderFailed on order change {client_order_id:?}"));
@@ -104,6 +103,7 @@ mod test {
     use super::*;
     use crate::exchanges::general::test_helper;
     use crate::exchanges::general::test_helper::get_test_exchange;
+    use chrono::Utc;
     use mmb_domain::events::ExchangeEvent;
     use mmb_domain::market::CurrencyPair;
     use mmb_domain::market::ExchangeErrorType;
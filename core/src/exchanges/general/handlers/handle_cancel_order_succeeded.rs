@@ -1,6 +1,6 @@
 use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::general::handlers::should_ignore_event;
-use chrono::Utc;
+use crate::orders::dead_letter_queue::DeadLetter;
 use function_name::named;
 use mmb_domain::events::EventSourceType;
 use mmb_domain::order::event::OrderEventType;
@@ -50,9 +50,11 @@ impl Exchange {
 
         match self.orders.cache_by_exchange_id.get(exchange_order_id) {
             None => {
-                self.buffered_canceled_orders_manager
-                    .lock()
-                    .add_order(self.exchange_account_id, exchange_order_id.clone());
+                self.add_dead_letter(DeadLetter::Cancel {
+                    exchange_order_id: exchange_order_id.clone(),
+                    filled_amount,
+                    source_type,
+                });
 
                 match client_order_id {
                     Some(client_order_id) => self.raise_order_created(client_order_id, exchange_order_id, source_type),
@@ -100,7 +102,7 @@ impl Exchange {
         }
 
         let is_canceling_from_wait_cancel_order = order.fn_mut(|x| {
-            x.set_status(OrderStatus::Canceled, Utc::now());
+            x.set_status(OrderStatus::Canceled, self.clock.now());
             x.internal_props.filled_amount_after_cancellation = filled_amount;
             x.internal_props.cancellation_event_source_type = Some(source_type);
             x.internal_props.is_canceling_from_wait_cancel_order
@@ -138,6 +140,7 @@ impl Exchange {
 mod test {
     use super::*;
     use crate::exchanges::general::test_helper;
+    use chrono::Utc;
     use mmb_domain::events::ExchangeEvent;
     use mmb_domain::market::CurrencyPair;
     use mmb_domain::order::snapshot::{OrderRole, OrderSide};
@@ -0,0 +1,193 @@
+use std::sync::Arc;
+
+use mmb_domain::events::UnmatchedExchangeEvent;
+use mmb_domain::order::snapshot::ClientOrderId;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::orders::dead_letter_queue::{DeadLetter, DEAD_LETTERS_REMATCH_INTERVAL};
+
+impl Exchange {
+    /// Keeps order event until its order becomes known
+    pub(crate) fn add_dead_letter(&self, letter: DeadLetter) {
+        let evicted = self.dead_letter_queue.lock().push(letter, self.clock.now());
+
+        if let Some(evicted) = evicted {
+            log::error!(
+                "Dead letter queue of {} is full, unmatched order event is dropped: {evicted:?}",
+                self.exchange_account_id
+            );
+        }
+    }
+
+    pub(crate) fn apply_dead_letters(
+        &self,
+        dead_letters: Vec<DeadLetter>,
+        client_order_id: &ClientOrderId,
+    ) {
+        for dead_letter in dead_letters {
+            match dead_letter {
+                DeadLetter::Fill(mut fill_event) => {
+                    fill_event.client_order_id = Some(client_order_id.clone());
+                    self.handle_order_filled(&mut fill_event);
+                }
+                DeadLetter::Cancel {
+                    exchange_order_id,
+                    filled_amount,
+                    source_type,
+                } => self.handle_cancel_order_succeeded(
+                    Some(client_order_id),
+                    &exchange_order_id,
+                    filled_amount,
+                    source_type,
+                ),
+            }
+        }
+    }
+
+    /// Starts periodical matching of kept order events with orders which became known not via
+    /// order creation, e.g. loaded by open orders reconciliation or restored from journal
+    pub fn start_dead_letters_rematching(self: &Arc<Self>) {
+        let clock = self.clock.clone();
+        let self_weak = Arc::downgrade(self);
+        let action = async move {
+            loop {
+                clock.sleep(DEAD_LETTERS_REMATCH_INTERVAL).await;
+                match self_weak.upgrade() {
+                    Some(exchange) => exchange.rematch_dead_letters(),
+                    None => return Ok(()),
+                }
+            }
+        };
+        spawn_future(
+            &format!(
+                "Exchange account id {} dead letters rematching",
+                self.exchange_account_id
+            ),
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action,
+        );
+    }
+
+    pub(crate) fn rematch_dead_letters(&self) {
+        let matched = {
+            let mut dead_letter_queue = self.dead_letter_queue.lock();
+            if dead_letter_queue.is_empty() {
+                return;
+            }
+
+            dead_letter_queue.take_matched(|x| self.orders.cache_by_exchange_id.contains_key(x))
+        };
+
+        for dead_letter in matched {
+            let client_order_id = self
+                .orders
+                .cache_by_exchange_id
+                .get(dead_letter.exchange_order_id())
+                .map(|order| order.client_order_id());
+
+            match client_order_id {
+                Some(client_order_id) => {
+                    log::info!(
+                        "Unmatched event of order {client_order_id} on {} is matched: {dead_letter:?}",
+                        self.exchange_account_id
+                    );
+                    self.apply_dead_letters(vec![dead_letter], &client_order_id);
+                }
+                // order was removed from cache in between
+                None => self.add_dead_letter(dead_letter),
+            }
+        }
+    }
+
+    /// Order events which aren't matched to any known order yet
+    pub fn unmatched_events(&self) -> Vec<UnmatchedExchangeEvent> {
+        self.dead_letter_queue
+            .lock()
+            .unmatched_events(self.exchange_account_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::handlers::handle_order_filled::{FillAmount, FillEvent};
+    use crate::exchanges::general::test_helper::{self, get_test_exchange};
+    use crate::infrastructure::init_lifetime_manager;
+    use chrono::Utc;
+    use mmb_domain::events::{EventSourceType, TradeId};
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::fill::OrderFillType;
+    use mmb_domain::order::pool::OrdersPool;
+    use mmb_domain::order::snapshot::{
+        ExchangeOrderId, OrderFills, OrderHeader, OrderRole, OrderSide, OrderSimpleProps,
+        OrderSnapshot, OrderStatusHistory, SystemInternalOrderProps, UserOrder,
+    };
+    use rust_decimal_macros::dec;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn fill_is_applied_after_order_became_known() {
+        let _ = init_lifetime_manager();
+        let (exchange, _event_receiver) = get_test_exchange(false);
+        let exchange_order_id: ExchangeOrderId = "adopted_order_id".into();
+
+        let mut fill_event = FillEvent {
+            source_type: EventSourceType::WebSocket,
+            trade_id: Some(TradeId::Number(1)),
+            client_order_id: None,
+            exchange_order_id: exchange_order_id.clone(),
+            fill_price: dec!(0.2),
+            fill_amount: FillAmount::Incremental {
+                fill_amount: dec!(5),
+                total_filled_amount: None,
+            },
+            order_role: None,
+            commission_currency_code: None,
+            commission_rate: None,
+            commission_amount: Some(dec!(0.01)),
+            fill_type: OrderFillType::UserTrade,
+            special_order_data: None,
+            fill_date: None,
+        };
+        exchange.handle_order_filled(&mut fill_event);
+
+        let unmatched_events = exchange.unmatched_events();
+        assert_eq!(unmatched_events.len(), 1);
+        assert_eq!(unmatched_events[0].exchange_order_id, exchange_order_id);
+
+        let header = OrderHeader::with_user_order(
+            ClientOrderId::unique_id(),
+            exchange.exchange_account_id,
+            CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+            OrderSide::Buy,
+            dec!(12),
+            UserOrder::limit(dec!(0.2)),
+            None,
+            None,
+            "FromTest".to_owned(),
+        );
+        let props = OrderSimpleProps::new(
+            Utc::now(),
+            Some(OrderRole::Maker),
+            Some(exchange_order_id),
+            Default::default(),
+            None,
+        );
+        let order = OrderSnapshot::new(
+            header,
+            props,
+            OrderFills::default(),
+            OrderStatusHistory::default(),
+            SystemInternalOrderProps::default(),
+            None,
+        );
+        let order_ref = OrdersPool::new().add_snapshot_initial(&order);
+        test_helper::try_add_snapshot_by_exchange_id(&exchange, &order_ref);
+
+        exchange.rematch_dead_letters();
+
+        assert_eq!(order_ref.filled_amount(), dec!(5));
+        assert!(exchange.unmatched_events().is_empty());
+    }
+}
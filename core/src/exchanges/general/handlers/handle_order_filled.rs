@@ -1,6 +1,7 @@
 use crate::exchanges::general::handlers::should_ignore_event;
+use crate::orders::dead_letter_queue::DeadLetter;
 use crate::{exchanges::general::exchange::Exchange, math::ConvertPercentToRate};
-use chrono::Utc;
+use chrono::SecondsFormat;
 use function_name::named;
 use mmb_domain::events::{
     AllowedEventSourceType, EventSourceType, MetricsEventInfoBase, MetricsEventType, TradeId,
@@ -12,13 +13,14 @@ use mmb_domain::order::event::OrderEventType;
 use mmb_domain::order::fill::{OrderFill, OrderFillType};
 use mmb_domain::order::pool::OrderRef;
 use mmb_domain::order::snapshot::{Amount, OrderOptions, Price};
-use mmb_domain::order::snapshot::{ClientOrderFillId, OrderRole};
+use mmb_domain::order::snapshot::{ClientOrderFillId, OrderExecutionType, OrderRole};
 use mmb_domain::order::snapshot::{
     ClientOrderId, ExchangeOrderId, OrderSide, OrderSnapshot, OrderStatus,
 };
 use mmb_utils::DateTime;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -84,6 +86,55 @@ pub struct FillEvent {
     pub fill_date: Option<DateTime>,
 }
 
+impl FillEvent {
+    /// Identity of fill without trade id, so its replay after reconnection can be detected.
+    /// Fills without time can't be distinguished from other fills with the same price and amount,
+    /// so they have no fingerprint
+    pub fn fingerprint(&self) -> Option<u64> {
+        if self.trade_id.is_some() {
+            return None;
+        }
+
+        let fill_date = self.fill_date?;
+        let FillAmount::Incremental {
+            fill_amount,
+            total_filled_amount,
+        } = self.fill_amount
+        else {
+            return None;
+        };
+
+        // Total filled amount is joined to fill amount by '/', so fingerprints of fills without
+        // it aren't changed. Decimals don't contain '/', so it can't make encoding ambiguous
+        let amount = match total_filled_amount {
+            Some(total_filled_amount) => format!(
+                "{}/{}",
+                fill_amount.normalize(),
+                total_filled_amount.normalize()
+            ),
+            None => fill_amount.normalize().to_string(),
+        };
+
+        // fingerprints are persisted with orders, so hash should be stable across Rust releases
+        // unlike std hashers. Exchange order id is the last one, so any content of it can't make
+        // encoding ambiguous
+        let canonical = format!(
+            "{}|{}|{}|{}|{}",
+            self.fill_price.normalize(),
+            amount,
+            fill_date.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            self.commission_amount
+                .map(|x| x.normalize().to_string())
+                .unwrap_or_default(),
+            self.exchange_order_id.as_str(),
+        );
+        let digest = Sha256::digest(canonical.as_bytes());
+        let mut fingerprint = [0; 8];
+        fingerprint.copy_from_slice(&digest[..8]);
+        Some(u64::from_be_bytes(fingerprint))
+    }
+}
+
 impl Exchange {
     #[named]
     pub fn handle_order_filled(&self, fill_event: &mut FillEvent) {
@@ -118,11 +169,15 @@ impl Exchange {
             .get(&fill_event.exchange_order_id)
         {
             None => {
+                if let Some(order_ref) = self.adopt_external_order_by_fill(fill_event) {
+                    fill_event.client_order_id = Some(order_ref.client_order_id());
+                    return self.create_and_add_order_fill(fill_event, &order_ref);
+                }
+
                 log::info!("Received a fill for not existing order {args_to_log:?}",);
 
-                self.buffered_fills_manager
-                    .lock()
-                    .add_fill(self.exchange_account_id, fill_event);
+                // likely fill notification is received before order creation notification
+                self.add_dead_letter(DeadLetter::Fill(fill_event.clone()));
 
                 if let Some(client_order_id) = &fill_event.client_order_id {
                     self.raise_order_created(
@@ -161,6 +216,24 @@ impl Exchange {
         false
     }
 
+    fn was_fill_fingerprint_received(fingerprint: Option<u64>, order_ref: &OrderRef) -> bool {
+        let fingerprint = match fingerprint {
+            None => return false,
+            Some(fingerprint) => fingerprint,
+        };
+
+        let was_received = order_ref.fn_ref(move |x| {
+            x.internal_props
+                .received_fill_fingerprints
+                .contains(&fingerprint)
+        });
+        if was_received {
+            log::info!("Fill without trade id was received already for order {order_ref:?}");
+        }
+
+        was_received
+    }
+
     fn diff_fill_after_non_diff(
         fill_event: &FillEvent,
         order_fills: &[OrderFill],
@@ -346,6 +419,13 @@ impl Exchange {
     fn get_order_role(fill_event: &FillEvent, order_ref: &OrderRef) -> OrderRole {
         match fill_event.order_role {
             Some(order_role) => order_role,
+            // role isn't reported by fills of rest fallback, but maker only order can't be a taker
+            None if order_ref.role().is_none()
+                && order_ref.header().options.execution_type()
+                    == Some(OrderExecutionType::MakerOnly) =>
+            {
+                OrderRole::Maker
+            }
             None => {
                 if fill_event.commission_amount.is_none()
                     && fill_event.commission_rate.is_none()
@@ -388,7 +468,7 @@ impl Exchange {
     }
 
     fn set_commission_rate(&self, fill_event: &mut FillEvent, order_role: OrderRole) -> Decimal {
-        let commission = self.commission.get_commission(order_role).fee;
+        let commission = self.commission().get_commission(order_role).fee;
         let expected_commission_rate = commission.percent_to_rate();
 
         if fill_event.commission_amount.is_none() && fill_event.commission_rate.is_none() {
@@ -437,10 +517,27 @@ impl Exchange {
                             *converted_commission_amount = commission_amount / price_quote_bnb;
                             *converted_commission_currency_code = symbol.quote_currency_code();
                         }
-                        None => log::error!(
-                            "Top bids and asks for {} and currency pair {currency_pair:?} do not exist",
-                            self.exchange_account_id,
-                        ),
+                        None => {
+                            // commission currency isn't traded against quote currency directly
+                            let converted = self.cross_rate_service().and_then(|x| {
+                                x.convert(
+                                    commission_amount,
+                                    commission_currency_code,
+                                    symbol.quote_currency_code(),
+                                )
+                            });
+                            match converted {
+                                Some(amount) => {
+                                    *converted_commission_amount = amount;
+                                    *converted_commission_currency_code =
+                                        symbol.quote_currency_code();
+                                }
+                                None => log::error!(
+                                    "Top bids and asks for {} and currency pair {currency_pair:?} do not exist",
+                                    self.exchange_account_id,
+                                ),
+                            }
+                        }
                     }
                 }
             }
@@ -460,7 +557,7 @@ impl Exchange {
     }
 
     fn send_order_filled_event(&self, order_ref: &OrderRef) {
-        let cloned_order = Arc::new(order_ref.deep_clone());
+        let cloned_order = self.clone_order_pooled(order_ref);
         self.add_event_on_order_change(order_ref, OrderEventType::OrderFilled { cloned_order })
             .expect("Unable to send event, probably receiver is dropped already");
     }
@@ -468,10 +565,10 @@ impl Exchange {
     fn react_if_order_completed(&self, order_filled_amount: Amount, order_ref: &OrderRef) {
         if order_filled_amount == order_ref.amount() {
             order_ref.fn_mut(|order| {
-                order.set_status(OrderStatus::Completed, Utc::now());
+                order.set_status(OrderStatus::Completed, self.clock.now());
             });
 
-            let cloned_order = Arc::new(order_ref.deep_clone());
+            let cloned_order = self.clone_order_pooled(order_ref);
             self.add_event_on_order_change(
                 order_ref,
                 OrderEventType::OrderCompleted { cloned_order },
@@ -480,6 +577,13 @@ impl Exchange {
         }
     }
 
+    fn clone_order_pooled(&self, order_ref: &OrderRef) -> Arc<OrderSnapshot> {
+        self.order_snapshots_pool.take(
+            || order_ref.deep_clone(),
+            |snapshot| order_ref.deep_clone_into(snapshot),
+        )
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn add_fill(
         &self,
@@ -507,7 +611,7 @@ impl Exchange {
         let expected_converted_commission_amount =
             last_fill_amount_in_converted_commission_currency_code * expected_commission_rate;
 
-        let referral_reward = self.commission.get_commission(order_role).referral_reward;
+        let referral_reward = self.commission().get_commission(order_role).referral_reward;
         let referral_reward_amount = commission_amount * referral_reward.percent_to_rate();
 
         let rounded_fill_price = symbol.price_round(last_fill_price, Round::ToNearest);
@@ -519,7 +623,7 @@ impl Exchange {
         let order_fill = OrderFill::new(
             Uuid::new_v4(),
             Some(ClientOrderFillId::unique_id()),
-            Utc::now(),
+            self.clock.now(),
             fill_type,
             trade_id.clone(),
             rounded_fill_price,
@@ -546,12 +650,18 @@ impl Exchange {
     }
 
     fn create_and_add_order_fill(&self, fill_event: &mut FillEvent, order_ref: &OrderRef) {
-        let (order_fills, order_filled_amount) = order_ref.get_fills();
+        let mut order_fills = self.order_fills_pool.take();
+        let order_filled_amount = order_ref.get_fills_into(&mut order_fills);
 
         if Self::was_trade_already_received(&fill_event.trade_id, &order_fills, order_ref) {
             return;
         }
 
+        let fingerprint = fill_event.fingerprint();
+        if Self::was_fill_fingerprint_received(fingerprint, order_ref) {
+            return;
+        }
+
         if Self::diff_fill_after_non_diff(fill_event, &order_fills, order_ref) {
             return;
         }
@@ -630,6 +740,14 @@ impl Exchange {
             converted_commission_amount,
         );
 
+        if let Some(fingerprint) = fingerprint {
+            order_ref.fn_mut(move |x| {
+                x.internal_props
+                    .received_fill_fingerprints
+                    .push(fingerprint)
+            });
+        }
+
         // This order fields updated, so let's use actual values
         let order_filled_amount = order_ref.filled_amount();
 
@@ -2186,6 +2304,45 @@ mod test {
         Exchange::get_order_role(&fill_event, &order_ref);
     }
 
+    #[test]
+    fn maker_only_order_is_filled_as_maker() {
+        let order = OrderSnapshot::with_params(
+            ClientOrderId::unique_id(),
+            OrderOptions::maker_only(dec!(0.2)),
+            None,
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+            dec!(12),
+            OrderSide::Buy,
+            None,
+            "FromTest",
+        );
+        let order_ref = OrdersPool::new().add_snapshot_initial(&order);
+
+        let fill_event = FillEvent {
+            source_type: EventSourceType::RestFallback,
+            trade_id: None,
+            client_order_id: Some(order_ref.client_order_id()),
+            exchange_order_id: ExchangeOrderId::new("test".into()),
+            fill_price: dec!(0.2),
+            fill_amount: FillAmount::Total {
+                total_filled_amount: dec!(5),
+            },
+            order_role: None,
+            commission_currency_code: None,
+            commission_rate: None,
+            commission_amount: None,
+            fill_type: OrderFillType::UserTrade,
+            special_order_data: None,
+            fill_date: None,
+        };
+
+        assert_eq!(
+            Exchange::get_order_role(&fill_event, &order_ref),
+            OrderRole::Maker
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn use_commission_currency_code_from_fill_event() {
         let (exchange, _event_receiver) = get_test_exchange(false);
@@ -2629,6 +2786,61 @@ mod test {
         assert_eq!(first_fill.commission_amount(), result_value);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn replayed_fill_without_trade_id_is_ignored() {
+        let _ = crate::infrastructure::init_lifetime_manager();
+        let (exchange, _event_receiver) = get_test_exchange(false);
+
+        let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
+        let fill_amount = dec!(2);
+        let fill_date = Utc::now();
+        let create_fill_event = |fill_date| FillEvent {
+            source_type: EventSourceType::WebSocket,
+            trade_id: None,
+            client_order_id: None,
+            exchange_order_id: ExchangeOrderId::new("".into()),
+            fill_price: dec!(0.8),
+            fill_amount: FillAmount::Incremental {
+                fill_amount,
+                total_filled_amount: None,
+            },
+            order_role: None,
+            commission_currency_code: None,
+            commission_rate: None,
+            commission_amount: None,
+            fill_type: OrderFillType::Liquidation,
+            special_order_data: Some(SpecialOrderData {
+                currency_pair,
+                order_side: OrderSide::Buy,
+                order_amount: dec!(0),
+            }),
+            fill_date: Some(fill_date),
+        };
+
+        let order = OrderSnapshot::with_params(
+            ClientOrderId::unique_id(),
+            OrderOptions::liquidation(dec!(0.2)),
+            Some(OrderRole::Maker),
+            exchange.exchange_account_id,
+            currency_pair,
+            dec!(12),
+            OrderSide::Buy,
+            None,
+            "FromTest",
+        );
+        let order_pool = OrdersPool::new();
+        let order_ref = order_pool.add_snapshot_initial(&order);
+
+        exchange.create_and_add_order_fill(&mut create_fill_event(fill_date), &order_ref);
+        exchange.create_and_add_order_fill(&mut create_fill_event(fill_date), &order_ref);
+        assert_eq!(order_ref.filled_amount(), fill_amount);
+
+        // fill with the same amount, but another time is a new fill
+        let next_fill_date = fill_date + chrono::Duration::seconds(1);
+        exchange.create_and_add_order_fill(&mut create_fill_event(next_fill_date), &order_ref);
+        assert_eq!(order_ref.filled_amount(), fill_amount * dec!(2));
+    }
+
     mod get_commission_amount {
         use super::*;
 
@@ -3242,4 +3454,63 @@ mod test {
         let order_status = order_ref.status();
         assert_eq!(order_status, OrderStatus::Completed);
     }
+
+    fn fingerprinted_fill_event(fill_price: Price, fill_amount: Amount) -> FillEvent {
+        FillEvent {
+            source_type: EventSourceType::Rest,
+            trade_id: None,
+            client_order_id: None,
+            exchange_order_id: ExchangeOrderId::new("exchange_order_id".into()),
+            fill_price,
+            fill_amount: FillAmount::Incremental {
+                fill_amount,
+                total_filled_amount: None,
+            },
+            order_role: None,
+            commission_currency_code: None,
+            commission_rate: None,
+            commission_amount: Some(dec!(0.01)),
+            fill_type: OrderFillType::UserTrade,
+            special_order_data: None,
+            fill_date: Some(
+                chrono::DateTime::parse_from_rfc3339("2026-01-02T03:04:05.123Z")
+                    .expect("in test")
+                    .with_timezone(&Utc),
+            ),
+        }
+    }
+
+    #[test]
+    fn fill_fingerprint_is_stable() {
+        let fingerprint = fingerprinted_fill_event(dec!(0.2), dec!(5)).fingerprint();
+
+        // persisted fingerprints should match after restart with another toolchain
+        assert_eq!(fingerprint, Some(9_681_422_789_737_269_413));
+        assert_eq!(
+            fingerprinted_fill_event(dec!(0.20), dec!(5.000)).fingerprint(),
+            fingerprint
+        );
+        assert_ne!(
+            fingerprinted_fill_event(dec!(0.2), dec!(6)).fingerprint(),
+            fingerprint
+        );
+    }
+
+    #[test]
+    fn fill_fingerprint_depends_on_total_filled_amount() {
+        let fill_event = |total_filled_amount| {
+            let mut fill_event = fingerprinted_fill_event(dec!(0.2), dec!(5));
+            fill_event.fill_amount = FillAmount::Incremental {
+                fill_amount: dec!(5),
+                total_filled_amount,
+            };
+            fill_event
+        };
+
+        let fingerprint = fill_event(Some(dec!(5))).fingerprint();
+        assert!(fingerprint.is_some());
+        assert_eq!(fill_event(Some(dec!(5.00))).fingerprint(), fingerprint);
+        assert_ne!(fill_event(Some(dec!(10))).fingerprint(), fingerprint);
+        assert_ne!(fill_event(None).fingerprint(), fingerprint);
+    }
 }
@@ -2,7 +2,7 @@ use mmb_domain::events::{ExchangeEvent, Trade, TradesEvent};
 use mmb_domain::market::CurrencyPair;
 use mmb_domain::market::MarketId;
 
-use crate::exchanges::{general::exchange::Exchange, timeouts::timeout_manager};
+use crate::exchanges::general::exchange::Exchange;
 
 impl Exchange {
     pub fn handle_trade(&self, currency_pair: CurrencyPair, trade: Trade) {
@@ -14,7 +14,7 @@ impl Exchange {
             exchange_account_id: self.exchange_account_id,
             currency_pair,
             trades: vec![trade],
-            receipt_time: timeout_manager::now(),
+            receipt_time: self.clock.now(),
         };
 
         let market_id = MarketId::new(self.exchange_account_id.exchange_id, currency_pair);
@@ -2,6 +2,7 @@ use mmb_domain::events::{AllowedEventSourceType, EventSourceType};
 
 pub mod handle_cancel_order_failed;
 pub mod handle_cancel_order_succeeded;
+pub mod handle_dead_letters;
 pub mod handle_order_filled;
 pub mod handle_trade;
 
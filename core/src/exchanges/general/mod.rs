@@ -8,6 +8,8 @@ pub mod handlers;
 pub mod order;
 pub mod polling_timeout_manager;
 pub mod request_type;
+pub mod system_status;
+pub mod transfer;
 
 #[cfg(test)]
 pub mod test_helper;
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{ClientOrderId, OrderSnapshot};
+
+use crate::database::order_archive::{load_archived_order, ArchivedOrder};
+use crate::exchanges::general::exchange::Exchange;
+
+impl Exchange {
+    /// Saves final state of orders evicted from orders pool, so they can be found later by
+    /// `find_order`
+    pub fn archive_orders(&self, orders: &[OrderRef]) {
+        let archive_time = self.clock.now();
+        for order in orders {
+            let archived_order = ArchivedOrder {
+                client_order_id: order.client_order_id(),
+                exchange_account_id: self.exchange_account_id,
+                archive_time,
+                order: order.deep_clone(),
+            };
+            if let Err(err) = self.event_recorder.save(archived_order) {
+                log::error!(
+                    "Failed to archive order {} on {}: {err:?}",
+                    order.client_order_id(),
+                    self.exchange_account_id
+                );
+            }
+        }
+    }
+
+    /// Order from orders pool or from archive if it was already evicted from the pool
+    pub async fn find_order(
+        &self,
+        client_order_id: &ClientOrderId,
+    ) -> Result<Option<OrderSnapshot>> {
+        if let Some(order) = self.orders.cache_by_client_id.get(client_order_id) {
+            return Ok(Some(order.deep_clone()));
+        }
+
+        match self.event_recorder.storage() {
+            Some(storage) => {
+                load_archived_order(storage.as_ref(), self.exchange_account_id, client_order_id)
+                    .await
+            }
+            None => Ok(None),
+        }
+    }
+}
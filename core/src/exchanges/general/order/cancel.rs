@@ -7,10 +7,13 @@ use mmb_domain::order::pool::OrderRef;
 use mmb_domain::order::snapshot::Amount;
 use mmb_domain::order::snapshot::{ClientOrderId, ExchangeOrderId, OrderInfo, OrderStatus};
 use mmb_utils::cancellation_token::CancellationToken;
+use std::time::Instant;
 use tokio::sync::oneshot;
+use tracing::field::Empty;
 
+use super::record_exchange_order_id;
+use crate::exchanges::timeouts::order_rate_limiter::OrderRequestKind;
 use crate::exchanges::traits::ExchangeError;
-use crate::misc::time::time_manager;
 use crate::{exchanges::general::exchange::Exchange, exchanges::general::exchange::RequestResult};
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -44,51 +47,57 @@ impl CancelOrderResult {
 }
 
 impl Exchange {
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            client_order_id = %order.client_order_id(),
+            exchange_order_id = Empty,
+            exchange_account_id = %self.exchange_account_id,
+        )
+    )]
     pub async fn start_cancel_order(
         &self,
         order: &OrderRef,
         cancellation_token: CancellationToken,
     ) -> Result<Option<CancelOrderResult>> {
-        let client_order_id = order.client_order_id();
-        let (status, exchange_order_id) = order.fn_ref(|x| (x.status(), x.exchange_order_id()));
-        match status {
+        record_exchange_order_id(order);
+        match order.status() {
             OrderStatus::Canceled => {
-                log::info!(
-                    "Order {client_order_id} {exchange_order_id:?} are already canceled on {}",
-                    self.exchange_account_id
-                );
+                tracing::info!("Order is already canceled");
                 Ok(None)
             }
             OrderStatus::Completed => {
-                log::info!(
-                    "Order {client_order_id} {exchange_order_id:?} are already completed on {}",
-                    self.exchange_account_id
-                );
+                tracing::info!("Order is already completed");
                 Ok(None)
             }
             _ => {
-                order.fn_mut(|order| order.set_status(OrderStatus::Canceling, time_manager::now()));
+                order.fn_mut(|order| order.set_status(OrderStatus::Canceling, self.clock.now()));
 
-                log::info!(
-                    "Submitting order cancellation {client_order_id} {exchange_order_id:?} on {}",
-                    self.exchange_account_id
-                );
+                tracing::info!("Submitting order cancellation");
 
                 let order_cancellation_outcome = self.cancel_order(order, cancellation_token).await;
 
-                log::info!(
-                    "Submitted order cancellation {client_order_id} {exchange_order_id:?} on {}: {order_cancellation_outcome:?}", self.exchange_account_id);
+                tracing::info!("Submitted order cancellation: {order_cancellation_outcome:?}");
 
                 Ok(order_cancellation_outcome)
             }
         }
     }
 
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            client_order_id = %order.client_order_id(),
+            exchange_order_id = Empty,
+            exchange_account_id = %self.exchange_account_id,
+        )
+    )]
     pub async fn cancel_order(
         &self,
         order: &OrderRef,
         cancellation_token: CancellationToken,
     ) -> Option<CancelOrderResult> {
+        record_exchange_order_id(order);
         match order.exchange_order_id() {
             Some(exchange_order_id) => {
                 let order_cancellation_outcome = self
@@ -121,7 +130,7 @@ impl Exchange {
                 order_cancellation_outcome
             }
             None => {
-                log::warn!("Missing exchange_order_id in cancelling order");
+                tracing::warn!("Missing exchange_order_id in cancelling order");
                 None
             }
         }
@@ -140,9 +149,10 @@ impl Exchange {
         self.order_cancellation_events
             .insert(exchange_order_id.clone(), (tx, None));
 
+        let started = Instant::now();
         let cancel_order_future = self.exchange_client.cancel_order(order, exchange_order_id);
 
-        tokio::select! {
+        let cancel_order_result = tokio::select! {
             cancel_order_result = cancel_order_future => {
                 match cancel_order_result.outcome {
                     RequestResult::Error(_) => {
@@ -159,7 +169,12 @@ impl Exchange {
             }
             _ = cancellation_token.when_cancelled() => None,
             websocket_outcome = &mut websocket_event_receiver => websocket_outcome.ok(),
+        };
+
+        if cancel_order_result.is_some() {
+            self.register_order_latency(OrderRequestKind::Cancel, started);
         }
+        cancel_order_result
     }
 
     pub(crate) fn raise_order_cancelled(
@@ -1,28 +1,45 @@
+use super::record_exchange_order_id;
+use crate::database::journal::JournalRecord;
 use crate::exchanges::general::exchange::RequestResult::{Error, Success};
 use crate::exchanges::general::handlers::should_ignore_event;
 use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::timeouts::order_rate_limiter::OrderRequestKind;
 use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
 use crate::exchanges::traits::ExchangeError;
-use crate::misc::time::time_manager;
+use crate::risk::risk_manager::{RiskContext, RiskViolation};
 use crate::{exchanges::general::exchange::Exchange, exchanges::general::exchange::RequestResult};
 use anyhow::{bail, Context, Result};
-use chrono::Utc;
 use function_name::named;
 use futures::pin_mut;
 use mmb_domain::events::{AllowedEventSourceType, EventSourceType};
-use mmb_domain::market::{ExchangeAccountId, ExchangeErrorType};
+use mmb_domain::exchanges::symbol::SymbolStatus;
+use mmb_domain::market::{ExchangeAccountId, ExchangeErrorType, MarketId};
 use mmb_domain::order::event::OrderEventType;
 use mmb_domain::order::pool::OrderRef;
 use mmb_domain::order::snapshot::{
-    ClientOrderId, ExchangeOrderId, OrderHeader, OrderInfo, OrderStatus, OrderType,
+    ClientOrderId, ExchangeOrderId, OrderHeader, OrderInfo, OrderStatus,
 };
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::time::ToStdExpected;
 use mmb_utils::{nothing_to_do, OPERATION_CANCELED_MSG};
 use std::borrow::Cow;
+use std::sync::Weak;
 use std::time::Duration;
 use tokio::sync::oneshot;
-use tokio::time::{sleep, timeout};
+use tokio::time::timeout;
+use tracing::field::Empty;
+
+/// Max count of creation requests with the same client order id when creation status is unknown
+pub const MAX_CREATE_ORDER_ATTEMPTS: usize = 3;
+
+/// Errors after which order can exist on exchange, e.g. request was sent, but its response
+/// wasn't received
+fn is_creation_status_unknown(error: &ExchangeError) -> bool {
+    matches!(
+        error.error_type,
+        ExchangeErrorType::SendError | ExchangeErrorType::ServiceUnavailable
+    )
+}
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct CreateOrderResult {
@@ -52,17 +69,104 @@ impl Exchange {
         order_header: &OrderHeader,
         pre_reservation_group_id: Option<RequestGroupId>,
         cancellation_token: CancellationToken,
+    ) -> Result<OrderRef> {
+        self.create_order_with_balance_check(
+            order_header,
+            pre_reservation_group_id,
+            true,
+            cancellation_token,
+        )
+        .await
+    }
+
+    /// Creates order. If `reserve_balance` is set, balance for order without reservation is
+    /// reserved and order is rejected locally when funds are insufficient
+    #[tracing::instrument(
+        name = "create_order",
+        skip_all,
+        fields(
+            client_order_id = %order_header.client_order_id,
+            exchange_order_id = Empty,
+            exchange_account_id = %self.exchange_account_id,
+        )
+    )]
+    pub(super) async fn create_order_with_balance_check(
+        &self,
+        order_header: &OrderHeader,
+        pre_reservation_group_id: Option<RequestGroupId>,
+        reserve_balance: bool,
+        cancellation_token: CancellationToken,
     ) -> Result<OrderRef> {
         use AllowedEventSourceType::*;
 
-        log::info!("Submitting order {order_header:?}");
+        if self.is_watch_only() {
+            bail!(
+                "Unable to create order {} because exchange account {} is watch-only",
+                order_header.client_order_id,
+                self.exchange_account_id
+            );
+        }
+
+        if self.is_trading_halted() {
+            bail!(
+                "Unable to create order {} because trading is halted by kill switch",
+                order_header.client_order_id
+            );
+        }
+
+        let symbol_status = self
+            .symbols
+            .get(&order_header.currency_pair)
+            .map(|x| x.status);
+        if let Some(status @ (SymbolStatus::Halted | SymbolStatus::Delisted)) = symbol_status {
+            bail!(
+                "Unable to create order {} because symbol {} is {status:?} on {}",
+                order_header.client_order_id,
+                order_header.currency_pair,
+                self.exchange_account_id
+            );
+        }
+
+        tracing::info!("Submitting order {order_header:?}");
+
+        let validated_header = self.validate_order(order_header);
+        let order_header = match &validated_header {
+            Ok(validated_header) => validated_header.as_ref(),
+            Err(_) => order_header,
+        };
+
+        // balance is reserved for rounded amount of order
+        let reservation = match reserve_balance && validated_header.is_ok() {
+            true => self.reserve_order_balance(order_header),
+            false => Ok(None),
+        };
+        let order_header = match reservation {
+            Ok(Some(reservation_id)) => Cow::Owned(OrderHeader {
+                reservation_id: Some(reservation_id),
+                ..order_header.clone()
+            }),
+            _ => Cow::Borrowed(order_header),
+        };
 
         let order = self.orders.add_simple_initial(
-            order_header,
-            time_manager::now(),
+            &order_header,
+            self.clock.now(),
             self.exchange_client.get_initial_extension_data(),
         );
 
+        if let Err(error) = validated_header {
+            return self.reject_by_validation(&order, error);
+        }
+
+        if let Err(reason) = reservation {
+            return self.reject_by_balance(&order, reason);
+        }
+
+        // reservation made above is released on RejectedByRisk event
+        if let Err(violation) = self.check_risk(&order_header) {
+            return self.reject_by_risk(&order, violation);
+        }
+
         let linked_ct = cancellation_token.create_linked_token();
 
         let create_order_fut = self.create_order_base(&order, linked_ct.clone());
@@ -177,13 +281,81 @@ impl Exchange {
             }
         }
 
+        record_exchange_order_id(&order);
+
         self.handle_created_order(&order, pre_reservation_group_id, cancellation_token)
             .await
-            .unwrap_or_else(|err| log::error!("failed handle_created_order: {err}"));
+            .unwrap_or_else(|err| tracing::error!("failed handle_created_order: {err}"));
 
         Ok(order)
     }
 
+    pub(super) fn check_risk(&self, order_header: &OrderHeader) -> Result<(), RiskViolation> {
+        let risk_manager = match self.risk_manager.lock().clone() {
+            None => return Ok(()),
+            Some(risk_manager) => risk_manager,
+        };
+
+        let currency_pair = order_header.currency_pair;
+        let mid_price = self.mid_price(currency_pair);
+
+        let balance_manager = self.balance_manager.lock().as_ref().and_then(Weak::upgrade);
+        let position = balance_manager
+            .map(|x| {
+                x.lock()
+                    .net_position(self.exchange_account_id, currency_pair)
+            })
+            .unwrap_or_default();
+
+        let context = RiskContext {
+            mid_price,
+            position,
+            // new order is already added to pool
+            open_orders_count: self.orders.not_finished.len().saturating_sub(1),
+            request_weight_utilization: self.timeout_manager.utilization(self.exchange_account_id),
+            volatility: self.volatility_service.lock().as_ref().and_then(|x| {
+                x.volatility(MarketId::new(
+                    self.exchange_account_id.exchange_id,
+                    currency_pair,
+                ))
+            }),
+        };
+
+        risk_manager.check(order_header, &context)
+    }
+
+    pub(super) fn reject_by_risk(
+        &self,
+        order: &OrderRef,
+        violation: RiskViolation,
+    ) -> Result<OrderRef> {
+        let client_order_id = order.client_order_id();
+        tracing::warn!("Order {client_order_id} is rejected by risk manager: {violation}");
+
+        order.fn_mut(|x| {
+            x.set_status(OrderStatus::FailedToCreate, self.clock.now());
+            x.internal_props.last_creation_error_message = violation.to_string();
+        });
+
+        self.add_event_on_order_change(order, OrderEventType::RejectedByRisk)?;
+
+        self.event_recorder
+            .save(&mut order.deep_clone())
+            .expect("Failure save order");
+
+        let kill_switch_on_violation = self
+            .risk_manager
+            .lock()
+            .as_ref()
+            .is_some_and(|x| x.settings().kill_switch_on_violation);
+        if kill_switch_on_violation {
+            let reason = format!("risk violation of order {client_order_id}: {violation}");
+            self.lifetime_manager.spawn_kill_switch(&reason, false);
+        }
+
+        bail!("Order {client_order_id} is rejected by risk manager: {violation}")
+    }
+
     async fn handle_created_order(
         &self,
         order: &OrderRef,
@@ -202,23 +374,18 @@ impl Exchange {
                 .await?;
         }
 
-        let client_order_id = order.client_order_id();
-
         if order.status() == OrderStatus::Creating {
-            log::error!("OrderStatus of order {client_order_id} is Creating at the end of create order procedure");
+            tracing::error!(
+                "OrderStatus of order is Creating at the end of create order procedure"
+            );
         }
 
         self.event_recorder
             .save(&mut order.deep_clone())
             .expect("Failure save order");
 
-        let header = order.header();
-        log::info!(
-            "Order was submitted {client_order_id} {:?} {:?} on {}",
-            order.exchange_order_id(),
-            header.reservation_id,
-            header.exchange_account_id,
-        );
+        let reservation_id = order.header().reservation_id;
+        tracing::info!(?reservation_id, "Order was submitted");
 
         Ok(())
     }
@@ -241,7 +408,7 @@ impl Exchange {
                 break;
             }
 
-            let now = time_manager::now();
+            let now = self.clock.now();
             let order_creation_status_request_period = chrono::Duration::seconds(5);
             let delay_till_fallback_request = match last_order_creation_status_request_time {
                 None => Some(order_creation_status_request_period.to_std_expected()),
@@ -252,7 +419,7 @@ impl Exchange {
             };
 
             if let Some(delay) = delay_till_fallback_request {
-                sleep(delay).await;
+                self.clock.sleep(delay).await;
             }
 
             self.check_order_creation(
@@ -276,7 +443,7 @@ impl Exchange {
     ) {
         let client_order_id = order.client_order_id();
         while !cancellation_token.is_cancellation_requested() {
-            let (status, exchange_order_id) = order.fn_ref(|o| (o.status(), o.exchange_order_id()));
+            let status = order.status();
 
             if status != OrderStatus::Creating {
                 return;
@@ -289,10 +456,10 @@ impl Exchange {
             {
                 order.fn_mut(|o| {
                     o.internal_props.last_order_creation_status_request_time =
-                        Some(time_manager::now())
+                        Some(self.clock.now())
                 });
 
-                log::trace!("Checking order info in CheckOrderCreation {client_order_id} {exchange_order_id:?} {}", self.exchange_account_id);
+                tracing::trace!("Checking order info in CheckOrderCreation");
 
                 self.timeout_manager
                     .reserve_when_available(
@@ -329,13 +496,8 @@ impl Exchange {
                         return;
                     }
                     Err(err) => {
-                        self.handle_error_from_check_order_info(
-                            &order,
-                            &client_order_id,
-                            &error,
-                            err,
-                        )
-                        .await
+                        self.handle_error_from_check_order_info(&order, &error, err)
+                            .await
                     }
                 }
             } else if let Some(error) = &error {
@@ -346,13 +508,13 @@ impl Exchange {
                     EventSourceType::RestFallback,
                 )
                 .unwrap_or_else(|err| {
-                    log::error!(
+                    tracing::error!(
                         "Failed handle_create_order_failed in check_order_creation: {err:?}"
                     )
                 })
             } else {
                 tokio::select! {
-                    _ = sleep(Duration::from_millis(25)) => nothing_to_do(),
+                    _ = self.clock.sleep(Duration::from_millis(25)) => nothing_to_do(),
                     _ = cancellation_token.when_cancelled() => nothing_to_do(),
                 }
             }
@@ -362,14 +524,10 @@ impl Exchange {
     async fn handle_error_from_check_order_info(
         &self,
         order: &OrderRef,
-        client_order_id: &ClientOrderId,
         error: &Option<ExchangeError>,
         get_order_info_error: ExchangeError,
     ) {
-        log::trace!(
-            "CheckOrderCreation GetOrderInfo response err {get_order_info_error:?} for {client_order_id} on {}",
-            self.exchange_account_id
-        );
+        tracing::trace!("CheckOrderCreation GetOrderInfo response err {get_order_info_error:?}");
 
         // TODO hack for aax
 
@@ -378,7 +536,7 @@ impl Exchange {
                 let client_order_id = order.client_order_id();
                 let init_time = order.fn_ref(|o| o.init_time());
 
-                let now = time_manager::now();
+                let now = self.clock.now();
                 let min_timeout_for_failed_to_create_order = chrono::Duration::minutes(1);
 
                 if now - init_time > min_timeout_for_failed_to_create_order {
@@ -387,11 +545,7 @@ impl Exchange {
                         Some(error) => Cow::Borrowed(error),
                     };
 
-                    log::warn!(
-                        "{} {client_order_id} on {}",
-                        new_error.message,
-                        self.exchange_account_id
-                    );
+                    tracing::warn!("{}", new_error.message);
 
                     self.handle_create_order_failed(
                         &client_order_id,
@@ -399,7 +553,7 @@ impl Exchange {
                         EventSourceType::RestFallback,
                     )
                     .unwrap_or_else(|err| {
-                        log::error!(
+                        tracing::error!(
                             "failed handle_create_order_failed in check_order_creation: {err:?}"
                         )
                     })
@@ -409,7 +563,7 @@ impl Exchange {
                 // TODO Integrate ExchangeBlocker to wait_order_finish/wait_cancel_order fallbacks #641
                 let delay = self.get_timeout();
                 // TODO fix for AAX
-                sleep(delay).await;
+                self.clock.sleep(delay).await;
             }
             _ => nothing_to_do(),
         }
@@ -422,29 +576,24 @@ impl Exchange {
         order: &OrderRef,
         order_info: &OrderInfo,
     ) {
-        fn log_status(
-            this: &Exchange,
-            status: OrderStatus,
-            client_order_id: &ClientOrderId,
-            exchange_order_id: &Option<ExchangeOrderId>,
-        ) {
-            log::warn!("CheckOrderCreation fallback found a {status:?} order {client_order_id} {exchange_order_id:?} on {}", this.exchange_account_id);
+        fn log_status(status: OrderStatus) {
+            tracing::warn!("CheckOrderCreation fallback found a {status:?} order");
         }
 
         let status = order_info.order_status;
         match status {
             OrderStatus::FailedToCreate => {
-                log_status(self, status, client_order_id, exchange_order_id);
+                log_status(status);
 
                 self.handle_create_order_failed(
                     client_order_id,
                     &ExchangeError::unknown("Fallback"),
                     EventSourceType::RestFallback,
                 )
-                .unwrap_or_else(|err| log::error!("Failed 'check_order_creation' for order status 'FailedToCreate' with error: {err:?}"));
+                .unwrap_or_else(|err| tracing::error!("Failed 'check_order_creation' for order status 'FailedToCreate' with error: {err:?}"));
             }
             OrderStatus::Canceled => {
-                log_status(self, status, client_order_id, exchange_order_id);
+                log_status(status);
 
                 let exchange_order_id = exchange_order_id
                     .as_ref()
@@ -457,7 +606,7 @@ impl Exchange {
                 )
             }
             OrderStatus::Created | OrderStatus::Completed => {
-                log_status(self, status, client_order_id, exchange_order_id);
+                log_status(status);
 
                 order.fn_mut(|x| {
                     let filled_amount = Some(order_info.filled_amount);
@@ -472,10 +621,7 @@ impl Exchange {
                     EventSourceType::RestFallback,
                 );
             }
-            _ => log::warn!(
-                "Unknown order status {status:?} {client_order_id} {exchange_order_id:?} on {}",
-                self.exchange_account_id
-            ),
+            _ => tracing::warn!("Unknown order status {status:?}"),
         }
     }
 
@@ -485,7 +631,28 @@ impl Exchange {
         cancellation_token: CancellationToken,
     ) -> Result<CreateOrderResult> {
         let client_order_id = order.client_order_id();
-        let create_order_result = self.create_order_core(order, cancellation_token).await;
+
+        self.timeout_manager
+            .acquire_order_request(
+                self.exchange_account_id,
+                OrderRequestKind::Create,
+                cancellation_token.clone(),
+            )
+            .await?;
+
+        // order should be known after crash if exchange accepted it
+        if let Some(journal) = self.journal() {
+            journal
+                .append_durable(JournalRecord::OrderIntent(order.deep_clone()))
+                .await
+                .with_context(|| {
+                    format!("Failed to write intent of order {client_order_id} to journal")
+                })?;
+        }
+
+        let create_order_result = self
+            .create_order_idempotent(order, cancellation_token)
+            .await;
 
         if let Some(created_order) = create_order_result {
             match &created_order.outcome {
@@ -514,8 +681,88 @@ impl Exchange {
         bail!(OPERATION_CANCELED_MSG)
     }
 
+    /// Sends order creation request. If execution status of request is unknown, order is
+    /// requested by client order id and creation is repeated with the same client order id
+    /// only if exchange doesn't know the order, so order is submitted at most once
+    async fn create_order_idempotent(
+        &self,
+        order: &OrderRef,
+        cancellation_token: CancellationToken,
+    ) -> Option<CreateOrderResult> {
+        let client_order_id = order.client_order_id();
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .create_order_core(order, cancellation_token.clone())
+                .await?;
+
+            let error = match &result.outcome {
+                Error(error) => error,
+                Success(_) => return Some(result),
+            };
+
+            // repeated request can be rejected as duplicate if the previous one has reached exchange
+            let is_ambiguous = attempt > 1 || is_creation_status_unknown(error);
+            if !is_ambiguous
+                || !self
+                    .features
+                    .order_features
+                    .supports_get_order_info_by_client_order_id
+            {
+                return Some(result);
+            }
+
+            tracing::warn!(
+                "Creation status of order {client_order_id} is unknown after attempt {attempt}: {error:?}"
+            );
+
+            self.timeout_manager
+                .reserve_when_available(
+                    self.exchange_account_id,
+                    RequestType::GetOrderInfo,
+                    None,
+                    cancellation_token.clone(),
+                )
+                .await;
+
+            match self.get_order_info(order).await {
+                Ok(order_info) => {
+                    tracing::info!("Order {client_order_id} is found on exchange after creation with unknown status");
+                    return Some(CreateOrderResult::succeed(
+                        &order_info.exchange_order_id,
+                        EventSourceType::Rest,
+                    ));
+                }
+                Err(info_error)
+                    if info_error.error_type == ExchangeErrorType::OrderNotFound
+                        && attempt < MAX_CREATE_ORDER_ATTEMPTS =>
+                {
+                    attempt += 1;
+                    tracing::warn!(
+                        "Order {client_order_id} isn't found on exchange, repeating its creation"
+                    );
+
+                    self.timeout_manager
+                        .acquire_order_request(
+                            self.exchange_account_id,
+                            OrderRequestKind::Create,
+                            cancellation_token.clone(),
+                        )
+                        .await
+                        .ok()?;
+                }
+                Err(info_error) => {
+                    tracing::error!(
+                        "Unable to determine creation status of order {client_order_id}: {info_error:?}"
+                    );
+                    return Some(result);
+                }
+            }
+        }
+    }
+
     #[named]
-    fn handle_create_order_failed(
+    pub(super) fn handle_create_order_failed(
         &self,
         client_order_id: &ClientOrderId,
         exchange_error: &ExchangeError,
@@ -583,7 +830,7 @@ impl Exchange {
                 // TODO RestFallback and some metrics
 
                 order.fn_mut(|x| {
-                    x.set_status(OrderStatus::FailedToCreate, Utc::now());
+                    x.set_status(OrderStatus::FailedToCreate, self.clock.now());
                     x.internal_props.last_creation_error_type = Some(exchange_error.error_type);
                     x.internal_props.last_creation_error_message = exchange_error.message.clone();
                 });
@@ -691,7 +938,7 @@ impl Exchange {
                 // TODO RestFallback and some metrics
 
                 order.fn_mut(|order| {
-                    order.set_status(OrderStatus::Created, Utc::now());
+                    order.set_status(OrderStatus::Created, self.clock.now());
                     order.internal_props.creation_event_source_type = Some(source_type);
                 });
 
@@ -701,7 +948,7 @@ impl Exchange {
 
                 let header = order.header();
                 let client_order_id = header.client_order_id.clone();
-                if order.order_type() != OrderType::Liquidation {
+                if !order.order_type().is_external_order() {
                     match header.reservation_id {
                         None => {
                             log::warn!("Created order {client_order_id} without reservation_id")
@@ -722,35 +969,18 @@ impl Exchange {
 
                 self.add_event_on_order_change(order, OrderEventType::CreateOrderSucceeded)?;
 
-                let mut buffered_fills_manager = self.buffered_fills_manager.lock();
-                if let Some(buffered_fills) = buffered_fills_manager.get_fills(exchange_order_id) {
+                let dead_letters = self
+                    .dead_letter_queue
+                    .lock()
+                    .take_by_order(exchange_order_id);
+                if !dead_letters.is_empty() {
                     log::trace!(
-                        "Found buffered fills for an order {client_order_id} {exchange_order_id} on {}:\n{buffered_fills:?}",
+                        "Found buffered events for an order {client_order_id} {exchange_order_id} on {}:\n{dead_letters:?}",
                         self.exchange_account_id,
                     );
 
-                    for buffered_fill in buffered_fills {
-                        let mut fill_event =
-                            buffered_fill.to_fill_event_data(client_order_id.clone());
-                        self.handle_order_filled(&mut fill_event);
-                    }
-
-                    buffered_fills_manager.remove_fills(exchange_order_id);
-                }
-                drop(buffered_fills_manager);
-
-                let mut buffered_canceled_orders_manager =
-                    self.buffered_canceled_orders_manager.lock();
-                if buffered_canceled_orders_manager.is_order_buffered(exchange_order_id) {
-                    self.handle_cancel_order_succeeded(
-                        Some(&client_order_id),
-                        exchange_order_id,
-                        None,
-                        source_type,
-                    );
-                    buffered_canceled_orders_manager.remove_order(exchange_order_id);
+                    self.apply_dead_letters(dead_letters, &client_order_id);
                 }
-                drop(buffered_canceled_orders_manager);
 
                 self.event_recorder
                     .save(&mut order.deep_clone())
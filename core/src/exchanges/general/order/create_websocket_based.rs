@@ -1,8 +1,11 @@
 use mmb_domain::events::EventSourceType;
 use mmb_domain::order::snapshot::{ClientOrderId, ExchangeOrderId};
 use mmb_utils::cancellation_token::CancellationToken;
+use std::time::Instant;
 use tokio::sync::oneshot;
 
+use crate::exchanges::timeouts::order_rate_limiter::OrderRequestKind;
+
 use crate::{exchanges::general::exchange::Exchange, exchanges::general::exchange::RequestResult};
 use mmb_domain::order::pool::OrderRef;
 use mmb_utils::infrastructure::WithExpect;
@@ -23,9 +26,10 @@ impl Exchange {
         self.order_creation_events
             .insert(client_order_id.clone(), (tx, None));
 
+        let started = Instant::now();
         let create_order_future = self.exchange_client.create_order(order);
 
-        tokio::select! {
+        let create_order_result = tokio::select! {
             create_order_result = create_order_future => {
                 match create_order_result.outcome {
                     RequestResult::Error(_) => {
@@ -40,7 +44,12 @@ impl Exchange {
             }
             _ = cancellation_token.when_cancelled() => None,
             websocket_outcome = &mut websocket_event_receiver => websocket_outcome.ok(),
+        };
+
+        if create_order_result.is_some() {
+            self.register_order_latency(OrderRequestKind::Create, started);
         }
+        create_order_result
     }
 
     pub(crate) fn raise_order_created(
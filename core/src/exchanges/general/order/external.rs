@@ -0,0 +1,251 @@
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::handlers::handle_order_filled::FillEvent;
+use crate::infrastructure::spawn_future;
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use crate::settings::{ExternalOrderPolicy, ExternalOrdersSettings};
+use anyhow::Result;
+use mmb_domain::events::EventSourceType;
+use mmb_domain::market::CurrencyPair;
+use mmb_domain::order::event::{OrderEvent, OrderEventType};
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, ExchangeOrderId, OrderInfo, OrderOptions, OrderRole, OrderSide,
+    OrderSnapshot, OrderType, Price,
+};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use std::sync::{Arc, Weak};
+
+/// Strategy name of orders placed on exchange outside of engine
+pub const EXTERNAL_ORDER_STRATEGY_NAME: &str = "ExternalOrder";
+
+/// Parameters of order placed outside of engine known from exchange
+pub(crate) struct ExternalOrderData {
+    pub client_order_id: Option<ClientOrderId>,
+    pub exchange_order_id: ExchangeOrderId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub amount: Amount,
+    pub price: Price,
+    pub role: Option<OrderRole>,
+}
+
+impl From<&OrderInfo> for ExternalOrderData {
+    fn from(order_info: &OrderInfo) -> Self {
+        ExternalOrderData {
+            client_order_id: Some(order_info.client_order_id.clone()),
+            exchange_order_id: order_info.exchange_order_id.clone(),
+            currency_pair: order_info.currency_pair,
+            side: order_info.order_side,
+            amount: order_info.amount,
+            price: order_info.price,
+            role: None,
+        }
+    }
+}
+
+impl Exchange {
+    /// Enables adoption of orders placed on exchange outside of engine, e.g. manually in exchange UI.
+    /// Adopted orders are added to orders pool as `OrderType::Manual`
+    pub fn setup_external_orders(&self, settings: &ExternalOrdersSettings) {
+        *self.external_orders.lock() = Some(settings.clone());
+    }
+
+    pub(crate) fn is_external_orders_adoption_enabled(&self) -> bool {
+        self.external_orders.lock().is_some()
+    }
+
+    /// Adopts order of fill which isn't known by orders pool. Order can be adopted only if exchange
+    /// reports its side and amount, otherwise fill waits in dead letter queue until order is
+    /// adopted by open orders reconciliation
+    pub(crate) fn adopt_external_order_by_fill(&self, fill_event: &FillEvent) -> Option<OrderRef> {
+        if !self.is_external_orders_adoption_enabled() {
+            return None;
+        }
+
+        // fill of order created by engine can be received before creation response
+        if let Some(client_order_id) = &fill_event.client_order_id {
+            if self.orders.cache_by_client_id.contains_key(client_order_id) {
+                return None;
+            }
+        }
+
+        let special = fill_event.special_order_data.as_ref()?;
+        let data = ExternalOrderData {
+            client_order_id: fill_event.client_order_id.clone(),
+            exchange_order_id: fill_event.exchange_order_id.clone(),
+            currency_pair: special.currency_pair,
+            side: special.order_side,
+            amount: special.order_amount,
+            price: fill_event.fill_price,
+            role: fill_event.order_role,
+        };
+
+        self.adopt_external_order(data, fill_event.source_type)
+            .map_err(|err| {
+                log::error!(
+                    "Failed to adopt external order {} on {}: {err:?}",
+                    fill_event.exchange_order_id,
+                    self.exchange_account_id
+                )
+            })
+            .ok()
+    }
+
+    /// Registers order placed outside of engine in orders pool. Its events buffered in dead letter
+    /// queue are applied right after adoption
+    pub(crate) fn adopt_external_order(
+        &self,
+        data: ExternalOrderData,
+        source_type: EventSourceType,
+    ) -> Result<OrderRef> {
+        // client order id reported by exchange can match id of order already known by pool
+        let client_order_id = data
+            .client_order_id
+            .filter(|x| !x.as_str().is_empty() && !self.orders.cache_by_client_id.contains_key(x))
+            .unwrap_or_else(ClientOrderId::unique_id);
+
+        log::warn!(
+            "Adopting external order {} as {client_order_id} on {}",
+            data.exchange_order_id,
+            self.exchange_account_id
+        );
+
+        let snapshot = OrderSnapshot::with_params(
+            client_order_id.clone(),
+            OrderOptions::manual(data.price),
+            data.role,
+            self.exchange_account_id,
+            data.currency_pair,
+            data.amount,
+            data.side,
+            None,
+            EXTERNAL_ORDER_STRATEGY_NAME,
+        );
+        let order = self.orders.add_snapshot_initial(&snapshot);
+
+        self.handle_create_order_succeeded(
+            self.exchange_account_id,
+            &client_order_id,
+            &data.exchange_order_id,
+            source_type,
+        )?;
+
+        Ok(order)
+    }
+
+    /// Applies fills of adopted orders to balances and cancels adopted orders if it's required
+    /// by policy
+    pub(crate) fn handle_external_order_event(self: &Arc<Self>, order_event: &OrderEvent) {
+        let order = &order_event.order;
+        if order.order_type() != OrderType::Manual {
+            return;
+        }
+
+        match &order_event.event_type {
+            OrderEventType::OrderFilled { cloned_order } => {
+                let balance_manager =
+                    match self.balance_manager.lock().as_ref().and_then(Weak::upgrade) {
+                        None => return,
+                        Some(balance_manager) => balance_manager,
+                    };
+
+                let configuration_descriptor = ConfigurationDescriptor::new(
+                    EXTERNAL_ORDER_STRATEGY_NAME.into(),
+                    cloned_order.market_id().into(),
+                );
+                balance_manager
+                    .lock()
+                    .order_was_filled(configuration_descriptor, cloned_order);
+            }
+            OrderEventType::CreateOrderSucceeded => {
+                let policy = self.external_orders.lock().as_ref().map(|x| x.policy);
+                if policy == Some(ExternalOrderPolicy::Cancel) {
+                    self.spawn_external_order_cancellation(order.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn spawn_external_order_cancellation(self: &Arc<Self>, order: OrderRef) {
+        let exchange = self.clone();
+        let client_order_id = order.client_order_id();
+        let action = format!("Cancel external order {client_order_id}");
+        spawn_future(&action, SpawnFutureFlags::STOP_BY_TOKEN, async move {
+            let cancellation_token = exchange.lifetime_manager.stop_token();
+            log::info!("Cancelling external order {client_order_id} by policy");
+            if let Err(err) = exchange
+                .wait_cancel_order(order, None, true, cancellation_token)
+                .await
+            {
+                log::error!("Failed to cancel external order {client_order_id}: {err:?}");
+            }
+
+            Ok(())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::handlers::handle_order_filled::{FillAmount, SpecialOrderData};
+    use crate::exchanges::general::test_helper::get_test_exchange;
+    use crate::infrastructure::init_lifetime_manager;
+    use mmb_domain::order::fill::OrderFillType;
+    use mmb_domain::order::snapshot::OrderStatus;
+    use rust_decimal_macros::dec;
+
+    fn fill_event(exchange_order_id: &str) -> FillEvent {
+        FillEvent {
+            source_type: EventSourceType::WebSocket,
+            trade_id: None,
+            client_order_id: None,
+            exchange_order_id: exchange_order_id.into(),
+            fill_price: dec!(0.8),
+            fill_amount: FillAmount::Incremental {
+                fill_amount: dec!(2),
+                total_filled_amount: None,
+            },
+            order_role: Some(OrderRole::Taker),
+            commission_currency_code: None,
+            commission_rate: None,
+            commission_amount: None,
+            fill_type: OrderFillType::UserTrade,
+            special_order_data: Some(SpecialOrderData {
+                currency_pair: CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+                order_side: OrderSide::Buy,
+                order_amount: dec!(5),
+            }),
+            fill_date: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn fill_of_unknown_order_is_tracked_after_adoption() {
+        let _ = init_lifetime_manager();
+        let (exchange, _event_receiver) = get_test_exchange(false);
+
+        let mut unknown_fill = fill_event("external_1");
+        exchange.handle_order_filled(&mut unknown_fill);
+        assert!(!exchange
+            .orders
+            .cache_by_exchange_id
+            .contains_key(&unknown_fill.exchange_order_id));
+
+        exchange.setup_external_orders(&ExternalOrdersSettings::default());
+        let mut fill = fill_event("external_2");
+        exchange.handle_order_filled(&mut fill);
+
+        let order = exchange
+            .orders
+            .cache_by_exchange_id
+            .get(&fill.exchange_order_id)
+            .map(|x| x.value().clone())
+            .expect("order should be adopted");
+        assert_eq!(order.order_type(), OrderType::Manual);
+        assert_eq!(order.status(), OrderStatus::Created);
+        assert_eq!(order.amount(), dec!(5));
+        assert_eq!(order.filled_amount(), dec!(2));
+    }
+}
@@ -1,8 +1,9 @@
 use crate::exchanges::general::request_type::RequestType;
-use crate::misc::time::time_manager;
 use crate::{exchanges::general::exchange::Exchange, exchanges::general::features::OpenOrdersType};
 use anyhow::bail;
 use itertools::Itertools;
+use mmb_domain::events::EventSourceType;
+use mmb_domain::order::pool::OrderRef;
 use mmb_domain::order::snapshot::{
     ClientOrderId, OrderHeader, OrderInfo, OrderOptions, OrderSimpleProps, OrderSnapshot,
 };
@@ -29,7 +30,7 @@ impl Exchange {
                     }
                 }
             }
-            tokio::time::sleep(Duration::from_secs(1)).await;
+            self.clock.sleep(Duration::from_secs(1)).await;
         }
     }
 
@@ -82,13 +83,15 @@ impl Exchange {
         };
 
         if check_missing_orders {
-            self.add_missing_open_orders(&open_orders);
+            let _ = self.add_missing_open_orders(&open_orders);
         }
 
         Ok(open_orders)
     }
 
-    fn add_missing_open_orders(&self, open_orders: &[OrderInfo]) {
+    /// Adds open orders which aren't known by orders pool. Returns added orders
+    pub(super) fn add_missing_open_orders(&self, open_orders: &[OrderInfo]) -> Vec<OrderRef> {
+        let mut added_orders = Vec::new();
         for order_info in open_orders {
             if order_info.client_order_id.as_str().is_empty()
                 && self
@@ -109,6 +112,18 @@ impl Exchange {
                 continue;
             }
 
+            if self.is_external_orders_adoption_enabled() {
+                match self.adopt_external_order(order_info.into(), EventSourceType::Rest) {
+                    Ok(order) => added_orders.push(order),
+                    Err(err) => log::error!(
+                        "Failed to adopt open order {} on {}: {err:?}",
+                        order_info.exchange_order_id,
+                        self.exchange_account_id
+                    ),
+                }
+                continue;
+            }
+
             let id_for_new_header = if order_info.client_order_id.as_str().is_empty() {
                 ClientOrderId::unique_id()
             } else {
@@ -128,7 +143,7 @@ impl Exchange {
             );
 
             let props = OrderSimpleProps::new(
-                time_manager::now(),
+                self.clock.now(),
                 None,
                 Some(order_info.exchange_order_id.clone()),
                 order_info.order_status,
@@ -150,7 +165,7 @@ impl Exchange {
 
             self.orders
                 .cache_by_exchange_id
-                .insert(order_info.exchange_order_id.clone(), new_order);
+                .insert(order_info.exchange_order_id.clone(), new_order.clone());
 
             log::trace!(
                 "Added open order {} {} on {}",
@@ -158,6 +173,10 @@ impl Exchange {
                 order_info.exchange_order_id,
                 self.exchange_account_id,
             );
+
+            added_orders.push(new_order);
         }
+
+        added_orders
     }
 }
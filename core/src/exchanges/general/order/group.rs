@@ -0,0 +1,712 @@
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::timeouts::order_rate_limiter::OrderRequestKind;
+use crate::exchanges::traits::ExchangeError;
+use crate::infrastructure::spawn_future;
+use anyhow::{bail, Result};
+use futures::future::join_all;
+use itertools::Itertools;
+use mmb_domain::events::{EventSourceType, ExchangeEvent};
+use mmb_domain::order::event::{OrderEvent, OrderEventType};
+use mmb_domain::order::group::{
+    ExchangeOrderGroupId, OrderGroupEvent, OrderGroupEventType, OrderGroupId, OrderGroupRoute,
+    OrderGroupType,
+};
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{ClientOrderId, ExchangeOrderId, OrderHeader};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use rust_decimal_macros::dec;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Result of order group creation through conditional/OCO endpoint of exchange
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CreateOrderGroupResult {
+    pub exchange_group_id: ExchangeOrderGroupId,
+    pub exchange_order_ids: Vec<(ClientOrderId, ExchangeOrderId)>,
+}
+
+/// Orders managed by engine as a whole, e.g. OCO pair of take profit and stop loss
+pub struct OrderGroup {
+    pub id: OrderGroupId,
+    pub group_type: OrderGroupType,
+    pub route: OrderGroupRoute,
+    pub legs: Vec<OrderRef>,
+    is_legs_cancellation_started: AtomicBool,
+}
+
+impl OrderGroup {
+    fn new(
+        id: OrderGroupId,
+        group_type: OrderGroupType,
+        route: OrderGroupRoute,
+        legs: Vec<OrderRef>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            id,
+            group_type,
+            route,
+            legs,
+            is_legs_cancellation_started: AtomicBool::new(false),
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.legs.iter().all(|x| x.is_finished())
+    }
+
+    fn is_any_leg_filled(&self) -> bool {
+        self.legs.iter().any(|x| x.filled_amount() > dec!(0))
+    }
+}
+
+impl Exchange {
+    /// Creates OCO order group: fill of any leg cancels the other one.
+    /// If exchange supports the legs as native OCO order, the group is created through that endpoint
+    /// and linked by exchange. Otherwise legs are created as separate orders and linked by engine
+    pub async fn create_oco_order(
+        self: &Arc<Self>,
+        legs: &[OrderHeader],
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<OrderGroup>> {
+        self.create_order_group(OrderGroupType::Oco, legs, cancellation_token)
+            .await
+    }
+
+    pub async fn create_order_group(
+        self: &Arc<Self>,
+        group_type: OrderGroupType,
+        legs: &[OrderHeader],
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<OrderGroup>> {
+        let group_id = OrderGroupId::generate();
+        self.validate_order_group(group_id, legs)?;
+
+        let group = match self
+            .exchange_client
+            .supports_native_order_group(group_type, legs)
+        {
+            true => {
+                self.create_native_order_group(group_id, group_type, legs, cancellation_token)
+                    .await?
+            }
+            false => {
+                self.create_local_order_group(group_id, group_type, legs, cancellation_token)
+                    .await?
+            }
+        };
+
+        for leg in &group.legs {
+            let _ = self
+                .order_group_by_leg
+                .insert(leg.client_order_id(), group_id);
+        }
+        let _ = self.order_groups.insert(group_id, group.clone());
+
+        log::info!(
+            "Order group {group_id} {group_type:?} was created on {} by route {:?} with legs {:?}",
+            self.exchange_account_id,
+            group.route,
+            group.legs.iter().map(|x| x.client_order_id()).collect_vec(),
+        );
+        self.send_order_group_event(&group, OrderGroupEventType::Created);
+
+        // legs could be changed before the group was registered
+        self.handle_order_group_changes(&group);
+
+        Ok(group)
+    }
+
+    pub fn get_order_group(&self, group_id: OrderGroupId) -> Option<Arc<OrderGroup>> {
+        self.order_groups.get(&group_id).map(|x| x.value().clone())
+    }
+
+    /// Cancels all unfinished legs of the order group
+    pub async fn cancel_order_group(
+        &self,
+        group_id: OrderGroupId,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let group = match self.get_order_group(group_id) {
+            Some(group) => group,
+            None => bail!(
+                "Order group {group_id} not found on {}",
+                self.exchange_account_id
+            ),
+        };
+
+        cancel_legs(self, &group.legs, cancellation_token).await;
+        Ok(())
+    }
+
+    fn validate_order_group(&self, group_id: OrderGroupId, legs: &[OrderHeader]) -> Result<()> {
+        if legs.len() < 2 {
+            bail!("Order group {group_id} should contain at least 2 legs");
+        }
+
+        if self.is_watch_only() {
+            bail!(
+                "Unable to create order group {group_id} because exchange account {} is watch-only",
+                self.exchange_account_id
+            );
+        }
+
+        if self.is_trading_halted() {
+            bail!(
+                "Unable to create order group {group_id} because trading is halted by kill switch"
+            );
+        }
+
+        let currency_pair = legs[0].currency_pair;
+        for leg in legs {
+            if leg.exchange_account_id != self.exchange_account_id
+                || leg.currency_pair != currency_pair
+            {
+                bail!(
+                    "Legs of order group {group_id} should be on the same exchange account {} and currency pair {currency_pair}, but got {} {} for order {}",
+                    self.exchange_account_id,
+                    leg.exchange_account_id,
+                    leg.currency_pair,
+                    leg.client_order_id,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn create_native_order_group(
+        &self,
+        group_id: OrderGroupId,
+        group_type: OrderGroupType,
+        headers: &[OrderHeader],
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<OrderGroup>> {
+        log::info!("Submitting order group {group_id} {group_type:?} with legs {headers:?}");
+
+        // legs of OCO group use the same funds, so balance is reserved only for the first one
+        let reservation = self.reserve_order_balance(&headers[0]);
+        let legs = headers
+            .iter()
+            .enumerate()
+            .map(|(index, header)| {
+                let header = match (index, &reservation) {
+                    (0, Ok(Some(reservation_id))) => Cow::Owned(OrderHeader {
+                        reservation_id: Some(*reservation_id),
+                        ..header.clone()
+                    }),
+                    _ => Cow::Borrowed(header),
+                };
+
+                self.orders.add_simple_initial(
+                    &header,
+                    self.clock.now(),
+                    self.exchange_client.get_initial_extension_data(),
+                )
+            })
+            .collect_vec();
+
+        if let Err(reason) = reservation {
+            let message = format!("Order group {group_id} is rejected by balance: {reason}");
+            let _ = self.reject_by_balance(&legs[0], reason);
+            self.fail_order_group_legs(&legs, &ExchangeError::unknown(&message));
+            bail!(message)
+        }
+
+        // reservation made above is released on RejectedByRisk event
+        for leg in &legs {
+            if let Err(violation) = self.check_risk(leg.header()) {
+                let message =
+                    format!("Order group {group_id} is rejected by risk manager: {violation}");
+                // rejection error is the same as the group one
+                let _ = self.reject_by_risk(leg, violation);
+                self.fail_order_group_legs(&legs, &ExchangeError::unknown(&message));
+                bail!(message)
+            }
+        }
+
+        // exchanges count every leg of group as separate order
+        for _ in &legs {
+            if let Err(error) = self
+                .timeout_manager
+                .acquire_order_request(
+                    self.exchange_account_id,
+                    OrderRequestKind::Create,
+                    cancellation_token.clone(),
+                )
+                .await
+            {
+                let message = format!("Order group {group_id} isn't submitted: {error:?}");
+                self.fail_order_group_legs(&legs, &ExchangeError::unknown(&message));
+                bail!(message)
+            }
+        }
+
+        match self
+            .exchange_client
+            .create_order_group(group_id, group_type, &legs)
+            .await
+        {
+            Ok(result) => {
+                for (client_order_id, exchange_order_id) in &result.exchange_order_ids {
+                    self.handle_create_order_succeeded(
+                        self.exchange_account_id,
+                        client_order_id,
+                        exchange_order_id,
+                        EventSourceType::Rest,
+                    )?;
+                }
+
+                let route = OrderGroupRoute::Native {
+                    exchange_group_id: result.exchange_group_id,
+                };
+                Ok(OrderGroup::new(group_id, group_type, route, legs))
+            }
+            Err(error) => {
+                self.fail_order_group_legs(&legs, &error);
+                bail!("Failed to create order group {group_id}: {error}")
+            }
+        }
+    }
+
+    fn fail_order_group_legs(&self, legs: &[OrderRef], error: &ExchangeError) {
+        for leg in legs.iter().filter(|x| !x.is_finished()) {
+            self.handle_create_order_failed(&leg.client_order_id(), error, EventSourceType::Rest)
+                .unwrap_or_else(|err| {
+                    log::error!("Failed to handle creation failure of order group leg: {err:?}")
+                });
+        }
+    }
+
+    async fn create_local_order_group(
+        &self,
+        group_id: OrderGroupId,
+        group_type: OrderGroupType,
+        headers: &[OrderHeader],
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<OrderGroup>> {
+        let mut legs = Vec::with_capacity(headers.len());
+        for (index, header) in headers.iter().enumerate() {
+            // legs of OCO group use the same funds, so balance is reserved only for the first one
+            let reserve_balance = index == 0;
+            match self
+                .create_order_with_balance_check(
+                    header,
+                    None,
+                    reserve_balance,
+                    cancellation_token.clone(),
+                )
+                .await
+            {
+                Ok(leg) => legs.push(leg),
+                Err(error) => {
+                    log::warn!("Cancelling created legs of order group {group_id} because creation of order {} failed", header.client_order_id);
+                    cancel_legs(self, &legs, cancellation_token).await;
+                    bail!("Failed to create order group {group_id}: {error:?}")
+                }
+            }
+        }
+
+        Ok(OrderGroup::new(
+            group_id,
+            group_type,
+            OrderGroupRoute::Local,
+            legs,
+        ))
+    }
+
+    /// Translates changes of group legs into order group events
+    pub(crate) fn handle_order_group_leg_event(self: &Arc<Self>, order_event: &OrderEvent) {
+        let client_order_id = order_event.order.client_order_id();
+        let group = match self
+            .order_group_by_leg
+            .get(&client_order_id)
+            .and_then(|group_id| self.get_order_group(*group_id))
+        {
+            Some(group) => group,
+            None => return,
+        };
+
+        if let OrderEventType::OrderFilled { .. } = order_event.event_type {
+            self.send_order_group_event(&group, OrderGroupEventType::LegFilled { client_order_id });
+        }
+
+        self.handle_order_group_changes(&group);
+    }
+
+    fn handle_order_group_changes(self: &Arc<Self>, group: &Arc<OrderGroup>) {
+        if group.is_finished() {
+            if self.order_groups.remove(&group.id).is_none() {
+                // group is already finished by another event
+                return;
+            }
+
+            for leg in &group.legs {
+                let _ = self.order_group_by_leg.remove(&leg.client_order_id());
+            }
+
+            let event_type = match group.is_any_leg_filled() {
+                true => OrderGroupEventType::Completed,
+                false => OrderGroupEventType::Canceled,
+            };
+            self.send_order_group_event(group, event_type);
+            return;
+        }
+
+        // native groups are maintained by exchange
+        let is_local_oco =
+            group.group_type == OrderGroupType::Oco && group.route == OrderGroupRoute::Local;
+        if is_local_oco
+            && group.is_any_leg_filled()
+            && !group
+                .is_legs_cancellation_started
+                .swap(true, Ordering::AcqRel)
+        {
+            log::info!(
+                "Cancelling other legs of OCO order group {} because one of legs was filled",
+                group.id
+            );
+
+            let exchange = self.clone();
+            let group = group.clone();
+            spawn_future(
+                &format!("Cancel legs of order group {}", group.id),
+                SpawnFutureFlags::STOP_BY_TOKEN,
+                async move {
+                    let legs = group
+                        .legs
+                        .iter()
+                        .filter(|x| x.filled_amount() == dec!(0))
+                        .cloned()
+                        .collect_vec();
+                    cancel_legs(&exchange, &legs, CancellationToken::default()).await;
+                    Ok(())
+                },
+            );
+        }
+    }
+
+    fn send_order_group_event(&self, group: &OrderGroup, event_type: OrderGroupEventType) {
+        let event = ExchangeEvent::OrderGroupEvent(OrderGroupEvent {
+            exchange_account_id: self.exchange_account_id,
+            group_id: group.id,
+            group_type: group.group_type,
+            route: group.route.clone(),
+            event_type,
+        });
+
+        if let Err(error) = self.events_channel.send(event) {
+            log::error!(
+                "Unable to send event of order group {}. Probably receiver is already dropped: {error:?}",
+                group.id
+            );
+        }
+    }
+}
+
+async fn cancel_legs(
+    exchange: &Exchange,
+    legs: &[OrderRef],
+    cancellation_token: CancellationToken,
+) {
+    let futures = legs.iter().filter(|x| !x.is_finished()).map(|leg| {
+        let cancellation_token = cancellation_token.clone();
+        async move {
+            if let Err(error) = exchange
+                .wait_cancel_order(leg.clone(), None, true, cancellation_token)
+                .await
+            {
+                log::error!(
+                    "Failed to cancel order group leg {}: {error:?}",
+                    leg.client_order_id()
+                );
+            }
+        }
+    });
+
+    join_all(futures).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::manager::balance_manager::BalanceManager;
+    use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+    use crate::exchanges::general::test_helper;
+    use crate::infrastructure::init_lifetime_manager;
+    use crate::misc::time;
+    use chrono::Utc;
+    use mmb_domain::events::{ExchangeBalance, ExchangeBalancesAndPositions};
+    use mmb_domain::exchanges::symbol::{Precision, Symbol};
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::snapshot::{Amount, OrderSide, OrderStatus, UserOrder};
+    use mmb_utils::hashmap;
+    use parking_lot::Mutex;
+    use tokio::sync::broadcast;
+
+    fn add_native_group(exchange: &Exchange) -> Arc<OrderGroup> {
+        let legs = (0..2)
+            .map(|_| {
+                test_helper::create_order_ref(
+                    &ClientOrderId::unique_id(),
+                    None,
+                    exchange.exchange_account_id,
+                    CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+                    dec!(0.8),
+                    dec!(12),
+                    OrderSide::Sell,
+                )
+            })
+            .collect_vec();
+
+        let route = OrderGroupRoute::Native {
+            exchange_group_id: "7".into(),
+        };
+        let group = OrderGroup::new(OrderGroupId::generate(), OrderGroupType::Oco, route, legs);
+        for leg in &group.legs {
+            let _ = exchange
+                .order_group_by_leg
+                .insert(leg.client_order_id(), group.id);
+        }
+        let _ = exchange.order_groups.insert(group.id, group.clone());
+
+        group
+    }
+
+    fn cancel_leg(exchange: &Arc<Exchange>, leg: &OrderRef) {
+        leg.fn_mut(|x| x.set_status(OrderStatus::Canceled, Utc::now()));
+        exchange.handle_order_group_leg_event(&OrderEvent::new(
+            leg.clone(),
+            OrderEventType::CancelOrderSucceeded,
+        ));
+    }
+
+    fn try_recv_group_event(
+        receiver: &mut broadcast::Receiver<ExchangeEvent>,
+    ) -> Option<OrderGroupEvent> {
+        match receiver.try_recv() {
+            Ok(ExchangeEvent::OrderGroupEvent(event)) => Some(event),
+            Ok(_) => panic!("Should be OrderGroupEvent"),
+            Err(_) => None,
+        }
+    }
+
+    fn oco_legs(exchange: &Exchange, amount: Amount) -> Vec<OrderHeader> {
+        [UserOrder::limit(dec!(0.2)), UserOrder::limit(dec!(0.1))]
+            .into_iter()
+            .map(|user_order| {
+                OrderHeader::with_user_order(
+                    ClientOrderId::unique_id(),
+                    exchange.exchange_account_id,
+                    CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+                    OrderSide::Buy,
+                    amount,
+                    user_order,
+                    None,
+                    None,
+                    "StrategyInUnitTests".to_owned(),
+                )
+            })
+            .collect_vec()
+    }
+
+    fn native_group_result(legs: &[OrderHeader]) -> CreateOrderGroupResult {
+        CreateOrderGroupResult {
+            exchange_group_id: "7".into(),
+            exchange_order_ids: legs
+                .iter()
+                .enumerate()
+                .map(|(index, x)| {
+                    (
+                        x.client_order_id.clone(),
+                        format!("7-{index}").as_str().into(),
+                    )
+                })
+                .collect_vec(),
+        }
+    }
+
+    /// Balance manager should be kept alive because exchange holds weak reference to it
+    fn get_exchange_with_btc_balance(
+        balance: Amount,
+    ) -> (
+        Arc<Exchange>,
+        Arc<Mutex<BalanceManager>>,
+        broadcast::Receiver<ExchangeEvent>,
+    ) {
+        let symbol = Arc::new(Symbol::new(
+            false,
+            "PHB".into(),
+            "PHB".into(),
+            "BTC".into(),
+            "BTC".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "PHB".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(1) },
+        ));
+        let (exchange, receiver) = test_helper::get_test_exchange_with_symbol(symbol);
+
+        let exchange_account_id = exchange.exchange_account_id;
+        let converter =
+            CurrencyPairToSymbolConverter::new(hashmap![exchange_account_id => exchange.clone()]);
+        let balance_manager = BalanceManager::new(converter, None);
+        exchange.setup_balance_manager(balance_manager.clone());
+
+        balance_manager
+            .lock()
+            .update_exchange_balance(
+                exchange_account_id,
+                &ExchangeBalancesAndPositions {
+                    balances: vec![ExchangeBalance {
+                        currency_code: "BTC".into(),
+                        balance,
+                    }],
+                    positions: None,
+                },
+            )
+            .expect("in test");
+
+        (exchange, balance_manager, receiver)
+    }
+
+    fn leg_statuses(exchange: &Exchange, legs: &[OrderHeader]) -> Vec<OrderStatus> {
+        legs.iter()
+            .map(|x| {
+                exchange
+                    .orders
+                    .cache_by_client_id
+                    .get(&x.client_order_id)
+                    .expect("in test")
+                    .status()
+            })
+            .collect_vec()
+    }
+
+    #[tokio::test]
+    async fn create_native_group() {
+        let _ = init_lifetime_manager();
+        // reservation of balance manager is stamped by time manager
+        let (_time_manager_mock, _time_manager_locker) = time::tests::init_mock(Default::default());
+        let (exchange, _balance_manager, _receiver) = get_exchange_with_btc_balance(dec!(1));
+        let legs = oco_legs(&exchange, dec!(2));
+        *test_helper::test_client(&exchange)
+            .order_group_result
+            .lock() = Some(Ok(native_group_result(&legs)));
+
+        let group = exchange
+            .create_oco_order(&legs, CancellationToken::default())
+            .await
+            .expect("in test");
+
+        assert_eq!(
+            group.route,
+            OrderGroupRoute::Native {
+                exchange_group_id: "7".into()
+            }
+        );
+        assert_eq!(
+            group
+                .legs
+                .iter()
+                .map(|x| x.exchange_order_id())
+                .collect_vec(),
+            vec![Some("7-0".into()), Some("7-1".into())]
+        );
+        assert_eq!(
+            leg_statuses(&exchange, &legs),
+            vec![OrderStatus::Created, OrderStatus::Created]
+        );
+        // legs use the same funds, so only the first one is reserved
+        assert!(group.legs[0].header().reservation_id.is_some());
+        assert!(group.legs[1].header().reservation_id.is_none());
+        assert!(exchange.get_order_group(group.id).is_some());
+    }
+
+    #[tokio::test]
+    async fn native_group_rejected_by_exchange() {
+        let _ = init_lifetime_manager();
+        let (exchange, _receiver) = test_helper::get_test_exchange(false);
+        let legs = oco_legs(&exchange, dec!(2));
+        *test_helper::test_client(&exchange)
+            .order_group_result
+            .lock() = Some(Err(ExchangeError::unknown("rejected in test")));
+
+        let result = exchange
+            .create_oco_order(&legs, CancellationToken::default())
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            leg_statuses(&exchange, &legs),
+            vec![OrderStatus::FailedToCreate, OrderStatus::FailedToCreate]
+        );
+        assert!(exchange.order_groups.is_empty());
+        assert!(exchange.order_group_by_leg.is_empty());
+    }
+
+    #[tokio::test]
+    async fn native_group_rejected_by_balance() {
+        let _ = init_lifetime_manager();
+        let (exchange, _balance_manager, _receiver) = get_exchange_with_btc_balance(dec!(1));
+        // 2 BTC is required for the first leg
+        let legs = oco_legs(&exchange, dec!(10));
+        *test_helper::test_client(&exchange)
+            .order_group_result
+            .lock() = Some(Ok(native_group_result(&legs)));
+
+        let result = exchange
+            .create_oco_order(&legs, CancellationToken::default())
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            leg_statuses(&exchange, &legs),
+            vec![OrderStatus::FailedToCreate, OrderStatus::FailedToCreate]
+        );
+        // group isn't submitted to exchange
+        assert!(test_helper::test_client(&exchange)
+            .order_group_result
+            .lock()
+            .is_some());
+        assert!(exchange.order_groups.is_empty());
+    }
+
+    #[tokio::test]
+    async fn group_is_canceled_when_all_legs_canceled() {
+        let _ = init_lifetime_manager();
+        let (exchange, mut receiver) = test_helper::get_test_exchange(false);
+        let group = add_native_group(&exchange);
+
+        cancel_leg(&exchange, &group.legs[0]);
+        assert!(try_recv_group_event(&mut receiver).is_none());
+        assert!(exchange.get_order_group(group.id).is_some());
+
+        cancel_leg(&exchange, &group.legs[1]);
+        let event = try_recv_group_event(&mut receiver).expect("in test");
+        assert_eq!(event.group_id, group.id);
+        assert_eq!(event.event_type, OrderGroupEventType::Canceled);
+        assert!(exchange.get_order_group(group.id).is_none());
+        assert!(exchange.order_group_by_leg.is_empty());
+    }
+
+    #[tokio::test]
+    async fn events_of_finished_group_are_ignored() {
+        let _ = init_lifetime_manager();
+        let (exchange, mut receiver) = test_helper::get_test_exchange(false);
+        let group = add_native_group(&exchange);
+
+        cancel_leg(&exchange, &group.legs[0]);
+        cancel_leg(&exchange, &group.legs[1]);
+        cancel_leg(&exchange, &group.legs[1]);
+
+        assert!(try_recv_group_event(&mut receiver).is_some());
+        assert!(try_recv_group_event(&mut receiver).is_none());
+    }
+}
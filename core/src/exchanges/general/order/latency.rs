@@ -0,0 +1,107 @@
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::timeouts::order_rate_limiter::OrderRequestKind;
+use chrono::Duration;
+use mmb_utils::DateTime;
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+/// Max count of stored latencies of every kind of order request
+const MAX_LATENCY_SAMPLES: usize = 100;
+
+/// Round-trip latencies of recent order requests of exchange account
+#[derive(Debug, Default)]
+pub(crate) struct OrderLatencies {
+    samples: HashMap<OrderRequestKind, VecDeque<(DateTime, Duration)>>,
+}
+
+impl OrderLatencies {
+    fn register(&mut self, kind: OrderRequestKind, time: DateTime, latency: Duration) {
+        let samples = self.samples.entry(kind).or_default();
+        samples.push_back((time, latency));
+        if samples.len() > MAX_LATENCY_SAMPLES {
+            let _ = samples.pop_front();
+        }
+    }
+
+    /// Median latency of requests of `kind` completed within window ending at `now`
+    fn median(&self, kind: OrderRequestKind, now: DateTime, window: Duration) -> Option<Duration> {
+        let window_start = now - window;
+        let mut latencies: Vec<Duration> = self
+            .samples
+            .get(&kind)?
+            .iter()
+            .filter(|(time, _)| *time > window_start)
+            .map(|(_, latency)| *latency)
+            .collect();
+        if latencies.is_empty() {
+            return None;
+        }
+
+        latencies.sort();
+        Some(latencies[latencies.len() / 2])
+    }
+}
+
+impl Exchange {
+    /// Registers round-trip latency of order request started at `started`
+    pub(crate) fn register_order_latency(&self, kind: OrderRequestKind, started: Instant) {
+        let latency = match Duration::from_std(started.elapsed()) {
+            Ok(latency) => latency,
+            Err(_) => return,
+        };
+        self.order_latencies
+            .lock()
+            .register(kind, self.clock.now(), latency);
+    }
+
+    /// Median round-trip latency of order requests of `kind` completed within window ending at
+    /// `now`. It's `None` if there are no such requests
+    pub fn order_latency(
+        &self,
+        kind: OrderRequestKind,
+        now: DateTime,
+        window: Duration,
+    ) -> Option<Duration> {
+        self.order_latencies.lock().median(kind, now, window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn median_latency_within_window() {
+        let mut latencies = OrderLatencies::default();
+        let start = Utc::now();
+        let window = Duration::seconds(60);
+        let create = OrderRequestKind::Create;
+
+        for (secs, millis) in [(0, 900), (30, 100), (40, 300), (50, 200)] {
+            latencies.register(
+                create,
+                start + Duration::seconds(secs),
+                Duration::milliseconds(millis),
+            );
+        }
+
+        assert_eq!(
+            latencies.median(create, start + Duration::seconds(50), window),
+            Some(Duration::milliseconds(300))
+        );
+        // the slowest request is outdated
+        assert_eq!(
+            latencies.median(create, start + Duration::seconds(60), window),
+            Some(Duration::milliseconds(200))
+        );
+        assert_eq!(
+            latencies.median(OrderRequestKind::Cancel, start, window),
+            None
+        );
+        assert_eq!(
+            latencies.median(create, start + Duration::seconds(120), window),
+            None
+        );
+    }
+}
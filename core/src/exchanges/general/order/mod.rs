@@ -1,8 +1,29 @@
+pub mod archive;
 pub mod cancel;
 pub mod create;
 pub mod create_websocket_based;
+pub mod external;
 pub mod get_info;
 pub mod get_open_orders;
 pub mod get_order_trades;
+pub mod group;
+pub mod latency;
+pub mod reconcile_open_orders;
+pub mod reservation;
+pub mod stuck;
+pub mod validation;
 pub mod wait_cancel;
 pub mod wait_finish;
+pub mod wait_status;
+
+use mmb_domain::order::pool::OrderRef;
+
+/// Fills `exchange_order_id` field of current order span when exchange order id is already known
+pub(crate) fn record_exchange_order_id(order: &OrderRef) {
+    if let Some(exchange_order_id) = order.exchange_order_id() {
+        tracing::Span::current().record(
+            "exchange_order_id",
+            tracing::field::display(exchange_order_id),
+        );
+    }
+}
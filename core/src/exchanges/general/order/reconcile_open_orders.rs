@@ -0,0 +1,475 @@
+use crate::alerting::{alert, AlertKind, AlertSeverity};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::features::OpenOrdersType;
+use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::sequence_tracker::SequenceGap;
+use crate::infrastructure::spawn_future;
+use anyhow::Result;
+use itertools::Itertools;
+use mmb_domain::events::{AllowedEventSourceType, EventSourceType};
+use mmb_domain::order::pool::{OrderRef, OrdersFilter};
+use mmb_domain::order::snapshot::{
+    ClientOrderId, ExchangeOrderId, OrderInfo, OrderStatus, OrderType,
+};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+const FILLS_PENDING_RECHECK_DELAY: Duration = Duration::from_secs(5);
+
+/// Changes of orders pool made by open orders reconciliation
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OpenOrdersReconciliation {
+    /// Open orders on exchange which weren't known by engine and were added to orders pool
+    pub adopted: Vec<ExchangeOrderId>,
+    /// Orders which were open in orders pool, but weren't found in open orders on exchange
+    pub finished: Vec<ClientOrderId>,
+    /// Open orders which fills were requested because exchange reported bigger filled amount
+    pub fills_checked: Vec<ClientOrderId>,
+    /// Orders which weren't found in open orders on exchange, but aren't finished because their
+    /// trades aren't available on exchange yet
+    pub fills_pending: Vec<ClientOrderId>,
+}
+
+impl Exchange {
+    /// Brings orders pool in line with open orders on exchange after restart or reconnection:
+    /// adopts unknown open orders, replays missed fills and finishes orders which aren't open anymore
+    pub async fn reconcile_open_orders(
+        &self,
+        cancellation_token: CancellationToken,
+    ) -> Result<OpenOrdersReconciliation> {
+        // orders created after the request can be missing in response, so only orders
+        // which were already created before the request are checked
+        let created_orders = self.orders.find_not_finished(&OrdersFilter {
+            statuses: vec![OrderStatus::Created],
+            ..Default::default()
+        });
+
+        let open_orders = self.get_open_orders(false).await?;
+
+        let mut result = OpenOrdersReconciliation::default();
+
+        let (known_orders, unknown_orders): (Vec<_>, Vec<_>) = open_orders
+            .iter()
+            .map(|order_info| (order_info, self.find_open_order(order_info)))
+            .partition(|(_, order)| order.is_some());
+
+        let unknown_orders = unknown_orders
+            .into_iter()
+            .map(|(order_info, _)| order_info.clone())
+            .collect_vec();
+        let adopted_orders = self.add_missing_open_orders(&unknown_orders);
+        result.adopted = unknown_orders
+            .iter()
+            .map(|x| x.exchange_order_id.clone())
+            .collect();
+
+        // fills of adopted external orders made before adoption are requested too
+        let adopted_external_orders = unknown_orders
+            .iter()
+            .zip(adopted_orders)
+            .filter(|(_, order)| order.order_type() == OrderType::Manual);
+        let known_orders = known_orders
+            .into_iter()
+            .map(|(order_info, order)| (order_info, order.expect("order is known")))
+            .chain(adopted_external_orders);
+
+        for (order_info, order) in known_orders {
+            if order_info.filled_amount > order.filled_amount() {
+                self.check_order_fills(&order, false, None, cancellation_token.clone())
+                    .await?;
+                result.fills_checked.push(order.client_order_id());
+            }
+        }
+
+        let open_exchange_order_ids: HashSet<_> =
+            open_orders.iter().map(|x| &x.exchange_order_id).collect();
+        for order in created_orders {
+            let exchange_order_id = match order.exchange_order_id() {
+                Some(exchange_order_id) => exchange_order_id,
+                None => continue,
+            };
+
+            if order.is_finished() || open_exchange_order_ids.contains(&exchange_order_id) {
+                continue;
+            }
+
+            self.check_order_fills(&order, false, None, cancellation_token.clone())
+                .await?;
+
+            // trades can appear on exchange with delay after order disappeared from open orders,
+            // so order isn't finished until all its fills are received
+            if !order.is_finished()
+                && self
+                    .has_unreceived_fills(&order, cancellation_token.clone())
+                    .await?
+            {
+                result.fills_pending.push(order.client_order_id());
+                continue;
+            }
+
+            // order isn't open on exchange and isn't completely filled, so it was canceled
+            if !order.is_finished() {
+                self.handle_cancel_order_succeeded(
+                    Some(&order.client_order_id()),
+                    &exchange_order_id,
+                    Some(order.filled_amount()),
+                    self.reconciliation_event_source_type(),
+                );
+            }
+
+            result.finished.push(order.client_order_id());
+        }
+
+        Ok(result)
+    }
+
+    /// Starts open orders reconciliation in background if exchange supports open orders requests
+    pub(crate) fn spawn_open_orders_reconciliation(self: &Arc<Self>) {
+        if self.is_watch_only() || matches!(self.features.open_orders_type, OpenOrdersType::None) {
+            return;
+        }
+
+        let exchange = self.clone();
+        let action = format!("Reconcile open orders on {}", self.exchange_account_id);
+        spawn_future(&action, SpawnFutureFlags::STOP_BY_TOKEN, async move {
+            let cancellation_token = exchange.lifetime_manager.stop_token();
+            match exchange.reconcile_open_orders(cancellation_token).await {
+                Ok(result) => {
+                    let exchange_account_id = exchange.exchange_account_id;
+                    log::info!("Open orders on {exchange_account_id} are reconciled: {result:?}");
+                    if !result.fills_pending.is_empty() {
+                        exchange.schedule_open_orders_reconciliation();
+                    }
+                    if !result.adopted.is_empty()
+                        || !result.finished.is_empty()
+                        || !result.fills_pending.is_empty()
+                    {
+                        alert(
+                            AlertSeverity::Warning,
+                            AlertKind::ReconciliationMismatch,
+                            &exchange_account_id.to_string(),
+                            format!(
+                                "Open orders on {exchange_account_id} differ from exchange ones: unknown orders {:?} are adopted, orders {:?} aren't open on exchange, fills of orders {:?} aren't available yet",
+                                result.adopted, result.finished, result.fills_pending
+                            ),
+                        );
+                    }
+                }
+                Err(err) => log::error!(
+                    "Failed to reconcile open orders on {}: {err:?}",
+                    exchange.exchange_account_id
+                ),
+            }
+
+            Ok(())
+        });
+    }
+
+    /// Orders with fills which aren't available yet are checked again after delay instead of
+    /// waiting for the next reconnection
+    fn schedule_open_orders_reconciliation(self: &Arc<Self>) {
+        let exchange = self.clone();
+        let action = format!(
+            "Schedule open orders reconciliation on {}",
+            self.exchange_account_id
+        );
+        spawn_future(&action, SpawnFutureFlags::STOP_BY_TOKEN, async move {
+            exchange.clock.sleep(FILLS_PENDING_RECHECK_DELAY).await;
+            exchange.spawn_open_orders_reconciliation();
+            Ok(())
+        });
+    }
+
+    /// Updates of private stream were missed, so open orders and their fills are requested
+    /// instead of waiting for cancellation to find missed fills
+    pub(crate) fn handle_sequence_gap(self: &Arc<Self>, gap: SequenceGap) {
+        log::warn!(
+            "Updates {}..{} of stream {} on {} are missed, reconciling open orders",
+            gap.expected,
+            gap.received,
+            gap.stream,
+            self.exchange_account_id
+        );
+
+        self.spawn_open_orders_reconciliation();
+    }
+
+    /// Order can't be finished if its filled amount can't be checked, because fills can be lost
+    async fn has_unreceived_fills(
+        &self,
+        order: &OrderRef,
+        cancellation_token: CancellationToken,
+    ) -> Result<bool> {
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::GetOrderInfo,
+                None,
+                cancellation_token,
+            )
+            .await
+            .into_result()?;
+
+        match self.get_order_info(order).await {
+            Ok(order_info) => Ok(order_info.filled_amount > order.filled_amount()),
+            Err(error) => {
+                log::warn!(
+                    "Failed to get info of order {} missing in open orders on {}: {error:?}",
+                    order.client_order_id(),
+                    self.exchange_account_id
+                );
+                Ok(true)
+            }
+        }
+    }
+
+    fn find_open_order(&self, order_info: &OrderInfo) -> Option<OrderRef> {
+        let by_client_order_id = (!order_info.client_order_id.as_str().is_empty())
+            .then(|| {
+                self.orders
+                    .cache_by_client_id
+                    .get(&order_info.client_order_id)
+            })
+            .flatten()
+            .map(|x| x.value().clone());
+
+        by_client_order_id.or_else(|| {
+            self.orders
+                .cache_by_exchange_id
+                .get(&order_info.exchange_order_id)
+                .map(|x| x.value().clone())
+        })
+    }
+
+    pub(super) fn reconciliation_event_source_type(&self) -> EventSourceType {
+        match self.features.allowed_cancel_event_source_type {
+            AllowedEventSourceType::FallbackOnly => EventSourceType::RestFallback,
+            AllowedEventSourceType::All | AllowedEventSourceType::NonFallback => {
+                EventSourceType::Rest
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::features::RestFillsType;
+    use crate::exchanges::general::order::get_order_trades::OrderTrade;
+    use crate::exchanges::general::test_helper::{
+        add_created_order, get_test_exchange_with_rest_fills, test_client,
+    };
+    use crate::infrastructure::init_lifetime_manager;
+    use chrono::Utc;
+    use mmb_domain::events::TradeId;
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::fill::OrderFillType;
+    use mmb_domain::order::snapshot::{Amount, OrderRole, OrderSide};
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn order_info(
+        exchange_order_id: &ExchangeOrderId,
+        client_order_id: ClientOrderId,
+        order_status: OrderStatus,
+        filled_amount: Decimal,
+    ) -> OrderInfo {
+        OrderInfo::new(
+            CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+            exchange_order_id.clone(),
+            client_order_id,
+            OrderSide::Buy,
+            order_status,
+            dec!(0.2),
+            dec!(1),
+            dec!(0.2),
+            filled_amount,
+            None,
+            None,
+            None,
+        )
+    }
+
+    fn order_trade(
+        exchange_order_id: &ExchangeOrderId,
+        trade_id: u64,
+        amount: Amount,
+    ) -> OrderTrade {
+        OrderTrade::new(
+            exchange_order_id.clone(),
+            TradeId::Number(trade_id),
+            Utc::now(),
+            dec!(0.2),
+            amount,
+            OrderRole::Maker,
+            "BTC".into(),
+            None,
+            None,
+            OrderFillType::UserTrade,
+        )
+    }
+
+    #[tokio::test]
+    async fn unknown_open_order_is_adopted() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange_with_rest_fills(RestFillsType::MyTrades);
+
+        let exchange_order_id = ExchangeOrderId::from("unknown");
+        test_client(&exchange).open_orders.lock().push(order_info(
+            &exchange_order_id,
+            ClientOrderId::unique_id(),
+            OrderStatus::Created,
+            dec!(0),
+        ));
+
+        let result = exchange
+            .reconcile_open_orders(CancellationToken::default())
+            .await
+            .expect("in test");
+
+        assert_eq!(result.adopted, vec![exchange_order_id.clone()]);
+        assert!(result.finished.is_empty());
+        let order = exchange
+            .orders
+            .cache_by_exchange_id
+            .get(&exchange_order_id)
+            .map(|x| x.value().clone())
+            .expect("order should be adopted");
+        assert_eq!(order.status(), OrderStatus::Created);
+    }
+
+    #[tokio::test]
+    async fn missing_order_without_fills_is_canceled() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange_with_rest_fills(RestFillsType::MyTrades);
+
+        let exchange_order_id = ExchangeOrderId::from("missing");
+        let order = add_created_order(&exchange, &exchange_order_id, dec!(0.2), dec!(1));
+        test_client(&exchange).order_infos.lock().push(order_info(
+            &exchange_order_id,
+            order.client_order_id(),
+            OrderStatus::Canceled,
+            dec!(0),
+        ));
+
+        let result = exchange
+            .reconcile_open_orders(CancellationToken::default())
+            .await
+            .expect("in test");
+
+        assert_eq!(result.finished, vec![order.client_order_id()]);
+        assert!(result.fills_pending.is_empty());
+        assert_eq!(order.status(), OrderStatus::Canceled);
+    }
+
+    #[tokio::test]
+    async fn missed_fills_of_open_order_are_replayed() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange_with_rest_fills(RestFillsType::MyTrades);
+
+        let exchange_order_id = ExchangeOrderId::from("partially_filled");
+        let order = add_created_order(&exchange, &exchange_order_id, dec!(0.2), dec!(1));
+        let client = test_client(&exchange);
+        client.open_orders.lock().push(order_info(
+            &exchange_order_id,
+            order.client_order_id(),
+            OrderStatus::Created,
+            dec!(0.4),
+        ));
+        client
+            .my_trades
+            .lock()
+            .push(order_trade(&exchange_order_id, 1, dec!(0.4)));
+
+        let result = exchange
+            .reconcile_open_orders(CancellationToken::default())
+            .await
+            .expect("in test");
+
+        assert_eq!(result.fills_checked, vec![order.client_order_id()]);
+        assert!(result.finished.is_empty());
+        assert_eq!(order.filled_amount(), dec!(0.4));
+        assert_eq!(order.status(), OrderStatus::Created);
+    }
+
+    #[tokio::test]
+    async fn missing_filled_order_is_completed() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange_with_rest_fills(RestFillsType::MyTrades);
+
+        let exchange_order_id = ExchangeOrderId::from("filled");
+        let order = add_created_order(&exchange, &exchange_order_id, dec!(0.2), dec!(1));
+        test_client(&exchange).my_trades.lock().extend([
+            order_trade(&exchange_order_id, 1, dec!(0.4)),
+            order_trade(&exchange_order_id, 2, dec!(0.6)),
+        ]);
+
+        let result = exchange
+            .reconcile_open_orders(CancellationToken::default())
+            .await
+            .expect("in test");
+
+        assert_eq!(result.finished, vec![order.client_order_id()]);
+        assert_eq!(order.filled_amount(), dec!(1));
+        assert_eq!(order.status(), OrderStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn missing_order_isnt_finished_until_its_trades_are_available() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange_with_rest_fills(RestFillsType::MyTrades);
+
+        let exchange_order_id = ExchangeOrderId::from("filled");
+        let order = add_created_order(&exchange, &exchange_order_id, dec!(0.2), dec!(1));
+        let client = test_client(&exchange);
+        client.order_infos.lock().push(order_info(
+            &exchange_order_id,
+            order.client_order_id(),
+            OrderStatus::Completed,
+            dec!(1),
+        ));
+
+        let result = exchange
+            .reconcile_open_orders(CancellationToken::default())
+            .await
+            .expect("in test");
+
+        assert_eq!(result.fills_pending, vec![order.client_order_id()]);
+        assert!(result.finished.is_empty());
+        assert_eq!(order.status(), OrderStatus::Created);
+
+        client
+            .my_trades
+            .lock()
+            .push(order_trade(&exchange_order_id, 1, dec!(1)));
+
+        let result = exchange
+            .reconcile_open_orders(CancellationToken::default())
+            .await
+            .expect("in test");
+
+        assert_eq!(result.finished, vec![order.client_order_id()]);
+        assert!(result.fills_pending.is_empty());
+        assert_eq!(order.status(), OrderStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn missing_order_isnt_finished_if_its_info_isnt_received() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange_with_rest_fills(RestFillsType::MyTrades);
+
+        let order = add_created_order(&exchange, &"unavailable".into(), dec!(0.2), dec!(1));
+
+        let result = exchange
+            .reconcile_open_orders(CancellationToken::default())
+            .await
+            .expect("in test");
+
+        assert_eq!(result.fills_pending, vec![order.client_order_id()]);
+        assert!(result.finished.is_empty());
+        assert_eq!(order.status(), OrderStatus::Created);
+    }
+}
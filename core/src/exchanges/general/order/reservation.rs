@@ -0,0 +1,235 @@
+use crate::exchanges::general::exchange::Exchange;
+use crate::misc::reserve_parameters::ReserveParameters;
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use anyhow::{bail, Result};
+use mmb_domain::order::event::{OrderEvent, OrderEventType};
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{OrderHeader, OrderStatus, ReservationId};
+use mmb_utils::infrastructure::WithExpect;
+use std::sync::Weak;
+
+impl Exchange {
+    /// Reserves balance for order created without reservation, so order exceeding available funds
+    /// is rejected locally instead of by exchange. Returns error message if funds are insufficient.
+    /// Order isn't checked if balance of exchange account or price of order is unknown yet
+    pub(super) fn reserve_order_balance(
+        &self,
+        header: &OrderHeader,
+    ) -> Result<Option<ReservationId>, String> {
+        if header.reservation_id.is_some() || header.order_type.is_external_order() {
+            return Ok(None);
+        }
+
+        let balance_manager = match self.balance_manager.lock().as_ref().and_then(Weak::upgrade) {
+            None => return Ok(None),
+            Some(balance_manager) => balance_manager,
+        };
+
+        let currency_pair = header.currency_pair;
+        let symbol = match self.symbols.get(&currency_pair) {
+            None => return Ok(None),
+            Some(symbol) => symbol.clone(),
+        };
+
+        let price = match header
+            .source_price
+            .or_else(|| self.mid_price(currency_pair))
+        {
+            None => {
+                log::warn!(
+                    "Balance isn't reserved for order {} because its price is unknown",
+                    header.client_order_id
+                );
+                return Ok(None);
+            }
+            Some(price) => price,
+        };
+
+        let configuration_descriptor = ConfigurationDescriptor::new(
+            header.strategy_name.as_str().into(),
+            header.market_account_id().market_id().into(),
+        );
+        let reserve_parameters = ReserveParameters::new(
+            configuration_descriptor,
+            self.exchange_account_id,
+            symbol,
+            header.side,
+            price,
+            header.amount,
+        );
+
+        let mut balance_manager = balance_manager.lock();
+        if !balance_manager.balance_was_received(self.exchange_account_id) {
+            return Ok(None);
+        }
+
+        match balance_manager.try_reserve(&reserve_parameters, &mut None) {
+            Some(reservation_id) => {
+                let _ = self
+                    .balance_reserved_orders
+                    .insert(header.client_order_id.clone(), configuration_descriptor);
+                Ok(Some(reservation_id))
+            }
+            None => Err(format!(
+                "insufficient balance for {:?} {} {currency_pair} by price {price}",
+                header.side, header.amount
+            )),
+        }
+    }
+
+    pub(super) fn reject_by_balance(&self, order: &OrderRef, reason: String) -> Result<OrderRef> {
+        let client_order_id = order.client_order_id();
+        log::warn!("Order {client_order_id} is rejected locally: {reason}");
+
+        order.fn_mut(|x| {
+            x.set_status(OrderStatus::FailedToCreate, self.clock.now());
+            x.internal_props.last_creation_error_message = reason.clone();
+        });
+
+        self.add_event_on_order_change(order, OrderEventType::RejectedByBalance)?;
+
+        self.event_recorder
+            .save(&mut order.deep_clone())
+            .expect("Failure save order");
+
+        bail!("Order {client_order_id} is rejected locally: {reason}")
+    }
+
+    /// Applies fills to balance and releases reservation of orders reserved by `reserve_order_balance`.
+    /// Orders reserved by disposition executor are handled by executor itself
+    pub(crate) fn handle_order_reservation_event(&self, order_event: &OrderEvent) {
+        let order = &order_event.order;
+        let client_order_id = order.client_order_id();
+        let configuration_descriptor = match self.balance_reserved_orders.get(&client_order_id) {
+            None => return,
+            Some(x) => *x,
+        };
+
+        let balance_manager = match self.balance_manager.lock().as_ref().and_then(Weak::upgrade) {
+            None => return,
+            Some(balance_manager) => balance_manager,
+        };
+
+        match &order_event.event_type {
+            OrderEventType::OrderFilled { cloned_order } => balance_manager
+                .lock()
+                .order_was_filled(configuration_descriptor, cloned_order),
+            OrderEventType::CreateOrderFailed
+            | OrderEventType::RejectedByRisk
+            | OrderEventType::OrderCompleted { .. }
+            | OrderEventType::CancelOrderSucceeded => {
+                let _ = self.balance_reserved_orders.remove(&client_order_id);
+
+                let reservation_id = order.header().reservation_id.with_expect(|| {
+                    format!("Reserved order {client_order_id} should have reservation_id")
+                });
+                balance_manager
+                    .lock()
+                    .unreserve_by_client_order_id(
+                        reservation_id,
+                        client_order_id.clone(),
+                        order.amount(),
+                    )
+                    .unwrap_or_else(|err| {
+                        log::error!(
+                            "Failed to release reservation of order {client_order_id}: {err:?}"
+                        )
+                    });
+            }
+            OrderEventType::CreateOrderSucceeded
+            | OrderEventType::RejectedByBalance
+            | OrderEventType::RejectedByValidation
+            | OrderEventType::CancelOrderFailed
+            | OrderEventType::OrderExpired => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::manager::balance_manager::BalanceManager;
+    use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+    use crate::exchanges::general::test_helper;
+    use crate::infrastructure::init_lifetime_manager;
+    use mmb_domain::events::{ExchangeBalance, ExchangeBalancesAndPositions};
+    use mmb_domain::exchanges::symbol::{Precision, Symbol};
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::snapshot::{Amount, ClientOrderId, OrderSide, Price, UserOrder};
+    use mmb_utils::hashmap;
+    use rust_decimal_macros::dec;
+    use std::sync::Arc;
+
+    fn order_header(exchange: &Exchange, price: Price, amount: Amount) -> OrderHeader {
+        OrderHeader::with_user_order(
+            ClientOrderId::unique_id(),
+            exchange.exchange_account_id,
+            CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+            OrderSide::Buy,
+            amount,
+            UserOrder::limit(price),
+            None,
+            None,
+            "StrategyInUnitTests".to_owned(),
+        )
+    }
+
+    #[tokio::test]
+    async fn reserve_order_balance() {
+        let _ = init_lifetime_manager();
+        let symbol = Arc::new(Symbol::new(
+            false,
+            "PHB".into(),
+            "PHB".into(),
+            "BTC".into(),
+            "BTC".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "PHB".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(1) },
+        ));
+        let (exchange, _rx) = test_helper::get_test_exchange_with_symbol(symbol);
+        let exchange_account_id = exchange.exchange_account_id;
+
+        let converter =
+            CurrencyPairToSymbolConverter::new(hashmap![exchange_account_id => exchange.clone()]);
+        let balance_manager = BalanceManager::new(converter, None);
+        exchange.setup_balance_manager(balance_manager.clone());
+
+        // balance isn't received yet, so order can't be checked
+        let unchecked_order = order_header(&exchange, dec!(0.1), dec!(100));
+        assert_eq!(exchange.reserve_order_balance(&unchecked_order), Ok(None));
+
+        balance_manager
+            .lock()
+            .update_exchange_balance(
+                exchange_account_id,
+                &ExchangeBalancesAndPositions {
+                    balances: vec![ExchangeBalance {
+                        currency_code: "BTC".into(),
+                        balance: dec!(1),
+                    }],
+                    positions: None,
+                },
+            )
+            .expect("in test");
+
+        let reserved_order = order_header(&exchange, dec!(0.1), dec!(6));
+        let reservation_id = exchange
+            .reserve_order_balance(&reserved_order)
+            .expect("in test");
+        assert!(reservation_id.is_some());
+        assert!(exchange
+            .balance_reserved_orders
+            .contains_key(&reserved_order.client_order_id));
+
+        // 0.6 BTC of 1 BTC is already reserved
+        let rejected_order = order_header(&exchange, dec!(0.1), dec!(6));
+        assert!(exchange.reserve_order_balance(&rejected_order).is_err());
+    }
+}
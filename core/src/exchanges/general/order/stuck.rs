@@ -0,0 +1,269 @@
+use crate::alerting::{alert, AlertKind, AlertSeverity};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::traits::ExchangeError;
+use crate::infrastructure::spawn_future;
+use crate::settings::StuckOrdersSettings;
+use anyhow::Result;
+use chrono::Duration;
+use itertools::Itertools;
+use mmb_domain::market::ExchangeErrorType;
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{OrderInfo, OrderStatus};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use std::sync::Arc;
+
+/// Result of check of order which is creating or canceling for too long
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StuckOrderResolution {
+    /// Order state is brought in line with the exchange one
+    Repaired,
+    /// Order state can't be repaired automatically, so alert is raised
+    Escalated { reason: String },
+}
+
+impl Exchange {
+    /// Starts periodical search of orders stuck in `Creating` or `Canceling` status. Stuck orders
+    /// are requested from exchange to repair their state
+    pub fn start_stuck_orders_watchdog(self: &Arc<Self>, settings: &StuckOrdersSettings) {
+        let settings = settings.clone();
+        let check_interval = settings.check_interval();
+        let clock = self.clock.clone();
+        let self_weak = Arc::downgrade(self);
+        let action = async move {
+            loop {
+                clock.sleep(check_interval).await;
+                let exchange = match self_weak.upgrade() {
+                    Some(exchange) => exchange,
+                    None => return Ok(()),
+                };
+
+                let cancellation_token = exchange.lifetime_manager.stop_token();
+                for order in exchange.find_stuck_orders(&settings, exchange.clock.now()) {
+                    let _ = exchange
+                        .remediate_stuck_order(&order, cancellation_token.clone())
+                        .await;
+                }
+            }
+        };
+        spawn_future(
+            &format!(
+                "Exchange account id {} stuck orders watchdog",
+                self.exchange_account_id
+            ),
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action,
+        );
+    }
+
+    /// Orders which are in `Creating` or `Canceling` status longer than allowed
+    pub fn find_stuck_orders(
+        &self,
+        settings: &StuckOrdersSettings,
+        now: DateTime,
+    ) -> Vec<OrderRef> {
+        let max_creating_time = Duration::seconds(settings.max_creating_secs as i64);
+        let max_canceling_time = Duration::seconds(settings.max_canceling_secs as i64);
+
+        self.orders
+            .not_finished
+            .iter()
+            .filter(|x| {
+                let (status, status_time) = x.fn_ref(|x| {
+                    let status_time = x
+                        .status_history
+                        .last_change_time()
+                        .unwrap_or(x.props.init_time);
+                    (x.props.status, status_time)
+                });
+                match status {
+                    OrderStatus::Creating => now - status_time > max_creating_time,
+                    OrderStatus::Canceling => now - status_time > max_canceling_time,
+                    _ => false,
+                }
+            })
+            .map(|x| x.value().clone())
+            .collect_vec()
+    }
+
+    /// Requests stuck order from exchange and applies its state. Alert is raised if state
+    /// can't be determined or order is still open on exchange while it's canceling
+    pub async fn remediate_stuck_order(
+        &self,
+        order: &OrderRef,
+        cancellation_token: CancellationToken,
+    ) -> StuckOrderResolution {
+        let client_order_id = order.client_order_id();
+        let stuck_status = order.status();
+        log::warn!(
+            "Order {client_order_id} on {} is stuck in {stuck_status:?} status, requesting its state",
+            self.exchange_account_id
+        );
+
+        let _ = self
+            .timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::GetOrderInfo,
+                None,
+                cancellation_token.clone(),
+            )
+            .await;
+
+        let resolution = match self.get_order_info(order).await {
+            Ok(order_info) => self
+                .repair_stuck_order(order, stuck_status, &order_info, cancellation_token)
+                .await
+                .unwrap_or_else(|err| StuckOrderResolution::Escalated {
+                    reason: format!("failed to repair order state: {err:?}"),
+                }),
+            Err(error)
+                if stuck_status == OrderStatus::Creating
+                    && error.error_type == ExchangeErrorType::OrderNotFound =>
+            {
+                self.handle_create_order_failed(
+                    &client_order_id,
+                    &error,
+                    self.reconciliation_event_source_type(),
+                )
+                .map(|_| StuckOrderResolution::Repaired)
+                .unwrap_or_else(|err| StuckOrderResolution::Escalated {
+                    reason: format!("failed to repair order state: {err:?}"),
+                })
+            }
+            Err(error) => StuckOrderResolution::Escalated {
+                reason: format!("order info isn't received: {error:?}"),
+            },
+        };
+
+        match &resolution {
+            StuckOrderResolution::Repaired => log::info!(
+                "State of stuck order {client_order_id} is repaired, current status is {:?}",
+                order.status()
+            ),
+            StuckOrderResolution::Escalated { reason } => alert(
+                AlertSeverity::Warning,
+                AlertKind::StuckOrder,
+                &self.exchange_account_id.to_string(),
+                format!(
+                    "Order {client_order_id} on {} is stuck in {stuck_status:?} status: {reason}",
+                    self.exchange_account_id
+                ),
+            ),
+        }
+
+        resolution
+    }
+
+    async fn repair_stuck_order(
+        &self,
+        order: &OrderRef,
+        stuck_status: OrderStatus,
+        order_info: &OrderInfo,
+        cancellation_token: CancellationToken,
+    ) -> Result<StuckOrderResolution> {
+        let client_order_id = order.client_order_id();
+        let exchange_order_id = &order_info.exchange_order_id;
+        let source_type = self.reconciliation_event_source_type();
+
+        // every status except failed creation means that order was created on exchange
+        if stuck_status == OrderStatus::Creating
+            && order_info.order_status != OrderStatus::FailedToCreate
+        {
+            self.handle_create_order_succeeded(
+                self.exchange_account_id,
+                &client_order_id,
+                exchange_order_id,
+                source_type,
+            )?;
+        }
+
+        match (stuck_status, order_info.order_status) {
+            (_, OrderStatus::Canceled) => self.handle_cancel_order_succeeded(
+                Some(&client_order_id),
+                exchange_order_id,
+                Some(order_info.filled_amount),
+                source_type,
+            ),
+            (_, OrderStatus::Completed) => {
+                self.check_order_fills(order, false, None, cancellation_token)
+                    .await?
+            }
+            (OrderStatus::Creating, OrderStatus::FailedToCreate) => self
+                .handle_create_order_failed(
+                    &client_order_id,
+                    &ExchangeError::unknown("Order is failed to create on exchange"),
+                    source_type,
+                )?,
+            (OrderStatus::Creating, _) => {}
+            (_, status) => {
+                return Ok(StuckOrderResolution::Escalated {
+                    reason: format!("order is {status:?} on exchange"),
+                })
+            }
+        }
+
+        Ok(StuckOrderResolution::Repaired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::test_helper;
+    use crate::infrastructure::init_lifetime_manager;
+    use chrono::Utc;
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::snapshot::{ClientOrderId, OrderSide};
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn orders_are_stuck_after_threshold_of_their_status() {
+        let _ = init_lifetime_manager();
+        let (exchange, _receiver) = test_helper::get_test_exchange(false);
+        let settings = StuckOrdersSettings {
+            max_creating_secs: 10,
+            max_canceling_secs: 30,
+        };
+
+        let add_order = |status: OrderStatus, time: DateTime| {
+            let order = test_helper::create_order_ref(
+                &ClientOrderId::unique_id(),
+                None,
+                exchange.exchange_account_id,
+                CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+                dec!(0.8),
+                dec!(12),
+                OrderSide::Sell,
+            );
+            order.fn_mut(|x| x.set_status(status, time));
+            exchange.orders.add_snapshot_initial(&order.deep_clone())
+        };
+
+        let start = Utc::now();
+        let creating = add_order(OrderStatus::Creating, start);
+        let canceling = add_order(OrderStatus::Canceling, start);
+        let _created = add_order(OrderStatus::Created, start);
+
+        assert!(exchange
+            .find_stuck_orders(&settings, start + Duration::seconds(10))
+            .is_empty());
+
+        let stuck_orders = exchange.find_stuck_orders(&settings, start + Duration::seconds(11));
+        assert_eq!(
+            stuck_orders
+                .iter()
+                .map(|x| x.client_order_id())
+                .collect_vec(),
+            vec![creating.client_order_id()]
+        );
+
+        let stuck_orders = exchange.find_stuck_orders(&settings, start + Duration::seconds(31));
+        assert_eq!(stuck_orders.len(), 2);
+        assert!(stuck_orders
+            .iter()
+            .any(|x| x.client_order_id() == canceling.client_order_id()));
+    }
+}
@@ -0,0 +1,368 @@
+use std::borrow::Cow;
+
+use anyhow::Result;
+use mmb_domain::exchanges::symbol::{Round, Symbol};
+use mmb_domain::order::event::OrderEventType;
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{
+    Amount, OrderHeader, OrderOptions, OrderSide, OrderStatus, Price, UserOrder,
+};
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::settings::{OrderValidationSettings, RoundingMode};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum OrderValidationError {
+    #[error("price {price} isn't multiple of tick size, nearest valid price is {nearest}")]
+    PriceNotMultipleOfTick { price: Price, nearest: Price },
+    #[error("amount {amount} isn't multiple of lot size, nearest valid amount is {nearest}")]
+    AmountNotMultipleOfLot { amount: Amount, nearest: Amount },
+    #[error("amount {0} is zero after rounding to lot size")]
+    ZeroAmount(Amount),
+    #[error("price {price} is out of allowed range from {min_price:?} to {max_price:?}")]
+    PriceOutOfRange {
+        price: Price,
+        min_price: Option<Price>,
+        max_price: Option<Price>,
+    },
+    #[error("amount {amount} is less than min amount {min_amount}")]
+    AmountBelowMin { amount: Amount, min_amount: Amount },
+    #[error("amount {amount} exceeds max amount {max_amount}")]
+    AmountAboveMax { amount: Amount, max_amount: Amount },
+    #[error("order notional {notional} is less than min notional {min_notional}")]
+    NotionalBelowMin {
+        notional: Decimal,
+        min_notional: Decimal,
+    },
+}
+
+/// Aligns price and amount of user order with tick and lot size of symbol and checks limits of
+/// symbol. Header is returned as is if nothing was rounded. External orders aren't checked
+pub fn validate_by_symbol<'a>(
+    header: &'a OrderHeader,
+    symbol: &Symbol,
+    settings: &OrderValidationSettings,
+) -> Result<Cow<'a, OrderHeader>, OrderValidationError> {
+    let OrderOptions::User(user_order) = &header.options else {
+        return Ok(Cow::Borrowed(header));
+    };
+
+    let price = match *user_order {
+        UserOrder::Limit { price, .. } => Some(price),
+        UserOrder::StopLoss { stop_price } => Some(stop_price),
+        UserOrder::Market | UserOrder::TrailingStop { .. } => None,
+    };
+    let rounded_price = price
+        .map(|x| round_price(x, header.side, symbol, settings.price_rounding))
+        .transpose()?;
+    let rounded_amount = round_amount(header.amount, symbol, settings.amount_rounding)?;
+
+    check_limits(rounded_price, rounded_amount, symbol)?;
+
+    if rounded_price == price && rounded_amount == header.amount {
+        return Ok(Cow::Borrowed(header));
+    }
+
+    let user_order = match (*user_order, rounded_price) {
+        (UserOrder::Limit { execution_type, .. }, Some(price)) => UserOrder::Limit {
+            price,
+            execution_type,
+        },
+        (UserOrder::StopLoss { .. }, Some(stop_price)) => UserOrder::StopLoss { stop_price },
+        (user_order, _) => user_order,
+    };
+
+    Ok(Cow::Owned(OrderHeader::with_options(
+        header.client_order_id.clone(),
+        header.exchange_account_id,
+        header.currency_pair,
+        header.side,
+        rounded_amount,
+        OrderOptions::User(user_order),
+        header.reservation_id,
+        header.signal_id.clone(),
+        header.strategy_name.clone(),
+    )))
+}
+
+fn round_price(
+    price: Price,
+    side: OrderSide,
+    symbol: &Symbol,
+    mode: RoundingMode,
+) -> Result<Price, OrderValidationError> {
+    let round = match (mode, side) {
+        (RoundingMode::Passive, OrderSide::Buy) => Round::Floor,
+        (RoundingMode::Passive, OrderSide::Sell) => Round::Ceiling,
+        (RoundingMode::ToNearest | RoundingMode::Reject, _) => Round::ToNearest,
+    };
+
+    let rounded = symbol.price_round(price, round);
+    match mode == RoundingMode::Reject && rounded != price {
+        true => Err(OrderValidationError::PriceNotMultipleOfTick {
+            price,
+            nearest: rounded,
+        }),
+        false => Ok(rounded),
+    }
+}
+
+fn round_amount(
+    amount: Amount,
+    symbol: &Symbol,
+    mode: RoundingMode,
+) -> Result<Amount, OrderValidationError> {
+    let round = match mode {
+        RoundingMode::Passive => Round::Floor,
+        RoundingMode::ToNearest | RoundingMode::Reject => Round::ToNearest,
+    };
+
+    let rounded = symbol.amount_round(amount, round);
+    if mode == RoundingMode::Reject && rounded != amount {
+        return Err(OrderValidationError::AmountNotMultipleOfLot {
+            amount,
+            nearest: rounded,
+        });
+    }
+
+    match rounded > Decimal::ZERO {
+        true => Ok(rounded),
+        false => Err(OrderValidationError::ZeroAmount(amount)),
+    }
+}
+
+fn check_limits(
+    price: Option<Price>,
+    amount: Amount,
+    symbol: &Symbol,
+) -> Result<(), OrderValidationError> {
+    if let Some(price) = price {
+        let is_out_of_range = price <= Decimal::ZERO
+            || symbol.min_price.is_some_and(|x| price < x)
+            || symbol.max_price.is_some_and(|x| price > x);
+        if is_out_of_range {
+            return Err(OrderValidationError::PriceOutOfRange {
+                price,
+                min_price: symbol.min_price,
+                max_price: symbol.max_price,
+            });
+        }
+    }
+
+    if let Some(min_amount) = symbol.min_amount.filter(|x| amount < *x) {
+        return Err(OrderValidationError::AmountBelowMin { amount, min_amount });
+    }
+
+    if let Some(max_amount) = symbol.max_amount.filter(|x| amount > *x) {
+        return Err(OrderValidationError::AmountAboveMax { amount, max_amount });
+    }
+
+    // min cost of derivatives isn't notional of order
+    if symbol.is_derivative {
+        return Ok(());
+    }
+
+    if let (Some(price), Some(min_notional)) = (price, symbol.min_cost) {
+        let notional = price * amount;
+        if notional < min_notional {
+            return Err(OrderValidationError::NotionalBelowMin {
+                notional,
+                min_notional,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+impl Exchange {
+    /// Validates order by trading rules of symbol if validation is configured for exchange account.
+    /// Order isn't checked if symbol of its currency pair is unknown
+    pub(super) fn validate_order<'a>(
+        &self,
+        header: &'a OrderHeader,
+    ) -> Result<Cow<'a, OrderHeader>, OrderValidationError> {
+        let settings = match &self.exchange_client.get_settings().order_validation {
+            None => return Ok(Cow::Borrowed(header)),
+            Some(settings) => settings,
+        };
+        let symbol = match self.symbols.get(&header.currency_pair) {
+            None => return Ok(Cow::Borrowed(header)),
+            Some(symbol) => symbol.clone(),
+        };
+
+        let validated = validate_by_symbol(header, &symbol, settings)?;
+        if let Cow::Owned(rounded) = &validated {
+            log::info!(
+                "Order {} is rounded by trading rules of symbol: price {:?} -> {:?}, amount {} -> {}",
+                header.client_order_id,
+                header.source_price,
+                rounded.source_price,
+                header.amount,
+                rounded.amount
+            );
+        }
+
+        Ok(validated)
+    }
+
+    /// Returned error contains [`OrderValidationError`], so callers can get reason of rejection
+    /// by downcasting
+    pub(super) fn reject_by_validation(
+        &self,
+        order: &OrderRef,
+        error: OrderValidationError,
+    ) -> Result<OrderRef> {
+        let client_order_id = order.client_order_id();
+        log::warn!("Order {client_order_id} is rejected by validation: {error}");
+
+        order.fn_mut(|x| {
+            x.set_status(OrderStatus::FailedToCreate, self.clock.now());
+            x.internal_props.last_creation_error_message = error.to_string();
+        });
+
+        self.add_event_on_order_change(order, OrderEventType::RejectedByValidation)?;
+
+        self.event_recorder
+            .save(&mut order.deep_clone())
+            .expect("Failure save order");
+
+        Err(anyhow::Error::new(error)
+            .context(format!("Order {client_order_id} is rejected by validation")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::exchanges::symbol::Precision;
+    use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+    use mmb_domain::order::snapshot::ClientOrderId;
+    use rust_decimal_macros::dec;
+
+    fn symbol() -> Symbol {
+        Symbol::new(
+            false,
+            "BTC".into(),
+            "BTC".into(),
+            "USDT".into(),
+            "USDT".into(),
+            Some(dec!(0.01)),
+            Some(dec!(1000000)),
+            Some(dec!(0.001)),
+            Some(dec!(100)),
+            Some(dec!(10)),
+            "BTC".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.01) },
+            Precision::ByTick { tick: dec!(0.001) },
+        )
+    }
+
+    fn order_header(side: OrderSide, price: Price, amount: Amount) -> OrderHeader {
+        OrderHeader::with_user_order(
+            ClientOrderId::unique_id(),
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("BTC".into(), "USDT".into()),
+            side,
+            amount,
+            UserOrder::limit(price),
+            None,
+            None,
+            "StrategyInUnitTests".to_owned(),
+        )
+    }
+
+    fn settings(rounding: RoundingMode) -> OrderValidationSettings {
+        OrderValidationSettings {
+            price_rounding: rounding,
+            amount_rounding: rounding,
+        }
+    }
+
+    #[test]
+    fn passive_rounding_keeps_order_away_from_market() {
+        let settings = settings(RoundingMode::Passive);
+
+        let buy = order_header(OrderSide::Buy, dec!(30000.128), dec!(0.0129));
+        let buy = validate_by_symbol(&buy, &symbol(), &settings).expect("in test");
+        assert_eq!(buy.source_price, Some(dec!(30000.12)));
+        assert_eq!(buy.amount, dec!(0.012));
+        assert!(matches!(
+            buy.options,
+            OrderOptions::User(UserOrder::Limit { price, .. }) if price == dec!(30000.12)
+        ));
+
+        let sell = order_header(OrderSide::Sell, dec!(30000.121), dec!(0.0129));
+        let sell = validate_by_symbol(&sell, &symbol(), &settings).expect("in test");
+        assert_eq!(sell.source_price, Some(dec!(30000.13)));
+        assert_eq!(sell.amount, dec!(0.012));
+    }
+
+    #[test]
+    fn aligned_order_is_not_copied() {
+        let header = order_header(OrderSide::Buy, dec!(30000.12), dec!(0.012));
+
+        let validated = validate_by_symbol(&header, &symbol(), &settings(RoundingMode::ToNearest));
+
+        assert!(matches!(validated, Ok(Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn not_aligned_price_is_rejected_in_reject_mode() {
+        let header = order_header(OrderSide::Buy, dec!(30000.128), dec!(0.012));
+
+        let error = validate_by_symbol(&header, &symbol(), &settings(RoundingMode::Reject))
+            .expect_err("in test");
+
+        assert_eq!(
+            error,
+            OrderValidationError::PriceNotMultipleOfTick {
+                price: dec!(30000.128),
+                nearest: dec!(30000.13),
+            }
+        );
+    }
+
+    #[test]
+    fn limits_of_symbol_are_checked_after_rounding() {
+        let settings = settings(RoundingMode::Passive);
+        let check = |price, amount| {
+            validate_by_symbol(
+                &order_header(OrderSide::Buy, price, amount),
+                &symbol(),
+                &settings,
+            )
+            .expect_err("in test")
+        };
+
+        assert_eq!(
+            check(dec!(30000), dec!(0.0009)),
+            OrderValidationError::ZeroAmount(dec!(0.0009))
+        );
+        assert_eq!(
+            check(dec!(30000), dec!(101)),
+            OrderValidationError::AmountAboveMax {
+                amount: dec!(101),
+                max_amount: dec!(100),
+            }
+        );
+        assert_eq!(
+            check(dec!(0.005), dec!(1)),
+            OrderValidationError::PriceOutOfRange {
+                price: dec!(0),
+                min_price: Some(dec!(0.01)),
+                max_price: Some(dec!(1000000)),
+            }
+        );
+        assert_eq!(
+            check(dec!(1000), dec!(0.0099)),
+            OrderValidationError::NotionalBelowMin {
+                notional: dec!(9),
+                min_notional: dec!(10),
+            }
+        );
+    }
+}
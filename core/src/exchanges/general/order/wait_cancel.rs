@@ -1,15 +1,14 @@
 use super::cancel::CancelOrderResult;
+use super::record_exchange_order_id;
 use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::general::exchange::RequestResult;
 use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::timeouts::order_rate_limiter::OrderRequestKind;
 use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
 use crate::exchanges::traits::ExchangeError;
-use crate::misc::time::time_manager;
 use anyhow::{bail, Context, Result};
-use chrono::Utc;
 use dashmap::mapref::entry::Entry::{Occupied, Vacant};
 use futures::pin_mut;
-use log::log;
 use mmb_domain::events::{AllowedEventSourceType, EventSourceType};
 use mmb_domain::market::ExchangeErrorType;
 use mmb_domain::order::event::OrderEventType;
@@ -21,11 +20,20 @@ use mmb_utils::time::ToStdExpected;
 use scopeguard;
 use std::time::Duration;
 use tokio::sync::broadcast;
-use tokio::time::{sleep, timeout};
+use tokio::time::timeout;
+use tracing::field::Empty;
 
 const CANCEL_DELAY: Duration = Duration::from_secs(10);
 
 impl Exchange {
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            client_order_id = %order.client_order_id(),
+            exchange_order_id = Empty,
+            exchange_account_id = %self.exchange_account_id,
+        )
+    )]
     pub async fn wait_cancel_order(
         &self,
         order: OrderRef,
@@ -33,11 +41,10 @@ impl Exchange {
         check_order_fills: bool,
         cancellation_token: CancellationToken,
     ) -> Result<()> {
-        let (client_order_id, exchange_order_id) = order.order_ids();
-        log::info!(
-            "Executing wait_cancel_order() with order: {client_order_id} {exchange_order_id:?} {}",
-            self.exchange_account_id,
-        );
+        record_exchange_order_id(&order);
+        tracing::info!("Executing wait_cancel_order()");
+
+        let client_order_id = order.client_order_id();
 
         // Be sure value will be removed anyway
         let _guard = scopeguard::guard(client_order_id.clone(), |client_order_id| {
@@ -83,23 +90,22 @@ impl Exchange {
         if order.status() == OrderStatus::Creating {
             self.create_order_created_fut(order, cancellation_token.clone())
                 .await?;
+            record_exchange_order_id(order);
         }
 
-        let (is_canceling_from_wait_cancel_order, is_finished, exchange_order_id) =
-            order.fn_mut(|order| {
-                let current = order.internal_props.is_canceling_from_wait_cancel_order;
-                order.internal_props.is_canceling_from_wait_cancel_order = true;
+        let (is_canceling_from_wait_cancel_order, is_finished) = order.fn_mut(|order| {
+            let current = order.internal_props.is_canceling_from_wait_cancel_order;
+            order.internal_props.is_canceling_from_wait_cancel_order = true;
 
-                (current, order.is_finished(), order.exchange_order_id())
-            });
+            (current, order.is_finished())
+        });
 
         if is_finished {
             return Ok(());
         }
 
-        let client_order_id = order.client_order_id();
         if is_canceling_from_wait_cancel_order {
-            log::error!("Order {client_order_id} {exchange_order_id:?} is already cancelling by wait_cancel_order");
+            tracing::error!("Order is already cancelling by wait_cancel_order");
 
             return Ok(());
         }
@@ -130,12 +136,10 @@ impl Exchange {
         while !cancellation_token.is_cancellation_requested() {
             attempt_number += 1;
 
-            let log_event_level = match attempt_number == 1 {
-                true => log::Level::Trace,
-                false => log::Level::Warn,
-            };
-
-            log!(log_event_level, "Cancellation iteration is {attempt_number} on {client_order_id} {exchange_order_id:?} {}", self.exchange_account_id);
+            match attempt_number == 1 {
+                true => tracing::trace!(attempt_number, "Cancellation iteration"),
+                false => tracing::warn!(attempt_number, "Cancellation iteration"),
+            }
 
             self.timeout_manager
                 .reserve_when_available(
@@ -147,6 +151,14 @@ impl Exchange {
                 .await
                 .into_result()?;
 
+            self.timeout_manager
+                .acquire_order_request(
+                    self.exchange_account_id,
+                    OrderRequestKind::Cancel,
+                    order_is_finished_token.clone(),
+                )
+                .await?;
+
             let cancel_order_fut = self.start_cancel_order(order, cancellation_token.clone());
             pin_mut!(cancel_order_fut);
 
@@ -170,25 +182,18 @@ impl Exchange {
                             continue;
                         }
                     }
-                    _ = sleep(CANCEL_DELAY) => {
+                    _ = self.clock.sleep(CANCEL_DELAY) => {
                         if self.features.allowed_cancel_event_source_type != AllowedEventSourceType::All {
                             bail!("Order was expected to cancel explicitly via Rest or Web Socket but got timeout instead")
                         }
 
-                       log::warn!("Cancel response TimedOut - re-cancelling order {client_order_id} {exchange_order_id:?} {}", self.exchange_account_id);
+                        tracing::warn!("Cancel response TimedOut - re-cancelling order");
                     }
                     poll_result = &mut poll_cancellation_fut, if is_poll_enabled => {
-                        let level = match poll_result {
-                            Ok(()) => log::Level::Trace,
-                            Err(_) => log::Level::Error,
-                        };
-
-                        let error_part = match poll_result {
-                            Ok(()) => String::new(),
-                            Err(err) => format!("with result: {err:?}"),
-                        };
-
-                        log!(level, "'poll_order_cancellation_status_fut' finished first {client_order_id} {exchange_order_id:?} {} {error_part}", self.exchange_account_id);
+                        match poll_result {
+                            Ok(()) => tracing::trace!("'poll_order_cancellation_status_fut' finished first"),
+                            Err(err) => tracing::error!("'poll_order_cancellation_status_fut' finished first with result: {err:?}"),
+                        }
                     }
                 };
 
@@ -203,25 +208,23 @@ impl Exchange {
 
         let order_has_missed_fills = self.has_missed_fill(order);
 
-        let (
-            exchange_order_id,
-            order_cancellation_event_source_type,
-            order_last_cancellation_error,
-            status,
-        ) = order.fn_ref(|s| {
-            (
-                s.exchange_order_id(),
-                s.internal_props.cancellation_event_source_type,
-                s.internal_props.last_cancellation_error,
-                s.props.status,
-            )
-        });
+        let (order_cancellation_event_source_type, order_last_cancellation_error, status) = order
+            .fn_ref(|s| {
+                (
+                    s.internal_props.cancellation_event_source_type,
+                    s.internal_props.last_cancellation_error,
+                    s.props.status,
+                )
+            });
 
-        log::trace!(
-            "Order data in wait_cancel_order_work(): client_order_id: {client_order_id}, exchange_order_id: {exchange_order_id:?},
-            checked_order_fills: {check_order_fills}, order_has_missed_fills: {order_has_missed_fills:?},
-            order_cancellation_event_source_type: {order_cancellation_event_source_type:?}, last_cancellation_error: {order_last_cancellation_error:?},
-            order_status: {status:?}");
+        tracing::trace!(
+            check_order_fills,
+            order_has_missed_fills,
+            ?order_cancellation_event_source_type,
+            ?order_last_cancellation_error,
+            ?status,
+            "Order data in wait_cancel_order_work()"
+        );
 
         if check_order_fills
             || order_has_missed_fills
@@ -249,8 +252,8 @@ impl Exchange {
                 .then(|| s.exchange_order_id())
         });
 
-        if let Some(exchange_order_id) = cancelled_order {
-            log::trace!("Adding CancelOrderSucceeded event from wait_cancel_order() for order {client_order_id} {exchange_order_id:?} on {}", self.exchange_account_id);
+        if cancelled_order.is_some() {
+            tracing::trace!("Adding CancelOrderSucceeded event from wait_cancel_order()");
 
             self.add_event_on_order_change(order, OrderEventType::CancelOrderSucceeded)?;
         }
@@ -266,12 +269,7 @@ impl Exchange {
         cancellation_token: CancellationToken,
         order_is_finished_token: CancellationToken,
     ) -> Result<()> {
-        log::info!(
-            "cancel_order_fut finished first on order {:?} {:?} on {}",
-            order.order_ids(),
-            cancel_order_outcome,
-            self.exchange_account_id
-        );
+        tracing::info!("cancel_order_fut finished first with {cancel_order_outcome:?}");
 
         if let Some(cancel_order_outcome) = cancel_order_outcome {
             if let RequestResult::Error(error) = cancel_order_outcome.outcome {
@@ -286,7 +284,7 @@ impl Exchange {
                         .await?;
                     }
                     ExchangeErrorType::PendingError(pending_time) => {
-                        sleep(pending_time).await;
+                        self.clock.sleep(pending_time).await;
                     }
                     ExchangeErrorType::OrderCompleted => {
                         // Happens when an order is completed while we are waiting for cancellation
@@ -318,7 +316,7 @@ impl Exchange {
                 } else {
                     order
                         .internal_props
-                        .last_order_cancellation_status_request_time = Some(Utc::now());
+                        .last_order_cancellation_status_request_time = Some(self.clock.now());
 
                     false
                 }
@@ -342,8 +340,7 @@ impl Exchange {
                 return Ok(());
             }
 
-            let (client_order_id, exchange_order_id) = order.order_ids();
-            log::trace!("Checking order status in check_order_cancellation_status with order {client_order_id} {exchange_order_id:?} {}", self.exchange_account_id);
+            tracing::trace!("Checking order status in check_order_cancellation_status");
 
             let order_info = self.get_order_info(order).await;
 
@@ -360,7 +357,8 @@ impl Exchange {
 
                         let exchange_order_id = exchange_order_id.with_context(|| {
                             format!(
-                                "exchange_order_id is None in order {client_order_id} on {}",
+                                "exchange_order_id is None in order {} on {}",
+                                order.client_order_id(),
                                 self.exchange_account_id
                             )
                         })?;
@@ -374,10 +372,9 @@ impl Exchange {
                         break;
                     }
 
-                    log::warn!(
-                        "Error for order_info was received {client_order_id} {exchange_order_id:?} {} {:?} {error:?}",
-                        self.exchange_account_id,
-                        order.currency_pair(),
+                    tracing::warn!(
+                        currency_pair = %order.currency_pair(),
+                        "Error for order_info was received {error:?}"
                     );
 
                     continue;
@@ -387,7 +384,7 @@ impl Exchange {
                         OrderStatus::Canceled => {
                             if let Some(exchange_order_id) = exchange_order_id {
                                 self.handle_cancel_order_succeeded(
-                                    Some(&client_order_id),
+                                    Some(&order.client_order_id()),
                                     &exchange_order_id,
                                     Some(order_info.filled_amount),
                                     EventSourceType::RestFallback,
@@ -419,22 +416,23 @@ impl Exchange {
     }
 
     fn has_missed_fill(&self, order: &OrderRef) -> bool {
-        let client_order_id = order.client_order_id();
-        let (exchange_order_id, order_filled_amount_after_cancellation, order_filled_amount) =
-            order.fn_ref(|x| {
-                (
-                    x.exchange_order_id(),
-                    x.internal_props.filled_amount_after_cancellation,
-                    x.filled_amount(),
-                )
-            });
+        let (order_filled_amount_after_cancellation, order_filled_amount) = order.fn_ref(|x| {
+            (
+                x.internal_props.filled_amount_after_cancellation,
+                x.filled_amount(),
+            )
+        });
 
-        log::trace!("Order with {client_order_id}, {exchange_order_id:?} order_filled_amount_after_cancellation: {order_filled_amount_after_cancellation:?}, order_filed_amount: {order_filled_amount}");
+        tracing::trace!(
+            ?order_filled_amount_after_cancellation,
+            %order_filled_amount,
+            "Checking missed fills"
+        );
 
         match order_filled_amount_after_cancellation {
             Some(order_filled_amount_after_cancellation) => {
                 if order_filled_amount_after_cancellation < order_filled_amount {
-                    log::error!("Received order with filled amount {order_filled_amount_after_cancellation} less then order.filled_amount {order_filled_amount} {client_order_id} {exchange_order_id:?} on {}", self.exchange_account_id);
+                    tracing::error!("Received order with filled amount {order_filled_amount_after_cancellation} less then order.filled_amount {order_filled_amount}");
                     return false;
                 }
 
@@ -462,7 +460,7 @@ impl Exchange {
                 return Ok(());
             }
 
-            let now = time_manager::now();
+            let now = self.clock.now();
 
             let order_cancellation_status_request_period = chrono::Duration::seconds(5);
             let delay_till_fallback_request = match last_order_creation_status_request_time {
@@ -474,7 +472,7 @@ impl Exchange {
 
             if let Some(delay_till_fallback_request) = delay_till_fallback_request {
                 tokio::select! {
-                    _ = sleep(delay_till_fallback_request) => nothing_to_do(),
+                    _ = self.clock.sleep(delay_till_fallback_request) => nothing_to_do(),
                     _ = cancellation_token.when_cancelled() => return Ok(()),
                 }
             }
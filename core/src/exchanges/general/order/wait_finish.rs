@@ -1,5 +1,4 @@
 use anyhow::{bail, Context, Result};
-use chrono::Utc;
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::infrastructure::{SpawnFutureFlags, WithExpect};
 use std::sync::Arc;
@@ -11,6 +10,8 @@ use mmb_domain::market::CurrencyCode;
 use mmb_utils::nothing_to_do;
 use tokio::sync::{broadcast, oneshot};
 use tokio::time::timeout;
+use tracing::field::Empty;
+use tracing::Instrument;
 
 use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::general::exchange::RequestResult;
@@ -27,8 +28,17 @@ use mmb_domain::order::snapshot::{OrderExecutionType, OrderInfo, OrderStatus, Or
 use mmb_utils::time::ToStdExpected;
 
 use super::get_order_trades::OrderTrade;
+use super::record_exchange_order_id;
 
 impl Exchange {
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            client_order_id = %order.client_order_id(),
+            exchange_order_id = Empty,
+            exchange_account_id = %self.exchange_account_id,
+        )
+    )]
     pub async fn wait_order_finish(
         self: Arc<Self>,
         order: &OrderRef,
@@ -38,6 +48,7 @@ impl Exchange {
         // TODO make MetricsRegistry.Metrics.Measure.Timer.Time(MetricsRegistry.Timers.WaitOrderFinishTimer,
         //     MetricsRegistry.Timers.CreateExchangeTimerTags(order.ExchangeId));
 
+        record_exchange_order_id(order);
         let client_order_id = order.client_order_id();
 
         if order.status() == OrderStatus::FailedToCreate {
@@ -106,12 +117,14 @@ impl Exchange {
             "poll_order_fills future",
             SpawnFutureFlags::STOP_BY_TOKEN,
             three_hours,
-            self.clone().poll_order_fills(
-                order.clone(),
-                has_websocket_notification,
-                pre_reservation_group_id,
-                linked_cancellation_token.clone(),
-            ),
+            self.clone()
+                .poll_order_fills(
+                    order.clone(),
+                    has_websocket_notification,
+                    pre_reservation_group_id,
+                    linked_cancellation_token.clone(),
+                )
+                .in_current_span(),
         );
 
         if !has_websocket_notification {
@@ -138,7 +151,7 @@ impl Exchange {
         while !order.is_finished() && !cancellation_token.is_cancellation_requested() {
             if is_fallback {
                 // TODO optimize by counting time since order.LastFillDateTime
-                let current_time = Utc::now();
+                let current_time = self.clock.now();
 
                 const ORDER_TRADES_FALLBACK_REQUEST_PERIOD_FOR_STOP_LOSS: Duration =
                     Duration::from_secs(30);
@@ -330,7 +343,7 @@ impl Exchange {
             }
 
             order.fn_mut(|order| {
-                order.internal_props.last_order_trades_request_time = Some(Utc::now())
+                order.internal_props.last_order_trades_request_time = Some(self.clock.now())
             });
 
             let result = self
@@ -0,0 +1,173 @@
+use crate::exchanges::general::exchange::Exchange;
+use anyhow::{bail, Result};
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::OrderStatus;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::OPERATION_CANCELED_MSG;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+impl Exchange {
+    /// Waits until order is completely filled. See `wait_until_status`
+    pub async fn wait_until_filled(
+        &self,
+        order: &OrderRef,
+        timeout: Duration,
+        cancellation_token: CancellationToken,
+    ) -> Result<OrderRef> {
+        self.wait_until_status(order, OrderStatus::Completed, timeout, cancellation_token)
+            .await
+    }
+
+    /// Waits until order reaches the specified status by order events of the exchange.
+    /// `OrderStatus::Created` is considered as reached for orders which were created and
+    /// then finished or started canceling.
+    /// Returns error if order is finished with another status, timeout is over or operation
+    /// is cancelled
+    pub async fn wait_until_status(
+        &self,
+        order: &OrderRef,
+        status: OrderStatus,
+        timeout: Duration,
+        cancellation_token: CancellationToken,
+    ) -> Result<OrderRef> {
+        let client_order_id = order.client_order_id();
+
+        // subscribe before checking current status to not miss events between them
+        let mut events_receiver = self.events_channel.subscribe();
+        let timeout_fut = self.clock.sleep(timeout);
+        tokio::pin!(timeout_fut);
+
+        loop {
+            let current_status = order.status();
+            if is_status_reached(current_status, status) {
+                return Ok(order.clone());
+            }
+
+            if current_status.is_finished() {
+                bail!("Order {client_order_id} is finished with status {current_status:?} while waiting for status {status:?}");
+            }
+
+            tokio::select! {
+                event = events_receiver.recv() => match event {
+                    // status of the order is checked on the next iteration
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => bail!("Events channel is closed while waiting for status {status:?} of order {client_order_id}"),
+                },
+                _ = &mut timeout_fut => bail!("Order {client_order_id} didn't reach status {status:?} within {timeout:?}. Current status is {current_status:?}"),
+                _ = cancellation_token.when_cancelled() => bail!(OPERATION_CANCELED_MSG),
+            }
+        }
+    }
+}
+
+fn is_status_reached(current: OrderStatus, expected: OrderStatus) -> bool {
+    use OrderStatus::*;
+
+    match expected {
+        Created => !matches!(current, Creating | FailedToCreate),
+        _ => current == expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::test_helper;
+    use crate::infrastructure::init_lifetime_manager;
+    use chrono::Utc;
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::event::OrderEventType;
+    use mmb_domain::order::snapshot::{ClientOrderId, OrderSide};
+    use rstest::rstest;
+    use rust_decimal_macros::dec;
+
+    fn order(exchange: &Exchange) -> OrderRef {
+        test_helper::create_order_ref(
+            &ClientOrderId::unique_id(),
+            None,
+            exchange.exchange_account_id,
+            CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+            dec!(0.8),
+            dec!(12),
+            OrderSide::Buy,
+        )
+    }
+
+    fn set_status(exchange: &Exchange, order: &OrderRef, status: OrderStatus) {
+        order.fn_mut(|x| x.set_status(status, Utc::now()));
+        exchange
+            .add_event_on_order_change(order, OrderEventType::CreateOrderSucceeded)
+            .expect("in test");
+    }
+
+    #[rstest]
+    #[case(OrderStatus::Created, OrderStatus::Created, true)]
+    #[case(OrderStatus::Canceled, OrderStatus::Created, true)]
+    #[case(OrderStatus::FailedToCreate, OrderStatus::Created, false)]
+    #[case(OrderStatus::Created, OrderStatus::Completed, false)]
+    #[case(OrderStatus::Completed, OrderStatus::Completed, true)]
+    fn status_reached(
+        #[case] current: OrderStatus,
+        #[case] expected: OrderStatus,
+        #[case] is_reached: bool,
+    ) {
+        assert_eq!(is_status_reached(current, expected), is_reached);
+    }
+
+    #[tokio::test]
+    async fn wait_until_status_by_order_events() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = test_helper::get_test_exchange(false);
+        let order = order(&exchange);
+
+        let wait_fut = tokio::spawn({
+            let exchange = exchange.clone();
+            let order = order.clone();
+            async move {
+                exchange
+                    .wait_until_filled(&order, Duration::from_secs(5), CancellationToken::new())
+                    .await
+            }
+        });
+
+        tokio::task::yield_now().await;
+        set_status(&exchange, &order, OrderStatus::Created);
+        set_status(&exchange, &order, OrderStatus::Completed);
+
+        let result = wait_fut.await.expect("in test").expect("in test");
+        assert_eq!(result.status(), OrderStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn wait_until_status_fails_if_order_finished_with_other_status() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = test_helper::get_test_exchange(false);
+        let order = order(&exchange);
+        set_status(&exchange, &order, OrderStatus::Canceled);
+
+        let result = exchange
+            .wait_until_filled(&order, Duration::from_secs(5), CancellationToken::new())
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn wait_until_status_timeout() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = test_helper::get_test_exchange(false);
+        let order = order(&exchange);
+
+        let result = exchange
+            .wait_until_status(
+                &order,
+                OrderStatus::Created,
+                Duration::from_millis(10),
+                CancellationToken::new(),
+            )
+            .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -1,18 +1,24 @@
-use chrono::{Duration, Utc};
+use std::sync::Arc;
+
+use chrono::Duration;
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::DateTime;
-use tokio::time::timeout;
 
 use crate::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use crate::misc::clock::Clock;
 use mmb_utils::time::ToStdExpected;
 
 pub(crate) struct PollingTimeoutManager {
     timeout_arguments: RequestTimeoutArguments,
+    clock: Arc<dyn Clock>,
 }
 
 impl PollingTimeoutManager {
-    pub(crate) fn new(timeout_arguments: RequestTimeoutArguments) -> Self {
-        Self { timeout_arguments }
+    pub(crate) fn new(timeout_arguments: RequestTimeoutArguments, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            timeout_arguments,
+            clock,
+        }
     }
 
     pub(crate) async fn wait(
@@ -32,20 +38,14 @@ impl PollingTimeoutManager {
         let divisor = requests_per_period as f64 * request_range * 0.01;
         let interval = Duration::milliseconds((period.num_milliseconds() as f64 / divisor) as i64);
 
-        let time_since_last_request = Utc::now() - last_request_time;
+        let time_since_last_request = self.clock.now() - last_request_time;
         let delay_till_fallback_request = interval - time_since_last_request;
 
         if delay_till_fallback_request.num_milliseconds() > 0 {
-            #[allow(clippy::single_match)]
-            match timeout(
-                delay_till_fallback_request.to_std_expected(),
-                cancellation_token.when_cancelled(),
-            )
-            .await
-            {
-                Ok(_) => {}
-                Err(_) => {}
-            };
+            tokio::select! {
+                _ = self.clock.sleep(delay_till_fallback_request.to_std_expected()) => {}
+                _ = cancellation_token.when_cancelled() => {}
+            }
         }
     }
 }
@@ -22,4 +22,28 @@ pub enum RequestType {
     GetProfileId,
     GetMyTrades,
     SetLeverage,
+    Transfer,
+    GetTransferStatus,
+    Withdraw,
+    GetFundsMovements,
+}
+
+/// Order of requests when requests limit of exchange account is saturated
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum RequestPriority {
+    /// Placing of new quotes. Such requests give their scheduled time to high priority requests
+    Low,
+    Normal,
+    /// Cancellations and risk-driven actions
+    High,
+}
+
+impl RequestType {
+    pub fn priority(&self) -> RequestPriority {
+        match self {
+            RequestType::CancelOrder | RequestType::ClosePosition => RequestPriority::High,
+            RequestType::CreateOrder => RequestPriority::Low,
+            _ => RequestPriority::Normal,
+        }
+    }
 }
@@ -0,0 +1,169 @@
+use crate::alerting::{alert, AlertKind, AlertSeverity};
+use crate::exchanges::block_reasons::EXCHANGE_MAINTENANCE;
+use crate::exchanges::exchange_blocker::BlockType;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::settings::SystemStatusSettings;
+use chrono::Duration;
+use mmb_domain::exchanges::system_status::SystemStatus;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use std::sync::Arc;
+
+/// Maintenance declared by exchange
+#[derive(Debug, Default)]
+pub(crate) struct MaintenanceState {
+    /// Start of maintenance, `None` if exchange isn't under maintenance
+    since: Option<DateTime>,
+    /// Time since which exchange reports normal status after maintenance
+    healthy_since: Option<DateTime>,
+}
+
+impl Exchange {
+    /// Starts periodical request of status declared by exchange. Exchange account is blocked
+    /// while exchange is under maintenance
+    pub fn start_system_status_polling(self: &Arc<Self>, settings: &SystemStatusSettings) {
+        let poll_interval = std::time::Duration::from_secs(settings.poll_interval_secs);
+        let healthy_period = Duration::seconds(settings.healthy_period_secs as i64);
+        let clock = self.clock.clone();
+        let self_weak = Arc::downgrade(self);
+        let action = async move {
+            loop {
+                let exchange = match self_weak.upgrade() {
+                    Some(exchange) => exchange,
+                    None => return Ok(()),
+                };
+
+                let status = match exchange.exchange_client.get_system_status().await {
+                    None => {
+                        log::warn!(
+                            "Exchange {} doesn't report its status, polling is stopped",
+                            exchange.exchange_account_id
+                        );
+                        return Ok(());
+                    }
+                    Some(Ok(status)) => Some(status),
+                    Some(Err(err)) => {
+                        log::warn!(
+                            "Unable to request status of {}: {err}",
+                            exchange.exchange_account_id
+                        );
+                        None
+                    }
+                };
+                exchange.update_maintenance_state(status.as_ref(), clock.now(), healthy_period);
+                drop(exchange);
+
+                clock.sleep(poll_interval).await;
+            }
+        };
+        spawn_future(
+            &format!(
+                "Exchange account id {} system status polling",
+                self.exchange_account_id
+            ),
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            action,
+        );
+    }
+
+    /// Whether exchange declared maintenance and isn't verified as healthy after it yet
+    pub fn is_under_maintenance(&self) -> bool {
+        self.maintenance.lock().since.is_some()
+    }
+
+    /// Blocks exchange account when exchange declares maintenance and unblocks it when exchange
+    /// reports normal status for `healthy_period`. Unknown status doesn't end maintenance
+    pub(crate) fn update_maintenance_state(
+        &self,
+        status: Option<&SystemStatus>,
+        now: DateTime,
+        healthy_period: Duration,
+    ) {
+        let mut maintenance = self.maintenance.lock();
+        match (status, maintenance.since) {
+            (Some(SystemStatus::Maintenance { message }), since) => {
+                maintenance.healthy_since = None;
+                if since.is_some() {
+                    return;
+                }
+
+                maintenance.since = Some(now);
+                if let Some(exchange_blocker) = self.exchange_blocker.upgrade() {
+                    exchange_blocker.block(
+                        self.exchange_account_id,
+                        EXCHANGE_MAINTENANCE,
+                        BlockType::Manual,
+                    );
+                }
+                alert(
+                    AlertSeverity::Warning,
+                    AlertKind::ExchangeMaintenance,
+                    &self.exchange_account_id.to_string(),
+                    format!(
+                        "Trading on {} is paused because exchange is under maintenance: {message}",
+                        self.exchange_account_id
+                    ),
+                );
+            }
+            (Some(SystemStatus::Normal), Some(since)) => {
+                let healthy_since = *maintenance.healthy_since.get_or_insert(now);
+                if now - healthy_since < healthy_period {
+                    return;
+                }
+
+                *maintenance = MaintenanceState::default();
+                if let Some(exchange_blocker) = self.exchange_blocker.upgrade() {
+                    exchange_blocker.unblock(self.exchange_account_id, EXCHANGE_MAINTENANCE);
+                }
+                alert(
+                    AlertSeverity::Info,
+                    AlertKind::ExchangeMaintenance,
+                    &self.exchange_account_id.to_string(),
+                    format!(
+                        "Trading on {} is resumed after maintenance started at {since}",
+                        self.exchange_account_id
+                    ),
+                );
+            }
+            (None, Some(_)) => maintenance.healthy_since = None,
+            (Some(SystemStatus::Normal), None) | (None, None) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::test_helper::get_test_exchange;
+    use crate::infrastructure::init_lifetime_manager;
+    use chrono::Utc;
+
+    #[tokio::test]
+    async fn trading_is_resumed_after_healthy_period() {
+        let _ = init_lifetime_manager();
+        let (exchange, _event_receiver) = get_test_exchange(false);
+        let healthy_period = Duration::minutes(5);
+        let maintenance = SystemStatus::Maintenance {
+            message: "system maintenance".to_owned(),
+        };
+        let start = Utc::now();
+        let update = |status: Option<&SystemStatus>, minutes: i64| {
+            exchange.update_maintenance_state(
+                status,
+                start + Duration::minutes(minutes),
+                healthy_period,
+            );
+            exchange.is_under_maintenance()
+        };
+
+        assert!(!update(Some(&SystemStatus::Normal), 0));
+        assert!(update(Some(&maintenance), 1));
+        assert!(update(Some(&SystemStatus::Normal), 2));
+        // failed status request restarts healthy period
+        assert!(update(None, 3));
+        assert!(update(Some(&SystemStatus::Normal), 4));
+        assert!(update(Some(&SystemStatus::Normal), 8));
+        assert!(!update(Some(&SystemStatus::Normal), 9));
+    }
+}
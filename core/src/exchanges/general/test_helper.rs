@@ -11,7 +11,7 @@ use crate::{
             exchange::Exchange,
             features::{
                 ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption,
-                RestFillsFeatures, WebSocketOptions,
+                RestFillsFeatures, RestFillsType, WebSocketOptions,
             },
         },
         timeouts::{
@@ -24,18 +24,24 @@ use crate::{
 };
 use anyhow::Result;
 use async_trait::async_trait;
-use chrono::Duration;
+use chrono::{Duration, Utc};
 use dashmap::DashMap;
 use futures::executor::block_on;
-use mmb_domain::events::{AllowedEventSourceType, ExchangeBalancesAndPositions, ExchangeEvent};
+use mmb_domain::events::{
+    AllowedEventSourceType, EventSourceType, ExchangeBalancesAndPositions, ExchangeEvent,
+};
 use mmb_domain::exchanges::commission::{Commission, CommissionForType};
 use mmb_domain::exchanges::symbol::{BeforeAfter, Precision, Symbol};
 use mmb_domain::market::{
-    CurrencyCode, CurrencyId, CurrencyPair, ExchangeAccountId, SpecificCurrencyPair,
+    CurrencyCode, CurrencyId, CurrencyPair, ExchangeAccountId, ExchangeErrorType,
+    SpecificCurrencyPair,
 };
+use mmb_domain::order::group::{OrderGroupId, OrderGroupType};
 use mmb_domain::order::pool::{OrderRef, OrdersPool};
-use mmb_domain::order::snapshot::{Amount, ExchangeOrderId, OrderOptions, Price};
-use mmb_domain::order::snapshot::{ClientOrderId, OrderInfo, OrderRole, OrderSide, OrderSnapshot};
+use mmb_domain::order::snapshot::{Amount, ExchangeOrderId, OrderHeader, OrderOptions, Price};
+use mmb_domain::order::snapshot::{
+    ClientOrderId, OrderInfo, OrderRole, OrderSide, OrderSnapshot, OrderStatus,
+};
 use mmb_domain::position::{ActivePosition, ClosedPosition};
 use rust_decimal_macros::dec;
 use tokio::sync::broadcast;
@@ -46,15 +52,32 @@ use crate::exchanges::exchange_blocker::ExchangeBlocker;
 use crate::exchanges::general::exchange::RequestResult;
 use crate::exchanges::general::order::cancel::CancelOrderResult;
 use crate::exchanges::general::order::create::CreateOrderResult;
+use crate::exchanges::general::order::group::CreateOrderGroupResult;
 use crate::exchanges::timeouts::requests_timeout_manager_factory::RequestsTimeoutManagerFactory;
 use crate::exchanges::traits::{
     ExchangeError, HandleMetricsCb, HandleOrderFilledCb, SendWebsocketMessageCb,
 };
 use mmb_utils::{cancellation_token::CancellationToken, hashmap, DateTime};
+use parking_lot::Mutex;
 
 use super::order::get_order_trades::OrderTrade;
 
-pub struct TestClient;
+/// Exchange client for UT with scripted responses of requests which are used by tests
+#[derive(Default)]
+pub struct TestClient {
+    /// Orders returned by `get_open_orders`
+    pub open_orders: Mutex<Vec<OrderInfo>>,
+    /// Exchange order ids of orders which cancellation was requested
+    pub cancel_requests: Mutex<Vec<ExchangeOrderId>>,
+    /// Trades returned by `get_my_trades`
+    pub my_trades: Mutex<Vec<OrderTrade>>,
+    /// Orders returned by `get_order_info`, other orders aren't found
+    pub order_infos: Mutex<Vec<OrderInfo>>,
+    /// Result of `create_order_group`, native order groups are supported only if it is specified
+    pub order_group_result: Mutex<Option<Result<CreateOrderGroupResult, ExchangeError>>>,
+    settings: ExchangeSettings,
+    order_cancelled_callback: Option<OrderCancelledCb>,
+}
 
 #[async_trait]
 impl ExchangeClient for TestClient {
@@ -64,18 +87,53 @@ impl ExchangeClient for TestClient {
 
     async fn cancel_order(
         &self,
-        _order: &OrderRef,
-        _exchange_order_id: &ExchangeOrderId,
+        order: &OrderRef,
+        exchange_order_id: &ExchangeOrderId,
     ) -> CancelOrderResult {
-        unimplemented!("doesn't need in UT")
+        self.cancel_requests.lock().push(exchange_order_id.clone());
+        self.open_orders
+            .lock()
+            .retain(|x| &x.exchange_order_id != exchange_order_id);
+
+        // cancellation is confirmed by web socket notification like on real exchange
+        let client_order_id = order.client_order_id();
+        if let Some(callback) = &self.order_cancelled_callback {
+            callback(
+                client_order_id.clone(),
+                exchange_order_id.clone(),
+                EventSourceType::WebSocket,
+            );
+        }
+
+        CancelOrderResult::succeed(client_order_id, EventSourceType::Rest, None)
     }
 
     async fn cancel_all_orders(&self, _currency_pair: CurrencyPair) -> Result<()> {
         unimplemented!("doesn't need in UT")
     }
 
+    fn supports_native_order_group(
+        &self,
+        _group_type: OrderGroupType,
+        _legs: &[OrderHeader],
+    ) -> bool {
+        self.order_group_result.lock().is_some()
+    }
+
+    async fn create_order_group(
+        &self,
+        _group_id: OrderGroupId,
+        _group_type: OrderGroupType,
+        _legs: &[OrderRef],
+    ) -> Result<CreateOrderGroupResult, ExchangeError> {
+        self.order_group_result
+            .lock()
+            .take()
+            .expect("order group result should be scripted in test")
+    }
+
     async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
-        unimplemented!("doesn't need in UT")
+        Ok(self.open_orders.lock().clone())
     }
 
     async fn get_open_orders_by_currency_pair(
@@ -85,8 +143,20 @@ impl ExchangeClient for TestClient {
         unimplemented!("doesn't need in UT")
     }
 
-    async fn get_order_info(&self, _order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
-        unimplemented!("doesn't need in UT")
+    async fn get_order_info(&self, order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
+        let exchange_order_id = order.exchange_order_id();
+        self.order_infos
+            .lock()
+            .iter()
+            .find(|x| Some(&x.exchange_order_id) == exchange_order_id.as_ref())
+            .cloned()
+            .ok_or_else(|| {
+                ExchangeError::new(
+                    ExchangeErrorType::OrderNotFound,
+                    "Order isn't found".to_owned(),
+                    None,
+                )
+            })
     }
 
     async fn close_position(
@@ -110,7 +180,7 @@ impl ExchangeClient for TestClient {
         _symbol: &Symbol,
         _last_date_time: Option<DateTime>,
     ) -> RequestResult<Vec<OrderTrade>> {
-        unimplemented!("doesn't need in UT")
+        RequestResult::Success(self.my_trades.lock().clone())
     }
 
     async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>> {
@@ -147,7 +217,9 @@ impl Support for TestClient {
 
     fn set_order_created_callback(&mut self, _callback: OrderCreatedCb) {}
 
-    fn set_order_cancelled_callback(&mut self, _callback: OrderCancelledCb) {}
+    fn set_order_cancelled_callback(&mut self, callback: OrderCancelledCb) {
+        self.order_cancelled_callback = Some(callback);
+    }
 
     fn set_handle_order_filled_callback(&mut self, _callback: HandleOrderFilledCb) {}
 
@@ -190,7 +262,7 @@ impl Support for TestClient {
     }
 
     fn get_settings(&self) -> &ExchangeSettings {
-        unimplemented!("doesn't need in UT")
+        &self.settings
     }
 }
 
@@ -208,8 +280,23 @@ pub(crate) fn get_test_exchange_by_currency_codes_and_amount_code(
     quote_currency_code: &str,
     amount_currency_code: &str,
 ) -> (Arc<Exchange>, broadcast::Receiver<ExchangeEvent>) {
+    let symbol = get_test_symbol(
+        is_derivative,
+        base_currency_code,
+        quote_currency_code,
+        amount_currency_code,
+    );
+    get_test_exchange_with_symbol(symbol)
+}
+
+fn get_test_symbol(
+    is_derivative: bool,
+    base_currency_code: &str,
+    quote_currency_code: &str,
+    amount_currency_code: &str,
+) -> Arc<Symbol> {
     let price_tick = dec!(0.1);
-    let symbol = Arc::new(Symbol::new(
+    Arc::new(Symbol::new(
         is_derivative,
         base_currency_code.into(),
         base_currency_code.into(),
@@ -224,8 +311,16 @@ pub(crate) fn get_test_exchange_by_currency_codes_and_amount_code(
         None,
         Precision::ByTick { tick: price_tick },
         Precision::ByTick { tick: dec!(0) },
-    ));
-    get_test_exchange_with_symbol(symbol)
+    ))
+}
+
+/// Test exchange which requests fills of orders from exchange by specified way
+pub(crate) fn get_test_exchange_with_rest_fills(
+    rest_fills_type: RestFillsType,
+) -> (Arc<Exchange>, broadcast::Receiver<ExchangeEvent>) {
+    let symbol = get_test_symbol(false, "PHB", "BTC", "PHB");
+    let exchange_account_id = ExchangeAccountId::new("local_exchange_account_id", 0);
+    create_test_exchange(symbol, exchange_account_id, rest_fills_type)
 }
 
 pub(crate) fn get_test_exchange_by_currency_codes(
@@ -255,11 +350,19 @@ pub(crate) fn get_test_exchange_with_symbol(
 pub(crate) fn get_test_exchange_with_symbol_and_id(
     symbol: Arc<Symbol>,
     exchange_account_id: ExchangeAccountId,
+) -> (Arc<Exchange>, broadcast::Receiver<ExchangeEvent>) {
+    create_test_exchange(symbol, exchange_account_id, RestFillsType::None)
+}
+
+fn create_test_exchange(
+    symbol: Arc<Symbol>,
+    exchange_account_id: ExchangeAccountId,
+    rest_fills_type: RestFillsType,
 ) -> (Arc<Exchange>, broadcast::Receiver<ExchangeEvent>) {
     let lifetime_manager = AppLifetimeManager::new(CancellationToken::new());
     let (tx, rx) = broadcast::channel(10);
 
-    let exchange_client = Box::new(TestClient);
+    let exchange_client = Box::<TestClient>::default();
     let referral_reward = dec!(40);
     let commission = Commission::new(
         CommissionForType::new(dec!(0.1), referral_reward),
@@ -274,8 +377,8 @@ pub(crate) fn get_test_exchange_with_symbol_and_id(
     );
     let timeout_managers = hashmap![exchange_account_id => request_timeout_manager];
     let timeout_manager = TimeoutManager::new(timeout_managers);
-    let event_recorder =
-        block_on(EventRecorder::start(None, None)).expect("Failure start EventRecorder");
+    let event_recorder = block_on(EventRecorder::start(None, None, Default::default()))
+        .expect("Failure start EventRecorder");
 
     let exchange = Exchange::new(
         exchange_account_id,
@@ -283,7 +386,7 @@ pub(crate) fn get_test_exchange_with_symbol_and_id(
         OrdersPool::new(),
         ExchangeFeatures::new(
             OpenOrdersType::AllCurrencyPair,
-            RestFillsFeatures::default(),
+            RestFillsFeatures::new(rest_fills_type),
             OrderFeatures {
                 supports_get_order_info_by_client_order_id: true,
                 ..OrderFeatures::default()
@@ -317,6 +420,15 @@ pub(crate) fn get_test_exchange_with_symbol_and_id(
     (exchange, rx)
 }
 
+/// Scripted client of exchange created by test helpers
+pub(crate) fn test_client(exchange: &Exchange) -> &TestClient {
+    exchange
+        .exchange_client
+        .as_any()
+        .downcast_ref::<TestClient>()
+        .expect("exchange client should be TestClient")
+}
+
 pub(crate) fn create_order_ref(
     client_order_id: &ClientOrderId,
     role: Option<OrderRole>,
@@ -348,6 +460,41 @@ pub(crate) fn create_order_ref(
     order_ref.clone()
 }
 
+/// Adds buy order of the first symbol of exchange which is already created on exchange
+pub(crate) fn add_created_order(
+    exchange: &Exchange,
+    exchange_order_id: &ExchangeOrderId,
+    price: Price,
+    amount: Amount,
+) -> OrderRef {
+    let currency_pair = exchange
+        .symbols
+        .iter()
+        .next()
+        .expect("exchange should have symbol")
+        .currency_pair();
+    let snapshot = OrderSnapshot::with_params(
+        ClientOrderId::unique_id(),
+        OrderOptions::liquidation(price),
+        None,
+        exchange.exchange_account_id,
+        currency_pair,
+        amount,
+        OrderSide::Buy,
+        None,
+        "StrategyInUnitTests",
+    );
+
+    let order = exchange.orders.add_snapshot_initial(&snapshot);
+    order.fn_mut(|x| {
+        x.props.exchange_order_id = Some(exchange_order_id.clone());
+        x.set_status(OrderStatus::Created, Utc::now());
+    });
+    try_add_snapshot_by_exchange_id(exchange, &order);
+
+    order
+}
+
 pub(crate) fn try_add_snapshot_by_exchange_id(exchange: &Exchange, order_ref: &OrderRef) {
     if let Some(exchange_order_id) = order_ref.exchange_order_id() {
         let _ = exchange
@@ -0,0 +1,231 @@
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::request_type::RequestType;
+use anyhow::{bail, ensure, Context, Result};
+use mmb_domain::market::CurrencyCode;
+use mmb_domain::order::snapshot::Amount;
+use mmb_domain::transfer::{
+    AccountTransfer, ExchangeFundsMovementId, ExchangeTransferId, FundsMovement, TransferStatus,
+    Withdrawal,
+};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+const TRANSFER_STATUS_POLLING_INTERVAL: Duration = Duration::from_secs(2);
+const TRANSFER_COMPLETION_TIMEOUT: Duration = Duration::from_secs(300);
+
+impl Exchange {
+    /// Transfers currency to another account of the same exchange and waits until the transfer
+    /// is completed. Request is sent with API key of this account, so on exchanges which allow
+    /// transfers only by master key this account should be the master one.
+    /// Balances of both accounts are requested from exchange and updated in balance manager
+    /// after the transfer is completed
+    pub async fn transfer_to_account(
+        self: &Arc<Self>,
+        to: &Arc<Exchange>,
+        currency_code: CurrencyCode,
+        amount: Amount,
+        cancellation_token: CancellationToken,
+    ) -> Result<ExchangeTransferId> {
+        let transfer = AccountTransfer {
+            from: self.exchange_account_id,
+            to: to.exchange_account_id,
+            currency_code,
+            amount,
+        };
+        self.validate_transfer(&transfer)?;
+
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::Transfer,
+                None,
+                cancellation_token.clone(),
+            )
+            .await;
+
+        let from_settings = self.exchange_client.get_settings();
+        let to_settings = to.exchange_client.get_settings();
+        let transfer_id = self
+            .exchange_client
+            .transfer_between_accounts(
+                &transfer,
+                from_settings.sub_account.as_deref(),
+                to_settings.sub_account.as_deref(),
+            )
+            .await
+            .with_context(|| format!("Failed to create transfer {transfer:?}"))?;
+
+        log::info!("Transfer {transfer_id} {transfer:?} was created");
+
+        self.wait_transfer_completion(&transfer_id, cancellation_token.clone())
+            .await
+            .with_context(|| format!("Transfer {transfer_id} {transfer:?} wasn't completed"))?;
+
+        log::info!("Transfer {transfer_id} was completed");
+
+        for exchange in [self, to] {
+            if let Err(err) = exchange
+                .update_balance_from_exchange(cancellation_token.clone())
+                .await
+            {
+                log::error!(
+                    "Failed to update balance of {} after transfer {transfer_id}: {err:?}",
+                    exchange.exchange_account_id
+                );
+            }
+        }
+
+        Ok(transfer_id)
+    }
+
+    fn validate_transfer(&self, transfer: &AccountTransfer) -> Result<()> {
+        ensure!(
+            transfer.amount > Amount::ZERO,
+            "Transfer amount should be positive: {transfer:?}"
+        );
+        ensure!(
+            transfer.from.exchange_id == transfer.to.exchange_id,
+            "Transfer is possible only between accounts of the same exchange: {transfer:?}"
+        );
+        ensure!(
+            transfer.from != transfer.to,
+            "Transfer to the same account isn't possible: {transfer:?}"
+        );
+        ensure!(
+            !self.is_watch_only(),
+            "Unable to transfer from watch-only account: {transfer:?}"
+        );
+
+        Ok(())
+    }
+
+    async fn wait_transfer_completion(
+        &self,
+        transfer_id: &ExchangeTransferId,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let deadline = self.clock.now()
+            + chrono::Duration::from_std(TRANSFER_COMPLETION_TIMEOUT)
+                .expect("valid transfer completion timeout");
+
+        loop {
+            if cancellation_token.is_cancellation_requested() {
+                bail!("Waiting of transfer {transfer_id} completion was cancelled");
+            }
+
+            self.timeout_manager
+                .reserve_when_available(
+                    self.exchange_account_id,
+                    RequestType::GetTransferStatus,
+                    None,
+                    cancellation_token.clone(),
+                )
+                .await;
+
+            match self.exchange_client.get_transfer_status(transfer_id).await {
+                Ok(TransferStatus::Completed) => return Ok(()),
+                Ok(TransferStatus::Failed) => bail!("Transfer {transfer_id} failed on exchange"),
+                Ok(TransferStatus::Pending) => {}
+                Err(err) => log::warn!("Failed to get status of transfer {transfer_id}: {err:?}"),
+            }
+
+            if self.clock.now() >= deadline {
+                bail!(
+                    "Transfer {transfer_id} is still pending after {TRANSFER_COMPLETION_TIMEOUT:?}"
+                );
+            }
+
+            tokio::select! {
+                _ = self.clock.sleep(TRANSFER_STATUS_POLLING_INTERVAL) => {}
+                _ = cancellation_token.when_cancelled() => {}
+            }
+        }
+    }
+
+    /// Initiates withdrawal to external address. Withdrawal is only created on exchange, its
+    /// completion is tracked by `Treasury`
+    pub async fn withdraw(
+        &self,
+        withdrawal: &Withdrawal,
+        cancellation_token: CancellationToken,
+    ) -> Result<ExchangeFundsMovementId> {
+        ensure!(
+            withdrawal.amount > Amount::ZERO,
+            "Withdrawal amount should be positive: {withdrawal:?}"
+        );
+        ensure!(
+            !self.is_watch_only(),
+            "Unable to withdraw from watch-only account {}: {withdrawal:?}",
+            self.exchange_account_id
+        );
+
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::Withdraw,
+                None,
+                cancellation_token,
+            )
+            .await;
+
+        let withdrawal_id = self
+            .exchange_client
+            .withdraw(withdrawal)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to withdraw {withdrawal:?} from {}",
+                    self.exchange_account_id
+                )
+            })?;
+
+        log::info!(
+            "Withdrawal {withdrawal_id} {withdrawal:?} was created on {}",
+            self.exchange_account_id
+        );
+
+        Ok(withdrawal_id)
+    }
+
+    pub async fn get_funds_movements(
+        &self,
+        from_datetime: DateTime,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<FundsMovement>> {
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::GetFundsMovements,
+                None,
+                cancellation_token,
+            )
+            .await;
+
+        self.exchange_client
+            .get_funds_movements(from_datetime)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to get deposits and withdrawals of {}",
+                    self.exchange_account_id
+                )
+            })
+    }
+
+    /// Requests balances from exchange and replaces them in balance manager
+    pub(crate) async fn update_balance_from_exchange(
+        self: &Arc<Self>,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let balance_manager = match self.balance_manager.lock().as_ref().and_then(Weak::upgrade) {
+            Some(balance_manager) => balance_manager,
+            None => return Ok(()),
+        };
+
+        let balances_and_positions = self.get_balance(cancellation_token).await?;
+        let mut balance_manager = balance_manager.lock();
+        balance_manager.update_exchange_balance(self.exchange_account_id, &balances_and_positions)
+    }
+}
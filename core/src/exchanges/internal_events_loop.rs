@@ -17,12 +17,14 @@ use mmb_domain::order::event::OrderEventType;
 use mmb_domain::order::snapshot::OrderType;
 use mmb_domain::order_book::event::OrderBookEvent;
 
-pub(crate) struct InternalEventsLoop {
+/// Updates order book tops, balance reservations and order notifications of exchanges by engine
+/// events
+pub struct InternalEventsLoop {
     work_finished_receiver: Mutex<Option<oneshot::Receiver<Result<()>>>>,
 }
 
 impl InternalEventsLoop {
-    pub(crate) fn new() -> Arc<Self> {
+    pub fn new() -> Arc<Self> {
         Arc::new(InternalEventsLoop {
             work_finished_receiver: Default::default(),
         })
@@ -61,11 +63,18 @@ impl InternalEventsLoop {
                         .get(&target_eai)
                         .with_expect(|| format!("Failed to get Exchange for {}", target_eai));
 
+                    exchange.handle_order_group_leg_event(&order_event);
+                    exchange.handle_order_reservation_event(&order_event);
+                    exchange.handle_external_order_event(&order_event);
+
                     match order_event.event_type {
                         OrderEventType::CreateOrderSucceeded => {
                             exchange.order_created_notify(&order_event.order);
                         }
-                        OrderEventType::CreateOrderFailed => {
+                        OrderEventType::CreateOrderFailed
+                        | OrderEventType::RejectedByRisk
+                        | OrderEventType::RejectedByBalance
+                        | OrderEventType::RejectedByValidation => {
                             exchange.order_created_notify(&order_event.order);
                             exchange.order_finished_notify(&order_event.order);
                         }
@@ -82,6 +91,13 @@ impl InternalEventsLoop {
                 ExchangeEvent::BalanceUpdate(_) => {}
                 ExchangeEvent::LiquidationPrice(_) => {}
                 ExchangeEvent::Trades(_) => {}
+                ExchangeEvent::TradingHalted(_) => {}
+                ExchangeEvent::OrderGroupEvent(_) => {}
+                ExchangeEvent::BasketEvent(_) => {}
+                ExchangeEvent::Connectivity(_) => {}
+                ExchangeEvent::StreamStale(_) => {}
+                ExchangeEvent::SymbolStatus(_) => {}
+                ExchangeEvent::MarketDataSuspect(_) => {}
             }
         }
     }
@@ -105,13 +121,12 @@ fn update_order_book_top_for_exchange(
                 .map(|(price, amount)| PriceLevel { price, amount }),
         };
 
-        exchanges_map
-            .get(&market_account_id.exchange_account_id)
-            .map(|exchange| {
-                exchange
-                    .order_book_top
-                    .insert(market_account_id.currency_pair, order_book_top)
-            });
+        if let Some(exchange) = exchanges_map.get(&market_account_id.exchange_account_id) {
+            exchange.order_book_updated(market_account_id.currency_pair);
+            let _ = exchange
+                .order_book_top
+                .insert(market_account_id.currency_pair, order_book_top);
+        }
     }
 }
 
@@ -1,9 +1,14 @@
+pub mod account_router;
 pub mod block_reasons;
 pub mod common;
 pub mod exchange_blocker;
+pub mod fault_injection;
 pub mod general;
 pub mod hosts;
-pub(crate) mod internal_events_loop;
+pub mod internal_events_loop;
 pub mod rest_client;
+pub mod sequence_tracker;
+pub mod stream_watchdog;
+pub mod symbol_service;
 pub mod timeouts;
 pub mod traits;
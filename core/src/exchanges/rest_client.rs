@@ -1,4 +1,5 @@
 use crate::exchanges::traits::ExchangeError;
+use crate::telemetry::record_rest_request;
 use anyhow::Result;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use hyper::client::HttpConnector;
@@ -13,6 +14,7 @@ use std::borrow::Cow;
 use std::convert::TryInto;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter, Write};
+use std::time::Instant;
 use uuid::Uuid;
 
 pub type QueryKey = &'static str;
@@ -24,6 +26,17 @@ pub trait RestHeaders {
         uri: &Uri,
         request_type: RequestType,
     ) -> Builder;
+
+    /// Adds headers for request with body. Should be overridden by exchanges which sign body of requests
+    fn add_specific_headers_with_body(
+        &self,
+        builder: Builder,
+        uri: &Uri,
+        request_type: RequestType,
+        _body: &[u8],
+    ) -> Builder {
+        self.add_specific_headers(builder, uri, request_type)
+    }
 }
 
 #[derive(Default)]
@@ -246,6 +259,7 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static, SpecHeaders: RestHeaders
                 format!("Error during creation of http {request_type} request {request_id}")
             });
 
+        let started = Instant::now();
         let response = self.client.request(req).await;
 
         self.handle_response(
@@ -254,6 +268,7 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static, SpecHeaders: RestHeaders
             action_name,
             log_args,
             request_id,
+            started,
         )
         .await
     }
@@ -279,6 +294,7 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static, SpecHeaders: RestHeaders
                 format!("Error during creation of http {request_type} request {request_id}")
             });
 
+        let started = Instant::now();
         let response = self.client.request(req).await;
 
         self.handle_response(
@@ -287,6 +303,7 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static, SpecHeaders: RestHeaders
             action_name,
             log_args,
             request_id,
+            started,
         )
         .await
     }
@@ -303,9 +320,10 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static, SpecHeaders: RestHeaders
 
         let builder = Request::builder().method(Method::POST);
         let request_type = RequestType::Post;
+        let body = query.as_deref().unwrap_or_default();
         let req = self
             .headers
-            .add_specific_headers(builder, &uri, request_type)
+            .add_specific_headers_with_body(builder, &uri, request_type, body)
             .uri(uri)
             .header(hyper::header::CONNECTION, KEEP_ALIVE)
             .body(match query {
@@ -316,6 +334,7 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static, SpecHeaders: RestHeaders
                 format!("Error during creation of http {request_type} request {request_id}")
             });
 
+        let started = Instant::now();
         let response = self.client.request(req).await;
 
         self.handle_response(
@@ -324,6 +343,7 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static, SpecHeaders: RestHeaders
             action_name,
             log_args,
             request_id,
+            started,
         )
         .await
     }
@@ -349,6 +369,7 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static, SpecHeaders: RestHeaders
                 format!("Error during creation of http {request_type} request {request_id}")
             });
 
+        let started = Instant::now();
         let response = self.client.request(req).await;
 
         self.handle_response(
@@ -357,6 +378,7 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static, SpecHeaders: RestHeaders
             action_name,
             log_args,
             request_id,
+            started,
         )
         .await
     }
@@ -368,6 +390,7 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static, SpecHeaders: RestHeaders
         action_name: &'static str,
         log_args: String,
         request_id: Uuid,
+        started: Instant,
     ) -> Result<RestResponse, ExchangeError> {
         let response = response.with_expect(|| {
             format!("Unable to send {rest_action} request, request_id: {request_id}")
@@ -383,6 +406,14 @@ impl<ErrHandler: ErrorHandler + Send + Sync + 'static, SpecHeaders: RestHeaders
             .with_expect(|| format!("Unable to convert response content from utf8: {request_bytes:?}, request_id: {request_id}"))
             .to_owned();
 
+        record_rest_request(
+            self.error_handler.exchange_account_id,
+            action_name,
+            rest_action,
+            status.as_u16(),
+            started.elapsed(),
+        );
+
         let request_outcome = RestResponse { status, content };
 
         let err_handler_data = &self.error_handler;
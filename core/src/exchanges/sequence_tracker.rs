@@ -0,0 +1,103 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+/// Updates of private stream missed between two received ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceGap {
+    pub stream: String,
+    pub expected: u64,
+    pub received: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceCheck {
+    InOrder,
+    /// Update was already received, e.g. it's duplicated by exchange
+    Outdated,
+    Gap(SequenceGap),
+}
+
+/// Tracks sequence numbers (update ids) of private streams for connectors which receive them.
+/// Sequence of every stream is expected to be incremented by one with every update
+#[derive(Debug, Default)]
+pub struct SequenceTracker {
+    last_sequences: Mutex<HashMap<String, u64>>,
+}
+
+impl SequenceTracker {
+    pub fn check(&self, stream: &str, sequence: u64) -> SequenceCheck {
+        let mut last_sequences = self.last_sequences.lock();
+        let last_sequence = match last_sequences.get_mut(stream) {
+            Some(last_sequence) => last_sequence,
+            None => {
+                // nothing is known about updates before the first one
+                let _ = last_sequences.insert(stream.to_owned(), sequence);
+                return SequenceCheck::InOrder;
+            }
+        };
+
+        if sequence <= *last_sequence {
+            return SequenceCheck::Outdated;
+        }
+
+        let expected = *last_sequence + 1;
+        *last_sequence = sequence;
+        match sequence == expected {
+            true => SequenceCheck::InOrder,
+            false => SequenceCheck::Gap(SequenceGap {
+                stream: stream.to_owned(),
+                expected,
+                received: sequence,
+            }),
+        }
+    }
+
+    /// Should be called on reconnection because exchanges usually start sequences of new
+    /// connection from scratch
+    pub fn reset(&self) {
+        self.last_sequences.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gap_is_detected() {
+        let tracker = SequenceTracker::default();
+
+        assert_eq!(tracker.check("orders", 10), SequenceCheck::InOrder);
+        assert_eq!(tracker.check("orders", 11), SequenceCheck::InOrder);
+        assert_eq!(
+            tracker.check("orders", 14),
+            SequenceCheck::Gap(SequenceGap {
+                stream: "orders".to_owned(),
+                expected: 12,
+                received: 14,
+            })
+        );
+        assert_eq!(tracker.check("orders", 15), SequenceCheck::InOrder);
+    }
+
+    #[test]
+    fn outdated_update_is_not_gap() {
+        let tracker = SequenceTracker::default();
+        tracker.check("orders", 10);
+
+        assert_eq!(tracker.check("orders", 10), SequenceCheck::Outdated);
+        assert_eq!(tracker.check("orders", 9), SequenceCheck::Outdated);
+        // streams are tracked independently
+        assert_eq!(tracker.check("fills", 1), SequenceCheck::InOrder);
+    }
+
+    #[test]
+    fn sequence_is_restarted_after_reset() {
+        let tracker = SequenceTracker::default();
+        tracker.check("orders", 10);
+
+        tracker.reset();
+
+        assert_eq!(tracker.check("orders", 1), SequenceCheck::InOrder);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::settings::StreamWatchdogSettings;
+use chrono::Duration;
+use dashmap::DashMap;
+use mmb_domain::market::CurrencyPair;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+
+/// Stream which was silent longer than allowed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleStream {
+    /// `None` if whole websocket connection is silent
+    pub currency_pair: Option<CurrencyPair>,
+    pub last_update_time: DateTime,
+}
+
+/// Tracks time of last websocket message of exchange account and last order book update of every
+/// currency pair, so frozen data can be detected
+pub struct StreamWatchdog {
+    max_exchange_silence: Duration,
+    max_stream_silence: Option<Duration>,
+    last_message_time: Mutex<DateTime>,
+    last_stream_update_times: DashMap<CurrencyPair, DateTime>,
+}
+
+impl StreamWatchdog {
+    pub fn new(settings: &StreamWatchdogSettings, now: DateTime) -> Self {
+        Self {
+            max_exchange_silence: Duration::milliseconds(settings.max_exchange_silence_ms as i64),
+            max_stream_silence: settings
+                .max_stream_silence_ms
+                .map(|x| Duration::milliseconds(x as i64)),
+            last_message_time: Mutex::new(now),
+            last_stream_update_times: DashMap::new(),
+        }
+    }
+
+    /// Silence is checked twice per shortest threshold, so it's detected with delay of half
+    /// threshold at most
+    pub fn check_interval(&self) -> std::time::Duration {
+        let min_silence = self
+            .max_stream_silence
+            .map_or(self.max_exchange_silence, |x| {
+                x.min(self.max_exchange_silence)
+            });
+
+        (min_silence / 2)
+            .to_std()
+            .unwrap_or(std::time::Duration::ZERO)
+            .max(std::time::Duration::from_millis(1))
+    }
+
+    pub fn message_received(&self, now: DateTime) {
+        *self.last_message_time.lock() = now;
+    }
+
+    /// Streams are tracked since their first update
+    pub fn stream_updated(&self, currency_pair: CurrencyPair, now: DateTime) {
+        let _ = self.last_stream_update_times.insert(currency_pair, now);
+    }
+
+    /// Silence of all streams is counted from now, e.g. after reconnection
+    pub fn restart(&self, now: DateTime) {
+        self.message_received(now);
+        self.last_stream_update_times
+            .iter_mut()
+            .for_each(|mut x| *x.value_mut() = now);
+    }
+
+    /// Returns silent connection only if the whole connection is silent, otherwise returns
+    /// silent order book streams
+    pub fn find_stale_streams(&self, now: DateTime) -> Vec<StaleStream> {
+        let last_message_time = *self.last_message_time.lock();
+        if now - last_message_time > self.max_exchange_silence {
+            return vec![StaleStream {
+                currency_pair: None,
+                last_update_time: last_message_time,
+            }];
+        }
+
+        let max_stream_silence = match self.max_stream_silence {
+            None => return vec![],
+            Some(x) => x,
+        };
+
+        self.last_stream_update_times
+            .iter()
+            .filter(|x| now - *x.value() > max_stream_silence)
+            .map(|x| StaleStream {
+                currency_pair: Some(*x.key()),
+                last_update_time: *x.value(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn watchdog(now: DateTime) -> StreamWatchdog {
+        let settings = StreamWatchdogSettings {
+            max_exchange_silence_ms: 10_000,
+            max_stream_silence_ms: Some(60_000),
+        };
+        StreamWatchdog::new(&settings, now)
+    }
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    #[test]
+    fn silent_connection_is_stale() {
+        let start = Utc::now();
+        let watchdog = watchdog(start);
+        watchdog.stream_updated(currency_pair(), start);
+
+        assert_eq!(
+            watchdog.find_stale_streams(start + Duration::seconds(10)),
+            vec![]
+        );
+        assert_eq!(
+            watchdog.find_stale_streams(start + Duration::seconds(11)),
+            vec![StaleStream {
+                currency_pair: None,
+                last_update_time: start,
+            }]
+        );
+        assert_eq!(watchdog.check_interval(), std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn silent_stream_is_stale_while_connection_is_alive() {
+        let start = Utc::now();
+        let watchdog = watchdog(start);
+        watchdog.stream_updated(currency_pair(), start);
+
+        let now = start + Duration::seconds(61);
+        watchdog.message_received(now);
+
+        assert_eq!(
+            watchdog.find_stale_streams(now),
+            vec![StaleStream {
+                currency_pair: Some(currency_pair()),
+                last_update_time: start,
+            }]
+        );
+
+        watchdog.restart(now);
+        assert_eq!(watchdog.find_stale_streams(now), vec![]);
+    }
+}
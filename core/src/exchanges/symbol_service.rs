@@ -0,0 +1,114 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use mmb_domain::exchanges::symbol::{Round, Symbol};
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::snapshot::{Amount, Price};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_by_timer;
+use crate::settings::SymbolRefreshSettings;
+
+/// Trading rules (price and amount precisions, min amount and cost, status) of traded symbols
+/// over all exchange accounts. Rules are downloaded on exchange creation and refreshed
+/// periodically. New orders are rejected for symbols which became halted or delisted
+pub struct SymbolService {
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+}
+
+impl SymbolService {
+    pub fn new(exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>) -> Arc<Self> {
+        Arc::new(Self { exchanges })
+    }
+
+    pub fn start_refreshing(self: &Arc<Self>, settings: &SymbolRefreshSettings) {
+        let interval = Duration::from_secs(settings.interval_secs.max(1));
+        let symbol_service = self.clone();
+        spawn_by_timer(
+            "Refresh symbols",
+            interval,
+            interval,
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            move || symbol_service.clone().refresh(),
+        );
+    }
+
+    pub async fn refresh(self: Arc<Self>) {
+        let exchanges = self
+            .exchanges
+            .iter()
+            .map(|x| x.value().clone())
+            .collect::<Vec<_>>();
+
+        for exchange in exchanges {
+            if let Err(err) = exchange.refresh_symbols().await {
+                log::warn!(
+                    "Failed to refresh symbols of {}: {err:?}",
+                    exchange.exchange_account_id
+                );
+            }
+        }
+    }
+
+    pub fn symbol(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+    ) -> Result<Arc<Symbol>> {
+        self.exchanges
+            .get(&exchange_account_id)
+            .with_context(|| format!("Unknown exchange account {exchange_account_id}"))?
+            .symbols
+            .get(&currency_pair)
+            .map(|x| x.value().clone())
+            .with_context(|| {
+                format!("Symbol {currency_pair} isn't traded on {exchange_account_id}")
+            })
+    }
+
+    /// Returns `false` for unknown symbols as well
+    pub fn is_trading(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+    ) -> bool {
+        self.symbol(exchange_account_id, currency_pair)
+            .is_ok_and(|x| x.is_trading())
+    }
+
+    pub fn price_round(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        price: Price,
+        round: Round,
+    ) -> Result<Price> {
+        let symbol = self.symbol(exchange_account_id, currency_pair)?;
+        Ok(symbol.price_round(price, round))
+    }
+
+    pub fn amount_round(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        amount: Amount,
+        round: Round,
+    ) -> Result<Amount> {
+        let symbol = self.symbol(exchange_account_id, currency_pair)?;
+        Ok(symbol.amount_round(amount, round))
+    }
+
+    /// Min order amount which satisfies both min amount and min cost of symbol for the price
+    pub fn min_amount(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        price: Price,
+    ) -> Result<Amount> {
+        self.symbol(exchange_account_id, currency_pair)?
+            .get_min_amount(price)
+    }
+}
@@ -3,7 +3,7 @@ use super::{
     pre_reserved_group::PreReservedGroup, request::Request,
     triggers::handle_trigger_trait::TriggerHandler,
 };
-use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::general::request_type::{RequestPriority, RequestType};
 use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
 use anyhow::{bail, Result};
 use chrono::Duration;
@@ -19,6 +19,8 @@ pub(super) struct InnerRequestsTimeoutManager {
     pub(super) requests: Vec<Request>,
     pub(super) pre_reserved_groups: Vec<PreReservedGroup>,
     pub(super) last_time: Option<DateTime>,
+    /// Count of requests reserved since start. Every request uses 1 of `requests_per_period`
+    pub(super) used_weight: u64,
 
     pub(super) group_was_reserved: Box<dyn Fn(PreReservedGroup) + Send>,
     pub(super) group_was_removed: Box<dyn Fn(PreReservedGroup) + Send>,
@@ -93,6 +95,7 @@ impl InnerRequestsTimeoutManager {
             .map_or_else(|idx| idx, |idx| idx);
 
         self.requests.insert(request_index, request.clone());
+        self.used_weight += 1;
 
         let last_request_start_time = self
             .requests
@@ -106,6 +109,46 @@ impl InnerRequestsTimeoutManager {
         request
     }
 
+    /// Moves the earliest low priority request scheduled after `current_time` to `free_time`, so
+    /// its time can be taken by high priority request. Returns the taken time
+    pub(super) fn preempt_low_priority_request(
+        &mut self,
+        current_time: DateTime,
+        free_time: DateTime,
+    ) -> Option<DateTime> {
+        let index = self.requests.iter().position(|x| {
+            x.allowed_start_time > current_time
+                && x.allowed_start_time < free_time
+                && x.group_id.is_none()
+                && x.request_type.priority() == RequestPriority::Low
+        })?;
+
+        let mut preempted = self.requests.remove(index);
+        let taken_time = preempted.allowed_start_time;
+        preempted.allowed_start_time = free_time;
+
+        let request_index = self
+            .requests
+            .partition_point(|x| x.allowed_start_time <= free_time);
+        self.requests.insert(request_index, preempted);
+
+        Some(taken_time)
+    }
+
+    /// Time left until allowed start time of request which could be postponed by preemption.
+    /// `None` if request can be started now
+    pub(super) fn get_postponed_delay(
+        &self,
+        request: &Request,
+        current_time: DateTime,
+    ) -> Option<Duration> {
+        self.requests
+            .iter()
+            .find(|x| x.id == request.id)
+            .map(|x| x.allowed_start_time - current_time)
+            .filter(|delay| *delay > Duration::zero())
+    }
+
     pub(super) fn handle_all_decreasing_triggers(&mut self) {
         let available_requests_count = self.get_all_available_requests_count();
 
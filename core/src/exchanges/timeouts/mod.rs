@@ -1,5 +1,6 @@
 pub mod inner_request_manager;
 pub mod more_or_equals_available_requests_count_trigger_scheduler;
+pub mod order_rate_limiter;
 pub mod pre_reserved_group;
 pub mod request;
 pub mod requests_timeout_manager;
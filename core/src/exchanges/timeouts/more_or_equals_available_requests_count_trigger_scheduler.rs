@@ -1,29 +1,39 @@
 use std::sync::Arc;
 
 use crate::infrastructure::spawn_future_ok;
+use crate::misc::clock::{Clock, SystemClock};
 use anyhow::Result;
-use chrono::{Duration, Utc};
+use chrono::Duration;
 use mmb_utils::time::ToStdExpected;
 use mmb_utils::{infrastructure::SpawnFutureFlags, DateTime};
 use parking_lot::Mutex;
-use tokio::time::sleep;
 
 pub type TriggerHandler = Mutex<Box<dyn FnMut() -> Result<()> + Send>>;
 
-#[derive(Default)]
 pub struct MoreOrEqualsAvailableRequestsCountTriggerScheduler {
     increasing_count_triggers: Mutex<Vec<Arc<MoreOrEqualsAvailableRequestsCountTrigger>>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for MoreOrEqualsAvailableRequestsCountTriggerScheduler {
+    fn default() -> Self {
+        Self::new(SystemClock::shared())
+    }
 }
 
 impl MoreOrEqualsAvailableRequestsCountTriggerScheduler {
-    pub fn utc_now() -> DateTime {
-        Utc::now()
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            increasing_count_triggers: Default::default(),
+            clock,
+        }
     }
 
     pub fn register_trigger(&self, count_threshold: usize, handler: TriggerHandler) {
         let trigger = Arc::new(MoreOrEqualsAvailableRequestsCountTrigger::new(
             count_threshold,
             handler,
+            self.clock.clone(),
         ));
         self.increasing_count_triggers.lock().push(trigger);
     }
@@ -34,7 +44,7 @@ impl MoreOrEqualsAvailableRequestsCountTriggerScheduler {
         last_request_time: DateTime,
         period_duration: Duration,
     ) {
-        let current_time = Self::utc_now();
+        let current_time = self.clock.now();
 
         for trigger in self.increasing_count_triggers.lock().iter() {
             trigger.clone().schedule_handler(
@@ -50,13 +60,15 @@ impl MoreOrEqualsAvailableRequestsCountTriggerScheduler {
 struct MoreOrEqualsAvailableRequestsCountTrigger {
     count_threshold: usize,
     handler: TriggerHandler,
+    clock: Arc<dyn Clock>,
 }
 
 impl MoreOrEqualsAvailableRequestsCountTrigger {
-    fn new(count_threshold: usize, handler: TriggerHandler) -> Self {
+    fn new(count_threshold: usize, handler: TriggerHandler, clock: Arc<dyn Clock>) -> Self {
         Self {
             count_threshold,
             handler,
+            clock,
         }
     }
 
@@ -89,7 +101,7 @@ impl MoreOrEqualsAvailableRequestsCountTrigger {
     async fn handle_inner(self: Arc<Self>, delay: Duration) {
         let delay_std = delay.to_std_expected();
 
-        sleep(delay_std).await;
+        self.clock.sleep(delay_std).await;
         if let Err(error) = (*self.handler.lock())() {
             log::error!("MoreOrEqualsAvailableRequestsCountTrigger: {:?}", error);
         }
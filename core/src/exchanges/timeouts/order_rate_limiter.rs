@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use mmb_domain::market::ExchangeAccountId;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::{DateTime, OPERATION_CANCELED_MSG};
+use parking_lot::Mutex;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::misc::clock::Clock;
+use crate::settings::{OrderRateLimitSettings, TokenBucketSettings};
+
+/// Kind of order request. Every kind is counted by its own token bucket
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum OrderRequestKind {
+    Create,
+    Cancel,
+}
+
+struct TokenBucket {
+    capacity: Decimal,
+    refill_per_second: Decimal,
+    // can be negative when requests are queued: every waiting request already took its token
+    tokens: Decimal,
+    last_refill_time: DateTime,
+}
+
+impl TokenBucket {
+    /// Returns `None` if bucket is never refilled. Such settings are rejected when settings are
+    /// loaded, see `OrderRateLimitSettings::validate`
+    fn new(settings: &TokenBucketSettings, now: DateTime) -> Option<Self> {
+        if settings.refill_per_second <= dec!(0) {
+            return None;
+        }
+
+        let capacity = Decimal::from(settings.capacity.max(1));
+        Some(TokenBucket {
+            capacity,
+            refill_per_second: settings.refill_per_second,
+            tokens: capacity,
+            last_refill_time: now,
+        })
+    }
+
+    fn refill(&mut self, now: DateTime) {
+        if now <= self.last_refill_time {
+            return;
+        }
+
+        let elapsed_secs = (now - self.last_refill_time)
+            .num_microseconds()
+            .map_or(Decimal::MAX, |x| Decimal::new(x, 6));
+        let refilled = elapsed_secs
+            .checked_mul(self.refill_per_second)
+            .unwrap_or(Decimal::MAX);
+        self.tokens = self.tokens.saturating_add(refilled).min(self.capacity);
+        self.last_refill_time = now;
+    }
+
+    /// Takes token and returns delay after which request can be sent
+    fn take(&mut self, now: DateTime) -> Duration {
+        self.refill(now);
+        self.tokens -= dec!(1);
+
+        if self.tokens >= dec!(0) {
+            return Duration::ZERO;
+        }
+
+        // rounded up, so request isn't sent before its token is refilled
+        let delay_micros = (-self.tokens)
+            .checked_div(self.refill_per_second)
+            .and_then(|x| x.checked_mul(dec!(1_000_000)))
+            .and_then(|x| x.ceil().to_u64())
+            .unwrap_or(u64::MAX);
+        Duration::from_micros(delay_micros)
+    }
+
+    /// Returns token taken by request which won't be sent
+    fn give_back(&mut self, now: DateTime) {
+        self.refill(now);
+        self.tokens = (self.tokens + dec!(1)).min(self.capacity);
+    }
+}
+
+/// Token bucket limiter of order requests for single exchange account.
+/// Requests exceeding the limit wait in queue until tokens are refilled
+pub struct OrderRateLimiter {
+    exchange_account_id: ExchangeAccountId,
+    buckets: HashMap<OrderRequestKind, Mutex<TokenBucket>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl OrderRateLimiter {
+    pub fn new(
+        exchange_account_id: ExchangeAccountId,
+        settings: &OrderRateLimitSettings,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        let now = clock.now();
+        let buckets = [
+            (OrderRequestKind::Create, &settings.create),
+            (OrderRequestKind::Cancel, &settings.cancel),
+        ]
+        .into_iter()
+        .filter_map(|(kind, bucket)| {
+            let bucket = TokenBucket::new(bucket.as_ref()?, now);
+            if bucket.is_none() {
+                log::error!(
+                    "{kind:?} order requests on {exchange_account_id} aren't limited because refill rate of their bucket isn't positive"
+                );
+            }
+            bucket.map(|bucket| (kind, Mutex::new(bucket)))
+        })
+        .collect();
+
+        Arc::new(OrderRateLimiter {
+            exchange_account_id,
+            buckets,
+            clock,
+        })
+    }
+
+    /// Waits until request of specified kind can be sent without exceeding rate limit.
+    /// Requests are served in order of calls
+    pub async fn acquire(
+        &self,
+        kind: OrderRequestKind,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let bucket = match self.buckets.get(&kind) {
+            Some(bucket) => bucket,
+            None => return Ok(()),
+        };
+
+        let delay = bucket.lock().take(self.clock.now());
+        if delay.is_zero() {
+            return Ok(());
+        }
+
+        log::trace!(
+            "{kind:?} order request on {} is queued for {delay:?} by order rate limit",
+            self.exchange_account_id
+        );
+
+        tokio::select! {
+            _ = self.clock.sleep(delay) => Ok(()),
+            _ = cancellation_token.when_cancelled() => {
+                bucket.lock().give_back(self.clock.now());
+                bail!(OPERATION_CANCELED_MSG)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::misc::clock::VirtualClock;
+    use chrono::Utc;
+    use futures::FutureExt;
+
+    fn limiter(clock: Arc<VirtualClock>) -> Arc<OrderRateLimiter> {
+        let settings = OrderRateLimitSettings {
+            create: Some(TokenBucketSettings {
+                capacity: 2,
+                refill_per_second: dec!(1),
+            }),
+            cancel: None,
+        };
+
+        OrderRateLimiter::new(ExchangeAccountId::new("Binance", 0), &settings, clock)
+    }
+
+    #[tokio::test]
+    async fn burst_over_capacity_is_queued() {
+        let clock = VirtualClock::new(Utc::now());
+        let limiter = limiter(clock.clone());
+        let acquire = || limiter.acquire(OrderRequestKind::Create, CancellationToken::new());
+
+        acquire().await.expect("in test");
+        acquire().await.expect("in test");
+
+        let mut third = acquire().boxed();
+        assert!((&mut third).now_or_never().is_none());
+
+        clock.advance(Duration::from_millis(999));
+        assert!((&mut third).now_or_never().is_none());
+
+        clock.advance(Duration::from_millis(1));
+        third.await.expect("in test");
+    }
+
+    #[tokio::test]
+    async fn kinds_are_counted_separately() {
+        let clock = VirtualClock::new(Utc::now());
+        let limiter = limiter(clock.clone());
+
+        for _ in 0..2 {
+            limiter
+                .acquire(OrderRequestKind::Create, CancellationToken::new())
+                .await
+                .expect("in test");
+        }
+
+        // cancels aren't limited, so they shouldn't wait for create tokens
+        for _ in 0..10 {
+            limiter
+                .acquire(OrderRequestKind::Cancel, CancellationToken::new())
+                .await
+                .expect("in test");
+        }
+        assert_eq!(clock.pending_sleeps_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn cancelled_request_returns_token() {
+        let clock = VirtualClock::new(Utc::now());
+        let limiter = limiter(clock.clone());
+
+        for _ in 0..2 {
+            limiter
+                .acquire(OrderRequestKind::Create, CancellationToken::new())
+                .await
+                .expect("in test");
+        }
+
+        let cancellation_token = CancellationToken::new();
+        cancellation_token.cancel();
+        let result = limiter
+            .acquire(OrderRequestKind::Create, cancellation_token)
+            .await;
+        assert!(result.is_err());
+
+        clock.advance(Duration::from_secs(1));
+        limiter
+            .acquire(OrderRequestKind::Create, CancellationToken::new())
+            .await
+            .expect("in test");
+    }
+}
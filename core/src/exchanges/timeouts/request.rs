@@ -1,10 +1,12 @@
 use mmb_utils::DateTime;
+use uuid::Uuid;
 
 use crate::exchanges::general::request_type::RequestType;
 use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Request {
+    pub(crate) id: Uuid,
     pub(crate) request_type: RequestType,
     pub(crate) allowed_start_time: DateTime,
     pub(crate) group_id: Option<RequestGroupId>,
@@ -17,6 +19,7 @@ impl Request {
         group_id: Option<RequestGroupId>,
     ) -> Self {
         Self {
+            id: Uuid::new_v4(),
             request_type,
             allowed_start_time,
             group_id,
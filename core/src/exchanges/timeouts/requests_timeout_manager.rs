@@ -3,12 +3,13 @@ use std::sync::{Arc, Weak};
 
 use anyhow::{anyhow, bail, Context, Result};
 use chrono::Duration;
+use futures::future::BoxFuture;
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::infrastructure::{FutureOutcome, SpawnFutureFlags};
 use mmb_utils::{DateTime, OPERATION_CANCELED_MSG};
 use parking_lot::Mutex;
+use rust_decimal::Decimal;
 use tokio::task::JoinHandle;
-use tokio::time::timeout;
 use uuid::Uuid;
 
 use super::{
@@ -18,7 +19,9 @@ use super::{
     triggers::every_requests_count_change_trigger::EveryRequestsCountChangeTrigger,
     triggers::less_or_equals_requests_count_trigger::LessOrEqualsRequestsCountTrigger,
 };
-use crate::{exchanges::general::request_type::RequestType, infrastructure::spawn_future};
+use crate::exchanges::general::request_type::{RequestPriority, RequestType};
+use crate::infrastructure::spawn_future;
+use crate::misc::clock::Clock;
 use mmb_domain::market::ExchangeAccountId;
 use mmb_utils::time::ToStdExpected;
 
@@ -39,6 +42,7 @@ impl Display for RequestGroupId {
 
 pub struct RequestsTimeoutManager {
     inner: Mutex<InnerRequestsTimeoutManager>,
+    clock: Arc<dyn Clock>,
 }
 
 impl RequestsTimeoutManager {
@@ -47,6 +51,7 @@ impl RequestsTimeoutManager {
         period_duration: Duration,
         exchange_account_id: ExchangeAccountId,
         more_or_equals_available_requests_count_trigger_scheduler: MoreOrEqualsAvailableRequestsCountTriggerScheduler,
+        clock: Arc<dyn Clock>,
     ) -> Arc<Self> {
         let inner = InnerRequestsTimeoutManager {
             requests_per_period,
@@ -55,6 +60,7 @@ impl RequestsTimeoutManager {
             requests: Default::default(),
             pre_reserved_groups: Default::default(),
             last_time: None,
+            used_weight: 0,
             delay_to_next_time_period: Duration::milliseconds(1),
             group_was_reserved: Box::new(|_| {}),
             group_was_removed: Box::new(|_| {}),
@@ -65,6 +71,7 @@ impl RequestsTimeoutManager {
 
         Arc::new(Self {
             inner: Mutex::new(inner),
+            clock,
         })
     }
 
@@ -262,6 +269,15 @@ impl RequestsTimeoutManager {
             };
 
             request_start_time = request_start_time.max(current_time);
+            // cancellations shouldn't wait behind new quotes when requests limit is saturated
+            if request_type.priority() == RequestPriority::High {
+                if let Some(preempted_start_time) =
+                    inner.preempt_low_priority_request(current_time, request_start_time)
+                {
+                    log::info!("Request {request_type:?} preempted low priority request scheduled at {preempted_start_time}");
+                    request_start_time = preempted_start_time;
+                }
+            }
             delay = request_start_time - current_time;
             inner.add_request(request_type, request_start_time, None)
         } else {
@@ -283,7 +299,7 @@ impl RequestsTimeoutManager {
         let action = Self::wait_for_request_availability(
             Arc::downgrade(&self),
             request,
-            delay,
+            self.clock.sleep(delay.to_std_expected()),
             cancellation_token,
         );
         let request_availability = spawn_future(
@@ -298,35 +314,41 @@ impl RequestsTimeoutManager {
     async fn wait_for_request_availability(
         weak_self: Weak<Self>,
         request: Request,
-        delay: Duration,
+        mut delay: BoxFuture<'static, ()>,
         cancellation_token: CancellationToken,
     ) -> Result<()> {
-        // Should never panic, because function wait_for_request_availability
-        // has one call with guaranteed non-negative delay.
-        let delay = delay.to_std_expected();
-
-        match timeout(delay, cancellation_token.when_cancelled()).await {
-            Err(_) => {
-                let strong_self = Self::try_get_strong(weak_self)?;
-                (strong_self.inner.lock().time_has_come_for_request)(request);
-            }
-            Ok(()) => {
-                let strong_self = Self::try_get_strong(weak_self)?;
-                let mut inner = strong_self.inner.lock();
+        loop {
+            let is_cancelled = tokio::select! {
+                _ = delay => false,
+                _ = cancellation_token.when_cancelled() => true,
+            };
+
+            let strong_self = Self::try_get_strong(weak_self.clone())?;
+            let mut inner = strong_self.inner.lock();
+            if is_cancelled {
                 (inner.time_has_come_for_request)(request.clone());
                 if let Some(position) = inner
                     .requests
                     .iter()
-                    .position(|stored_request| *stored_request == request)
+                    .position(|stored_request| stored_request.id == request.id)
                 {
                     inner.requests.remove(position);
                 }
 
                 bail!(OPERATION_CANCELED_MSG)
             }
-        };
 
-        Ok(())
+            // request could be postponed by high priority request meanwhile
+            match inner.get_postponed_delay(&request, strong_self.clock.now()) {
+                Some(postponed_delay) => {
+                    delay = strong_self.clock.sleep(postponed_delay.to_std_expected())
+                }
+                None => {
+                    (inner.time_has_come_for_request)(request);
+                    return Ok(());
+                }
+            }
+        }
     }
 
     fn try_get_strong(
@@ -382,6 +404,27 @@ impl RequestsTimeoutManager {
     pub fn get_period_duration(&self) -> std::time::Duration {
         self.inner.lock().get_period_duration().to_std_expected()
     }
+
+    /// Share of requests of current period which are already made or reserved, from 0 to 1
+    pub fn utilization(&self, current_time: DateTime) -> Decimal {
+        let mut inner = self.inner.lock();
+        let current_time = inner.get_non_decreasing_time(current_time);
+        inner.remove_outdated_requests(current_time);
+
+        let requests_per_period = inner.requests_per_period;
+        if requests_per_period == 0 {
+            return Decimal::ONE;
+        }
+
+        let available_requests_count = inner.get_available_requests_count_at_present(current_time);
+        let used_requests_count = requests_per_period.saturating_sub(available_requests_count);
+        Decimal::from(used_requests_count) / Decimal::from(requests_per_period)
+    }
+
+    /// Weight of all requests reserved since start
+    pub fn used_weight(&self) -> u64 {
+        self.inner.lock().used_weight
+    }
 }
 
 #[cfg(test)]
@@ -1261,6 +1304,8 @@ mod test {
 
     mod reserve_when_available {
         use crate::infrastructure::init_lifetime_manager;
+        use crate::misc::clock::VirtualClock;
+        use tokio::time::timeout;
 
         use super::*;
 
@@ -2203,6 +2248,129 @@ mod test {
 
             Ok(())
         }
+
+        #[tokio::test]
+        async fn request_becomes_available_by_virtual_clock() -> Result<()> {
+            let _ = init_lifetime_manager();
+
+            // Arrange
+            let clock = VirtualClock::new(Utc::now());
+            let timeout_manager =
+                RequestsTimeoutManagerFactory::from_requests_per_period_with_clock(
+                    RequestTimeoutArguments::new(1, Duration::seconds(10)),
+                    ExchangeAccountId::new("test_exchange_account_id", 0),
+                    clock.clone(),
+                );
+            let current_time = clock.now();
+            assert!(timeout_manager.try_reserve_instant(
+                RequestType::CreateOrder,
+                current_time,
+                None
+            ));
+
+            // Act
+            let (handle, _, delay) = timeout_manager.clone().reserve_when_available(
+                RequestType::CreateOrder,
+                current_time,
+                CancellationToken::default(),
+            );
+            tokio::task::yield_now().await;
+            let is_finished_before_advance = handle.is_finished();
+            clock.advance(delay.to_std_expected());
+
+            // Assert
+            assert_eq!(delay, Duration::seconds(10) + Duration::milliseconds(1));
+            assert!(!is_finished_before_advance);
+            handle.await?.into_result()
+        }
+
+        fn saturated_timeout_manager(clock: Arc<VirtualClock>) -> Arc<RequestsTimeoutManager> {
+            let timeout_manager =
+                RequestsTimeoutManagerFactory::from_requests_per_period_with_clock(
+                    RequestTimeoutArguments::new(1, Duration::seconds(10)),
+                    ExchangeAccountId::new("test_exchange_account_id", 0),
+                    clock.clone(),
+                );
+            assert!(timeout_manager.try_reserve_instant(
+                RequestType::CreateOrder,
+                clock.now(),
+                None
+            ));
+            timeout_manager
+        }
+
+        #[tokio::test]
+        async fn cancel_preempts_queued_create_order() -> Result<()> {
+            let _ = init_lifetime_manager();
+
+            // Arrange
+            let clock = VirtualClock::new(Utc::now());
+            let timeout_manager = saturated_timeout_manager(clock.clone());
+            let current_time = clock.now();
+            let (create_handle, create_start_time, _) =
+                timeout_manager.clone().reserve_when_available(
+                    RequestType::CreateOrder,
+                    current_time,
+                    CancellationToken::default(),
+                );
+
+            // Act
+            let (cancel_handle, cancel_start_time, cancel_delay) =
+                timeout_manager.clone().reserve_when_available(
+                    RequestType::CancelOrder,
+                    current_time,
+                    CancellationToken::default(),
+                );
+            tokio::task::yield_now().await;
+            clock.advance(cancel_delay.to_std_expected());
+            cancel_handle.await?.into_result()?;
+            tokio::task::yield_now().await;
+            let is_create_finished_after_cancel = create_handle.is_finished();
+
+            // Assert
+            assert_eq!(cancel_start_time, create_start_time);
+            assert!(!is_create_finished_after_cancel);
+
+            let postponed_create_start_time = timeout_manager
+                .inner
+                .lock()
+                .requests
+                .iter()
+                .rfind(|x| x.request_type == RequestType::CreateOrder)
+                .expect("in test")
+                .allowed_start_time;
+            assert!(postponed_create_start_time > cancel_start_time);
+
+            clock.advance((postponed_create_start_time - clock.now()).to_std_expected());
+            create_handle.await?.into_result()
+        }
+
+        #[tokio::test]
+        async fn normal_priority_request_does_not_preempt_create_order() -> Result<()> {
+            let _ = init_lifetime_manager();
+
+            // Arrange
+            let clock = VirtualClock::new(Utc::now());
+            let timeout_manager = saturated_timeout_manager(clock.clone());
+            let current_time = clock.now();
+            let (_, create_start_time, _) = timeout_manager.clone().reserve_when_available(
+                RequestType::CreateOrder,
+                current_time,
+                CancellationToken::default(),
+            );
+
+            // Act
+            let (_, balance_start_time, _) = timeout_manager.clone().reserve_when_available(
+                RequestType::GetBalance,
+                current_time,
+                CancellationToken::default(),
+            );
+
+            // Assert
+            assert!(balance_start_time > create_start_time);
+
+            Ok(())
+        }
     }
 
     mod triggers {
@@ -2457,4 +2625,26 @@ mod test {
             Ok(())
         }
     }
+
+    mod utilization {
+        use super::*;
+        use rust_decimal_macros::dec;
+
+        #[rstest]
+        fn counts_reserved_requests(timeout_manager: Arc<RequestsTimeoutManager>) {
+            let current_time = Utc::now();
+            assert_eq!(timeout_manager.utilization(current_time), dec!(0));
+
+            let _ =
+                timeout_manager.try_reserve_request_instant(RequestType::CreateOrder, current_time);
+            let _ =
+                timeout_manager.try_reserve_request_instant(RequestType::CreateOrder, current_time);
+
+            assert_eq!(timeout_manager.utilization(current_time), dec!(0.4));
+            let period_duration = timeout_manager.inner.lock().period_duration;
+            let after_period = current_time + period_duration + Duration::milliseconds(1);
+            assert_eq!(timeout_manager.utilization(after_period), dec!(0));
+            assert_eq!(timeout_manager.used_weight(), 2);
+        }
+    }
 }
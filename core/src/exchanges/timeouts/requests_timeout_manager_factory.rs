@@ -6,6 +6,7 @@ use std::{
 use chrono::{Duration, Utc};
 use mmb_utils::DateTime;
 
+use crate::misc::clock::{Clock, SystemClock};
 use mmb_domain::market::ExchangeAccountId;
 
 use super::{
@@ -24,12 +25,26 @@ impl RequestsTimeoutManagerFactory {
         timeout_arguments: RequestTimeoutArguments,
         exchange_account_id: ExchangeAccountId,
     ) -> Arc<RequestsTimeoutManager> {
-        let trigger_scheduler = MoreOrEqualsAvailableRequestsCountTriggerScheduler::default();
+        Self::from_requests_per_period_with_clock(
+            timeout_arguments,
+            exchange_account_id,
+            SystemClock::shared(),
+        )
+    }
+
+    pub fn from_requests_per_period_with_clock(
+        timeout_arguments: RequestTimeoutArguments,
+        exchange_account_id: ExchangeAccountId,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<RequestsTimeoutManager> {
+        let trigger_scheduler =
+            MoreOrEqualsAvailableRequestsCountTriggerScheduler::new(clock.clone());
         RequestsTimeoutManager::new(
             timeout_arguments.requests_per_period,
             timeout_arguments.period,
             exchange_account_id,
             trigger_scheduler,
+            clock,
         )
     }
 }
@@ -15,33 +15,74 @@ use anyhow::Result;
 use chrono::Utc;
 
 use crate::exchanges::general::request_type::RequestType;
+use crate::exchanges::timeouts::order_rate_limiter::{OrderRateLimiter, OrderRequestKind};
 use crate::exchanges::timeouts::requests_timeout_manager::{
     RequestGroupId, RequestsTimeoutManager,
 };
+use crate::misc::clock::{Clock, SystemClock};
+use crate::settings::OrderRateLimitSettings;
 use mmb_domain::market::ExchangeAccountId;
+use rust_decimal::Decimal;
 
 pub type BoxFuture = Box<dyn Future<Output = Result<()>> + Sync + Send>;
 
 pub struct TimeoutManager {
     inner: HashMap<ExchangeAccountId, Arc<RequestsTimeoutManager>>,
+    order_rate_limiters: HashMap<ExchangeAccountId, Arc<OrderRateLimiter>>,
+    clock: Arc<dyn Clock>,
 }
 
 impl TimeoutManager {
     pub fn new(
         timeout_managers: HashMap<ExchangeAccountId, Arc<RequestsTimeoutManager>>,
     ) -> Arc<Self> {
+        Self::with_clock(timeout_managers, SystemClock::shared())
+    }
+
+    /// Timeout manager with the specified clock. Exchanges using this timeout manager share its clock
+    pub fn with_clock(
+        timeout_managers: HashMap<ExchangeAccountId, Arc<RequestsTimeoutManager>>,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        Self::with_order_rate_limits(timeout_managers, HashMap::new(), clock)
+    }
+
+    /// Timeout manager which additionally limits rate of order requests for specified exchange accounts
+    pub fn with_order_rate_limits(
+        timeout_managers: HashMap<ExchangeAccountId, Arc<RequestsTimeoutManager>>,
+        order_rate_limits: HashMap<ExchangeAccountId, OrderRateLimitSettings>,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        let order_rate_limiters = order_rate_limits
+            .iter()
+            .map(|(&exchange_account_id, settings)| {
+                let limiter = OrderRateLimiter::new(exchange_account_id, settings, clock.clone());
+                (exchange_account_id, limiter)
+            })
+            .collect();
+
         Arc::new(TimeoutManager {
             inner: timeout_managers,
+            order_rate_limiters,
+            clock,
         })
     }
 
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
     pub fn try_reserve_group(
         &self,
         exchange_account_id: ExchangeAccountId,
         requests_count: usize,
         group_type: String,
     ) -> Option<RequestGroupId> {
-        self.inner[&exchange_account_id].try_reserve_group(group_type, now(), requests_count)
+        self.inner[&exchange_account_id].try_reserve_group(
+            group_type,
+            self.clock.now(),
+            requests_count,
+        )
     }
 
     pub fn remove_group(
@@ -49,7 +90,7 @@ impl TimeoutManager {
         exchange_account_id: ExchangeAccountId,
         group_id: RequestGroupId,
     ) -> bool {
-        self.inner[&exchange_account_id].remove_group(group_id, now())
+        self.inner[&exchange_account_id].remove_group(group_id, self.clock.now())
     }
 
     pub fn try_reserve_instant(
@@ -57,7 +98,7 @@ impl TimeoutManager {
         exchange_account_id: ExchangeAccountId,
         request_type: RequestType,
     ) -> bool {
-        self.inner[&exchange_account_id].try_reserve_instant(request_type, now(), None)
+        self.inner[&exchange_account_id].try_reserve_instant(request_type, self.clock.now(), None)
     }
 
     pub fn try_reserve_group_instant(
@@ -68,7 +109,7 @@ impl TimeoutManager {
     ) -> bool {
         self.inner[&exchange_account_id].try_reserve_instant(
             request_type,
-            now(),
+            self.clock.now(),
             pre_reserved_group_id,
         )
     }
@@ -97,7 +138,7 @@ impl TimeoutManager {
             })
         };
 
-        let now = now();
+        let now = self.clock.now();
         if pre_reservation_group_id.is_none() {
             let result = inner.reserve_when_available(request_type, now, cancellation_token);
             return Either::Left(convert(result.0));
@@ -115,6 +156,36 @@ impl TimeoutManager {
         Either::Left(convert(result.0))
     }
 
+    /// Waits until order request of specified kind fits in order rate limit of exchange account.
+    /// Returns immediately if there is no order rate limit for the account
+    pub async fn acquire_order_request(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        kind: OrderRequestKind,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        match self.order_rate_limiters.get(&exchange_account_id) {
+            Some(limiter) => limiter.acquire(kind, cancellation_token).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Share of request limit of exchange account used in current period, from 0 to 1
+    pub fn utilization(&self, exchange_account_id: ExchangeAccountId) -> Decimal {
+        self.inner
+            .get(&exchange_account_id)
+            .with_expect(|| format!("Can't find timeout manger for {exchange_account_id}"))
+            .utilization(self.clock.now())
+    }
+
+    /// Weight of all requests of exchange account reserved since start
+    pub fn used_weight(&self, exchange_account_id: ExchangeAccountId) -> u64 {
+        self.inner
+            .get(&exchange_account_id)
+            .with_expect(|| format!("Can't find timeout manger for {exchange_account_id}"))
+            .used_weight()
+    }
+
     pub fn get_period_duration(&self, exchange_account_id: ExchangeAccountId) -> Duration {
         self.inner
             .get(&exchange_account_id)
@@ -9,26 +9,36 @@ use crate::exchanges::general::exchange::{Exchange, RequestResult};
 use crate::exchanges::general::features::ExchangeFeatures;
 use crate::exchanges::general::order::cancel::CancelOrderResult;
 use crate::exchanges::general::order::create::CreateOrderResult;
+use crate::exchanges::general::order::group::CreateOrderGroupResult;
+use crate::exchanges::sequence_tracker::SequenceGap;
 use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::secrets::ExchangeCredentials;
 use crate::settings::ExchangeSettings;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use async_trait::async_trait;
 use dashmap::DashMap;
 use mmb_domain::events::{EventSourceType, ExchangeBalancesAndPositions, MetricsEventInfo};
 use mmb_domain::events::{ExchangeEvent, Trade};
+use mmb_domain::exchanges::commission::Commission;
 use mmb_domain::exchanges::symbol::{BeforeAfter, Symbol};
+use mmb_domain::exchanges::system_status::SystemStatus;
 use mmb_domain::market::CurrencyId;
 use mmb_domain::market::{
     CurrencyCode, CurrencyPair, ExchangeAccountId, ExchangeErrorType, ExchangeId,
     SpecificCurrencyPair,
 };
+use mmb_domain::order::group::{OrderGroupId, OrderGroupType};
 use mmb_domain::order::pool::{OrderRef, OrdersPool};
 use mmb_domain::order::snapshot::Price;
 use mmb_domain::order::snapshot::{
-    ClientOrderId, ExchangeOrderId, OrderInfo, OrderInfoExtensionData, OrderSide,
+    ClientOrderId, ExchangeOrderId, OrderHeader, OrderInfo, OrderInfoExtensionData, OrderSide,
 };
 use mmb_domain::position::{ActivePosition, ClosedPosition};
+use mmb_domain::transfer::{
+    AccountTransfer, ExchangeFundsMovementId, ExchangeTransferId, FundsMovement, TransferStatus,
+    Withdrawal,
+};
 use mmb_utils::DateTime;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
@@ -142,6 +152,86 @@ pub trait ExchangeClient: Support {
     /// Need for server time latency calculating
     /// Should return server time with millis accuracy
     async fn get_server_time(&self) -> Option<Result<i64>>;
+
+    /// Whether legs can be created as a single order group through conditional/OCO endpoint of
+    /// exchange. Otherwise order group is created as separate orders and maintained by engine
+    fn supports_native_order_group(
+        &self,
+        _group_type: OrderGroupType,
+        _legs: &[OrderHeader],
+    ) -> bool {
+        false
+    }
+
+    /// Creates legs as a single order group on exchange side.
+    /// Called only if `supports_native_order_group` returns true for the legs
+    async fn create_order_group(
+        &self,
+        group_id: OrderGroupId,
+        _group_type: OrderGroupType,
+        _legs: &[OrderRef],
+    ) -> Result<CreateOrderGroupResult, ExchangeError> {
+        Err(ExchangeError::unknown(&format!(
+            "Unable to create order group {group_id}: native order groups aren't supported"
+        )))
+    }
+
+    /// Internal transfer of currency between accounts of exchange. Sub-account identifiers are
+    /// taken from `ExchangeSettings::sub_account` of the accounts, `None` means master account
+    async fn transfer_between_accounts(
+        &self,
+        transfer: &AccountTransfer,
+        _from_sub_account: Option<&str>,
+        _to_sub_account: Option<&str>,
+    ) -> Result<ExchangeTransferId, ExchangeError> {
+        Err(ExchangeError::unknown(&format!(
+            "Unable to transfer {} {} from {} to {}: transfers between accounts aren't supported",
+            transfer.amount, transfer.currency_code, transfer.from, transfer.to
+        )))
+    }
+
+    /// Status of transfer created by `transfer_between_accounts`
+    async fn get_transfer_status(
+        &self,
+        transfer_id: &ExchangeTransferId,
+    ) -> Result<TransferStatus, ExchangeError> {
+        Err(ExchangeError::unknown(&format!(
+            "Unable to get status of transfer {transfer_id}: transfers between accounts aren't supported"
+        )))
+    }
+
+    /// Initiates withdrawal of currency from account to external address
+    async fn withdraw(
+        &self,
+        withdrawal: &Withdrawal,
+    ) -> Result<ExchangeFundsMovementId, ExchangeError> {
+        Err(ExchangeError::unknown(&format!(
+            "Unable to withdraw {} {}: withdrawals aren't supported",
+            withdrawal.amount, withdrawal.currency_code
+        )))
+    }
+
+    /// Maker and taker fees of account reported by exchange before discounts of exchange token.
+    /// Returns `None` if exchange doesn't report fees
+    async fn get_commission(&self) -> Option<Result<Commission, ExchangeError>> {
+        None
+    }
+
+    /// Operational status declared by exchange, e.g. maintenance.
+    /// Returns `None` if exchange doesn't report its status
+    async fn get_system_status(&self) -> Option<Result<SystemStatus, ExchangeError>> {
+        None
+    }
+
+    /// Deposits and withdrawals of account created since `from_datetime`
+    async fn get_funds_movements(
+        &self,
+        _from_datetime: DateTime,
+    ) -> Result<Vec<FundsMovement>, ExchangeError> {
+        Err(ExchangeError::unknown(
+            "Unable to get deposits and withdrawals: funds movements aren't supported",
+        ))
+    }
 }
 
 pub type OrderCreatedCb =
@@ -158,6 +248,8 @@ pub type SendWebsocketMessageCb = Box<dyn Fn(WebSocketRole, String) -> Result<()
 
 pub type HandleMetricsCb = Box<dyn Fn(MetricsEventInfo) + Send + Sync>;
 
+pub type HandleSequenceGapCb = Box<dyn Fn(SequenceGap) + Send + Sync>;
+
 #[async_trait]
 pub trait Support: Send + Sync {
     /// Needed to call the `downcast_ref` method
@@ -181,6 +273,10 @@ pub trait Support: Send + Sync {
 
     fn set_handle_metrics_callback(&mut self, callback: HandleMetricsCb);
 
+    /// Needed only for exchanges which provide sequence numbers of private streams. Callback
+    /// should be called when updates are missed, so orders are reconciled through REST API
+    fn set_handle_sequence_gap_callback(&mut self, _callback: HandleSequenceGapCb) {}
+
     fn set_traded_specific_currencies(&self, currencies: Vec<SpecificCurrencyPair>);
 
     fn is_websocket_enabled(&self, role: WebSocketRole) -> bool;
@@ -207,6 +303,12 @@ pub trait Support: Send + Sync {
 
     fn get_settings(&self) -> &ExchangeSettings;
 
+    /// Replaces credentials used by requests. Exchange is reconnected after it, so websocket
+    /// streams are authenticated by new credentials too
+    fn update_credentials(&self, _credentials: &ExchangeCredentials) -> Result<()> {
+        bail!("Connector doesn't support rotation of credentials, restart is required")
+    }
+
     fn get_initial_extension_data(&self) -> Option<Box<dyn OrderInfoExtensionData>> {
         None
     }
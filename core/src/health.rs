@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_domain::events::{ConnectivityState, ExchangeEvent};
+use mmb_domain::market::ExchangeAccountId;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::Instant;
+
+use crate::database::events::recorder::EventRecorder;
+use crate::exchanges::general::exchange::Exchange;
+use crate::misc::clock::Clock;
+use crate::settings::HealthSettings;
+use crate::supervisor::{RestartPolicy, Supervisor};
+
+const EVENT_LOOP_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Status of single engine component reported by health endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentHealth {
+    pub component: String,
+    pub is_healthy: bool,
+    pub details: String,
+}
+
+impl ComponentHealth {
+    fn new(component: impl Into<String>, is_healthy: bool, details: impl Into<String>) -> Self {
+        ComponentHealth {
+            component: component.into(),
+            is_healthy,
+            details: details.into(),
+        }
+    }
+}
+
+/// Engine is healthy only if all its components are healthy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    pub is_healthy: bool,
+    pub components: Vec<ComponentHealth>,
+}
+
+impl HealthReport {
+    pub fn new(components: Vec<ComponentHealth>) -> Self {
+        HealthReport {
+            is_healthy: components.iter().all(|x| x.is_healthy),
+            components,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).context("serializing health report")
+    }
+}
+
+struct EventLoopState {
+    last_tick: Instant,
+    lag: Duration,
+}
+
+/// Tracks state of engine components for liveness and readiness probes: responsiveness of
+/// tokio event loop, websocket connections and market data of exchange accounts and backlog of
+/// events waiting to be saved to database
+pub struct HealthMonitor {
+    settings: HealthSettings,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    event_recorder: Arc<EventRecorder>,
+    clock: Arc<dyn Clock>,
+    started_at: DateTime,
+    event_loop: Mutex<EventLoopState>,
+    /// Receipt time of the last order book or trades event by exchange account
+    last_market_data: Mutex<HashMap<ExchangeAccountId, DateTime>>,
+}
+
+impl HealthMonitor {
+    pub fn new(
+        settings: HealthSettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        event_recorder: Arc<EventRecorder>,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        Arc::new(HealthMonitor {
+            settings,
+            exchanges,
+            event_recorder,
+            started_at: clock.now(),
+            clock,
+            event_loop: Mutex::new(EventLoopState {
+                last_tick: Instant::now(),
+                lag: Duration::ZERO,
+            }),
+            last_market_data: Default::default(),
+        })
+    }
+
+    /// Health watching is restarted after crash, because probes report stalled event loop
+    /// without it
+    pub fn start(
+        self: &Arc<Self>,
+        events: broadcast::Receiver<ExchangeEvent>,
+        supervisor: &Arc<Supervisor>,
+    ) {
+        let this = self.clone();
+        supervisor.spawn(
+            "Watch engine health",
+            RestartPolicy::WithBackoff {
+                initial_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(60),
+            },
+            true,
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            move || this.clone().watch(events.resubscribe()),
+        );
+    }
+
+    async fn watch(self: Arc<Self>, mut events: broadcast::Receiver<ExchangeEvent>) -> Result<()> {
+        let mut check_interval = tokio::time::interval(EVENT_LOOP_CHECK_INTERVAL);
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(ExchangeEvent::OrderBookEvent(event)) => self.on_market_data(event.exchange_account_id),
+                    Ok(ExchangeEvent::Trades(event)) => self.on_market_data(event.exchange_account_id),
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Health monitor skipped {skipped} engine events");
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                scheduled = check_interval.tick() => {
+                    let now = Instant::now();
+                    *self.event_loop.lock() = EventLoopState {
+                        last_tick: now,
+                        lag: now - scheduled,
+                    };
+                }
+            }
+        }
+    }
+
+    fn on_market_data(&self, exchange_account_id: ExchangeAccountId) {
+        let _ = self
+            .last_market_data
+            .lock()
+            .insert(exchange_account_id, self.clock.now());
+    }
+
+    /// Engine is alive while its event loop is running
+    pub fn liveness(&self) -> HealthReport {
+        let since_last_tick = self.event_loop.lock().last_tick.elapsed();
+        let stall_timeout = Duration::from_secs(self.settings.event_loop_stall_secs);
+        HealthReport::new(vec![ComponentHealth::new(
+            "event_loop",
+            since_last_tick < stall_timeout,
+            format!("Last health check task ran {since_last_tick:?} ago"),
+        )])
+    }
+
+    /// Engine is ready to trade if all websockets are connected, market data is fresh and
+    /// neither event loop nor persistence fall behind
+    pub fn readiness(&self) -> HealthReport {
+        let mut components = vec![
+            self.event_loop_health(),
+            persistence_health(
+                self.event_recorder.backlog(),
+                self.settings.max_persistence_backlog,
+            ),
+        ];
+
+        let now = self.clock.now();
+        let max_market_data_age =
+            chrono::Duration::seconds(self.settings.max_market_data_age_secs as i64);
+        let last_market_data = self.last_market_data.lock().clone();
+        let connectivity_states = self
+            .exchanges
+            .iter()
+            .map(|x| (x.exchange_account_id, x.connectivity_state()))
+            .sorted_by_key(|(exchange_account_id, _)| exchange_account_id.to_string());
+        for (exchange_account_id, connectivity_state) in connectivity_states {
+            components.push(connection_health(exchange_account_id, connectivity_state));
+            components.push(market_data_health(
+                exchange_account_id,
+                last_market_data.get(&exchange_account_id).copied(),
+                self.started_at,
+                now,
+                max_market_data_age,
+            ));
+        }
+
+        HealthReport::new(components)
+    }
+
+    fn event_loop_health(&self) -> ComponentHealth {
+        let lag = self.event_loop.lock().lag;
+        ComponentHealth::new(
+            "event_loop",
+            lag <= Duration::from_millis(self.settings.max_event_loop_lag_millis),
+            format!("Lag is {lag:?}"),
+        )
+    }
+}
+
+fn persistence_health(backlog: usize, max_backlog: usize) -> ComponentHealth {
+    ComponentHealth::new(
+        "persistence",
+        backlog <= max_backlog,
+        format!("{backlog} events are waiting to be saved"),
+    )
+}
+
+fn connection_health(
+    exchange_account_id: ExchangeAccountId,
+    state: Option<ConnectivityState>,
+) -> ComponentHealth {
+    let component = format!("connection:{exchange_account_id}");
+    match state {
+        Some(ConnectivityState::Connected) => ComponentHealth::new(component, true, "Connected"),
+        Some(state) => ComponentHealth::new(component, false, format!("{state:?}")),
+        None => ComponentHealth::new(component, false, "Never connected"),
+    }
+}
+
+/// Age of market data is counted from engine start if nothing has been received yet
+fn market_data_health(
+    exchange_account_id: ExchangeAccountId,
+    last_received: Option<DateTime>,
+    started_at: DateTime,
+    now: DateTime,
+    max_age: chrono::Duration,
+) -> ComponentHealth {
+    let component = format!("market_data:{exchange_account_id}");
+    let age = now - last_received.unwrap_or(started_at);
+    let details = match last_received {
+        Some(_) => format!("Last received {} seconds ago", age.num_seconds()),
+        None => format!("Nothing received for {} seconds", age.num_seconds()),
+    };
+    ComponentHealth::new(component, age <= max_age, details)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn time(secs: i64) -> DateTime {
+        chrono::Utc
+            .timestamp_opt(1_000_000 + secs, 0)
+            .single()
+            .expect("valid time")
+    }
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        "Binance_0".parse().expect("valid id")
+    }
+
+    #[test]
+    fn report_is_unhealthy_if_any_component_is_unhealthy() {
+        let report = HealthReport::new(vec![
+            persistence_health(10, 100),
+            connection_health(exchange_account_id(), Some(ConnectivityState::Degraded)),
+        ]);
+
+        assert!(!report.is_healthy);
+        assert_eq!(
+            report.to_json().expect("serializable"),
+            r#"{"is_healthy":false,"components":[{"component":"persistence","is_healthy":true,"details":"10 events are waiting to be saved"},{"component":"connection:Binance_0","is_healthy":false,"details":"Degraded"}]}"#
+        );
+        assert!(HealthReport::new(vec![persistence_health(10, 100)]).is_healthy);
+    }
+
+    #[test]
+    fn market_data_age_is_counted_from_start_until_first_data() {
+        let max_age = chrono::Duration::seconds(60);
+        let check = |last_received, now| {
+            market_data_health(exchange_account_id(), last_received, time(0), now, max_age)
+        };
+
+        assert!(check(None, time(30)).is_healthy);
+        assert_eq!(
+            check(None, time(90)),
+            ComponentHealth::new(
+                "market_data:Binance_0",
+                false,
+                "Nothing received for 90 seconds"
+            )
+        );
+        assert!(check(Some(time(80)), time(90)).is_healthy);
+        assert!(!check(Some(time(80)), time(141)).is_healthy);
+    }
+}
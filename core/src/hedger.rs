@@ -0,0 +1,263 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, OrderHeader, OrderSide, Price, UserOrder,
+};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+
+use crate::balance::manager::balance_manager::BalanceManager;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_by_timer;
+use crate::misc::clock::Clock;
+use crate::settings::{HedgeAggression, HedgerSettings};
+
+/// Offsets net position of market making markets on hedge venue, so market making stays
+/// delta-neutral. Only one hedging order exists at a time
+pub struct Hedger {
+    settings: HedgerSettings,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    balance_manager: Arc<Mutex<BalanceManager>>,
+    clock: Arc<dyn Clock>,
+    active_order: Mutex<Option<OrderRef>>,
+    last_order_time: Mutex<Option<DateTime>>,
+}
+
+impl Hedger {
+    pub fn new(
+        settings: HedgerSettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        balance_manager: Arc<Mutex<BalanceManager>>,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            settings,
+            exchanges,
+            balance_manager,
+            clock,
+            active_order: Default::default(),
+            last_order_time: Default::default(),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, cancellation_token: CancellationToken) {
+        let interval = Duration::from_millis(self.settings.interval_millis.max(1));
+        let hedger = self.clone();
+        spawn_by_timer(
+            &format!("Hedger '{}'", self.settings.name),
+            interval,
+            interval,
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            move || {
+                let hedger = hedger.clone();
+                let cancellation_token = cancellation_token.clone();
+                async move {
+                    if let Err(err) = hedger.check(cancellation_token).await {
+                        log::error!("Hedger '{}' failed: {err:?}", hedger.settings.name);
+                    }
+                }
+            },
+        );
+    }
+
+    /// Signed sum of weighted positions of hedged markets and hedge market
+    pub fn net_position(&self) -> Amount {
+        let balance_manager = self.balance_manager.lock();
+        net_position(&self.settings, |exchange_account_id, currency_pair| {
+            balance_manager.net_position(exchange_account_id, currency_pair)
+        })
+    }
+
+    async fn check(&self, cancellation_token: CancellationToken) -> Result<()> {
+        let hedge_market = &self.settings.hedge_market;
+        let exchange = self
+            .exchanges
+            .get(&hedge_market.exchange_account_id)
+            .map(|x| x.value().clone())
+            .with_context(|| {
+                format!("Exchange {} isn't found", hedge_market.exchange_account_id)
+            })?;
+
+        let active_order = self.active_order.lock().clone();
+        if let Some(order) = active_order {
+            if !order.is_finished() {
+                // new order is created on later checks, when the canceled one is finished
+                if self.should_chase(&exchange, &order) {
+                    let _ = exchange.cancel_order(&order, cancellation_token).await;
+                }
+                return Ok(());
+            }
+
+            *self.active_order.lock() = None;
+        }
+
+        let (side, amount) = match hedge_order_params(&self.settings, self.net_position()) {
+            Some(params) => params,
+            None => return Ok(()),
+        };
+
+        let now = self.clock.now();
+        let cooldown = chrono::Duration::seconds(self.settings.cooldown_secs as i64);
+        if let Some(last_order_time) = *self.last_order_time.lock() {
+            if now - last_order_time < cooldown {
+                return Ok(());
+            }
+        }
+
+        let user_order = match self.settings.aggression {
+            HedgeAggression::Market => UserOrder::Market,
+            HedgeAggression::LimitChase => {
+                match top_price(&exchange, hedge_market.currency_pair, side) {
+                    Some(price) => UserOrder::maker_only(price),
+                    None => {
+                        log::warn!(
+                            "Hedger '{}' can't create order because order book of {} {} is unknown",
+                            self.settings.name,
+                            hedge_market.exchange_account_id,
+                            hedge_market.currency_pair
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+        };
+
+        let header = OrderHeader::with_user_order(
+            ClientOrderId::unique_id(),
+            hedge_market.exchange_account_id,
+            hedge_market.currency_pair,
+            side,
+            amount,
+            user_order,
+            None,
+            None,
+            self.settings.name.clone(),
+        );
+        *self.last_order_time.lock() = Some(now);
+
+        let order = exchange
+            .create_order(&header, None, cancellation_token)
+            .await
+            .with_context(|| format!("Failed to create hedging order {header:?}"))?;
+        *self.active_order.lock() = Some(order);
+
+        Ok(())
+    }
+
+    fn should_chase(&self, exchange: &Exchange, order: &OrderRef) -> bool {
+        if self.settings.aggression != HedgeAggression::LimitChase {
+            return false;
+        }
+
+        let top_price = top_price(
+            exchange,
+            self.settings.hedge_market.currency_pair,
+            order.side(),
+        );
+        top_price.is_some_and(|price| price != order.price())
+    }
+}
+
+/// Price of order book top on the side of the order, so the order is the best one without crossing
+fn top_price(exchange: &Exchange, currency_pair: CurrencyPair, side: OrderSide) -> Option<Price> {
+    let top = exchange.order_book_top.get(&currency_pair)?;
+    let level = match side {
+        OrderSide::Buy => top.bid.as_ref()?,
+        OrderSide::Sell => top.ask.as_ref()?,
+    };
+    Some(level.price)
+}
+
+fn net_position(
+    settings: &HedgerSettings,
+    get_position: impl Fn(ExchangeAccountId, CurrencyPair) -> Amount,
+) -> Amount {
+    settings
+        .markets
+        .iter()
+        .chain([&settings.hedge_market])
+        .map(|x| get_position(x.exchange_account_id, x.currency_pair) * x.weight())
+        .sum()
+}
+
+/// Side and amount of order on hedge market which offsets the net position
+fn hedge_order_params(
+    settings: &HedgerSettings,
+    net_position: Amount,
+) -> Option<(OrderSide, Amount)> {
+    if net_position.abs() <= settings.threshold {
+        return None;
+    }
+
+    let side = match net_position.is_sign_positive() {
+        true => OrderSide::Sell,
+        false => OrderSide::Buy,
+    };
+    Some((side, net_position.abs() / settings.hedge_market.weight()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::ExposureMarketSettings;
+    use rust_decimal_macros::dec;
+
+    fn settings() -> HedgerSettings {
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        HedgerSettings {
+            name: "hedger".to_owned(),
+            markets: vec![ExposureMarketSettings {
+                exchange_account_id: ExchangeAccountId::new("Binance", 0),
+                currency_pair,
+                weight: None,
+            }],
+            hedge_market: ExposureMarketSettings {
+                exchange_account_id: ExchangeAccountId::new("Okx", 0),
+                currency_pair,
+                weight: Some(dec!(0.01)),
+            },
+            threshold: dec!(0.1),
+            aggression: HedgeAggression::Market,
+            cooldown_secs: 5,
+            interval_millis: 100,
+        }
+    }
+
+    #[test]
+    fn net_position_includes_hedge_market() {
+        let settings = settings();
+        let spot = settings.markets[0].exchange_account_id;
+
+        let net_position = net_position(&settings, |exchange_account_id, _| {
+            match exchange_account_id == spot {
+                true => dec!(1.5),
+                false => dec!(-100),
+            }
+        });
+
+        assert_eq!(net_position, dec!(0.5));
+    }
+
+    #[test]
+    fn hedge_order_offsets_net_position_above_threshold() {
+        let settings = settings();
+
+        assert_eq!(hedge_order_params(&settings, dec!(0.1)), None);
+        assert_eq!(hedge_order_params(&settings, dec!(-0.05)), None);
+        assert_eq!(
+            hedge_order_params(&settings, dec!(0.5)),
+            Some((OrderSide::Sell, dec!(50)))
+        );
+        assert_eq!(
+            hedge_order_params(&settings, dec!(-0.2)),
+            Some((OrderSide::Buy, dec!(20)))
+        );
+    }
+}
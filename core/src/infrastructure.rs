@@ -9,6 +9,7 @@ use std::panic;
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::alerting::{alert, AlertKind, AlertSeverity};
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
 
 static LIFETIME_MANAGER: OnceCell<Mutex<Option<Arc<AppLifetimeManager>>>> = OnceCell::new();
@@ -42,7 +43,7 @@ pub(crate) fn unset_lifetime_manager() {
     };
 }
 
-fn get_futures_cancellation_token() -> CancellationToken {
+pub(crate) fn get_futures_cancellation_token() -> CancellationToken {
     LIFETIME_MANAGER
         .get()
         .expect("Unable to get_futures_cancellation_token if AppLifetimeManager isn't set")
@@ -137,7 +138,14 @@ pub fn spawn_future_standalone(
     )
 }
 
-fn spawn_graceful_shutdown(log_template: String, error_message: &str) {
+pub(crate) fn spawn_graceful_shutdown(log_template: String, error_message: &str) {
+    alert(
+        AlertSeverity::Critical,
+        AlertKind::Panic,
+        "",
+        format!("{log_template} panicked: {error_message}. Graceful shutdown is started"),
+    );
+
     match LIFETIME_MANAGER.get() {
         Some(lifetime_manager) => {
             match &*lifetime_manager.lock() {
@@ -16,26 +16,41 @@
     clippy::unwrap_used
 )]
 
+pub mod accounting;
+pub mod alerting;
 pub mod balance;
 pub mod connectivity;
 pub mod exchanges;
 pub mod infrastructure;
 pub mod misc;
 pub mod orders;
+pub mod risk;
 pub mod rpc;
+pub mod runtimes;
 pub mod service_configuration;
 pub mod statistic_service;
+pub mod telemetry;
 
 pub mod config;
+pub mod config_watcher;
 pub mod database;
 pub mod disposition_execution;
 pub mod explanation;
+pub mod health;
+pub mod hedger;
 pub mod lifecycle;
 pub mod math;
 pub mod order_book;
-pub(crate) mod services;
+pub mod rebalancing;
+pub mod secrets;
+pub mod services;
 pub mod settings;
+pub mod strategy_registry;
+pub mod supervisor;
 pub mod text;
+pub mod treasury;
+
+pub use lifecycle::engine_builder::{EngineBuilder, EngineCommand, EngineController, EngineHandle};
 
 #[cfg(test)]
 use parking_lot::ReentrantMutex;
@@ -80,6 +80,33 @@ impl AppLifetimeManager {
         }))
     }
 
+    /// Synchronous method for halting trading by kill switch without waiting for the operation
+    /// to complete. See `EngineContext::kill_switch`
+    pub fn spawn_kill_switch(
+        &self,
+        reason: &str,
+        flatten_positions: bool,
+    ) -> Option<JoinHandle<()>> {
+        let engine_context = match self.engine_context.try_lock() {
+            Ok(engine_context_guard) => engine_context_guard.as_ref()?.upgrade(),
+            // lock is held only while graceful shutdown is started, so trading is stopping anyway
+            Err(_) => return None,
+        };
+
+        let engine_context = match engine_context {
+            Some(engine_context) => engine_context,
+            None => {
+                log::warn!("Can't trigger kill switch with reason '{reason}', because 'engine_context' was dropped already");
+                return None;
+            }
+        };
+
+        let reason = reason.to_owned();
+        Some(tokio::spawn(async move {
+            engine_context.kill_switch(&reason, flatten_positions).await
+        }))
+    }
+
     /// Launch async graceful shutdown operation
     pub async fn run_graceful_shutdown(&self, reason: &str) {
         let engine_context_guard = self.engine_context.lock().await;
@@ -114,6 +141,8 @@ fn start_graceful_shutdown_inner(
             log::warn!("Can't execute graceful shutdown with reason '{}', because 'engine_context' was dropped already", reason);
             None
         }
-        Some(ctx) => Some(ctx.graceful_shutdown(action, futures_cancellation_token)),
+        Some(ctx) => {
+            Some(ctx.graceful_shutdown(reason.to_owned(), action, futures_cancellation_token))
+        }
     }
 }
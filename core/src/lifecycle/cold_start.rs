@@ -0,0 +1,345 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use futures::future::join_all;
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::snapshot::Amount;
+use mmb_domain::position::ActivePosition;
+use mmb_utils::cancellation_token::CancellationToken;
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::exchanges::block_reasons::COLD_START_CONFIRMATION;
+use crate::exchanges::exchange_blocker::{BlockType, ExchangeBlocker};
+use crate::exchanges::general::exchange::Exchange;
+use crate::settings::ColdStartSettings;
+
+/// State recovered from exchange at startup that exceeds configured threshold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ColdStartViolation {
+    /// Open orders can't be requested, so recovered state is unknown
+    UnknownOpenOrders {
+        exchange_account_id: ExchangeAccountId,
+    },
+    OpenOrders {
+        exchange_account_id: ExchangeAccountId,
+        count: usize,
+        limit: usize,
+    },
+    Position {
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        amount: Amount,
+        limit: Amount,
+    },
+}
+
+impl ColdStartViolation {
+    pub fn exchange_account_id(&self) -> ExchangeAccountId {
+        match self {
+            ColdStartViolation::UnknownOpenOrders {
+                exchange_account_id,
+            }
+            | ColdStartViolation::OpenOrders {
+                exchange_account_id,
+                ..
+            }
+            | ColdStartViolation::Position {
+                exchange_account_id,
+                ..
+            } => *exchange_account_id,
+        }
+    }
+}
+
+impl Display for ColdStartViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColdStartViolation::UnknownOpenOrders {
+                exchange_account_id,
+            } => write!(f, "open orders of {exchange_account_id} can't be requested"),
+            ColdStartViolation::OpenOrders {
+                exchange_account_id,
+                count,
+                limit,
+            } => write!(
+                f,
+                "{exchange_account_id} has {count} open orders (limit {limit})"
+            ),
+            ColdStartViolation::Position {
+                exchange_account_id,
+                currency_pair,
+                amount,
+                limit,
+            } => write!(
+                f,
+                "{exchange_account_id} has position {amount} on {currency_pair} (limit {limit})"
+            ),
+        }
+    }
+}
+
+/// Keeps trading paused after startup if open orders or positions recovered from exchanges
+/// exceed configured thresholds, e.g. after a crash during a volatile period. Affected exchange
+/// accounts are blocked until operator confirms the recovered state via control API
+pub struct ColdStartGuard {
+    exchange_blocker: Arc<ExchangeBlocker>,
+    violations: Mutex<Vec<ColdStartViolation>>,
+}
+
+impl ColdStartGuard {
+    pub fn new(exchange_blocker: Arc<ExchangeBlocker>) -> Arc<Self> {
+        Arc::new(Self {
+            exchange_blocker,
+            violations: Default::default(),
+        })
+    }
+
+    /// Checks state of exchange accounts and blocks the ones exceeding thresholds
+    pub(crate) async fn check(
+        &self,
+        settings: &ColdStartSettings,
+        exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+        cancellation_token: CancellationToken,
+    ) {
+        let exchanges = exchanges
+            .iter()
+            .map(|x| x.value().clone())
+            .filter(|x| !x.is_watch_only())
+            .collect::<Vec<_>>();
+
+        let violations = join_all(
+            exchanges
+                .into_iter()
+                .map(|x| recovered_state_violations(settings, x, cancellation_token.clone())),
+        )
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        for violation in &violations {
+            log::warn!("Cold start protection: {violation}. Trading is paused until confirmation");
+
+            let exchange_account_id = violation.exchange_account_id();
+            if !self
+                .exchange_blocker
+                .is_blocked_by_reason(exchange_account_id, COLD_START_CONFIRMATION)
+            {
+                self.exchange_blocker.block(
+                    exchange_account_id,
+                    COLD_START_CONFIRMATION,
+                    BlockType::Manual,
+                );
+            }
+        }
+
+        *self.violations.lock() = violations;
+    }
+
+    pub fn is_confirmation_required(&self) -> bool {
+        !self.violations.lock().is_empty()
+    }
+
+    /// Violations waiting for operator confirmation
+    pub fn violations(&self) -> Vec<ColdStartViolation> {
+        self.violations.lock().clone()
+    }
+
+    /// Operator accepts recovered state, so strategies can resume trading.
+    /// Returns confirmed violations
+    pub fn confirm(&self) -> Vec<ColdStartViolation> {
+        let violations = std::mem::take(&mut *self.violations.lock());
+
+        let exchange_account_ids = violations
+            .iter()
+            .map(|x| x.exchange_account_id())
+            .collect::<HashSet<_>>();
+        for exchange_account_id in exchange_account_ids {
+            log::info!("Cold start state of {exchange_account_id} is confirmed by operator");
+            self.exchange_blocker
+                .unblock(exchange_account_id, COLD_START_CONFIRMATION);
+        }
+
+        violations
+    }
+}
+
+async fn recovered_state_violations(
+    settings: &ColdStartSettings,
+    exchange: Arc<Exchange>,
+    cancellation_token: CancellationToken,
+) -> Vec<ColdStartViolation> {
+    let open_orders_count = match settings.max_open_orders_count {
+        None => Some(0),
+        Some(_) => match exchange.get_open_orders(false).await {
+            Ok(orders) => Some(orders.len()),
+            Err(error) => {
+                log::error!(
+                    "Unable to get open orders of {} on cold start: {error:?}",
+                    exchange.exchange_account_id
+                );
+                None
+            }
+        },
+    };
+
+    let is_margin_trading = exchange.exchange_client.get_settings().is_margin_trading;
+    let positions = match settings.max_position_amount.is_some() && is_margin_trading {
+        true => exchange.get_active_positions(cancellation_token).await,
+        false => Vec::new(),
+    };
+
+    find_violations(
+        settings,
+        exchange.exchange_account_id,
+        open_orders_count,
+        &positions,
+    )
+}
+
+fn find_violations(
+    settings: &ColdStartSettings,
+    exchange_account_id: ExchangeAccountId,
+    open_orders_count: Option<usize>,
+    positions: &[ActivePosition],
+) -> Vec<ColdStartViolation> {
+    let mut violations = Vec::new();
+
+    if let Some(limit) = settings.max_open_orders_count {
+        match open_orders_count {
+            // unknown state should be checked by operator too
+            None => violations.push(ColdStartViolation::UnknownOpenOrders {
+                exchange_account_id,
+            }),
+            Some(count) if count > limit => violations.push(ColdStartViolation::OpenOrders {
+                exchange_account_id,
+                count,
+                limit,
+            }),
+            Some(_) => {}
+        }
+    }
+
+    if let Some(limit) = settings.max_position_amount {
+        violations.extend(
+            positions
+                .iter()
+                .filter(|x| x.derivative.position.abs() > limit)
+                .map(|x| ColdStartViolation::Position {
+                    exchange_account_id,
+                    currency_pair: x.derivative.currency_pair,
+                    amount: x.derivative.position,
+                    limit,
+                }),
+        );
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use mmb_domain::position::DerivativePosition;
+    use rust_decimal_macros::dec;
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", 0)
+    }
+
+    fn position(amount: Amount) -> ActivePosition {
+        ActivePosition::new(
+            DerivativePosition::new(
+                CurrencyPair::from_codes("btc".into(), "usdt".into()),
+                amount,
+                dec!(20000),
+                dec!(0),
+                dec!(1),
+            ),
+            Utc::now(),
+        )
+    }
+
+    #[test]
+    fn no_violations_within_thresholds() {
+        let settings = ColdStartSettings {
+            max_open_orders_count: Some(2),
+            max_position_amount: Some(dec!(1)),
+        };
+
+        let violations = find_violations(
+            &settings,
+            exchange_account_id(),
+            Some(2),
+            &[position(dec!(-1)), position(dec!(0.5))],
+        );
+
+        assert_eq!(violations, vec![]);
+    }
+
+    #[test]
+    fn violations_above_thresholds() {
+        let settings = ColdStartSettings {
+            max_open_orders_count: Some(2),
+            max_position_amount: Some(dec!(1)),
+        };
+
+        let violations = find_violations(
+            &settings,
+            exchange_account_id(),
+            Some(3),
+            &[position(dec!(-1.5)), position(dec!(0.5))],
+        );
+
+        assert_eq!(
+            violations,
+            vec![
+                ColdStartViolation::OpenOrders {
+                    exchange_account_id: exchange_account_id(),
+                    count: 3,
+                    limit: 2,
+                },
+                ColdStartViolation::Position {
+                    exchange_account_id: exchange_account_id(),
+                    currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+                    amount: dec!(-1.5),
+                    limit: dec!(1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unset_thresholds_are_not_checked() {
+        let violations = find_violations(
+            &ColdStartSettings::default(),
+            exchange_account_id(),
+            None,
+            &[position(dec!(100))],
+        );
+
+        assert_eq!(violations, vec![]);
+    }
+
+    #[test]
+    fn unknown_open_orders_require_confirmation() {
+        let settings = ColdStartSettings {
+            max_open_orders_count: Some(2),
+            max_position_amount: None,
+        };
+
+        let violations = find_violations(&settings, exchange_account_id(), None, &[]);
+
+        assert_eq!(
+            violations,
+            vec![ColdStartViolation::UnknownOpenOrders {
+                exchange_account_id: exchange_account_id(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use mmb_domain::events::ExchangeEvent;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+
+use crate::alerting::Notifier;
+use crate::exchanges::traits::ExchangeClientBuilder;
+use crate::infrastructure::spawn_future_ok;
+use crate::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
+use crate::lifecycle::launcher::{launch_trading_engine, EngineBuildConfig, InitSettings};
+use crate::lifecycle::trading_engine::{EngineContext, TradingEngine};
+use crate::misc::clock::Clock;
+use crate::settings::AppSettings;
+use crate::strategy_registry::Strategy;
+
+const CONTROL_CHANNEL_SIZE: usize = 32;
+
+/// Commands which can be sent to running engine by `EngineController`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineCommand {
+    /// Starts graceful shutdown, `EngineHandle::run` returns after it
+    Stop,
+    /// Starts graceful shutdown after which `EngineHandle::run` returns
+    /// `ActionAfterGracefulShutdown::Restart`
+    Restart,
+    /// Starts stopped or paused strategy registered by `EngineBuilder::strategy`
+    StartStrategy(String),
+    /// Cancels orders of strategy and stops it
+    StopStrategy(String),
+    /// Stops placing new orders by strategy, its orders stay on exchanges
+    PauseStrategy(String),
+    /// Halts trading until restart, see `AppLifetimeManager::spawn_kill_switch`
+    KillSwitch { flatten_positions: bool },
+}
+
+struct ControlRequest {
+    command: EngineCommand,
+    reply: oneshot::Sender<Result<()>>,
+}
+
+/// Sends commands to running engine. Can be cloned and used from any task
+#[derive(Clone)]
+pub struct EngineController {
+    sender: mpsc::Sender<ControlRequest>,
+}
+
+impl EngineController {
+    /// Returns error if command can't be executed or engine is stopped
+    pub async fn send(&self, command: EngineCommand) -> Result<()> {
+        let (reply, reply_receiver) = oneshot::channel();
+        self.sender
+            .send(ControlRequest { command, reply })
+            .await
+            .map_err(|_| anyhow!("Engine is stopped"))?;
+        reply_receiver
+            .await
+            .context("Engine stopped before command is executed")?
+    }
+}
+
+/// Builds trading engine from code instead of config files and binary, e.g. for embedding
+/// engine into other application:
+/// ```ignore
+/// let engine = EngineBuilder::new(InitSettings::Directly(settings))
+///     .connector(Box::new(BinanceBuilder))
+///     .strategy("grid", Arc::new(grid_strategy))
+///     .notifier(Arc::new(my_notifier))
+///     .start()
+///     .await?;
+/// let mut events = engine.subscribe_events();
+/// let controller = engine.controller();
+/// engine.run().await;
+/// ```
+pub struct EngineBuilder<StrategySettings: Clone> {
+    build_config: EngineBuildConfig,
+    settings: InitSettings<StrategySettings>,
+    strategies: Vec<(String, Arc<dyn Strategy>)>,
+}
+
+impl<StrategySettings> EngineBuilder<StrategySettings>
+where
+    StrategySettings: Clone + Debug + DeserializeOwned + Serialize + Send + Sync + 'static,
+{
+    pub fn new(settings: InitSettings<StrategySettings>) -> Self {
+        EngineBuilder {
+            build_config: EngineBuildConfig::new(Vec::new()),
+            settings,
+            strategies: Vec::new(),
+        }
+    }
+
+    /// Registers connector for exchange accounts of its exchange
+    pub fn connector(mut self, builder: Box<dyn ExchangeClientBuilder>) -> Self {
+        let _ = self
+            .build_config
+            .supported_exchange_clients
+            .insert(builder.get_exchange_id(), builder);
+        self
+    }
+
+    /// Strategy is registered in `StrategyRegistry` and started by `start`
+    pub fn strategy(mut self, name: &str, strategy: Arc<dyn Strategy>) -> Self {
+        self.strategies.push((name.to_owned(), strategy));
+        self
+    }
+
+    /// Replaces system clock, e.g. by `VirtualClock` for simulations
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.build_config.clock = clock;
+        self
+    }
+
+    /// Notifier receives alerts in addition to ones configured by alerting settings. Alerts with
+    /// default alerting settings are sent if they aren't configured
+    pub fn notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.build_config.notifiers.push(notifier);
+        self
+    }
+
+    /// Launches engine and starts strategies. Websockets are connected by `EngineHandle::run`
+    pub async fn start(self) -> Result<EngineHandle<StrategySettings>> {
+        let engine = launch_trading_engine(&self.build_config, self.settings).await?;
+        for (name, strategy) in self.strategies {
+            engine
+                .start_strategy(&name, strategy)
+                .with_context(|| format!("Unable to start strategy '{name}'"))?;
+        }
+
+        let (sender, receiver) = mpsc::channel(CONTROL_CHANNEL_SIZE);
+        spawn_future_ok(
+            "Handle engine commands",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            handle_commands(engine.context(), receiver),
+        );
+
+        Ok(EngineHandle {
+            engine,
+            controller: EngineController { sender },
+        })
+    }
+}
+
+/// Running engine created by `EngineBuilder`
+pub struct EngineHandle<StrategySettings: Clone> {
+    engine: TradingEngine<StrategySettings>,
+    controller: EngineController,
+}
+
+impl<StrategySettings: Clone> EngineHandle<StrategySettings> {
+    pub fn context(&self) -> Arc<EngineContext> {
+        self.engine.context()
+    }
+
+    pub fn settings(&self) -> &AppSettings<StrategySettings> {
+        self.engine.settings()
+    }
+
+    pub fn subscribe_strategy_settings(&self) -> watch::Receiver<StrategySettings> {
+        self.engine.subscribe_strategy_settings()
+    }
+
+    /// Receiver of all exchange events processed by engine
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ExchangeEvent> {
+        self.engine.context().get_events_channel()
+    }
+
+    pub fn controller(&self) -> EngineController {
+        self.controller.clone()
+    }
+
+    /// Connects websockets and waits for graceful shutdown
+    pub async fn run(self) -> ActionAfterGracefulShutdown {
+        self.engine.run().await
+    }
+}
+
+async fn handle_commands(
+    engine_context: Arc<EngineContext>,
+    mut receiver: mpsc::Receiver<ControlRequest>,
+) {
+    while let Some(ControlRequest { command, reply }) = receiver.recv().await {
+        log::info!("Engine command {command:?} is received");
+        let result = execute(&engine_context, command);
+        if reply.send(result).is_err() {
+            log::warn!("Result of engine command isn't received by sender");
+        }
+    }
+}
+
+fn execute(engine_context: &EngineContext, command: EngineCommand) -> Result<()> {
+    let lifetime_manager = &engine_context.lifetime_manager;
+    let registry = &engine_context.strategy_registry;
+    match command {
+        EngineCommand::Stop => {
+            let _ = lifetime_manager.spawn_graceful_shutdown("requested by engine controller");
+            Ok(())
+        }
+        EngineCommand::Restart => {
+            let _ = lifetime_manager.spawn_graceful_shutdown_with_action(
+                "restart requested by engine controller",
+                ActionAfterGracefulShutdown::Restart,
+            );
+            Ok(())
+        }
+        EngineCommand::StartStrategy(name) => registry.start(&name),
+        EngineCommand::StopStrategy(name) => registry.stop(&name),
+        EngineCommand::PauseStrategy(name) => registry.pause(&name),
+        EngineCommand::KillSwitch { flatten_positions } => lifetime_manager
+            .spawn_kill_switch("requested by engine controller", flatten_positions)
+            .map(|_| ())
+            .context("Unable to trigger kill switch"),
+    }
+}
@@ -1,6 +1,11 @@
+use crate::alerting::Notifier;
 use crate::balance::manager::balance_manager::BalanceManager;
 use crate::config::{load_pretty_settings, try_load_settings};
+use crate::config_watcher::ConfigWatcher;
+use crate::database::clickhouse::ClickHouseSink;
 use crate::database::events::recorder::EventRecorder;
+use crate::database::journal::{restore_recovered_state, Journal};
+use crate::database::order_history::OrderHistoryRecorder;
 use crate::exchanges::exchange_blocker::ExchangeBlocker;
 use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
 use crate::exchanges::general::exchange::Exchange;
@@ -12,18 +17,31 @@ use crate::exchanges::traits::ExchangeClientBuilder;
 use crate::infrastructure::spawn_future;
 use crate::infrastructure::{init_lifetime_manager, spawn_by_timer, spawn_future_ok};
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::lifecycle::cold_start::ColdStartGuard;
 use crate::lifecycle::trading_engine::{EngineContext, TradingEngine};
+use crate::misc::clock::{Clock, SystemClock};
+use crate::risk::kill_switch::KillSwitch;
+use crate::risk::loss_limit::LossLimitGuard;
+use crate::risk::risk_manager::RiskManager;
+use crate::rpc::cached_queries::{CachedQueries, CACHED_QUERIES_TTL};
 use crate::rpc::config_waiter::ConfigWaiter;
 use crate::rpc::core_api::CoreApi;
+use crate::runtimes::init_runtimes;
+use crate::secrets::{create_provider, resolve_credentials, CredentialsRotation};
 use crate::services::cleanup_orders::CleanupOrdersService;
-use crate::settings::{AppSettings, CoreSettings};
+use crate::settings::{
+    AppSettings, BalanceReconciliationSettings, CoreSettings, DbBackend, RiskSettings,
+};
 use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
 use core::fmt::Debug;
 use dashmap::DashMap;
 use futures::{future::join_all, FutureExt};
 use itertools::Itertools;
 use mmb_database::postgres_db::migrator::apply_migrations;
 use mmb_database::postgres_db::PgPool;
+use mmb_database::sqlite_db::SqliteStorage;
+use mmb_database::storage::EventStorage;
 use mmb_domain::events::{ExchangeEvent, ExchangeEvents, CHANNEL_MAX_EVENTS_COUNT};
 use mmb_domain::market::ExchangeAccountId;
 use mmb_domain::market::ExchangeId;
@@ -47,10 +65,16 @@ use uuid::Uuid;
 use crate::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
 use crate::services::cleanup_database::CleanupDatabaseService;
 use crate::services::exchange_time_latency::ExchangeTimeLatencyService;
+use crate::services::kafka_publisher::KafkaPublisher;
 use crate::services::live_ranges::LiveRangesService;
+use crate::services::redis_bridge::RedisBridge;
 
 pub struct EngineBuildConfig {
     pub supported_exchange_clients: HashMap<ExchangeId, Box<dyn ExchangeClientBuilder + 'static>>,
+    /// Clock of timeouts, rate limits and engine services. System clock is used by default
+    pub clock: Arc<dyn Clock>,
+    /// Notifiers receiving alerts in addition to ones configured by alerting settings
+    pub notifiers: Vec<Arc<dyn Notifier>>,
 }
 
 impl EngineBuildConfig {
@@ -62,10 +86,14 @@ impl EngineBuildConfig {
 
         EngineBuildConfig {
             supported_exchange_clients,
+            clock: SystemClock::shared(),
+            notifiers: Vec::new(),
         }
     }
 }
 
+// settings are passed once at startup, so size of the enum doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum InitSettings<StrategySettings: Clone> {
     Directly(AppSettings<StrategySettings>),
@@ -138,7 +166,7 @@ where
 
     let lifetime_manager = init_lifetime_manager();
 
-    let settings = match init_user_settings {
+    let mut settings = match init_user_settings {
         InitSettings::Directly(v) => v,
         InitSettings::Load {
             config_path,
@@ -151,6 +179,15 @@ where
         }
     };
 
+    if let Some(secrets) = &settings.core.secrets {
+        let provider = create_provider(&secrets.provider)?;
+        resolve_credentials(&mut settings.core, &*provider).await?;
+    }
+
+    if let Some(runtimes) = &settings.core.runtimes {
+        init_runtimes(runtimes)?;
+    }
+
     let (events_sender, events_receiver) = broadcast::channel(CHANNEL_MAX_EVENTS_COUNT);
 
     let timeout_manager = create_timeout_manager(&settings.core, build_settings);
@@ -164,23 +201,44 @@ where
 
     let exchange_blocker = ExchangeBlocker::new(exchange_account_ids);
 
-    let (pool, postponed_events_dir) = if let Some(db) = &settings.core.database {
-        apply_migrations(&db.url, db.migrations.clone())
-            .await
-            .context("unable apply db migrations")?;
+    let (pool, storage, postponed_events_dir, postponed_events_format) =
+        if let Some(db) = &settings.core.database {
+            let (pool, storage): (_, Arc<dyn EventStorage>) = match db.backend {
+                DbBackend::Postgres => {
+                    apply_migrations(&db.url, db.migrations.clone())
+                        .await
+                        .context("unable apply db migrations")?;
 
-        let pool = PgPool::create(&db.url, 5)
-            .await
-            .with_context(|| format!("from `launcher` with connection_string: {}", &db.url))?;
+                    let pool = PgPool::create(&db.url, 5).await.with_context(|| {
+                        format!("from `launcher` with connection_string: {}", &db.url)
+                    })?;
 
-        (Some(pool), db.postponed_events_dir.clone())
-    } else {
-        (None, None)
-    };
+                    (Some(pool.clone()), Arc::new(pool))
+                }
+                DbBackend::Sqlite => {
+                    // sqlite allows only one writer at a time
+                    let storage = SqliteStorage::create(&db.url, 1).await.with_context(|| {
+                        format!("from `launcher` with connection_string: {}", &db.url)
+                    })?;
+
+                    (None, Arc::new(storage))
+                }
+            };
+
+            (
+                pool,
+                Some(storage),
+                db.postponed_events_dir.clone(),
+                db.postponed_events_format.unwrap_or_default(),
+            )
+        } else {
+            (None, None, None, Default::default())
+        };
 
-    let event_recorder = EventRecorder::start(pool.clone(), postponed_events_dir)
-        .await
-        .expect("can't start EventRecorder");
+    let event_recorder =
+        EventRecorder::start(storage, postponed_events_dir, postponed_events_format)
+            .await
+            .expect("can't start EventRecorder");
 
     let exchanges = create_exchanges(
         &settings.core,
@@ -208,6 +266,18 @@ where
         Some(event_recorder.clone()),
     );
 
+    if let Some(journal_settings) = &settings.core.journal {
+        let (journal, recovered) =
+            Journal::open(&journal_settings.path, journal_settings.compaction_records)
+                .context("unable to recover state from journal")?;
+        restore_recovered_state(recovered, &exchanges_map, &balance_manager);
+
+        for exchange in &exchanges_map {
+            exchange.setup_journal(journal.clone());
+        }
+        balance_manager.lock().set_journal(journal);
+    }
+
     BalanceManager::update_balances_for_exchanges(
         balance_manager.clone(),
         lifetime_manager.stop_token(),
@@ -220,7 +290,62 @@ where
             .setup_balance_manager(balance_manager.clone())
     }
 
-    start_updating_balances(&lifetime_manager, &balance_manager);
+    let kill_switch = KillSwitch::new();
+    for exchange in &exchanges_map {
+        exchange.value().setup_kill_switch(kill_switch.clone())
+    }
+
+    if let Some(risk_settings) = &settings.core.risk {
+        check_exposure_markets(risk_settings, &exchanges_map)?;
+
+        let risk_manager =
+            RiskManager::with_clock(risk_settings.clone(), timeout_manager.clock().clone());
+        for exchange in &exchanges_map {
+            exchange.value().setup_risk_manager(risk_manager.clone())
+        }
+
+        let balance_manager = balance_manager.lock();
+        risk_manager.update_exposures(|exchange_account_id, currency_pair| {
+            balance_manager.net_position(exchange_account_id, currency_pair)
+        });
+    }
+
+    for hedger in &settings.core.hedgers {
+        for market in hedger.markets.iter().chain([&hedger.hedge_market]) {
+            let is_known_market = exchanges_map
+                .get(&market.exchange_account_id)
+                .is_some_and(|x| x.symbols.contains_key(&market.currency_pair));
+            if !is_known_market {
+                bail!(
+                    "Hedger '{}' contains unknown market {} {}",
+                    hedger.name,
+                    market.exchange_account_id,
+                    market.currency_pair
+                );
+            }
+        }
+    }
+
+    start_updating_balances(
+        &lifetime_manager,
+        &balance_manager,
+        settings
+            .core
+            .balance_reconciliation
+            .clone()
+            .unwrap_or_default(),
+    );
+
+    let cold_start_guard = ColdStartGuard::new(exchange_blocker.clone());
+    if let Some(cold_start_settings) = &settings.core.cold_start {
+        cold_start_guard
+            .check(
+                cold_start_settings,
+                &exchanges_map,
+                lifetime_manager.stop_token(),
+            )
+            .await;
+    }
 
     let (finish_graceful_shutdown_tx, finish_graceful_shutdown_rx) = oneshot::channel();
 
@@ -229,11 +354,70 @@ where
         exchanges_map.clone(),
         ExchangeEvents::new(events_sender),
         finish_graceful_shutdown_tx,
-        exchange_blocker,
+        exchange_blocker.clone(),
         timeout_manager,
         lifetime_manager.clone(),
         balance_manager,
         event_recorder,
+        cold_start_guard,
+        LossLimitGuard::new(exchange_blocker.clone()),
+        kill_switch,
+        build_settings.notifiers.clone(),
+    );
+    for exchange in &engine_context.exchanges {
+        exchange.setup_volatility_service(engine_context.volatility.clone());
+        exchange.setup_index_price_service(&engine_context.index_prices);
+        if let Some(cross_rates) = &engine_context.cross_rates {
+            exchange.setup_cross_rate_service(cross_rates);
+            if let Some(risk_manager) = exchange.risk_manager() {
+                risk_manager.setup_cross_rates(cross_rates);
+            }
+        }
+    }
+    engine_context
+        .symbol_service
+        .start_refreshing(&settings.core.symbol_refresh.clone().unwrap_or_default());
+    if let Some(treasury) = &engine_context.treasury {
+        treasury.start_tracking(lifetime_manager.stop_token());
+    }
+    if let Some(rebalancer) = &engine_context.rebalancer {
+        rebalancer.start(lifetime_manager.stop_token());
+    }
+    for hedger in &engine_context.hedgers {
+        hedger.start(lifetime_manager.stop_token());
+    }
+    engine_context
+        .candle_service
+        .start(engine_context.get_events_channel());
+    engine_context
+        .signal_service
+        .start(lifetime_manager.stop_token());
+    engine_context
+        .order_expiry
+        .start(lifetime_manager.stop_token());
+    engine_context
+        .trading_sessions
+        .start(lifetime_manager.stop_token());
+    engine_context.latency_throttling.start();
+    if let Some(event_stream) = &engine_context.event_stream {
+        event_stream.start(
+            Arc::downgrade(&engine_context),
+            engine_context.get_events_channel(),
+            lifetime_manager.stop_token(),
+        );
+    }
+    if let Some(alert_service) = &engine_context.alert_service {
+        alert_service.start(engine_context.get_events_channel());
+    }
+    if let Some(daily_report) = &engine_context.daily_report {
+        daily_report.start();
+    }
+    if let Some(eod_report) = &engine_context.eod_report {
+        eod_report.start(engine_context.get_events_channel());
+    }
+    engine_context.health_monitor.start(
+        engine_context.get_events_channel(),
+        &engine_context.supervisor,
     );
 
     Ok((
@@ -246,21 +430,47 @@ where
     ))
 }
 
+pub(crate) fn check_exposure_markets(
+    risk_settings: &RiskSettings,
+    exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+) -> Result<()> {
+    for group in &risk_settings.exposure_groups {
+        for market in &group.markets {
+            let is_known_market = exchanges
+                .get(&market.exchange_account_id)
+                .is_some_and(|x| x.symbols.contains_key(&market.currency_pair));
+            if !is_known_market {
+                bail!(
+                    "Exposure group '{}' contains unknown market {} {}",
+                    group.name,
+                    market.exchange_account_id,
+                    market.currency_pair
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn start_updating_balances(
     lifetime_manager: &Arc<AppLifetimeManager>,
     balance_manager: &Arc<Mutex<BalanceManager>>,
+    settings: BalanceReconciliationSettings,
 ) {
+    let interval = Duration::from_secs(settings.interval_secs.max(1));
     spawn_by_timer(
         "Update balances",
-        Duration::from_secs(60),
-        Duration::from_secs(60),
+        interval,
+        interval,
         SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
         {
             let balance_manager = balance_manager.clone();
             let stop_token = lifetime_manager.stop_token();
             move || {
-                BalanceManager::update_balances_for_exchanges(
+                BalanceManager::reconcile_balances_for_exchanges(
                     balance_manager.clone(),
+                    settings.clone(),
                     stop_token.clone(),
                 )
             }
@@ -292,6 +502,19 @@ where
         engine_context.lifetime_manager.clone(),
         load_pretty_settings(init_user_settings),
         engine_context.statistic_service.clone(),
+        CachedQueries::new(
+            engine_context.get_events_channel(),
+            engine_context.exchanges.clone(),
+            engine_context.balance_manager.clone(),
+            CACHED_QUERIES_TTL,
+        ),
+        engine_context.cold_start_guard.clone(),
+        engine_context.loss_limit_guard.clone(),
+        engine_context.rebalancer.clone(),
+        engine_context.strategy_registry.clone(),
+        engine_context.signal_service.clone(),
+        engine_context.health_monitor.clone(),
+        engine_context.exchanges.clone(),
     )
     .expect("Unable to start control panel");
     engine_context
@@ -312,6 +535,37 @@ where
         ),
     );
 
+    if settings.core.database.is_some() {
+        let _ = OrderHistoryRecorder::start(
+            engine_context.get_events_channel(),
+            engine_context.event_recorder.clone(),
+        );
+    }
+
+    if let Some(clickhouse) = &settings.core.clickhouse {
+        let _ = ClickHouseSink::start(clickhouse.clone(), engine_context.get_events_channel());
+    }
+
+    if let Some(kafka) = &settings.core.kafka {
+        let _ = KafkaPublisher::start(
+            kafka.clone(),
+            engine_context.statistic_service.clone(),
+            engine_context.get_events_channel(),
+            engine_context.lifetime_manager.stop_token(),
+        )
+        .expect("Unable to start Kafka publisher");
+    }
+
+    if let Some(redis_bridge) = &settings.core.redis_bridge {
+        let _ = RedisBridge::start(
+            redis_bridge.clone(),
+            engine_context.statistic_service.clone(),
+            Arc::downgrade(&engine_context),
+            engine_context.get_events_channel(),
+            engine_context.lifetime_manager.stop_token(),
+        );
+    }
+
     if let Some(data_services) = data_services {
         engine_context
             .shutdown_service
@@ -338,12 +592,41 @@ where
         );
     }
 
+    // loss limit is taken from risk manager every time, because it can be changed by config reload
+    if settings.core.risk.is_some() {
+        let engine_context = Arc::downgrade(&engine_context);
+        let _ = spawn_by_timer(
+            "loss limit",
+            Duration::ZERO,
+            Duration::from_secs(10),
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            move || {
+                if let Some(ctx) = engine_context.upgrade() {
+                    let loss_limit_settings = ctx
+                        .exchanges
+                        .iter()
+                        .find_map(|x| x.risk_manager())
+                        .and_then(|x| x.settings().loss_limit);
+                    if let Some(loss_limit_settings) = loss_limit_settings {
+                        ctx.loss_limit_guard.check(
+                            &loss_limit_settings,
+                            &ctx.exchanges,
+                            &ctx.balance_manager,
+                            Utc::now(),
+                        );
+                    }
+                }
+                futures::future::ready(())
+            },
+        );
+    }
+
     let cleanup_orders_service_weak = Arc::downgrade(&cleanup_orders_service);
 
     let _ = spawn_by_timer(
         "cleanup_outdated_orders",
         Duration::ZERO,
-        Duration::from_secs(600), // 10 minutes
+        cleanup_orders_service.interval(),
         SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
         move || {
             let cleanup_orders_service_weak = cleanup_orders_service_weak.clone();
@@ -441,7 +724,7 @@ pub async fn launch_trading_engine<StrategySettings>(
     init_user_settings: InitSettings<StrategySettings>,
 ) -> Result<TradingEngine<StrategySettings>>
 where
-    StrategySettings: Clone + Debug + DeserializeOwned + Serialize,
+    StrategySettings: Clone + Debug + DeserializeOwned + Serialize + Send + Sync + 'static,
 {
     print_info("The TradingEngine is going to start...");
     let action_outcome = AssertUnwindSafe(before_engine_context_init(
@@ -463,20 +746,25 @@ where
 
     let cloned_lifetime_manager = engine_context.lifetime_manager.clone();
     let action = async move {
-        signal::ctrl_c().await.expect("failed to listen for event");
+        let signal_name = wait_stop_signal().await;
 
-        print_info("Ctrl-C signal was received so graceful_shutdown will be started");
-        cloned_lifetime_manager.spawn_graceful_shutdown("Ctrl-C signal was received");
+        print_info(format_args!(
+            "{signal_name} signal was received so graceful_shutdown will be started"
+        ));
+        cloned_lifetime_manager
+            .spawn_graceful_shutdown(&format!("{signal_name} signal was received"));
     };
 
     let _ = spawn_future_ok(
-        "Start Ctrl-C handler",
+        "Start stop signals handler",
         SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
         action,
     );
 
-    let cleanup_orders_service =
-        Arc::new(CleanupOrdersService::new(engine_context.exchanges.clone()));
+    let cleanup_orders_service = Arc::new(CleanupOrdersService::new(
+        engine_context.exchanges.clone(),
+        settings.core.order_retention.as_ref(),
+    ));
 
     let data_services = match pool {
         None => None,
@@ -501,7 +789,7 @@ where
             events_receiver,
             settings,
             exchanges_map,
-            init_user_settings,
+            init_user_settings.clone(),
             finish_graceful_shutdown_rx,
             cleanup_orders_service,
             data_services,
@@ -516,6 +804,36 @@ where
         Some(engine_context.lifetime_manager.clone()),
     );
 
+    if let (
+        Ok(engine),
+        InitSettings::Load {
+            config_path,
+            credentials_path,
+        },
+    ) = (&result, init_user_settings)
+    {
+        if let Some(config_reload) = &engine.settings().core.config_reload {
+            let _ = ConfigWatcher::start(
+                config_reload,
+                config_path,
+                credentials_path,
+                engine.settings().clone(),
+                &engine.context(),
+                engine.strategy_settings_sender(),
+            );
+        }
+    }
+
+    if let Ok(engine) = &result {
+        if let Some(secrets) = &engine.settings().core.secrets {
+            if let Err(err) =
+                CredentialsRotation::start(secrets, &engine.settings().core, &engine.context())
+            {
+                log::error!("Unable to start rotation of credentials: {err:?}");
+            }
+        }
+    }
+
     print_info("The TradingEngine has been successfully launched");
 
     result
@@ -543,3 +861,26 @@ pub async fn create_exchanges(
     }))
     .await
 }
+
+/// Waits for Ctrl-C or SIGTERM which is sent by Kubernetes, Docker and systemd to stop process.
+/// Returns name of received signal
+async fn wait_stop_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM");
+        tokio::select! {
+            result = signal::ctrl_c() => {
+                result.expect("failed to listen for event");
+                "Ctrl-C"
+            }
+            _ = terminate.recv() => "SIGTERM",
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        signal::ctrl_c().await.expect("failed to listen for event");
+        "Ctrl-C"
+    }
+}
@@ -1,4 +1,9 @@
 pub mod app_lifetime_manager;
+pub mod cold_start;
+pub mod engine_builder;
 pub mod launcher;
 pub mod shutdown;
+pub mod shutdown_coordinator;
+pub mod test_helper;
 pub mod trading_engine;
+pub mod validation;
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use mmb_domain::market::{ExchangeAccountId, MarketAccountId};
+use mmb_domain::order::snapshot::ClientOrderId;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::logger::print_info;
+use mmb_utils::DateTime;
+use serde::Serialize;
+use tokio::time::{timeout, Duration};
+
+use crate::lifecycle::trading_engine::{
+    cancel_opened_orders, close_active_positions, EngineContext,
+};
+use crate::settings::{ShutdownSettings, ShutdownStep, ShutdownStepSettings};
+use crate::statistic_service::MarketAccountIdStatistic;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum StepOutcome {
+    Completed,
+    /// Step was interrupted by its timeout
+    TimedOut,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepReport {
+    pub step: ShutdownStep,
+    pub outcome: StepOutcome,
+    pub duration_millis: u64,
+}
+
+/// Status of graceful shutdown written to log and report file
+#[derive(Debug, Clone, Serialize)]
+pub struct ShutdownReport {
+    pub reason: String,
+    pub started_at: DateTime,
+    pub finished_at: DateTime,
+    pub steps: Vec<StepReport>,
+    /// Orders which are still not finished after shutdown by exchange account
+    pub open_orders: HashMap<ExchangeAccountId, Vec<ClientOrderId>>,
+    /// Final statistics of every trade place
+    pub statistics: HashMap<MarketAccountId, MarketAccountIdStatistic>,
+}
+
+impl ShutdownReport {
+    /// All steps are completed and no open orders are left on exchanges
+    pub fn is_successful(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|x| x.outcome == StepOutcome::Completed)
+            && self.open_orders.is_empty()
+    }
+
+    fn print(&self) {
+        let status = match self.is_successful() {
+            true => "successfully",
+            false => "with problems",
+        };
+        print_info(format_args!(
+            "Graceful shutdown '{}' finished {status}",
+            self.reason
+        ));
+        for step in &self.steps {
+            print_info(format_args!(
+                "\t{:?}: {:?} in {} ms",
+                step.step, step.outcome, step.duration_millis
+            ));
+        }
+        for (exchange_account_id, orders) in &self.open_orders {
+            print_info(format_args!(
+                "\tOrders left open on {exchange_account_id}: {}",
+                orders.iter().join(", ")
+            ));
+        }
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("serializing shutdown report")?;
+        std::fs::write(path, json)
+            .with_context(|| format!("writing shutdown report to {}", path.display()))
+    }
+}
+
+/// Time for saving events waiting in queue to database. It isn't included in timeout of
+/// `FlushPersistence` step, so events are saved even if stopping of core services took all its time
+const EVENTS_FLUSH_TIMEOUT_SECS: u64 = 5;
+
+/// Steps in order of execution. Configured steps go in listed order and the rest default steps
+/// follow them. Strategies stopping is moved to the start and persistence flushing to the end,
+/// because they are needed for all other steps
+fn shutdown_sequence(settings: &ShutdownSettings) -> Vec<ShutdownStepSettings> {
+    let defaults = ShutdownSettings::default().steps;
+    let configured = settings.steps.iter().unique_by(|x| x.step);
+    let step_settings = |step| {
+        configured
+            .clone()
+            .chain(&defaults)
+            .find(|x| x.step == step)
+            .cloned()
+            .expect("all steps are in default settings")
+    };
+
+    let is_middle_step = |x: &&ShutdownStepSettings| {
+        !matches!(
+            x.step,
+            ShutdownStep::StopStrategies | ShutdownStep::FlushPersistence
+        )
+    };
+
+    let mut sequence = vec![step_settings(ShutdownStep::StopStrategies)];
+    sequence.extend(
+        configured
+            .clone()
+            .chain(&defaults)
+            .filter(is_middle_step)
+            .unique_by(|x| x.step)
+            .cloned(),
+    );
+    sequence.push(step_settings(ShutdownStep::FlushPersistence));
+
+    sequence
+}
+
+/// Executes configured sequence of graceful shutdown steps and reports their outcomes
+pub(crate) async fn run_shutdown_sequence(ctx: &EngineContext, reason: &str) -> ShutdownReport {
+    let settings = ctx.core_settings.shutdown.clone().unwrap_or_default();
+    let clock = ctx.timeout_manager.clock().clone();
+    let started_at = clock.now();
+
+    let mut steps = Vec::new();
+    for step_settings in shutdown_sequence(&settings) {
+        steps.push(run_step(ctx, step_settings).await);
+    }
+
+    let open_orders = ctx
+        .exchanges
+        .iter()
+        .filter(|x| !x.orders.not_finished.is_empty())
+        .map(|x| {
+            let orders = x.orders.not_finished.iter().map(|x| x.key().clone());
+            (x.exchange_account_id, orders.collect())
+        })
+        .collect();
+
+    let report = ShutdownReport {
+        reason: reason.to_owned(),
+        started_at,
+        finished_at: clock.now(),
+        steps,
+        open_orders,
+        statistics: ctx
+            .statistic_service
+            .market_account_id_stats()
+            .into_iter()
+            .collect(),
+    };
+
+    report.print();
+    if let Some(path) = &settings.report_path {
+        if let Err(err) = report.write(path) {
+            log::error!("Failed to write shutdown report: {err:?}");
+        }
+    }
+
+    report
+}
+
+async fn run_step(ctx: &EngineContext, settings: ShutdownStepSettings) -> StepReport {
+    let ShutdownStepSettings { step, timeout_secs } = settings;
+    log::info!("Shutdown step {step:?} started");
+
+    let started = Instant::now();
+    let mut outcome = run_with_timeout(step, timeout_secs, |cancellation_token| {
+        execute_step(ctx, step, cancellation_token)
+    })
+    .await;
+
+    if step == ShutdownStep::FlushPersistence {
+        let flush_outcome = run_with_timeout(step, EVENTS_FLUSH_TIMEOUT_SECS, |_| {
+            ctx.event_recorder.flush_and_stop()
+        })
+        .await;
+        if outcome == StepOutcome::Completed {
+            outcome = flush_outcome;
+        }
+    }
+
+    StepReport {
+        step,
+        outcome,
+        duration_millis: started.elapsed().as_millis() as u64,
+    }
+}
+
+async fn run_with_timeout<F>(
+    step: ShutdownStep,
+    timeout_secs: u64,
+    action: impl FnOnce(CancellationToken) -> F,
+) -> StepOutcome
+where
+    F: Future<Output = Result<()>>,
+{
+    let cancellation_token = CancellationToken::default();
+    match timeout(
+        Duration::from_secs(timeout_secs),
+        action(cancellation_token.clone()),
+    )
+    .await
+    {
+        Ok(Ok(())) => StepOutcome::Completed,
+        Ok(Err(err)) => {
+            log::error!("Shutdown step {step:?} failed: {err:?}");
+            StepOutcome::Failed(format!("{err:#}"))
+        }
+        Err(_) => {
+            cancellation_token.cancel();
+            log::error!(
+                "Timeout {timeout_secs} secs is exceeded: shutdown step {step:?} has been stopped"
+            );
+            StepOutcome::TimedOut
+        }
+    }
+}
+
+async fn execute_step(
+    ctx: &EngineContext,
+    step: ShutdownStep,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    match step {
+        ShutdownStep::StopStrategies => {
+            let _ = ctx.shutdown_service.user_lvl_shutdown().await;
+        }
+        ShutdownStep::CancelOrders => {
+            cancel_opened_orders(&ctx.exchanges, cancellation_token, true).await;
+        }
+        ShutdownStep::FlattenPositions => {
+            close_active_positions(&ctx.exchanges, cancellation_token).await;
+        }
+        ShutdownStep::FlushPersistence => {
+            // events are flushed separately with their own timeout
+            ctx.exchange_blocker.stop_blocker().await;
+            let _ = ctx.shutdown_service.core_lvl_shutdown().await;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(step: ShutdownStep, timeout_secs: u64) -> ShutdownStepSettings {
+        ShutdownStepSettings { step, timeout_secs }
+    }
+
+    #[test]
+    fn strategies_are_stopped_first_and_persistence_is_flushed_last() {
+        let settings = ShutdownSettings {
+            steps: vec![
+                step(ShutdownStep::FlushPersistence, 20),
+                step(ShutdownStep::FlattenPositions, 30),
+                step(ShutdownStep::CancelOrders, 10),
+                step(ShutdownStep::FlattenPositions, 40),
+            ],
+            report_path: None,
+        };
+
+        assert_eq!(
+            shutdown_sequence(&settings),
+            vec![
+                step(ShutdownStep::StopStrategies, 5),
+                step(ShutdownStep::FlattenPositions, 30),
+                step(ShutdownStep::CancelOrders, 10),
+                step(ShutdownStep::FlushPersistence, 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn configured_timeouts_are_merged_into_default_steps() {
+        let settings = ShutdownSettings {
+            steps: vec![step(ShutdownStep::FlattenPositions, 30)],
+            report_path: None,
+        };
+
+        assert_eq!(
+            shutdown_sequence(&settings),
+            vec![
+                step(ShutdownStep::StopStrategies, 5),
+                step(ShutdownStep::FlattenPositions, 30),
+                step(ShutdownStep::CancelOrders, 5),
+                step(ShutdownStep::FlushPersistence, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_sequence_contains_all_steps() {
+        let sequence = shutdown_sequence(&ShutdownSettings::default())
+            .into_iter()
+            .map(|x| x.step)
+            .collect_vec();
+
+        assert_eq!(
+            sequence,
+            vec![
+                ShutdownStep::StopStrategies,
+                ShutdownStep::CancelOrders,
+                ShutdownStep::FlattenPositions,
+                ShutdownStep::FlushPersistence,
+            ]
+        );
+    }
+}
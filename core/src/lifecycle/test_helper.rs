@@ -0,0 +1,68 @@
+#![cfg(test)]
+
+use std::sync::Arc;
+
+use chrono::Duration;
+use dashmap::DashMap;
+use mmb_domain::events::ExchangeEvents;
+use mmb_utils::hashmap;
+use tokio::sync::{broadcast, oneshot};
+
+use crate::balance::manager::balance_manager::BalanceManager;
+use crate::exchanges::exchange_blocker::ExchangeBlocker;
+use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::timeouts::requests_timeout_manager_factory::{
+    RequestTimeoutArguments, RequestsTimeoutManagerFactory,
+};
+use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
+use crate::infrastructure::init_lifetime_manager;
+use crate::lifecycle::cold_start::ColdStartGuard;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::misc::clock::Clock;
+use crate::risk::kill_switch::KillSwitch;
+use crate::risk::loss_limit::LossLimitGuard;
+use crate::settings::CoreSettings;
+
+/// Engine context with single exchange and services which aren't started, so only logic under
+/// test runs in background
+pub(crate) fn create_engine_context(
+    exchange: &Arc<Exchange>,
+    core_settings: CoreSettings,
+    clock: Arc<dyn Clock>,
+) -> Arc<EngineContext> {
+    let lifetime_manager = init_lifetime_manager();
+    let exchange_account_id = exchange.exchange_account_id;
+
+    let exchanges = DashMap::new();
+    exchanges.insert(exchange_account_id, exchange.clone());
+    let exchange_blocker = ExchangeBlocker::new(vec![exchange_account_id]);
+    let converter =
+        CurrencyPairToSymbolConverter::new(hashmap![exchange_account_id => exchange.clone()]);
+    let (events_sender, _) = broadcast::channel(100);
+    let (finish_graceful_shutdown_sender, _) = oneshot::channel();
+    let request_timeout_manager = RequestsTimeoutManagerFactory::from_requests_per_period(
+        RequestTimeoutArguments::new(100, Duration::minutes(1)),
+        exchange_account_id,
+    );
+    let timeout_manager = TimeoutManager::with_clock(
+        hashmap![exchange_account_id => request_timeout_manager],
+        clock,
+    );
+
+    EngineContext::new(
+        core_settings,
+        exchanges,
+        ExchangeEvents::new(events_sender),
+        finish_graceful_shutdown_sender,
+        exchange_blocker.clone(),
+        timeout_manager,
+        lifetime_manager,
+        BalanceManager::new(converter, None),
+        exchange.event_recorder.clone(),
+        ColdStartGuard::new(exchange_blocker.clone()),
+        LossLimitGuard::new(exchange_blocker),
+        KillSwitch::new(),
+        Vec::new(),
+    )
+}
@@ -1,39 +1,64 @@
 use super::launcher::unwrap_or_handle_panic;
+use crate::alerting::daily_report::DailyReportService;
+use crate::alerting::{AlertService, Notifier};
 use crate::balance::manager::balance_manager::BalanceManager;
 use crate::database::events::recorder::EventRecorder;
 use crate::disposition_execution::executor::DispositionExecutorService;
 use crate::disposition_execution::strategy::DispositionStrategy;
+use crate::exchanges::account_router::AccountRouter;
 use crate::exchanges::block_reasons;
 use crate::exchanges::exchange_blocker::BlockType;
 use crate::exchanges::exchange_blocker::ExchangeBlocker;
 use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::symbol_service::SymbolService;
 use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
+use crate::health::HealthMonitor;
+use crate::hedger::Hedger;
 use crate::infrastructure::unset_lifetime_manager;
 use crate::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
 use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use crate::lifecycle::cold_start::ColdStartGuard;
 use crate::lifecycle::shutdown::ShutdownService;
+use crate::lifecycle::shutdown_coordinator::run_shutdown_sequence;
+use crate::misc::trading_calendar::TradingCalendar;
 use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::expiry::OrderExpiryService;
+use crate::rebalancing::InventoryRebalancer;
+use crate::risk::kill_switch::KillSwitch;
+use crate::risk::loss_limit::LossLimitGuard;
+use crate::services::candles::CandleService;
+use crate::services::cross_rates::CrossRateService;
+use crate::services::eod_report::EodReportService;
+use crate::services::event_stream::EventStreamService;
+use crate::services::index_price::IndexPriceService;
+use crate::services::latency_throttling::LatencyThrottlingService;
+use crate::services::signals::SignalService;
+use crate::services::trading_sessions::TradingSessionService;
+use crate::services::volatility::VolatilityService;
 use crate::settings::DispositionStrategySettings;
 use crate::settings::{AppSettings, CoreSettings};
 use crate::statistic_service::{StatisticEventHandler, StatisticService};
+use crate::strategy_registry::{Strategy, StrategyRegistry};
+use crate::supervisor::Supervisor;
+use crate::telemetry::Telemetry;
+use crate::treasury::Treasury;
 use anyhow::Result;
 use dashmap::DashMap;
 use futures::future::join_all;
 use futures::FutureExt;
-use mmb_domain::events::{ExchangeEvent, ExchangeEvents};
+use itertools::Itertools;
+use mmb_domain::events::{ExchangeEvent, ExchangeEvents, TradingHaltedEvent};
 use mmb_domain::market::ExchangeAccountId;
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::infrastructure::WithExpect;
 use mmb_utils::logger::print_info;
-use mmb_utils::nothing_to_do;
 use mmb_utils::send_expected::SendExpected;
 use parking_lot::Mutex;
 use std::panic::AssertUnwindSafe;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use tokio::sync::{broadcast, oneshot};
-use tokio::time::{timeout, Duration};
+use tokio::sync::{broadcast, oneshot, watch};
 
 pub trait Service: Send + Sync + 'static {
     fn name(&self) -> &str;
@@ -54,6 +79,43 @@ pub struct EngineContext {
     pub balance_manager: Arc<Mutex<BalanceManager>>,
     pub event_recorder: Arc<EventRecorder>,
     pub statistic_service: Arc<StatisticService>,
+    pub symbol_service: Arc<SymbolService>,
+    pub account_router: Arc<AccountRouter>,
+    /// Tracking of deposits and withdrawals, exists only if treasury settings are specified
+    pub treasury: Option<Arc<Treasury>>,
+    /// Rebalancing of inventory across exchange accounts, exists only if rebalancing settings are specified
+    pub rebalancer: Option<Arc<InventoryRebalancer>>,
+    pub hedgers: Vec<Arc<Hedger>>,
+    pub candle_service: Arc<CandleService>,
+    /// Realized volatility of markets for spread sizing and position limits
+    pub volatility: Arc<VolatilityService>,
+    /// Composite index prices of currency pairs across exchange accounts
+    pub index_prices: Arc<IndexPriceService>,
+    /// Conversion into reporting currency, exists only if cross rates settings are specified
+    pub cross_rates: Option<Arc<CrossRateService>>,
+    pub signal_service: Arc<SignalService>,
+    /// WebSocket stream of engine events, exists only if event stream settings are specified
+    pub event_stream: Option<Arc<EventStreamService>>,
+    /// Exists only if alerting settings are specified
+    pub alert_service: Option<Arc<AlertService>>,
+    /// Exists only if daily report and email settings are specified
+    pub daily_report: Option<Arc<DailyReportService>>,
+    pub eod_report: Option<Arc<EodReportService>>,
+    /// Export to OpenTelemetry collector, exists only if telemetry settings are specified
+    pub telemetry: Option<Telemetry>,
+    pub health_monitor: Arc<HealthMonitor>,
+    /// Restarts crashed engine tasks by their restart policies
+    pub supervisor: Arc<Supervisor>,
+    pub strategy_registry: Arc<StrategyRegistry>,
+    pub cold_start_guard: Arc<ColdStartGuard>,
+    pub loss_limit_guard: Arc<LossLimitGuard>,
+    pub kill_switch: Arc<KillSwitch>,
+    /// Cancels orders with local time-to-live when it's over
+    pub order_expiry: Arc<OrderExpiryService>,
+    /// Quoting schedule of markets
+    pub trading_sessions: Arc<TradingSessionService>,
+    /// Quoting throttling of exchange accounts by latency of order requests
+    pub latency_throttling: Arc<LatencyThrottlingService>,
     is_graceful_shutdown_started: AtomicBool,
     exchange_events: ExchangeEvents,
     finish_graceful_shutdown_sender: Mutex<Option<oneshot::Sender<ActionAfterGracefulShutdown>>>,
@@ -61,7 +123,7 @@ pub struct EngineContext {
 
 impl EngineContext {
     #[allow(clippy::too_many_arguments)]
-    pub(crate) fn new(
+    pub fn new(
         core_settings: CoreSettings,
         exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
         exchange_events: ExchangeEvents,
@@ -71,8 +133,125 @@ impl EngineContext {
         lifetime_manager: Arc<AppLifetimeManager>,
         balance_manager: Arc<Mutex<BalanceManager>>,
         event_recorder: Arc<EventRecorder>,
+        cold_start_guard: Arc<ColdStartGuard>,
+        loss_limit_guard: Arc<LossLimitGuard>,
+        kill_switch: Arc<KillSwitch>,
+        custom_notifiers: Vec<Arc<dyn Notifier>>,
     ) -> Arc<Self> {
-        let statistic_service = StatisticService::new();
+        let statistic_service = StatisticService::with_timeout_manager(
+            TradingCalendar::new(core_settings.trading_calendar.as_ref()),
+            timeout_manager.clone(),
+        );
+        let symbol_service = SymbolService::new(exchanges.clone());
+        let account_router = AccountRouter::new(
+            core_settings.account_routing.clone().unwrap_or_default(),
+            exchanges.clone(),
+            timeout_manager.clone(),
+            exchange_blocker.clone(),
+        );
+        let treasury = core_settings.treasury.clone().map(|settings| {
+            Treasury::new(
+                settings,
+                exchanges.clone(),
+                statistic_service.clone(),
+                timeout_manager.clock().clone(),
+            )
+        });
+        let rebalancer = core_settings.rebalancing.clone().map(|settings| {
+            InventoryRebalancer::new(
+                settings,
+                exchanges.clone(),
+                balance_manager.clone(),
+                event_recorder.clone(),
+                timeout_manager.clock().clone(),
+            )
+        });
+        let hedgers = core_settings
+            .hedgers
+            .iter()
+            .map(|settings| {
+                Hedger::new(
+                    settings.clone(),
+                    exchanges.clone(),
+                    balance_manager.clone(),
+                    timeout_manager.clock().clone(),
+                )
+            })
+            .collect();
+        let candle_service = CandleService::new(&core_settings.candles.clone().unwrap_or_default());
+        let volatility = VolatilityService::new(
+            core_settings.volatility.clone().unwrap_or_default(),
+            candle_service.clone(),
+        );
+        let index_prices = IndexPriceService::new(&core_settings.index_prices, exchanges.clone());
+        let cross_rates = core_settings.cross_rates.clone().map(|settings| {
+            CrossRateService::new(settings, exchanges.clone(), timeout_manager.clock().clone())
+        });
+        let signal_service = SignalService::new(
+            core_settings.signals.clone().unwrap_or_default(),
+            timeout_manager.clock().clone(),
+        );
+        let event_stream = core_settings
+            .event_stream
+            .clone()
+            .map(|settings| EventStreamService::new(settings, statistic_service.clone()));
+        // custom notifiers receive alerts with default settings if alerting isn't configured
+        let alert_service = match (&core_settings.alerting, custom_notifiers.is_empty()) {
+            (None, true) => None,
+            (settings, _) => Some(AlertService::with_custom_notifiers(
+                settings.clone().unwrap_or_default(),
+                timeout_manager.clock().clone(),
+                custom_notifiers,
+            )),
+        };
+        let daily_report = core_settings
+            .alerting
+            .as_ref()
+            .zip(alert_service.clone())
+            .and_then(|(settings, alert_service)| {
+                DailyReportService::new(
+                    settings,
+                    alert_service,
+                    statistic_service.clone(),
+                    exchanges.clone(),
+                    balance_manager.clone(),
+                    timeout_manager.clock().clone(),
+                )
+            });
+        let eod_report = core_settings
+            .eod_report
+            .clone()
+            .map(|settings| EodReportService::new(settings, timeout_manager.clock().clone()));
+        let telemetry = core_settings.telemetry.as_ref().and_then(|settings| {
+            let exchange_account_ids = exchanges.iter().map(|x| *x.key()).collect_vec();
+            Telemetry::start(settings, &exchange_account_ids, &statistic_service)
+                .map_err(|err| log::error!("Failed to start telemetry: {err:?}"))
+                .ok()
+        });
+        let health_monitor = HealthMonitor::new(
+            core_settings.health.clone().unwrap_or_default(),
+            exchanges.clone(),
+            event_recorder.clone(),
+            timeout_manager.clock().clone(),
+        );
+        let supervisor = Supervisor::new(
+            core_settings.supervisor.clone().unwrap_or_default(),
+            statistic_service.clone(),
+        );
+        let order_expiry =
+            OrderExpiryService::new(exchanges.clone(), timeout_manager.clock().clone());
+        let trading_sessions = TradingSessionService::new(
+            core_settings.trading_sessions.as_ref(),
+            exchanges.clone(),
+            timeout_manager.clock().clone(),
+        );
+        let latency_throttling = LatencyThrottlingService::new(
+            &core_settings.exchanges,
+            exchanges.clone(),
+            exchange_blocker.clone(),
+            statistic_service.clone(),
+            timeout_manager.clock().clone(),
+        );
         let engine_context = Arc::new(EngineContext {
             core_settings,
             exchanges,
@@ -83,18 +262,46 @@ impl EngineContext {
             balance_manager,
             event_recorder,
             statistic_service,
+            symbol_service,
+            account_router,
+            treasury,
+            rebalancer,
+            hedgers,
+            candle_service,
+            volatility,
+            index_prices,
+            cross_rates,
+            signal_service,
+            event_stream,
+            alert_service,
+            daily_report,
+            eod_report,
+            telemetry,
+            health_monitor,
+            supervisor,
+            strategy_registry: Default::default(),
+            cold_start_guard,
+            loss_limit_guard,
+            kill_switch,
+            order_expiry,
+            trading_sessions,
+            latency_throttling,
             is_graceful_shutdown_started: Default::default(),
             exchange_events,
             finish_graceful_shutdown_sender: Mutex::new(Some(finish_graceful_shutdown_sender)),
         });
 
         lifetime_manager.setup_engine_context(engine_context.clone());
+        engine_context
+            .shutdown_service
+            .register_user_service(engine_context.strategy_registry.clone());
 
         engine_context
     }
 
     pub(crate) async fn graceful_shutdown(
         self: Arc<Self>,
+        reason: String,
         action: ActionAfterGracefulShutdown,
         futures_cancellation_token: CancellationToken,
     ) {
@@ -118,50 +325,10 @@ impl EngineContext {
 
         self.lifetime_manager.stop_token().cancel();
 
-        self.shutdown_service.user_lvl_shutdown().await;
-        self.exchange_blocker.stop_blocker().await;
-
-        let cancellation_token = CancellationToken::default();
-        const TIMEOUT: Duration = Duration::from_secs(5);
-
-        match timeout(
-            TIMEOUT,
-            cancel_opened_orders(&self.exchanges, cancellation_token.clone(), true),
-        )
-        .await
-        {
-            Ok(()) => (),
-            Err(_) => {
-                cancellation_token.cancel();
-                log::error!(
-                    "Timeout {} secs is exceeded: cancel open orders has been stopped",
-                    TIMEOUT.as_secs(),
-                );
-            }
-        }
-
-        match timeout(
-            TIMEOUT,
-            close_active_positions(&self.exchanges, cancellation_token.clone()),
-        )
-        .await
-        {
-            Ok(()) => (),
-            Err(_) => {
-                cancellation_token.cancel();
-                log::error!(
-                    "Timeout {} secs is exceeded: active positions closing has been stopped",
-                    TIMEOUT.as_secs(),
-                );
-            }
-        }
+        let _ = run_shutdown_sequence(&self, &reason).await;
 
-        self.shutdown_service.core_lvl_shutdown().await;
-
-        match timeout(Duration::from_secs(5), self.event_recorder.flush_and_stop()).await {
-            Err(_) => log::error!("In graceful shutdown EventRecorder::flush_and_stop() was not finished during 5 seconds"),
-            Ok(Err(err)) => log::error!("In graceful shutdown error from EventRecorder::flush_and_stop(): {err:?}"),
-            Ok(Ok(())) => nothing_to_do(),
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.shutdown().await;
         }
 
         let disconnect_websockets = self
@@ -185,19 +352,68 @@ impl EngineContext {
         print_info("Graceful shutdown finished");
     }
 
+    /// Halts trading on all exchange accounts: new orders are rejected, all open orders are
+    /// canceled and positions are closed if `flatten_positions` is set. Trading can be resumed
+    /// only by restart of the engine
+    pub async fn kill_switch(&self, reason: &str, flatten_positions: bool) {
+        if !self.kill_switch.trigger(reason) {
+            log::warn!(
+                "Kill switch with reason '{reason}' is ignored because trading is already halted"
+            );
+            return;
+        }
+
+        log::error!("Kill switch is triggered: {reason}");
+
+        self.exchanges.iter().for_each(|x| {
+            self.exchange_blocker.block(
+                x.exchange_account_id,
+                block_reasons::KILL_SWITCH,
+                BlockType::Manual,
+            )
+        });
+
+        let cancellation_token = self.lifetime_manager.stop_token().create_linked_token();
+        cancel_opened_orders(&self.exchanges, cancellation_token.clone(), true).await;
+
+        if flatten_positions {
+            close_active_positions(&self.exchanges, cancellation_token).await;
+        }
+
+        let event = TradingHaltedEvent {
+            reason: reason.to_owned(),
+            is_positions_flattened: flatten_positions,
+            time: self.timeout_manager.clock().now(),
+        };
+        if self
+            .exchange_events
+            .send(ExchangeEvent::TradingHalted(event))
+            .is_err()
+        {
+            log::warn!("TradingHalted event has no receivers");
+        }
+    }
+
     pub fn get_events_channel(&self) -> broadcast::Receiver<ExchangeEvent> {
         self.exchange_events.get_events_channel()
     }
+
+    /// Sends event to subscribers of engine events channel
+    pub(crate) fn send_event(&self, event: ExchangeEvent) {
+        if let Err(err) = self.exchange_events.send(event) {
+            log::warn!("Event {:?} has no receivers", err.0);
+        }
+    }
 }
 
-async fn cancel_opened_orders(
+pub(crate) async fn cancel_opened_orders(
     exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
     cancellation_token: CancellationToken,
     add_missing_open_orders: bool,
 ) {
     log::info!("Canceling opened orders started");
 
-    join_all(exchanges.iter().map(|x| {
+    join_all(exchanges.iter().filter(|x| !x.is_watch_only()).map(|x| {
         x.clone()
             .cancel_opened_orders(cancellation_token.clone(), add_missing_open_orders)
     }))
@@ -206,7 +422,7 @@ async fn cancel_opened_orders(
     log::info!("Canceling opened orders finished");
 }
 
-async fn close_active_positions(
+pub(crate) async fn close_active_positions(
     exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
     cancellation_token: CancellationToken,
 ) {
@@ -215,7 +431,7 @@ async fn close_active_positions(
     join_all(
         exchanges
             .iter()
-            .filter(|x| x.exchange_client.get_settings().is_margin_trading)
+            .filter(|x| x.exchange_client.get_settings().is_margin_trading && !x.is_watch_only())
             .map(|x| x.clone().close_active_positions(cancellation_token.clone())),
     )
     .await;
@@ -226,6 +442,8 @@ async fn close_active_positions(
 pub struct TradingEngine<StrategySettings: Clone> {
     context: Arc<EngineContext>,
     settings: AppSettings<StrategySettings>,
+    /// Strategy settings updated by `ConfigWatcher` without restart
+    strategy_settings: Arc<watch::Sender<StrategySettings>>,
     finished_graceful_shutdown: oneshot::Receiver<ActionAfterGracefulShutdown>,
 }
 
@@ -235,9 +453,11 @@ impl<StrategySettings: Clone> TradingEngine<StrategySettings> {
         settings: AppSettings<StrategySettings>,
         finished_graceful_shutdown: oneshot::Receiver<ActionAfterGracefulShutdown>,
     ) -> Self {
+        let (strategy_settings, _) = watch::channel(settings.strategy.clone());
         TradingEngine {
             context,
             settings,
+            strategy_settings: Arc::new(strategy_settings),
             finished_graceful_shutdown,
         }
     }
@@ -245,10 +465,22 @@ impl<StrategySettings: Clone> TradingEngine<StrategySettings> {
     pub fn context(&self) -> Arc<EngineContext> {
         self.context.clone()
     }
+    /// Settings loaded at startup. Strategies that should follow changes of settings file
+    /// receive them by `subscribe_strategy_settings`
     pub fn settings(&self) -> &AppSettings<StrategySettings> {
         &self.settings
     }
 
+    /// Receiver of strategy settings which gets new value every time changed settings file is
+    /// reloaded
+    pub fn subscribe_strategy_settings(&self) -> watch::Receiver<StrategySettings> {
+        self.strategy_settings.subscribe()
+    }
+
+    pub(crate) fn strategy_settings_sender(&self) -> Arc<watch::Sender<StrategySettings>> {
+        self.strategy_settings.clone()
+    }
+
     pub async fn run(self) -> ActionAfterGracefulShutdown {
         join_all(self.context.exchanges.iter().map(|x| async move {
             x.value().connect_ws().await.with_expect(move || {
@@ -297,4 +529,106 @@ impl<StrategySettings: Clone> TradingEngine<StrategySettings> {
         ctx.shutdown_service
             .register_user_service(disposition_executor_service);
     }
+
+    /// Registers strategy in `StrategyRegistry` and starts it. Several strategies with
+    /// different names can run in the same engine
+    pub fn start_strategy(&self, name: &str, strategy: Arc<dyn Strategy>) -> Result<()> {
+        self.context
+            .strategy_registry
+            .register(self.context(), name, strategy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::test_helper::{get_test_exchange, test_client};
+    use crate::infrastructure::init_lifetime_manager;
+    use crate::lifecycle::test_helper::create_engine_context;
+    use crate::misc::clock::VirtualClock;
+    use chrono::TimeZone;
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::snapshot::{
+        ClientOrderId, ExchangeOrderId, OrderHeader, OrderInfo, OrderSide, OrderStatus, UserOrder,
+    };
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn kill_switch_halts_trading() {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange(false);
+        let exchange_account_id = exchange.exchange_account_id;
+        let currency_pair = CurrencyPair::from_codes("PHB".into(), "BTC".into());
+
+        let exchange_order_id = ExchangeOrderId::from("opened_order");
+        test_client(&exchange)
+            .open_orders
+            .lock()
+            .push(OrderInfo::new(
+                currency_pair,
+                exchange_order_id.clone(),
+                ClientOrderId::unique_id(),
+                OrderSide::Buy,
+                OrderStatus::Created,
+                dec!(0.2),
+                dec!(1),
+                dec!(0),
+                dec!(0),
+                None,
+                None,
+                None,
+            ));
+
+        let halt_time = chrono::Utc
+            .timestamp_opt(1_000_000, 0)
+            .single()
+            .expect("valid time");
+        let engine_context =
+            create_engine_context(&exchange, Default::default(), VirtualClock::new(halt_time));
+        let kill_switch = engine_context.kill_switch.clone();
+        exchange.setup_kill_switch(kill_switch.clone());
+        let mut events = engine_context.get_events_channel();
+
+        engine_context.kill_switch("loss limit", false).await;
+
+        assert_eq!(kill_switch.reason().as_deref(), Some("loss limit"));
+        assert_eq!(
+            *test_client(&exchange).cancel_requests.lock(),
+            vec![exchange_order_id.clone()]
+        );
+        let canceled_order = exchange
+            .orders
+            .cache_by_exchange_id
+            .get(&exchange_order_id)
+            .map(|x| x.value().clone())
+            .expect("opened order should be adopted");
+        assert_eq!(canceled_order.status(), OrderStatus::Canceled);
+
+        let halted_event = loop {
+            match events.recv().await.expect("in test") {
+                ExchangeEvent::TradingHalted(event) => break event,
+                _ => continue,
+            }
+        };
+        assert_eq!(halted_event.reason, "loss limit");
+        assert!(!halted_event.is_positions_flattened);
+        assert_eq!(halted_event.time, halt_time);
+
+        let order_header = OrderHeader::with_user_order(
+            ClientOrderId::unique_id(),
+            exchange_account_id,
+            currency_pair,
+            OrderSide::Buy,
+            dec!(1),
+            UserOrder::limit(dec!(0.2)),
+            None,
+            None,
+            "StrategyInUnitTests".to_owned(),
+        );
+        let error = exchange
+            .create_order(&order_header, None, CancellationToken::default())
+            .await
+            .expect_err("order shouldn't be created after kill switch");
+        assert!(error.to_string().contains("trading is halted"));
+    }
 }
@@ -0,0 +1,581 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use mmb_domain::events::CHANNEL_MAX_EVENTS_COUNT;
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::pool::OrdersPool;
+use mmb_utils::infrastructure::init_infrastructure;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::time::timeout;
+
+use crate::config::try_load_settings;
+use crate::exchanges::general::exchange_creation::create_timeout_manager;
+use crate::exchanges::general::exchange_symbol::match_symbol;
+use crate::exchanges::traits::ExchangeClient;
+use crate::infrastructure::init_lifetime_manager;
+use crate::lifecycle::launcher::{EngineBuildConfig, InitSettings};
+use crate::secrets::{create_provider, resolve_credentials};
+use crate::settings::{CoreSettings, ExchangeSettings, ExposureMarketSettings, RiskSettings};
+
+/// Command line argument which runs engine in validation mode
+pub const VALIDATE_ARG: &str = "--validate";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Result of single check of settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationCheck {
+    pub check: String,
+    pub is_passed: bool,
+    pub details: String,
+}
+
+impl ValidationCheck {
+    fn new(check: impl Into<String>, is_passed: bool, details: impl Into<String>) -> Self {
+        ValidationCheck {
+            check: check.into(),
+            is_passed,
+            details: details.into(),
+        }
+    }
+
+    fn from_problems(check: impl Into<String>, problems: Vec<String>) -> Self {
+        match problems.is_empty() {
+            true => ValidationCheck::new(check, true, "OK"),
+            false => ValidationCheck::new(check, false, problems.join("; ")),
+        }
+    }
+}
+
+/// Settings are valid only if all checks are passed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    pub is_valid: bool,
+    pub checks: Vec<ValidationCheck>,
+}
+
+impl ValidationReport {
+    pub fn new(checks: Vec<ValidationCheck>) -> Self {
+        ValidationReport {
+            is_valid: checks.iter().all(|x| x.is_passed),
+            checks,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("serializing validation report")
+    }
+}
+
+/// Engine should be run in validation mode if `--validate` argument is passed
+pub fn is_validate_mode() -> bool {
+    std::env::args().skip(1).any(|x| x == VALIDATE_ARG)
+}
+
+/// Checks settings without starting trading: parses them, checks credentials of exchange
+/// accounts by requesting balances, checks that configured symbols exist on exchanges and
+/// that risk limits are consistent. Process is expected to exit after validation
+pub async fn validate_settings<StrategySettings>(
+    build_settings: &EngineBuildConfig,
+    init_user_settings: InitSettings<StrategySettings>,
+) -> ValidationReport
+where
+    StrategySettings: Clone + Debug + DeserializeOwned + Serialize,
+{
+    init_infrastructure();
+
+    let mut settings = match init_user_settings {
+        InitSettings::Directly(settings) => settings,
+        InitSettings::Load {
+            config_path,
+            credentials_path,
+        } => match try_load_settings::<StrategySettings>(&config_path, &credentials_path) {
+            Ok(settings) => settings,
+            Err(err) => {
+                return ValidationReport::new(vec![ValidationCheck::new(
+                    "config",
+                    false,
+                    format!("{err:#}"),
+                )])
+            }
+        },
+    };
+
+    let mut checks = vec![
+        ValidationCheck::new("config", true, "Settings are parsed"),
+        ValidationCheck::from_problems("risk", risk_problems(&settings.core)),
+    ];
+
+    if let Some(secrets) = settings.core.secrets.clone() {
+        let result = match create_provider(&secrets.provider) {
+            Ok(provider) => resolve_credentials(&mut settings.core, &*provider).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(()) => checks.push(ValidationCheck::new(
+                "secrets",
+                true,
+                "Credentials are received",
+            )),
+            Err(err) => {
+                checks.push(ValidationCheck::new("secrets", false, format!("{err:#}")));
+                return ValidationReport::new(checks);
+            }
+        }
+    }
+
+    let (supported, unsupported): (Vec<_>, Vec<_>) =
+        settings.core.exchanges.iter().partition(|x| {
+            build_settings
+                .supported_exchange_clients
+                .contains_key(&x.exchange_account_id.exchange_id)
+        });
+    for exchange_settings in unsupported {
+        let exchange_account_id = exchange_settings.exchange_account_id;
+        checks.push(ValidationCheck::new(
+            format!("exchange:{exchange_account_id}"),
+            false,
+            format!(
+                "Exchange {} isn't supported by this engine build",
+                exchange_account_id.exchange_id
+            ),
+        ));
+    }
+
+    let core_settings = CoreSettings {
+        exchanges: supported.into_iter().cloned().collect(),
+        ..settings.core.clone()
+    };
+    let lifetime_manager = init_lifetime_manager();
+    let timeout_manager = create_timeout_manager(&core_settings, build_settings);
+    let (events_sender, _) = broadcast::channel(CHANNEL_MAX_EVENTS_COUNT);
+
+    let mut traded_pairs = HashMap::new();
+    for exchange_settings in &core_settings.exchanges {
+        let exchange_account_id = exchange_settings.exchange_account_id;
+        let client = build_settings.supported_exchange_clients[&exchange_account_id.exchange_id]
+            .create_exchange_client(
+                exchange_settings.clone(),
+                events_sender.clone(),
+                lifetime_manager.clone(),
+                timeout_manager.clone(),
+                OrdersPool::new(),
+            )
+            .client;
+
+        checks.push(check_credentials(&*client, exchange_settings).await);
+        let (symbols_check, currency_pairs) = check_symbols(&*client, exchange_settings).await;
+        checks.push(symbols_check);
+        let _ = traded_pairs.insert(exchange_account_id, currency_pairs);
+    }
+
+    checks.push(ValidationCheck::from_problems(
+        "markets",
+        market_problems(&settings.core, &traded_pairs),
+    ));
+
+    ValidationReport::new(checks)
+}
+
+/// Validates settings, prints machine-readable report to stdout and exits. Exit code is non-zero
+/// if settings are invalid
+pub async fn validate_and_exit<StrategySettings>(
+    build_settings: &EngineBuildConfig,
+    init_user_settings: InitSettings<StrategySettings>,
+) -> !
+where
+    StrategySettings: Clone + Debug + DeserializeOwned + Serialize,
+{
+    let report = validate_settings(build_settings, init_user_settings).await;
+    match report.to_json() {
+        Ok(json) => println!("{json}"),
+        Err(err) => {
+            eprintln!("{err:?}");
+            std::process::exit(2);
+        }
+    }
+
+    std::process::exit(if report.is_valid { 0 } else { 1 })
+}
+
+/// Balances request is the lightest authenticated request supported by all exchanges
+async fn check_credentials(
+    client: &dyn ExchangeClient,
+    settings: &ExchangeSettings,
+) -> ValidationCheck {
+    let check = format!("credentials:{}", settings.exchange_account_id);
+    if settings.is_watch_only {
+        return ValidationCheck::new(check, true, "Watch-only account doesn't use credentials");
+    }
+
+    match timeout(REQUEST_TIMEOUT, client.get_balance_and_positions()).await {
+        Ok(Ok(balances)) => ValidationCheck::new(
+            check,
+            true,
+            format!("{} balances are received", balances.balances.len()),
+        ),
+        Ok(Err(err)) => ValidationCheck::new(check, false, format!("{err:#}")),
+        Err(_) => ValidationCheck::new(check, false, "Balances request timed out"),
+    }
+}
+
+/// Returns currency pairs of configured symbols found on exchange
+async fn check_symbols(
+    client: &dyn ExchangeClient,
+    settings: &ExchangeSettings,
+) -> (ValidationCheck, Vec<CurrencyPair>) {
+    let check = format!("symbols:{}", settings.exchange_account_id);
+    let exchange_symbols = match timeout(REQUEST_TIMEOUT, client.build_all_symbols()).await {
+        Ok(Ok(symbols)) => symbols,
+        Ok(Err(err)) => {
+            return (
+                ValidationCheck::new(check, false, format!("{err:#}")),
+                vec![],
+            )
+        }
+        Err(_) => {
+            return (
+                ValidationCheck::new(check, false, "Symbols request timed out"),
+                vec![],
+            )
+        }
+    };
+
+    let currency_pair_settings = match &settings.currency_pairs {
+        Some(currency_pairs) => currency_pairs,
+        None => {
+            return (
+                ValidationCheck::new(check, false, "Setting `currency_pairs` isn't specified"),
+                vec![],
+            )
+        }
+    };
+
+    let mut currency_pairs = Vec::new();
+    let mut problems = Vec::new();
+    for currency_pair_setting in currency_pair_settings {
+        match match_symbol(currency_pair_setting, &exchange_symbols) {
+            Ok(symbol) => currency_pairs.push(symbol.currency_pair()),
+            Err(err) => problems.push(err.to_string()),
+        }
+    }
+
+    (
+        ValidationCheck::from_problems(check, problems),
+        currency_pairs,
+    )
+}
+
+/// Limits should be positive, exposure groups should be distinguishable and risk budgets of
+/// strategies shouldn't exceed limits of the engine
+fn risk_problems(settings: &CoreSettings) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(risk) = &settings.risk {
+        problems.extend(limit_problems("core.risk", risk));
+    }
+
+    for strategy in &settings.strategies {
+        let budget = match &strategy.risk_budget {
+            Some(budget) => budget,
+            None => continue,
+        };
+
+        let section = format!("core.strategies.{}.risk_budget", strategy.name);
+        problems.extend(limit_problems(&section, budget));
+
+        let engine_risk = match &settings.risk {
+            Some(engine_risk) => engine_risk,
+            None => continue,
+        };
+        let limits = [
+            (
+                "max_order_notional",
+                budget.max_order_notional,
+                engine_risk.max_order_notional,
+            ),
+            (
+                "max_position_amount",
+                budget.max_position_amount,
+                engine_risk.max_position_amount,
+            ),
+            (
+                "max_open_orders_count",
+                budget.max_open_orders_count.map(Decimal::from),
+                engine_risk.max_open_orders_count.map(Decimal::from),
+            ),
+            (
+                "price_collar_percent",
+                budget.price_collar_percent,
+                engine_risk.price_collar_percent,
+            ),
+        ];
+        for (name, budget_limit, engine_limit) in limits {
+            if let (Some(budget_limit), Some(engine_limit)) = (budget_limit, engine_limit) {
+                if budget_limit > engine_limit {
+                    problems.push(format!(
+                        "{section}.{name} {budget_limit} exceeds engine limit {engine_limit}"
+                    ));
+                }
+            }
+        }
+    }
+
+    if settings.cross_rates.is_none() {
+        let exposure_groups = settings
+            .risk
+            .iter()
+            .chain(
+                settings
+                    .strategies
+                    .iter()
+                    .filter_map(|x| x.risk_budget.as_ref()),
+            )
+            .flat_map(|x| &x.exposure_groups);
+        for group in exposure_groups {
+            if let Some(currency_code) = group.currency_code {
+                problems.push(format!(
+                    "exposure group '{}' is valued in {currency_code}, but core.cross_rates isn't specified",
+                    group.name
+                ));
+            }
+        }
+    }
+
+    let duplicated_strategies = settings
+        .strategies
+        .iter()
+        .map(|x| &x.name)
+        .duplicates()
+        .collect_vec();
+    for name in duplicated_strategies {
+        problems.push(format!(
+            "core.strategies: strategy '{name}' is specified twice"
+        ));
+    }
+
+    problems
+}
+
+fn limit_problems(section: &str, risk: &RiskSettings) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut check_positive = |name: &str, value: Option<Decimal>| {
+        if let Some(value) = value.filter(|x| *x <= Decimal::ZERO) {
+            problems.push(format!(
+                "{section}.{name} should be positive, but it's {value}"
+            ));
+        }
+    };
+    check_positive("max_order_notional", risk.max_order_notional);
+    check_positive("max_position_amount", risk.max_position_amount);
+    check_positive(
+        "max_open_orders_count",
+        risk.max_open_orders_count.map(Decimal::from),
+    );
+    check_positive("price_collar_percent", risk.price_collar_percent);
+    check_positive(
+        "loss_limit.max_loss",
+        risk.loss_limit.as_ref().map(|x| x.max_loss),
+    );
+    for group in &risk.exposure_groups {
+        check_positive(
+            &format!("exposure_groups.{}.max_amount", group.name),
+            Some(group.max_amount),
+        );
+    }
+    if let Some(order_to_trade) = &risk.order_to_trade {
+        check_positive(
+            "order_to_trade.max_cancel_fill_ratio",
+            order_to_trade.max_cancel_fill_ratio,
+        );
+        check_positive(
+            "order_to_trade.max_request_weight_utilization",
+            order_to_trade.max_request_weight_utilization,
+        );
+        check_positive(
+            "order_to_trade.window_secs",
+            Some(Decimal::from(order_to_trade.window_secs)),
+        );
+    }
+
+    if let Some(price_collar_percent) = risk.price_collar_percent.filter(|x| *x >= dec!(100)) {
+        problems.push(format!(
+            "{section}.price_collar_percent {price_collar_percent} doesn't limit anything, it should be less than 100"
+        ));
+    }
+
+    let max_request_weight_utilization = risk
+        .order_to_trade
+        .as_ref()
+        .and_then(|x| x.max_request_weight_utilization)
+        .filter(|x| *x > Decimal::ONE);
+    if let Some(utilization) = max_request_weight_utilization {
+        problems.push(format!(
+            "{section}.order_to_trade.max_request_weight_utilization {utilization} doesn't limit anything, it should be at most 1"
+        ));
+    }
+
+    let mut group_names = HashSet::new();
+    for group in &risk.exposure_groups {
+        if !group_names.insert(&group.name) {
+            problems.push(format!(
+                "{section}.exposure_groups: group '{}' is specified twice",
+                group.name
+            ));
+        }
+        if group.markets.is_empty() {
+            problems.push(format!(
+                "{section}.exposure_groups.{}: markets aren't specified",
+                group.name
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Markets of exposure groups and hedgers should be traded by engine
+fn market_problems(
+    settings: &CoreSettings,
+    traded_pairs: &HashMap<ExchangeAccountId, Vec<CurrencyPair>>,
+) -> Vec<String> {
+    let is_traded = |market: &ExposureMarketSettings| {
+        traded_pairs
+            .get(&market.exchange_account_id)
+            .is_some_and(|x| x.contains(&market.currency_pair))
+    };
+
+    let exposure_markets = settings
+        .risk
+        .iter()
+        .flat_map(|x| &x.exposure_groups)
+        .flat_map(|group| {
+            group
+                .markets
+                .iter()
+                .map(move |x| (format!("exposure group '{}'", group.name), x))
+        });
+    let hedger_markets = settings.hedgers.iter().flat_map(|hedger| {
+        hedger
+            .markets
+            .iter()
+            .chain([&hedger.hedge_market])
+            .map(move |x| (format!("hedger '{}'", hedger.name), x))
+    });
+
+    exposure_markets
+        .chain(hedger_markets)
+        .filter(|(_, market)| !is_traded(market))
+        .map(|(owner, market)| {
+            format!(
+                "Market {} {} of {owner} isn't traded",
+                market.exchange_account_id, market.currency_pair
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{ExposureGroupSettings, StrategyInstanceSettings};
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        "Binance_0".parse().expect("valid id")
+    }
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    fn core_settings(risk: RiskSettings, budget: RiskSettings) -> CoreSettings {
+        CoreSettings {
+            risk: Some(risk),
+            strategies: vec![StrategyInstanceSettings {
+                name: "grid".to_owned(),
+                timer_interval_millis: 1000,
+                risk_budget: Some(budget),
+                event_queue: None,
+            }],
+            ..CoreSettings::default()
+        }
+    }
+
+    #[test]
+    fn consistent_risk_limits_have_no_problems() {
+        let settings = core_settings(
+            RiskSettings {
+                max_position_amount: Some(dec!(10)),
+                price_collar_percent: Some(dec!(5)),
+                ..RiskSettings::default()
+            },
+            RiskSettings {
+                max_position_amount: Some(dec!(2)),
+                ..RiskSettings::default()
+            },
+        );
+
+        assert_eq!(risk_problems(&settings), Vec::<String>::new());
+    }
+
+    #[test]
+    fn inconsistent_risk_limits_are_reported() {
+        let settings = core_settings(
+            RiskSettings {
+                max_position_amount: Some(dec!(10)),
+                max_open_orders_count: Some(0),
+                price_collar_percent: Some(dec!(150)),
+                ..RiskSettings::default()
+            },
+            RiskSettings {
+                max_position_amount: Some(dec!(20)),
+                ..RiskSettings::default()
+            },
+        );
+
+        assert_eq!(
+            risk_problems(&settings),
+            vec![
+                "core.risk.max_open_orders_count should be positive, but it's 0",
+                "core.risk.price_collar_percent 150 doesn't limit anything, it should be less than 100",
+                "core.strategies.grid.risk_budget.max_position_amount 20 exceeds engine limit 10",
+            ]
+        );
+    }
+
+    #[test]
+    fn markets_of_exposure_groups_should_be_traded() {
+        let market = |currency_pair| ExposureMarketSettings {
+            exchange_account_id: exchange_account_id(),
+            currency_pair,
+            weight: None,
+        };
+        let settings = CoreSettings {
+            risk: Some(RiskSettings {
+                exposure_groups: vec![ExposureGroupSettings {
+                    name: "BTC".to_owned(),
+                    markets: vec![
+                        market(currency_pair()),
+                        market(CurrencyPair::from_codes("btc".into(), "busd".into())),
+                    ],
+                    max_amount: dec!(5),
+                    currency_code: None,
+                }],
+                ..RiskSettings::default()
+            }),
+            ..CoreSettings::default()
+        };
+        let traded_pairs = HashMap::from([(exchange_account_id(), vec![currency_pair()])]);
+
+        assert_eq!(
+            market_problems(&settings, &traded_pairs),
+            vec!["Market Binance_0 btc/busd of exposure group 'BTC' isn't traded"]
+        );
+    }
+}
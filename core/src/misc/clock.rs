@@ -0,0 +1,203 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+/// Source of delays for engine components
+pub trait Timer: Send + Sync {
+    /// Future that completes when the specified duration passed by this timer
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// Source of current time for engine components. Components get it by injection instead of
+/// calling `Utc::now()` and `tokio::time::sleep`, so tests and backtests can control time
+pub trait Clock: Timer {
+    fn now(&self) -> DateTime;
+}
+
+/// Wall clock time
+#[derive(Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    pub fn shared() -> Arc<dyn Clock> {
+        Arc::new(SystemClock)
+    }
+}
+
+impl Timer for SystemClock {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        tokio::time::sleep(duration).boxed()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime {
+        chrono::Utc::now()
+    }
+}
+
+struct VirtualClockState {
+    now: DateTime,
+    sleepers: Vec<(DateTime, oneshot::Sender<()>)>,
+}
+
+/// Clock which time moves only by explicit calls of `advance` or `set`.
+/// Pending sleeps are completed when their deadline is reached
+pub struct VirtualClock {
+    state: Mutex<VirtualClockState>,
+}
+
+impl VirtualClock {
+    pub fn new(start: DateTime) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(VirtualClockState {
+                now: start,
+                sleepers: Vec::new(),
+            }),
+        })
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let now = self.state.lock().now;
+        self.set(now + to_chrono(duration));
+    }
+
+    /// Moves clock to the specified time. Time can't go backwards, so earlier time is ignored
+    pub fn set(&self, time: DateTime) {
+        let mut state = self.state.lock();
+        if time < state.now {
+            log::warn!(
+                "Virtual clock can't be moved back from {} to {time}",
+                state.now
+            );
+            return;
+        }
+
+        state.now = time;
+        let (expired, pending) = std::mem::take(&mut state.sleepers)
+            .into_iter()
+            .partition(|(deadline, _)| *deadline <= time);
+        state.sleepers = pending;
+        drop(state);
+
+        for (_, sender) in expired {
+            let _ = sender.send(());
+        }
+    }
+
+    /// Count of sleeps waiting for the clock to be advanced
+    pub fn pending_sleeps_count(&self) -> usize {
+        self.state.lock().sleepers.len()
+    }
+
+    /// The earliest deadline of pending sleeps which are still awaited
+    pub fn next_deadline(&self) -> Option<DateTime> {
+        self.state
+            .lock()
+            .sleepers
+            .iter()
+            .filter(|(_, sender)| !sender.is_closed())
+            .map(|(deadline, _)| *deadline)
+            .min()
+    }
+}
+
+impl Timer for VirtualClock {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        if duration.is_zero() {
+            return futures::future::ready(()).boxed();
+        }
+
+        let mut state = self.state.lock();
+        let deadline = match state.now.checked_add_signed(to_chrono(duration)) {
+            Some(deadline) => deadline,
+            None => return futures::future::pending().boxed(),
+        };
+        let (sender, receiver) = oneshot::channel();
+        state.sleepers.push((deadline, sender));
+
+        async move {
+            // sender is dropped only together with the clock, so there is no time to wait anymore
+            let _ = receiver.await;
+        }
+        .boxed()
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> DateTime {
+        self.state.lock().now
+    }
+}
+
+fn to_chrono(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or(chrono::TimeDelta::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn start() -> DateTime {
+        chrono::Utc
+            .with_ymd_and_hms(2022, 11, 17, 10, 0, 0)
+            .single()
+            .expect("in test")
+    }
+
+    #[test]
+    fn advance_moves_time() {
+        let clock = VirtualClock::new(start());
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(clock.now(), start() + chrono::Duration::seconds(10));
+
+        clock.set(start());
+        assert_eq!(clock.now(), start() + chrono::Duration::seconds(10));
+    }
+
+    #[tokio::test]
+    async fn sleep_completes_when_deadline_reached() {
+        let clock = VirtualClock::new(start());
+        let mut sleep = clock.sleep(Duration::from_secs(10));
+        assert_eq!(clock.pending_sleeps_count(), 1);
+
+        clock.advance(Duration::from_secs(9));
+        assert!((&mut sleep).now_or_never().is_none());
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(clock.pending_sleeps_count(), 0);
+        assert!(sleep.now_or_never().is_some());
+    }
+
+    #[tokio::test]
+    async fn next_deadline_skips_dropped_sleeps() {
+        let clock = VirtualClock::new(start());
+        let sleep = clock.sleep(Duration::from_secs(5));
+        let _other_sleep = clock.sleep(Duration::from_secs(10));
+        assert_eq!(
+            clock.next_deadline(),
+            Some(start() + chrono::Duration::seconds(5))
+        );
+
+        drop(sleep);
+        assert_eq!(
+            clock.next_deadline(),
+            Some(start() + chrono::Duration::seconds(10))
+        );
+    }
+
+    #[tokio::test]
+    async fn zero_sleep_is_ready() {
+        let clock = VirtualClock::new(start());
+
+        assert!(clock.sleep(Duration::ZERO).now_or_never().is_some());
+        assert_eq!(clock.pending_sleeps_count(), 0);
+    }
+}
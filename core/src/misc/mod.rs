@@ -1,6 +1,8 @@
+pub mod clock;
 pub(crate) mod position_helper;
 pub(crate) mod price_source_model;
 pub mod reserve_parameters;
 pub(crate) mod service_value_tree;
 pub mod time;
+pub mod trading_calendar;
 pub mod traits;
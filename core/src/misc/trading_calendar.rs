@@ -0,0 +1,209 @@
+use chrono::{Duration, TimeZone, Utc};
+use itertools::Itertools;
+use mmb_domain::market::ExchangeAccountId;
+use mmb_utils::DateTime;
+
+use crate::settings::{NonTradingWindowSettings, TradingCalendarSettings};
+
+/// Calendar of periods when engine intentionally doesn't trade
+#[derive(Debug, Default, Clone)]
+pub struct TradingCalendar {
+    non_trading_windows: Vec<NonTradingWindowSettings>,
+}
+
+impl TradingCalendar {
+    pub fn new(settings: Option<&TradingCalendarSettings>) -> Self {
+        TradingCalendar {
+            non_trading_windows: settings
+                .map(|x| x.non_trading_windows.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Duration of interval `[from, to)` excluding non-trading windows of exchange account
+    pub fn active_duration(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        from: DateTime,
+        to: DateTime,
+    ) -> Duration {
+        if to <= from {
+            return Duration::zero();
+        }
+
+        let mut intervals = Vec::new();
+        for window in &self.non_trading_windows {
+            window_intervals(window, exchange_account_id, from, to, &mut intervals);
+        }
+
+        // windows can overlap each other, so they are merged before subtraction
+        let mut non_trading = Duration::zero();
+        let mut current: Option<(DateTime, DateTime)> = None;
+        for (start, end) in intervals.into_iter().sorted() {
+            current = match current {
+                Some((cur_start, cur_end)) if start <= cur_end => {
+                    Some((cur_start, cur_end.max(end)))
+                }
+                Some((cur_start, cur_end)) => {
+                    non_trading += cur_end - cur_start;
+                    Some((start, end))
+                }
+                None => Some((start, end)),
+            };
+        }
+
+        if let Some((start, end)) = current {
+            non_trading += end - start;
+        }
+
+        (to - from) - non_trading
+    }
+}
+
+/// Whether `time` is inside of window of exchange account
+pub(crate) fn is_within_window(
+    window: &NonTradingWindowSettings,
+    exchange_account_id: ExchangeAccountId,
+    time: DateTime,
+) -> bool {
+    let mut intervals = Vec::new();
+    window_intervals(
+        window,
+        exchange_account_id,
+        time,
+        time + Duration::milliseconds(1),
+        &mut intervals,
+    );
+    !intervals.is_empty()
+}
+
+/// Adds intervals of window clipped by `[from, to)`
+fn window_intervals(
+    window: &NonTradingWindowSettings,
+    exchange_account_id: ExchangeAccountId,
+    from: DateTime,
+    to: DateTime,
+    intervals: &mut Vec<(DateTime, DateTime)>,
+) {
+    let mut add = |start: DateTime, end: DateTime| {
+        let start = start.max(from);
+        let end = end.min(to);
+        if start < end {
+            intervals.push((start, end));
+        }
+    };
+
+    match window {
+        NonTradingWindowSettings::Daily {
+            exchange_account_id: window_account,
+            start,
+            end,
+        } => {
+            if window_account.is_some_and(|x| x != exchange_account_id) {
+                return;
+            }
+
+            // window started on the previous day can pass over midnight
+            let mut date = from.naive_utc().date() - Duration::days(1);
+            while date <= to.naive_utc().date() {
+                let window_start = Utc.from_utc_datetime(&date.and_time(*start));
+                let mut window_end = Utc.from_utc_datetime(&date.and_time(*end));
+                if window_end <= window_start {
+                    window_end += Duration::days(1);
+                }
+
+                add(window_start, window_end);
+                date += Duration::days(1);
+            }
+        }
+        NonTradingWindowSettings::Period {
+            exchange_account_id: window_account,
+            start,
+            end,
+        } => {
+            if window_account.is_some_and(|x| x != exchange_account_id) {
+                return;
+            }
+
+            add(*start, *end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveTime;
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", 0)
+    }
+
+    fn time(day: u32, hour: u32) -> DateTime {
+        Utc.with_ymd_and_hms(2022, 6, day, hour, 0, 0)
+            .single()
+            .expect("in test")
+    }
+
+    fn daily(start_hour: u32, end_hour: u32) -> NonTradingWindowSettings {
+        NonTradingWindowSettings::Daily {
+            exchange_account_id: None,
+            start: NaiveTime::from_hms_opt(start_hour, 0, 0).expect("in test"),
+            end: NaiveTime::from_hms_opt(end_hour, 0, 0).expect("in test"),
+        }
+    }
+
+    fn calendar(non_trading_windows: Vec<NonTradingWindowSettings>) -> TradingCalendar {
+        TradingCalendar::new(Some(&TradingCalendarSettings {
+            non_trading_windows,
+        }))
+    }
+
+    #[test]
+    fn without_windows_whole_interval_is_active() {
+        let calendar = TradingCalendar::new(None);
+
+        let active = calendar.active_duration(exchange_account_id(), time(1, 0), time(2, 0));
+
+        assert_eq!(active, Duration::days(1));
+    }
+
+    #[test]
+    fn daily_window_over_midnight() {
+        let calendar = calendar(vec![daily(23, 1)]);
+
+        // windows 31 May 23:00 - 1 Jun 01:00 and 1 Jun 23:00 - 2 Jun 01:00 are partially in interval
+        let active = calendar.active_duration(exchange_account_id(), time(1, 0), time(2, 0));
+
+        assert_eq!(active, Duration::hours(22));
+    }
+
+    #[test]
+    fn overlapped_windows_are_excluded_once() {
+        let calendar = calendar(vec![
+            daily(10, 12),
+            NonTradingWindowSettings::Period {
+                exchange_account_id: None,
+                start: time(1, 11),
+                end: time(1, 14),
+            },
+        ]);
+
+        let active = calendar.active_duration(exchange_account_id(), time(1, 0), time(2, 0));
+
+        assert_eq!(active, Duration::hours(20));
+    }
+
+    #[test]
+    fn window_of_other_exchange_account_is_ignored() {
+        let calendar = calendar(vec![NonTradingWindowSettings::Daily {
+            exchange_account_id: Some(ExchangeAccountId::new("Bitmex", 0)),
+            start: NaiveTime::from_hms_opt(10, 0, 0).expect("in test"),
+            end: NaiveTime::from_hms_opt(12, 0, 0).expect("in test"),
+        }]);
+
+        let active = calendar.active_duration(exchange_account_id(), time(1, 0), time(2, 0));
+
+        assert_eq!(active, Duration::days(1));
+    }
+}
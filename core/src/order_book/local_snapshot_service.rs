@@ -2,16 +2,21 @@ use mmb_domain::market::{MarketAccountId, MarketId};
 use mmb_domain::order_book::event;
 use mmb_domain::order_book::local_order_book_snapshot::{LocalOrderBookSnapshot, ResultAskBidFix};
 use mmb_utils::infrastructure::WithExpect;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Produce and actualize current logical state of order book snapshot according to logical time of handled order book events
 pub struct LocalSnapshotsService {
     local_snapshots: HashMap<MarketId, LocalOrderBookSnapshot>,
+    /// Markets which order books were crossed by the last handled event
+    crossed_markets: HashSet<MarketId>,
 }
 
 impl LocalSnapshotsService {
     pub fn new(local_snapshots: HashMap<MarketId, LocalOrderBookSnapshot>) -> Self {
-        Self { local_snapshots }
+        Self {
+            local_snapshots,
+            crossed_markets: HashSet::new(),
+        }
     }
 
     pub fn get_snapshot(&self, market_id: MarketId) -> Option<&LocalOrderBookSnapshot> {
@@ -24,6 +29,12 @@ impl LocalSnapshotsService {
             .with_expect(|| format!("Can't get snapshot for {:?}", market_id))
     }
 
+    /// Whether order book of market was crossed by the last handled event. Crossed levels are
+    /// removed from snapshot, but remaining levels can't be trusted either
+    pub fn is_crossed(&self, market_id: MarketId) -> bool {
+        self.crossed_markets.contains(&market_id)
+    }
+
     /// Create snapshot if it does not exist
     /// Update snapshot if suitable data arrive
     /// Returns `Some(MarketAccountId)` if snapshot update succeeded, otherwise `None`
@@ -34,11 +45,7 @@ impl LocalSnapshotsService {
         match event.event_type {
             event::EventType::Snapshot => {
                 let mut snapshot = event.to_orderbook_snapshot();
-                if let ResultAskBidFix::Fixed { top_ask, top_bid } =
-                    snapshot.fix_asks_bids_if_needed()
-                {
-                    log::warn!("On {market_account_id} orderbook top asks {top_ask} and bids {top_bid} was crossed (fixed now {})", snapshot.get_top_prices())
-                }
+                fix_crossed_snapshot(&mut self.crossed_markets, market_account_id, &mut snapshot);
 
                 self.local_snapshots.insert(market_id, snapshot);
 
@@ -49,11 +56,7 @@ impl LocalSnapshotsService {
                 Some(snapshot) => {
                     snapshot.apply_update(&event.data, event.creation_time);
 
-                    if let ResultAskBidFix::Fixed { top_ask, top_bid } =
-                        snapshot.fix_asks_bids_if_needed()
-                    {
-                        log::warn!("On {market_account_id} orderbook top asks {top_ask} and bids {top_bid} was crossed (fixed now {})", snapshot.get_top_prices())
-                    }
+                    fix_crossed_snapshot(&mut self.crossed_markets, market_account_id, snapshot);
 
                     Some(market_account_id)
                 }
@@ -62,6 +65,23 @@ impl LocalSnapshotsService {
     }
 }
 
+fn fix_crossed_snapshot(
+    crossed_markets: &mut HashSet<MarketId>,
+    market_account_id: MarketAccountId,
+    snapshot: &mut LocalOrderBookSnapshot,
+) {
+    let market_id = market_account_id.market_id();
+    match snapshot.fix_asks_bids_if_needed() {
+        ResultAskBidFix::Fixed { top_ask, top_bid } => {
+            log::warn!("On {market_account_id} orderbook top asks {top_ask} and bids {top_bid} was crossed (fixed now {})", snapshot.get_top_prices());
+            let _ = crossed_markets.insert(market_id);
+        }
+        ResultAskBidFix::Ok => {
+            let _ = crossed_markets.remove(&market_id);
+        }
+    }
+}
+
 impl Default for LocalSnapshotsService {
     fn default() -> Self {
         LocalSnapshotsService::new(HashMap::new())
@@ -247,6 +267,8 @@ mod tests {
             .get_snapshot(market_account_id.market_id())
             .expect("in test");
 
+        assert!(snapshot_service.is_crossed(market_account_id.market_id()));
+
         // Check all snapshot
         let expected = order_book_data![
             dec!(3.4) => dec!(1.2),
@@ -1 +1,2 @@
 pub mod local_snapshot_service;
+pub mod sanity;
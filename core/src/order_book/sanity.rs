@@ -0,0 +1,78 @@
+use chrono::Duration;
+use mmb_domain::events::MarketDataSuspectReason;
+use mmb_domain::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+
+use crate::settings::MarketDataSanitySettings;
+
+/// Reason why order book shouldn't be used for quoting or `None` if it looks valid.
+/// `is_crossed` is whether the book was crossed by the last event, because crossed levels are
+/// already removed from snapshot
+pub fn check_order_book(
+    snapshot: &LocalOrderBookSnapshot,
+    is_crossed: bool,
+    now: DateTime,
+    settings: &MarketDataSanitySettings,
+) -> Option<MarketDataSuspectReason> {
+    if is_crossed {
+        return Some(MarketDataSuspectReason::CrossedBook);
+    }
+
+    let has_non_positive_price = snapshot
+        .get_asks_price_levels()
+        .chain(snapshot.get_bids_price_levels())
+        .any(|(price, _)| *price <= Decimal::ZERO);
+    if has_non_positive_price {
+        return Some(MarketDataSuspectReason::NonPositivePrice);
+    }
+
+    let max_age = Duration::milliseconds(settings.max_book_age_millis as i64);
+    if snapshot.last_update_time + max_age < now {
+        return Some(MarketDataSuspectReason::StaleBook);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use mmb_domain::order_book_data;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn suspect_order_books() {
+        let settings = MarketDataSanitySettings {
+            max_book_age_millis: 1000,
+        };
+        let now = Utc::now();
+        let valid = order_book_data![
+            dec!(3.4) => dec!(1.2),
+            ;
+            dec!(2.9) => dec!(7.8),
+        ]
+        .to_orderbook_snapshot(now - Duration::milliseconds(500));
+        assert_eq!(check_order_book(&valid, false, now, &settings), None);
+        assert_eq!(
+            check_order_book(&valid, true, now, &settings),
+            Some(MarketDataSuspectReason::CrossedBook)
+        );
+        assert_eq!(
+            check_order_book(&valid, false, now + Duration::seconds(1), &settings),
+            Some(MarketDataSuspectReason::StaleBook)
+        );
+
+        let zero_bid = order_book_data![
+            dec!(3.4) => dec!(1.2),
+            ;
+            dec!(0) => dec!(7.8),
+        ]
+        .to_orderbook_snapshot(now);
+        assert_eq!(
+            check_order_book(&zero_bid, false, now, &settings),
+            Some(MarketDataSuspectReason::NonPositivePrice)
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use futures::future::join_all;
+use itertools::Itertools;
+use mmb_domain::events::ExchangeEvent;
+use mmb_domain::order::basket::{BasketEvent, BasketId, BasketLeg, BasketOutcome};
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{OrderHeader, OrderStatus};
+use mmb_utils::cancellation_token::CancellationToken;
+use tokio::time::timeout;
+
+use crate::exchanges::general::exchange::Exchange;
+
+/// How legs of basket are placed
+#[derive(Debug, Clone, Copy)]
+pub struct LeggingPolicy {
+    /// Max time for every leg to be placed on exchange. If any leg isn't placed in time,
+    /// the rest legs are cancelled
+    pub place_deadline: Duration,
+}
+
+/// Several orders across exchange accounts submitted as one logical operation
+pub struct Basket {
+    pub id: BasketId,
+    policy: LeggingPolicy,
+    legs: Vec<(Arc<Exchange>, OrderHeader)>,
+}
+
+impl Basket {
+    pub fn new(policy: LeggingPolicy) -> Self {
+        Basket {
+            id: BasketId::generate(),
+            policy,
+            legs: Vec::new(),
+        }
+    }
+
+    pub fn add_leg(&mut self, exchange: Arc<Exchange>, header: OrderHeader) -> &mut Self {
+        self.legs.push((exchange, header));
+        self
+    }
+
+    /// Places all legs concurrently. Returns placed orders if every leg is placed within
+    /// deadline of legging policy, otherwise cancels placed legs and returns error.
+    /// Outcome of basket is reported to events channel as `ExchangeEvent::BasketEvent`
+    pub async fn submit(self, cancellation_token: CancellationToken) -> Result<Vec<OrderRef>> {
+        let basket_id = self.id;
+        self.validate()?;
+
+        log::info!(
+            "Submitting basket {basket_id} with legs {:?}",
+            self.legs.iter().map(|(_, header)| header).collect_vec()
+        );
+
+        let place_deadline = self.policy.place_deadline;
+        let leg_tokens = self
+            .legs
+            .iter()
+            .map(|_| cancellation_token.create_linked_token())
+            .collect_vec();
+        let results = join_all(self.legs.iter().zip(&leg_tokens).map(
+            |((exchange, header), leg_token)| async move {
+                match timeout(
+                    place_deadline,
+                    exchange.create_order(header, None, leg_token.clone()),
+                )
+                .await
+                {
+                    Ok(result) => result,
+                    Err(_) => {
+                        // stop waiting of creation, order will be cancelled with the rest legs
+                        leg_token.cancel();
+                        bail!("Leg is not placed within {place_deadline:?}")
+                    }
+                }
+            },
+        ))
+        .await;
+
+        let legs = self
+            .legs
+            .iter()
+            .map(|(_, header)| BasketLeg {
+                exchange_account_id: header.exchange_account_id,
+                client_order_id: header.client_order_id.clone(),
+            })
+            .collect_vec();
+
+        let (failed_index, reason) = match find_failed_leg(&results) {
+            None => {
+                log::info!("All legs of basket {basket_id} are placed");
+                self.send_event(legs, BasketOutcome::Placed);
+                return Ok(results.into_iter().flatten().collect());
+            }
+            Some(failed_leg) => failed_leg,
+        };
+
+        log::warn!(
+            "Cancelling legs of basket {basket_id} because leg {} is not placed: {reason}",
+            legs[failed_index].client_order_id
+        );
+
+        // order of timed out leg can be already added to pool, so it's cancelled too
+        let cancel_futures = self.legs.iter().filter_map(|(exchange, header)| {
+            let order = exchange
+                .orders
+                .cache_by_client_id
+                .get(&header.client_order_id)
+                .map(|x| x.value().clone())
+                .filter(|x| !x.is_finished())?;
+
+            let cancellation_token = cancellation_token.clone();
+            Some(async move {
+                if let Err(error) = exchange
+                    .wait_cancel_order(order, None, true, cancellation_token)
+                    .await
+                {
+                    log::error!(
+                        "Failed to cancel leg {} of basket {basket_id}: {error:?}",
+                        header.client_order_id
+                    );
+                }
+            })
+        });
+        join_all(cancel_futures).await;
+
+        let failed_leg = legs[failed_index].clone();
+        self.send_event(
+            legs,
+            BasketOutcome::Aborted {
+                failed_leg,
+                reason: reason.clone(),
+            },
+        );
+
+        bail!("Basket {basket_id} is aborted: {reason}")
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.legs.is_empty() {
+            bail!("Basket {} should contain at least 1 leg", self.id);
+        }
+
+        for (exchange, header) in &self.legs {
+            if exchange.exchange_account_id != header.exchange_account_id {
+                bail!(
+                    "Leg {} of basket {} is for exchange account {}, but submitted to {}",
+                    header.client_order_id,
+                    self.id,
+                    header.exchange_account_id,
+                    exchange.exchange_account_id
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn send_event(&self, legs: Vec<BasketLeg>, outcome: BasketOutcome) {
+        // events channel is shared by all exchanges, so any leg exchange is suitable
+        let (exchange, _) = &self.legs[0];
+        let event = ExchangeEvent::BasketEvent(BasketEvent {
+            basket_id: self.id,
+            legs,
+            outcome,
+        });
+
+        if let Err(error) = exchange.events_channel.send(event) {
+            log::error!(
+                "Unable to send event of basket {}. Probably receiver is already dropped: {error:?}",
+                self.id
+            );
+        }
+    }
+}
+
+/// Index and failure reason of the first leg which isn't placed
+fn find_failed_leg(results: &[Result<OrderRef>]) -> Option<(usize, String)> {
+    results
+        .iter()
+        .enumerate()
+        .find_map(|(index, result)| match result {
+            Ok(order) if order.status() == OrderStatus::FailedToCreate => {
+                let reason = order.fn_ref(|x| x.internal_props.last_creation_error_message.clone());
+                Some((index, format!("Failed to create order: {reason}")))
+            }
+            Ok(_) => None,
+            Err(error) => Some((index, format!("{error:?}"))),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::test_helper;
+    use anyhow::anyhow;
+    use chrono::Utc;
+    use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+    use mmb_domain::order::snapshot::{ClientOrderId, OrderSide};
+    use rust_decimal_macros::dec;
+
+    fn create_leg(status: OrderStatus) -> OrderRef {
+        let order = test_helper::create_order_ref(
+            &ClientOrderId::unique_id(),
+            None,
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+            dec!(0.8),
+            dec!(12),
+            OrderSide::Sell,
+        );
+        order.fn_mut(|x| x.set_status(status, Utc::now()));
+        order
+    }
+
+    #[test]
+    fn first_not_placed_leg_is_failed() {
+        let results = vec![
+            Ok(create_leg(OrderStatus::Created)),
+            Ok(create_leg(OrderStatus::FailedToCreate)),
+            Err(anyhow!("Leg is not placed")),
+        ];
+
+        assert_eq!(find_failed_leg(&results[..1]), None);
+        assert_eq!(find_failed_leg(&results).map(|x| x.0), Some(1));
+        assert_eq!(find_failed_leg(&results[2..]).map(|x| x.0), Some(0));
+    }
+}
@@ -1,3 +0,0 @@
-pub mod buffered_canceled_orders_manager;
-pub mod buffered_fill;
-pub mod buffered_fills_manager;
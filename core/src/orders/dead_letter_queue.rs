@@ -0,0 +1,255 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use mmb_domain::events::{EventSourceType, UnmatchedEventType, UnmatchedExchangeEvent};
+use mmb_domain::market::ExchangeAccountId;
+use mmb_domain::order::snapshot::{Amount, ExchangeOrderId};
+use mmb_utils::DateTime;
+
+use crate::exchanges::general::handlers::handle_order_filled::{FillAmount, FillEvent};
+
+/// Max count of order events kept while their orders are unknown
+pub const DEAD_LETTERS_CAPACITY: usize = 1_000;
+
+/// Interval of attempts to match kept order events with orders which became known, e.g. loaded by
+/// open orders reconciliation
+pub const DEAD_LETTERS_REMATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Order event which can't be applied because there is no order with its exchange order id
+#[derive(Debug, Clone)]
+pub enum DeadLetter {
+    Fill(FillEvent),
+    Cancel {
+        exchange_order_id: ExchangeOrderId,
+        filled_amount: Option<Amount>,
+        source_type: EventSourceType,
+    },
+}
+
+impl DeadLetter {
+    pub fn exchange_order_id(&self) -> &ExchangeOrderId {
+        match self {
+            DeadLetter::Fill(fill_event) => &fill_event.exchange_order_id,
+            DeadLetter::Cancel {
+                exchange_order_id, ..
+            } => exchange_order_id,
+        }
+    }
+
+    fn event_type(&self) -> UnmatchedEventType {
+        match self {
+            DeadLetter::Fill(fill_event) => UnmatchedEventType::Fill {
+                trade_id: fill_event.trade_id.clone(),
+                price: fill_event.fill_price,
+                amount: match fill_event.fill_amount {
+                    FillAmount::Incremental { fill_amount, .. } => fill_amount,
+                    FillAmount::Total {
+                        total_filled_amount,
+                    } => total_filled_amount,
+                },
+            },
+            DeadLetter::Cancel { .. } => UnmatchedEventType::Cancel,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DeadLetterEntry {
+    letter: DeadLetter,
+    received_time: DateTime,
+    rematch_attempts: u32,
+}
+
+/// Bounded buffer of order events which arrived before their orders are known, e.g. fill
+/// notification received before response of order creation. The oldest events are evicted when
+/// the buffer is full
+#[derive(Debug)]
+pub struct DeadLetterQueue {
+    capacity: usize,
+    entries: VecDeque<DeadLetterEntry>,
+}
+
+impl DeadLetterQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Returns the evicted oldest letter if the queue is full
+    pub fn push(&mut self, letter: DeadLetter, now: DateTime) -> Option<DeadLetter> {
+        let evicted = match self.entries.len() >= self.capacity {
+            true => self.entries.pop_front().map(|x| x.letter),
+            false => None,
+        };
+
+        self.entries.push_back(DeadLetterEntry {
+            letter,
+            received_time: now,
+            rematch_attempts: 0,
+        });
+
+        evicted
+    }
+
+    /// Takes letters of the specified order
+    pub fn take_by_order(&mut self, exchange_order_id: &ExchangeOrderId) -> Vec<DeadLetter> {
+        self.take_where(|x| x == exchange_order_id)
+    }
+
+    /// Takes letters which orders are known now. Rematch attempts of the rest letters are counted
+    pub fn take_matched(
+        &mut self,
+        is_known_order: impl Fn(&ExchangeOrderId) -> bool,
+    ) -> Vec<DeadLetter> {
+        let matched = self.take_where(is_known_order);
+        self.entries
+            .iter_mut()
+            .for_each(|x| x.rematch_attempts += 1);
+        matched
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn unmatched_events(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> Vec<UnmatchedExchangeEvent> {
+        self.entries
+            .iter()
+            .map(|x| UnmatchedExchangeEvent {
+                exchange_account_id,
+                exchange_order_id: x.letter.exchange_order_id().clone(),
+                event_type: x.letter.event_type(),
+                received_time: x.received_time,
+                rematch_attempts: x.rematch_attempts,
+            })
+            .collect()
+    }
+
+    /// Fills go before cancellations, so canceled order gets all its fills
+    fn take_where(&mut self, predicate: impl Fn(&ExchangeOrderId) -> bool) -> Vec<DeadLetter> {
+        let (mut taken, rest): (Vec<_>, Vec<_>) = self
+            .entries
+            .drain(..)
+            .partition(|x| predicate(x.letter.exchange_order_id()));
+        self.entries = rest.into();
+
+        taken.sort_by_key(|x| matches!(x.letter, DeadLetter::Cancel { .. }));
+        taken.into_iter().map(|x| x.letter).collect()
+    }
+}
+
+impl Default for DeadLetterQueue {
+    fn default() -> Self {
+        Self::new(DEAD_LETTERS_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use mmb_domain::events::TradeId;
+    use mmb_domain::order::fill::OrderFillType;
+    use rust_decimal_macros::dec;
+
+    fn fill(exchange_order_id: &str, trade_id: u64) -> DeadLetter {
+        DeadLetter::Fill(FillEvent {
+            source_type: EventSourceType::WebSocket,
+            trade_id: Some(TradeId::Number(trade_id)),
+            client_order_id: None,
+            exchange_order_id: exchange_order_id.into(),
+            fill_price: dec!(0.2),
+            fill_amount: FillAmount::Incremental {
+                fill_amount: dec!(5),
+                total_filled_amount: None,
+            },
+            order_role: None,
+            commission_currency_code: None,
+            commission_rate: None,
+            commission_amount: None,
+            fill_type: OrderFillType::UserTrade,
+            special_order_data: None,
+            fill_date: None,
+        })
+    }
+
+    fn cancel(exchange_order_id: &str) -> DeadLetter {
+        DeadLetter::Cancel {
+            exchange_order_id: exchange_order_id.into(),
+            filled_amount: None,
+            source_type: EventSourceType::WebSocket,
+        }
+    }
+
+    fn trade_ids(letters: &[DeadLetter]) -> Vec<Option<TradeId>> {
+        letters
+            .iter()
+            .map(|x| match x {
+                DeadLetter::Fill(fill_event) => fill_event.trade_id.clone(),
+                DeadLetter::Cancel { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn oldest_letter_is_evicted_when_queue_is_full() {
+        let mut queue = DeadLetterQueue::new(2);
+        let now = Utc::now();
+
+        assert!(queue.push(fill("1", 1), now).is_none());
+        assert!(queue.push(fill("2", 2), now).is_none());
+        let evicted = queue.push(fill("3", 3), now).expect("in test");
+
+        assert_eq!(evicted.exchange_order_id().as_str(), "1");
+        let unmatched_ids = queue
+            .unmatched_events(ExchangeAccountId::new("Binance", 0))
+            .into_iter()
+            .map(|x| x.exchange_order_id)
+            .collect::<Vec<_>>();
+        assert_eq!(unmatched_ids, vec!["2".into(), "3".into()]);
+    }
+
+    #[test]
+    fn fills_are_taken_before_cancellation() {
+        let mut queue = DeadLetterQueue::default();
+        let now = Utc::now();
+        queue.push(fill("1", 1), now);
+        queue.push(cancel("1"), now);
+        queue.push(fill("2", 2), now);
+        queue.push(fill("1", 3), now);
+
+        let letters = queue.take_by_order(&"1".into());
+
+        assert_eq!(
+            trade_ids(&letters),
+            vec![Some(TradeId::Number(1)), Some(TradeId::Number(3)), None]
+        );
+        assert_eq!(trade_ids(&queue.take_by_order(&"2".into())).len(), 1);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn rematch_attempts_are_counted_for_unmatched_letters() {
+        let mut queue = DeadLetterQueue::default();
+        let now = Utc::now();
+        queue.push(fill("1", 1), now);
+        queue.push(cancel("2"), now);
+
+        assert!(queue.take_matched(|_| false).is_empty());
+        let matched = queue.take_matched(|x| x.as_str() == "1");
+
+        assert_eq!(trade_ids(&matched), vec![Some(TradeId::Number(1))]);
+        let unmatched = queue.unmatched_events(ExchangeAccountId::new("Binance", 0));
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!(unmatched[0].rematch_attempts, 2);
+        assert!(matches!(
+            unmatched[0].event_type,
+            UnmatchedEventType::Cancel
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use futures::future::join_all;
+use itertools::Itertools;
+use mmb_domain::market::ExchangeAccountId;
+use mmb_domain::order::event::OrderEventType;
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::OrderStatus;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_by_timer;
+use crate::misc::clock::Clock;
+
+/// Resolution of order expiry
+pub const EXPIRY_TICK: Duration = Duration::from_millis(100);
+
+const WHEEL_SLOTS_COUNT: usize = 1024;
+
+/// Hashed timer wheel: item with deadline tick is kept in slot `deadline % slots_count`, so
+/// advancing by one tick looks only through a single slot
+struct TimerWheel<T> {
+    slots: Vec<Vec<(u64, T)>>,
+    current_tick: u64,
+}
+
+impl<T> TimerWheel<T> {
+    fn new(slots_count: usize) -> Self {
+        TimerWheel {
+            slots: (0..slots_count).map(|_| Vec::new()).collect(),
+            current_tick: 0,
+        }
+    }
+
+    fn insert(&mut self, deadline_tick: u64, item: T) {
+        // item with passed deadline is expired on the next advance
+        let deadline_tick = deadline_tick.max(self.current_tick + 1);
+        let slot = (deadline_tick % self.slots.len() as u64) as usize;
+        self.slots[slot].push((deadline_tick, item));
+    }
+
+    /// Removes items with deadline not later than `tick`
+    fn advance(&mut self, tick: u64) -> Vec<T> {
+        let mut expired = Vec::new();
+        if tick <= self.current_tick {
+            return expired;
+        }
+
+        // after long pause every slot can contain expired items
+        let slots_count = self.slots.len() as u64;
+        let steps = (tick - self.current_tick).min(slots_count);
+        for step in 1..=steps {
+            let slot = &mut self.slots[((self.current_tick + step) % slots_count) as usize];
+            let (ready, rest) = slot.drain(..).partition(|(deadline, _)| *deadline <= tick);
+            *slot = rest;
+            expired.extend(ready.into_iter().map(|(_, item)| item));
+        }
+
+        self.current_tick = tick;
+        expired
+    }
+}
+
+/// Cancels orders when their local time-to-live is over, so orders can be good-till-time
+/// regardless of time-in-force support of exchange
+pub struct OrderExpiryService {
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    clock: Arc<dyn Clock>,
+    started_at: DateTime,
+    wheel: Mutex<TimerWheel<OrderRef>>,
+}
+
+impl OrderExpiryService {
+    pub fn new(
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        Arc::new(OrderExpiryService {
+            exchanges,
+            started_at: clock.now(),
+            clock,
+            wheel: Mutex::new(TimerWheel::new(WHEEL_SLOTS_COUNT)),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, cancellation_token: CancellationToken) {
+        let service = self.clone();
+        spawn_by_timer(
+            "Expire orders by time-to-live",
+            EXPIRY_TICK,
+            EXPIRY_TICK,
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            move || {
+                let service = service.clone();
+                let cancellation_token = cancellation_token.clone();
+                async move {
+                    let orders = service.take_expired_orders();
+                    let futures = orders
+                        .into_iter()
+                        .map(|order| service.expire(order, cancellation_token.clone()));
+                    join_all(futures).await;
+                }
+            },
+        );
+    }
+
+    /// Order will be cancelled with `OrderExpired` event if it isn't finished within `ttl`
+    pub fn expire_after(&self, order: OrderRef, ttl: chrono::Duration) {
+        let deadline_tick = self.to_tick(self.clock.now() + ttl);
+        self.wheel.lock().insert(deadline_tick, order);
+    }
+
+    fn to_tick(&self, time: DateTime) -> u64 {
+        let elapsed = (time - self.started_at).num_milliseconds().max(0) as u64;
+        elapsed / EXPIRY_TICK.as_millis() as u64
+    }
+
+    fn take_expired_orders(&self) -> Vec<OrderRef> {
+        let now_tick = self.to_tick(self.clock.now());
+        self.wheel
+            .lock()
+            .advance(now_tick)
+            .into_iter()
+            .filter(|x| !x.is_finished())
+            .collect_vec()
+    }
+
+    async fn expire(&self, order: OrderRef, cancellation_token: CancellationToken) {
+        let client_order_id = order.client_order_id();
+        let exchange = match self.exchanges.get(&order.exchange_account_id()) {
+            Some(exchange) => exchange.value().clone(),
+            None => {
+                log::error!(
+                    "Unable to expire order {client_order_id} because exchange {} isn't found",
+                    order.exchange_account_id()
+                );
+                return;
+            }
+        };
+
+        log::info!("Cancelling order {client_order_id} because its time-to-live is over");
+
+        if let Err(err) = exchange
+            .wait_cancel_order(order.clone(), None, true, cancellation_token)
+            .await
+        {
+            log::error!("Failed to cancel expired order {client_order_id}: {err:?}");
+            return;
+        }
+
+        // order can be completed by fill instead of cancellation
+        if order.status() != OrderStatus::Canceled {
+            return;
+        }
+
+        exchange
+            .add_event_on_order_change(&order, OrderEventType::OrderExpired)
+            .unwrap_or_else(|err| {
+                log::error!("Failed to add OrderExpired event of order {client_order_id}: {err:?}")
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::test_helper;
+    use crate::misc::clock::VirtualClock;
+    use chrono::Utc;
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::snapshot::{ClientOrderId, OrderSide};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn items_are_expired_after_wrap_of_wheel() {
+        let mut wheel = TimerWheel::new(4);
+        wheel.insert(3, "a");
+        wheel.insert(9, "b");
+        wheel.insert(0, "c");
+
+        assert_eq!(wheel.advance(1), vec!["c"]);
+        assert_eq!(wheel.advance(5), vec!["a"]);
+        assert!(wheel.advance(8).is_empty());
+        assert_eq!(wheel.advance(100), vec!["b"]);
+    }
+
+    #[test]
+    fn order_is_expired_after_ttl() {
+        let clock = VirtualClock::new(Utc::now());
+        let service = OrderExpiryService::new(DashMap::new(), clock.clone());
+        let create_order = || {
+            test_helper::create_order_ref(
+                &ClientOrderId::unique_id(),
+                None,
+                ExchangeAccountId::new("Binance", 0),
+                CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+                dec!(0.8),
+                dec!(12),
+                OrderSide::Sell,
+            )
+        };
+        let order = create_order();
+        let completed_order = create_order();
+        completed_order.fn_mut(|x| x.set_status(OrderStatus::Completed, Utc::now()));
+
+        service.expire_after(order.clone(), chrono::Duration::seconds(5));
+        service.expire_after(completed_order, chrono::Duration::seconds(5));
+
+        clock.advance(Duration::from_secs(4));
+        assert!(service.take_expired_orders().is_empty());
+
+        clock.advance(Duration::from_secs(2));
+        let expired = service.take_expired_orders();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].client_order_id(), order.client_order_id());
+    }
+}
@@ -1 +1,3 @@
-pub mod buffered_fills;
+pub mod basket;
+pub mod dead_letter_queue;
+pub mod expiry;
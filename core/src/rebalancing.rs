@@ -0,0 +1,563 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_database::impl_event;
+use mmb_domain::market::{CurrencyCode, CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::snapshot::{Amount, ClientOrderId, OrderHeader, OrderSide, UserOrder};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use mmb_utils::{impl_u64_id, time::get_atomic_current_secs};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::balance::manager::balance_manager::BalanceManager;
+use crate::database::events::recorder::EventRecorder;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::{spawn_by_timer, spawn_future};
+use crate::misc::clock::Clock;
+use crate::settings::{InventoryTargetSettings, RebalancingMode, RebalancingSettings};
+
+const STRATEGY_NAME: &str = "InventoryRebalancer";
+/// Finished decisions above this count are forgotten, they stay only in audit trail
+const MAX_KEPT_DECISIONS: usize = 1000;
+
+impl_u64_id!(RebalanceDecisionId);
+
+impl From<u64> for RebalanceDecisionId {
+    fn from(id: u64) -> Self {
+        RebalanceDecisionId(id)
+    }
+}
+
+/// Balance of exchange account relative to its target at decision time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InventoryState {
+    pub exchange_account_id: ExchangeAccountId,
+    pub balance: Amount,
+    pub target_amount: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RebalanceAction {
+    /// Internal transfer between accounts of the same exchange
+    Transfer {
+        from: ExchangeAccountId,
+        to: ExchangeAccountId,
+        amount: Amount,
+    },
+    /// Market sell of surplus on one exchange and market buy of the same amount on another one
+    HedgeOrders {
+        sell: ExchangeAccountId,
+        buy: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        amount: Amount,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RebalanceDecisionStatus {
+    AwaitingApproval,
+    Rejected,
+    Executing,
+    /// Transfer is completed or hedging orders are created
+    Executed,
+    Failed,
+}
+
+impl RebalanceDecisionStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, Self::Rejected | Self::Executed | Self::Failed)
+    }
+}
+
+/// Decision to rebalance inventory of currency. Every change of its status is saved to
+/// `rebalancing_decisions` table as audit trail
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RebalanceDecision {
+    pub id: RebalanceDecisionId,
+    pub time: DateTime,
+    pub currency_code: CurrencyCode,
+    pub inventory: Vec<InventoryState>,
+    pub action: RebalanceAction,
+    pub status: RebalanceDecisionStatus,
+    /// Reason of execution failure
+    pub error: Option<String>,
+}
+
+impl_event!(RebalanceDecision, "rebalancing_decisions");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlannedAction {
+    currency_code: CurrencyCode,
+    inventory: Vec<InventoryState>,
+    action: RebalanceAction,
+}
+
+/// Monitors inventory of currencies on exchange accounts against configured targets and moves
+/// surplus to accounts with deficit. Accounts of the same exchange are rebalanced by internal
+/// transfers, different exchanges by hedging orders
+pub struct InventoryRebalancer {
+    settings: RebalancingSettings,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    balance_manager: Arc<Mutex<BalanceManager>>,
+    event_recorder: Arc<EventRecorder>,
+    clock: Arc<dyn Clock>,
+    decisions: Mutex<Vec<RebalanceDecision>>,
+}
+
+impl InventoryRebalancer {
+    pub fn new(
+        settings: RebalancingSettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        balance_manager: Arc<Mutex<BalanceManager>>,
+        event_recorder: Arc<EventRecorder>,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            settings,
+            exchanges,
+            balance_manager,
+            event_recorder,
+            clock,
+            decisions: Default::default(),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, cancellation_token: CancellationToken) {
+        let interval = Duration::from_secs(self.settings.interval_secs.max(1));
+        let rebalancer = self.clone();
+        spawn_by_timer(
+            "Rebalance inventory",
+            interval,
+            interval,
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            move || rebalancer.clone().check(cancellation_token.clone()),
+        );
+    }
+
+    /// Known decisions ordered by time of making
+    pub fn decisions(&self) -> Vec<RebalanceDecision> {
+        self.decisions.lock().clone()
+    }
+
+    /// Makes decisions for currencies which inventory deviates from targets. Decisions are
+    /// executed immediately in automatic mode
+    pub async fn check(self: Arc<Self>, cancellation_token: CancellationToken) {
+        let balances = match self
+            .balance_manager
+            .lock()
+            .get_balances()
+            .balances_by_exchange_id
+        {
+            Some(balances) => balances,
+            None => return,
+        };
+
+        let status = match self.settings.mode {
+            RebalancingMode::Automatic => RebalanceDecisionStatus::Executing,
+            RebalancingMode::OperatorApproval => RebalanceDecisionStatus::AwaitingApproval,
+        };
+
+        let mut decision_ids = vec![];
+        {
+            let mut decisions = self.decisions.lock();
+            // inventory is rebalanced again only after previous decisions are finished
+            let currencies_in_progress = decisions
+                .iter()
+                .filter(|x| !x.status.is_finished())
+                .map(|x| x.currency_code)
+                .collect_vec();
+            for planned in plan_actions(&self.settings.targets, &balances) {
+                if currencies_in_progress.contains(&planned.currency_code) {
+                    continue;
+                }
+
+                let decision = RebalanceDecision {
+                    id: RebalanceDecisionId::generate(),
+                    time: self.clock.now(),
+                    currency_code: planned.currency_code,
+                    inventory: planned.inventory,
+                    action: planned.action,
+                    status,
+                    error: None,
+                };
+                self.record(&decision);
+                decision_ids.push(decision.id);
+                decisions.push(decision);
+            }
+
+            while decisions.len() > MAX_KEPT_DECISIONS {
+                match decisions.iter().position(|x| x.status.is_finished()) {
+                    Some(index) => drop(decisions.remove(index)),
+                    None => break,
+                }
+            }
+        }
+
+        if status != RebalanceDecisionStatus::Executing {
+            return;
+        }
+
+        for decision_id in decision_ids {
+            self.execute(decision_id, cancellation_token.clone()).await;
+        }
+    }
+
+    /// Approves decision awaiting approval and starts its execution
+    pub fn approve(
+        self: &Arc<Self>,
+        decision_id: RebalanceDecisionId,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        self.set_awaiting_decision_status(decision_id, RebalanceDecisionStatus::Executing)?;
+
+        let rebalancer = self.clone();
+        spawn_future(
+            "Execute rebalancing decision",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            async move {
+                rebalancer.execute(decision_id, cancellation_token).await;
+                Ok(())
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn reject(&self, decision_id: RebalanceDecisionId) -> Result<()> {
+        self.set_awaiting_decision_status(decision_id, RebalanceDecisionStatus::Rejected)
+    }
+
+    fn set_awaiting_decision_status(
+        &self,
+        decision_id: RebalanceDecisionId,
+        status: RebalanceDecisionStatus,
+    ) -> Result<()> {
+        let mut decisions = self.decisions.lock();
+        let decision = decisions
+            .iter_mut()
+            .find(|x| x.id == decision_id)
+            .with_context(|| format!("Rebalancing decision {decision_id} isn't found"))?;
+
+        if decision.status != RebalanceDecisionStatus::AwaitingApproval {
+            bail!(
+                "Rebalancing decision {decision_id} isn't awaiting approval, its status is {:?}",
+                decision.status
+            );
+        }
+
+        decision.status = status;
+        self.record(decision);
+
+        Ok(())
+    }
+
+    async fn execute(
+        &self,
+        decision_id: RebalanceDecisionId,
+        cancellation_token: CancellationToken,
+    ) {
+        let decision = self
+            .decisions
+            .lock()
+            .iter()
+            .find(|x| x.id == decision_id)
+            .cloned();
+        let decision = match decision {
+            Some(decision) => decision,
+            None => return,
+        };
+
+        let result = self
+            .execute_action(decision.currency_code, &decision.action, cancellation_token)
+            .await;
+
+        let mut decisions = self.decisions.lock();
+        if let Some(decision) = decisions.iter_mut().find(|x| x.id == decision_id) {
+            match result {
+                Ok(()) => decision.status = RebalanceDecisionStatus::Executed,
+                Err(err) => {
+                    decision.status = RebalanceDecisionStatus::Failed;
+                    decision.error = Some(format!("{err:?}"));
+                }
+            }
+            self.record(decision);
+        }
+    }
+
+    async fn execute_action(
+        &self,
+        currency_code: CurrencyCode,
+        action: &RebalanceAction,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        match *action {
+            RebalanceAction::Transfer { from, to, amount } => {
+                let from = self.exchange(from)?;
+                let to = self.exchange(to)?;
+                let _ = from
+                    .transfer_to_account(&to, currency_code, amount, cancellation_token)
+                    .await?;
+            }
+            RebalanceAction::HedgeOrders {
+                sell,
+                buy,
+                currency_pair,
+                amount,
+            } => {
+                let create_order = |exchange_account_id, side| {
+                    let exchange = self.exchange(exchange_account_id);
+                    let cancellation_token = cancellation_token.clone();
+                    async move {
+                        let header = OrderHeader::with_user_order(
+                            ClientOrderId::unique_id(),
+                            exchange_account_id,
+                            currency_pair,
+                            side,
+                            amount,
+                            UserOrder::Market,
+                            None,
+                            None,
+                            STRATEGY_NAME.to_owned(),
+                        );
+                        exchange?
+                            .create_order(&header, None, cancellation_token)
+                            .await
+                            .with_context(|| {
+                                format!("Failed to create hedging order on {exchange_account_id}")
+                            })
+                    }
+                };
+
+                let (sell_order, buy_order) = futures::join!(
+                    create_order(sell, OrderSide::Sell),
+                    create_order(buy, OrderSide::Buy)
+                );
+                let _ = sell_order?;
+                let _ = buy_order?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn record(&self, decision: &RebalanceDecision) {
+        log::info!("Rebalancing decision: {decision:?}");
+        if let Err(err) = self.event_recorder.save(decision.clone()) {
+            log::error!(
+                "Failed to save rebalancing decision {}: {err:?}",
+                decision.id
+            );
+        }
+    }
+
+    fn exchange(&self, exchange_account_id: ExchangeAccountId) -> Result<Arc<Exchange>> {
+        self.exchanges
+            .get(&exchange_account_id)
+            .map(|x| x.value().clone())
+            .with_context(|| format!("Exchange {exchange_account_id} isn't found"))
+    }
+}
+
+/// Matches accounts with surplus of currency to accounts with deficit of it. Accounts of the
+/// same exchange are matched first, because internal transfers are free. Currencies are
+/// skipped until balances of all their accounts are known
+fn plan_actions(
+    targets: &[InventoryTargetSettings],
+    balances: &HashMap<ExchangeAccountId, HashMap<CurrencyCode, Amount>>,
+) -> Vec<PlannedAction> {
+    let mut planned = vec![];
+    for currency_code in targets.iter().map(|x| x.currency_code).unique() {
+        let targets = targets
+            .iter()
+            .filter(|x| x.currency_code == currency_code)
+            .collect_vec();
+        if targets
+            .iter()
+            .any(|x| !balances.contains_key(&x.exchange_account_id))
+        {
+            continue;
+        }
+
+        let inventory = targets
+            .iter()
+            .map(|x| InventoryState {
+                exchange_account_id: x.exchange_account_id,
+                balance: balances[&x.exchange_account_id]
+                    .get(&currency_code)
+                    .copied()
+                    .unwrap_or_default(),
+                target_amount: x.target_amount,
+            })
+            .collect_vec();
+
+        let mut surpluses = vec![];
+        let mut deficits = vec![];
+        for (target, state) in targets.iter().zip(&inventory) {
+            let deviation = state.balance - state.target_amount;
+            if deviation > target.tolerance {
+                surpluses.push((*target, deviation));
+            } else if -deviation > target.tolerance {
+                deficits.push((*target, -deviation));
+            }
+        }
+
+        let mut push_action = |action| {
+            planned.push(PlannedAction {
+                currency_code,
+                inventory: inventory.clone(),
+                action,
+            })
+        };
+
+        for (deficit_target, deficit) in &mut deficits {
+            for (surplus_target, surplus) in &mut surpluses {
+                let same_exchange = surplus_target.exchange_account_id.exchange_id
+                    == deficit_target.exchange_account_id.exchange_id;
+                let amount = (*surplus).min(*deficit);
+                if !same_exchange || amount.is_zero() {
+                    continue;
+                }
+
+                *surplus -= amount;
+                *deficit -= amount;
+                push_action(RebalanceAction::Transfer {
+                    from: surplus_target.exchange_account_id,
+                    to: deficit_target.exchange_account_id,
+                    amount,
+                });
+            }
+        }
+
+        for (deficit_target, deficit) in &mut deficits {
+            for (surplus_target, surplus) in &mut surpluses {
+                let currency_pair = match (
+                    surplus_target.hedge_currency_pair,
+                    deficit_target.hedge_currency_pair,
+                ) {
+                    (Some(sell_pair), Some(buy_pair)) if sell_pair == buy_pair => sell_pair,
+                    _ => continue,
+                };
+                let amount = (*surplus).min(*deficit);
+                if amount.is_zero() {
+                    continue;
+                }
+
+                *surplus -= amount;
+                *deficit -= amount;
+                push_action(RebalanceAction::HedgeOrders {
+                    sell: surplus_target.exchange_account_id,
+                    buy: deficit_target.exchange_account_id,
+                    currency_pair,
+                    amount,
+                });
+            }
+        }
+    }
+
+    planned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn target(
+        exchange_account_id: ExchangeAccountId,
+        target_amount: Amount,
+        hedge_currency_pair: Option<CurrencyPair>,
+    ) -> InventoryTargetSettings {
+        InventoryTargetSettings {
+            exchange_account_id,
+            currency_code: "btc".into(),
+            target_amount,
+            tolerance: dec!(0.1),
+            hedge_currency_pair,
+        }
+    }
+
+    fn balances(
+        items: &[(ExchangeAccountId, Amount)],
+    ) -> HashMap<ExchangeAccountId, HashMap<CurrencyCode, Amount>> {
+        items
+            .iter()
+            .map(|&(id, amount)| (id, HashMap::from([("btc".into(), amount)])))
+            .collect()
+    }
+
+    #[test]
+    fn transfer_between_accounts_of_the_same_exchange() {
+        let binance_0 = ExchangeAccountId::new("Binance", 0);
+        let binance_1 = ExchangeAccountId::new("Binance", 1);
+        let targets = [
+            target(binance_0, dec!(1), None),
+            target(binance_1, dec!(1), None),
+        ];
+
+        let planned = plan_actions(
+            &targets,
+            &balances(&[(binance_0, dec!(1.5)), (binance_1, dec!(0.5))]),
+        );
+
+        let actions = planned.into_iter().map(|x| x.action).collect_vec();
+        assert_eq!(
+            actions,
+            vec![RebalanceAction::Transfer {
+                from: binance_0,
+                to: binance_1,
+                amount: dec!(0.5),
+            }]
+        );
+    }
+
+    #[test]
+    fn hedge_orders_between_exchanges() {
+        let binance = ExchangeAccountId::new("Binance", 0);
+        let okx = ExchangeAccountId::new("Okx", 0);
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let targets = [
+            target(binance, dec!(1), Some(currency_pair)),
+            target(okx, dec!(1), Some(currency_pair)),
+        ];
+
+        let planned = plan_actions(&targets, &balances(&[(binance, dec!(0.2)), (okx, dec!(2))]));
+
+        let actions = planned.into_iter().map(|x| x.action).collect_vec();
+        assert_eq!(
+            actions,
+            vec![RebalanceAction::HedgeOrders {
+                sell: okx,
+                buy: binance,
+                currency_pair,
+                amount: dec!(0.8),
+            }]
+        );
+    }
+
+    #[test]
+    fn nothing_to_do_within_tolerance_or_without_known_balances() {
+        let binance = ExchangeAccountId::new("Binance", 0);
+        let okx = ExchangeAccountId::new("Okx", 0);
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let targets = [
+            target(binance, dec!(1), Some(currency_pair)),
+            target(okx, dec!(1), Some(currency_pair)),
+        ];
+
+        let within_tolerance = balances(&[(binance, dec!(0.95)), (okx, dec!(1.05))]);
+        assert!(plan_actions(&targets, &within_tolerance).is_empty());
+
+        let unknown_okx_balances = balances(&[(binance, dec!(0.2))]);
+        assert!(plan_actions(&targets, &unknown_okx_balances).is_empty());
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+/// Global flag of halted trading. After triggering no new orders can be created until restart
+#[derive(Default)]
+pub struct KillSwitch {
+    is_triggered: AtomicBool,
+    reason: Mutex<Option<String>>,
+}
+
+impl KillSwitch {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Returns `false` if kill switch was already triggered before
+    pub(crate) fn trigger(&self, reason: &str) -> bool {
+        if self
+            .is_triggered
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return false;
+        }
+
+        *self.reason.lock() = Some(reason.to_owned());
+        true
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.is_triggered.load(Ordering::SeqCst)
+    }
+
+    pub fn reason(&self) -> Option<String> {
+        self.reason.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triggered_only_once() {
+        let kill_switch = KillSwitch::new();
+        assert!(!kill_switch.is_triggered());
+
+        assert!(kill_switch.trigger("first"));
+        assert!(!kill_switch.trigger("second"));
+
+        assert!(kill_switch.is_triggered());
+        assert_eq!(kill_switch.reason().as_deref(), Some("first"));
+    }
+}
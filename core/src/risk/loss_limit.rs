@@ -0,0 +1,289 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use chrono::Duration;
+use dashmap::DashMap;
+use mmb_domain::market::{CurrencyCode, ExchangeAccountId};
+use mmb_domain::order::snapshot::Price;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::alerting::{alert, AlertKind, AlertSeverity};
+use crate::balance::manager::balance_manager::BalanceManager;
+use crate::exchanges::block_reasons::LOSS_LIMIT_ACKNOWLEDGEMENT;
+use crate::exchanges::exchange_blocker::{BlockType, ExchangeBlocker};
+use crate::exchanges::general::exchange::Exchange;
+use crate::settings::LossLimitSettings;
+
+/// Loss of exchange account within rolling window exceeding configured limit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LossLimitBreach {
+    pub exchange_account_id: ExchangeAccountId,
+    pub time: DateTime,
+    /// Negative PnL within rolling window in valuation currency
+    pub pnl: Decimal,
+    pub limit: Decimal,
+    pub valuation_currency_code: CurrencyCode,
+}
+
+impl Display for LossLimitBreach {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PnL of {} within {WINDOW_HOURS}h is {} {} (limit -{})",
+            self.exchange_account_id, self.pnl, self.valuation_currency_code, self.limit
+        )
+    }
+}
+
+const WINDOW_HOURS: i64 = 24;
+
+/// Equity samples within rolling window
+struct PnlWindow {
+    window: Duration,
+    samples: VecDeque<(DateTime, Decimal)>,
+}
+
+impl PnlWindow {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Adds equity sample and returns PnL relative to the oldest sample within window
+    fn add(&mut self, time: DateTime, equity: Decimal) -> Decimal {
+        self.samples.push_back((time, equity));
+
+        // the oldest sample at the window start or before it is kept as reference
+        while self
+            .samples
+            .get(1)
+            .is_some_and(|(sample_time, _)| *sample_time <= time - self.window)
+        {
+            let _ = self.samples.pop_front();
+        }
+
+        let (_, reference) = self.samples.front().expect("sample was just added");
+        equity - reference
+    }
+
+    fn reset(&mut self) {
+        self.samples.clear();
+    }
+}
+
+/// Pauses trading on exchange account when its realized and unrealized PnL within rolling 24h
+/// window falls below configured loss limit. Account is blocked until operator acknowledges the
+/// loss via control API, so strategies trading on it are paused too
+pub struct LossLimitGuard {
+    exchange_blocker: Arc<ExchangeBlocker>,
+    windows: Mutex<HashMap<ExchangeAccountId, PnlWindow>>,
+    breaches: Mutex<Vec<LossLimitBreach>>,
+}
+
+impl LossLimitGuard {
+    pub fn new(exchange_blocker: Arc<ExchangeBlocker>) -> Arc<Self> {
+        Arc::new(Self {
+            exchange_blocker,
+            windows: Default::default(),
+            breaches: Default::default(),
+        })
+    }
+
+    /// Values balances of exchange accounts by current mid prices and blocks the ones which
+    /// loss exceeds the limit
+    pub(crate) fn check(
+        &self,
+        settings: &LossLimitSettings,
+        exchanges: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+        balance_manager: &Mutex<BalanceManager>,
+        now: DateTime,
+    ) {
+        for exchange in exchanges.iter() {
+            if exchange.is_watch_only() {
+                continue;
+            }
+
+            let exchange_account_id = exchange.exchange_account_id;
+            let equity = balance_manager.lock().get_exchange_balances_value(
+                exchange_account_id,
+                |balance, currency_code| {
+                    value_in(&exchange, currency_code, settings.valuation_currency_code)
+                        .map(|price| balance * price)
+                },
+            );
+            match equity {
+                Some(equity) => self.add_equity(settings, exchange_account_id, now, equity),
+                None => log::debug!(
+                    "Loss limit isn't checked for {exchange_account_id} because some balances can't be valued in {}",
+                    settings.valuation_currency_code
+                ),
+            }
+        }
+    }
+
+    fn add_equity(
+        &self,
+        settings: &LossLimitSettings,
+        exchange_account_id: ExchangeAccountId,
+        time: DateTime,
+        equity: Decimal,
+    ) {
+        if self.is_breached(exchange_account_id) {
+            return;
+        }
+
+        let pnl = self
+            .windows
+            .lock()
+            .entry(exchange_account_id)
+            .or_insert_with(|| PnlWindow::new(Duration::hours(WINDOW_HOURS)))
+            .add(time, equity);
+
+        if pnl > -settings.max_loss {
+            return;
+        }
+
+        let breach = LossLimitBreach {
+            exchange_account_id,
+            time,
+            pnl,
+            limit: settings.max_loss,
+            valuation_currency_code: settings.valuation_currency_code,
+        };
+        let message =
+            format!("Loss limit is breached: {breach}. Trading is paused until acknowledgement");
+        log::error!("{message}");
+        alert(
+            AlertSeverity::Critical,
+            AlertKind::RiskRule,
+            &exchange_account_id.to_string(),
+            message,
+        );
+
+        self.exchange_blocker.block(
+            exchange_account_id,
+            LOSS_LIMIT_ACKNOWLEDGEMENT,
+            BlockType::Manual,
+        );
+        self.breaches.lock().push(breach);
+    }
+
+    fn is_breached(&self, exchange_account_id: ExchangeAccountId) -> bool {
+        self.breaches
+            .lock()
+            .iter()
+            .any(|x| x.exchange_account_id == exchange_account_id)
+    }
+
+    /// Breaches waiting for operator acknowledgement
+    pub fn breaches(&self) -> Vec<LossLimitBreach> {
+        self.breaches.lock().clone()
+    }
+
+    /// Operator accepts the loss, so strategies can resume trading. PnL window is restarted from
+    /// the current equity. Returns acknowledged breaches
+    pub fn acknowledge(&self) -> Vec<LossLimitBreach> {
+        let breaches = std::mem::take(&mut *self.breaches.lock());
+
+        let mut windows = self.windows.lock();
+        for breach in &breaches {
+            let exchange_account_id = breach.exchange_account_id;
+            log::info!("Loss limit breach of {exchange_account_id} is acknowledged by operator");
+
+            if let Some(window) = windows.get_mut(&exchange_account_id) {
+                window.reset();
+            }
+            self.exchange_blocker
+                .unblock(exchange_account_id, LOSS_LIMIT_ACKNOWLEDGEMENT);
+        }
+
+        breaches
+    }
+}
+
+/// Balances of exchange account valued by current mark prices. `None` if some balance can't be valued
+pub(crate) fn equity(
+    exchange: &Exchange,
+    balances: &HashMap<CurrencyCode, Decimal>,
+    valuation_currency_code: CurrencyCode,
+) -> Option<Decimal> {
+    balances
+        .iter()
+        .filter(|(_, balance)| !balance.is_zero())
+        .map(|(&currency_code, balance)| {
+            value_in(exchange, currency_code, valuation_currency_code).map(|price| *balance * price)
+        })
+        .sum()
+}
+
+/// Price of currency in valuation currency by mark price of direct or inverse market or by cross
+/// rate if there is no such market
+pub(crate) fn value_in(
+    exchange: &Exchange,
+    currency_code: CurrencyCode,
+    valuation_currency_code: CurrencyCode,
+) -> Option<Price> {
+    if currency_code == valuation_currency_code {
+        return Some(dec!(1));
+    }
+
+    let direct_price = exchange.symbols.iter().find_map(|symbol| {
+        let (base, quote) = (symbol.base_currency_code, symbol.quote_currency_code);
+        if base == currency_code && quote == valuation_currency_code {
+            exchange.mark_price(symbol.currency_pair())
+        } else if base == valuation_currency_code && quote == currency_code {
+            exchange
+                .mark_price(symbol.currency_pair())
+                .filter(|price| !price.is_zero())
+                .map(|price| dec!(1) / price)
+        } else {
+            None
+        }
+    });
+    direct_price.or_else(|| {
+        exchange
+            .cross_rate_service()?
+            .rate(currency_code, valuation_currency_code)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn time(hours: i64) -> DateTime {
+        Utc.ymd(2022, 11, 17).and_hms(0, 0, 0) + Duration::hours(hours)
+    }
+
+    #[test]
+    fn pnl_is_relative_to_window_start() {
+        let mut window = PnlWindow::new(Duration::hours(24));
+
+        assert_eq!(window.add(time(0), dec!(1000)), dec!(0));
+        assert_eq!(window.add(time(10), dec!(900)), dec!(-100));
+        assert_eq!(window.add(time(20), dec!(950)), dec!(-50));
+        // sample of 0h is out of window, so 10h sample is the reference
+        assert_eq!(window.add(time(34), dec!(850)), dec!(-50));
+        assert_eq!(window.add(time(44), dec!(850)), dec!(-100));
+    }
+
+    #[test]
+    fn pnl_after_reset() {
+        let mut window = PnlWindow::new(Duration::hours(24));
+        let _ = window.add(time(0), dec!(1000));
+        let _ = window.add(time(1), dec!(700));
+
+        window.reset();
+
+        assert_eq!(window.add(time(2), dec!(700)), dec!(0));
+        assert_eq!(window.add(time(3), dec!(650)), dec!(-50));
+    }
+}
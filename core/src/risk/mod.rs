@@ -0,0 +1,4 @@
+pub mod kill_switch;
+pub mod loss_limit;
+pub mod order_to_trade;
+pub mod risk_manager;
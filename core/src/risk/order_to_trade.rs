@@ -0,0 +1,73 @@
+use std::collections::VecDeque;
+
+use chrono::Duration;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+
+/// Cancellations and fills of exchange account within rolling window
+#[derive(Debug, Default)]
+pub(crate) struct OrderToTradeWindow {
+    cancels: VecDeque<DateTime>,
+    fills: VecDeque<DateTime>,
+}
+
+impl OrderToTradeWindow {
+    pub(crate) fn register_cancel(&mut self, time: DateTime, window: Duration) {
+        self.cancels.push_back(time);
+        self.remove_outdated(time, window);
+    }
+
+    pub(crate) fn register_fill(&mut self, time: DateTime, window: Duration) {
+        self.fills.push_back(time);
+        self.remove_outdated(time, window);
+    }
+
+    /// Counts of cancellations and fills within window ending at `now`
+    pub(crate) fn counts(&mut self, now: DateTime, window: Duration) -> (usize, usize) {
+        self.remove_outdated(now, window);
+        (self.cancels.len(), self.fills.len())
+    }
+
+    fn remove_outdated(&mut self, now: DateTime, window: Duration) {
+        let window_start = now - window;
+        for times in [&mut self.cancels, &mut self.fills] {
+            while times.front().is_some_and(|x| *x <= window_start) {
+                let _ = times.pop_front();
+            }
+        }
+    }
+}
+
+/// Canceled orders per fill. Window without fills is counted as having a single fill,
+/// so ratio grows with every cancellation
+pub(crate) fn cancel_fill_ratio(cancels_count: usize, fills_count: usize) -> Decimal {
+    Decimal::from(cancels_count) / Decimal::from(fills_count.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn outdated_events_are_not_counted() {
+        let window = Duration::minutes(10);
+        let start = Utc::now();
+        let mut order_to_trade = OrderToTradeWindow::default();
+        order_to_trade.register_cancel(start, window);
+        order_to_trade.register_fill(start, window);
+        order_to_trade.register_cancel(start + Duration::minutes(6), window);
+        order_to_trade.register_cancel(start + Duration::minutes(8), window);
+
+        assert_eq!(
+            order_to_trade.counts(start + Duration::minutes(9), window),
+            (3, 1)
+        );
+        assert_eq!(
+            order_to_trade.counts(start + Duration::minutes(12), window),
+            (2, 0)
+        );
+        assert_eq!(cancel_fill_ratio(2, 0), dec!(2));
+    }
+}
@@ -0,0 +1,759 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::event::OrderEventType;
+use mmb_domain::order::snapshot::{Amount, OrderHeader, OrderSide, Price};
+use mmb_utils::nothing_to_do;
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use thiserror::Error;
+
+use crate::alerting::{alert, AlertKind, AlertSeverity};
+use crate::misc::clock::{Clock, SystemClock};
+use crate::risk::order_to_trade::{cancel_fill_ratio, OrderToTradeWindow};
+use crate::services::cross_rates::CrossRateService;
+use crate::settings::{
+    ExposureGroupSettings, OrderToTradeSettings, RiskSettings, VolatilityScalingSettings,
+};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RiskViolation {
+    #[error("order notional {notional} exceeds limit {limit}")]
+    OrderNotional { notional: Decimal, limit: Decimal },
+    #[error("position {position} on {currency_pair} after order exceeds limit {limit}")]
+    Position {
+        currency_pair: CurrencyPair,
+        position: Amount,
+        limit: Amount,
+    },
+    #[error("open orders count {count} reached limit {limit}")]
+    OpenOrders { count: usize, limit: usize },
+    #[error(
+        "order price {price} deviates from mid price {mid_price} by more than {limit_percent}%"
+    )]
+    PriceCollar {
+        price: Price,
+        mid_price: Price,
+        limit_percent: Decimal,
+    },
+    #[error("exposure {exposure} of group '{group}' after order exceeds limit {limit}")]
+    Exposure {
+        group: String,
+        exposure: Amount,
+        limit: Amount,
+    },
+    #[error("cancel/fill ratio {ratio} of {exchange_account_id} reached limit {limit}")]
+    CancelFillRatio {
+        exchange_account_id: ExchangeAccountId,
+        ratio: Decimal,
+        limit: Decimal,
+    },
+    #[error(
+        "request weight utilization {utilization} of {exchange_account_id} reached limit {limit}"
+    )]
+    RequestWeight {
+        exchange_account_id: ExchangeAccountId,
+        utilization: Decimal,
+        limit: Decimal,
+    },
+    /// Check can't be done because there is no market data for the currency pair yet
+    #[error("mid price of {0} is unknown")]
+    UnknownMidPrice(CurrencyPair),
+    #[error("exposure of group '{group}' can't be checked without cross rate of {currency_pair}")]
+    UnknownCrossRate {
+        group: String,
+        currency_pair: CurrencyPair,
+    },
+}
+
+/// Trading state of exchange account used for pre-trade checks of an order
+#[derive(Debug, Clone, Default)]
+pub struct RiskContext {
+    pub mid_price: Option<Price>,
+    /// Signed net position on currency pair of order (long is positive)
+    pub position: Amount,
+    /// Count of not finished orders on exchange account
+    pub open_orders_count: usize,
+    /// Share of REST request limit of exchange account used in current period, from 0 to 1
+    pub request_weight_utilization: Decimal,
+    /// Realized volatility of market of order per candle interval
+    pub volatility: Option<Decimal>,
+}
+
+/// Pre-trade checks which every order passes before it is sent to exchange.
+/// Orders violating configured limits are rejected locally
+pub struct RiskManager {
+    settings: RwLock<RiskSettings>,
+    /// Combined signed positions of exposure groups by group name
+    exposures: Mutex<HashMap<String, Amount>>,
+    order_to_trade: Mutex<HashMap<ExchangeAccountId, OrderToTradeWindow>>,
+    /// Weak because cross rate service holds exchanges which hold risk manager
+    cross_rates: Mutex<Option<Weak<CrossRateService>>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl RiskManager {
+    pub fn new(settings: RiskSettings) -> Arc<Self> {
+        Self::with_clock(settings, SystemClock::shared())
+    }
+
+    pub fn with_clock(settings: RiskSettings, clock: Arc<dyn Clock>) -> Arc<Self> {
+        Arc::new(Self {
+            settings: RwLock::new(settings),
+            exposures: Default::default(),
+            order_to_trade: Default::default(),
+            cross_rates: Default::default(),
+            clock,
+        })
+    }
+
+    /// Cross rates are required by exposure groups with `currency_code`
+    pub fn setup_cross_rates(&self, cross_rates: &Arc<CrossRateService>) {
+        *self.cross_rates.lock() = Some(Arc::downgrade(cross_rates));
+    }
+
+    pub fn settings(&self) -> RiskSettings {
+        self.settings.read().clone()
+    }
+
+    /// Replaces limits without restart. Exposures of removed groups are forgotten, exposures of
+    /// other groups should be recalculated by `update_exposures`
+    pub fn update_settings(&self, settings: RiskSettings) {
+        self.exposures
+            .lock()
+            .retain(|name, _| settings.exposure_groups.iter().any(|x| &x.name == name));
+        *self.settings.write() = settings;
+    }
+
+    pub fn check(&self, header: &OrderHeader, context: &RiskContext) -> Result<(), RiskViolation> {
+        let settings = &*self.settings.read();
+
+        if let Some(limit) = settings.max_open_orders_count {
+            if context.open_orders_count >= limit {
+                return Err(RiskViolation::OpenOrders {
+                    count: context.open_orders_count,
+                    limit,
+                });
+            }
+        }
+
+        if let Some(limit) = settings.max_position_amount {
+            let limit = position_limit(
+                limit,
+                settings.volatility_scaling.as_ref(),
+                context.volatility,
+            );
+            let position = context.position + signed_amount(header);
+
+            // orders reducing position are allowed even if limit is already exceeded
+            if position.abs() > limit && position.abs() > context.position.abs() {
+                return Err(RiskViolation::Position {
+                    currency_pair: header.currency_pair,
+                    position,
+                    limit,
+                });
+            }
+        }
+
+        self.check_exposure(settings, header)?;
+
+        if let Some(order_to_trade) = &settings.order_to_trade {
+            self.check_order_to_trade(order_to_trade, header.exchange_account_id, context)?;
+        }
+
+        let need_mid_price =
+            settings.max_order_notional.is_some() || settings.price_collar_percent.is_some();
+        if !need_mid_price {
+            return Ok(());
+        }
+
+        let mid_price = context
+            .mid_price
+            .ok_or(RiskViolation::UnknownMidPrice(header.currency_pair))?;
+
+        if let Some(limit) = settings.max_order_notional {
+            // market orders are estimated by mid price
+            let price = header.source_price().unwrap_or(mid_price);
+            let notional = price * header.amount;
+            if notional > limit {
+                return Err(RiskViolation::OrderNotional { notional, limit });
+            }
+        }
+
+        if let (Some(limit_percent), Some(price)) =
+            (settings.price_collar_percent, header.source_price())
+        {
+            let deviation_percent = (price - mid_price).abs() / mid_price * dec!(100);
+            if deviation_percent > limit_percent {
+                return Err(RiskViolation::PriceCollar {
+                    price,
+                    mid_price,
+                    limit_percent,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_exposure(
+        &self,
+        settings: &RiskSettings,
+        header: &OrderHeader,
+    ) -> Result<(), RiskViolation> {
+        for group in &settings.exposure_groups {
+            let market = group.markets.iter().find(|x| {
+                x.exchange_account_id == header.exchange_account_id
+                    && x.currency_pair == header.currency_pair
+            });
+            let market = match market {
+                Some(market) => market,
+                None => continue,
+            };
+
+            let exposure = self
+                .exposures
+                .lock()
+                .get(&group.name)
+                .copied()
+                .unwrap_or_default();
+            let order_exposure = self
+                .group_amount(
+                    group,
+                    header.exchange_account_id,
+                    header.currency_pair,
+                    signed_amount(header),
+                )
+                .ok_or_else(|| RiskViolation::UnknownCrossRate {
+                    group: group.name.clone(),
+                    currency_pair: header.currency_pair,
+                })?;
+            let new_exposure = exposure + order_exposure * market.weight();
+
+            // orders reducing exposure are allowed even if limit is already exceeded
+            if new_exposure.abs() > group.max_amount && new_exposure.abs() > exposure.abs() {
+                return Err(RiskViolation::Exposure {
+                    group: group.name.clone(),
+                    exposure: new_exposure,
+                    limit: group.max_amount,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_order_to_trade(
+        &self,
+        settings: &OrderToTradeSettings,
+        exchange_account_id: ExchangeAccountId,
+        context: &RiskContext,
+    ) -> Result<(), RiskViolation> {
+        if let Some(limit) = settings.max_request_weight_utilization {
+            if context.request_weight_utilization >= limit {
+                return Err(RiskViolation::RequestWeight {
+                    exchange_account_id,
+                    utilization: context.request_weight_utilization,
+                    limit,
+                });
+            }
+        }
+
+        if let Some(limit) = settings.max_cancel_fill_ratio {
+            let (cancels_count, fills_count) = self
+                .order_to_trade
+                .lock()
+                .entry(exchange_account_id)
+                .or_default()
+                .counts(self.clock.now(), settings.window());
+
+            let ratio = cancel_fill_ratio(cancels_count, fills_count);
+            if cancels_count >= settings.min_cancels_count && ratio >= limit {
+                return Err(RiskViolation::CancelFillRatio {
+                    exchange_account_id,
+                    ratio,
+                    limit,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Counts cancellations and fills of exchange account for order-to-trade limit.
+    /// Should be called on each order event
+    pub fn register_order_event(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        event_type: &OrderEventType,
+    ) {
+        let window = match &self.settings.read().order_to_trade {
+            Some(settings) => settings.window(),
+            None => return,
+        };
+
+        let now = self.clock.now();
+        let mut order_to_trade = self.order_to_trade.lock();
+        let order_to_trade = order_to_trade.entry(exchange_account_id).or_default();
+        match event_type {
+            OrderEventType::CancelOrderSucceeded => order_to_trade.register_cancel(now, window),
+            OrderEventType::OrderFilled { .. } => order_to_trade.register_fill(now, window),
+            _ => nothing_to_do(),
+        }
+    }
+
+    /// Recalculates exposure groups by signed net positions of their markets.
+    /// Should be called on each fill, so size-increasing orders are blocked as soon as a group
+    /// exceeds its limit
+    pub fn update_exposures(
+        &self,
+        get_position: impl Fn(ExchangeAccountId, CurrencyPair) -> Amount,
+    ) {
+        let settings = self.settings.read();
+        let mut exposures = self.exposures.lock();
+        for group in &settings.exposure_groups {
+            let exposure = group
+                .markets
+                .iter()
+                .map(|x| {
+                    let position = get_position(x.exchange_account_id, x.currency_pair);
+                    self.group_amount(group, x.exchange_account_id, x.currency_pair, position)
+                        .map(|amount| amount * x.weight())
+                })
+                .sum::<Option<Amount>>();
+            let Some(exposure) = exposure else {
+                log::warn!(
+                    "Exposure of group '{}' isn't updated because cross rates of its markets are unknown",
+                    group.name
+                );
+                continue;
+            };
+
+            let limit = group.max_amount;
+            let was_exceeded = exposures
+                .insert(group.name.clone(), exposure)
+                .is_some_and(|x| x.abs() > limit);
+            match (was_exceeded, exposure.abs() > limit) {
+                (false, true) => {
+                    let message = format!("Exposure {exposure} of group '{}' exceeds limit {limit}. Orders increasing it are blocked", group.name);
+                    log::warn!("{message}");
+                    alert(
+                        AlertSeverity::Warning,
+                        AlertKind::RiskRule,
+                        &group.name,
+                        message,
+                    );
+                }
+                (true, false) => log::info!(
+                    "Exposure {exposure} of group '{}' is within limit {limit} again",
+                    group.name
+                ),
+                _ => nothing_to_do(),
+            }
+        }
+    }
+
+    pub fn exposures(&self) -> HashMap<String, Amount> {
+        self.exposures.lock().clone()
+    }
+
+    /// Amount in base currency of market converted into currency of exposure group if it's
+    /// specified. `None` if cross rate is unknown
+    fn group_amount(
+        &self,
+        group: &ExposureGroupSettings,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        amount: Amount,
+    ) -> Option<Amount> {
+        let currency_code = match group.currency_code {
+            Some(currency_code) if !amount.is_zero() => currency_code,
+            _ => return Some(amount),
+        };
+
+        self.cross_rates
+            .lock()
+            .as_ref()
+            .and_then(Weak::upgrade)?
+            .convert_base_amount(exchange_account_id, currency_pair, amount, currency_code)
+    }
+}
+
+fn signed_amount(header: &OrderHeader) -> Amount {
+    match header.side {
+        OrderSide::Buy => header.amount,
+        OrderSide::Sell => -header.amount,
+    }
+}
+
+/// Position limit reduced by ratio of target volatility to realized one
+fn position_limit(
+    limit: Amount,
+    volatility_scaling: Option<&VolatilityScalingSettings>,
+    volatility: Option<Decimal>,
+) -> Amount {
+    match (volatility_scaling, volatility) {
+        (Some(scaling), Some(volatility)) if volatility > scaling.target_volatility => {
+            limit * (scaling.target_volatility / volatility).max(scaling.min_position_share)
+        }
+        _ => limit,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::misc::clock::VirtualClock;
+    use crate::settings::{ExposureGroupSettings, ExposureMarketSettings};
+    use mmb_domain::order::snapshot::{ClientOrderId, OrderOptions, UserOrder};
+    use rstest::rstest;
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    fn order(side: OrderSide, amount: Amount, price: Option<Price>) -> OrderHeader {
+        let options = match price {
+            Some(price) => OrderOptions::limit(price),
+            None => OrderOptions::User(UserOrder::Market),
+        };
+
+        OrderHeader::with_options(
+            ClientOrderId::from("1"),
+            ExchangeAccountId::new("Binance", 0),
+            currency_pair(),
+            side,
+            amount,
+            options,
+            None,
+            None,
+            "test".to_owned(),
+        )
+    }
+
+    fn context(position: Amount, open_orders_count: usize) -> RiskContext {
+        RiskContext {
+            mid_price: Some(dec!(100)),
+            position,
+            open_orders_count,
+            request_weight_utilization: dec!(0),
+            volatility: None,
+        }
+    }
+
+    fn risk_manager() -> Arc<RiskManager> {
+        RiskManager::new(RiskSettings {
+            max_order_notional: Some(dec!(1000)),
+            max_position_amount: Some(dec!(5)),
+            max_open_orders_count: Some(3),
+            price_collar_percent: Some(dec!(10)),
+            kill_switch_on_violation: false,
+            loss_limit: None,
+            exposure_groups: vec![],
+            order_to_trade: None,
+            volatility_scaling: None,
+        })
+    }
+
+    #[test]
+    fn order_within_limits_is_accepted() {
+        let result = risk_manager().check(
+            &order(OrderSide::Buy, dec!(2), Some(dec!(99))),
+            &context(dec!(1), 2),
+        );
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn position_limit_is_reduced_by_high_volatility() {
+        let settings = RiskSettings {
+            volatility_scaling: Some(VolatilityScalingSettings {
+                target_volatility: dec!(0.01),
+                min_position_share: dec!(0.1),
+            }),
+            ..risk_manager().settings()
+        };
+        let risk_manager = RiskManager::new(settings);
+        let header = order(OrderSide::Buy, dec!(1), Some(dec!(100)));
+        let context = |volatility| RiskContext {
+            volatility: Some(volatility),
+            ..context(dec!(2), 0)
+        };
+
+        // calm market doesn't increase limit
+        assert_eq!(risk_manager.check(&header, &context(dec!(0.001))), Ok(()));
+        assert_eq!(risk_manager.check(&header, &context(dec!(0.01))), Ok(()));
+        assert_eq!(
+            risk_manager.check(&header, &context(dec!(0.02))),
+            Err(RiskViolation::Position {
+                currency_pair: currency_pair(),
+                position: dec!(3),
+                limit: dec!(2.5),
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::notional(
+        order(OrderSide::Buy, dec!(11), None),
+        dec!(-8),
+        RiskViolation::OrderNotional { notional: dec!(1100), limit: dec!(1000) }
+    )]
+    #[case::position(
+        order(OrderSide::Sell, dec!(7), Some(dec!(100))),
+        dec!(1),
+        RiskViolation::Position { currency_pair: currency_pair(), position: dec!(-6), limit: dec!(5) }
+    )]
+    #[case::price_collar(
+        order(OrderSide::Buy, dec!(1), Some(dec!(111))),
+        dec!(1),
+        RiskViolation::PriceCollar { price: dec!(111), mid_price: dec!(100), limit_percent: dec!(10) }
+    )]
+    fn order_exceeding_limit_is_rejected(
+        #[case] header: OrderHeader,
+        #[case] position: Amount,
+        #[case] expected: RiskViolation,
+    ) {
+        let result = risk_manager().check(&header, &context(position, 0));
+
+        assert_eq!(result, Err(expected));
+    }
+
+    #[test]
+    fn open_orders_limit() {
+        let result = risk_manager().check(
+            &order(OrderSide::Buy, dec!(1), Some(dec!(100))),
+            &context(dec!(0), 3),
+        );
+
+        assert_eq!(
+            result,
+            Err(RiskViolation::OpenOrders { count: 3, limit: 3 })
+        );
+    }
+
+    #[test]
+    fn reducing_position_is_allowed_above_limit() {
+        let result = risk_manager().check(
+            &order(OrderSide::Sell, dec!(2), Some(dec!(100))),
+            &context(dec!(8), 0),
+        );
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn unknown_mid_price_is_rejected() {
+        let mut context = context(dec!(0), 0);
+        context.mid_price = None;
+
+        let result = risk_manager().check(&order(OrderSide::Buy, dec!(1), None), &context);
+
+        assert_eq!(result, Err(RiskViolation::UnknownMidPrice(currency_pair())));
+    }
+
+    fn exposure_risk_manager() -> Arc<RiskManager> {
+        let market = |exchange_account_id, currency_pair, weight| ExposureMarketSettings {
+            exchange_account_id,
+            currency_pair,
+            weight,
+        };
+
+        RiskManager::new(RiskSettings {
+            exposure_groups: vec![ExposureGroupSettings {
+                name: "BTC".to_owned(),
+                markets: vec![
+                    market(ExchangeAccountId::new("Binance", 0), currency_pair(), None),
+                    market(
+                        ExchangeAccountId::new("Binance", 0),
+                        CurrencyPair::from_codes("btc".into(), "busd".into()),
+                        None,
+                    ),
+                    market(
+                        ExchangeAccountId::new("Bitmex", 0),
+                        CurrencyPair::from_codes("xbt".into(), "usd".into()),
+                        Some(dec!(0.001)),
+                    ),
+                ],
+                max_amount: dec!(5),
+                currency_code: None,
+            }],
+            ..RiskSettings::default()
+        })
+    }
+
+    fn update_exposures(risk_manager: &RiskManager, btc_usdt: Amount, xbt_usd: Amount) {
+        risk_manager.update_exposures(|exchange_account_id, pair| {
+            match exchange_account_id.exchange_id.as_str() {
+                "Binance" if pair == currency_pair() => btc_usdt,
+                "Bitmex" => xbt_usd,
+                _ => dec!(0),
+            }
+        });
+    }
+
+    #[test]
+    fn exposure_is_combined_by_group_markets() {
+        let risk_manager = exposure_risk_manager();
+        update_exposures(&risk_manager, dec!(2), dec!(2000));
+
+        assert_eq!(
+            risk_manager.exposures(),
+            HashMap::from([("BTC".to_owned(), dec!(4))])
+        );
+        assert_eq!(
+            risk_manager.check(
+                &order(OrderSide::Buy, dec!(1), Some(dec!(100))),
+                &RiskContext::default()
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            risk_manager.check(
+                &order(OrderSide::Buy, dec!(2), Some(dec!(100))),
+                &RiskContext::default()
+            ),
+            Err(RiskViolation::Exposure {
+                group: "BTC".to_owned(),
+                exposure: dec!(6),
+                limit: dec!(5),
+            })
+        );
+    }
+
+    #[test]
+    fn exposure_in_currency_requires_cross_rates() {
+        let mut settings = exposure_risk_manager().settings();
+        settings.exposure_groups[0].currency_code = Some("usdt".into());
+        let risk_manager = RiskManager::new(settings);
+
+        update_exposures(&risk_manager, dec!(2), dec!(0));
+        assert_eq!(risk_manager.exposures(), HashMap::new());
+        assert_eq!(
+            risk_manager.check(
+                &order(OrderSide::Buy, dec!(1), Some(dec!(100))),
+                &RiskContext::default()
+            ),
+            Err(RiskViolation::UnknownCrossRate {
+                group: "BTC".to_owned(),
+                currency_pair: currency_pair(),
+            })
+        );
+    }
+
+    #[test]
+    fn exceeded_exposure_blocks_only_increasing_orders() {
+        let risk_manager = exposure_risk_manager();
+        update_exposures(&risk_manager, dec!(3), dec!(-9000));
+
+        assert!(risk_manager
+            .check(
+                &order(OrderSide::Sell, dec!(1), Some(dec!(100))),
+                &RiskContext::default()
+            )
+            .is_err());
+        assert_eq!(
+            risk_manager.check(
+                &order(OrderSide::Buy, dec!(1), Some(dec!(100))),
+                &RiskContext::default()
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn unset_limits_are_not_checked() {
+        let risk_manager = RiskManager::new(RiskSettings::default());
+
+        let result = risk_manager.check(
+            &order(OrderSide::Buy, dec!(1000), None),
+            &RiskContext::default(),
+        );
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn updated_settings_are_used_for_next_checks() {
+        let risk_manager = exposure_risk_manager();
+        update_exposures(&risk_manager, dec!(4), dec!(0));
+        let header = order(OrderSide::Buy, dec!(2), Some(dec!(100)));
+        assert!(risk_manager
+            .check(&header, &RiskContext::default())
+            .is_err());
+
+        risk_manager.update_settings(RiskSettings {
+            max_open_orders_count: Some(1),
+            ..RiskSettings::default()
+        });
+
+        assert!(risk_manager.exposures().is_empty());
+        assert_eq!(risk_manager.check(&header, &RiskContext::default()), Ok(()));
+        assert_eq!(
+            risk_manager.check(&header, &context(dec!(0), 1)),
+            Err(RiskViolation::OpenOrders { count: 1, limit: 1 })
+        );
+    }
+
+    fn order_to_trade_risk_manager(clock: Arc<VirtualClock>) -> Arc<RiskManager> {
+        RiskManager::with_clock(
+            RiskSettings {
+                order_to_trade: Some(OrderToTradeSettings {
+                    max_cancel_fill_ratio: Some(dec!(3)),
+                    min_cancels_count: 4,
+                    window_secs: 60,
+                    max_request_weight_utilization: Some(dec!(0.8)),
+                }),
+                ..RiskSettings::default()
+            },
+            clock,
+        )
+    }
+
+    #[test]
+    fn orders_are_throttled_by_cancel_fill_ratio_within_window() {
+        let clock = VirtualClock::new(chrono::Utc::now());
+        let risk_manager = order_to_trade_risk_manager(clock.clone());
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        let header = order(OrderSide::Buy, dec!(1), Some(dec!(100)));
+        for _ in 0..6 {
+            risk_manager
+                .register_order_event(exchange_account_id, &OrderEventType::CancelOrderSucceeded);
+        }
+        assert_eq!(
+            risk_manager.check(&header, &RiskContext::default()),
+            Err(RiskViolation::CancelFillRatio {
+                exchange_account_id,
+                ratio: dec!(6),
+                limit: dec!(3),
+            })
+        );
+
+        clock.advance(std::time::Duration::from_secs(61));
+
+        assert_eq!(risk_manager.check(&header, &RiskContext::default()), Ok(()));
+    }
+
+    #[test]
+    fn orders_are_throttled_by_request_weight_utilization() {
+        let risk_manager = order_to_trade_risk_manager(VirtualClock::new(chrono::Utc::now()));
+        let header = order(OrderSide::Buy, dec!(1), Some(dec!(100)));
+        let context = |request_weight_utilization| RiskContext {
+            request_weight_utilization,
+            ..RiskContext::default()
+        };
+
+        assert_eq!(risk_manager.check(&header, &context(dec!(0.6))), Ok(()));
+        assert_eq!(
+            risk_manager.check(&header, &context(dec!(0.8))),
+            Err(RiskViolation::RequestWeight {
+                exchange_account_id: ExchangeAccountId::new("Binance", 0),
+                utilization: dec!(0.8),
+                limit: dec!(0.8),
+            })
+        );
+    }
+}
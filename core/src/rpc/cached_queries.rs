@@ -0,0 +1,449 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use dashmap::DashMap;
+use mmb_domain::events::ExchangeEvent;
+use mmb_domain::market::{CurrencyCode, CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::event::OrderEventType;
+use mmb_domain::order::pool::OrdersFilter;
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, ExchangeOrderId, OrderSide, OrderStatus, Price,
+};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::balance::manager::balance_manager::BalanceManager;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+
+/// Queries of control API are usually polled by dashboards every second, so results are kept for a
+/// short time to avoid contention with the trading hot path
+pub const CACHED_QUERIES_TTL: Duration = Duration::from_secs(1);
+
+/// Keeps the last calculated value for the specified time-to-live
+pub struct TtlCache<T> {
+    ttl: Duration,
+    entry: Mutex<Option<(Instant, Arc<T>)>>,
+}
+
+impl<T> TtlCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entry: Mutex::new(None),
+        }
+    }
+
+    /// Returns cached value if it is not expired, otherwise calculates and caches a new one.
+    /// Value is calculated under lock, so concurrent requests don't calculate it several times
+    pub fn get_or_update(&self, calculate: impl FnOnce() -> T) -> Arc<T> {
+        let mut entry = self.entry.lock();
+        match &*entry {
+            Some((created_at, value)) if created_at.elapsed() < self.ttl => value.clone(),
+            _ => {
+                let value = Arc::new(calculate());
+                *entry = Some((Instant::now(), value.clone()));
+                value
+            }
+        }
+    }
+
+    pub fn invalidate(&self) {
+        *self.entry.lock() = None;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenOrderInfo {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub client_order_id: ClientOrderId,
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    pub side: OrderSide,
+    pub price: Option<Price>,
+    pub amount: Amount,
+    pub filled_amount: Amount,
+    pub status: OrderStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrencyValuation {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    pub balance: Amount,
+    /// Currency in which balance is valued. `None` if there is no market to value the balance
+    pub valuation_currency_code: Option<CurrencyCode>,
+    pub price: Option<Price>,
+    pub value: Option<Decimal>,
+}
+
+/// Signed net position of market, positive for long one
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionInfo {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub position: Amount,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PortfolioValuation {
+    pub balances: Vec<CurrencyValuation>,
+    pub total_by_currency: HashMap<CurrencyCode, Decimal>,
+}
+
+/// Cached expensive queries of control API.
+/// Caches are invalidated by relevant exchange events and expire after `CACHED_QUERIES_TTL`
+pub struct CachedQueries {
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    balance_manager: Arc<Mutex<BalanceManager>>,
+    open_orders: TtlCache<String>,
+    portfolio: TtlCache<String>,
+    positions: TtlCache<String>,
+    unmatched_events: TtlCache<String>,
+}
+
+impl CachedQueries {
+    pub fn new(
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        balance_manager: Arc<Mutex<BalanceManager>>,
+        ttl: Duration,
+    ) -> Arc<Self> {
+        let cached_queries = Arc::new(Self {
+            exchanges,
+            balance_manager,
+            open_orders: TtlCache::new(ttl),
+            portfolio: TtlCache::new(ttl),
+            positions: TtlCache::new(ttl),
+            unmatched_events: TtlCache::new(ttl),
+        });
+
+        spawn_future(
+            "Start cached queries invalidation",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            cached_queries.clone().start(events_receiver),
+        );
+
+        cached_queries
+    }
+
+    async fn start(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Result<()> {
+        loop {
+            match events_receiver.recv().await {
+                Ok(event) => self.handle_event(&event),
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("CachedQueries skipped {skipped} events, all caches are reset");
+                    self.open_orders.invalidate();
+                    self.portfolio.invalidate();
+                    self.positions.invalidate();
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
+    fn handle_event(&self, event: &ExchangeEvent) {
+        match event {
+            ExchangeEvent::OrderEvent(order_event) => {
+                self.open_orders.invalidate();
+
+                if let OrderEventType::OrderFilled { .. } | OrderEventType::OrderCompleted { .. } =
+                    order_event.event_type
+                {
+                    self.portfolio.invalidate();
+                    self.positions.invalidate();
+                }
+            }
+            ExchangeEvent::BalanceUpdate(_) => self.portfolio.invalidate(),
+            ExchangeEvent::OrderBookEvent(_)
+            | ExchangeEvent::LiquidationPrice(_)
+            | ExchangeEvent::Trades(_)
+            | ExchangeEvent::TradingHalted(_)
+            | ExchangeEvent::OrderGroupEvent(_)
+            | ExchangeEvent::BasketEvent(_)
+            | ExchangeEvent::Connectivity(_)
+            | ExchangeEvent::StreamStale(_)
+            | ExchangeEvent::SymbolStatus(_)
+            | ExchangeEvent::MarketDataSuspect(_) => {}
+        }
+    }
+
+    /// Serialized list of not finished orders over all exchange accounts
+    pub fn open_orders(&self) -> serde_json::Result<Arc<String>> {
+        try_get_or_update(&self.open_orders, || {
+            serde_json::to_string(&self.collect_open_orders())
+        })
+    }
+
+    /// Serialized exchange balances valued by current order book tops
+    pub fn portfolio(&self) -> serde_json::Result<Arc<String>> {
+        try_get_or_update(&self.portfolio, || {
+            serde_json::to_string(&self.calculate_portfolio())
+        })
+    }
+
+    /// Serialized not zero positions of all markets of exchange accounts
+    pub fn positions(&self) -> serde_json::Result<Arc<String>> {
+        try_get_or_update(&self.positions, || {
+            serde_json::to_string(&self.collect_positions())
+        })
+    }
+
+    /// Serialized order events which aren't matched to known orders over all exchange accounts.
+    /// Cache expires by TTL only
+    pub fn unmatched_events(&self) -> serde_json::Result<Arc<String>> {
+        try_get_or_update(&self.unmatched_events, || {
+            let unmatched_events = self
+                .exchanges
+                .iter()
+                .flat_map(|exchange| exchange.unmatched_events())
+                .collect::<Vec<_>>();
+            serde_json::to_string(&unmatched_events)
+        })
+    }
+
+    fn collect_open_orders(&self) -> Vec<OpenOrderInfo> {
+        self.exchanges
+            .iter()
+            .flat_map(|exchange| {
+                exchange
+                    .orders
+                    .select_not_finished(&OrdersFilter::default(), |order| OpenOrderInfo {
+                        exchange_account_id: order.header.exchange_account_id,
+                        currency_pair: order.header.currency_pair,
+                        client_order_id: order.header.client_order_id.clone(),
+                        exchange_order_id: order.exchange_order_id(),
+                        side: order.header.side,
+                        price: order.header.source_price,
+                        amount: order.header.amount,
+                        filled_amount: order.filled_amount(),
+                        status: order.status(),
+                    })
+            })
+            .collect()
+    }
+
+    fn collect_positions(&self) -> Vec<PositionInfo> {
+        let markets = self
+            .exchanges
+            .iter()
+            .flat_map(|exchange| {
+                let exchange_account_id = exchange.exchange_account_id;
+                exchange
+                    .symbols
+                    .iter()
+                    .map(|symbol| (exchange_account_id, symbol.currency_pair()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let balance_manager = self.balance_manager.lock();
+        markets
+            .into_iter()
+            .filter_map(|(exchange_account_id, currency_pair)| {
+                let position = balance_manager.net_position(exchange_account_id, currency_pair);
+                (!position.is_zero()).then_some(PositionInfo {
+                    exchange_account_id,
+                    currency_pair,
+                    position,
+                })
+            })
+            .collect()
+    }
+
+    fn calculate_portfolio(&self) -> PortfolioValuation {
+        let balances_by_exchange_id = self
+            .balance_manager
+            .lock()
+            .get_balances()
+            .balances_by_exchange_id
+            .unwrap_or_default();
+
+        let mut portfolio = PortfolioValuation::default();
+        for (exchange_account_id, balances) in balances_by_exchange_id {
+            let exchange = self.exchanges.get(&exchange_account_id);
+
+            for (currency_code, balance) in balances {
+                let valuation = exchange
+                    .as_ref()
+                    .and_then(|exchange| value_currency(exchange, currency_code));
+
+                let (valuation_currency_code, price) = valuation.unzip();
+                let value = price.map(|price| balance * price);
+                if let (Some(code), Some(value)) = (valuation_currency_code, value) {
+                    *portfolio.total_by_currency.entry(code).or_default() += value;
+                }
+
+                portfolio.balances.push(CurrencyValuation {
+                    exchange_account_id,
+                    currency_code,
+                    balance,
+                    valuation_currency_code,
+                    price,
+                    value,
+                });
+            }
+        }
+
+        portfolio
+    }
+}
+
+fn try_get_or_update<E>(
+    cache: &TtlCache<String>,
+    calculate: impl FnOnce() -> Result<String, E>,
+) -> Result<Arc<String>, E> {
+    let mut error = None;
+    let value = cache.get_or_update(|| {
+        calculate().unwrap_or_else(|err| {
+            error = Some(err);
+            String::new()
+        })
+    });
+
+    match error {
+        None => Ok(value),
+        Some(err) => {
+            cache.invalidate();
+            Err(err)
+        }
+    }
+}
+
+/// Returns valuation currency and price of the specified currency in it.
+/// Quote currencies are valued in itself, other ones by mid price of any market where they are base
+fn value_currency(
+    exchange: &Exchange,
+    currency_code: CurrencyCode,
+) -> Option<(CurrencyCode, Price)> {
+    let is_quote_currency = exchange
+        .symbols
+        .iter()
+        .any(|symbol| symbol.quote_currency_code == currency_code);
+    if is_quote_currency {
+        return Some((currency_code, dec!(1)));
+    }
+
+    exchange
+        .symbols
+        .iter()
+        .filter(|symbol| symbol.base_currency_code == currency_code)
+        .find_map(|symbol| {
+            let top = exchange.order_book_top.get(&symbol.currency_pair())?;
+            let mid_price = match (&top.ask, &top.bid) {
+                (Some(ask), Some(bid)) => (ask.price + bid.price) / dec!(2),
+                (Some(level), None) | (None, Some(level)) => level.price,
+                (None, None) => return None,
+            };
+            Some((symbol.quote_currency_code, mid_price))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+    use crate::exchanges::general::test_helper::get_test_exchange_by_currency_codes;
+    use crate::infrastructure::init_lifetime_manager;
+    use crate::misc::time;
+    use mmb_domain::events::ExchangeBalancesAndPositions;
+    use mmb_domain::position::DerivativePosition;
+    use mmb_utils::hashmap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn return_cached_value_until_ttl_expired() {
+        let calculations = AtomicUsize::new(0);
+        let calculate = || calculations.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let cache = TtlCache::new(Duration::from_secs(60));
+        assert_eq!(*cache.get_or_update(calculate), 1);
+        assert_eq!(*cache.get_or_update(calculate), 1);
+
+        let expired_cache = TtlCache::new(Duration::ZERO);
+        assert_eq!(*expired_cache.get_or_update(calculate), 2);
+        assert_eq!(*expired_cache.get_or_update(calculate), 3);
+    }
+
+    #[test]
+    fn recalculate_after_invalidation() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        assert_eq!(*cache.get_or_update(|| 1), 1);
+
+        cache.invalidate();
+
+        assert_eq!(*cache.get_or_update(|| 2), 2);
+    }
+
+    #[test]
+    fn do_not_cache_failed_calculation() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+
+        let result = try_get_or_update(&cache, || Err("failed"));
+        assert_eq!(result, Err("failed"));
+
+        let result = try_get_or_update::<&str>(&cache, || Ok("value".to_owned()));
+        assert_eq!(result.as_deref().map(String::as_str), Ok("value"));
+    }
+
+    #[tokio::test]
+    async fn collect_not_zero_positions_of_all_markets() {
+        let _ = init_lifetime_manager();
+        let (_time_manager_mock, _time_manager_locker) = time::tests::init_mock(Default::default());
+        let (btc_exchange, _btc_rx) = get_test_exchange_by_currency_codes(true, "BTC", "USD");
+        let (eth_exchange, _eth_rx) = get_test_exchange_by_currency_codes(true, "ETH", "USD");
+        let exchange_account_id = btc_exchange.exchange_account_id;
+        let btc_usd = CurrencyPair::from_codes("BTC".into(), "USD".into());
+        let eth_usd = CurrencyPair::from_codes("ETH".into(), "USD".into());
+        // both markets are on the same exchange account
+        let eth_symbol = eth_exchange.symbols.get(&eth_usd).expect("in test").clone();
+        btc_exchange.symbols.insert(eth_usd, eth_symbol);
+        btc_exchange
+            .leverage_by_currency_pair
+            .insert(eth_usd, dec!(1));
+
+        let exchanges = DashMap::new();
+        exchanges.insert(exchange_account_id, btc_exchange.clone());
+        let converter =
+            CurrencyPairToSymbolConverter::new(hashmap![exchange_account_id => btc_exchange]);
+        let balance_manager = BalanceManager::new(converter, None);
+        balance_manager
+            .lock()
+            .update_exchange_balance(
+                exchange_account_id,
+                &ExchangeBalancesAndPositions {
+                    balances: Vec::new(),
+                    positions: Some(vec![
+                        DerivativePosition::new(btc_usd, dec!(-2), dec!(0), dec!(0), dec!(1)),
+                        DerivativePosition::new(eth_usd, dec!(0), dec!(0), dec!(0), dec!(1)),
+                    ]),
+                },
+            )
+            .expect("in test");
+
+        let (_, events_receiver) = broadcast::channel(10);
+        let cached_queries = CachedQueries::new(
+            events_receiver,
+            exchanges,
+            balance_manager,
+            CACHED_QUERIES_TTL,
+        );
+
+        let positions = cached_queries.collect_positions();
+
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].exchange_account_id, exchange_account_id);
+        assert_eq!(positions[0].currency_pair, btc_usd);
+        // short position is negative
+        assert_eq!(positions[0].position, dec!(-2));
+    }
+}
@@ -11,6 +11,7 @@ use tokio::sync::{mpsc, oneshot};
 
 use crate::{
     config::{save_settings, CONFIG_PATH, CREDENTIALS_PATH},
+    health::HealthReport,
     infrastructure::spawn_future_ok,
     rpc::core_api::FAILED_TO_SEND_STOP_NOTIFICATION,
 };
@@ -27,6 +28,13 @@ pub(super) fn set_config(settings: String) -> Result<()> {
     Ok(())
 }
 
+pub(super) fn health_report_json(report: HealthReport) -> Result<String> {
+    report.to_json().map_err(|err| {
+        log::warn!("Failed to serialize health report: {err:?}");
+        server_side_error(ErrorCode::FailedToSerializeResponse)
+    })
+}
+
 /// Send signal to stop TradingEngine
 pub(super) fn send_stop(
     stopper: Arc<Mutex<Option<mpsc::Sender<ActionAfterGracefulShutdown>>>>,
@@ -1,11 +1,23 @@
 use anyhow::Result;
+use dashmap::DashMap;
+use mmb_domain::market::ExchangeAccountId;
 use parking_lot::Mutex;
 use tokio::sync::{mpsc, oneshot};
 
+use crate::exchanges::general::exchange::Exchange;
+use crate::health::HealthMonitor;
 use crate::lifecycle::app_lifetime_manager::{ActionAfterGracefulShutdown, AppLifetimeManager};
+use crate::lifecycle::cold_start::ColdStartGuard;
+use crate::rebalancing::InventoryRebalancer;
+use crate::risk::loss_limit::LossLimitGuard;
+use crate::services::signals::SignalService;
+use crate::strategy_registry::StrategyRegistry;
 use std::sync::Arc;
 
-use crate::{lifecycle::trading_engine::Service, statistic_service::StatisticService};
+use crate::{
+    lifecycle::trading_engine::Service, rpc::cached_queries::CachedQueries,
+    statistic_service::StatisticService,
+};
 
 use super::{
     common::{
@@ -23,10 +35,19 @@ pub(crate) struct CoreApi {
 }
 
 impl CoreApi {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn create_and_start(
         lifetime_manager: Arc<AppLifetimeManager>,
         engine_settings: String,
         statistics: Arc<StatisticService>,
+        cached_queries: Arc<CachedQueries>,
+        cold_start_guard: Arc<ColdStartGuard>,
+        loss_limit_guard: Arc<LossLimitGuard>,
+        rebalancer: Option<Arc<InventoryRebalancer>>,
+        strategy_registry: Arc<StrategyRegistry>,
+        signal_service: Arc<SignalService>,
+        health_monitor: Arc<HealthMonitor>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
     ) -> Result<Arc<Self>> {
         let (server_stopper_tx, server_stopper_rx) =
             mpsc::channel::<ActionAfterGracefulShutdown>(10);
@@ -38,6 +59,15 @@ impl CoreApi {
         } = crate_server_and_channels(RpcImpl::new(
             server_stopper_tx.clone(),
             statistics,
+            cached_queries,
+            cold_start_guard,
+            loss_limit_guard,
+            rebalancer,
+            strategy_registry,
+            signal_service,
+            health_monitor,
+            exchanges,
+            lifetime_manager.clone(),
             engine_settings,
         ));
 
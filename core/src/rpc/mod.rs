@@ -1,3 +1,4 @@
+pub mod cached_queries;
 pub mod common;
 pub mod config_waiter;
 pub mod core_api;
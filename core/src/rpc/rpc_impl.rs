@@ -1,34 +1,78 @@
+use dashmap::DashMap;
 use jsonrpc_core::Result;
+use mmb_domain::market::ExchangeAccountId;
+use mmb_domain::order::snapshot::ClientOrderId;
 use mmb_rpc::rest_api::server_side_error;
 use mmb_rpc::rest_api::MmbRpc;
+use mmb_utils::infrastructure::SpawnFutureFlags;
 use parking_lot::Mutex;
 use tokio::sync::mpsc;
 
 use std::sync::Arc;
 
-use crate::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
+use crate::exchanges::general::exchange::Exchange;
+use crate::health::HealthMonitor;
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::app_lifetime_manager::{ActionAfterGracefulShutdown, AppLifetimeManager};
+use crate::lifecycle::cold_start::ColdStartGuard;
+use crate::rebalancing::InventoryRebalancer;
+use crate::risk::loss_limit::LossLimitGuard;
+use crate::rpc::cached_queries::CachedQueries;
+use crate::services::signals::SignalService;
 use crate::statistic_service::StatisticService;
+use crate::strategy_registry::StrategyRegistry;
 use mmb_rpc::rest_api::ErrorCode;
 
+use super::common::health_report_json;
 use super::common::send_restart;
 use super::common::send_stop;
 use super::common::set_config;
 
+static REBALANCING_IS_NOT_CONFIGURED: &str = "Inventory rebalancing isn't configured";
+
 pub struct RpcImpl {
     server_stopper_tx: Arc<Mutex<Option<mpsc::Sender<ActionAfterGracefulShutdown>>>>,
     statistics: Arc<StatisticService>,
+    cached_queries: Arc<CachedQueries>,
+    cold_start_guard: Arc<ColdStartGuard>,
+    loss_limit_guard: Arc<LossLimitGuard>,
+    rebalancer: Option<Arc<InventoryRebalancer>>,
+    strategy_registry: Arc<StrategyRegistry>,
+    signal_service: Arc<SignalService>,
+    health_monitor: Arc<HealthMonitor>,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    lifetime_manager: Arc<AppLifetimeManager>,
     engine_settings: String,
 }
 
 impl RpcImpl {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         server_stopper_tx: Arc<Mutex<Option<mpsc::Sender<ActionAfterGracefulShutdown>>>>,
         statistics: Arc<StatisticService>,
+        cached_queries: Arc<CachedQueries>,
+        cold_start_guard: Arc<ColdStartGuard>,
+        loss_limit_guard: Arc<LossLimitGuard>,
+        rebalancer: Option<Arc<InventoryRebalancer>>,
+        strategy_registry: Arc<StrategyRegistry>,
+        signal_service: Arc<SignalService>,
+        health_monitor: Arc<HealthMonitor>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        lifetime_manager: Arc<AppLifetimeManager>,
         engine_settings: String,
     ) -> Self {
         Self {
             server_stopper_tx,
             statistics,
+            cached_queries,
+            cold_start_guard,
+            loss_limit_guard,
+            rebalancer,
+            strategy_registry,
+            signal_service,
+            health_monitor,
+            exchanges,
+            lifetime_manager,
             engine_settings,
         }
     }
@@ -39,6 +83,14 @@ impl MmbRpc for RpcImpl {
         Ok("Engine is working".into())
     }
 
+    fn health_live(&self) -> Result<String> {
+        health_report_json(self.health_monitor.liveness())
+    }
+
+    fn health_ready(&self) -> Result<String> {
+        health_report_json(self.health_monitor.readiness())
+    }
+
     fn stop(&self) -> Result<String> {
         send_stop(self.server_stopper_tx.clone())
     }
@@ -54,6 +106,10 @@ impl MmbRpc for RpcImpl {
     }
 
     fn stats(&self) -> Result<String> {
+        self.statistics.update_active_trading_time();
+        self.statistics.update_request_weight();
+        self.statistics.update_runtime_utilization();
+
         let json_statistic = serde_json::to_string(&self.statistics.statistic_service_state)
             .map_err(|err| {
                 log::warn!(
@@ -66,4 +122,314 @@ impl MmbRpc for RpcImpl {
 
         Ok(json_statistic)
     }
+
+    fn open_orders(&self) -> Result<String> {
+        let open_orders = self.cached_queries.open_orders().map_err(|err| {
+            log::warn!("Failed to serialize open orders: {err}");
+            server_side_error(ErrorCode::FailedToSerializeResponse)
+        })?;
+
+        Ok(open_orders.to_string())
+    }
+
+    fn portfolio(&self) -> Result<String> {
+        let portfolio = self.cached_queries.portfolio().map_err(|err| {
+            log::warn!("Failed to serialize portfolio: {err}");
+            server_side_error(ErrorCode::FailedToSerializeResponse)
+        })?;
+
+        Ok(portfolio.to_string())
+    }
+
+    fn positions(&self) -> Result<String> {
+        let positions = self.cached_queries.positions().map_err(|err| {
+            log::warn!("Failed to serialize positions: {err}");
+            server_side_error(ErrorCode::FailedToSerializeResponse)
+        })?;
+
+        Ok(positions.to_string())
+    }
+
+    fn cancel_order(&self, client_order_id: String) -> Result<String> {
+        let client_order_id = ClientOrderId::from(client_order_id.as_str());
+        let found = self.exchanges.iter().find_map(|exchange| {
+            let order = exchange.orders.not_finished.get(&client_order_id)?.clone();
+            Some((exchange.clone(), order))
+        });
+        let (exchange, order) = match found {
+            Some(found) => found,
+            None => return Ok(format!("Order {client_order_id} isn't open")),
+        };
+
+        let cancellation_token = self.lifetime_manager.stop_token();
+        spawn_future(
+            &format!("Cancel order {client_order_id} requested via control panel"),
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            async move {
+                // order can be still in creation, so cancellation waits for it like the engine does
+                match exchange
+                    .wait_cancel_order(order.clone(), None, true, cancellation_token)
+                    .await
+                {
+                    Ok(()) => log::info!(
+                        "Cancellation of order {} requested via control panel finished with status {:?}",
+                        order.client_order_id(),
+                        order.status()
+                    ),
+                    Err(err) => log::error!(
+                        "Failed to cancel order {} requested via control panel: {err:?}",
+                        order.client_order_id()
+                    ),
+                }
+                Ok(())
+            },
+        );
+
+        Ok(format!(
+            "Cancellation of order {client_order_id} is requested"
+        ))
+    }
+
+    fn unmatched_events(&self) -> Result<String> {
+        let unmatched_events = self.cached_queries.unmatched_events().map_err(|err| {
+            log::warn!("Failed to serialize unmatched events: {err}");
+            server_side_error(ErrorCode::FailedToSerializeResponse)
+        })?;
+
+        Ok(unmatched_events.to_string())
+    }
+
+    fn cold_start(&self) -> Result<String> {
+        serde_json::to_string(&self.cold_start_guard.violations()).map_err(|err| {
+            log::warn!("Failed to serialize cold start violations: {err}");
+            server_side_error(ErrorCode::FailedToSerializeResponse)
+        })
+    }
+
+    fn confirm_cold_start(&self) -> Result<String> {
+        let confirmed = self.cold_start_guard.confirm();
+        match confirmed.is_empty() {
+            true => Ok("Nothing to confirm".into()),
+            false => Ok(format!(
+                "Confirmed {} cold start violations. Trading is resumed",
+                confirmed.len()
+            )),
+        }
+    }
+
+    fn loss_limit(&self) -> Result<String> {
+        serde_json::to_string(&self.loss_limit_guard.breaches()).map_err(|err| {
+            log::warn!("Failed to serialize loss limit breaches: {err}");
+            server_side_error(ErrorCode::FailedToSerializeResponse)
+        })
+    }
+
+    fn acknowledge_loss_limit(&self) -> Result<String> {
+        let acknowledged = self.loss_limit_guard.acknowledge();
+        match acknowledged.is_empty() {
+            true => Ok("Nothing to acknowledge".into()),
+            false => Ok(format!(
+                "Acknowledged {} loss limit breaches. Trading is resumed",
+                acknowledged.len()
+            )),
+        }
+    }
+
+    fn rebalancing(&self) -> Result<String> {
+        let decisions = self
+            .rebalancer
+            .as_ref()
+            .map(|x| x.decisions())
+            .unwrap_or_default();
+        serde_json::to_string(&decisions).map_err(|err| {
+            log::warn!("Failed to serialize rebalancing decisions: {err}");
+            server_side_error(ErrorCode::FailedToSerializeResponse)
+        })
+    }
+
+    fn approve_rebalancing(&self, decision_id: u64) -> Result<String> {
+        let rebalancer = match &self.rebalancer {
+            Some(rebalancer) => rebalancer,
+            None => return Ok(REBALANCING_IS_NOT_CONFIGURED.into()),
+        };
+
+        match rebalancer.approve(decision_id.into(), self.lifetime_manager.stop_token()) {
+            Ok(()) => Ok(format!(
+                "Rebalancing decision {decision_id} is approved and being executed"
+            )),
+            Err(err) => Ok(format!("{err:#}")),
+        }
+    }
+
+    fn reject_rebalancing(&self, decision_id: u64) -> Result<String> {
+        let rebalancer = match &self.rebalancer {
+            Some(rebalancer) => rebalancer,
+            None => return Ok(REBALANCING_IS_NOT_CONFIGURED.into()),
+        };
+
+        match rebalancer.reject(decision_id.into()) {
+            Ok(()) => Ok(format!("Rebalancing decision {decision_id} is rejected")),
+            Err(err) => Ok(format!("{err:#}")),
+        }
+    }
+
+    fn strategies(&self) -> Result<String> {
+        self.strategy_registry.statuses().map_err(|err| {
+            log::warn!("Failed to serialize strategies: {err:?}");
+            server_side_error(ErrorCode::FailedToSerializeResponse)
+        })
+    }
+
+    fn start_strategy(&self, name: String) -> Result<String> {
+        match self.strategy_registry.start(&name) {
+            Ok(()) => Ok(format!("Strategy '{name}' is started")),
+            Err(err) => Ok(format!("{err:#}")),
+        }
+    }
+
+    fn stop_strategy(&self, name: String) -> Result<String> {
+        match self.strategy_registry.stop(&name) {
+            Ok(()) => Ok(format!("Strategy '{name}' is being stopped")),
+            Err(err) => Ok(format!("{err:#}")),
+        }
+    }
+
+    fn pause_strategy(&self, name: String) -> Result<String> {
+        match self.strategy_registry.pause(&name) {
+            Ok(()) => Ok(format!("Strategy '{name}' is paused")),
+            Err(err) => Ok(format!("{err:#}")),
+        }
+    }
+
+    fn resume_strategy(&self, name: String) -> Result<String> {
+        match self.strategy_registry.start(&name) {
+            Ok(()) => Ok(format!("Strategy '{name}' is resumed")),
+            Err(err) => Ok(format!("{err:#}")),
+        }
+    }
+
+    fn signals(&self) -> Result<String> {
+        self.signal_service.signals_json().map_err(|err| {
+            log::warn!("Failed to serialize signals: {err:?}");
+            server_side_error(ErrorCode::FailedToSerializeResponse)
+        })
+    }
+
+    fn push_signals(&self, signals: String) -> Result<String> {
+        match self.signal_service.push_json(&signals) {
+            Ok(count) => Ok(format!("{count} signals are pushed")),
+            Err(err) => Ok(format!("{err:#}")),
+        }
+    }
+
+    fn kill_switch(&self, flatten_positions: bool) -> Result<String> {
+        match self
+            .lifetime_manager
+            .spawn_kill_switch("requested via control panel", flatten_positions)
+        {
+            Some(_) => Ok("Kill switch is triggered. Trading is halted until restart".into()),
+            None => Err(server_side_error(ErrorCode::UnableToSendSignal)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::manager::balance_manager::BalanceManager;
+    use crate::exchanges::exchange_blocker::ExchangeBlocker;
+    use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+    use crate::exchanges::general::test_helper::{
+        add_created_order, get_test_exchange, test_client,
+    };
+    use crate::infrastructure::init_lifetime_manager;
+    use crate::misc::clock::SystemClock;
+    use crate::misc::trading_calendar::TradingCalendar;
+    use mmb_domain::order::snapshot::{ExchangeOrderId, OrderStatus};
+    use mmb_utils::hashmap;
+    use rust_decimal_macros::dec;
+    use std::time::Duration;
+    use tokio::sync::broadcast;
+
+    fn rpc(exchange: &Arc<Exchange>, lifetime_manager: Arc<AppLifetimeManager>) -> RpcImpl {
+        let exchange_account_id = exchange.exchange_account_id;
+        let exchanges = DashMap::new();
+        exchanges.insert(exchange_account_id, exchange.clone());
+        let exchange_blocker = ExchangeBlocker::new(vec![exchange_account_id]);
+        let converter =
+            CurrencyPairToSymbolConverter::new(hashmap![exchange_account_id => exchange.clone()]);
+        let (_, events_receiver) = broadcast::channel(10);
+        let clock = SystemClock::shared();
+
+        RpcImpl::new(
+            Default::default(),
+            StatisticService::new(TradingCalendar::new(None), clock.clone()),
+            CachedQueries::new(
+                events_receiver,
+                exchanges.clone(),
+                BalanceManager::new(converter, None),
+                Duration::from_secs(1),
+            ),
+            ColdStartGuard::new(exchange_blocker.clone()),
+            LossLimitGuard::new(exchange_blocker),
+            None,
+            Default::default(),
+            SignalService::new(Default::default(), clock.clone()),
+            HealthMonitor::new(
+                Default::default(),
+                exchanges.clone(),
+                exchange.event_recorder.clone(),
+                clock,
+            ),
+            exchanges,
+            lifetime_manager,
+            String::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn cancel_open_order() {
+        let lifetime_manager = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange(false);
+        let exchange_order_id = ExchangeOrderId::from("opened_order");
+        let order = add_created_order(&exchange, &exchange_order_id, dec!(0.2), dec!(1));
+        let rpc = rpc(&exchange, lifetime_manager);
+
+        let response = rpc
+            .cancel_order(order.client_order_id().to_string())
+            .expect("in test");
+        assert_eq!(
+            response,
+            format!(
+                "Cancellation of order {} is requested",
+                order.client_order_id()
+            )
+        );
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !order.is_finished() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("order should be canceled");
+
+        assert_eq!(order.status(), OrderStatus::Canceled);
+        assert_eq!(
+            *test_client(&exchange).cancel_requests.lock(),
+            vec![exchange_order_id]
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_order_which_is_not_open() {
+        let lifetime_manager = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange(false);
+        let rpc = rpc(&exchange, lifetime_manager);
+
+        let response = rpc.cancel_order("unknown".to_owned()).expect("in test");
+
+        assert_eq!(response, "Order unknown isn't open");
+        assert!(test_client(&exchange).cancel_requests.lock().is_empty());
+    }
 }
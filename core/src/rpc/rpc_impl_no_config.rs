@@ -6,8 +6,10 @@ use tokio::sync::mpsc;
 
 use std::sync::Arc;
 
+use crate::health::{ComponentHealth, HealthReport};
 use crate::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
 
+use super::common::health_report_json;
 use super::common::send_stop;
 use super::common::set_config;
 
@@ -35,6 +37,19 @@ impl MmbRpc for RpcImplNoConfig {
         Ok(CONFIG_IS_NOT_SET.into())
     }
 
+    /// Engine is alive while it waits for config
+    fn health_live(&self) -> Result<String> {
+        health_report_json(HealthReport::new(Vec::new()))
+    }
+
+    fn health_ready(&self) -> Result<String> {
+        health_report_json(HealthReport::new(vec![ComponentHealth {
+            component: "config".to_owned(),
+            is_healthy: false,
+            details: CONFIG_IS_NOT_SET.to_owned(),
+        }]))
+    }
+
     fn stop(&self) -> Result<String> {
         send_stop(self.server_stopper_tx.clone())
     }
@@ -52,4 +67,84 @@ impl MmbRpc for RpcImplNoConfig {
     fn stats(&self) -> Result<String> {
         Ok(CONFIG_IS_NOT_SET.into())
     }
+
+    fn open_orders(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn portfolio(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn positions(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn cancel_order(&self, _client_order_id: String) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn unmatched_events(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn cold_start(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn confirm_cold_start(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn loss_limit(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn acknowledge_loss_limit(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn rebalancing(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn approve_rebalancing(&self, _decision_id: u64) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn reject_rebalancing(&self, _decision_id: u64) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn strategies(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn start_strategy(&self, _name: String) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn stop_strategy(&self, _name: String) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn pause_strategy(&self, _name: String) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn resume_strategy(&self, _name: String) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn signals(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn push_signals(&self, _signals: String) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn kill_switch(&self, _flatten_positions: bool) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
 }
@@ -0,0 +1,221 @@
+use std::cell::Cell;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use core_affinity::CoreId;
+use futures::Future;
+use mmb_utils::infrastructure::{FutureOutcome, SpawnFutureFlags};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use tokio::runtime::{Builder, Runtime};
+use tokio::task::JoinHandle;
+
+use crate::infrastructure::spawn_future;
+use crate::settings::{RuntimeSettings, RuntimesSettings};
+
+/// Work which can be isolated on dedicated runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RuntimeKind {
+    MarketData,
+    OrderManagement,
+}
+
+impl RuntimeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuntimeKind::MarketData => "market_data",
+            RuntimeKind::OrderManagement => "order_management",
+        }
+    }
+}
+
+impl Display for RuntimeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Busy time of worker threads of runtime since the previous sample
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeUtilization {
+    pub kind: RuntimeKind,
+    pub workers_count: usize,
+    /// Share of time worker threads weren't parked, from 0 to 1
+    pub utilization: Decimal,
+}
+
+struct DedicatedRuntime {
+    runtime: Runtime,
+    workers_count: usize,
+    busy_nanos: Arc<AtomicU64>,
+    /// Time and busy nanos of the previous utilization sample
+    last_sample: Mutex<(Instant, u64)>,
+}
+
+thread_local! {
+    /// Time when worker thread of dedicated runtime was unparked last time
+    static UNPARKED_AT: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+impl DedicatedRuntime {
+    fn new(kind: RuntimeKind, settings: &RuntimeSettings) -> Result<Self> {
+        let workers_count = settings.worker_threads.max(1);
+        let busy_nanos = Arc::new(AtomicU64::new(0));
+        let core_ids = settings.core_ids.clone();
+        let started_threads_count = AtomicUsize::new(0);
+
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(workers_count)
+            .thread_name(format!("{kind}_runtime"))
+            .enable_all()
+            .on_thread_start(move || {
+                let thread_number = started_threads_count.fetch_add(1, Ordering::Relaxed);
+                if !core_ids.is_empty() {
+                    let id = core_ids[thread_number % core_ids.len()];
+                    if !core_affinity::set_for_current(CoreId { id }) {
+                        log::warn!("Unable to pin thread of {kind} runtime to core {id}");
+                    }
+                }
+                UNPARKED_AT.with(|x| x.set(Some(Instant::now())));
+            })
+            .on_thread_unpark(|| UNPARKED_AT.with(|x| x.set(Some(Instant::now()))))
+            .on_thread_park({
+                let busy_nanos = busy_nanos.clone();
+                move || {
+                    if let Some(unparked_at) = UNPARKED_AT.with(|x| x.take()) {
+                        let busy = unparked_at.elapsed().as_nanos() as u64;
+                        let _ = busy_nanos.fetch_add(busy, Ordering::Relaxed);
+                    }
+                }
+            })
+            .build()
+            .with_context(|| format!("Unable to build {kind} runtime"))?;
+
+        Ok(DedicatedRuntime {
+            runtime,
+            workers_count,
+            busy_nanos,
+            last_sample: Mutex::new((Instant::now(), 0)),
+        })
+    }
+
+    fn sample_utilization(&self, kind: RuntimeKind) -> RuntimeUtilization {
+        let now = Instant::now();
+        let busy_nanos = self.busy_nanos.load(Ordering::Relaxed);
+        let (last_time, last_busy_nanos) =
+            std::mem::replace(&mut *self.last_sample.lock(), (now, busy_nanos));
+
+        let available = (now - last_time).as_secs_f64() * self.workers_count as f64;
+        let busy = Duration::from_nanos(busy_nanos - last_busy_nanos).as_secs_f64();
+        let utilization = match available > 0. {
+            true => Decimal::from_f64(busy / available).unwrap_or_default(),
+            false => Decimal::ZERO,
+        };
+
+        RuntimeUtilization {
+            kind,
+            workers_count: self.workers_count,
+            utilization: utilization.min(Decimal::ONE).round_dp(4),
+        }
+    }
+}
+
+struct DedicatedRuntimes {
+    market_data: Option<DedicatedRuntime>,
+    order_management: Option<DedicatedRuntime>,
+}
+
+impl DedicatedRuntimes {
+    fn get(&self, kind: RuntimeKind) -> Option<&DedicatedRuntime> {
+        match kind {
+            RuntimeKind::MarketData => self.market_data.as_ref(),
+            RuntimeKind::OrderManagement => self.order_management.as_ref(),
+        }
+    }
+}
+
+/// Runtimes live until the end of process, because runtime can't be dropped in async context
+static RUNTIMES: OnceCell<DedicatedRuntimes> = OnceCell::new();
+
+/// Starts dedicated runtimes. They can be started only once, so later calls are ignored
+pub fn init_runtimes(settings: &RuntimesSettings) -> Result<()> {
+    if RUNTIMES.get().is_some() {
+        log::warn!("Dedicated runtimes are already started, so new settings are ignored");
+        return Ok(());
+    }
+
+    let create = |kind, settings: &Option<RuntimeSettings>| {
+        settings
+            .as_ref()
+            .map(|x| DedicatedRuntime::new(kind, x))
+            .transpose()
+    };
+    let runtimes = DedicatedRuntimes {
+        market_data: create(RuntimeKind::MarketData, &settings.market_data)?,
+        order_management: create(RuntimeKind::OrderManagement, &settings.order_management)?,
+    };
+
+    if RUNTIMES.set(runtimes).is_err() {
+        log::warn!("Dedicated runtimes are already started, so new settings are ignored");
+    }
+    Ok(())
+}
+
+/// Same as `spawn_future` but future is run on dedicated runtime of `kind` if it's started
+pub fn spawn_future_in(
+    kind: RuntimeKind,
+    action_name: &str,
+    flags: SpawnFutureFlags,
+    action: impl Future<Output = Result<()>> + Send + 'static,
+) -> JoinHandle<FutureOutcome> {
+    match RUNTIMES.get().and_then(|x| x.get(kind)) {
+        Some(dedicated) => {
+            let _guard = dedicated.runtime.enter();
+            spawn_future(action_name, flags, action)
+        }
+        None => spawn_future(action_name, flags, action),
+    }
+}
+
+/// Utilization of started dedicated runtimes since the previous call
+pub fn runtimes_utilization() -> Vec<RuntimeUtilization> {
+    let Some(runtimes) = RUNTIMES.get() else {
+        return Vec::new();
+    };
+
+    [RuntimeKind::MarketData, RuntimeKind::OrderManagement]
+        .into_iter()
+        .filter_map(|kind| Some(runtimes.get(kind)?.sample_utilization(kind)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn busy_time_of_workers_is_measured() {
+        let settings = RuntimeSettings {
+            worker_threads: 1,
+            core_ids: Vec::new(),
+        };
+        let dedicated = DedicatedRuntime::new(RuntimeKind::MarketData, &settings).expect("in test");
+
+        dedicated.runtime.block_on(async {
+            tokio::spawn(async { std::thread::sleep(Duration::from_millis(50)) })
+                .await
+                .expect("in test");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        });
+
+        let utilization = dedicated.sample_utilization(RuntimeKind::MarketData);
+        assert_eq!(utilization.workers_count, 1);
+        assert!(utilization.utilization > Decimal::ZERO);
+        assert!(utilization.utilization < Decimal::ONE);
+    }
+}
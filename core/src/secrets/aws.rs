@@ -0,0 +1,201 @@
+use std::env;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use hyper::{Body, Method, Request};
+use mmb_domain::market::ExchangeAccountId;
+use mmb_utils::DateTime;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::secrets::{
+    create_client, send_request, CredentialsProvider, ExchangeCredentials, HttpClient,
+};
+
+const SERVICE: &str = "secretsmanager";
+const TARGET: &str = "secretsmanager.GetSecretValue";
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+
+#[derive(Deserialize)]
+struct SecretValueResponse {
+    #[serde(rename = "SecretString")]
+    secret_string: String,
+}
+
+pub(crate) struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    /// Reads credentials from standard variables of AWS SDK
+    pub(crate) fn from_env() -> Result<Self> {
+        let read = |name: &str| {
+            env::var(name).with_context(|| format!("Unable to read AWS credentials from {name}"))
+        };
+
+        Ok(AwsCredentials {
+            access_key_id: read("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: read("AWS_SECRET_ACCESS_KEY")?,
+            session_token: env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+}
+
+/// Request to AWS service before signing
+pub(crate) struct AwsRequest {
+    pub method: Method,
+    pub host: String,
+    /// URI encoded path, e.g. `/`
+    pub path: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Signs request by Signature Version 4
+pub(crate) fn sign_request(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    request: AwsRequest,
+    now: DateTime,
+) -> Result<Request<Body>> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex::encode(Sha256::digest(&request.body));
+
+    let mut headers = request.headers;
+    headers.push(("host", request.host.clone()));
+    headers.push(("x-amz-content-sha256", payload_hash.clone()));
+    headers.push(("x-amz-date", amz_date.clone()));
+    if let Some(session_token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", session_token.clone()));
+    }
+    headers.sort();
+
+    let signed_headers = headers.iter().map(|x| x.0).collect::<Vec<_>>().join(";");
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect();
+    let canonical_request = format!(
+        "{}\n{}\n\n{canonical_headers}\n{signed_headers}\n{payload_hash}",
+        request.method, request.path
+    );
+
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let signing_key = signing_key(&credentials.secret_access_key, &date, region, service);
+    let signature = hex::encode(hmac_sha256(&signing_key, &string_to_sign));
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        credentials.access_key_id
+    );
+
+    let mut builder = Request::builder()
+        .method(request.method)
+        .uri(format!("https://{}{}", request.host, request.path))
+        .header(hyper::header::AUTHORIZATION, authorization);
+    for (name, value) in headers.into_iter().filter(|x| x.0 != "host") {
+        builder = builder.header(name, value);
+    }
+    builder
+        .body(Body::from(request.body))
+        .with_context(|| format!("building {service} request"))
+}
+
+/// Reads secrets of AWS Secrets Manager by `GetSecretValue` requests signed by Signature
+/// Version 4
+pub struct AwsSecretsManagerProvider {
+    region: String,
+    secret_prefix: String,
+    credentials: AwsCredentials,
+    client: HttpClient,
+}
+
+impl AwsSecretsManagerProvider {
+    pub fn new(region: &str, secret_prefix: &str) -> Result<Self> {
+        Ok(AwsSecretsManagerProvider {
+            region: region.to_owned(),
+            secret_prefix: secret_prefix.to_owned(),
+            credentials: AwsCredentials::from_env()?,
+            client: create_client(),
+        })
+    }
+
+    fn build_request(&self, secret_id: &str, now: DateTime) -> Result<Request<Body>> {
+        let request = AwsRequest {
+            method: Method::POST,
+            host: format!("{SERVICE}.{}.amazonaws.com", self.region),
+            path: "/".to_owned(),
+            headers: vec![
+                ("content-type", CONTENT_TYPE.to_owned()),
+                ("x-amz-target", TARGET.to_owned()),
+            ],
+            body: json!({ "SecretId": secret_id }).to_string().into_bytes(),
+        };
+        sign_request(&self.credentials, &self.region, SERVICE, request, now)
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for AwsSecretsManagerProvider {
+    fn name(&self) -> &str {
+        "AWS Secrets Manager"
+    }
+
+    async fn get_credentials(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> Result<ExchangeCredentials> {
+        let secret_id = format!("{}{exchange_account_id}", self.secret_prefix);
+        let request = self.build_request(&secret_id, Utc::now())?;
+
+        let content = send_request(&self.client, self.name(), request).await?;
+        let response: SecretValueResponse = serde_json::from_slice(&content)
+            .context("Unable to parse AWS Secrets Manager response")?;
+        serde_json::from_str(&response.secret_string)
+            .with_context(|| format!("Unable to parse secret {secret_id}"))
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut hmac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts key of any size");
+    hmac.update(data.as_bytes());
+    hmac.finalize().into_bytes().to_vec()
+}
+
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let date_key = hmac_sha256(format!("AWS4{secret_access_key}").as_bytes(), date);
+    let region_key = hmac_sha256(&date_key, region);
+    let service_key = hmac_sha256(&region_key, service);
+    hmac_sha256(&service_key, "aws4_request")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signing_key_is_derived_by_sigv4() {
+        // example from AWS documentation
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, ensure, Context, Result};
+use async_trait::async_trait;
+use mmb_domain::market::ExchangeAccountId;
+use rand::RngCore;
+
+use crate::secrets::{CredentialsProvider, ExchangeCredentials};
+
+const NONCE_SIZE: usize = 12;
+const KEY_SIZE: usize = 32;
+
+/// Reads credentials file encrypted by `encrypt_credentials`. File is read on every request, so
+/// credentials can be rotated by replacing it
+pub struct EncryptedFileProvider {
+    path: PathBuf,
+    cipher: Aes256Gcm,
+}
+
+impl EncryptedFileProvider {
+    pub fn new(path: PathBuf, key_env: &str) -> Result<Self> {
+        let key = env::var(key_env)
+            .with_context(|| format!("Unable to read encryption key from variable {key_env}"))?;
+        Ok(EncryptedFileProvider {
+            path,
+            cipher: create_cipher(&key)?,
+        })
+    }
+
+    fn read_credentials(&self) -> Result<HashMap<String, ExchangeCredentials>> {
+        let content = std::fs::read(&self.path)
+            .with_context(|| format!("Unable to read file {}", self.path.display()))?;
+        let credentials = decrypt(&self.cipher, &content)?;
+        toml_edit::de::from_str(&credentials).context("Unable to parse decrypted credentials")
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for EncryptedFileProvider {
+    fn name(&self) -> &str {
+        "encrypted file"
+    }
+
+    async fn get_credentials(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> Result<ExchangeCredentials> {
+        self.read_credentials()?
+            .remove(&exchange_account_id.to_string())
+            .with_context(|| format!("Credentials of {exchange_account_id} aren't found"))
+    }
+}
+
+/// Encrypts content of credentials file by key of 64 hex characters. Result starts with random
+/// nonce and can be written to file read by `EncryptedFileProvider`
+pub fn encrypt_credentials(key: &str, credentials: &str) -> Result<Vec<u8>> {
+    let cipher = create_cipher(key)?;
+    let mut nonce = [0u8; NONCE_SIZE];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let encrypted = cipher
+        .encrypt(Nonce::from_slice(&nonce), credentials.as_bytes())
+        .map_err(|_| anyhow!("Unable to encrypt credentials"))?;
+
+    Ok([nonce.as_slice(), &encrypted].concat())
+}
+
+fn create_cipher(key: &str) -> Result<Aes256Gcm> {
+    let key = hex::decode(key.trim()).context("Encryption key should be hex string")?;
+    ensure!(
+        key.len() == KEY_SIZE,
+        "Encryption key should be {KEY_SIZE} bytes, but it's {}",
+        key.len()
+    );
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+fn decrypt(cipher: &Aes256Gcm, content: &[u8]) -> Result<String> {
+    ensure!(content.len() > NONCE_SIZE, "Encrypted file is too short");
+    let (nonce, encrypted) = content.split_at(NONCE_SIZE);
+    let decrypted = cipher
+        .decrypt(Nonce::from_slice(nonce), encrypted)
+        .map_err(|_| anyhow!("Unable to decrypt credentials, key is wrong or file is damaged"))?;
+    String::from_utf8(decrypted).context("Decrypted credentials aren't UTF-8 text")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn encrypted_credentials_are_decrypted() {
+        let credentials = r#"
+[Binance_0]
+api_key = "key"
+secret_key = "secret"
+"#;
+        let encrypted = encrypt_credentials(KEY, credentials).expect("in test");
+
+        let decrypted =
+            decrypt(&create_cipher(KEY).expect("in test"), &encrypted).expect("in test");
+
+        assert_eq!(decrypted, credentials);
+    }
+
+    #[test]
+    fn wrong_key_is_rejected() {
+        let encrypted = encrypt_credentials(KEY, "").expect("in test");
+        let wrong_key = KEY.replace("00", "ff");
+
+        let result = decrypt(&create_cipher(&wrong_key).expect("in test"), &encrypted);
+
+        assert!(result.is_err());
+    }
+}
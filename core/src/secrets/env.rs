@@ -0,0 +1,68 @@
+use std::env;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use mmb_domain::market::ExchangeAccountId;
+
+use crate::secrets::{CredentialsProvider, ExchangeCredentials};
+
+/// Reads credentials from environment variables named by exchange account id, e.g.
+/// `MMB_BINANCE_0_API_KEY`
+pub struct EnvProvider {
+    prefix: String,
+}
+
+impl EnvProvider {
+    pub fn new(prefix: String) -> Self {
+        EnvProvider { prefix }
+    }
+
+    fn variable_name(&self, exchange_account_id: ExchangeAccountId, suffix: &str) -> String {
+        let account = exchange_account_id.to_string().to_uppercase();
+        format!("{}{account}_{suffix}", self.prefix)
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for EnvProvider {
+    fn name(&self) -> &str {
+        "environment variables"
+    }
+
+    async fn get_credentials(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> Result<ExchangeCredentials> {
+        let read = |suffix: &str| {
+            let name = self.variable_name(exchange_account_id, suffix);
+            env::var(&name).with_context(|| format!("Unable to read variable {name}"))
+        };
+
+        Ok(ExchangeCredentials {
+            api_key: read("API_KEY")?,
+            secret_key: read("SECRET_KEY")?,
+            api_passphrase: read("API_PASSPHRASE").unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn credentials_are_read_by_exchange_account_id() {
+        env::set_var("MMB_TEST_BINANCE_7_API_KEY", "key");
+        env::set_var("MMB_TEST_BINANCE_7_SECRET_KEY", "secret");
+        let provider = EnvProvider::new("MMB_TEST_".to_owned());
+
+        let credentials = provider
+            .get_credentials("Binance_7".parse().expect("in test"))
+            .await
+            .expect("in test");
+
+        assert_eq!(credentials.api_key, "key");
+        assert_eq!(credentials.secret_key, "secret");
+        assert_eq!(credentials.api_passphrase, "");
+    }
+}
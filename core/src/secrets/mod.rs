@@ -0,0 +1,269 @@
+pub mod aws;
+pub mod encrypted_file;
+pub mod env;
+pub mod vault;
+
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Request};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use mmb_domain::market::ExchangeAccountId;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use parking_lot::Mutex;
+use serde::Deserialize;
+
+use crate::infrastructure::spawn_by_timer;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::secrets::aws::AwsSecretsManagerProvider;
+use crate::secrets::encrypted_file::EncryptedFileProvider;
+use crate::secrets::env::EnvProvider;
+use crate::secrets::vault::VaultProvider;
+use crate::settings::{CoreSettings, ExchangeSettings, SecretsProviderSettings, SecretsSettings};
+
+pub(crate) type HttpClient = Client<HttpsConnector<HttpConnector>>;
+
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+pub struct ExchangeCredentials {
+    pub api_key: String,
+    pub secret_key: String,
+    /// Needed only for some exchanges
+    #[serde(default)]
+    pub api_passphrase: String,
+}
+
+impl ExchangeCredentials {
+    pub fn from_settings(settings: &ExchangeSettings) -> Self {
+        ExchangeCredentials {
+            api_key: settings.api_key.clone(),
+            secret_key: settings.secret_key.clone(),
+            api_passphrase: settings.api_passphrase.clone(),
+        }
+    }
+
+    pub fn apply_to(&self, settings: &mut ExchangeSettings) {
+        settings.api_key = self.api_key.clone();
+        settings.secret_key = self.secret_key.clone();
+        settings.api_passphrase = self.api_passphrase.clone();
+    }
+}
+
+/// Secrets shouldn't get into logs
+impl std::fmt::Debug for ExchangeCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExchangeCredentials")
+            .field("api_key", &"***")
+            .field("secret_key", &"***")
+            .field("api_passphrase", &"***")
+            .finish()
+    }
+}
+
+/// Source of API keys of exchange accounts
+#[async_trait]
+pub trait CredentialsProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn get_credentials(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> Result<ExchangeCredentials>;
+}
+
+pub fn create_provider(settings: &SecretsProviderSettings) -> Result<Box<dyn CredentialsProvider>> {
+    Ok(match settings {
+        SecretsProviderSettings::Env { prefix } => Box::new(EnvProvider::new(prefix.clone())),
+        SecretsProviderSettings::EncryptedFile { path, key_env } => {
+            Box::new(EncryptedFileProvider::new(path.clone(), key_env)?)
+        }
+        SecretsProviderSettings::Vault {
+            address,
+            mount,
+            path,
+            token_env,
+        } => Box::new(VaultProvider::new(address, mount, path, token_env)?),
+        SecretsProviderSettings::AwsSecretsManager {
+            region,
+            secret_prefix,
+        } => Box::new(AwsSecretsManagerProvider::new(region, secret_prefix)?),
+    })
+}
+
+/// Replaces credentials of trading exchange accounts by ones received from provider. Watch-only
+/// accounts don't use credentials
+pub async fn resolve_credentials(
+    settings: &mut CoreSettings,
+    provider: &dyn CredentialsProvider,
+) -> Result<()> {
+    for exchange_settings in settings.exchanges.iter_mut() {
+        if exchange_settings.is_watch_only {
+            continue;
+        }
+
+        let exchange_account_id = exchange_settings.exchange_account_id;
+        let credentials = provider
+            .get_credentials(exchange_account_id)
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to get credentials of {exchange_account_id} from {}",
+                    provider.name()
+                )
+            })?;
+        if credentials.api_key.is_empty() || credentials.secret_key.is_empty() {
+            bail!(
+                "Api or secret key of {exchange_account_id} received from {} is empty",
+                provider.name()
+            );
+        }
+
+        credentials.apply_to(exchange_settings);
+    }
+
+    Ok(())
+}
+
+/// Requests credentials of exchange accounts periodically and reconnects accounts which
+/// credentials are changed, e.g. after key rotation in secrets storage
+pub struct CredentialsRotation {
+    provider: Box<dyn CredentialsProvider>,
+    engine_context: Weak<EngineContext>,
+    /// Credentials used by exchange accounts now
+    current: Mutex<Vec<(ExchangeAccountId, ExchangeCredentials)>>,
+}
+
+impl CredentialsRotation {
+    pub(crate) fn start(
+        settings: &SecretsSettings,
+        core_settings: &CoreSettings,
+        engine_context: &Arc<EngineContext>,
+    ) -> Result<Option<Arc<Self>>> {
+        let interval = match settings.rotation_check_interval_secs {
+            Some(interval) => Duration::from_secs(interval.max(1)),
+            None => return Ok(None),
+        };
+
+        let rotation = Arc::new(CredentialsRotation {
+            provider: create_provider(&settings.provider)?,
+            engine_context: Arc::downgrade(engine_context),
+            current: Mutex::new(
+                core_settings
+                    .exchanges
+                    .iter()
+                    .filter(|x| !x.is_watch_only)
+                    .map(|x| (x.exchange_account_id, ExchangeCredentials::from_settings(x)))
+                    .collect(),
+            ),
+        });
+
+        spawn_by_timer(
+            "Check rotation of credentials",
+            interval,
+            interval,
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            {
+                let rotation = rotation.clone();
+                move || {
+                    let rotation = rotation.clone();
+                    async move { rotation.check().await }
+                }
+            },
+        );
+
+        log::info!(
+            "Rotation of credentials from {} is checked every {interval:?}",
+            rotation.provider.name()
+        );
+        Ok(Some(rotation))
+    }
+
+    async fn check(&self) {
+        let accounts = self.current.lock().clone();
+        for (exchange_account_id, current) in accounts {
+            let credentials = match self.provider.get_credentials(exchange_account_id).await {
+                Ok(credentials) if credentials == current => continue,
+                Ok(credentials) if credentials.api_key.is_empty() => {
+                    log::warn!("Received api key of {exchange_account_id} is empty");
+                    continue;
+                }
+                Ok(credentials) => credentials,
+                Err(err) => {
+                    log::warn!("Unable to check credentials of {exchange_account_id}: {err:?}");
+                    continue;
+                }
+            };
+
+            match self.rotate(exchange_account_id, &credentials).await {
+                Ok(()) => {
+                    log::info!("Credentials of {exchange_account_id} are rotated");
+                    if let Some(x) = self
+                        .current
+                        .lock()
+                        .iter_mut()
+                        .find(|x| x.0 == exchange_account_id)
+                    {
+                        x.1 = credentials;
+                    }
+                }
+                Err(err) => {
+                    log::error!("Failed to rotate credentials of {exchange_account_id}: {err:?}")
+                }
+            }
+        }
+    }
+
+    async fn rotate(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        credentials: &ExchangeCredentials,
+    ) -> Result<()> {
+        let exchange = self
+            .engine_context
+            .upgrade()
+            .and_then(|x| x.exchanges.get(&exchange_account_id).map(|x| x.clone()))
+            .context("Exchange isn't found")?;
+
+        exchange
+            .exchange_client
+            .update_credentials(credentials)
+            .context("Connector rejected new credentials")?;
+        exchange.reconnect_ws().await
+    }
+}
+
+pub(crate) fn create_client() -> HttpClient {
+    let https = HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    Client::builder().build::<_, Body>(https)
+}
+
+/// Returns body of successful response. `target` names secrets storage in errors
+pub(crate) async fn send_request(
+    client: &HttpClient,
+    target: &str,
+    request: Request<Body>,
+) -> Result<Vec<u8>> {
+    let response = client
+        .request(request)
+        .await
+        .with_context(|| format!("sending {target} request"))?;
+
+    let status = response.status();
+    let content = hyper::body::to_bytes(response.into_body())
+        .await
+        .with_context(|| format!("reading {target} response"))?;
+    if !status.is_success() {
+        bail!(
+            "{target} responded with {status}: {}",
+            String::from_utf8_lossy(&content)
+        );
+    }
+
+    Ok(content.to_vec())
+}
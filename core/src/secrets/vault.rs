@@ -0,0 +1,97 @@
+use std::env;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use hyper::{Body, Method, Request};
+use mmb_domain::market::ExchangeAccountId;
+use serde::Deserialize;
+
+use crate::secrets::{
+    create_client, send_request, CredentialsProvider, ExchangeCredentials, HttpClient,
+};
+
+#[derive(Deserialize)]
+struct SecretResponse {
+    data: SecretData,
+}
+
+#[derive(Deserialize)]
+struct SecretData {
+    data: ExchangeCredentials,
+}
+
+/// Reads secrets of KV version 2 secrets engine of HashiCorp Vault
+pub struct VaultProvider {
+    address: String,
+    mount: String,
+    path: String,
+    token: String,
+    client: HttpClient,
+}
+
+impl VaultProvider {
+    pub fn new(address: &str, mount: &str, path: &str, token_env: &str) -> Result<Self> {
+        let token = env::var(token_env)
+            .with_context(|| format!("Unable to read Vault token from variable {token_env}"))?;
+        Ok(VaultProvider {
+            address: address.trim_end_matches('/').to_owned(),
+            mount: mount.trim_matches('/').to_owned(),
+            path: path.trim_matches('/').to_owned(),
+            token,
+            client: create_client(),
+        })
+    }
+
+    fn secret_uri(&self, exchange_account_id: ExchangeAccountId) -> String {
+        format!(
+            "{}/v1/{}/data/{}/{exchange_account_id}",
+            self.address, self.mount, self.path
+        )
+    }
+}
+
+#[async_trait]
+impl CredentialsProvider for VaultProvider {
+    fn name(&self) -> &str {
+        "Vault"
+    }
+
+    async fn get_credentials(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> Result<ExchangeCredentials> {
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(self.secret_uri(exchange_account_id))
+            .header("X-Vault-Token", &self.token)
+            .body(Body::empty())
+            .context("building Vault request")?;
+
+        let content = send_request(&self.client, self.name(), request).await?;
+        let response: SecretResponse =
+            serde_json::from_slice(&content).context("Unable to parse Vault secret")?;
+        Ok(response.data.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_is_read_from_kv_v2_path() {
+        env::set_var("MMB_TEST_VAULT_TOKEN", "token");
+        let provider = VaultProvider::new(
+            "https://vault.local:8200/",
+            "secret",
+            "/mmb/prod/",
+            "MMB_TEST_VAULT_TOKEN",
+        )
+        .expect("in test");
+
+        assert_eq!(
+            provider.secret_uri("Binance_0".parse().expect("in test")),
+            "https://vault.local:8200/v1/secret/data/mmb/prod/Binance_0"
+        );
+    }
+}
@@ -2,7 +2,7 @@ use std::hash::Hash;
 
 use mmb_domain::market::MarketId;
 use mmb_utils::impl_table_type;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 // An unique name of service, like strategy name or something else.
 impl_table_type!(ServiceName, 16, u16);
@@ -19,7 +19,7 @@ impl From<MarketId> for ServiceConfigurationKey {
 }
 
 /// Entity needed to describe a configuration of trading strategy, which helps to determine which strategy the balance change refers.
-#[derive(Hash, Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[derive(Hash, Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ConfigurationDescriptor {
     /// Trading strategy name
     pub service_name: ServiceName,
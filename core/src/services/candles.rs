@@ -0,0 +1,256 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Duration;
+use itertools::Itertools;
+use mmb_domain::events::{ExchangeEvent, Trade};
+use mmb_domain::market::MarketId;
+use mmb_domain::order::snapshot::{Amount, Price};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use tokio::sync::broadcast;
+
+use crate::infrastructure::spawn_future;
+use crate::settings::CandlesSettings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candle {
+    pub open_time: DateTime,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: Amount,
+}
+
+impl Candle {
+    fn new(open_time: DateTime, price: Price, volume: Amount) -> Self {
+        Candle {
+            open_time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume,
+        }
+    }
+
+    fn add_trade(&mut self, price: Price, volume: Amount) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += volume;
+    }
+}
+
+/// Aggregates public trades of markets into candles of fixed interval. Intervals without
+/// trades are filled by flat candles with previous close price
+pub struct CandleService {
+    interval: Duration,
+    history_size: usize,
+    candles: Mutex<HashMap<MarketId, VecDeque<Candle>>>,
+}
+
+impl CandleService {
+    pub fn new(settings: &CandlesSettings) -> Arc<Self> {
+        Arc::new(CandleService {
+            interval: Duration::seconds(settings.interval_secs.max(1) as i64),
+            history_size: settings.history_size.max(1),
+            candles: Default::default(),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, events_receiver: broadcast::Receiver<ExchangeEvent>) {
+        spawn_future(
+            "Start candle service",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            self.clone().handle_events(events_receiver),
+        );
+    }
+
+    async fn handle_events(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Result<()> {
+        loop {
+            let event = events_receiver
+                .recv()
+                .await
+                .context("Error during receiving event in CandleService")?;
+
+            if let ExchangeEvent::Trades(trades_event) = event {
+                let market_id = MarketId::new(
+                    trades_event.exchange_account_id.exchange_id,
+                    trades_event.currency_pair,
+                );
+                for trade in &trades_event.trades {
+                    self.add_trade(market_id, trade);
+                }
+            }
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn add_trade(&self, market_id: MarketId, trade: &Trade) {
+        let open_time = self.open_time(trade.transaction_time);
+
+        let mut candles = self.candles.lock();
+        let candles = candles.entry(market_id).or_default();
+        match candles.back_mut() {
+            None => candles.push_back(Candle::new(open_time, trade.price, trade.quantity)),
+            Some(last) if last.open_time == open_time => {
+                last.add_trade(trade.price, trade.quantity)
+            }
+            // trades of already closed candles are skipped
+            Some(last) if last.open_time > open_time => return,
+            Some(last) => {
+                let last = *last;
+                let mut flat_open_time = last.open_time + self.interval;
+                let mut flat_count = 0;
+                while flat_open_time < open_time && flat_count < self.history_size {
+                    candles.push_back(Candle::new(flat_open_time, last.close, Amount::ZERO));
+                    flat_open_time += self.interval;
+                    flat_count += 1;
+                }
+                candles.push_back(Candle::new(open_time, trade.price, trade.quantity));
+            }
+        }
+
+        while candles.len() > self.history_size {
+            let _ = candles.pop_front();
+        }
+    }
+
+    /// Candles of market from the oldest to the newest one. The last candle can be not closed yet
+    pub fn candles(&self, market_id: MarketId) -> Vec<Candle> {
+        self.candles
+            .lock()
+            .get(&market_id)
+            .map(|x| x.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Variance of close price changes between adjacent candles among last `candles_count`
+    /// candles in squared price units per candle interval
+    pub fn price_variance(&self, market_id: MarketId, candles_count: usize) -> Option<Decimal> {
+        let candles = self.candles.lock();
+        let candles = candles.get(&market_id)?;
+
+        let changes = candles
+            .iter()
+            .skip(candles.len().saturating_sub(candles_count))
+            .tuple_windows()
+            .map(|(previous, next)| next.close - previous.close)
+            .collect_vec();
+        if changes.len() < 2 {
+            return None;
+        }
+
+        let count = Decimal::from(changes.len());
+        let mean = changes.iter().sum::<Decimal>() / count;
+        let variance = changes
+            .iter()
+            .map(|x| (x - mean) * (x - mean))
+            .sum::<Decimal>()
+            / (count - Decimal::ONE);
+        Some(variance)
+    }
+
+    fn open_time(&self, time: DateTime) -> DateTime {
+        let interval_millis = self.interval.num_milliseconds();
+        time - Duration::milliseconds(time.timestamp_millis().rem_euclid(interval_millis))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use mmb_domain::events::TradeId;
+    use mmb_domain::market::{CurrencyPair, ExchangeId};
+    use mmb_domain::order::snapshot::OrderSide;
+    use rust_decimal_macros::dec;
+
+    fn market_id() -> MarketId {
+        MarketId::new(
+            ExchangeId::new("Binance"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    fn trade(secs: i64, price: Price) -> Trade {
+        Trade {
+            trade_id: TradeId::Number(secs as u64),
+            price,
+            quantity: dec!(1),
+            side: OrderSide::Buy,
+            transaction_time: chrono::Utc
+                .timestamp_opt(secs, 0)
+                .single()
+                .expect("valid time"),
+        }
+    }
+
+    fn service() -> Arc<CandleService> {
+        CandleService::new(&CandlesSettings {
+            interval_secs: 60,
+            history_size: 10,
+        })
+    }
+
+    #[test]
+    fn trades_are_aggregated_into_candles_with_flat_gaps() {
+        let service = service();
+        for trade in [
+            trade(60, dec!(10)),
+            trade(90, dec!(12)),
+            trade(100, dec!(9)),
+            trade(200, dec!(11)),
+            // trade of closed candle
+            trade(110, dec!(100)),
+        ] {
+            service.add_trade(market_id(), &trade);
+        }
+
+        let candles = service.candles(market_id());
+        assert_eq!(candles.len(), 3);
+        assert_eq!(
+            (
+                candles[0].open,
+                candles[0].high,
+                candles[0].low,
+                candles[0].close
+            ),
+            (dec!(10), dec!(12), dec!(9), dec!(9))
+        );
+        assert_eq!(candles[0].volume, dec!(3));
+        assert_eq!((candles[1].open, candles[1].close), (dec!(9), dec!(9)));
+        assert_eq!(candles[1].volume, dec!(0));
+        assert_eq!(candles[2].open_time.timestamp(), 180);
+        assert_eq!(candles[2].close, dec!(11));
+    }
+
+    #[test]
+    fn price_variance_of_close_changes() {
+        let service = service();
+        assert_eq!(service.price_variance(market_id(), 10), None);
+
+        for (index, price) in [dec!(10), dec!(11), dec!(10), dec!(11), dec!(12)]
+            .into_iter()
+            .enumerate()
+        {
+            service.add_trade(market_id(), &trade(index as i64 * 60, price));
+        }
+
+        // changes 1, -1, 1, 1
+        assert_eq!(service.price_variance(market_id(), 10), Some(dec!(1)));
+        // changes 1, 1
+        assert_eq!(service.price_variance(market_id(), 3), Some(dec!(0)));
+    }
+}
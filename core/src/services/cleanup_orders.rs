@@ -1,15 +1,20 @@
 use crate::exchanges::general::exchange::Exchange;
 use crate::lifecycle::trading_engine::Service;
+use crate::settings::OrderRetentionSettings;
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use mmb_domain::market::ExchangeAccountId;
 use mmb_domain::order::pool::OrderRef;
 use std::hash::Hash;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::oneshot::Receiver;
 
 pub struct CleanupOrdersService {
     exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    finished_order_ttl: chrono::Duration,
+    interval: Duration,
+    archive: bool,
 }
 
 impl Service for CleanupOrdersService {
@@ -23,25 +28,58 @@ impl Service for CleanupOrdersService {
 }
 
 impl CleanupOrdersService {
-    pub fn new(exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>) -> Self {
-        Self { exchanges }
+    pub fn new(
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        settings: Option<&OrderRetentionSettings>,
+    ) -> Self {
+        let (finished_order_ttl_secs, interval_secs, archive) = match settings {
+            Some(settings) => (
+                settings.finished_order_ttl_secs,
+                settings.interval_secs,
+                settings.archive,
+            ),
+            None => (30 * 60, 10 * 60, false),
+        };
+
+        Self {
+            exchanges,
+            finished_order_ttl: chrono::Duration::seconds(finished_order_ttl_secs as i64),
+            interval: Duration::from_secs(interval_secs.max(1)),
+            archive,
+        }
+    }
+
+    /// Interval between evictions of outdated orders
+    pub fn interval(&self) -> Duration {
+        self.interval
     }
 
     pub async fn cleanup_outdated_orders(self: Arc<Self>) {
-        let deadline = Utc::now() - chrono::Duration::minutes(30);
+        let deadline = Utc::now() - self.finished_order_ttl;
         self.exchanges.iter().for_each(|pair| {
-            cleanup(&pair.orders.cache_by_exchange_id, deadline);
-            cleanup(&pair.orders.cache_by_client_id, deadline);
+            let _ = cleanup(&pair.orders.cache_by_exchange_id, deadline);
+            let evicted = cleanup(&pair.orders.cache_by_client_id, deadline);
+            if self.archive && !evicted.is_empty() {
+                pair.archive_orders(&evicted);
+            }
         });
     }
 }
 
-fn cleanup<T>(orders: &DashMap<T, OrderRef>, deadline: DateTime<Utc>)
+/// Removes orders finished before `deadline` and returns them
+fn cleanup<T>(orders: &DashMap<T, OrderRef>, deadline: DateTime<Utc>) -> Vec<OrderRef>
 where
     T: Eq + Hash,
 {
-    orders
-        .retain(|_, v| v.fn_ref(|x| x.props.finished_time.map(|x| x >= deadline).unwrap_or(true)));
+    let mut evicted = Vec::new();
+    orders.retain(|_, v| {
+        let is_actual = v.fn_ref(|x| x.props.finished_time.map(|x| x >= deadline).unwrap_or(true));
+        if !is_actual {
+            evicted.push(v.clone());
+        }
+        is_actual
+    });
+    evicted
 }
 
 #[cfg(test)]
@@ -75,8 +113,9 @@ mod tests {
 
         // deadline has arrived
         let deadline = now + Duration::minutes(1);
-        cleanup(&pool.cache_by_client_id, deadline);
+        let evicted = cleanup(&pool.cache_by_client_id, deadline);
         assert!(pool.cache_by_client_id.is_empty());
+        assert_eq!(evicted, vec![order_ref]);
     }
 
     #[rstest]
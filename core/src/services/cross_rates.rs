@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Duration;
+use dashmap::DashMap;
+use mmb_domain::market::{CurrencyCode, CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::snapshot::{Amount, Price};
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::misc::clock::Clock;
+use crate::settings::CrossRatesSettings;
+
+/// Conversion of one currency into another through the shortest chain of markets with fresh
+/// prices, e.g. ALT -> BTC -> USDT when there is no ALT/USDT market
+pub struct CrossRateService {
+    settings: CrossRatesSettings,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    clock: Arc<dyn Clock>,
+}
+
+/// Conversion of `from` currency into `to` currency by single market
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Leg {
+    from: CurrencyCode,
+    to: CurrencyCode,
+    rate: Price,
+}
+
+impl CrossRateService {
+    pub fn new(
+        settings: CrossRatesSettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        Arc::new(CrossRateService {
+            settings,
+            exchanges,
+            clock,
+        })
+    }
+
+    pub fn reporting_currency(&self) -> CurrencyCode {
+        self.settings.reporting_currency
+    }
+
+    /// Price of 1 unit of `from` currency in `to` currency. `None` if there is no chain of markets
+    /// with fresh prices between them
+    pub fn rate(&self, from: CurrencyCode, to: CurrencyCode) -> Option<Price> {
+        if from == to {
+            return Some(Decimal::ONE);
+        }
+
+        let legs = self.fresh_legs(self.clock.now());
+        find_rate(&legs, from, to, self.settings.max_legs)
+    }
+
+    pub fn convert(&self, amount: Amount, from: CurrencyCode, to: CurrencyCode) -> Option<Amount> {
+        self.rate(from, to).map(|rate| amount * rate)
+    }
+
+    pub fn convert_to_reporting(&self, amount: Amount, from: CurrencyCode) -> Option<Amount> {
+        self.convert(amount, from, self.settings.reporting_currency)
+    }
+
+    /// Converts amount in base currency of market into `to` currency
+    pub fn convert_base_amount(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        amount: Amount,
+        to: CurrencyCode,
+    ) -> Option<Amount> {
+        let base_currency_code = self
+            .exchanges
+            .get(&exchange_account_id)?
+            .symbols
+            .get(&currency_pair)?
+            .base_currency_code;
+        self.convert(amount, base_currency_code, to)
+    }
+
+    /// Direct and inverse conversions by markets of all exchange accounts which order books were
+    /// updated within `max_price_age_millis`
+    fn fresh_legs(&self, now: DateTime) -> Vec<Leg> {
+        let max_age = Duration::milliseconds(self.settings.max_price_age_millis as i64);
+        let mut legs = Vec::new();
+        for exchange in self.exchanges.iter() {
+            for symbol in exchange.symbols.iter() {
+                let currency_pair = symbol.currency_pair();
+                let is_fresh = exchange
+                    .order_book_update_time(currency_pair)
+                    .is_some_and(|time| time + max_age >= now);
+                if !is_fresh {
+                    continue;
+                }
+
+                let price = match exchange.mark_price(currency_pair) {
+                    Some(price) if price > Decimal::ZERO => price,
+                    _ => continue,
+                };
+                let (base, quote) = (symbol.base_currency_code, symbol.quote_currency_code);
+                legs.push(Leg {
+                    from: base,
+                    to: quote,
+                    rate: price,
+                });
+                legs.push(Leg {
+                    from: quote,
+                    to: base,
+                    rate: Decimal::ONE / price,
+                });
+            }
+        }
+        legs
+    }
+}
+
+/// Rate of the shortest chain of legs from `from` to `to` with at most `max_legs` legs
+fn find_rate(legs: &[Leg], from: CurrencyCode, to: CurrencyCode, max_legs: usize) -> Option<Price> {
+    if from == to {
+        return Some(Decimal::ONE);
+    }
+
+    let mut rates = HashMap::from([(from, Decimal::ONE)]);
+    let mut frontier = vec![from];
+    for _ in 0..max_legs {
+        let mut next_frontier = Vec::new();
+        for currency_code in frontier {
+            let rate = rates[&currency_code];
+            for leg in legs.iter().filter(|x| x.from == currency_code) {
+                if rates.contains_key(&leg.to) {
+                    continue;
+                }
+
+                let leg_rate = rate * leg.rate;
+                if leg.to == to {
+                    return Some(leg_rate);
+                }
+                let _ = rates.insert(leg.to, leg_rate);
+                next_frontier.push(leg.to);
+            }
+        }
+        frontier = next_frontier;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn legs(markets: &[(&str, &str, Price)]) -> Vec<Leg> {
+        markets
+            .iter()
+            .flat_map(|&(base, quote, price)| {
+                [
+                    Leg {
+                        from: base.into(),
+                        to: quote.into(),
+                        rate: price,
+                    },
+                    Leg {
+                        from: quote.into(),
+                        to: base.into(),
+                        rate: dec!(1) / price,
+                    },
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rate_through_chain_of_markets() {
+        let legs = legs(&[
+            ("alt", "btc", dec!(0.001)),
+            ("btc", "usdt", dec!(20000)),
+            ("eth", "btc", dec!(0.05)),
+        ]);
+
+        assert_eq!(
+            find_rate(&legs, "alt".into(), "usdt".into(), 3),
+            Some(dec!(20))
+        );
+        assert_eq!(
+            find_rate(&legs, "usdt".into(), "btc".into(), 3),
+            Some(dec!(0.00005))
+        );
+        assert_eq!(
+            find_rate(&legs, "alt".into(), "eth".into(), 3),
+            Some(dec!(0.02))
+        );
+        // alt -> btc -> usdt needs 2 legs
+        assert_eq!(find_rate(&legs, "alt".into(), "usdt".into(), 1), None);
+        assert_eq!(find_rate(&legs, "alt".into(), "bnb".into(), 3), None);
+        assert_eq!(
+            find_rate(&legs, "bnb".into(), "bnb".into(), 3),
+            Some(dec!(1))
+        );
+    }
+}
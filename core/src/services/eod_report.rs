@@ -0,0 +1,504 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use hyper::Method;
+use mmb_domain::events::ExchangeEvent;
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId, MarketAccountId};
+use mmb_domain::order::event::{OrderEvent, OrderEventType};
+use mmb_domain::order::snapshot::{Amount, ClientOrderId, OrderFillRole, OrderSide, Price};
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::alerting::daily_report::next_report_time;
+use crate::infrastructure::spawn_future;
+use crate::misc::clock::Clock;
+use crate::secrets::aws::{sign_request, AwsCredentials, AwsRequest};
+use crate::secrets::{create_client, send_request};
+use crate::settings::{EodReportSettings, ReportDestination, ReportFormat};
+
+/// Trading of trade place within report period
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TradePlaceReport {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub bought_amount: Amount,
+    pub sold_amount: Amount,
+    /// Executed volume in quote currency
+    pub volume: Decimal,
+    /// Fees by currency code
+    pub fees: BTreeMap<String, Amount>,
+    /// PnL of closed part of position valued by average open price, in quote currency
+    pub realized_pnl: Decimal,
+    pub created_orders_count: u64,
+    pub canceled_orders_count: u64,
+    /// Orders with at least one fill within period
+    pub filled_orders_count: u64,
+    /// Canceled orders per filled order. It isn't specified if there are no filled orders
+    pub cancel_fill_ratio: Option<Decimal>,
+    pub maker_fills_count: u64,
+    pub taker_fills_count: u64,
+    pub maker_amount: Amount,
+    pub taker_amount: Amount,
+}
+
+impl TradePlaceReport {
+    fn new(market_account_id: MarketAccountId) -> Self {
+        TradePlaceReport {
+            exchange_account_id: market_account_id.exchange_account_id,
+            currency_pair: market_account_id.currency_pair,
+            bought_amount: Amount::ZERO,
+            sold_amount: Amount::ZERO,
+            volume: Decimal::ZERO,
+            fees: BTreeMap::new(),
+            realized_pnl: Decimal::ZERO,
+            created_orders_count: 0,
+            canceled_orders_count: 0,
+            filled_orders_count: 0,
+            cancel_fill_ratio: None,
+            maker_fills_count: 0,
+            taker_fills_count: 0,
+            maker_amount: Amount::ZERO,
+            taker_amount: Amount::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EodReport {
+    pub period_start: DateTime,
+    pub period_end: DateTime,
+    /// Trade places with orders within period sorted by exchange account and currency pair
+    pub trade_places: Vec<TradePlaceReport>,
+    /// Fees of all trade places by currency code
+    pub fees: BTreeMap<String, Amount>,
+}
+
+impl EodReport {
+    /// Name is based on date of the last moment of report period
+    pub fn file_name(&self, format: ReportFormat) -> String {
+        let date = (self.period_end - chrono::Duration::seconds(1)).format("%Y-%m-%d");
+        match format {
+            ReportFormat::Csv => format!("eod_report_{date}.csv"),
+            ReportFormat::Json => format!("eod_report_{date}.json"),
+        }
+    }
+
+    pub fn to_format(&self, format: ReportFormat) -> Result<String> {
+        match format {
+            ReportFormat::Csv => self.to_csv(),
+            ReportFormat::Json => {
+                serde_json::to_string_pretty(self).context("Unable to serialize EOD report")
+            }
+        }
+    }
+
+    /// Row for every trade place. Fees are listed as `{currency}:{amount}` separated by `;`
+    fn to_csv(&self) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record([
+            "period_start",
+            "period_end",
+            "exchange_account_id",
+            "currency_pair",
+            "bought_amount",
+            "sold_amount",
+            "volume",
+            "fees",
+            "realized_pnl",
+            "created_orders_count",
+            "canceled_orders_count",
+            "filled_orders_count",
+            "cancel_fill_ratio",
+            "maker_fills_count",
+            "taker_fills_count",
+            "maker_amount",
+            "taker_amount",
+        ])?;
+        for x in &self.trade_places {
+            let fees = x
+                .fees
+                .iter()
+                .map(|(currency_code, amount)| format!("{currency_code}:{amount}"))
+                .collect::<Vec<_>>()
+                .join(";");
+            writer.write_record([
+                self.period_start.to_rfc3339(),
+                self.period_end.to_rfc3339(),
+                x.exchange_account_id.to_string(),
+                x.currency_pair.to_string(),
+                x.bought_amount.to_string(),
+                x.sold_amount.to_string(),
+                x.volume.to_string(),
+                fees,
+                x.realized_pnl.to_string(),
+                x.created_orders_count.to_string(),
+                x.canceled_orders_count.to_string(),
+                x.filled_orders_count.to_string(),
+                x.cancel_fill_ratio
+                    .map(|x| x.to_string())
+                    .unwrap_or_default(),
+                x.maker_fills_count.to_string(),
+                x.taker_fills_count.to_string(),
+                x.maker_amount.to_string(),
+                x.taker_amount.to_string(),
+            ])?;
+        }
+
+        let bytes = writer.into_inner().context("Unable to write CSV")?;
+        String::from_utf8(bytes).context("CSV isn't valid UTF-8")
+    }
+}
+
+/// Position of trade place valued by average open price
+#[derive(Debug, Clone, Copy, Default)]
+struct AverageCostPosition {
+    /// Positive for long position
+    amount: Amount,
+    average_price: Price,
+}
+
+impl AverageCostPosition {
+    /// Returns realized PnL of closed part of position
+    fn add_fill(&mut self, side: OrderSide, price: Price, amount: Amount) -> Decimal {
+        if amount.is_zero() {
+            return Decimal::ZERO;
+        }
+
+        let signed_amount = match side {
+            OrderSide::Buy => amount,
+            OrderSide::Sell => -amount,
+        };
+
+        if self.amount.is_zero()
+            || self.amount.is_sign_positive() == signed_amount.is_sign_positive()
+        {
+            let new_amount = self.amount + signed_amount;
+            self.average_price =
+                (self.average_price * self.amount + price * signed_amount) / new_amount;
+            self.amount = new_amount;
+            return Decimal::ZERO;
+        }
+
+        let closed_amount = self.amount.abs().min(amount);
+        let realized_pnl = match self.amount.is_sign_positive() {
+            true => (price - self.average_price) * closed_amount,
+            false => (self.average_price - price) * closed_amount,
+        };
+
+        self.amount += signed_amount;
+        if amount > closed_amount {
+            // position is reversed, the rest of fill opens new one
+            self.average_price = price;
+        } else if self.amount.is_zero() {
+            self.average_price = Decimal::ZERO;
+        }
+
+        realized_pnl
+    }
+}
+
+/// Accumulates trading of report period. Positions are kept between periods, so PnL of position
+/// opened within one period and closed within another one is realized by the latter
+#[derive(Default)]
+struct EodReportAccumulator {
+    trade_places: HashMap<MarketAccountId, TradePlaceReport>,
+    filled_orders: HashSet<ClientOrderId>,
+    positions: HashMap<MarketAccountId, AverageCostPosition>,
+}
+
+impl EodReportAccumulator {
+    fn add_order_event(&mut self, event: &OrderEvent) {
+        let market_account_id = MarketAccountId::new(
+            event.order.exchange_account_id(),
+            event.order.currency_pair(),
+        );
+        let report = self
+            .trade_places
+            .entry(market_account_id)
+            .or_insert_with(|| TradePlaceReport::new(market_account_id));
+
+        match &event.event_type {
+            OrderEventType::CreateOrderSucceeded => report.created_orders_count += 1,
+            OrderEventType::CancelOrderSucceeded => report.canceled_orders_count += 1,
+            OrderEventType::OrderFilled { cloned_order } => {
+                let fill = match cloned_order.fills.fills.last() {
+                    Some(fill) => fill,
+                    None => return,
+                };
+                let side = fill.side().unwrap_or(cloned_order.header.side);
+                let amount = fill.amount();
+
+                match side {
+                    OrderSide::Buy => report.bought_amount += amount,
+                    OrderSide::Sell => report.sold_amount += amount,
+                }
+                report.volume += fill.price() * amount;
+                *report
+                    .fees
+                    .entry(fill.commission_currency_code().to_string())
+                    .or_default() += fill.commission_amount();
+                match fill.role() {
+                    OrderFillRole::Maker => {
+                        report.maker_fills_count += 1;
+                        report.maker_amount += amount;
+                    }
+                    OrderFillRole::Taker => {
+                        report.taker_fills_count += 1;
+                        report.taker_amount += amount;
+                    }
+                }
+                report.realized_pnl += self
+                    .positions
+                    .entry(market_account_id)
+                    .or_default()
+                    .add_fill(side, fill.price(), amount);
+
+                if self
+                    .filled_orders
+                    .insert(cloned_order.header.client_order_id.clone())
+                {
+                    report.filled_orders_count += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns report of period and starts the next one
+    fn take_report(&mut self, period_start: DateTime, period_end: DateTime) -> EodReport {
+        self.filled_orders.clear();
+        let mut trade_places: Vec<_> = self
+            .trade_places
+            .drain()
+            .map(|(_, mut report)| {
+                if report.filled_orders_count > 0 {
+                    report.cancel_fill_ratio = Some(
+                        Decimal::from(report.canceled_orders_count)
+                            / Decimal::from(report.filled_orders_count),
+                    );
+                }
+                report
+            })
+            .collect();
+        trade_places.sort_by_key(|x| {
+            (
+                x.exchange_account_id.to_string(),
+                x.currency_pair.to_string(),
+            )
+        });
+
+        let mut fees = BTreeMap::new();
+        for (currency_code, amount) in trade_places.iter().flat_map(|x| &x.fees) {
+            *fees.entry(currency_code.clone()).or_default() += *amount;
+        }
+
+        EodReport {
+            period_start,
+            period_end,
+            trade_places,
+            fees,
+        }
+    }
+}
+
+/// Writes end-of-day reports of trading every day at configured time to directory or S3 bucket
+pub struct EodReportService {
+    settings: EodReportSettings,
+    accumulator: Mutex<EodReportAccumulator>,
+    clock: Arc<dyn Clock>,
+}
+
+impl EodReportService {
+    pub fn new(settings: EodReportSettings, clock: Arc<dyn Clock>) -> Arc<Self> {
+        Arc::new(EodReportService {
+            settings,
+            accumulator: Default::default(),
+            clock,
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, events_receiver: broadcast::Receiver<ExchangeEvent>) {
+        spawn_future(
+            "Accumulate EOD report",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            self.clone().handle_events(events_receiver),
+        );
+        spawn_future(
+            "Write EOD reports",
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            self.clone().write_reports(),
+        );
+    }
+
+    async fn handle_events(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+    ) -> Result<()> {
+        loop {
+            match events_receiver.recv().await {
+                Ok(ExchangeEvent::OrderEvent(event)) => {
+                    self.accumulator.lock().add_order_event(&event)
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    log::error!("EOD report skipped {skipped} events, report is incomplete")
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
+    async fn write_reports(self: Arc<Self>) -> Result<()> {
+        let mut period_start = self.clock.now();
+        loop {
+            let now = self.clock.now();
+            let report_time = next_report_time(now, self.settings.time);
+            let delay = (report_time - now).to_std().unwrap_or_default();
+            self.clock.sleep(delay).await;
+
+            let period_end = self.clock.now();
+            let report = self
+                .accumulator
+                .lock()
+                .take_report(period_start, period_end);
+            period_start = period_end;
+
+            for format in &self.settings.formats {
+                if let Err(err) = self.write_report(&report, *format).await {
+                    log::error!("Failed to write EOD report: {err:?}");
+                }
+            }
+        }
+    }
+
+    async fn write_report(&self, report: &EodReport, format: ReportFormat) -> Result<()> {
+        let file_name = report.file_name(format);
+        let content = report.to_format(format)?;
+
+        match &self.settings.destination {
+            ReportDestination::Directory { path } => {
+                fs::create_dir_all(path)
+                    .with_context(|| format!("Unable to create directory {}", path.display()))?;
+                let path = path.join(&file_name);
+                fs::write(&path, content)
+                    .with_context(|| format!("Unable to write {}", path.display()))?;
+            }
+            ReportDestination::S3 {
+                bucket,
+                region,
+                prefix,
+            } => {
+                let request = AwsRequest {
+                    method: Method::PUT,
+                    host: format!("{bucket}.s3.{region}.amazonaws.com"),
+                    path: format!("/{}", uri_encode_path(&format!("{prefix}{file_name}"))),
+                    headers: Vec::new(),
+                    body: content.into_bytes(),
+                };
+                let credentials = AwsCredentials::from_env()?;
+                let request = sign_request(&credentials, region, "s3", request, self.clock.now())?;
+                let _ = send_request(&create_client(), "S3", request).await?;
+            }
+        }
+
+        log::info!("EOD report {file_name} is written");
+        Ok(())
+    }
+}
+
+/// Encodes all characters except unreserved ones and `/` as required by Signature Version 4
+fn uri_encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|x| match x {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (x as char).to_string()
+            }
+            _ => format!("%{x:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn pnl_is_realized_by_average_open_price() {
+        let mut position = AverageCostPosition::default();
+
+        assert_eq!(
+            position.add_fill(OrderSide::Buy, dec!(100), dec!(1)),
+            dec!(0)
+        );
+        assert_eq!(
+            position.add_fill(OrderSide::Buy, dec!(110), dec!(1)),
+            dec!(0)
+        );
+        assert_eq!(position.average_price, dec!(105));
+
+        assert_eq!(
+            position.add_fill(OrderSide::Sell, dec!(120), dec!(1)),
+            dec!(15)
+        );
+        // position is reversed to short 1 at 90
+        assert_eq!(
+            position.add_fill(OrderSide::Sell, dec!(90), dec!(2)),
+            dec!(-15)
+        );
+        assert_eq!(position.amount, dec!(-1));
+        assert_eq!(position.average_price, dec!(90));
+
+        assert_eq!(
+            position.add_fill(OrderSide::Buy, dec!(80), dec!(1)),
+            dec!(10)
+        );
+        assert_eq!(position.amount, dec!(0));
+    }
+
+    #[test]
+    fn report_file_is_named_by_last_day_of_period() {
+        let time = |day, hour| chrono::Utc.ymd(2022, 11, day).and_hms(hour, 0, 0);
+        let mut trade_place = TradePlaceReport::new(MarketAccountId::new(
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        ));
+        trade_place.canceled_orders_count = 3;
+        trade_place.filled_orders_count = 2;
+        trade_place.cancel_fill_ratio = Some(dec!(1.5));
+        trade_place.fees =
+            BTreeMap::from([("bnb".to_owned(), dec!(0.1)), ("usdt".to_owned(), dec!(2))]);
+        let report = EodReport {
+            period_start: time(16, 0),
+            period_end: time(17, 0),
+            trade_places: vec![trade_place],
+            fees: BTreeMap::new(),
+        };
+
+        assert_eq!(
+            report.file_name(ReportFormat::Csv),
+            "eod_report_2022-11-16.csv"
+        );
+        let csv = report.to_format(ReportFormat::Csv).expect("in test");
+        let row = csv.lines().nth(1).expect("in test");
+        assert!(
+            row.contains(",Binance_0,btc/usdt,0,0,0,bnb:0.1;usdt:2,0,0,3,2,1.5,"),
+            "{row}"
+        );
+    }
+
+    #[test]
+    fn s3_key_is_uri_encoded() {
+        assert_eq!(
+            uri_encode_path("mmb reports/eod.csv"),
+            "mmb%20reports/eod.csv"
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use mmb_domain::events::ExchangeEvent;
+use mmb_domain::market::MarketAccountId;
+use mmb_domain::order_book::event::OrderBookEvent;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use parking_lot::Mutex;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::Notify;
+
+use crate::infrastructure::spawn_future;
+use crate::settings::{EventOverflowPolicy, EventQueueSettings};
+use crate::statistic_service::StatisticService;
+
+#[derive(Default)]
+struct QueueState {
+    events: VecDeque<ExchangeEvent>,
+    /// Latest order books of markets waiting in the queue, used only if order books are
+    /// conflated. Order book is removed on delivery, so next updates aren't merged into it again
+    order_books: HashMap<MarketAccountId, OrderBookEvent>,
+    /// Markets which order book event is already in the queue, used only if order books are
+    /// conflated
+    waiting_order_books: HashSet<MarketAccountId>,
+}
+
+/// Bounded queue of engine events of single consumer. Events are moved into the queue from
+/// engine events channel by separate task, so slow consumer doesn't make the channel lag. When
+/// the queue is full, new event is handled by overflow policy and every dropped event is counted
+/// in statistics. If order books are conflated, consumer receives the latest order book of market
+/// instead of backlog of its stale updates
+pub struct EventQueue {
+    consumer: String,
+    capacity: usize,
+    overflow_policy: EventOverflowPolicy,
+    conflate_order_books: bool,
+    state: Mutex<QueueState>,
+    is_closed: AtomicBool,
+    received: Notify,
+    consumed: Notify,
+    statistics: Arc<StatisticService>,
+}
+
+impl EventQueue {
+    fn new(
+        consumer: &str,
+        settings: &EventQueueSettings,
+        statistics: Arc<StatisticService>,
+    ) -> Arc<Self> {
+        Arc::new(EventQueue {
+            consumer: consumer.to_owned(),
+            capacity: settings.capacity.max(1),
+            overflow_policy: settings.overflow_policy,
+            conflate_order_books: settings.conflate_order_books,
+            state: Default::default(),
+            is_closed: AtomicBool::new(false),
+            received: Notify::new(),
+            consumed: Notify::new(),
+            statistics,
+        })
+    }
+
+    /// Starts moving events from engine events channel into the queue until `stop_token` is
+    /// cancelled or the channel is closed
+    pub fn start(
+        consumer: &str,
+        settings: &EventQueueSettings,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        statistics: Arc<StatisticService>,
+        stop_token: CancellationToken,
+    ) -> Arc<Self> {
+        let queue = EventQueue::new(consumer, settings, statistics);
+        spawn_future(
+            &format!("Event queue of '{consumer}'"),
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            queue.clone().forward(events_receiver, stop_token),
+        );
+        queue
+    }
+
+    /// Next event of the queue. `None` if the queue is closed and all events are received
+    pub async fn recv(&self) -> Option<ExchangeEvent> {
+        loop {
+            if let Some(event) = self.pop_front() {
+                self.consumed.notify_one();
+                return Some(event);
+            }
+
+            if self.is_closed.load(Ordering::Acquire) {
+                // events pushed before closing are received first
+                return self.pop_front();
+            }
+
+            self.received.notified().await;
+        }
+    }
+
+    fn pop_front(&self) -> Option<ExchangeEvent> {
+        let mut state = self.state.lock();
+        let event = state.events.pop_front()?;
+        match event {
+            ExchangeEvent::OrderBookEvent(order_book_event) if self.conflate_order_books => {
+                let market_account_id = order_book_event.market_account_id();
+                let _ = state.waiting_order_books.remove(&market_account_id);
+                let latest = state.order_books.remove(&market_account_id);
+                Some(ExchangeEvent::OrderBookEvent(
+                    latest.unwrap_or(order_book_event),
+                ))
+            }
+            event => Some(event),
+        }
+    }
+
+    async fn forward(
+        self: Arc<Self>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+        stop_token: CancellationToken,
+    ) -> Result<()> {
+        loop {
+            let event = tokio::select! {
+                _ = stop_token.when_cancelled() => break,
+                event = events_receiver.recv() => event,
+            };
+
+            match event {
+                Ok(event) => tokio::select! {
+                    _ = stop_token.when_cancelled() => break,
+                    _ = self.push(event) => {}
+                },
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!(
+                        "Event queue of '{}' skipped {skipped} events",
+                        self.consumer
+                    );
+                    self.statistics
+                        .register_dropped_events(&self.consumer, skipped);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+
+        self.close();
+        Ok(())
+    }
+
+    async fn push(&self, event: ExchangeEvent) {
+        if self.conflate_order_book(&event) {
+            return;
+        }
+
+        let mut is_blocked = false;
+        loop {
+            if self.try_push(&event) {
+                self.received.notify_one();
+                return;
+            }
+
+            if !is_blocked {
+                is_blocked = true;
+                self.statistics.register_blocked_event_queue(&self.consumer);
+            }
+            self.consumed.notified().await;
+        }
+    }
+
+    /// Applies order book event to the latest order book of its market. Returns `true` if order
+    /// book of the market is already waiting in the queue, so the event doesn't need place in it
+    fn conflate_order_book(&self, event: &ExchangeEvent) -> bool {
+        let ExchangeEvent::OrderBookEvent(newer) = event else {
+            return false;
+        };
+        if !self.conflate_order_books {
+            return false;
+        }
+
+        let market_account_id = newer.market_account_id();
+        let mut state = self.state.lock();
+        match state.order_books.entry(market_account_id) {
+            Entry::Occupied(mut latest) => latest.get_mut().coalesce(newer),
+            Entry::Vacant(latest) => {
+                let _ = latest.insert(newer.clone());
+            }
+        }
+
+        let is_waiting = state.waiting_order_books.contains(&market_account_id);
+        if is_waiting {
+            self.statistics.register_coalesced_event(&self.consumer);
+        }
+        is_waiting
+    }
+
+    /// Returns `false` if event should wait for free space in the queue
+    fn try_push(&self, event: &ExchangeEvent) -> bool {
+        let mut state = self.state.lock();
+        if state.events.len() >= self.capacity {
+            match self.overflow_policy {
+                EventOverflowPolicy::Block => return false,
+                EventOverflowPolicy::DropOldest => {
+                    if let Some(ExchangeEvent::OrderBookEvent(dropped)) = state.events.pop_front() {
+                        let _ = state
+                            .waiting_order_books
+                            .remove(&dropped.market_account_id());
+                    }
+                    self.statistics.register_dropped_events(&self.consumer, 1);
+                }
+                EventOverflowPolicy::CoalesceOrderBooks => {
+                    return self.coalesce_order_book(&mut state.events, event)
+                }
+            }
+        }
+
+        if let ExchangeEvent::OrderBookEvent(order_book_event) = event {
+            if self.conflate_order_books {
+                let _ = state
+                    .waiting_order_books
+                    .insert(order_book_event.market_account_id());
+            }
+        }
+        state.events.push_back(event.clone());
+        true
+    }
+
+    /// Merges order book event into waiting event of the same market. Returns `false` if there
+    /// is no such event
+    fn coalesce_order_book(
+        &self,
+        events: &mut VecDeque<ExchangeEvent>,
+        event: &ExchangeEvent,
+    ) -> bool {
+        let ExchangeEvent::OrderBookEvent(newer) = event else {
+            return false;
+        };
+
+        let waiting = events.iter_mut().rev().find_map(|x| match x {
+            ExchangeEvent::OrderBookEvent(waiting)
+                if waiting.market_account_id() == newer.market_account_id() =>
+            {
+                Some(waiting)
+            }
+            _ => None,
+        });
+        match waiting {
+            Some(waiting) => {
+                waiting.coalesce(newer);
+                self.statistics.register_coalesced_event(&self.consumer);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn close(&self) {
+        self.is_closed.store(true, Ordering::Release);
+        self.received.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::misc::clock::SystemClock;
+    use crate::misc::trading_calendar::TradingCalendar;
+    use chrono::Utc;
+    use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+    use mmb_domain::order_book::event::EventType;
+    use mmb_domain::order_book_data;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn order_book_event(base: &str, event_type: EventType, price: Decimal) -> ExchangeEvent {
+        ExchangeEvent::OrderBookEvent(OrderBookEvent::new(
+            Utc::now(),
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes(base.into(), "usdt".into()),
+            String::new(),
+            event_type,
+            Arc::new(order_book_data![price => dec!(1), ;]),
+        ))
+    }
+
+    fn queue(overflow_policy: EventOverflowPolicy, conflate_order_books: bool) -> Arc<EventQueue> {
+        let settings = EventQueueSettings {
+            capacity: 2,
+            overflow_policy,
+            conflate_order_books,
+        };
+        let statistics = StatisticService::new(TradingCalendar::new(None), SystemClock::shared());
+        EventQueue::new("test", &settings, statistics)
+    }
+
+    fn ask_prices(queue: &EventQueue) -> Vec<Vec<Decimal>> {
+        queue
+            .state
+            .lock()
+            .events
+            .iter()
+            .map(|x| match x {
+                ExchangeEvent::OrderBookEvent(x) => x.data.asks.keys().copied().collect(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn oldest_event_is_dropped_on_overflow() {
+        let queue = queue(EventOverflowPolicy::DropOldest, false);
+        for price in [dec!(1), dec!(2), dec!(3)] {
+            assert!(queue.try_push(&order_book_event("btc", EventType::Update, price)));
+        }
+
+        assert_eq!(ask_prices(&queue), [vec![dec!(2)], vec![dec!(3)]]);
+        assert_eq!(
+            queue.statistics.event_queue_stats()["test"].dropped_events_count,
+            1
+        );
+    }
+
+    #[test]
+    fn order_books_are_coalesced_on_overflow() {
+        let queue = queue(EventOverflowPolicy::CoalesceOrderBooks, false);
+        assert!(queue.try_push(&order_book_event("btc", EventType::Snapshot, dec!(1))));
+        assert!(queue.try_push(&order_book_event("eth", EventType::Update, dec!(10))));
+        assert!(queue.try_push(&order_book_event("btc", EventType::Update, dec!(2))));
+        // nothing to coalesce with, so event waits for free space
+        assert!(!queue.try_push(&order_book_event("ltc", EventType::Update, dec!(3))));
+
+        assert_eq!(ask_prices(&queue), [vec![dec!(1), dec!(2)], vec![dec!(10)]]);
+        let stats = &queue.statistics.event_queue_stats()["test"];
+        assert_eq!(stats.coalesced_events_count, 1);
+        assert_eq!(stats.dropped_events_count, 0);
+    }
+
+    #[tokio::test]
+    async fn latest_order_book_is_received_when_conflated() {
+        let queue = queue(EventOverflowPolicy::Block, true);
+        queue
+            .push(order_book_event("btc", EventType::Snapshot, dec!(1)))
+            .await;
+        queue
+            .push(order_book_event("eth", EventType::Update, dec!(10)))
+            .await;
+        for price in [dec!(2), dec!(3), dec!(4)] {
+            // events of markets waiting in the queue don't need free space
+            queue
+                .push(order_book_event("btc", EventType::Update, price))
+                .await;
+        }
+        assert_eq!(queue.state.lock().events.len(), 2);
+
+        let Some(ExchangeEvent::OrderBookEvent(received)) = queue.recv().await else {
+            panic!("order book event should be received");
+        };
+        assert!(matches!(received.event_type, EventType::Snapshot));
+        assert_eq!(
+            received.data.asks.keys().copied().collect::<Vec<_>>(),
+            [dec!(1), dec!(2), dec!(3), dec!(4)]
+        );
+
+        // market isn't waiting anymore, so next event takes place in the queue
+        queue
+            .push(order_book_event("btc", EventType::Update, dec!(5)))
+            .await;
+        assert_eq!(queue.state.lock().events.len(), 2);
+        assert_eq!(
+            queue.statistics.event_queue_stats()["test"].coalesced_events_count,
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn delivered_order_book_is_not_conflated_again() {
+        let queue = queue(EventOverflowPolicy::Block, true);
+
+        for prices in [[dec!(1), dec!(2)], [dec!(3), dec!(4)]] {
+            for price in prices {
+                queue
+                    .push(order_book_event("btc", EventType::Update, price))
+                    .await;
+            }
+
+            let Some(ExchangeEvent::OrderBookEvent(received)) = queue.recv().await else {
+                panic!("order book event should be received");
+            };
+            // every update is delivered only once
+            assert_eq!(
+                received.data.asks.keys().copied().collect::<Vec<_>>(),
+                prices
+            );
+            assert!(queue.state.lock().order_books.is_empty());
+        }
+    }
+}
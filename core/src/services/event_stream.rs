@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::{SinkExt, StreamExt};
+use mmb_domain::events::{BalanceUpdateEvent, ExchangeEvent};
+use mmb_domain::market::{CurrencyCode, CurrencyPair, ExchangeAccountId, MarketAccountId};
+use mmb_domain::order::event::{OrderEvent, OrderEventType};
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, ExchangeOrderId, OrderFillRole, OrderSide, Price,
+};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use serde::Serialize;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::services::liquidity_snapshot::{LiquiditySnapshot, LiquiditySnapshotBuilder};
+use crate::settings::EventStreamSettings;
+use crate::statistic_service::{MarketAccountIdStatistic, StatisticService};
+
+const CLIENT_MESSAGES_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderMessage {
+    /// Name of order event type, e.g. `CreateOrderSucceeded`
+    pub event: &'static str,
+    pub client_order_id: ClientOrderId,
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub price: Price,
+    pub amount: Amount,
+    pub filled_amount: Amount,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FillMessage {
+    pub client_order_id: ClientOrderId,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub price: Price,
+    pub amount: Amount,
+    pub role: OrderFillRole,
+    pub commission_currency_code: CurrencyCode,
+    pub commission_amount: Amount,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceMessage {
+    pub currency_code: CurrencyCode,
+    pub balance: Amount,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BalancesMessage {
+    pub exchange_account_id: ExchangeAccountId,
+    pub balances: Vec<BalanceMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketStatsMessage {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    #[serde(flatten)]
+    pub stats: MarketAccountIdStatistic,
+}
+
+/// Normalized engine event sent to clients of event stream as JSON object with field `type`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum StreamMessage {
+    Order(OrderMessage),
+    Fill(FillMessage),
+    Balances(BalancesMessage),
+    /// Statistics of markets changed since the previous message. The first message for every
+    /// client contains all markets
+    Stats {
+        markets: Vec<MarketStatsMessage>,
+    },
+    /// Top price levels of order book, our open orders, recent trades, position and PnL of
+    /// trade place
+    Liquidity(LiquiditySnapshot),
+}
+
+/// WebSocket server which streams normalized engine events (order lifecycle, fills, balance
+/// changes, statistics deltas and liquidity snapshots) to connected clients such as
+/// visualization web app. Clients don't send anything except control frames
+pub struct EventStreamService {
+    settings: EventStreamSettings,
+    statistics: Arc<StatisticService>,
+    sender: broadcast::Sender<Arc<String>>,
+}
+
+impl EventStreamService {
+    pub fn new(settings: EventStreamSettings, statistics: Arc<StatisticService>) -> Arc<Self> {
+        let (sender, _) = broadcast::channel(CLIENT_MESSAGES_CAPACITY);
+        Arc::new(EventStreamService {
+            settings,
+            statistics,
+            sender,
+        })
+    }
+
+    /// Engine context is used to get our open orders for liquidity snapshots
+    pub fn start(
+        self: &Arc<Self>,
+        engine_context: Weak<EngineContext>,
+        events: broadcast::Receiver<ExchangeEvent>,
+        stop_token: CancellationToken,
+    ) {
+        spawn_future(
+            "Accept event stream clients",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            self.clone().accept_clients(stop_token.clone()),
+        );
+        spawn_future(
+            "Publish event stream messages",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            self.clone()
+                .publish_messages(engine_context, events, stop_token),
+        );
+    }
+
+    async fn accept_clients(self: Arc<Self>, stop_token: CancellationToken) -> Result<()> {
+        let listener = TcpListener::bind(&self.settings.address)
+            .await
+            .with_context(|| format!("binding event stream to {}", self.settings.address))?;
+        log::info!("Event stream is started on {}", self.settings.address);
+
+        loop {
+            let (stream, address) = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        log::warn!("Failed to accept event stream client: {err:?}");
+                        continue;
+                    }
+                },
+                _ = stop_token.when_cancelled() => return Ok(()),
+            };
+
+            spawn_future(
+                &format!("Serve event stream client {address}"),
+                SpawnFutureFlags::STOP_BY_TOKEN,
+                self.clone().serve_client(stream, stop_token.clone()),
+            );
+        }
+    }
+
+    async fn serve_client(
+        self: Arc<Self>,
+        stream: TcpStream,
+        stop_token: CancellationToken,
+    ) -> Result<()> {
+        // subscribe before sending of initial state, so no message is lost between them
+        let mut messages = self.sender.subscribe();
+        let mut websocket = tokio_tungstenite::accept_async(stream)
+            .await
+            .context("WebSocket handshake of event stream client")?;
+
+        let initial_stats = StreamMessage::Stats {
+            markets: stats_messages(self.statistics.market_account_id_stats()),
+        };
+        websocket
+            .send(Message::Text(serde_json::to_string(&initial_stats)?))
+            .await?;
+
+        loop {
+            tokio::select! {
+                message = messages.recv() => match message {
+                    Ok(message) => {
+                        if let Err(err) = websocket.send(Message::Text(message.to_string())).await {
+                            log::info!("Event stream client is disconnected: {err}");
+                            return Ok(());
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Event stream client is too slow, {skipped} messages are skipped");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                incoming = websocket.next() => match incoming {
+                    Some(Ok(Message::Close(_))) | None => return Ok(()),
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => {
+                        log::info!("Event stream client is disconnected: {err}");
+                        return Ok(());
+                    }
+                },
+                _ = stop_token.when_cancelled() => break,
+            }
+        }
+
+        let _ = websocket.close(None).await;
+        Ok(())
+    }
+
+    async fn publish_messages(
+        self: Arc<Self>,
+        engine_context: Weak<EngineContext>,
+        mut events: broadcast::Receiver<ExchangeEvent>,
+        stop_token: CancellationToken,
+    ) -> Result<()> {
+        let mut stats_interval =
+            tokio::time::interval(Duration::from_millis(self.settings.stats_interval_millis));
+        let mut liquidity_interval = tokio::time::interval(Duration::from_millis(
+            self.settings.liquidity_interval_millis,
+        ));
+
+        let mut stats_tracker = StatsTracker::default();
+        let mut liquidity = LiquiditySnapshotBuilder::new(
+            self.settings.liquidity_depth,
+            self.settings.recent_trades_count,
+        );
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        liquidity.update(&event);
+                        for message in normalize(&event) {
+                            self.publish(&message);
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Event stream skipped {skipped} engine events");
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                _ = stats_interval.tick() => {
+                    let markets = stats_tracker.changed(self.statistics.market_account_id_stats());
+                    if !markets.is_empty() {
+                        self.publish(&StreamMessage::Stats { markets });
+                    }
+                }
+                _ = liquidity_interval.tick() => {
+                    let engine_context = match engine_context.upgrade() {
+                        Some(engine_context) => engine_context,
+                        None => return Ok(()),
+                    };
+                    let now = Utc::now();
+                    for market_account_id in liquidity.take_changed() {
+                        let exchange_account_id = market_account_id.exchange_account_id;
+                        let snapshot = engine_context
+                            .exchanges
+                            .get(&exchange_account_id)
+                            .and_then(|x| liquidity.build(market_account_id, &x.orders, now));
+                        if let Some(snapshot) = snapshot {
+                            self.publish(&StreamMessage::Liquidity(snapshot));
+                        }
+                    }
+                }
+                _ = stop_token.when_cancelled() => return Ok(()),
+            }
+        }
+    }
+
+    fn publish(&self, message: &StreamMessage) {
+        // there are no receivers while no client is connected
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+
+        match serde_json::to_string(message) {
+            Ok(json) => {
+                let _ = self.sender.send(Arc::new(json));
+            }
+            Err(err) => log::error!("Failed to serialize event stream message: {err:?}"),
+        }
+    }
+}
+
+/// Remembers the last sent statistics of markets to send only changed ones
+#[derive(Default)]
+pub(crate) struct StatsTracker {
+    sent: HashMap<MarketAccountId, MarketAccountIdStatistic>,
+}
+
+impl StatsTracker {
+    pub(crate) fn changed(
+        &mut self,
+        stats: Vec<(MarketAccountId, MarketAccountIdStatistic)>,
+    ) -> Vec<MarketStatsMessage> {
+        let changed = stats
+            .into_iter()
+            .filter(|(market_account_id, stats)| self.sent.get(market_account_id) != Some(stats))
+            .collect::<Vec<_>>();
+        for (market_account_id, stats) in &changed {
+            let _ = self.sent.insert(*market_account_id, stats.clone());
+        }
+
+        stats_messages(changed)
+    }
+}
+
+fn stats_messages(
+    stats: Vec<(MarketAccountId, MarketAccountIdStatistic)>,
+) -> Vec<MarketStatsMessage> {
+    stats
+        .into_iter()
+        .map(|(market_account_id, stats)| MarketStatsMessage {
+            exchange_account_id: market_account_id.exchange_account_id,
+            currency_pair: market_account_id.currency_pair,
+            stats,
+        })
+        .collect()
+}
+
+pub(crate) fn normalize(event: &ExchangeEvent) -> Vec<StreamMessage> {
+    match event {
+        ExchangeEvent::OrderEvent(event) => normalize_order_event(event),
+        ExchangeEvent::BalanceUpdate(event) => vec![normalize_balance_update(event)],
+        _ => Vec::new(),
+    }
+}
+
+fn normalize_order_event(event: &OrderEvent) -> Vec<StreamMessage> {
+    let order = &event.order;
+    let (event_name, filled_order) = match &event.event_type {
+        OrderEventType::CreateOrderSucceeded => ("CreateOrderSucceeded", None),
+        OrderEventType::CreateOrderFailed => ("CreateOrderFailed", None),
+        OrderEventType::RejectedByRisk => ("RejectedByRisk", None),
+        OrderEventType::RejectedByBalance => ("RejectedByBalance", None),
+        OrderEventType::RejectedByValidation => ("RejectedByValidation", None),
+        OrderEventType::OrderFilled { cloned_order } => ("OrderFilled", Some(cloned_order)),
+        OrderEventType::OrderCompleted { .. } => ("OrderCompleted", None),
+        OrderEventType::CancelOrderSucceeded => ("CancelOrderSucceeded", None),
+        OrderEventType::CancelOrderFailed => ("CancelOrderFailed", None),
+        OrderEventType::OrderExpired => ("OrderExpired", None),
+    };
+
+    let mut messages = vec![StreamMessage::Order(OrderMessage {
+        event: event_name,
+        client_order_id: order.client_order_id(),
+        exchange_order_id: order.exchange_order_id(),
+        exchange_account_id: order.exchange_account_id(),
+        currency_pair: order.currency_pair(),
+        side: order.side(),
+        price: order.price(),
+        amount: order.amount(),
+        filled_amount: order.filled_amount(),
+        status: format!("{:?}", order.status()),
+    })];
+
+    let fill = filled_order.and_then(|x| Some((x, x.fills.fills.last()?)));
+    if let Some((filled_order, fill)) = fill {
+        let header = &filled_order.header;
+        messages.push(StreamMessage::Fill(FillMessage {
+            client_order_id: header.client_order_id.clone(),
+            exchange_account_id: header.exchange_account_id,
+            currency_pair: header.currency_pair,
+            side: fill.side().unwrap_or(header.side),
+            price: fill.price(),
+            amount: fill.amount(),
+            role: fill.role(),
+            commission_currency_code: fill.commission_currency_code(),
+            commission_amount: fill.commission_amount(),
+        }));
+    }
+
+    messages
+}
+
+fn normalize_balance_update(event: &BalanceUpdateEvent) -> StreamMessage {
+    StreamMessage::Balances(BalancesMessage {
+        exchange_account_id: event.exchange_account_id,
+        balances: event
+            .balances_and_positions
+            .balances
+            .iter()
+            .map(|x| BalanceMessage {
+                currency_code: x.currency_code,
+                balance: x.balance,
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::events::{ExchangeBalance, ExchangeBalancesAndPositions};
+    use rust_decimal_macros::dec;
+
+    fn market_account_id() -> MarketAccountId {
+        MarketAccountId::new(
+            "Binance_0".parse().expect("valid id"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    #[test]
+    fn only_changed_stats_are_sent_again() {
+        let mut tracker = StatsTracker::default();
+        let stats = MarketAccountIdStatistic {
+            opened_orders_count: 1,
+            ..Default::default()
+        };
+
+        let markets = tracker.changed(vec![(market_account_id(), stats.clone())]);
+        assert_eq!(markets.len(), 1);
+        assert!(tracker
+            .changed(vec![(market_account_id(), stats.clone())])
+            .is_empty());
+
+        let changed_stats = MarketAccountIdStatistic {
+            opened_orders_count: 2,
+            ..stats
+        };
+        let markets = tracker.changed(vec![(market_account_id(), changed_stats)]);
+        assert_eq!(markets.len(), 1);
+        assert_eq!(markets[0].stats.opened_orders_count, 2);
+    }
+
+    #[test]
+    fn balance_update_is_serialized_with_type() {
+        let event = ExchangeEvent::BalanceUpdate(BalanceUpdateEvent {
+            exchange_account_id: market_account_id().exchange_account_id,
+            balances_and_positions: ExchangeBalancesAndPositions {
+                balances: vec![ExchangeBalance {
+                    currency_code: "btc".into(),
+                    balance: dec!(1.5),
+                }],
+                positions: None,
+            },
+        });
+
+        let messages = normalize(&event);
+        assert_eq!(messages.len(), 1);
+        let json: serde_json::Value =
+            serde_json::to_value(&messages[0]).expect("message is serialized");
+        assert_eq!(json["type"], "Balances");
+        assert_eq!(json["exchange_account_id"], "Binance_0");
+        assert_eq!(json["balances"][0]["currency_code"], "btc");
+        assert_eq!(json["balances"][0]["balance"], "1.5");
+    }
+}
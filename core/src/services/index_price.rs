@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::snapshot::Price;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::settings::IndexPriceSettings;
+
+/// Composite index prices of currency pairs by weighted mid prices of several exchange accounts.
+/// Components deviating too much from the others are rejected, so single venue glitch doesn't
+/// move the index
+pub struct IndexPriceService {
+    indices: HashMap<CurrencyPair, IndexPriceSettings>,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+}
+
+impl IndexPriceService {
+    pub fn new(
+        settings: &[IndexPriceSettings],
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    ) -> Arc<Self> {
+        let indices = settings
+            .iter()
+            .map(|x| (x.currency_pair, x.clone()))
+            .collect();
+
+        Arc::new(IndexPriceService { indices, exchanges })
+    }
+
+    /// Whether index is configured for currency pair
+    pub fn has_index(&self, currency_pair: CurrencyPair) -> bool {
+        self.indices.contains_key(&currency_pair)
+    }
+
+    /// Index price of currency pair. `None` if index isn't configured or there are not enough
+    /// components with known mid prices after rejection of outliers
+    pub fn index_price(&self, currency_pair: CurrencyPair) -> Option<Price> {
+        let settings = self.indices.get(&currency_pair)?;
+        let components = settings
+            .components
+            .iter()
+            .filter_map(|component| {
+                let exchange = self.exchanges.get(&component.exchange_account_id)?;
+                let mid_price = exchange.mid_price(currency_pair)?;
+                Some((mid_price, component.weight))
+            })
+            .collect::<Vec<_>>();
+
+        compose_index(components, settings)
+    }
+}
+
+/// Weighted average of component prices which deviate from weighted median by no more than
+/// `max_deviation_percent`
+fn compose_index(
+    mut components: Vec<(Price, Decimal)>,
+    settings: &IndexPriceSettings,
+) -> Option<Price> {
+    components.retain(|(price, weight)| *price > Decimal::ZERO && *weight > Decimal::ZERO);
+    let median = weighted_median(&mut components)?;
+
+    let max_deviation = median * settings.max_deviation_percent / dec!(100);
+    components.retain(|(price, _)| (*price - median).abs() <= max_deviation);
+    if components.len() < settings.min_components.max(1) {
+        return None;
+    }
+
+    let weights_sum: Decimal = components.iter().map(|(_, weight)| weight).sum();
+    let weighted_sum: Decimal = components
+        .iter()
+        .map(|(price, weight)| price * weight)
+        .sum();
+    Some(weighted_sum / weights_sum)
+}
+
+fn weighted_median(components: &mut [(Price, Decimal)]) -> Option<Price> {
+    components.sort_by_key(|(price, _)| *price);
+    let half_weight = components.iter().map(|(_, weight)| weight).sum::<Decimal>() / dec!(2);
+
+    let mut cumulative_weight = Decimal::ZERO;
+    components.iter().find_map(|(price, weight)| {
+        cumulative_weight += weight;
+        (cumulative_weight >= half_weight).then_some(*price)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(max_deviation_percent: Decimal, min_components: usize) -> IndexPriceSettings {
+        IndexPriceSettings {
+            currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            components: Vec::new(),
+            max_deviation_percent,
+            min_components,
+        }
+    }
+
+    #[test]
+    fn index_is_weighted_average_without_outliers() {
+        let settings = settings(dec!(1), 2);
+
+        assert_eq!(
+            compose_index(vec![(dec!(100), dec!(1)), (dec!(101), dec!(3))], &settings),
+            Some(dec!(100.75))
+        );
+        // 120 deviates from median 101 by more than 1%
+        assert_eq!(
+            compose_index(
+                vec![
+                    (dec!(100), dec!(1)),
+                    (dec!(120), dec!(1)),
+                    (dec!(101), dec!(2))
+                ],
+                &settings
+            ),
+            Some(dec!(302) / dec!(3))
+        );
+        // only one component is left after rejection
+        assert_eq!(
+            compose_index(vec![(dec!(100), dec!(1)), (dec!(120), dec!(3))], &settings),
+            None
+        );
+        assert_eq!(compose_index(Vec::new(), &settings), None);
+    }
+}
@@ -0,0 +1,665 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use mmb_domain::events::ExchangeEvent;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use rdkafka::producer::{BaseRecord, DeliveryResult, Producer, ProducerContext, ThreadedProducer};
+use rdkafka::{ClientConfig, ClientContext};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::infrastructure::spawn_future;
+use crate::services::event_stream::{normalize, StatsTracker, StreamMessage};
+use crate::settings::{KafkaSerialization, KafkaSettings};
+use crate::statistic_service::StatisticService;
+
+type HttpClient = Client<HttpsConnector<HttpConnector>>;
+
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Magic byte of messages in wire format of Confluent schema registry
+const AVRO_MAGIC_BYTE: u8 = 0;
+
+/// Decimals are strings to keep their precision
+const ORDER_SCHEMA: &str = r#"{
+    "type": "record",
+    "name": "Order",
+    "namespace": "mmb",
+    "fields": [
+        {"name": "event", "type": "string"},
+        {"name": "client_order_id", "type": "string"},
+        {"name": "exchange_order_id", "type": ["null", "string"]},
+        {"name": "exchange_account_id", "type": "string"},
+        {"name": "currency_pair", "type": "string"},
+        {"name": "side", "type": "string"},
+        {"name": "price", "type": "string"},
+        {"name": "amount", "type": "string"},
+        {"name": "filled_amount", "type": "string"},
+        {"name": "status", "type": "string"}
+    ]
+}"#;
+
+const FILL_SCHEMA: &str = r#"{
+    "type": "record",
+    "name": "Fill",
+    "namespace": "mmb",
+    "fields": [
+        {"name": "client_order_id", "type": "string"},
+        {"name": "exchange_account_id", "type": "string"},
+        {"name": "currency_pair", "type": "string"},
+        {"name": "side", "type": "string"},
+        {"name": "price", "type": "string"},
+        {"name": "amount", "type": "string"},
+        {"name": "role", "type": "string"},
+        {"name": "commission_currency_code", "type": "string"},
+        {"name": "commission_amount", "type": "string"}
+    ]
+}"#;
+
+const BALANCES_SCHEMA: &str = r#"{
+    "type": "record",
+    "name": "Balances",
+    "namespace": "mmb",
+    "fields": [
+        {"name": "exchange_account_id", "type": "string"},
+        {"name": "balances", "type": {"type": "array", "items": {
+            "type": "record",
+            "name": "Balance",
+            "fields": [
+                {"name": "currency_code", "type": "string"},
+                {"name": "balance", "type": "string"}
+            ]
+        }}}
+    ]
+}"#;
+
+const STATS_SCHEMA: &str = r#"{
+    "type": "record",
+    "name": "MarketStats",
+    "namespace": "mmb",
+    "fields": [
+        {"name": "exchange_account_id", "type": "string"},
+        {"name": "currency_pair", "type": "string"},
+        {"name": "opened_orders_count", "type": "long"},
+        {"name": "canceled_orders_count", "type": "long"},
+        {"name": "partially_filled_orders_count", "type": "long"},
+        {"name": "fully_filled_orders_count", "type": "long"},
+        {"name": "summary_filled_amount", "type": "string"},
+        {"name": "summary_commission", "type": "string"},
+        {"name": "active_trading_time_secs", "type": "long"},
+        {"name": "filled_orders_per_hour", "type": ["null", "string"]},
+        {"name": "filled_amount_per_day", "type": ["null", "string"]},
+        {"name": "commission_per_day", "type": ["null", "string"]}
+    ]
+}"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Topic {
+    Orders,
+    Fills,
+    Balances,
+    Stats,
+}
+
+impl Topic {
+    const ALL: [Topic; 4] = [Topic::Orders, Topic::Fills, Topic::Balances, Topic::Stats];
+
+    fn suffix(self) -> &'static str {
+        match self {
+            Topic::Orders => "orders",
+            Topic::Fills => "fills",
+            Topic::Balances => "balances",
+            Topic::Stats => "stats",
+        }
+    }
+
+    fn avro_schema(self) -> &'static str {
+        match self {
+            Topic::Orders => ORDER_SCHEMA,
+            Topic::Fills => FILL_SCHEMA,
+            Topic::Balances => BALANCES_SCHEMA,
+            Topic::Stats => STATS_SCHEMA,
+        }
+    }
+}
+
+/// Message ready for publishing. Key is used for partitioning, so events of the same order or
+/// exchange account are consumed in order of publishing
+#[derive(Debug)]
+struct KafkaMessage {
+    topic: Topic,
+    key: String,
+    payload: Value,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct KafkaPublisherStats {
+    pub published_messages: u64,
+    /// Messages which weren't delivered because of Kafka errors or full producer queue
+    pub lost_messages: u64,
+}
+
+#[derive(Default)]
+struct DeliveryCounters {
+    published_messages: AtomicU64,
+    lost_messages: AtomicU64,
+}
+
+impl ClientContext for DeliveryCounters {}
+
+impl ProducerContext for DeliveryCounters {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, delivery_result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        match delivery_result {
+            Ok(_) => {
+                let _ = self.published_messages.fetch_add(1, Ordering::Relaxed);
+            }
+            Err((err, _)) => {
+                log::error!("Failed to deliver message to Kafka: {err}");
+                let _ = self.lost_messages.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Publishes normalized order lifecycle events, fills, balance updates and changed market
+/// statistics to Kafka topics as JSON or Avro messages. Messages are enqueued to producer without
+/// waiting, so unavailable Kafka doesn't delay handling of events
+pub struct KafkaPublisher {
+    settings: KafkaSettings,
+    statistics: Arc<StatisticService>,
+    producer: ThreadedProducer<DeliveryCounters>,
+    /// Parsed Avro schemas of topics, empty for JSON serialization
+    avro_schemas: HashMap<Topic, Value>,
+}
+
+impl KafkaPublisher {
+    pub fn start(
+        settings: KafkaSettings,
+        statistics: Arc<StatisticService>,
+        events: broadcast::Receiver<ExchangeEvent>,
+        stop_token: CancellationToken,
+    ) -> Result<Arc<Self>> {
+        let mut config = ClientConfig::new();
+        let _ = config.set("bootstrap.servers", &settings.brokers);
+        for (key, value) in &settings.producer_properties {
+            let _ = config.set(key, value);
+        }
+        let producer = config
+            .create_with_context(DeliveryCounters::default())
+            .context("creating Kafka producer")?;
+
+        let avro_schemas = match settings.serialization {
+            KafkaSerialization::Json => HashMap::new(),
+            KafkaSerialization::Avro { .. } => parse_avro_schemas()?,
+        };
+
+        let publisher = Arc::new(KafkaPublisher {
+            settings,
+            statistics,
+            producer,
+            avro_schemas,
+        });
+
+        spawn_future(
+            "Publish events to Kafka",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            publisher.clone().publish_events(events, stop_token),
+        );
+
+        Ok(publisher)
+    }
+
+    pub fn stats(&self) -> KafkaPublisherStats {
+        let counters = self.producer.context();
+        KafkaPublisherStats {
+            published_messages: counters.published_messages.load(Ordering::Relaxed),
+            lost_messages: counters.lost_messages.load(Ordering::Relaxed),
+        }
+    }
+
+    fn topic_name(&self, topic: Topic) -> String {
+        format!("{}.{}", self.settings.topic_prefix, topic.suffix())
+    }
+
+    async fn publish_events(
+        self: Arc<Self>,
+        mut events: broadcast::Receiver<ExchangeEvent>,
+        stop_token: CancellationToken,
+    ) -> Result<()> {
+        let avro_schemas = match &self.settings.serialization {
+            KafkaSerialization::Json => None,
+            KafkaSerialization::Avro {
+                schema_registry_url,
+            } => Some(self.register_schemas(schema_registry_url).await?),
+        };
+        let encoder = MessageEncoder { avro_schemas };
+
+        let mut stats_interval =
+            tokio::time::interval(Duration::from_millis(self.settings.stats_interval_millis));
+        let mut stats_tracker = StatsTracker::default();
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        for message in normalize(&event) {
+                            self.publish(&encoder, &message);
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::error!("KafkaPublisher skipped {skipped} events");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = stats_interval.tick() => {
+                    let markets = stats_tracker.changed(self.statistics.market_account_id_stats());
+                    if !markets.is_empty() {
+                        self.publish(&encoder, &StreamMessage::Stats { markets });
+                    }
+                }
+                _ = stop_token.when_cancelled() => break,
+            }
+        }
+
+        tokio::task::spawn_blocking(move || self.producer.flush(FLUSH_TIMEOUT))
+            .await
+            .context("flushing Kafka producer")?;
+
+        Ok(())
+    }
+
+    fn publish(&self, encoder: &MessageEncoder, message: &StreamMessage) {
+        let messages = match kafka_messages(message) {
+            Ok(messages) => messages,
+            Err(err) => {
+                log::error!("Failed to prepare Kafka messages: {err:?}");
+                return;
+            }
+        };
+
+        for message in messages {
+            let topic = self.topic_name(message.topic);
+            let payload = match encoder.encode(&message) {
+                Ok(payload) => payload,
+                Err(err) => {
+                    log::error!("Failed to serialize message for Kafka topic {topic}: {err:?}");
+                    let _ = self
+                        .producer
+                        .context()
+                        .lost_messages
+                        .fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+            };
+
+            let record = BaseRecord::to(&topic).key(&message.key).payload(&payload);
+            if let Err((err, _)) = self.producer.send(record) {
+                log::error!("Failed to enqueue message to Kafka topic {topic}: {err}");
+                let _ = self
+                    .producer
+                    .context()
+                    .lost_messages
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    async fn register_schemas(
+        &self,
+        schema_registry_url: &str,
+    ) -> Result<HashMap<Topic, RegisteredAvroSchema>> {
+        let https = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        let client: HttpClient = Client::builder().build(https);
+
+        let mut registered = HashMap::new();
+        for (topic, schema) in &self.avro_schemas {
+            let subject = format!("{}-value", self.topic_name(*topic));
+            let id = register_schema(&client, schema_registry_url, &subject, topic.avro_schema())
+                .await
+                .with_context(|| format!("registering Avro schema of subject {subject}"))?;
+            let schema = schema.clone();
+            let _ = registered.insert(*topic, RegisteredAvroSchema { id, schema });
+        }
+
+        Ok(registered)
+    }
+}
+
+#[derive(Deserialize)]
+struct RegisteredSchema {
+    id: u32,
+}
+
+async fn register_schema(
+    client: &HttpClient,
+    schema_registry_url: &str,
+    subject: &str,
+    schema: &str,
+) -> Result<u32> {
+    let body = serde_json::json!({ "schema": schema }).to_string();
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!(
+            "{}/subjects/{subject}/versions",
+            schema_registry_url.trim_end_matches('/')
+        ))
+        .header("Content-Type", "application/vnd.schemaregistry.v1+json")
+        .body(Body::from(body))
+        .context("building schema registry request")?;
+
+    let response = client
+        .request(request)
+        .await
+        .context("sending schema registry request")?;
+    let status = response.status();
+    let content = hyper::body::to_bytes(response.into_body())
+        .await
+        .context("reading schema registry response")?;
+    if !status.is_success() {
+        bail!(
+            "Schema registry responded with {status}: {}",
+            String::from_utf8_lossy(&content)
+        );
+    }
+
+    let registered: RegisteredSchema =
+        serde_json::from_slice(&content).context("parsing schema registry response")?;
+    Ok(registered.id)
+}
+
+fn kafka_messages(message: &StreamMessage) -> Result<Vec<KafkaMessage>> {
+    let messages = match message {
+        StreamMessage::Order(order) => vec![KafkaMessage {
+            topic: Topic::Orders,
+            key: order.client_order_id.to_string(),
+            payload: serde_json::to_value(order)?,
+        }],
+        StreamMessage::Fill(fill) => vec![KafkaMessage {
+            topic: Topic::Fills,
+            key: fill.client_order_id.to_string(),
+            payload: serde_json::to_value(fill)?,
+        }],
+        StreamMessage::Balances(balances) => vec![KafkaMessage {
+            topic: Topic::Balances,
+            key: balances.exchange_account_id.to_string(),
+            payload: serde_json::to_value(balances)?,
+        }],
+        StreamMessage::Stats { markets } => markets
+            .iter()
+            .map(|market| {
+                Ok(KafkaMessage {
+                    topic: Topic::Stats,
+                    key: market.exchange_account_id.to_string(),
+                    payload: serde_json::to_value(market)?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        StreamMessage::Liquidity(_) => Vec::new(),
+    };
+
+    Ok(messages)
+}
+
+fn parse_avro_schemas() -> Result<HashMap<Topic, Value>> {
+    Topic::ALL
+        .into_iter()
+        .map(|topic| {
+            let schema = serde_json::from_str(topic.avro_schema())
+                .with_context(|| format!("parsing Avro schema of topic {topic:?}"))?;
+            Ok((topic, schema))
+        })
+        .collect()
+}
+
+struct RegisteredAvroSchema {
+    id: u32,
+    schema: Value,
+}
+
+/// Serializes messages as JSON or as Avro with schemas registered for topics
+struct MessageEncoder {
+    avro_schemas: Option<HashMap<Topic, RegisteredAvroSchema>>,
+}
+
+impl MessageEncoder {
+    fn encode(&self, message: &KafkaMessage) -> Result<Vec<u8>> {
+        let Some(avro_schemas) = &self.avro_schemas else {
+            return Ok(serde_json::to_vec(&message.payload)?);
+        };
+
+        let avro_schema = avro_schemas
+            .get(&message.topic)
+            .with_context(|| format!("schema of topic {:?} isn't registered", message.topic))?;
+
+        let mut buffer = vec![AVRO_MAGIC_BYTE];
+        buffer.extend_from_slice(&avro_schema.id.to_be_bytes());
+        write_avro(&avro_schema.schema, &message.payload, &mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+/// Writes JSON value in Avro binary encoding. Only records, arrays, unions with null and
+/// primitive types used by schemas of this module are supported
+fn write_avro(schema: &Value, value: &Value, buffer: &mut Vec<u8>) -> Result<()> {
+    match schema {
+        Value::String(type_name) => write_avro_primitive(type_name, value, buffer),
+        Value::Array(branches) => {
+            let index = branches
+                .iter()
+                .position(|branch| (branch == "null") == value.is_null())
+                .with_context(|| format!("no branch of union {schema} for {value}"))?;
+            write_avro_long(index as i64, buffer);
+            write_avro(&branches[index], value, buffer)
+        }
+        Value::Object(schema) => match schema.get("type") {
+            Some(Value::String(type_name)) if type_name == "record" => {
+                let fields = schema
+                    .get("fields")
+                    .and_then(Value::as_array)
+                    .context("record schema without fields")?;
+                for field in fields {
+                    let name = field["name"].as_str().context("field without name")?;
+                    let field_value = value.get(name).unwrap_or(&Value::Null);
+                    write_avro(&field["type"], field_value, buffer)
+                        .with_context(|| format!("writing field {name}"))?;
+                }
+                Ok(())
+            }
+            Some(Value::String(type_name)) if type_name == "array" => {
+                let items = value
+                    .as_array()
+                    .with_context(|| format!("expected array instead of {value}"))?;
+                if !items.is_empty() {
+                    write_avro_long(items.len() as i64, buffer);
+                    for item in items {
+                        write_avro(&schema["items"], item, buffer)?;
+                    }
+                }
+                write_avro_long(0, buffer);
+                Ok(())
+            }
+            Some(item_type) => write_avro(item_type, value, buffer),
+            None => bail!("schema without type: {value}"),
+        },
+        _ => bail!("unsupported Avro schema {schema}"),
+    }
+}
+
+fn write_avro_primitive(type_name: &str, value: &Value, buffer: &mut Vec<u8>) -> Result<()> {
+    match (type_name, value) {
+        ("null", Value::Null) => {}
+        ("boolean", Value::Bool(value)) => buffer.push(u8::from(*value)),
+        ("long" | "int", Value::Number(number)) => write_avro_long(
+            number
+                .as_i64()
+                .with_context(|| format!("{number} doesn't fit to long"))?,
+            buffer,
+        ),
+        ("double", Value::Number(number)) => buffer.extend_from_slice(
+            &number
+                .as_f64()
+                .with_context(|| format!("{number} isn't double"))?
+                .to_le_bytes(),
+        ),
+        ("string", Value::String(value)) => {
+            write_avro_long(value.len() as i64, buffer);
+            buffer.extend_from_slice(value.as_bytes());
+        }
+        _ => bail!("unable to write {value} as Avro {type_name}"),
+    }
+
+    Ok(())
+}
+
+/// Zig-zag encoded variable-length integer
+fn write_avro_long(value: i64, buffer: &mut Vec<u8>) {
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::event_stream::{
+        BalanceMessage, BalancesMessage, FillMessage, MarketStatsMessage, OrderMessage,
+    };
+    use crate::statistic_service::MarketAccountIdStatistic;
+    use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+    use mmb_domain::order::snapshot::{ClientOrderId, OrderFillRole, OrderSide};
+    use rust_decimal_macros::dec;
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", 0)
+    }
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    fn stream_messages() -> Vec<StreamMessage> {
+        let client_order_id = ClientOrderId::from("order_1");
+        vec![
+            StreamMessage::Order(OrderMessage {
+                event: "CreateOrderSucceeded",
+                client_order_id: client_order_id.clone(),
+                exchange_order_id: None,
+                exchange_account_id: exchange_account_id(),
+                currency_pair: currency_pair(),
+                side: OrderSide::Buy,
+                price: dec!(100),
+                amount: dec!(2),
+                filled_amount: dec!(0),
+                status: "Created".to_owned(),
+            }),
+            StreamMessage::Fill(FillMessage {
+                client_order_id,
+                exchange_account_id: exchange_account_id(),
+                currency_pair: currency_pair(),
+                side: OrderSide::Buy,
+                price: dec!(100),
+                amount: dec!(1),
+                role: OrderFillRole::Maker,
+                commission_currency_code: "usdt".into(),
+                commission_amount: dec!(0.1),
+            }),
+            StreamMessage::Balances(BalancesMessage {
+                exchange_account_id: exchange_account_id(),
+                balances: vec![BalanceMessage {
+                    currency_code: "btc".into(),
+                    balance: dec!(1.5),
+                }],
+            }),
+            StreamMessage::Stats {
+                markets: vec![MarketStatsMessage {
+                    exchange_account_id: exchange_account_id(),
+                    currency_pair: currency_pair(),
+                    stats: MarketAccountIdStatistic {
+                        fully_filled_orders_count: 3,
+                        filled_orders_per_hour: Some(dec!(1.5)),
+                        ..Default::default()
+                    },
+                }],
+            },
+        ]
+    }
+
+    #[test]
+    fn messages_are_routed_to_topics_by_kind() {
+        let messages = stream_messages()
+            .iter()
+            .flat_map(|x| kafka_messages(x).expect("in test"))
+            .collect::<Vec<_>>();
+
+        let topics = messages.iter().map(|x| x.topic).collect::<Vec<_>>();
+        assert_eq!(topics, Topic::ALL.to_vec());
+        assert_eq!(messages[0].key, "order_1");
+        assert_eq!(messages[2].key, "Binance_0");
+        assert_eq!(messages[3].payload["fully_filled_orders_count"], 3);
+    }
+
+    #[test]
+    fn every_message_is_written_by_its_avro_schema() {
+        let avro_schemas = parse_avro_schemas()
+            .expect("in test")
+            .into_iter()
+            .zip(1..)
+            .map(|((topic, schema), id)| (topic, RegisteredAvroSchema { id, schema }))
+            .collect();
+        let encoder = MessageEncoder {
+            avro_schemas: Some(avro_schemas),
+        };
+
+        for stream_message in stream_messages() {
+            for message in kafka_messages(&stream_message).expect("in test") {
+                let payload = encoder.encode(&message).expect("in test");
+                let schema_id = encoder.avro_schemas.as_ref().expect("in test")[&message.topic].id;
+                assert_eq!(payload[0], AVRO_MAGIC_BYTE);
+                assert_eq!(payload[1..5], schema_id.to_be_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn avro_binary_encoding() {
+        let schema = serde_json::json!({
+            "type": "record",
+            "name": "Test",
+            "fields": [
+                {"name": "a", "type": "long"},
+                {"name": "b", "type": ["null", "string"]},
+                {"name": "c", "type": {"type": "array", "items": "string"}}
+            ]
+        });
+        let value = serde_json::json!({"a": -65, "b": "hi", "c": ["x"]});
+
+        let mut buffer = Vec::new();
+        write_avro(&schema, &value, &mut buffer).expect("in test");
+
+        assert_eq!(
+            buffer,
+            vec![0x81, 0x01, 0x02, 0x04, b'h', b'i', 0x02, 0x02, b'x', 0x00]
+        );
+
+        let mut buffer = Vec::new();
+        write_avro(&schema, &serde_json::json!({"a": 0, "c": []}), &mut buffer).expect("in test");
+        assert_eq!(buffer, vec![0x00, 0x00, 0x00]);
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Duration as ChronoDuration;
+use dashmap::DashMap;
+use mmb_domain::market::ExchangeAccountId;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+
+use crate::exchanges::block_reasons::HIGH_ORDER_LATENCY;
+use crate::exchanges::exchange_blocker::{BlockType, ExchangeBlocker};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::timeouts::order_rate_limiter::OrderRequestKind;
+use crate::infrastructure::spawn_by_timer;
+use crate::misc::clock::Clock;
+use crate::services::signals::QuotingMode;
+use crate::settings::{ExchangeSettings, LatencyThrottlingSettings};
+use crate::statistic_service::StatisticService;
+
+const LATENCY_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+/// Throttles quoting of exchange accounts by round-trip latency of order creations and
+/// cancellations. Spread is widened or exchange account is blocked while latency is degraded
+pub struct LatencyThrottlingService {
+    settings: HashMap<ExchangeAccountId, LatencyThrottlingSettings>,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    exchange_blocker: Arc<ExchangeBlocker>,
+    statistic_service: Arc<StatisticService>,
+    clock: Arc<dyn Clock>,
+    modes: Mutex<HashMap<ExchangeAccountId, QuotingMode>>,
+}
+
+impl LatencyThrottlingService {
+    pub fn new(
+        exchange_settings: &[ExchangeSettings],
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        exchange_blocker: Arc<ExchangeBlocker>,
+        statistic_service: Arc<StatisticService>,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        let settings = exchange_settings
+            .iter()
+            .filter_map(|x| Some((x.exchange_account_id, x.latency_throttling.clone()?)))
+            .collect();
+
+        Arc::new(LatencyThrottlingService {
+            settings,
+            exchanges,
+            exchange_blocker,
+            statistic_service,
+            clock,
+            modes: Default::default(),
+        })
+    }
+
+    /// Starts periodical check of latencies if throttling is configured for any exchange account
+    pub fn start(self: &Arc<Self>) {
+        if self.settings.is_empty() {
+            return;
+        }
+
+        let service = self.clone();
+        spawn_by_timer(
+            "Throttle quoting by latency of order requests",
+            Duration::ZERO,
+            LATENCY_CHECK_PERIOD,
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            move || {
+                service.update_modes(service.clock.now());
+                async {}
+            },
+        );
+    }
+
+    /// How exchange account should be quoted according to latency of its order requests
+    pub fn quoting_mode(&self, exchange_account_id: ExchangeAccountId) -> QuotingMode {
+        self.modes
+            .lock()
+            .get(&exchange_account_id)
+            .copied()
+            .unwrap_or(QuotingMode::Normal)
+    }
+
+    fn update_modes(&self, now: DateTime) {
+        for (&exchange_account_id, settings) in &self.settings {
+            let latency = match self.exchanges.get(&exchange_account_id) {
+                Some(exchange) => max_order_latency(&exchange, now, settings),
+                None => continue,
+            };
+            self.update_mode(exchange_account_id, latency, settings);
+        }
+    }
+
+    fn update_mode(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        latency: Option<ChronoDuration>,
+        settings: &LatencyThrottlingSettings,
+    ) {
+        let latency_millis = latency.map_or(0, |x| x.num_milliseconds().max(0) as u64);
+        let mode = if latency_millis > settings.pause_latency_millis {
+            QuotingMode::Disabled
+        } else if latency_millis > settings.widen_latency_millis {
+            QuotingMode::Widened(settings.spread_multiplier)
+        } else {
+            QuotingMode::Normal
+        };
+
+        let previous_mode = self
+            .modes
+            .lock()
+            .insert(exchange_account_id, mode)
+            .unwrap_or(QuotingMode::Normal);
+        if previous_mode == mode {
+            return;
+        }
+
+        log::warn!(
+            "Quoting mode of {exchange_account_id} is changed from {previous_mode:?} to {mode:?} by order requests latency {latency_millis}ms"
+        );
+        match (previous_mode, mode) {
+            (_, QuotingMode::Disabled) => self.exchange_blocker.block(
+                exchange_account_id,
+                HIGH_ORDER_LATENCY,
+                BlockType::Manual,
+            ),
+            (QuotingMode::Disabled, _) => self
+                .exchange_blocker
+                .unblock(exchange_account_id, HIGH_ORDER_LATENCY),
+            _ => {}
+        }
+        self.statistic_service
+            .register_latency_throttling(exchange_account_id, mode);
+    }
+}
+
+/// The worst of median latencies of order creations and cancellations
+fn max_order_latency(
+    exchange: &Exchange,
+    now: DateTime,
+    settings: &LatencyThrottlingSettings,
+) -> Option<ChronoDuration> {
+    let window = ChronoDuration::seconds(settings.window_secs as i64);
+    [OrderRequestKind::Create, OrderRequestKind::Cancel]
+        .into_iter()
+        .filter_map(|kind| exchange.order_latency(kind, now, window))
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::init_lifetime_manager;
+    use crate::misc::clock::SystemClock;
+    use crate::misc::trading_calendar::TradingCalendar;
+    use mmb_utils::cancellation_token::CancellationToken;
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn quoting_is_throttled_by_latency() {
+        let _ = init_lifetime_manager();
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        let exchange_blocker = ExchangeBlocker::new(vec![exchange_account_id]);
+        let statistic_service =
+            StatisticService::new(TradingCalendar::new(None), SystemClock::shared());
+        let settings = LatencyThrottlingSettings {
+            widen_latency_millis: 200,
+            pause_latency_millis: 1000,
+            spread_multiplier: dec!(3),
+            window_secs: 60,
+        };
+        let service = LatencyThrottlingService::new(
+            &[],
+            DashMap::new(),
+            exchange_blocker.clone(),
+            statistic_service.clone(),
+            SystemClock::shared(),
+        );
+        let update = |millis: i64| {
+            service.update_mode(
+                exchange_account_id,
+                Some(ChronoDuration::milliseconds(millis)),
+                &settings,
+            );
+            service.quoting_mode(exchange_account_id)
+        };
+
+        assert_eq!(update(100), QuotingMode::Normal);
+        assert_eq!(update(500), QuotingMode::Widened(dec!(3)));
+        assert_eq!(update(1500), QuotingMode::Disabled);
+        assert!(exchange_blocker.is_blocked_by_reason(exchange_account_id, HIGH_ORDER_LATENCY));
+        assert_eq!(update(100), QuotingMode::Normal);
+        exchange_blocker
+            .wait_unblock_with_reason(
+                exchange_account_id,
+                HIGH_ORDER_LATENCY,
+                CancellationToken::default(),
+            )
+            .await;
+
+        let stats = statistic_service.exchange_account_stats()[&exchange_account_id].clone();
+        assert_eq!(stats.latency_widenings_count, 1);
+        assert_eq!(stats.latency_pauses_count, 1);
+        assert_eq!(stats.latency_recoveries_count, 1);
+    }
+}
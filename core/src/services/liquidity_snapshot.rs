@@ -0,0 +1,260 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use mmb_domain::events::{ExchangeEvent, Trade, TradesEvent};
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId, MarketAccountId};
+use mmb_domain::order::event::{OrderEvent, OrderEventType};
+use mmb_domain::order::pool::{OrdersFilter, OrdersPool};
+use mmb_domain::order::snapshot::{Amount, ClientOrderId, OrderSide, OrderStatus, Price};
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LiquidityOrder {
+    pub client_order_id: ClientOrderId,
+    pub side: OrderSide,
+    pub price: Price,
+    pub amount: Amount,
+    pub filled_amount: Amount,
+}
+
+/// State of trade place drawn by "liquidity view" of visualization web app
+#[derive(Debug, Clone, Serialize)]
+pub struct LiquiditySnapshot {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub time: DateTime,
+    pub asks: Vec<(Price, Amount)>,
+    pub bids: Vec<(Price, Amount)>,
+    /// Our limit orders which are open on exchange
+    pub orders: Vec<LiquidityOrder>,
+    /// The latest public trades, the newest is the last one
+    pub recent_trades: Vec<Trade>,
+    /// Bought minus sold amount by our fills since engine start
+    pub position: Amount,
+    /// PnL of our fills since engine start in quote currency. Position is valued by middle price
+    /// of order book, so PnL isn't specified while order book is empty
+    pub pnl: Option<Decimal>,
+}
+
+#[derive(Default)]
+struct TradePlaceState {
+    recent_trades: VecDeque<Trade>,
+    position: Amount,
+    /// Quote currency received by sells minus paid by buys and commissions
+    cash_flow: Decimal,
+}
+
+impl TradePlaceState {
+    fn add_trades(&mut self, trades: &[Trade], recent_trades_count: usize) {
+        self.recent_trades.extend(trades.iter().cloned());
+        let excess = self.recent_trades.len().saturating_sub(recent_trades_count);
+        let _ = self.recent_trades.drain(..excess);
+    }
+
+    fn add_fill(&mut self, side: OrderSide, price: Price, amount: Amount, commission: Decimal) {
+        let volume = price * amount;
+        match side {
+            OrderSide::Buy => {
+                self.position += amount;
+                self.cash_flow -= volume;
+            }
+            OrderSide::Sell => {
+                self.position -= amount;
+                self.cash_flow += volume;
+            }
+        }
+        self.cash_flow -= commission;
+    }
+
+    fn pnl(&self, middle_price: Option<Price>) -> Option<Decimal> {
+        Some(self.cash_flow + self.position * middle_price?)
+    }
+}
+
+/// Collects order books, public trades and our fills from engine events and assembles
+/// `LiquiditySnapshot` for trade places changed since the previous assembling
+pub struct LiquiditySnapshotBuilder {
+    depth: usize,
+    recent_trades_count: usize,
+    order_books: LocalSnapshotsService,
+    trade_places: HashMap<MarketAccountId, TradePlaceState>,
+    changed: HashSet<MarketAccountId>,
+}
+
+impl LiquiditySnapshotBuilder {
+    pub fn new(depth: usize, recent_trades_count: usize) -> Self {
+        LiquiditySnapshotBuilder {
+            depth,
+            recent_trades_count,
+            order_books: LocalSnapshotsService::default(),
+            trade_places: HashMap::new(),
+            changed: HashSet::new(),
+        }
+    }
+
+    pub fn update(&mut self, event: &ExchangeEvent) {
+        let changed = match event {
+            ExchangeEvent::OrderBookEvent(event) => self.order_books.update(event),
+            ExchangeEvent::Trades(event) => Some(self.add_trades(event)),
+            ExchangeEvent::OrderEvent(event) => self.add_order_event(event),
+            _ => None,
+        };
+
+        if let Some(market_account_id) = changed {
+            let _ = self.changed.insert(market_account_id);
+        }
+    }
+
+    fn add_trades(&mut self, event: &TradesEvent) -> MarketAccountId {
+        let market_account_id =
+            MarketAccountId::new(event.exchange_account_id, event.currency_pair);
+        self.trade_places
+            .entry(market_account_id)
+            .or_default()
+            .add_trades(&event.trades, self.recent_trades_count);
+        market_account_id
+    }
+
+    fn add_order_event(&mut self, event: &OrderEvent) -> Option<MarketAccountId> {
+        let market_account_id = MarketAccountId::new(
+            event.order.exchange_account_id(),
+            event.order.currency_pair(),
+        );
+        match &event.event_type {
+            OrderEventType::OrderFilled { cloned_order } => {
+                let fill = cloned_order.fills.fills.last()?;
+                let quote = cloned_order.header.currency_pair.to_codes().quote;
+                let commission = match fill.commission_currency_code() == quote {
+                    true => fill.commission_amount(),
+                    false => Decimal::ZERO,
+                };
+                self.trade_places
+                    .entry(market_account_id)
+                    .or_default()
+                    .add_fill(
+                        fill.side().unwrap_or(cloned_order.header.side),
+                        fill.price(),
+                        fill.amount(),
+                        commission,
+                    );
+            }
+            OrderEventType::CreateOrderSucceeded
+            | OrderEventType::OrderCompleted { .. }
+            | OrderEventType::CancelOrderSucceeded => {}
+            _ => return None,
+        }
+
+        Some(market_account_id)
+    }
+
+    /// Returns trade places changed since the previous call
+    pub fn take_changed(&mut self) -> Vec<MarketAccountId> {
+        self.changed.drain().collect()
+    }
+
+    /// `orders` is orders pool of exchange account of trade place. Snapshot isn't assembled until
+    /// order book snapshot is received
+    pub fn build(
+        &self,
+        market_account_id: MarketAccountId,
+        orders: &OrdersPool,
+        time: DateTime,
+    ) -> Option<LiquiditySnapshot> {
+        let market_id = market_account_id.market_id();
+        let order_book = self.order_books.get_snapshot(market_id)?;
+        let levels = |levels: &mut dyn Iterator<Item = (&Price, &Amount)>| {
+            levels
+                .take(self.depth)
+                .map(|(price, amount)| (*price, *amount))
+                .collect()
+        };
+
+        let no_state = TradePlaceState::default();
+        let state = self
+            .trade_places
+            .get(&market_account_id)
+            .unwrap_or(&no_state);
+
+        Some(LiquiditySnapshot {
+            exchange_account_id: market_account_id.exchange_account_id,
+            currency_pair: market_account_id.currency_pair,
+            time,
+            asks: levels(&mut order_book.get_asks_price_levels()),
+            bids: levels(&mut order_book.get_bids_price_levels()),
+            orders: open_orders(market_account_id, orders),
+            recent_trades: state.recent_trades.iter().cloned().collect(),
+            position: state.position,
+            pnl: state.pnl(order_book.calculate_middle_price(market_id)),
+        })
+    }
+}
+
+fn open_orders(market_account_id: MarketAccountId, orders: &OrdersPool) -> Vec<LiquidityOrder> {
+    let filter = OrdersFilter {
+        exchange_account_id: Some(market_account_id.exchange_account_id),
+        currency_pair: Some(market_account_id.currency_pair),
+        statuses: vec![OrderStatus::Created, OrderStatus::Canceling],
+        ..Default::default()
+    };
+    let mut open_orders: Vec<_> = orders
+        .select_not_finished(&filter, |order| {
+            // market orders aren't drawn
+            let price = order.header.source_price?;
+            Some(LiquidityOrder {
+                client_order_id: order.header.client_order_id.clone(),
+                side: order.header.side,
+                price,
+                amount: order.header.amount,
+                filled_amount: order.filled_amount(),
+            })
+        })
+        .into_iter()
+        .flatten()
+        .collect();
+    open_orders.sort_by_key(|x| std::cmp::Reverse(x.price));
+    open_orders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::events::TradeId;
+    use rust_decimal_macros::dec;
+
+    fn trade(trade_id: u64, price: Price) -> Trade {
+        Trade {
+            trade_id: TradeId::Number(trade_id),
+            price,
+            quantity: dec!(1),
+            side: OrderSide::Buy,
+            transaction_time: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn only_latest_trades_are_kept() {
+        let mut state = TradePlaceState::default();
+
+        state.add_trades(&[trade(1, dec!(10)), trade(2, dec!(11))], 3);
+        state.add_trades(&[trade(3, dec!(12)), trade(4, dec!(13))], 3);
+
+        let prices: Vec<_> = state.recent_trades.iter().map(|x| x.price).collect();
+        assert_eq!(prices, vec![dec!(11), dec!(12), dec!(13)]);
+    }
+
+    #[test]
+    fn pnl_includes_position_valued_by_middle_price() {
+        let mut state = TradePlaceState::default();
+
+        state.add_fill(OrderSide::Buy, dec!(100), dec!(2), dec!(0.2));
+        state.add_fill(OrderSide::Sell, dec!(110), dec!(1), dec!(0.1));
+
+        assert_eq!(state.position, dec!(1));
+        assert_eq!(state.pnl(None), None);
+        // -200 - 0.2 + 110 - 0.1 + 1 * 105
+        assert_eq!(state.pnl(Some(dec!(105))), Some(dec!(14.7)));
+    }
+}
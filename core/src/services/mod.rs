@@ -1,6 +1,19 @@
+pub mod candles;
 pub mod cleanup_database;
 pub mod cleanup_orders;
+pub mod cross_rates;
+pub mod eod_report;
+pub mod event_queue;
+pub mod event_stream;
 pub mod exchange_time_latency;
+pub mod index_price;
+pub mod kafka_publisher;
+pub mod latency_throttling;
+pub mod liquidity_snapshot;
 pub mod live_ranges;
 pub(crate) mod market_prices;
+pub mod redis_bridge;
+pub mod signals;
+pub mod trading_sessions;
 pub mod usd_convertion;
+pub mod volatility;
@@ -0,0 +1,356 @@
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use itertools::Itertools;
+use mmb_domain::events::ExchangeEvent;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use redis::AsyncCommands;
+use serde::Deserialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::trading_engine::{cancel_opened_orders, EngineContext};
+use crate::services::event_stream::{normalize, StatsTracker, StreamMessage};
+use crate::services::signals::SignalMessage;
+use crate::settings::{RedisBridgeEvent, RedisBridgeSettings};
+use crate::statistic_service::StatisticService;
+
+const REDIS_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Command received from Redis channel as JSON object with field `command`, e.g.
+/// `{"command":"pause","strategy":"market_maker"}`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum BridgeCommand {
+    /// Pauses strategy without cancellation of its orders. All running strategies are paused if
+    /// name isn't specified
+    Pause {
+        #[serde(default)]
+        strategy: Option<String>,
+    },
+    /// Resumes paused strategy. All paused strategies are resumed if name isn't specified
+    Resume {
+        #[serde(default)]
+        strategy: Option<String>,
+    },
+    /// Cancels open orders on all exchange accounts
+    CancelAll,
+    /// Parameters are pushed as signals, so strategies read them by `SignalService`
+    SetParameters { parameters: Vec<SignalMessage> },
+}
+
+/// Bridge between the engine and Redis pub/sub: publishes selected normalized engine events to
+/// one channel and executes commands received from another one
+pub struct RedisBridge {
+    settings: RedisBridgeSettings,
+    statistics: Arc<StatisticService>,
+    engine_context: Weak<EngineContext>,
+}
+
+impl RedisBridge {
+    pub fn start(
+        settings: RedisBridgeSettings,
+        statistics: Arc<StatisticService>,
+        engine_context: Weak<EngineContext>,
+        events: broadcast::Receiver<ExchangeEvent>,
+        stop_token: CancellationToken,
+    ) -> Arc<Self> {
+        let bridge = Arc::new(RedisBridge {
+            settings,
+            statistics,
+            engine_context,
+        });
+
+        if let Some(channel) = bridge.settings.events_channel.clone() {
+            spawn_future(
+                "Publish events to Redis",
+                SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+                bridge
+                    .clone()
+                    .publish_events(channel, events, stop_token.clone()),
+            );
+        }
+
+        if let Some(channel) = bridge.settings.commands_channel.clone() {
+            spawn_future(
+                "Receive commands from Redis",
+                SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+                bridge.clone().receive_commands(channel, stop_token),
+            );
+        }
+
+        bridge
+    }
+
+    async fn publish_events(
+        self: Arc<Self>,
+        channel: String,
+        mut events: broadcast::Receiver<ExchangeEvent>,
+        stop_token: CancellationToken,
+    ) -> Result<()> {
+        let client =
+            redis::Client::open(self.settings.url.as_str()).context("opening Redis client")?;
+        let mut connection = None;
+
+        let mut stats_interval =
+            tokio::time::interval(Duration::from_millis(self.settings.stats_interval_millis));
+        let mut stats_tracker = StatsTracker::default();
+        let publish_stats = self.settings.events.contains(&RedisBridgeEvent::Stats);
+
+        loop {
+            let messages = tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => normalize(&event)
+                        .into_iter()
+                        .filter(|x| self.is_published(x))
+                        .collect_vec(),
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("RedisBridge skipped {skipped} events");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                _ = stats_interval.tick(), if publish_stats => {
+                    let markets = stats_tracker.changed(self.statistics.market_account_id_stats());
+                    match markets.is_empty() {
+                        true => continue,
+                        false => vec![StreamMessage::Stats { markets }],
+                    }
+                }
+                _ = stop_token.when_cancelled() => return Ok(()),
+            };
+
+            for message in messages {
+                if let Err(err) = publish(&client, &mut connection, &channel, &message).await {
+                    log::warn!("Failed to publish event to Redis channel {channel}: {err:?}");
+                }
+            }
+        }
+    }
+
+    fn is_published(&self, message: &StreamMessage) -> bool {
+        event_kind(message).is_some_and(|x| self.settings.events.contains(&x))
+    }
+
+    async fn receive_commands(
+        self: Arc<Self>,
+        channel: String,
+        stop_token: CancellationToken,
+    ) -> Result<()> {
+        while !stop_token.is_cancellation_requested() {
+            if let Err(err) = self.subscribe_commands(&channel, &stop_token).await {
+                log::warn!("Receiving commands from Redis channel {channel} failed: {err:?}");
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(REDIS_RECONNECT_DELAY) => {}
+                _ = stop_token.when_cancelled() => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn subscribe_commands(
+        &self,
+        channel: &str,
+        stop_token: &CancellationToken,
+    ) -> Result<()> {
+        let client =
+            redis::Client::open(self.settings.url.as_str()).context("opening Redis client")?;
+        let mut pubsub = client
+            .get_async_connection()
+            .await
+            .context("connecting to Redis")?
+            .into_pubsub();
+        pubsub
+            .subscribe(channel)
+            .await
+            .context("subscribing to Redis channel")?;
+        log::info!("Subscribed to commands Redis channel {channel}");
+
+        let mut messages = pubsub.on_message();
+        loop {
+            let message = tokio::select! {
+                message = messages.next() => match message {
+                    Some(message) => message,
+                    None => return Ok(()),
+                },
+                _ = stop_token.when_cancelled() => return Ok(()),
+            };
+
+            let payload: String = match message.get_payload() {
+                Ok(payload) => payload,
+                Err(err) => {
+                    log::warn!("Failed to get payload of Redis command: {err:?}");
+                    continue;
+                }
+            };
+
+            let command = match serde_json::from_str::<BridgeCommand>(&payload) {
+                Ok(command) => command,
+                Err(err) => {
+                    log::warn!("Failed to parse Redis command {payload}: {err:?}");
+                    continue;
+                }
+            };
+
+            let engine_context = match self.engine_context.upgrade() {
+                Some(engine_context) => engine_context,
+                None => return Ok(()),
+            };
+            match execute(&engine_context, command) {
+                Ok(result) => log::info!("Redis command {payload} is executed: {result}"),
+                Err(err) => log::warn!("Failed to execute Redis command {payload}: {err:#}"),
+            }
+        }
+    }
+}
+
+async fn publish(
+    client: &redis::Client,
+    connection: &mut Option<redis::aio::Connection>,
+    channel: &str,
+    message: &StreamMessage,
+) -> Result<()> {
+    let json = serde_json::to_string(message).context("serializing event")?;
+    // failed connection isn't put back, so it's reestablished on the next message
+    let mut opened = match connection.take() {
+        Some(opened) => opened,
+        None => client
+            .get_async_connection()
+            .await
+            .context("connecting to Redis")?,
+    };
+    opened
+        .publish::<_, _, ()>(channel, json)
+        .await
+        .context("publishing to Redis")?;
+    *connection = Some(opened);
+    Ok(())
+}
+
+fn event_kind(message: &StreamMessage) -> Option<RedisBridgeEvent> {
+    match message {
+        StreamMessage::Order(_) => Some(RedisBridgeEvent::Orders),
+        StreamMessage::Fill(_) => Some(RedisBridgeEvent::Fills),
+        StreamMessage::Balances(_) => Some(RedisBridgeEvent::Balances),
+        StreamMessage::Stats { .. } => Some(RedisBridgeEvent::Stats),
+        StreamMessage::Liquidity(_) => None,
+    }
+}
+
+fn execute(engine_context: &EngineContext, command: BridgeCommand) -> Result<String> {
+    let registry = &engine_context.strategy_registry;
+    match command {
+        BridgeCommand::Pause {
+            strategy: Some(name),
+        } => {
+            registry.pause(&name)?;
+            Ok(format!("Strategy '{name}' is paused"))
+        }
+        BridgeCommand::Pause { strategy: None } => {
+            // strategies which aren't running are skipped
+            let paused = registry
+                .names()
+                .iter()
+                .filter(|x| registry.pause(x).is_ok())
+                .count();
+            Ok(format!("{paused} strategies are paused"))
+        }
+        BridgeCommand::Resume {
+            strategy: Some(name),
+        } => {
+            registry.start(&name)?;
+            Ok(format!("Strategy '{name}' is resumed"))
+        }
+        BridgeCommand::Resume { strategy: None } => {
+            let resumed = registry
+                .names()
+                .iter()
+                .filter(|x| registry.start(x).is_ok())
+                .count();
+            Ok(format!("{resumed} strategies are resumed"))
+        }
+        BridgeCommand::CancelAll => {
+            let exchanges = engine_context.exchanges.clone();
+            let cancellation_token = engine_context.lifetime_manager.stop_token();
+            spawn_future(
+                "Cancel all orders requested via Redis",
+                SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+                async move {
+                    cancel_opened_orders(&exchanges, cancellation_token, true).await;
+                    Ok(())
+                },
+            );
+            Ok("Cancellation of all open orders is requested".to_owned())
+        }
+        BridgeCommand::SetParameters { parameters } => {
+            let count = parameters.len();
+            for parameter in parameters {
+                engine_context.signal_service.push(parameter);
+            }
+            Ok(format!("{count} parameters are updated"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::event_stream::{BalancesMessage, MarketStatsMessage};
+    use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn commands_are_parsed() {
+        let parse = |json| serde_json::from_str::<BridgeCommand>(json).expect("in test");
+
+        assert_eq!(
+            parse(r#"{"command":"pause","strategy":"market_maker"}"#),
+            BridgeCommand::Pause {
+                strategy: Some("market_maker".to_owned())
+            }
+        );
+        assert_eq!(
+            parse(r#"{"command":"resume"}"#),
+            BridgeCommand::Resume { strategy: None }
+        );
+        assert_eq!(
+            parse(r#"{"command":"cancel_all"}"#),
+            BridgeCommand::CancelAll
+        );
+
+        let BridgeCommand::SetParameters { parameters } = parse(
+            r#"{"command":"set_parameters","parameters":[{"name":"spread","value":"0.002"}]}"#,
+        ) else {
+            panic!("set_parameters command is expected");
+        };
+        assert_eq!(parameters[0].name, "spread");
+        assert_eq!(parameters[0].value, dec!(0.002));
+
+        assert!(serde_json::from_str::<BridgeCommand>(r#"{"command":"unknown"}"#).is_err());
+    }
+
+    #[test]
+    fn messages_are_mapped_to_event_kinds() {
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        let balances = StreamMessage::Balances(BalancesMessage {
+            exchange_account_id,
+            balances: Vec::new(),
+        });
+        let stats = StreamMessage::Stats {
+            markets: vec![MarketStatsMessage {
+                exchange_account_id,
+                currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+                stats: Default::default(),
+            }],
+        };
+
+        assert_eq!(event_kind(&balances), Some(RedisBridgeEvent::Balances));
+        assert_eq!(event_kind(&stats), Some(RedisBridgeEvent::Stats));
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use chrono::Duration;
+use futures::StreamExt;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::infrastructure::{spawn_by_timer, spawn_future};
+use crate::misc::clock::Clock;
+use crate::settings::{RedisSignalsSettings, SignalsSettings};
+
+const REDIS_RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Signal value pushed by external source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignalMessage {
+    pub name: String,
+    pub value: Decimal,
+    /// Time when the value was calculated, time of receiving is used if it isn't specified
+    #[serde(default)]
+    pub timestamp: Option<DateTime>,
+    /// Lifetime of the value, default TTL from settings is used if it isn't specified
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Signal {
+    pub value: Decimal,
+    pub timestamp: DateTime,
+    pub expiration: DateTime,
+}
+
+/// How strategy should quote according to freshness of signals used by it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotingMode {
+    Normal,
+    /// Some signal is stale, spread should be multiplied by the value
+    Widened(Decimal),
+    /// Some signal is missing or stale for too long, orders shouldn't be placed
+    Disabled,
+}
+
+impl QuotingMode {
+    /// The most restrictive of two modes, so spread is widened by the largest multiplier
+    pub fn most_restrictive(self, other: QuotingMode) -> QuotingMode {
+        match (self, other) {
+            (QuotingMode::Disabled, _) | (_, QuotingMode::Disabled) => QuotingMode::Disabled,
+            (QuotingMode::Widened(left), QuotingMode::Widened(right)) => {
+                QuotingMode::Widened(left.max(right))
+            }
+            (QuotingMode::Widened(multiplier), QuotingMode::Normal)
+            | (QuotingMode::Normal, QuotingMode::Widened(multiplier)) => {
+                QuotingMode::Widened(multiplier)
+            }
+            (QuotingMode::Normal, QuotingMode::Normal) => QuotingMode::Normal,
+        }
+    }
+}
+
+/// Latest values of named numeric signals pushed by external sources (Redis channel, control
+/// panel HTTP API, watched file). Value becomes stale after its TTL is expired
+pub struct SignalService {
+    settings: SignalsSettings,
+    clock: Arc<dyn Clock>,
+    signals: Mutex<HashMap<String, Signal>>,
+}
+
+impl SignalService {
+    pub fn new(settings: SignalsSettings, clock: Arc<dyn Clock>) -> Arc<Self> {
+        Arc::new(SignalService {
+            settings,
+            clock,
+            signals: Default::default(),
+        })
+    }
+
+    /// Starts receiving signals from sources specified in settings
+    pub fn start(self: &Arc<Self>, stop_token: CancellationToken) {
+        if let Some(path) = self.settings.file.clone() {
+            let period = std::time::Duration::from_millis(self.settings.file_poll_interval_millis);
+            let modified = Mutex::new(None);
+            spawn_by_timer(
+                "Watch signals file",
+                std::time::Duration::ZERO,
+                period,
+                SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+                {
+                    let this = self.clone();
+                    move || {
+                        if let Err(err) = this.read_file_if_changed(&path, &modified) {
+                            log::warn!("Failed to read signals file: {err:?}");
+                        }
+                        async {}
+                    }
+                },
+            );
+        }
+
+        if let Some(redis) = self.settings.redis.clone() {
+            spawn_future(
+                "Receive signals from Redis",
+                SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+                self.clone().receive_from_redis(redis, stop_token),
+            );
+        }
+    }
+
+    pub fn push(&self, message: SignalMessage) {
+        let timestamp = message.timestamp.unwrap_or_else(|| self.clock.now());
+        let ttl_secs = message.ttl_secs.unwrap_or(self.settings.default_ttl_secs);
+        let signal = Signal {
+            value: message.value,
+            timestamp,
+            expiration: timestamp + Duration::seconds(ttl_secs as i64),
+        };
+
+        let mut signals = self.signals.lock();
+        match signals.get(&message.name) {
+            // values are received from several sources, so older ones can come later
+            Some(existing) if existing.timestamp > signal.timestamp => {}
+            _ => {
+                let _ = signals.insert(message.name, signal);
+            }
+        }
+    }
+
+    /// Parses JSON with single message or list of messages
+    pub fn push_json(&self, json: &str) -> Result<usize> {
+        let messages = parse_messages(json)?;
+        let count = messages.len();
+        for message in messages {
+            self.push(message);
+        }
+        Ok(count)
+    }
+
+    /// Latest not expired value of signal
+    pub fn value(&self, name: &str) -> Option<Decimal> {
+        let now = self.clock.now();
+        self.signals
+            .lock()
+            .get(name)
+            .filter(|x| x.expiration >= now)
+            .map(|x| x.value)
+    }
+
+    /// Latest value of signal even if it's expired
+    pub fn signal(&self, name: &str) -> Option<Signal> {
+        self.signals.lock().get(name).copied()
+    }
+
+    pub fn quoting_mode(&self, names: &[String]) -> QuotingMode {
+        let now = self.clock.now();
+        let disable_after = Duration::seconds(self.settings.disable_after_stale_secs as i64);
+
+        let signals = self.signals.lock();
+        let mut mode = QuotingMode::Normal;
+        for name in names {
+            match signals.get(name) {
+                None => return QuotingMode::Disabled,
+                Some(signal) if signal.expiration + disable_after < now => {
+                    return QuotingMode::Disabled
+                }
+                Some(signal) if signal.expiration < now => {
+                    mode = QuotingMode::Widened(self.settings.stale_spread_multiplier)
+                }
+                Some(_) => {}
+            }
+        }
+        mode
+    }
+
+    /// All signals as JSON
+    pub fn signals_json(&self) -> Result<String> {
+        serde_json::to_string(&*self.signals.lock()).context("serializing signals")
+    }
+
+    fn read_file_if_changed(
+        &self,
+        path: &Path,
+        modified: &Mutex<Option<SystemTime>>,
+    ) -> Result<()> {
+        let file_modified = fs::metadata(path)
+            .and_then(|x| x.modified())
+            .with_context(|| format!("getting modification time of {}", path.display()))?;
+        if *modified.lock() == Some(file_modified) {
+            return Ok(());
+        }
+
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("reading signals file {}", path.display()))?;
+        *modified.lock() = Some(file_modified);
+        let count = self.push_json(&json)?;
+        log::info!("{count} signals are read from {}", path.display());
+        Ok(())
+    }
+
+    async fn receive_from_redis(
+        self: Arc<Self>,
+        settings: RedisSignalsSettings,
+        stop_token: CancellationToken,
+    ) -> Result<()> {
+        while !stop_token.is_cancellation_requested() {
+            if let Err(err) = self.subscribe_redis(&settings).await {
+                log::warn!(
+                    "Receiving signals from Redis channel {} failed: {err:?}",
+                    settings.channel
+                );
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(REDIS_RECONNECT_DELAY) => {}
+                _ = stop_token.when_cancelled() => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn subscribe_redis(&self, settings: &RedisSignalsSettings) -> Result<()> {
+        let client = redis::Client::open(settings.url.as_str()).context("opening Redis client")?;
+        let mut pubsub = client
+            .get_async_connection()
+            .await
+            .context("connecting to Redis")?
+            .into_pubsub();
+        pubsub
+            .subscribe(&settings.channel)
+            .await
+            .context("subscribing to Redis channel")?;
+        log::info!("Subscribed to signals Redis channel {}", settings.channel);
+
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            let payload: String = match message.get_payload() {
+                Ok(payload) => payload,
+                Err(err) => {
+                    log::warn!("Failed to get payload of Redis signal: {err:?}");
+                    continue;
+                }
+            };
+            if let Err(err) = self.push_json(&payload) {
+                log::warn!("Failed to parse Redis signal {payload}: {err:?}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_messages(json: &str) -> Result<Vec<SignalMessage>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Messages {
+        Single(SignalMessage),
+        List(Vec<SignalMessage>),
+    }
+
+    let messages = serde_json::from_str(json).context("parsing signal messages")?;
+    Ok(match messages {
+        Messages::Single(message) => vec![message],
+        Messages::List(messages) => messages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::misc::clock::VirtualClock;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn service() -> (Arc<SignalService>, Arc<VirtualClock>) {
+        let clock = VirtualClock::new(
+            chrono::Utc
+                .timestamp_opt(1_000_000, 0)
+                .single()
+                .expect("valid time"),
+        );
+        let settings = SignalsSettings {
+            default_ttl_secs: 10,
+            stale_spread_multiplier: dec!(2),
+            disable_after_stale_secs: 20,
+            ..Default::default()
+        };
+        (SignalService::new(settings, clock.clone()), clock)
+    }
+
+    #[test]
+    fn stale_signal_widens_and_then_disables_quoting() {
+        let (service, clock) = service();
+        let names = vec!["alpha".to_owned()];
+        assert_eq!(service.quoting_mode(&names), QuotingMode::Disabled);
+
+        let count = service
+            .push_json(r#"{"name":"alpha","value":1.5}"#)
+            .expect("valid message");
+        assert_eq!(count, 1);
+        assert_eq!(service.value("alpha"), Some(dec!(1.5)));
+        assert_eq!(service.quoting_mode(&names), QuotingMode::Normal);
+
+        clock.advance(std::time::Duration::from_secs(11));
+        assert_eq!(service.value("alpha"), None);
+        assert_eq!(service.quoting_mode(&names), QuotingMode::Widened(dec!(2)));
+
+        clock.advance(std::time::Duration::from_secs(20));
+        assert_eq!(service.quoting_mode(&names), QuotingMode::Disabled);
+    }
+
+    #[test]
+    fn older_value_doesnt_replace_newer_one() {
+        let (service, _) = service();
+        service
+            .push_json(
+                r#"[
+                    {"name":"alpha","value":"2","timestamp":"1970-01-12T13:46:40Z","ttl_secs":100},
+                    {"name":"alpha","value":"1","timestamp":"1970-01-12T13:46:30Z"}
+                ]"#,
+            )
+            .expect("valid messages");
+
+        let signal = service.signal("alpha").expect("signal exists");
+        assert_eq!(signal.value, dec!(2));
+        assert_eq!(service.value("alpha"), Some(dec!(2)));
+    }
+}
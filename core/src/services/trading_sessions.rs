@@ -0,0 +1,232 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use futures::future::join_all;
+use itertools::Itertools;
+use mmb_domain::market::{ExchangeAccountId, MarketAccountId};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_by_timer;
+use crate::misc::clock::Clock;
+use crate::misc::trading_calendar::is_within_window;
+use crate::settings::{MarketSessionSettings, TradingSessionsSettings};
+
+const SESSIONS_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+/// Enables and disables quoting of markets according to their trading sessions. Orders of market
+/// are canceled when its session is closed
+pub struct TradingSessionService {
+    sessions: HashMap<MarketAccountId, MarketSessionSettings>,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    clock: Arc<dyn Clock>,
+    closed_markets: Mutex<HashSet<MarketAccountId>>,
+}
+
+impl TradingSessionService {
+    pub fn new(
+        settings: Option<&TradingSessionsSettings>,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        let sessions = settings
+            .map(|x| x.markets.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|x| {
+                let market_account_id =
+                    MarketAccountId::new(x.exchange_account_id, x.currency_pair);
+                (market_account_id, x.clone())
+            })
+            .collect();
+
+        Arc::new(TradingSessionService {
+            sessions,
+            exchanges,
+            clock,
+            closed_markets: Default::default(),
+        })
+    }
+
+    /// Starts periodical check of sessions if any session is configured
+    pub fn start(self: &Arc<Self>, cancellation_token: CancellationToken) {
+        if self.sessions.is_empty() {
+            return;
+        }
+
+        let service = self.clone();
+        spawn_by_timer(
+            "Check trading sessions of markets",
+            Duration::ZERO,
+            SESSIONS_CHECK_PERIOD,
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            move || {
+                let service = service.clone();
+                let cancellation_token = cancellation_token.clone();
+                async move {
+                    let closed_markets = service.update_sessions(service.clock.now());
+                    let futures = closed_markets
+                        .into_iter()
+                        .map(|x| service.cancel_market_orders(x, cancellation_token.clone()));
+                    join_all(futures).await;
+                }
+            },
+        );
+    }
+
+    /// Whether market can be quoted at `time`. Market without configured session is always open
+    pub fn is_session_open(&self, market_account_id: MarketAccountId, time: DateTime) -> bool {
+        let session = match self.sessions.get(&market_account_id) {
+            None => return true,
+            Some(session) => session,
+        };
+
+        let time_of_day = time.time();
+        let is_within_windows = session.windows.is_empty()
+            || session.windows.iter().any(|x| match x.start <= x.end {
+                true => x.start <= time_of_day && time_of_day < x.end,
+                false => x.start <= time_of_day || time_of_day < x.end,
+            });
+
+        is_within_windows
+            && !session
+                .exceptions
+                .iter()
+                .any(|x| is_within_window(x, market_account_id.exchange_account_id, time))
+    }
+
+    /// Remembers current state of sessions. Returns markets which sessions are closed since
+    /// previous update
+    fn update_sessions(&self, now: DateTime) -> Vec<MarketAccountId> {
+        let mut closed_markets = self.closed_markets.lock();
+        let mut just_closed = Vec::new();
+        for &market_account_id in self.sessions.keys() {
+            if self.is_session_open(market_account_id, now) {
+                if closed_markets.remove(&market_account_id) {
+                    log::info!("Trading session of {market_account_id} is opened");
+                }
+            } else if closed_markets.insert(market_account_id) {
+                log::info!("Trading session of {market_account_id} is closed");
+                just_closed.push(market_account_id);
+            }
+        }
+
+        just_closed
+    }
+
+    async fn cancel_market_orders(
+        &self,
+        market_account_id: MarketAccountId,
+        cancellation_token: CancellationToken,
+    ) {
+        let exchange = match self.exchanges.get(&market_account_id.exchange_account_id) {
+            Some(exchange) => exchange.value().clone(),
+            None => {
+                log::error!(
+                    "Unable to cancel orders of closed session {market_account_id} because exchange isn't found"
+                );
+                return;
+            }
+        };
+
+        // orders placed outside of engine are handled by their own policy
+        let orders = exchange
+            .orders
+            .not_finished
+            .iter()
+            .filter(|x| {
+                x.currency_pair() == market_account_id.currency_pair
+                    && !x.order_type().is_external_order()
+            })
+            .map(|x| x.value().clone())
+            .collect_vec();
+
+        let futures = orders.into_iter().map(|order| {
+            let exchange = exchange.clone();
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                let client_order_id = order.client_order_id();
+                if let Err(err) = exchange
+                    .wait_cancel_order(order, None, true, cancellation_token)
+                    .await
+                {
+                    log::error!(
+                        "Failed to cancel order {client_order_id} of closed session {market_account_id}: {err:?}"
+                    );
+                }
+            }
+        });
+        join_all(futures).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::misc::clock::VirtualClock;
+    use crate::settings::{NonTradingWindowSettings, SessionWindowSettings};
+    use chrono::{NaiveTime, TimeZone, Utc};
+    use mmb_domain::market::CurrencyPair;
+
+    fn market_account_id() -> MarketAccountId {
+        MarketAccountId::new(
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    fn time(hour: u32, minute: u32) -> DateTime {
+        Utc.with_ymd_and_hms(2022, 6, 1, hour, minute, 0)
+            .single()
+            .expect("in test")
+    }
+
+    #[test]
+    fn session_is_closed_outside_of_windows_and_within_exceptions() {
+        let market_account_id = market_account_id();
+        let settings = TradingSessionsSettings {
+            markets: vec![MarketSessionSettings {
+                exchange_account_id: market_account_id.exchange_account_id,
+                currency_pair: market_account_id.currency_pair,
+                windows: vec![SessionWindowSettings {
+                    start: NaiveTime::from_hms_opt(22, 0, 0).expect("in test"),
+                    end: NaiveTime::from_hms_opt(10, 0, 0).expect("in test"),
+                }],
+                exceptions: vec![NonTradingWindowSettings::Daily {
+                    exchange_account_id: None,
+                    start: NaiveTime::from_hms_opt(23, 59, 0).expect("in test"),
+                    end: NaiveTime::from_hms_opt(0, 1, 0).expect("in test"),
+                }],
+            }],
+        };
+        let clock = VirtualClock::new(time(12, 0));
+        let service = TradingSessionService::new(Some(&settings), DashMap::new(), clock);
+
+        assert!(!service.is_session_open(market_account_id, time(12, 0)));
+        assert!(service.is_session_open(market_account_id, time(23, 0)));
+        assert!(!service.is_session_open(market_account_id, time(0, 0)));
+        assert!(service.is_session_open(market_account_id, time(9, 59)));
+
+        let other_market = MarketAccountId::new(
+            market_account_id.exchange_account_id,
+            CurrencyPair::from_codes("eth".into(), "usdt".into()),
+        );
+        assert!(service.is_session_open(other_market, time(12, 0)));
+
+        assert_eq!(
+            service.update_sessions(time(12, 0)),
+            vec![market_account_id]
+        );
+        assert!(service.update_sessions(time(12, 1)).is_empty());
+        assert!(service.update_sessions(time(23, 0)).is_empty());
+        assert_eq!(
+            service.update_sessions(time(23, 59)),
+            vec![market_account_id]
+        );
+    }
+}
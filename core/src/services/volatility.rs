@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use itertools::Itertools;
+use mmb_domain::market::MarketId;
+use rust_decimal::{Decimal, MathematicalOps};
+use rust_decimal_macros::dec;
+
+use crate::services::candles::{Candle, CandleService};
+use crate::settings::{VolatilityEstimator, VolatilitySettings};
+
+/// Rolling realized volatility of markets estimated by the last candles of `CandleService`.
+/// Volatility is a standard deviation of log returns per candle interval
+pub struct VolatilityService {
+    settings: VolatilitySettings,
+    candle_service: Arc<CandleService>,
+}
+
+impl VolatilityService {
+    pub fn new(settings: VolatilitySettings, candle_service: Arc<CandleService>) -> Arc<Self> {
+        Arc::new(VolatilityService {
+            settings,
+            candle_service,
+        })
+    }
+
+    /// Volatility by estimator from settings
+    pub fn volatility(&self, market_id: MarketId) -> Option<Decimal> {
+        match self.settings.estimator {
+            VolatilityEstimator::Ewma => self.ewma_volatility(market_id),
+            VolatilityEstimator::Parkinson => self.parkinson_volatility(market_id),
+        }
+    }
+
+    /// EWMA of squared close-to-close log returns. It isn't known until there are 2 returns
+    pub fn ewma_volatility(&self, market_id: MarketId) -> Option<Decimal> {
+        let candles = self.last_candles(market_id);
+        ewma_volatility(&candles, self.settings.ewma_lambda)
+    }
+
+    /// Parkinson estimator by high and low prices of candles
+    pub fn parkinson_volatility(&self, market_id: MarketId) -> Option<Decimal> {
+        let candles = self.last_candles(market_id);
+        parkinson_volatility(&candles)
+    }
+
+    fn last_candles(&self, market_id: MarketId) -> Vec<Candle> {
+        let mut candles = self.candle_service.candles(market_id);
+        let skip_count = candles
+            .len()
+            .saturating_sub(self.settings.candles_count.max(2));
+        let _ = candles.drain(..skip_count);
+        candles
+    }
+}
+
+fn ewma_volatility(candles: &[Candle], lambda: Decimal) -> Option<Decimal> {
+    let returns: Vec<Decimal> = candles
+        .iter()
+        .tuple_windows()
+        .map(|(previous, next)| (next.close / previous.close).checked_ln())
+        .collect::<Option<_>>()?;
+    if returns.len() < 2 {
+        return None;
+    }
+
+    let variance = returns
+        .iter()
+        .skip(1)
+        .fold(returns[0] * returns[0], |variance, x| {
+            lambda * variance + (Decimal::ONE - lambda) * x * x
+        });
+    variance.sqrt()
+}
+
+fn parkinson_volatility(candles: &[Candle]) -> Option<Decimal> {
+    if candles.is_empty() {
+        return None;
+    }
+
+    let squared_ranges_sum = candles
+        .iter()
+        .map(|x| (x.high / x.low).checked_ln().map(|range| range * range))
+        .sum::<Option<Decimal>>()?;
+    let count = Decimal::from(candles.len());
+    let variance = squared_ranges_sum / (dec!(4) * count * Decimal::TWO.ln());
+    variance.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use mmb_domain::order::snapshot::Price;
+
+    fn candle(low: Price, high: Price, close: Price) -> Candle {
+        Candle {
+            open_time: Utc::now(),
+            open: close,
+            high,
+            low,
+            close,
+            volume: dec!(1),
+        }
+    }
+
+    #[test]
+    fn ewma_of_log_returns() {
+        let close = |x| candle(x, x, x);
+        assert_eq!(ewma_volatility(&[close(dec!(100))], dec!(0.5)), None);
+        assert_eq!(
+            ewma_volatility(&[close(dec!(100)), close(dec!(100))], dec!(0.5)),
+            None
+        );
+
+        let e = Decimal::E;
+        let volatility = ewma_volatility(
+            &[close(dec!(1)), close(e), close(e * e), close(e * e)],
+            dec!(0.5),
+        )
+        .expect("in test");
+        // variances: 1, 0.5 * 1 + 0.5 * 1 = 1, 0.5 * 1 + 0.5 * 0 = 0.5
+        assert!((volatility - dec!(0.5).sqrt().expect("in test")).abs() < dec!(0.0001));
+    }
+
+    #[test]
+    fn parkinson_by_high_low_range() {
+        assert_eq!(parkinson_volatility(&[]), None);
+
+        let e = Decimal::E;
+        let volatility =
+            parkinson_volatility(&[candle(dec!(1), e, e), candle(e, e, e)]).expect("in test");
+        let expected = (Decimal::ONE / (dec!(8) * Decimal::TWO.ln()))
+            .sqrt()
+            .expect("in test");
+        assert!((volatility - expected).abs() < dec!(0.0001));
+    }
+}
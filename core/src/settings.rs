@@ -1,6 +1,16 @@
+use crate::alerting::{AlertKind, AlertSeverity};
+use crate::database::serialization::SerializationFormat;
+use anyhow::{ensure, Result};
+use chrono::{Duration, NaiveTime};
+use mmb_domain::events::CHANNEL_MAX_EVENTS_COUNT;
+use mmb_domain::exchanges::commission::FeeSchedule;
 use mmb_domain::market::{CurrencyCode, CurrencyPair, ExchangeAccountId};
 use mmb_domain::order::snapshot::Amount;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 pub trait DispositionStrategySettings {
@@ -11,7 +21,8 @@ pub trait DispositionStrategySettings {
 
 /// Application settings
 /// Attention! After changing in runtime, you need to save the settings. See issue #146
-/// For the settings to be applied, the trading engine must be restarted after changing the config
+/// For the settings to be applied, the trading engine must be restarted after changing the config,
+/// except sections applied by `ConfigWatcher` if `core.config_reload` is specified
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct AppSettings<StrategySettings: Clone> {
     pub strategy: StrategySettings,
@@ -21,16 +32,1084 @@ pub struct AppSettings<StrategySettings: Clone> {
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct CoreSettings {
     pub database: Option<DbSettings>,
+    /// Thresholds of open orders and positions recovered from exchanges at startup. If any of them
+    /// is exceeded, trading stays paused until operator confirmation via control API
+    pub cold_start: Option<ColdStartSettings>,
+    /// Pre-trade limits checked for every order before it is sent to exchange
+    pub risk: Option<RiskSettings>,
+    /// Periods when engine intentionally doesn't trade. They are excluded from statistics rates
+    pub trading_calendar: Option<TradingCalendarSettings>,
+    /// Time windows when strategies quote markets. Orders of market are canceled when its session
+    /// is closed. Markets without sessions are quoted all the time
+    pub trading_sessions: Option<TradingSessionsSettings>,
+    /// Orders aren't placed by strategies while order book of their market is crossed, has
+    /// non-positive prices or isn't updated for too long. Order books aren't checked if it isn't set
+    pub market_data_sanity: Option<MarketDataSanitySettings>,
+    /// Periodic comparison of local balances with exchange ones. Balances are requested every
+    /// 60 seconds and local state is replaced by them if settings aren't specified
+    pub balance_reconciliation: Option<BalanceReconciliationSettings>,
+    /// Periodic refresh of exchange symbols trading rules. Symbols are requested every hour if
+    /// settings aren't specified
+    pub symbol_refresh: Option<SymbolRefreshSettings>,
+    /// Choosing of exchange account for orders routed by `AccountRouter` when several accounts
+    /// of the same exchange trade the currency pair. Round-robin is used if settings aren't specified
+    pub account_routing: Option<AccountRoutingSettings>,
+    /// Tracking of deposits and withdrawals of exchange accounts. Confirmed movements update
+    /// balances and statistics. Movements aren't tracked if settings aren't specified
+    pub treasury: Option<TreasurySettings>,
+    /// Keeping inventory of currencies on exchange accounts near configured targets by transfers
+    /// and hedging orders. Inventory isn't rebalanced if settings aren't specified
+    pub rebalancing: Option<RebalancingSettings>,
+    /// Automatic offsetting of net position of market making markets on hedge venues
+    #[serde(default)]
+    pub hedgers: Vec<HedgerSettings>,
+    /// Timers and risk budgets of strategies run by `StrategyRegistry`, found by strategy name
+    #[serde(default)]
+    pub strategies: Vec<StrategyInstanceSettings>,
+    /// Aggregation of trades into candles. Default settings are used if they aren't specified
+    pub candles: Option<CandlesSettings>,
+    /// Sources and staleness handling of external signals used by strategies. Signals can be
+    /// pushed only through control panel if settings aren't specified
+    pub signals: Option<SignalsSettings>,
+    /// Estimation of realized volatility of markets by candles. Default settings are used if
+    /// they aren't specified
+    pub volatility: Option<VolatilitySettings>,
+    /// Composite index prices of currency pairs by mid prices of several exchange accounts. They
+    /// are used as fair value for quoting and marking of positions instead of single venue prices
+    #[serde(default)]
+    pub index_prices: Vec<IndexPriceSettings>,
+    /// Conversion of amounts into reporting currency through chains of markets. Conversion
+    /// isn't available if settings aren't specified
+    pub cross_rates: Option<CrossRatesSettings>,
+    /// Write-ahead journal of order and balance changes used for recovery after crash
+    pub journal: Option<JournalSettings>,
+    /// WebSocket stream of normalized engine events for visualization web app. Stream isn't
+    /// started if settings aren't specified
+    pub event_stream: Option<EventStreamSettings>,
+    /// Pushing of alerts about risk rule triggers, kill switch activation, websocket
+    /// disconnections, reconciliation mismatches, panics and crash loops of critical tasks. Alerts
+    /// are only logged if settings aren't specified
+    pub alerting: Option<AlertingSettings>,
+    /// Export of order lifecycle spans, REST request durations and statistic counters to
+    /// OpenTelemetry collector by OTLP. Nothing is exported if settings aren't specified
+    pub telemetry: Option<TelemetrySettings>,
+    /// Thresholds of engine readiness reported by `/health/ready` endpoint of control panel.
+    /// Default thresholds are used if settings aren't specified
+    pub health: Option<HealthSettings>,
+    /// Sequence of graceful shutdown started by SIGTERM, Ctrl-C or control panel. Strategies are
+    /// stopped, open orders are canceled, positions are flattened and persistence is flushed
+    /// with 5 seconds timeout for every step if settings aren't specified
+    pub shutdown: Option<ShutdownSettings>,
+    /// Escalation of crashes of critical tasks run by `Supervisor`. Default thresholds are used
+    /// if settings aren't specified
+    pub supervisor: Option<SupervisorSettings>,
+    /// Watching of settings file loaded at startup. Changed strategy settings, risk limits and
+    /// alerting settings are applied without restart. File isn't watched if settings aren't
+    /// specified
+    pub config_reload: Option<ConfigReloadSettings>,
+    /// Source of API keys of exchange accounts used instead of credentials file. Credentials file
+    /// is required if settings aren't specified
+    pub secrets: Option<SecretsSettings>,
+    /// Daily files with executed volume, fees, realized PnL, cancel/fill ratios and maker/taker
+    /// split of trade places. Reports aren't generated if settings aren't specified
+    pub eod_report: Option<EodReportSettings>,
+    /// Dedicated tokio runtimes for market data processing and order management, so a flood of
+    /// order book updates doesn't delay order creation and cancellation. Everything runs on the
+    /// main runtime if settings aren't specified
+    pub runtimes: Option<RuntimesSettings>,
+    /// Eviction of finished orders from memory. Evicted orders are archived to database and are
+    /// still found by `Exchange::find_order`. Finished orders are kept for 30 minutes and aren't
+    /// archived if settings aren't specified
+    pub order_retention: Option<OrderRetentionSettings>,
+    /// Writing of order books, public trades and fills to ClickHouse for long-horizon analytics.
+    /// Nothing is written if settings aren't specified
+    pub clickhouse: Option<ClickHouseSettings>,
+    /// Publishing of normalized order, fill, balance and statistics events to Kafka topics for
+    /// downstream risk and reporting systems. Nothing is published if settings aren't specified
+    pub kafka: Option<KafkaSettings>,
+    /// Publishing of selected engine events to Redis channel and receiving of commands (pause,
+    /// cancel-all, parameter updates) from another one, so external tools can interact with the
+    /// engine without API client. Redis isn't used if settings aren't specified
+    pub redis_bridge: Option<RedisBridgeSettings>,
     pub exchanges: Vec<ExchangeSettings>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AlertingSettings {
+    /// Alerts with lower severity aren't sent
+    #[serde(default = "default_alert_min_severity")]
+    pub min_severity: AlertSeverity,
+    /// Minimal interval between alerts of the same kind from the same source. Count of
+    /// suppressed alerts is added to the next sent one
+    #[serde(default = "default_alert_rate_limit_secs")]
+    pub rate_limit_secs: u64,
+    /// Websocket disconnection is alerted if connection isn't restored within this time
+    #[serde(default = "default_disconnect_alert_secs")]
+    pub disconnect_alert_secs: u64,
+    /// Templates of alert text by alert kind with placeholders `{severity}`, `{kind}`, `{source}`,
+    /// `{message}`, `{time}` and `{suppressed}`. Default text is used for kinds without template
+    #[serde(default)]
+    pub templates: HashMap<AlertKind, String>,
+    pub telegram: Option<TelegramSettings>,
+    pub slack: Option<SlackSettings>,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookSettings>,
+    pub email: Option<EmailSettings>,
+    /// Summary of trading sent by email every day. Requires email settings
+    pub daily_report: Option<DailyReportSettings>,
+}
+
+impl Default for AlertingSettings {
+    fn default() -> Self {
+        AlertingSettings {
+            min_severity: default_alert_min_severity(),
+            rate_limit_secs: default_alert_rate_limit_secs(),
+            disconnect_alert_secs: default_disconnect_alert_secs(),
+            templates: HashMap::new(),
+            telegram: None,
+            slack: None,
+            webhooks: Vec::new(),
+            email: None,
+            daily_report: None,
+        }
+    }
+}
+
+fn default_alert_min_severity() -> AlertSeverity {
+    AlertSeverity::Warning
+}
+
+fn default_alert_rate_limit_secs() -> u64 {
+    60
+}
+
+fn default_disconnect_alert_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TelegramSettings {
+    /// Token of bot which sends alerts. Bot should be added to the chat
+    pub bot_token: String,
+    /// Id of chat or `@channel_name`
+    pub chat_id: String,
+    #[serde(default = "default_telegram_api_url")]
+    pub api_url: String,
+}
+
+fn default_telegram_api_url() -> String {
+    "https://api.telegram.org".to_owned()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SlackSettings {
+    /// URL of Slack incoming webhook bound to alerts channel
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SmtpEncryption {
+    #[default]
+    StartTls,
+    /// Implicit TLS, usually on port 465
+    Tls,
+    /// Unencrypted connection, only for local relays
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EmailSettings {
+    pub smtp_host: String,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    #[serde(default)]
+    pub encryption: SmtpEncryption,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender address, e.g. `MMB <mmb@example.com>`
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DailyReportSettings {
+    /// UTC time of sending report. Report covers time since the previous one or since start
+    pub time: NaiveTime,
+    /// Currency in which PnL and volumes are valued by mid prices
+    pub valuation_currency_code: CurrencyCode,
+    /// Count of markets with the biggest volume listed in report
+    #[serde(default = "default_report_top_pairs_count")]
+    pub top_pairs_count: usize,
+}
+
+fn default_report_top_pairs_count() -> usize {
+    5
+}
+
+/// Endpoint receiving alerts as JSON by POST requests
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WebhookSettings {
+    pub url: String,
+    /// Headers added to every request, e.g. `Authorization`
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Templates of request body by alert kind with the same placeholders as alert text
+    /// templates. Values are JSON escaped, so placeholders should be inside string literals.
+    /// Alert is sent as JSON object with all its fields if there is no template for its kind
+    #[serde(default)]
+    pub body_templates: HashMap<AlertKind, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EventStreamSettings {
+    /// Address of WebSocket server, e.g. `127.0.0.1:8090`
+    pub address: String,
+    /// Interval of sending statistics of markets changed since the previous sending
+    #[serde(default = "default_event_stream_interval_millis")]
+    pub stats_interval_millis: u64,
+    /// Interval of sending order book snapshots of markets changed since the previous sending
+    #[serde(default = "default_event_stream_interval_millis")]
+    pub liquidity_interval_millis: u64,
+    /// Count of price levels of every order book side in liquidity snapshot
+    #[serde(default = "default_liquidity_depth")]
+    pub liquidity_depth: usize,
+    /// Count of the latest public trades in liquidity snapshot
+    #[serde(default = "default_recent_trades_count")]
+    pub recent_trades_count: usize,
+}
+
+fn default_event_stream_interval_millis() -> u64 {
+    1000
+}
+
+fn default_liquidity_depth() -> usize {
+    20
+}
+
+fn default_recent_trades_count() -> usize {
+    50
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TelemetrySettings {
+    /// gRPC endpoint of OTLP receiver, e.g. OpenTelemetry Collector, Jaeger or Tempo
+    #[serde(default = "default_telemetry_endpoint")]
+    pub endpoint: String,
+    #[serde(default = "default_telemetry_service_name")]
+    pub service_name: String,
+    /// Part of traces which are exported, from 0 to 1. Nested spans follow decision of their
+    /// parent span
+    #[serde(default = "default_sampling_ratio")]
+    pub sampling_ratio: Decimal,
+    #[serde(default = "default_metrics_interval_secs")]
+    pub metrics_interval_secs: u64,
+    /// Resource attributes added to service name and exchange accounts of engine, e.g.
+    /// `deployment.environment`
+    #[serde(default)]
+    pub resource_attributes: HashMap<String, String>,
+}
+
+fn default_telemetry_endpoint() -> String {
+    "http://localhost:4317".to_owned()
+}
+
+fn default_telemetry_service_name() -> String {
+    "mmb".to_owned()
+}
+
+fn default_sampling_ratio() -> Decimal {
+    Decimal::ONE
+}
+
+fn default_metrics_interval_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HealthSettings {
+    /// Engine isn't ready if tokio event loop runs scheduled tasks later by more than this time
+    pub max_event_loop_lag_millis: u64,
+    /// Engine isn't alive if event loop hasn't run health check task for this time
+    pub event_loop_stall_secs: u64,
+    /// Engine isn't ready if more events are waiting to be saved to database
+    pub max_persistence_backlog: usize,
+    /// Engine isn't ready if some exchange account hasn't received order book or trades for
+    /// this time
+    pub max_market_data_age_secs: u64,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        HealthSettings {
+            max_event_loop_lag_millis: 500,
+            event_loop_stall_secs: 30,
+            max_persistence_backlog: 10_000,
+            max_market_data_age_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum ShutdownStep {
+    /// Stop strategies and other user services
+    StopStrategies,
+    /// Cancel open orders of all exchange accounts and wait for cancellation
+    CancelOrders,
+    /// Close active positions of margin trading exchange accounts
+    FlattenPositions,
+    /// Stop core services and save events waiting in queue to database
+    FlushPersistence,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ShutdownStepSettings {
+    pub step: ShutdownStep,
+    /// Step is interrupted after this time and shutdown goes on with the next step
+    #[serde(default = "default_shutdown_step_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ShutdownSettings {
+    /// Steps executed in listed order. Steps which aren't listed are executed after them with
+    /// default timeout. Strategies are always stopped first and persistence is always flushed
+    /// last, so these steps are listed only to change their timeouts
+    pub steps: Vec<ShutdownStepSettings>,
+    /// JSON file where status report of shutdown is written
+    pub report_path: Option<PathBuf>,
+}
+
+impl Default for ShutdownSettings {
+    fn default() -> Self {
+        let steps = [
+            ShutdownStep::StopStrategies,
+            ShutdownStep::CancelOrders,
+            ShutdownStep::FlattenPositions,
+            ShutdownStep::FlushPersistence,
+        ];
+        ShutdownSettings {
+            steps: steps
+                .into_iter()
+                .map(|step| ShutdownStepSettings {
+                    step,
+                    timeout_secs: default_shutdown_step_timeout_secs(),
+                })
+                .collect(),
+            report_path: None,
+        }
+    }
+}
+
+pub(crate) fn default_shutdown_step_timeout_secs() -> u64 {
+    5
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SupervisorSettings {
+    /// Critical alert is sent if critical task crashes this many times within the window
+    pub crash_alert_threshold: usize,
+    pub crash_alert_window_secs: u64,
+}
+
+impl Default for SupervisorSettings {
+    fn default() -> Self {
+        SupervisorSettings {
+            crash_alert_threshold: 3,
+            crash_alert_window_secs: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ConfigReloadSettings {
+    /// Interval of checking modification time of settings and credentials files
+    pub check_interval_secs: u64,
+}
+
+impl Default for ConfigReloadSettings {
+    fn default() -> Self {
+        ConfigReloadSettings {
+            check_interval_secs: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SecretsSettings {
+    pub provider: SecretsProviderSettings,
+    /// Interval of requesting credentials again. Exchange account is reconnected with changed
+    /// credentials. Credentials aren't rotated if it isn't specified
+    pub rotation_check_interval_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SecretsProviderSettings {
+    /// Variables `{prefix}{EXCHANGE_ACCOUNT_ID}_API_KEY`, `..._SECRET_KEY` and optional
+    /// `..._API_PASSPHRASE`, e.g. `MMB_BINANCE_0_API_KEY`
+    Env {
+        #[serde(default = "default_secrets_env_prefix")]
+        prefix: String,
+    },
+    /// File in format of credentials file encrypted by AES-256-GCM. Key is read from environment
+    /// variable as 64 hex characters
+    EncryptedFile { path: PathBuf, key_env: String },
+    /// KV version 2 secrets engine of HashiCorp Vault. Secret `{path}/{exchange_account_id}`
+    /// contains `api_key`, `secret_key` and optional `api_passphrase`
+    Vault {
+        address: String,
+        #[serde(default = "default_vault_mount")]
+        mount: String,
+        path: String,
+        /// Environment variable with Vault token
+        #[serde(default = "default_vault_token_env")]
+        token_env: String,
+    },
+    /// Secret `{secret_prefix}{exchange_account_id}` of AWS Secrets Manager with JSON object of
+    /// the same fields as Vault secret. AWS credentials are read from `AWS_ACCESS_KEY_ID`,
+    /// `AWS_SECRET_ACCESS_KEY` and optional `AWS_SESSION_TOKEN` variables
+    AwsSecretsManager {
+        region: String,
+        #[serde(default)]
+        secret_prefix: String,
+    },
+}
+
+fn default_secrets_env_prefix() -> String {
+    "MMB_".to_owned()
+}
+
+fn default_vault_mount() -> String {
+    "secret".to_owned()
+}
+
+fn default_vault_token_env() -> String {
+    "VAULT_TOKEN".to_owned()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EodReportSettings {
+    /// UTC time of generating report. Report covers time since the previous one or since start
+    pub time: NaiveTime,
+    /// Every format is written to its own file `eod_report_{date}.{format}`
+    #[serde(default = "default_eod_report_formats")]
+    pub formats: Vec<ReportFormat>,
+    pub destination: ReportDestination,
+}
+
+fn default_eod_report_formats() -> Vec<ReportFormat> {
+    vec![ReportFormat::Csv, ReportFormat::Json]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReportDestination {
+    /// Directory is created if it doesn't exist
+    Directory { path: PathBuf },
+    /// Object `{prefix}{file name}` of S3 bucket. AWS credentials are read from the same
+    /// variables as for AWS Secrets Manager
+    S3 {
+        bucket: String,
+        region: String,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct JournalSettings {
+    /// Path to journal file. Not finished orders and balances are restored from it at startup
+    pub path: PathBuf,
+    /// Count of records after which journal is rewritten with only not finished orders and
+    /// the latest balances
+    #[serde(default = "default_journal_compaction_records")]
+    pub compaction_records: usize,
+}
+
+fn default_journal_compaction_records() -> usize {
+    10_000
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BalanceReconciliationSettings {
+    /// Interval between balance requests to exchanges
+    pub interval_secs: u64,
+    /// Max difference between local and exchange balances in percents of exchange balance
+    /// which isn't reported as discrepancy
+    pub tolerance_percent: Decimal,
+    /// Replace local balances by exchange ones when discrepancy is found. Otherwise discrepancy
+    /// is only reported and local balances are kept
+    #[serde(default = "default_snap_to_exchange")]
+    pub snap_to_exchange: bool,
+    /// Tolerance for exchange accounts under maintenance, when exchange can report stale
+    /// balances. `tolerance_percent` is used if it isn't set
+    pub maintenance_tolerance_percent: Option<Decimal>,
+}
+
+fn default_snap_to_exchange() -> bool {
+    true
+}
+
+impl Default for BalanceReconciliationSettings {
+    fn default() -> Self {
+        BalanceReconciliationSettings {
+            interval_secs: 60,
+            tolerance_percent: Decimal::ZERO,
+            snap_to_exchange: default_snap_to_exchange(),
+            maintenance_tolerance_percent: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SymbolRefreshSettings {
+    /// Interval between symbols requests to exchanges
+    pub interval_secs: u64,
+}
+
+impl Default for SymbolRefreshSettings {
+    fn default() -> Self {
+        SymbolRefreshSettings {
+            interval_secs: 3600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StrategyInstanceSettings {
+    pub name: String,
+    /// Interval between `Strategy::on_timer` calls
+    pub timer_interval_millis: u64,
+    /// Limits of orders created by the strategy, checked in addition to limits of the engine.
+    /// Loss limit and kill switch settings aren't used for budgets
+    pub risk_budget: Option<RiskSettings>,
+    /// Queue of events waiting for handling by the strategy. Default settings are used if they
+    /// aren't specified
+    pub event_queue: Option<EventQueueSettings>,
+}
+
+/// Bounded queue of events of single consumer, so slow consumer doesn't lose events silently
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EventQueueSettings {
+    /// Max count of events waiting for handling
+    #[serde(default = "default_event_queue_capacity")]
+    pub capacity: usize,
+    /// What to do with new event when the queue is full
+    #[serde(default)]
+    pub overflow_policy: EventOverflowPolicy,
+    /// Consumer receives the latest order book of market instead of every its event. Order book
+    /// event of market takes place in the queue only if the market isn't already waiting in it,
+    /// so bursts of order book updates don't grow the queue
+    #[serde(default)]
+    pub conflate_order_books: bool,
+}
+
+impl Default for EventQueueSettings {
+    fn default() -> Self {
+        EventQueueSettings {
+            capacity: default_event_queue_capacity(),
+            overflow_policy: EventOverflowPolicy::default(),
+            conflate_order_books: false,
+        }
+    }
+}
+
+fn default_event_queue_capacity() -> usize {
+    CHANNEL_MAX_EVENTS_COUNT
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventOverflowPolicy {
+    /// Wait until the consumer handles some events. Events are dropped only if engine events
+    /// channel overflows meanwhile
+    #[default]
+    Block,
+    /// Drop the oldest waiting event
+    DropOldest,
+    /// Merge order book event into waiting event of the same market. Other events wait like
+    /// with `Block` policy
+    CoalesceOrderBooks,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RuntimesSettings {
+    /// Reading of market data websockets and handling of their messages
+    pub market_data: Option<RuntimeSettings>,
+    /// Reading of order entry websockets and requests of order creation and cancellation
+    pub order_management: Option<RuntimeSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RuntimeSettings {
+    #[serde(default = "default_runtime_worker_threads")]
+    pub worker_threads: usize,
+    /// CPU cores which worker threads are pinned to in round-robin order. Threads aren't pinned
+    /// if cores aren't specified
+    #[serde(default)]
+    pub core_ids: Vec<usize>,
+}
+
+fn default_runtime_worker_threads() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CandlesSettings {
+    /// Duration of single candle
+    pub interval_secs: u64,
+    /// Max count of stored candles by each market
+    pub history_size: usize,
+}
+
+impl Default for CandlesSettings {
+    fn default() -> Self {
+        CandlesSettings {
+            interval_secs: 60,
+            history_size: 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum VolatilityEstimator {
+    /// Exponentially weighted moving average of squared close-to-close log returns
+    #[default]
+    Ewma,
+    /// Estimator by high and low prices of candles
+    Parkinson,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct VolatilitySettings {
+    /// Weight of the previous variance in EWMA estimator
+    #[serde(default = "default_ewma_lambda")]
+    pub ewma_lambda: Decimal,
+    /// Count of the last candles used by estimators
+    #[serde(default = "default_volatility_candles_count")]
+    pub candles_count: usize,
+    /// Estimator used by risk manager
+    #[serde(default)]
+    pub estimator: VolatilityEstimator,
+}
+
+impl Default for VolatilitySettings {
+    fn default() -> Self {
+        VolatilitySettings {
+            ewma_lambda: default_ewma_lambda(),
+            candles_count: default_volatility_candles_count(),
+            estimator: VolatilityEstimator::default(),
+        }
+    }
+}
+
+fn default_ewma_lambda() -> Decimal {
+    dec!(0.94)
+}
+
+fn default_volatility_candles_count() -> usize {
+    30
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IndexPriceSettings {
+    pub currency_pair: CurrencyPair,
+    /// Exchange accounts which mid prices of `currency_pair` compose the index
+    pub components: Vec<IndexComponentSettings>,
+    /// Components which mid price deviates from weighted median of all components by more than
+    /// this percent are excluded from index
+    #[serde(default = "default_index_max_deviation_percent")]
+    pub max_deviation_percent: Decimal,
+    /// Min count of components which are required to be known and not rejected
+    #[serde(default = "default_index_min_components")]
+    pub min_components: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IndexComponentSettings {
+    pub exchange_account_id: ExchangeAccountId,
+    /// Relative weight of component. Weights are normalized by sum of weights of used components
+    pub weight: Decimal,
+}
+
+fn default_index_max_deviation_percent() -> Decimal {
+    dec!(1)
+}
+
+fn default_index_min_components() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CrossRatesSettings {
+    /// Currency which balances, commissions and exposures are valued in
+    pub reporting_currency: CurrencyCode,
+    /// Legs which order book wasn't updated for longer time aren't used for conversion
+    #[serde(default = "default_cross_rate_max_price_age_millis")]
+    pub max_price_age_millis: u64,
+    /// Max count of markets in conversion chain
+    #[serde(default = "default_cross_rate_max_legs")]
+    pub max_legs: usize,
+}
+
+fn default_cross_rate_max_price_age_millis() -> u64 {
+    10_000
+}
+
+fn default_cross_rate_max_legs() -> usize {
+    3
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SignalsSettings {
+    /// Lifetime of signal values pushed without TTL
+    pub default_ttl_secs: u64,
+    /// Multiplier of spread while some signal used by strategy is stale
+    pub stale_spread_multiplier: Decimal,
+    /// Time after expiration of signal when quoting by it is disabled
+    pub disable_after_stale_secs: u64,
+    /// JSON file with signal messages which is read again after every change
+    pub file: Option<PathBuf>,
+    /// Interval of checking the signals file for changes
+    #[serde(default = "default_file_poll_interval_millis")]
+    pub file_poll_interval_millis: u64,
+    pub redis: Option<RedisSignalsSettings>,
+}
+
+fn default_file_poll_interval_millis() -> u64 {
+    1000
+}
+
+impl Default for SignalsSettings {
+    fn default() -> Self {
+        SignalsSettings {
+            default_ttl_secs: 60,
+            stale_spread_multiplier: Decimal::TWO,
+            disable_after_stale_secs: 60,
+            file: None,
+            file_poll_interval_millis: default_file_poll_interval_millis(),
+            redis: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RedisSignalsSettings {
+    /// Redis connection URL, e.g. `redis://127.0.0.1/`
+    pub url: String,
+    /// Pub/sub channel with JSON signal messages
+    pub channel: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TreasurySettings {
+    /// Accounts which deposits and withdrawals are tracked. Connectors of the accounts should
+    /// support requesting of deposits and withdrawals history
+    pub exchange_accounts: Vec<ExchangeAccountId>,
+    /// Interval between requests of deposits and withdrawals history
+    pub interval_secs: u64,
+    /// Movements created before engine start within this period are tracked as well, so
+    /// movements pending during restart are confirmed
+    pub lookback_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RebalancingSettings {
+    /// Interval between checks of inventory
+    pub interval_secs: u64,
+    #[serde(default)]
+    pub mode: RebalancingMode,
+    pub targets: Vec<InventoryTargetSettings>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RebalancingMode {
+    /// Decisions are executed right after they are made
+    Automatic,
+    /// Decisions are executed only after approval via control API
+    #[default]
+    OperatorApproval,
+}
+
+/// Desired inventory of currency on exchange account. Inventory is moved from accounts above
+/// their targets to accounts below them
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InventoryTargetSettings {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    pub target_amount: Amount,
+    /// Max deviation of balance from target amount which doesn't need rebalancing
+    pub tolerance: Amount,
+    /// Pair with the currency as base one, which is used to move inventory between different
+    /// exchanges by selling surplus on one of them and buying deficit on another. Inventory is
+    /// moved only between accounts of the same exchange if the pair isn't specified
+    pub hedge_currency_pair: Option<CurrencyPair>,
+}
+
+/// Net position of `markets` and `hedge_market` is offset on `hedge_market` when its absolute
+/// value exceeds `threshold`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HedgerSettings {
+    /// Used as strategy name of hedging orders
+    pub name: String,
+    /// Markets which positions are hedged. Weights convert their positions into units of net position
+    pub markets: Vec<ExposureMarketSettings>,
+    /// Market where hedging orders are created, e.g. perpetual
+    pub hedge_market: ExposureMarketSettings,
+    /// Max absolute net position which isn't hedged
+    pub threshold: Amount,
+    #[serde(default)]
+    pub aggression: HedgeAggression,
+    /// Min interval between creations of hedging orders, including re-posting of chased orders
+    pub cooldown_secs: u64,
+    /// Interval between checks of net position
+    pub interval_millis: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum HedgeAggression {
+    /// Net position is offset by market order
+    #[default]
+    Market,
+    /// Net position is offset by maker only order at top of order book side of the order.
+    /// The order is canceled and posted again when the top moves away from its price
+    LimitChase,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RoutingPolicy {
+    #[default]
+    RoundRobin,
+    /// Account with the smallest used share of its request limit
+    LowestUtilization,
+    /// Strategy trades only on its designated account
+    PerStrategy,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AccountRoutingSettings {
+    #[serde(default)]
+    pub policy: RoutingPolicy,
+    /// Account numbers designated for strategies by strategy name. Used by `PerStrategy` policy
+    #[serde(default)]
+    pub strategy_accounts: HashMap<String, u8>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TradingCalendarSettings {
+    pub non_trading_windows: Vec<NonTradingWindowSettings>,
+}
+
+/// Time window without trading. Window is applied to all exchange accounts if
+/// `exchange_account_id` isn't specified
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum NonTradingWindowSettings {
+    /// Window repeated every day in UTC, e.g. daily maintenance of exchange.
+    /// Window passes over midnight if `end` is earlier than `start`
+    Daily {
+        exchange_account_id: Option<ExchangeAccountId>,
+        start: NaiveTime,
+        end: NaiveTime,
+    },
+    /// Single period, e.g. planned pause of trading
+    Period {
+        exchange_account_id: Option<ExchangeAccountId>,
+        start: DateTime,
+        end: DateTime,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TradingSessionsSettings {
+    pub markets: Vec<MarketSessionSettings>,
+}
+
+/// Schedule of quoting of market. Market is quoted within any of `windows` or all day if there
+/// are no windows, except `exceptions`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MarketSessionSettings {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    #[serde(default)]
+    pub windows: Vec<SessionWindowSettings>,
+    /// Periods without quoting inside windows, e.g. minutes around funding or known maintenance
+    /// of exchange. Exchange account of exception isn't checked
+    #[serde(default)]
+    pub exceptions: Vec<NonTradingWindowSettings>,
+}
+
+/// Window repeated every day in UTC. Window passes over midnight if `end` is earlier than `start`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SessionWindowSettings {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MarketDataSanitySettings {
+    /// Order book is stale if it isn't updated for this time
+    #[serde(default = "default_max_book_age_millis")]
+    pub max_book_age_millis: u64,
+}
+
+impl Default for MarketDataSanitySettings {
+    fn default() -> Self {
+        Self {
+            max_book_age_millis: default_max_book_age_millis(),
+        }
+    }
+}
+
+fn default_max_book_age_millis() -> u64 {
+    5_000
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ColdStartSettings {
+    /// Max count of open orders per exchange account
+    pub max_open_orders_count: Option<usize>,
+    /// Max absolute amount of derivative position per currency pair
+    pub max_position_amount: Option<Amount>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RiskSettings {
+    /// Max order notional in quote currency. Market orders are estimated by mid price
+    pub max_order_notional: Option<Decimal>,
+    /// Max absolute net position per currency pair after order execution
+    pub max_position_amount: Option<Amount>,
+    /// Max count of not finished orders per exchange account
+    pub max_open_orders_count: Option<usize>,
+    /// Max deviation of order price from mid price in percents (fat-finger check)
+    pub price_collar_percent: Option<Decimal>,
+    /// Trigger kill switch on any violation instead of rejecting only the violating order
+    #[serde(default)]
+    pub kill_switch_on_violation: bool,
+    pub loss_limit: Option<LossLimitSettings>,
+    /// Correlated markets with combined position cap
+    #[serde(default)]
+    pub exposure_groups: Vec<ExposureGroupSettings>,
+    pub order_to_trade: Option<OrderToTradeSettings>,
+    /// Position limit is reduced while realized volatility of market is above target one
+    pub volatility_scaling: Option<VolatilityScalingSettings>,
+}
+
+/// Position limit is multiplied by ratio of target volatility to realized one, but it isn't
+/// increased above `max_position_amount` when market is calm
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct VolatilityScalingSettings {
+    /// Volatility per candle interval at which full position limit is allowed
+    pub target_volatility: Decimal,
+    /// Min share of `max_position_amount` allowed however high volatility is
+    #[serde(default = "default_min_position_share")]
+    pub min_position_share: Decimal,
+}
+
+fn default_min_position_share() -> Decimal {
+    dec!(0.1)
+}
+
+/// Throttling of quoting before exchange penalizes exchange account for poor order-to-trade ratio
+/// or heavy REST usage. Limits should be set below exchange ones: new orders are rejected while
+/// a limit is reached, so cancellations and fills bring the account back within limits
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OrderToTradeSettings {
+    /// Max canceled orders per fill within rolling window
+    pub max_cancel_fill_ratio: Option<Decimal>,
+    /// Ratio isn't checked until this count of cancellations within window
+    #[serde(default = "default_min_cancels_count")]
+    pub min_cancels_count: usize,
+    #[serde(default = "default_order_to_trade_window_secs")]
+    pub window_secs: u64,
+    /// Max share of REST request limit used in current period, from 0 to 1
+    pub max_request_weight_utilization: Option<Decimal>,
+}
+
+impl OrderToTradeSettings {
+    pub fn window(&self) -> Duration {
+        Duration::seconds(self.window_secs as i64)
+    }
+}
+
+fn default_min_cancels_count() -> usize {
+    50
+}
+
+fn default_order_to_trade_window_secs() -> u64 {
+    60 * 60
+}
+
+/// Markets which positions are counted toward a single exposure, e.g. BTC/USDT, BTC/BUSD and
+/// BTC/USD perpetual as "BTC exposure". Orders increasing the exposure above `max_amount` are rejected
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ExposureGroupSettings {
+    pub name: String,
+    pub markets: Vec<ExposureMarketSettings>,
+    /// Max absolute combined net position of the group markets
+    pub max_amount: Amount,
+    /// Currency of exposure when markets have different base currencies. Positions are converted
+    /// into it by cross rates before weights are applied, so `max_amount` is in this currency
+    pub currency_code: Option<CurrencyCode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ExposureMarketSettings {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    /// Multiplier converting position of the market into units of the group exposure,
+    /// e.g. contract size. 1 by default
+    pub weight: Option<Decimal>,
+}
+
+impl ExposureMarketSettings {
+    pub fn weight(&self) -> Decimal {
+        self.weight.unwrap_or(Decimal::ONE)
+    }
+}
+
+/// Exchange account is paused when its PnL within rolling 24h window falls below `-max_loss`.
+/// Trading is resumed only after acknowledgement via control API
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LossLimitSettings {
+    /// Max loss in valuation currency
+    pub max_loss: Decimal,
+    /// Currency in which balances of exchange account are valued by mid prices
+    pub valuation_currency_code: CurrencyCode,
+}
+
+/// Storage of persisted events
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DbBackend {
+    #[default]
+    Postgres,
+    /// Embedded database in single file, e.g. `url = "sqlite://mmb.db"`.
+    /// Tables are created automatically, so `migrations` are ignored
+    Sqlite,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct DbSettings {
+    #[serde(default)]
+    pub backend: DbBackend,
     pub url: String,
     pub migrations: Vec<PathBuf>,
     /// Path to directory for creating temporary directory for save events that was not saved to
     /// database by any reason and will be resaved to db late
     pub postponed_events_dir: Option<PathBuf>,
+    /// Format of files with postponed events. `Json` by default, `MessagePack` takes less space
+    pub postponed_events_format: Option<SerializationFormat>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -52,12 +1131,58 @@ pub struct ExchangeSettings {
     pub exchange_account_id: ExchangeAccountId,
     pub api_key: String,
     pub secret_key: String,
+    /// Passphrase of API key for exchanges which require it, e.g. OKX or KuCoin
+    #[serde(default)]
+    pub api_passphrase: String,
     pub is_margin_trading: bool,
     pub request_trades: bool,
     pub is_reducing_market_data: Option<bool>,
     pub subscribe_to_market_data: bool,
+    /// Account without trading credentials that only supplies market data.
+    /// Balances aren't requested and orders can't be created for such accounts
+    #[serde(default)]
+    pub is_watch_only: bool,
+    /// Websocket host for market data connection instead of default one, e.g. in another region
+    pub market_data_websocket_host: Option<String>,
+    /// Websocket host for private order flow connection instead of default one
+    pub order_entry_websocket_host: Option<String>,
+    /// Use separate websocket connection for private order flow on exchanges which combine it
+    /// with market data by default, so heavy order book traffic can't delay order acknowledgements
+    #[serde(default)]
+    pub separate_order_entry_connection: bool,
     pub websocket_channels: Vec<String>,
     pub currency_pairs: Option<Vec<CurrencyPairSetting>>,
+    /// Limits of order requests rate of the account, so bursts are queued by engine instead of
+    /// being rejected by exchange
+    pub order_rate_limits: Option<OrderRateLimitSettings>,
+    /// Leverage and margin type applied to traded symbols of derivative market at startup
+    pub derivative: Option<DerivativeSettings>,
+    /// Faults injected into REST responses and websocket messages of connector. Only for stress
+    /// testing of engine resilience, must not be set for real trading
+    pub fault_injection: Option<FaultInjectionSettings>,
+    /// Websocket is reconnected if data streams of exchange account are silent for too long
+    pub stream_watchdog: Option<StreamWatchdogSettings>,
+    /// Prices and amounts of orders are aligned with trading rules of symbol before sending, so
+    /// invalid orders are rejected locally instead of by exchange
+    pub order_validation: Option<OrderValidationSettings>,
+    /// Identifier of the account on exchange used for internal transfers between accounts, e.g.
+    /// email of Binance sub-account or name of OKX sub-account. Not set for master account
+    pub sub_account: Option<String>,
+    /// Fee schedule of the account. Fees aren't expected for fills without reported commission
+    /// if it isn't specified
+    pub fees: Option<FeeSettings>,
+    /// Orders which are creating or canceling for too long are checked on exchange, so their
+    /// state is repaired or alert is raised
+    pub stuck_orders: Option<StuckOrdersSettings>,
+    /// Orders placed on exchange outside of engine are adopted to orders pool, so their fills
+    /// affect balances and positions. Such orders are ignored if it isn't set
+    pub external_orders: Option<ExternalOrdersSettings>,
+    /// Polling of status declared by exchange. Trading is paused while exchange is under
+    /// maintenance. Status isn't requested if it isn't set
+    pub system_status: Option<SystemStatusSettings>,
+    /// Quoting is throttled while round-trip latency of order requests is degraded. Latency
+    /// doesn't affect quoting if it isn't set
+    pub latency_throttling: Option<LatencyThrottlingSettings>,
 }
 
 impl ExchangeSettings {
@@ -72,12 +1197,28 @@ impl ExchangeSettings {
             exchange_account_id,
             api_key,
             secret_key,
+            api_passphrase: String::new(),
             is_margin_trading,
             request_trades: false,
             websocket_channels: vec![],
             currency_pairs: None,
             subscribe_to_market_data: true,
+            is_watch_only: false,
+            market_data_websocket_host: None,
+            order_entry_websocket_host: None,
+            separate_order_entry_connection: false,
             is_reducing_market_data: None,
+            order_rate_limits: None,
+            derivative: None,
+            fault_injection: None,
+            stream_watchdog: None,
+            order_validation: None,
+            sub_account: None,
+            fees: None,
+            stuck_orders: None,
+            external_orders: None,
+            system_status: None,
+            latency_throttling: None,
         }
     }
 }
@@ -88,16 +1229,393 @@ impl Default for ExchangeSettings {
             exchange_account_id: ExchangeAccountId::new("", 0),
             api_key: "".to_string(),
             secret_key: "".to_string(),
+            api_passphrase: "".to_string(),
             is_margin_trading: false,
             request_trades: false,
             websocket_channels: vec![],
             currency_pairs: None,
             subscribe_to_market_data: true,
+            is_watch_only: false,
+            market_data_websocket_host: None,
+            order_entry_websocket_host: None,
+            separate_order_entry_connection: false,
             is_reducing_market_data: None,
+            order_rate_limits: None,
+            derivative: None,
+            fault_injection: None,
+            stream_watchdog: None,
+            order_validation: None,
+            sub_account: None,
+            fees: None,
+            stuck_orders: None,
+            external_orders: None,
+            system_status: None,
+            latency_throttling: None,
+        }
+    }
+}
+
+/// Fees of exchange account by tiers of trading volume. Fees reported by exchange replace tier
+/// fees if exchange client can request them
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FeeSettings {
+    #[serde(flatten)]
+    pub schedule: FeeSchedule,
+    /// Trading volume of the account within fee schedule period which determines fee tier
+    #[serde(default)]
+    pub trading_volume: Decimal,
+    /// Interval of requesting fees from exchange. Fees are requested only at start if it isn't
+    /// specified
+    pub refresh_interval_secs: Option<u64>,
+}
+
+/// Token buckets for order requests of exchange account. Creates and cancels are counted
+/// separately, kinds of requests without bucket aren't limited
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OrderRateLimitSettings {
+    pub create: Option<TokenBucketSettings>,
+    pub cancel: Option<TokenBucketSettings>,
+}
+
+impl OrderRateLimitSettings {
+    /// Buckets should be refilled, otherwise requests would wait for tokens forever
+    pub fn validate(&self) -> Result<()> {
+        for (kind, bucket) in [("create", &self.create), ("cancel", &self.cancel)] {
+            if let Some(bucket) = bucket {
+                ensure!(
+                    bucket.refill_per_second > dec!(0),
+                    "refill_per_second of {kind} order rate limit should be positive, but it is {}",
+                    bucket.refill_per_second
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TokenBucketSettings {
+    /// Max count of requests which can be sent in a burst
+    pub capacity: u32,
+    /// Count of requests restored in bucket per second
+    pub refill_per_second: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StreamWatchdogSettings {
+    /// Max time without any websocket message of exchange account
+    pub max_exchange_silence_ms: u64,
+    /// Max time without order book updates of currency pair. Order book streams aren't checked
+    /// if it isn't set, e.g. when traded markets are illiquid
+    pub max_stream_silence_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SystemStatusSettings {
+    #[serde(default = "default_system_status_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// Trading is resumed after maintenance only if exchange reports normal status for this time
+    #[serde(default = "default_healthy_period_secs")]
+    pub healthy_period_secs: u64,
+}
+
+impl Default for SystemStatusSettings {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: default_system_status_poll_interval_secs(),
+            healthy_period_secs: default_healthy_period_secs(),
         }
     }
 }
 
+fn default_system_status_poll_interval_secs() -> u64 {
+    30
+}
+
+fn default_healthy_period_secs() -> u64 {
+    5 * 60
+}
+
+/// Thresholds of median round-trip latency of order creations or cancellations
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LatencyThrottlingSettings {
+    /// Spread is widened while latency exceeds it
+    pub widen_latency_millis: u64,
+    /// Quoting is paused while latency exceeds it
+    pub pause_latency_millis: u64,
+    /// Multiplier of spread while latency exceeds `widen_latency_millis`
+    #[serde(default = "default_latency_spread_multiplier")]
+    pub spread_multiplier: Decimal,
+    /// Only requests completed within this period are taken into account, so paused quoting is
+    /// resumed when latencies of slow requests are outdated
+    #[serde(default = "default_latency_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_latency_spread_multiplier() -> Decimal {
+    Decimal::TWO
+}
+
+fn default_latency_window_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClickHouseSettings {
+    /// Address of ClickHouse HTTP interface, e.g. `http://localhost:8123`
+    pub url: String,
+    #[serde(default = "default_clickhouse_database")]
+    pub database: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    /// Rows of table are inserted when count of buffered rows reaches batch size or flush
+    /// interval is elapsed
+    #[serde(default = "default_clickhouse_batch_size")]
+    pub batch_size: usize,
+    #[serde(default = "default_clickhouse_flush_interval_millis")]
+    pub flush_interval_millis: u64,
+    /// Max count of batches waiting for insertion. New batches are dropped while the queue is full
+    #[serde(default = "default_clickhouse_max_pending_batches")]
+    pub max_pending_batches: usize,
+    /// Count of the best price levels of every side of local order book written after applying
+    /// of order book event to it
+    #[serde(default = "default_clickhouse_book_depth")]
+    pub book_depth: usize,
+}
+
+fn default_clickhouse_database() -> String {
+    "default".to_owned()
+}
+
+fn default_clickhouse_batch_size() -> usize {
+    10_000
+}
+
+fn default_clickhouse_flush_interval_millis() -> u64 {
+    1000
+}
+
+fn default_clickhouse_max_pending_batches() -> usize {
+    64
+}
+
+fn default_clickhouse_book_depth() -> usize {
+    20
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KafkaSettings {
+    /// Comma separated addresses of Kafka brokers, e.g. `localhost:9092`
+    pub brokers: String,
+    /// Events are published to topics `{prefix}.orders`, `{prefix}.fills`, `{prefix}.balances`
+    /// and `{prefix}.stats`
+    #[serde(default = "default_kafka_topic_prefix")]
+    pub topic_prefix: String,
+    #[serde(default)]
+    pub serialization: KafkaSerialization,
+    /// Interval of publishing statistics of markets changed since the previous publishing
+    #[serde(default = "default_kafka_stats_interval_millis")]
+    pub stats_interval_millis: u64,
+    /// Additional properties of librdkafka producer, e.g. `security.protocol` or `linger.ms`
+    #[serde(default)]
+    pub producer_properties: HashMap<String, String>,
+}
+
+fn default_kafka_topic_prefix() -> String {
+    "mmb".to_owned()
+}
+
+fn default_kafka_stats_interval_millis() -> u64 {
+    1000
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KafkaSerialization {
+    /// Message is JSON object
+    #[default]
+    Json,
+    /// Message is Avro binary prefixed by magic byte and id of schema registered in schema
+    /// registry under subject `{topic}-value`
+    Avro { schema_registry_url: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RedisBridgeSettings {
+    /// Redis connection URL, e.g. `redis://127.0.0.1/`
+    pub url: String,
+    /// Pub/sub channel where engine events are published as JSON messages of event stream.
+    /// Events aren't published if it isn't specified
+    pub events_channel: Option<String>,
+    #[serde(default = "default_redis_bridge_events")]
+    pub events: Vec<RedisBridgeEvent>,
+    /// Interval of publishing statistics of markets changed since the previous publishing
+    #[serde(default = "default_redis_bridge_stats_interval_millis")]
+    pub stats_interval_millis: u64,
+    /// Pub/sub channel with JSON commands. Commands aren't received if it isn't specified
+    pub commands_channel: Option<String>,
+}
+
+fn default_redis_bridge_events() -> Vec<RedisBridgeEvent> {
+    vec![
+        RedisBridgeEvent::Orders,
+        RedisBridgeEvent::Fills,
+        RedisBridgeEvent::Balances,
+    ]
+}
+
+fn default_redis_bridge_stats_interval_millis() -> u64 {
+    1000
+}
+
+/// Kind of engine events published by Redis bridge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedisBridgeEvent {
+    Orders,
+    Fills,
+    Balances,
+    Stats,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OrderRetentionSettings {
+    /// Time after finishing of order when it's evicted from memory
+    #[serde(default = "default_finished_order_ttl_secs")]
+    pub finished_order_ttl_secs: u64,
+    /// Interval between checks of finished orders
+    #[serde(default = "default_order_retention_interval_secs")]
+    pub interval_secs: u64,
+    /// Save final state of evicted orders to `archived_orders` table
+    #[serde(default = "default_archive_orders")]
+    pub archive: bool,
+}
+
+fn default_finished_order_ttl_secs() -> u64 {
+    30 * 60
+}
+
+fn default_order_retention_interval_secs() -> u64 {
+    10 * 60
+}
+
+fn default_archive_orders() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StuckOrdersSettings {
+    /// Max time of order in `Creating` status
+    #[serde(default = "default_max_creating_secs")]
+    pub max_creating_secs: u64,
+    /// Max time of order in `Canceling` status
+    #[serde(default = "default_max_canceling_secs")]
+    pub max_canceling_secs: u64,
+}
+
+impl StuckOrdersSettings {
+    /// Stuck orders are checked twice per shortest threshold
+    pub fn check_interval(&self) -> std::time::Duration {
+        let min_secs = self.max_creating_secs.min(self.max_canceling_secs).max(1);
+        std::time::Duration::from_millis(min_secs * 1000 / 2)
+    }
+}
+
+impl Default for StuckOrdersSettings {
+    fn default() -> Self {
+        Self {
+            max_creating_secs: default_max_creating_secs(),
+            max_canceling_secs: default_max_canceling_secs(),
+        }
+    }
+}
+
+fn default_max_creating_secs() -> u64 {
+    60
+}
+
+fn default_max_canceling_secs() -> u64 {
+    60
+}
+
+/// What is done with order placed on exchange outside of engine after its adoption
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ExternalOrderPolicy {
+    /// Order is only tracked for fills
+    #[default]
+    Track,
+    /// Order is cancelled right after adoption, fills received before cancellation are tracked
+    Cancel,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ExternalOrdersSettings {
+    #[serde(default)]
+    pub policy: ExternalOrderPolicy,
+}
+
+/// How price or amount of order which isn't multiple of tick or lot size of symbol is handled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RoundingMode {
+    /// Price is rounded away from market (down for buy and up for sell order) and amount is
+    /// rounded down, so order never becomes more aggressive or bigger than requested
+    #[default]
+    Passive,
+    ToNearest,
+    /// Order is rejected locally
+    Reject,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OrderValidationSettings {
+    #[serde(default)]
+    pub price_rounding: RoundingMode,
+    #[serde(default)]
+    pub amount_rounding: RoundingMode,
+}
+
+/// Seed-controlled schedule of faults. The same seed produces the same sequence of faults for
+/// the same sequence of requests and messages
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FaultInjectionSettings {
+    pub seed: u64,
+    /// Upper bound of randomly chosen delay in milliseconds
+    pub max_delay_ms: u64,
+    #[serde(default)]
+    pub rest: FaultRates,
+    #[serde(default)]
+    pub websocket: FaultRates,
+}
+
+/// Probabilities of every kind of fault in range [0, 1]. Their sum must not exceed 1
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FaultRates {
+    #[serde(default)]
+    pub drop: Decimal,
+    #[serde(default)]
+    pub delay: Decimal,
+    #[serde(default)]
+    pub duplicate: Decimal,
+    #[serde(default)]
+    pub corrupt: Decimal,
+}
+
+/// Parameters of derivative positions. Unset parameters stay as configured on exchange
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DerivativeSettings {
+    pub leverage: Option<u8>,
+    pub margin_type: Option<MarginType>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum MarginType {
+    /// Margin is shared between all positions of account
+    Cross,
+    /// Margin is assigned to every position separately
+    Isolated,
+}
+
 pub struct CurrencyPriceSourceSettings {
     pub start_currency_code: CurrencyCode,
     pub end_currency_code: CurrencyCode,
@@ -138,3 +1656,40 @@ pub struct StopperCondition {
 pub struct ProfitLossStopperSettings {
     pub conditions: Vec<StopperCondition>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn balance_reconciliation_snaps_to_exchange_by_default() {
+        let settings: BalanceReconciliationSettings = toml_edit::de::from_str(
+            r#"
+            interval_secs = 30
+            tolerance_percent = "0.5"
+            "#,
+        )
+        .expect("in test");
+
+        assert_eq!(
+            settings,
+            BalanceReconciliationSettings {
+                interval_secs: 30,
+                tolerance_percent: dec!(0.5),
+                snap_to_exchange: true,
+                maintenance_tolerance_percent: None,
+            }
+        );
+
+        let settings: BalanceReconciliationSettings = toml_edit::de::from_str(
+            r#"
+            interval_secs = 30
+            tolerance_percent = "0.5"
+            snap_to_exchange = false
+            "#,
+        )
+        .expect("in test");
+        assert!(!settings.snap_to_exchange);
+    }
+}
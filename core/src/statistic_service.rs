@@ -1,30 +1,50 @@
 use anyhow::{Context, Result};
+use chrono::Duration;
+use dashmap::{DashMap, DashSet};
 use mmb_domain::order::event::OrderEventType;
 use mmb_utils::infrastructure::SpawnFutureFlags;
-use mmb_utils::nothing_to_do;
-use std::collections::{HashMap, HashSet};
+use mmb_utils::{nothing_to_do, DateTime};
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
 use std::sync::Arc;
 
 use mmb_domain::events::ExchangeEvent;
-use mmb_domain::market::MarketAccountId;
+use mmb_domain::market::{CurrencyCode, ExchangeAccountId, MarketAccountId};
 use mmb_domain::order::snapshot::ClientOrderId;
 use mmb_domain::order::snapshot::{Amount, Price};
+use mmb_domain::transfer::{FundsMovement, FundsMovementType};
 use parking_lot::{Mutex, RwLock};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast;
 
 use super::infrastructure::spawn_future;
+use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
+use crate::misc::clock::Clock;
+use crate::misc::trading_calendar::TradingCalendar;
+use crate::runtimes::runtimes_utilization;
+use crate::services::signals::QuotingMode;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+const SECONDS_IN_HOUR: u32 = 60 * 60;
+const SECONDS_IN_DAY: u32 = 24 * SECONDS_IN_HOUR;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MarketAccountIdStatistic {
-    opened_orders_count: u64,
-    canceled_orders_count: u64,
-    partially_filled_orders_count: u64,
-    fully_filled_orders_count: u64,
+    pub opened_orders_count: u64,
+    pub canceled_orders_count: u64,
+    pub partially_filled_orders_count: u64,
+    pub fully_filled_orders_count: u64,
     // Calculated only for completely filled orders
-    summary_filled_amount: Amount,
+    pub summary_filled_amount: Amount,
     // Calculated only for completely filled orders
-    summary_commission: Amount,
+    pub summary_commission: Amount,
+    // Time since engine start excluding non-trading windows of trading calendar
+    pub active_trading_time_secs: u64,
+    // Rates are normalized by active trading time, so they aren't diluted by non-trading windows
+    pub filled_orders_per_hour: Option<Decimal>,
+    pub filled_amount_per_day: Option<Amount>,
+    pub commission_per_day: Option<Amount>,
 }
 
 impl MarketAccountIdStatistic {
@@ -59,6 +79,81 @@ impl MarketAccountIdStatistic {
     fn add_summary_commission(&mut self, commission: Price) {
         self.summary_commission += commission;
     }
+
+    fn update_active_trading_time(&mut self, active_trading_time: Duration) {
+        self.active_trading_time_secs = active_trading_time.num_seconds().max(0) as u64;
+
+        if self.active_trading_time_secs == 0 {
+            self.filled_orders_per_hour = None;
+            self.filled_amount_per_day = None;
+            self.commission_per_day = None;
+            return;
+        }
+
+        let active_secs = Decimal::from(self.active_trading_time_secs);
+        let per_period =
+            |value: Decimal, period_secs: u32| value * Decimal::from(period_secs) / active_secs;
+
+        self.filled_orders_per_hour = Some(per_period(
+            self.fully_filled_orders_count.into(),
+            SECONDS_IN_HOUR,
+        ));
+        self.filled_amount_per_day = Some(per_period(self.summary_filled_amount, SECONDS_IN_DAY));
+        self.commission_per_day = Some(per_period(self.summary_commission, SECONDS_IN_DAY));
+    }
+}
+
+/// Order-to-trade activity and REST request weight of exchange account. Exchanges penalize
+/// accounts with too many cancellations per fill or too heavy request usage
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExchangeAccountStatistic {
+    pub canceled_orders_count: u64,
+    /// Count of fills of orders, so partially filled order is counted by each its fill
+    pub fills_count: u64,
+    /// Canceled orders per fill. It isn't set until the first fill
+    pub cancel_fill_ratio: Option<Decimal>,
+    /// Weight of all REST requests reserved since engine start
+    pub used_request_weight: u64,
+    /// Share of request limit used in current period, from 0 to 1
+    pub request_weight_utilization: Decimal,
+    /// Count of spread widenings because of degraded latency of order requests
+    #[serde(default)]
+    pub latency_widenings_count: u64,
+    /// Count of quoting pauses because of degraded latency of order requests
+    #[serde(default)]
+    pub latency_pauses_count: u64,
+    /// Count of returns to normal quoting after latency of order requests is recovered
+    #[serde(default)]
+    pub latency_recoveries_count: u64,
+}
+
+impl ExchangeAccountStatistic {
+    fn register_canceled_order(&mut self) {
+        self.canceled_orders_count += 1;
+        self.update_cancel_fill_ratio();
+    }
+
+    fn register_fill(&mut self) {
+        self.fills_count += 1;
+        self.update_cancel_fill_ratio();
+    }
+
+    fn register_latency_throttling(&mut self, mode: QuotingMode) {
+        match mode {
+            QuotingMode::Normal => self.latency_recoveries_count += 1,
+            QuotingMode::Widened(_) => self.latency_widenings_count += 1,
+            QuotingMode::Disabled => self.latency_pauses_count += 1,
+        }
+    }
+
+    fn update_cancel_fill_ratio(&mut self) {
+        self.cancel_fill_ratio = match self.fills_count {
+            0 => None,
+            fills_count => {
+                Some(Decimal::from(self.canceled_orders_count) / Decimal::from(fills_count))
+            }
+        };
+    }
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -66,16 +161,114 @@ pub struct DispositionExecutorStatistic {
     skipped_events_amount: u64,
 }
 
+/// Confirmed deposits and withdrawals of currency on exchange account
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FundsMovementStatistic {
+    deposits_count: u64,
+    deposited_amount: Amount,
+    withdrawals_count: u64,
+    withdrawn_amount: Amount,
+    withdrawal_fee: Amount,
+}
+
+impl FundsMovementStatistic {
+    fn register_funds_movement(&mut self, movement: &FundsMovement) {
+        match movement.movement_type {
+            FundsMovementType::Deposit => {
+                self.deposits_count += 1;
+                self.deposited_amount += movement.amount;
+            }
+            FundsMovementType::Withdrawal => {
+                self.withdrawals_count += 1;
+                self.withdrawn_amount += movement.amount;
+                self.withdrawal_fee += movement.fee;
+            }
+        }
+    }
+}
+
+/// Outcomes of two-legged arbitrage trades
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ArbitrageStatistic {
+    trades_count: u64,
+    /// Both legs are filled by the same amount
+    hedged_count: u64,
+    /// Legs are filled by different amounts
+    legged_count: u64,
+    /// Neither leg is filled
+    missed_count: u64,
+    /// Bought amount minus sold amount over legged trades
+    unhedged_amount: Amount,
+}
+
+impl ArbitrageStatistic {
+    fn register_trade(&mut self, bought_amount: Amount, sold_amount: Amount) {
+        self.trades_count += 1;
+        if bought_amount.is_zero() && sold_amount.is_zero() {
+            self.missed_count += 1;
+        } else if bought_amount == sold_amount {
+            self.hedged_count += 1;
+        } else {
+            self.legged_count += 1;
+            self.unhedged_amount += bought_amount - sold_amount;
+        }
+    }
+}
+
+/// Crashes of task run by `Supervisor`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskCrashStatistic {
+    pub panics_count: u64,
+    pub errors_count: u64,
+    pub restarts_count: u64,
+}
+
+/// Overflows of bounded event queue of consumer
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventQueueStatistic {
+    /// Events dropped by overflow policy or because engine events channel overflowed while the
+    /// queue was full
+    pub dropped_events_count: u64,
+    /// Order book events merged into waiting events of the same market
+    pub coalesced_events_count: u64,
+    /// Count of waits for free space in the queue
+    pub blocked_count: u64,
+}
+
+/// Load of dedicated runtime since the previous statistics request
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeStatistic {
+    pub workers_count: usize,
+    /// Share of time worker threads weren't parked, from 0 to 1
+    pub utilization: Decimal,
+}
+
+/// Statistics are sharded by trade places and exchange accounts, so events of different pairs
+/// don't serialize on single lock
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub(crate) struct StatisticServiceState {
-    market_account_id_stats: RwLock<HashMap<MarketAccountId, MarketAccountIdStatistic>>,
+    market_account_id_stats: DashMap<MarketAccountId, MarketAccountIdStatistic>,
+    #[serde(default)]
+    exchange_account_stats: DashMap<ExchangeAccountId, ExchangeAccountStatistic>,
     disposition_executor_stats: Mutex<DispositionExecutorStatistic>,
+    funds_movement_stats:
+        RwLock<HashMap<ExchangeAccountId, HashMap<CurrencyCode, FundsMovementStatistic>>>,
+    /// Arbitrage trades by strategy name
+    arbitrage_stats: DashMap<String, ArbitrageStatistic>,
+    /// Crashes of supervised tasks by task name
+    #[serde(default)]
+    task_crash_stats: DashMap<String, TaskCrashStatistic>,
+    /// Overflows of bounded event queues by consumer name
+    #[serde(default)]
+    event_queue_stats: DashMap<String, EventQueueStatistic>,
+    /// Utilization of dedicated runtimes by runtime name
+    #[serde(default)]
+    runtime_stats: DashMap<String, RuntimeStatistic>,
 }
 
 impl StatisticServiceState {
     pub(crate) fn register_created_order(&self, market_account_id: MarketAccountId) {
         self.market_account_id_stats
-            .write()
             .entry(market_account_id)
             .or_default()
             .register_created_order();
@@ -83,15 +276,36 @@ impl StatisticServiceState {
 
     pub(crate) fn register_canceled_order(&self, market_account_id: MarketAccountId) {
         self.market_account_id_stats
-            .write()
             .entry(market_account_id)
             .or_default()
             .register_canceled_order();
+
+        self.exchange_account_stats
+            .entry(market_account_id.exchange_account_id)
+            .or_default()
+            .register_canceled_order();
+    }
+
+    pub(crate) fn register_fill(&self, exchange_account_id: ExchangeAccountId) {
+        self.exchange_account_stats
+            .entry(exchange_account_id)
+            .or_default()
+            .register_fill();
+    }
+
+    pub(crate) fn register_latency_throttling(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        mode: QuotingMode,
+    ) {
+        self.exchange_account_stats
+            .entry(exchange_account_id)
+            .or_default()
+            .register_latency_throttling(mode);
     }
 
     pub(crate) fn register_partially_filled_order(&self, market_account_id: MarketAccountId) {
         self.market_account_id_stats
-            .write()
             .entry(market_account_id)
             .or_default()
             .increment_partially_filled_orders();
@@ -99,61 +313,200 @@ impl StatisticServiceState {
 
     fn decrement_partially_filled_orders(&self, market_account_id: MarketAccountId) {
         self.market_account_id_stats
-            .write()
             .entry(market_account_id)
             .or_default()
             .decrement_partially_filled_orders();
     }
 
-    pub(crate) fn register_completely_filled_order(&self, market_account_id: MarketAccountId) {
-        self.market_account_id_stats
-            .write()
-            .entry(market_account_id)
-            .or_default()
-            .increment_completely_filled_orders();
-    }
-
-    pub(crate) fn register_filled_amount(
+    /// Registers completely filled order with its filled amount and commission at once
+    pub(crate) fn register_completely_filled_order(
         &self,
         market_account_id: MarketAccountId,
         filled_amount: Amount,
+        commission: Price,
     ) {
-        self.market_account_id_stats
-            .write()
+        let mut stats = self
+            .market_account_id_stats
             .entry(market_account_id)
+            .or_default();
+        stats.increment_completely_filled_orders();
+        stats.add_summary_filled_amount(filled_amount);
+        stats.add_summary_commission(commission);
+    }
+
+    pub(crate) fn register_skipped_event(&self) {
+        self.disposition_executor_stats.lock().skipped_events_amount += 1;
+    }
+
+    pub(crate) fn register_funds_movement(&self, movement: &FundsMovement) {
+        self.funds_movement_stats
+            .write()
+            .entry(movement.exchange_account_id)
+            .or_default()
+            .entry(movement.currency_code)
             .or_default()
-            .add_summary_filled_amount(filled_amount);
+            .register_funds_movement(movement);
     }
 
-    pub(crate) fn register_commission(
+    pub(crate) fn register_arbitrage_trade(
         &self,
-        market_account_id: MarketAccountId,
-        commission: Price,
+        strategy_name: &str,
+        bought_amount: Amount,
+        sold_amount: Amount,
     ) {
-        self.market_account_id_stats
-            .write()
-            .entry(market_account_id)
+        self.arbitrage_stats
+            .entry(strategy_name.to_owned())
             .or_default()
-            .add_summary_commission(commission);
+            .register_trade(bought_amount, sold_amount);
     }
 
-    pub(crate) fn register_skipped_event(&self) {
-        self.disposition_executor_stats.lock().skipped_events_amount += 1;
+    pub(crate) fn register_task_crash(&self, task_name: &str, is_panic: bool) {
+        let mut stats = self
+            .task_crash_stats
+            .entry(task_name.to_owned())
+            .or_default();
+        match is_panic {
+            true => stats.panics_count += 1,
+            false => stats.errors_count += 1,
+        }
+    }
+
+    pub(crate) fn register_task_restart(&self, task_name: &str) {
+        self.task_crash_stats
+            .entry(task_name.to_owned())
+            .or_default()
+            .restarts_count += 1;
+    }
+
+    pub(crate) fn register_dropped_events(&self, consumer: &str, count: u64) {
+        self.update_event_queue_stats(consumer, |x| x.dropped_events_count += count);
+    }
+
+    pub(crate) fn register_coalesced_event(&self, consumer: &str) {
+        self.update_event_queue_stats(consumer, |x| x.coalesced_events_count += 1);
+    }
+
+    pub(crate) fn register_blocked_event_queue(&self, consumer: &str) {
+        self.update_event_queue_stats(consumer, |x| x.blocked_count += 1);
+    }
+
+    /// Name of consumer isn't copied for every event, because it's registered for every order
+    /// book update of conflated queues
+    fn update_event_queue_stats(
+        &self,
+        consumer: &str,
+        action: impl FnOnce(&mut EventQueueStatistic),
+    ) {
+        match self.event_queue_stats.get_mut(consumer) {
+            Some(mut stats) => action(&mut stats),
+            None => action(
+                &mut self
+                    .event_queue_stats
+                    .entry(consumer.to_owned())
+                    .or_default(),
+            ),
+        }
+    }
+
+    fn update_active_trading_time(
+        &self,
+        get_active_trading_time: impl Fn(MarketAccountId) -> Duration,
+    ) {
+        for mut stats in self.market_account_id_stats.iter_mut() {
+            let active_trading_time = get_active_trading_time(*stats.key());
+            stats.update_active_trading_time(active_trading_time);
+        }
+    }
+
+    fn update_request_weight(&self, timeout_manager: &TimeoutManager) {
+        for mut stats in self.exchange_account_stats.iter_mut() {
+            let exchange_account_id = *stats.key();
+            stats.used_request_weight = timeout_manager.used_weight(exchange_account_id);
+            stats.request_weight_utilization = timeout_manager.utilization(exchange_account_id);
+        }
     }
 }
 
-#[derive(Default, Debug)]
 pub struct StatisticService {
     pub(crate) statistic_service_state: StatisticServiceState,
-    partially_filled_orders: Mutex<HashSet<ClientOrderId>>,
+    partially_filled_orders: DashSet<ClientOrderId>,
+    trading_calendar: TradingCalendar,
+    timeout_manager: Option<Arc<TimeoutManager>>,
+    clock: Arc<dyn Clock>,
+    started_at: DateTime,
 }
 
 impl StatisticService {
-    pub fn new() -> Arc<Self> {
-        Default::default()
+    pub fn new(trading_calendar: TradingCalendar, clock: Arc<dyn Clock>) -> Arc<Self> {
+        Arc::new(StatisticService {
+            statistic_service_state: Default::default(),
+            partially_filled_orders: Default::default(),
+            trading_calendar,
+            timeout_manager: None,
+            started_at: clock.now(),
+            clock,
+        })
     }
 
-    pub(crate) fn register_created_order(&self, market_account_id: MarketAccountId) {
+    /// Statistic service which additionally tracks REST request weight of exchange accounts by
+    /// the timeout manager. Clock of the timeout manager is used
+    pub fn with_timeout_manager(
+        trading_calendar: TradingCalendar,
+        timeout_manager: Arc<TimeoutManager>,
+    ) -> Arc<Self> {
+        let clock = timeout_manager.clock().clone();
+        Arc::new(StatisticService {
+            statistic_service_state: Default::default(),
+            partially_filled_orders: Default::default(),
+            trading_calendar,
+            timeout_manager: Some(timeout_manager),
+            started_at: clock.now(),
+            clock,
+        })
+    }
+
+    /// Order-to-trade statistics of every exchange account with up to date request weight
+    pub fn exchange_account_stats(&self) -> HashMap<ExchangeAccountId, ExchangeAccountStatistic> {
+        self.update_request_weight();
+        self.statistic_service_state
+            .exchange_account_stats
+            .iter()
+            .map(|x| (*x.key(), x.value().clone()))
+            .collect()
+    }
+
+    /// Updates used REST request weight of every exchange account
+    pub(crate) fn update_request_weight(&self) {
+        if let Some(timeout_manager) = &self.timeout_manager {
+            self.statistic_service_state
+                .update_request_weight(timeout_manager);
+        }
+    }
+
+    /// Statistics of every trade place with up to date active trading time
+    pub fn market_account_id_stats(&self) -> Vec<(MarketAccountId, MarketAccountIdStatistic)> {
+        self.update_active_trading_time();
+        self.statistic_service_state
+            .market_account_id_stats
+            .iter()
+            .map(|x| (*x.key(), x.value().clone()))
+            .collect()
+    }
+
+    /// Updates active trading time of every trade place and rates normalized by it
+    pub(crate) fn update_active_trading_time(&self) {
+        let now = self.clock.now();
+        self.statistic_service_state
+            .update_active_trading_time(|market_account_id| {
+                self.trading_calendar.active_duration(
+                    market_account_id.exchange_account_id,
+                    self.started_at,
+                    now,
+                )
+            });
+    }
+
+    pub fn register_created_order(&self, market_account_id: MarketAccountId) {
         self.statistic_service_state
             .register_created_order(market_account_id);
     }
@@ -174,16 +527,13 @@ impl StatisticService {
         market_account_id: MarketAccountId,
         client_order_id: &ClientOrderId,
     ) {
-        let mut partially_filled_orders = self.partially_filled_orders.lock();
-
-        if !(*partially_filled_orders).contains(client_order_id) {
+        if self.partially_filled_orders.insert(client_order_id.clone()) {
             self.statistic_service_state
                 .register_partially_filled_order(market_account_id);
-            let _ = partially_filled_orders.insert(client_order_id.clone());
         }
     }
 
-    pub(crate) fn register_completely_filled_order(
+    pub fn register_completely_filled_order(
         &self,
         market_account_id: MarketAccountId,
         client_order_id: &ClientOrderId,
@@ -191,15 +541,9 @@ impl StatisticService {
         commission: Amount,
     ) {
         self.statistic_service_state
-            .register_completely_filled_order(market_account_id);
+            .register_completely_filled_order(market_account_id, filled_amount, commission);
 
         self.remove_filled_order_if_exist(market_account_id, client_order_id);
-
-        self.statistic_service_state
-            .register_filled_amount(market_account_id, filled_amount);
-
-        self.statistic_service_state
-            .register_commission(market_account_id, commission);
     }
 
     fn remove_filled_order_if_exist(
@@ -207,18 +551,132 @@ impl StatisticService {
         market_account_id: MarketAccountId,
         client_order_id: &ClientOrderId,
     ) {
-        let mut partially_filled_orders = self.partially_filled_orders.lock();
-
-        if (*partially_filled_orders).contains(client_order_id) {
+        if self
+            .partially_filled_orders
+            .remove(client_order_id)
+            .is_some()
+        {
             self.statistic_service_state
                 .decrement_partially_filled_orders(market_account_id);
-            let _ = partially_filled_orders.remove(client_order_id);
         }
     }
 
+    /// Registers fill of order, every fill of partially filled order is registered separately
+    pub fn register_fill(&self, exchange_account_id: ExchangeAccountId) {
+        self.statistic_service_state
+            .register_fill(exchange_account_id);
+    }
+
+    /// Registers change of quoting mode of exchange account by latency of order requests
+    pub(crate) fn register_latency_throttling(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        mode: QuotingMode,
+    ) {
+        self.statistic_service_state
+            .register_latency_throttling(exchange_account_id, mode);
+    }
+
     pub(crate) fn register_skipped_event(&self) {
         self.statistic_service_state.register_skipped_event();
     }
+
+    /// Registers deposit or withdrawal confirmed by exchange
+    pub(crate) fn register_funds_movement(&self, movement: &FundsMovement) {
+        self.statistic_service_state
+            .register_funds_movement(movement);
+    }
+
+    /// Registers filled amounts of buy and sell legs of arbitrage trade
+    pub fn register_arbitrage_trade(
+        &self,
+        strategy_name: &str,
+        bought_amount: Amount,
+        sold_amount: Amount,
+    ) {
+        self.statistic_service_state.register_arbitrage_trade(
+            strategy_name,
+            bought_amount,
+            sold_amount,
+        );
+    }
+
+    /// Registers panic or error of task run by `Supervisor`
+    pub(crate) fn register_task_crash(&self, task_name: &str, is_panic: bool) {
+        self.statistic_service_state
+            .register_task_crash(task_name, is_panic);
+    }
+
+    pub(crate) fn register_task_restart(&self, task_name: &str) {
+        self.statistic_service_state
+            .register_task_restart(task_name);
+    }
+
+    /// Crashes of tasks run by `Supervisor` by task name
+    pub fn task_crash_stats(&self) -> HashMap<String, TaskCrashStatistic> {
+        self.statistic_service_state
+            .task_crash_stats
+            .iter()
+            .map(|x| (x.key().clone(), x.value().clone()))
+            .collect()
+    }
+
+    /// Registers events dropped by bounded event queue of consumer
+    pub(crate) fn register_dropped_events(&self, consumer: &str, count: u64) {
+        self.statistic_service_state
+            .register_dropped_events(consumer, count);
+    }
+
+    pub(crate) fn register_coalesced_event(&self, consumer: &str) {
+        self.statistic_service_state
+            .register_coalesced_event(consumer);
+    }
+
+    pub(crate) fn register_blocked_event_queue(&self, consumer: &str) {
+        self.statistic_service_state
+            .register_blocked_event_queue(consumer);
+    }
+
+    pub(crate) fn update_runtime_utilization(&self) {
+        for x in runtimes_utilization() {
+            let _ = self.statistic_service_state.runtime_stats.insert(
+                x.kind.to_string(),
+                RuntimeStatistic {
+                    workers_count: x.workers_count,
+                    utilization: x.utilization,
+                },
+            );
+        }
+    }
+
+    /// Utilization of dedicated runtimes by runtime name
+    pub fn runtime_stats(&self) -> HashMap<String, RuntimeStatistic> {
+        self.statistic_service_state
+            .runtime_stats
+            .iter()
+            .map(|x| (x.key().clone(), x.value().clone()))
+            .collect()
+    }
+
+    /// Overflows of bounded event queues by consumer name
+    pub fn event_queue_stats(&self) -> HashMap<String, EventQueueStatistic> {
+        self.statistic_service_state
+            .event_queue_stats
+            .iter()
+            .map(|x| (x.key().clone(), x.value().clone()))
+            .collect()
+    }
+}
+
+impl Debug for StatisticService {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatisticService")
+            .field("statistic_service_state", &self.statistic_service_state)
+            .field("partially_filled_orders", &self.partially_filled_orders)
+            .field("trading_calendar", &self.trading_calendar)
+            .field("started_at", &self.started_at)
+            .finish()
+    }
 }
 
 pub struct StatisticEventHandler {
@@ -258,7 +716,7 @@ impl StatisticEventHandler {
         }
     }
 
-    fn handle_event(&self, event: ExchangeEvent) -> Result<()> {
+    pub(crate) fn handle_event(&self, event: ExchangeEvent) -> Result<()> {
         match event {
             ExchangeEvent::OrderEvent(order_event) => {
                 let market_account_id = MarketAccountId::new(
@@ -275,6 +733,8 @@ impl StatisticEventHandler {
                             .register_canceled_order(market_account_id, &client_order_id);
                     }
                     OrderEventType::OrderFilled { cloned_order } => {
+                        self.stats
+                            .register_fill(market_account_id.exchange_account_id);
                         self.stats.register_partially_filled_order(
                             market_account_id,
                             &cloned_order.header.client_order_id,
@@ -306,3 +766,115 @@ impl StatisticEventHandler {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::market::CurrencyPair;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn rates_are_normalized_by_active_trading_time() {
+        let mut stats = MarketAccountIdStatistic::default();
+        for _ in 0..6 {
+            stats.increment_completely_filled_orders();
+        }
+        stats.add_summary_filled_amount(dec!(3));
+        stats.add_summary_commission(dec!(0.5));
+
+        stats.update_active_trading_time(Duration::hours(12));
+
+        assert_eq!(stats.active_trading_time_secs, 12 * 60 * 60);
+        assert_eq!(stats.filled_orders_per_hour, Some(dec!(0.5)));
+        assert_eq!(stats.filled_amount_per_day, Some(dec!(6)));
+        assert_eq!(stats.commission_per_day, Some(dec!(1)));
+    }
+
+    #[test]
+    fn no_rates_without_active_trading_time() {
+        let mut stats = MarketAccountIdStatistic::default();
+        stats.increment_completely_filled_orders();
+
+        stats.update_active_trading_time(Duration::zero());
+
+        assert_eq!(stats.filled_orders_per_hour, None);
+    }
+
+    #[test]
+    fn concurrent_fills_of_different_trade_places_are_counted() {
+        let state = StatisticServiceState::default();
+        let market_account_ids: Vec<_> = ["btc", "eth", "ltc", "xrp"]
+            .iter()
+            .map(|base| {
+                MarketAccountId::new(
+                    ExchangeAccountId::new("Binance", 0),
+                    CurrencyPair::from_codes((*base).into(), "usdt".into()),
+                )
+            })
+            .collect();
+
+        std::thread::scope(|s| {
+            for &market_account_id in &market_account_ids {
+                let state = &state;
+                s.spawn(move || {
+                    for _ in 0..1000 {
+                        state.register_completely_filled_order(
+                            market_account_id,
+                            dec!(1),
+                            dec!(0.1),
+                        );
+                        state.register_fill(market_account_id.exchange_account_id);
+                    }
+                });
+            }
+        });
+
+        for market_account_id in &market_account_ids {
+            let stats = state
+                .market_account_id_stats
+                .get(market_account_id)
+                .expect("statistic of trade place should be registered");
+            assert_eq!(stats.fully_filled_orders_count, 1000);
+            assert_eq!(stats.summary_filled_amount, dec!(1000));
+            assert_eq!(stats.summary_commission, dec!(100));
+        }
+        let exchange_account_id = ExchangeAccountId::new("Binance", 0);
+        assert_eq!(
+            state
+                .exchange_account_stats
+                .get(&exchange_account_id)
+                .expect("statistic of exchange account should be registered")
+                .fills_count,
+            4000
+        );
+    }
+
+    #[test]
+    fn arbitrage_trades_are_classified_by_filled_legs() {
+        let mut stats = ArbitrageStatistic::default();
+        stats.register_trade(dec!(1), dec!(1));
+        stats.register_trade(dec!(0), dec!(0));
+        stats.register_trade(dec!(1), dec!(0.4));
+        stats.register_trade(dec!(0), dec!(0.1));
+
+        assert_eq!(stats.trades_count, 4);
+        assert_eq!(stats.hedged_count, 1);
+        assert_eq!(stats.missed_count, 1);
+        assert_eq!(stats.legged_count, 2);
+        assert_eq!(stats.unhedged_amount, dec!(0.5));
+    }
+
+    #[test]
+    fn cancel_fill_ratio_is_set_after_first_fill() {
+        let mut stats = ExchangeAccountStatistic::default();
+        stats.register_canceled_order();
+        stats.register_canceled_order();
+        stats.register_canceled_order();
+        assert_eq!(stats.cancel_fill_ratio, None);
+
+        stats.register_fill();
+        stats.register_fill();
+
+        assert_eq!(stats.cancel_fill_ratio, Some(dec!(1.5)));
+    }
+}
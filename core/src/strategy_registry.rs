@@ -0,0 +1,873 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use futures::future::join_all;
+use itertools::Itertools;
+use mmb_domain::events::ExchangeEvent;
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId, MarketAccountId, MarketId};
+use mmb_domain::order::event::OrderEventType;
+use mmb_domain::order::pool::{OrderRef, OrdersFilter};
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, OrderHeader, OrderSide, OrderSnapshot, UserOrder,
+};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::nothing_to_do;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::trading_engine::{EngineContext, Service};
+use crate::misc::trading_calendar::TradingCalendar;
+use crate::risk::risk_manager::{RiskContext, RiskManager};
+use crate::services::event_queue::EventQueue;
+use crate::settings::{EventQueueSettings, StrategyInstanceSettings};
+use crate::statistic_service::{StatisticEventHandler, StatisticService, StatisticServiceState};
+
+const STRATEGY_REGISTRY: &str = "StrategyRegistry";
+const DEFAULT_TIMER_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Trading logic run by `StrategyRegistry`. Orders of the strategy should be created through
+/// `StrategyContext`, so they are counted in statistics and risk budget of the strategy
+#[async_trait]
+pub trait Strategy: Send + Sync + 'static {
+    /// Called for market data events and events of orders of this strategy while it's running
+    async fn on_event(&self, ctx: &StrategyContext, event: &ExchangeEvent) -> Result<()>;
+
+    /// Called periodically with interval from settings of the strategy while it's running
+    async fn on_timer(&self, _ctx: &StrategyContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when strategy is stopped. Not finished orders of the strategy are canceled after it
+    async fn on_stop(&self, _ctx: &StrategyContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// State of strategy isolated from other strategies of the engine
+pub struct StrategyContext {
+    name: String,
+    engine_context: Arc<EngineContext>,
+    statistics: Arc<StatisticService>,
+    risk_budget: Option<Arc<RiskManager>>,
+    /// Signed net positions by fills of orders of the strategy
+    positions: Mutex<HashMap<MarketAccountId, Amount>>,
+    /// Last known filled amount of not finished orders of the strategy
+    filled_amounts: Mutex<HashMap<ClientOrderId, Amount>>,
+    /// Token of current run, `None` if strategy is stopped
+    run_token: Mutex<Option<CancellationToken>>,
+}
+
+impl StrategyContext {
+    fn new(
+        name: String,
+        engine_context: Arc<EngineContext>,
+        settings: Option<&StrategyInstanceSettings>,
+    ) -> Self {
+        let statistics = StatisticService::new(
+            TradingCalendar::new(engine_context.core_settings.trading_calendar.as_ref()),
+            engine_context.timeout_manager.clock().clone(),
+        );
+        let risk_budget = settings
+            .and_then(|x| x.risk_budget.clone())
+            .map(|risk_budget| {
+                let risk_budget = RiskManager::new(risk_budget);
+                if let Some(cross_rates) = &engine_context.cross_rates {
+                    risk_budget.setup_cross_rates(cross_rates);
+                }
+                risk_budget
+            });
+
+        StrategyContext {
+            name,
+            engine_context,
+            statistics,
+            risk_budget,
+            positions: Default::default(),
+            filled_amounts: Default::default(),
+            run_token: Default::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn engine_context(&self) -> &Arc<EngineContext> {
+        &self.engine_context
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.run_token.lock().is_some()
+    }
+
+    /// Token cancelled when the strategy is stopped
+    pub fn cancellation_token(&self) -> Option<CancellationToken> {
+        self.run_token.lock().clone()
+    }
+
+    /// Signed net position by fills of orders of the strategy
+    pub fn position(&self, market_account_id: MarketAccountId) -> Amount {
+        self.positions
+            .lock()
+            .get(&market_account_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn open_orders(&self) -> Vec<OrderRef> {
+        self.engine_context
+            .exchanges
+            .iter()
+            .flat_map(|exchange| {
+                exchange.orders.find_not_finished(&OrdersFilter {
+                    strategy_name: Some(self.name.clone()),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    /// Creates order on behalf of the strategy if it fits into risk budget of the strategy
+    pub async fn create_order(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        side: OrderSide,
+        amount: Amount,
+        user_order: UserOrder,
+    ) -> Result<OrderRef> {
+        self.create_order_with_id(
+            ClientOrderId::unique_id(),
+            exchange_account_id,
+            currency_pair,
+            side,
+            amount,
+            user_order,
+        )
+        .await
+    }
+
+    /// Same as `create_order`, but with client order id assigned by the caller in advance
+    pub async fn create_order_with_id(
+        &self,
+        client_order_id: ClientOrderId,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        side: OrderSide,
+        amount: Amount,
+        user_order: UserOrder,
+    ) -> Result<OrderRef> {
+        let cancellation_token = self
+            .cancellation_token()
+            .with_context(|| format!("Strategy '{}' isn't running", self.name))?;
+
+        let header = OrderHeader::with_user_order(
+            client_order_id,
+            exchange_account_id,
+            currency_pair,
+            side,
+            amount,
+            user_order,
+            None,
+            None,
+            self.name.clone(),
+        );
+
+        let exchange = self.exchange(exchange_account_id)?;
+        self.check_risk_budget(&exchange, &header)?;
+        exchange
+            .create_order(&header, None, cancellation_token)
+            .await
+    }
+
+    pub async fn cancel_order(&self, order: &OrderRef) -> Result<()> {
+        let exchange = self.exchange(order.exchange_account_id())?;
+        let _ = exchange
+            .cancel_order(order, self.engine_context.lifetime_manager.stop_token())
+            .await;
+        Ok(())
+    }
+
+    fn check_risk_budget(&self, exchange: &Exchange, header: &OrderHeader) -> Result<()> {
+        let risk_budget = match &self.risk_budget {
+            Some(risk_budget) => risk_budget,
+            None => return Ok(()),
+        };
+
+        let context = RiskContext {
+            mid_price: exchange.mid_price(header.currency_pair),
+            position: self.position(MarketAccountId::new(
+                header.exchange_account_id,
+                header.currency_pair,
+            )),
+            open_orders_count: self.open_orders().len(),
+            request_weight_utilization: self
+                .engine_context
+                .timeout_manager
+                .utilization(header.exchange_account_id),
+            volatility: self.engine_context.volatility.volatility(MarketId::new(
+                header.exchange_account_id.exchange_id,
+                header.currency_pair,
+            )),
+        };
+        risk_budget.check(header, &context).map_err(|violation| {
+            anyhow!(
+                "Order {} exceeds risk budget of strategy '{}': {violation}",
+                header.client_order_id,
+                self.name
+            )
+        })
+    }
+
+    fn exchange(&self, exchange_account_id: ExchangeAccountId) -> Result<Arc<Exchange>> {
+        self.engine_context
+            .exchanges
+            .get(&exchange_account_id)
+            .map(|x| x.value().clone())
+            .with_context(|| format!("Exchange {exchange_account_id} isn't found"))
+    }
+
+    fn is_own_order_event(&self, event: &ExchangeEvent) -> Option<bool> {
+        match event {
+            ExchangeEvent::OrderEvent(order_event) => {
+                Some(order_event.order.header().strategy_name == self.name)
+            }
+            _ => None,
+        }
+    }
+
+    /// Updates statistics and positions of the strategy by event of its order
+    fn handle_order_event(&self, event: &ExchangeEvent) {
+        let order_event = match event {
+            ExchangeEvent::OrderEvent(order_event) => order_event,
+            _ => return,
+        };
+
+        match &order_event.event_type {
+            OrderEventType::OrderFilled { cloned_order } => self.update_position(cloned_order),
+            OrderEventType::OrderCompleted { cloned_order } => {
+                self.update_position(cloned_order);
+                let _ = self
+                    .filled_amounts
+                    .lock()
+                    .remove(&cloned_order.header.client_order_id);
+            }
+            OrderEventType::CancelOrderSucceeded => {
+                let _ = self
+                    .filled_amounts
+                    .lock()
+                    .remove(&order_event.order.client_order_id());
+            }
+            _ => nothing_to_do(),
+        }
+
+        let statistic_event_handler = StatisticEventHandler {
+            stats: self.statistics.clone(),
+        };
+        if let Err(err) = statistic_event_handler.handle_event(event.clone()) {
+            log::error!(
+                "Failed to update statistics of strategy '{}': {err:?}",
+                self.name
+            );
+        }
+    }
+
+    fn update_position(&self, order: &OrderSnapshot) {
+        let header = &order.header;
+        let filled_amount = order.fills.filled_amount;
+        let previous_filled_amount = self
+            .filled_amounts
+            .lock()
+            .insert(header.client_order_id.clone(), filled_amount)
+            .unwrap_or_default();
+
+        let filled_delta = filled_amount - previous_filled_amount;
+        let signed_delta = match header.side {
+            OrderSide::Buy => filled_delta,
+            OrderSide::Sell => -filled_delta,
+        };
+        *self
+            .positions
+            .lock()
+            .entry(MarketAccountId::new(
+                header.exchange_account_id,
+                header.currency_pair,
+            ))
+            .or_default() += signed_delta;
+
+        if let Some(risk_budget) = &self.risk_budget {
+            risk_budget.update_exposures(|exchange_account_id, currency_pair| {
+                self.position(MarketAccountId::new(exchange_account_id, currency_pair))
+            });
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct StrategyStatus<'a> {
+    name: &'a str,
+    is_running: bool,
+    positions: HashMap<String, Amount>,
+    statistic: &'a StatisticServiceState,
+}
+
+struct RegisteredStrategy {
+    strategy: Arc<dyn Strategy>,
+    context: Arc<StrategyContext>,
+    timer_interval: Duration,
+    event_queue: EventQueueSettings,
+}
+
+/// Strategies running in the same engine. Each strategy has its own statistics and risk
+/// budget and can be started and stopped independently from other ones
+#[derive(Default)]
+pub struct StrategyRegistry {
+    strategies: Mutex<HashMap<String, Arc<RegisteredStrategy>>>,
+}
+
+impl StrategyRegistry {
+    /// Registers strategy and starts it. Timer interval and risk budget are taken from
+    /// settings of strategy with the same name
+    pub fn register(
+        &self,
+        engine_context: Arc<EngineContext>,
+        name: &str,
+        strategy: Arc<dyn Strategy>,
+    ) -> Result<()> {
+        let settings = engine_context
+            .core_settings
+            .strategies
+            .iter()
+            .find(|x| x.name == name)
+            .cloned();
+        let timer_interval = settings
+            .as_ref()
+            .map(|x| Duration::from_millis(x.timer_interval_millis.max(1)))
+            .unwrap_or(DEFAULT_TIMER_INTERVAL);
+        let event_queue = settings
+            .as_ref()
+            .and_then(|x| x.event_queue.clone())
+            .unwrap_or_default();
+
+        let registered = {
+            let mut strategies = self.strategies.lock();
+            if strategies.contains_key(name) {
+                bail!("Strategy '{name}' is already registered");
+            }
+
+            let registered = Arc::new(RegisteredStrategy {
+                strategy,
+                context: Arc::new(StrategyContext::new(
+                    name.to_owned(),
+                    engine_context,
+                    settings.as_ref(),
+                )),
+                timer_interval,
+                event_queue,
+            });
+            let _ = strategies.insert(name.to_owned(), registered.clone());
+            registered
+        };
+
+        log::info!("Strategy '{name}' is registered");
+        start(registered)
+    }
+
+    pub fn start(&self, name: &str) -> Result<()> {
+        start(self.get(name)?)
+    }
+
+    pub fn stop(&self, name: &str) -> Result<()> {
+        let registered = self.get(name)?;
+        let run_token = registered
+            .context
+            .run_token
+            .lock()
+            .take()
+            .with_context(|| format!("Strategy '{name}' isn't running"))?;
+        run_token.cancel();
+
+        spawn_future(
+            &format!("Stop strategy '{name}'"),
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            async move {
+                stop(&registered).await;
+
+                let orders = registered.context.open_orders();
+                join_all(orders.iter().map(|x| registered.context.cancel_order(x))).await;
+                Ok(())
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Stops calling strategy callbacks without cancellation of its orders. Strategy is resumed
+    /// by `start`
+    pub fn pause(&self, name: &str) -> Result<()> {
+        let registered = self.get(name)?;
+        let run_token = registered
+            .context
+            .run_token
+            .lock()
+            .take()
+            .with_context(|| format!("Strategy '{name}' isn't running"))?;
+        run_token.cancel();
+        log::info!("Strategy '{name}' is paused");
+
+        Ok(())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.strategies.lock().keys().sorted().cloned().collect()
+    }
+
+    /// Serialized states of all registered strategies
+    pub fn statuses(&self) -> Result<String> {
+        let strategies = self
+            .strategies
+            .lock()
+            .values()
+            .cloned()
+            .sorted_by(|a, b| a.context.name.cmp(&b.context.name))
+            .collect_vec();
+
+        let statuses = strategies
+            .iter()
+            .map(|x| {
+                let context = &x.context;
+                context.statistics.update_active_trading_time();
+                StrategyStatus {
+                    name: &context.name,
+                    is_running: context.is_running(),
+                    positions: context
+                        .positions
+                        .lock()
+                        .iter()
+                        .map(|(market_account_id, position)| {
+                            (market_account_id.to_string(), *position)
+                        })
+                        .collect(),
+                    statistic: &context.statistics.statistic_service_state,
+                }
+            })
+            .collect_vec();
+
+        serde_json::to_string(&statuses).context("Failed to serialize statuses of strategies")
+    }
+
+    fn get(&self, name: &str) -> Result<Arc<RegisteredStrategy>> {
+        self.strategies
+            .lock()
+            .get(name)
+            .cloned()
+            .with_context(|| format!("Strategy '{name}' isn't registered"))
+    }
+}
+
+impl Service for StrategyRegistry {
+    fn name(&self) -> &str {
+        STRATEGY_REGISTRY
+    }
+
+    fn graceful_shutdown(self: Arc<Self>) -> Option<oneshot::Receiver<Result<()>>> {
+        // strategies are removed, so they don't hold engine context after shutdown
+        let strategies = self
+            .strategies
+            .lock()
+            .drain()
+            .map(|(_, x)| x)
+            .filter(|x| x.context.is_running())
+            .collect_vec();
+        if strategies.is_empty() {
+            return None;
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        spawn_future(
+            "Stop strategies on graceful shutdown",
+            SpawnFutureFlags::DENY_CANCELLATION,
+            async move {
+                join_all(strategies.iter().map(|x| async move {
+                    if let Some(run_token) = x.context.run_token.lock().take() {
+                        run_token.cancel();
+                    }
+                    stop(x).await;
+                }))
+                .await;
+
+                let _ = sender.send(Ok(()));
+                Ok(())
+            },
+        );
+
+        Some(receiver)
+    }
+}
+
+fn start(registered: Arc<RegisteredStrategy>) -> Result<()> {
+    let context = &registered.context;
+    let run_token = {
+        let mut run_token = context.run_token.lock();
+        if run_token.is_some() {
+            bail!("Strategy '{}' is already running", context.name);
+        }
+
+        let token = context
+            .engine_context
+            .lifetime_manager
+            .stop_token()
+            .create_linked_token();
+        *run_token = Some(token.clone());
+        token
+    };
+
+    let events = EventQueue::start(
+        &context.name,
+        &registered.event_queue,
+        context.engine_context.get_events_channel(),
+        context.engine_context.statistic_service.clone(),
+        run_token.clone(),
+    );
+    spawn_future(
+        &format!("Run strategy '{}'", context.name),
+        SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+        run(registered.clone(), run_token, events),
+    );
+
+    log::info!("Strategy '{}' is started", context.name);
+    Ok(())
+}
+
+async fn run(
+    registered: Arc<RegisteredStrategy>,
+    run_token: CancellationToken,
+    events: Arc<EventQueue>,
+) -> Result<()> {
+    let context = &registered.context;
+    // timer runs on clock of the engine, so strategies are driven by virtual time in backtests.
+    // The first tick is immediate
+    let clock = context.engine_context.timeout_manager.clock().clone();
+    let mut timer = clock.sleep(Duration::ZERO);
+
+    loop {
+        tokio::select! {
+            _ = run_token.when_cancelled() => return Ok(()),
+            event = events.recv() => {
+                let Some(event) = event else {
+                    return Ok(());
+                };
+
+                match context.is_own_order_event(&event) {
+                    // orders of other strategies are isolated
+                    Some(false) => continue,
+                    Some(true) => context.handle_order_event(&event),
+                    None => nothing_to_do(),
+                }
+
+                if let Err(err) = registered.strategy.on_event(context, &event).await {
+                    log::error!("Strategy '{}' failed to handle event: {err:?}", context.name);
+                }
+            }
+            _ = &mut timer => {
+                timer = clock.sleep(registered.timer_interval);
+                if let Err(err) = registered.strategy.on_timer(context).await {
+                    log::error!("Strategy '{}' failed on timer: {err:?}", context.name);
+                }
+            }
+        }
+    }
+}
+
+async fn stop(registered: &RegisteredStrategy) {
+    let context = &registered.context;
+    match registered.strategy.on_stop(context).await {
+        Ok(()) => log::info!("Strategy '{}' is stopped", context.name),
+        Err(err) => log::error!("Strategy '{}' failed to stop: {err:?}", context.name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::test_helper::get_test_exchange;
+    use crate::infrastructure::init_lifetime_manager;
+    use crate::lifecycle::test_helper::create_engine_context;
+    use crate::misc::clock::SystemClock;
+    use crate::settings::{CoreSettings, RiskSettings};
+    use mmb_domain::order::event::OrderEvent;
+    use mmb_domain::order::pool::OrdersPool;
+    use mmb_domain::order::snapshot::OrderOptions;
+    use rust_decimal_macros::dec;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const MAKER: &str = "maker";
+    const TAKER: &str = "taker";
+
+    #[derive(Default)]
+    struct RecordingStrategy {
+        order_events: Mutex<Vec<ClientOrderId>>,
+        stops_count: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Strategy for RecordingStrategy {
+        async fn on_event(&self, _ctx: &StrategyContext, event: &ExchangeEvent) -> Result<()> {
+            if let ExchangeEvent::OrderEvent(order_event) = event {
+                self.order_events
+                    .lock()
+                    .push(order_event.order.client_order_id());
+            }
+            Ok(())
+        }
+
+        async fn on_stop(&self, _ctx: &StrategyContext) -> Result<()> {
+            self.stops_count.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn engine_context(core_settings: CoreSettings) -> (Arc<EngineContext>, MarketAccountId) {
+        let _ = init_lifetime_manager();
+        let (exchange, _rx) = get_test_exchange(false);
+        let market_account_id = MarketAccountId::new(
+            exchange.exchange_account_id,
+            CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+        );
+        let engine_context = create_engine_context(&exchange, core_settings, SystemClock::shared());
+        (engine_context, market_account_id)
+    }
+
+    fn order(strategy_name: &str, market_account_id: MarketAccountId, side: OrderSide) -> OrderRef {
+        let snapshot = OrderSnapshot::with_params(
+            ClientOrderId::unique_id(),
+            OrderOptions::liquidation(dec!(0.2)),
+            None,
+            market_account_id.exchange_account_id,
+            market_account_id.currency_pair,
+            dec!(1),
+            side,
+            None,
+            strategy_name,
+        );
+        OrdersPool::new().add_snapshot_initial(&snapshot)
+    }
+
+    fn filled_event(order: &OrderRef, filled_amount: Amount, is_completed: bool) -> ExchangeEvent {
+        let mut snapshot = order.deep_clone();
+        snapshot.fills.filled_amount = filled_amount;
+        let cloned_order = Arc::new(snapshot);
+        let event_type = match is_completed {
+            true => OrderEventType::OrderCompleted { cloned_order },
+            false => OrderEventType::OrderFilled { cloned_order },
+        };
+        ExchangeEvent::OrderEvent(OrderEvent::new(order.clone(), event_type))
+    }
+
+    fn canceled_event(order: &OrderRef) -> ExchangeEvent {
+        ExchangeEvent::OrderEvent(OrderEvent::new(
+            order.clone(),
+            OrderEventType::CancelOrderSucceeded,
+        ))
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("condition isn't satisfied in time");
+    }
+
+    #[tokio::test]
+    async fn position_is_accumulated_by_fills() {
+        let (engine_context, market_account_id) = engine_context(Default::default());
+        let context = StrategyContext::new(MAKER.to_owned(), engine_context, None);
+
+        let buy_order = order(MAKER, market_account_id, OrderSide::Buy);
+        context.handle_order_event(&filled_event(&buy_order, dec!(0.3), false));
+        assert_eq!(context.position(market_account_id), dec!(0.3));
+
+        // repeated event with the same filled amount doesn't change position
+        context.handle_order_event(&filled_event(&buy_order, dec!(0.3), false));
+        assert_eq!(context.position(market_account_id), dec!(0.3));
+
+        context.handle_order_event(&filled_event(&buy_order, dec!(0.5), false));
+        assert_eq!(context.position(market_account_id), dec!(0.5));
+
+        context.handle_order_event(&filled_event(&buy_order, dec!(1), true));
+        assert_eq!(context.position(market_account_id), dec!(1));
+        assert!(!context
+            .filled_amounts
+            .lock()
+            .contains_key(&buy_order.client_order_id()));
+
+        let sell_order = order(MAKER, market_account_id, OrderSide::Sell);
+        context.handle_order_event(&filled_event(&sell_order, dec!(0.4), false));
+        assert_eq!(context.position(market_account_id), dec!(0.6));
+
+        // filled part of canceled order stays in position
+        context.handle_order_event(&canceled_event(&sell_order));
+        assert_eq!(context.position(market_account_id), dec!(0.6));
+        assert!(context.filled_amounts.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn order_exceeding_risk_budget_is_rejected() {
+        let core_settings = CoreSettings {
+            strategies: vec![StrategyInstanceSettings {
+                name: MAKER.to_owned(),
+                timer_interval_millis: 1000,
+                risk_budget: Some(RiskSettings {
+                    max_position_amount: Some(dec!(1)),
+                    ..Default::default()
+                }),
+                event_queue: None,
+            }],
+            ..Default::default()
+        };
+        let (engine_context, market_account_id) = engine_context(core_settings.clone());
+        let context = StrategyContext::new(
+            MAKER.to_owned(),
+            engine_context,
+            core_settings.strategies.first(),
+        );
+        let exchange = context
+            .exchange(market_account_id.exchange_account_id)
+            .expect("in test");
+        let header = |side, amount| {
+            OrderHeader::with_user_order(
+                ClientOrderId::unique_id(),
+                market_account_id.exchange_account_id,
+                market_account_id.currency_pair,
+                side,
+                amount,
+                UserOrder::limit(dec!(0.2)),
+                None,
+                None,
+                MAKER.to_owned(),
+            )
+        };
+
+        let buy_order = order(MAKER, market_account_id, OrderSide::Buy);
+        context.handle_order_event(&filled_event(&buy_order, dec!(0.8), false));
+
+        assert!(context
+            .check_risk_budget(&exchange, &header(OrderSide::Buy, dec!(0.1)))
+            .is_ok());
+        // order reducing position fits into budget
+        assert!(context
+            .check_risk_budget(&exchange, &header(OrderSide::Sell, dec!(1.5)))
+            .is_ok());
+        let error = context
+            .check_risk_budget(&exchange, &header(OrderSide::Buy, dec!(0.5)))
+            .expect_err("position limit should be exceeded");
+        assert!(error
+            .to_string()
+            .contains("exceeds risk budget of strategy 'maker'"));
+
+        let create_order = |amount| {
+            context.create_order(
+                market_account_id.exchange_account_id,
+                market_account_id.currency_pair,
+                OrderSide::Buy,
+                amount,
+                UserOrder::limit(dec!(0.2)),
+            )
+        };
+        let error = create_order(dec!(0.5))
+            .await
+            .expect_err("strategy isn't running");
+        assert!(error.to_string().contains("isn't running"));
+
+        *context.run_token.lock() = Some(CancellationToken::new());
+        let error = create_order(dec!(0.5))
+            .await
+            .expect_err("order shouldn't be sent to exchange");
+        assert!(error.to_string().contains("exceeds risk budget"));
+    }
+
+    #[tokio::test]
+    async fn strategies_are_isolated() {
+        let (engine_context, market_account_id) = engine_context(Default::default());
+        let registry = &engine_context.strategy_registry;
+        let maker = Arc::new(RecordingStrategy::default());
+        let taker = Arc::new(RecordingStrategy::default());
+        registry
+            .register(engine_context.clone(), MAKER, maker.clone())
+            .expect("in test");
+        registry
+            .register(engine_context.clone(), TAKER, taker.clone())
+            .expect("in test");
+
+        let maker_order = order(MAKER, market_account_id, OrderSide::Buy);
+        let taker_order = order(TAKER, market_account_id, OrderSide::Sell);
+        engine_context.send_event(filled_event(&maker_order, dec!(0.3), false));
+        engine_context.send_event(filled_event(&taker_order, dec!(0.1), false));
+
+        wait_until(|| maker.order_events.lock().len() == 1 && taker.order_events.lock().len() == 1)
+            .await;
+        assert_eq!(
+            *maker.order_events.lock(),
+            vec![maker_order.client_order_id()]
+        );
+        assert_eq!(
+            *taker.order_events.lock(),
+            vec![taker_order.client_order_id()]
+        );
+
+        let maker_context = registry.get(MAKER).expect("in test").context.clone();
+        let taker_context = registry.get(TAKER).expect("in test").context.clone();
+        assert_eq!(maker_context.position(market_account_id), dec!(0.3));
+        assert_eq!(taker_context.position(market_account_id), dec!(-0.1));
+
+        registry.pause(MAKER).expect("in test");
+        assert!(!maker_context.is_running());
+        assert!(taker_context.is_running());
+    }
+
+    #[tokio::test]
+    async fn strategy_is_started_and_stopped() {
+        let (engine_context, _) = engine_context(Default::default());
+        let registry = &engine_context.strategy_registry;
+        let strategy = Arc::new(RecordingStrategy::default());
+
+        registry
+            .register(engine_context.clone(), MAKER, strategy.clone())
+            .expect("in test");
+        let context = registry.get(MAKER).expect("in test").context.clone();
+        assert!(context.is_running());
+        assert_eq!(registry.names(), vec![MAKER.to_owned()]);
+        assert!(registry
+            .register(engine_context.clone(), MAKER, strategy.clone())
+            .is_err());
+        assert!(registry.start(MAKER).is_err());
+
+        registry.stop(MAKER).expect("in test");
+        assert!(!context.is_running());
+        assert!(context.cancellation_token().is_none());
+        wait_until(|| strategy.stops_count.load(Ordering::SeqCst) == 1).await;
+        assert!(registry.stop(MAKER).is_err());
+        assert!(registry.pause(MAKER).is_err());
+
+        registry.start(MAKER).expect("in test");
+        assert!(context.is_running());
+
+        // paused strategy isn't stopped
+        registry.pause(MAKER).expect("in test");
+        assert!(!context.is_running());
+        assert_eq!(strategy.stops_count.load(Ordering::SeqCst), 1);
+
+        assert!(registry.start(TAKER).is_err());
+        assert!(registry.stop(TAKER).is_err());
+    }
+}
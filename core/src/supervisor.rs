@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use futures::Future;
+use mmb_utils::infrastructure::{CompletionReason, FutureOutcome, SpawnFutureFlags};
+
+use crate::alerting::{alert, AlertKind, AlertSeverity};
+use crate::infrastructure::{
+    get_futures_cancellation_token, spawn_future, spawn_graceful_shutdown,
+};
+use crate::settings::SupervisorSettings;
+use crate::statistic_service::StatisticService;
+
+/// When task run by `Supervisor` is started again after it stops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Task isn't restarted and its panic starts graceful shutdown as for `spawn_future`
+    Never,
+    /// Task is restarted immediately after panic, but not after error
+    OnPanic,
+    /// Task is restarted after panic or error with delay doubled after every crash up to
+    /// `max_delay`. Delay is reset after task has worked longer than `max_delay`
+    WithBackoff {
+        initial_delay: Duration,
+        max_delay: Duration,
+    },
+}
+
+/// Runs named tasks and restarts them by their restart policies. Crashes are counted in
+/// statistics and critical alert is sent if critical task keeps crashing
+pub struct Supervisor {
+    settings: SupervisorSettings,
+    statistics: Arc<StatisticService>,
+}
+
+impl Supervisor {
+    pub fn new(settings: SupervisorSettings, statistics: Arc<StatisticService>) -> Arc<Self> {
+        Arc::new(Supervisor {
+            settings,
+            statistics,
+        })
+    }
+
+    /// Spawns task created by `create_task` and creates it again after every restart.
+    /// Supervision stops when task completes successfully or is canceled
+    pub fn spawn<F, Fut>(
+        self: &Arc<Self>,
+        name: &str,
+        policy: RestartPolicy,
+        is_critical: bool,
+        flags: SpawnFutureFlags,
+        create_task: F,
+    ) -> tokio::task::JoinHandle<FutureOutcome>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let task = SupervisedTask {
+            name: name.to_owned(),
+            policy,
+            is_critical,
+            flags,
+        };
+        spawn_future(
+            &format!("Supervise {name}"),
+            SpawnFutureFlags::STOP_BY_TOKEN,
+            self.clone().supervise(task, create_task),
+        )
+    }
+
+    async fn supervise<F, Fut>(self: Arc<Self>, task: SupervisedTask, create_task: F) -> Result<()>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let name = task.name.as_str();
+        let mut crash_times = VecDeque::new();
+        let mut consecutive_crashes = 0;
+
+        loop {
+            let started = Instant::now();
+            let is_panic = match task.run(create_task()).await {
+                CompletionReason::Panicked => true,
+                CompletionReason::Error => false,
+                _ => return Ok(()),
+            };
+
+            self.statistics.register_task_crash(name, is_panic);
+            if task.is_critical {
+                self.escalate_crash(name, &mut crash_times);
+            }
+
+            let restart_delay = match task.policy {
+                RestartPolicy::Never => return Ok(()),
+                RestartPolicy::OnPanic if is_panic => Duration::ZERO,
+                RestartPolicy::OnPanic => return Ok(()),
+                RestartPolicy::WithBackoff {
+                    initial_delay,
+                    max_delay,
+                } => {
+                    if started.elapsed() > max_delay {
+                        consecutive_crashes = 0;
+                    }
+                    consecutive_crashes += 1;
+                    backoff_delay(initial_delay, max_delay, consecutive_crashes)
+                }
+            };
+
+            log::warn!("Task '{name}' crashed and will be restarted in {restart_delay:?}");
+            tokio::time::sleep(restart_delay).await;
+            self.statistics.register_task_restart(name);
+        }
+    }
+
+    /// Sends critical alert if task has crashed too many times within the window
+    fn escalate_crash(&self, name: &str, crash_times: &mut VecDeque<Instant>) {
+        let now = Instant::now();
+        let window = Duration::from_secs(self.settings.crash_alert_window_secs);
+        crash_times.push_back(now);
+        while crash_times
+            .front()
+            .is_some_and(|&x| now.duration_since(x) > window)
+        {
+            let _ = crash_times.pop_front();
+        }
+
+        if crash_times.len() >= self.settings.crash_alert_threshold {
+            alert(
+                AlertSeverity::Critical,
+                AlertKind::TaskCrashLoop,
+                name,
+                format!(
+                    "Critical task '{name}' crashed {} times within {} seconds",
+                    crash_times.len(),
+                    window.as_secs()
+                ),
+            );
+            crash_times.clear();
+        }
+    }
+}
+
+struct SupervisedTask {
+    name: String,
+    policy: RestartPolicy,
+    is_critical: bool,
+    flags: SpawnFutureFlags,
+}
+
+impl SupervisedTask {
+    async fn run(
+        &self,
+        task: impl Future<Output = Result<()>> + Send + 'static,
+    ) -> CompletionReason {
+        // panic of restarted task is handled by supervisor instead of graceful shutdown
+        let on_panic: fn(String, &str) = match self.policy {
+            RestartPolicy::Never => spawn_graceful_shutdown,
+            _ => |log_template, _| log::warn!("{log_template} is handled by supervisor"),
+        };
+
+        let outcome = mmb_utils::infrastructure::spawn_future(
+            &self.name,
+            self.flags,
+            task,
+            on_panic,
+            get_futures_cancellation_token(),
+        )
+        .await;
+
+        match outcome {
+            Ok(outcome) => outcome.completion_reason(),
+            Err(err) => {
+                log::error!("Unable to get outcome of task '{}': {err:?}", self.name);
+                CompletionReason::Canceled
+            }
+        }
+    }
+}
+
+fn backoff_delay(
+    initial_delay: Duration,
+    max_delay: Duration,
+    consecutive_crashes: u32,
+) -> Duration {
+    let multiplier = 2u32.saturating_pow(consecutive_crashes.saturating_sub(1));
+    initial_delay
+        .checked_mul(multiplier)
+        .unwrap_or(max_delay)
+        .min(max_delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::keep_lifetime_manager;
+    use crate::lifecycle::app_lifetime_manager::AppLifetimeManager;
+    use crate::misc::clock::SystemClock;
+    use crate::misc::trading_calendar::TradingCalendar;
+    use mmb_utils::cancellation_token::CancellationToken;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn backoff_delay_is_doubled_up_to_max() {
+        let initial = Duration::from_millis(100);
+        let max = Duration::from_secs(1);
+
+        let delays = (1..=6)
+            .map(|x| backoff_delay(initial, max, x).as_millis())
+            .collect::<Vec<_>>();
+
+        assert_eq!(delays, vec![100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(backoff_delay(initial, max, 100), max);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn task_is_restarted_after_panics_until_completion() {
+        keep_lifetime_manager(AppLifetimeManager::new(CancellationToken::new()));
+        let statistics = StatisticService::new(TradingCalendar::new(None), SystemClock::shared());
+        let supervisor = Supervisor::new(SupervisorSettings::default(), statistics.clone());
+
+        let attempts = Arc::new(AtomicU32::new(0));
+        let outcome = supervisor
+            .spawn(
+                "flaky task",
+                RestartPolicy::OnPanic,
+                true,
+                SpawnFutureFlags::empty(),
+                {
+                    let attempts = attempts.clone();
+                    move || {
+                        let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                        async move {
+                            if attempt < 2 {
+                                panic!("attempt {attempt} failed");
+                            }
+                            Ok(())
+                        }
+                    }
+                },
+            )
+            .await
+            .expect("supervisor shouldn't panic");
+
+        assert_eq!(
+            outcome.completion_reason(),
+            CompletionReason::CompletedSuccessfully
+        );
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        let stats = statistics.task_crash_stats()["flaky task"].clone();
+        assert_eq!(stats.panics_count, 2);
+        assert_eq!(stats.errors_count, 0);
+        assert_eq!(stats.restarts_count, 2);
+    }
+}
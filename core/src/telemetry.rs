@@ -0,0 +1,288 @@
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use mmb_domain::market::ExchangeAccountId;
+use mmb_utils::tracing_bridge::{set_extra_layer, LogSubscriber};
+use opentelemetry::metrics::{Histogram, Meter, Unit};
+use opentelemetry::{global, Array, KeyValue, Value};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::trace::{self, Sampler};
+use opentelemetry_sdk::{runtime, Resource};
+use parking_lot::RwLock;
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::settings::TelemetrySettings;
+use crate::statistic_service::{MarketAccountIdStatistic, StatisticService};
+
+/// Only spans of engine crates are exported, so exporter doesn't trace its own gRPC requests
+const EXPORTED_TARGET_PREFIX: &str = "mmb";
+
+static REST_REQUEST_DURATION: RwLock<Option<Histogram<f64>>> = parking_lot::const_rwlock(None);
+
+/// Exports spans of order lifecycle, REST request durations and statistic counters to
+/// OpenTelemetry collector by OTLP
+pub struct Telemetry {
+    meter_provider: SdkMeterProvider,
+}
+
+impl Telemetry {
+    pub fn start(
+        settings: &TelemetrySettings,
+        exchange_account_ids: &[ExchangeAccountId],
+        statistics: &Arc<StatisticService>,
+    ) -> Result<Self> {
+        let resource = resource(settings, exchange_account_ids);
+
+        let sampling_ratio = settings
+            .sampling_ratio
+            .to_f64()
+            .with_context(|| format!("Invalid sampling ratio {}", settings.sampling_ratio))?;
+        let tracer = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(&settings.endpoint),
+            )
+            .with_trace_config(
+                trace::config()
+                    .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                        sampling_ratio,
+                    ))))
+                    .with_resource(resource.clone()),
+            )
+            .install_batch(runtime::Tokio)
+            .context("Unable to create OTLP span exporter")?;
+        set_extra_layer(
+            Box::new(tracing_opentelemetry::layer::<LogSubscriber>().with_tracer(tracer)),
+            vec![EXPORTED_TARGET_PREFIX.to_owned()],
+        )?;
+
+        // provider is set as global one, so instruments are created by `global::meter`
+        let meter_provider = opentelemetry_otlp::new_pipeline()
+            .metrics(runtime::Tokio)
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(&settings.endpoint),
+            )
+            .with_resource(resource)
+            .with_period(Duration::from_secs(settings.metrics_interval_secs))
+            .build()
+            .context("Unable to create OTLP metrics exporter")?;
+
+        let meter = global::meter("mmb");
+        *REST_REQUEST_DURATION.write() = Some(
+            meter
+                .f64_histogram("mmb.rest.request.duration")
+                .with_description("Duration of REST requests to exchanges")
+                .with_unit(Unit::new("s"))
+                .init(),
+        );
+        observe_statistics(&meter, statistics);
+
+        log::info!("Telemetry is exported to {}", settings.endpoint);
+
+        Ok(Telemetry { meter_provider })
+    }
+
+    /// Exports spans and metrics which aren't exported yet
+    pub async fn shutdown(&self) {
+        let meter_provider = self.meter_provider.clone();
+        // providers are flushed by blocking calls
+        let result = tokio::task::spawn_blocking(move || {
+            global::shutdown_tracer_provider();
+            meter_provider.shutdown()
+        })
+        .await;
+
+        match result {
+            Ok(Ok(())) => log::info!("Telemetry is stopped"),
+            Ok(Err(err)) => log::error!("Failed to stop metrics export: {err:?}"),
+            Err(err) => log::error!("Failed to stop telemetry: {err:?}"),
+        }
+    }
+}
+
+/// Records duration of REST request to exchange if telemetry is started
+pub fn record_rest_request(
+    exchange_account_id: ExchangeAccountId,
+    action_name: &'static str,
+    method: &'static str,
+    status: u16,
+    duration: Duration,
+) {
+    if let Some(histogram) = REST_REQUEST_DURATION.read().as_ref() {
+        histogram.record(
+            duration.as_secs_f64(),
+            &[
+                KeyValue::new("exchange_account_id", exchange_account_id.to_string()),
+                KeyValue::new("action", action_name),
+                KeyValue::new("method", method),
+                KeyValue::new("status", i64::from(status)),
+            ],
+        );
+    }
+}
+
+fn resource(settings: &TelemetrySettings, exchange_account_ids: &[ExchangeAccountId]) -> Resource {
+    let exchange_account_ids = exchange_account_ids
+        .iter()
+        .map(|x| x.to_string().into())
+        .collect_vec();
+
+    let mut attributes = vec![
+        KeyValue::new("service.name", settings.service_name.clone()),
+        KeyValue::new(
+            "mmb.exchange_account_ids",
+            Value::Array(Array::String(exchange_account_ids)),
+        ),
+    ];
+    attributes.extend(
+        settings
+            .resource_attributes
+            .iter()
+            .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+    );
+
+    Resource::new(attributes)
+}
+
+/// Statistic counters are observed by market when metrics are exported
+fn observe_statistics(meter: &Meter, statistics: &Arc<StatisticService>) {
+    type Counter<T> = (
+        &'static str,
+        &'static str,
+        fn(&MarketAccountIdStatistic) -> T,
+    );
+
+    let order_counters: [Counter<u64>; 3] = [
+        ("mmb.orders.opened", "Count of created orders", |x| {
+            x.opened_orders_count
+        }),
+        ("mmb.orders.canceled", "Count of canceled orders", |x| {
+            x.canceled_orders_count
+        }),
+        (
+            "mmb.orders.filled",
+            "Count of completely filled orders",
+            |x| x.fully_filled_orders_count,
+        ),
+    ];
+    for (name, description, value) in order_counters {
+        let statistics = Arc::downgrade(statistics);
+        let _ = meter
+            .u64_observable_counter(name)
+            .with_description(description)
+            .with_callback(move |observer| {
+                for_each_market(&statistics, |stats, attributes| {
+                    observer.observe(value(stats), attributes)
+                })
+            })
+            .init();
+    }
+
+    let amount_counters: [Counter<f64>; 2] = [
+        (
+            "mmb.filled_amount",
+            "Filled amount of completely filled orders",
+            |x| x.summary_filled_amount.to_f64().unwrap_or_default(),
+        ),
+        (
+            "mmb.commission",
+            "Commission of completely filled orders",
+            |x| x.summary_commission.to_f64().unwrap_or_default(),
+        ),
+    ];
+    for (name, description, value) in amount_counters {
+        let statistics = Arc::downgrade(statistics);
+        let _ = meter
+            .f64_observable_counter(name)
+            .with_description(description)
+            .with_callback(move |observer| {
+                for_each_market(&statistics, |stats, attributes| {
+                    observer.observe(value(stats), attributes)
+                })
+            })
+            .init();
+    }
+
+    let statistics = Arc::downgrade(statistics);
+    let _ = meter
+        .u64_observable_gauge("mmb.orders.partially_filled")
+        .with_description("Count of partially filled orders")
+        .with_callback(move |observer| {
+            for_each_market(&statistics, |stats, attributes| {
+                observer.observe(stats.partially_filled_orders_count, attributes)
+            })
+        })
+        .init();
+}
+
+fn for_each_market(
+    statistics: &Weak<StatisticService>,
+    mut action: impl FnMut(&MarketAccountIdStatistic, &[KeyValue]),
+) {
+    let statistics = match statistics.upgrade() {
+        Some(statistics) => statistics,
+        None => return,
+    };
+
+    for (market_account_id, stats) in statistics.market_account_id_stats() {
+        let attributes = [
+            KeyValue::new(
+                "exchange_account_id",
+                market_account_id.exchange_account_id.to_string(),
+            ),
+            KeyValue::new("currency_pair", market_account_id.currency_pair.to_string()),
+        ];
+        action(&stats, &attributes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::Key;
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+
+    #[test]
+    fn resource_contains_exchange_accounts_and_custom_attributes() {
+        let settings = TelemetrySettings {
+            endpoint: "http://localhost:4317".to_owned(),
+            service_name: "mmb-binance".to_owned(),
+            sampling_ratio: Decimal::ONE,
+            metrics_interval_secs: 60,
+            resource_attributes: HashMap::from([(
+                "deployment.environment".to_owned(),
+                "prod".to_owned(),
+            )]),
+        };
+        let exchange_account_ids = [
+            "Binance_0".parse().expect("valid id"),
+            "Bitmex_1".parse().expect("valid id"),
+        ];
+
+        let resource = resource(&settings, &exchange_account_ids);
+
+        assert_eq!(
+            resource.get(Key::new("service.name")),
+            Some("mmb-binance".into())
+        );
+        assert_eq!(
+            resource.get(Key::new("deployment.environment")),
+            Some("prod".into())
+        );
+        assert_eq!(
+            resource.get(Key::new("mmb.exchange_account_ids")),
+            Some(Value::Array(Array::String(vec![
+                "Binance_0".into(),
+                "Bitmex_1".into()
+            ])))
+        );
+    }
+}
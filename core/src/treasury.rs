@@ -0,0 +1,274 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_domain::market::ExchangeAccountId;
+use mmb_domain::order::snapshot::Amount;
+use mmb_domain::transfer::{
+    ExchangeFundsMovementId, FundsMovement, FundsMovementType, TransferStatus, Withdrawal,
+};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_by_timer;
+use crate::misc::clock::Clock;
+use crate::settings::TreasurySettings;
+use crate::statistic_service::StatisticService;
+
+type MovementsByAccount =
+    HashMap<ExchangeAccountId, HashMap<ExchangeFundsMovementId, FundsMovement>>;
+
+/// Initiates withdrawals and tracks deposits and withdrawals of exchange accounts by polling
+/// their history. When exchange confirms a movement, balances of the account are requested
+/// from exchange and the movement is registered in statistics
+pub struct Treasury {
+    settings: TreasurySettings,
+    exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+    statistic_service: Arc<StatisticService>,
+    clock: Arc<dyn Clock>,
+    started_at: DateTime,
+    /// Last known state of deposits and withdrawals
+    movements: Mutex<MovementsByAccount>,
+}
+
+impl Treasury {
+    pub fn new(
+        settings: TreasurySettings,
+        exchanges: DashMap<ExchangeAccountId, Arc<Exchange>>,
+        statistic_service: Arc<StatisticService>,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            settings,
+            exchanges,
+            statistic_service,
+            started_at: clock.now(),
+            clock,
+            movements: Default::default(),
+        })
+    }
+
+    pub fn start_tracking(self: &Arc<Self>, cancellation_token: CancellationToken) {
+        let interval = Duration::from_secs(self.settings.interval_secs.max(1));
+        let treasury = self.clone();
+        spawn_by_timer(
+            "Track deposits and withdrawals",
+            Duration::ZERO,
+            interval,
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            move || treasury.clone().poll(cancellation_token.clone()),
+        );
+    }
+
+    /// Initiates withdrawal and tracks it until exchange confirms it
+    pub async fn withdraw(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        withdrawal: Withdrawal,
+        cancellation_token: CancellationToken,
+    ) -> Result<ExchangeFundsMovementId> {
+        let exchange = self.exchange(exchange_account_id)?;
+        let withdrawal_id = exchange.withdraw(&withdrawal, cancellation_token).await?;
+
+        let movement = FundsMovement {
+            id: withdrawal_id.clone(),
+            movement_type: FundsMovementType::Withdrawal,
+            exchange_account_id,
+            currency_code: withdrawal.currency_code,
+            amount: withdrawal.amount,
+            fee: Amount::ZERO,
+            status: TransferStatus::Pending,
+            transaction_id: None,
+            time: self.clock.now(),
+        };
+        let _ = self
+            .movements
+            .lock()
+            .entry(exchange_account_id)
+            .or_default()
+            .insert(withdrawal_id.clone(), movement);
+
+        Ok(withdrawal_id)
+    }
+
+    /// Deposits and withdrawals of exchange account known by treasury
+    pub fn movements(&self, exchange_account_id: ExchangeAccountId) -> Vec<FundsMovement> {
+        self.movements
+            .lock()
+            .get(&exchange_account_id)
+            .map(|x| x.values().cloned().sorted_by_key(|x| x.time).collect())
+            .unwrap_or_default()
+    }
+
+    pub async fn poll(self: Arc<Self>, cancellation_token: CancellationToken) {
+        for &exchange_account_id in &self.settings.exchange_accounts {
+            if let Err(err) = self
+                .poll_exchange(exchange_account_id, cancellation_token.clone())
+                .await
+            {
+                log::warn!(
+                    "Failed to track deposits and withdrawals of {exchange_account_id}: {err:?}"
+                );
+            }
+        }
+    }
+
+    async fn poll_exchange(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let exchange = self.exchange(exchange_account_id)?;
+        let from_datetime =
+            self.started_at - chrono::Duration::seconds(self.settings.lookback_secs as i64);
+        let movements = exchange
+            .get_funds_movements(from_datetime, cancellation_token.clone())
+            .await?;
+
+        let confirmed = self.apply_movements(exchange_account_id, movements);
+        if confirmed.is_empty() {
+            return Ok(());
+        }
+
+        for movement in &confirmed {
+            log::info!("Funds movement is confirmed by exchange: {movement:?}");
+            self.statistic_service.register_funds_movement(movement);
+        }
+
+        exchange
+            .update_balance_from_exchange(cancellation_token)
+            .await
+            .with_context(|| {
+                format!("Failed to update balance of {exchange_account_id} after funds movements")
+            })
+    }
+
+    /// Updates known movements and returns ones which became completed. Movements which were
+    /// already completed before engine start are included into balances, so they are skipped
+    fn apply_movements(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        movements: Vec<FundsMovement>,
+    ) -> Vec<FundsMovement> {
+        let mut known_movements = self.movements.lock();
+        let known_movements = known_movements.entry(exchange_account_id).or_default();
+
+        let mut confirmed = vec![];
+        for movement in movements {
+            let is_completed = movement.status == TransferStatus::Completed;
+            match known_movements.entry(movement.id.clone()) {
+                Entry::Occupied(mut entry) => {
+                    let was_completed = entry.get().status == TransferStatus::Completed;
+                    if is_completed && !was_completed {
+                        confirmed.push(movement.clone());
+                    }
+                    let _ = entry.insert(movement);
+                }
+                Entry::Vacant(entry) => {
+                    if is_completed && movement.time >= self.started_at {
+                        confirmed.push(movement.clone());
+                    }
+                    let _ = entry.insert(movement);
+                }
+            }
+        }
+
+        confirmed
+    }
+
+    fn exchange(&self, exchange_account_id: ExchangeAccountId) -> Result<Arc<Exchange>> {
+        self.exchanges
+            .get(&exchange_account_id)
+            .map(|x| x.value().clone())
+            .with_context(|| format!("Exchange {exchange_account_id} isn't found"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::misc::clock::SystemClock;
+    use crate::misc::trading_calendar::TradingCalendar;
+    use rust_decimal_macros::dec;
+
+    fn account() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance", 0)
+    }
+
+    fn treasury() -> Arc<Treasury> {
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        Treasury::new(
+            TreasurySettings {
+                exchange_accounts: vec![account()],
+                interval_secs: 60,
+                lookback_secs: 3600,
+            },
+            DashMap::new(),
+            StatisticService::new(TradingCalendar::new(None), clock.clone()),
+            clock,
+        )
+    }
+
+    fn deposit(id: &str, status: TransferStatus, time: DateTime) -> FundsMovement {
+        FundsMovement {
+            id: id.into(),
+            movement_type: FundsMovementType::Deposit,
+            exchange_account_id: account(),
+            currency_code: "BTC".into(),
+            amount: dec!(1),
+            fee: dec!(0),
+            status,
+            transaction_id: None,
+            time,
+        }
+    }
+
+    #[test]
+    fn movement_is_confirmed_once_when_completed() {
+        let treasury = treasury();
+        let time = treasury.started_at;
+
+        let pending = deposit("1", TransferStatus::Pending, time);
+        assert!(treasury
+            .apply_movements(account(), vec![pending])
+            .is_empty());
+
+        let completed = deposit("1", TransferStatus::Completed, time);
+        let confirmed = treasury.apply_movements(account(), vec![completed.clone()]);
+        assert_eq!(confirmed, vec![completed.clone()]);
+
+        assert!(treasury
+            .apply_movements(account(), vec![completed])
+            .is_empty());
+        assert_eq!(treasury.movements(account()).len(), 1);
+    }
+
+    #[test]
+    fn movement_completed_before_start_is_skipped() {
+        let treasury = treasury();
+        let before_start = treasury.started_at - chrono::Duration::minutes(5);
+
+        let completed = deposit("1", TransferStatus::Completed, before_start);
+        assert!(treasury
+            .apply_movements(account(), vec![completed])
+            .is_empty());
+
+        // created before start, but completed after it
+        let pending = deposit("2", TransferStatus::Pending, before_start);
+        assert!(treasury
+            .apply_movements(account(), vec![pending])
+            .is_empty());
+        let completed = deposit("2", TransferStatus::Completed, before_start);
+        assert_eq!(
+            treasury.apply_movements(account(), vec![completed]).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,427 @@
+use anyhow::{bail, ensure, Context, Result};
+use async_trait::async_trait;
+use futures::Future;
+use mmb_core::balance::manager::balance_manager::BalanceManager;
+use mmb_core::connectivity::WebSocketRole;
+use mmb_core::database::events::recorder::EventRecorder;
+use mmb_core::exchanges::exchange_blocker::ExchangeBlocker;
+use mmb_core::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::exchanges::general::exchange_creation::create_exchange;
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::RequestsTimeoutManagerFactory;
+use mmb_core::exchanges::timeouts::timeout_manager::TimeoutManager;
+use mmb_core::exchanges::traits::ExchangeClientBuilder;
+use mmb_core::infrastructure::init_lifetime_manager;
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::lifecycle::launcher::EngineBuildConfig;
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::ExchangeEvent;
+use mmb_domain::market::CurrencyPair;
+use mmb_domain::order::event::OrderEventType;
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, ExchangeOrderId, OrderHeader, OrderSide, OrderStatus, Price, UserOrder,
+};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::hashmap;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::{Duration, Instant};
+
+const EVENTS_CHANNEL_CAPACITY: usize = 1000;
+
+/// Mock of exchange server which connector under test is connected to.
+/// It's implemented for every connector because protocols of exchanges differ.
+#[async_trait]
+pub trait MockExchange: Send + Sync {
+    /// Waits until connector is connected and ready to send requests
+    async fn wait_connected(&self) -> Result<()>;
+
+    /// Breaks connections of connector, connector should restore them by itself
+    async fn disconnect(&self) -> Result<()>;
+
+    /// Fills order on exchange. Fill is pushed to connector only if `notify` is set,
+    /// otherwise it can be found by requests of order info or open orders only
+    async fn fill(
+        &self,
+        exchange_order_id: &ExchangeOrderId,
+        amount: Amount,
+        price: Price,
+        notify: bool,
+    ) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScenarioStep {
+    /// Maker only order is created and reported as created
+    Create,
+    /// Fill of a quarter of order amount is pushed by exchange
+    PartialFill,
+    /// Fill of a quarter of order amount isn't pushed by exchange, so it should be
+    /// found by reconciliation of open orders
+    MissedFill,
+    /// Connections are broken, connector should restore them and order should stay open
+    Reconnect,
+    /// Order is cancelled and reported as cancelled with all its fills
+    Cancel,
+}
+
+/// Scripted scenario which checks that connector reports order lifecycle to the engine correctly.
+/// Exchange is created from the builder the same way as by the engine, connector should be
+/// configured to use [`MockExchange`] by exchange settings or the builder.
+///
+/// ```no_run
+/// use core_tests::conformance::{ConformanceScenario, MockExchange};
+/// use mmb_core::exchanges::traits::ExchangeClientBuilder;
+/// use mmb_core::settings::ExchangeSettings;
+/// use mmb_domain::market::CurrencyPair;
+/// use rust_decimal_macros::dec;
+///
+/// async fn example(builder: Box<dyn ExchangeClientBuilder>, settings: ExchangeSettings, mock: &dyn MockExchange) {
+///     let currency_pair = CurrencyPair::from_codes("btc".into(), "usd".into());
+///     ConformanceScenario::new(currency_pair, dec!(30000), dec!(0.01))
+///         .run(builder, settings, mock)
+///         .await
+///         .expect("in test");
+/// }
+/// ```
+pub struct ConformanceScenario {
+    pub currency_pair: CurrencyPair,
+    pub price: Price,
+    pub amount: Amount,
+    pub steps: Vec<ScenarioStep>,
+    /// Max duration of every step
+    pub timeout: Duration,
+}
+
+impl ConformanceScenario {
+    /// Scenario with all steps. Connectors which can't receive fills from exchange
+    /// by themselves should skip `ScenarioStep::PartialFill`
+    pub fn new(currency_pair: CurrencyPair, price: Price, amount: Amount) -> Self {
+        Self {
+            currency_pair,
+            price,
+            amount,
+            steps: vec![
+                ScenarioStep::Create,
+                ScenarioStep::PartialFill,
+                ScenarioStep::MissedFill,
+                ScenarioStep::Reconnect,
+                ScenarioStep::Cancel,
+            ],
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Runs steps one by one and returns the order of scenario for additional checks
+    pub async fn run(
+        &self,
+        builder: Box<dyn ExchangeClientBuilder>,
+        settings: ExchangeSettings,
+        mock: &dyn MockExchange,
+    ) -> Result<OrderRef> {
+        let (events_tx, events_rx) = broadcast::channel(EVENTS_CHANNEL_CAPACITY);
+        let lifetime_manager = init_lifetime_manager();
+        // exchange is blocked through weak reference, so blocker should live until the end
+        let (exchange, _exchange_blocker) =
+            create_exchange_with_builder(builder, &settings, events_tx, lifetime_manager.clone())
+                .await?;
+
+        let mut run = ScenarioRun {
+            scenario: self,
+            exchange,
+            mock,
+            events: events_rx,
+            cancellation_token: lifetime_manager.stop_token(),
+            order: None,
+            filled_amount: Amount::ZERO,
+            expected_statuses: Vec::new(),
+        };
+        let result = run.run().await;
+
+        // background tasks of connector shouldn't outlive the scenario
+        lifetime_manager.stop_token().cancel();
+
+        result
+    }
+}
+
+pub(crate) async fn create_exchange_with_builder(
+    builder: Box<dyn ExchangeClientBuilder>,
+    settings: &ExchangeSettings,
+    events_tx: broadcast::Sender<ExchangeEvent>,
+    lifetime_manager: Arc<AppLifetimeManager>,
+) -> Result<(Arc<Exchange>, Arc<ExchangeBlocker>)> {
+    let exchange_account_id = settings.exchange_account_id;
+    let timeout_manager = TimeoutManager::new(hashmap![
+        exchange_account_id => RequestsTimeoutManagerFactory::from_requests_per_period(
+            builder.get_timeout_arguments(),
+            exchange_account_id,
+        )
+    ]);
+    let exchange_blocker = ExchangeBlocker::new(vec![exchange_account_id]);
+    let event_recorder = EventRecorder::start(None, None, Default::default()).await?;
+
+    let exchange = create_exchange(
+        settings,
+        &EngineBuildConfig::new(vec![builder]),
+        events_tx,
+        lifetime_manager,
+        timeout_manager,
+        Arc::downgrade(&exchange_blocker),
+        event_recorder,
+    )
+    .await;
+
+    let currency_pair_to_symbol_converter =
+        CurrencyPairToSymbolConverter::new(hashmap![exchange_account_id => exchange.clone()]);
+    exchange.setup_balance_manager(BalanceManager::new(currency_pair_to_symbol_converter, None));
+
+    if exchange
+        .exchange_client
+        .is_websocket_enabled(WebSocketRole::Main)
+    {
+        exchange.connect_ws().await?;
+    }
+
+    Ok((exchange, exchange_blocker))
+}
+
+struct ScenarioRun<'a> {
+    scenario: &'a ConformanceScenario,
+    exchange: Arc<Exchange>,
+    mock: &'a dyn MockExchange,
+    events: broadcast::Receiver<ExchangeEvent>,
+    cancellation_token: CancellationToken,
+    order: Option<OrderRef>,
+    /// Sum of fills made by mock exchange
+    filled_amount: Amount,
+    expected_statuses: Vec<OrderStatus>,
+}
+
+impl ScenarioRun<'_> {
+    async fn run(&mut self) -> Result<OrderRef> {
+        self.within("connection", self.mock.wait_connected())
+            .await??;
+
+        for step in &self.scenario.steps {
+            self.run_step(*step)
+                .await
+                .with_context(|| format!("Conformance step {step:?} failed"))?;
+        }
+
+        let order = self.order()?.clone();
+        let mut statuses = order.fn_ref(|x| x.status_history.statuses().collect::<Vec<_>>());
+        statuses.dedup();
+        // status of order being created isn't always recorded
+        statuses.retain(|x| *x != OrderStatus::Creating);
+        ensure!(
+            statuses == self.expected_statuses,
+            "Unexpected status transitions {statuses:?}, expected {:?}",
+            self.expected_statuses
+        );
+
+        Ok(order)
+    }
+
+    async fn run_step(&mut self, step: ScenarioStep) -> Result<()> {
+        match step {
+            ScenarioStep::Create => self.create().await,
+            ScenarioStep::PartialFill => self.fill(true).await,
+            ScenarioStep::MissedFill => self.fill(false).await,
+            ScenarioStep::Reconnect => self.reconnect().await,
+            ScenarioStep::Cancel => self.cancel().await,
+        }
+    }
+
+    async fn create(&mut self) -> Result<()> {
+        ensure!(self.order.is_none(), "Order is already created");
+
+        let scenario = self.scenario;
+        let header = OrderHeader::with_user_order(
+            ClientOrderId::unique_id(),
+            self.exchange.exchange_account_id,
+            scenario.currency_pair,
+            OrderSide::Buy,
+            scenario.amount,
+            UserOrder::maker_only(scenario.price),
+            None,
+            None,
+            "Conformance".to_owned(),
+        );
+        let order = self
+            .within(
+                "order creation",
+                self.exchange
+                    .create_order(&header, None, self.cancellation_token.clone()),
+            )
+            .await??;
+        self.order = Some(order.clone());
+
+        self.wait_event(&order, "CreateOrderSucceeded", |x| {
+            matches!(x, OrderEventType::CreateOrderSucceeded)
+        })
+        .await?;
+        ensure_status(&order, OrderStatus::Created)?;
+        ensure!(
+            order.exchange_order_id().is_some(),
+            "Exchange order id isn't set"
+        );
+        self.expected_statuses.push(OrderStatus::Created);
+
+        Ok(())
+    }
+
+    async fn fill(&mut self, notify: bool) -> Result<()> {
+        let order = self.order()?.clone();
+        let exchange_order_id = order
+            .exchange_order_id()
+            .context("Exchange order id isn't set")?;
+
+        let amount = self.scenario.amount / Amount::from(4);
+        self.mock
+            .fill(&exchange_order_id, amount, self.scenario.price, notify)
+            .await?;
+        self.filled_amount += amount;
+
+        if !notify {
+            let reconciliation = self
+                .within(
+                    "reconciliation of open orders",
+                    self.exchange
+                        .reconcile_open_orders(self.cancellation_token.clone()),
+                )
+                .await??;
+            ensure!(
+                reconciliation
+                    .fills_checked
+                    .contains(&order.client_order_id()),
+                "Fills of order aren't checked by reconciliation: {reconciliation:?}"
+            );
+        }
+
+        self.wait_event(&order, "OrderFilled", |x| {
+            matches!(x, OrderEventType::OrderFilled { .. })
+        })
+        .await?;
+        self.ensure_filled_amount(&order)?;
+        ensure_status(&order, OrderStatus::Created)
+    }
+
+    async fn reconnect(&mut self) -> Result<()> {
+        let order = self.order()?.clone();
+
+        self.mock.disconnect().await?;
+        self.within("reconnection", self.mock.wait_connected())
+            .await??;
+
+        // the engine reconciles open orders after reconnection
+        let reconciliation = self
+            .within(
+                "reconciliation of open orders",
+                self.exchange
+                    .reconcile_open_orders(self.cancellation_token.clone()),
+            )
+            .await??;
+        ensure!(
+            !reconciliation.finished.contains(&order.client_order_id()),
+            "Open order is finished after reconnection: {reconciliation:?}"
+        );
+        ensure!(
+            reconciliation.adopted.is_empty(),
+            "Unknown orders are found after reconnection: {reconciliation:?}"
+        );
+
+        self.ensure_filled_amount(&order)?;
+        ensure_status(&order, OrderStatus::Created)
+    }
+
+    async fn cancel(&mut self) -> Result<()> {
+        let order = self.order()?.clone();
+
+        self.within(
+            "order cancellation",
+            self.exchange.wait_cancel_order(
+                order.clone(),
+                None,
+                true,
+                self.cancellation_token.clone(),
+            ),
+        )
+        .await??;
+
+        self.wait_event(&order, "CancelOrderSucceeded", |x| {
+            matches!(x, OrderEventType::CancelOrderSucceeded)
+        })
+        .await?;
+        ensure_status(&order, OrderStatus::Canceled)?;
+        self.ensure_filled_amount(&order)?;
+        self.expected_statuses
+            .extend([OrderStatus::Canceling, OrderStatus::Canceled]);
+
+        Ok(())
+    }
+
+    /// Waits for event of order, earlier events are skipped
+    async fn wait_event(
+        &mut self,
+        order: &OrderRef,
+        name: &str,
+        predicate: impl Fn(&OrderEventType) -> bool,
+    ) -> Result<()> {
+        let client_order_id = order.client_order_id();
+        let deadline = Instant::now() + self.scenario.timeout;
+
+        loop {
+            let event = match tokio::time::timeout_at(deadline, self.events.recv()).await {
+                Ok(Ok(event)) => event,
+                Ok(Err(RecvError::Lagged(count))) => bail!("{count} exchange events are missed"),
+                Ok(Err(RecvError::Closed)) => bail!("Exchange events channel is closed"),
+                Err(_) => bail!("Event {name} of order {client_order_id} isn't received"),
+            };
+
+            if let ExchangeEvent::OrderEvent(event) = event {
+                if event.order.client_order_id() == client_order_id && predicate(&event.event_type)
+                {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn ensure_filled_amount(&self, order: &OrderRef) -> Result<()> {
+        let filled_amount = order.filled_amount();
+        ensure!(
+            filled_amount == self.filled_amount,
+            "Filled amount of order is {filled_amount}, expected {}",
+            self.filled_amount
+        );
+        Ok(())
+    }
+
+    fn order(&self) -> Result<&OrderRef> {
+        self.order
+            .as_ref()
+            .context("Order isn't created, scenario should start with ScenarioStep::Create")
+    }
+
+    async fn within<T>(&self, action: &str, fut: impl Future<Output = T>) -> Result<T> {
+        tokio::time::timeout(self.scenario.timeout, fut)
+            .await
+            .with_context(|| {
+                format!(
+                    "Timeout {:?} of {action} is exceeded",
+                    self.scenario.timeout
+                )
+            })
+    }
+}
+
+fn ensure_status(order: &OrderRef, expected: OrderStatus) -> Result<()> {
+    let status = order.status();
+    ensure!(
+        status == expected,
+        "Order status is {status:?}, expected {expected:?}"
+    );
+    Ok(())
+}
@@ -16,4 +16,6 @@
     clippy::unwrap_used
 )]
 
+pub mod conformance;
+pub mod mock_exchange;
 pub mod order;
@@ -0,0 +1,560 @@
+use super::{
+    BalanceInfo, CancelledOrder, CreatedOrder, ErrorResponse, NewOrder, Notification, OrderState,
+    SequencedNotification, SymbolInfo, TradeInfo, MOCK_EXCHANGE_ID,
+};
+use crate::mock_exchange::MockExchangeServer;
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use hyper::client::HttpConnector;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Client, Method, Request};
+use mmb_core::connectivity::WebSocketRole;
+use mmb_core::exchanges::general::exchange::RequestResult;
+use mmb_core::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    RestFillsType, WebSocketOptions,
+};
+use mmb_core::exchanges::general::handlers::handle_order_filled::{FillAmount, FillEvent};
+use mmb_core::exchanges::general::order::cancel::CancelOrderResult;
+use mmb_core::exchanges::general::order::create::CreateOrderResult;
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::sequence_tracker::{SequenceCheck, SequenceTracker};
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use mmb_core::exchanges::timeouts::timeout_manager::TimeoutManager;
+use mmb_core::exchanges::traits::{
+    ExchangeClient, ExchangeClientBuilder, ExchangeClientBuilderResult, ExchangeError,
+    HandleMetricsCb, HandleOrderFilledCb, HandleSequenceGapCb, HandleTradeCb, OrderCancelledCb,
+    OrderCreatedCb, SendWebsocketMessageCb, Support,
+};
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{
+    AllowedEventSourceType, EventSourceType, ExchangeBalance, ExchangeBalancesAndPositions,
+    ExchangeEvent, TradeId,
+};
+use mmb_domain::exchanges::symbol::{Precision, Symbol};
+use mmb_domain::market::{
+    CurrencyCode, CurrencyId, CurrencyPair, ExchangeErrorType, ExchangeId, SpecificCurrencyPair,
+};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order::pool::{OrderRef, OrdersPool};
+use mmb_domain::order::snapshot::{ExchangeOrderId, OrderInfo, OrderRole, Price};
+use mmb_domain::position::{ActivePosition, ClosedPosition};
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use std::any::Any;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use url::Url;
+
+const REQUESTS_PER_MINUTE: usize = 6000;
+
+const NOTIFICATIONS_STREAM: &str = "notifications";
+
+impl From<ErrorResponse> for ExchangeError {
+    fn from(error: ErrorResponse) -> Self {
+        let (error_type, message) = match error {
+            ErrorResponse::Pending { retry_after_ms } => (
+                ExchangeErrorType::PendingError(Duration::from_millis(retry_after_ms)),
+                "Exchange is busy",
+            ),
+            ErrorResponse::OrderNotFound => (ExchangeErrorType::OrderNotFound, "Order not found"),
+            ErrorResponse::OrderCompleted => {
+                (ExchangeErrorType::OrderCompleted, "Order is completed")
+            }
+            ErrorResponse::DuplicateOrder => (
+                ExchangeErrorType::InvalidOrder,
+                "Order with the same client order id already exists",
+            ),
+            ErrorResponse::Timeout => (
+                ExchangeErrorType::ServiceUnavailable,
+                "Execution status of request is unknown",
+            ),
+        };
+
+        ExchangeError::new(error_type, message.to_owned(), None)
+    }
+}
+
+/// Connector of [`MockExchangeServer`]. Orders are reported by websocket notifications,
+/// fills missed by websocket are requested as trades
+struct MockExchangeClient {
+    settings: ExchangeSettings,
+    rest_uri: String,
+    websocket_url: Url,
+    http_client: Client<HttpConnector>,
+    supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    traded_specific_currencies: Mutex<Vec<SpecificCurrencyPair>>,
+    order_created_callback: OrderCreatedCb,
+    order_cancelled_callback: OrderCancelledCb,
+    handle_order_filled_callback: HandleOrderFilledCb,
+    handle_trade_callback: HandleTradeCb,
+    handle_metrics_callback: HandleMetricsCb,
+    websocket_message_callback: SendWebsocketMessageCb,
+    handle_sequence_gap_callback: HandleSequenceGapCb,
+    sequence_tracker: SequenceTracker,
+}
+
+impl MockExchangeClient {
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<String>,
+    ) -> Result<T, ExchangeError> {
+        let request = Request::builder()
+            .method(method)
+            .uri(format!("{}{path}", self.rest_uri))
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.map_or_else(Body::empty, Body::from))
+            .map_err(|err| ExchangeError::send(err.into()))?;
+
+        let response = self
+            .http_client
+            .request(request)
+            .await
+            .map_err(|err| ExchangeError::send(err.into()))?;
+        let status = response.status();
+        let content = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|err| ExchangeError::send(err.into()))?;
+
+        if !status.is_success() {
+            return Err(match serde_json::from_slice::<ErrorResponse>(&content) {
+                Ok(error) => error.into(),
+                Err(_) => ExchangeError::unknown(&String::from_utf8_lossy(&content)),
+            });
+        }
+
+        serde_json::from_slice(&content).map_err(|err| {
+            ExchangeError::parsing(format!(
+                "Unable to parse response {}: {err:?}",
+                String::from_utf8_lossy(&content)
+            ))
+        })
+    }
+
+    fn order_info(order: OrderState) -> OrderInfo {
+        OrderInfo::new(
+            order.currency_pair,
+            order.exchange_order_id,
+            order.client_order_id,
+            order.side,
+            order.status,
+            order.price,
+            order.amount,
+            order.average_fill_price,
+            order.filled_amount,
+            Some(order.currency_pair.to_codes().quote.as_str().to_owned()),
+            None,
+            Some(order.commission_amount),
+        )
+    }
+
+    fn order_trade(trade: TradeInfo) -> OrderTrade {
+        OrderTrade::new(
+            trade.exchange_order_id,
+            TradeId::Number(trade.trade_id),
+            trade.datetime,
+            trade.price,
+            trade.amount,
+            OrderRole::Maker,
+            trade.commission_currency_code,
+            None,
+            Some(trade.commission_amount),
+            OrderFillType::UserTrade,
+        )
+    }
+}
+
+#[async_trait]
+impl ExchangeClient for MockExchangeClient {
+    async fn create_order(&self, order: &OrderRef) -> CreateOrderResult {
+        let new_order = NewOrder {
+            client_order_id: order.client_order_id(),
+            currency_pair: order.currency_pair(),
+            side: order.side(),
+            price: order.price(),
+            amount: order.amount(),
+        };
+        let body = serde_json::to_string(&new_order).expect("Unable to serialize new order");
+
+        match self
+            .request::<CreatedOrder>(Method::POST, "/orders", Some(body))
+            .await
+        {
+            Ok(created_order) => {
+                CreateOrderResult::succeed(&created_order.exchange_order_id, EventSourceType::Rest)
+            }
+            Err(err) => CreateOrderResult::failed(err, EventSourceType::Rest),
+        }
+    }
+
+    async fn cancel_order(
+        &self,
+        order: &OrderRef,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> CancelOrderResult {
+        let path = format!("/orders/{exchange_order_id}");
+        match self
+            .request::<CancelledOrder>(Method::DELETE, &path, None)
+            .await
+        {
+            Ok(cancelled_order) => CancelOrderResult::succeed(
+                order.client_order_id(),
+                EventSourceType::Rest,
+                Some(cancelled_order.filled_amount),
+            ),
+            Err(err) => CancelOrderResult::failed(err, EventSourceType::Rest),
+        }
+    }
+
+    async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        let orders = self.get_open_orders_by_currency_pair(currency_pair).await?;
+        for order in orders {
+            let path = format!("/orders/{}", order.exchange_order_id);
+            if let Err(err) = self
+                .request::<CancelledOrder>(Method::DELETE, &path, None)
+                .await
+            {
+                bail!(
+                    "Failed to cancel order {}: {err:?}",
+                    order.exchange_order_id
+                )
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
+        let orders = self
+            .request::<Vec<OrderState>>(Method::GET, "/orders", None)
+            .await
+            .context("Failed to get open orders of mock exchange")?;
+
+        Ok(orders.into_iter().map(Self::order_info).collect())
+    }
+
+    async fn get_open_orders_by_currency_pair(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<Vec<OrderInfo>> {
+        let mut orders = self.get_open_orders().await?;
+        orders.retain(|x| x.currency_pair == currency_pair);
+        Ok(orders)
+    }
+
+    async fn get_order_info(&self, order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
+        let path = match order.exchange_order_id() {
+            Some(exchange_order_id) => format!("/orders/{exchange_order_id}"),
+            None => format!("/client_orders/{}", order.client_order_id()),
+        };
+
+        self.request::<OrderState>(Method::GET, &path, None)
+            .await
+            .map(Self::order_info)
+    }
+
+    async fn close_position(
+        &self,
+        _position: &ActivePosition,
+        _price: Option<Price>,
+    ) -> Result<ClosedPosition> {
+        Err(anyhow!("Mock exchange supports only spot trading"))
+    }
+
+    async fn get_active_positions(&self) -> Result<Vec<ActivePosition>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_balance_and_positions(&self) -> Result<ExchangeBalancesAndPositions> {
+        let balances = self
+            .request::<Vec<BalanceInfo>>(Method::GET, "/balances", None)
+            .await
+            .context("Failed to get balances of mock exchange")?;
+
+        Ok(ExchangeBalancesAndPositions {
+            balances: balances
+                .into_iter()
+                .map(|x| ExchangeBalance {
+                    currency_code: x.currency_code,
+                    balance: x.balance,
+                })
+                .collect(),
+            positions: None,
+        })
+    }
+
+    async fn get_my_trades(
+        &self,
+        _symbol: &Symbol,
+        _last_date_time: Option<DateTime>,
+    ) -> RequestResult<Vec<OrderTrade>> {
+        match self
+            .request::<Vec<TradeInfo>>(Method::GET, "/trades", None)
+            .await
+        {
+            Ok(trades) => {
+                RequestResult::Success(trades.into_iter().map(Self::order_trade).collect())
+            }
+            Err(err) => RequestResult::Error(err),
+        }
+    }
+
+    async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>> {
+        let symbols = self
+            .request::<Vec<SymbolInfo>>(Method::GET, "/symbols", None)
+            .await
+            .context("Failed to get symbols of mock exchange")?;
+
+        Ok(symbols
+            .into_iter()
+            .map(|symbol| {
+                let codes = symbol.currency_pair.to_codes();
+                for currency_code in codes.to_array() {
+                    let _ = self
+                        .supported_currencies
+                        .insert(currency_code.as_str().into(), currency_code);
+                }
+
+                Arc::new(Symbol::new(
+                    false,
+                    codes.base.as_str().into(),
+                    codes.base,
+                    codes.quote.as_str().into(),
+                    codes.quote,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    codes.base,
+                    None,
+                    Precision::ByTick {
+                        tick: symbol.price_tick,
+                    },
+                    Precision::ByTick {
+                        tick: symbol.amount_tick,
+                    },
+                ))
+            })
+            .collect())
+    }
+
+    async fn get_server_time(&self) -> Option<Result<i64>> {
+        None
+    }
+}
+
+#[async_trait]
+impl Support for MockExchangeClient {
+    fn as_any(&self) -> &(dyn Any + Send + Sync + 'static) {
+        self
+    }
+
+    fn on_websocket_message(&self, msg: &str) -> Result<()> {
+        let SequencedNotification {
+            sequence,
+            notification,
+        } = serde_json::from_str(msg)
+            .with_context(|| format!("Unable to parse mock exchange notification {msg}"))?;
+
+        match self.sequence_tracker.check(NOTIFICATIONS_STREAM, sequence) {
+            SequenceCheck::InOrder => {}
+            SequenceCheck::Outdated => return Ok(()),
+            SequenceCheck::Gap(gap) => (self.handle_sequence_gap_callback)(gap),
+        }
+
+        match notification {
+            Notification::Created {
+                client_order_id,
+                exchange_order_id,
+            } => (self.order_created_callback)(
+                client_order_id,
+                exchange_order_id,
+                EventSourceType::WebSocket,
+            ),
+            Notification::Cancelled {
+                client_order_id,
+                exchange_order_id,
+            } => (self.order_cancelled_callback)(
+                client_order_id,
+                exchange_order_id,
+                EventSourceType::WebSocket,
+            ),
+            Notification::Filled {
+                trade,
+                total_filled_amount,
+            } => (self.handle_order_filled_callback)(FillEvent {
+                source_type: EventSourceType::WebSocket,
+                trade_id: Some(TradeId::Number(trade.trade_id)),
+                client_order_id: Some(trade.client_order_id),
+                exchange_order_id: trade.exchange_order_id,
+                fill_price: trade.price,
+                fill_amount: FillAmount::Incremental {
+                    fill_amount: trade.amount,
+                    total_filled_amount: Some(total_filled_amount),
+                },
+                order_role: Some(OrderRole::Maker),
+                commission_currency_code: Some(trade.commission_currency_code),
+                commission_rate: None,
+                commission_amount: Some(trade.commission_amount),
+                fill_type: OrderFillType::UserTrade,
+                special_order_data: None,
+                fill_date: Some(trade.datetime),
+            }),
+        }
+
+        Ok(())
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_connected(&self) -> Result<()> {
+        self.sequence_tracker.reset();
+        Ok(())
+    }
+
+    fn on_disconnected(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_send_websocket_message_callback(&mut self, callback: SendWebsocketMessageCb) {
+        self.websocket_message_callback = callback;
+    }
+
+    fn set_order_created_callback(&mut self, callback: OrderCreatedCb) {
+        self.order_created_callback = callback;
+    }
+
+    fn set_order_cancelled_callback(&mut self, callback: OrderCancelledCb) {
+        self.order_cancelled_callback = callback;
+    }
+
+    fn set_handle_order_filled_callback(&mut self, callback: HandleOrderFilledCb) {
+        self.handle_order_filled_callback = callback;
+    }
+
+    fn set_handle_trade_callback(&mut self, callback: HandleTradeCb) {
+        self.handle_trade_callback = callback;
+    }
+
+    fn set_handle_metrics_callback(&mut self, callback: HandleMetricsCb) {
+        self.handle_metrics_callback = callback;
+    }
+
+    fn set_handle_sequence_gap_callback(&mut self, callback: HandleSequenceGapCb) {
+        self.handle_sequence_gap_callback = callback;
+    }
+
+    fn set_traded_specific_currencies(&self, currencies: Vec<SpecificCurrencyPair>) {
+        *self.traded_specific_currencies.lock() = currencies;
+    }
+
+    /// Order events are pushed by main connection only
+    fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
+        role == WebSocketRole::Main
+    }
+
+    async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url> {
+        match role {
+            WebSocketRole::Main => Ok(self.websocket_url.clone()),
+            WebSocketRole::Secondary => bail!("Mock exchange has no secondary websocket"),
+        }
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        currency_pair.as_str().into()
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        &self.supported_currencies
+    }
+
+    fn should_log_message(&self, _message: &str) -> bool {
+        true
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        &self.settings
+    }
+}
+
+/// Builds connector of [`MockExchangeServer`], exchange id of settings should be [`MOCK_EXCHANGE_ID`]
+pub struct MockExchangeClientBuilder {
+    rest_uri: String,
+    websocket_url: String,
+}
+
+impl MockExchangeClientBuilder {
+    pub fn new(server: &MockExchangeServer) -> Self {
+        Self {
+            rest_uri: server.rest_uri(),
+            websocket_url: server.websocket_url(),
+        }
+    }
+}
+
+impl ExchangeClientBuilder for MockExchangeClientBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        _events_channel: broadcast::Sender<ExchangeEvent>,
+        _lifetime_manager: Arc<AppLifetimeManager>,
+        _timeout_manager: Arc<TimeoutManager>,
+        _orders: Arc<OrdersPool>,
+    ) -> ExchangeClientBuilderResult {
+        let client = MockExchangeClient {
+            settings: exchange_settings,
+            rest_uri: self.rest_uri.clone(),
+            websocket_url: self
+                .websocket_url
+                .parse()
+                .expect("Invalid websocket url of mock exchange"),
+            http_client: Client::new(),
+            supported_currencies: Default::default(),
+            traded_specific_currencies: Default::default(),
+            order_created_callback: Box::new(|_, _, _| {}),
+            order_cancelled_callback: Box::new(|_, _, _| {}),
+            handle_order_filled_callback: Box::new(|_| {}),
+            handle_trade_callback: Box::new(|_, _| {}),
+            handle_metrics_callback: Box::new(|_| {}),
+            websocket_message_callback: Box::new(|_, _| Ok(())),
+            handle_sequence_gap_callback: Box::new(|_| {}),
+            sequence_tracker: SequenceTracker::default(),
+        };
+
+        ExchangeClientBuilderResult {
+            client: Box::new(client),
+            features: ExchangeFeatures::new(
+                OpenOrdersType::AllCurrencyPair,
+                RestFillsFeatures::new(RestFillsType::MyTrades),
+                OrderFeatures {
+                    maker_only: true,
+                    supports_get_order_info_by_client_order_id: true,
+                    ..Default::default()
+                },
+                OrderTradeOption::default(),
+                WebSocketOptions::new(true, true, false, false),
+                false,
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+            ),
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        RequestTimeoutArguments::from_requests_per_minute(REQUESTS_PER_MINUTE)
+    }
+
+    fn get_exchange_id(&self) -> ExchangeId {
+        MOCK_EXCHANGE_ID.into()
+    }
+}
@@ -0,0 +1,692 @@
+mod client;
+mod server;
+
+pub use client::MockExchangeClientBuilder;
+pub use server::{MockError, MockExchangeServer, MockRequest, ScriptedFill};
+
+use mmb_domain::market::{CurrencyCode, CurrencyPair};
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, ExchangeOrderId, OrderSide, OrderStatus, Price,
+};
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Exchange id of connector to mock exchange, it should be used in exchange settings
+pub const MOCK_EXCHANGE_ID: &str = "MockExchange";
+
+// Protocol of mock exchange, the same structures are used by server and connector
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SymbolInfo {
+    currency_pair: CurrencyPair,
+    price_tick: Decimal,
+    amount_tick: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct BalanceInfo {
+    currency_code: CurrencyCode,
+    balance: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct NewOrder {
+    client_order_id: ClientOrderId,
+    currency_pair: CurrencyPair,
+    side: OrderSide,
+    price: Price,
+    amount: Amount,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CreatedOrder {
+    exchange_order_id: ExchangeOrderId,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CancelledOrder {
+    filled_amount: Amount,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct OrderState {
+    exchange_order_id: ExchangeOrderId,
+    client_order_id: ClientOrderId,
+    currency_pair: CurrencyPair,
+    side: OrderSide,
+    price: Price,
+    amount: Amount,
+    /// Only `Created`, `Canceled` and `Completed` statuses are used
+    status: OrderStatus,
+    filled_amount: Amount,
+    average_fill_price: Price,
+    commission_amount: Amount,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct TradeInfo {
+    trade_id: u64,
+    exchange_order_id: ExchangeOrderId,
+    client_order_id: ClientOrderId,
+    price: Price,
+    amount: Amount,
+    commission_currency_code: CurrencyCode,
+    commission_amount: Amount,
+    datetime: DateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Notification {
+    Created {
+        client_order_id: ClientOrderId,
+        exchange_order_id: ExchangeOrderId,
+    },
+    Cancelled {
+        client_order_id: ClientOrderId,
+        exchange_order_id: ExchangeOrderId,
+    },
+    Filled {
+        trade: TradeInfo,
+        total_filled_amount: Amount,
+    },
+}
+
+/// Notifications are numbered, so connector can detect lost ones
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SequencedNotification {
+    sequence: u64,
+    #[serde(flatten)]
+    notification: Notification,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "error", rename_all = "snake_case")]
+enum ErrorResponse {
+    Pending {
+        retry_after_ms: u64,
+    },
+    OrderNotFound,
+    OrderCompleted,
+    /// Order with the same client order id is already created
+    DuplicateOrder,
+    /// Execution status of request is unknown
+    Timeout,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance::{create_exchange_with_builder, ConformanceScenario, MockExchange};
+    use dashmap::DashMap;
+    use mmb_core::connectivity::WebSocketRole;
+    use mmb_core::exchanges::exchange_blocker::ExchangeBlocker;
+    use mmb_core::exchanges::general::exchange::Exchange;
+    use mmb_core::exchanges::general::order::validation::OrderValidationError;
+    use mmb_core::exchanges::symbol_service::SymbolService;
+    use mmb_core::infrastructure::init_lifetime_manager;
+    use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+    use mmb_core::settings::{
+        CurrencyPairSetting, ExchangeSettings, FaultInjectionSettings, FaultRates,
+        OrderValidationSettings, RoundingMode, StreamWatchdogSettings,
+    };
+    use mmb_domain::events::{ConnectivityState, ExchangeEvent};
+    use mmb_domain::exchanges::symbol::{Round, SymbolStatus};
+    use mmb_domain::market::ExchangeAccountId;
+    use mmb_domain::order::event::OrderEventType;
+    use mmb_domain::order::pool::OrderRef;
+    use mmb_domain::order::snapshot::{OrderHeader, UserOrder};
+    use rust_decimal_macros::dec;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::broadcast;
+    use tokio::time::{timeout, Instant};
+
+    const PRICE: Price = dec!(30000);
+    const AMOUNT: Amount = dec!(0.01);
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usd".into())
+    }
+
+    fn exchange_settings() -> ExchangeSettings {
+        let mut settings = ExchangeSettings::new_short(
+            ExchangeAccountId::new(MOCK_EXCHANGE_ID, 0),
+            "api_key".to_owned(),
+            "secret_key".to_owned(),
+            false,
+        );
+        settings.currency_pairs = Some(vec![CurrencyPairSetting::Ordinary {
+            base: "btc".into(),
+            quote: "usd".into(),
+        }]);
+        settings
+    }
+
+    struct TestContext {
+        server: Arc<MockExchangeServer>,
+        exchange: Arc<Exchange>,
+        events_tx: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+        _exchange_blocker: Arc<ExchangeBlocker>,
+    }
+
+    impl TestContext {
+        async fn new() -> Self {
+            Self::with_settings(exchange_settings()).await
+        }
+
+        async fn with_settings(settings: ExchangeSettings) -> Self {
+            let server = MockExchangeServer::start(vec![currency_pair()])
+                .await
+                .expect("in test");
+            let (events_tx, events) = broadcast::channel(1000);
+            let lifetime_manager = init_lifetime_manager();
+            let (exchange, exchange_blocker) = create_exchange_with_builder(
+                Box::new(MockExchangeClientBuilder::new(&server)),
+                &settings,
+                events_tx.clone(),
+                lifetime_manager.clone(),
+            )
+            .await
+            .expect("in test");
+            timeout(TIMEOUT, server.wait_connected())
+                .await
+                .expect("in test")
+                .expect("in test");
+            tokio::spawn(notify_finished_orders(exchange.clone(), events));
+
+            Self {
+                server,
+                exchange,
+                events_tx,
+                lifetime_manager,
+                _exchange_blocker: exchange_blocker,
+            }
+        }
+
+        async fn create_order(&self) -> OrderRef {
+            let header = OrderHeader::with_user_order(
+                ClientOrderId::unique_id(),
+                self.exchange.exchange_account_id,
+                currency_pair(),
+                OrderSide::Buy,
+                AMOUNT,
+                UserOrder::maker_only(PRICE),
+                None,
+                None,
+                "MockExchangeTest".to_owned(),
+            );
+            let order = timeout(
+                TIMEOUT,
+                self.exchange
+                    .create_order(&header, None, self.lifetime_manager.stop_token()),
+            )
+            .await
+            .expect("in test")
+            .expect("in test");
+            assert_eq!(order.status(), OrderStatus::Created);
+
+            order
+        }
+
+        async fn cancel_order(&self, order: &OrderRef) {
+            timeout(
+                TIMEOUT,
+                self.exchange.wait_cancel_order(
+                    order.clone(),
+                    None,
+                    false,
+                    self.lifetime_manager.stop_token(),
+                ),
+            )
+            .await
+            .expect("in test")
+            .expect("in test");
+        }
+
+        async fn wait_received_messages(&self, expected: Vec<String>) {
+            timeout(TIMEOUT, async {
+                while self.server.received_messages() != expected {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await
+            .expect("in test");
+        }
+    }
+
+    /// Waiters of order finish are notified by events loop of the engine
+    async fn notify_finished_orders(
+        exchange: Arc<Exchange>,
+        mut events: broadcast::Receiver<ExchangeEvent>,
+    ) {
+        while let Ok(event) = events.recv().await {
+            if let ExchangeEvent::OrderEvent(event) = event {
+                if let OrderEventType::CancelOrderSucceeded
+                | OrderEventType::OrderCompleted { .. } = event.event_type
+                {
+                    exchange.order_finished_notify(&event.order);
+                }
+            }
+        }
+    }
+
+    impl Drop for TestContext {
+        fn drop(&mut self) {
+            self.lifetime_manager.stop_token().cancel();
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn conformance() {
+        let server = MockExchangeServer::start(vec![currency_pair()])
+            .await
+            .expect("in test");
+
+        let order = ConformanceScenario::new(currency_pair(), PRICE, AMOUNT)
+            .run(
+                Box::new(MockExchangeClientBuilder::new(&server)),
+                exchange_settings(),
+                server.as_ref(),
+            )
+            .await
+            .expect("in test");
+
+        assert_eq!(order.filled_amount(), dec!(0.005));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn response_is_delayed_by_latency() {
+        let context = TestContext::new().await;
+        let latency = Duration::from_millis(300);
+
+        let order = context.create_order().await;
+        context
+            .server
+            .set_latency(MockRequest::GetOrderInfo, latency);
+
+        let started_at = Instant::now();
+        let order_info = context
+            .exchange
+            .get_order_info(&order)
+            .await
+            .expect("in test");
+
+        assert!(started_at.elapsed() >= latency);
+        assert_eq!(order_info.order_status, OrderStatus::Created);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn missed_fill_is_found_after_order_not_found() {
+        let context = TestContext::new().await;
+        let order = context.create_order().await;
+        context.server.script_fills(
+            MockRequest::CancelOrder,
+            vec![ScriptedFill::new(dec!(0.004), false)],
+        );
+        context
+            .server
+            .inject_error(MockRequest::CancelOrder, MockError::OrderNotFound);
+
+        context.cancel_order(&order).await;
+
+        assert_eq!(order.status(), OrderStatus::Canceled);
+        assert_eq!(order.filled_amount(), dec!(0.004));
+        assert_eq!(context.server.requests_count(MockRequest::GetMyTrades), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn missed_fill_is_found_after_parsing_error() {
+        let context = TestContext::new().await;
+        let order = context.create_order().await;
+        context.server.script_fills(
+            MockRequest::CancelOrder,
+            vec![ScriptedFill::new(dec!(0.004), false)],
+        );
+        context
+            .server
+            .inject_error(MockRequest::CancelOrder, MockError::ParsingError);
+
+        context.cancel_order(&order).await;
+
+        // cancellation is confirmed by order info with filled amount greater than known one
+        assert_eq!(order.status(), OrderStatus::Canceled);
+        assert_eq!(order.filled_amount(), dec!(0.004));
+        assert_eq!(context.server.requests_count(MockRequest::CancelOrder), 1);
+        assert_eq!(context.server.requests_count(MockRequest::GetOrderInfo), 1);
+        assert_eq!(context.server.requests_count(MockRequest::GetMyTrades), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn cancellation_is_repeated_after_pending_error() {
+        let context = TestContext::new().await;
+        let order = context.create_order().await;
+        let pending_time = Duration::from_millis(300);
+        context.server.inject_error(
+            MockRequest::CancelOrder,
+            MockError::PendingError(pending_time),
+        );
+
+        let started_at = Instant::now();
+        context.cancel_order(&order).await;
+
+        assert!(started_at.elapsed() >= pending_time);
+        assert_eq!(order.status(), OrderStatus::Canceled);
+        assert_eq!(context.server.requests_count(MockRequest::CancelOrder), 2);
+        let exchange_order_id = order.exchange_order_id().expect("in test");
+        assert_eq!(
+            context.server.order_status(&exchange_order_id),
+            Some(OrderStatus::Canceled)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn order_is_found_after_creation_timeout() {
+        let context = TestContext::new().await;
+        context.server.inject_error(
+            MockRequest::CreateOrder,
+            MockError::Timeout { is_processed: true },
+        );
+
+        let order = context.create_order().await;
+
+        assert_eq!(context.server.requests_count(MockRequest::CreateOrder), 1);
+        assert_eq!(context.server.requests_count(MockRequest::GetOrderInfo), 1);
+        let exchange_order_id = order.exchange_order_id().expect("in test");
+        assert_eq!(
+            context.server.order_status(&exchange_order_id),
+            Some(OrderStatus::Created)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn creation_is_repeated_if_order_is_not_found_after_timeout() {
+        let context = TestContext::new().await;
+        context.server.inject_error(
+            MockRequest::CreateOrder,
+            MockError::Timeout {
+                is_processed: false,
+            },
+        );
+
+        let order = context.create_order().await;
+
+        assert_eq!(context.server.requests_count(MockRequest::CreateOrder), 2);
+        assert_eq!(context.server.requests_count(MockRequest::GetOrderInfo), 1);
+        assert!(order.exchange_order_id().is_some());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn repeated_creation_is_rejected_as_duplicate() {
+        let context = TestContext::new().await;
+        context.server.inject_error(
+            MockRequest::CreateOrder,
+            MockError::Timeout { is_processed: true },
+        );
+        // exchange hasn't indexed created order yet
+        context
+            .server
+            .inject_error(MockRequest::GetOrderInfo, MockError::OrderNotFound);
+
+        let order = context.create_order().await;
+
+        // the second creation request is rejected, so order is submitted once
+        assert_eq!(context.server.requests_count(MockRequest::CreateOrder), 2);
+        assert_eq!(context.server.requests_count(MockRequest::GetOrderInfo), 2);
+        let exchange_order_id = order.exchange_order_id().expect("in test");
+        assert_eq!(exchange_order_id.as_str(), "MOCK1");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn order_completed_during_cancellation() {
+        let context = TestContext::new().await;
+        let order = context.create_order().await;
+        // fill is pushed after response to cancellation request
+        context
+            .server
+            .set_notification_latency(Duration::from_millis(300));
+        context.server.script_fills(
+            MockRequest::CancelOrder,
+            vec![ScriptedFill::new(AMOUNT, true)],
+        );
+
+        context.cancel_order(&order).await;
+
+        assert_eq!(order.status(), OrderStatus::Completed);
+        assert_eq!(order.filled_amount(), AMOUNT);
+        assert_eq!(context.server.requests_count(MockRequest::CancelOrder), 1);
+        assert_eq!(context.server.requests_count(MockRequest::GetMyTrades), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn fill_is_counted_once_for_duplicated_notifications() {
+        let mut settings = exchange_settings();
+        settings.fault_injection = Some(FaultInjectionSettings {
+            seed: 1,
+            max_delay_ms: 50,
+            rest: FaultRates {
+                delay: dec!(1),
+                ..Default::default()
+            },
+            websocket: FaultRates {
+                duplicate: dec!(1),
+                ..Default::default()
+            },
+        });
+        let context = TestContext::with_settings(settings).await;
+        let order = context.create_order().await;
+        context.server.script_fills(
+            MockRequest::CancelOrder,
+            vec![ScriptedFill::new(dec!(0.004), true)],
+        );
+
+        context.cancel_order(&order).await;
+
+        assert_eq!(order.status(), OrderStatus::Canceled);
+        assert_eq!(order.filled_amount(), dec!(0.004));
+        assert_eq!(context.server.requests_count(MockRequest::CancelOrder), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn channels_are_resubscribed_after_reconnection() {
+        let context = TestContext::new().await;
+        let mut events = context.events_tx.subscribe();
+        let subscription = r#"{"subscribe":"trades"}"#.to_owned();
+        context
+            .exchange
+            .subscribe_websocket(WebSocketRole::Main, "trades", subscription.clone())
+            .expect("in test");
+        context
+            .wait_received_messages(vec![subscription.clone()])
+            .await;
+
+        context.server.disconnect().await.expect("in test");
+
+        let states = timeout(TIMEOUT, async {
+            let mut states = vec![];
+            while states.last() != Some(&ConnectivityState::Connected) {
+                if let ExchangeEvent::Connectivity(event) = events.recv().await.expect("in test") {
+                    states.push(event.state);
+                }
+            }
+            states
+        })
+        .await
+        .expect("in test");
+
+        assert_eq!(
+            states,
+            vec![
+                ConnectivityState::Disconnected,
+                ConnectivityState::Connected
+            ]
+        );
+        context
+            .wait_received_messages(vec![subscription.clone(); 2])
+            .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn silent_connection_is_reconnected() {
+        let mut settings = exchange_settings();
+        settings.stream_watchdog = Some(StreamWatchdogSettings {
+            max_exchange_silence_ms: 200,
+            max_stream_silence_ms: None,
+        });
+        let context = TestContext::with_settings(settings).await;
+        let mut events = context.events_tx.subscribe();
+
+        let stale_event = timeout(TIMEOUT, async {
+            loop {
+                if let ExchangeEvent::StreamStale(event) = events.recv().await.expect("in test") {
+                    return event;
+                }
+            }
+        })
+        .await
+        .expect("in test");
+        assert_eq!(stale_event.currency_pair, None);
+
+        timeout(TIMEOUT, async {
+            loop {
+                if let ExchangeEvent::Connectivity(event) = events.recv().await.expect("in test") {
+                    if event.state == ConnectivityState::Connected {
+                        return;
+                    }
+                }
+            }
+        })
+        .await
+        .expect("in test");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn lost_fill_is_found_after_sequence_gap() {
+        let context = TestContext::new().await;
+        let order = context.create_order().await;
+        let exchange_order_id = order.exchange_order_id().expect("in test");
+
+        context.server.lose_notifications(1);
+        context
+            .server
+            .fill(&exchange_order_id, dec!(0.004), PRICE, true)
+            .await
+            .expect("in test");
+        context
+            .server
+            .fill(&exchange_order_id, dec!(0.002), PRICE, true)
+            .await
+            .expect("in test");
+
+        timeout(TIMEOUT, async {
+            while order.filled_amount() != dec!(0.006) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("in test");
+        // fill is found by reconciliation without cancellation of order
+        assert_eq!(order.status(), OrderStatus::Created);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn orders_are_paused_for_delisted_symbol() {
+        let context = TestContext::new().await;
+        let mut events = context.events_tx.subscribe();
+        let exchange_account_id = context.exchange.exchange_account_id;
+        let exchanges = DashMap::new();
+        exchanges.insert(exchange_account_id, context.exchange.clone());
+        let symbol_service = SymbolService::new(exchanges);
+        assert_eq!(
+            symbol_service
+                .price_round(
+                    exchange_account_id,
+                    currency_pair(),
+                    dec!(30000.128),
+                    Round::Floor
+                )
+                .expect("in test"),
+            dec!(30000.12)
+        );
+
+        context.server.delist(currency_pair());
+        symbol_service.clone().refresh().await;
+
+        assert!(!symbol_service.is_trading(exchange_account_id, currency_pair()));
+        let status_event = timeout(TIMEOUT, async {
+            loop {
+                if let ExchangeEvent::SymbolStatus(event) = events.recv().await.expect("in test") {
+                    return event;
+                }
+            }
+        })
+        .await
+        .expect("in test");
+        assert_eq!(status_event.status, SymbolStatus::Delisted);
+
+        let header = OrderHeader::with_user_order(
+            ClientOrderId::unique_id(),
+            exchange_account_id,
+            currency_pair(),
+            OrderSide::Buy,
+            AMOUNT,
+            UserOrder::maker_only(PRICE),
+            None,
+            None,
+            "MockExchangeTest".to_owned(),
+        );
+        let result = context
+            .exchange
+            .create_order(&header, None, context.lifetime_manager.stop_token())
+            .await;
+        assert!(result.is_err());
+        assert_eq!(context.server.requests_count(MockRequest::CreateOrder), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn order_violating_trading_rules_is_rejected_locally() {
+        let mut settings = exchange_settings();
+        settings.order_validation = Some(OrderValidationSettings {
+            price_rounding: RoundingMode::Reject,
+            amount_rounding: RoundingMode::Passive,
+        });
+        let context = TestContext::with_settings(settings).await;
+        let create_order = |price| {
+            let header = OrderHeader::with_user_order(
+                ClientOrderId::unique_id(),
+                context.exchange.exchange_account_id,
+                currency_pair(),
+                OrderSide::Buy,
+                dec!(0.01005),
+                UserOrder::maker_only(price),
+                None,
+                None,
+                "MockExchangeTest".to_owned(),
+            );
+            let exchange = context.exchange.clone();
+            let stop_token = context.lifetime_manager.stop_token();
+            async move {
+                timeout(TIMEOUT, exchange.create_order(&header, None, stop_token))
+                    .await
+                    .expect("in test")
+            }
+        };
+
+        let error = create_order(dec!(30000.128)).await.expect_err("in test");
+        assert_eq!(
+            error.downcast_ref::<OrderValidationError>(),
+            Some(&OrderValidationError::PriceNotMultipleOfTick {
+                price: dec!(30000.128),
+                nearest: dec!(30000.13),
+            })
+        );
+        assert_eq!(context.server.requests_count(MockRequest::CreateOrder), 0);
+
+        let order = create_order(PRICE).await.expect("in test");
+        assert_eq!(order.amount(), AMOUNT);
+        assert_eq!(context.server.requests_count(MockRequest::CreateOrder), 1);
+    }
+}
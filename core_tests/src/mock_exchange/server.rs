@@ -0,0 +1,714 @@
+use super::{
+    BalanceInfo, CancelledOrder, CreatedOrder, ErrorResponse, NewOrder, Notification, OrderState,
+    SequencedNotification, SymbolInfo, TradeInfo,
+};
+use crate::conformance::MockExchange;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::{SinkExt, StreamExt};
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use mmb_domain::market::CurrencyPair;
+use mmb_domain::order::snapshot::{Amount, ClientOrderId, ExchangeOrderId, OrderStatus, Price};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::sync::{Arc, Weak};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, watch};
+use tokio::time::{sleep, sleep_until, Duration, Instant};
+use tokio_tungstenite::tungstenite::Message;
+
+const PRICE_TICK: Decimal = dec!(0.01);
+const AMOUNT_TICK: Decimal = dec!(0.0001);
+const COMMISSION_RATE: Decimal = dec!(0.001);
+/// Balance of every currency of traded currency pairs
+const BALANCE: Decimal = dec!(1000000);
+/// Body which is returned instead of response in case of `MockError::ParsingError`
+const MALFORMED_BODY: &str = "<html><body>502 Bad Gateway</body></html>";
+
+/// Requests of connector which behavior can be programmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockRequest {
+    CreateOrder,
+    CancelOrder,
+    GetOrderInfo,
+    GetOpenOrders,
+    GetMyTrades,
+}
+
+/// Error which is returned by exchange instead of processing of request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockError {
+    /// Request is processed, but its response can't be parsed and its notifications are lost
+    ParsingError,
+    /// Exchange is busy and asks to repeat the request after the delay
+    PendingError(Duration),
+    OrderNotFound,
+    OrderCompleted,
+    /// Exchange responds with timeout, so execution status of request is unknown for connector.
+    /// Request is processed if `is_processed` is set, but its notifications are lost
+    Timeout {
+        is_processed: bool,
+    },
+}
+
+/// Fill by order price which is made by exchange when request is received
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptedFill {
+    pub amount: Amount,
+    /// Whether fill is pushed by websocket, otherwise it can be found only by requests
+    pub notify: bool,
+}
+
+impl ScriptedFill {
+    pub fn new(amount: Amount, notify: bool) -> Self {
+        Self { amount, notify }
+    }
+}
+
+#[derive(Default)]
+struct ServerState {
+    currency_pairs: Vec<CurrencyPair>,
+    orders: Vec<OrderState>,
+    trades: Vec<TradeInfo>,
+    /// Counter of order and trade ids
+    next_id: u64,
+    latencies: HashMap<MockRequest, Duration>,
+    notification_latency: Duration,
+    errors: HashMap<MockRequest, VecDeque<MockError>>,
+    scripted_fills: HashMap<MockRequest, Vec<ScriptedFill>>,
+    requests_count: HashMap<MockRequest, usize>,
+    /// Senders of messages to websocket connections
+    connections: Vec<mpsc::UnboundedSender<String>>,
+    /// Messages sent by connector through websocket
+    received_messages: Vec<String>,
+    /// Sequence number of last notification
+    last_sequence: u64,
+    /// Count of next notifications which are lost
+    notifications_to_lose: usize,
+}
+
+impl ServerState {
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn find_order(&self, exchange_order_id: &ExchangeOrderId) -> Option<usize> {
+        self.orders
+            .iter()
+            .position(|x| &x.exchange_order_id == exchange_order_id)
+    }
+
+    fn find_order_by_key(&self, order_key: &OrderKey) -> Option<usize> {
+        match order_key {
+            OrderKey::Exchange(exchange_order_id) => self.find_order(exchange_order_id),
+            OrderKey::Client(client_order_id) => self
+                .orders
+                .iter()
+                .position(|x| &x.client_order_id == client_order_id),
+        }
+    }
+
+    fn fill(
+        &mut self,
+        index: usize,
+        amount: Amount,
+        price: Price,
+        notify: bool,
+        notifications: &mut Vec<Notification>,
+    ) -> Result<()> {
+        let trade_id = self.next_id();
+        let order = &mut self.orders[index];
+        if order.status != OrderStatus::Created {
+            bail!(
+                "Unable to fill {:?} order {}",
+                order.status,
+                order.exchange_order_id
+            );
+        }
+        if order.filled_amount + amount > order.amount {
+            bail!(
+                "Fill {amount} exceeds unfilled amount of order {}",
+                order.exchange_order_id
+            );
+        }
+
+        let filled_amount = order.filled_amount + amount;
+        order.average_fill_price =
+            (order.average_fill_price * order.filled_amount + price * amount) / filled_amount;
+        order.filled_amount = filled_amount;
+        if order.filled_amount == order.amount {
+            order.status = OrderStatus::Completed;
+        }
+
+        let commission_amount = price * amount * COMMISSION_RATE;
+        order.commission_amount += commission_amount;
+
+        let trade = TradeInfo {
+            trade_id,
+            exchange_order_id: order.exchange_order_id.clone(),
+            client_order_id: order.client_order_id.clone(),
+            price,
+            amount,
+            commission_currency_code: order.currency_pair.to_codes().quote,
+            commission_amount,
+            datetime: Utc::now(),
+        };
+        if notify {
+            notifications.push(Notification::Filled {
+                trade: trade.clone(),
+                total_filled_amount: filled_amount,
+            });
+        }
+        self.trades.push(trade);
+
+        Ok(())
+    }
+
+    fn apply_scripted_fills(
+        &mut self,
+        request: MockRequest,
+        index: Option<usize>,
+        notifications: &mut Vec<Notification>,
+    ) -> Result<()> {
+        let fills = match self.scripted_fills.remove(&request) {
+            Some(fills) => fills,
+            None => return Ok(()),
+        };
+        let index = index.with_context(|| format!("No order for fills scripted on {request:?}"))?;
+
+        for fill in fills {
+            let price = self.orders[index].price;
+            self.fill(index, fill.amount, price, fill.notify, notifications)?;
+        }
+
+        Ok(())
+    }
+
+    fn create_order(
+        &mut self,
+        new_order: NewOrder,
+        notifications: &mut Vec<Notification>,
+    ) -> Result<CreatedOrder, ErrorResponse> {
+        // client order id is idempotency key of creation
+        if self
+            .orders
+            .iter()
+            .any(|x| x.client_order_id == new_order.client_order_id)
+        {
+            return Err(ErrorResponse::DuplicateOrder);
+        }
+
+        let exchange_order_id: ExchangeOrderId = format!("MOCK{}", self.next_id()).as_str().into();
+        self.orders.push(OrderState {
+            exchange_order_id: exchange_order_id.clone(),
+            client_order_id: new_order.client_order_id.clone(),
+            currency_pair: new_order.currency_pair,
+            side: new_order.side,
+            price: new_order.price,
+            amount: new_order.amount,
+            status: OrderStatus::Created,
+            filled_amount: Amount::ZERO,
+            average_fill_price: Price::ZERO,
+            commission_amount: Amount::ZERO,
+        });
+        notifications.push(Notification::Created {
+            client_order_id: new_order.client_order_id,
+            exchange_order_id: exchange_order_id.clone(),
+        });
+
+        Ok(CreatedOrder { exchange_order_id })
+    }
+
+    fn cancel_order(
+        &mut self,
+        index: usize,
+        notifications: &mut Vec<Notification>,
+    ) -> Result<CancelledOrder, ErrorResponse> {
+        let order = &mut self.orders[index];
+        match order.status {
+            OrderStatus::Completed => Err(ErrorResponse::OrderCompleted),
+            OrderStatus::Canceled => Err(ErrorResponse::OrderNotFound),
+            _ => {
+                order.status = OrderStatus::Canceled;
+                notifications.push(Notification::Cancelled {
+                    client_order_id: order.client_order_id.clone(),
+                    exchange_order_id: order.exchange_order_id.clone(),
+                });
+
+                Ok(CancelledOrder {
+                    filled_amount: order.filled_amount,
+                })
+            }
+        }
+    }
+}
+
+/// Identifier of order in request path
+enum OrderKey {
+    Exchange(ExchangeOrderId),
+    Client(ClientOrderId),
+}
+
+enum Route {
+    Symbols,
+    Balances,
+    Mock(MockRequest, Option<OrderKey>),
+}
+
+fn get_route(method: &Method, path: &str) -> Option<Route> {
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    let route = match (method, segments.as_slice()) {
+        (&Method::GET, ["symbols"]) => Route::Symbols,
+        (&Method::GET, ["balances"]) => Route::Balances,
+        (&Method::POST, ["orders"]) => Route::Mock(MockRequest::CreateOrder, None),
+        (&Method::GET, ["orders"]) => Route::Mock(MockRequest::GetOpenOrders, None),
+        (&Method::GET, ["trades"]) => Route::Mock(MockRequest::GetMyTrades, None),
+        (&Method::DELETE, ["orders", id]) => Route::Mock(
+            MockRequest::CancelOrder,
+            Some(OrderKey::Exchange((*id).into())),
+        ),
+        (&Method::GET, ["orders", id]) => Route::Mock(
+            MockRequest::GetOrderInfo,
+            Some(OrderKey::Exchange((*id).into())),
+        ),
+        (&Method::GET, ["client_orders", id]) => Route::Mock(
+            MockRequest::GetOrderInfo,
+            Some(OrderKey::Client((*id).into())),
+        ),
+        _ => return None,
+    };
+
+    Some(route)
+}
+
+fn json_response(status: StatusCode, value: &impl Serialize) -> Response<Body> {
+    let body = serde_json::to_string(value).expect("Unable to serialize mock exchange response");
+    create_response(status, body)
+}
+
+fn create_response(status: StatusCode, body: impl Into<Body>) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.into())
+        .expect("Unable to build mock exchange response")
+}
+
+/// Programmable exchange which serves REST API and pushes order events by websocket.
+/// Latencies, errors and fills of every kind of request can be set up beforehand, so
+/// scenarios with races between requests and fills can be reproduced deterministically.
+/// Connector of the exchange is created by [`super::MockExchangeClientBuilder`].
+pub struct MockExchangeServer {
+    rest_port: u16,
+    websocket_port: u16,
+    state: Mutex<ServerState>,
+    is_connected: watch::Sender<bool>,
+    /// Notifications with time of their delivery
+    notifications: mpsc::UnboundedSender<(Instant, String)>,
+}
+
+impl MockExchangeServer {
+    pub async fn start(currency_pairs: Vec<CurrencyPair>) -> Result<Arc<Self>> {
+        let rest_listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+        rest_listener.set_nonblocking(true)?;
+        let websocket_listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let (is_connected, _) = watch::channel(false);
+        let (notifications_tx, notifications_rx) = mpsc::unbounded_channel();
+
+        let server = Arc::new(Self {
+            rest_port: rest_listener.local_addr()?.port(),
+            websocket_port: websocket_listener.local_addr()?.port(),
+            state: Mutex::new(ServerState {
+                currency_pairs,
+                ..Default::default()
+            }),
+            is_connected,
+            notifications: notifications_tx,
+        });
+
+        let weak_server = Arc::downgrade(&server);
+        let make_service = make_service_fn(move |_| {
+            let weak_server = weak_server.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    Self::serve_request(weak_server.clone(), request)
+                }))
+            }
+        });
+        let rest_server = Server::from_tcp(rest_listener)?.serve(make_service);
+        tokio::spawn(async move {
+            if let Err(err) = rest_server.await {
+                log::error!("Mock exchange REST server is stopped: {err:?}");
+            }
+        });
+
+        let weak_server = Arc::downgrade(&server);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = websocket_listener.accept().await {
+                let server = match weak_server.upgrade() {
+                    Some(server) => server,
+                    None => return,
+                };
+                tokio::spawn(async move {
+                    if let Err(err) = server.serve_websocket(stream).await {
+                        log::warn!("Mock exchange websocket connection is broken: {err:?}");
+                    }
+                });
+            }
+        });
+
+        tokio::spawn(Self::deliver_notifications(
+            Arc::downgrade(&server),
+            notifications_rx,
+        ));
+
+        Ok(server)
+    }
+
+    pub fn rest_uri(&self) -> String {
+        format!("http://127.0.0.1:{}", self.rest_port)
+    }
+
+    pub fn websocket_url(&self) -> String {
+        format!("ws://127.0.0.1:{}/ws", self.websocket_port)
+    }
+
+    /// Response of every request of the kind is delayed by `latency` after the request is processed
+    pub fn set_latency(&self, request: MockRequest, latency: Duration) {
+        let _ = self.state.lock().latencies.insert(request, latency);
+    }
+
+    /// Websocket notifications are delayed by `latency`, their order is kept
+    pub fn set_notification_latency(&self, latency: Duration) {
+        self.state.lock().notification_latency = latency;
+    }
+
+    /// Next request of the kind fails with the error, errors are returned in order of injection
+    pub fn inject_error(&self, request: MockRequest, error: MockError) {
+        self.state
+            .lock()
+            .errors
+            .entry(request)
+            .or_default()
+            .push_back(error);
+    }
+
+    /// Fills are made when next request of the kind is received, i.e. before the request is
+    /// processed. Fills are made for the order of request, or for the last created order if
+    /// request doesn't refer to an order. Fills on `MockRequest::CreateOrder` are made right
+    /// after order is created.
+    pub fn script_fills(&self, request: MockRequest, fills: Vec<ScriptedFill>) {
+        let _ = self.state.lock().scripted_fills.insert(request, fills);
+    }
+
+    pub fn requests_count(&self, request: MockRequest) -> usize {
+        self.state
+            .lock()
+            .requests_count
+            .get(&request)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Next `count` notifications are lost, but their sequence numbers are used, so connector
+    /// can detect the gap
+    pub fn lose_notifications(&self, count: usize) {
+        self.state.lock().notifications_to_lose = count;
+    }
+
+    /// Symbol of the currency pair disappears from symbols list
+    pub fn delist(&self, currency_pair: CurrencyPair) {
+        self.state
+            .lock()
+            .currency_pairs
+            .retain(|&x| x != currency_pair);
+    }
+
+    pub fn received_messages(&self) -> Vec<String> {
+        self.state.lock().received_messages.clone()
+    }
+
+    pub fn order_status(&self, exchange_order_id: &ExchangeOrderId) -> Option<OrderStatus> {
+        let state = self.state.lock();
+        state
+            .find_order(exchange_order_id)
+            .map(|index| state.orders[index].status)
+    }
+
+    async fn serve_request(
+        weak_server: Weak<Self>,
+        request: Request<Body>,
+    ) -> Result<Response<Body>, Infallible> {
+        let server = match weak_server.upgrade() {
+            Some(server) => server,
+            None => return Ok(create_response(StatusCode::SERVICE_UNAVAILABLE, "")),
+        };
+
+        let route = get_route(request.method(), request.uri().path());
+        let body = hyper::body::to_bytes(request.into_body())
+            .await
+            .unwrap_or_default();
+
+        let response = match route {
+            None => create_response(StatusCode::NOT_FOUND, ""),
+            Some(Route::Symbols) => json_response(StatusCode::OK, &server.symbols()),
+            Some(Route::Balances) => json_response(StatusCode::OK, &server.balances()),
+            Some(Route::Mock(request, order_key)) => {
+                let (response, latency) = match server.handle(request, order_key.as_ref(), &body) {
+                    Ok(result) => result,
+                    Err(err) => {
+                        log::error!("Mock exchange failed to handle {request:?}: {err:?}");
+                        let body = format!("{err:?}");
+                        (
+                            create_response(StatusCode::INTERNAL_SERVER_ERROR, body),
+                            Duration::ZERO,
+                        )
+                    }
+                };
+                sleep(latency).await;
+                response
+            }
+        };
+
+        Ok(response)
+    }
+
+    fn symbols(&self) -> Vec<SymbolInfo> {
+        self.state
+            .lock()
+            .currency_pairs
+            .iter()
+            .map(|&currency_pair| SymbolInfo {
+                currency_pair,
+                price_tick: PRICE_TICK,
+                amount_tick: AMOUNT_TICK,
+            })
+            .collect()
+    }
+
+    fn balances(&self) -> Vec<BalanceInfo> {
+        let mut currency_codes = Vec::new();
+        for currency_pair in &self.state.lock().currency_pairs {
+            for currency_code in currency_pair.to_codes().to_array() {
+                if !currency_codes.contains(&currency_code) {
+                    currency_codes.push(currency_code);
+                }
+            }
+        }
+
+        currency_codes
+            .into_iter()
+            .map(|currency_code| BalanceInfo {
+                currency_code,
+                balance: BALANCE,
+            })
+            .collect()
+    }
+
+    /// Returns response and its latency
+    fn handle(
+        &self,
+        request: MockRequest,
+        order_key: Option<&OrderKey>,
+        body: &[u8],
+    ) -> Result<(Response<Body>, Duration)> {
+        let mut state = self.state.lock();
+        *state.requests_count.entry(request).or_default() += 1;
+        let latency = state.latencies.get(&request).copied().unwrap_or_default();
+
+        let index = match order_key {
+            Some(order_key) => state.find_order_by_key(order_key),
+            None => state.orders.len().checked_sub(1),
+        };
+        let mut notifications = Vec::new();
+        if request != MockRequest::CreateOrder {
+            state.apply_scripted_fills(request, index, &mut notifications)?;
+        }
+
+        let error = state
+            .errors
+            .get_mut(&request)
+            .and_then(|errors| errors.pop_front());
+        let error_response = match error {
+            Some(MockError::PendingError(delay)) => Some(ErrorResponse::Pending {
+                retry_after_ms: delay.as_millis() as u64,
+            }),
+            Some(MockError::OrderNotFound) => Some(ErrorResponse::OrderNotFound),
+            Some(MockError::OrderCompleted) => Some(ErrorResponse::OrderCompleted),
+            Some(MockError::Timeout {
+                is_processed: false,
+            }) => Some(ErrorResponse::Timeout),
+            Some(MockError::ParsingError | MockError::Timeout { is_processed: true }) | None => {
+                None
+            }
+        };
+        if let Some(error_response) = error_response {
+            self.notify(&mut state, notifications);
+            return Ok((
+                json_response(StatusCode::BAD_REQUEST, &error_response),
+                latency,
+            ));
+        }
+
+        let response = match (request, index) {
+            (MockRequest::CreateOrder, _) => {
+                let new_order: NewOrder =
+                    serde_json::from_slice(body).context("Unable to parse new order")?;
+                match state.create_order(new_order, &mut notifications) {
+                    Ok(created_order) => {
+                        let index = state.orders.len() - 1;
+                        state.apply_scripted_fills(request, Some(index), &mut notifications)?;
+                        json_response(StatusCode::OK, &created_order)
+                    }
+                    Err(error_response) => json_response(StatusCode::BAD_REQUEST, &error_response),
+                }
+            }
+            (MockRequest::GetOpenOrders, _) => {
+                let open_orders = state
+                    .orders
+                    .iter()
+                    .filter(|x| x.status == OrderStatus::Created)
+                    .collect::<Vec<_>>();
+                json_response(StatusCode::OK, &open_orders)
+            }
+            (MockRequest::GetMyTrades, _) => json_response(StatusCode::OK, &state.trades),
+            (MockRequest::CancelOrder | MockRequest::GetOrderInfo, None) => {
+                json_response(StatusCode::BAD_REQUEST, &ErrorResponse::OrderNotFound)
+            }
+            (MockRequest::CancelOrder, Some(index)) => {
+                match state.cancel_order(index, &mut notifications) {
+                    Ok(cancelled_order) => json_response(StatusCode::OK, &cancelled_order),
+                    Err(error_response) => json_response(StatusCode::BAD_REQUEST, &error_response),
+                }
+            }
+            (MockRequest::GetOrderInfo, Some(index)) => {
+                json_response(StatusCode::OK, &state.orders[index])
+            }
+        };
+
+        if error == Some(MockError::ParsingError) {
+            // exchange has processed the request, but its response and notifications are lost
+            return Ok((create_response(StatusCode::OK, MALFORMED_BODY), latency));
+        }
+
+        if error == Some(MockError::Timeout { is_processed: true }) {
+            return Ok((
+                json_response(StatusCode::GATEWAY_TIMEOUT, &ErrorResponse::Timeout),
+                latency,
+            ));
+        }
+
+        self.notify(&mut state, notifications);
+        Ok((response, latency))
+    }
+
+    fn notify(&self, state: &mut ServerState, notifications: Vec<Notification>) {
+        let deliver_at = Instant::now() + state.notification_latency;
+        for notification in notifications {
+            state.last_sequence += 1;
+            if state.notifications_to_lose > 0 {
+                state.notifications_to_lose -= 1;
+                continue;
+            }
+
+            let notification = SequencedNotification {
+                sequence: state.last_sequence,
+                notification,
+            };
+            let message = serde_json::to_string(&notification)
+                .expect("Unable to serialize mock exchange notification");
+            let _ = self.notifications.send((deliver_at, message));
+        }
+    }
+
+    async fn deliver_notifications(
+        weak_server: Weak<Self>,
+        mut notifications: mpsc::UnboundedReceiver<(Instant, String)>,
+    ) {
+        while let Some((deliver_at, message)) = notifications.recv().await {
+            sleep_until(deliver_at).await;
+
+            let server = match weak_server.upgrade() {
+                Some(server) => server,
+                None => return,
+            };
+            server
+                .state
+                .lock()
+                .connections
+                .retain(|connection| connection.send(message.clone()).is_ok());
+        }
+    }
+
+    async fn serve_websocket(&self, stream: TcpStream) -> Result<()> {
+        let mut websocket = tokio_tungstenite::accept_async(stream).await?;
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        self.state.lock().connections.push(tx);
+        let _ = self.is_connected.send_replace(true);
+
+        loop {
+            tokio::select! {
+                message = rx.recv() => match message {
+                    Some(message) => websocket.send(Message::Text(message)).await?,
+                    // connection is dropped by disconnect()
+                    None => break,
+                },
+                message = websocket.next() => match message {
+                    Some(Ok(Message::Close(_))) | None => return Ok(()),
+                    Some(Err(err)) => return Err(err.into()),
+                    Some(Ok(Message::Text(message))) => {
+                        self.state.lock().received_messages.push(message)
+                    }
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+
+        websocket.close(None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MockExchange for MockExchangeServer {
+    async fn wait_connected(&self) -> Result<()> {
+        let mut is_connected = self.is_connected.subscribe();
+        while !*is_connected.borrow_and_update() {
+            is_connected.changed().await?;
+        }
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        let _ = self.is_connected.send_replace(false);
+        self.state.lock().connections.clear();
+        Ok(())
+    }
+
+    async fn fill(
+        &self,
+        exchange_order_id: &ExchangeOrderId,
+        amount: Amount,
+        price: Price,
+        notify: bool,
+    ) -> Result<()> {
+        let mut state = self.state.lock();
+        let index = state
+            .find_order(exchange_order_id)
+            .with_context(|| format!("Order {exchange_order_id} not found"))?;
+
+        let mut notifications = Vec::new();
+        state.fill(index, amount, price, notify, &mut notifications)?;
+        self.notify(&mut state, notifications);
+
+        Ok(())
+    }
+}
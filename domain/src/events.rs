@@ -11,9 +11,12 @@ use serde::{de, Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 use tokio::sync::broadcast;
 
+use crate::exchanges::symbol::SymbolStatus;
 use crate::market::{CurrencyCode, CurrencyPair, ExchangeAccountId};
+use crate::order::basket::BasketEvent;
 use crate::order::event::OrderEvent;
-use crate::order::snapshot::{Amount, OrderSide, OrderStatus, Price};
+use crate::order::group::OrderGroupEvent;
+use crate::order::snapshot::{Amount, ExchangeOrderId, OrderSide, OrderStatus, Price};
 use crate::order_book::event::OrderBookEvent;
 use crate::position::DerivativePosition;
 
@@ -212,6 +215,98 @@ pub struct TradesEvent {
 
 impl_event!(TradesEvent, "trades_events");
 
+/// Trading was halted by kill switch: all orders are canceled and new ones can't be created
+#[derive(Debug, Clone, Serialize)]
+pub struct TradingHaltedEvent {
+    pub reason: String,
+    pub is_positions_flattened: bool,
+    pub time: DateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ConnectivityState {
+    /// Websocket connections are opened, authenticated and all channels are subscribed
+    Connected,
+    /// Websocket connections are opened, but authentication or resubscription failed,
+    /// so some streams may be missing
+    Degraded,
+    /// Websocket connection is lost, reconnection is in progress
+    Disconnected,
+}
+
+/// Websocket connectivity of exchange account was changed. Strategies may pause quoting until
+/// state is `Connected` again
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectivityEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub state: ConnectivityState,
+    pub time: DateTime,
+}
+
+/// Data stream was silent for too long, so its data is considered frozen and websocket is
+/// reconnected
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamStaleEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    /// Currency pair of silent order book stream. `None` if whole websocket connection is silent
+    pub currency_pair: Option<CurrencyPair>,
+    pub last_update_time: DateTime,
+    pub time: DateTime,
+}
+
+/// Trading state of symbol was changed on exchange. Orders can be created only for symbols in
+/// `Trading` state
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolStatusEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub status: SymbolStatus,
+    pub time: DateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MarketDataSuspectReason {
+    /// Top bid isn't lower than top ask
+    CrossedBook,
+    /// Some price level has zero or negative price
+    NonPositivePrice,
+    /// Order book isn't updated for too long
+    StaleBook,
+}
+
+/// Order book of market looks invalid, so orders referencing it aren't placed until valid data
+/// is received
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketDataSuspectEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub reason: MarketDataSuspectReason,
+    pub last_update_time: DateTime,
+    pub time: DateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum UnmatchedEventType {
+    Fill {
+        trade_id: Option<TradeId>,
+        price: Price,
+        /// Amount of the fill or total filled amount of order if exchange sends only it
+        amount: Amount,
+    },
+    Cancel,
+}
+
+/// Order event of exchange which isn't matched to any known order yet, e.g. fill of order which
+/// was created outside of the engine
+#[derive(Debug, Clone, Serialize)]
+pub struct UnmatchedExchangeEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub exchange_order_id: ExchangeOrderId,
+    pub event_type: UnmatchedEventType,
+    pub received_time: DateTime,
+    pub rematch_attempts: u32,
+}
+
 #[derive(Debug, Clone)]
 pub enum ExchangeEvent {
     OrderBookEvent(OrderBookEvent),
@@ -219,6 +314,13 @@ pub enum ExchangeEvent {
     BalanceUpdate(BalanceUpdateEvent),
     LiquidationPrice(LiquidationPriceEvent),
     Trades(TradesEvent),
+    TradingHalted(TradingHaltedEvent),
+    OrderGroupEvent(OrderGroupEvent),
+    BasketEvent(BasketEvent),
+    Connectivity(ConnectivityEvent),
+    StreamStale(StreamStaleEvent),
+    SymbolStatus(SymbolStatusEvent),
+    MarketDataSuspect(MarketDataSuspectEvent),
 }
 
 pub struct ExchangeEvents {
@@ -233,6 +335,13 @@ impl ExchangeEvents {
     pub fn get_events_channel(&self) -> broadcast::Receiver<ExchangeEvent> {
         self.events_sender.subscribe()
     }
+
+    pub fn send(
+        &self,
+        event: ExchangeEvent,
+    ) -> Result<usize, broadcast::error::SendError<ExchangeEvent>> {
+        self.events_sender.send(event)
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Copy)]
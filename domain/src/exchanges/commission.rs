@@ -1,5 +1,7 @@
+use crate::market::CurrencyCode;
 use crate::order::snapshot::OrderRole;
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 
 pub type Percent = Decimal;
 
@@ -36,3 +38,134 @@ impl Commission {
         }
     }
 }
+
+/// Fees of exchange account which trading volume within fee schedule period (usually 30 days)
+/// reached `min_volume`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeTier {
+    pub min_volume: Decimal,
+    pub maker_fee: Percent,
+    pub taker_fee: Percent,
+}
+
+/// Discount of fees paid by exchange token, e.g. BNB on Binance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeTokenDiscount {
+    pub currency_code: CurrencyCode,
+    /// Discount in percents of fee
+    pub discount: Percent,
+}
+
+/// Maker and taker fees of exchange account by tiers of trading volume
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    pub tiers: Vec<FeeTier>,
+    /// Fees are paid by exchange token with discount if it is set
+    pub fee_token_discount: Option<FeeTokenDiscount>,
+    #[serde(default)]
+    pub referral_reward: Percent,
+}
+
+impl FeeSchedule {
+    /// Schedule with the same fees for any trading volume
+    pub fn flat(maker_fee: Percent, taker_fee: Percent) -> Self {
+        Self {
+            tiers: vec![FeeTier {
+                min_volume: Decimal::ZERO,
+                maker_fee,
+                taker_fee,
+            }],
+            fee_token_discount: None,
+            referral_reward: Decimal::ZERO,
+        }
+    }
+
+    /// The highest tier reached by trading volume. The lowest tier is used if volume doesn't
+    /// reach any of them
+    pub fn tier(&self, volume: Decimal) -> Option<&FeeTier> {
+        self.tiers
+            .iter()
+            .filter(|x| x.min_volume <= volume)
+            .max_by_key(|x| x.min_volume)
+            .or_else(|| self.tiers.iter().min_by_key(|x| x.min_volume))
+    }
+
+    /// Fees of tier reached by trading volume with exchange token discount
+    pub fn commission(&self, volume: Decimal) -> Commission {
+        let (maker_fee, taker_fee) = self
+            .tier(volume)
+            .map_or((Decimal::ZERO, Decimal::ZERO), |x| {
+                (x.maker_fee, x.taker_fee)
+            });
+
+        self.apply_discount(Commission::new(
+            CommissionForType::new(maker_fee, self.referral_reward),
+            CommissionForType::new(taker_fee, self.referral_reward),
+        ))
+    }
+
+    /// Applies exchange token discount to fees, e.g. to fees reported by exchange.
+    /// Rebates (negative fees) aren't changed
+    pub fn apply_discount(&self, mut commission: Commission) -> Commission {
+        if let Some(discount) = &self.fee_token_discount {
+            let multiplier = Decimal::ONE - discount.discount / Decimal::ONE_HUNDRED;
+            for commission in [&mut commission.maker, &mut commission.taker] {
+                if commission.fee.is_sign_positive() {
+                    commission.fee *= multiplier;
+                }
+            }
+        }
+
+        commission
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn schedule() -> FeeSchedule {
+        let tier = |min_volume, maker_fee, taker_fee| FeeTier {
+            min_volume,
+            maker_fee,
+            taker_fee,
+        };
+
+        FeeSchedule {
+            tiers: vec![
+                tier(dec!(1_000_000), dec!(0.09), dec!(0.1)),
+                tier(dec!(0), dec!(0.1), dec!(0.1)),
+                tier(dec!(5_000_000), dec!(-0.01), dec!(0.08)),
+            ],
+            fee_token_discount: Some(FeeTokenDiscount {
+                currency_code: "bnb".into(),
+                discount: dec!(25),
+            }),
+            referral_reward: dec!(0),
+        }
+    }
+
+    #[test]
+    fn fees_are_chosen_by_trading_volume_tier() {
+        let schedule = schedule();
+
+        assert_eq!(schedule.tier(dec!(10)).map(|x| x.min_volume), Some(dec!(0)));
+        assert_eq!(
+            schedule.tier(dec!(1_000_000)).map(|x| x.min_volume),
+            Some(dec!(1_000_000))
+        );
+        assert_eq!(
+            schedule.tier(dec!(7_000_000)).map(|x| x.min_volume),
+            Some(dec!(5_000_000))
+        );
+    }
+
+    #[test]
+    fn fee_token_discount_is_applied_except_rebates() {
+        let commission = schedule().commission(dec!(6_000_000));
+
+        assert_eq!(commission.maker.fee, dec!(-0.01));
+        assert_eq!(commission.taker.fee, dec!(0.06));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod commission;
 pub mod symbol;
+pub mod system_status;
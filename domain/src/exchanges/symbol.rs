@@ -4,10 +4,11 @@ use crate::market::{powi, CurrencyCode, CurrencyId, CurrencyPair};
 use crate::order::snapshot::OrderSide;
 use crate::order::snapshot::{Amount, Price};
 use anyhow::{Context, Result};
+use mmb_utils::DateTime;
 use rust_decimal::Decimal;
 use rust_decimal::MathematicalOps;
 use rust_decimal_macros::dec;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 pub enum Round {
     Floor,
@@ -27,7 +28,7 @@ pub enum BeforeAfter {
 /// ```ignore
 /// Precision::ByTick { tick: dec!(0.001) } // for AmountPrecision = 3 equal pow(0.1, 3)
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum Precision {
     /// Rounding is performed to a number divisible to the specified tick
     /// Look at round_by_tick test below
@@ -53,8 +54,39 @@ impl Precision {
     }
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum OptionType {
+    Call,
+    Put,
+}
+
+/// Terms of derivative contract, needed for strategies which trade expiring instruments
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DerivativeKind {
+    Perpetual,
+    Future {
+        expiration: DateTime,
+    },
+    Option {
+        expiration: DateTime,
+        strike: Price,
+        option_type: OptionType,
+    },
+}
+
+/// Trading state of symbol on exchange
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum SymbolStatus {
+    #[default]
+    Trading,
+    /// Trading is suspended by exchange temporarily
+    Halted,
+    /// Symbol disappeared from exchange symbols list
+    Delisted,
+}
+
 /// Metadata for a currency pair
-#[derive(Debug, Clone, Eq, Serialize)]
+#[derive(Debug, Clone, Eq, Serialize, Deserialize)]
 pub struct Symbol {
     pub is_derivative: bool,
     pub base_currency_id: CurrencyId,
@@ -76,6 +108,12 @@ pub struct Symbol {
 
     pub price_precision: Precision,
     pub amount_precision: Precision,
+
+    /// Set only by exchanges which trade several kinds of derivatives
+    #[serde(default)]
+    pub derivative_kind: Option<DerivativeKind>,
+    #[serde(default)]
+    pub status: SymbolStatus,
 }
 
 impl Symbol {
@@ -120,6 +158,8 @@ impl Symbol {
             amount_multiplier: dec!(1),
             price_precision,
             amount_precision,
+            derivative_kind: None,
+            status: SymbolStatus::Trading,
         }
     }
 
@@ -144,6 +184,25 @@ impl Symbol {
         self.is_derivative
     }
 
+    pub fn is_trading(&self) -> bool {
+        self.status == SymbolStatus::Trading
+    }
+
+    pub fn expiration(&self) -> Option<DateTime> {
+        match self.derivative_kind {
+            Some(DerivativeKind::Future { expiration })
+            | Some(DerivativeKind::Option { expiration, .. }) => Some(expiration),
+            Some(DerivativeKind::Perpetual) | None => None,
+        }
+    }
+
+    pub fn strike(&self) -> Option<Price> {
+        match self.derivative_kind {
+            Some(DerivativeKind::Option { strike, .. }) => Some(strike),
+            _ => None,
+        }
+    }
+
     pub fn price_round(&self, price: Price, round: Round) -> Price {
         match self.price_precision {
             Precision::ByTick { tick } => Self::round_by_tick(price, tick, round),
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// Operational status of exchange declared by exchange itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SystemStatus {
+    Normal,
+    /// Orders can be rejected or left unprocessed by exchange, balances can be stale
+    Maintenance {
+        message: String,
+    },
+}
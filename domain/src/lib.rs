@@ -4,3 +4,4 @@ pub mod market;
 pub mod order;
 pub mod order_book;
 pub mod position;
+pub mod transfer;
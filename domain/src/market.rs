@@ -145,7 +145,7 @@ impl MarketId {
 }
 
 /// Exchange account id and currency pair
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub struct MarketAccountId {
     pub exchange_account_id: ExchangeAccountId,
     pub currency_pair: CurrencyPair,
@@ -180,6 +180,51 @@ impl Serialize for MarketAccountId {
     }
 }
 
+impl<'de> Deserialize<'de> for MarketAccountId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // string is produced by `Serialize`, struct form is kept for compatibility
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum MarketAccountIdRepr {
+            Str(String),
+            Struct {
+                exchange_account_id: ExchangeAccountId,
+                currency_pair: CurrencyPair,
+            },
+        }
+
+        match MarketAccountIdRepr::deserialize(deserializer)? {
+            MarketAccountIdRepr::Str(value) => {
+                let (exchange_account_id, currency_pair) =
+                    value.split_once('|').ok_or_else(|| {
+                        de::Error::invalid_value(
+                            de::Unexpected::Str(&value),
+                            &"MarketAccountId as a string with currency pair on the tail that separated by a '|' character",
+                        )
+                    })?;
+                let exchange_account_id = exchange_account_id.parse().map_err(|_| {
+                    de::Error::invalid_value(
+                        de::Unexpected::Str(exchange_account_id),
+                        &"ExchangeAccountId",
+                    )
+                })?;
+
+                Ok(MarketAccountId::new(
+                    exchange_account_id,
+                    CurrencyPair::from_raw(currency_pair),
+                ))
+            }
+            MarketAccountIdRepr::Struct {
+                exchange_account_id,
+                currency_pair,
+            } => Ok(MarketAccountId::new(exchange_account_id, currency_pair)),
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum ExchangeErrorType {
     Unknown,
@@ -311,6 +356,21 @@ mod tests {
             assert_eq!(result, "Binance_1".to_string())
         }
     }
+
+    #[test]
+    fn market_account_id_serde_roundtrip_as_map_key() {
+        let market_account_id = MarketAccountId::new(
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+        );
+        let map = std::collections::HashMap::from([(market_account_id, 1)]);
+
+        let json = serde_json::to_string(&map).expect("in test");
+        let deserialized: std::collections::HashMap<MarketAccountId, i32> =
+            serde_json::from_str(&json).expect("in test");
+
+        assert_eq!(deserialized, map);
+    }
 }
 
 impl CurrencyCode {
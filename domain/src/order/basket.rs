@@ -0,0 +1,36 @@
+use crate::market::ExchangeAccountId;
+use crate::order::snapshot::ClientOrderId;
+use mmb_utils::{impl_u64_id, time::get_atomic_current_secs};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+impl_u64_id!(BasketId);
+
+/// Identification of order submitted as a leg of basket
+#[derive(Debug, Eq, PartialEq, Clone, Serialize)]
+pub struct BasketLeg {
+    pub exchange_account_id: ExchangeAccountId,
+    pub client_order_id: ClientOrderId,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize)]
+pub enum BasketOutcome {
+    /// All legs were placed within deadline
+    Placed,
+    /// Some leg wasn't placed within deadline, so already placed legs were cancelled
+    Aborted {
+        failed_leg: BasketLeg,
+        reason: String,
+    },
+}
+
+/// Result of submission of basket reported as single event for all its legs
+#[derive(Debug, Clone, Serialize)]
+pub struct BasketEvent {
+    pub basket_id: BasketId,
+    pub legs: Vec<BasketLeg>,
+    pub outcome: BasketOutcome,
+}
@@ -9,10 +9,24 @@ use crate::order::snapshot::OrderSnapshot;
 pub enum OrderEventType {
     CreateOrderSucceeded,
     CreateOrderFailed,
-    OrderFilled { cloned_order: Arc<OrderSnapshot> },
-    OrderCompleted { cloned_order: Arc<OrderSnapshot> },
+    /// Order was rejected locally by pre-trade risk checks and wasn't sent to exchange
+    RejectedByRisk,
+    /// Order was rejected locally because of insufficient balance and wasn't sent to exchange
+    RejectedByBalance,
+    /// Order was rejected locally because its price or amount violates trading rules of symbol
+    /// and wasn't sent to exchange
+    RejectedByValidation,
+    OrderFilled {
+        cloned_order: Arc<OrderSnapshot>,
+    },
+    OrderCompleted {
+        cloned_order: Arc<OrderSnapshot>,
+    },
     CancelOrderSucceeded,
     CancelOrderFailed,
+    /// Order was cancelled by engine because its local time-to-live is over. Raised after
+    /// `CancelOrderSucceeded` of the order
+    OrderExpired,
 }
 
 #[derive(Debug, Clone)]
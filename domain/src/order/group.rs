@@ -0,0 +1,51 @@
+use crate::market::ExchangeAccountId;
+use crate::order::snapshot::ClientOrderId;
+use mmb_utils::{impl_str_id, impl_u64_id, time::get_atomic_current_secs};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use smallstr::SmallString;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+impl_u64_id!(OrderGroupId);
+// id of order list assigned by exchange
+impl_str_id!(ExchangeOrderGroupId);
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash)]
+pub enum OrderGroupType {
+    /// One-cancels-the-other: fill of any leg cancels the rest legs
+    Oco,
+}
+
+/// How legs of order group are linked together
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub enum OrderGroupRoute {
+    /// Group was created through conditional/OCO endpoint of exchange, so legs are linked on server side
+    Native {
+        exchange_group_id: ExchangeOrderGroupId,
+    },
+    /// Legs were created as independent orders and are linked by engine
+    Local,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum OrderGroupEventType {
+    Created,
+    LegFilled {
+        client_order_id: ClientOrderId,
+    },
+    /// All legs are finished and at least one of them was filled
+    Completed,
+    /// All legs are finished without any fills
+    Canceled,
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderGroupEvent {
+    pub exchange_account_id: ExchangeAccountId,
+    pub group_id: OrderGroupId,
+    pub group_type: OrderGroupType,
+    pub route: OrderGroupRoute,
+    pub event_type: OrderGroupEventType,
+}
@@ -0,0 +1,209 @@
+use chrono::Utc;
+use mmb_database::impl_event;
+use mmb_utils::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::market::{CurrencyPair, ExchangeAccountId};
+use crate::order::event::{OrderEvent, OrderEventType};
+use crate::order::fill::OrderFill;
+use crate::order::snapshot::{
+    Amount, ClientOrderId, ExchangeOrderId, OrderSide, OrderSnapshot, OrderStatus, OrderType, Price,
+};
+
+/// Kind of order change saved to order history
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum OrderChangeType {
+    CreateOrderSucceeded,
+    CreateOrderFailed,
+    RejectedByRisk,
+    RejectedByBalance,
+    RejectedByValidation,
+    OrderFilled,
+    OrderCompleted,
+    CancelOrderSucceeded,
+    CancelOrderFailed,
+    OrderExpired,
+}
+
+impl From<&OrderEventType> for OrderChangeType {
+    fn from(event_type: &OrderEventType) -> Self {
+        match event_type {
+            OrderEventType::CreateOrderSucceeded => OrderChangeType::CreateOrderSucceeded,
+            OrderEventType::CreateOrderFailed => OrderChangeType::CreateOrderFailed,
+            OrderEventType::RejectedByRisk => OrderChangeType::RejectedByRisk,
+            OrderEventType::RejectedByBalance => OrderChangeType::RejectedByBalance,
+            OrderEventType::RejectedByValidation => OrderChangeType::RejectedByValidation,
+            OrderEventType::OrderFilled { .. } => OrderChangeType::OrderFilled,
+            OrderEventType::OrderCompleted { .. } => OrderChangeType::OrderCompleted,
+            OrderEventType::CancelOrderSucceeded => OrderChangeType::CancelOrderSucceeded,
+            OrderEventType::CancelOrderFailed => OrderChangeType::CancelOrderFailed,
+            OrderEventType::OrderExpired => OrderChangeType::OrderExpired,
+        }
+    }
+}
+
+/// Order state after its change. Saved for every order event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderStateTransition {
+    pub client_order_id: ClientOrderId,
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub price: Option<Price>,
+    pub amount: Amount,
+    pub filled_amount: Amount,
+    pub change_type: OrderChangeType,
+    pub status: OrderStatus,
+    pub strategy_name: String,
+    pub time: DateTime,
+}
+
+impl_event!(OrderStateTransition, "order_state_transitions");
+
+/// Single fill of order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderFillRecord {
+    pub client_order_id: ClientOrderId,
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub strategy_name: String,
+    pub fill: OrderFill,
+}
+
+impl_event!(OrderFillRecord, "order_fills");
+
+/// Builds order history records from order event: state transition and fill for `OrderFilled` event
+pub fn order_history_records(
+    order_event: &OrderEvent,
+) -> (OrderStateTransition, Option<OrderFillRecord>) {
+    let change_type = OrderChangeType::from(&order_event.event_type);
+
+    match &order_event.event_type {
+        OrderEventType::OrderFilled { cloned_order } => (
+            state_transition(cloned_order, change_type),
+            fill_record(cloned_order),
+        ),
+        OrderEventType::OrderCompleted { cloned_order } => {
+            (state_transition(cloned_order, change_type), None)
+        }
+        _ => (
+            state_transition(&order_event.order.deep_clone(), change_type),
+            None,
+        ),
+    }
+}
+
+fn state_transition(order: &OrderSnapshot, change_type: OrderChangeType) -> OrderStateTransition {
+    OrderStateTransition {
+        client_order_id: order.header.client_order_id.clone(),
+        exchange_order_id: order.props.exchange_order_id.clone(),
+        exchange_account_id: order.header.exchange_account_id,
+        currency_pair: order.header.currency_pair,
+        side: order.header.side,
+        order_type: order.header.order_type,
+        price: order.header.source_price,
+        amount: order.header.amount,
+        filled_amount: order.fills.filled_amount,
+        change_type,
+        status: order.props.status,
+        strategy_name: order.header.strategy_name.clone(),
+        time: order
+            .status_history
+            .last_change_time()
+            .max(order.fills.last_fill_received_time())
+            .unwrap_or_else(Utc::now),
+    }
+}
+
+fn fill_record(order: &OrderSnapshot) -> Option<OrderFillRecord> {
+    // every `OrderFilled` event is raised right after adding of a single fill
+    order.fills.fills.last().map(|fill| OrderFillRecord {
+        client_order_id: order.header.client_order_id.clone(),
+        exchange_order_id: order.props.exchange_order_id.clone(),
+        exchange_account_id: order.header.exchange_account_id,
+        currency_pair: order.header.currency_pair,
+        side: order.header.side,
+        strategy_name: order.header.strategy_name.clone(),
+        fill: fill.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::fill::OrderFillType;
+    use crate::order::pool::OrdersPool;
+    use crate::order::snapshot::{OrderFillRole, OrderOptions};
+    use rust_decimal_macros::dec;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    #[test]
+    fn filled_event_produces_transition_and_fill() {
+        let snapshot = OrderSnapshot::with_params(
+            ClientOrderId::unique_id(),
+            OrderOptions::limit(dec!(10)),
+            None,
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("PHB".into(), "BTC".into()),
+            dec!(2),
+            OrderSide::Buy,
+            None,
+            "StrategyInUnitTests",
+        );
+        let order = OrdersPool::new().add_snapshot_initial(&snapshot);
+
+        let fill_time = Utc::now();
+        order.fn_mut(|x| {
+            x.set_status(OrderStatus::Created, fill_time);
+            x.add_fill(OrderFill::new(
+                Uuid::new_v4(),
+                None,
+                fill_time,
+                OrderFillType::UserTrade,
+                None,
+                dec!(10),
+                dec!(1),
+                dec!(10),
+                OrderFillRole::Maker,
+                "BTC".into(),
+                dec!(0.01),
+                dec!(0),
+                "BTC".into(),
+                dec!(0.01),
+                dec!(0.01),
+                false,
+                None,
+                Some(OrderSide::Buy),
+            ));
+        });
+
+        let cloned_order = Arc::new(order.deep_clone());
+        let (transition, fill) = order_history_records(&OrderEvent::new(
+            order.clone(),
+            OrderEventType::OrderFilled { cloned_order },
+        ));
+
+        assert_eq!(transition.change_type, OrderChangeType::OrderFilled);
+        assert_eq!(transition.status, OrderStatus::Created);
+        assert_eq!(transition.filled_amount, dec!(1));
+        assert_eq!(transition.time, fill_time);
+        let fill = fill.expect("in test");
+        assert_eq!(fill.client_order_id, order.client_order_id());
+        assert_eq!(fill.fill.amount(), dec!(1));
+
+        let (transition, fill) = order_history_records(&OrderEvent::new(
+            order,
+            OrderEventType::CancelOrderSucceeded,
+        ));
+        assert_eq!(
+            transition.change_type,
+            OrderChangeType::CancelOrderSucceeded
+        );
+        assert!(fill.is_none());
+    }
+}
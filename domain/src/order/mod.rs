@@ -1,4 +1,7 @@
+pub mod basket;
 pub mod event;
 pub mod fill;
+pub mod group;
+pub mod history;
 pub mod pool;
 pub mod snapshot;
@@ -11,6 +11,7 @@ use mmb_utils::DateTime;
 use parking_lot::RwLock;
 use std::borrow::{Borrow, BorrowMut};
 use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
 use std::sync::Arc;
 
 pub struct OrderRefData {
@@ -133,12 +134,111 @@ impl OrderRef {
         })
     }
 
+    /// Overwrites `snapshot` by current state of order. Allocated memory of fills and status
+    /// history of `snapshot` is reused
+    pub fn deep_clone_into(&self, snapshot: &mut OrderSnapshot) {
+        self.fn_ref(|order| {
+            snapshot.header.clone_from(self.header());
+            snapshot.props.clone_from(&order.props);
+            snapshot.fills.fills.clone_from(&order.fills.fills);
+            snapshot.fills.filled_amount = order.fills.filled_amount;
+            snapshot.status_history.clone_from(&order.status_history);
+            snapshot.internal_props.clone_from(&order.internal_props);
+            snapshot.extension_data.clone_from(&order.extension_data);
+        })
+    }
+
     pub fn filled_amount(&self) -> Amount {
         self.fn_ref(|order| order.filled_amount())
     }
     pub fn get_fills(&self) -> (Vec<OrderFill>, Amount) {
         self.fn_ref(|order| (order.fills.fills.clone(), order.fills.filled_amount))
     }
+
+    /// Same as `get_fills` but fills are copied into `fills` reusing its allocated memory
+    pub fn get_fills_into(&self, fills: &mut Vec<OrderFill>) -> Amount {
+        self.fn_ref(|order| {
+            fills.clone_from(&order.fills.fills);
+            order.fills.filled_amount
+        })
+    }
+
+    /// Lock order for read and provide view of whole order state without copying it
+    pub fn view<T: 'static>(&self, f: impl FnOnce(&OrderView) -> T) -> T {
+        self.fn_ref(|data| {
+            f(&OrderView {
+                header: self.header(),
+                data,
+            })
+        })
+    }
+}
+
+/// Read-only view of order which is valid while order is locked for read
+pub struct OrderView<'a> {
+    pub header: &'a OrderHeader,
+    pub data: &'a OrderMut,
+}
+
+impl OrderView<'_> {
+    pub fn status(&self) -> OrderStatus {
+        self.data.status()
+    }
+    pub fn filled_amount(&self) -> Amount {
+        self.data.filled_amount()
+    }
+    pub fn remaining_amount(&self) -> Amount {
+        self.header.amount - self.data.filled_amount()
+    }
+    pub fn exchange_order_id(&self) -> Option<ExchangeOrderId> {
+        self.data.exchange_order_id()
+    }
+}
+
+/// Conditions of orders selection. Conditions which aren't specified match any order
+#[derive(Debug, Clone, Default)]
+pub struct OrdersFilter {
+    pub exchange_account_id: Option<ExchangeAccountId>,
+    pub currency_pair: Option<CurrencyPair>,
+    pub side: Option<OrderSide>,
+    pub strategy_name: Option<String>,
+    /// Empty list matches any status
+    pub statuses: Vec<OrderStatus>,
+    /// Bounds are inclusive. Orders without price match only if price bounds aren't specified
+    pub min_price: Option<Price>,
+    pub max_price: Option<Price>,
+    pub min_amount: Option<Amount>,
+    pub max_amount: Option<Amount>,
+}
+
+impl OrdersFilter {
+    /// Checks conditions of immutable order properties, so it doesn't need lock of order
+    fn matches_header(&self, header: &OrderHeader) -> bool {
+        fn in_bounds<T: PartialOrd>(value: T, min: Option<T>, max: Option<T>) -> bool {
+            min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+        }
+
+        let price_matches = match header.source_price {
+            Some(price) => in_bounds(price, self.min_price, self.max_price),
+            None => self.min_price.is_none() && self.max_price.is_none(),
+        };
+
+        self.exchange_account_id
+            .is_none_or(|x| x == header.exchange_account_id)
+            && self.currency_pair.is_none_or(|x| x == header.currency_pair)
+            && self.side.is_none_or(|x| x == header.side)
+            && self
+                .strategy_name
+                .as_ref()
+                .is_none_or(|x| *x == header.strategy_name)
+            && price_matches
+            && in_bounds(header.amount, self.min_amount, self.max_amount)
+    }
+
+    pub fn matches(&self, view: &OrderView) -> bool {
+        self.matches_header(view.header)
+            && (self.statuses.is_empty() || self.statuses.contains(&view.status()))
+    }
 }
 
 #[derive(Debug)]
@@ -160,6 +260,34 @@ impl OrdersPool {
         })
     }
 
+    /// Projects not finished orders matched by `filter` with `f` without cloning whole orders
+    pub fn select_not_finished<T: 'static>(
+        &self,
+        filter: &OrdersFilter,
+        f: impl FnMut(&OrderView) -> T,
+    ) -> Vec<T> {
+        select(&self.not_finished, filter, f)
+    }
+
+    /// Same as `select_not_finished` but over all cached orders including finished ones
+    pub fn select_all<T: 'static>(
+        &self,
+        filter: &OrdersFilter,
+        f: impl FnMut(&OrderView) -> T,
+    ) -> Vec<T> {
+        select(&self.cache_by_client_id, filter, f)
+    }
+
+    /// References to not finished orders matched by `filter`
+    pub fn find_not_finished(&self, filter: &OrdersFilter) -> Vec<OrderRef> {
+        self.not_finished
+            .iter()
+            .filter(|x| filter.matches_header(x.header()))
+            .filter(|x| filter.statuses.is_empty() || filter.statuses.contains(&x.status()))
+            .map(|x| x.value().clone())
+            .collect()
+    }
+
     /// Built `OrderRef` by specified `OrderSnapshot` and Insert it in order pool.
     pub fn add_snapshot_initial(&self, snapshot: &OrderSnapshot) -> OrderRef {
         let client_order_id = snapshot.header.client_order_id.clone();
@@ -210,3 +338,67 @@ impl OrdersPool {
         }
     }
 }
+
+fn select<K: Eq + Hash, T: 'static>(
+    orders: &DashMap<K, OrderRef>,
+    filter: &OrdersFilter,
+    mut f: impl FnMut(&OrderView) -> T,
+) -> Vec<T> {
+    orders
+        .iter()
+        // header is checked before locking of order
+        .filter(|x| filter.matches_header(x.header()))
+        .filter_map(|x| x.view(|view| filter.matches(view).then(|| f(view))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::snapshot::UserOrder;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    fn add_order(pool: &OrdersPool, id: &str, side: OrderSide, price: Price) -> OrderRef {
+        let header = OrderHeader::with_user_order(
+            id.into(),
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("a".into(), "b".into()),
+            side,
+            dec!(1),
+            UserOrder::limit(price),
+            None,
+            None,
+            "strategy".to_string(),
+        );
+        pool.add_simple_initial(&header, Utc::now(), None)
+    }
+
+    #[test]
+    fn orders_are_selected_by_filter() {
+        let pool = OrdersPool::new();
+        let _ = add_order(&pool, "buy_low", OrderSide::Buy, dec!(1));
+        let _ = add_order(&pool, "sell", OrderSide::Sell, dec!(2));
+        add_order(&pool, "buy_high", OrderSide::Buy, dec!(3))
+            .fn_mut(|x| x.set_status(OrderStatus::Created, Utc::now()));
+
+        let filter = OrdersFilter {
+            side: Some(OrderSide::Buy),
+            min_price: Some(dec!(2)),
+            ..Default::default()
+        };
+        let selected = pool.select_not_finished(&filter, |x| x.header.client_order_id.clone());
+        assert_eq!(selected, vec![ClientOrderId::from("buy_high")]);
+
+        let filter = OrdersFilter {
+            statuses: vec![OrderStatus::Created],
+            ..Default::default()
+        };
+        let found = pool.find_not_finished(&filter);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].client_order_id(), "buy_high".into());
+
+        let remaining = pool.select_all(&OrdersFilter::default(), |x| x.remaining_amount());
+        assert_eq!(remaining, vec![dec!(1); 3]);
+    }
+}
@@ -96,12 +96,14 @@ pub enum OrderType {
     Liquidation = 5,
     ClosePosition = 6,
     MissedFill = 7,
+    /// Order placed on exchange outside of engine, e.g. manually in exchange UI
+    Manual = 8,
 }
 
 impl OrderType {
     pub fn is_external_order(&self) -> bool {
         use OrderType::*;
-        matches!(*self, Liquidation | ClosePosition | MissedFill)
+        matches!(*self, Liquidation | ClosePosition | MissedFill | Manual)
     }
 }
 
@@ -109,6 +111,8 @@ impl OrderType {
 pub enum OrderExecutionType {
     None = 0,
     MakerOnly = 1,
+    /// Part of order which isn't filled immediately is canceled
+    ImmediateOrCancel = 2,
 }
 
 impl_str_id!(ClientOrderId);
@@ -201,6 +205,14 @@ impl UserOrder {
             execution_type: OrderExecutionType::MakerOnly,
         }
     }
+
+    /// Limit immediate or cancel order
+    pub fn immediate_or_cancel(price: Price) -> Self {
+        Self::Limit {
+            price,
+            execution_type: OrderExecutionType::ImmediateOrCancel,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -208,6 +220,7 @@ pub enum ExternalOrder {
     Liquidation { price: Price },
     ClosePosition { price: Price },
     MissedFill { price: Price },
+    Manual { price: Price },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -228,6 +241,11 @@ impl OrderOptions {
         Self::User(UserOrder::maker_only(price))
     }
 
+    /// Limit immediate or cancel order
+    pub fn immediate_or_cancel(price: Price) -> Self {
+        Self::User(UserOrder::immediate_or_cancel(price))
+    }
+
     pub fn unknown(price: Option<Price>) -> Self {
         Self::Unknown { price }
     }
@@ -240,12 +258,17 @@ impl OrderOptions {
         Self::External(ExternalOrder::ClosePosition { price })
     }
 
+    pub fn manual(price: Price) -> Self {
+        Self::External(ExternalOrder::Manual { price })
+    }
+
     pub(crate) fn get_source_price(&self) -> Option<Price> {
         match self {
             OrderOptions::User(UserOrder::Limit { price, .. })
             | OrderOptions::External(ExternalOrder::Liquidation { price })
             | OrderOptions::External(ExternalOrder::ClosePosition { price })
-            | OrderOptions::External(ExternalOrder::MissedFill { price }) => Some(*price),
+            | OrderOptions::External(ExternalOrder::MissedFill { price })
+            | OrderOptions::External(ExternalOrder::Manual { price }) => Some(*price),
             OrderOptions::Unknown { price } => *price,
             _ => None,
         }
@@ -261,6 +284,7 @@ impl OrderOptions {
             OrderOptions::External(ExternalOrder::Liquidation { .. }) => OrderType::Liquidation,
             OrderOptions::External(ExternalOrder::ClosePosition { .. }) => OrderType::ClosePosition,
             OrderOptions::External(ExternalOrder::MissedFill { .. }) => OrderType::MissedFill,
+            OrderOptions::External(ExternalOrder::Manual { .. }) => OrderType::Manual,
         }
     }
 
@@ -457,11 +481,34 @@ pub struct OrderStatusChange {
     time: DateTime,
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct OrderStatusHistory {
     status_changes: Vec<OrderStatusChange>,
 }
 
+impl Clone for OrderStatusHistory {
+    fn clone(&self) -> Self {
+        OrderStatusHistory {
+            status_changes: self.status_changes.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.status_changes.clone_from(&source.status_changes);
+    }
+}
+
+impl OrderStatusHistory {
+    pub fn last_change_time(&self) -> Option<DateTime> {
+        self.status_changes.last().map(|x| x.time)
+    }
+
+    /// Statuses of order in order of their setting
+    pub fn statuses(&self) -> impl Iterator<Item = OrderStatus> + '_ {
+        self.status_changes.iter().map(|x| x.status)
+    }
+}
+
 /// Helping properties for trading engine internal use
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct SystemInternalOrderProps {
@@ -474,19 +521,23 @@ pub struct SystemInternalOrderProps {
     pub last_order_cancellation_status_request_time: Option<DateTime>,
     pub last_cancellation_error: Option<ExchangeErrorType>,
 
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub is_canceling_from_wait_cancel_order: bool,
 
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub canceled_not_from_wait_cancel_order: bool,
 
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub was_cancellation_event_raised: bool,
 
     pub last_order_trades_request_time: Option<DateTime>,
 
     pub handled_by_balance_recovery: bool,
     pub filled_amount_after_cancellation: Option<Amount>,
+
+    /// Fingerprints of received fills without trade id, so their replays are skipped
+    #[serde(default)]
+    pub received_fill_fingerprints: Vec<u64>,
 }
 
 /// It may be necessary for an exchange to store specific information for an order.
@@ -56,4 +56,88 @@ impl OrderBookEvent {
     pub fn to_orderbook_snapshot(&self) -> LocalOrderBookSnapshot {
         self.data.to_orderbook_snapshot(self.creation_time)
     }
+
+    /// Merges newer event of the same market into this one, so applying of the result is the same
+    /// as applying of both events one after another
+    pub fn coalesce(&mut self, newer: &OrderBookEvent) {
+        if let EventType::Snapshot = newer.event_type {
+            *self = newer.clone();
+            return;
+        }
+
+        let data = Arc::make_mut(&mut self.data);
+        match self.event_type {
+            EventType::Snapshot => {
+                OrderBookData::apply_update(&mut data.asks, &mut data.bids, &newer.data)
+            }
+            // zero amounts are kept because they remove price levels from snapshot
+            EventType::Update => {
+                data.asks.extend(newer.data.asks.iter());
+                data.bids.extend(newer.data.bids.iter());
+            }
+        }
+        self.creation_time = newer.creation_time;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book_data;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    fn event(event_type: EventType, data: OrderBookData) -> OrderBookEvent {
+        OrderBookEvent::new(
+            Utc::now(),
+            ExchangeAccountId::new("Binance", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            String::new(),
+            event_type,
+            Arc::new(data),
+        )
+    }
+
+    #[test]
+    fn coalesced_updates_are_applied_to_snapshot() {
+        let mut snapshot = event(
+            EventType::Snapshot,
+            order_book_data![
+                dec!(101) => dec!(1),
+                dec!(102) => dec!(2),
+                ;
+                dec!(99) => dec!(1),
+            ],
+        );
+        let mut update = event(
+            EventType::Update,
+            order_book_data![
+                dec!(101) => dec!(0),
+                ;
+                dec!(98) => dec!(3),
+            ],
+        );
+        update.coalesce(&event(
+            EventType::Update,
+            order_book_data![
+                dec!(102) => dec!(5),
+                ;
+                dec!(98) => dec!(0),
+            ],
+        ));
+        assert_eq!(update.data.asks.get(&dec!(101)), Some(&dec!(0)));
+        assert_eq!(update.data.bids.get(&dec!(98)), Some(&dec!(0)));
+
+        snapshot.coalesce(&update);
+
+        assert!(matches!(snapshot.event_type, EventType::Snapshot));
+        assert_eq!(
+            *snapshot.data,
+            order_book_data![
+                dec!(102) => dec!(5),
+                ;
+                dec!(99) => dec!(1),
+            ]
+        );
+    }
 }
@@ -0,0 +1,64 @@
+use crate::market::{CurrencyCode, ExchangeAccountId};
+use crate::order::snapshot::Amount;
+use mmb_utils::{impl_str_id, time::get_atomic_current_secs, DateTime};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use smallstr::SmallString;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// id of internal transfer assigned by exchange
+impl_str_id!(ExchangeTransferId);
+// id of deposit or withdrawal assigned by exchange
+impl_str_id!(ExchangeFundsMovementId);
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash)]
+pub enum TransferStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+/// Internal transfer of currency between accounts of the same exchange
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AccountTransfer {
+    pub from: ExchangeAccountId,
+    pub to: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    pub amount: Amount,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash)]
+pub enum FundsMovementType {
+    Deposit,
+    Withdrawal,
+}
+
+/// Deposit to or withdrawal from account of exchange
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FundsMovement {
+    pub id: ExchangeFundsMovementId,
+    pub movement_type: FundsMovementType,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    pub amount: Amount,
+    /// Fee charged by exchange, it's zero for deposits
+    pub fee: Amount,
+    pub status: TransferStatus,
+    /// Id of blockchain transaction if it's already known
+    pub transaction_id: Option<String>,
+    pub time: DateTime,
+}
+
+/// Withdrawal of currency from account of exchange to external address
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Withdrawal {
+    pub currency_code: CurrencyCode,
+    pub amount: Amount,
+    pub address: String,
+    /// Memo or tag of address for currencies which require it
+    pub address_tag: Option<String>,
+    /// Network of currency, default network of exchange is used if it isn't specified
+    pub network: Option<String>,
+}
@@ -18,10 +18,10 @@
 
 use anyhow::Result;
 use binance::binance::BinanceBuilder;
-use itertools::Itertools;
 use mmb_core::config::{CONFIG_PATH, CREDENTIALS_PATH};
 use mmb_core::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
 use mmb_core::lifecycle::launcher::{launch_trading_engine, EngineBuildConfig, InitSettings};
+use mmb_core::lifecycle::validation::{is_validate_mode, validate_and_exit};
 use mmb_core::settings::DispositionStrategySettings;
 use std::env;
 use strategies::example_strategy::{ExampleStrategy, ExampleStrategySettings};
@@ -42,6 +42,10 @@ async fn main() -> Result<()> {
         config_path,
         credentials_path,
     };
+    if is_validate_mode() {
+        validate_and_exit(&engine_config, init_settings).await;
+    }
+
     loop {
         let engine = launch_trading_engine(&engine_config, init_settings.clone()).await?;
 
@@ -52,7 +56,10 @@ async fn main() -> Result<()> {
             settings.strategy.spread,
             settings.strategy.max_amount,
             engine.context(),
-        );
+        )
+        .with_quoting_model(settings.strategy.quoting_model.clone(), &init_settings)
+        .with_quoting_script(settings.strategy.quoting_script.clone())?
+        .with_required_signals(settings.strategy.required_signals.clone());
 
         engine.start_disposition_executor(strategy);
 
@@ -65,7 +72,5 @@ async fn main() -> Result<()> {
 }
 
 fn is_futures_demo() -> bool {
-    let args = env::args().collect_vec();
-
-    2 == args.len() && "--futures" == args[1]
+    env::args().skip(1).any(|x| x == "--futures")
 }
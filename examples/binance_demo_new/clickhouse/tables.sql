@@ -0,0 +1,49 @@
+-- Tables written by `ClickHouseSink`. Decimals are received as JSON strings
+
+-- Every row contains the best levels of local order book after applying of snapshot or update to it
+CREATE TABLE IF NOT EXISTS order_books (
+    time DateTime64(9, 'UTC'),
+    exchange_account_id LowCardinality(String),
+    currency_pair LowCardinality(String),
+    is_snapshot Bool,
+    ask_prices Array(Decimal(38, 18)),
+    ask_amounts Array(Decimal(38, 18)),
+    bid_prices Array(Decimal(38, 18)),
+    bid_amounts Array(Decimal(38, 18))
+)
+ENGINE = MergeTree
+PARTITION BY toYYYYMM(time)
+ORDER BY (exchange_account_id, currency_pair, time);
+
+CREATE TABLE IF NOT EXISTS trades (
+    time DateTime64(9, 'UTC'),
+    receipt_time DateTime64(9, 'UTC'),
+    exchange_account_id LowCardinality(String),
+    currency_pair LowCardinality(String),
+    trade_id String,
+    price Decimal(38, 18),
+    amount Decimal(38, 18),
+    side LowCardinality(String)
+)
+ENGINE = MergeTree
+PARTITION BY toYYYYMM(time)
+ORDER BY (exchange_account_id, currency_pair, time);
+
+CREATE TABLE IF NOT EXISTS fills (
+    time DateTime64(9, 'UTC'),
+    client_order_id String,
+    exchange_order_id Nullable(String),
+    exchange_account_id LowCardinality(String),
+    currency_pair LowCardinality(String),
+    side LowCardinality(String),
+    strategy_name LowCardinality(String),
+    trade_id Nullable(String),
+    price Decimal(38, 18),
+    amount Decimal(38, 18),
+    role LowCardinality(String),
+    commission_currency_code LowCardinality(String),
+    commission_amount Decimal(38, 18)
+)
+ENGINE = MergeTree
+PARTITION BY toYYYYMM(time)
+ORDER BY (exchange_account_id, currency_pair, time);
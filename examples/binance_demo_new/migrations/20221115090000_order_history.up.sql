@@ -0,0 +1,21 @@
+CREATE TABLE order_state_transitions (
+    id bigint PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    insert_time timestamp WITH TIME ZONE NOT NULL DEFAULT now(),
+    version int,
+    json jsonb NOT NULL
+);
+
+CREATE INDEX order_state_transitions__insert_time_idx ON order_state_transitions USING btree (insert_time);
+CREATE INDEX order_state_transitions__client_order_id_idx ON order_state_transitions USING btree (((json ->> 'client_order_id')::text));
+CREATE INDEX order_state_transitions__exchange_account_id_idx ON order_state_transitions USING btree (((json ->> 'exchange_account_id')::text));
+
+CREATE TABLE order_fills (
+    id bigint PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    insert_time timestamp WITH TIME ZONE NOT NULL DEFAULT now(),
+    version int,
+    json jsonb NOT NULL
+);
+
+CREATE INDEX order_fills__insert_time_idx ON order_fills USING btree (insert_time);
+CREATE INDEX order_fills__client_order_id_idx ON order_fills USING btree (((json ->> 'client_order_id')::text));
+CREATE INDEX order_fills__exchange_account_id_idx ON order_fills USING btree (((json ->> 'exchange_account_id')::text));
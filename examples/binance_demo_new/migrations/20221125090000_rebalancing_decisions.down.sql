@@ -0,0 +1 @@
+DROP TABLE rebalancing_decisions;
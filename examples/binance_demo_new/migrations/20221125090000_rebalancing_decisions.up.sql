@@ -0,0 +1,9 @@
+CREATE TABLE rebalancing_decisions (
+    id bigint PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    insert_time timestamp WITH TIME ZONE NOT NULL DEFAULT now(),
+    version int,
+    json jsonb NOT NULL
+);
+
+CREATE INDEX rebalancing_decisions__insert_time_idx ON rebalancing_decisions USING btree (insert_time);
+CREATE INDEX rebalancing_decisions__id_idx ON rebalancing_decisions USING btree (((json ->> 'id')::text));
@@ -0,0 +1,9 @@
+CREATE TABLE archived_orders (
+    id bigint PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    insert_time timestamp WITH TIME ZONE NOT NULL DEFAULT now(),
+    version int,
+    json jsonb NOT NULL
+);
+
+CREATE INDEX archived_orders__insert_time_idx ON archived_orders USING btree (insert_time);
+CREATE INDEX archived_orders__client_order_id_idx ON archived_orders USING btree (((json ->> 'client_order_id')::text));
@@ -25,6 +25,7 @@ use mmb_core::config::{CONFIG_PATH, CREDENTIALS_PATH};
 use mmb_core::infrastructure::{spawn_future, spawn_future_ok};
 use mmb_core::lifecycle::app_lifetime_manager::ActionAfterGracefulShutdown;
 use mmb_core::lifecycle::launcher::{launch_trading_engine, EngineBuildConfig, InitSettings};
+use mmb_core::lifecycle::validation::{is_validate_mode, validate_and_exit};
 use mmb_core::settings::DispositionStrategySettings;
 use mmb_utils::infrastructure::SpawnFutureFlags;
 use strategies::example_strategy::{ExampleStrategy, ExampleStrategySettings};
@@ -40,6 +41,10 @@ async fn main() -> Result<()> {
         config_path: CONFIG_PATH.to_owned(),
         credentials_path: CREDENTIALS_PATH.to_owned(),
     };
+    if is_validate_mode() {
+        validate_and_exit(&engine_config, init_settings).await;
+    }
+
     loop {
         let engine = launch_trading_engine(&engine_config, init_settings.clone()).await?;
 
@@ -0,0 +1,419 @@
+use anyhow::{Context, Result};
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::infrastructure::spawn_by_timer;
+use mmb_core::lifecycle::trading_engine::EngineContext;
+use mmb_domain::exchanges::symbol::{BeforeAfter, Round};
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, OrderHeader, OrderRole, OrderSide, Price, UserOrder,
+};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+const STRATEGY_NAME: &str = "ArbitrageStrategy";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ArbitrageMarketSettings {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ArbitrageStrategySettings {
+    pub first: ArbitrageMarketSettings,
+    pub second: ArbitrageMarketSettings,
+    /// Min profit of arbitrage trade after taker fees of both legs in percents of buy price
+    pub min_edge_percent: Decimal,
+    /// Max amount of single arbitrage trade
+    pub max_amount: Amount,
+    /// Interval between checks of order book tops
+    pub check_interval_millis: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Opportunity {
+    buy: ArbitrageMarketSettings,
+    sell: ArbitrageMarketSettings,
+    buy_price: Price,
+    sell_price: Price,
+    amount: Amount,
+    edge_percent: Decimal,
+}
+
+/// Buys on the trade place where ask is lower and simultaneously sells on the other one when
+/// its bid is higher by more than fees. Both legs are immediate or cancel orders, so one of
+/// them may be filled partially or not filled at all. Such legged trades are tracked by
+/// `StatisticService` and aren't closed automatically
+pub struct ArbitrageStrategy {
+    settings: ArbitrageStrategySettings,
+    engine_context: Arc<EngineContext>,
+}
+
+impl ArbitrageStrategy {
+    pub fn new(
+        settings: ArbitrageStrategySettings,
+        engine_context: Arc<EngineContext>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            settings,
+            engine_context,
+        })
+    }
+
+    pub fn start(self: &Arc<Self>) {
+        let interval = Duration::from_millis(self.settings.check_interval_millis.max(1));
+        let strategy = self.clone();
+        spawn_by_timer(
+            STRATEGY_NAME,
+            Duration::ZERO,
+            interval,
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            move || {
+                let strategy = strategy.clone();
+                async move {
+                    if let Err(err) = strategy.check().await {
+                        log::error!("{STRATEGY_NAME} failed: {err:?}");
+                    }
+                }
+            },
+        );
+    }
+
+    async fn check(&self) -> Result<()> {
+        let first = self.settings.first;
+        let second = self.settings.second;
+        let opportunity = match self.find_opportunity(first, second)? {
+            Some(opportunity) => Some(opportunity),
+            None => self.find_opportunity(second, first)?,
+        };
+
+        match opportunity {
+            Some(opportunity) => self.execute(opportunity).await,
+            None => Ok(()),
+        }
+    }
+
+    fn find_opportunity(
+        &self,
+        buy: ArbitrageMarketSettings,
+        sell: ArbitrageMarketSettings,
+    ) -> Result<Option<Opportunity>> {
+        let buy_exchange = self.exchange(buy.exchange_account_id)?;
+        let sell_exchange = self.exchange(sell.exchange_account_id)?;
+
+        let (buy_price, buy_top_amount) =
+            match top(&buy_exchange, buy.currency_pair, OrderSide::Sell) {
+                Some(level) => level,
+                None => return Ok(None),
+            };
+        let (sell_price, sell_top_amount) =
+            match top(&sell_exchange, sell.currency_pair, OrderSide::Buy) {
+                Some(level) => level,
+                None => return Ok(None),
+            };
+
+        let edge_percent = match fee_adjusted_edge_percent(
+            buy_price,
+            buy_exchange
+                .commission()
+                .get_commission(OrderRole::Taker)
+                .fee,
+            sell_price,
+            sell_exchange
+                .commission()
+                .get_commission(OrderRole::Taker)
+                .fee,
+        ) {
+            Some(edge_percent) if edge_percent >= self.settings.min_edge_percent => edge_percent,
+            _ => return Ok(None),
+        };
+
+        let buy_symbol = buy_exchange.get_symbol(buy.currency_pair)?;
+        let sell_symbol = sell_exchange.get_symbol(sell.currency_pair)?;
+
+        // spent currencies of legs
+        let (quote_balance, base_balance) = {
+            let balance_manager = self.engine_context.balance_manager.lock();
+            let quote_balance = balance_manager.get_exchange_balance(
+                buy.exchange_account_id,
+                buy_symbol.clone(),
+                buy_symbol.get_trade_code(OrderSide::Buy, BeforeAfter::Before),
+            );
+            let base_balance = balance_manager.get_exchange_balance(
+                sell.exchange_account_id,
+                sell_symbol.clone(),
+                sell_symbol.get_trade_code(OrderSide::Sell, BeforeAfter::Before),
+            );
+            (
+                quote_balance.unwrap_or_default(),
+                base_balance.unwrap_or_default(),
+            )
+        };
+
+        let amount = trade_amount(
+            self.settings.max_amount,
+            buy_price,
+            buy_top_amount,
+            sell_top_amount,
+            quote_balance,
+            base_balance,
+        );
+        let amount = buy_symbol.amount_round(amount, Round::Floor);
+        let amount = sell_symbol.amount_round(amount, Round::Floor);
+        if amount <= Amount::ZERO {
+            return Ok(None);
+        }
+
+        Ok(Some(Opportunity {
+            buy,
+            sell,
+            buy_price,
+            sell_price,
+            amount,
+            edge_percent,
+        }))
+    }
+
+    async fn execute(&self, opportunity: Opportunity) -> Result<()> {
+        log::info!("{STRATEGY_NAME} executes {opportunity:?}");
+
+        let cancellation_token = self.engine_context.lifetime_manager.stop_token();
+        let (buy_order, sell_order) = futures::join!(
+            self.create_leg(
+                opportunity.buy,
+                OrderSide::Buy,
+                opportunity.buy_price,
+                opportunity.amount,
+                cancellation_token.clone(),
+            ),
+            self.create_leg(
+                opportunity.sell,
+                OrderSide::Sell,
+                opportunity.sell_price,
+                opportunity.amount,
+                cancellation_token.clone(),
+            )
+        );
+
+        let (bought_amount, sold_amount) = futures::join!(
+            self.wait_leg(opportunity.buy, buy_order, cancellation_token.clone()),
+            self.wait_leg(opportunity.sell, sell_order, cancellation_token)
+        );
+
+        if bought_amount != sold_amount {
+            log::warn!(
+                "{STRATEGY_NAME} trade is legged: bought {bought_amount} on {}, sold {sold_amount} on {}",
+                opportunity.buy.exchange_account_id,
+                opportunity.sell.exchange_account_id
+            );
+        }
+
+        self.engine_context
+            .statistic_service
+            .register_arbitrage_trade(STRATEGY_NAME, bought_amount, sold_amount);
+
+        Ok(())
+    }
+
+    async fn create_leg(
+        &self,
+        market: ArbitrageMarketSettings,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        cancellation_token: CancellationToken,
+    ) -> Result<OrderRef> {
+        let header = OrderHeader::with_user_order(
+            ClientOrderId::unique_id(),
+            market.exchange_account_id,
+            market.currency_pair,
+            side,
+            amount,
+            UserOrder::immediate_or_cancel(price),
+            None,
+            None,
+            STRATEGY_NAME.to_owned(),
+        );
+
+        self.exchange(market.exchange_account_id)?
+            .create_order(&header, None, cancellation_token)
+            .await
+    }
+
+    /// Filled amount of the leg after it's finished
+    async fn wait_leg(
+        &self,
+        market: ArbitrageMarketSettings,
+        order: Result<OrderRef>,
+        cancellation_token: CancellationToken,
+    ) -> Amount {
+        let order = match order {
+            Ok(order) => order,
+            Err(err) => {
+                log::error!(
+                    "{STRATEGY_NAME} failed to create leg on {}: {err:?}",
+                    market.exchange_account_id
+                );
+                return Amount::ZERO;
+            }
+        };
+
+        let exchange = match self.exchange(market.exchange_account_id) {
+            Ok(exchange) => exchange,
+            Err(_) => return order.filled_amount(),
+        };
+        if let Err(err) = exchange
+            .wait_order_finish(&order, None, cancellation_token)
+            .await
+        {
+            log::error!(
+                "{STRATEGY_NAME} failed to wait finish of order {}: {err:?}",
+                order.client_order_id()
+            );
+        }
+
+        order.filled_amount()
+    }
+
+    fn exchange(&self, exchange_account_id: ExchangeAccountId) -> Result<Arc<Exchange>> {
+        self.engine_context
+            .exchanges
+            .get(&exchange_account_id)
+            .map(|x| x.value().clone())
+            .with_context(|| format!("Exchange {exchange_account_id} isn't found"))
+    }
+}
+
+/// Price and amount of best bid for `Buy` side and best ask for `Sell` side. Levels with
+/// non-positive price are skipped, because they can come only from broken market data
+fn top(
+    exchange: &Exchange,
+    currency_pair: CurrencyPair,
+    side: OrderSide,
+) -> Option<(Price, Amount)> {
+    let top = exchange.order_book_top.get(&currency_pair)?;
+    let level = match side {
+        OrderSide::Buy => top.bid.as_ref()?,
+        OrderSide::Sell => top.ask.as_ref()?,
+    };
+    (level.price > Price::ZERO).then_some((level.price, level.amount))
+}
+
+/// Profit of buying by `buy_price` and selling by `sell_price` after fees in percents of buy price.
+/// Returns `None` for non-positive prices, because edge of them makes no sense
+fn fee_adjusted_edge_percent(
+    buy_price: Price,
+    buy_fee_percent: Decimal,
+    sell_price: Price,
+    sell_fee_percent: Decimal,
+) -> Option<Decimal> {
+    if buy_price <= Price::ZERO || sell_price <= Price::ZERO {
+        return None;
+    }
+
+    let cost = buy_price * (Decimal::ONE + buy_fee_percent / dec!(100));
+    let proceeds = sell_price * (Decimal::ONE - sell_fee_percent / dec!(100));
+    Some((proceeds - cost) / cost * dec!(100))
+}
+
+/// Amount of both legs limited by order book tops and balances of spent currencies before
+/// rounding by symbols. `buy_price` should be positive
+fn trade_amount(
+    max_amount: Amount,
+    buy_price: Price,
+    buy_top_amount: Amount,
+    sell_top_amount: Amount,
+    quote_balance: Amount,
+    base_balance: Amount,
+) -> Amount {
+    max_amount
+        .min(buy_top_amount)
+        .min(sell_top_amount)
+        .min(quote_balance / buy_price)
+        .min(base_balance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edge_is_adjusted_by_fees_of_both_legs() {
+        // cost 100.1, proceeds 101.898
+        let edge_percent =
+            fee_adjusted_edge_percent(dec!(100), dec!(0.1), dec!(102), dec!(0.1)).expect("in test");
+        assert_eq!(edge_percent.round_dp(6), dec!(1.796204));
+
+        // price difference is eaten by fees
+        let edge_percent = fee_adjusted_edge_percent(dec!(100), dec!(0.5), dec!(100.5), dec!(0.5))
+            .expect("in test");
+        assert!(edge_percent < Decimal::ZERO);
+
+        assert_eq!(
+            fee_adjusted_edge_percent(dec!(100), Decimal::ZERO, dec!(100), Decimal::ZERO),
+            Some(Decimal::ZERO)
+        );
+    }
+
+    #[test]
+    fn edge_is_not_calculated_for_non_positive_prices() {
+        assert_eq!(
+            fee_adjusted_edge_percent(Price::ZERO, dec!(0.1), dec!(102), dec!(0.1)),
+            None
+        );
+        assert_eq!(
+            fee_adjusted_edge_percent(dec!(-1), dec!(0.1), dec!(102), dec!(0.1)),
+            None
+        );
+        assert_eq!(
+            fee_adjusted_edge_percent(dec!(100), dec!(0.1), Price::ZERO, dec!(0.1)),
+            None
+        );
+    }
+
+    #[test]
+    fn trade_amount_is_limited_by_tops_and_balances() {
+        let amount = |max_amount, buy_top_amount, sell_top_amount, quote_balance, base_balance| {
+            trade_amount(
+                max_amount,
+                dec!(100),
+                buy_top_amount,
+                sell_top_amount,
+                quote_balance,
+                base_balance,
+            )
+        };
+
+        assert_eq!(
+            amount(dec!(1), dec!(5), dec!(5), dec!(1000), dec!(5)),
+            dec!(1)
+        );
+        assert_eq!(
+            amount(dec!(5), dec!(2), dec!(5), dec!(1000), dec!(5)),
+            dec!(2)
+        );
+        assert_eq!(
+            amount(dec!(5), dec!(5), dec!(3), dec!(1000), dec!(5)),
+            dec!(3)
+        );
+        // quote balance is enough to buy only 2.5
+        assert_eq!(
+            amount(dec!(5), dec!(5), dec!(5), dec!(250), dec!(5)),
+            dec!(2.5)
+        );
+        assert_eq!(
+            amount(dec!(5), dec!(5), dec!(5), dec!(1000), dec!(0.5)),
+            dec!(0.5)
+        );
+        assert_eq!(
+            amount(dec!(5), dec!(5), dec!(5), dec!(1000), Amount::ZERO),
+            Amount::ZERO
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use mmb_domain::order::snapshot::{Amount, Price};
+use rust_decimal::{Decimal, MathematicalOps};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AvellanedaStoikovSettings {
+    /// Risk aversion (gamma). Higher values skew quotes stronger against inventory
+    pub risk_aversion: Decimal,
+    /// Order book liquidity (k). Probability of order fill decreases as `exp(-k * distance)`
+    /// of distance from middle price
+    pub order_book_liquidity: Decimal,
+    /// Remaining trading horizon (T - t) in candle intervals
+    pub horizon_candles: Decimal,
+    /// Count of last candles used to estimate volatility
+    pub volatility_candles: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quotes {
+    pub reservation_price: Price,
+    pub spread: Price,
+}
+
+impl Quotes {
+    pub fn bid(&self) -> Price {
+        self.reservation_price - self.spread / Decimal::TWO
+    }
+
+    pub fn ask(&self) -> Price {
+        self.reservation_price + self.spread / Decimal::TWO
+    }
+}
+
+/// Reservation price and optimal spread of Avellaneda-Stoikov model for current inventory in
+/// base currency and variance of price changes per candle interval
+pub fn calculate_quotes(
+    settings: &AvellanedaStoikovSettings,
+    middle_price: Price,
+    inventory: Amount,
+    price_variance: Decimal,
+) -> Option<Quotes> {
+    let gamma = settings.risk_aversion;
+    let k = settings.order_book_liquidity;
+    if gamma <= Decimal::ZERO || k <= Decimal::ZERO {
+        return None;
+    }
+
+    let inventory_risk = gamma * price_variance * settings.horizon_candles;
+    let reservation_price = middle_price - inventory * inventory_risk;
+    let spread = inventory_risk + Decimal::TWO / gamma * (Decimal::ONE + gamma / k).ln();
+
+    Some(Quotes {
+        reservation_price,
+        spread,
+    })
+}
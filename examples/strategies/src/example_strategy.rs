@@ -1,28 +1,39 @@
+use crate::avellaneda_stoikov::{calculate_quotes, AvellanedaStoikovSettings};
+use crate::scripted_quoting::{QuotingInputs, QuotingScript, ScriptQuotes};
 use anyhow::Result;
 use itertools::Itertools;
 use mmb_core::balance::manager::balance_manager::BalanceManager;
+use mmb_core::config::try_load_settings;
 use mmb_core::disposition_execution::strategy::DispositionStrategy;
 use mmb_core::disposition_execution::{
     PriceSlot, TradeCycle, TradeDisposition, TradingContext, TradingContextBySide,
 };
 use mmb_core::explanation::{Explanation, WithExplanation};
+use mmb_core::infrastructure::spawn_by_timer;
+use mmb_core::lifecycle::launcher::InitSettings;
 use mmb_core::lifecycle::trading_engine::EngineContext;
 use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
 use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::services::signals::QuotingMode;
 use mmb_core::settings::{CurrencyPairSetting, DispositionStrategySettings};
 use mmb_domain::events::ExchangeEvent;
-use mmb_domain::exchanges::symbol::Round;
+use mmb_domain::exchanges::symbol::{Round, Symbol};
 use mmb_domain::market::CurrencyPair;
 use mmb_domain::market::{ExchangeAccountId, MarketAccountId, MarketId};
-use mmb_domain::order::snapshot::Amount;
+use mmb_domain::order::snapshot::{Amount, Price};
 use mmb_domain::order::snapshot::{OrderRole, OrderSide, OrderSnapshot};
 use mmb_utils::cancellation_token::CancellationToken;
-use mmb_utils::infrastructure::WithExpect;
+use mmb_utils::infrastructure::{SpawnFutureFlags, WithExpect};
 use mmb_utils::DateTime;
+use parking_lot::Mutex;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+
+const QUOTING_MODEL_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct ExampleStrategySettings {
@@ -30,6 +41,17 @@ pub struct ExampleStrategySettings {
     pub currency_pair: CurrencyPairSetting,
     pub max_amount: Decimal,
     pub exchange_account_id: ExchangeAccountId,
+    /// Quoting by Avellaneda-Stoikov model instead of fixed spread
+    #[serde(default)]
+    pub quoting_model: Option<AvellanedaStoikovSettings>,
+    /// Path to Rhai script with quoting rules. It has priority over `quoting_model` and
+    /// fixed spread
+    #[serde(default)]
+    pub quoting_script: Option<PathBuf>,
+    /// Names of signals from `SignalService` required for quoting. Spread is widened while some
+    /// of them is stale and orders are canceled when some of them is missing or stale for too long
+    #[serde(default)]
+    pub required_signals: Vec<String>,
 }
 
 impl DispositionStrategySettings for ExampleStrategySettings {
@@ -61,6 +83,9 @@ pub struct ExampleStrategy {
     engine_context: Arc<EngineContext>,
     configuration_descriptor: ConfigurationDescriptor,
     max_amount: Decimal,
+    quoting_model: Arc<Mutex<Option<AvellanedaStoikovSettings>>>,
+    quoting_script: Option<QuotingScript>,
+    required_signals: Vec<String>,
 }
 
 impl ExampleStrategy {
@@ -102,9 +127,56 @@ impl ExampleStrategy {
             engine_context,
             configuration_descriptor,
             max_amount,
+            quoting_model: Default::default(),
+            quoting_script: None,
+            required_signals: Vec::new(),
         })
     }
 
+    /// Uses Avellaneda-Stoikov quoting model instead of fixed spread. If settings are loaded
+    /// from config file, parameters of the model are reloaded when the file is changed
+    pub fn with_quoting_model(
+        self: Box<Self>,
+        quoting_model: Option<AvellanedaStoikovSettings>,
+        init_settings: &InitSettings<ExampleStrategySettings>,
+    ) -> Box<Self> {
+        *self.quoting_model.lock() = quoting_model;
+
+        if let InitSettings::Load {
+            config_path,
+            credentials_path,
+        } = init_settings.clone()
+        {
+            let quoting_model = self.quoting_model.clone();
+            spawn_by_timer(
+                "Reload quoting model of ExampleStrategy",
+                QUOTING_MODEL_RELOAD_INTERVAL,
+                QUOTING_MODEL_RELOAD_INTERVAL,
+                SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+                move || {
+                    reload_quoting_model(&quoting_model, &config_path, &credentials_path);
+                    async {}
+                },
+            );
+        }
+
+        self
+    }
+
+    /// Uses quoting rules from script instead of quoting model and fixed spread. Script is
+    /// reloaded when its file is changed
+    pub fn with_quoting_script(mut self: Box<Self>, path: Option<PathBuf>) -> Result<Box<Self>> {
+        self.quoting_script = path.map(QuotingScript::load).transpose()?;
+        Ok(self)
+    }
+
+    /// Quoting depends on freshness of the signals. Fresh values of them are passed into
+    /// quoting script
+    pub fn with_required_signals(mut self: Box<Self>, required_signals: Vec<String>) -> Box<Self> {
+        self.required_signals = required_signals;
+        self
+    }
+
     fn strategy_name() -> &'static str {
         "ExampleStrategy"
     }
@@ -117,11 +189,138 @@ impl ExampleStrategy {
         self.market_account_id().market_id()
     }
 
+    fn quoting_model_price(
+        &self,
+        side: OrderSide,
+        top_bid: Price,
+        top_ask: Price,
+        fair_price: Price,
+        symbol: &Symbol,
+        explanation: &mut Explanation,
+    ) -> Option<Price> {
+        let settings = self.quoting_model.lock().clone()?;
+
+        let price_variance = self
+            .engine_context
+            .candle_service
+            .price_variance(self.market_id(), settings.volatility_candles);
+        let Some(price_variance) = price_variance else {
+            explanation.add_reason("Volatility is unknown yet, so fixed spread is used");
+            return None;
+        };
+
+        let inventory = self
+            .engine_context
+            .balance_manager
+            .lock()
+            .net_position(self.target_eai, self.currency_pair);
+        let quotes = calculate_quotes(&settings, fair_price, inventory, price_variance)?;
+        explanation.add_reason(format!(
+            "Avellaneda-Stoikov reservation price {} spread {} by inventory {inventory} and price variance {price_variance}",
+            quotes.reservation_price, quotes.spread
+        ));
+
+        // quotes crossing order book are moved to its top, so orders stay makers
+        let price = match side {
+            OrderSide::Buy => match symbol.price_round(quotes.bid(), Round::Floor) {
+                price if price >= top_ask => top_bid,
+                price => price,
+            },
+            OrderSide::Sell => match symbol.price_round(quotes.ask(), Round::Ceiling) {
+                price if price <= top_bid => top_ask,
+                price => price,
+            },
+        };
+        Some(price)
+    }
+
+    fn script_quotes(
+        &self,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: &mut Explanation,
+    ) -> Option<ScriptQuotes> {
+        let quoting_script = self.quoting_script.as_ref()?;
+
+        let snapshot = local_snapshots_service.get_snapshot(self.market_id())?;
+        let position = self
+            .engine_context
+            .balance_manager
+            .lock()
+            .net_position(self.target_eai, self.currency_pair);
+        let inputs = QuotingInputs {
+            top_bid: snapshot.get_top_bid()?.0,
+            top_ask: snapshot.get_top_ask()?.0,
+            position,
+            max_amount: self.max_amount,
+            signals: self
+                .required_signals
+                .iter()
+                .filter_map(|name| {
+                    let value = self.engine_context.signal_service.value(name)?;
+                    Some((name.clone(), value))
+                })
+                .collect(),
+        };
+
+        match quoting_script.evaluate(&inputs) {
+            Ok(quotes) => {
+                explanation.add_reason(format!(
+                    "Quoting script spread {} skew {} size {:?} by position {position}",
+                    quotes.spread, quotes.skew, quotes.size
+                ));
+                Some(quotes)
+            }
+            Err(err) => {
+                log::error!("Quoting script failed, orders aren't placed: {err:?}");
+                explanation.add_reason(format!("Quoting script failed: {err:#}"));
+                None
+            }
+        }
+    }
+
+    fn script_price(
+        quotes: &ScriptQuotes,
+        side: OrderSide,
+        top_bid: Price,
+        top_ask: Price,
+        fair_price: Price,
+        symbol: &Symbol,
+    ) -> Price {
+        // quotes crossing order book are moved to its top, so orders stay makers
+        match side {
+            OrderSide::Buy => match symbol.price_round(quotes.bid(fair_price), Round::Floor) {
+                price if price >= top_ask => top_bid,
+                price => price,
+            },
+            OrderSide::Sell => match symbol.price_round(quotes.ask(fair_price), Round::Ceiling) {
+                price if price <= top_bid => top_ask,
+                price => price,
+            },
+        }
+    }
+
+    /// Index price of currency pair if it's known, otherwise middle of order book top
+    fn fair_price(&self, top_bid: Price, top_ask: Price, explanation: &mut Explanation) -> Price {
+        match self
+            .engine_context
+            .index_prices
+            .index_price(self.currency_pair)
+        {
+            Some(index_price) => {
+                explanation.add_reason(format!("Index price {index_price} is used as fair price"));
+                index_price
+            }
+            None => (top_bid + top_ask) * dec!(0.5),
+        }
+    }
+
     fn calc_trading_context_by_side(
         &mut self,
         side: OrderSide,
         _now: DateTime,
         local_snapshots_service: &LocalSnapshotsService,
+        script_quotes: Option<&ScriptQuotes>,
+        spread_multiplier: Option<Decimal>,
         mut explanation: Explanation,
     ) -> Option<TradingContextBySide> {
         let snapshot = local_snapshots_service.get_snapshot(self.market_id())?;
@@ -138,16 +337,36 @@ impl ExampleStrategy {
             .get(&self.currency_pair)?
             .clone();
 
-        let price = if current_spread < self.spread {
-            let order_book_middle = (bid_max_price + ask_min_price) * dec!(0.5);
+        let fair_price = self.fair_price(bid_max_price, ask_min_price, &mut explanation);
+        let quoting_model_price = match script_quotes {
+            Some(quotes) => Some(Self::script_price(
+                quotes,
+                side,
+                bid_max_price,
+                ask_min_price,
+                fair_price,
+                &symbol,
+            )),
+            None => self.quoting_model_price(
+                side,
+                bid_max_price,
+                ask_min_price,
+                fair_price,
+                &symbol,
+                &mut explanation,
+            ),
+        };
 
+        let price = if let Some(price) = quoting_model_price {
+            price
+        } else if current_spread < self.spread {
             match side {
                 OrderSide::Sell => {
-                    let price = order_book_middle + (self.spread * dec!(0.5));
+                    let price = fair_price + (self.spread * dec!(0.5));
                     symbol.price_round(price, Round::Ceiling)
                 }
                 OrderSide::Buy => {
-                    let price = order_book_middle - (self.spread * dec!(0.5));
+                    let price = fair_price - (self.spread * dec!(0.5));
                     symbol.price_round(price, Round::Floor)
                 }
             }
@@ -155,6 +374,20 @@ impl ExampleStrategy {
             snapshot.get_top(side)?.0
         };
 
+        let price = match spread_multiplier {
+            Some(multiplier) => {
+                explanation.add_reason(format!(
+                    "Distance from fair price is multiplied by {multiplier} because of stale signals or slow order requests"
+                ));
+                let price = fair_price + (price - fair_price) * multiplier;
+                match side {
+                    OrderSide::Buy => symbol.price_round(price, Round::Floor),
+                    OrderSide::Sell => symbol.price_round(price, Round::Ceiling),
+                }
+            }
+            None => price,
+        };
+
         let amount;
         explanation = {
             let mut explanation = Some(explanation);
@@ -197,6 +430,10 @@ impl ExampleStrategy {
             )
         };
 
+        let amount = match script_quotes.and_then(|x| x.size) {
+            Some(size) => amount.min(size),
+            None => amount,
+        };
         let amount = symbol.amount_round(amount, Round::Floor);
 
         Some(TradingContextBySide {
@@ -226,10 +463,47 @@ impl DispositionStrategy for ExampleStrategy {
         local_snapshots_service: &LocalSnapshotsService,
         explanation: &mut Explanation,
     ) -> Option<TradingContext> {
+        let quoting_mode = self
+            .engine_context
+            .signal_service
+            .quoting_mode(&self.required_signals)
+            .most_restrictive(
+                self.engine_context
+                    .latency_throttling
+                    .quoting_mode(self.target_eai),
+            );
+        let spread_multiplier = match quoting_mode {
+            QuotingMode::Normal => None,
+            QuotingMode::Widened(multiplier) => Some(multiplier),
+            QuotingMode::Disabled => {
+                explanation.add_reason(
+                    "Quoting is disabled because required signals are missing or stale or order requests are too slow",
+                );
+                let not_trading = |explanation: &Explanation| TradingContextBySide {
+                    max_amount: self.max_amount,
+                    estimating: vec![WithExplanation {
+                        value: None,
+                        explanation: explanation.clone(),
+                    }],
+                };
+                return Some(TradingContext::new(
+                    not_trading(explanation),
+                    not_trading(explanation),
+                ));
+            }
+        };
+
+        let script_quotes = match self.quoting_script {
+            Some(_) => Some(self.script_quotes(local_snapshots_service, explanation)?),
+            None => None,
+        };
+
         let buy_trading_ctx = self.calc_trading_context_by_side(
             OrderSide::Buy,
             now,
             local_snapshots_service,
+            script_quotes.as_ref(),
+            spread_multiplier,
             explanation.clone(),
         )?;
 
@@ -237,6 +511,8 @@ impl DispositionStrategy for ExampleStrategy {
             OrderSide::Sell,
             now,
             local_snapshots_service,
+            script_quotes.as_ref(),
+            spread_multiplier,
             explanation.clone(),
         )?;
 
@@ -258,3 +534,27 @@ impl DispositionStrategy for ExampleStrategy {
         self.configuration_descriptor
     }
 }
+
+fn reload_quoting_model(
+    quoting_model: &Mutex<Option<AvellanedaStoikovSettings>>,
+    config_path: &str,
+    credentials_path: &str,
+) {
+    let settings = match try_load_settings::<ExampleStrategySettings>(config_path, credentials_path)
+    {
+        Ok(settings) => settings,
+        Err(err) => {
+            log::warn!("Failed to reload quoting model of ExampleStrategy: {err:?}");
+            return;
+        }
+    };
+
+    let mut quoting_model = quoting_model.lock();
+    if *quoting_model != settings.strategy.quoting_model {
+        log::info!(
+            "Quoting model of ExampleStrategy is reloaded: {:?}",
+            settings.strategy.quoting_model
+        );
+        *quoting_model = settings.strategy.quoting_model;
+    }
+}
@@ -0,0 +1,599 @@
+use anyhow::{ensure, Context, Result};
+use mmb_core::disposition_execution::strategy::DispositionStrategy;
+use mmb_core::disposition_execution::{
+    PriceSlot, TradeCycle, TradeDisposition, TradingContext, TradingContextBySide,
+};
+use mmb_core::explanation::{Explanation, WithExplanation};
+use mmb_core::lifecycle::trading_engine::EngineContext;
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::settings::{CurrencyPairSetting, DispositionStrategySettings};
+use mmb_domain::events::ExchangeEvent;
+use mmb_domain::exchanges::symbol::{Round, Symbol};
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId, MarketAccountId, MarketId};
+use mmb_domain::order::snapshot::{
+    Amount, OrderRole, OrderSide, OrderSnapshot, OrderStatus, Price,
+};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::WithExpect;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const STRATEGY_NAME: &str = "GridStrategy";
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GridStrategySettings {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPairSetting,
+    /// Price of the lowest grid level
+    pub lower_price: Price,
+    /// Price of the highest grid level
+    pub upper_price: Price,
+    /// Count of grid levels evenly distributed from `lower_price` to `upper_price`
+    pub levels_count: usize,
+    /// Amount of order on each grid level
+    pub amount_per_level: Amount,
+    /// Path to file with state of grid levels. Grid is restored from it after restart
+    pub state_path: PathBuf,
+}
+
+impl DispositionStrategySettings for GridStrategySettings {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        self.exchange_account_id
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        if let CurrencyPairSetting::Ordinary { base, quote } = self.currency_pair {
+            CurrencyPair::from_codes(base, quote)
+        } else {
+            panic!(
+                "Incorrect currency pair setting enum type {:?}",
+                self.currency_pair
+            );
+        }
+    }
+
+    fn max_amount(&self) -> Amount {
+        self.amount_per_level * Decimal::from(self.levels_count)
+    }
+}
+
+/// Side of order waiting on each grid level. Grid parameters are stored to detect that
+/// settings were changed since the state was saved
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+struct GridState {
+    lower_price: Price,
+    upper_price: Price,
+    levels_count: usize,
+    levels: Vec<Option<OrderSide>>,
+}
+
+impl GridState {
+    /// Levels below middle price are bought and levels above it are sold. The first level
+    /// which isn't lower than middle price stays empty, so every filled order can be
+    /// re-posted on the adjacent level
+    fn new(settings: &GridStrategySettings, level_prices: &[Price], middle_price: Price) -> Self {
+        let empty_level = level_prices.iter().position(|&x| x >= middle_price);
+        let levels = (0..level_prices.len())
+            .map(|index| match empty_level {
+                Some(empty_level) if index == empty_level => None,
+                Some(empty_level) if index > empty_level => Some(OrderSide::Sell),
+                _ => Some(OrderSide::Buy),
+            })
+            .collect();
+
+        GridState {
+            lower_price: settings.lower_price,
+            upper_price: settings.upper_price,
+            levels_count: settings.levels_count,
+            levels,
+        }
+    }
+
+    fn matches(&self, settings: &GridStrategySettings) -> bool {
+        self.lower_price == settings.lower_price
+            && self.upper_price == settings.upper_price
+            && self.levels_count == settings.levels_count
+            && self.levels.len() == settings.levels_count
+    }
+
+    /// Filled level becomes empty and order of opposite side is posted on the adjacent level
+    fn fill_level(&mut self, level_index: usize, side: OrderSide) {
+        self.levels[level_index] = None;
+
+        let adjacent_level = match side {
+            OrderSide::Buy => level_index.checked_add(1),
+            OrderSide::Sell => level_index.checked_sub(1),
+        };
+        if let Some(adjacent_level) = adjacent_level.filter(|&x| x < self.levels.len()) {
+            self.levels[adjacent_level] = Some(side.change_side());
+        }
+    }
+}
+
+/// Places orders on fixed price levels between `lower_price` and `upper_price`. When order on
+/// a level is completely filled, order of opposite side is placed on the adjacent level, so
+/// the strategy earns the distance between levels on every price oscillation
+pub struct GridStrategy {
+    settings: GridStrategySettings,
+    market_account_id: MarketAccountId,
+    symbol: Arc<Symbol>,
+    level_prices: Vec<Price>,
+    /// `None` until middle price is known for the first time, if state wasn't restored from file
+    state: Mutex<Option<GridState>>,
+    configuration_descriptor: ConfigurationDescriptor,
+}
+
+impl GridStrategy {
+    pub fn new(
+        settings: GridStrategySettings,
+        engine_context: Arc<EngineContext>,
+    ) -> Result<Box<Self>> {
+        ensure!(
+            settings.levels_count >= 2,
+            "{STRATEGY_NAME} should contain at least 2 levels"
+        );
+        ensure!(
+            settings.lower_price < settings.upper_price,
+            "{STRATEGY_NAME} lower price {} should be less than upper price {}",
+            settings.lower_price,
+            settings.upper_price
+        );
+
+        let target_eai = settings.exchange_account_id();
+        let currency_pair = settings.currency_pair();
+        let configuration_descriptor = ConfigurationDescriptor::new(
+            STRATEGY_NAME.into(),
+            format!("{target_eai};{currency_pair}").as_str().into(),
+        );
+
+        let symbol = engine_context
+            .exchanges
+            .get(&target_eai)
+            .with_expect(|| format!("failed to get exchange from trading_engine for {target_eai}"))
+            .get_symbol(currency_pair)?;
+
+        // position can be changed from the bottom of the grid to the top of it
+        engine_context
+            .balance_manager
+            .lock()
+            .set_target_amount_limit(
+                configuration_descriptor,
+                target_eai,
+                symbol.clone(),
+                settings.max_amount(),
+            );
+
+        let level_prices = level_prices(&settings, &symbol);
+        let state = load_state(&settings.state_path)?.filter(|state| {
+            let matches = state.matches(&settings);
+            if !matches {
+                log::warn!(
+                    "{STRATEGY_NAME} state in {} doesn't match settings and will be recreated",
+                    settings.state_path.display()
+                );
+            }
+            matches
+        });
+
+        Ok(Box::new(GridStrategy {
+            market_account_id: MarketAccountId::new(target_eai, currency_pair),
+            settings,
+            symbol,
+            level_prices,
+            state: Mutex::new(state),
+            configuration_descriptor,
+        }))
+    }
+
+    fn market_id(&self) -> MarketId {
+        self.market_account_id.market_id()
+    }
+
+    fn calc_trading_context_by_side(
+        &self,
+        state: &GridState,
+        side: OrderSide,
+        explanation: &Explanation,
+    ) -> TradingContextBySide {
+        let amount = self
+            .symbol
+            .amount_round(self.settings.amount_per_level, Round::Floor);
+
+        let estimating = state
+            .levels
+            .iter()
+            .zip(&self.level_prices)
+            .map(|(&level_side, &price)| {
+                let mut explanation = explanation.clone();
+                let value = match level_side == Some(side) {
+                    true => {
+                        explanation.add_reason(format!("Grid level {price} is waiting for {side}"));
+                        Some(TradeCycle {
+                            order_role: OrderRole::Maker,
+                            strategy_name: STRATEGY_NAME.to_owned(),
+                            disposition: TradeDisposition::new(
+                                self.market_account_id,
+                                side,
+                                price,
+                                amount,
+                            ),
+                        })
+                    }
+                    false => None,
+                };
+
+                WithExplanation { value, explanation }
+            })
+            .collect();
+
+        TradingContextBySide {
+            max_amount: self.settings.max_amount(),
+            estimating,
+        }
+    }
+
+    fn save_state(&self, state: &GridState) {
+        if let Err(err) = save_state(&self.settings.state_path, state) {
+            log::error!("{STRATEGY_NAME} failed to save state: {err:?}");
+        }
+    }
+}
+
+impl DispositionStrategy for GridStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        _: &ExchangeEvent,
+        _now: DateTime,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        let mut state = self.state.lock();
+        if state.is_none() {
+            let snapshot = local_snapshots_service.get_snapshot(self.market_id())?;
+            let middle_price =
+                (snapshot.get_top_ask()?.0 + snapshot.get_top_bid()?.0) / Decimal::TWO;
+
+            let new_state = GridState::new(&self.settings, &self.level_prices, middle_price);
+            log::info!("{STRATEGY_NAME} created grid {new_state:?} by middle price {middle_price}");
+            self.save_state(&new_state);
+            *state = Some(new_state);
+        }
+        let state = state.as_ref()?;
+
+        Some(TradingContext::new(
+            self.calc_trading_context_by_side(state, OrderSide::Buy, explanation),
+            self.calc_trading_context_by_side(state, OrderSide::Sell, explanation),
+        ))
+    }
+
+    fn handle_order_fill(
+        &self,
+        cloned_order: &Arc<OrderSnapshot>,
+        price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        _cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        // level is re-posted only when its order is filled completely
+        if cloned_order.status() != OrderStatus::Completed {
+            return Ok(());
+        }
+
+        let mut state = self.state.lock();
+        let state = state
+            .as_mut()
+            .context("Grid state should exist when its order is filled")?;
+
+        let level_index = price_slot.id.level_index;
+        let side = cloned_order.header.side;
+        ensure!(
+            state.levels.get(level_index) == Some(&Some(side)),
+            "{STRATEGY_NAME} level {level_index} isn't waiting for {side} of filled order {}",
+            cloned_order.header.client_order_id
+        );
+
+        state.fill_level(level_index, side);
+        log::info!(
+            "{STRATEGY_NAME} level {level_index} was filled by {side} order {}",
+            cloned_order.header.client_order_id
+        );
+        self.save_state(state);
+
+        Ok(())
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        self.configuration_descriptor
+    }
+
+    fn price_slots_count(&self) -> usize {
+        self.settings.levels_count
+    }
+}
+
+fn level_prices(settings: &GridStrategySettings, symbol: &Symbol) -> Vec<Price> {
+    let step =
+        (settings.upper_price - settings.lower_price) / Decimal::from(settings.levels_count - 1);
+    (0..settings.levels_count)
+        .map(|index| {
+            let price = settings.lower_price + step * Decimal::from(index);
+            symbol.price_round(price, Round::ToNearest)
+        })
+        .collect()
+}
+
+fn load_state(path: &Path) -> Result<Option<GridState>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let state = fs::read_to_string(path)
+        .with_context(|| format!("reading grid state {}", path.display()))?;
+    let state = serde_json::from_str(&state)
+        .with_context(|| format!("parsing grid state {}", path.display()))?;
+    Ok(Some(state))
+}
+
+fn save_state(path: &Path, state: &GridState) -> Result<()> {
+    // state is replaced atomically, so crash can't leave damaged file
+    let tmp_path = path.with_extension("tmp");
+    let state = serde_json::to_vec(state).context("serializing grid state")?;
+    fs::write(&tmp_path, state)
+        .with_context(|| format!("writing grid state {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path).with_context(|| format!("replacing grid state {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_core::disposition_execution::{CompositeOrder, PriceSlotId};
+    use mmb_core::misc::time::time_manager;
+    use mmb_domain::events::TradingHaltedEvent;
+    use mmb_domain::exchanges::symbol::Precision;
+    use mmb_domain::order::snapshot::{ClientOrderId, OrderOptions};
+    use mmb_domain::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+    use mmb_utils::hashmap;
+    use rust_decimal_macros::dec;
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    fn settings(name: &str) -> GridStrategySettings {
+        GridStrategySettings {
+            exchange_account_id: ExchangeAccountId::new("Binance", 0),
+            currency_pair: CurrencyPairSetting::Ordinary {
+                base: "btc".into(),
+                quote: "usdt".into(),
+            },
+            lower_price: dec!(100),
+            upper_price: dec!(101),
+            levels_count: 4,
+            amount_per_level: dec!(0.5),
+            state_path: std::env::temp_dir()
+                .join(format!("grid_state_{name}_{}.json", std::process::id())),
+        }
+    }
+
+    fn symbol() -> Arc<Symbol> {
+        Arc::new(Symbol::new(
+            false,
+            "btc".into(),
+            "btc".into(),
+            "usdt".into(),
+            "usdt".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "btc".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(0.1) },
+        ))
+    }
+
+    /// Strategy without engine context, state is restored from file like on start
+    fn strategy(settings: GridStrategySettings) -> GridStrategy {
+        let symbol = symbol();
+        let state = load_state(&settings.state_path)
+            .expect("in test")
+            .filter(|x| x.matches(&settings));
+        GridStrategy {
+            market_account_id: MarketAccountId::new(
+                settings.exchange_account_id(),
+                settings.currency_pair(),
+            ),
+            level_prices: level_prices(&settings, &symbol),
+            symbol,
+            state: Mutex::new(state),
+            configuration_descriptor: ConfigurationDescriptor::new(
+                STRATEGY_NAME.into(),
+                "Binance_0;btc/usdt".into(),
+            ),
+            settings,
+        }
+    }
+
+    fn fill_order(
+        strategy: &GridStrategy,
+        level_index: usize,
+        side: OrderSide,
+        status: OrderStatus,
+    ) -> Result<()> {
+        let mut order = OrderSnapshot::with_params(
+            ClientOrderId::unique_id(),
+            OrderOptions::liquidation(strategy.level_prices[level_index]),
+            Some(OrderRole::Maker),
+            strategy.settings.exchange_account_id,
+            strategy.settings.currency_pair(),
+            strategy.settings.amount_per_level,
+            side,
+            None,
+            STRATEGY_NAME,
+        );
+        order.set_status(status, time_manager::now());
+        let price_slot = PriceSlot {
+            id: PriceSlotId::new(STRATEGY_NAME.to_owned(), level_index),
+            estimating: RefCell::new(None),
+            order: RefCell::new(CompositeOrder::new(side)),
+        };
+
+        strategy.handle_order_fill(
+            &Arc::new(order),
+            &price_slot,
+            strategy.settings.exchange_account_id,
+            CancellationToken::default(),
+        )
+    }
+
+    fn levels(strategy: &GridStrategy) -> Vec<Option<OrderSide>> {
+        strategy
+            .state
+            .lock()
+            .as_ref()
+            .expect("grid state should exist")
+            .levels
+            .clone()
+    }
+
+    #[test]
+    fn levels_are_distributed_evenly_by_price_tick() {
+        assert_eq!(
+            level_prices(&settings("prices"), &symbol()),
+            vec![dec!(100), dec!(100.3), dec!(100.7), dec!(101)]
+        );
+    }
+
+    #[test]
+    fn grid_is_split_by_middle_price() {
+        let settings = settings("split");
+        let level_prices = level_prices(&settings, &symbol());
+        let levels = |middle_price| GridState::new(&settings, &level_prices, middle_price).levels;
+
+        use OrderSide::{Buy, Sell};
+        assert_eq!(
+            levels(dec!(100.5)),
+            vec![Some(Buy), Some(Buy), None, Some(Sell)]
+        );
+        assert_eq!(
+            levels(dec!(100.3)),
+            vec![Some(Buy), None, Some(Sell), Some(Sell)]
+        );
+        assert_eq!(
+            levels(dec!(99)),
+            vec![None, Some(Sell), Some(Sell), Some(Sell)]
+        );
+        assert_eq!(levels(dec!(102)), vec![Some(Buy); 4]);
+    }
+
+    #[test]
+    fn grid_is_created_by_middle_price_of_order_book() {
+        let settings = settings("created");
+        let _ = fs::remove_file(&settings.state_path);
+        let mut strategy = strategy(settings.clone());
+
+        let market_id = strategy.market_id();
+        let local_snapshots_service = LocalSnapshotsService::new(hashmap![
+            market_id => LocalOrderBookSnapshot::new(
+                BTreeMap::from([(dec!(100.6), dec!(1))]),
+                BTreeMap::from([(dec!(100.4), dec!(1))]),
+                time_manager::now(),
+            )
+        ]);
+        let event = ExchangeEvent::TradingHalted(TradingHaltedEvent {
+            reason: "test".to_owned(),
+            is_positions_flattened: false,
+            time: time_manager::now(),
+        });
+
+        let trading_context = strategy
+            .calculate_trading_context(
+                &event,
+                time_manager::now(),
+                &local_snapshots_service,
+                &mut Explanation::default(),
+            )
+            .expect("trading context should be calculated");
+
+        let prices = |side| {
+            trading_context.by_side[side]
+                .estimating
+                .iter()
+                .filter_map(|x| x.value.as_ref())
+                .map(|x| x.disposition.order.price)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(prices(OrderSide::Buy), vec![dec!(100), dec!(100.3)]);
+        assert_eq!(prices(OrderSide::Sell), vec![dec!(101)]);
+
+        let saved_state = load_state(&settings.state_path).expect("in test");
+        let _ = fs::remove_file(&settings.state_path);
+        assert_eq!(saved_state.as_ref(), strategy.state.lock().as_ref());
+    }
+
+    #[test]
+    fn completely_filled_level_is_reposted_on_adjacent_level() {
+        let settings = settings("reposted");
+        let _ = fs::remove_file(&settings.state_path);
+        let strategy = strategy(settings.clone());
+        *strategy.state.lock() = Some(GridState::new(
+            &settings,
+            &strategy.level_prices,
+            dec!(100.5),
+        ));
+
+        use OrderSide::{Buy, Sell};
+        fill_order(&strategy, 1, Buy, OrderStatus::Created).expect("in test");
+        assert_eq!(
+            levels(&strategy),
+            vec![Some(Buy), Some(Buy), None, Some(Sell)]
+        );
+
+        fill_order(&strategy, 1, Buy, OrderStatus::Completed).expect("in test");
+        assert_eq!(
+            levels(&strategy),
+            vec![Some(Buy), None, Some(Sell), Some(Sell)]
+        );
+
+        fill_order(&strategy, 3, Sell, OrderStatus::Completed).expect("in test");
+        assert_eq!(levels(&strategy), vec![Some(Buy), None, Some(Buy), None]);
+
+        // level can't be filled by order of side which it isn't waiting for
+        let error = fill_order(&strategy, 0, Sell, OrderStatus::Completed).expect_err("in test");
+        assert!(format!("{error:?}").contains("isn't waiting for"));
+
+        let _ = fs::remove_file(&settings.state_path);
+    }
+
+    #[test]
+    fn grid_is_restored_after_restart() {
+        let settings = settings("restored");
+        let _ = fs::remove_file(&settings.state_path);
+        let strategy = strategy(settings.clone());
+        assert!(strategy.state.lock().is_none());
+
+        *strategy.state.lock() = Some(GridState::new(
+            &settings,
+            &strategy.level_prices,
+            dec!(100.5),
+        ));
+        fill_order(&strategy, 1, OrderSide::Buy, OrderStatus::Completed).expect("in test");
+
+        let restarted_strategy = self::strategy(settings.clone());
+        assert_eq!(levels(&restarted_strategy), levels(&strategy));
+
+        // grid is recreated if its parameters are changed
+        let changed_settings = GridStrategySettings {
+            levels_count: 5,
+            ..settings.clone()
+        };
+        assert!(self::strategy(changed_settings).state.lock().is_none());
+
+        let _ = fs::remove_file(&settings.state_path);
+    }
+}
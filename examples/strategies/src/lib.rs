@@ -16,4 +16,8 @@
     clippy::unwrap_used
 )]
 
+pub mod arbitrage_strategy;
+pub mod avellaneda_stoikov;
 pub mod example_strategy;
+pub mod grid_strategy;
+pub mod scripted_quoting;
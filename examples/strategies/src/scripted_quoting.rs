@@ -0,0 +1,307 @@
+use anyhow::{anyhow, bail, Context, Result};
+use mmb_domain::order::snapshot::{Amount, Price};
+use parking_lot::Mutex;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use rust_decimal::Decimal;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Market state passed into quoting script as variables with the same names
+#[derive(Clone, Debug)]
+pub struct QuotingInputs {
+    pub top_bid: Price,
+    pub top_ask: Price,
+    /// Signed net position in base currency
+    pub position: Amount,
+    pub max_amount: Amount,
+    /// Fresh values of signals used by strategy, passed into script as map `signals`
+    pub signals: Vec<(String, Decimal)>,
+}
+
+/// Quoting rules returned by script. Bid and ask are placed symmetrically around middle price
+/// shifted by `skew`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScriptQuotes {
+    pub spread: Price,
+    pub skew: Price,
+    /// Amount of orders, available balance is used if not specified
+    pub size: Option<Amount>,
+}
+
+impl ScriptQuotes {
+    pub fn bid(&self, middle_price: Price) -> Price {
+        middle_price + self.skew - self.spread / Decimal::TWO
+    }
+
+    pub fn ask(&self, middle_price: Price) -> Price {
+        middle_price + self.skew + self.spread / Decimal::TWO
+    }
+}
+
+struct CompiledScript {
+    ast: AST,
+    modified: SystemTime,
+}
+
+/// Rhai script with quoting rules which is re-evaluated on every market event. Script gets
+/// variables `top_bid`, `top_ask`, `mid`, `position`, `max_amount` and map `signals` with fresh
+/// values of signals required by strategy and should return map
+/// `#{ spread: .., skew: .., size: .. }`, where `skew` and `size` are optional. Script is
+/// recompiled when its file is changed, previous version is used if new one can't be compiled.
+/// Numbers are decimals in script (floats are disabled), so script should be trusted: operations
+/// limit is unavailable because rhai is built with `unchecked` feature within workspace
+pub struct QuotingScript {
+    path: PathBuf,
+    engine: Engine,
+    compiled: Mutex<CompiledScript>,
+}
+
+impl QuotingScript {
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let engine = Engine::new();
+        let compiled = compile(&engine, &path)?;
+        log::info!("Quoting script {} is loaded", path.display());
+
+        Ok(QuotingScript {
+            path,
+            engine,
+            compiled: Mutex::new(compiled),
+        })
+    }
+
+    pub fn evaluate(&self, inputs: &QuotingInputs) -> Result<ScriptQuotes> {
+        self.reload_if_changed();
+
+        let mut scope = Scope::new();
+        let middle_price = (inputs.top_bid + inputs.top_ask) / Decimal::TWO;
+        for (name, value) in [
+            ("top_bid", inputs.top_bid),
+            ("top_ask", inputs.top_ask),
+            ("mid", middle_price),
+            ("position", inputs.position),
+            ("max_amount", inputs.max_amount),
+        ] {
+            let _ = scope.push(name, value);
+        }
+        let mut signals = Map::new();
+        for (name, value) in &inputs.signals {
+            let _ = signals.insert(name.as_str().into(), Dynamic::from_decimal(*value));
+        }
+        let _ = scope.push("signals", signals);
+
+        let result = self
+            .engine
+            .eval_ast_with_scope::<Map>(&mut scope, &self.compiled.lock().ast)
+            .map_err(|err| anyhow!("evaluating quoting script: {err}"))?;
+
+        let spread = match result.get("spread") {
+            Some(spread) => to_decimal("spread", spread)?,
+            None => bail!("Quoting script should return spread"),
+        };
+        let skew = match result.get("skew") {
+            Some(skew) => to_decimal("skew", skew)?,
+            None => Decimal::ZERO,
+        };
+        let size = match result.get("size") {
+            Some(size) => Some(to_decimal("size", size)?),
+            None => None,
+        };
+        if spread < Decimal::ZERO {
+            bail!("Quoting script returned negative spread {spread}");
+        }
+
+        Ok(ScriptQuotes { spread, skew, size })
+    }
+
+    fn reload_if_changed(&self) {
+        let modified = match modified_time(&self.path) {
+            Ok(modified) => modified,
+            Err(err) => {
+                log::warn!("Failed to check quoting script: {err:?}");
+                return;
+            }
+        };
+        if self.compiled.lock().modified == modified {
+            return;
+        }
+
+        match compile(&self.engine, &self.path) {
+            Ok(compiled) => {
+                *self.compiled.lock() = compiled;
+                log::info!("Quoting script {} is reloaded", self.path.display());
+            }
+            Err(err) => {
+                // changed file isn't compiled again until the next change
+                self.compiled.lock().modified = modified;
+                log::error!("Failed to reload quoting script, previous version is used: {err:?}");
+            }
+        }
+    }
+}
+
+fn compile(engine: &Engine, path: &Path) -> Result<CompiledScript> {
+    let modified = modified_time(path)?;
+    let script = fs::read_to_string(path)
+        .with_context(|| format!("reading quoting script {}", path.display()))?;
+    let ast = engine
+        .compile(script)
+        .with_context(|| format!("compiling quoting script {}", path.display()))?;
+
+    Ok(CompiledScript { ast, modified })
+}
+
+fn modified_time(path: &Path) -> Result<SystemTime> {
+    fs::metadata(path)
+        .and_then(|x| x.modified())
+        .with_context(|| format!("getting modification time of {}", path.display()))
+}
+
+fn to_decimal(name: &str, value: &Dynamic) -> Result<Decimal> {
+    let value = match (value.as_decimal(), value.as_int()) {
+        (Ok(value), _) => Some(value),
+        (_, Ok(value)) => Some(Decimal::from(value)),
+        _ => None,
+    };
+    value.with_context(|| format!("Quoting script returned non numeric {name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::fs::File;
+    use std::time::Duration;
+
+    fn script_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("quoting_script_{name}_{}.rhai", std::process::id()))
+    }
+
+    /// Writes script and moves its modification time forward, so the change is detected
+    /// regardless of file system timestamps resolution
+    fn write_script(path: &Path, script: &str, modified: SystemTime) {
+        fs::write(path, script).expect("in test");
+        File::options()
+            .write(true)
+            .open(path)
+            .and_then(|x| x.set_modified(modified))
+            .expect("in test");
+    }
+
+    fn inputs() -> QuotingInputs {
+        QuotingInputs {
+            top_bid: dec!(99),
+            top_ask: dec!(101),
+            position: dec!(-2),
+            max_amount: dec!(10),
+            signals: vec![("imbalance".to_owned(), dec!(0.5))],
+        }
+    }
+
+    #[test]
+    fn script_is_evaluated_with_market_inputs() {
+        let path = script_path("evaluated");
+        write_script(
+            &path,
+            r#"#{ spread: top_ask - top_bid + signals.imbalance, skew: -position / max_amount, size: mid / 100 }"#,
+            SystemTime::now(),
+        );
+
+        let script = QuotingScript::load(path.clone()).expect("in test");
+        let quotes = script.evaluate(&inputs()).expect("in test");
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(
+            quotes,
+            ScriptQuotes {
+                spread: dec!(2.5),
+                skew: dec!(0.2),
+                size: Some(dec!(1)),
+            }
+        );
+        assert_eq!(quotes.bid(dec!(100)), dec!(98.95));
+        assert_eq!(quotes.ask(dec!(100)), dec!(101.45));
+    }
+
+    #[test]
+    fn optional_quotes_have_defaults() {
+        let path = script_path("defaults");
+        write_script(&path, "#{ spread: 2 }", SystemTime::now());
+
+        let script = QuotingScript::load(path.clone()).expect("in test");
+        let quotes = script.evaluate(&inputs()).expect("in test");
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(
+            quotes,
+            ScriptQuotes {
+                spread: dec!(2),
+                skew: dec!(0),
+                size: None,
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_script_results_are_errors() {
+        let path = script_path("errors");
+        let script_errors = [
+            ("#{ skew: 1 }", "should return spread"),
+            ("#{ spread: -1 }", "negative spread"),
+            (r#"#{ spread: "wide" }"#, "non numeric spread"),
+            (
+                "#{ spread: signals.unknown + 1 }",
+                "evaluating quoting script",
+            ),
+        ];
+
+        for (i, (text, expected_error)) in script_errors.into_iter().enumerate() {
+            write_script(
+                &path,
+                text,
+                SystemTime::now() + Duration::from_secs(i as u64),
+            );
+            let script = QuotingScript::load(path.clone()).expect("in test");
+
+            let error = script.evaluate(&inputs()).expect_err("in test");
+            assert!(
+                format!("{error:?}").contains(expected_error),
+                "script {text} failed with unexpected error {error:?}"
+            );
+        }
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn script_which_isnt_compiled_isnt_loaded() {
+        let path = script_path("not_compiled");
+        write_script(&path, "#{ spread: ", SystemTime::now());
+
+        let error = QuotingScript::load(path.clone())
+            .err()
+            .expect("script shouldn't be loaded");
+        let _ = fs::remove_file(&path);
+
+        assert!(format!("{error:?}").contains("compiling quoting script"));
+    }
+
+    #[test]
+    fn changed_script_is_reloaded() {
+        let path = script_path("reloaded");
+        let start = SystemTime::now();
+        write_script(&path, "#{ spread: 1 }", start);
+        let script = QuotingScript::load(path.clone()).expect("in test");
+        assert_eq!(script.evaluate(&inputs()).expect("in test").spread, dec!(1));
+
+        write_script(&path, "#{ spread: 2 }", start + Duration::from_secs(1));
+        assert_eq!(script.evaluate(&inputs()).expect("in test").spread, dec!(2));
+
+        // previous version is used while changed script can't be compiled
+        write_script(&path, "#{ spread: ", start + Duration::from_secs(2));
+        assert_eq!(script.evaluate(&inputs()).expect("in test").spread, dec!(2));
+
+        write_script(&path, "#{ spread: 3 }", start + Duration::from_secs(3));
+        assert_eq!(script.evaluate(&inputs()).expect("in test").spread, dec!(3));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::{anyhow, bail, Context, Result};
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use dashmap::DashMap;
 use function_name::named;
 use hmac::digest::generic_array;
@@ -11,6 +11,7 @@ use itertools::Itertools;
 use mmb_utils::time::{get_current_milliseconds, u64_to_date_time};
 use mmb_utils::DateTime;
 use parking_lot::{Mutex, RwLock};
+use rust_decimal::Decimal;
 use serde_json::Value;
 use sha2::Sha256;
 use std::collections::{HashMap, HashSet};
@@ -20,8 +21,10 @@ use std::time::{Duration, UNIX_EPOCH};
 use tokio::sync::broadcast;
 
 use super::support::{
-    BinanceDerivativeAccountInfo, BinanceOrderInfo, BinancePosition, BinanceSpotAccountInfo,
+    BinanceAccountUpdate, BinanceDerivativeAccountInfo, BinanceOrderInfo, BinancePosition,
+    BinanceSpotAccountInfo,
 };
+use mmb_core::exchanges::common::send_event;
 use mmb_core::exchanges::general::exchange::BoxExchangeClient;
 use mmb_core::exchanges::general::exchange::Exchange;
 use mmb_core::exchanges::general::features::{
@@ -30,6 +33,7 @@ use mmb_core::exchanges::general::features::{
 use mmb_core::exchanges::general::handlers::handle_order_filled::FillAmount;
 use mmb_core::exchanges::general::handlers::handle_order_filled::FillEvent;
 use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::general::order::group::CreateOrderGroupResult;
 use mmb_core::exchanges::hosts::Hosts;
 use mmb_core::exchanges::rest_client::{
     ErrorHandler, ErrorHandlerData, RequestType, RestClient, RestHeaders, RestResponse, UriBuilder,
@@ -45,17 +49,26 @@ use mmb_core::exchanges::{
     timeouts::requests_timeout_manager_factory::RequestTimeoutArguments,
 };
 use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
-use mmb_core::settings::ExchangeSettings;
+use mmb_core::settings::{ExchangeSettings, MarginType};
 use mmb_domain::events::{AllowedEventSourceType, EventSourceType};
-use mmb_domain::events::{ExchangeBalance, ExchangeEvent, TradeId};
-use mmb_domain::exchanges::symbol::{Precision, Symbol};
+use mmb_domain::events::{
+    BalanceUpdateEvent, ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent, TradeId,
+};
+use mmb_domain::exchanges::commission::{Commission, CommissionForType};
+use mmb_domain::exchanges::symbol::{Precision, Symbol, SymbolStatus};
+use mmb_domain::exchanges::system_status::SystemStatus;
 use mmb_domain::market::{CurrencyCode, CurrencyId, CurrencyPair, ExchangeErrorType, ExchangeId};
 use mmb_domain::market::{ExchangeAccountId, SpecificCurrencyPair};
 use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order::group::OrderGroupId;
 use mmb_domain::order::pool::{OrderRef, OrdersPool};
 use mmb_domain::order::snapshot::*;
 use mmb_domain::order::snapshot::{Amount, Price};
 use mmb_domain::position::{ActivePosition, DerivativePosition};
+use mmb_domain::transfer::{
+    AccountTransfer, ExchangeFundsMovementId, ExchangeTransferId, FundsMovement, FundsMovementType,
+    TransferStatus, Withdrawal,
+};
 use mmb_utils::value_to_decimal::GetOrErr;
 use serde::{Deserialize, Serialize};
 use sha2::digest::generic_array::GenericArray;
@@ -66,7 +79,8 @@ const LISTEN_KEY: &str = "listenKey";
 pub struct ErrorHandlerBinance;
 
 pub struct RestHeadersBinance {
-    pub api_key: String,
+    /// Shared with `Binance`, so rotated key is used by next requests
+    pub api_key: Arc<RwLock<String>>,
     pub is_usd_m_futures: bool,
 }
 
@@ -81,7 +95,7 @@ impl RestHeaders for RestHeadersBinance {
             true => builder.header(CONTENT_TYPE, "application/x-www-form-urlencoded"),
             false => builder,
         }
-        .header("X-MBX-APIKEY", &self.api_key)
+        .header("X-MBX-APIKEY", self.api_key.read().as_str())
     }
 }
 
@@ -169,9 +183,19 @@ pub struct Binance {
     pub(super) is_reducing_market_data: bool,
 
     pub(super) rest_client: RestClient<ErrorHandlerBinance, RestHeadersBinance>,
+    // Credentials replaced on rotation, `settings` keep credentials used at startup
+    pub(super) api_key: Arc<RwLock<String>>,
+    pub(super) secret_key: RwLock<String>,
 
     // NOTE: None when websocket is disconnected
     pub(super) listen_key: RwLock<Option<String>>,
+
+    // Last known derivative positions, because user data stream doesn't contain
+    // leverage and liquidation price of positions
+    pub(super) positions: DashMap<CurrencyPair, DerivativePosition>,
+    // Last known balances, because user data stream contains only changed ones.
+    // None until balances are received by request
+    pub(super) balances: Mutex<Option<HashMap<CurrencyCode, Amount>>>,
 }
 
 impl Binance {
@@ -212,6 +236,7 @@ impl Binance {
 
         let hosts = Self::make_hosts(settings.is_margin_trading);
         let exchange_account_id = settings.exchange_account_id;
+        let api_key = Arc::new(RwLock::new(settings.api_key.clone()));
 
         Self {
             id,
@@ -234,10 +259,12 @@ impl Binance {
                     ErrorHandlerBinance::default(),
                 ),
                 RestHeadersBinance {
-                    api_key: settings.api_key.clone(),
+                    api_key: api_key.clone(),
                     is_usd_m_futures: settings.is_margin_trading,
                 },
             ),
+            api_key,
+            secret_key: RwLock::new(settings.secret_key.clone()),
             timeout_manager,
             is_reducing_market_data,
             settings,
@@ -245,6 +272,8 @@ impl Binance {
             events_channel,
             lifetime_manager,
             listen_key: Default::default(),
+            positions: Default::default(),
+            balances: Default::default(),
         }
     }
 
@@ -317,7 +346,7 @@ impl Binance {
     }
 
     fn write_signature_to_builder(&self, builder: &mut UriBuilder) {
-        let mut hmac = Hmac::<Sha256>::new_from_slice(self.settings.secret_key.as_bytes())
+        let mut hmac = Hmac::<Sha256>::new_from_slice(self.secret_key.read().as_bytes())
             .expect("Unable to calculate hmac for Binance signature");
         hmac.update(builder.query());
 
@@ -381,8 +410,6 @@ impl Binance {
         json_response: Value,
         event_time: DateTime,
     ) -> Result<()> {
-        // TODO need special handler for OCO orders
-
         let exchange_order_id = json_response["i"].to_string();
         let exchange_order_id = exchange_order_id.trim_matches('"');
         let execution_type = json_response["x"]
@@ -400,10 +427,15 @@ impl Binance {
                 "CANCELED" | "EXPIRED" if !self.settings.is_margin_trading => "C",
                 _ => "c",
             };
-            json_response[field_name]
-                .as_str()
-                .ok_or_else(|| anyhow!("Unable to parse client order id"))?
+            match json_response[field_name].as_str() {
+                Some(client_order_id) if !client_order_id.is_empty() => client_order_id,
+                // leg of OCO order list expired by exchange has no original client order id
+                _ => json_response["c"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Unable to parse client order id"))?,
+            }
         };
+        let is_order_list_leg = json_response["g"].as_i64().is_some_and(|x| x != -1);
 
         match execution_type {
             "NEW" => match order_status {
@@ -431,6 +463,15 @@ impl Binance {
                 // We get notification of rejected orders from the rest responses
             }
             "EXPIRED" => match time_in_force {
+                // other leg of OCO order list was triggered or filled
+                // or futures order wasn't executed because of its time in force or trigger
+                _ if is_order_list_leg || self.settings.is_margin_trading => {
+                    (self.order_cancelled_callback)(
+                        client_order_id.into(),
+                        exchange_order_id.into(),
+                        EventSourceType::WebSocket,
+                    );
+                }
                 "GTX" => {
                     (self.order_cancelled_callback)(
                         client_order_id.into(),
@@ -440,6 +481,9 @@ impl Binance {
                 }
                 _ => log::error!("Order {client_order_id} was expired, message: {msg_to_log}"),
             },
+            "AMENDMENT" => {
+                // price or amount of futures order was modified, remaining amount is handled by fills
+            }
             "TRADE" | "CALCULATED" => {
                 let event_data = self.prepare_data_for_fill_handler(
                     &json_response,
@@ -717,12 +761,178 @@ impl Binance {
                     position.liquidation_price,
                     position.leverage,
                 );
+                let _ = self
+                    .positions
+                    .insert(*currency_pair, derivative_position.clone());
 
                 // We don't receive `timestamp` from exchange
                 Ok(ActivePosition::new(derivative_position, Utc::now()))
             }))
     }
 
+    /// Applies leverage and margin type from settings to specified symbols of futures market
+    pub(super) async fn configure_derivative_positions(&self, currency_pairs: &[CurrencyPair]) {
+        let settings = match &self.settings.derivative {
+            Some(settings) => settings,
+            None => return,
+        };
+
+        for &currency_pair in currency_pairs {
+            if let Some(margin_type) = settings.margin_type {
+                match self
+                    .request_change_margin_type(currency_pair, margin_type)
+                    .await
+                {
+                    Ok(_) => {}
+                    // -4046 "No need to change margin type."
+                    Err(err) if err.code == Some(-4046) => {}
+                    Err(err) => log::error!(
+                        "Unable to set margin type {margin_type:?} for {currency_pair} on {}: {err:?}",
+                        self.id
+                    ),
+                }
+            }
+
+            if let Some(leverage) = settings.leverage {
+                if let Err(err) = self.request_change_leverage(currency_pair, leverage).await {
+                    log::error!(
+                        "Unable to set leverage {leverage} for {currency_pair} on {}: {err:?}",
+                        self.id
+                    );
+                }
+            }
+        }
+    }
+
+    #[named]
+    pub(super) async fn request_change_leverage(
+        &self,
+        currency_pair: CurrencyPair,
+        leverage: u8,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/fapi/v1/leverage");
+        builder.add_kv("symbol", self.get_specific_currency_pair(currency_pair));
+        builder.add_kv("leverage", leverage);
+        self.add_authentification(&mut builder);
+
+        let (uri, query) = builder.build_uri_and_query(self.hosts.rest_uri_host(), false);
+
+        let log_args = format!("Change leverage for {currency_pair} to {leverage}");
+        self.rest_client
+            .post(uri, Some(query), function_name!(), log_args)
+            .await
+    }
+
+    #[named]
+    pub(super) async fn request_change_margin_type(
+        &self,
+        currency_pair: CurrencyPair,
+        margin_type: MarginType,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/fapi/v1/marginType");
+        builder.add_kv("symbol", self.get_specific_currency_pair(currency_pair));
+        let margin_type_value = match margin_type {
+            MarginType::Cross => "CROSSED",
+            MarginType::Isolated => "ISOLATED",
+        };
+        builder.add_kv("marginType", margin_type_value);
+        self.add_authentification(&mut builder);
+
+        let (uri, query) = builder.build_uri_and_query(self.hosts.rest_uri_host(), false);
+
+        let log_args = format!("Change margin type for {currency_pair} to {margin_type_value}");
+        self.rest_client
+            .post(uri, Some(query), function_name!(), log_args)
+            .await
+    }
+
+    /// Handles balance and position changes from futures user data stream. The stream contains
+    /// only changed balances and positions, so they are merged into the last known state received
+    /// by requests. Positions are published only if leverage and liquidation price of all of them
+    /// are known from the last positions request
+    pub(super) fn handle_account_update(&self, data: &Value) -> Result<()> {
+        let account_update = BinanceAccountUpdate::deserialize(data)
+            .context("Unable to parse Binance account update")?;
+
+        let balances = {
+            let mut last_balances = self.balances.lock();
+            let last_balances = match last_balances.as_mut() {
+                Some(last_balances) => last_balances,
+                None => {
+                    log::warn!(
+                        "Binance account update is skipped because balances aren't received yet"
+                    );
+                    return Ok(());
+                }
+            };
+
+            for balance in &account_update.balances {
+                if let Some(currency_code) = self.get_currency_code(&balance.asset.as_str().into())
+                {
+                    let _ = last_balances.insert(currency_code, balance.wallet_balance);
+                }
+            }
+
+            last_balances
+                .iter()
+                .map(|(&currency_code, &balance)| ExchangeBalance {
+                    currency_code,
+                    balance,
+                })
+                .collect_vec()
+        };
+
+        let mut is_positions_known = true;
+        for position in &account_update.positions {
+            let currency_pair = match self
+                .specific_to_unified
+                .read()
+                .get(&position.specific_currency_pair)
+            {
+                Some(currency_pair) => *currency_pair,
+                None => continue,
+            };
+
+            if position.position_amount.is_zero() {
+                let _ = self.positions.remove(&currency_pair);
+                continue;
+            }
+
+            match self.positions.get_mut(&currency_pair) {
+                Some(mut last_position) => {
+                    last_position.position = position.position_amount;
+                    last_position.average_entry_price = position.average_entry_price;
+                }
+                None => {
+                    log::warn!("Leverage and liquidation price of new Binance position {currency_pair} are unknown until positions request");
+                    is_positions_known = false;
+                }
+            }
+        }
+
+        let positions = is_positions_known.then(|| {
+            self.positions
+                .iter()
+                .map(|x| x.value().clone())
+                .collect_vec()
+        });
+
+        let event = ExchangeEvent::BalanceUpdate(BalanceUpdateEvent {
+            exchange_account_id: self.id,
+            balances_and_positions: ExchangeBalancesAndPositions {
+                balances,
+                positions,
+            },
+        });
+
+        send_event(
+            &self.events_channel,
+            self.lifetime_manager.clone(),
+            self.id,
+            event,
+        )
+    }
+
     #[named]
     pub(super) async fn request_get_balance(&self) -> Result<RestResponse, ExchangeError> {
         let path = self.get_uri_path("/fapi/v2/account", "/api/v3/account");
@@ -913,6 +1123,10 @@ impl Binance {
                             builder.add_kv("timeInForce", "GTC");
                         }
                         OrderExecutionType::MakerOnly => builder.add_kv("type", "LIMIT_MAKER"),
+                        OrderExecutionType::ImmediateOrCancel => {
+                            builder.add_kv("type", "LIMIT");
+                            builder.add_kv("timeInForce", "IOC");
+                        }
                     }
                     builder.add_kv("price", price);
                 }
@@ -942,9 +1156,12 @@ impl Binance {
                 } => {
                     builder.add_kv("type", "LIMIT");
                     builder.add_kv("price", price);
-                    match *execution_type == OrderExecutionType::MakerOnly {
-                        true => builder.add_kv("timeInForce", "GTX"),
-                        false => builder.add_kv("timeInForce", "GTC"),
+                    match execution_type {
+                        OrderExecutionType::None => builder.add_kv("timeInForce", "GTC"),
+                        OrderExecutionType::MakerOnly => builder.add_kv("timeInForce", "GTX"),
+                        OrderExecutionType::ImmediateOrCancel => {
+                            builder.add_kv("timeInForce", "IOC")
+                        }
                     }
                 }
                 UserOrder::Market => builder.add_kv("type", "MARKET"),
@@ -970,6 +1187,365 @@ impl Binance {
             .await
     }
 
+    #[named]
+    pub(super) async fn request_create_oco_order(
+        &self,
+        group_id: OrderGroupId,
+        legs: &OcoLegs<'_>,
+    ) -> Result<RestResponse, ExchangeError> {
+        let header = legs.limit;
+        let specific_currency_pair = self.get_specific_currency_pair(header.currency_pair);
+
+        let mut builder = UriBuilder::from_path("/api/v3/order/oco");
+        builder.add_kv("symbol", specific_currency_pair);
+        builder.add_kv("listClientOrderId", group_id);
+        builder.add_kv("side", get_server_order_side(header.side));
+        builder.add_kv("quantity", header.amount);
+        builder.add_kv("limitClientOrderId", &header.client_order_id);
+        builder.add_kv("price", legs.price);
+        builder.add_kv("stopClientOrderId", &legs.stop_loss.client_order_id);
+        builder.add_kv("stopPrice", legs.stop_price);
+        self.add_authentification(&mut builder);
+
+        let (uri, query) = builder.build_uri_and_query(self.hosts.rest_uri_host(), false);
+
+        let log_args = format!("Create OCO order {group_id} for {legs:?}");
+        self.rest_client
+            .post(uri, Some(query), function_name!(), log_args)
+            .await
+    }
+
+    pub(super) fn parse_order_list(
+        response: &RestResponse,
+    ) -> Result<CreateOrderGroupResult, ExchangeError> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct OrderListOrder {
+            order_id: u64,
+            client_order_id: ClientOrderId,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct OrderList {
+            order_list_id: i64,
+            orders: Vec<OrderListOrder>,
+        }
+
+        let deserialized: OrderList = serde_json::from_str(&response.content).map_err(|err| {
+            ExchangeError::parsing(format!("Unable to parse order list: {err:?}"))
+        })?;
+
+        Ok(CreateOrderGroupResult {
+            exchange_group_id: deserialized.order_list_id.to_string().as_str().into(),
+            exchange_order_ids: deserialized
+                .orders
+                .into_iter()
+                .map(|x| (x.client_order_id, x.order_id.to_string().as_str().into()))
+                .collect(),
+        })
+    }
+
+    fn get_transfer_account_type(&self) -> &'static str {
+        match self.settings.is_margin_trading {
+            true => "USDT_FUTURE",
+            false => "SPOT",
+        }
+    }
+
+    fn get_currency_id(&self, currency_code: CurrencyCode) -> CurrencyId {
+        self.supported_currencies
+            .iter()
+            .find(|x| *x.value() == currency_code)
+            .map(|x| *x.key())
+            .unwrap_or_else(|| currency_code.as_str().to_uppercase().as_str().into())
+    }
+
+    /// Transfers between master account and sub-accounts are made by universal transfer endpoint
+    /// which is available only for API key of master account
+    #[named]
+    pub(super) async fn request_universal_transfer(
+        &self,
+        transfer: &AccountTransfer,
+        from_sub_account: Option<&str>,
+        to_sub_account: Option<&str>,
+    ) -> Result<RestResponse, ExchangeError> {
+        let account_type = self.get_transfer_account_type();
+
+        let mut builder = UriBuilder::from_path("/sapi/v1/sub-account/universalTransfer");
+        if let Some(from_email) = from_sub_account {
+            builder.add_kv("fromEmail", from_email);
+        }
+        if let Some(to_email) = to_sub_account {
+            builder.add_kv("toEmail", to_email);
+        }
+        builder.add_kv("fromAccountType", account_type);
+        builder.add_kv("toAccountType", account_type);
+        builder.add_kv("asset", self.get_currency_id(transfer.currency_code));
+        builder.add_kv("amount", transfer.amount);
+        self.add_authentification(&mut builder);
+
+        // sub-account endpoints are served only by spot host
+        let host = Binance::make_hosts(false).rest_host;
+        let (uri, query) = builder.build_uri_and_query(host, false);
+
+        let log_args = format!("Transfer {transfer:?}");
+        self.rest_client
+            .post(uri, Some(query), function_name!(), log_args)
+            .await
+    }
+
+    #[named]
+    pub(super) async fn request_transfer_status(
+        &self,
+        transfer_id: &ExchangeTransferId,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/sapi/v1/sub-account/universalTransfer");
+        builder.add_kv("tranId", transfer_id);
+        self.add_authentification(&mut builder);
+
+        let host = Binance::make_hosts(false).rest_host;
+        let uri = builder.build_uri(host, true);
+
+        self.rest_client
+            .get(uri, function_name!(), format!("Transfer {transfer_id}"))
+            .await
+    }
+
+    pub(super) fn parse_transfer_id(
+        response: &RestResponse,
+    ) -> Result<ExchangeTransferId, ExchangeError> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct TransferResponse {
+            tran_id: u64,
+        }
+
+        let deserialized: TransferResponse =
+            serde_json::from_str(&response.content).map_err(|err| {
+                ExchangeError::parsing(format!("Unable to parse transfer response: {err:?}"))
+            })?;
+
+        Ok(deserialized.tran_id.to_string().as_str().into())
+    }
+
+    pub(super) fn parse_transfer_status(
+        response: &RestResponse,
+        transfer_id: &ExchangeTransferId,
+    ) -> Result<TransferStatus, ExchangeError> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct TransferHistoryRecord {
+            tran_id: u64,
+            status: String,
+        }
+
+        #[derive(Deserialize)]
+        struct TransferHistory {
+            result: Vec<TransferHistoryRecord>,
+        }
+
+        let deserialized: TransferHistory =
+            serde_json::from_str(&response.content).map_err(|err| {
+                ExchangeError::parsing(format!("Unable to parse transfer history: {err:?}"))
+            })?;
+
+        // transfer can be absent in history right after creation
+        let status = match deserialized
+            .result
+            .iter()
+            .find(|x| x.tran_id.to_string() == transfer_id.as_str())
+        {
+            Some(record) => match record.status.as_str() {
+                "SUCCESS" => TransferStatus::Completed,
+                "FAILURE" => TransferStatus::Failed,
+                _ => TransferStatus::Pending,
+            },
+            None => TransferStatus::Pending,
+        };
+
+        Ok(status)
+    }
+
+    #[named]
+    pub(super) async fn request_withdraw(
+        &self,
+        withdrawal: &Withdrawal,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/sapi/v1/capital/withdraw/apply");
+        builder.add_kv("coin", self.get_currency_id(withdrawal.currency_code));
+        builder.add_kv("address", &withdrawal.address);
+        if let Some(address_tag) = &withdrawal.address_tag {
+            builder.add_kv("addressTag", address_tag);
+        }
+        if let Some(network) = &withdrawal.network {
+            builder.add_kv("network", network);
+        }
+        builder.add_kv("amount", withdrawal.amount);
+        self.add_authentification(&mut builder);
+
+        // capital endpoints are served only by spot host
+        let host = Binance::make_hosts(false).rest_host;
+        let (uri, query) = builder.build_uri_and_query(host, false);
+
+        let log_args = format!("Withdraw {withdrawal:?}");
+        self.rest_client
+            .post(uri, Some(query), function_name!(), log_args)
+            .await
+    }
+
+    pub(super) fn parse_withdrawal_id(
+        response: &RestResponse,
+    ) -> Result<ExchangeFundsMovementId, ExchangeError> {
+        #[derive(Deserialize)]
+        struct WithdrawResponse {
+            id: String,
+        }
+
+        let deserialized: WithdrawResponse =
+            serde_json::from_str(&response.content).map_err(|err| {
+                ExchangeError::parsing(format!("Unable to parse withdraw response: {err:?}"))
+            })?;
+
+        Ok(deserialized.id.as_str().into())
+    }
+
+    #[named]
+    pub(super) async fn request_funds_movements_history(
+        &self,
+        path: &str,
+        from_datetime: DateTime,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path(path);
+        builder.add_kv("startTime", from_datetime.timestamp_millis());
+        self.add_authentification(&mut builder);
+
+        let host = Binance::make_hosts(false).rest_host;
+        let uri = builder.build_uri(host, true);
+
+        self.rest_client
+            .get(uri, function_name!(), format!("From {from_datetime}"))
+            .await
+    }
+
+    /// Status is requested from spot API for both spot and futures accounts
+    #[named]
+    pub(super) async fn request_system_status(&self) -> Result<RestResponse, ExchangeError> {
+        let builder = UriBuilder::from_path("/sapi/v1/system/status");
+        let host = Binance::make_hosts(false).rest_host;
+        let uri = builder.build_uri(host, true);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    fn get_funds_movement_currency_code(&self, coin: &str) -> CurrencyCode {
+        self.get_currency_code(&coin.into())
+            .unwrap_or_else(|| coin.to_lowercase().as_str().into())
+    }
+
+    pub(super) fn parse_commission(
+        &self,
+        response: &RestResponse,
+    ) -> Result<Commission, ExchangeError> {
+        parse_spot_commission(&response.content)
+    }
+
+    pub(super) fn parse_deposit_history(
+        &self,
+        response: &RestResponse,
+    ) -> Result<Vec<FundsMovement>, ExchangeError> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Deposit {
+            id: String,
+            amount: Decimal,
+            coin: String,
+            status: u8,
+            tx_id: Option<String>,
+            insert_time: u64,
+        }
+
+        let deposits: Vec<Deposit> = serde_json::from_str(&response.content).map_err(|err| {
+            ExchangeError::parsing(format!("Unable to parse deposit history: {err:?}"))
+        })?;
+
+        Ok(deposits
+            .into_iter()
+            .map(|x| FundsMovement {
+                id: x.id.as_str().into(),
+                movement_type: FundsMovementType::Deposit,
+                exchange_account_id: self.id,
+                currency_code: self.get_funds_movement_currency_code(&x.coin),
+                amount: x.amount,
+                fee: Decimal::ZERO,
+                status: match x.status {
+                    // 1 - success, 6 - credited but cannot withdraw
+                    1 | 6 => TransferStatus::Completed,
+                    // 7 - wrong deposit, 2 - rejected
+                    2 | 7 => TransferStatus::Failed,
+                    _ => TransferStatus::Pending,
+                },
+                transaction_id: x.tx_id.filter(|x| !x.is_empty()),
+                time: u64_to_date_time(x.insert_time),
+            })
+            .collect())
+    }
+
+    pub(super) fn parse_withdraw_history(
+        &self,
+        response: &RestResponse,
+    ) -> Result<Vec<FundsMovement>, ExchangeError> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct WithdrawRecord {
+            id: String,
+            amount: Decimal,
+            transaction_fee: Decimal,
+            coin: String,
+            status: u8,
+            tx_id: Option<String>,
+            apply_time: String,
+        }
+
+        let withdrawals: Vec<WithdrawRecord> =
+            serde_json::from_str(&response.content).map_err(|err| {
+                ExchangeError::parsing(format!("Unable to parse withdraw history: {err:?}"))
+            })?;
+
+        withdrawals
+            .into_iter()
+            .map(|x| {
+                let apply_time = NaiveDateTime::parse_from_str(&x.apply_time, "%Y-%m-%d %H:%M:%S")
+                    .map_err(|err| {
+                        ExchangeError::parsing(format!(
+                            "Unable to parse apply time of withdrawal {}: {err:?}",
+                            x.id
+                        ))
+                    })?;
+                let time = DateTime::from_utc(apply_time, Utc);
+
+                Ok(FundsMovement {
+                    id: x.id.as_str().into(),
+                    movement_type: FundsMovementType::Withdrawal,
+                    exchange_account_id: self.id,
+                    currency_code: self.get_funds_movement_currency_code(&x.coin),
+                    amount: x.amount,
+                    fee: x.transaction_fee,
+                    status: match x.status {
+                        6 => TransferStatus::Completed,
+                        // 1 - cancelled, 3 - rejected, 5 - failure
+                        1 | 3 | 5 => TransferStatus::Failed,
+                        _ => TransferStatus::Pending,
+                    },
+                    transaction_id: x.tx_id.filter(|x| !x.is_empty()),
+                    time,
+                })
+            })
+            .collect()
+    }
+
     #[named]
     pub(super) async fn request_all_symbols(&self) -> Result<RestResponse, ExchangeError> {
         let path = self.get_uri_path("/fapi/v1/exchangeInfo", "/api/v3/exchangeInfo");
@@ -1076,7 +1652,12 @@ impl Binance {
                 ),
             };
 
-            let symbol = Symbol::new(
+            let status = match symbol["status"] == "TRADING" {
+                true => SymbolStatus::Trading,
+                false => SymbolStatus::Halted,
+            };
+
+            let mut symbol = Symbol::new(
                 self.settings.is_margin_trading,
                 base_currency_id.as_str().into(),
                 base,
@@ -1092,6 +1673,7 @@ impl Binance {
                 price_precision,
                 amount_precision,
             );
+            symbol.status = status;
 
             supported_symbols.push(Arc::new(symbol))
         }
@@ -1105,7 +1687,12 @@ impl Binance {
             .expect("Unable to get symbol code from Binance");
 
         // Binance adds "_<NUMBERS>" to old symbol's code
-        code.contains('_') || symbol["status"] != "TRADING"
+        // Symbols with trading halted temporarily are kept to pause orders for them
+        code.contains('_')
+            || !matches!(
+                symbol["status"].as_str(),
+                Some("TRADING" | "HALT" | "BREAK")
+            )
     }
 
     pub(super) fn get_event_time(data: &Value) -> Result<DateTime> {
@@ -1140,6 +1727,50 @@ impl Binance {
     }
 }
 
+/// Legs of order list which can be created through OCO endpoint of Binance spot API:
+/// limit order and stop-loss order with the same side and amount
+#[derive(Debug)]
+pub(super) struct OcoLegs<'a> {
+    pub limit: &'a OrderHeader,
+    pub price: Price,
+    pub stop_loss: &'a OrderHeader,
+    pub stop_price: Price,
+}
+
+pub(super) fn get_oco_legs<'a>(
+    first: &'a OrderHeader,
+    second: &'a OrderHeader,
+) -> Option<OcoLegs<'a>> {
+    fn get_limit_price(header: &OrderHeader) -> Option<Price> {
+        match header.options {
+            OrderOptions::User(UserOrder::Limit { price, .. }) => Some(price),
+            _ => None,
+        }
+    }
+
+    fn get_stop_price(header: &OrderHeader) -> Option<Price> {
+        match header.options {
+            OrderOptions::User(UserOrder::StopLoss { stop_price }) => Some(stop_price),
+            _ => None,
+        }
+    }
+
+    if first.side != second.side || first.amount != second.amount {
+        return None;
+    }
+
+    [(first, second), (second, first)]
+        .into_iter()
+        .find_map(|(limit, stop_loss)| {
+            Some(OcoLegs {
+                limit,
+                price: get_limit_price(limit)?,
+                stop_loss,
+                stop_price: get_stop_price(stop_loss)?,
+            })
+        })
+}
+
 pub(super) fn get_server_order_side(side: OrderSide) -> &'static str {
     match side {
         OrderSide::Buy => "BUY",
@@ -1157,10 +1788,11 @@ pub(super) fn get_local_order_side(side: &str) -> OrderSide {
 
 fn get_local_order_status(status: &str) -> OrderStatus {
     match status {
-        "NEW" | "PARTIALLY_FILLED" => OrderStatus::Created,
+        // NEW_INSURANCE and NEW_ADL are futures liquidation orders
+        "NEW" | "PARTIALLY_FILLED" | "NEW_INSURANCE" | "NEW_ADL" => OrderStatus::Created,
         "FILLED" => OrderStatus::Completed,
         "PENDING_CANCEL" => OrderStatus::Canceling,
-        "CANCELED" | "EXPIRED" | "REJECTED" => OrderStatus::Canceled,
+        "CANCELED" | "EXPIRED" | "EXPIRED_IN_MATCH" | "REJECTED" => OrderStatus::Canceled,
         _ => panic!("Unexpected order status"),
     }
 }
@@ -1213,6 +1845,50 @@ impl ExchangeClientBuilder for BinanceBuilder {
     }
 }
 
+/// Commission rates of spot account info are rates, not percents
+fn parse_spot_commission(content: &str) -> Result<Commission, ExchangeError> {
+    #[derive(Deserialize)]
+    struct CommissionRates {
+        maker: Decimal,
+        taker: Decimal,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct AccountInfo {
+        commission_rates: CommissionRates,
+    }
+
+    let account_info: AccountInfo = serde_json::from_str(content).map_err(|err| {
+        ExchangeError::parsing(format!("Unable to parse commission rates: {err:?}"))
+    })?;
+
+    let rates = account_info.commission_rates;
+    let percent = |rate: Decimal| rate * Decimal::ONE_HUNDRED;
+    Ok(Commission::new(
+        CommissionForType::new(percent(rates.maker), Decimal::ZERO),
+        CommissionForType::new(percent(rates.taker), Decimal::ZERO),
+    ))
+}
+
+pub(super) fn parse_system_status(content: &str) -> Result<SystemStatus, ExchangeError> {
+    #[derive(Deserialize)]
+    struct Status {
+        status: u8,
+        msg: String,
+    }
+
+    let status: Status = serde_json::from_str(content)
+        .map_err(|err| ExchangeError::parsing(format!("Unable to parse system status: {err:?}")))?;
+
+    Ok(match status.status {
+        0 => SystemStatus::Normal,
+        _ => SystemStatus::Maintenance {
+            message: status.msg,
+        },
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1220,6 +1896,7 @@ mod tests {
     use mmb_core::lifecycle::launcher::EngineBuildConfig;
     use mmb_utils::cancellation_token::CancellationToken;
     use mmb_utils::hashmap;
+    use rust_decimal_macros::dec;
 
     pub(crate) fn get_timeout_manager(
         exchange_account_id: ExchangeAccountId,
@@ -1279,4 +1956,307 @@ mod tests {
 
         assert_eq!(signature_value, expected);
     }
+
+    fn order_header(side: OrderSide, amount: Amount, user_order: UserOrder) -> OrderHeader {
+        OrderHeader::with_user_order(
+            ClientOrderId::unique_id(),
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            side,
+            amount,
+            user_order,
+            None,
+            None,
+            "test".to_owned(),
+        )
+    }
+
+    #[test]
+    fn commission_rates_are_parsed_as_percents() {
+        let content = r#"{
+            "makerCommission": 10,
+            "takerCommission": 10,
+            "commissionRates": {
+                "maker": "0.00075000",
+                "taker": "0.00100000",
+                "buyer": "0.00000000",
+                "seller": "0.00000000"
+            },
+            "balances": []
+        }"#;
+
+        let commission = parse_spot_commission(content).expect("in test");
+
+        assert_eq!(commission.maker.fee, dec!(0.075));
+        assert_eq!(commission.taker.fee, dec!(0.1));
+    }
+
+    #[test]
+    fn oco_legs_in_any_order() {
+        let limit = order_header(OrderSide::Sell, dec!(1), UserOrder::limit(dec!(110)));
+        let stop_loss = order_header(
+            OrderSide::Sell,
+            dec!(1),
+            UserOrder::StopLoss {
+                stop_price: dec!(90),
+            },
+        );
+
+        for (first, second) in [(&limit, &stop_loss), (&stop_loss, &limit)] {
+            let legs = get_oco_legs(first, second).expect("in test");
+            assert_eq!(legs.limit.client_order_id, limit.client_order_id);
+            assert_eq!(legs.price, dec!(110));
+            assert_eq!(legs.stop_loss.client_order_id, stop_loss.client_order_id);
+            assert_eq!(legs.stop_price, dec!(90));
+        }
+    }
+
+    #[test]
+    fn not_oco_legs() {
+        let limit = order_header(OrderSide::Sell, dec!(1), UserOrder::limit(dec!(110)));
+        let stop_loss = |side, amount| {
+            order_header(
+                side,
+                amount,
+                UserOrder::StopLoss {
+                    stop_price: dec!(90),
+                },
+            )
+        };
+
+        assert!(get_oco_legs(&limit, &stop_loss(OrderSide::Buy, dec!(1))).is_none());
+        assert!(get_oco_legs(&limit, &stop_loss(OrderSide::Sell, dec!(2))).is_none());
+        assert!(get_oco_legs(&limit, &limit).is_none());
+    }
+
+    #[test]
+    fn parse_oco_order_list() {
+        let response = RestResponse {
+            status: hyper::StatusCode::OK,
+            content: r#"{"orderListId":7,"contingencyType":"OCO","listStatusType":"EXEC_STARTED","listOrderStatus":"EXECUTING","listClientOrderId":"1668679200","transactionTime":1668679200000,"symbol":"BTCUSDT","orders":[{"symbol":"BTCUSDT","orderId":20,"clientOrderId":"stop"},{"symbol":"BTCUSDT","orderId":21,"clientOrderId":"limit"}],"orderReports":[]}"#.to_owned(),
+        };
+
+        let result = Binance::parse_order_list(&response).expect("in test");
+
+        assert_eq!(result.exchange_group_id, "7".into());
+        assert_eq!(
+            result.exchange_order_ids,
+            vec![("stop".into(), "20".into()), ("limit".into(), "21".into())]
+        );
+    }
+
+    #[test]
+    fn parse_universal_transfer_status() {
+        let response = |content: &str| RestResponse {
+            status: hyper::StatusCode::OK,
+            content: content.to_owned(),
+        };
+
+        let transfer_id = Binance::parse_transfer_id(&response(
+            r#"{"tranId":11945860693,"clientTranId":"test"}"#,
+        ))
+        .expect("in test");
+        assert_eq!(transfer_id, "11945860693".into());
+
+        let history = |status: &str| {
+            response(&format!(
+                r#"{{"result":[{{"tranId":11945860693,"fromEmail":"","toEmail":"sub@test.com","asset":"BTC","amount":"0.1","createTimeStamp":1640317374000,"fromAccountType":"SPOT","toAccountType":"SPOT","status":"{status}","clientTranId":"test"}}],"totalCount":1}}"#
+            ))
+        };
+        let status = |response: RestResponse| {
+            Binance::parse_transfer_status(&response, &transfer_id).expect("in test")
+        };
+
+        assert_eq!(status(history("SUCCESS")), TransferStatus::Completed);
+        assert_eq!(status(history("FAILURE")), TransferStatus::Failed);
+        assert_eq!(status(history("PROCESS")), TransferStatus::Pending);
+        assert_eq!(
+            status(response(r#"{"result":[],"totalCount":0}"#)),
+            TransferStatus::Pending
+        );
+    }
+
+    #[test]
+    fn parse_funds_movements_history() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let settings =
+            ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), false);
+        let (tx, _) = broadcast::channel(10);
+        let binance = Binance::new(
+            exchange_account_id,
+            settings,
+            tx,
+            AppLifetimeManager::new(CancellationToken::default()),
+            get_timeout_manager(exchange_account_id),
+            false,
+        );
+        let _ = binance
+            .supported_currencies
+            .insert("USDT".into(), "usdt".into());
+        let response = |content: &str| RestResponse {
+            status: hyper::StatusCode::OK,
+            content: content.to_owned(),
+        };
+
+        let deposits = binance
+            .parse_deposit_history(&response(
+                r#"[{"id":"769800519366885376","amount":"0.5","coin":"USDT","network":"BSC","status":1,"address":"0xa","addressTag":"","txId":"0xb","insertTime":1661493146000,"transferType":0,"confirmTimes":"10/10","unlockConfirm":0,"walletType":0},{"id":"769754833590042625","amount":"2","coin":"BTC","network":"BTC","status":0,"address":"1a","addressTag":"","txId":"","insertTime":1661482251000,"transferType":0,"confirmTimes":"0/1","unlockConfirm":0,"walletType":0}]"#,
+            ))
+            .expect("in test");
+        assert_eq!(deposits.len(), 2);
+        assert_eq!(deposits[0].currency_code, "usdt".into());
+        assert_eq!(deposits[0].amount, dec!(0.5));
+        assert_eq!(deposits[0].status, TransferStatus::Completed);
+        assert_eq!(deposits[0].transaction_id, Some("0xb".to_owned()));
+        assert_eq!(deposits[1].currency_code, "btc".into());
+        assert_eq!(deposits[1].status, TransferStatus::Pending);
+        assert_eq!(deposits[1].transaction_id, None);
+
+        let withdrawals = binance
+            .parse_withdraw_history(&response(
+                r#"[{"id":"b6ae22b3aa844210a7041aee7589627c","amount":"8.91","transactionFee":"0.004","coin":"USDT","status":6,"address":"0x94","txId":"0xb5","applyTime":"2019-10-12 11:12:02","network":"ETH","transferType":0,"info":"","confirmNo":3,"walletType":1,"txKey":""}]"#,
+            ))
+            .expect("in test");
+        assert_eq!(withdrawals.len(), 1);
+        assert_eq!(withdrawals[0].id, "b6ae22b3aa844210a7041aee7589627c".into());
+        assert_eq!(withdrawals[0].movement_type, FundsMovementType::Withdrawal);
+        assert_eq!(withdrawals[0].fee, dec!(0.004));
+        assert_eq!(withdrawals[0].status, TransferStatus::Completed);
+        assert_eq!(
+            withdrawals[0].time.to_rfc3339(),
+            "2019-10-12T11:12:02+00:00"
+        );
+    }
+
+    fn binance_with_account_state() -> (Binance, broadcast::Receiver<ExchangeEvent>) {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let settings = ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), true);
+
+        let (tx, rx) = broadcast::channel(10);
+        let binance = Binance::new(
+            exchange_account_id,
+            settings,
+            tx,
+            AppLifetimeManager::new(CancellationToken::default()),
+            get_timeout_manager(exchange_account_id),
+            false,
+        );
+
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let _ = binance
+            .specific_to_unified
+            .write()
+            .insert("BTCUSDT".into(), currency_pair);
+        let _ = binance
+            .supported_currencies
+            .insert("USDT".into(), "usdt".into());
+
+        (binance, rx)
+    }
+
+    fn account_update(positions: &str) -> Value {
+        serde_json::from_str(&format!(
+            r#"{{"m":"ORDER","B":[{{"a":"USDT","wb":"122.6","cw":"100.1","bc":"0"}}],"P":[{positions}]}}"#
+        ))
+        .expect("in test")
+    }
+
+    fn recv_balances_and_positions(
+        rx: &mut broadcast::Receiver<ExchangeEvent>,
+    ) -> ExchangeBalancesAndPositions {
+        match rx.try_recv().expect("in test") {
+            ExchangeEvent::BalanceUpdate(event) => event.balances_and_positions,
+            _ => panic!("Unexpected event"),
+        }
+    }
+
+    const BTC_POSITION: &str = r#"{"s":"BTCUSDT","pa":"0.5","ep":"110.0","cr":"200","up":"0","mt":"isolated","iw":"0","ps":"BOTH"}"#;
+
+    #[test]
+    fn account_update_is_merged_into_last_known_state() {
+        let (binance, mut rx) = binance_with_account_state();
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        *binance.balances.lock() = Some(hashmap![
+            "usdt".into() => dec!(100),
+            "bnb".into() => dec!(3)
+        ]);
+        let _ = binance.positions.insert(
+            currency_pair,
+            DerivativePosition::new(currency_pair, dec!(1), dec!(100), dec!(50), dec!(20)),
+        );
+
+        binance
+            .handle_account_update(&account_update(BTC_POSITION))
+            .expect("in test");
+
+        let balances_and_positions = recv_balances_and_positions(&mut rx);
+        let balances = balances_and_positions
+            .balances
+            .iter()
+            .map(|x| (x.currency_code, x.balance))
+            .collect::<HashMap<_, _>>();
+        // balance which isn't changed is kept
+        assert_eq!(
+            balances,
+            hashmap!["bnb".into() => dec!(3), "usdt".into() => dec!(122.6)]
+        );
+
+        let positions = balances_and_positions.positions.expect("in test");
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].position, dec!(0.5));
+        assert_eq!(positions[0].average_entry_price, dec!(110));
+        assert_eq!(positions[0].liquidation_price, dec!(50));
+        assert_eq!(positions[0].leverage, dec!(20));
+    }
+
+    #[test]
+    fn account_update_is_skipped_until_balances_received() {
+        let (binance, mut rx) = binance_with_account_state();
+
+        binance
+            .handle_account_update(&account_update(BTC_POSITION))
+            .expect("in test");
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn positions_of_account_update_are_not_published_if_unknown() {
+        let (binance, mut rx) = binance_with_account_state();
+        *binance.balances.lock() = Some(HashMap::new());
+
+        binance
+            .handle_account_update(&account_update(BTC_POSITION))
+            .expect("in test");
+
+        let balances_and_positions = recv_balances_and_positions(&mut rx);
+        assert_eq!(balances_and_positions.balances.len(), 1);
+        assert!(balances_and_positions.positions.is_none());
+    }
+
+    #[test]
+    fn maintenance_system_status() {
+        assert_eq!(
+            parse_system_status(r#"{"status": 0, "msg": "normal"}"#).expect("in test"),
+            SystemStatus::Normal
+        );
+        assert_eq!(
+            parse_system_status(r#"{"status": 1, "msg": "system maintenance"}"#).expect("in test"),
+            SystemStatus::Maintenance {
+                message: "system maintenance".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn futures_order_statuses() {
+        assert_eq!(
+            get_local_order_status("NEW_INSURANCE"),
+            OrderStatus::Created
+        );
+        assert_eq!(
+            get_local_order_status("EXPIRED_IN_MATCH"),
+            OrderStatus::Canceled
+        );
+    }
 }
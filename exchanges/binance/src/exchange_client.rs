@@ -1,4 +1,4 @@
-use super::binance::Binance;
+use super::binance::{get_oco_legs, parse_system_status, Binance};
 use crate::support::BinanceOrderInfo;
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
@@ -8,16 +8,24 @@ use mmb_core::exchanges::general::exchange::RequestResult;
 use mmb_core::exchanges::general::order::cancel::CancelOrderResult;
 use mmb_core::exchanges::general::order::create::CreateOrderResult;
 use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::general::order::group::CreateOrderGroupResult;
 use mmb_core::exchanges::general::request_type::RequestType;
 use mmb_core::exchanges::rest_client::UriBuilder;
 use mmb_core::exchanges::traits::{ExchangeClient, ExchangeError, Support};
 use mmb_domain::events::{EventSourceType, ExchangeBalancesAndPositions};
+use mmb_domain::exchanges::commission::Commission;
 use mmb_domain::exchanges::symbol::Symbol;
+use mmb_domain::exchanges::system_status::SystemStatus;
 use mmb_domain::market::CurrencyPair;
+use mmb_domain::order::group::{OrderGroupId, OrderGroupType};
 use mmb_domain::order::pool::OrderRef;
 use mmb_domain::order::snapshot::Price;
 use mmb_domain::order::snapshot::*;
-use mmb_domain::position::{ActivePosition, ClosedPosition};
+use mmb_domain::position::{ActivePosition, ClosedPosition, DerivativePosition};
+use mmb_domain::transfer::{
+    AccountTransfer, ExchangeFundsMovementId, ExchangeTransferId, FundsMovement, TransferStatus,
+    Withdrawal,
+};
 use mmb_utils::DateTime;
 use std::sync::Arc;
 
@@ -50,7 +58,8 @@ impl ExchangeClient for Binance {
     async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
         let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
 
-        let mut builder = UriBuilder::from_path("/api/v3/openOrders");
+        let path = self.get_uri_path("/fapi/v1/allOpenOrders", "/api/v3/openOrders");
+        let mut builder = UriBuilder::from_path(path);
         builder.add_kv("symbol", specific_currency_pair);
         self.add_authentification(&mut builder);
 
@@ -111,17 +120,22 @@ impl ExchangeClient for Binance {
     async fn get_balance_and_positions(&self) -> Result<ExchangeBalancesAndPositions> {
         // Binance does return positions from GET request /fapi/v2/account but without liquidation_price field
         // so we have to use separate requests for balance and positions
-        Ok(match self.settings.is_margin_trading {
+        let balances_and_positions = match self.settings.is_margin_trading {
             true => {
                 let (balance_response, position_response) =
                     tokio::join!(self.request_get_balance(), self.request_get_position());
+                let positions: Vec<DerivativePosition> = self
+                    .get_active_positions(&position_response?)?
+                    .map(|position| Ok::<_, anyhow::Error>(position?.derivative))
+                    .try_collect()?;
+                // closed positions aren't returned by exchange
+                self.positions.retain(|currency_pair, _| {
+                    positions.iter().any(|x| x.currency_pair == *currency_pair)
+                });
+
                 ExchangeBalancesAndPositions {
                     balances: self.parse_derivative_balance(&balance_response?)?,
-                    positions: Some(
-                        self.get_active_positions(&position_response?)?
-                            .map(|position| Ok::<_, anyhow::Error>(position?.derivative))
-                            .try_collect()?,
-                    ),
+                    positions: Some(positions),
                 }
             }
             false => {
@@ -131,7 +145,17 @@ impl ExchangeClient for Binance {
                     positions: None,
                 }
             }
-        })
+        };
+
+        *self.balances.lock() = Some(
+            balances_and_positions
+                .balances
+                .iter()
+                .map(|x| (x.currency_code, x.balance))
+                .collect(),
+        );
+
+        Ok(balances_and_positions)
     }
 
     async fn get_my_trades(
@@ -160,6 +184,105 @@ impl ExchangeClient for Binance {
             Err(err) => Some(Err(anyhow!("Get server time request failed: {err:?}"))),
         }
     }
+
+    fn supports_native_order_group(
+        &self,
+        group_type: OrderGroupType,
+        legs: &[OrderHeader],
+    ) -> bool {
+        match (group_type, legs) {
+            (OrderGroupType::Oco, [first, second]) => {
+                !self.settings.is_margin_trading && get_oco_legs(first, second).is_some()
+            }
+            _ => false,
+        }
+    }
+
+    async fn create_order_group(
+        &self,
+        group_id: OrderGroupId,
+        group_type: OrderGroupType,
+        legs: &[OrderRef],
+    ) -> Result<CreateOrderGroupResult, ExchangeError> {
+        let oco_legs = match (group_type, legs) {
+            (OrderGroupType::Oco, [first, second]) => get_oco_legs(first.header(), second.header()),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            ExchangeError::unknown(&format!(
+                "Legs of order group {group_id} can't be created as Binance OCO order"
+            ))
+        })?;
+
+        let response = self.request_create_oco_order(group_id, &oco_legs).await?;
+        Binance::parse_order_list(&response)
+    }
+
+    async fn transfer_between_accounts(
+        &self,
+        transfer: &AccountTransfer,
+        from_sub_account: Option<&str>,
+        to_sub_account: Option<&str>,
+    ) -> Result<ExchangeTransferId, ExchangeError> {
+        let response = self
+            .request_universal_transfer(transfer, from_sub_account, to_sub_account)
+            .await?;
+        Binance::parse_transfer_id(&response)
+    }
+
+    async fn get_transfer_status(
+        &self,
+        transfer_id: &ExchangeTransferId,
+    ) -> Result<TransferStatus, ExchangeError> {
+        let response = self.request_transfer_status(transfer_id).await?;
+        Binance::parse_transfer_status(&response, transfer_id)
+    }
+
+    async fn withdraw(
+        &self,
+        withdrawal: &Withdrawal,
+    ) -> Result<ExchangeFundsMovementId, ExchangeError> {
+        let response = self.request_withdraw(withdrawal).await?;
+        Binance::parse_withdrawal_id(&response)
+    }
+
+    async fn get_commission(&self) -> Option<Result<Commission, ExchangeError>> {
+        // fees of futures account are reported only by symbol
+        if self.settings.is_margin_trading {
+            return None;
+        }
+
+        let response = match self.request_get_balance().await {
+            Ok(response) => response,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(self.parse_commission(&response))
+    }
+
+    async fn get_system_status(&self) -> Option<Result<SystemStatus, ExchangeError>> {
+        let response = match self.request_system_status().await {
+            Ok(response) => response,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(parse_system_status(&response.content))
+    }
+
+    async fn get_funds_movements(
+        &self,
+        from_datetime: DateTime,
+    ) -> Result<Vec<FundsMovement>, ExchangeError> {
+        let response = self
+            .request_funds_movements_history("/sapi/v1/capital/deposit/hisrec", from_datetime)
+            .await?;
+        let mut movements = self.parse_deposit_history(&response)?;
+
+        let response = self
+            .request_funds_movements_history("/sapi/v1/capital/withdraw/history", from_datetime)
+            .await?;
+        movements.extend(self.parse_withdraw_history(&response)?);
+
+        Ok(movements)
+    }
 }
 
 impl Binance {
@@ -1,4 +1,5 @@
 use mmb_utils::infrastructure::{SpawnFutureFlags, WithExpect};
+use mmb_utils::nothing_to_do;
 use std::any::Any;
 
 use anyhow::{anyhow, Context, Result};
@@ -8,13 +9,17 @@ use dashmap::DashMap;
 use itertools::Itertools;
 use mmb_domain::order::snapshot::{Amount, Price};
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use serde::de::{IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
+use std::fmt;
+use std::marker::PhantomData;
 use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
 
 use super::binance::Binance;
+use mmb_core::connectivity::json::parse_frame;
 use mmb_core::connectivity::WebSocketRole;
 use mmb_core::exchanges::common::send_event;
 use mmb_core::exchanges::general::exchange::Exchange;
@@ -23,6 +28,7 @@ use mmb_core::exchanges::traits::{
     HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb,
 };
 use mmb_core::infrastructure::spawn_by_timer;
+use mmb_core::secrets::ExchangeCredentials;
 use mmb_core::settings::ExchangeSettings;
 use mmb_domain::events::{
     EventSourceType, ExchangeEvent, MetricsEventInfo, MetricsEventType, Trade, TradeId,
@@ -105,6 +111,123 @@ pub(super) struct BinancePosition {
     pub(super) leverage: Decimal,
 }
 
+/// Corresponds https://binance-docs.github.io/apidocs/futures/en/#event-balance-and-position-update
+#[derive(Debug, Deserialize)]
+pub(super) struct BinanceAccountUpdate {
+    #[serde(rename = "B")]
+    pub(super) balances: Vec<BinanceAccountUpdateBalance>,
+    #[serde(rename = "P")]
+    pub(super) positions: Vec<BinanceAccountUpdatePosition>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct BinanceAccountUpdateBalance {
+    #[serde(rename = "a")]
+    pub(super) asset: String,
+    #[serde(rename = "wb")]
+    pub(super) wallet_balance: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct BinanceAccountUpdatePosition {
+    #[serde(rename = "s")]
+    pub(super) specific_currency_pair: SpecificCurrencyPair,
+    #[serde(rename = "pa")]
+    pub(super) position_amount: Amount,
+    #[serde(rename = "ep")]
+    pub(super) average_entry_price: Price,
+}
+
+/// Frame of combined stream. Market data is borrowed from the frame, so it's parsed without
+/// allocations. Frames of other streams have neither stream nor data
+pub(crate) struct BinanceStreamFrame<'a> {
+    stream: Option<&'a str>,
+    data: Option<BinanceStreamData<'a>>,
+}
+
+pub(crate) enum BinanceStreamData<'a> {
+    Trade(BinanceTrade<'a>),
+    Depth(BinanceDepth<'a>),
+}
+
+/// Corresponds https://binance-docs.github.io/apidocs/spot/en/#trade-streams
+#[derive(Debug, Deserialize)]
+#[serde(bound(deserialize = "'de: 'a"))]
+pub(crate) struct BinanceTrade<'a> {
+    #[serde(rename = "t")]
+    trade_id: u64,
+    #[serde(rename = "p")]
+    price: &'a str,
+    #[serde(rename = "q")]
+    quantity: &'a str,
+    #[serde(rename = "m")]
+    is_buyer_maker: bool,
+    #[serde(rename = "T")]
+    time: i64,
+}
+
+/// Partial book depth of spot or diff book depth of futures
+#[derive(Debug, Deserialize)]
+#[serde(bound(deserialize = "'de: 'a"))]
+pub(crate) struct BinanceDepth<'a> {
+    #[serde(rename = "lastUpdateId", alias = "u")]
+    last_update_id: u64,
+    #[serde(rename = "T")]
+    time: Option<i64>,
+    #[serde(alias = "a")]
+    asks: Vec<(&'a str, &'a str)>,
+    #[serde(alias = "b")]
+    bids: Vec<(&'a str, &'a str)>,
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for BinanceStreamFrame<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct FrameVisitor<'a>(PhantomData<&'a ()>);
+
+        impl<'de: 'a, 'a> Visitor<'de> for FrameVisitor<'a> {
+            type Value = BinanceStreamFrame<'a>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("Binance websocket frame")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let mut frame = BinanceStreamFrame {
+                    stream: None,
+                    data: None,
+                };
+                while let Some(key) = map.next_key::<&str>()? {
+                    // type of data depends on stream which precedes data in frames
+                    match (key, frame.stream.and_then(stream_tail)) {
+                        ("stream", _) => frame.stream = Some(map.next_value()?),
+                        ("data", Some("trade")) => {
+                            frame.data = Some(BinanceStreamData::Trade(map.next_value()?))
+                        }
+                        ("data", Some(tail))
+                            if tail.starts_with("depth") && !tail.starts_with("depth1000") =>
+                        {
+                            frame.data = Some(BinanceStreamData::Depth(map.next_value()?))
+                        }
+                        _ => {
+                            let _ = map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(frame)
+            }
+        }
+
+        deserializer.deserialize_map(FrameVisitor(PhantomData))
+    }
+}
+
+fn stream_tail(stream: &str) -> Option<&str> {
+    stream.find('@').map(|byte_index| &stream[byte_index + 1..])
+}
+
 #[async_trait]
 impl Support for Binance {
     fn as_any(&self) -> &(dyn Any + Send + Sync + 'static) {
@@ -114,43 +237,43 @@ impl Support for Binance {
     async fn initialized(&self, exchange: Arc<Exchange>) {
         self.initialize_working_currencies(&exchange);
 
+        if self.settings.is_margin_trading {
+            let currency_pairs = exchange.symbols.iter().map(|x| *x.key()).collect_vec();
+            self.configure_derivative_positions(&currency_pairs).await;
+        }
+
         start_updating_listen_key(&exchange);
     }
 
     fn on_websocket_message(&self, msg: &str) -> Result<()> {
-        let mut data: Value =
-            serde_json::from_str(msg).context("Unable to parse websocket message")?;
+        let mut buffer = Vec::new();
+        let frame: BinanceStreamFrame =
+            parse_frame(msg, &mut buffer).context("Unable to parse websocket message")?;
         // Public stream
-        if let Some(stream) = data.get("stream") {
-            let stream = stream
-                .as_str()
-                .ok_or_else(|| anyhow!("Unable to parse stream data"))?;
-
+        if let Some(stream) = frame.stream {
             if let Some(byte_index) = stream.find('@') {
                 let currency_pair = self.currency_pair_from_web_socket(&stream[..byte_index])?;
-                let data = &data["data"];
-
-                if stream.ends_with("@trade") {
-                    self.handle_trade(currency_pair, data)?;
-                    return Ok(());
-                }
 
-                // TODO handle public stream
-                let stream_tail = &stream[byte_index + 1..];
-                if stream_tail.starts_with("depth1000") {
-                    log::warn!("depth1000 is unsuported for Binance in current implementation");
-                    return Ok(());
-                }
-
-                if stream_tail.starts_with("depth") {
-                    self.process_snapshot_update(currency_pair, data)?;
-                    return Ok(());
+                match frame.data {
+                    Some(BinanceStreamData::Trade(trade)) => {
+                        self.handle_trade(currency_pair, &trade)?
+                    }
+                    Some(BinanceStreamData::Depth(depth)) => {
+                        self.process_snapshot_update(currency_pair, &depth)?
+                    }
+                    // TODO handle public stream
+                    None if stream[byte_index + 1..].starts_with("depth1000") => {
+                        log::warn!("depth1000 is unsuported for Binance in current implementation");
+                    }
+                    None => nothing_to_do(),
                 }
             }
 
             return Ok(());
         }
 
+        let mut data: Value =
+            parse_frame(msg, &mut buffer).context("Unable to parse websocket message")?;
         // so it is userData stream
         let event_type = data["e"]
             .as_str()
@@ -162,6 +285,12 @@ impl Support for Binance {
             let json_response = data["o"].take();
             let event_time = Self::get_event_time(&data)?;
             self.handle_order_fill(msg, json_response, event_time)?;
+        } else if event_type == "ACCOUNT_UPDATE" {
+            self.handle_account_update(&data["a"])?;
+        } else if event_type == "MARGIN_CALL" {
+            log::warn!("Margin call received for {}: {msg}", self.id);
+        } else if event_type == "listStatus" {
+            // state of OCO order list is handled by execution reports of its legs
         } else {
             self.log_unknown_message(self.id, msg);
         }
@@ -222,7 +351,9 @@ impl Support for Binance {
         match role {
             WebSocketRole::Main => true,
             WebSocketRole::Secondary => {
-                !self.settings.api_key.is_empty() && !self.settings.secret_key.is_empty()
+                !self.settings.is_watch_only
+                    && !self.settings.api_key.is_empty()
+                    && !self.settings.secret_key.is_empty()
             }
         }
     }
@@ -252,7 +383,7 @@ impl Support for Binance {
     }
 
     fn should_log_message(&self, message: &str) -> bool {
-        message.contains("executionReport")
+        message.contains("executionReport") || message.contains("ORDER_TRADE_UPDATE")
     }
 
     fn log_unknown_message(
@@ -266,11 +397,21 @@ impl Support for Binance {
     fn get_settings(&self) -> &ExchangeSettings {
         &self.settings
     }
+
+    fn update_credentials(&self, credentials: &ExchangeCredentials) -> Result<()> {
+        *self.api_key.write() = credentials.api_key.clone();
+        *self.secret_key.write() = credentials.secret_key.clone();
+        Ok(())
+    }
 }
 
 impl Binance {
-    pub(crate) fn handle_trade(&self, currency_pair: CurrencyPair, data: &Value) -> Result<()> {
-        let trade_id = TradeId::from(data["t"].clone());
+    pub(crate) fn handle_trade(
+        &self,
+        currency_pair: CurrencyPair,
+        trade: &BinanceTrade,
+    ) -> Result<()> {
+        let trade_id = TradeId::Number(trade.trade_id);
 
         let mut trade_id_from_lasts =
             self.last_trade_ids.get_mut(&currency_pair).with_expect(|| {
@@ -285,23 +426,20 @@ impl Binance {
 
         *trade_id_from_lasts = trade_id.clone();
 
-        let price: Decimal = data["p"]
-            .as_str()
-            .context("Unable to get string from 'p' field json data")?
-            .parse()?;
-
-        let quantity: Decimal = data["q"]
-            .as_str()
-            .context("Unable to get string from 'q' field json data")?
-            .parse()?;
-        let order_side = if data["m"] == true {
+        let price: Decimal = trade
+            .price
+            .parse()
+            .context("Unable to parse price of trade")?;
+        let quantity: Decimal = trade
+            .quantity
+            .parse()
+            .context("Unable to parse quantity of trade")?;
+        let order_side = if trade.is_buyer_maker {
             OrderSide::Sell
         } else {
             OrderSide::Buy
         };
-        let datetime = data["T"]
-            .as_i64()
-            .context("Unable to get i64 from 'T' field json data")?;
+        let datetime = trade.time;
 
         (self.handle_metrics_callback)(MetricsEventInfo::new(
             datetime,
@@ -324,32 +462,14 @@ impl Binance {
         Ok(())
     }
 
-    pub fn process_snapshot_update(&self, currency_pair: CurrencyPair, data: &Value) -> Result<()> {
-        let (last_update_id, raw_asks, raw_bids) = match self.settings.is_margin_trading {
-            true => {
-                let last_update_id = data["u"].to_string();
-                let raw_asks = data["a"]
-                    .as_array()
-                    .ok_or_else(|| anyhow!("Unable to parse 'asks' in Binance"))?;
-                let raw_bids = data["b"]
-                    .as_array()
-                    .ok_or_else(|| anyhow!("Unable to parse 'bids' in Binance"))?;
-                (last_update_id, raw_asks, raw_bids)
-            }
-            false => {
-                let last_update_id = data["lastUpdateId"].to_string();
-                let raw_asks = data["asks"]
-                    .as_array()
-                    .ok_or_else(|| anyhow!("Unable to parse 'asks' in Binance"))?;
-                let raw_bids = data["bids"]
-                    .as_array()
-                    .ok_or_else(|| anyhow!("Unable to parse 'bids' in Binance"))?;
-                (last_update_id, raw_asks, raw_bids)
-            }
-        };
-
-        let datetime = data["T"]
-            .as_i64()
+    pub(crate) fn process_snapshot_update(
+        &self,
+        currency_pair: CurrencyPair,
+        depth: &BinanceDepth,
+    ) -> Result<()> {
+        let last_update_id = depth.last_update_id.to_string();
+        let datetime = depth
+            .time
             .context("Unable to get i64 from 'T' field json data")?;
 
         (self.handle_metrics_callback)(MetricsEventInfo::new(
@@ -359,8 +479,8 @@ impl Binance {
             MetricsEventType::OrderBookEvent,
         ));
 
-        let asks = get_order_book_side(raw_asks)?;
-        let bids = get_order_book_side(raw_bids)?;
+        let asks = get_order_book_side(&depth.asks)?;
+        let bids = get_order_book_side(&depth.bids)?;
 
         let order_book_data = OrderBookData::new(asks, bids);
         self.handle_order_book_snapshot(currency_pair, &last_update_id, order_book_data, None)
@@ -466,19 +586,63 @@ fn start_updating_listen_key(exchange: &Arc<Exchange>) {
     );
 }
 
-fn get_order_book_side(levels: &[Value]) -> Result<SortedOrderData> {
+fn get_order_book_side(levels: &[(&str, &str)]) -> Result<SortedOrderData> {
     levels
         .iter()
-        .map(|x| {
-            let price = x[0]
-                .as_str()
-                .ok_or_else(|| anyhow!("Unable parse price of order book side in Binance"))?
-                .parse()?;
-            let amount = x[1]
-                .as_str()
-                .ok_or_else(|| anyhow!("Unable parse amount of order book side in Binance"))?
-                .parse()?;
+        .map(|(price, amount)| {
+            let price = price
+                .parse()
+                .context("Unable parse price of order book side in Binance")?;
+            let amount = amount
+                .parse()
+                .context("Unable parse amount of order book side in Binance")?;
             Ok((price, amount))
         })
         .try_collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(msg: &str, check: impl FnOnce(BinanceStreamFrame)) {
+        let mut buffer = Vec::new();
+        check(parse_frame(msg, &mut buffer).expect("frame should be parsed"));
+    }
+
+    #[test]
+    fn stream_frames_are_parsed_by_stream_name() {
+        parse(
+            r#"{"stream":"btcusdt@trade","data":{"e":"trade","E":2,"s":"BTCUSDT","t":12,"p":"0.1","q":"3","b":88,"a":50,"T":1,"m":true,"M":true}}"#,
+            |frame| {
+                assert_eq!(frame.stream, Some("btcusdt@trade"));
+                let Some(BinanceStreamData::Trade(trade)) = frame.data else {
+                    panic!("trade should be parsed");
+                };
+                assert_eq!(
+                    (trade.trade_id, trade.price, trade.quantity),
+                    (12, "0.1", "3")
+                );
+                assert!(trade.is_buyer_maker);
+            },
+        );
+        parse(
+            r#"{"stream":"btcusdt@depth20@100ms","data":{"lastUpdateId":160,"T":1,"bids":[["0.0024","10"]],"asks":[["0.0026","100"],["0.0027","5"]]}}"#,
+            |frame| {
+                let Some(BinanceStreamData::Depth(depth)) = frame.data else {
+                    panic!("depth should be parsed");
+                };
+                assert_eq!(depth.last_update_id, 160);
+                assert_eq!(depth.bids, [("0.0024", "10")]);
+                assert_eq!(depth.asks.len(), 2);
+            },
+        );
+        parse(
+            r#"{"stream":"btcusdt@depth1000","data":{"lastUpdateId":160}}"#,
+            |frame| assert!(frame.data.is_none()),
+        );
+        parse(r#"{"e":"outboundAccountPosition","E":1}"#, |frame| {
+            assert!(frame.stream.is_none() && frame.data.is_none())
+        });
+    }
+}
@@ -192,7 +192,7 @@ impl BinanceBuilder {
 
         let exchange_blocker = ExchangeBlocker::new(vec![exchange_account_id]);
 
-        let event_recorder = EventRecorder::start(None, None)
+        let event_recorder = EventRecorder::start(None, None, Default::default())
             .await
             .expect("Failure start EventRecorder");
 
@@ -20,6 +20,7 @@ use mmb_domain::order::snapshot::{Amount, OrderSide, Price};
 use mmb_utils::hashmap;
 use mmb_utils::infrastructure::WithExpect;
 use mmb_utils::value_to_decimal::GetOrErr;
+use parking_lot::RwLock;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
@@ -90,7 +91,7 @@ async fn send_request(
     let rest_client = RestClient::new(
         ErrorHandlerData::new(false, exchange_account_id, ErrorHandlerBinance::default()),
         RestHeadersBinance {
-            api_key: api_key.to_owned(),
+            api_key: Arc::new(RwLock::new(api_key.to_owned())),
             is_usd_m_futures,
         },
     );
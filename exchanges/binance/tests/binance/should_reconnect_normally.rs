@@ -34,9 +34,10 @@ pub async fn connect_disconnect() {
     };
 
     for _ in 0..3 {
-        let (sender, mut receiver) = websocket_open(account, main.clone(), Some(secondary.clone()))
+        let (sender, receivers) = websocket_open(account, main.clone(), Some(secondary.clone()))
             .await
             .expect("in test");
+        let mut receiver = receivers.main;
 
         // receive first message
         // should arrive in few milliseconds (on production)
@@ -328,8 +328,14 @@ impl Bitmex {
                 } => {
                     builder.add_kv("ordType", "Limit");
                     builder.add_kv("price", price);
-                    if execution_type == OrderExecutionType::MakerOnly {
-                        builder.add_kv("execInst", "ParticipateDoNotInitiate");
+                    match execution_type {
+                        OrderExecutionType::None => {}
+                        OrderExecutionType::MakerOnly => {
+                            builder.add_kv("execInst", "ParticipateDoNotInitiate")
+                        }
+                        OrderExecutionType::ImmediateOrCancel => {
+                            builder.add_kv("timeInForce", "ImmediateOrCancel")
+                        }
                     }
                 }
                 UserOrder::Market => builder.add_kv("ordType", "Market"),
@@ -66,6 +66,18 @@ impl Support for Bitmex {
     }
 
     fn on_connected(&self) -> Result<()> {
+        if self.settings.is_watch_only || self.settings.separate_order_entry_connection {
+            // Market data channels are public, so there is nothing to auth
+            self.subscribe_to_channels(
+                WebSocketRole::Main,
+                vec![SubscriptionType::OrderBookL2_25, SubscriptionType::Trade],
+            )?;
+
+            if self.settings.is_watch_only {
+                return Ok(());
+            }
+        }
+
         // First of all we should auth to be able to subscribe to private messages
         let expire_time = Bitmex::get_key_expire_time(60);
         let signature =
@@ -91,7 +103,7 @@ impl Support for Bitmex {
         let private_auth = serde_json::to_string(&request)
             .expect("Failed to serialize Bitmex private auth message");
 
-        (self.websocket_message_callback)(WebSocketRole::Main, private_auth)
+        (self.websocket_message_callback)(self.order_entry_role(), private_auth)
     }
 
     fn on_disconnected(&self) -> Result<()> {
@@ -129,9 +141,15 @@ impl Support for Bitmex {
     fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
         match role {
             WebSocketRole::Main => {
-                !self.settings.api_key.is_empty() && !self.settings.secret_key.is_empty()
+                self.settings.is_watch_only
+                    || !self.settings.api_key.is_empty() && !self.settings.secret_key.is_empty()
+            }
+            WebSocketRole::Secondary => {
+                self.settings.separate_order_entry_connection
+                    && !self.settings.is_watch_only
+                    && !self.settings.api_key.is_empty()
+                    && !self.settings.secret_key.is_empty()
             }
-            WebSocketRole::Secondary => false,
         }
     }
 
@@ -461,18 +479,43 @@ impl Bitmex {
     }
 
     fn on_auth_success(&self) -> Result<()> {
-        let traded_currencies = self.traded_specific_currencies.lock();
-        let subscriptions = Self::subscribe_to_websocket_events(
-            // Note that OrderBookL2_25 get only top 25 levels
+        if self.settings.separate_order_entry_connection {
+            // market data is already subscribed on main connection
+            return self.subscribe_to_channels(
+                WebSocketRole::Secondary,
+                vec![SubscriptionType::Execution],
+            );
+        }
+
+        // Note that OrderBookL2_25 get only top 25 levels
+        self.subscribe_to_channels(
+            WebSocketRole::Main,
             vec![
                 SubscriptionType::OrderBookL2_25,
                 SubscriptionType::Trade,
                 SubscriptionType::Execution,
             ],
-            traded_currencies.deref(),
-        );
+        )
+    }
+
+    /// Private order flow is received by secondary connection if it is separated from market data
+    fn order_entry_role(&self) -> WebSocketRole {
+        match self.settings.separate_order_entry_connection {
+            true => WebSocketRole::Secondary,
+            false => WebSocketRole::Main,
+        }
+    }
+
+    fn subscribe_to_channels(
+        &self,
+        role: WebSocketRole,
+        subscriptions: Vec<SubscriptionType>,
+    ) -> Result<()> {
+        let traded_currencies = self.traded_specific_currencies.lock();
+        let subscriptions =
+            Self::subscribe_to_websocket_events(subscriptions, traded_currencies.deref());
 
-        (self.websocket_message_callback)(WebSocketRole::Main, subscriptions)
+        (self.websocket_message_callback)(role, subscriptions)
     }
 
     fn subscribe_to_websocket_events(
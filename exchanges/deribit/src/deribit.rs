@@ -0,0 +1,905 @@
+use crate::types::{
+    DeribitAccountSummaries, DeribitError, DeribitInstrument, DeribitInstrumentKind, DeribitOrder,
+    DeribitOrderPlacement, DeribitPosition, DeribitResponse, DeribitRpcRequest, DeribitUserTrades,
+};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use dashmap::DashMap;
+use function_name::named;
+use hmac::{Hmac, Mac};
+use hyper::http::request::Builder;
+use hyper::Uri;
+use itertools::Itertools;
+use mmb_core::connectivity::WebSocketRole;
+use mmb_core::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    RestFillsType, WebSocketOptions,
+};
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::hosts::Hosts;
+use mmb_core::exchanges::rest_client::{
+    ErrorHandler, ErrorHandlerData, RequestType, RestClient, RestHeaders, RestResponse, UriBuilder,
+};
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use mmb_core::exchanges::timeouts::timeout_manager::TimeoutManager;
+use mmb_core::exchanges::traits::{
+    ExchangeClientBuilder, ExchangeClientBuilderResult, ExchangeError, HandleMetricsCb,
+    HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb,
+    Support,
+};
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{AllowedEventSourceType, ExchangeBalance, ExchangeEvent, TradeId};
+use mmb_domain::exchanges::symbol::{DerivativeKind, OptionType, Precision, Symbol};
+use mmb_domain::market::{
+    CurrencyCode, CurrencyId, CurrencyPair, ExchangeErrorType, ExchangeId, SpecificCurrencyPair,
+};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order::pool::{OrderRef, OrdersPool};
+use mmb_domain::order::snapshot::{
+    ExchangeOrderId, OrderExecutionType, OrderInfo, OrderOptions, OrderRole, OrderSide,
+    OrderStatus, Price, UserOrder,
+};
+use mmb_domain::position::{ActivePosition, DerivativePosition};
+use mmb_utils::DateTime;
+use parking_lot::{Mutex, RwLock};
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot};
+
+/// Max time of waiting for response to request sent by websocket
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(crate) type RpcResult = Result<Value, ExchangeError>;
+
+#[derive(Default)]
+pub struct ErrorHandlerDeribit;
+
+impl ErrorHandler for ErrorHandlerDeribit {
+    fn check_spec_rest_error(&self, response: &RestResponse) -> Result<(), ExchangeError> {
+        let content: Value = serde_json::from_str(&response.content).map_err(|err| {
+            ExchangeError::parsing(format!(
+                "Unable to parse response.content: {err:?}\n{}",
+                response.content
+            ))
+        })?;
+
+        match content.get("error") {
+            None => Ok(()),
+            Some(error) => Err(rpc_error_to_exchange_error(DeribitError::deserialize_from(
+                error,
+            ))),
+        }
+    }
+
+    fn clarify_error_type(&self, error: &ExchangeError) -> ExchangeErrorType {
+        use ExchangeErrorType::*;
+        // According to https://docs.deribit.com/#rpc-error-codes
+        match error.code {
+            Some(10028) => RateLimit,
+            Some(13004) | Some(13009) | Some(13010) | Some(13021) => Authentication,
+            Some(10009) => InsufficientFunds,
+            Some(10004) => OrderNotFound,
+            Some(10010) | Some(11044) => OrderCompleted,
+            Some(10001) | Some(10002) | Some(10007) | Some(10011) | Some(10012) | Some(10013)
+            | Some(10024) | Some(10027) | Some(11029) | Some(11030) => InvalidOrder,
+            Some(10029) | Some(10040) | Some(10041) | Some(11035) => ServiceUnavailable,
+            _ => Unknown,
+        }
+    }
+}
+
+impl DeribitError {
+    fn deserialize_from(value: &Value) -> DeribitError {
+        serde_json::from_value(value.clone()).unwrap_or_else(|_| DeribitError {
+            code: 0,
+            message: value.to_string(),
+        })
+    }
+}
+
+pub(crate) fn rpc_error_to_exchange_error(error: DeribitError) -> ExchangeError {
+    let mut error = ExchangeError::new(ExchangeErrorType::Unknown, error.message, Some(error.code));
+    error.error_type = ErrorHandlerDeribit.clarify_error_type(&error);
+    error
+}
+
+pub struct RestHeadersDeribit {
+    client_id: String,
+    client_secret: String,
+}
+
+impl RestHeadersDeribit {
+    pub fn new(client_id: String, client_secret: String) -> Self {
+        Self {
+            client_id,
+            client_secret,
+        }
+    }
+}
+
+impl RestHeaders for RestHeadersDeribit {
+    fn add_specific_headers(
+        &self,
+        builder: Builder,
+        uri: &Uri,
+        request_type: RequestType,
+    ) -> Builder {
+        // Only private methods require authentication
+        if self.client_id.is_empty() || !uri.path().starts_with("/api/v2/private") {
+            return builder;
+        }
+
+        let path_and_query = match uri.path_and_query() {
+            Some(path_and_query) => path_and_query.as_str(),
+            None => uri.path(),
+        };
+        let timestamp = Utc::now().timestamp_millis();
+        let nonce = Deribit::create_nonce();
+        let request_data = format!("{}\n{path_and_query}\n\n", request_type.as_str());
+        let signature =
+            Deribit::create_signature(&self.client_secret, timestamp, &nonce, &request_data);
+
+        builder.header(
+            hyper::header::AUTHORIZATION,
+            format!(
+                "deri-hmac-sha256 id={},ts={timestamp},sig={signature},nonce={nonce}",
+                self.client_id
+            ),
+        )
+    }
+}
+
+const EMPTY_RESPONSE_IS_OK: bool = false;
+
+pub struct Deribit {
+    pub(crate) settings: ExchangeSettings,
+    pub hosts: Hosts,
+    rest_client: RestClient<ErrorHandlerDeribit, RestHeadersDeribit>,
+    pub(crate) unified_to_specific: RwLock<HashMap<CurrencyPair, SpecificCurrencyPair>>,
+    pub(crate) specific_to_unified: RwLock<HashMap<SpecificCurrencyPair, CurrencyPair>>,
+    pub(crate) supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    /// Settlement currency of instruments which is needed for requests by currency
+    settlement_currencies: RwLock<HashMap<SpecificCurrencyPair, String>>,
+    // Currencies used for trading according to user settings
+    pub(super) traded_specific_currencies: Mutex<Vec<SpecificCurrencyPair>>,
+    pub(super) lifetime_manager: Arc<AppLifetimeManager>,
+    pub(super) events_channel: broadcast::Sender<ExchangeEvent>,
+    next_request_id: AtomicU64,
+    /// Requests sent by websocket which are waiting for response
+    pending_requests: Mutex<HashMap<u64, oneshot::Sender<RpcResult>>>,
+    /// Id of authentication request of private connection, it's answered before subscription to private channels
+    pub(super) auth_request_id: AtomicU64,
+    pub(crate) order_created_callback: OrderCreatedCb,
+    pub(crate) order_cancelled_callback: OrderCancelledCb,
+    pub(crate) handle_order_filled_callback: HandleOrderFilledCb,
+    pub(crate) handle_trade_callback: HandleTradeCb,
+    pub(super) handle_metrics_callback: HandleMetricsCb,
+    pub(crate) websocket_message_callback: SendWebsocketMessageCb,
+}
+
+impl Deribit {
+    pub fn new(
+        settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+    ) -> Deribit {
+        Self {
+            rest_client: RestClient::new(
+                ErrorHandlerData::new(
+                    EMPTY_RESPONSE_IS_OK,
+                    settings.exchange_account_id,
+                    ErrorHandlerDeribit,
+                ),
+                RestHeadersDeribit::new(settings.api_key.clone(), settings.secret_key.clone()),
+            ),
+            settings,
+            hosts: Self::make_hosts(),
+            unified_to_specific: Default::default(),
+            specific_to_unified: Default::default(),
+            supported_currencies: Default::default(),
+            settlement_currencies: Default::default(),
+            traded_specific_currencies: Default::default(),
+            events_channel,
+            lifetime_manager,
+            next_request_id: AtomicU64::new(1),
+            pending_requests: Default::default(),
+            auth_request_id: AtomicU64::new(0),
+            order_created_callback: Box::new(|_, _, _| {}),
+            order_cancelled_callback: Box::new(|_, _, _| {}),
+            handle_order_filled_callback: Box::new(|_| {}),
+            handle_trade_callback: Box::new(|_, _| {}),
+            handle_metrics_callback: Box::new(|_| {}),
+            websocket_message_callback: Box::new(|_, _| Ok(())),
+        }
+    }
+
+    /// Market data is received by main connection, order management and private
+    /// channels use secondary authenticated one
+    fn make_hosts() -> Hosts {
+        Hosts {
+            web_socket_host: "wss://www.deribit.com/ws/api/v2",
+            web_socket2_host: "wss://www.deribit.com/ws/api/v2",
+            rest_host: "https://www.deribit.com",
+        }
+    }
+
+    pub(super) fn create_nonce() -> String {
+        Utc::now().timestamp_nanos().to_string()
+    }
+
+    /// Signature of both REST requests and websocket authentication:
+    /// HEX(HMAC-SHA256(secret, timestamp + "\n" + nonce + "\n" + data))
+    pub(super) fn create_signature(
+        client_secret: &str,
+        timestamp: i64,
+        nonce: &str,
+        data: &str,
+    ) -> String {
+        let mut hmac = Hmac::<Sha256>::new_from_slice(client_secret.as_bytes())
+            .expect("Unable to calculate hmac for Deribit signature");
+        hmac.update(format!("{timestamp}\n{nonce}\n{data}").as_bytes());
+
+        hex::encode(hmac.finalize().into_bytes())
+    }
+
+    pub(super) fn create_rpc_request(&self, method: &str, params: Value) -> (u64, String) {
+        let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let request = DeribitRpcRequest {
+            jsonrpc: "2.0",
+            id,
+            method,
+            params,
+        };
+        let request = serde_json::to_string(&request).expect("Failed to serialize Deribit request");
+
+        (id, request)
+    }
+
+    /// Sends JSON-RPC request by private websocket connection and waits for its response
+    pub(super) async fn call(&self, method: &str, params: Value) -> RpcResult {
+        let (id, request) = self.create_rpc_request(method, params);
+
+        let (tx, rx) = oneshot::channel();
+        let _ = self.pending_requests.lock().insert(id, tx);
+
+        if let Err(err) = (self.websocket_message_callback)(WebSocketRole::Secondary, request) {
+            let _ = self.pending_requests.lock().remove(&id);
+            return Err(ExchangeError::new(
+                ExchangeErrorType::SendError,
+                format!("Unable to send {method} request: {err:?}"),
+                None,
+            ));
+        }
+
+        match tokio::time::timeout(RPC_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(ExchangeError::new(
+                ExchangeErrorType::SendError,
+                format!("Connection was closed before response to {method} request"),
+                None,
+            )),
+            Err(_) => {
+                let _ = self.pending_requests.lock().remove(&id);
+                Err(ExchangeError::new(
+                    ExchangeErrorType::SendError,
+                    format!("Response to {method} request wasn't received in time"),
+                    None,
+                ))
+            }
+        }
+    }
+
+    /// Returns false if nobody waits for response with such id
+    pub(super) fn complete_request(&self, id: u64, result: RpcResult) -> bool {
+        match self.pending_requests.lock().remove(&id) {
+            Some(tx) => {
+                // receiver is dropped if request is timed out
+                let _ = tx.send(result);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Responses to pending requests can't be received after disconnection
+    pub(super) fn fail_pending_requests(&self) {
+        self.pending_requests.lock().clear();
+    }
+
+    #[named]
+    pub(super) async fn request_all_symbols(&self) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/api/v2/public/get_instruments");
+        builder.add_kv("currency", "any");
+        builder.add_kv("expired", false);
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_all_symbols(&self, response: &RestResponse) -> Result<Vec<Arc<Symbol>>> {
+        let instruments: DeribitResponse<Vec<DeribitInstrument>> =
+            serde_json::from_str(&response.content)
+                .context("Unable to deserialize instruments from Deribit")?;
+
+        instruments
+            .result
+            .into_iter()
+            .filter(|x| x.is_active)
+            .filter(|x| {
+                matches!(
+                    x.kind,
+                    DeribitInstrumentKind::Spot
+                        | DeribitInstrumentKind::Future
+                        | DeribitInstrumentKind::Option
+                )
+            })
+            .map(|x| self.instrument_to_symbol(x))
+            .try_collect()
+    }
+
+    /// Spot instruments are mapped to usual currency pairs. Every derivative instrument
+    /// gets its own currency pair with instrument name as base currency code,
+    /// e.g. `btc-29dec23-40000-c/usd`, because a lot of instruments have the same underlying
+    fn instrument_to_symbol(&self, instrument: DeribitInstrument) -> Result<Arc<Symbol>> {
+        let base_currency: CurrencyCode = instrument.base_currency.as_str().into();
+        let quote_currency: CurrencyCode = instrument.quote_currency.as_str().into();
+        let settlement_currency = instrument
+            .settlement_currency
+            .as_deref()
+            .unwrap_or(&instrument.quote_currency);
+        for currency in [
+            &instrument.base_currency,
+            &instrument.quote_currency,
+            settlement_currency,
+        ] {
+            let _ = self
+                .supported_currencies
+                .insert(currency.into(), currency.into());
+        }
+
+        let is_derivative = instrument.kind != DeribitInstrumentKind::Spot;
+        let base: CurrencyCode = match is_derivative {
+            true => instrument.instrument_name.as_str().into(),
+            false => base_currency,
+        };
+        let unified_currency_pair = CurrencyPair::from_codes(base, quote_currency);
+        self.unified_to_specific
+            .write()
+            .insert(unified_currency_pair, instrument.instrument_name);
+        self.specific_to_unified
+            .write()
+            .insert(instrument.instrument_name, unified_currency_pair);
+        self.settlement_currencies
+            .write()
+            .insert(instrument.instrument_name, settlement_currency.to_owned());
+
+        let amount_currency_code = match instrument.instrument_type.as_deref() {
+            // amount of inverse futures is specified in USD
+            Some("reversed") if instrument.kind == DeribitInstrumentKind::Future => quote_currency,
+            _ => base_currency,
+        };
+        let balance_currency_code = is_derivative.then(|| settlement_currency.into());
+
+        let mut symbol = Symbol::new(
+            is_derivative,
+            instrument.base_currency.as_str().into(),
+            base,
+            instrument.quote_currency.as_str().into(),
+            quote_currency,
+            None,
+            None,
+            Some(instrument.min_trade_amount),
+            None,
+            None,
+            amount_currency_code,
+            balance_currency_code,
+            Precision::ByTick {
+                tick: instrument.tick_size,
+            },
+            Precision::ByTick {
+                tick: instrument.min_trade_amount,
+            },
+        );
+        symbol.derivative_kind = Self::get_derivative_kind(&instrument)?;
+
+        Ok(Arc::new(symbol))
+    }
+
+    pub(super) fn get_derivative_kind(
+        instrument: &DeribitInstrument,
+    ) -> Result<Option<DerivativeKind>> {
+        let expiration = instrument.expiration_timestamp;
+        Ok(match instrument.kind {
+            DeribitInstrumentKind::Future => {
+                match instrument.settlement_period.as_deref() == Some("perpetual") {
+                    true => Some(DerivativeKind::Perpetual),
+                    false => Some(DerivativeKind::Future { expiration }),
+                }
+            }
+            DeribitInstrumentKind::Option => {
+                let option_type = match instrument.option_type.as_deref() {
+                    Some("call") => OptionType::Call,
+                    Some("put") => OptionType::Put,
+                    option_type => {
+                        return Err(anyhow!(
+                            "Unknown option type {option_type:?} of {}",
+                            instrument.instrument_name
+                        ))
+                    }
+                };
+                let strike = instrument.strike.with_context(|| {
+                    format!("Strike isn't specified for {}", instrument.instrument_name)
+                })?;
+
+                Some(DerivativeKind::Option {
+                    expiration,
+                    strike,
+                    option_type,
+                })
+            }
+            _ => None,
+        })
+    }
+
+    pub(super) fn get_unified_currency_pair(
+        &self,
+        currency_pair: &SpecificCurrencyPair,
+    ) -> Result<CurrencyPair> {
+        self.specific_to_unified
+            .read()
+            .get(currency_pair)
+            .cloned()
+            .with_context(|| {
+                format!(
+                    "Not found currency pair '{currency_pair:?}' in {}",
+                    self.settings.exchange_account_id
+                )
+            })
+    }
+
+    pub(super) fn get_currency_code(&self, currency_id: &str) -> Result<CurrencyCode> {
+        self.supported_currencies
+            .get(&currency_id.into())
+            .map(|x| *x.value())
+            .ok_or_else(|| anyhow!("Unknown currency {currency_id} in Deribit"))
+    }
+
+    fn get_settlement_currency(&self, currency_pair: CurrencyPair) -> Result<String> {
+        let instrument = self.get_specific_currency_pair(currency_pair);
+        self.settlement_currencies
+            .read()
+            .get(&instrument)
+            .cloned()
+            .with_context(|| format!("Unknown settlement currency of {instrument}"))
+    }
+
+    pub(super) async fn do_create_order(&self, order: &OrderRef) -> RpcResult {
+        let header = order.header();
+        let mut params = json!({
+            "instrument_name": self.get_specific_currency_pair(header.currency_pair),
+            "amount": header.amount,
+            "label": header.client_order_id.as_str(),
+        });
+        match header.options {
+            OrderOptions::User(UserOrder::Limit {
+                price,
+                execution_type,
+            }) => {
+                params["type"] = json!("limit");
+                params["price"] = json!(price);
+                params["post_only"] = json!(execution_type == OrderExecutionType::MakerOnly);
+                // order is rejected instead of price changing if it would be taker
+                params["reject_post_only"] = params["post_only"].clone();
+                if execution_type == OrderExecutionType::ImmediateOrCancel {
+                    params["time_in_force"] = json!("immediate_or_cancel");
+                }
+            }
+            OrderOptions::User(UserOrder::Market) => params["type"] = json!("market"),
+            _ => return Err(ExchangeError::unknown("Unexpected order type")),
+        }
+
+        let method = match header.side {
+            OrderSide::Buy => "private/buy",
+            OrderSide::Sell => "private/sell",
+        };
+        self.call(method, params).await
+    }
+
+    /// Parses response of requests which place order: `private/buy`, `private/sell` and `private/close_position`
+    pub(super) fn parse_placed_order(&self, result: Value) -> Result<DeribitOrder, ExchangeError> {
+        let placement: DeribitOrderPlacement = serde_json::from_value(result).map_err(|err| {
+            ExchangeError::parsing(format!("Unable to parse placed order: {err:?}"))
+        })?;
+
+        Ok(placement.order)
+    }
+
+    pub(super) async fn do_cancel_order(&self, exchange_order_id: &ExchangeOrderId) -> RpcResult {
+        self.call(
+            "private/cancel",
+            json!({ "order_id": exchange_order_id.as_str() }),
+        )
+        .await
+    }
+
+    pub(super) async fn do_cancel_all_orders(&self, currency_pair: CurrencyPair) -> RpcResult {
+        self.call(
+            "private/cancel_all_by_instrument",
+            json!({ "instrument_name": self.get_specific_currency_pair(currency_pair) }),
+        )
+        .await
+    }
+
+    /// Orders are searched by label which is client order id
+    pub(super) async fn request_order_info(&self, order: &OrderRef) -> RpcResult {
+        let currency = self
+            .get_settlement_currency(order.currency_pair())
+            .map_err(|err| ExchangeError::unknown(&err.to_string()))?;
+
+        self.call(
+            "private/get_order_state_by_label",
+            json!({
+                "currency": currency,
+                "label": order.client_order_id().as_str(),
+            }),
+        )
+        .await
+    }
+
+    pub(super) fn parse_order_info(&self, result: Value) -> Result<OrderInfo> {
+        let orders: Vec<DeribitOrder> =
+            serde_json::from_value(result).context("Unable to parse order info")?;
+
+        // the last order is actual if label was used several times
+        let order = orders.into_iter().last().context("Order not found")?;
+        self.specific_order_info_to_unified(order)
+    }
+
+    #[named]
+    pub(super) async fn request_open_orders(
+        &self,
+        currency_pair: Option<CurrencyPair>,
+    ) -> Result<RestResponse, ExchangeError> {
+        let builder = match currency_pair {
+            Some(currency_pair) => {
+                let mut builder =
+                    UriBuilder::from_path("/api/v2/private/get_open_orders_by_instrument");
+                builder.add_kv(
+                    "instrument_name",
+                    self.get_specific_currency_pair(currency_pair),
+                );
+                builder
+            }
+            None => UriBuilder::from_path("/api/v2/private/get_open_orders"),
+        };
+
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_open_orders(&self, response: &RestResponse) -> Result<Vec<OrderInfo>> {
+        let orders: DeribitResponse<Vec<DeribitOrder>> = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for get_open_orders request")?;
+
+        orders
+            .result
+            .into_iter()
+            // orders of instruments which aren't traded by the engine are skipped
+            .filter(|x| {
+                self.specific_to_unified
+                    .read()
+                    .contains_key(&x.instrument_name)
+            })
+            .map(|order| self.specific_order_info_to_unified(order))
+            .try_collect()
+    }
+
+    pub(super) fn specific_order_info_to_unified(&self, order: DeribitOrder) -> Result<OrderInfo> {
+        Ok(OrderInfo::new(
+            self.get_unified_currency_pair(&order.instrument_name)?,
+            order.order_id,
+            // orders created without label can't be matched with local orders
+            order.label.unwrap_or_else(|| "".into()),
+            order.direction,
+            Deribit::get_local_order_status(&order.order_state),
+            order.price.unwrap_or(order.average_price),
+            order.amount,
+            order.average_price,
+            order.filled_amount,
+            None,
+            None,
+            None,
+        ))
+    }
+
+    pub(super) fn get_local_order_status(status: &str) -> OrderStatus {
+        match status {
+            "open" | "untriggered" => OrderStatus::Created,
+            "filled" => OrderStatus::Completed,
+            "cancelled" => OrderStatus::Canceled,
+            "rejected" => OrderStatus::FailedToCreate,
+            _ => panic!("Deribit: unexpected order status {status}"),
+        }
+    }
+
+    pub(super) fn get_order_role(liquidity: &str) -> Result<OrderRole> {
+        match liquidity {
+            "T" => Ok(OrderRole::Taker),
+            "M" => Ok(OrderRole::Maker),
+            _ => Err(anyhow!("Unknown Deribit liquidity {liquidity}")),
+        }
+    }
+
+    #[named]
+    pub(super) async fn request_my_trades(
+        &self,
+        symbol: &Symbol,
+        last_date_time: Option<DateTime>,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder =
+            UriBuilder::from_path("/api/v2/private/get_user_trades_by_instrument_and_time");
+        builder.add_kv(
+            "instrument_name",
+            self.get_specific_currency_pair(symbol.currency_pair()),
+        );
+        builder.add_kv(
+            "start_timestamp",
+            last_date_time.map_or(0, |x| x.timestamp_millis()),
+        );
+        builder.add_kv("end_timestamp", Utc::now().timestamp_millis());
+        builder.add_kv("count", 1000);
+        builder.add_kv("sorting", "asc");
+
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_my_trades(&self, response: &RestResponse) -> Result<Vec<OrderTrade>> {
+        let trades: DeribitResponse<DeribitUserTrades> =
+            serde_json::from_str(&response.content).context("Failed to parse trade data")?;
+
+        trades
+            .result
+            .trades
+            .into_iter()
+            .map(|trade| {
+                Ok(OrderTrade {
+                    exchange_order_id: trade.order_id,
+                    trade_id: TradeId::String(trade.trade_id.into_boxed_str()),
+                    datetime: trade.timestamp,
+                    price: trade.price,
+                    amount: trade.amount,
+                    order_role: Deribit::get_order_role(&trade.liquidity)?,
+                    fee_currency_code: self.get_currency_code(&trade.fee_currency)?,
+                    fee_rate: None,
+                    fee_amount: Some(trade.fee),
+                    fill_type: OrderFillType::UserTrade,
+                })
+            })
+            .try_collect()
+    }
+
+    #[named]
+    pub(super) async fn request_get_position(&self) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/api/v2/private/get_positions");
+        builder.add_kv("currency", "any");
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_active_positions(
+        &self,
+        response: &RestResponse,
+    ) -> Result<Vec<ActivePosition>> {
+        let positions: DeribitResponse<Vec<DeribitPosition>> =
+            serde_json::from_str(&response.content).context("Failed to parse positions")?;
+
+        let now = Utc::now();
+        positions
+            .result
+            .into_iter()
+            .filter(|x| !x.size.is_zero())
+            .map(|position| {
+                let derivative_position = DerivativePosition::new(
+                    self.get_unified_currency_pair(&position.instrument_name)?,
+                    position.size,
+                    position.average_price,
+                    position.estimated_liquidation_price.unwrap_or_default(),
+                    position.leverage.unwrap_or(dec!(1)),
+                );
+
+                Ok(ActivePosition::new(derivative_position, now))
+            })
+            .try_collect()
+    }
+
+    /// Position is closed by market order
+    pub(super) async fn request_close_position(
+        &self,
+        position: &ActivePosition,
+        price: Option<Price>,
+    ) -> RpcResult {
+        let mut params = json!({
+            "instrument_name": self.get_specific_currency_pair(position.derivative.currency_pair),
+            "type": "market",
+        });
+        if let Some(price) = price {
+            params["type"] = json!("limit");
+            params["price"] = json!(price);
+        }
+
+        self.call("private/close_position", params).await
+    }
+
+    #[named]
+    pub(super) async fn request_get_balance(&self) -> Result<RestResponse, ExchangeError> {
+        let uri = UriBuilder::from_path("/api/v2/private/get_account_summaries")
+            .build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_get_balance(
+        &self,
+        response: &RestResponse,
+    ) -> Result<Vec<ExchangeBalance>> {
+        let summaries: DeribitResponse<DeribitAccountSummaries> =
+            serde_json::from_str(&response.content).context("Failed to parse balance")?;
+
+        Ok(summaries
+            .result
+            .summaries
+            .iter()
+            .filter_map(|summary| {
+                self.get_currency_code(&summary.currency)
+                    .ok()
+                    .map(|currency_code| ExchangeBalance {
+                        currency_code,
+                        balance: summary.available_funds,
+                    })
+            })
+            .collect_vec())
+    }
+
+    #[named]
+    pub(super) async fn request_get_server_time(&self) -> Result<RestResponse, ExchangeError> {
+        let uri = UriBuilder::from_path("/api/v2/public/get_time")
+            .build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_get_server_time(&self, response: &RestResponse) -> Result<i64> {
+        let time: DeribitResponse<i64> =
+            serde_json::from_str(&response.content).context("Failed to parse server time")?;
+
+        Ok(time.result)
+    }
+}
+
+pub struct DeribitBuilder;
+
+impl ExchangeClientBuilder for DeribitBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+        _timeout_manager: Arc<TimeoutManager>,
+        _orders: Arc<OrdersPool>,
+    ) -> ExchangeClientBuilderResult {
+        ExchangeClientBuilderResult {
+            client: Box::new(Deribit::new(
+                exchange_settings,
+                events_channel,
+                lifetime_manager,
+            )),
+            features: ExchangeFeatures::new(
+                OpenOrdersType::AllCurrencyPair,
+                RestFillsFeatures::new(RestFillsType::MyTrades),
+                OrderFeatures {
+                    maker_only: true,
+                    supports_get_order_info_by_client_order_id: true,
+                    cancellation_response_from_rest_only_for_errors: true,
+                    creation_response_from_rest_only_for_errors: true,
+                    order_was_completed_error_for_cancellation: true,
+                    supports_already_cancelled_order: false,
+                    supports_stop_loss_order: false,
+                },
+                OrderTradeOption {
+                    supports_trade_time: true,
+                    supports_trade_incremented_id: false,
+                    supports_get_prints: true,
+                    supports_tick_direction: false,
+                    supports_my_trades_from_time: true,
+                },
+                WebSocketOptions {
+                    execution_notification: true,
+                    cancellation_notification: true,
+                    supports_ping_pong: true,
+                    supports_subscription_response: true,
+                },
+                EMPTY_RESPONSE_IS_OK,
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+            ),
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        // default credit based limit of matching engine requests is 5 per second
+        RequestTimeoutArguments::from_requests_per_minute(300)
+    }
+
+    fn get_exchange_id(&self) -> ExchangeId {
+        "Deribit".into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_utils::time::u64_to_date_time;
+
+    #[test]
+    fn generate_signature() {
+        let signature = Deribit::create_signature(
+            "AMANDASECRECT",
+            1550069797567,
+            "abcd",
+            "GET\n/api/v2/private/get_account_summary?currency=BTC\n\n",
+        );
+
+        assert_eq!(
+            signature,
+            "a9efec1a6648e86c5a5df96fc8dae215325fc85c106c3b3a13bf5c09c3c65af3"
+        );
+    }
+
+    #[test]
+    fn option_instrument_terms() {
+        let instrument = r#"{"tick_size":0.0005,"taker_commission":0.0003,"strike":40000.0,"settlement_period":"month","settlement_currency":"BTC","quote_currency":"USD","option_type":"call","min_trade_amount":0.1,"maker_commission":0.0003,"kind":"option","is_active":true,"instrument_name":"BTC-29DEC23-40000-C","expiration_timestamp":1703836800000,"creation_timestamp":1672905600000,"counter_currency":"USD","contract_size":1.0,"block_trade_commission":0.0003,"base_currency":"BTC"}"#;
+        let instrument: DeribitInstrument = serde_json::from_str(instrument).expect("in test");
+
+        let kind = Deribit::get_derivative_kind(&instrument).expect("in test");
+
+        assert_eq!(
+            kind,
+            Some(DerivativeKind::Option {
+                expiration: u64_to_date_time(1703836800000),
+                strike: dec!(40000),
+                option_type: OptionType::Call,
+            })
+        );
+    }
+
+    #[test]
+    fn perpetual_instrument_terms() {
+        let instrument = r#"{"tick_size":0.5,"settlement_period":"perpetual","settlement_currency":"BTC","quote_currency":"USD","min_trade_amount":10.0,"kind":"future","is_active":true,"instrument_type":"reversed","instrument_name":"BTC-PERPETUAL","expiration_timestamp":32503708800000,"contract_size":10.0,"base_currency":"BTC"}"#;
+        let instrument: DeribitInstrument = serde_json::from_str(instrument).expect("in test");
+
+        let kind = Deribit::get_derivative_kind(&instrument).expect("in test");
+
+        assert_eq!(kind, Some(DerivativeKind::Perpetual));
+    }
+}
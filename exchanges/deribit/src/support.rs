@@ -0,0 +1,396 @@
+use crate::deribit::{rpc_error_to_exchange_error, Deribit};
+use crate::types::{
+    DeribitNotification, DeribitOrder, DeribitOrderBook, DeribitRpcMessage, DeribitTrade,
+    DeribitUserTrade,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use dashmap::DashMap;
+use mmb_core::connectivity::WebSocketRole;
+use mmb_core::exchanges::common::send_event;
+use mmb_core::exchanges::general::handlers::handle_order_filled::{
+    FillAmount, FillEvent, SpecialOrderData,
+};
+use mmb_core::exchanges::traits::{
+    HandleMetricsCb, HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb,
+    SendWebsocketMessageCb, Support,
+};
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{EventSourceType, ExchangeEvent, Trade, TradeId};
+use mmb_domain::market::{CurrencyCode, CurrencyId, CurrencyPair, SpecificCurrencyPair};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order_book::event::{EventType, OrderBookEvent};
+use mmb_domain::order_book::order_book_data::OrderBookData;
+use serde_json::{json, Value};
+use std::any::Any;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use url::Url;
+
+/// Max number of instruments in single subscription request
+const SUBSCRIPTION_CHUNK_SIZE: usize = 100;
+
+#[async_trait]
+impl Support for Deribit {
+    fn as_any(&self) -> &(dyn Any + Send + Sync + 'static) {
+        self
+    }
+
+    fn on_websocket_message(&self, msg: &str) -> Result<()> {
+        let message: DeribitRpcMessage = serde_json::from_str(msg)
+            .with_context(|| format!("Unable to parse websocket message:\n{msg}"))?;
+
+        if let Some(notification) = message.params {
+            return match message.method.as_deref() {
+                Some("subscription") => self.handle_notification(notification),
+                method => {
+                    log::warn!("Unsupported Deribit websocket method {method:?}: {msg}");
+                    Ok(())
+                }
+            };
+        }
+
+        let id = message.id.context("Deribit message doesn't contain id")?;
+        let result = match message.error {
+            Some(error) => Err(rpc_error_to_exchange_error(error)),
+            None => Ok(message.result.unwrap_or(Value::Null)),
+        };
+
+        if id == self.auth_request_id.load(Ordering::Relaxed) {
+            return match result {
+                Ok(_) => self.subscribe_to_private_channels(),
+                Err(err) => {
+                    log::error!("Deribit websocket authentication failed: {err:?}");
+                    Err(err.into())
+                }
+            };
+        }
+
+        if !self.complete_request(id, result) {
+            // responses to subscriptions aren't awaited
+            log::trace!("Deribit response without pending request: {msg}");
+        }
+
+        Ok(())
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_connected(&self) -> Result<()> {
+        let traded_currencies = self.traded_specific_currencies.lock().clone();
+        for chunk in traded_currencies.chunks(SUBSCRIPTION_CHUNK_SIZE) {
+            let channels = chunk
+                .iter()
+                .flat_map(|instrument| {
+                    [
+                        format!("book.{instrument}.none.10.100ms"),
+                        format!("trades.{instrument}.100ms"),
+                    ]
+                })
+                .collect::<Vec<_>>();
+
+            self.send_request(
+                WebSocketRole::Main,
+                "public/subscribe",
+                json!({ "channels": channels }),
+            )?;
+        }
+
+        if !self.is_websocket_enabled(WebSocketRole::Secondary) {
+            return Ok(());
+        }
+
+        self.authenticate()
+    }
+
+    fn on_disconnected(&self) -> Result<()> {
+        self.fail_pending_requests();
+        Ok(())
+    }
+
+    fn set_send_websocket_message_callback(&mut self, callback: SendWebsocketMessageCb) {
+        self.websocket_message_callback = callback;
+    }
+
+    fn set_order_created_callback(&mut self, callback: OrderCreatedCb) {
+        self.order_created_callback = callback;
+    }
+
+    fn set_order_cancelled_callback(&mut self, callback: OrderCancelledCb) {
+        self.order_cancelled_callback = callback;
+    }
+
+    fn set_handle_order_filled_callback(&mut self, callback: HandleOrderFilledCb) {
+        self.handle_order_filled_callback = callback;
+    }
+
+    fn set_handle_trade_callback(&mut self, callback: HandleTradeCb) {
+        self.handle_trade_callback = callback;
+    }
+
+    fn set_handle_metrics_callback(&mut self, callback: HandleMetricsCb) {
+        self.handle_metrics_callback = callback;
+    }
+
+    fn set_traded_specific_currencies(&self, currencies: Vec<SpecificCurrencyPair>) {
+        *self.traded_specific_currencies.lock() = currencies;
+    }
+
+    /// Main connection is used for market data and secondary authenticated one
+    /// for order management and private channels
+    fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
+        match role {
+            WebSocketRole::Main => true,
+            WebSocketRole::Secondary => {
+                !self.settings.is_watch_only
+                    && !self.settings.api_key.is_empty()
+                    && !self.settings.secret_key.is_empty()
+            }
+        }
+    }
+
+    async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url> {
+        let host = match role {
+            WebSocketRole::Main => self.hosts.web_socket_host,
+            WebSocketRole::Secondary => self.hosts.web_socket2_host,
+        };
+
+        Url::parse(host).with_context(|| format!("Unable parse websocket {role:?} uri"))
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        self.unified_to_specific.read()[&currency_pair]
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        &self.supported_currencies
+    }
+
+    fn should_log_message(&self, message: &str) -> bool {
+        message.contains("user.")
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        &self.settings
+    }
+}
+
+impl Deribit {
+    fn send_request(&self, role: WebSocketRole, method: &str, params: Value) -> Result<u64> {
+        let (id, request) = self.create_rpc_request(method, params);
+        (self.websocket_message_callback)(role, request)?;
+
+        Ok(id)
+    }
+
+    /// Private connection is authenticated by signature of client credentials
+    fn authenticate(&self) -> Result<()> {
+        let timestamp = Utc::now().timestamp_millis();
+        let nonce = Deribit::create_nonce();
+        let signature = Deribit::create_signature(&self.settings.secret_key, timestamp, &nonce, "");
+
+        let id = self.send_request(
+            WebSocketRole::Secondary,
+            "public/auth",
+            json!({
+                "grant_type": "client_signature",
+                "client_id": self.settings.api_key,
+                "timestamp": timestamp,
+                "signature": signature,
+                "nonce": nonce,
+                "data": "",
+            }),
+        )?;
+        self.auth_request_id.store(id, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    fn subscribe_to_private_channels(&self) -> Result<()> {
+        let _ = self.send_request(
+            WebSocketRole::Secondary,
+            "private/subscribe",
+            json!({ "channels": ["user.orders.any.any.raw", "user.trades.any.any.raw"] }),
+        )?;
+
+        Ok(())
+    }
+
+    fn handle_notification(&self, notification: DeribitNotification) -> Result<()> {
+        let channel = notification.channel.as_str();
+        if channel.starts_with("book.") {
+            let order_book: DeribitOrderBook = serde_json::from_value(notification.data)
+                .context("Unable to parse Deribit order book")?;
+            return self.handle_order_book(order_book);
+        }
+
+        if channel.starts_with("trades.") {
+            let trades: Vec<DeribitTrade> = serde_json::from_value(notification.data)
+                .context("Unable to parse Deribit trades")?;
+            return trades
+                .into_iter()
+                .try_for_each(|trade| self.handle_trade(trade));
+        }
+
+        // raw orders channel pushes single order, unlike trades channels
+        if channel.starts_with("user.orders.") {
+            let order: DeribitOrder = serde_json::from_value(notification.data)
+                .context("Unable to parse Deribit user order")?;
+            self.handle_order_update(order);
+            return Ok(());
+        }
+
+        if channel.starts_with("user.trades.") {
+            let trades: Vec<DeribitUserTrade> = serde_json::from_value(notification.data)
+                .context("Unable to parse Deribit user trades")?;
+            return trades
+                .into_iter()
+                .try_for_each(|trade| self.handle_user_trade(trade));
+        }
+
+        log::warn!("Unsupported Deribit channel {channel}");
+        Ok(())
+    }
+
+    /// Channel with fixed depth pushes full snapshot of order book
+    fn handle_order_book(&self, order_book: DeribitOrderBook) -> Result<()> {
+        let order_book_event = OrderBookEvent::new(
+            Utc::now(),
+            self.settings.exchange_account_id,
+            self.get_unified_currency_pair(&order_book.instrument_name)?,
+            String::default(),
+            EventType::Snapshot,
+            Arc::new(OrderBookData::new(
+                order_book.asks.into_iter().collect(),
+                order_book.bids.into_iter().collect(),
+            )),
+        );
+
+        send_event(
+            &self.events_channel,
+            self.lifetime_manager.clone(),
+            self.settings.exchange_account_id,
+            ExchangeEvent::OrderBookEvent(order_book_event),
+        )
+    }
+
+    fn handle_trade(&self, trade: DeribitTrade) -> Result<()> {
+        (self.handle_trade_callback)(
+            self.get_unified_currency_pair(&trade.instrument_name)?,
+            Trade {
+                trade_id: TradeId::String(trade.trade_id.into_boxed_str()),
+                price: trade.price,
+                quantity: trade.amount,
+                side: trade.direction,
+                transaction_time: trade.timestamp,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Fills are handled by user trades channel, so only creation and cancellation are taken from orders
+    pub(super) fn handle_order_update(&self, order: DeribitOrder) {
+        // Orders created outside of the engine can't be matched with local orders by label
+        let client_order_id = match order.label {
+            Some(client_order_id) => client_order_id,
+            None => {
+                log::trace!("Skipped Deribit order update without label {order:?}");
+                return;
+            }
+        };
+
+        match order.order_state.as_str() {
+            "open" => (self.order_created_callback)(
+                client_order_id,
+                order.order_id,
+                EventSourceType::WebSocket,
+            ),
+            "cancelled" => (self.order_cancelled_callback)(
+                client_order_id,
+                order.order_id,
+                EventSourceType::WebSocket,
+            ),
+            _ => (),
+        }
+    }
+
+    fn handle_user_trade(&self, trade: DeribitUserTrade) -> Result<()> {
+        let fill_event = FillEvent {
+            source_type: EventSourceType::WebSocket,
+            trade_id: Some(TradeId::String(trade.trade_id.into_boxed_str())),
+            // order without label is searched by exchange order id
+            client_order_id: trade.label,
+            exchange_order_id: trade.order_id,
+            fill_price: trade.price,
+            fill_amount: FillAmount::Incremental {
+                fill_amount: trade.amount,
+                total_filled_amount: None,
+            },
+            order_role: Some(Deribit::get_order_role(&trade.liquidity)?),
+            commission_currency_code: Some(self.get_currency_code(&trade.fee_currency)?),
+            commission_rate: None,
+            commission_amount: Some(trade.fee),
+            fill_type: OrderFillType::UserTrade,
+            special_order_data: Some(SpecialOrderData {
+                currency_pair: self.get_unified_currency_pair(&trade.instrument_name)?,
+                order_side: trade.direction,
+                order_amount: trade.amount,
+            }),
+            fill_date: Some(trade.timestamp),
+        };
+
+        (self.handle_order_filled_callback)(fill_event);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{DeribitOrder, DeribitRpcMessage, DeribitUserTrade};
+    use mmb_domain::order::snapshot::{ClientOrderId, OrderSide};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parse_rpc_error_response() {
+        let msg = r#"{"jsonrpc":"2.0","id":8,"error":{"message":"not_enough_funds","code":10009},"usIn":1535037392434763,"usOut":1535037392448119,"usDiff":13356,"testnet":false}"#;
+
+        let message: DeribitRpcMessage = serde_json::from_str(msg).expect("in test");
+
+        assert_eq!(message.id, Some(8));
+        assert!(message.result.is_none());
+        let error = message.error.expect("in test");
+        assert_eq!(error.code, 10009);
+        assert_eq!(error.message, "not_enough_funds");
+    }
+
+    #[test]
+    fn parse_user_orders_notification() {
+        let msg = r#"{"jsonrpc":"2.0","method":"subscription","params":{"channel":"user.orders.any.any.raw","data":{"time_in_force":"good_til_cancelled","replaced":false,"reduce_only":false,"price":10502.52,"post_only":false,"order_type":"limit","order_state":"open","order_id":"5","max_show":200,"last_update_timestamp":1581507423789,"label":"","is_liquidation":false,"instrument_name":"BTC-PERPETUAL","filled_amount":0,"direction":"buy","creation_timestamp":1581507423789,"average_price":0,"api":false,"amount":200}}}"#;
+
+        let message: DeribitRpcMessage = serde_json::from_str(msg).expect("in test");
+        let notification = message.params.expect("in test");
+        assert_eq!(notification.channel, "user.orders.any.any.raw");
+
+        let order: DeribitOrder = serde_json::from_value(notification.data).expect("in test");
+        assert_eq!(order.label, None);
+        assert_eq!(order.direction, OrderSide::Buy);
+        assert_eq!(order.price, Some(dec!(10502.52)));
+        assert_eq!(order.order_state, "open");
+    }
+
+    #[test]
+    fn parse_user_trade() {
+        let data = r#"{"trade_seq":30289432,"trade_id":"48079254","timestamp":1590484156350,"tick_direction":0,"state":"filled","self_trade":false,"reduce_only":false,"price":8954,"post_only":false,"order_type":"market","order_id":"4008965646","matching_id":null,"mark_price":8952.86,"liquidity":"T","label":"1590484156000024","instrument_name":"BTC-PERPETUAL","index_price":8956.73,"fee_currency":"BTC","fee":0.00000168,"direction":"sell","amount":20}"#;
+
+        let trade: DeribitUserTrade = serde_json::from_str(data).expect("in test");
+
+        assert_eq!(trade.label, Some(ClientOrderId::from("1590484156000024")));
+        assert_eq!(trade.direction, OrderSide::Sell);
+        assert_eq!(trade.fee, dec!(0.00000168));
+        assert_eq!(trade.timestamp.timestamp_millis(), 1590484156350);
+    }
+}
@@ -0,0 +1,244 @@
+use mmb_domain::market::SpecificCurrencyPair;
+use mmb_domain::order::snapshot::{Amount, ClientOrderId, ExchangeOrderId, OrderSide, Price};
+use mmb_utils::time::u64_to_date_time;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Successful JSON-RPC response of REST API
+#[derive(Deserialize, Debug)]
+pub struct DeribitResponse<T> {
+    pub result: T,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeribitError {
+    pub code: i64,
+    pub message: String,
+}
+
+/// Any JSON-RPC message received by websocket: response to request or subscription notification
+#[derive(Deserialize, Debug)]
+pub struct DeribitRpcMessage {
+    pub id: Option<u64>,
+    pub result: Option<Value>,
+    pub error: Option<DeribitError>,
+    pub method: Option<String>,
+    pub params: Option<DeribitNotification>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeribitNotification {
+    pub channel: String,
+    pub data: Value,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DeribitRpcRequest<'a> {
+    pub jsonrpc: &'static str,
+    pub id: u64,
+    pub method: &'a str,
+    pub params: Value,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeribitInstrumentKind {
+    Spot,
+    Future,
+    Option,
+    FutureCombo,
+    OptionCombo,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeribitInstrument {
+    pub instrument_name: SpecificCurrencyPair,
+    pub kind: DeribitInstrumentKind,
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub settlement_currency: Option<String>,
+    /// `linear` or `reversed`, amount of reversed instruments is specified in quote currency
+    pub instrument_type: Option<String>,
+    /// `perpetual` for perpetual futures
+    pub settlement_period: Option<String>,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub tick_size: Price,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub min_trade_amount: Amount,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub contract_size: Decimal,
+    #[serde(deserialize_with = "timestamp")]
+    pub expiration_timestamp: DateTime,
+    #[serde(default, with = "rust_decimal::serde::float_option")]
+    pub strike: Option<Price>,
+    /// `call` or `put` for options
+    pub option_type: Option<String>,
+    pub is_active: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeribitOrder {
+    pub order_id: ExchangeOrderId,
+    pub instrument_name: SpecificCurrencyPair,
+    /// Echo of client order id, empty for orders which were created without it
+    #[serde(default, deserialize_with = "optional_client_order_id")]
+    pub label: Option<ClientOrderId>,
+    #[serde(deserialize_with = "order_side")]
+    pub direction: OrderSide,
+    /// `open`, `filled`, `rejected`, `cancelled` or `untriggered`
+    pub order_state: String,
+    /// Price is `market_price` string for market orders
+    #[serde(default, deserialize_with = "optional_price")]
+    pub price: Option<Price>,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub amount: Amount,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub filled_amount: Amount,
+    #[serde(default, with = "rust_decimal::serde::float")]
+    pub average_price: Price,
+}
+
+/// Response of `private/buy` and `private/sell` requests
+#[derive(Deserialize, Debug)]
+pub struct DeribitOrderPlacement {
+    pub order: DeribitOrder,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeribitUserTrade {
+    pub trade_id: String,
+    pub order_id: ExchangeOrderId,
+    pub instrument_name: SpecificCurrencyPair,
+    #[serde(default, deserialize_with = "optional_client_order_id")]
+    pub label: Option<ClientOrderId>,
+    #[serde(deserialize_with = "order_side")]
+    pub direction: OrderSide,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub price: Price,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub amount: Amount,
+    /// Positive fee is charged, negative one is rebate
+    #[serde(with = "rust_decimal::serde::float")]
+    pub fee: Amount,
+    pub fee_currency: String,
+    /// `M` for maker, `T` for taker
+    pub liquidity: String,
+    #[serde(deserialize_with = "timestamp")]
+    pub timestamp: DateTime,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeribitUserTrades {
+    pub trades: Vec<DeribitUserTrade>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeribitTrade {
+    pub trade_id: String,
+    pub instrument_name: SpecificCurrencyPair,
+    #[serde(deserialize_with = "order_side")]
+    pub direction: OrderSide,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub price: Price,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub amount: Amount,
+    #[serde(deserialize_with = "timestamp")]
+    pub timestamp: DateTime,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeribitOrderBook {
+    pub instrument_name: SpecificCurrencyPair,
+    #[serde(deserialize_with = "price_levels")]
+    pub asks: Vec<(Price, Amount)>,
+    #[serde(deserialize_with = "price_levels")]
+    pub bids: Vec<(Price, Amount)>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeribitAccountSummaries {
+    pub summaries: Vec<DeribitAccountSummary>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeribitAccountSummary {
+    pub currency: String,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub available_funds: Amount,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeribitPosition {
+    pub instrument_name: SpecificCurrencyPair,
+    /// Signed size of position, negative for short one
+    #[serde(with = "rust_decimal::serde::float")]
+    pub size: Amount,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub average_price: Price,
+    #[serde(default, with = "rust_decimal::serde::float_option")]
+    pub estimated_liquidation_price: Option<Price>,
+    #[serde(default, with = "rust_decimal::serde::float_option")]
+    pub leverage: Option<Decimal>,
+}
+
+fn timestamp<'de, D>(deserializer: D) -> Result<DateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(u64_to_date_time(u64::deserialize(deserializer)?))
+}
+
+fn optional_price<'de, D>(deserializer: D) -> Result<Option<Price>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::String(_) | Value::Null => Ok(None),
+        value => rust_decimal::serde::float::deserialize(value)
+            .map(Some)
+            .map_err(de::Error::custom),
+    }
+}
+
+/// Deribit sends all numbers as JSON numbers instead of strings
+fn price_levels<'de, D>(deserializer: D) -> Result<Vec<(Price, Amount)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct PriceLevel(
+        #[serde(with = "rust_decimal::serde::float")] Price,
+        #[serde(with = "rust_decimal::serde::float")] Amount,
+    );
+
+    Ok(Vec::<PriceLevel>::deserialize(deserializer)?
+        .into_iter()
+        .map(|PriceLevel(price, amount)| (price, amount))
+        .collect())
+}
+
+/// Label is echoed as empty string if order was created without it
+fn optional_client_order_id<'de, D>(deserializer: D) -> Result<Option<ClientOrderId>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<Cow<str>>::deserialize(deserializer)?;
+    Ok(value
+        .filter(|x| !x.is_empty())
+        .map(|x| ClientOrderId::from(x.as_ref())))
+}
+
+fn order_side<'de, D>(deserializer: D) -> Result<OrderSide, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Cow::<str>::deserialize(deserializer)?;
+    match value.as_ref() {
+        "buy" => Ok(OrderSide::Buy),
+        "sell" => Ok(OrderSide::Sell),
+        _ => Err(de::Error::custom(format!("unknown order side {value}"))),
+    }
+}
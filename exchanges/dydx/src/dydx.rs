@@ -0,0 +1,1133 @@
+use crate::signer::DydxSigner;
+use crate::transaction::{
+    build_transaction, AccountSequence, Any, CancelGoodTil, GoodTil, MsgCancelOrder, MsgPlaceOrder,
+    Order, OrderId, Side, SubaccountId, TimeInForce, MSG_CANCEL_ORDER_TYPE_URL,
+    MSG_PLACE_ORDER_TYPE_URL, ORDER_FLAGS_LONG_TERM, ORDER_FLAGS_SHORT_TERM,
+};
+use crate::types::{
+    DydxBroadcastResponse, DydxFill, DydxFills, DydxHeight, DydxIndexerErrors,
+    DydxNodeAccountResponse, DydxOrder, DydxPerpetualMarket, DydxPerpetualMarkets,
+    DydxSubaccountResponse, DydxTime,
+};
+use anyhow::{anyhow, Context, Result};
+use bytes::Bytes;
+use chrono::Utc;
+use dashmap::DashMap;
+use function_name::named;
+use hyper::header::CONTENT_TYPE;
+use hyper::http::request::Builder;
+use hyper::Uri;
+use itertools::Itertools;
+use mmb_core::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    RestFillsType, WebSocketOptions,
+};
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::hosts::Hosts;
+use mmb_core::exchanges::rest_client::{
+    ErrorHandler, ErrorHandlerData, RequestType, RestClient, RestHeaders, RestResponse, UriBuilder,
+};
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use mmb_core::exchanges::timeouts::timeout_manager::TimeoutManager;
+use mmb_core::exchanges::traits::{
+    ExchangeClientBuilder, ExchangeClientBuilderResult, ExchangeError, HandleMetricsCb,
+    HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb,
+    Support,
+};
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{AllowedEventSourceType, ExchangeBalance, ExchangeEvent, TradeId};
+use mmb_domain::exchanges::symbol::{DerivativeKind, Precision, Symbol};
+use mmb_domain::market::{
+    CurrencyCode, CurrencyId, CurrencyPair, ExchangeErrorType, ExchangeId, SpecificCurrencyPair,
+};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order::pool::{OrderRef, OrdersPool};
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, ExchangeOrderId, OrderExecutionType, OrderInfo, OrderOptions, OrderRole,
+    OrderSide, OrderStatus, Price, UserOrder,
+};
+use mmb_domain::position::{ActivePosition, ClosedPosition, DerivativePosition};
+use mmb_utils::DateTime;
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::{Decimal, MathematicalOps};
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+const CHAIN_ID: &str = "dydx-mainnet-1";
+
+/// Any full node with enabled REST gateway of gRPC services can be used for broadcasting
+const NODE_REST_HOST: &str = "dydx-rest.publicnode.com";
+
+/// Only the first subaccount of wallet is traded
+pub(crate) const SUBACCOUNT_NUMBER: u32 = 0;
+
+/// Currency of collateral, fees and PnL
+pub(crate) const COLLATERAL_CURRENCY: &str = "usdc";
+
+/// Atomic resolution of USDC quantums, it's used for conversion of price to subticks
+const QUOTE_ATOMIC_RESOLUTION: i32 = -6;
+
+/// Limit orders are placed as long-term ones, max allowed lifetime is 95 days
+const LONG_TERM_ORDER_LIFETIME_DAYS: i64 = 28;
+
+/// Short-term orders can't live more than 20 blocks
+const SHORT_TERM_ORDER_BLOCKS: u32 = 20;
+
+/// Market orders are immediate-or-cancel orders with price which is worse than oracle price by this ratio
+const MARKET_ORDER_SLIPPAGE: Decimal = dec!(0.05);
+
+/// Code of `ErrWrongSequence` of Cosmos SDK
+const WRONG_SEQUENCE_CODE: u32 = 32;
+
+#[derive(Default)]
+pub struct ErrorHandlerDydx;
+
+impl ErrorHandler for ErrorHandlerDydx {
+    fn check_spec_rest_error(&self, response: &RestResponse) -> Result<(), ExchangeError> {
+        // Indexer errors
+        if let Ok(indexer_errors) = serde_json::from_str::<DydxIndexerErrors>(&response.content) {
+            let message = indexer_errors.errors.iter().map(|x| &x.msg).join("; ");
+            return Err(ExchangeError::new(
+                ExchangeErrorType::Unknown,
+                message,
+                None,
+            ));
+        }
+
+        // Errors of gRPC gateway of node: {"code": 3, "message": "...", "details": []}
+        let content: Value = serde_json::from_str(&response.content).map_err(|err| {
+            ExchangeError::parsing(format!(
+                "Unable to parse response.content: {err:?}\n{}",
+                response.content
+            ))
+        })?;
+        match (content.get("code"), content.get("message")) {
+            (Some(Value::Number(code)), Some(Value::String(message))) => Err(ExchangeError::new(
+                ExchangeErrorType::Unknown,
+                message.clone(),
+                code.as_i64(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn clarify_error_type(&self, error: &ExchangeError) -> ExchangeErrorType {
+        use ExchangeErrorType::*;
+        // Codes of transactions are specific for codespace, so error type is detected by message
+        let message = error.message.to_lowercase();
+        if message.contains("rate limit") {
+            RateLimit
+        } else if message.contains("signature verification failed")
+            || message.contains("unauthorized")
+        {
+            Authentication
+        } else if message.contains("undercollateralized") || message.contains("insufficient") {
+            InsufficientFunds
+        } else if message.contains("does not exist") || message.contains("not found") {
+            OrderNotFound
+        } else if message.contains("fully filled") {
+            OrderCompleted
+        } else if message.contains("post-only") || message.contains("invalid") {
+            InvalidOrder
+        } else {
+            Unknown
+        }
+    }
+}
+
+/// Indexer and node REST APIs are public, all requests which change state are signed transactions
+pub struct RestHeadersDydx;
+
+impl RestHeaders for RestHeadersDydx {
+    fn add_specific_headers(
+        &self,
+        builder: Builder,
+        _uri: &Uri,
+        _request_type: RequestType,
+    ) -> Builder {
+        builder.header(CONTENT_TYPE, "application/json")
+    }
+}
+
+const EMPTY_RESPONSE_IS_OK: bool = false;
+
+/// Parameters of market which are needed to convert amount and price to integers of the chain
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MarketParams {
+    pub(crate) clob_pair_id: u32,
+    atomic_resolution: i32,
+    quantum_conversion_exponent: i32,
+    step_base_quantums: u64,
+    subticks_per_tick: u64,
+}
+
+impl From<&DydxPerpetualMarket> for MarketParams {
+    fn from(market: &DydxPerpetualMarket) -> Self {
+        Self {
+            clob_pair_id: market.clob_pair_id,
+            atomic_resolution: market.atomic_resolution,
+            quantum_conversion_exponent: market.quantum_conversion_exponent,
+            step_base_quantums: market.step_base_quantums,
+            subticks_per_tick: market.subticks_per_tick,
+        }
+    }
+}
+
+impl MarketParams {
+    /// Amount in quantums of base asset rounded to step of market
+    pub(crate) fn to_quantums(self, amount: Amount) -> Result<u64> {
+        let quantums = amount * Decimal::TEN.powi(-i64::from(self.atomic_resolution));
+        let steps = (quantums / Decimal::from(self.step_base_quantums)).round();
+        let steps = u64::try_from(steps).context("Unable to convert amount to quantums")?;
+
+        Ok(steps.max(1) * self.step_base_quantums)
+    }
+
+    /// Price in subticks rounded to tick of market
+    pub(crate) fn to_subticks(self, price: Price) -> Result<u64> {
+        let exponent =
+            self.atomic_resolution - self.quantum_conversion_exponent - QUOTE_ATOMIC_RESOLUTION;
+        let subticks = price * Decimal::TEN.powi(i64::from(exponent));
+        let ticks = (subticks / Decimal::from(self.subticks_per_tick)).round();
+        let ticks = u64::try_from(ticks).context("Unable to convert price to subticks")?;
+
+        Ok(ticks.max(1) * self.subticks_per_tick)
+    }
+}
+
+pub struct Dydx {
+    pub(crate) settings: ExchangeSettings,
+    pub hosts: Hosts,
+    rest_client: RestClient<ErrorHandlerDydx, RestHeadersDydx>,
+    /// Address of wallet which owns subaccount
+    pub(crate) address: String,
+    signer: Option<DydxSigner>,
+    /// Account number and sequence of wallet for signing, it's requested from node lazily and
+    /// the lock serializes transactions because long-term orders require successive sequences
+    account: tokio::sync::Mutex<Option<AccountSequence>>,
+    pub(crate) markets: RwLock<HashMap<SpecificCurrencyPair, MarketParams>>,
+    pub(crate) unified_to_specific: RwLock<HashMap<CurrencyPair, SpecificCurrencyPair>>,
+    pub(crate) specific_to_unified: RwLock<HashMap<SpecificCurrencyPair, CurrencyPair>>,
+    pub(crate) supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    /// Indexer identifies orders by its own id, but fills have to be matched by client id
+    pub(crate) client_ids_by_order_id: DashMap<String, u32>,
+    // Currencies used for trading according to user settings
+    pub(super) traded_specific_currencies: Mutex<Vec<SpecificCurrencyPair>>,
+    pub(super) lifetime_manager: Arc<AppLifetimeManager>,
+    pub(super) events_channel: broadcast::Sender<ExchangeEvent>,
+    pub(crate) order_created_callback: OrderCreatedCb,
+    pub(crate) order_cancelled_callback: OrderCancelledCb,
+    pub(crate) handle_order_filled_callback: HandleOrderFilledCb,
+    pub(crate) handle_trade_callback: HandleTradeCb,
+    pub(super) handle_metrics_callback: HandleMetricsCb,
+    pub(crate) websocket_message_callback: SendWebsocketMessageCb,
+}
+
+impl Dydx {
+    pub fn new(
+        settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+    ) -> Dydx {
+        let signer = match settings.secret_key.is_empty() {
+            true => None,
+            false => Some(
+                DydxSigner::from_hex(&settings.secret_key)
+                    .unwrap_or_else(|err| panic!("Unable to create dYdX signer: {err:?}")),
+            ),
+        };
+
+        Self {
+            rest_client: RestClient::new(
+                ErrorHandlerData::new(
+                    EMPTY_RESPONSE_IS_OK,
+                    settings.exchange_account_id,
+                    ErrorHandlerDydx,
+                ),
+                RestHeadersDydx,
+            ),
+            address: settings.api_key.clone(),
+            settings,
+            hosts: Self::make_hosts(),
+            signer,
+            account: Default::default(),
+            markets: Default::default(),
+            unified_to_specific: Default::default(),
+            specific_to_unified: Default::default(),
+            supported_currencies: Default::default(),
+            client_ids_by_order_id: Default::default(),
+            traded_specific_currencies: Default::default(),
+            events_channel,
+            lifetime_manager,
+            order_created_callback: Box::new(|_, _, _| {}),
+            order_cancelled_callback: Box::new(|_, _, _| {}),
+            handle_order_filled_callback: Box::new(|_| {}),
+            handle_trade_callback: Box::new(|_, _| {}),
+            handle_metrics_callback: Box::new(|_| {}),
+            websocket_message_callback: Box::new(|_, _| Ok(())),
+        }
+    }
+
+    /// Market data and subaccount updates are received from indexer by separate connections
+    fn make_hosts() -> Hosts {
+        Hosts {
+            web_socket_host: "wss://indexer.dydx.trade/v4/ws",
+            web_socket2_host: "wss://indexer.dydx.trade/v4/ws",
+            rest_host: "https://indexer.dydx.trade",
+        }
+    }
+
+    /// Client id of dYdX order is u32, client order ids generated by the engine are counters
+    /// started from current unix time, so they fit into it
+    pub(crate) fn get_client_id(client_order_id: &ClientOrderId) -> Result<u32, ExchangeError> {
+        client_order_id.as_str().parse::<u32>().map_err(|_| {
+            ExchangeError::new(
+                ExchangeErrorType::InvalidOrder,
+                format!("dYdX requires client order id which fits into u32, got {client_order_id}"),
+                None,
+            )
+        })
+    }
+
+    pub(crate) fn get_client_order_id(client_id: u32) -> ClientOrderId {
+        u64::from(client_id).into()
+    }
+
+    /// Exchange order id is client id, because indexer id of order isn't known until it's handled by the chain
+    pub(crate) fn get_exchange_order_id(client_id: u32) -> ExchangeOrderId {
+        u64::from(client_id).into()
+    }
+
+    fn get_market_params(&self, currency_pair: CurrencyPair) -> Result<MarketParams> {
+        let ticker = self.get_specific_currency_pair(currency_pair);
+        self.markets
+            .read()
+            .get(&ticker)
+            .copied()
+            .with_context(|| format!("Unknown dYdX market {ticker}"))
+    }
+
+    fn subaccount_id(&self) -> SubaccountId {
+        SubaccountId {
+            owner: self.address.clone(),
+            number: SUBACCOUNT_NUMBER,
+        }
+    }
+
+    #[named]
+    pub(super) async fn request_all_symbols(&self) -> Result<RestResponse, ExchangeError> {
+        let uri = UriBuilder::from_path("/v4/perpetualMarkets")
+            .build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_all_symbols(&self, response: &RestResponse) -> Result<Vec<Arc<Symbol>>> {
+        let markets: DydxPerpetualMarkets = serde_json::from_str(&response.content)
+            .context("Unable to deserialize perpetual markets from dYdX")?;
+
+        let _ = self
+            .supported_currencies
+            .insert(COLLATERAL_CURRENCY.into(), COLLATERAL_CURRENCY.into());
+
+        markets
+            .markets
+            .values()
+            .filter(|x| x.status == "ACTIVE")
+            .map(|market| {
+                let (base, quote) = market
+                    .ticker
+                    .as_str()
+                    .split_once('-')
+                    .with_context(|| format!("Unexpected dYdX ticker {}", market.ticker))?;
+                let base_currency: CurrencyCode = base.into();
+                let quote_currency: CurrencyCode = quote.into();
+                for currency in [base, quote] {
+                    let _ = self
+                        .supported_currencies
+                        .insert(currency.into(), currency.into());
+                }
+
+                let unified_currency_pair = CurrencyPair::from_codes(base_currency, quote_currency);
+                self.unified_to_specific
+                    .write()
+                    .insert(unified_currency_pair, market.ticker);
+                self.specific_to_unified
+                    .write()
+                    .insert(market.ticker, unified_currency_pair);
+                self.markets.write().insert(market.ticker, market.into());
+
+                let mut symbol = Symbol::new(
+                    true,
+                    base.into(),
+                    base_currency,
+                    quote.into(),
+                    quote_currency,
+                    None,
+                    None,
+                    Some(market.step_size),
+                    None,
+                    None,
+                    base_currency,
+                    Some(COLLATERAL_CURRENCY.into()),
+                    Precision::ByTick {
+                        tick: market.tick_size,
+                    },
+                    Precision::ByTick {
+                        tick: market.step_size,
+                    },
+                );
+                symbol.derivative_kind = Some(DerivativeKind::Perpetual);
+
+                Ok(Arc::new(symbol))
+            })
+            .try_collect()
+    }
+
+    pub(super) fn get_unified_currency_pair(
+        &self,
+        currency_pair: &SpecificCurrencyPair,
+    ) -> Result<CurrencyPair> {
+        self.specific_to_unified
+            .read()
+            .get(currency_pair)
+            .cloned()
+            .with_context(|| {
+                format!(
+                    "Not found currency pair '{currency_pair:?}' in {}",
+                    self.settings.exchange_account_id
+                )
+            })
+    }
+
+    #[named]
+    async fn request_account(&self) -> Result<RestResponse, ExchangeError> {
+        let uri = UriBuilder::from_path(&format!("/cosmos/auth/v1beta1/accounts/{}", self.address))
+            .build_uri(NODE_REST_HOST, false);
+
+        self.rest_client
+            .get(uri, function_name!(), self.address.clone())
+            .await
+    }
+
+    fn parse_account(response: &RestResponse) -> Result<AccountSequence, ExchangeError> {
+        let account: DydxNodeAccountResponse =
+            serde_json::from_str(&response.content).map_err(|err| {
+                ExchangeError::parsing(format!("Unable to parse dYdX account: {err:?}"))
+            })?;
+
+        Ok(AccountSequence {
+            account_number: account.account.account_number,
+            sequence: account.account.sequence,
+        })
+    }
+
+    #[named]
+    async fn request_height(&self) -> Result<RestResponse, ExchangeError> {
+        let uri = UriBuilder::from_path("/v4/height").build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    /// Short-term orders and their cancellations expire after block with returned height
+    async fn get_good_til_block(&self) -> Result<u32, ExchangeError> {
+        let response = self.request_height().await?;
+        let height: DydxHeight = serde_json::from_str(&response.content).map_err(|err| {
+            ExchangeError::parsing(format!("Unable to parse dYdX height: {err:?}"))
+        })?;
+
+        Ok(height.height + SHORT_TERM_ORDER_BLOCKS)
+    }
+
+    fn get_good_til_block_time() -> u32 {
+        let expiration = Utc::now() + chrono::Duration::days(LONG_TERM_ORDER_LIFETIME_DAYS);
+        expiration.timestamp() as u32
+    }
+
+    #[named]
+    async fn request_oracle_price(&self, ticker: SpecificCurrencyPair) -> Result<Price> {
+        let mut builder = UriBuilder::from_path("/v4/perpetualMarkets");
+        builder.add_kv("ticker", ticker);
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+
+        let response = self
+            .rest_client
+            .get(uri, function_name!(), ticker.to_string())
+            .await?;
+        let markets: DydxPerpetualMarkets =
+            serde_json::from_str(&response.content).context("Unable to parse dYdX market")?;
+
+        markets
+            .markets
+            .get(ticker.as_str())
+            .and_then(|x| x.oracle_price)
+            .with_context(|| format!("Oracle price of {ticker} isn't received"))
+    }
+
+    #[named]
+    async fn broadcast(&self, tx_bytes: Vec<u8>) -> Result<RestResponse, ExchangeError> {
+        let uri = UriBuilder::from_path("/cosmos/tx/v1beta1/txs").build_uri(NODE_REST_HOST, false);
+        let body = json!({
+            "tx_bytes": base64::encode(tx_bytes),
+            "mode": "BROADCAST_MODE_SYNC",
+        });
+
+        self.rest_client
+            .post(
+                uri,
+                Some(Bytes::from(body.to_string())),
+                function_name!(),
+                "".to_string(),
+            )
+            .await
+    }
+
+    fn parse_broadcast(response: &RestResponse) -> Result<(), ExchangeError> {
+        let broadcast: DydxBroadcastResponse =
+            serde_json::from_str(&response.content).map_err(|err| {
+                ExchangeError::parsing(format!("Unable to parse dYdX broadcast response: {err:?}"))
+            })?;
+
+        let tx_response = broadcast.tx_response;
+        if tx_response.code == 0 {
+            log::trace!("dYdX transaction {} is accepted", tx_response.txhash);
+            return Ok(());
+        }
+
+        let mut error = ExchangeError::new(
+            ExchangeErrorType::Unknown,
+            format!("{}: {}", tx_response.codespace, tx_response.raw_log),
+            Some(i64::from(tx_response.code)),
+        );
+        error.error_type = ErrorHandlerDydx.clarify_error_type(&error);
+        Err(error)
+    }
+
+    /// Signs and broadcasts transaction with single message. Sequence of account is incremented
+    /// only by stateful messages, short-term orders and their cancellations don't use it
+    async fn send_transaction(&self, message: Any, is_stateful: bool) -> Result<(), ExchangeError> {
+        let signer = self.signer.as_ref().ok_or_else(|| {
+            ExchangeError::authentication("Private key of dYdX wallet isn't set".to_owned())
+        })?;
+
+        let mut account_guard = self.account.lock().await;
+        let account = match *account_guard {
+            Some(account) => account,
+            None => Self::parse_account(&self.request_account().await?)?,
+        };
+
+        let tx_bytes = build_transaction(signer, CHAIN_ID, account, message);
+        let result = match self.broadcast(tx_bytes).await {
+            Ok(response) => Self::parse_broadcast(&response),
+            Err(err) => Err(err),
+        };
+
+        *account_guard = match &result {
+            Ok(_) if is_stateful => Some(AccountSequence {
+                sequence: account.sequence + 1,
+                ..account
+            }),
+            // sequence is requested again because it's unknown whether transaction was accepted
+            Err(err) if err.code == Some(i64::from(WRONG_SEQUENCE_CODE)) || is_stateful => None,
+            _ => Some(account),
+        };
+
+        result
+    }
+
+    pub(super) async fn do_create_order(
+        &self,
+        order: &OrderRef,
+    ) -> Result<ExchangeOrderId, ExchangeError> {
+        let header = order.header();
+        let client_id = Self::get_client_id(&header.client_order_id)?;
+        let ticker = self.get_specific_currency_pair(header.currency_pair);
+        let market = self
+            .get_market_params(header.currency_pair)
+            .map_err(|err| ExchangeError::unknown(&err.to_string()))?;
+
+        let (price, order_flags, good_til, time_in_force) = match header.options {
+            OrderOptions::User(UserOrder::Limit {
+                price,
+                execution_type,
+            }) => match execution_type {
+                // immediate or cancel orders can be only short-term
+                OrderExecutionType::ImmediateOrCancel => (
+                    price,
+                    ORDER_FLAGS_SHORT_TERM,
+                    GoodTil::Block(self.get_good_til_block().await?),
+                    TimeInForce::ImmediateOrCancel,
+                ),
+                OrderExecutionType::MakerOnly => (
+                    price,
+                    ORDER_FLAGS_LONG_TERM,
+                    GoodTil::BlockTime(Self::get_good_til_block_time()),
+                    TimeInForce::PostOnly,
+                ),
+                OrderExecutionType::None => (
+                    price,
+                    ORDER_FLAGS_LONG_TERM,
+                    GoodTil::BlockTime(Self::get_good_til_block_time()),
+                    TimeInForce::Unspecified,
+                ),
+            },
+            OrderOptions::User(UserOrder::Market) => (
+                self.get_market_order_price(ticker, header.side).await?,
+                ORDER_FLAGS_SHORT_TERM,
+                GoodTil::Block(self.get_good_til_block().await?),
+                TimeInForce::ImmediateOrCancel,
+            ),
+            _ => return Err(ExchangeError::unknown("Unexpected order type")),
+        };
+
+        let message = MsgPlaceOrder {
+            order: Some(Order {
+                order_id: Some(OrderId {
+                    subaccount_id: Some(self.subaccount_id()),
+                    client_id,
+                    order_flags,
+                    clob_pair_id: market.clob_pair_id,
+                }),
+                side: Self::to_side(header.side) as i32,
+                quantums: market
+                    .to_quantums(header.amount)
+                    .map_err(|err| ExchangeError::unknown(&err.to_string()))?,
+                subticks: market
+                    .to_subticks(price)
+                    .map_err(|err| ExchangeError::unknown(&err.to_string()))?,
+                good_til: Some(good_til),
+                time_in_force: time_in_force as i32,
+                reduce_only: false,
+            }),
+        };
+
+        self.send_transaction(
+            Any::pack(MSG_PLACE_ORDER_TYPE_URL, &message),
+            order_flags == ORDER_FLAGS_LONG_TERM,
+        )
+        .await?;
+
+        Ok(Self::get_exchange_order_id(client_id))
+    }
+
+    fn to_side(side: OrderSide) -> Side {
+        match side {
+            OrderSide::Buy => Side::Buy,
+            OrderSide::Sell => Side::Sell,
+        }
+    }
+
+    /// Worst acceptable price of market order
+    async fn get_market_order_price(
+        &self,
+        ticker: SpecificCurrencyPair,
+        side: OrderSide,
+    ) -> Result<Price, ExchangeError> {
+        let oracle_price = self
+            .request_oracle_price(ticker)
+            .await
+            .map_err(|err| ExchangeError::unknown(&err.to_string()))?;
+
+        Ok(match side {
+            OrderSide::Buy => oracle_price * (Decimal::ONE + MARKET_ORDER_SLIPPAGE),
+            OrderSide::Sell => oracle_price * (Decimal::ONE - MARKET_ORDER_SLIPPAGE),
+        })
+    }
+
+    pub(super) async fn do_cancel_order(
+        &self,
+        currency_pair: CurrencyPair,
+        exchange_order_id: &ExchangeOrderId,
+        is_long_term: bool,
+    ) -> Result<(), ExchangeError> {
+        let client_id = exchange_order_id.as_str().parse::<u32>().map_err(|_| {
+            ExchangeError::unknown(&format!("Unexpected dYdX order id {exchange_order_id}"))
+        })?;
+        let market = self
+            .get_market_params(currency_pair)
+            .map_err(|err| ExchangeError::unknown(&err.to_string()))?;
+
+        let (order_flags, good_til) = match is_long_term {
+            true => (
+                ORDER_FLAGS_LONG_TERM,
+                CancelGoodTil::BlockTime(Self::get_good_til_block_time()),
+            ),
+            false => (
+                ORDER_FLAGS_SHORT_TERM,
+                CancelGoodTil::Block(self.get_good_til_block().await?),
+            ),
+        };
+
+        let message = MsgCancelOrder {
+            order_id: Some(OrderId {
+                subaccount_id: Some(self.subaccount_id()),
+                client_id,
+                order_flags,
+                clob_pair_id: market.clob_pair_id,
+            }),
+            good_til: Some(good_til),
+        };
+
+        self.send_transaction(Any::pack(MSG_CANCEL_ORDER_TYPE_URL, &message), is_long_term)
+            .await
+    }
+
+    /// Chain doesn't have mass cancellation, so open orders are cancelled one by one
+    pub(super) async fn do_cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        let response = self.request_orders(Some(currency_pair), true).await?;
+        let orders = self.parse_orders(&response)?;
+
+        for order in orders {
+            self.do_cancel_order(
+                currency_pair,
+                &Self::get_exchange_order_id(order.client_id),
+                order.order_flags == ORDER_FLAGS_LONG_TERM,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    #[named]
+    pub(super) async fn request_orders(
+        &self,
+        currency_pair: Option<CurrencyPair>,
+        only_open: bool,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/v4/orders");
+        builder.add_kv("address", &self.address);
+        builder.add_kv("subaccountNumber", SUBACCOUNT_NUMBER);
+        if let Some(currency_pair) = currency_pair {
+            builder.add_kv("ticker", self.get_specific_currency_pair(currency_pair));
+        }
+        if only_open {
+            builder.add_kv("status", "OPEN");
+        }
+        builder.add_kv("limit", 100);
+
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_orders(&self, response: &RestResponse) -> Result<Vec<DydxOrder>> {
+        let orders: Vec<DydxOrder> = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for orders request")?;
+
+        for order in &orders {
+            self.remember_client_id(order);
+        }
+
+        Ok(orders)
+    }
+
+    pub(crate) fn remember_client_id(&self, order: &DydxOrder) {
+        let _ = self
+            .client_ids_by_order_id
+            .insert(order.id.clone(), order.client_id);
+    }
+
+    pub(super) fn parse_open_orders(&self, response: &RestResponse) -> Result<Vec<OrderInfo>> {
+        self.parse_orders(response)?
+            .into_iter()
+            // orders of markets which aren't traded by the engine are skipped
+            .filter(|x| {
+                x.ticker
+                    .is_some_and(|ticker| self.specific_to_unified.read().contains_key(&ticker))
+            })
+            .map(|order| self.specific_order_info_to_unified(order))
+            .try_collect()
+    }
+
+    /// Indexer doesn't allow to filter orders by client id, so it's searched among the latest orders
+    pub(super) fn parse_order_info(
+        &self,
+        response: &RestResponse,
+        client_order_id: &ClientOrderId,
+    ) -> Result<OrderInfo> {
+        let client_id = Self::get_client_id(client_order_id)?;
+        let order = self
+            .parse_orders(response)?
+            .into_iter()
+            .find(|x| x.client_id == client_id)
+            .with_context(|| format!("Order {client_order_id} not found in dYdX"))?;
+
+        self.specific_order_info_to_unified(order)
+    }
+
+    pub(super) fn specific_order_info_to_unified(&self, order: DydxOrder) -> Result<OrderInfo> {
+        let ticker = order.ticker.context("Order doesn't contain ticker")?;
+        let price = order.price.context("Order doesn't contain price")?;
+
+        Ok(OrderInfo::new(
+            self.get_unified_currency_pair(&ticker)?,
+            Self::get_exchange_order_id(order.client_id),
+            Self::get_client_order_id(order.client_id),
+            order.side.context("Order doesn't contain side")?,
+            Self::get_local_order_status(&order.status)?,
+            price,
+            order.size.context("Order doesn't contain size")?,
+            price,
+            order.total_filled.unwrap_or_default(),
+            None,
+            None,
+            None,
+        ))
+    }
+
+    pub(super) fn get_local_order_status(status: &str) -> Result<OrderStatus> {
+        match status {
+            "OPEN" | "BEST_EFFORT_OPENED" | "UNTRIGGERED" => Ok(OrderStatus::Created),
+            "FILLED" => Ok(OrderStatus::Completed),
+            "CANCELED" | "BEST_EFFORT_CANCELED" => Ok(OrderStatus::Canceled),
+            _ => Err(anyhow!("Unexpected dYdX order status {status}")),
+        }
+    }
+
+    pub(super) fn get_order_role(liquidity: &str) -> Result<OrderRole> {
+        match liquidity {
+            "TAKER" => Ok(OrderRole::Taker),
+            "MAKER" => Ok(OrderRole::Maker),
+            _ => Err(anyhow!("Unknown dYdX liquidity {liquidity}")),
+        }
+    }
+
+    #[named]
+    pub(super) async fn request_my_trades(
+        &self,
+        symbol: &Symbol,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/v4/fills");
+        builder.add_kv("address", &self.address);
+        builder.add_kv("subaccountNumber", SUBACCOUNT_NUMBER);
+        builder.add_kv(
+            "market",
+            self.get_specific_currency_pair(symbol.currency_pair()),
+        );
+        builder.add_kv("marketType", "PERPETUAL");
+        builder.add_kv("limit", 100);
+
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    /// Indexer returns the latest fills, fills before `last_date_time` are filtered locally
+    pub(super) fn parse_my_trades(
+        &self,
+        response: &RestResponse,
+        last_date_time: Option<DateTime>,
+    ) -> Result<Vec<DydxFill>> {
+        let fills: DydxFills =
+            serde_json::from_str(&response.content).context("Failed to parse fills")?;
+
+        Ok(fills
+            .fills
+            .into_iter()
+            .filter(|x| last_date_time.is_none_or(|time| x.created_at >= time))
+            .filter(|x| x.order_id.is_some())
+            .collect())
+    }
+
+    #[named]
+    async fn request_order(&self, order_id: &str) -> Result<RestResponse, ExchangeError> {
+        let uri = UriBuilder::from_path(&format!("/v4/orders/{order_id}"))
+            .build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .get(uri, function_name!(), order_id.to_owned())
+            .await
+    }
+
+    /// Client id of order which wasn't received by websocket yet is requested from indexer
+    async fn get_client_id_by_order_id(&self, order_id: &str) -> Result<u32> {
+        if let Some(client_id) = self.client_ids_by_order_id.get(order_id) {
+            return Ok(*client_id);
+        }
+
+        let response = self.request_order(order_id).await?;
+        let order: DydxOrder =
+            serde_json::from_str(&response.content).context("Unable to parse dYdX order")?;
+        self.remember_client_id(&order);
+
+        Ok(order.client_id)
+    }
+
+    pub(super) async fn fills_to_order_trades(
+        &self,
+        fills: Vec<DydxFill>,
+    ) -> Result<Vec<OrderTrade>> {
+        let mut trades = Vec::with_capacity(fills.len());
+        for fill in fills {
+            let order_id = fill.order_id.context("Fill doesn't contain order id")?;
+            let client_id = self.get_client_id_by_order_id(&order_id).await?;
+
+            trades.push(OrderTrade {
+                exchange_order_id: Self::get_exchange_order_id(client_id),
+                trade_id: TradeId::String(fill.id.into_boxed_str()),
+                datetime: fill.created_at,
+                price: fill.price,
+                amount: fill.size,
+                order_role: Self::get_order_role(&fill.liquidity)?,
+                fee_currency_code: COLLATERAL_CURRENCY.into(),
+                fee_rate: None,
+                fee_amount: Some(fill.fee),
+                fill_type: OrderFillType::UserTrade,
+            });
+        }
+
+        Ok(trades)
+    }
+
+    #[named]
+    pub(super) async fn request_get_subaccount(&self) -> Result<RestResponse, ExchangeError> {
+        let uri = UriBuilder::from_path(&format!(
+            "/v4/addresses/{}/subaccountNumber/{SUBACCOUNT_NUMBER}",
+            self.address
+        ))
+        .build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_get_balance(&self, response: &RestResponse) -> Result<ExchangeBalance> {
+        let subaccount: DydxSubaccountResponse =
+            serde_json::from_str(&response.content).context("Failed to parse subaccount")?;
+
+        Ok(ExchangeBalance {
+            currency_code: COLLATERAL_CURRENCY.into(),
+            balance: subaccount.subaccount.free_collateral,
+        })
+    }
+
+    pub(super) fn parse_active_positions(
+        &self,
+        response: &RestResponse,
+    ) -> Result<Vec<ActivePosition>> {
+        let subaccount: DydxSubaccountResponse =
+            serde_json::from_str(&response.content).context("Failed to parse subaccount")?;
+
+        let now = Utc::now();
+        subaccount
+            .subaccount
+            .open_perpetual_positions
+            .into_values()
+            .filter(|x| !x.size.is_zero())
+            .map(|position| {
+                let derivative_position = DerivativePosition::new(
+                    self.get_unified_currency_pair(&position.market)?,
+                    position.size,
+                    position.entry_price,
+                    // liquidation price depends on the whole cross margin subaccount
+                    Decimal::ZERO,
+                    dec!(1),
+                );
+
+                Ok(ActivePosition::new(derivative_position, now))
+            })
+            .try_collect()
+    }
+
+    /// Position is closed by reduce-only immediate-or-cancel order
+    pub(super) async fn do_close_position(
+        &self,
+        position: &ActivePosition,
+        price: Option<Price>,
+    ) -> Result<ClosedPosition> {
+        let currency_pair = position.derivative.currency_pair;
+        let ticker = self.get_specific_currency_pair(currency_pair);
+        let market = self.get_market_params(currency_pair)?;
+        let side = match position.derivative.position.is_sign_positive() {
+            true => OrderSide::Sell,
+            false => OrderSide::Buy,
+        };
+        let price = match price {
+            Some(price) => price,
+            None => self.get_market_order_price(ticker, side).await?,
+        };
+        let amount = position.derivative.position.abs();
+        let client_order_id = ClientOrderId::unique_id();
+        let client_id = Self::get_client_id(&client_order_id)?;
+
+        let message = MsgPlaceOrder {
+            order: Some(Order {
+                order_id: Some(OrderId {
+                    subaccount_id: Some(self.subaccount_id()),
+                    client_id,
+                    order_flags: ORDER_FLAGS_SHORT_TERM,
+                    clob_pair_id: market.clob_pair_id,
+                }),
+                side: Self::to_side(side) as i32,
+                quantums: market.to_quantums(amount)?,
+                subticks: market.to_subticks(price)?,
+                good_til: Some(GoodTil::Block(self.get_good_til_block().await?)),
+                time_in_force: TimeInForce::ImmediateOrCancel as i32,
+                reduce_only: true,
+            }),
+        };
+        self.send_transaction(Any::pack(MSG_PLACE_ORDER_TYPE_URL, &message), false)
+            .await?;
+
+        Ok(ClosedPosition::new(
+            Self::get_exchange_order_id(client_id),
+            amount,
+        ))
+    }
+
+    #[named]
+    pub(super) async fn request_get_server_time(&self) -> Result<RestResponse, ExchangeError> {
+        let uri = UriBuilder::from_path("/v4/time").build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_get_server_time(&self, response: &RestResponse) -> Result<i64> {
+        let time: DydxTime =
+            serde_json::from_str(&response.content).context("Failed to parse server time")?;
+
+        Ok((time.epoch * 1000.0) as i64)
+    }
+}
+
+pub struct DydxBuilder;
+
+impl ExchangeClientBuilder for DydxBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+        _timeout_manager: Arc<TimeoutManager>,
+        _orders: Arc<OrdersPool>,
+    ) -> ExchangeClientBuilderResult {
+        ExchangeClientBuilderResult {
+            client: Box::new(Dydx::new(
+                exchange_settings,
+                events_channel,
+                lifetime_manager,
+            )),
+            features: ExchangeFeatures::new(
+                OpenOrdersType::AllCurrencyPair,
+                RestFillsFeatures::new(RestFillsType::MyTrades),
+                OrderFeatures {
+                    maker_only: true,
+                    supports_get_order_info_by_client_order_id: true,
+                    cancellation_response_from_rest_only_for_errors: true,
+                    creation_response_from_rest_only_for_errors: true,
+                    order_was_completed_error_for_cancellation: false,
+                    supports_already_cancelled_order: false,
+                    supports_stop_loss_order: false,
+                },
+                OrderTradeOption {
+                    supports_trade_time: true,
+                    supports_trade_incremented_id: false,
+                    supports_get_prints: true,
+                    supports_tick_direction: false,
+                    supports_my_trades_from_time: false,
+                },
+                WebSocketOptions {
+                    execution_notification: true,
+                    cancellation_notification: true,
+                    supports_ping_pong: true,
+                    supports_subscription_response: true,
+                },
+                EMPTY_RESPONSE_IS_OK,
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+            ),
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        // indexer allows 100 requests per 10 seconds
+        RequestTimeoutArguments::from_requests_per_minute(600)
+    }
+
+    fn get_exchange_id(&self) -> ExchangeId {
+        "dYdX".into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn btc_market() -> MarketParams {
+        MarketParams {
+            clob_pair_id: 0,
+            atomic_resolution: -10,
+            quantum_conversion_exponent: -9,
+            step_base_quantums: 1000000,
+            subticks_per_tick: 100000,
+        }
+    }
+
+    #[test]
+    fn amount_to_quantums() {
+        let market = btc_market();
+
+        assert_eq!(market.to_quantums(dec!(0.0001)).expect("in test"), 1000000);
+        assert_eq!(
+            market.to_quantums(dec!(1.23454)).expect("in test"),
+            12345000000
+        );
+        // amount less than step is rounded up to min size
+        assert_eq!(market.to_quantums(dec!(0.00001)).expect("in test"), 1000000);
+    }
+
+    #[test]
+    fn price_to_subticks() {
+        let market = btc_market();
+
+        assert_eq!(
+            market.to_subticks(dec!(30000)).expect("in test"),
+            3000000000
+        );
+        assert_eq!(
+            market.to_subticks(dec!(30000.4)).expect("in test"),
+            3000000000
+        );
+        assert_eq!(
+            market.to_subticks(dec!(30001)).expect("in test"),
+            3000100000
+        );
+    }
+
+    #[test]
+    fn client_id_conversion() {
+        let client_id = Dydx::get_client_id(&"1690000000".into()).expect("in test");
+
+        assert_eq!(client_id, 1690000000);
+        assert_eq!(Dydx::get_client_order_id(client_id).as_str(), "1690000000");
+        assert!(Dydx::get_client_id(&"99999999999".into()).is_err());
+    }
+
+    #[test]
+    fn rejected_transaction() {
+        let response = RestResponse::new(
+            r#"{"tx_response":{"height":"0","txhash":"6A2D1D2F","codespace":"clob","code":3007,"data":"","raw_log":"Order would cross maker orders with post-only flag: invalid order","logs":[],"info":"","gas_wanted":"0","gas_used":"0","tx":null,"timestamp":"","events":[]}}"#.to_owned(),
+            hyper::StatusCode::OK,
+        );
+
+        let error = Dydx::parse_broadcast(&response).expect_err("in test");
+
+        assert_eq!(error.error_type, ExchangeErrorType::InvalidOrder);
+        assert_eq!(error.code, Some(3007));
+    }
+}
@@ -0,0 +1,161 @@
+use crate::dydx::Dydx;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use mmb_core::exchanges::general::exchange::RequestResult;
+use mmb_core::exchanges::general::order::cancel::CancelOrderResult;
+use mmb_core::exchanges::general::order::create::CreateOrderResult;
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::traits::{ExchangeClient, ExchangeError};
+use mmb_domain::events::{EventSourceType, ExchangeBalancesAndPositions};
+use mmb_domain::exchanges::symbol::Symbol;
+use mmb_domain::market::CurrencyPair;
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{ExchangeOrderId, OrderInfo, OrderOptions, Price, UserOrder};
+use mmb_domain::position::{ActivePosition, ClosedPosition};
+use mmb_utils::DateTime;
+use std::sync::Arc;
+
+/// Orders are managed by signed transactions broadcasted to full node,
+/// indexer REST API is used for snapshots of subaccount state and metadata
+#[async_trait]
+impl ExchangeClient for Dydx {
+    async fn create_order(&self, order: &OrderRef) -> CreateOrderResult {
+        match self.do_create_order(order).await {
+            Ok(order_id) => CreateOrderResult::succeed(&order_id, EventSourceType::Rest),
+            Err(err) => CreateOrderResult::failed(err, EventSourceType::Rest),
+        }
+    }
+
+    async fn cancel_order(
+        &self,
+        order: &OrderRef,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> CancelOrderResult {
+        // market orders are placed as short-term ones
+        let header = order.header();
+        let is_long_term = !matches!(header.options, OrderOptions::User(UserOrder::Market));
+
+        match self
+            .do_cancel_order(header.currency_pair, exchange_order_id, is_long_term)
+            .await
+        {
+            Ok(_) => {
+                CancelOrderResult::succeed(order.client_order_id(), EventSourceType::Rest, None)
+            }
+            Err(err) => CancelOrderResult::failed(err, EventSourceType::Rest),
+        }
+    }
+
+    async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        match self.do_cancel_all_orders(currency_pair).await {
+            Ok(_) => Ok(()),
+            Err(error) => bail!("Failed to cancel all orders: {error:?}"),
+        }
+    }
+
+    async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
+        let response = self.request_orders(None, true).await?;
+
+        self.parse_open_orders(&response)
+    }
+
+    async fn get_open_orders_by_currency_pair(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<Vec<OrderInfo>> {
+        let response = self.request_orders(Some(currency_pair), true).await?;
+
+        self.parse_open_orders(&response)
+    }
+
+    async fn get_order_info(&self, order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
+        match self
+            .request_orders(Some(order.currency_pair()), false)
+            .await
+        {
+            Ok(response) => self
+                .parse_order_info(&response, &order.client_order_id())
+                .map_err(|err| {
+                    ExchangeError::parsing(format!("Unable to parse order info: {err:?}"))
+                }),
+            Err(error) => Err(ExchangeError::unknown(
+                format!("Failed to get order info: {error:?}").as_str(),
+            )),
+        }
+    }
+
+    async fn close_position(
+        &self,
+        position: &ActivePosition,
+        price: Option<Price>,
+    ) -> Result<ClosedPosition> {
+        self.do_close_position(position, price).await
+    }
+
+    async fn get_active_positions(&self) -> Result<Vec<ActivePosition>> {
+        let response = self.request_get_subaccount().await?;
+
+        self.parse_active_positions(&response)
+    }
+
+    /// Balance and positions are received by single request of subaccount
+    async fn get_balance_and_positions(&self) -> Result<ExchangeBalancesAndPositions> {
+        let response = self.request_get_subaccount().await?;
+        let balance = self.parse_get_balance(&response)?;
+        let positions = self
+            .parse_active_positions(&response)?
+            .into_iter()
+            .map(|x| x.derivative)
+            .collect();
+
+        Ok(ExchangeBalancesAndPositions {
+            balances: vec![balance],
+            positions: Some(positions),
+        })
+    }
+
+    async fn get_my_trades(
+        &self,
+        symbol: &Symbol,
+        last_date_time: Option<DateTime>,
+    ) -> RequestResult<Vec<OrderTrade>> {
+        let fills = match self.request_my_trades(symbol).await {
+            Ok(response) => match self.parse_my_trades(&response, last_date_time) {
+                Ok(fills) => fills,
+                Err(err) => {
+                    return RequestResult::Error(ExchangeError::parsing(format!(
+                        "Unable to parse trades: {err:?}"
+                    )))
+                }
+            },
+            Err(err) => {
+                return RequestResult::Error(ExchangeError::unknown(
+                    format!("Failed to get trades: {err:?}").as_str(),
+                ))
+            }
+        };
+
+        match self.fills_to_order_trades(fills).await {
+            Ok(trades) => RequestResult::Success(trades),
+            Err(err) => RequestResult::Error(ExchangeError::unknown(
+                format!("Failed to get orders of trades: {err:?}").as_str(),
+            )),
+        }
+    }
+
+    async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>> {
+        let response = self
+            .request_all_symbols()
+            .await
+            .context("Unable to request dYdX perpetual markets")?;
+
+        self.parse_all_symbols(&response)
+    }
+
+    async fn get_server_time(&self) -> Option<Result<i64>> {
+        Some(match self.request_get_server_time().await {
+            Ok(response) => self.parse_get_server_time(&response),
+            Err(err) => Err(err.into()),
+        })
+    }
+}
@@ -0,0 +1,73 @@
+use anyhow::{Context, Result};
+use k256::ecdsa::signature::Signer;
+use k256::ecdsa::{Signature, SigningKey};
+
+/// Signs transactions by secp256k1 key of Cosmos account which owns dYdX subaccounts
+pub struct DydxSigner {
+    signing_key: SigningKey,
+}
+
+impl DydxSigner {
+    /// Private key is expected as hex string of 32 bytes, e.g. exported from wallet
+    pub fn from_hex(private_key: &str) -> Result<Self> {
+        let private_key = private_key.trim_start_matches("0x");
+        let bytes = hex::decode(private_key).context("Private key of dYdX isn't hex string")?;
+        let signing_key = SigningKey::from_slice(&bytes)
+            .map_err(|err| anyhow::anyhow!("Invalid private key of dYdX: {err}"))?;
+
+        Ok(Self { signing_key })
+    }
+
+    /// Compressed SEC1 public key (33 bytes), the only format accepted by Cosmos SDK
+    pub fn public_key(&self) -> Vec<u8> {
+        self.signing_key
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec()
+    }
+
+    /// Deterministic (RFC 6979) signature of SHA-256 of message in 64 bytes `r || s` format
+    /// with normalized low `s` as required by Cosmos SDK
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        let signature: Signature = self.signing_key.sign(message);
+        let signature = signature.normalize_s().unwrap_or(signature);
+
+        signature.to_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIVATE_KEY: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+
+    #[test]
+    fn public_key_is_compressed() {
+        let signer = DydxSigner::from_hex(PRIVATE_KEY).expect("in test");
+
+        assert_eq!(
+            hex::encode(signer.public_key()),
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        );
+    }
+
+    #[test]
+    fn deterministic_signature() {
+        let signer = DydxSigner::from_hex(PRIVATE_KEY).expect("in test");
+
+        let signature = signer.sign(b"Satoshi Nakamoto");
+
+        assert_eq!(
+            hex::encode(signature),
+            "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d82442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5"
+        );
+    }
+
+    #[test]
+    fn invalid_private_key() {
+        assert!(DydxSigner::from_hex("not a key").is_err());
+        assert!(DydxSigner::from_hex(&"0".repeat(64)).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+use crate::dydx::{Dydx, COLLATERAL_CURRENCY, SUBACCOUNT_NUMBER};
+use crate::types::{
+    DydxFill, DydxOrder, DydxOrderBookSnapshot, DydxOrderBookUpdate, DydxSubaccountUpdate,
+    DydxTrades, DydxWebsocketMessage,
+};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use dashmap::DashMap;
+use mmb_core::connectivity::WebSocketRole;
+use mmb_core::exchanges::common::send_event;
+use mmb_core::exchanges::general::handlers::handle_order_filled::{
+    FillAmount, FillEvent, SpecialOrderData,
+};
+use mmb_core::exchanges::traits::{
+    HandleMetricsCb, HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb,
+    SendWebsocketMessageCb, Support,
+};
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{EventSourceType, ExchangeEvent, Trade, TradeId};
+use mmb_domain::market::{CurrencyCode, CurrencyId, CurrencyPair, SpecificCurrencyPair};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order_book::event::{EventType, OrderBookEvent};
+use mmb_domain::order_book::order_book_data::OrderBookData;
+use serde_json::{json, Value};
+use std::any::Any;
+use std::sync::Arc;
+use url::Url;
+
+#[async_trait]
+impl Support for Dydx {
+    fn as_any(&self) -> &(dyn Any + Send + Sync + 'static) {
+        self
+    }
+
+    fn on_websocket_message(&self, msg: &str) -> Result<()> {
+        let message: DydxWebsocketMessage = serde_json::from_str(msg)
+            .with_context(|| format!("Unable to parse websocket message:\n{msg}"))?;
+
+        match message.message_type.as_str() {
+            "subscribed" | "channel_data" => self.handle_channel_message(message, false),
+            // channels subscribed with `batched` flag send arrays of updates
+            "channel_batch_data" => self.handle_channel_message(message, true),
+            "connected" | "unsubscribed" => Ok(()),
+            "error" => {
+                let err = format!("dYdX websocket: error received: {msg}");
+                log::error!("{err}");
+                bail!(err)
+            }
+            message_type => {
+                log::warn!("Unsupported dYdX websocket message type {message_type}: {msg}");
+                Ok(())
+            }
+        }
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_connected(&self) -> Result<()> {
+        // indexer accepts single market per subscription
+        let traded_currencies = self.traded_specific_currencies.lock().clone();
+        for ticker in traded_currencies {
+            for channel in ["v4_orderbook", "v4_trades"] {
+                self.subscribe(WebSocketRole::Main, channel, ticker.as_str())?;
+            }
+        }
+
+        if !self.is_websocket_enabled(WebSocketRole::Secondary) {
+            return Ok(());
+        }
+
+        // Subaccount channel is public, it's identified by address of wallet and number of subaccount
+        self.subscribe(
+            WebSocketRole::Secondary,
+            "v4_subaccounts",
+            &format!("{}/{SUBACCOUNT_NUMBER}", self.address),
+        )
+    }
+
+    fn on_disconnected(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_send_websocket_message_callback(&mut self, callback: SendWebsocketMessageCb) {
+        self.websocket_message_callback = callback;
+    }
+
+    fn set_order_created_callback(&mut self, callback: OrderCreatedCb) {
+        self.order_created_callback = callback;
+    }
+
+    fn set_order_cancelled_callback(&mut self, callback: OrderCancelledCb) {
+        self.order_cancelled_callback = callback;
+    }
+
+    fn set_handle_order_filled_callback(&mut self, callback: HandleOrderFilledCb) {
+        self.handle_order_filled_callback = callback;
+    }
+
+    fn set_handle_trade_callback(&mut self, callback: HandleTradeCb) {
+        self.handle_trade_callback = callback;
+    }
+
+    fn set_handle_metrics_callback(&mut self, callback: HandleMetricsCb) {
+        self.handle_metrics_callback = callback;
+    }
+
+    fn set_traded_specific_currencies(&self, currencies: Vec<SpecificCurrencyPair>) {
+        *self.traded_specific_currencies.lock() = currencies;
+    }
+
+    /// Main connection is used for market data and secondary one for updates of subaccount
+    fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
+        match role {
+            WebSocketRole::Main => true,
+            WebSocketRole::Secondary => {
+                !self.settings.is_watch_only && !self.settings.api_key.is_empty()
+            }
+        }
+    }
+
+    async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url> {
+        let host = match role {
+            WebSocketRole::Main => self.hosts.web_socket_host,
+            WebSocketRole::Secondary => self.hosts.web_socket2_host,
+        };
+
+        Url::parse(host).with_context(|| format!("Unable parse websocket {role:?} uri"))
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        self.unified_to_specific.read()[&currency_pair]
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        &self.supported_currencies
+    }
+
+    fn should_log_message(&self, message: &str) -> bool {
+        message.contains("v4_subaccounts")
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        &self.settings
+    }
+}
+
+impl Dydx {
+    fn subscribe(&self, role: WebSocketRole, channel: &str, id: &str) -> Result<()> {
+        let request = json!({
+            "type": "subscribe",
+            "channel": channel,
+            "id": id,
+        });
+
+        (self.websocket_message_callback)(role, request.to_string())
+    }
+
+    fn handle_channel_message(&self, message: DydxWebsocketMessage, is_batch: bool) -> Result<()> {
+        let is_initial = message.message_type == "subscribed";
+        let channel = message.channel.context("Message doesn't contain channel")?;
+        let contents = message
+            .contents
+            .context("Message doesn't contain contents")?;
+
+        let contents = match (is_batch, contents) {
+            (true, Value::Array(contents)) => contents,
+            (true, contents) => bail!("Unexpected dYdX batch contents {contents}"),
+            (false, contents) => vec![contents],
+        };
+
+        for contents in contents {
+            match channel.as_str() {
+                "v4_orderbook" => {
+                    let ticker = message.id.as_deref().context("Order book without market")?;
+                    self.handle_order_book(ticker.into(), contents, is_initial)?;
+                }
+                // initial message contains history of trades which is skipped
+                "v4_trades" if is_initial => {}
+                "v4_trades" => {
+                    let ticker = message.id.as_deref().context("Trades without market")?;
+                    self.handle_trades(ticker.into(), contents)?;
+                }
+                "v4_subaccounts" => self.handle_subaccount_message(contents, is_initial)?,
+                _ => log::warn!("Unsupported dYdX channel {channel}"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Initial message of channel contains full order book and next ones contain changed levels
+    fn handle_order_book(
+        &self,
+        ticker: SpecificCurrencyPair,
+        contents: Value,
+        is_snapshot: bool,
+    ) -> Result<()> {
+        let (event_type, data) = match is_snapshot {
+            true => {
+                let order_book: DydxOrderBookSnapshot = serde_json::from_value(contents)
+                    .context("Unable to parse dYdX order book snapshot")?;
+                (
+                    EventType::Snapshot,
+                    OrderBookData::new(
+                        order_book
+                            .asks
+                            .into_iter()
+                            .map(|x| (x.price, x.size))
+                            .collect(),
+                        order_book
+                            .bids
+                            .into_iter()
+                            .map(|x| (x.price, x.size))
+                            .collect(),
+                    ),
+                )
+            }
+            false => {
+                let order_book: DydxOrderBookUpdate = serde_json::from_value(contents)
+                    .context("Unable to parse dYdX order book update")?;
+                (
+                    EventType::Update,
+                    OrderBookData::new(
+                        order_book.asks.into_iter().collect(),
+                        order_book.bids.into_iter().collect(),
+                    ),
+                )
+            }
+        };
+
+        let order_book_event = OrderBookEvent::new(
+            Utc::now(),
+            self.settings.exchange_account_id,
+            self.get_unified_currency_pair(&ticker)?,
+            String::default(),
+            event_type,
+            Arc::new(data),
+        );
+
+        send_event(
+            &self.events_channel,
+            self.lifetime_manager.clone(),
+            self.settings.exchange_account_id,
+            ExchangeEvent::OrderBookEvent(order_book_event),
+        )
+    }
+
+    fn handle_trades(&self, ticker: SpecificCurrencyPair, contents: Value) -> Result<()> {
+        let trades: DydxTrades =
+            serde_json::from_value(contents).context("Unable to parse dYdX trades")?;
+        let currency_pair = self.get_unified_currency_pair(&ticker)?;
+
+        for trade in trades.trades {
+            (self.handle_trade_callback)(
+                currency_pair,
+                Trade {
+                    trade_id: TradeId::String(trade.id.into_boxed_str()),
+                    price: trade.price,
+                    quantity: trade.size,
+                    side: trade.side,
+                    transaction_time: trade.created_at,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Initial message contains state of subaccount with open orders, they are only remembered
+    /// to match fills. Orders are handled before fills because fills refer to orders by indexer id
+    fn handle_subaccount_message(&self, contents: Value, is_initial: bool) -> Result<()> {
+        if is_initial {
+            let orders = contents.get("orders").cloned().unwrap_or(Value::Null);
+            let orders: Option<Vec<DydxOrder>> =
+                serde_json::from_value(orders).context("Unable to parse dYdX subaccount orders")?;
+            for order in orders.unwrap_or_default() {
+                self.remember_client_id(&order);
+            }
+            return Ok(());
+        }
+
+        let update: DydxSubaccountUpdate =
+            serde_json::from_value(contents).context("Unable to parse dYdX subaccount update")?;
+
+        for order in update.orders {
+            self.handle_order_update(order);
+        }
+
+        for fill in update.fills {
+            self.handle_fill(fill)?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn handle_order_update(&self, order: DydxOrder) {
+        self.remember_client_id(&order);
+
+        let client_order_id = Dydx::get_client_order_id(order.client_id);
+        let exchange_order_id = Dydx::get_exchange_order_id(order.client_id);
+        match order.status.as_str() {
+            "OPEN" | "BEST_EFFORT_OPENED" => (self.order_created_callback)(
+                client_order_id,
+                exchange_order_id,
+                EventSourceType::WebSocket,
+            ),
+            "CANCELED" | "BEST_EFFORT_CANCELED" => (self.order_cancelled_callback)(
+                client_order_id,
+                exchange_order_id,
+                EventSourceType::WebSocket,
+            ),
+            // order state is completed by fills
+            _ => (),
+        }
+    }
+
+    fn handle_fill(&self, fill: DydxFill) -> Result<()> {
+        let client_id = match fill
+            .order_id
+            .as_ref()
+            .and_then(|x| self.client_ids_by_order_id.get(x))
+        {
+            Some(client_id) => *client_id,
+            None => {
+                // liquidations and deleveraging aren't initiated by orders
+                log::warn!("Skipped dYdX fill of unknown order {fill:?}");
+                return Ok(());
+            }
+        };
+
+        let fill_event = FillEvent {
+            source_type: EventSourceType::WebSocket,
+            trade_id: Some(TradeId::String(fill.id.into_boxed_str())),
+            client_order_id: Some(Dydx::get_client_order_id(client_id)),
+            exchange_order_id: Dydx::get_exchange_order_id(client_id),
+            fill_price: fill.price,
+            fill_amount: FillAmount::Incremental {
+                fill_amount: fill.size,
+                total_filled_amount: None,
+            },
+            order_role: Some(Dydx::get_order_role(&fill.liquidity)?),
+            commission_currency_code: Some(COLLATERAL_CURRENCY.into()),
+            commission_rate: None,
+            commission_amount: Some(fill.fee),
+            fill_type: OrderFillType::UserTrade,
+            special_order_data: Some(SpecialOrderData {
+                currency_pair: self.get_unified_currency_pair(&fill.market)?,
+                order_side: fill.side,
+                order_amount: fill.size,
+            }),
+            fill_date: Some(fill.created_at),
+        };
+
+        (self.handle_order_filled_callback)(fill_event);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{
+        DydxOrderBookSnapshot, DydxOrderBookUpdate, DydxSubaccountUpdate, DydxWebsocketMessage,
+    };
+    use mmb_domain::order::snapshot::OrderSide;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parse_order_book_messages() {
+        let msg = r#"{"type":"subscribed","connection_id":"0c9d1c8d","message_id":1,"channel":"v4_orderbook","id":"BTC-USD","contents":{"bids":[{"price":"30000","size":"0.5"}],"asks":[{"price":"30001","size":"1.2"}]}}"#;
+        let message: DydxWebsocketMessage = serde_json::from_str(msg).expect("in test");
+        let snapshot: DydxOrderBookSnapshot =
+            serde_json::from_value(message.contents.expect("in test")).expect("in test");
+
+        assert_eq!(message.id.as_deref(), Some("BTC-USD"));
+        assert_eq!(snapshot.bids[0].price, dec!(30000));
+        assert_eq!(snapshot.asks[0].size, dec!(1.2));
+
+        let msg = r#"{"type":"channel_data","connection_id":"0c9d1c8d","message_id":2,"id":"BTC-USD","channel":"v4_orderbook","version":"1.0.0","contents":{"bids":[["29999","0"]]}}"#;
+        let message: DydxWebsocketMessage = serde_json::from_str(msg).expect("in test");
+        let update: DydxOrderBookUpdate =
+            serde_json::from_value(message.contents.expect("in test")).expect("in test");
+
+        assert!(update.asks.is_empty());
+        assert_eq!(update.bids, vec![(dec!(29999), dec!(0))]);
+    }
+
+    #[test]
+    fn parse_subaccount_update() {
+        let msg = r#"{"type":"channel_data","connection_id":"0c9d1c8d","message_id":5,"id":"dydx1qqgzqvzq2ps8pqys5zcvp58q7rluextx92xhln/0","channel":"v4_subaccounts","version":"3.0.0","contents":{"orders":[{"id":"1b2d8a5c-7b6e-5f4a-9a2d-3c1e0f6b8d7a","subaccountId":"9a2e8c53-0c4e-5a6f-8d1b-7e3f2a1c0b9d","clientId":"1690000001","clobPairId":"0","side":"BUY","size":"0.01","totalFilled":"0.01","price":"30000","type":"LIMIT","status":"FILLED","timeInForce":"GTT","postOnly":false,"reduceOnly":false,"orderFlags":"64","goodTilBlockTime":"2023-08-20T00:00:00.000Z","ticker":"BTC-USD"}],"fills":[{"id":"7c1f2e3d-4b5a-5968-8776-a5b4c3d2e1f0","subaccountId":"9a2e8c53-0c4e-5a6f-8d1b-7e3f2a1c0b9d","side":"BUY","liquidity":"MAKER","type":"LIMIT","clobPairId":"0","orderId":"1b2d8a5c-7b6e-5f4a-9a2d-3c1e0f6b8d7a","size":"0.01","price":"30000","quoteAmount":"300","eventId":"0001","transactionHash":"5B2C","createdAt":"2023-07-21T10:00:00.000Z","createdAtHeight":"1000","ticker":"BTC-USD","fee":"-0.033"}]}}"#;
+
+        let message: DydxWebsocketMessage = serde_json::from_str(msg).expect("in test");
+        let update: DydxSubaccountUpdate =
+            serde_json::from_value(message.contents.expect("in test")).expect("in test");
+
+        let order = &update.orders[0];
+        assert_eq!(order.client_id, 1690000001);
+        assert_eq!(order.order_flags, 64);
+        assert_eq!(order.side, Some(OrderSide::Buy));
+        assert_eq!(order.status, "FILLED");
+
+        let fill = &update.fills[0];
+        assert_eq!(fill.order_id.as_deref(), Some(order.id.as_str()));
+        assert_eq!(fill.market.as_str(), "BTC-USD");
+        assert_eq!(fill.fee, dec!(-0.033));
+        assert_eq!(fill.created_at.timestamp_millis(), 1689933600000);
+    }
+}
@@ -0,0 +1,285 @@
+//! Protobuf messages of dYdX chain and Cosmos SDK transactions which are needed for order management.
+//! Definitions mirror `dydxprotocol/clob` and `cosmos/tx/v1beta1` proto files, only used fields are declared.
+
+use crate::signer::DydxSigner;
+use prost::Message;
+
+pub const MSG_PLACE_ORDER_TYPE_URL: &str = "/dydxprotocol.clob.MsgPlaceOrder";
+pub const MSG_CANCEL_ORDER_TYPE_URL: &str = "/dydxprotocol.clob.MsgCancelOrder";
+const SECP256K1_PUBLIC_KEY_TYPE_URL: &str = "/cosmos.crypto.secp256k1.PubKey";
+
+/// `SIGN_MODE_DIRECT` of Cosmos SDK
+const SIGN_MODE_DIRECT: i32 = 1;
+
+/// Order messages don't charge fees, but gas limit is still checked by the chain
+const GAS_LIMIT: u64 = 1_000_000;
+
+/// Flags of orders which are stored in state of the chain until expiration or cancellation
+pub const ORDER_FLAGS_LONG_TERM: u32 = 64;
+/// Flags of orders which live only in memory of validators during several blocks
+pub const ORDER_FLAGS_SHORT_TERM: u32 = 0;
+
+#[derive(Clone, PartialEq, Eq, Copy, Debug)]
+#[repr(i32)]
+pub enum Side {
+    Buy = 1,
+    Sell = 2,
+}
+
+#[derive(Clone, PartialEq, Eq, Copy, Debug)]
+#[repr(i32)]
+pub enum TimeInForce {
+    Unspecified = 0,
+    ImmediateOrCancel = 1,
+    PostOnly = 2,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct SubaccountId {
+    #[prost(string, tag = "1")]
+    pub owner: String,
+    #[prost(uint32, tag = "2")]
+    pub number: u32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct OrderId {
+    #[prost(message, optional, tag = "1")]
+    pub subaccount_id: Option<SubaccountId>,
+    #[prost(fixed32, tag = "2")]
+    pub client_id: u32,
+    #[prost(uint32, tag = "3")]
+    pub order_flags: u32,
+    #[prost(uint32, tag = "4")]
+    pub clob_pair_id: u32,
+}
+
+/// Expiration of order or cancellation: short-term ones expire by block height and long-term ones by time
+#[derive(Clone, PartialEq, Eq, prost::Oneof)]
+pub enum GoodTil {
+    #[prost(uint32, tag = "5")]
+    Block(u32),
+    #[prost(fixed32, tag = "6")]
+    BlockTime(u32),
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Order {
+    #[prost(message, optional, tag = "1")]
+    pub order_id: Option<OrderId>,
+    #[prost(int32, tag = "2")]
+    pub side: i32,
+    #[prost(uint64, tag = "3")]
+    pub quantums: u64,
+    #[prost(uint64, tag = "4")]
+    pub subticks: u64,
+    #[prost(oneof = "GoodTil", tags = "5, 6")]
+    pub good_til: Option<GoodTil>,
+    #[prost(int32, tag = "7")]
+    pub time_in_force: i32,
+    #[prost(bool, tag = "8")]
+    pub reduce_only: bool,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgPlaceOrder {
+    #[prost(message, optional, tag = "1")]
+    pub order: Option<Order>,
+}
+
+/// Tags of expiration differ from the ones of `Order`
+#[derive(Clone, PartialEq, Eq, prost::Oneof)]
+pub enum CancelGoodTil {
+    #[prost(uint32, tag = "2")]
+    Block(u32),
+    #[prost(fixed32, tag = "3")]
+    BlockTime(u32),
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgCancelOrder {
+    #[prost(message, optional, tag = "1")]
+    pub order_id: Option<OrderId>,
+    #[prost(oneof = "CancelGoodTil", tags = "2, 3")]
+    pub good_til: Option<CancelGoodTil>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Any {
+    #[prost(string, tag = "1")]
+    pub type_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: Vec<u8>,
+}
+
+impl Any {
+    pub fn pack(type_url: &str, message: &impl Message) -> Self {
+        Self {
+            type_url: type_url.to_owned(),
+            value: message.encode_to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TxBody {
+    #[prost(message, repeated, tag = "1")]
+    messages: Vec<Any>,
+    #[prost(string, tag = "2")]
+    memo: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct PubKey {
+    #[prost(bytes = "vec", tag = "1")]
+    key: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ModeInfoSingle {
+    #[prost(int32, tag = "1")]
+    mode: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ModeInfo {
+    #[prost(message, optional, tag = "1")]
+    single: Option<ModeInfoSingle>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct SignerInfo {
+    #[prost(message, optional, tag = "1")]
+    public_key: Option<Any>,
+    #[prost(message, optional, tag = "2")]
+    mode_info: Option<ModeInfo>,
+    #[prost(uint64, tag = "3")]
+    sequence: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Fee {
+    #[prost(uint64, tag = "2")]
+    gas_limit: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct AuthInfo {
+    #[prost(message, repeated, tag = "1")]
+    signer_infos: Vec<SignerInfo>,
+    #[prost(message, optional, tag = "2")]
+    fee: Option<Fee>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct SignDoc {
+    #[prost(bytes = "vec", tag = "1")]
+    body_bytes: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    auth_info_bytes: Vec<u8>,
+    #[prost(string, tag = "3")]
+    chain_id: String,
+    #[prost(uint64, tag = "4")]
+    account_number: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TxRaw {
+    #[prost(bytes = "vec", tag = "1")]
+    body_bytes: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    auth_info_bytes: Vec<u8>,
+    #[prost(bytes = "vec", repeated, tag = "3")]
+    signatures: Vec<Vec<u8>>,
+}
+
+/// Account state which is required to sign transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccountSequence {
+    pub account_number: u64,
+    pub sequence: u64,
+}
+
+/// Builds signed transaction in `SIGN_MODE_DIRECT` and returns bytes of `TxRaw` ready for broadcasting
+pub fn build_transaction(
+    signer: &DydxSigner,
+    chain_id: &str,
+    account: AccountSequence,
+    message: Any,
+) -> Vec<u8> {
+    let body_bytes = TxBody {
+        messages: vec![message],
+        memo: String::new(),
+    }
+    .encode_to_vec();
+
+    let auth_info_bytes = AuthInfo {
+        signer_infos: vec![SignerInfo {
+            public_key: Some(Any::pack(
+                SECP256K1_PUBLIC_KEY_TYPE_URL,
+                &PubKey {
+                    key: signer.public_key(),
+                },
+            )),
+            mode_info: Some(ModeInfo {
+                single: Some(ModeInfoSingle {
+                    mode: SIGN_MODE_DIRECT,
+                }),
+            }),
+            sequence: account.sequence,
+        }],
+        fee: Some(Fee {
+            gas_limit: GAS_LIMIT,
+        }),
+    }
+    .encode_to_vec();
+
+    let sign_doc = SignDoc {
+        body_bytes: body_bytes.clone(),
+        auth_info_bytes: auth_info_bytes.clone(),
+        chain_id: chain_id.to_owned(),
+        account_number: account.account_number,
+    };
+    let signature = signer.sign(&sign_doc.encode_to_vec());
+
+    TxRaw {
+        body_bytes,
+        auth_info_bytes,
+        signatures: vec![signature],
+    }
+    .encode_to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_place_order() {
+        let message = MsgPlaceOrder {
+            order: Some(Order {
+                order_id: Some(OrderId {
+                    subaccount_id: Some(SubaccountId {
+                        owner: "dydx1".to_owned(),
+                        number: 0,
+                    }),
+                    client_id: 1,
+                    order_flags: ORDER_FLAGS_LONG_TERM,
+                    clob_pair_id: 0,
+                }),
+                side: Side::Buy as i32,
+                quantums: 1000000,
+                subticks: 3000000000,
+                good_til: Some(GoodTil::BlockTime(1700000000)),
+                time_in_force: TimeInForce::PostOnly as i32,
+                reduce_only: false,
+            }),
+        };
+
+        // fields with default values are skipped according to proto3 encoding
+        assert_eq!(
+            hex::encode(message.encode_to_vec()),
+            "0a250a100a070a05647964783115010000001840100118c0843d2080bcc1960b3500f153653802"
+        );
+    }
+}
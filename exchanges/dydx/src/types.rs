@@ -0,0 +1,255 @@
+use mmb_domain::market::SpecificCurrencyPair;
+use mmb_domain::order::snapshot::{Amount, OrderSide, Price};
+use mmb_utils::DateTime;
+use serde::{de, Deserialize, Deserializer};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Error of indexer API
+/// {
+/// "errors": [{ "msg": "...", "param": "...", "location": "query" }]
+/// }
+#[derive(Deserialize, Debug)]
+pub struct DydxIndexerErrors {
+    pub errors: Vec<DydxIndexerError>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DydxIndexerError {
+    pub msg: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DydxPerpetualMarkets {
+    pub markets: HashMap<String, DydxPerpetualMarket>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DydxPerpetualMarket {
+    pub ticker: SpecificCurrencyPair,
+    #[serde(deserialize_with = "number_from_string")]
+    pub clob_pair_id: u32,
+    /// `ACTIVE`, `PAUSED`, `CANCEL_ONLY`, `POST_ONLY`, `INITIALIZING` or `FINAL_SETTLEMENT`
+    pub status: String,
+    pub oracle_price: Option<Price>,
+    pub tick_size: Price,
+    pub step_size: Amount,
+    pub atomic_resolution: i32,
+    pub quantum_conversion_exponent: i32,
+    pub step_base_quantums: u64,
+    pub subticks_per_tick: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DydxHeight {
+    #[serde(deserialize_with = "number_from_string")]
+    pub height: u32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DydxTime {
+    /// Unix time in seconds with fractional milliseconds
+    pub epoch: f64,
+}
+
+/// Order of indexer API and `v4_subaccounts` channel
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DydxOrder {
+    /// Identifier of order in indexer, it's derived from subaccount, client id, order flags and clob pair
+    pub id: String,
+    #[serde(deserialize_with = "number_from_string")]
+    pub client_id: u32,
+    /// `0` for short-term orders, `64` for long-term ones and `32` for conditional ones
+    #[serde(default, deserialize_with = "number_from_string")]
+    pub order_flags: u32,
+    pub ticker: Option<SpecificCurrencyPair>,
+    #[serde(default, deserialize_with = "optional_order_side")]
+    pub side: Option<OrderSide>,
+    pub size: Option<Amount>,
+    pub total_filled: Option<Amount>,
+    pub price: Option<Price>,
+    /// `OPEN`, `FILLED`, `CANCELED`, `BEST_EFFORT_CANCELED`, `BEST_EFFORT_OPENED` or `UNTRIGGERED`
+    pub status: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DydxFills {
+    pub fills: Vec<DydxFill>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DydxFill {
+    pub id: String,
+    #[serde(deserialize_with = "order_side")]
+    pub side: OrderSide,
+    /// `TAKER` or `MAKER`
+    pub liquidity: String,
+    /// Fills of liquidations and deleveraging don't have order
+    pub order_id: Option<String>,
+    #[serde(alias = "ticker")]
+    pub market: SpecificCurrencyPair,
+    pub price: Price,
+    pub size: Amount,
+    /// Fee in USDC, negative one is rebate
+    #[serde(default)]
+    pub fee: Amount,
+    pub created_at: DateTime,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DydxSubaccountResponse {
+    pub subaccount: DydxSubaccount,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DydxSubaccount {
+    pub free_collateral: Amount,
+    #[serde(default)]
+    pub open_perpetual_positions: HashMap<String, DydxPerpetualPosition>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DydxPerpetualPosition {
+    pub market: SpecificCurrencyPair,
+    /// Signed size of position, negative for short one
+    pub size: Amount,
+    pub entry_price: Price,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DydxNodeAccountResponse {
+    pub account: DydxNodeAccount,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DydxNodeAccount {
+    #[serde(deserialize_with = "number_from_string")]
+    pub account_number: u64,
+    #[serde(deserialize_with = "number_from_string")]
+    pub sequence: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DydxBroadcastResponse {
+    pub tx_response: DydxTxResponse,
+}
+
+/// Result of `CheckTx` of broadcasted transaction, non zero code means rejection
+#[derive(Deserialize, Debug)]
+pub struct DydxTxResponse {
+    pub code: u32,
+    #[serde(default)]
+    pub codespace: String,
+    #[serde(default)]
+    pub raw_log: String,
+    pub txhash: String,
+}
+
+/// Message of indexer websocket
+#[derive(Deserialize, Debug)]
+pub struct DydxWebsocketMessage {
+    #[serde(rename = "type")]
+    pub message_type: String,
+    pub channel: Option<String>,
+    pub id: Option<String>,
+    pub contents: Option<serde_json::Value>,
+    pub message: Option<String>,
+}
+
+/// Initial snapshot of `v4_orderbook` channel has levels as objects
+#[derive(Deserialize, Debug)]
+pub struct DydxOrderBookSnapshot {
+    #[serde(default)]
+    pub asks: Vec<DydxPriceLevel>,
+    #[serde(default)]
+    pub bids: Vec<DydxPriceLevel>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DydxPriceLevel {
+    pub price: Price,
+    pub size: Amount,
+}
+
+/// Updates of `v4_orderbook` channel have levels as arrays, zero size means removal of level
+#[derive(Deserialize, Debug)]
+pub struct DydxOrderBookUpdate {
+    #[serde(default)]
+    pub asks: Vec<(Price, Amount)>,
+    #[serde(default)]
+    pub bids: Vec<(Price, Amount)>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DydxTrades {
+    pub trades: Vec<DydxTrade>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DydxTrade {
+    pub id: String,
+    #[serde(deserialize_with = "order_side")]
+    pub side: OrderSide,
+    pub size: Amount,
+    pub price: Price,
+    pub created_at: DateTime,
+}
+
+/// Contents of `v4_subaccounts` channel, orders are sent before fills which refer to them
+#[derive(Deserialize, Debug, Default)]
+pub struct DydxSubaccountUpdate {
+    #[serde(default)]
+    pub orders: Vec<DydxOrder>,
+    #[serde(default)]
+    pub fills: Vec<DydxFill>,
+}
+
+/// Indexer sends integers as strings
+fn number_from_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Number<'a> {
+        Number(u64),
+        String(Cow<'a, str>),
+    }
+
+    let value = match Number::deserialize(deserializer)? {
+        Number::Number(number) => Cow::Owned(number.to_string()),
+        Number::String(number) => number,
+    };
+    value.parse().map_err(de::Error::custom)
+}
+
+fn order_side<'de, D>(deserializer: D) -> Result<OrderSide, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Cow::<str>::deserialize(deserializer)?;
+    match value.as_ref() {
+        "BUY" => Ok(OrderSide::Buy),
+        "SELL" => Ok(OrderSide::Sell),
+        _ => Err(de::Error::custom(format!("unknown order side {value}"))),
+    }
+}
+
+/// Updates of orders in `v4_subaccounts` channel may contain only changed fields
+fn optional_order_side<'de, D>(deserializer: D) -> Result<Option<OrderSide>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Side(#[serde(deserialize_with = "order_side")] OrderSide);
+
+    Ok(Option::<Side>::deserialize(deserializer)?.map(|Side(side)| side))
+}
@@ -0,0 +1,135 @@
+//! Description of FIX 4.4 session and instruments of venue.
+//! Credentials of session are taken from exchange settings: `api_key` is sent as `Username(553)`
+//! and `secret_key` as `Password(554)` of Logon message.
+
+use anyhow::{bail, Context, Result};
+use mmb_domain::market::ExchangeId;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::fs::read_to_string;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FixConfig {
+    pub exchange_id: ExchangeId,
+    pub host: String,
+    pub port: u16,
+    /// Connection is encrypted by TLS, plain TCP is usually used through stunnel or cross-connect
+    #[serde(default)]
+    pub tls: bool,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    /// `Account(1)` of orders
+    pub account: Option<String>,
+    /// Heartbeat interval in seconds
+    #[serde(default = "default_heartbeat_interval")]
+    pub heartbeat_interval: u64,
+    /// Sequence numbers are reset by `ResetSeqNumFlag(141)` on every logon,
+    /// otherwise they are kept between reconnections while the engine is running
+    #[serde(default = "default_true")]
+    pub reset_on_logon: bool,
+    /// Delay before reconnection in seconds
+    #[serde(default = "default_reconnect_interval")]
+    pub reconnect_interval: u64,
+    /// Open orders are requested by `OrderMassStatusRequest(AF)`, venues without its support
+    /// can't restore orders after restart
+    #[serde(default)]
+    pub mass_status: bool,
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: usize,
+    /// Instruments aren't requested from venue because security definitions differ a lot
+    pub symbols: Vec<SymbolConfig>,
+}
+
+fn default_heartbeat_interval() -> u64 {
+    30
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_reconnect_interval() -> u64 {
+    5
+}
+
+fn default_requests_per_minute() -> usize {
+    600
+}
+
+impl FixConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        let config =
+            read_to_string(path).with_context(|| format!("Unable load FIX config: {path}"))?;
+
+        Self::parse(&config).with_context(|| format!("Unable parse FIX config: {path}"))
+    }
+
+    pub fn parse(config: &str) -> Result<Self> {
+        let config: Self = toml_edit::de::from_str(config)?;
+
+        if config.heartbeat_interval == 0 {
+            bail!("Heartbeat interval should be positive");
+        }
+        if config.symbols.is_empty() {
+            bail!("No symbols are specified");
+        }
+
+        Ok(config)
+    }
+
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_interval)
+    }
+
+    pub fn reconnect_interval(&self) -> Duration {
+        Duration::from_secs(self.reconnect_interval)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SymbolConfig {
+    /// `Symbol(55)` of instrument
+    pub symbol: String,
+    pub base: String,
+    pub quote: String,
+    pub price_tick: Decimal,
+    pub amount_step: Decimal,
+    pub min_amount: Option<Decimal>,
+    pub min_cost: Option<Decimal>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parse_example_config() {
+        let config = FixConfig::parse(include_str!("../example.toml")).expect("in test");
+
+        assert_eq!(config.exchange_id.as_str(), "FixVenue");
+        assert_eq!(config.port, 4198);
+        assert!(config.tls);
+        assert_eq!(config.heartbeat_interval(), Duration::from_secs(30));
+        assert!(config.reset_on_logon);
+        assert_eq!(config.symbols.len(), 2);
+        assert_eq!(config.symbols[0].symbol, "BTC/USD");
+        assert_eq!(config.symbols[0].price_tick, dec!(0.5));
+        assert_eq!(config.symbols[1].min_amount, None);
+    }
+
+    #[test]
+    fn reject_config_without_symbols() {
+        let config = r#"
+            exchange_id = "FixVenue"
+            host = "fix.example.com"
+            port = 4198
+            sender_comp_id = "CLIENT"
+            target_comp_id = "VENUE"
+            symbols = []
+        "#;
+
+        assert!(FixConfig::parse(config).is_err());
+    }
+}
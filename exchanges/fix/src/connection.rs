@@ -0,0 +1,159 @@
+use crate::config::FixConfig;
+use crate::fix::Fix;
+use crate::message::FixDecoder;
+use anyhow::{bail, Context, Result};
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_utils::cancellation_token::CancellationToken;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, WriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::{self, ClientConfig, RootCertStore, ServerName};
+use tokio_rustls::TlsConnector;
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+/// Period of checking heartbeats
+const TIMER_INTERVAL: Duration = Duration::from_secs(1);
+
+trait FixStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> FixStream for T {}
+
+fn get_fix(exchange: &Exchange) -> &Fix {
+    exchange
+        .exchange_client
+        .as_any()
+        .downcast_ref::<Fix>()
+        .expect("exchange client should be Fix")
+}
+
+/// Keeps FIX session connected until the engine is stopped
+pub(crate) async fn run_session(
+    exchange: Weak<Exchange>,
+    stop_token: CancellationToken,
+) -> Result<()> {
+    loop {
+        let exchange = match exchange.upgrade() {
+            Some(exchange) => exchange,
+            None => return Ok(()),
+        };
+        let fix = get_fix(&exchange);
+
+        if let Err(err) = serve_connection(fix, &stop_token).await {
+            log::error!(
+                "FIX session of {} is broken: {err:?}",
+                fix.settings.exchange_account_id
+            );
+        }
+        fix.on_session_closed();
+
+        if stop_token.is_cancellation_requested() {
+            return Ok(());
+        }
+
+        let reconnect_interval = fix.config.reconnect_interval();
+        drop(exchange);
+        tokio::select! {
+            _ = tokio::time::sleep(reconnect_interval) => {}
+            _ = stop_token.when_cancelled() => return Ok(()),
+        }
+    }
+}
+
+async fn serve_connection(fix: &Fix, stop_token: &CancellationToken) -> Result<()> {
+    let stream = connect(&fix.config).await?;
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    fix.session
+        .on_connected(tx, fix.logon_credentials(), Instant::now())?;
+
+    let mut decoder = FixDecoder::default();
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+    let mut timer = tokio::time::interval(TIMER_INTERVAL);
+
+    let result: Result<()> = async {
+        loop {
+            tokio::select! {
+                read = reader.read(&mut buffer) => {
+                    let size = read.context("Unable to read from FIX connection")?;
+                    if size == 0 {
+                        bail!("FIX connection is closed by counterparty");
+                    }
+
+                    decoder.extend(&buffer[..size]);
+                    while let Some(message) = decoder.next_message()? {
+                        log::debug!("FIX message is received: {message}");
+                        for message in fix.session.on_message(message, Instant::now())? {
+                            fix.on_application_message(message);
+                        }
+                    }
+                }
+                data = rx.recv() => match data {
+                    Some(data) => writer
+                        .write_all(&data)
+                        .await
+                        .context("Unable to write to FIX connection")?,
+                    None => bail!("FIX session is closed"),
+                },
+                _ = timer.tick() => fix.session.on_timer(Instant::now())?,
+                _ = stop_token.when_cancelled() => {
+                    fix.session.logout("Shutdown")?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+    .await;
+
+    // logout caused by error or shutdown should be delivered before closing
+    fix.session.on_disconnected();
+    flush(&mut rx, &mut writer).await;
+
+    result
+}
+
+async fn flush(
+    rx: &mut mpsc::UnboundedReceiver<Vec<u8>>,
+    writer: &mut WriteHalf<Box<dyn FixStream>>,
+) {
+    while let Ok(data) = rx.try_recv() {
+        if writer.write_all(&data).await.is_err() {
+            return;
+        }
+    }
+    let _ = writer.shutdown().await;
+}
+
+async fn connect(config: &FixConfig) -> Result<Box<dyn FixStream>> {
+    let tcp_stream = TcpStream::connect((config.host.as_str(), config.port))
+        .await
+        .with_context(|| format!("Unable to connect to {}:{}", config.host, config.port))?;
+    tcp_stream.set_nodelay(true)?;
+
+    if !config.tls {
+        return Ok(Box::new(tcp_stream));
+    }
+
+    let mut root_store = RootCertStore::empty();
+    for certificate in
+        rustls_native_certs::load_native_certs().context("Unable to load native certificates")?
+    {
+        // invalid system certificates are skipped like in other TLS clients
+        let _ = root_store.add(&rustls::Certificate(certificate.0));
+    }
+    let tls_config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(config.host.as_str())
+        .with_context(|| format!("Invalid FIX host {}", config.host))?;
+
+    let tls_stream = TlsConnector::from(Arc::new(tls_config))
+        .connect(server_name, tcp_stream)
+        .await
+        .context("Unable to establish TLS connection")?;
+
+    Ok(Box::new(tls_stream))
+}
@@ -0,0 +1,934 @@
+use crate::config::FixConfig;
+use crate::message::{format_timestamp, msg_types, tags, FixMessage};
+use crate::session::FixSession;
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_core::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    RestFillsType, WebSocketOptions,
+};
+use mmb_core::exchanges::general::handlers::handle_order_filled::{
+    FillAmount, FillEvent, SpecialOrderData,
+};
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use mmb_core::exchanges::timeouts::timeout_manager::TimeoutManager;
+use mmb_core::exchanges::traits::{
+    ExchangeClientBuilder, ExchangeClientBuilderResult, ExchangeError, HandleMetricsCb,
+    HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb,
+    Support,
+};
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{AllowedEventSourceType, EventSourceType, ExchangeEvent, TradeId};
+use mmb_domain::exchanges::symbol::{Precision, Symbol};
+use mmb_domain::market::{
+    CurrencyCode, CurrencyId, CurrencyPair, ExchangeErrorType, ExchangeId, SpecificCurrencyPair,
+};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order::pool::{OrderRef, OrdersPool};
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, ExchangeOrderId, OrderExecutionType, OrderInfo, OrderOptions, OrderRole,
+    OrderSide, OrderStatus, UserOrder,
+};
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot};
+
+/// Max time of waiting for execution report or reject of request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Execution reports which are received in response to request
+pub(crate) type FixResult = Result<Vec<FixMessage>, ExchangeError>;
+
+struct PendingRequest {
+    seq_num: u64,
+    /// Reports of mass status request are collected until the last one
+    reports: Vec<FixMessage>,
+    tx: oneshot::Sender<FixResult>,
+}
+
+pub struct Fix {
+    pub(crate) settings: ExchangeSettings,
+    pub(crate) config: FixConfig,
+    pub(crate) session: FixSession,
+    pub(crate) unified_to_specific: RwLock<HashMap<CurrencyPair, SpecificCurrencyPair>>,
+    pub(crate) specific_to_unified: RwLock<HashMap<SpecificCurrencyPair, CurrencyPair>>,
+    pub(crate) supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    // Currencies used for trading according to user settings
+    pub(super) traded_specific_currencies: Mutex<Vec<SpecificCurrencyPair>>,
+    pub(super) lifetime_manager: Arc<AppLifetimeManager>,
+    /// Requests by ClOrdID or id of status request which are waiting for response
+    pending_requests: Mutex<HashMap<String, PendingRequest>>,
+    pub(crate) order_created_callback: OrderCreatedCb,
+    pub(crate) order_cancelled_callback: OrderCancelledCb,
+    pub(crate) handle_order_filled_callback: HandleOrderFilledCb,
+    pub(crate) handle_trade_callback: HandleTradeCb,
+    pub(super) handle_metrics_callback: HandleMetricsCb,
+    pub(crate) websocket_message_callback: SendWebsocketMessageCb,
+}
+
+impl Fix {
+    pub fn new(
+        settings: ExchangeSettings,
+        config: FixConfig,
+        lifetime_manager: Arc<AppLifetimeManager>,
+    ) -> Fix {
+        Self {
+            session: FixSession::new(&config),
+            settings,
+            config,
+            unified_to_specific: Default::default(),
+            specific_to_unified: Default::default(),
+            supported_currencies: Default::default(),
+            traded_specific_currencies: Default::default(),
+            lifetime_manager,
+            pending_requests: Default::default(),
+            order_created_callback: Box::new(|_, _, _| {}),
+            order_cancelled_callback: Box::new(|_, _, _| {}),
+            handle_order_filled_callback: Box::new(|_| {}),
+            handle_trade_callback: Box::new(|_, _| {}),
+            handle_metrics_callback: Box::new(|_| {}),
+            websocket_message_callback: Box::new(|_, _| Ok(())),
+        }
+    }
+
+    /// Fields of Logon message with credentials from exchange settings
+    pub(crate) fn logon_credentials(&self) -> Vec<(u32, String)> {
+        let mut credentials = Vec::new();
+        if !self.settings.api_key.is_empty() {
+            credentials.push((tags::USERNAME, self.settings.api_key.clone()));
+        }
+        if !self.settings.secret_key.is_empty() {
+            credentials.push((tags::PASSWORD, self.settings.secret_key.clone()));
+        }
+        credentials
+    }
+
+    /// Sends request and waits for the first execution report or reject related to it
+    async fn call(&self, request_id: String, message: FixMessage) -> FixResult {
+        let msg_type = message.msg_type().to_owned();
+        let (tx, rx) = oneshot::channel();
+        {
+            // response can't be handled before request is registered
+            let mut pending_requests = self.pending_requests.lock();
+            let seq_num = self.session.send(message).map_err(|err| {
+                ExchangeError::new(
+                    ExchangeErrorType::SendError,
+                    format!("Unable to send FIX {msg_type} request: {err:?}"),
+                    None,
+                )
+            })?;
+            let _ = pending_requests.insert(
+                request_id.clone(),
+                PendingRequest {
+                    seq_num,
+                    reports: Vec::new(),
+                    tx,
+                },
+            );
+        }
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(ExchangeError::new(
+                ExchangeErrorType::SendError,
+                format!("FIX session was closed before response to {msg_type} request"),
+                None,
+            )),
+            Err(_) => {
+                let _ = self.pending_requests.lock().remove(&request_id);
+                Err(ExchangeError::new(
+                    ExchangeErrorType::SendError,
+                    format!("Response to FIX {msg_type} request wasn't received in time"),
+                    None,
+                ))
+            }
+        }
+    }
+
+    /// Returns false if nobody waits for response to request with such id
+    fn complete_request(&self, request_id: &str, result: FixResult) -> bool {
+        match self.pending_requests.lock().remove(request_id) {
+            Some(request) => {
+                // receiver is dropped if request is timed out
+                let _ = request.tx.send(result);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn complete_request_by_seq_num(&self, seq_num: u64, result: FixResult) -> bool {
+        let mut pending_requests = self.pending_requests.lock();
+        let request_id = pending_requests
+            .iter()
+            .find(|(_, request)| request.seq_num == seq_num)
+            .map(|(request_id, _)| request_id.clone());
+
+        match request_id.and_then(|x| pending_requests.remove(&x)) {
+            Some(request) => {
+                let _ = request.tx.send(result);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Responses to pending requests can't be received after disconnection
+    pub(crate) fn on_session_closed(&self) {
+        self.session.on_disconnected();
+        self.pending_requests.lock().clear();
+    }
+
+    pub(crate) fn on_application_message(&self, message: FixMessage) {
+        if let Err(err) = self.handle_application_message(message) {
+            log::error!(
+                "Unable to handle FIX message of {}: {err:?}",
+                self.settings.exchange_account_id
+            );
+        }
+    }
+
+    fn handle_application_message(&self, message: FixMessage) -> Result<()> {
+        match message.msg_type() {
+            msg_types::EXECUTION_REPORT => self.handle_execution_report(message),
+            msg_types::ORDER_CANCEL_REJECT => {
+                let request_id = message.get_required(tags::CL_ORD_ID)?.to_owned();
+                if !self.complete_request(&request_id, Err(reject_to_exchange_error(&message))) {
+                    log::warn!("FIX cancel reject without pending request: {message}");
+                }
+                Ok(())
+            }
+            msg_types::BUSINESS_MESSAGE_REJECT | msg_types::REJECT => {
+                let error = reject_to_exchange_error(&message);
+                let is_completed = match message.get(tags::BUSINESS_REJECT_REF_ID) {
+                    Some(request_id) => self.complete_request(request_id, Err(error)),
+                    None => self.complete_request_by_seq_num(
+                        message.get_parsed(tags::REF_SEQ_NUM)?,
+                        Err(error),
+                    ),
+                };
+                if !is_completed {
+                    log::warn!("FIX reject without pending request: {message}");
+                }
+                Ok(())
+            }
+            msg_type => {
+                log::warn!("Unsupported FIX message {msg_type}: {message}");
+                Ok(())
+            }
+        }
+    }
+
+    fn handle_execution_report(&self, message: FixMessage) -> Result<()> {
+        // responses to status requests aren't events of order lifecycle
+        if let Some(request_id) = message.get(tags::ORD_STATUS_REQ_ID) {
+            let request_id = request_id.to_owned();
+            let _ = self.complete_request(&request_id, Ok(vec![message]));
+            return Ok(());
+        }
+        if let Some(request_id) = message.get(tags::MASS_STATUS_REQ_ID) {
+            let request_id = request_id.to_owned();
+            self.add_mass_status_report(&request_id, message);
+            return Ok(());
+        }
+
+        let exec_type = message.get_required(tags::EXEC_TYPE)?.to_owned();
+        if exec_type == "F" {
+            self.handle_fill(&message)?;
+        }
+
+        // creation and cancellation requests wait for the first report of their ClOrdID
+        if let Some(request_id) = message.get(tags::CL_ORD_ID) {
+            let request_id = request_id.to_owned();
+            let result = match exec_type.as_str() {
+                "8" => Err(reject_to_exchange_error(&message)),
+                _ => Ok(vec![message.clone()]),
+            };
+            // the engine waits for events of order even if request succeeded,
+            // so only rejects are reported by responses
+            if self.complete_request(&request_id, result) && exec_type == "8" {
+                return Ok(());
+            }
+        }
+
+        let client_order_id = order_client_order_id(&message)?;
+        let exchange_order_id: ExchangeOrderId = message.get_required(tags::ORDER_ID)?.into();
+        match exec_type.as_str() {
+            "0" => (self.order_created_callback)(
+                client_order_id,
+                exchange_order_id,
+                EventSourceType::WebSocket,
+            ),
+            // orders can be cancelled by venue or expired by time in force
+            "4" | "C" => (self.order_cancelled_callback)(
+                client_order_id,
+                exchange_order_id,
+                EventSourceType::WebSocket,
+            ),
+            "8" => log::warn!("FIX order is rejected without pending request: {message}"),
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn add_mass_status_report(&self, request_id: &str, message: FixMessage) {
+        let is_last = message.get_flag(tags::LAST_RPT_REQUESTED);
+
+        let mut pending_requests = self.pending_requests.lock();
+        match pending_requests.get_mut(request_id) {
+            Some(request) => request.reports.push(message),
+            None => {
+                log::trace!("FIX mass status report without pending request: {message}");
+                return;
+            }
+        }
+
+        if is_last {
+            if let Some(request) = pending_requests.remove(request_id) {
+                let _ = request.tx.send(Ok(request.reports));
+            }
+        }
+    }
+
+    fn handle_fill(&self, message: &FixMessage) -> Result<()> {
+        let specific_currency_pair: SpecificCurrencyPair =
+            message.get_required(tags::SYMBOL)?.into();
+        let fill_amount = message.get_decimal(tags::LAST_QTY)?;
+
+        let fill_event = FillEvent {
+            source_type: EventSourceType::WebSocket,
+            trade_id: message
+                .get(tags::EXEC_ID)
+                .map(|x| TradeId::String(x.into())),
+            client_order_id: Some(order_client_order_id(message)?),
+            exchange_order_id: message.get_required(tags::ORDER_ID)?.into(),
+            fill_price: message.get_decimal(tags::LAST_PX)?,
+            fill_amount: FillAmount::Incremental {
+                fill_amount,
+                total_filled_amount: message.get_optional_decimal(tags::CUM_QTY)?,
+            },
+            order_role: get_order_role(message.get(tags::LAST_LIQUIDITY_IND)),
+            commission_currency_code: message.get(tags::COMM_CURRENCY).map(|x| x.into()),
+            commission_rate: None,
+            commission_amount: message.get_optional_decimal(tags::COMMISSION)?,
+            fill_type: OrderFillType::UserTrade,
+            special_order_data: Some(SpecialOrderData {
+                currency_pair: self.get_unified_currency_pair(&specific_currency_pair)?,
+                order_side: fix_to_side(message.get_required(tags::SIDE)?)?,
+                order_amount: message
+                    .get_optional_decimal(tags::ORDER_QTY)?
+                    .unwrap_or(fill_amount),
+            }),
+            fill_date: message.get_timestamp(tags::TRANSACT_TIME).ok(),
+        };
+
+        (self.handle_order_filled_callback)(fill_event);
+        Ok(())
+    }
+
+    pub(super) fn parse_all_symbols(&self) -> Vec<Arc<Symbol>> {
+        self.config
+            .symbols
+            .iter()
+            .map(|x| {
+                let base: CurrencyCode = x.base.as_str().into();
+                let quote: CurrencyCode = x.quote.as_str().into();
+                let specific_currency_pair: SpecificCurrencyPair = x.symbol.as_str().into();
+                let unified_currency_pair = CurrencyPair::from_codes(base, quote);
+
+                self.unified_to_specific
+                    .write()
+                    .insert(unified_currency_pair, specific_currency_pair);
+                self.specific_to_unified
+                    .write()
+                    .insert(specific_currency_pair, unified_currency_pair);
+                for (id, code) in [(&x.base, base), (&x.quote, quote)] {
+                    let _ = self.supported_currencies.insert(id.as_str().into(), code);
+                }
+
+                Arc::new(Symbol::new(
+                    false,
+                    x.base.as_str().into(),
+                    base,
+                    x.quote.as_str().into(),
+                    quote,
+                    None,
+                    None,
+                    x.min_amount,
+                    None,
+                    x.min_cost,
+                    base,
+                    None,
+                    Precision::ByTick { tick: x.price_tick },
+                    Precision::ByTick {
+                        tick: x.amount_step,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    pub(super) fn get_unified_currency_pair(
+        &self,
+        currency_pair: &SpecificCurrencyPair,
+    ) -> Result<CurrencyPair> {
+        self.specific_to_unified
+            .read()
+            .get(currency_pair)
+            .cloned()
+            .with_context(|| {
+                format!(
+                    "Not found currency pair '{currency_pair:?}' in {}",
+                    self.settings.exchange_account_id
+                )
+            })
+    }
+
+    fn with_account(&self, mut message: FixMessage) -> FixMessage {
+        if let Some(account) = &self.config.account {
+            message.push(tags::ACCOUNT, account);
+        }
+        message
+    }
+
+    pub(super) async fn do_create_order(
+        &self,
+        order: &OrderRef,
+    ) -> Result<ExchangeOrderId, ExchangeError> {
+        let header = order.header();
+        let mut message = FixMessage::new(msg_types::NEW_ORDER_SINGLE)
+            .with(tags::CL_ORD_ID, &header.client_order_id)
+            .with(
+                tags::SYMBOL,
+                self.get_specific_currency_pair(header.currency_pair),
+            )
+            .with(tags::SIDE, side_to_fix(header.side))
+            .with(tags::TRANSACT_TIME, format_timestamp(Utc::now()))
+            .with(tags::ORDER_QTY, header.amount.normalize());
+        match header.options {
+            OrderOptions::User(UserOrder::Limit {
+                price,
+                execution_type,
+            }) => {
+                message.push(tags::ORD_TYPE, "2");
+                message.push(tags::PRICE, price.normalize());
+                match execution_type {
+                    // immediate or cancel
+                    OrderExecutionType::ImmediateOrCancel => message.push(tags::TIME_IN_FORCE, "3"),
+                    // good till cancel
+                    _ => message.push(tags::TIME_IN_FORCE, "1"),
+                }
+                if execution_type == OrderExecutionType::MakerOnly {
+                    // participate don't initiate
+                    message.push(tags::EXEC_INST, "6");
+                }
+            }
+            OrderOptions::User(UserOrder::Market) => {
+                message.push(tags::ORD_TYPE, "1");
+                // immediate or cancel
+                message.push(tags::TIME_IN_FORCE, "3");
+            }
+            _ => return Err(ExchangeError::unknown("Unexpected order type")),
+        }
+
+        let reports = self
+            .call(
+                header.client_order_id.to_string(),
+                self.with_account(message),
+            )
+            .await?;
+        let report = reports
+            .first()
+            .ok_or_else(|| ExchangeError::unknown("Execution report wasn't received"))?;
+
+        report
+            .get_required(tags::ORDER_ID)
+            .map(|x| x.into())
+            .map_err(|err| ExchangeError::parsing(format!("{err:?}")))
+    }
+
+    pub(super) async fn do_cancel_order(
+        &self,
+        order: &OrderRef,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> Result<(), ExchangeError> {
+        let header = order.header();
+        self.cancel_order_by_ids(
+            &header.client_order_id,
+            exchange_order_id,
+            header.currency_pair,
+            header.side,
+            header.amount,
+        )
+        .await
+    }
+
+    /// Cancellation has its own ClOrdID which is unique like ids of orders
+    async fn cancel_order_by_ids(
+        &self,
+        client_order_id: &ClientOrderId,
+        exchange_order_id: &ExchangeOrderId,
+        currency_pair: CurrencyPair,
+        side: OrderSide,
+        amount: Amount,
+    ) -> Result<(), ExchangeError> {
+        let request_id = ClientOrderId::unique_id().to_string();
+        let mut message = FixMessage::new(msg_types::ORDER_CANCEL_REQUEST)
+            .with(tags::CL_ORD_ID, &request_id)
+            .with(tags::ORDER_ID, exchange_order_id)
+            .with(tags::SYMBOL, self.get_specific_currency_pair(currency_pair))
+            .with(tags::SIDE, side_to_fix(side))
+            .with(tags::ORDER_QTY, amount.normalize())
+            .with(tags::TRANSACT_TIME, format_timestamp(Utc::now()));
+        // orders created outside of the engine are cancelled by OrderID only
+        if !client_order_id.is_empty() {
+            message.push(tags::ORIG_CL_ORD_ID, client_order_id);
+        }
+
+        let _ = self.call(request_id, self.with_account(message)).await?;
+        Ok(())
+    }
+
+    /// Open orders are cancelled one by one because mass cancellation support differs between venues
+    pub(super) async fn do_cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        for order in self.get_open_orders_impl(Some(currency_pair)).await? {
+            self.cancel_order_by_ids(
+                &order.client_order_id,
+                &order.exchange_order_id,
+                order.currency_pair,
+                order.order_side,
+                order.amount,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) async fn request_order_info(
+        &self,
+        order: &OrderRef,
+    ) -> Result<FixMessage, ExchangeError> {
+        let header = order.header();
+        let request_id = ClientOrderId::unique_id().to_string();
+        let mut message = FixMessage::new(msg_types::ORDER_STATUS_REQUEST)
+            .with(tags::ORD_STATUS_REQ_ID, &request_id)
+            .with(tags::CL_ORD_ID, &header.client_order_id)
+            .with(
+                tags::SYMBOL,
+                self.get_specific_currency_pair(header.currency_pair),
+            )
+            .with(tags::SIDE, side_to_fix(header.side));
+        if let Some(exchange_order_id) = order.exchange_order_id() {
+            message.push(tags::ORDER_ID, exchange_order_id);
+        }
+
+        let report = self
+            .call(request_id, message)
+            .await?
+            .pop()
+            .ok_or_else(|| ExchangeError::unknown("Execution report wasn't received"))?;
+
+        // unknown orders are reported as rejected
+        if report.get(tags::ORD_STATUS) == Some("8")
+            && report.get(tags::ORD_REJ_REASON) == Some("5")
+        {
+            return Err(reject_to_exchange_error(&report));
+        }
+
+        Ok(report)
+    }
+
+    pub(super) fn parse_order_info(&self, report: &FixMessage) -> Result<OrderInfo> {
+        let specific_currency_pair: SpecificCurrencyPair =
+            report.get_required(tags::SYMBOL)?.into();
+        let average_fill_price = report
+            .get_optional_decimal(tags::AVG_PX)?
+            .unwrap_or_default();
+
+        Ok(OrderInfo::new(
+            self.get_unified_currency_pair(&specific_currency_pair)?,
+            report.get_required(tags::ORDER_ID)?.into(),
+            report.get(tags::CL_ORD_ID).unwrap_or_default().into(),
+            fix_to_side(report.get_required(tags::SIDE)?)?,
+            fix_to_order_status(report.get_required(tags::ORD_STATUS)?)?,
+            // market orders don't have price
+            report
+                .get_optional_decimal(tags::PRICE)?
+                .unwrap_or(average_fill_price),
+            report.get_decimal(tags::ORDER_QTY)?,
+            average_fill_price,
+            report.get_decimal(tags::CUM_QTY)?,
+            report.get(tags::COMM_CURRENCY).map(|x| x.to_owned()),
+            None,
+            // commission of status report is cumulative like filled amount
+            report.get_optional_decimal(tags::COMMISSION)?,
+        ))
+    }
+
+    /// Open orders aren't restored if venue doesn't support mass status requests
+    pub(super) async fn get_open_orders_impl(
+        &self,
+        currency_pair: Option<CurrencyPair>,
+    ) -> Result<Vec<OrderInfo>> {
+        if !self.config.mass_status {
+            log::warn!(
+                "Mass status requests aren't supported by {}",
+                self.settings.exchange_account_id
+            );
+            return Ok(Vec::new());
+        }
+
+        let request_id = ClientOrderId::unique_id().to_string();
+        let mut message = FixMessage::new(msg_types::ORDER_MASS_STATUS_REQUEST)
+            .with(tags::MASS_STATUS_REQ_ID, &request_id);
+        match currency_pair {
+            Some(currency_pair) => {
+                // status for orders of security
+                message.push(tags::MASS_STATUS_REQ_TYPE, 1);
+                message.push(tags::SYMBOL, self.get_specific_currency_pair(currency_pair));
+            }
+            // status for all orders
+            None => message.push(tags::MASS_STATUS_REQ_TYPE, 7),
+        }
+
+        let reports = self
+            .call(request_id, self.with_account(message))
+            .await
+            .map_err(|err| anyhow!("Failed to request mass status: {err:?}"))?;
+
+        self.parse_open_orders(&reports)
+    }
+
+    pub(super) fn parse_open_orders(&self, reports: &[FixMessage]) -> Result<Vec<OrderInfo>> {
+        reports
+            .iter()
+            // venues report absence of orders by single report without order, orders
+            // of instruments which aren't traded by the engine are skipped
+            .filter(|x| {
+                x.get(tags::ORDER_ID).is_some()
+                    && x.get(tags::SYMBOL).is_some_and(|symbol| {
+                        self.specific_to_unified.read().contains_key(&symbol.into())
+                    })
+            })
+            .map(|x| self.parse_order_info(x))
+            .filter_ok(|x| !x.order_status.is_finished())
+            .try_collect()
+    }
+}
+
+/// Orders are identified by OrigClOrdID in reports caused by cancellation
+fn order_client_order_id(message: &FixMessage) -> Result<ClientOrderId> {
+    message
+        .get(tags::ORIG_CL_ORD_ID)
+        .or_else(|| message.get(tags::CL_ORD_ID))
+        .map(|x| x.into())
+        .with_context(|| format!("ClOrdID not found in execution report: {message}"))
+}
+
+fn side_to_fix(side: OrderSide) -> &'static str {
+    match side {
+        OrderSide::Buy => "1",
+        OrderSide::Sell => "2",
+    }
+}
+
+fn fix_to_side(side: &str) -> Result<OrderSide> {
+    match side {
+        "1" => Ok(OrderSide::Buy),
+        "2" => Ok(OrderSide::Sell),
+        _ => Err(anyhow!("Unsupported FIX order side {side}")),
+    }
+}
+
+fn fix_to_order_status(status: &str) -> Result<OrderStatus> {
+    Ok(match status {
+        "A" => OrderStatus::Creating,
+        // new, partially filled, replaced, suspended, calculated, pending replace
+        "0" | "1" | "5" | "9" | "B" | "E" => OrderStatus::Created,
+        "6" => OrderStatus::Canceling,
+        // canceled, done for day, expired
+        "4" | "3" | "C" => OrderStatus::Canceled,
+        "2" => OrderStatus::Completed,
+        "8" => OrderStatus::FailedToCreate,
+        _ => return Err(anyhow!("Unsupported FIX order status {status}")),
+    })
+}
+
+fn get_order_role(last_liquidity_ind: Option<&str>) -> Option<OrderRole> {
+    match last_liquidity_ind {
+        Some("1") => Some(OrderRole::Maker),
+        Some("2") => Some(OrderRole::Taker),
+        _ => None,
+    }
+}
+
+/// Converts execution report with rejection, cancel reject or session and business level reject
+pub(crate) fn reject_to_exchange_error(message: &FixMessage) -> ExchangeError {
+    use ExchangeErrorType::*;
+
+    let (reason_tag, error_type) = match message.msg_type() {
+        msg_types::EXECUTION_REPORT => {
+            let error_type = match message.get(tags::ORD_REJ_REASON) {
+                // unknown symbol, duplicate order, unsupported order characteristic,
+                // incorrect quantity, unknown account
+                Some("1" | "6" | "11" | "13" | "15") => InvalidOrder,
+                Some("2") => ServiceUnavailable,
+                Some("3") => InsufficientFunds,
+                Some("5") => OrderNotFound,
+                _ => Unknown,
+            };
+            (tags::ORD_REJ_REASON, error_type)
+        }
+        msg_types::ORDER_CANCEL_REJECT => {
+            let error_type = match message.get(tags::CXL_REJ_REASON) {
+                // too late to cancel
+                Some("0") => OrderCompleted,
+                Some("1") => OrderNotFound,
+                _ => Unknown,
+            };
+            (tags::CXL_REJ_REASON, error_type)
+        }
+        // request isn't accepted by venue at all
+        msg_types::BUSINESS_MESSAGE_REJECT => (tags::BUSINESS_REJECT_REASON, InvalidOrder),
+        _ => (tags::SESSION_REJECT_REASON, InvalidOrder),
+    };
+
+    let text = match message.get(tags::TEXT) {
+        Some(text) => text.to_owned(),
+        None => message.to_string(),
+    };
+    let code = message.get(reason_tag).and_then(|x| x.parse().ok());
+
+    ExchangeError::new(error_type, text, code)
+}
+
+pub struct FixBuilder {
+    config: FixConfig,
+}
+
+impl FixBuilder {
+    pub fn new(config: FixConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn load(config_path: &str) -> Result<Self> {
+        Ok(Self::new(FixConfig::load(config_path)?))
+    }
+}
+
+impl ExchangeClientBuilder for FixBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        _events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+        _timeout_manager: Arc<TimeoutManager>,
+        _orders: Arc<OrdersPool>,
+    ) -> ExchangeClientBuilderResult {
+        let open_orders_type = match self.config.mass_status {
+            true => OpenOrdersType::AllCurrencyPair,
+            false => OpenOrdersType::None,
+        };
+
+        ExchangeClientBuilderResult {
+            client: Box::new(Fix::new(
+                exchange_settings,
+                self.config.clone(),
+                lifetime_manager,
+            )),
+            features: ExchangeFeatures::new(
+                open_orders_type,
+                RestFillsFeatures::new(RestFillsType::GetOrderInfo),
+                OrderFeatures {
+                    maker_only: true,
+                    supports_get_order_info_by_client_order_id: true,
+                    cancellation_response_from_rest_only_for_errors: false,
+                    creation_response_from_rest_only_for_errors: false,
+                    order_was_completed_error_for_cancellation: true,
+                    supports_already_cancelled_order: false,
+                    supports_stop_loss_order: false,
+                },
+                OrderTradeOption::default(),
+                WebSocketOptions {
+                    execution_notification: true,
+                    cancellation_notification: true,
+                    supports_ping_pong: false,
+                    supports_subscription_response: false,
+                },
+                false,
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+            ),
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        RequestTimeoutArguments::from_requests_per_minute(self.config.requests_per_minute)
+    }
+
+    fn get_exchange_id(&self) -> ExchangeId {
+        self.config.exchange_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::market::ExchangeAccountId;
+    use mmb_utils::cancellation_token::CancellationToken;
+    use parking_lot::Mutex as SyncMutex;
+    use rust_decimal_macros::dec;
+
+    fn create_client() -> Fix {
+        let config = FixConfig::parse(include_str!("../example.toml")).expect("in test");
+        let settings = ExchangeSettings::new_short(
+            ExchangeAccountId::new("FixVenue", 0),
+            "user".to_owned(),
+            "password".to_owned(),
+            false,
+        );
+        let client = Fix::new(
+            settings,
+            config,
+            AppLifetimeManager::new(CancellationToken::default()),
+        );
+        let _ = client.parse_all_symbols();
+        client
+    }
+
+    fn execution_report(exec_type: &str, ord_status: &str) -> FixMessage {
+        FixMessage::new(msg_types::EXECUTION_REPORT)
+            .with(tags::ORDER_ID, "ORD1")
+            .with(tags::CL_ORD_ID, "123")
+            .with(tags::EXEC_ID, "EX1")
+            .with(tags::EXEC_TYPE, exec_type)
+            .with(tags::ORD_STATUS, ord_status)
+            .with(tags::SYMBOL, "BTC/USD")
+            .with(tags::SIDE, "2")
+            .with(tags::ORDER_QTY, "0.5")
+            .with(tags::PRICE, "30000")
+            .with(tags::CUM_QTY, "0.2")
+            .with(tags::AVG_PX, "30000.5")
+    }
+
+    #[test]
+    fn build_symbols_from_config() {
+        let client = create_client();
+
+        let symbols = client.parse_all_symbols();
+
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[0].currency_pair().as_str(), "btc/usd");
+        assert_eq!(symbols[0].min_cost, Some(dec!(10)));
+        assert_eq!(
+            client.get_specific_currency_pair(symbols[1].currency_pair()),
+            "ETH/USD".into()
+        );
+    }
+
+    #[test]
+    fn parse_order_info_from_execution_report() {
+        let client = create_client();
+
+        let order_info = client
+            .parse_order_info(&execution_report("I", "1"))
+            .expect("in test");
+
+        assert_eq!(order_info.exchange_order_id.as_str(), "ORD1");
+        assert_eq!(order_info.client_order_id.as_str(), "123");
+        assert_eq!(order_info.order_side, OrderSide::Sell);
+        assert_eq!(order_info.order_status, OrderStatus::Created);
+        assert_eq!(order_info.amount, dec!(0.5));
+        assert_eq!(order_info.filled_amount, dec!(0.2));
+        assert_eq!(order_info.average_fill_price, dec!(30000.5));
+        assert_eq!(order_info.commission_amount, None);
+    }
+
+    #[test]
+    fn skip_finished_and_empty_mass_status_reports() {
+        let client = create_client();
+        let empty_report = FixMessage::new(msg_types::EXECUTION_REPORT)
+            .with(tags::EXEC_TYPE, "I")
+            .with(tags::ORD_STATUS, "8");
+
+        let orders = client
+            .parse_open_orders(&[
+                execution_report("I", "0"),
+                execution_report("I", "2"),
+                empty_report,
+            ])
+            .expect("in test");
+
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].order_status, OrderStatus::Created);
+    }
+
+    #[test]
+    fn handle_fill_report() {
+        let mut client = create_client();
+        let fills = Arc::new(SyncMutex::new(Vec::new()));
+        let fills_clone = fills.clone();
+        client
+            .set_handle_order_filled_callback(Box::new(move |fill| fills_clone.lock().push(fill)));
+
+        client.on_application_message(
+            execution_report("F", "1")
+                .with(tags::LAST_PX, "30000")
+                .with(tags::LAST_QTY, "0.2")
+                .with(tags::LAST_LIQUIDITY_IND, "1")
+                .with(tags::COMMISSION, "0.6")
+                .with(tags::COMM_CURRENCY, "USD")
+                .with(tags::TRANSACT_TIME, "20230102-03:04:05.678"),
+        );
+
+        let fills = fills.lock();
+        assert_eq!(fills.len(), 1);
+        let fill = &fills[0];
+        assert_eq!(fill.client_order_id, Some("123".into()));
+        assert_eq!(fill.fill_price, dec!(30000));
+        assert_eq!(fill.order_role, Some(OrderRole::Maker));
+        assert_eq!(fill.commission_amount, Some(dec!(0.6)));
+        assert!(matches!(
+            fill.fill_amount,
+            FillAmount::Incremental {
+                total_filled_amount: Some(_),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn convert_rejects_to_errors() {
+        let order_reject = execution_report("8", "8")
+            .with(tags::ORD_REJ_REASON, "3")
+            .with(tags::TEXT, "Not enough balance");
+        let error = reject_to_exchange_error(&order_reject);
+        assert_eq!(error.error_type, ExchangeErrorType::InsufficientFunds);
+        assert_eq!(error.message, "Not enough balance");
+        assert_eq!(error.code, Some(3));
+
+        let cancel_reject = FixMessage::new(msg_types::ORDER_CANCEL_REJECT)
+            .with(tags::CL_ORD_ID, "124")
+            .with(tags::CXL_REJ_REASON, "0");
+        assert_eq!(
+            reject_to_exchange_error(&cancel_reject).error_type,
+            ExchangeErrorType::OrderCompleted
+        );
+
+        let session_reject = FixMessage::new(msg_types::REJECT)
+            .with(tags::REF_SEQ_NUM, 5)
+            .with(tags::SESSION_REJECT_REASON, 1);
+        assert_eq!(
+            reject_to_exchange_error(&session_reject).error_type,
+            ExchangeErrorType::InvalidOrder
+        );
+    }
+}
@@ -0,0 +1,394 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+pub(crate) const BEGIN_STRING: &str = "FIX.4.4";
+const SOH: u8 = 0x01;
+/// Length of trailer `10=XXX<SOH>`
+const TRAILER_LENGTH: usize = 7;
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H:%M:%S%.3f";
+
+pub(crate) mod tags {
+    pub(crate) const ACCOUNT: u32 = 1;
+    pub(crate) const AVG_PX: u32 = 6;
+    pub(crate) const BEGIN_SEQ_NO: u32 = 7;
+    pub(crate) const BEGIN_STRING: u32 = 8;
+    pub(crate) const BODY_LENGTH: u32 = 9;
+    pub(crate) const CHECK_SUM: u32 = 10;
+    pub(crate) const CL_ORD_ID: u32 = 11;
+    pub(crate) const COMMISSION: u32 = 12;
+    pub(crate) const CUM_QTY: u32 = 14;
+    pub(crate) const END_SEQ_NO: u32 = 16;
+    pub(crate) const EXEC_ID: u32 = 17;
+    pub(crate) const EXEC_INST: u32 = 18;
+    pub(crate) const LAST_PX: u32 = 31;
+    pub(crate) const LAST_QTY: u32 = 32;
+    pub(crate) const MSG_SEQ_NUM: u32 = 34;
+    pub(crate) const MSG_TYPE: u32 = 35;
+    pub(crate) const NEW_SEQ_NO: u32 = 36;
+    pub(crate) const ORDER_ID: u32 = 37;
+    pub(crate) const ORDER_QTY: u32 = 38;
+    pub(crate) const ORD_STATUS: u32 = 39;
+    pub(crate) const ORD_TYPE: u32 = 40;
+    pub(crate) const ORIG_CL_ORD_ID: u32 = 41;
+    pub(crate) const POSS_DUP_FLAG: u32 = 43;
+    pub(crate) const PRICE: u32 = 44;
+    pub(crate) const REF_SEQ_NUM: u32 = 45;
+    pub(crate) const SENDER_COMP_ID: u32 = 49;
+    pub(crate) const SENDING_TIME: u32 = 52;
+    pub(crate) const SIDE: u32 = 54;
+    pub(crate) const SYMBOL: u32 = 55;
+    pub(crate) const TARGET_COMP_ID: u32 = 56;
+    pub(crate) const TEXT: u32 = 58;
+    pub(crate) const TIME_IN_FORCE: u32 = 59;
+    pub(crate) const TRANSACT_TIME: u32 = 60;
+    pub(crate) const ENCRYPT_METHOD: u32 = 98;
+    pub(crate) const CXL_REJ_REASON: u32 = 102;
+    pub(crate) const ORD_REJ_REASON: u32 = 103;
+    pub(crate) const HEART_BT_INT: u32 = 108;
+    pub(crate) const TEST_REQ_ID: u32 = 112;
+    pub(crate) const GAP_FILL_FLAG: u32 = 123;
+    pub(crate) const RESET_SEQ_NUM_FLAG: u32 = 141;
+    pub(crate) const EXEC_TYPE: u32 = 150;
+    pub(crate) const SESSION_REJECT_REASON: u32 = 373;
+    pub(crate) const BUSINESS_REJECT_REF_ID: u32 = 379;
+    pub(crate) const BUSINESS_REJECT_REASON: u32 = 380;
+    pub(crate) const COMM_CURRENCY: u32 = 479;
+    pub(crate) const USERNAME: u32 = 553;
+    pub(crate) const PASSWORD: u32 = 554;
+    pub(crate) const MASS_STATUS_REQ_ID: u32 = 584;
+    pub(crate) const MASS_STATUS_REQ_TYPE: u32 = 585;
+    pub(crate) const ORD_STATUS_REQ_ID: u32 = 790;
+    pub(crate) const LAST_LIQUIDITY_IND: u32 = 851;
+    pub(crate) const LAST_RPT_REQUESTED: u32 = 912;
+}
+
+pub(crate) mod msg_types {
+    pub(crate) const HEARTBEAT: &str = "0";
+    pub(crate) const TEST_REQUEST: &str = "1";
+    pub(crate) const RESEND_REQUEST: &str = "2";
+    pub(crate) const REJECT: &str = "3";
+    pub(crate) const SEQUENCE_RESET: &str = "4";
+    pub(crate) const LOGOUT: &str = "5";
+    pub(crate) const EXECUTION_REPORT: &str = "8";
+    pub(crate) const ORDER_CANCEL_REJECT: &str = "9";
+    pub(crate) const LOGON: &str = "A";
+    pub(crate) const NEW_ORDER_SINGLE: &str = "D";
+    pub(crate) const ORDER_CANCEL_REQUEST: &str = "F";
+    pub(crate) const ORDER_STATUS_REQUEST: &str = "H";
+    pub(crate) const BUSINESS_MESSAGE_REJECT: &str = "j";
+    pub(crate) const ORDER_MASS_STATUS_REQUEST: &str = "AF";
+}
+
+/// FIX message without `BeginString(8)`, `BodyLength(9)` and `CheckSum(10)` fields which
+/// are added by encoding. Repeating groups aren't interpreted, so only the first value of tag is available.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FixMessage {
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    pub(crate) fn new(msg_type: &str) -> Self {
+        Self {
+            fields: vec![(tags::MSG_TYPE, msg_type.to_owned())],
+        }
+    }
+
+    pub(crate) fn with(mut self, tag: u32, value: impl ToString) -> Self {
+        self.push(tag, value);
+        self
+    }
+
+    pub(crate) fn push(&mut self, tag: u32, value: impl ToString) {
+        self.fields.push((tag, value.to_string()));
+    }
+
+    pub(crate) fn msg_type(&self) -> &str {
+        self.get(tags::MSG_TYPE).unwrap_or_default()
+    }
+
+    pub(crate) fn get(&self, tag: u32) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(x, _)| *x == tag)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub(crate) fn get_required(&self, tag: u32) -> Result<&str> {
+        self.get(tag)
+            .with_context(|| format!("Tag {tag} not found in {}", self.msg_type()))
+    }
+
+    pub(crate) fn get_parsed<T>(&self, tag: u32) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        let value = self.get_required(tag)?;
+        value
+            .parse()
+            .with_context(|| format!("Unable to parse tag {tag} with value {value}"))
+    }
+
+    pub(crate) fn get_decimal(&self, tag: u32) -> Result<Decimal> {
+        self.get_parsed(tag)
+    }
+
+    pub(crate) fn get_optional_decimal(&self, tag: u32) -> Result<Option<Decimal>> {
+        match self.get(tag) {
+            Some(_) => self.get_decimal(tag).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn get_flag(&self, tag: u32) -> bool {
+        self.get(tag) == Some("Y")
+    }
+
+    pub(crate) fn get_timestamp(&self, tag: u32) -> Result<DateTime<Utc>> {
+        let value = self.get_required(tag)?;
+        // milliseconds are optional in UTCTimestamp
+        let date_time = NaiveDateTime::parse_from_str(value, TIMESTAMP_FORMAT)
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y%m%d-%H:%M:%S"))
+            .with_context(|| format!("Unable to parse timestamp of tag {tag}: {value}"))?;
+
+        Ok(DateTime::from_utc(date_time, Utc))
+    }
+
+    pub(crate) fn seq_num(&self) -> Result<u64> {
+        self.get_parsed(tags::MSG_SEQ_NUM)
+    }
+
+    /// Inserts header fields right after `MsgType(35)`
+    pub(crate) fn with_header(mut self, header: impl IntoIterator<Item = (u32, String)>) -> Self {
+        let body = self.fields.split_off(1);
+        self.fields.extend(header);
+        self.fields.extend(body);
+        self
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (tag, value) in &self.fields {
+            body.extend_from_slice(format!("{tag}={value}").as_bytes());
+            body.push(SOH);
+        }
+
+        let mut message = format!(
+            "{}={BEGIN_STRING}\u{1}{}={}\u{1}",
+            tags::BEGIN_STRING,
+            tags::BODY_LENGTH,
+            body.len()
+        )
+        .into_bytes();
+        message.extend_from_slice(&body);
+        let checksum = checksum(&message);
+        message.extend_from_slice(format!("{}={checksum:03}\u{1}", tags::CHECK_SUM).as_bytes());
+
+        message
+    }
+}
+
+/// Messages are logged with `|` instead of SOH delimiters
+impl Display for FixMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (tag, value) in &self.fields {
+            // password shouldn't be logged
+            match *tag == tags::PASSWORD {
+                true => write!(f, "{tag}=***|")?,
+                false => write!(f, "{tag}={value}|")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn format_timestamp(date_time: DateTime<Utc>) -> String {
+    date_time.format(TIMESTAMP_FORMAT).to_string()
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, x| sum.wrapping_add(*x))
+}
+
+/// Splits stream of bytes to messages
+#[derive(Default)]
+pub(crate) struct FixDecoder {
+    buffer: Vec<u8>,
+}
+
+impl FixDecoder {
+    pub(crate) fn extend(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns `None` if buffer doesn't contain complete message yet.
+    /// Malformed stream can't be resynchronized, so connection should be closed on error.
+    pub(crate) fn next_message(&mut self) -> Result<Option<FixMessage>> {
+        let begin_string_end = match self.buffer.iter().position(|x| *x == SOH) {
+            Some(position) => position,
+            None => return Ok(None),
+        };
+        let begin_string = &self.buffer[..begin_string_end];
+        if begin_string != format!("{}={BEGIN_STRING}", tags::BEGIN_STRING).as_bytes() {
+            bail!(
+                "Unexpected begin of FIX message: {}",
+                String::from_utf8_lossy(begin_string)
+            );
+        }
+
+        let body_length_start = begin_string_end + 1;
+        let body_length_end = match self.buffer[body_length_start..]
+            .iter()
+            .position(|x| *x == SOH)
+        {
+            Some(position) => body_length_start + position,
+            None => return Ok(None),
+        };
+        let body_length = std::str::from_utf8(&self.buffer[body_length_start..body_length_end])?
+            .strip_prefix("9=")
+            .context("BodyLength(9) should be the second field of FIX message")?
+            .parse::<usize>()
+            .context("Unable to parse BodyLength(9)")?;
+
+        let body_start = body_length_end + 1;
+        let body_end = body_start + body_length;
+        if self.buffer.len() < body_end + TRAILER_LENGTH {
+            return Ok(None);
+        }
+
+        let trailer = &self.buffer[body_end..body_end + TRAILER_LENGTH];
+        let expected_checksum = std::str::from_utf8(trailer)?
+            .strip_prefix("10=")
+            .and_then(|x| x.strip_suffix('\u{1}'))
+            .with_context(|| {
+                format!(
+                    "Unexpected trailer of FIX message: {}",
+                    String::from_utf8_lossy(trailer)
+                )
+            })?
+            .parse::<u8>()
+            .context("Unable to parse CheckSum(10)")?;
+        let actual_checksum = checksum(&self.buffer[..body_end]);
+        if actual_checksum != expected_checksum {
+            bail!("Invalid checksum of FIX message: expected {expected_checksum}, actual {actual_checksum}");
+        }
+
+        let fields = std::str::from_utf8(&self.buffer[body_start..body_end])?
+            .split_terminator('\u{1}')
+            .map(|field| {
+                let (tag, value) = field
+                    .split_once('=')
+                    .with_context(|| format!("Invalid field of FIX message: {field}"))?;
+                let tag = tag
+                    .parse()
+                    .with_context(|| format!("Invalid tag of FIX message: {field}"))?;
+                Ok((tag, value.to_owned()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let _ = self.buffer.drain(..body_end + TRAILER_LENGTH);
+
+        let message = FixMessage { fields };
+        if message.fields.first().map(|(tag, _)| *tag) != Some(tags::MSG_TYPE) {
+            bail!("MsgType(35) should be the third field of FIX message: {message}");
+        }
+
+        Ok(Some(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_fix(message: &str) -> Vec<u8> {
+        message.replace('|', "\u{1}").into_bytes()
+    }
+
+    #[test]
+    fn encode_message() {
+        let message = FixMessage::new(msg_types::HEARTBEAT).with_header([
+            (tags::SENDER_COMP_ID, "CLIENT".to_owned()),
+            (tags::TARGET_COMP_ID, "VENUE".to_owned()),
+            (tags::MSG_SEQ_NUM, "2".to_owned()),
+            (tags::SENDING_TIME, "20230101-00:00:00.000".to_owned()),
+        ]);
+
+        let encoded = message.encode();
+
+        let body = "35=0|49=CLIENT|56=VENUE|34=2|52=20230101-00:00:00.000|";
+        let head = format!("8=FIX.4.4|9={}|{body}", body.len());
+        let expected_checksum = checksum(&to_fix(&head));
+        assert_eq!(
+            encoded,
+            to_fix(&format!("{head}10={expected_checksum:03}|"))
+        );
+    }
+
+    #[test]
+    fn decode_encoded_messages_by_parts() {
+        let first = FixMessage::new(msg_types::TEST_REQUEST)
+            .with(tags::MSG_SEQ_NUM, 5)
+            .with(tags::TEST_REQ_ID, "test");
+        let second = FixMessage::new(msg_types::EXECUTION_REPORT)
+            .with(tags::MSG_SEQ_NUM, 6)
+            .with(tags::TEXT, "a=b");
+        let mut data = first.encode();
+        data.extend(second.encode());
+
+        let mut decoder = FixDecoder::default();
+        decoder.extend(&data[..10]);
+        assert_eq!(decoder.next_message().expect("in test"), None);
+
+        decoder.extend(&data[10..]);
+        assert_eq!(decoder.next_message().expect("in test"), Some(first));
+
+        let decoded = decoder.next_message().expect("in test").expect("in test");
+        assert_eq!(decoded.get(tags::TEXT), Some("a=b"));
+        assert_eq!(decoded.seq_num().expect("in test"), 6);
+        assert_eq!(decoded, second);
+        assert_eq!(decoder.next_message().expect("in test"), None);
+    }
+
+    #[test]
+    fn reject_invalid_checksum() {
+        let mut data = FixMessage::new(msg_types::HEARTBEAT)
+            .with(tags::MSG_SEQ_NUM, 1)
+            .encode();
+        let checksum_position = data.len() - 2;
+        data[checksum_position] = if data[checksum_position] == b'0' {
+            b'1'
+        } else {
+            b'0'
+        };
+
+        let mut decoder = FixDecoder::default();
+        decoder.extend(&data);
+
+        assert!(decoder.next_message().is_err());
+    }
+
+    #[test]
+    fn parse_timestamp() {
+        let message = FixMessage::new(msg_types::EXECUTION_REPORT)
+            .with(tags::TRANSACT_TIME, "20230102-03:04:05.678")
+            .with(tags::SENDING_TIME, "20230102-03:04:05");
+
+        assert_eq!(
+            message
+                .get_timestamp(tags::TRANSACT_TIME)
+                .expect("in test")
+                .timestamp_millis(),
+            1672628645678
+        );
+        assert_eq!(
+            message
+                .get_timestamp(tags::SENDING_TIME)
+                .expect("in test")
+                .timestamp_millis(),
+            1672628645000
+        );
+    }
+}
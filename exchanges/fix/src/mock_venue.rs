@@ -0,0 +1,381 @@
+#![cfg(test)]
+
+use crate::config::FixConfig;
+use crate::message::{format_timestamp, msg_types, tags, FixDecoder, FixMessage};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use core_tests::conformance::MockExchange;
+use mmb_domain::order::snapshot::{Amount, ExchangeOrderId, Price};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, watch};
+
+const LEAVES_QTY: u32 = 151;
+const COMMISSION_RATE: Decimal = dec!(0.001);
+/// TestRequest which is sent after logon, connector is ready when Heartbeat with it is received
+const READY_TEST_REQ_ID: &str = "ready";
+
+struct VenueOrder {
+    order_id: String,
+    client_order_id: String,
+    symbol: String,
+    side: String,
+    order_qty: Decimal,
+    price: Decimal,
+    cum_qty: Decimal,
+    /// Sum of price multiplied by amount of fills
+    cum_cost: Decimal,
+    ord_status: &'static str,
+}
+
+impl VenueOrder {
+    fn is_open(&self) -> bool {
+        matches!(self.ord_status, "0" | "1")
+    }
+
+    fn report(&self, client_order_id: &str, exec_type: &str, exec_id: u64) -> FixMessage {
+        let avg_px = match self.cum_qty.is_zero() {
+            true => Decimal::ZERO,
+            false => self.cum_cost / self.cum_qty,
+        };
+
+        FixMessage::new(msg_types::EXECUTION_REPORT)
+            .with(tags::ORDER_ID, &self.order_id)
+            .with(tags::CL_ORD_ID, client_order_id)
+            .with(tags::EXEC_ID, exec_id)
+            .with(tags::EXEC_TYPE, exec_type)
+            .with(tags::ORD_STATUS, self.ord_status)
+            .with(tags::SYMBOL, &self.symbol)
+            .with(tags::SIDE, &self.side)
+            .with(tags::ORDER_QTY, self.order_qty)
+            .with(tags::PRICE, self.price)
+            .with(LEAVES_QTY, self.order_qty - self.cum_qty)
+            .with(tags::CUM_QTY, self.cum_qty)
+            .with(tags::AVG_PX, avg_px.normalize())
+            .with(tags::COMM_CURRENCY, "USD")
+    }
+
+    /// Report with cumulative commission in response to status request
+    fn status_report(&self, exec_id: u64) -> FixMessage {
+        self.report(&self.client_order_id, "I", exec_id)
+            .with(tags::COMMISSION, self.cum_cost * COMMISSION_RATE)
+    }
+}
+
+struct VenueState {
+    sender_comp_id: String,
+    target_comp_id: String,
+    /// Encoded messages for current connection
+    outgoing: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    next_seq_num: u64,
+    /// Counter of order and execution ids
+    next_id: u64,
+    orders: Vec<VenueOrder>,
+}
+
+impl VenueState {
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn send(&mut self, message: FixMessage) {
+        let outgoing = match &self.outgoing {
+            Some(outgoing) => outgoing,
+            None => return,
+        };
+
+        let message = message.with_header([
+            (tags::SENDER_COMP_ID, self.sender_comp_id.clone()),
+            (tags::TARGET_COMP_ID, self.target_comp_id.clone()),
+            (tags::MSG_SEQ_NUM, self.next_seq_num.to_string()),
+            (tags::SENDING_TIME, format_timestamp(Utc::now())),
+        ]);
+        self.next_seq_num += 1;
+        let _ = outgoing.send(message.encode());
+    }
+
+    fn find_order(&mut self, message: &FixMessage, client_order_id_tag: u32) -> Option<usize> {
+        let order_id = message.get(tags::ORDER_ID);
+        let client_order_id = message.get(client_order_id_tag);
+        self.orders.iter().position(|x| {
+            Some(x.order_id.as_str()) == order_id
+                || Some(x.client_order_id.as_str()) == client_order_id
+        })
+    }
+}
+
+/// FIX acceptor which emulates venue with a single session for tests of the connector
+pub(crate) struct MockVenue {
+    pub(crate) port: u16,
+    state: Mutex<VenueState>,
+    is_ready: watch::Sender<bool>,
+}
+
+impl MockVenue {
+    pub(crate) async fn start(config: &FixConfig) -> Result<Arc<Self>> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let (is_ready, _) = watch::channel(false);
+        let venue = Arc::new(Self {
+            port: listener.local_addr()?.port(),
+            state: Mutex::new(VenueState {
+                sender_comp_id: config.target_comp_id.clone(),
+                target_comp_id: config.sender_comp_id.clone(),
+                outgoing: None,
+                next_seq_num: 1,
+                next_id: 0,
+                orders: Vec::new(),
+            }),
+            is_ready,
+        });
+
+        let weak_venue = Arc::downgrade(&venue);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let venue = match weak_venue.upgrade() {
+                    Some(venue) => venue,
+                    None => return,
+                };
+                if let Err(err) = venue.serve(stream).await {
+                    log::warn!("Mock FIX connection is broken: {err:?}");
+                }
+            }
+        });
+
+        Ok(venue)
+    }
+
+    async fn serve(&self, stream: TcpStream) -> Result<()> {
+        let (mut reader, mut writer) = stream.into_split();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        self.state.lock().outgoing = Some(tx);
+
+        let mut decoder = FixDecoder::default();
+        let mut buffer = vec![0u8; 4096];
+        loop {
+            tokio::select! {
+                read = reader.read(&mut buffer) => {
+                    let size = read?;
+                    if size == 0 {
+                        bail!("Connection is closed by connector");
+                    }
+
+                    decoder.extend(&buffer[..size]);
+                    while let Some(message) = decoder.next_message()? {
+                        self.handle(message)?;
+                    }
+                }
+                data = rx.recv() => match data {
+                    Some(data) => writer.write_all(&data).await?,
+                    // connection is dropped by disconnect()
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+
+    fn handle(&self, message: FixMessage) -> Result<()> {
+        let mut state = self.state.lock();
+        match message.msg_type() {
+            msg_types::LOGON => {
+                if message.get_flag(tags::RESET_SEQ_NUM_FLAG) {
+                    state.next_seq_num = 1;
+                }
+                state.send(
+                    FixMessage::new(msg_types::LOGON)
+                        .with(tags::ENCRYPT_METHOD, 0)
+                        .with(
+                            tags::HEART_BT_INT,
+                            message.get_required(tags::HEART_BT_INT)?,
+                        )
+                        .with(tags::RESET_SEQ_NUM_FLAG, "Y"),
+                );
+                state.send(
+                    FixMessage::new(msg_types::TEST_REQUEST)
+                        .with(tags::TEST_REQ_ID, READY_TEST_REQ_ID),
+                );
+            }
+            msg_types::HEARTBEAT if message.get(tags::TEST_REQ_ID) == Some(READY_TEST_REQ_ID) => {
+                let _ = self.is_ready.send_replace(true);
+            }
+            msg_types::NEW_ORDER_SINGLE => {
+                let order_id = format!("ORD{}", state.next_id());
+                let order = VenueOrder {
+                    order_id,
+                    client_order_id: message.get_required(tags::CL_ORD_ID)?.to_owned(),
+                    symbol: message.get_required(tags::SYMBOL)?.to_owned(),
+                    side: message.get_required(tags::SIDE)?.to_owned(),
+                    order_qty: message.get_decimal(tags::ORDER_QTY)?,
+                    price: message.get_decimal(tags::PRICE)?,
+                    cum_qty: Decimal::ZERO,
+                    cum_cost: Decimal::ZERO,
+                    ord_status: "0",
+                };
+                let exec_id = state.next_id();
+                let report = order.report(&order.client_order_id, "0", exec_id);
+                state.orders.push(order);
+                state.send(report);
+            }
+            msg_types::ORDER_CANCEL_REQUEST => {
+                let request_id = message.get_required(tags::CL_ORD_ID)?;
+                let exec_id = state.next_id();
+                let report = match state.find_order(&message, tags::ORIG_CL_ORD_ID) {
+                    Some(index) if state.orders[index].is_open() => {
+                        let order = &mut state.orders[index];
+                        order.ord_status = "4";
+                        order
+                            .report(request_id, "4", exec_id)
+                            .with(tags::ORIG_CL_ORD_ID, &order.client_order_id)
+                    }
+                    _ => FixMessage::new(msg_types::ORDER_CANCEL_REJECT)
+                        .with(tags::CL_ORD_ID, request_id)
+                        .with(tags::CXL_REJ_REASON, 1),
+                };
+                state.send(report);
+            }
+            msg_types::ORDER_STATUS_REQUEST => {
+                let request_id = message.get_required(tags::ORD_STATUS_REQ_ID)?;
+                let exec_id = state.next_id();
+                let report = match state.find_order(&message, tags::CL_ORD_ID) {
+                    Some(index) => state.orders[index].status_report(exec_id),
+                    None => FixMessage::new(msg_types::EXECUTION_REPORT)
+                        .with(tags::EXEC_TYPE, "I")
+                        .with(tags::ORD_STATUS, "8")
+                        .with(tags::ORD_REJ_REASON, 5),
+                };
+                state.send(report.with(tags::ORD_STATUS_REQ_ID, request_id));
+            }
+            msg_types::ORDER_MASS_STATUS_REQUEST => {
+                let request_id = message.get_required(tags::MASS_STATUS_REQ_ID)?;
+                let exec_id = state.next_id();
+                let mut reports = state
+                    .orders
+                    .iter()
+                    .filter(|x| x.is_open())
+                    .map(|x| x.status_report(exec_id))
+                    .collect::<Vec<_>>();
+                if reports.is_empty() {
+                    reports.push(
+                        FixMessage::new(msg_types::EXECUTION_REPORT)
+                            .with(tags::EXEC_TYPE, "I")
+                            .with(tags::ORD_STATUS, "8"),
+                    );
+                }
+
+                let last_index = reports.len() - 1;
+                for (index, report) in reports.into_iter().enumerate() {
+                    let is_last = if index == last_index { "Y" } else { "N" };
+                    state.send(
+                        report
+                            .with(tags::MASS_STATUS_REQ_ID, request_id)
+                            .with(tags::LAST_RPT_REQUESTED, is_last),
+                    );
+                }
+            }
+            msg_types::LOGOUT => {
+                state.send(FixMessage::new(msg_types::LOGOUT));
+                state.outgoing = None;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MockExchange for MockVenue {
+    async fn wait_connected(&self) -> Result<()> {
+        let mut is_ready = self.is_ready.subscribe();
+        while !*is_ready.borrow_and_update() {
+            is_ready.changed().await?;
+        }
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        let _ = self.is_ready.send_replace(false);
+        self.state.lock().outgoing = None;
+        Ok(())
+    }
+
+    async fn fill(
+        &self,
+        exchange_order_id: &ExchangeOrderId,
+        amount: Amount,
+        price: Price,
+        notify: bool,
+    ) -> Result<()> {
+        let mut state = self.state.lock();
+        let exec_id = state.next_id();
+        let order = state
+            .orders
+            .iter_mut()
+            .find(|x| x.order_id == exchange_order_id.as_str())
+            .with_context(|| format!("Order {exchange_order_id} not found"))?;
+
+        order.cum_qty += amount;
+        order.cum_cost += amount * price;
+        order.ord_status = if order.cum_qty < order.order_qty {
+            "1"
+        } else {
+            "2"
+        };
+
+        if notify {
+            let report = order
+                .report(&order.client_order_id, "F", exec_id)
+                .with(tags::COMMISSION, amount * price * COMMISSION_RATE)
+                .with(tags::LAST_QTY, amount)
+                .with(tags::LAST_PX, price)
+                .with(tags::LAST_LIQUIDITY_IND, 1)
+                .with(tags::TRANSACT_TIME, format_timestamp(Utc::now()));
+            state.send(report);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::FixBuilder;
+    use core_tests::conformance::ConformanceScenario;
+    use mmb_core::settings::{CurrencyPairSetting, ExchangeSettings};
+    use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn conformance() {
+        let mut config = FixConfig::parse(include_str!("../example.toml")).expect("in test");
+        let venue = MockVenue::start(&config).await.expect("in test");
+        config.host = "127.0.0.1".to_owned();
+        config.port = venue.port;
+        config.tls = false;
+        config.reconnect_interval = 1;
+
+        let mut settings = ExchangeSettings::new_short(
+            ExchangeAccountId::new("FixVenue", 0),
+            "user".to_owned(),
+            "password".to_owned(),
+            false,
+        );
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usd".into());
+        settings.currency_pairs = Some(vec![CurrencyPairSetting::Ordinary {
+            base: "btc".into(),
+            quote: "usd".into(),
+        }]);
+
+        let order = ConformanceScenario::new(currency_pair, dec!(30000), dec!(0.01))
+            .run(Box::new(FixBuilder::new(config)), settings, venue.as_ref())
+            .await
+            .expect("in test");
+
+        assert_eq!(order.filled_amount(), dec!(0.005));
+    }
+}
@@ -0,0 +1,598 @@
+use crate::config::FixConfig;
+use crate::message::{format_timestamp, msg_types, tags, FixMessage};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+const ORIG_SENDING_TIME: u32 = 122;
+
+struct SessionState {
+    /// Encoded messages which are written to connection, it's absent while disconnected
+    outgoing: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    is_logged_on: bool,
+    is_logout_sent: bool,
+    next_outgoing_seq_num: u64,
+    next_incoming_seq_num: u64,
+    /// Messages received ahead of sequence gap, they are processed after resending of missed ones
+    queued_messages: BTreeMap<u64, FixMessage>,
+    /// Highest sequence number received while resending is requested
+    resend_up_to: Option<u64>,
+    connected_at: Instant,
+    last_sent: Instant,
+    last_received: Instant,
+    test_request_sent: Option<Instant>,
+}
+
+/// Session layer of FIX 4.4: logon, heartbeats, sequence numbers and resending.
+/// Sent application messages aren't stored, so resend requests of counterparty are answered
+/// by gap fill: stale orders are never resent and their state is restored by status requests.
+pub(crate) struct FixSession {
+    sender_comp_id: String,
+    target_comp_id: String,
+    heartbeat_interval: Duration,
+    reset_on_logon: bool,
+    state: Mutex<SessionState>,
+}
+
+impl FixSession {
+    pub(crate) fn new(config: &FixConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            sender_comp_id: config.sender_comp_id.clone(),
+            target_comp_id: config.target_comp_id.clone(),
+            heartbeat_interval: config.heartbeat_interval(),
+            reset_on_logon: config.reset_on_logon,
+            state: Mutex::new(SessionState {
+                outgoing: None,
+                is_logged_on: false,
+                is_logout_sent: false,
+                next_outgoing_seq_num: 1,
+                next_incoming_seq_num: 1,
+                queued_messages: BTreeMap::new(),
+                resend_up_to: None,
+                connected_at: now,
+                last_sent: now,
+                last_received: now,
+                test_request_sent: None,
+            }),
+        }
+    }
+
+    /// Sends Logon with specified credentials fields as the first message of connection
+    pub(crate) fn on_connected(
+        &self,
+        outgoing: mpsc::UnboundedSender<Vec<u8>>,
+        credentials: Vec<(u32, String)>,
+        now: Instant,
+    ) -> Result<()> {
+        let mut state = self.state.lock();
+        if self.reset_on_logon {
+            state.next_outgoing_seq_num = 1;
+            state.next_incoming_seq_num = 1;
+        }
+        state.outgoing = Some(outgoing);
+        state.is_logged_on = false;
+        state.is_logout_sent = false;
+        state.connected_at = now;
+        state.last_sent = now;
+        state.last_received = now;
+        state.test_request_sent = None;
+
+        let mut logon = FixMessage::new(msg_types::LOGON)
+            .with(tags::ENCRYPT_METHOD, 0)
+            .with(tags::HEART_BT_INT, self.heartbeat_interval.as_secs());
+        if self.reset_on_logon {
+            logon.push(tags::RESET_SEQ_NUM_FLAG, "Y");
+        }
+        for (tag, value) in credentials {
+            logon.push(tag, value);
+        }
+
+        let _ = self.send_locked(&mut state, logon, now)?;
+        Ok(())
+    }
+
+    pub(crate) fn on_disconnected(&self) {
+        let mut state = self.state.lock();
+        state.outgoing = None;
+        state.is_logged_on = false;
+        state.queued_messages.clear();
+        state.resend_up_to = None;
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_logged_on(&self) -> bool {
+        self.state.lock().is_logged_on
+    }
+
+    /// Sends application message, returns its sequence number
+    pub(crate) fn send(&self, message: FixMessage) -> Result<u64> {
+        let mut state = self.state.lock();
+        if !state.is_logged_on {
+            bail!("FIX session isn't logged on");
+        }
+
+        self.send_locked(&mut state, message, Instant::now())
+    }
+
+    pub(crate) fn logout(&self, text: &str) -> Result<()> {
+        let mut state = self.state.lock();
+        state.is_logout_sent = true;
+        let logout = FixMessage::new(msg_types::LOGOUT).with(tags::TEXT, text);
+        let _ = self.send_locked(&mut state, logout, Instant::now())?;
+        Ok(())
+    }
+
+    /// Handles session level messages and returns application messages in order of sequence numbers.
+    /// Error means that session is broken and connection should be closed.
+    pub(crate) fn on_message(&self, message: FixMessage, now: Instant) -> Result<Vec<FixMessage>> {
+        let mut state = self.state.lock();
+        state.last_received = now;
+
+        let seq_num = message.seq_num()?;
+        let msg_type = message.msg_type();
+
+        // reset mode of SequenceReset and Logon with reset flag ignore sequence numbers
+        if msg_type == msg_types::SEQUENCE_RESET && !message.get_flag(tags::GAP_FILL_FLAG) {
+            let new_seq_num = message.get_parsed(tags::NEW_SEQ_NO)?;
+            log::warn!(
+                "FIX incoming sequence is reset from {} to {new_seq_num}",
+                state.next_incoming_seq_num
+            );
+            state.next_incoming_seq_num = new_seq_num;
+            return self.process_queued_messages(&mut state, Vec::new(), now);
+        }
+        if msg_type == msg_types::LOGON && message.get_flag(tags::RESET_SEQ_NUM_FLAG) {
+            state.next_incoming_seq_num = seq_num;
+        }
+
+        if seq_num < state.next_incoming_seq_num {
+            if message.get_flag(tags::POSS_DUP_FLAG) {
+                log::trace!("Skipped FIX message which was already received: {message}");
+                return Ok(Vec::new());
+            }
+
+            let text = format!(
+                "MsgSeqNum too low, expecting {} but received {seq_num}",
+                state.next_incoming_seq_num
+            );
+            let _ = self.send_logout_locked(&mut state, &text, now);
+            bail!(text);
+        }
+
+        if seq_num > state.next_incoming_seq_num {
+            log::warn!(
+                "FIX sequence gap: expected {}, received {seq_num}",
+                state.next_incoming_seq_num
+            );
+            match msg_type {
+                // logon and logout are handled before resending of missed messages,
+                // counterparty covers them by gap fill
+                msg_types::LOGON => self.handle_logon(&mut state),
+                msg_types::LOGOUT => self.handle_logout(&mut state, &message, now)?,
+                _ => {
+                    let _ = state.queued_messages.insert(seq_num, message);
+                }
+            }
+
+            if state.resend_up_to.is_none() {
+                let resend_request = FixMessage::new(msg_types::RESEND_REQUEST)
+                    .with(tags::BEGIN_SEQ_NO, state.next_incoming_seq_num)
+                    // all messages after BeginSeqNo
+                    .with(tags::END_SEQ_NO, 0);
+                let _ = self.send_locked(&mut state, resend_request, now)?;
+            }
+            state.resend_up_to = state.resend_up_to.max(Some(seq_num));
+
+            return Ok(Vec::new());
+        }
+
+        let mut application_messages = Vec::new();
+        self.process(&mut state, message, &mut application_messages, now)?;
+        self.process_queued_messages(&mut state, application_messages, now)
+    }
+
+    fn process_queued_messages(
+        &self,
+        state: &mut SessionState,
+        mut application_messages: Vec<FixMessage>,
+        now: Instant,
+    ) -> Result<Vec<FixMessage>> {
+        while let Some(entry) = state.queued_messages.first_entry() {
+            let seq_num = *entry.key();
+            if seq_num > state.next_incoming_seq_num {
+                break;
+            }
+
+            // queued message could be resent or covered by gap fill
+            let message = entry.remove();
+            if seq_num == state.next_incoming_seq_num {
+                self.process(state, message, &mut application_messages, now)?;
+            }
+        }
+
+        if state
+            .resend_up_to
+            .is_some_and(|x| x < state.next_incoming_seq_num)
+        {
+            log::info!("FIX sequence gap is filled");
+            state.resend_up_to = None;
+        }
+
+        Ok(application_messages)
+    }
+
+    /// Processes message with expected sequence number
+    fn process(
+        &self,
+        state: &mut SessionState,
+        message: FixMessage,
+        application_messages: &mut Vec<FixMessage>,
+        now: Instant,
+    ) -> Result<()> {
+        state.next_incoming_seq_num = message.seq_num()? + 1;
+
+        match message.msg_type() {
+            msg_types::HEARTBEAT => {}
+            msg_types::TEST_REQUEST => {
+                let mut heartbeat = FixMessage::new(msg_types::HEARTBEAT);
+                if let Some(test_request_id) = message.get(tags::TEST_REQ_ID) {
+                    heartbeat.push(tags::TEST_REQ_ID, test_request_id);
+                }
+                let _ = self.send_locked(state, heartbeat, now)?;
+            }
+            msg_types::RESEND_REQUEST => self.handle_resend_request(state, &message, now)?,
+            msg_types::SEQUENCE_RESET => {
+                let new_seq_num = message.get_parsed(tags::NEW_SEQ_NO)?;
+                if new_seq_num > state.next_incoming_seq_num {
+                    state.next_incoming_seq_num = new_seq_num;
+                }
+            }
+            msg_types::LOGON => self.handle_logon(state),
+            msg_types::LOGOUT => self.handle_logout(state, &message, now)?,
+            // session level reject refers to application request which should be failed
+            msg_types::REJECT => {
+                log::warn!("FIX message is rejected: {message}");
+                application_messages.push(message);
+            }
+            _ => application_messages.push(message),
+        }
+
+        Ok(())
+    }
+
+    fn handle_logon(&self, state: &mut SessionState) {
+        if !state.is_logged_on {
+            log::info!(
+                "FIX session {} -> {} is logged on",
+                self.sender_comp_id,
+                self.target_comp_id
+            );
+        }
+        state.is_logged_on = true;
+    }
+
+    fn handle_logout(
+        &self,
+        state: &mut SessionState,
+        message: &FixMessage,
+        now: Instant,
+    ) -> Result<()> {
+        state.is_logged_on = false;
+        if !state.is_logout_sent {
+            self.send_logout_locked(state, "Logout confirmation", now)?;
+        }
+
+        bail!(
+            "FIX session is logged out by counterparty: {}",
+            message.get(tags::TEXT).unwrap_or_default()
+        )
+    }
+
+    /// Missed messages are replaced by gap fill, see [`FixSession`]
+    fn handle_resend_request(
+        &self,
+        state: &mut SessionState,
+        message: &FixMessage,
+        now: Instant,
+    ) -> Result<()> {
+        let begin_seq_num: u64 = message.get_parsed(tags::BEGIN_SEQ_NO)?;
+        let next_seq_num = state.next_outgoing_seq_num;
+        if begin_seq_num >= next_seq_num {
+            log::warn!("FIX resend of messages which weren't sent is requested: {message}");
+            return Ok(());
+        }
+
+        let gap_fill = FixMessage::new(msg_types::SEQUENCE_RESET)
+            .with(tags::GAP_FILL_FLAG, "Y")
+            .with(tags::NEW_SEQ_NO, next_seq_num);
+        self.write(state, gap_fill, begin_seq_num, true, now)
+    }
+
+    /// Sends heartbeats and checks that counterparty is alive.
+    /// Error means that connection should be closed.
+    pub(crate) fn on_timer(&self, now: Instant) -> Result<()> {
+        let mut state = self.state.lock();
+        if state.outgoing.is_none() {
+            return Ok(());
+        }
+
+        if !state.is_logged_on {
+            if now - state.connected_at > self.heartbeat_interval {
+                bail!("FIX logon response wasn't received in time");
+            }
+            return Ok(());
+        }
+
+        // counterparty heartbeats can be delayed by transmission
+        let max_silence = self.heartbeat_interval + self.heartbeat_interval / 5;
+        if now - state.last_received < max_silence {
+            state.test_request_sent = None;
+        } else {
+            match state.test_request_sent {
+                Some(sent) if now - sent > self.heartbeat_interval => {
+                    bail!("FIX counterparty doesn't respond to TestRequest")
+                }
+                Some(_) => {}
+                None => {
+                    let test_request = FixMessage::new(msg_types::TEST_REQUEST)
+                        .with(tags::TEST_REQ_ID, format_timestamp(Utc::now()));
+                    let _ = self.send_locked(&mut state, test_request, now)?;
+                    state.test_request_sent = Some(now);
+                }
+            }
+        }
+
+        if now - state.last_sent >= self.heartbeat_interval {
+            let _ = self.send_locked(&mut state, FixMessage::new(msg_types::HEARTBEAT), now)?;
+        }
+
+        Ok(())
+    }
+
+    fn send_logout_locked(&self, state: &mut SessionState, text: &str, now: Instant) -> Result<()> {
+        state.is_logout_sent = true;
+        let logout = FixMessage::new(msg_types::LOGOUT).with(tags::TEXT, text);
+        let _ = self.send_locked(state, logout, now)?;
+        Ok(())
+    }
+
+    fn send_locked(
+        &self,
+        state: &mut SessionState,
+        message: FixMessage,
+        now: Instant,
+    ) -> Result<u64> {
+        let seq_num = state.next_outgoing_seq_num;
+        self.write(state, message, seq_num, false, now)?;
+        state.next_outgoing_seq_num += 1;
+
+        Ok(seq_num)
+    }
+
+    fn write(
+        &self,
+        state: &mut SessionState,
+        message: FixMessage,
+        seq_num: u64,
+        is_poss_dup: bool,
+        now: Instant,
+    ) -> Result<()> {
+        let outgoing = state
+            .outgoing
+            .as_ref()
+            .context("FIX session isn't connected")?;
+
+        let sending_time = format_timestamp(Utc::now());
+        let mut header = vec![
+            (tags::SENDER_COMP_ID, self.sender_comp_id.clone()),
+            (tags::TARGET_COMP_ID, self.target_comp_id.clone()),
+            (tags::MSG_SEQ_NUM, seq_num.to_string()),
+        ];
+        if is_poss_dup {
+            header.push((tags::POSS_DUP_FLAG, "Y".to_owned()));
+            header.push((ORIG_SENDING_TIME, sending_time.clone()));
+        }
+        header.push((tags::SENDING_TIME, sending_time));
+
+        let message = message.with_header(header);
+        log::debug!("FIX message is sent: {message}");
+        outgoing
+            .send(message.encode())
+            .map_err(|_| anyhow!("FIX connection is closed"))?;
+        state.last_sent = now;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::FixDecoder;
+
+    fn create_session() -> FixSession {
+        let config = FixConfig::parse(include_str!("../example.toml")).expect("in test");
+        FixSession::new(&config)
+    }
+
+    fn received(msg_type: &str, seq_num: u64) -> FixMessage {
+        FixMessage::new(msg_type).with(tags::MSG_SEQ_NUM, seq_num)
+    }
+
+    fn sent_messages(outgoing: &mut mpsc::UnboundedReceiver<Vec<u8>>) -> Vec<FixMessage> {
+        let mut decoder = FixDecoder::default();
+        while let Ok(data) = outgoing.try_recv() {
+            decoder.extend(&data);
+        }
+
+        let mut messages = Vec::new();
+        while let Some(message) = decoder.next_message().expect("in test") {
+            messages.push(message);
+        }
+        messages
+    }
+
+    fn logged_on_session(now: Instant) -> (FixSession, mpsc::UnboundedReceiver<Vec<u8>>) {
+        let session = create_session();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        session
+            .on_connected(tx, vec![(tags::USERNAME, "user".to_owned())], now)
+            .expect("in test");
+
+        let logon = sent_messages(&mut rx);
+        assert_eq!(logon.len(), 1);
+        assert_eq!(logon[0].msg_type(), msg_types::LOGON);
+        assert_eq!(logon[0].get(tags::RESET_SEQ_NUM_FLAG), Some("Y"));
+        assert_eq!(logon[0].get(tags::USERNAME), Some("user"));
+        assert_eq!(logon[0].get(tags::SENDER_COMP_ID), Some("CLIENT1"));
+        assert!(!session.is_logged_on());
+
+        let application_messages = session
+            .on_message(
+                received(msg_types::LOGON, 1).with(tags::RESET_SEQ_NUM_FLAG, "Y"),
+                now,
+            )
+            .expect("in test");
+        assert!(application_messages.is_empty());
+        assert!(session.is_logged_on());
+
+        (session, rx)
+    }
+
+    #[test]
+    fn answer_test_request() {
+        let now = Instant::now();
+        let (session, mut rx) = logged_on_session(now);
+
+        let _ = session
+            .on_message(
+                received(msg_types::TEST_REQUEST, 2).with(tags::TEST_REQ_ID, "ping"),
+                now,
+            )
+            .expect("in test");
+
+        let sent = sent_messages(&mut rx);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].msg_type(), msg_types::HEARTBEAT);
+        assert_eq!(sent[0].get(tags::TEST_REQ_ID), Some("ping"));
+        assert_eq!(sent[0].seq_num().expect("in test"), 2);
+    }
+
+    #[test]
+    fn request_resend_on_sequence_gap() {
+        let now = Instant::now();
+        let (session, mut rx) = logged_on_session(now);
+
+        let application_messages = session
+            .on_message(received(msg_types::EXECUTION_REPORT, 4), now)
+            .expect("in test");
+        assert!(application_messages.is_empty());
+
+        let sent = sent_messages(&mut rx);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].msg_type(), msg_types::RESEND_REQUEST);
+        assert_eq!(sent[0].get(tags::BEGIN_SEQ_NO), Some("2"));
+        assert_eq!(sent[0].get(tags::END_SEQ_NO), Some("0"));
+
+        // admin message is replaced by gap fill and application message is resent
+        let application_messages = session
+            .on_message(
+                received(msg_types::SEQUENCE_RESET, 2)
+                    .with(tags::POSS_DUP_FLAG, "Y")
+                    .with(tags::GAP_FILL_FLAG, "Y")
+                    .with(tags::NEW_SEQ_NO, 3),
+                now,
+            )
+            .expect("in test");
+        assert!(application_messages.is_empty());
+
+        let application_messages = session
+            .on_message(
+                received(msg_types::EXECUTION_REPORT, 3)
+                    .with(tags::POSS_DUP_FLAG, "Y")
+                    .with(tags::CL_ORD_ID, "resent"),
+                now,
+            )
+            .expect("in test");
+        assert_eq!(application_messages.len(), 2);
+        assert_eq!(application_messages[0].get(tags::CL_ORD_ID), Some("resent"));
+        assert_eq!(application_messages[1].seq_num().expect("in test"), 4);
+
+        // message which was queued is skipped when it's resent
+        let application_messages = session
+            .on_message(
+                received(msg_types::EXECUTION_REPORT, 4).with(tags::POSS_DUP_FLAG, "Y"),
+                now,
+            )
+            .expect("in test");
+        assert!(application_messages.is_empty());
+        assert!(sent_messages(&mut rx).is_empty());
+    }
+
+    #[test]
+    fn answer_resend_request_by_gap_fill() {
+        let now = Instant::now();
+        let (session, mut rx) = logged_on_session(now);
+        let _ = session
+            .send(FixMessage::new(msg_types::NEW_ORDER_SINGLE))
+            .expect("in test");
+        let _ = sent_messages(&mut rx);
+
+        let _ = session
+            .on_message(
+                received(msg_types::RESEND_REQUEST, 2)
+                    .with(tags::BEGIN_SEQ_NO, 1)
+                    .with(tags::END_SEQ_NO, 0),
+                now,
+            )
+            .expect("in test");
+
+        let sent = sent_messages(&mut rx);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].msg_type(), msg_types::SEQUENCE_RESET);
+        assert_eq!(sent[0].seq_num().expect("in test"), 1);
+        assert_eq!(sent[0].get(tags::GAP_FILL_FLAG), Some("Y"));
+        assert_eq!(sent[0].get(tags::POSS_DUP_FLAG), Some("Y"));
+        assert_eq!(sent[0].get(tags::NEW_SEQ_NO), Some("3"));
+    }
+
+    #[test]
+    fn disconnect_on_too_low_sequence_number() {
+        let now = Instant::now();
+        let (session, mut rx) = logged_on_session(now);
+
+        let result = session.on_message(received(msg_types::EXECUTION_REPORT, 1), now);
+
+        assert!(result.is_err());
+        let sent = sent_messages(&mut rx);
+        assert_eq!(sent[0].msg_type(), msg_types::LOGOUT);
+    }
+
+    #[test]
+    fn send_heartbeat_and_test_request() {
+        let now = Instant::now();
+        let (session, mut rx) = logged_on_session(now);
+        let heartbeat_interval = Duration::from_secs(30);
+
+        session
+            .on_timer(now + Duration::from_secs(1))
+            .expect("in test");
+        assert!(sent_messages(&mut rx).is_empty());
+
+        session.on_timer(now + heartbeat_interval).expect("in test");
+        let sent = sent_messages(&mut rx);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].msg_type(), msg_types::HEARTBEAT);
+
+        let silence = now + heartbeat_interval * 2;
+        session.on_timer(silence).expect("in test");
+        let sent = sent_messages(&mut rx);
+        assert_eq!(sent[0].msg_type(), msg_types::TEST_REQUEST);
+
+        assert!(session.on_timer(silence + heartbeat_interval * 2).is_err());
+    }
+}
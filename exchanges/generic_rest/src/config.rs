@@ -0,0 +1,304 @@
+//! Description of exchange REST API which is interpreted by generic connector.
+//! Paths and parameters are templates with placeholders like `{symbol}`, fields of responses
+//! are located by JSON pointers (RFC 6901), e.g. `/data/0/orderId`.
+
+use anyhow::{bail, Context, Result};
+use mmb_domain::market::{ExchangeErrorType, ExchangeId};
+use mmb_domain::order::snapshot::OrderStatus;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::read_to_string;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GenericRestConfig {
+    pub exchange_id: ExchangeId,
+    /// Host of REST API, e.g. `https://api.exchange.com`
+    pub rest_host: String,
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: usize,
+    /// Format of body of POST requests
+    #[serde(default)]
+    pub body_format: BodyFormat,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    pub values: ValuesConfig,
+    #[serde(default)]
+    pub errors: ErrorsConfig,
+    pub symbols: SymbolsEndpoint,
+    pub create_order: CreateOrderEndpoint,
+    pub cancel_order: RequestConfig,
+    pub order_info: OrderInfoEndpoint,
+    /// Exchange without request of open orders can't restore orders after restart
+    pub open_orders: Option<OpenOrdersEndpoint>,
+    pub balances: Option<BalancesEndpoint>,
+}
+
+fn default_requests_per_minute() -> usize {
+    600
+}
+
+impl GenericRestConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        let config = read_to_string(path)
+            .with_context(|| format!("Unable load generic exchange config: {path}"))?;
+
+        Self::parse(&config)
+            .with_context(|| format!("Unable parse generic exchange config: {path}"))
+    }
+
+    pub fn parse(config: &str) -> Result<Self> {
+        let config: Self = toml_edit::de::from_str(config)?;
+
+        let unknown_placeholder = config
+            .auth
+            .payload
+            .split('{')
+            .skip(1)
+            .filter_map(|x| x.split_once('}').map(|(name, _)| name))
+            .find(|name| !PAYLOAD_PLACEHOLDERS.contains(name));
+        if let Some(name) = unknown_placeholder {
+            bail!("Unknown placeholder {{{name}}} in signature payload");
+        }
+
+        Ok(config)
+    }
+
+    /// Host without scheme
+    pub fn rest_uri_host(&self) -> &str {
+        self.rest_host
+            .trim_start_matches("https://")
+            .trim_end_matches('/')
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyFormat {
+    #[default]
+    Json,
+    /// `application/x-www-form-urlencoded`
+    Form,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Put,
+    Delete,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthScheme {
+    /// Only public endpoints are available
+    #[default]
+    None,
+    /// Api key is sent in header without signature
+    ApiKey,
+    /// HMAC-SHA256 signature of payload by secret key
+    HmacSha256,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub scheme: AuthScheme,
+    pub api_key_header: Option<String>,
+    /// Header for `api_passphrase` of exchange settings
+    pub passphrase_header: Option<String>,
+    /// Signature is sent in header, timestamp of header signature is sent in `timestamp_header`
+    pub signature_header: Option<String>,
+    pub timestamp_header: Option<String>,
+    /// Signature is appended to query (or form body of POST requests) as last parameter,
+    /// timestamp of such signature is sent in `timestamp_param`
+    pub signature_param: Option<String>,
+    pub timestamp_param: Option<String>,
+    /// Template of signed payload with placeholders `{timestamp}`, `{method}`, `{path}`, `{query}`,
+    /// `{path_and_query}` (path with query after `?` if it isn't empty) and `{body}`
+    #[serde(default = "default_payload")]
+    pub payload: String,
+    #[serde(default)]
+    pub encoding: SignatureEncoding,
+}
+
+const PAYLOAD_PLACEHOLDERS: [&str; 6] = [
+    "timestamp",
+    "method",
+    "path",
+    "query",
+    "path_and_query",
+    "body",
+];
+
+fn default_payload() -> String {
+    "{timestamp}{method}{path_and_query}{body}".to_owned()
+}
+
+/// Values of exchange which correspond to values of the engine
+#[derive(Deserialize, Debug, Clone)]
+pub struct ValuesConfig {
+    pub side_buy: String,
+    pub side_sell: String,
+    pub order_type_limit: String,
+    pub order_type_market: String,
+    /// Order type of post-only orders, maker only orders aren't supported without it
+    pub order_type_limit_maker: Option<String>,
+    /// Value of `{time_in_force}` placeholder for ordinary limit orders
+    pub time_in_force_limit: Option<String>,
+    /// Value of `{time_in_force}` placeholder for immediate or cancel limit orders, such orders
+    /// aren't supported without it
+    pub time_in_force_immediate_or_cancel: Option<String>,
+    /// Statuses of orders in responses
+    pub statuses: HashMap<String, OrderStatus>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ErrorsConfig {
+    /// Pointer to error code, response is successful if it's missing or in `success_codes`
+    pub code: Option<String>,
+    /// Pointer to error message, response with message and without code is an error
+    pub message: Option<String>,
+    #[serde(default)]
+    pub success_codes: Vec<String>,
+    /// Error type by error code or substring of error message
+    #[serde(default)]
+    pub types: BTreeMap<String, ExchangeErrorType>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RequestConfig {
+    pub method: HttpMethod,
+    pub path: String,
+    /// Parameters with templates of values, parameter is skipped if value of any placeholder is absent
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+    /// Parameters are sent in body for POST requests and in query for other ones
+    #[serde(default)]
+    pub params_in_query: bool,
+    /// Request isn't signed even if credentials are set
+    #[serde(default)]
+    pub public: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SymbolsEndpoint {
+    #[serde(flatten)]
+    pub request: RequestConfig,
+    pub response: SymbolFields,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SymbolFields {
+    /// Pointer to array of symbols
+    #[serde(default)]
+    pub items: String,
+    pub symbol: String,
+    pub base: String,
+    pub quote: String,
+    pub price_tick: String,
+    pub amount_step: String,
+    pub min_amount: Option<String>,
+    pub min_cost: Option<String>,
+    /// Symbols are skipped if field `status` isn't equal to `trading_status`
+    pub status: Option<String>,
+    pub trading_status: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateOrderEndpoint {
+    #[serde(flatten)]
+    pub request: RequestConfig,
+    pub response: CreateOrderFields,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateOrderFields {
+    pub exchange_order_id: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct OrderInfoEndpoint {
+    #[serde(flatten)]
+    pub request: RequestConfig,
+    pub response: OrderFields,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct OpenOrdersEndpoint {
+    #[serde(flatten)]
+    pub request: RequestConfig,
+    /// Pointer to array of orders
+    #[serde(default)]
+    pub items: String,
+    pub response: OrderFields,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct OrderFields {
+    /// Pointer to order object in response of order info request
+    #[serde(default)]
+    pub item: String,
+    pub exchange_order_id: String,
+    pub client_order_id: String,
+    pub symbol: String,
+    pub side: String,
+    pub status: String,
+    pub price: String,
+    pub amount: String,
+    pub filled_amount: String,
+    pub average_fill_price: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BalancesEndpoint {
+    #[serde(flatten)]
+    pub request: RequestConfig,
+    pub response: BalanceFields,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BalanceFields {
+    /// Pointer to array of balances
+    #[serde(default)]
+    pub items: String,
+    pub currency: String,
+    pub balance: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_example_config() {
+        let config = GenericRestConfig::parse(include_str!("../example.toml")).expect("in test");
+
+        assert_eq!(config.exchange_id.as_str(), "Example");
+        assert_eq!(config.rest_uri_host(), "api.example.com");
+        assert_eq!(config.auth.scheme, AuthScheme::HmacSha256);
+        assert_eq!(config.create_order.request.method, HttpMethod::Post);
+        assert_eq!(
+            config.create_order.request.params.get("quantity"),
+            Some(&"{amount}".to_owned())
+        );
+        assert_eq!(
+            config.values.statuses.get("FILLED"),
+            Some(&OrderStatus::Completed)
+        );
+        assert_eq!(
+            config.errors.types.get("-2011"),
+            Some(&ExchangeErrorType::OrderNotFound)
+        );
+        assert!(config.open_orders.is_some());
+    }
+}
@@ -0,0 +1,1007 @@
+use crate::config::{
+    AuthConfig, AuthScheme, BodyFormat, ErrorsConfig, GenericRestConfig, HttpMethod, OrderFields,
+    RequestConfig, SignatureEncoding,
+};
+use crate::mapping::{
+    contains_placeholder, get_decimal, get_items, get_optional_decimal, get_string, get_value,
+    render, Vars,
+};
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use dashmap::DashMap;
+use function_name::named;
+use hmac::{Hmac, Mac};
+use hyper::body::Bytes;
+use hyper::header::CONTENT_TYPE;
+use hyper::http::request::Builder;
+use hyper::Uri;
+use itertools::Itertools;
+use mmb_core::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    RestFillsType, WebSocketOptions,
+};
+use mmb_core::exchanges::rest_client::{
+    ErrorHandler, ErrorHandlerData, RequestType, RestClient, RestHeaders, RestResponse,
+};
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use mmb_core::exchanges::timeouts::timeout_manager::TimeoutManager;
+use mmb_core::exchanges::traits::{
+    ExchangeClientBuilder, ExchangeClientBuilderResult, ExchangeError, HandleMetricsCb,
+    HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb,
+    Support,
+};
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{AllowedEventSourceType, ExchangeBalance, ExchangeEvent};
+use mmb_domain::exchanges::symbol::{Precision, Symbol};
+use mmb_domain::market::{
+    CurrencyCode, CurrencyId, CurrencyPair, ExchangeErrorType, ExchangeId, SpecificCurrencyPair,
+};
+use mmb_domain::order::pool::{OrderRef, OrdersPool};
+use mmb_domain::order::snapshot::{
+    ExchangeOrderId, OrderExecutionType, OrderInfo, OrderOptions, OrderSide, UserOrder,
+};
+use mmb_utils::hashmap;
+use parking_lot::{Mutex, RwLock};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use url::form_urlencoded;
+
+/// Errors are detected by fields of response described in config
+pub struct ErrorHandlerGeneric {
+    config: ErrorsConfig,
+}
+
+impl ErrorHandler for ErrorHandlerGeneric {
+    fn check_spec_rest_error(&self, response: &RestResponse) -> Result<(), ExchangeError> {
+        let content: Value = serde_json::from_str(&response.content).map_err(|err| {
+            ExchangeError::parsing(format!(
+                "Unable to parse response.content: {err:?}\n{}",
+                response.content
+            ))
+        })?;
+
+        let message = self
+            .config
+            .message
+            .as_deref()
+            .and_then(|pointer| get_string(&content, pointer).ok());
+        let code = self
+            .config
+            .code
+            .as_deref()
+            .and_then(|pointer| get_string(&content, pointer).ok());
+
+        match (code, message) {
+            (Some(code), _) if self.config.success_codes.contains(&code) => Ok(()),
+            (Some(code), message) => Err(ExchangeError::new(
+                ExchangeErrorType::Unknown,
+                message.unwrap_or_else(|| response.content.clone()),
+                code.parse().ok(),
+            )),
+            // message without code is an error only for exchanges which don't send codes
+            (None, Some(message)) if self.config.code.is_none() => Err(ExchangeError::new(
+                ExchangeErrorType::Unknown,
+                message,
+                None,
+            )),
+            (None, _) => Ok(()),
+        }
+    }
+
+    fn clarify_error_type(&self, error: &ExchangeError) -> ExchangeErrorType {
+        let code = error.code.map(|x| x.to_string());
+        let message = error.message.to_lowercase();
+        self.config
+            .types
+            .iter()
+            .find(|(key, _)| {
+                code.as_ref() == Some(*key) || message.contains(key.to_lowercase().as_str())
+            })
+            .map_or(ExchangeErrorType::Unknown, |(_, error_type)| *error_type)
+    }
+}
+
+/// Adds authentication headers, signature in query is added during building of request
+pub struct RestHeadersGeneric {
+    api_key: String,
+    secret_key: String,
+    api_passphrase: String,
+    auth: AuthConfig,
+    body_format: BodyFormat,
+}
+
+impl RestHeaders for RestHeadersGeneric {
+    fn add_specific_headers(
+        &self,
+        builder: Builder,
+        uri: &Uri,
+        request_type: RequestType,
+    ) -> Builder {
+        self.add_specific_headers_with_body(builder, uri, request_type, &[])
+    }
+
+    fn add_specific_headers_with_body(
+        &self,
+        builder: Builder,
+        uri: &Uri,
+        request_type: RequestType,
+        body: &[u8],
+    ) -> Builder {
+        let mut builder = match self.body_format {
+            BodyFormat::Json => builder.header(CONTENT_TYPE, "application/json"),
+            BodyFormat::Form => builder.header(CONTENT_TYPE, "application/x-www-form-urlencoded"),
+        };
+        // empty api key means public client
+        if self.auth.scheme == AuthScheme::None || self.api_key.is_empty() {
+            return builder;
+        }
+
+        if let Some(header) = &self.auth.api_key_header {
+            builder = builder.header(header, &self.api_key);
+        }
+        if let Some(header) = &self.auth.passphrase_header {
+            builder = builder.header(header, &self.api_passphrase);
+        }
+
+        let signature_header = match (&self.auth.scheme, &self.auth.signature_header) {
+            (AuthScheme::HmacSha256, Some(header)) => header,
+            _ => return builder,
+        };
+
+        let timestamp = Utc::now().timestamp_millis().to_string();
+        let payload = create_payload(
+            &self.auth.payload,
+            &timestamp,
+            request_type.as_str(),
+            uri.path(),
+            uri.query().unwrap_or_default(),
+            &String::from_utf8_lossy(body),
+        );
+        builder = builder.header(
+            signature_header,
+            create_signature(&self.secret_key, &payload, self.auth.encoding),
+        );
+
+        match &self.auth.timestamp_header {
+            Some(header) => builder.header(header, timestamp),
+            None => builder,
+        }
+    }
+}
+
+fn create_payload(
+    template: &str,
+    timestamp: &str,
+    method: &str,
+    path: &str,
+    query: &str,
+    body: &str,
+) -> String {
+    let path_and_query = match query.is_empty() {
+        true => path.to_owned(),
+        false => format!("{path}?{query}"),
+    };
+    let vars: Vars = hashmap![
+        "timestamp" => timestamp.to_owned(),
+        "method" => method.to_owned(),
+        "path" => path.to_owned(),
+        "query" => query.to_owned(),
+        "path_and_query" => path_and_query,
+        "body" => body.to_owned()
+    ];
+
+    // placeholders of payload are validated during loading of config
+    render(template, &vars).unwrap_or_else(|| panic!("Unknown placeholder in payload {template}"))
+}
+
+pub(crate) fn create_signature(
+    secret_key: &str,
+    payload: &str,
+    encoding: SignatureEncoding,
+) -> String {
+    let mut hmac = Hmac::<Sha256>::new_from_slice(secret_key.as_bytes())
+        .expect("Unable to calculate hmac for generic exchange signature");
+    hmac.update(payload.as_bytes());
+    let signature = hmac.finalize().into_bytes();
+
+    match encoding {
+        SignatureEncoding::Hex => hex::encode(signature),
+        SignatureEncoding::Base64 => base64::encode(signature),
+    }
+}
+
+/// Request ready for sending by `RestClient`
+#[derive(Debug)]
+pub(crate) struct PreparedRequest {
+    method: HttpMethod,
+    uri: Uri,
+    body: Option<Bytes>,
+    public: bool,
+}
+
+pub struct GenericRest {
+    pub(crate) settings: ExchangeSettings,
+    pub(crate) config: GenericRestConfig,
+    rest_client: RestClient<ErrorHandlerGeneric, RestHeadersGeneric>,
+    /// Client without credentials for public endpoints
+    public_rest_client: RestClient<ErrorHandlerGeneric, RestHeadersGeneric>,
+    pub(crate) unified_to_specific: RwLock<HashMap<CurrencyPair, SpecificCurrencyPair>>,
+    pub(crate) specific_to_unified: RwLock<HashMap<SpecificCurrencyPair, CurrencyPair>>,
+    pub(crate) supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    pub(super) traded_specific_currencies: Mutex<Vec<SpecificCurrencyPair>>,
+    pub(crate) order_created_callback: OrderCreatedCb,
+    pub(crate) order_cancelled_callback: OrderCancelledCb,
+    pub(crate) handle_order_filled_callback: HandleOrderFilledCb,
+    pub(crate) handle_trade_callback: HandleTradeCb,
+    pub(super) handle_metrics_callback: HandleMetricsCb,
+    pub(crate) websocket_message_callback: SendWebsocketMessageCb,
+}
+
+const EMPTY_RESPONSE_IS_OK: bool = true;
+
+impl GenericRest {
+    pub fn new(settings: ExchangeSettings, config: GenericRestConfig) -> GenericRest {
+        let create_rest_client = |api_key: &str, secret_key: &str| {
+            RestClient::new(
+                ErrorHandlerData::new(
+                    EMPTY_RESPONSE_IS_OK,
+                    settings.exchange_account_id,
+                    ErrorHandlerGeneric {
+                        config: config.errors.clone(),
+                    },
+                ),
+                RestHeadersGeneric {
+                    api_key: api_key.to_owned(),
+                    secret_key: secret_key.to_owned(),
+                    api_passphrase: settings.api_passphrase.clone(),
+                    auth: config.auth.clone(),
+                    body_format: config.body_format,
+                },
+            )
+        };
+
+        Self {
+            rest_client: create_rest_client(&settings.api_key, &settings.secret_key),
+            public_rest_client: create_rest_client("", ""),
+            settings,
+            config,
+            unified_to_specific: Default::default(),
+            specific_to_unified: Default::default(),
+            supported_currencies: Default::default(),
+            traded_specific_currencies: Default::default(),
+            order_created_callback: Box::new(|_, _, _| {}),
+            order_cancelled_callback: Box::new(|_, _, _| {}),
+            handle_order_filled_callback: Box::new(|_| {}),
+            handle_trade_callback: Box::new(|_, _| {}),
+            handle_metrics_callback: Box::new(|_| {}),
+            websocket_message_callback: Box::new(|_, _| Ok(())),
+        }
+    }
+
+    /// Renders path and parameters of request and signs them if signature is sent as parameter
+    pub(crate) fn prepare_request(
+        &self,
+        request: &RequestConfig,
+        vars: &Vars,
+    ) -> Result<PreparedRequest> {
+        let path = render(&request.path, vars)
+            .with_context(|| format!("Not enough values for path {}", request.path))?;
+        let mut params = request
+            .params
+            .iter()
+            .filter_map(|(name, template)| Some((name.clone(), render(template, vars)?)))
+            .collect_vec();
+
+        let auth = &self.config.auth;
+        let is_signed = !request.public
+            && auth.scheme == AuthScheme::HmacSha256
+            && !self.settings.api_key.is_empty();
+        let timestamp = Utc::now().timestamp_millis().to_string();
+        if let (true, Some(timestamp_param)) = (is_signed, &auth.timestamp_param) {
+            params.push((timestamp_param.clone(), timestamp.clone()));
+        }
+
+        let params_in_body = request.method == HttpMethod::Post && !request.params_in_query;
+        let encode = |params: &[(String, String)]| {
+            form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(params)
+                .finish()
+        };
+        let (mut query, mut body) = match (params_in_body, self.config.body_format) {
+            (false, _) => (encode(&params), String::new()),
+            (true, BodyFormat::Form) => (String::new(), encode(&params)),
+            (true, BodyFormat::Json) => (
+                String::new(),
+                Value::Object(
+                    params
+                        .into_iter()
+                        .map(|(name, value)| (name, Value::String(value)))
+                        .collect(),
+                )
+                .to_string(),
+            ),
+        };
+
+        if let (true, Some(signature_param)) = (is_signed, &auth.signature_param) {
+            let payload = create_payload(
+                &auth.payload,
+                &timestamp,
+                request_method(request.method).as_str(),
+                &path,
+                &query,
+                &body,
+            );
+            let signature = create_signature(&self.settings.secret_key, &payload, auth.encoding);
+            let signature = encode(&[(signature_param.clone(), signature)]);
+
+            // signature is the last parameter of form body or query
+            let target = match params_in_body && self.config.body_format == BodyFormat::Form {
+                true => &mut body,
+                false => &mut query,
+            };
+            if !target.is_empty() {
+                target.push('&');
+            }
+            target.push_str(&signature);
+        }
+
+        let path_and_query = match query.is_empty() {
+            true => path,
+            false => format!("{path}?{query}"),
+        };
+        let uri = Uri::builder()
+            .scheme("https")
+            .authority(self.config.rest_uri_host())
+            .path_and_query(path_and_query)
+            .build()
+            .context("Unable to build uri of request")?;
+
+        Ok(PreparedRequest {
+            method: request.method,
+            uri,
+            body: match params_in_body {
+                true => Some(Bytes::from(body)),
+                false => None,
+            },
+            public: request.public,
+        })
+    }
+
+    async fn send(
+        &self,
+        request: PreparedRequest,
+        action_name: &'static str,
+        log_args: String,
+    ) -> Result<RestResponse, ExchangeError> {
+        let rest_client = match request.public {
+            true => &self.public_rest_client,
+            false => &self.rest_client,
+        };
+
+        match request.method {
+            HttpMethod::Get => rest_client.get(request.uri, action_name, log_args).await,
+            HttpMethod::Post => {
+                rest_client
+                    .post(request.uri, request.body, action_name, log_args)
+                    .await
+            }
+            HttpMethod::Put => rest_client.put(request.uri, action_name, log_args).await,
+            HttpMethod::Delete => rest_client.delete(request.uri, action_name, log_args).await,
+        }
+    }
+
+    async fn send_request(
+        &self,
+        request: &RequestConfig,
+        vars: &Vars,
+        action_name: &'static str,
+        log_args: String,
+    ) -> Result<RestResponse, ExchangeError> {
+        let request = self
+            .prepare_request(request, vars)
+            .map_err(|err| ExchangeError::unknown(&format!("{err:?}")))?;
+
+        self.send(request, action_name, log_args).await
+    }
+
+    fn symbol_vars(&self, currency_pair: CurrencyPair) -> Vars {
+        hashmap!["symbol" => self.get_specific_currency_pair(currency_pair).to_string()]
+    }
+
+    fn order_vars(&self, order: &OrderRef) -> Vars {
+        let header = order.header();
+        let mut vars = self.symbol_vars(header.currency_pair);
+        let _ = vars.insert("client_order_id", header.client_order_id.to_string());
+        if let Some(exchange_order_id) = order.exchange_order_id() {
+            let _ = vars.insert("exchange_order_id", exchange_order_id.to_string());
+        }
+
+        vars
+    }
+
+    #[named]
+    pub(super) async fn request_all_symbols(&self) -> Result<RestResponse, ExchangeError> {
+        self.send_request(
+            &self.config.symbols.request,
+            &Vars::new(),
+            function_name!(),
+            "".to_string(),
+        )
+        .await
+    }
+
+    pub(super) fn parse_all_symbols(&self, response: &RestResponse) -> Result<Vec<Arc<Symbol>>> {
+        let content: Value = serde_json::from_str(&response.content)
+            .context("Unable to deserialize symbols of generic exchange")?;
+        let fields = &self.config.symbols.response;
+
+        let mut symbols = Vec::new();
+        for item in get_items(&content, &fields.items)? {
+            if let (Some(status), Some(trading_status)) = (&fields.status, &fields.trading_status) {
+                if &get_string(item, status)? != trading_status {
+                    continue;
+                }
+            }
+
+            let specific_currency_pair: SpecificCurrencyPair =
+                get_string(item, &fields.symbol)?.as_str().into();
+            let base_id = get_string(item, &fields.base)?;
+            let quote_id = get_string(item, &fields.quote)?;
+            let base: CurrencyCode = base_id.as_str().into();
+            let quote: CurrencyCode = quote_id.as_str().into();
+
+            let unified_currency_pair = CurrencyPair::from_codes(base, quote);
+            self.unified_to_specific
+                .write()
+                .insert(unified_currency_pair, specific_currency_pair);
+            self.specific_to_unified
+                .write()
+                .insert(specific_currency_pair, unified_currency_pair);
+            for (id, code) in [(&base_id, base), (&quote_id, quote)] {
+                let _ = self.supported_currencies.insert(id.as_str().into(), code);
+            }
+
+            symbols.push(Arc::new(Symbol::new(
+                false,
+                base_id.as_str().into(),
+                base,
+                quote_id.as_str().into(),
+                quote,
+                None,
+                None,
+                get_optional_decimal(item, fields.min_amount.as_deref())?,
+                None,
+                get_optional_decimal(item, fields.min_cost.as_deref())?,
+                base,
+                None,
+                Precision::ByTick {
+                    tick: get_decimal(item, &fields.price_tick)?,
+                },
+                Precision::ByTick {
+                    tick: get_decimal(item, &fields.amount_step)?,
+                },
+            )));
+        }
+
+        Ok(symbols)
+    }
+
+    pub(super) fn get_unified_currency_pair(
+        &self,
+        currency_pair: &SpecificCurrencyPair,
+    ) -> Result<CurrencyPair> {
+        self.specific_to_unified
+            .read()
+            .get(currency_pair)
+            .cloned()
+            .with_context(|| {
+                format!(
+                    "Not found currency pair '{currency_pair:?}' in {}",
+                    self.settings.exchange_account_id
+                )
+            })
+    }
+
+    pub(crate) fn create_order_vars(&self, order: &OrderRef) -> Result<Vars, ExchangeError> {
+        let header = order.header();
+        let values = &self.config.values;
+        let (order_type, price, time_in_force) = match header.options {
+            OrderOptions::User(UserOrder::Limit {
+                price,
+                execution_type: OrderExecutionType::MakerOnly,
+            }) => (
+                values.order_type_limit_maker.clone().ok_or_else(|| {
+                    ExchangeError::new(
+                        ExchangeErrorType::InvalidOrder,
+                        "Maker only orders aren't supported by generic exchange".to_owned(),
+                        None,
+                    )
+                })?,
+                Some(price),
+                None,
+            ),
+            OrderOptions::User(UserOrder::Limit {
+                price,
+                execution_type: OrderExecutionType::None,
+            }) => (
+                values.order_type_limit.clone(),
+                Some(price),
+                values.time_in_force_limit.clone(),
+            ),
+            OrderOptions::User(UserOrder::Limit {
+                price,
+                execution_type: OrderExecutionType::ImmediateOrCancel,
+            }) => (
+                values.order_type_limit.clone(),
+                Some(price),
+                Some(
+                    values
+                        .time_in_force_immediate_or_cancel
+                        .clone()
+                        .ok_or_else(|| {
+                            ExchangeError::new(
+                                ExchangeErrorType::InvalidOrder,
+                                "Immediate or cancel orders aren't supported by generic exchange"
+                                    .to_owned(),
+                                None,
+                            )
+                        })?,
+                ),
+            ),
+            OrderOptions::User(UserOrder::Market) => (values.order_type_market.clone(), None, None),
+            _ => return Err(ExchangeError::unknown("Unexpected order type")),
+        };
+
+        let mut vars = self.order_vars(order);
+        let _ = vars.insert("side", self.side_to_value(header.side).to_owned());
+        let _ = vars.insert("order_type", order_type);
+        let _ = vars.insert("amount", header.amount.normalize().to_string());
+        if let Some(price) = price {
+            let _ = vars.insert("price", price.normalize().to_string());
+        }
+        if let Some(time_in_force) = time_in_force {
+            let _ = vars.insert("time_in_force", time_in_force);
+        }
+
+        Ok(vars)
+    }
+
+    fn side_to_value(&self, side: OrderSide) -> &str {
+        match side {
+            OrderSide::Buy => &self.config.values.side_buy,
+            OrderSide::Sell => &self.config.values.side_sell,
+        }
+    }
+
+    fn value_to_side(&self, value: &str) -> Result<OrderSide> {
+        let values = &self.config.values;
+        if value == values.side_buy {
+            Ok(OrderSide::Buy)
+        } else if value == values.side_sell {
+            Ok(OrderSide::Sell)
+        } else {
+            bail!("Unknown order side {value}")
+        }
+    }
+
+    #[named]
+    pub(super) async fn do_create_order(
+        &self,
+        order: &OrderRef,
+    ) -> Result<ExchangeOrderId, ExchangeError> {
+        let vars = self.create_order_vars(order)?;
+        let response = self
+            .send_request(
+                &self.config.create_order.request,
+                &vars,
+                function_name!(),
+                format!("Create order {}", order.client_order_id()),
+            )
+            .await?;
+
+        self.parse_create_order(&response).map_err(|err| {
+            ExchangeError::parsing(format!("Unable to parse created order: {err:?}"))
+        })
+    }
+
+    fn parse_create_order(&self, response: &RestResponse) -> Result<ExchangeOrderId> {
+        let content: Value = serde_json::from_str(&response.content)?;
+        let exchange_order_id = get_string(
+            &content,
+            &self.config.create_order.response.exchange_order_id,
+        )?;
+
+        Ok(exchange_order_id.as_str().into())
+    }
+
+    #[named]
+    pub(super) async fn do_cancel_order(
+        &self,
+        order: &OrderRef,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut vars = self.order_vars(order);
+        let _ = vars.insert("exchange_order_id", exchange_order_id.to_string());
+
+        self.send_request(
+            &self.config.cancel_order,
+            &vars,
+            function_name!(),
+            format!("Cancel order {}", order.client_order_id()),
+        )
+        .await
+    }
+
+    /// Open orders are cancelled one by one because mass cancellation isn't described
+    pub(super) async fn do_cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        let orders = self.get_open_orders_impl(Some(currency_pair)).await?;
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+
+        for order in orders {
+            let vars = hashmap![
+                "symbol" => specific_currency_pair.to_string(),
+                "client_order_id" => order.client_order_id.to_string(),
+                "exchange_order_id" => order.exchange_order_id.to_string()
+            ];
+            let _ = self
+                .send_request(
+                    &self.config.cancel_order,
+                    &vars,
+                    "do_cancel_all_orders",
+                    order.client_order_id.to_string(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Open orders aren't restored if request isn't described in config
+    pub(super) async fn get_open_orders_impl(
+        &self,
+        currency_pair: Option<CurrencyPair>,
+    ) -> Result<Vec<OrderInfo>> {
+        let endpoint = match &self.config.open_orders {
+            Some(endpoint) => endpoint,
+            None => {
+                log::warn!(
+                    "Request of open orders isn't described for {}",
+                    self.settings.exchange_account_id
+                );
+                return Ok(Vec::new());
+            }
+        };
+
+        let vars = currency_pair
+            .map(|x| self.symbol_vars(x))
+            .unwrap_or_default();
+        let response = self
+            .send_request(
+                &endpoint.request,
+                &vars,
+                "get_open_orders",
+                format!("{currency_pair:?}"),
+            )
+            .await?;
+
+        let content: Value = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for open orders request")?;
+        get_items(&content, &endpoint.items)?
+            .iter()
+            .map(|item| self.parse_order(item, &endpoint.response))
+            .try_collect()
+    }
+
+    #[named]
+    pub(super) async fn request_order_info(
+        &self,
+        order: &OrderRef,
+    ) -> Result<RestResponse, ExchangeError> {
+        self.send_request(
+            &self.config.order_info.request,
+            &self.order_vars(order),
+            function_name!(),
+            format!("order {}", order.client_order_id()),
+        )
+        .await
+    }
+
+    pub(super) fn parse_order_info(&self, response: &RestResponse) -> Result<OrderInfo> {
+        let content: Value = serde_json::from_str(&response.content)?;
+        let fields = &self.config.order_info.response;
+
+        self.parse_order(get_value(&content, &fields.item)?, fields)
+    }
+
+    /// Average fill price is taken from order price if exchange doesn't return it
+    fn parse_order(&self, item: &Value, fields: &OrderFields) -> Result<OrderInfo> {
+        let status = get_string(item, &fields.status)?;
+        let order_status = *self
+            .config
+            .values
+            .statuses
+            .get(&status)
+            .with_context(|| format!("Unknown order status {status}"))?;
+        let specific_currency_pair: SpecificCurrencyPair =
+            get_string(item, &fields.symbol)?.as_str().into();
+        let price = get_decimal(item, &fields.price)?;
+
+        Ok(OrderInfo::new(
+            self.get_unified_currency_pair(&specific_currency_pair)?,
+            get_string(item, &fields.exchange_order_id)?.as_str().into(),
+            get_string(item, &fields.client_order_id)?.as_str().into(),
+            self.value_to_side(&get_string(item, &fields.side)?)?,
+            order_status,
+            price,
+            get_decimal(item, &fields.amount)?,
+            get_optional_decimal(item, fields.average_fill_price.as_deref())?
+                .filter(|x| !x.is_zero())
+                .unwrap_or(price),
+            get_decimal(item, &fields.filled_amount)?,
+            None,
+            None,
+            None,
+        ))
+    }
+
+    #[named]
+    pub(super) async fn request_get_balance(&self) -> Result<Vec<ExchangeBalance>> {
+        let endpoint = match &self.config.balances {
+            Some(endpoint) => endpoint,
+            None => return Ok(Vec::new()),
+        };
+
+        let response = self
+            .send_request(
+                &endpoint.request,
+                &Vars::new(),
+                function_name!(),
+                "".to_string(),
+            )
+            .await?;
+
+        let content: Value = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for balances request")?;
+        let fields = &endpoint.response;
+        get_items(&content, &fields.items)?
+            .iter()
+            .map(|item| {
+                let currency = get_string(item, &fields.currency)?;
+                Ok(ExchangeBalance {
+                    currency_code: currency.as_str().into(),
+                    balance: get_decimal(item, &fields.balance)?,
+                })
+            })
+            .try_collect()
+    }
+
+    fn open_orders_type(&self) -> OpenOrdersType {
+        match &self.config.open_orders {
+            Some(endpoint) if contains_placeholder(&endpoint.request.path, "symbol") => {
+                OpenOrdersType::OneCurrencyPair
+            }
+            Some(_) => OpenOrdersType::AllCurrencyPair,
+            None => OpenOrdersType::None,
+        }
+    }
+
+    /// Order info can be requested by client order id if exchange order id isn't used in its request
+    fn supports_get_order_info_by_client_order_id(&self) -> bool {
+        let request = &self.config.order_info.request;
+        !contains_placeholder(&request.path, "exchange_order_id")
+            && !request
+                .params
+                .values()
+                .any(|x| contains_placeholder(x, "exchange_order_id"))
+    }
+}
+
+fn request_method(method: HttpMethod) -> RequestType {
+    match method {
+        HttpMethod::Get => RequestType::Get,
+        HttpMethod::Post => RequestType::Post,
+        HttpMethod::Put => RequestType::Put,
+        HttpMethod::Delete => RequestType::Delete,
+    }
+}
+
+/// Builder of client for exchange which is described by config
+pub struct GenericRestBuilder {
+    config: GenericRestConfig,
+}
+
+impl GenericRestBuilder {
+    pub fn new(config: GenericRestConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn load(config_path: &str) -> Result<Self> {
+        Ok(Self::new(GenericRestConfig::load(config_path)?))
+    }
+}
+
+impl ExchangeClientBuilder for GenericRestBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        _events_channel: broadcast::Sender<ExchangeEvent>,
+        _lifetime_manager: Arc<AppLifetimeManager>,
+        _timeout_manager: Arc<TimeoutManager>,
+        _orders: Arc<OrdersPool>,
+    ) -> ExchangeClientBuilderResult {
+        let client = GenericRest::new(exchange_settings, self.config.clone());
+        let features = ExchangeFeatures::new(
+            client.open_orders_type(),
+            RestFillsFeatures::new(RestFillsType::GetOrderInfo),
+            OrderFeatures {
+                maker_only: self.config.values.order_type_limit_maker.is_some(),
+                supports_get_order_info_by_client_order_id: client
+                    .supports_get_order_info_by_client_order_id(),
+                cancellation_response_from_rest_only_for_errors: false,
+                creation_response_from_rest_only_for_errors: false,
+                order_was_completed_error_for_cancellation: false,
+                supports_already_cancelled_order: false,
+                supports_stop_loss_order: false,
+            },
+            OrderTradeOption::default(),
+            WebSocketOptions::default(),
+            EMPTY_RESPONSE_IS_OK,
+            AllowedEventSourceType::default(),
+            AllowedEventSourceType::default(),
+            AllowedEventSourceType::default(),
+        );
+
+        ExchangeClientBuilderResult {
+            client: Box::new(client),
+            features,
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        RequestTimeoutArguments::from_requests_per_minute(self.config.requests_per_minute)
+    }
+
+    fn get_exchange_id(&self) -> ExchangeId {
+        self.config.exchange_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::market::ExchangeAccountId;
+    use mmb_domain::order::snapshot::OrderStatus;
+    use rust_decimal_macros::dec;
+
+    fn create_client() -> GenericRest {
+        let config = GenericRestConfig::parse(include_str!("../example.toml")).expect("in test");
+        let settings = ExchangeSettings::new_short(
+            ExchangeAccountId::new("Example", 0),
+            "key".to_owned(),
+            "secret".to_owned(),
+            false,
+        );
+        let client = GenericRest::new(settings, config);
+
+        let response = RestResponse::new(
+            r#"{"symbols":[{"symbol":"BTCUSDT","status":"TRADING","baseAsset":"BTC","quoteAsset":"USDT","filters":[{"filterType":"PRICE_FILTER","tickSize":"0.01000000"},{"filterType":"LOT_SIZE","minQty":"0.00001000","stepSize":"0.00001000"}]},{"symbol":"ETHBTC","status":"BREAK","baseAsset":"ETH","quoteAsset":"BTC","filters":[{"filterType":"PRICE_FILTER","tickSize":"0.00001"},{"filterType":"LOT_SIZE","minQty":"0.0001","stepSize":"0.0001"}]}]}"#.to_owned(),
+            hyper::StatusCode::OK,
+        );
+        let symbols = client.parse_all_symbols(&response).expect("in test");
+        assert_eq!(symbols.len(), 1);
+
+        client
+    }
+
+    #[test]
+    fn parse_symbols() {
+        let client = create_client();
+
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        assert_eq!(
+            client.get_specific_currency_pair(currency_pair).as_str(),
+            "BTCUSDT"
+        );
+    }
+
+    #[test]
+    fn sign_query_of_request() {
+        let client = create_client();
+        let vars = hashmap![
+            "symbol" => "BTCUSDT".to_owned(),
+            "client_order_id" => "123".to_owned()
+        ];
+
+        let request = client
+            .prepare_request(&client.config.order_info.request, &vars)
+            .expect("in test");
+
+        let query = request.uri.query().expect("in test");
+        let (payload, signature) = query.split_once("&signature=").expect("in test");
+        assert!(payload.starts_with("origClientOrderId=123&symbol=BTCUSDT&timestamp="));
+        assert_eq!(
+            signature,
+            create_signature("secret", payload, SignatureEncoding::Hex)
+        );
+        assert!(request.body.is_none());
+    }
+
+    #[test]
+    fn sign_form_body_of_request() {
+        let client = create_client();
+        let vars = hashmap![
+            "symbol" => "BTCUSDT".to_owned(),
+            "side" => "BUY".to_owned(),
+            "order_type" => "MARKET".to_owned(),
+            "amount" => "0.1".to_owned(),
+            "client_order_id" => "123".to_owned()
+        ];
+
+        let request = client
+            .prepare_request(&client.config.create_order.request, &vars)
+            .expect("in test");
+
+        assert_eq!(
+            request.uri.to_string(),
+            "https://api.example.com/api/v3/order"
+        );
+        let body = request.body.expect("in test");
+        let body = std::str::from_utf8(&body).expect("in test");
+        // parameters without values (price and time in force of market order) are skipped
+        assert!(body.starts_with(
+            "newClientOrderId=123&quantity=0.1&side=BUY&symbol=BTCUSDT&type=MARKET&timestamp="
+        ));
+        let (payload, signature) = body.split_once("&signature=").expect("in test");
+        assert_eq!(
+            signature,
+            create_signature("secret", payload, SignatureEncoding::Hex)
+        );
+    }
+
+    #[test]
+    fn parse_order() {
+        let client = create_client();
+        let response = RestResponse::new(
+            r#"{"symbol":"BTCUSDT","orderId":28,"clientOrderId":"123","price":"30000.00","origQty":"0.10000000","executedQty":"0.05000000","cummulativeQuoteQty":"1500.00","status":"PARTIALLY_FILLED","timeInForce":"GTC","type":"LIMIT","side":"SELL"}"#.to_owned(),
+            hyper::StatusCode::OK,
+        );
+
+        let order_info = client.parse_order_info(&response).expect("in test");
+
+        assert_eq!(order_info.exchange_order_id.as_str(), "28");
+        assert_eq!(order_info.client_order_id.as_str(), "123");
+        assert_eq!(order_info.order_side, OrderSide::Sell);
+        assert_eq!(order_info.order_status, OrderStatus::Created);
+        assert_eq!(order_info.filled_amount, dec!(0.05));
+        assert_eq!(order_info.average_fill_price, dec!(30000));
+    }
+
+    #[test]
+    fn detect_errors() {
+        let client = create_client();
+        let error_handler = ErrorHandlerGeneric {
+            config: client.config.errors.clone(),
+        };
+        let response = RestResponse::new(
+            r#"{"code":-2011,"msg":"Unknown order sent."}"#.to_owned(),
+            hyper::StatusCode::BAD_REQUEST,
+        );
+
+        let error = error_handler
+            .check_spec_rest_error(&response)
+            .expect_err("in test");
+
+        assert_eq!(error.code, Some(-2011));
+        assert_eq!(
+            error_handler.clarify_error_type(&error),
+            ExchangeErrorType::OrderNotFound
+        );
+
+        let response = RestResponse::new(r#"{"orderId":1}"#.to_owned(), hyper::StatusCode::OK);
+        assert!(error_handler.check_spec_rest_error(&response).is_ok());
+    }
+}
@@ -0,0 +1,120 @@
+use anyhow::{bail, Context, Result};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Values of placeholders of templates
+pub(crate) type Vars = HashMap<&'static str, String>;
+
+/// Substitutes placeholders `{name}` by values, returns `None` if value of any placeholder is absent
+pub(crate) fn render(template: &str, vars: &Vars) -> Option<String> {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        result.push_str(&rest[..start]);
+        result.push_str(vars.get(&rest[start + 1..end])?);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+
+    Some(result)
+}
+
+/// Checks whether template uses placeholder with specified name
+pub(crate) fn contains_placeholder(template: &str, name: &str) -> bool {
+    template.contains(&format!("{{{name}}}"))
+}
+
+pub(crate) fn get_value<'a>(value: &'a Value, pointer: &str) -> Result<&'a Value> {
+    match value.pointer(pointer) {
+        Some(Value::Null) | None => bail!("Field '{pointer}' not found"),
+        Some(value) => Ok(value),
+    }
+}
+
+pub(crate) fn get_items<'a>(value: &'a Value, pointer: &str) -> Result<&'a Vec<Value>> {
+    get_value(value, pointer)?
+        .as_array()
+        .with_context(|| format!("Field '{pointer}' isn't array"))
+}
+
+/// Exchanges send identifiers both as strings and numbers
+pub(crate) fn get_string(value: &Value, pointer: &str) -> Result<String> {
+    match get_value(value, pointer)? {
+        Value::String(value) => Ok(value.clone()),
+        Value::Number(value) => Ok(value.to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        value => bail!("Field '{pointer}' has unexpected value {value}"),
+    }
+}
+
+pub(crate) fn get_decimal(value: &Value, pointer: &str) -> Result<Decimal> {
+    let value = get_string(value, pointer)?;
+    Decimal::from_str(&value)
+        .or_else(|_| Decimal::from_scientific(&value))
+        .with_context(|| format!("Field '{pointer}' isn't decimal: {value}"))
+}
+
+pub(crate) fn get_optional_decimal(
+    value: &Value,
+    pointer: Option<&str>,
+) -> Result<Option<Decimal>> {
+    match pointer {
+        Some(pointer) if value.pointer(pointer).is_some_and(|x| !x.is_null()) => {
+            get_decimal(value, pointer).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_utils::hashmap;
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+
+    #[test]
+    fn render_template() {
+        let vars: Vars = hashmap!["symbol" => "BTCUSDT".to_owned(), "price" => "1.5".to_owned()];
+
+        assert_eq!(
+            render("/api/order/{symbol}", &vars),
+            Some("/api/order/BTCUSDT".to_owned())
+        );
+        assert_eq!(render("{price}", &vars), Some("1.5".to_owned()));
+        assert_eq!(
+            render("{symbol}:{price}", &vars),
+            Some("BTCUSDT:1.5".to_owned())
+        );
+        assert_eq!(render("{amount}", &vars), None);
+        assert_eq!(render("plain", &vars), Some("plain".to_owned()));
+    }
+
+    #[test]
+    fn extract_fields() {
+        let value = json!({"data": [{"id": 42, "price": "0.10", "qty": 1e-3, "empty": null}]});
+
+        assert_eq!(get_items(&value, "/data").expect("in test").len(), 1);
+        assert_eq!(get_string(&value, "/data/0/id").expect("in test"), "42");
+        assert_eq!(
+            get_decimal(&value, "/data/0/price").expect("in test"),
+            dec!(0.10)
+        );
+        assert_eq!(
+            get_decimal(&value, "/data/0/qty").expect("in test"),
+            dec!(0.001)
+        );
+        assert!(get_string(&value, "/data/0/empty").is_err());
+        assert_eq!(
+            get_optional_decimal(&value, Some("/data/0/missing")).expect("in test"),
+            None
+        );
+    }
+}
@@ -0,0 +1,731 @@
+use crate::types::{
+    KucoinAccount, KucoinBulletToken, KucoinCreateOrderRequest, KucoinCreateOrderResult,
+    KucoinError, KucoinFill, KucoinOrderInfo, KucoinPage, KucoinResponse, KucoinSymbol,
+};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use dashmap::DashMap;
+use function_name::named;
+use hmac::{Hmac, Mac};
+use hyper::body::Bytes;
+use hyper::header::CONTENT_TYPE;
+use hyper::http::request::Builder;
+use hyper::Uri;
+use itertools::Itertools;
+use mmb_core::connectivity::WebSocketRole;
+use mmb_core::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    RestFillsType, WebSocketOptions,
+};
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::hosts::Hosts;
+use mmb_core::exchanges::rest_client::{
+    ErrorHandler, ErrorHandlerData, RequestType, RestClient, RestHeaders, RestResponse, UriBuilder,
+};
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use mmb_core::exchanges::timeouts::timeout_manager::TimeoutManager;
+use mmb_core::exchanges::traits::{
+    ExchangeClientBuilder, ExchangeClientBuilderResult, ExchangeError, HandleMetricsCb,
+    HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb,
+    Support,
+};
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{AllowedEventSourceType, ExchangeBalance, ExchangeEvent, TradeId};
+use mmb_domain::exchanges::symbol::{Precision, Symbol};
+use mmb_domain::market::{
+    CurrencyCode, CurrencyId, CurrencyPair, ExchangeErrorType, ExchangeId, SpecificCurrencyPair,
+};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order::pool::{OrderRef, OrdersPool};
+use mmb_domain::order::snapshot::{
+    ExchangeOrderId, OrderExecutionType, OrderInfo, OrderOptions, OrderRole, OrderSide,
+    OrderStatus, UserOrder,
+};
+use mmb_utils::DateTime;
+use parking_lot::{Mutex, RwLock};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+const SUCCESS_CODE: &str = "200000";
+
+#[derive(Default)]
+pub struct ErrorHandlerKucoin;
+
+impl ErrorHandler for ErrorHandlerKucoin {
+    fn check_spec_rest_error(&self, response: &RestResponse) -> Result<(), ExchangeError> {
+        let error: KucoinError = serde_json::from_str(&response.content).map_err(|err| {
+            ExchangeError::parsing(format!(
+                "Unable to parse response.content: {err:?}\n{}",
+                response.content
+            ))
+        })?;
+
+        if error.code == SUCCESS_CODE {
+            return Ok(());
+        }
+
+        Err(ExchangeError::new(
+            ExchangeErrorType::Unknown,
+            error.msg,
+            error.code.parse().ok(),
+        ))
+    }
+
+    fn clarify_error_type(&self, error: &ExchangeError) -> ExchangeErrorType {
+        use ExchangeErrorType::*;
+        // According to https://docs.kucoin.com/#request
+        match error.code {
+            Some(429000) => RateLimit,
+            Some(400001..=400007) => Authentication,
+            Some(500000) => ServiceUnavailable,
+            Some(200004) => InsufficientFunds,
+            // parameter errors include requests for unknown orders
+            Some(400100) if error.message.contains("not_exist") => OrderNotFound,
+            Some(400100) if error.message.contains("not exist") => OrderNotFound,
+            Some(400100) | Some(300000) => InvalidOrder,
+            _ => Unknown,
+        }
+    }
+}
+
+pub struct RestHeadersKucoin {
+    api_key: String,
+    secret_key: String,
+    /// Passphrase signed by secret key as required by version 2 of API keys
+    signed_passphrase: String,
+}
+
+impl RestHeadersKucoin {
+    pub fn new(api_key: String, secret_key: String, api_passphrase: &str) -> Self {
+        let signed_passphrase = Kucoin::create_signature(&secret_key, &[api_passphrase.as_bytes()]);
+        Self {
+            api_key,
+            secret_key,
+            signed_passphrase,
+        }
+    }
+}
+
+impl RestHeaders for RestHeadersKucoin {
+    fn add_specific_headers(
+        &self,
+        builder: Builder,
+        uri: &Uri,
+        request_type: RequestType,
+    ) -> Builder {
+        self.add_specific_headers_with_body(builder, uri, request_type, &[])
+    }
+
+    fn add_specific_headers_with_body(
+        &self,
+        builder: Builder,
+        uri: &Uri,
+        request_type: RequestType,
+        body: &[u8],
+    ) -> Builder {
+        let builder = builder.header(CONTENT_TYPE, "application/json");
+        if self.api_key.is_empty() {
+            // public endpoints don't need authentication
+            return builder;
+        }
+
+        let path_and_query = match uri.path_and_query() {
+            Some(path_and_query) => path_and_query.as_str(),
+            None => uri.path(),
+        };
+        let timestamp = Utc::now().timestamp_millis().to_string();
+        let signature = Kucoin::create_signature(
+            &self.secret_key,
+            &[
+                timestamp.as_bytes(),
+                request_type.as_str().as_bytes(),
+                path_and_query.as_bytes(),
+                body,
+            ],
+        );
+
+        builder
+            .header("KC-API-KEY", &self.api_key)
+            .header("KC-API-SIGN", signature)
+            .header("KC-API-TIMESTAMP", timestamp)
+            .header("KC-API-PASSPHRASE", &self.signed_passphrase)
+            .header("KC-API-KEY-VERSION", "2")
+    }
+}
+
+const EMPTY_RESPONSE_IS_OK: bool = false;
+
+pub struct Kucoin {
+    pub(crate) settings: ExchangeSettings,
+    pub hosts: Hosts,
+    rest_client: RestClient<ErrorHandlerKucoin, RestHeadersKucoin>,
+    pub(crate) unified_to_specific: RwLock<HashMap<CurrencyPair, SpecificCurrencyPair>>,
+    pub(crate) specific_to_unified: RwLock<HashMap<SpecificCurrencyPair, CurrencyPair>>,
+    pub(crate) supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    // Currencies used for trading according to user settings
+    pub(super) traded_specific_currencies: Mutex<Vec<SpecificCurrencyPair>>,
+    pub(super) lifetime_manager: Arc<AppLifetimeManager>,
+    pub(super) events_channel: broadcast::Sender<ExchangeEvent>,
+    pub(crate) order_created_callback: OrderCreatedCb,
+    pub(crate) order_cancelled_callback: OrderCancelledCb,
+    pub(crate) handle_order_filled_callback: HandleOrderFilledCb,
+    pub(crate) handle_trade_callback: HandleTradeCb,
+    pub(super) handle_metrics_callback: HandleMetricsCb,
+    pub(crate) websocket_message_callback: SendWebsocketMessageCb,
+}
+
+impl Kucoin {
+    pub fn new(
+        settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+    ) -> Kucoin {
+        Self {
+            rest_client: RestClient::new(
+                ErrorHandlerData::new(
+                    EMPTY_RESPONSE_IS_OK,
+                    settings.exchange_account_id,
+                    ErrorHandlerKucoin,
+                ),
+                RestHeadersKucoin::new(
+                    settings.api_key.clone(),
+                    settings.secret_key.clone(),
+                    &settings.api_passphrase,
+                ),
+            ),
+            settings,
+            hosts: Self::make_hosts(),
+            unified_to_specific: Default::default(),
+            specific_to_unified: Default::default(),
+            supported_currencies: Default::default(),
+            traded_specific_currencies: Default::default(),
+            events_channel,
+            lifetime_manager,
+            order_created_callback: Box::new(|_, _, _| {}),
+            order_cancelled_callback: Box::new(|_, _, _| {}),
+            handle_order_filled_callback: Box::new(|_| {}),
+            handle_trade_callback: Box::new(|_, _| {}),
+            handle_metrics_callback: Box::new(|_| {}),
+            websocket_message_callback: Box::new(|_, _| Ok(())),
+        }
+    }
+
+    /// Websocket hosts are only defaults, actual endpoints are received with connection token
+    fn make_hosts() -> Hosts {
+        Hosts {
+            web_socket_host: "wss://ws-api-spot.kucoin.com",
+            web_socket2_host: "wss://ws-api-spot.kucoin.com",
+            rest_host: "https://api.kucoin.com",
+        }
+    }
+
+    pub(super) fn create_signature(secret_key: &str, parts: &[&[u8]]) -> String {
+        let mut hmac = Hmac::<Sha256>::new_from_slice(secret_key.as_bytes())
+            .expect("Unable to calculate hmac for KuCoin signature");
+        for part in parts {
+            hmac.update(part);
+        }
+
+        base64::encode(hmac.finalize().into_bytes())
+    }
+
+    /// Websocket connection requires token which is valid only for single connection,
+    /// so it's requested before every connection. Private channels require private token
+    #[named]
+    pub(super) async fn request_bullet_token(
+        &self,
+        role: WebSocketRole,
+    ) -> Result<RestResponse, ExchangeError> {
+        let path = match role {
+            WebSocketRole::Main => "/api/v1/bullet-public",
+            WebSocketRole::Secondary => "/api/v1/bullet-private",
+        };
+        let uri = UriBuilder::from_path(path).build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .post(uri, None, function_name!(), format!("{role:?}"))
+            .await
+    }
+
+    pub(super) fn parse_bullet_token(&self, response: &RestResponse) -> Result<String> {
+        let bullet: KucoinResponse<KucoinBulletToken> = serde_json::from_str(&response.content)
+            .context("Unable to parse KuCoin websocket token")?;
+
+        let server = bullet
+            .data
+            .instance_servers
+            .first()
+            .context("KuCoin didn't provide websocket server")?;
+
+        // Connection id is echoed in welcome message, it has to be unique only for the client
+        let connect_id = Utc::now().timestamp_nanos();
+        Ok(format!(
+            "{}?token={}&connectId={connect_id}",
+            server.endpoint, bullet.data.token
+        ))
+    }
+
+    #[named]
+    pub(super) async fn request_all_symbols(&self) -> Result<RestResponse, ExchangeError> {
+        let uri =
+            UriBuilder::from_path("/api/v2/symbols").build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_all_symbols(&self, response: &RestResponse) -> Result<Vec<Arc<Symbol>>> {
+        let symbols: KucoinResponse<Vec<KucoinSymbol>> = serde_json::from_str(&response.content)
+            .context("Unable to deserialize symbols from KuCoin")?;
+
+        Ok(symbols
+            .data
+            .into_iter()
+            .filter(|x| x.enable_trading)
+            .map(|x| {
+                let base: CurrencyCode = x.base_currency.as_str().into();
+                let quote: CurrencyCode = x.quote_currency.as_str().into();
+
+                let unified_currency_pair = CurrencyPair::from_codes(base, quote);
+                self.unified_to_specific
+                    .write()
+                    .insert(unified_currency_pair, x.symbol);
+                self.specific_to_unified
+                    .write()
+                    .insert(x.symbol, unified_currency_pair);
+                for (id, code) in [(&x.base_currency, base), (&x.quote_currency, quote)] {
+                    let _ = self.supported_currencies.insert(id.as_str().into(), code);
+                }
+
+                Arc::new(Symbol::new(
+                    false,
+                    x.base_currency.as_str().into(),
+                    base,
+                    x.quote_currency.as_str().into(),
+                    quote,
+                    None,
+                    None,
+                    Some(x.base_min_size),
+                    Some(x.base_max_size),
+                    Some(x.quote_min_size),
+                    base,
+                    None,
+                    Precision::ByTick {
+                        tick: x.price_increment,
+                    },
+                    Precision::ByTick {
+                        tick: x.base_increment,
+                    },
+                ))
+            })
+            .collect_vec())
+    }
+
+    pub(super) fn get_unified_currency_pair(
+        &self,
+        currency_pair: &SpecificCurrencyPair,
+    ) -> Result<CurrencyPair> {
+        self.specific_to_unified
+            .read()
+            .get(currency_pair)
+            .cloned()
+            .with_context(|| {
+                format!(
+                    "Not found currency pair '{currency_pair:?}' in {}",
+                    self.settings.exchange_account_id
+                )
+            })
+    }
+
+    pub(super) fn get_currency_code(&self, currency_id: &str) -> Result<CurrencyCode> {
+        self.supported_currencies
+            .get(&currency_id.into())
+            .map(|x| *x.value())
+            .ok_or_else(|| anyhow!("Unknown currency {currency_id} in KuCoin"))
+    }
+
+    #[named]
+    pub(super) async fn do_create_order(
+        &self,
+        order: &OrderRef,
+    ) -> Result<RestResponse, ExchangeError> {
+        let header = order.header();
+        let (order_type, price, post_only, time_in_force) = match header.options {
+            OrderOptions::User(UserOrder::Limit {
+                price,
+                execution_type,
+            }) => (
+                "limit",
+                Some(price),
+                execution_type == OrderExecutionType::MakerOnly,
+                (execution_type == OrderExecutionType::ImmediateOrCancel).then_some("IOC"),
+            ),
+            OrderOptions::User(UserOrder::Market) => ("market", None, false, None),
+            _ => return Err(ExchangeError::unknown("Unexpected order type")),
+        };
+
+        let request = KucoinCreateOrderRequest {
+            client_oid: header.client_order_id.as_str(),
+            side: get_server_order_side(header.side),
+            symbol: self.get_specific_currency_pair(header.currency_pair),
+            order_type,
+            price,
+            size: header.amount,
+            post_only,
+            time_in_force,
+        };
+        let body = serde_json::to_vec(&request).map_err(|err| {
+            ExchangeError::parsing(format!("Unable to serialize create order request: {err}"))
+        })?;
+
+        let uri =
+            UriBuilder::from_path("/api/v1/orders").build_uri(self.hosts.rest_uri_host(), false);
+        let log_args = format!("Create order for {header:?}");
+        self.rest_client
+            .post(uri, Some(Bytes::from(body)), function_name!(), log_args)
+            .await
+    }
+
+    pub(super) fn get_order_id(
+        &self,
+        response: &RestResponse,
+    ) -> Result<ExchangeOrderId, ExchangeError> {
+        let deserialized: KucoinResponse<KucoinCreateOrderResult> =
+            serde_json::from_str(&response.content).map_err(|err| {
+                ExchangeError::parsing(format!("Unable to parse orderId: {err:?}"))
+            })?;
+
+        Ok(deserialized.data.order_id)
+    }
+
+    #[named]
+    pub(super) async fn do_cancel_order(
+        &self,
+        order: &OrderRef,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> Result<RestResponse, ExchangeError> {
+        let path = format!("/api/v1/orders/{exchange_order_id}");
+        let uri = UriBuilder::from_path(&path).build_uri(self.hosts.rest_uri_host(), false);
+
+        let log_args = format!("Cancel order for {}", order.client_order_id());
+        self.rest_client
+            .delete(uri, function_name!(), log_args)
+            .await
+    }
+
+    #[named]
+    pub(super) async fn do_cancel_all_orders(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/api/v1/orders");
+        builder.add_kv("symbol", self.get_specific_currency_pair(currency_pair));
+        builder.add_kv("tradeType", "TRADE");
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+
+        self.rest_client
+            .delete(uri, function_name!(), format!("{currency_pair}"))
+            .await
+    }
+
+    #[named]
+    pub(super) async fn request_open_orders(
+        &self,
+        currency_pair: Option<CurrencyPair>,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/api/v1/orders");
+        builder.add_kv("status", "active");
+        builder.add_kv("tradeType", "TRADE");
+        // max size of page, so all open orders are received by single request in usual cases
+        builder.add_kv("pageSize", 500);
+        if let Some(pair) = currency_pair {
+            builder.add_kv("symbol", self.get_specific_currency_pair(pair));
+        }
+
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_open_orders(&self, response: &RestResponse) -> Result<Vec<OrderInfo>> {
+        let orders: KucoinResponse<KucoinPage<KucoinOrderInfo>> =
+            serde_json::from_str(&response.content)
+                .context("Unable to parse response content for get_open_orders request")?;
+
+        orders
+            .data
+            .items
+            .into_iter()
+            .map(|order| self.specific_order_info_to_unified(order))
+            .try_collect()
+    }
+
+    #[named]
+    pub(super) async fn request_order_info(
+        &self,
+        order: &OrderRef,
+    ) -> Result<RestResponse, ExchangeError> {
+        let client_order_id = order.client_order_id();
+        let path = format!("/api/v1/order/client-order/{client_order_id}");
+        let uri = UriBuilder::from_path(&path).build_uri(self.hosts.rest_uri_host(), false);
+
+        let log_args = format!("order {client_order_id}");
+        self.rest_client.get(uri, function_name!(), log_args).await
+    }
+
+    pub(super) fn parse_order_info(&self, response: &RestResponse) -> Result<OrderInfo> {
+        let order: KucoinResponse<KucoinOrderInfo> = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for get_order_info request")?;
+
+        self.specific_order_info_to_unified(order.data)
+    }
+
+    fn specific_order_info_to_unified(&self, specific: KucoinOrderInfo) -> Result<OrderInfo> {
+        let status = match (specific.is_active, specific.cancel_exist) {
+            (true, _) => OrderStatus::Created,
+            (false, true) => OrderStatus::Canceled,
+            (false, false) => OrderStatus::Completed,
+        };
+        let average_price = match specific.deal_size.is_zero() {
+            true => specific.price,
+            false => specific.deal_funds / specific.deal_size,
+        };
+        let commission_currency_code = match specific.fee_currency.is_empty() {
+            true => None,
+            false => Some(self.get_currency_code(&specific.fee_currency)?.to_string()),
+        };
+
+        Ok(OrderInfo::new(
+            self.get_unified_currency_pair(&specific.symbol)?,
+            specific.id,
+            // orders created without client order id can't be matched with local orders
+            specific.client_oid.unwrap_or_else(|| "".into()),
+            specific.side,
+            status,
+            specific.price,
+            specific.size,
+            average_price,
+            specific.deal_size,
+            commission_currency_code,
+            None,
+            Some(specific.fee),
+        ))
+    }
+
+    pub(super) fn get_order_role(liquidity: &str) -> Result<OrderRole> {
+        match liquidity {
+            "taker" => Ok(OrderRole::Taker),
+            "maker" => Ok(OrderRole::Maker),
+            _ => Err(anyhow!("Unknown KuCoin liquidity {liquidity}")),
+        }
+    }
+
+    #[named]
+    pub(super) async fn request_my_trades(
+        &self,
+        symbol: &Symbol,
+        last_date_time: Option<DateTime>,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/api/v1/fills");
+        builder.add_kv(
+            "symbol",
+            self.get_specific_currency_pair(symbol.currency_pair()),
+        );
+        builder.add_kv("tradeType", "TRADE");
+        builder.add_kv("pageSize", 500);
+        if let Some(date_time) = last_date_time {
+            builder.add_kv("startAt", date_time.timestamp_millis());
+        }
+
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_my_trades(&self, response: &RestResponse) -> Result<Vec<OrderTrade>> {
+        let fills: KucoinResponse<KucoinPage<KucoinFill>> =
+            serde_json::from_str(&response.content).context("Failed to parse trade data")?;
+
+        fills
+            .data
+            .items
+            .into_iter()
+            .map(|fill| {
+                Ok(OrderTrade {
+                    exchange_order_id: fill.order_id,
+                    trade_id: TradeId::String(fill.trade_id.into_boxed_str()),
+                    datetime: fill.created_at,
+                    price: fill.price,
+                    amount: fill.size,
+                    order_role: Kucoin::get_order_role(&fill.liquidity)?,
+                    fee_currency_code: self.get_currency_code(&fill.fee_currency)?,
+                    fee_rate: Some(fill.fee_rate),
+                    fee_amount: Some(fill.fee),
+                    fill_type: OrderFillType::UserTrade,
+                })
+            })
+            .try_collect()
+    }
+
+    #[named]
+    pub(super) async fn request_get_balance(&self) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/api/v1/accounts");
+        builder.add_kv("type", "trade");
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_get_balance(
+        &self,
+        response: &RestResponse,
+    ) -> Result<Vec<ExchangeBalance>> {
+        let accounts: KucoinResponse<Vec<KucoinAccount>> =
+            serde_json::from_str(&response.content).context("Failed to parse balance")?;
+
+        Ok(accounts
+            .data
+            .iter()
+            .filter_map(|account| {
+                self.get_currency_code(&account.currency)
+                    .ok()
+                    .map(|currency_code| ExchangeBalance {
+                        currency_code,
+                        balance: account.available,
+                    })
+            })
+            .collect_vec())
+    }
+
+    #[named]
+    pub(super) async fn request_get_server_time(&self) -> Result<RestResponse, ExchangeError> {
+        let uri =
+            UriBuilder::from_path("/api/v1/timestamp").build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_get_server_time(&self, response: &RestResponse) -> Result<i64> {
+        let time: KucoinResponse<i64> =
+            serde_json::from_str(&response.content).context("Failed to parse server time")?;
+
+        Ok(time.data)
+    }
+}
+
+pub(super) fn get_server_order_side(side: OrderSide) -> &'static str {
+    match side {
+        OrderSide::Buy => "buy",
+        OrderSide::Sell => "sell",
+    }
+}
+
+pub struct KucoinBuilder;
+
+impl ExchangeClientBuilder for KucoinBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+        _timeout_manager: Arc<TimeoutManager>,
+        _orders: Arc<OrdersPool>,
+    ) -> ExchangeClientBuilderResult {
+        ExchangeClientBuilderResult {
+            client: Box::new(Kucoin::new(
+                exchange_settings,
+                events_channel,
+                lifetime_manager,
+            )),
+            features: ExchangeFeatures::new(
+                OpenOrdersType::AllCurrencyPair,
+                RestFillsFeatures::new(RestFillsType::MyTrades),
+                OrderFeatures {
+                    maker_only: true,
+                    supports_get_order_info_by_client_order_id: true,
+                    cancellation_response_from_rest_only_for_errors: true,
+                    creation_response_from_rest_only_for_errors: true,
+                    order_was_completed_error_for_cancellation: false,
+                    supports_already_cancelled_order: false,
+                    supports_stop_loss_order: false,
+                },
+                OrderTradeOption {
+                    supports_trade_time: true,
+                    supports_trade_incremented_id: false,
+                    supports_get_prints: true,
+                    supports_tick_direction: false,
+                    supports_my_trades_from_time: true,
+                },
+                WebSocketOptions {
+                    execution_notification: true,
+                    cancellation_notification: true,
+                    supports_ping_pong: true,
+                    supports_subscription_response: true,
+                },
+                EMPTY_RESPONSE_IS_OK,
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+            ),
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        // order placement allows 45 requests per 3 seconds
+        RequestTimeoutArguments::from_requests_per_minute(900)
+    }
+
+    fn get_exchange_id(&self) -> ExchangeId {
+        "KuCoin".into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_signature() {
+        let signature = Kucoin::create_signature(
+            "f03a5284-5c39-4aaa-9b20-dea10bdcf8e3",
+            &[
+                b"1547015186532",
+                b"POST",
+                b"/api/v1/orders",
+                br#"{"clientOid":"1","side":"buy","symbol":"BTC-USDT","type":"limit","price":"1","size":"1"}"#,
+            ],
+        );
+
+        assert_eq!(signature, "rMNHbGpaEM0lE4T2GJ2em1exc+0NoDcmeYCRwg1OFzI=");
+    }
+
+    #[test]
+    fn order_not_found_error() {
+        let response = RestResponse {
+            status: hyper::StatusCode::BAD_REQUEST,
+            content: r#"{"code":"400100","msg":"order_not_exist_or_not_allow_to_cancel"}"#
+                .to_owned(),
+        };
+
+        let error = ErrorHandlerKucoin
+            .check_spec_rest_error(&response)
+            .expect_err("in test");
+
+        assert_eq!(error.code, Some(400100));
+        assert_eq!(
+            ErrorHandlerKucoin.clarify_error_type(&error),
+            ExchangeErrorType::OrderNotFound
+        );
+    }
+}
@@ -0,0 +1,410 @@
+use crate::kucoin::Kucoin;
+use crate::types::{KucoinOrderBook, KucoinOrderChange, KucoinTrade};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_core::connectivity::WebSocketRole;
+use mmb_core::exchanges::common::send_event;
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::exchanges::general::handlers::handle_order_filled::{
+    FillAmount, FillEvent, SpecialOrderData,
+};
+use mmb_core::exchanges::traits::{
+    HandleMetricsCb, HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb,
+    SendWebsocketMessageCb, Support,
+};
+use mmb_core::infrastructure::spawn_by_timer;
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{EventSourceType, ExchangeEvent, Trade, TradeId};
+use mmb_domain::market::{CurrencyCode, CurrencyId, CurrencyPair, SpecificCurrencyPair};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order_book::event::{EventType, OrderBookEvent};
+use mmb_domain::order_book::order_book_data::OrderBookData;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::any::Any;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// KuCoin closes connection if there are no messages from client during ping interval
+/// received with connection token (18 seconds at the moment)
+const PING_PERIOD: Duration = Duration::from_secs(15);
+
+#[async_trait]
+impl Support for Kucoin {
+    fn as_any(&self) -> &(dyn Any + Send + Sync + 'static) {
+        self
+    }
+
+    async fn initialized(&self, exchange: Arc<Exchange>) {
+        start_pinging(&exchange);
+    }
+
+    fn on_websocket_message(&self, msg: &str) -> Result<()> {
+        let message: WebsocketMessage = serde_json::from_str(msg)
+            .with_context(|| format!("Unable to parse websocket message:\n{msg}"))?;
+
+        match message.message_type.as_str() {
+            "message" => {
+                let topic = message.topic.context("Message doesn't contain topic")?;
+                let data = message.data.context("Message doesn't contain data")?;
+                self.handle_topic_message(&topic, data)
+            }
+            "welcome" | "pong" | "ack" => Ok(()),
+            "error" => {
+                let err = format!("KuCoin websocket: error received: {msg}");
+                log::error!("{err}");
+                bail!(err)
+            }
+            message_type => {
+                log::warn!("Unsupported KuCoin websocket message type {message_type}: {msg}");
+                Ok(())
+            }
+        }
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_connected(&self) -> Result<()> {
+        // Single subscription accepts up to 100 symbols separated by commas
+        let traded_currencies = self.traded_specific_currencies.lock().clone();
+        for chunk in traded_currencies.chunks(100) {
+            let symbols = chunk.iter().join(",");
+            for topic in ["/spotMarket/level2Depth5", "/market/match"] {
+                self.subscribe(WebSocketRole::Main, format!("{topic}:{symbols}"), false)?;
+            }
+        }
+
+        if !self.is_websocket_enabled(WebSocketRole::Secondary) {
+            return Ok(());
+        }
+
+        // Connection of secondary socket is authorized by private token
+        self.subscribe(
+            WebSocketRole::Secondary,
+            "/spotMarket/tradeOrdersV2".to_owned(),
+            true,
+        )
+    }
+
+    fn on_disconnected(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_send_websocket_message_callback(&mut self, callback: SendWebsocketMessageCb) {
+        self.websocket_message_callback = callback;
+    }
+
+    fn set_order_created_callback(&mut self, callback: OrderCreatedCb) {
+        self.order_created_callback = callback;
+    }
+
+    fn set_order_cancelled_callback(&mut self, callback: OrderCancelledCb) {
+        self.order_cancelled_callback = callback;
+    }
+
+    fn set_handle_order_filled_callback(&mut self, callback: HandleOrderFilledCb) {
+        self.handle_order_filled_callback = callback;
+    }
+
+    fn set_handle_trade_callback(&mut self, callback: HandleTradeCb) {
+        self.handle_trade_callback = callback;
+    }
+
+    fn set_handle_metrics_callback(&mut self, callback: HandleMetricsCb) {
+        self.handle_metrics_callback = callback;
+    }
+
+    fn set_traded_specific_currencies(&self, currencies: Vec<SpecificCurrencyPair>) {
+        *self.traded_specific_currencies.lock() = currencies;
+    }
+
+    /// Main connection uses public token for market data and secondary one uses private token
+    /// for order flow
+    fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
+        match role {
+            WebSocketRole::Main => true,
+            WebSocketRole::Secondary => {
+                !self.settings.is_watch_only
+                    && !self.settings.api_key.is_empty()
+                    && !self.settings.secret_key.is_empty()
+            }
+        }
+    }
+
+    async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url> {
+        let response = self.request_bullet_token(role).await?;
+        let url = self.parse_bullet_token(&response)?;
+
+        Url::parse(&url).with_context(|| format!("Unable parse websocket {role:?} uri"))
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        self.unified_to_specific.read()[&currency_pair]
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        &self.supported_currencies
+    }
+
+    fn should_log_message(&self, message: &str) -> bool {
+        message.contains("orderChange")
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        &self.settings
+    }
+}
+
+impl Kucoin {
+    fn subscribe(&self, role: WebSocketRole, topic: String, private_channel: bool) -> Result<()> {
+        let request = Request {
+            id: Utc::now().timestamp_millis().to_string(),
+            request_type: "subscribe",
+            topic: Some(topic),
+            private_channel,
+            response: true,
+        };
+        let request =
+            serde_json::to_string(&request).expect("Failed to serialize KuCoin subscription");
+
+        (self.websocket_message_callback)(role, request)
+    }
+
+    fn send_ping(&self) {
+        let request = Request {
+            id: Utc::now().timestamp_millis().to_string(),
+            request_type: "ping",
+            topic: None,
+            private_channel: false,
+            response: false,
+        };
+        let request = serde_json::to_string(&request).expect("Failed to serialize KuCoin ping");
+
+        for role in [WebSocketRole::Main, WebSocketRole::Secondary] {
+            if !self.is_websocket_enabled(role) {
+                continue;
+            }
+
+            // socket may be reconnecting at the moment, so ping is just skipped
+            if let Err(err) = (self.websocket_message_callback)(role, request.clone()) {
+                log::trace!("Unable to send ping to KuCoin {role:?} websocket: {err:?}");
+            }
+        }
+    }
+
+    fn handle_topic_message(&self, topic: &str, data: Value) -> Result<()> {
+        let (channel, symbol) = match topic.split_once(':') {
+            Some((channel, symbol)) => (channel, Some(symbol)),
+            None => (topic, None),
+        };
+
+        match channel {
+            "/spotMarket/level2Depth5" => {
+                let symbol = symbol.context("Order book topic doesn't contain symbol")?;
+                self.handle_order_book(symbol.into(), data)
+            }
+            "/market/match" => self.handle_trade(data),
+            "/spotMarket/tradeOrdersV2" => {
+                let change: KucoinOrderChange =
+                    serde_json::from_value(data).context("Unable to parse KuCoin order change")?;
+                self.handle_order_change(change)
+            }
+            _ => {
+                log::warn!("Unsupported KuCoin websocket topic {topic}");
+                Ok(())
+            }
+        }
+    }
+
+    /// `level2Depth5` topic pushes full snapshot of top 5 levels
+    fn handle_order_book(&self, symbol: SpecificCurrencyPair, data: Value) -> Result<()> {
+        let order_book: KucoinOrderBook =
+            serde_json::from_value(data).context("Unable to parse KuCoin order book")?;
+
+        let order_book_event = OrderBookEvent::new(
+            Utc::now(),
+            self.settings.exchange_account_id,
+            self.get_unified_currency_pair(&symbol)?,
+            String::default(),
+            EventType::Snapshot,
+            Arc::new(OrderBookData::new(
+                order_book.asks.into_iter().collect(),
+                order_book.bids.into_iter().collect(),
+            )),
+        );
+
+        send_event(
+            &self.events_channel,
+            self.lifetime_manager.clone(),
+            self.settings.exchange_account_id,
+            ExchangeEvent::OrderBookEvent(order_book_event),
+        )
+    }
+
+    fn handle_trade(&self, data: Value) -> Result<()> {
+        let trade: KucoinTrade =
+            serde_json::from_value(data).context("Unable to parse KuCoin trade")?;
+
+        (self.handle_trade_callback)(
+            self.get_unified_currency_pair(&trade.symbol)?,
+            Trade {
+                trade_id: TradeId::String(trade.trade_id.into_boxed_str()),
+                price: trade.price,
+                quantity: trade.size,
+                side: trade.side,
+                transaction_time: trade.time,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub(super) fn handle_order_change(&self, change: KucoinOrderChange) -> Result<()> {
+        if change.change_type == "match" {
+            let fill_event = FillEvent {
+                source_type: EventSourceType::WebSocket,
+                trade_id: change.trade_id.map(|x| TradeId::String(x.into_boxed_str())),
+                // order without echoed client order id is searched by exchange order id
+                client_order_id: change.client_oid,
+                exchange_order_id: change.order_id,
+                fill_price: change.match_price.context("Match doesn't contain price")?,
+                fill_amount: FillAmount::Incremental {
+                    fill_amount: change.match_size.context("Match doesn't contain size")?,
+                    total_filled_amount: change.filled_size,
+                },
+                order_role: change
+                    .liquidity
+                    .as_deref()
+                    .map(Kucoin::get_order_role)
+                    .transpose()?,
+                commission_currency_code: None,
+                commission_rate: None,
+                commission_amount: None,
+                fill_type: OrderFillType::UserTrade,
+                special_order_data: Some(SpecialOrderData {
+                    currency_pair: self.get_unified_currency_pair(&change.symbol)?,
+                    order_side: change.side,
+                    order_amount: change.size.unwrap_or_default(),
+                }),
+                fill_date: Some(change.ts),
+            };
+
+            (self.handle_order_filled_callback)(fill_event);
+            return Ok(());
+        }
+
+        // Orders created outside of the engine can't be matched with local orders by client order id
+        let client_order_id = match change.client_oid {
+            Some(client_order_id) => client_order_id,
+            None => {
+                log::trace!("Skipped KuCoin order change without clientOid {change:?}");
+                return Ok(());
+            }
+        };
+
+        match change.change_type.as_str() {
+            "received" => (self.order_created_callback)(
+                client_order_id,
+                change.order_id,
+                EventSourceType::WebSocket,
+            ),
+            "canceled" => (self.order_cancelled_callback)(
+                client_order_id,
+                change.order_id,
+                EventSourceType::WebSocket,
+            ),
+            // order state is completed by fills, other changes don't affect it
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+fn start_pinging(exchange: &Arc<Exchange>) {
+    let exchange_wk = Arc::downgrade(exchange);
+    spawn_by_timer(
+        "KuCoin websocket ping",
+        PING_PERIOD,
+        PING_PERIOD,
+        SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+        move || {
+            let exchange_wk = exchange_wk.clone();
+            async move {
+                let exchange = match exchange_wk.upgrade() {
+                    None => return,
+                    Some(v) => v,
+                };
+
+                exchange
+                    .exchange_client
+                    .as_any()
+                    .downcast_ref::<Kucoin>()
+                    .expect("received non KuCoin exchange client in method of websocket pinging")
+                    .send_ping();
+            }
+        },
+    );
+}
+
+#[derive(Deserialize, Debug)]
+struct WebsocketMessage {
+    #[serde(rename = "type")]
+    message_type: String,
+    topic: Option<String>,
+    data: Option<Value>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    id: String,
+    #[serde(rename = "type")]
+    request_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    topic: Option<String>,
+    private_channel: bool,
+    response: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::KucoinOrderChange;
+    use mmb_domain::order::snapshot::{ClientOrderId, OrderSide};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parse_match_without_client_oid() {
+        let data = r#"{"symbol":"KCS-USDT","orderType":"limit","side":"sell","orderId":"5efab07953bdea00089965fa","liquidity":"taker","type":"match","orderTime":1593487481683297666,"size":"0.1","filledSize":"0.1","price":"0.938","matchPrice":"0.96738","matchSize":"0.1","tradeId":"5efab07a4ee4c7000a82d6d9","clientOid":"","remainSize":"0","status":"match","ts":1593487482038606180}"#;
+
+        let change: KucoinOrderChange = serde_json::from_str(data).expect("in test");
+
+        assert_eq!(change.change_type, "match");
+        assert_eq!(change.side, OrderSide::Sell);
+        assert_eq!(change.client_oid, None);
+        assert_eq!(change.match_price, Some(dec!(0.96738)));
+        assert_eq!(change.filled_size, Some(dec!(0.1)));
+        assert_eq!(change.ts.timestamp_millis(), 1593487482038);
+    }
+
+    #[test]
+    fn parse_received_with_client_oid() {
+        let data = r#"{"symbol":"KCS-USDT","orderType":"limit","side":"buy","orderId":"5efab07953bdea00089965d2","type":"received","orderTime":1593487481683297666,"price":"0.937","clientOid":"1593487481000906","status":"new","ts":1593487481683297666}"#;
+
+        let change: KucoinOrderChange = serde_json::from_str(data).expect("in test");
+
+        assert_eq!(change.change_type, "received");
+        assert_eq!(
+            change.client_oid,
+            Some(ClientOrderId::from("1593487481000906"))
+        );
+        assert_eq!(change.size, None);
+    }
+}
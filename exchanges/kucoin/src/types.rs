@@ -0,0 +1,226 @@
+use mmb_domain::market::SpecificCurrencyPair;
+use mmb_domain::order::snapshot::{Amount, ClientOrderId, ExchangeOrderId, OrderSide, Price};
+use mmb_utils::time::u64_to_date_time;
+use mmb_utils::DateTime;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+
+/// Common envelope of KuCoin REST responses
+/// {
+/// "code": "200000", // "200000" for success, otherwise error code
+/// "msg": "",        // error message, only for failed requests
+/// "data": {}        // payload, only for succeeded requests
+/// }
+#[derive(Deserialize, Debug)]
+pub struct KucoinResponse<T> {
+    pub code: String,
+    pub data: T,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct KucoinError {
+    pub code: String,
+    #[serde(default)]
+    pub msg: String,
+}
+
+/// Paginated payload of list requests
+#[derive(Deserialize, Debug)]
+pub struct KucoinPage<T> {
+    pub items: Vec<T>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KucoinSymbol {
+    pub symbol: SpecificCurrencyPair,
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub base_min_size: Amount,
+    pub base_max_size: Amount,
+    pub quote_min_size: Amount,
+    pub base_increment: Amount,
+    pub price_increment: Price,
+    pub enable_trading: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KucoinCreateOrderResult {
+    pub order_id: ExchangeOrderId,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KucoinOrderInfo {
+    pub id: ExchangeOrderId,
+    pub symbol: SpecificCurrencyPair,
+    #[serde(deserialize_with = "order_side")]
+    pub side: OrderSide,
+    pub price: Price,
+    pub size: Amount,
+    pub deal_funds: Amount,
+    pub deal_size: Amount,
+    pub fee: Amount,
+    pub fee_currency: String,
+    pub is_active: bool,
+    pub cancel_exist: bool,
+    /// Empty for orders which were created without client order id, e.g. from web interface
+    #[serde(default, deserialize_with = "optional_client_order_id")]
+    pub client_oid: Option<ClientOrderId>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KucoinFill {
+    pub trade_id: String,
+    pub order_id: ExchangeOrderId,
+    pub price: Price,
+    pub size: Amount,
+    pub fee: Amount,
+    pub fee_rate: Amount,
+    pub fee_currency: String,
+    /// `taker` or `maker`
+    pub liquidity: String,
+    #[serde(deserialize_with = "timestamp_millis")]
+    pub created_at: DateTime,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KucoinAccount {
+    pub currency: String,
+    pub available: Amount,
+}
+
+/// Token and servers for websocket connection which are requested before every connection
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KucoinBulletToken {
+    pub token: String,
+    pub instance_servers: Vec<KucoinInstanceServer>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KucoinInstanceServer {
+    pub endpoint: String,
+    /// Interval of pings expected by server in milliseconds
+    pub ping_interval: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct KucoinOrderBook {
+    pub asks: Vec<(Price, Amount)>,
+    pub bids: Vec<(Price, Amount)>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KucoinTrade {
+    pub symbol: SpecificCurrencyPair,
+    pub trade_id: String,
+    pub price: Price,
+    pub size: Amount,
+    #[serde(deserialize_with = "order_side")]
+    pub side: OrderSide,
+    #[serde(deserialize_with = "timestamp_nanos")]
+    pub time: DateTime,
+}
+
+/// Item of `/spotMarket/tradeOrdersV2` private topic. Fill fields are set only for `match` type
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KucoinOrderChange {
+    pub symbol: SpecificCurrencyPair,
+    pub order_id: ExchangeOrderId,
+    /// Echo of client order id, empty for orders which were created without it
+    #[serde(default, deserialize_with = "optional_client_order_id")]
+    pub client_oid: Option<ClientOrderId>,
+    #[serde(deserialize_with = "order_side")]
+    pub side: OrderSide,
+    /// `received`, `open`, `match`, `filled`, `canceled` or `update`
+    #[serde(rename = "type")]
+    pub change_type: String,
+    #[serde(default)]
+    pub size: Option<Amount>,
+    #[serde(default)]
+    pub filled_size: Option<Amount>,
+    #[serde(default)]
+    pub trade_id: Option<String>,
+    #[serde(default)]
+    pub match_price: Option<Price>,
+    #[serde(default)]
+    pub match_size: Option<Amount>,
+    #[serde(default)]
+    pub liquidity: Option<String>,
+    #[serde(deserialize_with = "timestamp_nanos")]
+    pub ts: DateTime,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KucoinCreateOrderRequest<'a> {
+    pub client_oid: &'a str,
+    pub side: &'a str,
+    pub symbol: SpecificCurrencyPair,
+    #[serde(rename = "type")]
+    pub order_type: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Price>,
+    pub size: Amount,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub post_only: bool,
+    #[serde(rename = "timeInForce", skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<&'a str>,
+}
+
+fn timestamp_millis<'de, D>(deserializer: D) -> Result<DateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(u64_to_date_time(u64::deserialize(deserializer)?))
+}
+
+/// Time of websocket events is sent as string with unix time in nanoseconds
+fn timestamp_nanos<'de, D>(deserializer: D) -> Result<DateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Nanos<'a> {
+        Number(u64),
+        String(Cow<'a, str>),
+    }
+
+    let nanos = match Nanos::deserialize(deserializer)? {
+        Nanos::Number(nanos) => nanos,
+        Nanos::String(nanos) => nanos.parse().map_err(de::Error::custom)?,
+    };
+
+    Ok(u64_to_date_time(nanos / 1_000_000))
+}
+
+/// KuCoin echoes client order id as empty string or null if order was created without it
+fn optional_client_order_id<'de, D>(deserializer: D) -> Result<Option<ClientOrderId>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<Cow<str>>::deserialize(deserializer)?;
+    Ok(value
+        .filter(|x| !x.is_empty())
+        .map(|x| ClientOrderId::from(x.as_ref())))
+}
+
+fn order_side<'de, D>(deserializer: D) -> Result<OrderSide, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Cow::<str>::deserialize(deserializer)?;
+    match value.as_ref() {
+        "buy" => Ok(OrderSide::Buy),
+        "sell" => Ok(OrderSide::Sell),
+        _ => Err(de::Error::custom(format!("unknown order side {value}"))),
+    }
+}
@@ -0,0 +1,205 @@
+use crate::okx::{get_oco_legs, AlgoOrderLegs, Okx};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use mmb_core::exchanges::general::exchange::RequestResult;
+use mmb_core::exchanges::general::order::cancel::CancelOrderResult;
+use mmb_core::exchanges::general::order::create::CreateOrderResult;
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::general::order::group::CreateOrderGroupResult;
+use mmb_core::exchanges::traits::{ExchangeClient, ExchangeError};
+use mmb_domain::events::{EventSourceType, ExchangeBalancesAndPositions};
+use mmb_domain::exchanges::symbol::Symbol;
+use mmb_domain::market::CurrencyPair;
+use mmb_domain::order::group::{OrderGroupId, OrderGroupType};
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{ExchangeOrderId, OrderHeader, OrderInfo, Price};
+use mmb_domain::position::{ActivePosition, ClosedPosition};
+use mmb_utils::DateTime;
+use std::sync::Arc;
+
+/// Max count of orders which can be canceled by single batch request
+const CANCEL_BATCH_SIZE: usize = 20;
+
+#[async_trait]
+impl ExchangeClient for Okx {
+    async fn create_order(&self, order: &OrderRef) -> CreateOrderResult {
+        match self.do_create_order(order).await {
+            Ok(request_outcome) => match self.get_order_id(&request_outcome) {
+                Ok(order_id) => CreateOrderResult::succeed(&order_id, EventSourceType::Rest),
+                Err(error) => CreateOrderResult::failed(error, EventSourceType::Rest),
+            },
+            Err(err) => CreateOrderResult::failed(err, EventSourceType::Rest),
+        }
+    }
+
+    async fn cancel_order(
+        &self,
+        order: &OrderRef,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> CancelOrderResult {
+        match self.do_cancel_order(order, exchange_order_id).await {
+            Ok(_) => {
+                CancelOrderResult::succeed(order.client_order_id(), EventSourceType::Rest, None)
+            }
+            Err(err) => CancelOrderResult::failed(err, EventSourceType::Rest),
+        }
+    }
+
+    /// OKX doesn't have request for cancellation of all orders, so open orders
+    /// are requested and canceled by batches
+    async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        let response = self.request_open_orders(Some(currency_pair)).await?;
+        let orders = self.parse_open_orders(&response)?;
+
+        for batch in orders.chunks(CANCEL_BATCH_SIZE) {
+            if let Err(error) = self.do_cancel_orders_batch(batch).await {
+                bail!("Failed to cancel all orders: {error:?}")
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
+        let response = self.request_open_orders(None).await?;
+
+        self.parse_open_orders(&response)
+    }
+
+    async fn get_open_orders_by_currency_pair(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<Vec<OrderInfo>> {
+        let response = self.request_open_orders(Some(currency_pair)).await?;
+
+        self.parse_open_orders(&response)
+    }
+
+    async fn get_order_info(&self, order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
+        match self.request_order_info(order).await {
+            Ok(request_outcome) => self.parse_order_info(&request_outcome).map_err(|err| {
+                ExchangeError::parsing(format!("Unable to parse order info: {err:?}"))
+            }),
+            Err(error) => Err(ExchangeError::unknown(
+                format!("Failed to get order info: {error:?}").as_str(),
+            )),
+        }
+    }
+
+    async fn close_position(
+        &self,
+        position: &ActivePosition,
+        price: Option<Price>,
+    ) -> Result<ClosedPosition> {
+        let response = self.request_close_position(position, price).await?;
+        let exchange_order_id = self.get_order_id(&response)?;
+
+        Ok(ClosedPosition::new(
+            exchange_order_id,
+            position.derivative.position.abs(),
+        ))
+    }
+
+    async fn get_active_positions(&self) -> Result<Vec<ActivePosition>> {
+        let response = self.request_get_position().await?;
+
+        self.parse_active_positions(&response)
+    }
+
+    async fn get_balance_and_positions(&self) -> Result<ExchangeBalancesAndPositions> {
+        Ok(match self.settings.is_margin_trading {
+            true => {
+                let (balance_response, position_response) =
+                    tokio::join!(self.request_get_balance(), self.request_get_position());
+                ExchangeBalancesAndPositions {
+                    balances: self.parse_get_balance(&balance_response?)?,
+                    positions: Some(
+                        self.parse_active_positions(&position_response?)?
+                            .into_iter()
+                            .map(|position| position.derivative)
+                            .collect(),
+                    ),
+                }
+            }
+            false => {
+                let balance_response = self.request_get_balance().await?;
+                ExchangeBalancesAndPositions {
+                    balances: self.parse_get_balance(&balance_response)?,
+                    positions: None,
+                }
+            }
+        })
+    }
+
+    async fn get_my_trades(
+        &self,
+        symbol: &Symbol,
+        last_date_time: Option<DateTime>,
+    ) -> RequestResult<Vec<OrderTrade>> {
+        match self.request_my_trades(symbol, last_date_time).await {
+            Ok(response) => match self.parse_my_trades(&response) {
+                Ok(data) => RequestResult::Success(data),
+                Err(err) => RequestResult::Error(ExchangeError::parsing(format!(
+                    "Unable to parse trades: {err:?}"
+                ))),
+            },
+            Err(err) => RequestResult::Error(ExchangeError::unknown(
+                format!("Failed to get trades: {err:?}").as_str(),
+            )),
+        }
+    }
+
+    async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>> {
+        let response = self.request_all_symbols().await?;
+
+        self.parse_all_symbols(&response)
+    }
+
+    async fn get_server_time(&self) -> Option<Result<i64>> {
+        Some(match self.request_get_server_time().await {
+            Ok(response) => self.parse_get_server_time(&response),
+            Err(err) => Err(err.into()),
+        })
+    }
+
+    fn supports_native_order_group(
+        &self,
+        group_type: OrderGroupType,
+        legs: &[OrderHeader],
+    ) -> bool {
+        match (group_type, legs) {
+            (OrderGroupType::Oco, [first, second]) => get_oco_legs(first, second).is_some(),
+            _ => false,
+        }
+    }
+
+    async fn create_order_group(
+        &self,
+        group_id: OrderGroupId,
+        group_type: OrderGroupType,
+        legs: &[OrderRef],
+    ) -> Result<CreateOrderGroupResult, ExchangeError> {
+        let oco_legs = match (group_type, legs) {
+            (OrderGroupType::Oco, [first, second]) => get_oco_legs(first.header(), second.header()),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            ExchangeError::unknown(&format!(
+                "Legs of order group {group_id} can't be created as OKX OCO algo order"
+            ))
+        })?;
+
+        let response = self.request_create_oco_order(group_id, &oco_legs).await?;
+        let result = Okx::parse_oco_order(&response, &oco_legs)?;
+
+        let _ = self.algo_order_legs.insert(
+            result.exchange_group_id.as_str().to_owned(),
+            AlgoOrderLegs {
+                take_profit: oco_legs.limit.client_order_id.clone(),
+                stop_loss: oco_legs.stop_loss.client_order_id.clone(),
+            },
+        );
+
+        Ok(result)
+    }
+}
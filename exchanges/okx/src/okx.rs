@@ -0,0 +1,1156 @@
+use crate::types::{
+    OkxAlgoOperationResult, OkxBalanceInfo, OkxCancelAlgoRequest, OkxCancelOrderRequest,
+    OkxCreateOcoOrderRequest, OkxCreateOrderRequest, OkxFill, OkxInstrument, OkxOrderInfo,
+    OkxOrderOperationResult, OkxPosition, OkxResponse, OkxServerTime,
+};
+use anyhow::{anyhow, Context, Result};
+use chrono::{SecondsFormat, Utc};
+use dashmap::DashMap;
+use function_name::named;
+use hmac::{Hmac, Mac};
+use hyper::body::Bytes;
+use hyper::header::CONTENT_TYPE;
+use hyper::http::request::Builder;
+use hyper::Uri;
+use itertools::Itertools;
+use mmb_core::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    RestFillsType, WebSocketOptions,
+};
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::general::order::group::CreateOrderGroupResult;
+use mmb_core::exchanges::hosts::Hosts;
+use mmb_core::exchanges::rest_client::{
+    ErrorHandler, ErrorHandlerData, RequestType, RestClient, RestHeaders, RestResponse, UriBuilder,
+};
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use mmb_core::exchanges::timeouts::timeout_manager::TimeoutManager;
+use mmb_core::exchanges::traits::{
+    ExchangeClientBuilder, ExchangeClientBuilderResult, ExchangeError, HandleMetricsCb,
+    HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb,
+    Support,
+};
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::settings::{ExchangeSettings, MarginType};
+use mmb_domain::events::{AllowedEventSourceType, ExchangeBalance, ExchangeEvent, TradeId};
+use mmb_domain::exchanges::symbol::{Precision, Symbol};
+use mmb_domain::market::{
+    CurrencyCode, CurrencyId, CurrencyPair, ExchangeErrorType, ExchangeId, SpecificCurrencyPair,
+};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order::group::OrderGroupId;
+use mmb_domain::order::pool::{OrderRef, OrdersPool};
+use mmb_domain::order::snapshot::{
+    ClientOrderId, ExchangeOrderId, OrderExecutionType, OrderHeader, OrderInfo, OrderOptions,
+    OrderRole, OrderSide, OrderStatus, Price, UserOrder,
+};
+use mmb_domain::position::{ActivePosition, DerivativePosition};
+use mmb_utils::DateTime;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+#[derive(Default)]
+pub struct ErrorHandlerOkx;
+
+impl ErrorHandler for ErrorHandlerOkx {
+    fn check_spec_rest_error(&self, response: &RestResponse) -> Result<(), ExchangeError> {
+        let envelope: OkxResponse<serde_json::Value> = serde_json::from_str(&response.content)
+            .map_err(|err| {
+                ExchangeError::parsing(format!(
+                    "Unable to parse response.content: {err:?}\n{}",
+                    response.content
+                ))
+            })?;
+
+        if envelope.code == "0" {
+            return Ok(());
+        }
+
+        // Errors of order operations are described in data items while common code is only
+        // a sign of failed operation
+        let operation_error = envelope
+            .data
+            .first()
+            .and_then(|x| Some((x.get("sCode")?.as_str()?, x.get("sMsg")?.as_str()?)))
+            .filter(|(code, _)| *code != "0");
+        let (code, message) = operation_error.unwrap_or((&envelope.code, &envelope.msg));
+
+        Err(ExchangeError::new(
+            ExchangeErrorType::Unknown,
+            message.to_owned(),
+            code.parse().ok(),
+        ))
+    }
+
+    fn clarify_error_type(&self, error: &ExchangeError) -> ExchangeErrorType {
+        use ExchangeErrorType::*;
+        // According to https://www.okx.com/docs-v5/en/#error-code
+        match error.code {
+            Some(50011) | Some(50061) => RateLimit,
+            Some(50102) | Some(50111) | Some(50113) | Some(50114) => Authentication,
+            Some(50001) | Some(50013) => ServiceUnavailable,
+            Some(51008) => InsufficientFunds,
+            Some(51603) => OrderNotFound,
+            Some(51401) | Some(51402) => OrderCompleted,
+            Some(51000) | Some(51006) | Some(51020) | Some(51121) | Some(51124) => InvalidOrder,
+            _ => Unknown,
+        }
+    }
+}
+
+pub struct RestHeadersOkx {
+    api_key: String,
+    secret_key: String,
+    api_passphrase: String,
+}
+
+impl RestHeadersOkx {
+    pub fn new(api_key: String, secret_key: String, api_passphrase: String) -> Self {
+        Self {
+            api_key,
+            secret_key,
+            api_passphrase,
+        }
+    }
+}
+
+impl RestHeaders for RestHeadersOkx {
+    fn add_specific_headers(
+        &self,
+        builder: Builder,
+        uri: &Uri,
+        request_type: RequestType,
+    ) -> Builder {
+        self.add_specific_headers_with_body(builder, uri, request_type, &[])
+    }
+
+    fn add_specific_headers_with_body(
+        &self,
+        builder: Builder,
+        uri: &Uri,
+        request_type: RequestType,
+        body: &[u8],
+    ) -> Builder {
+        let builder = builder.header(CONTENT_TYPE, "application/json");
+        if self.api_key.is_empty() {
+            // public endpoints don't need authentication
+            return builder;
+        }
+
+        let path_and_query = match uri.path_and_query() {
+            Some(path_and_query) => path_and_query.as_str(),
+            None => uri.path(),
+        };
+        let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let signature = Okx::create_signature(
+            &self.secret_key,
+            &timestamp,
+            request_type.as_str(),
+            path_and_query,
+            body,
+        );
+
+        builder
+            .header("OK-ACCESS-KEY", &self.api_key)
+            .header("OK-ACCESS-SIGN", signature)
+            .header("OK-ACCESS-TIMESTAMP", timestamp)
+            .header("OK-ACCESS-PASSPHRASE", &self.api_passphrase)
+    }
+}
+
+const EMPTY_RESPONSE_IS_OK: bool = false;
+
+pub struct Okx {
+    pub(crate) settings: ExchangeSettings,
+    pub hosts: Hosts,
+    rest_client: RestClient<ErrorHandlerOkx, RestHeadersOkx>,
+    pub(crate) unified_to_specific: RwLock<HashMap<CurrencyPair, SpecificCurrencyPair>>,
+    pub(crate) specific_to_unified: RwLock<HashMap<SpecificCurrencyPair, CurrencyPair>>,
+    pub(crate) supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    // Currencies used for trading according to user settings
+    pub(super) traded_specific_currencies: Mutex<Vec<SpecificCurrencyPair>>,
+    // Legs of OCO algo orders created by engine by algo id
+    pub(super) algo_order_legs: DashMap<String, AlgoOrderLegs>,
+    pub(super) lifetime_manager: Arc<AppLifetimeManager>,
+    pub(super) events_channel: broadcast::Sender<ExchangeEvent>,
+    pub(crate) order_created_callback: OrderCreatedCb,
+    pub(crate) order_cancelled_callback: OrderCancelledCb,
+    pub(crate) handle_order_filled_callback: HandleOrderFilledCb,
+    pub(crate) handle_trade_callback: HandleTradeCb,
+    pub(super) handle_metrics_callback: HandleMetricsCb,
+    pub(crate) websocket_message_callback: SendWebsocketMessageCb,
+}
+
+impl Okx {
+    pub fn new(
+        settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+    ) -> Okx {
+        Self {
+            rest_client: RestClient::new(
+                ErrorHandlerData::new(
+                    EMPTY_RESPONSE_IS_OK,
+                    settings.exchange_account_id,
+                    ErrorHandlerOkx,
+                ),
+                RestHeadersOkx::new(
+                    settings.api_key.clone(),
+                    settings.secret_key.clone(),
+                    settings.api_passphrase.clone(),
+                ),
+            ),
+            settings,
+            hosts: Self::make_hosts(),
+            unified_to_specific: Default::default(),
+            specific_to_unified: Default::default(),
+            supported_currencies: Default::default(),
+            traded_specific_currencies: Default::default(),
+            algo_order_legs: Default::default(),
+            events_channel,
+            lifetime_manager,
+            order_created_callback: Box::new(|_, _, _| {}),
+            order_cancelled_callback: Box::new(|_, _, _| {}),
+            handle_order_filled_callback: Box::new(|_| {}),
+            handle_trade_callback: Box::new(|_, _| {}),
+            handle_metrics_callback: Box::new(|_| {}),
+            websocket_message_callback: Box::new(|_, _| Ok(())),
+        }
+    }
+
+    /// Public websocket is used as main connection for market data
+    /// and private one as secondary connection for order flow
+    fn make_hosts() -> Hosts {
+        Hosts {
+            web_socket_host: "wss://ws.okx.com:8443/ws/v5/public",
+            web_socket2_host: "wss://ws.okx.com:8443/ws/v5/private",
+            rest_host: "https://www.okx.com",
+        }
+    }
+
+    /// Instruments of perpetual swaps are traded in derivative mode and spot ones otherwise
+    pub(super) fn instrument_type(&self) -> &'static str {
+        match self.settings.is_margin_trading {
+            true => "SWAP",
+            false => "SPOT",
+        }
+    }
+
+    /// Trade mode of orders in unified account: spot is traded by cash and swaps are
+    /// traded by cross margin if isolated one isn't specified in settings
+    fn trade_mode(&self) -> &'static str {
+        if !self.settings.is_margin_trading {
+            return "cash";
+        }
+
+        let margin_type = self
+            .settings
+            .derivative
+            .as_ref()
+            .and_then(|x| x.margin_type);
+        match margin_type {
+            Some(MarginType::Isolated) => "isolated",
+            Some(MarginType::Cross) | None => "cross",
+        }
+    }
+
+    pub(super) fn create_signature(
+        secret_key: &str,
+        timestamp: &str,
+        method: &str,
+        path_and_query: &str,
+        body: &[u8],
+    ) -> String {
+        let mut hmac = Hmac::<Sha256>::new_from_slice(secret_key.as_bytes())
+            .expect("Unable to calculate hmac for OKX signature");
+        hmac.update(timestamp.as_bytes());
+        hmac.update(method.as_bytes());
+        hmac.update(path_and_query.as_bytes());
+        hmac.update(body);
+
+        base64::encode(hmac.finalize().into_bytes())
+    }
+
+    fn to_json_body(request: &impl Serialize) -> Result<Bytes, ExchangeError> {
+        serde_json::to_vec(request)
+            .map(Bytes::from)
+            .map_err(|err| ExchangeError::parsing(format!("Unable to serialize request: {err}")))
+    }
+
+    #[named]
+    pub(super) async fn request_all_symbols(&self) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/api/v5/public/instruments");
+        builder.add_kv("instType", self.instrument_type());
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_all_symbols(&self, response: &RestResponse) -> Result<Vec<Arc<Symbol>>> {
+        let instruments: OkxResponse<OkxInstrument> = serde_json::from_str(&response.content)
+            .context("Unable to deserialize instruments from OKX")?;
+
+        let mut symbols = Vec::new();
+        for instrument in instruments.data {
+            if instrument.state != "live" {
+                continue;
+            }
+
+            let (base_id, quote_id) = match parse_instrument_id(instrument.inst_id.as_str()) {
+                Some(currencies) => currencies,
+                // dated futures and options aren't supported
+                None => continue,
+            };
+            let base: CurrencyCode = base_id.into();
+            let quote: CurrencyCode = quote_id.into();
+
+            let unified_currency_pair = CurrencyPair::from_codes(base, quote);
+            self.unified_to_specific
+                .write()
+                .insert(unified_currency_pair, instrument.inst_id);
+            self.specific_to_unified
+                .write()
+                .insert(instrument.inst_id, unified_currency_pair);
+            for (id, code) in [(base_id, base), (quote_id, quote)] {
+                let _ = self.supported_currencies.insert(id.into(), code);
+            }
+
+            let (amount_currency_code, balance_currency_code) =
+                match self.settings.is_margin_trading {
+                    true => (base, Some(quote)),
+                    false => (base, None),
+                };
+
+            let mut symbol = Symbol::new(
+                self.settings.is_margin_trading,
+                base_id.into(),
+                base,
+                quote_id.into(),
+                quote,
+                None,
+                None,
+                Some(instrument.min_sz),
+                instrument.max_lmt_sz,
+                None,
+                amount_currency_code,
+                balance_currency_code,
+                Precision::ByTick {
+                    tick: instrument.tick_sz,
+                },
+                Precision::ByTick {
+                    tick: instrument.lot_sz,
+                },
+            );
+            // amount of swaps is specified in contracts
+            if !instrument.ct_val.is_empty() {
+                symbol.amount_multiplier = instrument
+                    .ct_val
+                    .parse()
+                    .with_context(|| format!("Unable to parse ctVal of {}", instrument.inst_id))?;
+            }
+
+            symbols.push(Arc::new(symbol));
+        }
+
+        Ok(symbols)
+    }
+
+    pub(super) fn get_unified_currency_pair(
+        &self,
+        currency_pair: &SpecificCurrencyPair,
+    ) -> Result<CurrencyPair> {
+        self.specific_to_unified
+            .read()
+            .get(currency_pair)
+            .cloned()
+            .with_context(|| {
+                format!(
+                    "Not found currency pair '{currency_pair:?}' in {}",
+                    self.settings.exchange_account_id
+                )
+            })
+    }
+
+    pub(super) fn get_currency_code(&self, currency_id: &str) -> Result<CurrencyCode> {
+        self.supported_currencies
+            .get(&currency_id.into())
+            .map(|x| *x.value())
+            .ok_or_else(|| anyhow!("Unknown currency {currency_id} in OKX"))
+    }
+
+    #[named]
+    pub(super) async fn do_create_order(
+        &self,
+        order: &OrderRef,
+    ) -> Result<RestResponse, ExchangeError> {
+        let header = order.header();
+        let (ord_type, px) = match header.options {
+            OrderOptions::User(UserOrder::Limit {
+                price,
+                execution_type,
+            }) => match execution_type {
+                OrderExecutionType::MakerOnly => ("post_only", Some(price)),
+                OrderExecutionType::None => ("limit", Some(price)),
+                OrderExecutionType::ImmediateOrCancel => ("ioc", Some(price)),
+            },
+            OrderOptions::User(UserOrder::Market) => ("market", None),
+            _ => return Err(ExchangeError::unknown("Unexpected order type")),
+        };
+
+        let request = OkxCreateOrderRequest {
+            inst_id: self.get_specific_currency_pair(header.currency_pair),
+            td_mode: self.trade_mode(),
+            cl_ord_id: header.client_order_id.as_str(),
+            side: get_server_order_side(header.side),
+            ord_type,
+            px,
+            sz: header.amount,
+            reduce_only: false,
+        };
+
+        let uri = UriBuilder::from_path("/api/v5/trade/order")
+            .build_uri(self.hosts.rest_uri_host(), false);
+        let log_args = format!("Create order for {header:?}");
+        self.rest_client
+            .post(
+                uri,
+                Some(Self::to_json_body(&request)?),
+                function_name!(),
+                log_args,
+            )
+            .await
+    }
+
+    pub(super) fn get_order_id(
+        &self,
+        response: &RestResponse,
+    ) -> Result<ExchangeOrderId, ExchangeError> {
+        let deserialized: OkxResponse<OkxOrderOperationResult> =
+            serde_json::from_str(&response.content)
+                .map_err(|err| ExchangeError::parsing(format!("Unable to parse ordId: {err:?}")))?;
+
+        deserialized
+            .data
+            .into_iter()
+            .next()
+            .map(|x| x.ord_id)
+            .ok_or_else(|| ExchangeError::parsing("Response doesn't contain ordId".to_owned()))
+    }
+
+    #[named]
+    pub(super) async fn do_cancel_order(
+        &self,
+        order: &OrderRef,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> Result<RestResponse, ExchangeError> {
+        // legs of OCO algo order can be canceled only together
+        if let Some((algo_id, _)) = parse_algo_leg_order_id(exchange_order_id) {
+            return self.do_cancel_algo_order(order, algo_id).await;
+        }
+
+        let request = OkxCancelOrderRequest {
+            inst_id: self.get_specific_currency_pair(order.currency_pair()),
+            ord_id: exchange_order_id.as_str(),
+        };
+
+        let uri = UriBuilder::from_path("/api/v5/trade/cancel-order")
+            .build_uri(self.hosts.rest_uri_host(), false);
+        let log_args = format!("Cancel order for {}", order.client_order_id());
+        self.rest_client
+            .post(
+                uri,
+                Some(Self::to_json_body(&request)?),
+                function_name!(),
+                log_args,
+            )
+            .await
+    }
+
+    #[named]
+    pub(super) async fn request_create_oco_order(
+        &self,
+        group_id: OrderGroupId,
+        legs: &OcoLegs<'_>,
+    ) -> Result<RestResponse, ExchangeError> {
+        let header = legs.limit;
+        let request = OkxCreateOcoOrderRequest {
+            inst_id: self.get_specific_currency_pair(header.currency_pair),
+            td_mode: self.trade_mode(),
+            algo_cl_ord_id: group_id.to_string(),
+            side: get_server_order_side(header.side),
+            ord_type: "oco",
+            sz: header.amount,
+            tp_trigger_px: legs.price,
+            tp_ord_px: legs.price,
+            sl_trigger_px: legs.stop_price,
+            sl_ord_px: "-1",
+        };
+
+        let uri = UriBuilder::from_path("/api/v5/trade/order-algo")
+            .build_uri(self.hosts.rest_uri_host(), false);
+        let log_args = format!("Create OCO order {group_id} for {legs:?}");
+        self.rest_client
+            .post(
+                uri,
+                Some(Self::to_json_body(&request)?),
+                function_name!(),
+                log_args,
+            )
+            .await
+    }
+
+    pub(super) fn parse_oco_order(
+        response: &RestResponse,
+        legs: &OcoLegs<'_>,
+    ) -> Result<CreateOrderGroupResult, ExchangeError> {
+        let deserialized: OkxResponse<OkxAlgoOperationResult> =
+            serde_json::from_str(&response.content).map_err(|err| {
+                ExchangeError::parsing(format!("Unable to parse algoId: {err:?}"))
+            })?;
+
+        let algo_id = deserialized
+            .data
+            .into_iter()
+            .next()
+            .map(|x| x.algo_id)
+            .ok_or_else(|| ExchangeError::parsing("Response doesn't contain algoId".to_owned()))?;
+
+        Ok(CreateOrderGroupResult {
+            exchange_group_id: algo_id.as_str().into(),
+            exchange_order_ids: vec![
+                (
+                    legs.limit.client_order_id.clone(),
+                    algo_leg_order_id(&algo_id, AlgoLeg::TakeProfit),
+                ),
+                (
+                    legs.stop_loss.client_order_id.clone(),
+                    algo_leg_order_id(&algo_id, AlgoLeg::StopLoss),
+                ),
+            ],
+        })
+    }
+
+    #[named]
+    pub(super) async fn do_cancel_algo_order(
+        &self,
+        order: &OrderRef,
+        algo_id: &str,
+    ) -> Result<RestResponse, ExchangeError> {
+        let request = [OkxCancelAlgoRequest {
+            inst_id: self.get_specific_currency_pair(order.currency_pair()),
+            algo_id,
+        }];
+
+        let uri = UriBuilder::from_path("/api/v5/trade/cancel-algos")
+            .build_uri(self.hosts.rest_uri_host(), false);
+        let log_args = format!(
+            "Cancel algo order {algo_id} for {}",
+            order.client_order_id()
+        );
+        self.rest_client
+            .post(
+                uri,
+                Some(Self::to_json_body(&request)?),
+                function_name!(),
+                log_args,
+            )
+            .await
+    }
+
+    #[named]
+    pub(super) async fn do_cancel_orders_batch(
+        &self,
+        orders: &[OrderInfo],
+    ) -> Result<RestResponse, ExchangeError> {
+        let request = orders
+            .iter()
+            .map(|order| OkxCancelOrderRequest {
+                inst_id: self.get_specific_currency_pair(order.currency_pair),
+                ord_id: order.exchange_order_id.as_str(),
+            })
+            .collect_vec();
+
+        let uri = UriBuilder::from_path("/api/v5/trade/cancel-batch-orders")
+            .build_uri(self.hosts.rest_uri_host(), false);
+        let log_args = format!("Cancel {} orders", orders.len());
+        self.rest_client
+            .post(
+                uri,
+                Some(Self::to_json_body(&request)?),
+                function_name!(),
+                log_args,
+            )
+            .await
+    }
+
+    #[named]
+    pub(super) async fn request_open_orders(
+        &self,
+        currency_pair: Option<CurrencyPair>,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/api/v5/trade/orders-pending");
+        builder.add_kv("instType", self.instrument_type());
+        if let Some(pair) = currency_pair {
+            builder.add_kv("instId", self.get_specific_currency_pair(pair));
+        }
+
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_open_orders(&self, response: &RestResponse) -> Result<Vec<OrderInfo>> {
+        let okx_orders: OkxResponse<OkxOrderInfo> = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for get_open_orders request")?;
+
+        okx_orders
+            .data
+            .iter()
+            .map(|order| self.specific_order_info_to_unified(order))
+            .try_collect()
+    }
+
+    #[named]
+    pub(super) async fn request_order_info(
+        &self,
+        order: &OrderRef,
+    ) -> Result<RestResponse, ExchangeError> {
+        let client_order_id = order.client_order_id();
+
+        let mut builder = UriBuilder::from_path("/api/v5/trade/order");
+        builder.add_kv(
+            "instId",
+            self.get_specific_currency_pair(order.currency_pair()),
+        );
+        builder.add_kv("clOrdId", client_order_id.as_str());
+
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+        let log_args = format!("order {client_order_id}");
+
+        self.rest_client.get(uri, function_name!(), log_args).await
+    }
+
+    pub(super) fn parse_order_info(&self, response: &RestResponse) -> Result<OrderInfo> {
+        let okx_orders: OkxResponse<OkxOrderInfo> = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for get_order_info request")?;
+
+        let order = okx_orders
+            .data
+            .first()
+            .context("No one order info received")?;
+
+        self.specific_order_info_to_unified(order)
+    }
+
+    fn specific_order_info_to_unified(&self, specific: &OkxOrderInfo) -> Result<OrderInfo> {
+        let commission_currency_code = match specific.fee_ccy.is_empty() {
+            true => None,
+            false => Some(self.get_currency_code(&specific.fee_ccy)?.to_string()),
+        };
+
+        Ok(OrderInfo::new(
+            self.get_unified_currency_pair(&specific.inst_id)?,
+            specific.ord_id.clone(),
+            specific.cl_ord_id.clone(),
+            specific.side,
+            Okx::get_local_order_status(&specific.state),
+            specific.px,
+            specific.sz,
+            specific.avg_px,
+            specific.acc_fill_sz,
+            commission_currency_code,
+            None,
+            Some(-specific.fee),
+        ))
+    }
+
+    pub(super) fn get_local_order_status(status: &str) -> OrderStatus {
+        match status {
+            "live" | "partially_filled" => OrderStatus::Created,
+            "filled" => OrderStatus::Completed,
+            "canceled" | "mmp_canceled" => OrderStatus::Canceled,
+            _ => panic!("OKX: unexpected order status {status}"),
+        }
+    }
+
+    /// `T` means taker and `M` means maker
+    pub(super) fn get_order_role(exec_type: &str) -> Result<OrderRole> {
+        match exec_type {
+            "T" => Ok(OrderRole::Taker),
+            "M" => Ok(OrderRole::Maker),
+            _ => Err(anyhow!("Unknown OKX execution type {exec_type}")),
+        }
+    }
+
+    #[named]
+    pub(super) async fn request_my_trades(
+        &self,
+        symbol: &Symbol,
+        last_date_time: Option<DateTime>,
+    ) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/api/v5/trade/fills");
+        builder.add_kv("instType", self.instrument_type());
+        builder.add_kv(
+            "instId",
+            self.get_specific_currency_pair(symbol.currency_pair()),
+        );
+        if let Some(date_time) = last_date_time {
+            builder.add_kv("begin", date_time.timestamp_millis());
+        }
+
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_my_trades(&self, response: &RestResponse) -> Result<Vec<OrderTrade>> {
+        let fills: OkxResponse<OkxFill> =
+            serde_json::from_str(&response.content).context("Failed to parse trade data")?;
+
+        fills
+            .data
+            .into_iter()
+            .map(|fill| {
+                Ok(OrderTrade {
+                    exchange_order_id: fill.ord_id,
+                    trade_id: TradeId::String(fill.trade_id.into_boxed_str()),
+                    datetime: fill.ts,
+                    price: fill.fill_px,
+                    amount: fill.fill_sz,
+                    order_role: Okx::get_order_role(&fill.exec_type)?,
+                    fee_currency_code: self.get_currency_code(&fill.fee_ccy)?,
+                    fee_rate: None,
+                    fee_amount: Some(-fill.fee),
+                    fill_type: OrderFillType::UserTrade,
+                })
+            })
+            .try_collect()
+    }
+
+    #[named]
+    pub(super) async fn request_get_position(&self) -> Result<RestResponse, ExchangeError> {
+        let mut builder = UriBuilder::from_path("/api/v5/account/positions");
+        builder.add_kv("instType", self.instrument_type());
+        let uri = builder.build_uri(self.hosts.rest_uri_host(), true);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_active_positions(
+        &self,
+        response: &RestResponse,
+    ) -> Result<Vec<ActivePosition>> {
+        let positions: OkxResponse<OkxPosition> =
+            serde_json::from_str(&response.content).context("Failed to parse positions")?;
+
+        positions
+            .data
+            .into_iter()
+            .filter(|position| !position.pos.is_zero())
+            .map(|position| {
+                let derivative_position = DerivativePosition::new(
+                    self.get_unified_currency_pair(&position.inst_id)?,
+                    position.pos,
+                    position.avg_px,
+                    position.liq_px,
+                    position.lever,
+                );
+
+                Ok(ActivePosition::new(derivative_position, position.u_time))
+            })
+            .try_collect()
+    }
+
+    /// Position is closed by reduce only market order, so engine receives its fills as usual
+    #[named]
+    pub(super) async fn request_close_position(
+        &self,
+        position: &ActivePosition,
+        price: Option<Price>,
+    ) -> Result<RestResponse, ExchangeError> {
+        let side = position.derivative.get_side().change_side();
+        let request = OkxCreateOrderRequest {
+            inst_id: self.get_specific_currency_pair(position.derivative.currency_pair),
+            td_mode: self.trade_mode(),
+            cl_ord_id: "",
+            side: get_server_order_side(side),
+            ord_type: match price {
+                Some(_) => "limit",
+                None => "market",
+            },
+            px: price,
+            sz: position.derivative.position.abs(),
+            reduce_only: true,
+        };
+
+        let uri = UriBuilder::from_path("/api/v5/trade/order")
+            .build_uri(self.hosts.rest_uri_host(), false);
+        let log_args = format!("Close position response for {position:?} {price:?}");
+        self.rest_client
+            .post(
+                uri,
+                Some(Self::to_json_body(&request)?),
+                function_name!(),
+                log_args,
+            )
+            .await
+    }
+
+    #[named]
+    pub(super) async fn request_get_balance(&self) -> Result<RestResponse, ExchangeError> {
+        let uri = UriBuilder::from_path("/api/v5/account/balance")
+            .build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    /// Unified account has single balance for spot and derivatives trading
+    pub(super) fn parse_get_balance(
+        &self,
+        response: &RestResponse,
+    ) -> Result<Vec<ExchangeBalance>> {
+        let balances: OkxResponse<OkxBalanceInfo> =
+            serde_json::from_str(&response.content).context("Failed to parse balance")?;
+
+        Ok(balances
+            .data
+            .iter()
+            .flat_map(|x| x.details.iter())
+            .filter_map(|details| {
+                let currency_code = self.get_currency_code(&details.ccy).ok()?;
+                Some(ExchangeBalance {
+                    currency_code,
+                    balance: details.avail_bal,
+                })
+            })
+            .collect_vec())
+    }
+
+    #[named]
+    pub(super) async fn request_get_server_time(&self) -> Result<RestResponse, ExchangeError> {
+        let uri = UriBuilder::from_path("/api/v5/public/time")
+            .build_uri(self.hosts.rest_uri_host(), false);
+
+        self.rest_client
+            .get(uri, function_name!(), "".to_string())
+            .await
+    }
+
+    pub(super) fn parse_get_server_time(&self, response: &RestResponse) -> Result<i64> {
+        let time: OkxResponse<OkxServerTime> =
+            serde_json::from_str(&response.content).context("Failed to parse server time")?;
+
+        time.data
+            .first()
+            .context("Server time isn't received")?
+            .ts
+            .parse()
+            .context("Unable to parse server time")
+    }
+}
+
+/// Splits OKX instrument id like `BTC-USDT` or `BTC-USDT-SWAP` into ids of base and quote
+/// currencies. Returns `None` for instruments with expiration like `BTC-USD-221230`
+pub fn parse_instrument_id(inst_id: &str) -> Option<(&str, &str)> {
+    let mut parts = inst_id.split('-');
+    let base = parts.next()?;
+    let quote = parts.next()?;
+    match (parts.next(), parts.next()) {
+        (None, _) | (Some("SWAP"), None) => Some((base, quote)),
+        _ => None,
+    }
+}
+
+/// Legs of order group which can be created as OCO algo order of OKX:
+/// limit order and stop-loss order with the same side and amount
+#[derive(Debug)]
+pub(super) struct OcoLegs<'a> {
+    pub limit: &'a OrderHeader,
+    pub price: Price,
+    pub stop_loss: &'a OrderHeader,
+    pub stop_price: Price,
+}
+
+pub(super) fn get_oco_legs<'a>(
+    first: &'a OrderHeader,
+    second: &'a OrderHeader,
+) -> Option<OcoLegs<'a>> {
+    fn get_limit_price(header: &OrderHeader) -> Option<Price> {
+        match header.options {
+            OrderOptions::User(UserOrder::Limit { price, .. }) => Some(price),
+            _ => None,
+        }
+    }
+
+    fn get_stop_price(header: &OrderHeader) -> Option<Price> {
+        match header.options {
+            OrderOptions::User(UserOrder::StopLoss { stop_price }) => Some(stop_price),
+            _ => None,
+        }
+    }
+
+    if first.side != second.side || first.amount != second.amount {
+        return None;
+    }
+
+    [(first, second), (second, first)]
+        .into_iter()
+        .find_map(|(limit, stop_loss)| {
+            Some(OcoLegs {
+                limit,
+                price: get_limit_price(limit)?,
+                stop_loss,
+                stop_price: get_stop_price(stop_loss)?,
+            })
+        })
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub(super) enum AlgoLeg {
+    TakeProfit,
+    StopLoss,
+}
+
+pub(super) struct AlgoOrderLegs {
+    pub take_profit: ClientOrderId,
+    pub stop_loss: ClientOrderId,
+}
+
+impl AlgoOrderLegs {
+    pub fn get(&self, leg: AlgoLeg) -> &ClientOrderId {
+        match leg {
+            AlgoLeg::TakeProfit => &self.take_profit,
+            AlgoLeg::StopLoss => &self.stop_loss,
+        }
+    }
+}
+
+/// Legs of OCO algo order don't have own ids until one of them is triggered,
+/// so they are identified by algo id and kind of leg, e.g. `12345-tp`
+pub(super) fn algo_leg_order_id(algo_id: &str, leg: AlgoLeg) -> ExchangeOrderId {
+    let suffix = match leg {
+        AlgoLeg::TakeProfit => "tp",
+        AlgoLeg::StopLoss => "sl",
+    };
+    format!("{algo_id}-{suffix}").as_str().into()
+}
+
+pub(super) fn parse_algo_leg_order_id(
+    exchange_order_id: &ExchangeOrderId,
+) -> Option<(&str, AlgoLeg)> {
+    let (algo_id, suffix) = exchange_order_id.as_str().rsplit_once('-')?;
+    let leg = match suffix {
+        "tp" => AlgoLeg::TakeProfit,
+        "sl" => AlgoLeg::StopLoss,
+        _ => return None,
+    };
+    Some((algo_id, leg))
+}
+
+pub(super) fn get_server_order_side(side: OrderSide) -> &'static str {
+    match side {
+        OrderSide::Buy => "buy",
+        OrderSide::Sell => "sell",
+    }
+}
+
+pub struct OkxBuilder;
+
+impl ExchangeClientBuilder for OkxBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        lifetime_manager: Arc<AppLifetimeManager>,
+        _timeout_manager: Arc<TimeoutManager>,
+        _orders: Arc<OrdersPool>,
+    ) -> ExchangeClientBuilderResult {
+        ExchangeClientBuilderResult {
+            client: Box::new(Okx::new(
+                exchange_settings,
+                events_channel,
+                lifetime_manager,
+            )),
+            features: ExchangeFeatures::new(
+                OpenOrdersType::AllCurrencyPair,
+                RestFillsFeatures::new(RestFillsType::MyTrades),
+                OrderFeatures {
+                    maker_only: true,
+                    supports_get_order_info_by_client_order_id: true,
+                    cancellation_response_from_rest_only_for_errors: true,
+                    creation_response_from_rest_only_for_errors: true,
+                    order_was_completed_error_for_cancellation: true,
+                    supports_already_cancelled_order: true,
+                    supports_stop_loss_order: false,
+                },
+                OrderTradeOption {
+                    supports_trade_time: true,
+                    supports_trade_incremented_id: false,
+                    supports_get_prints: true,
+                    supports_tick_direction: false,
+                    supports_my_trades_from_time: true,
+                },
+                WebSocketOptions {
+                    execution_notification: true,
+                    cancellation_notification: true,
+                    supports_ping_pong: true,
+                    supports_subscription_response: true,
+                },
+                EMPTY_RESPONSE_IS_OK,
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+                AllowedEventSourceType::default(),
+            ),
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        // trading endpoints allow 60 requests per 2 seconds
+        RequestTimeoutArguments::from_requests_per_minute(1800)
+    }
+
+    fn get_exchange_id(&self) -> ExchangeId {
+        "OKX".into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::market::ExchangeAccountId;
+    use mmb_domain::order::snapshot::Amount;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn generate_signature() {
+        let signature = Okx::create_signature(
+            "22582BD0CFF14C41EDBF1AB98506286D",
+            "2020-12-08T09:08:57.715Z",
+            "GET",
+            "/api/v5/account/balance?ccy=BTC",
+            b"",
+        );
+
+        assert_eq!(signature, "HiZhvSfMtWJA3uUIVXV3a/bSXNPCWvYFXoGCVS8V4zY=");
+    }
+
+    #[test]
+    fn instrument_id_conventions() {
+        assert_eq!(parse_instrument_id("BTC-USDT"), Some(("BTC", "USDT")));
+        assert_eq!(parse_instrument_id("ETH-USDT-SWAP"), Some(("ETH", "USDT")));
+        assert_eq!(parse_instrument_id("BTC-USD-221230"), None);
+        assert_eq!(parse_instrument_id("BTC-USD-221230-20000-C"), None);
+        assert_eq!(parse_instrument_id("BTC"), None);
+    }
+
+    fn order_header(side: OrderSide, amount: Amount, user_order: UserOrder) -> OrderHeader {
+        OrderHeader::with_user_order(
+            ClientOrderId::unique_id(),
+            ExchangeAccountId::new("Okx", 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            side,
+            amount,
+            user_order,
+            None,
+            None,
+            "test".to_owned(),
+        )
+    }
+
+    #[test]
+    fn oco_legs_in_any_order() {
+        let limit = order_header(OrderSide::Sell, dec!(1), UserOrder::limit(dec!(110)));
+        let stop_loss = order_header(
+            OrderSide::Sell,
+            dec!(1),
+            UserOrder::StopLoss {
+                stop_price: dec!(90),
+            },
+        );
+
+        for (first, second) in [(&limit, &stop_loss), (&stop_loss, &limit)] {
+            let legs = get_oco_legs(first, second).expect("in test");
+            assert_eq!(legs.limit.client_order_id, limit.client_order_id);
+            assert_eq!(legs.price, dec!(110));
+            assert_eq!(legs.stop_loss.client_order_id, stop_loss.client_order_id);
+            assert_eq!(legs.stop_price, dec!(90));
+        }
+
+        assert!(get_oco_legs(&limit, &limit).is_none());
+    }
+
+    #[test]
+    fn parse_created_oco_order() {
+        let limit = order_header(OrderSide::Sell, dec!(1), UserOrder::limit(dec!(110)));
+        let stop_loss = order_header(
+            OrderSide::Sell,
+            dec!(1),
+            UserOrder::StopLoss {
+                stop_price: dec!(90),
+            },
+        );
+        let legs = get_oco_legs(&limit, &stop_loss).expect("in test");
+        let response = RestResponse {
+            status: hyper::StatusCode::OK,
+            content: r#"{"code":"0","msg":"","data":[{"algoId":"12345","clOrdId":"","algoClOrdId":"1","sCode":"0","sMsg":""}]}"#.to_owned(),
+        };
+
+        let result = Okx::parse_oco_order(&response, &legs).expect("in test");
+
+        assert_eq!(result.exchange_group_id, "12345".into());
+        assert_eq!(
+            result.exchange_order_ids,
+            vec![
+                (limit.client_order_id.clone(), "12345-tp".into()),
+                (stop_loss.client_order_id.clone(), "12345-sl".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn algo_leg_order_id_conventions() {
+        let take_profit = algo_leg_order_id("12345", AlgoLeg::TakeProfit);
+        assert_eq!(
+            parse_algo_leg_order_id(&take_profit),
+            Some(("12345", AlgoLeg::TakeProfit))
+        );
+        let stop_loss = algo_leg_order_id("12345", AlgoLeg::StopLoss);
+        assert_eq!(
+            parse_algo_leg_order_id(&stop_loss),
+            Some(("12345", AlgoLeg::StopLoss))
+        );
+        assert_eq!(parse_algo_leg_order_id(&"312269865356374016".into()), None);
+    }
+
+    #[test]
+    fn order_operation_error_is_taken_from_data() {
+        let response = RestResponse {
+            status: hyper::StatusCode::OK,
+            content: r#"{"code":"1","msg":"Operation failed.","data":[{"clOrdId":"1","ordId":"","sCode":"51008","sMsg":"Order failed. Insufficient balance.","tag":""}]}"#.to_owned(),
+        };
+
+        let error = ErrorHandlerOkx
+            .check_spec_rest_error(&response)
+            .expect_err("in test");
+
+        assert_eq!(error.code, Some(51008));
+        assert_eq!(error.message, "Order failed. Insufficient balance.");
+        assert_eq!(
+            ErrorHandlerOkx.clarify_error_type(&error),
+            ExchangeErrorType::InsufficientFunds
+        );
+    }
+}
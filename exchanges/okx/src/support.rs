@@ -0,0 +1,530 @@
+use crate::okx::{algo_leg_order_id, AlgoLeg, Okx};
+use crate::types::{OkxAlgoOrderUpdate, OkxOrderBook, OkxOrderUpdate, OkxTrade};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use dashmap::DashMap;
+use mmb_core::connectivity::WebSocketRole;
+use mmb_core::exchanges::common::send_event;
+use mmb_core::exchanges::general::handlers::handle_order_filled::{
+    FillAmount, FillEvent, SpecialOrderData,
+};
+use mmb_core::exchanges::traits::{
+    HandleMetricsCb, HandleOrderFilledCb, HandleTradeCb, OrderCancelledCb, OrderCreatedCb,
+    SendWebsocketMessageCb, Support,
+};
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{EventSourceType, ExchangeEvent, Trade, TradeId};
+use mmb_domain::market::{CurrencyCode, CurrencyId, CurrencyPair, SpecificCurrencyPair};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order_book::event::{EventType, OrderBookEvent};
+use mmb_domain::order_book::order_book_data::OrderBookData;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::any::Any;
+use std::sync::Arc;
+use url::Url;
+
+#[async_trait]
+impl Support for Okx {
+    fn as_any(&self) -> &(dyn Any + Send + Sync + 'static) {
+        self
+    }
+
+    fn on_websocket_message(&self, msg: &str) -> Result<()> {
+        if msg == "pong" {
+            return Ok(());
+        }
+
+        let message: WebsocketMessage = serde_json::from_str(msg)
+            .with_context(|| format!("Unable to parse websocket message:\n{msg}"))?;
+
+        match message {
+            WebsocketMessage::Event(event) => self.handle_event(event),
+            WebsocketMessage::Push { arg, data } => match arg.channel.as_str() {
+                "books5" => self.handle_order_book(arg.inst_id, data),
+                "trades" => self.handle_trades(data),
+                "orders" => self.handle_orders(data),
+                "orders-algo" => self.handle_algo_orders(data),
+                channel => {
+                    log::warn!("Unsupported OKX websocket channel {channel}: {msg}");
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_connected(&self) -> Result<()> {
+        // Market data channels are public, so they are subscribed without login
+        let traded_currencies = self.traded_specific_currencies.lock().clone();
+        let args = ["books5", "trades"]
+            .into_iter()
+            .flat_map(|channel| {
+                traded_currencies
+                    .iter()
+                    .map(move |inst_id| SubscriptionArg {
+                        channel: channel.to_owned(),
+                        inst_id: Some(*inst_id),
+                        inst_type: None,
+                    })
+            })
+            .collect();
+        self.send_request(WebSocketRole::Main, "subscribe", args)?;
+
+        if !self.is_websocket_enabled(WebSocketRole::Secondary) {
+            return Ok(());
+        }
+
+        // Private channels are available only after login
+        let timestamp = Utc::now().timestamp().to_string();
+        let signature = Okx::create_signature(
+            &self.settings.secret_key,
+            &timestamp,
+            "GET",
+            "/users/self/verify",
+            &[],
+        );
+        let login = LoginRequest {
+            op: "login",
+            args: [LoginArg {
+                api_key: &self.settings.api_key,
+                passphrase: &self.settings.api_passphrase,
+                timestamp,
+                sign: signature,
+            }],
+        };
+        let login = serde_json::to_string(&login).expect("Failed to serialize OKX login message");
+
+        (self.websocket_message_callback)(WebSocketRole::Secondary, login)
+    }
+
+    fn on_disconnected(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_send_websocket_message_callback(&mut self, callback: SendWebsocketMessageCb) {
+        self.websocket_message_callback = callback;
+    }
+
+    fn set_order_created_callback(&mut self, callback: OrderCreatedCb) {
+        self.order_created_callback = callback;
+    }
+
+    fn set_order_cancelled_callback(&mut self, callback: OrderCancelledCb) {
+        self.order_cancelled_callback = callback;
+    }
+
+    fn set_handle_order_filled_callback(&mut self, callback: HandleOrderFilledCb) {
+        self.handle_order_filled_callback = callback;
+    }
+
+    fn set_handle_trade_callback(&mut self, callback: HandleTradeCb) {
+        self.handle_trade_callback = callback;
+    }
+
+    fn set_handle_metrics_callback(&mut self, callback: HandleMetricsCb) {
+        self.handle_metrics_callback = callback;
+    }
+
+    fn set_traded_specific_currencies(&self, currencies: Vec<SpecificCurrencyPair>) {
+        *self.traded_specific_currencies.lock() = currencies;
+    }
+
+    /// OKX serves public and private channels on different endpoints, so main connection
+    /// is used for market data and secondary one for order flow
+    fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
+        match role {
+            WebSocketRole::Main => true,
+            WebSocketRole::Secondary => {
+                !self.settings.is_watch_only
+                    && !self.settings.api_key.is_empty()
+                    && !self.settings.secret_key.is_empty()
+            }
+        }
+    }
+
+    async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url> {
+        let host = match role {
+            WebSocketRole::Main => self.hosts.web_socket_host,
+            WebSocketRole::Secondary => self.hosts.web_socket2_host,
+        };
+
+        Url::parse(host).with_context(|| format!("Unable parse websocket {role:?} uri"))
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        self.unified_to_specific.read()[&currency_pair]
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        &self.supported_currencies
+    }
+
+    fn should_log_message(&self, message: &str) -> bool {
+        message.contains(r#""channel":"orders"#)
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        &self.settings
+    }
+}
+
+impl Okx {
+    fn handle_event(&self, event: EventMessage) -> Result<()> {
+        match event.event.as_str() {
+            "login" if event.code.as_deref() == Some("0") => {
+                log::info!("OKX websocket: successful login");
+
+                let args = ["orders", "orders-algo"]
+                    .into_iter()
+                    .map(|channel| SubscriptionArg {
+                        channel: channel.to_owned(),
+                        inst_id: None,
+                        inst_type: Some(self.instrument_type()),
+                    })
+                    .collect();
+                self.send_request(WebSocketRole::Secondary, "subscribe", args)
+            }
+            "subscribe" => {
+                log::info!("OKX websocket: successful subscription: {event:?}");
+                Ok(())
+            }
+            _ => {
+                let err = format!("OKX websocket: failed request: {event:?}");
+                log::error!("{err}");
+                bail!(err)
+            }
+        }
+    }
+
+    fn send_request(
+        &self,
+        role: WebSocketRole,
+        op: &'static str,
+        args: Vec<SubscriptionArg>,
+    ) -> Result<()> {
+        let request = serde_json::to_string(&SubscriptionRequest { op, args })
+            .expect("Failed to serialize OKX subscription message");
+
+        (self.websocket_message_callback)(role, request)
+    }
+
+    /// `books5` channel pushes full snapshot of top 5 levels on every change
+    fn handle_order_book(
+        &self,
+        inst_id: Option<SpecificCurrencyPair>,
+        data: Vec<Value>,
+    ) -> Result<()> {
+        let inst_id = inst_id.context("Order book push doesn't contain instId")?;
+        let currency_pair = self.get_unified_currency_pair(&inst_id)?;
+
+        for item in data {
+            let order_book: OkxOrderBook =
+                serde_json::from_value(item).context("Unable to parse OKX order book")?;
+            let order_book_data = OrderBookData::new(
+                order_book
+                    .asks
+                    .into_iter()
+                    .map(|(price, amount, ..)| (price, amount))
+                    .collect(),
+                order_book
+                    .bids
+                    .into_iter()
+                    .map(|(price, amount, ..)| (price, amount))
+                    .collect(),
+            );
+
+            let order_book_event = OrderBookEvent::new(
+                Utc::now(),
+                self.settings.exchange_account_id,
+                currency_pair,
+                String::default(),
+                EventType::Snapshot,
+                Arc::new(order_book_data),
+            );
+
+            send_event(
+                &self.events_channel,
+                self.lifetime_manager.clone(),
+                self.settings.exchange_account_id,
+                ExchangeEvent::OrderBookEvent(order_book_event),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_trades(&self, data: Vec<Value>) -> Result<()> {
+        for item in data {
+            let trade: OkxTrade =
+                serde_json::from_value(item).context("Unable to parse OKX trade")?;
+
+            (self.handle_trade_callback)(
+                self.get_unified_currency_pair(&trade.inst_id)?,
+                Trade {
+                    trade_id: TradeId::String(trade.trade_id.into_boxed_str()),
+                    price: trade.px,
+                    quantity: trade.sz,
+                    side: trade.side,
+                    transaction_time: trade.ts,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    fn handle_orders(&self, data: Vec<Value>) -> Result<()> {
+        for item in data {
+            let update: OkxOrderUpdate =
+                serde_json::from_value(item).context("Unable to parse OKX order update")?;
+            self.handle_order_update(update)?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn handle_order_update(&self, update: OkxOrderUpdate) -> Result<()> {
+        // Orders triggered by OCO algo order are tracked as legs of the algo order. Stop loss leg
+        // is triggered as market order and take profit one as limit order
+        let algo_leg = (!update.algo_id.is_empty()).then_some(match update.ord_type.as_str() {
+            "market" => AlgoLeg::StopLoss,
+            _ => AlgoLeg::TakeProfit,
+        });
+        let (client_order_id, exchange_order_id) = match algo_leg {
+            None => (update.cl_ord_id, update.ord_id),
+            Some(leg) => (
+                self.algo_order_legs
+                    .get(&update.algo_id)
+                    .map(|x| x.get(leg).clone())
+                    .unwrap_or(update.cl_ord_id),
+                algo_leg_order_id(&update.algo_id, leg),
+            ),
+        };
+        if algo_leg.is_some() && matches!(update.state.as_str(), "filled" | "canceled") {
+            let _ = self.algo_order_legs.remove(&update.algo_id);
+        }
+
+        // Every fill is pushed with its trade id, other updates are changes of order state
+        if !update.trade_id.is_empty() {
+            let fill_event = FillEvent {
+                source_type: EventSourceType::WebSocket,
+                trade_id: Some(TradeId::String(update.trade_id.into_boxed_str())),
+                client_order_id: (!client_order_id.as_str().is_empty()).then_some(client_order_id),
+                exchange_order_id,
+                fill_price: update.fill_px,
+                fill_amount: FillAmount::Incremental {
+                    fill_amount: update.fill_sz,
+                    total_filled_amount: Some(update.acc_fill_sz),
+                },
+                order_role: Some(Okx::get_order_role(&update.exec_type)?),
+                commission_currency_code: Some(self.get_currency_code(&update.fill_fee_ccy)?),
+                commission_rate: None,
+                commission_amount: Some(-update.fill_fee),
+                fill_type: OrderFillType::UserTrade,
+                special_order_data: Some(SpecialOrderData {
+                    currency_pair: self.get_unified_currency_pair(&update.inst_id)?,
+                    order_side: update.side,
+                    order_amount: update.sz,
+                }),
+                fill_date: update.fill_time,
+            };
+
+            (self.handle_order_filled_callback)(fill_event);
+            return Ok(());
+        }
+
+        match update.state.as_str() {
+            // legs of algo order are already created by request of OCO order
+            "live" if algo_leg.is_none() => (self.order_created_callback)(
+                client_order_id,
+                exchange_order_id,
+                EventSourceType::WebSocket,
+            ),
+            "canceled" | "mmp_canceled" => (self.order_cancelled_callback)(
+                client_order_id,
+                exchange_order_id,
+                EventSourceType::WebSocket,
+            ),
+            state => log::trace!("Skipped OKX order update {state} of order {exchange_order_id}"),
+        }
+
+        Ok(())
+    }
+
+    fn handle_algo_orders(&self, data: Vec<Value>) -> Result<()> {
+        for item in data {
+            let update: OkxAlgoOrderUpdate =
+                serde_json::from_value(item).context("Unable to parse OKX algo order update")?;
+            self.handle_algo_order_update(update);
+        }
+
+        Ok(())
+    }
+
+    /// Translates state of OCO algo order into cancellation of its legs. Triggered leg becomes
+    /// ordinary order which is pushed by `orders` channel
+    pub(super) fn handle_algo_order_update(&self, update: OkxAlgoOrderUpdate) {
+        let canceled_legs = match update.state.as_str() {
+            "effective" => match update.actual_side.as_str() {
+                "tp" => vec![AlgoLeg::StopLoss],
+                "sl" => vec![AlgoLeg::TakeProfit],
+                _ => vec![],
+            },
+            "canceled" | "order_failed" => vec![AlgoLeg::TakeProfit, AlgoLeg::StopLoss],
+            _ => {
+                log::trace!("Skipped OKX algo order update {update:?}");
+                return;
+            }
+        };
+
+        let legs = match self.algo_order_legs.get(&update.algo_id) {
+            Some(legs) => canceled_legs
+                .into_iter()
+                .map(|leg| (legs.get(leg).clone(), leg))
+                .collect::<Vec<_>>(),
+            None => {
+                log::warn!(
+                    "Skipped update of OKX algo order which isn't created by engine {update:?}"
+                );
+                return;
+            }
+        };
+        if update.state != "effective" {
+            let _ = self.algo_order_legs.remove(&update.algo_id);
+        }
+
+        for (client_order_id, leg) in legs {
+            (self.order_cancelled_callback)(
+                client_order_id,
+                algo_leg_order_id(&update.algo_id, leg),
+                EventSourceType::WebSocket,
+            );
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum WebsocketMessage {
+    Event(EventMessage),
+    Push { arg: PushArg, data: Vec<Value> },
+}
+
+/// Response to login and subscription requests or error notification
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)]
+struct EventMessage {
+    event: String,
+    code: Option<String>,
+    msg: Option<String>,
+    arg: Option<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PushArg {
+    channel: String,
+    inst_id: Option<SpecificCurrencyPair>,
+}
+
+#[derive(Serialize, Debug)]
+struct SubscriptionRequest {
+    op: &'static str,
+    args: Vec<SubscriptionArg>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SubscriptionArg {
+    channel: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inst_id: Option<SpecificCurrencyPair>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inst_type: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct LoginRequest<'a> {
+    op: &'static str,
+    args: [LoginArg<'a>; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LoginArg<'a> {
+    api_key: &'a str,
+    passphrase: &'a str,
+    timestamp: String,
+    sign: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::order::snapshot::OrderSide;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parse_order_update_without_fill() {
+        let msg = r#"{"arg":{"channel":"orders","instType":"SPOT","uid":"1"},"data":[{"instType":"SPOT","instId":"BTC-USDT","ordId":"312269865356374016","clOrdId":"b1","side":"buy","state":"live","sz":"0.001","px":"20000","tradeId":"","fillPx":"","fillSz":"0","accFillSz":"0","fillFee":"0","fillFeeCcy":"","execType":"","fillTime":"","uTime":"1597026383085"}]}"#;
+
+        let message: WebsocketMessage = serde_json::from_str(msg).expect("in test");
+        let WebsocketMessage::Push { arg, mut data } = message else {
+            panic!("Unexpected message {message:?}");
+        };
+        assert_eq!(arg.channel, "orders");
+
+        let update: OkxOrderUpdate = serde_json::from_value(data.remove(0)).expect("in test");
+        assert_eq!(update.inst_id.as_str(), "BTC-USDT");
+        assert_eq!(update.side, OrderSide::Buy);
+        assert_eq!(update.state, "live");
+        assert_eq!(update.sz, dec!(0.001));
+        assert_eq!(update.fill_px, dec!(0));
+        assert!(update.trade_id.is_empty());
+        assert!(update.fill_time.is_none());
+    }
+
+    #[test]
+    fn parse_algo_order_update() {
+        let msg = r#"{"arg":{"channel":"orders-algo","instType":"SPOT","uid":"1"},"data":[{"instType":"SPOT","instId":"BTC-USDT","ordId":"","algoId":"12345","clOrdId":"","algoClOrdId":"1","sz":"0.001","ordType":"oco","side":"sell","state":"effective","actualSide":"tp","tpTriggerPx":"21000","tpOrdPx":"21000","slTriggerPx":"19000","slOrdPx":"-1","uTime":"1597026383085"}]}"#;
+
+        let message: WebsocketMessage = serde_json::from_str(msg).expect("in test");
+        let WebsocketMessage::Push { arg, mut data } = message else {
+            panic!("Unexpected message {message:?}");
+        };
+        assert_eq!(arg.channel, "orders-algo");
+
+        let update: OkxAlgoOrderUpdate = serde_json::from_value(data.remove(0)).expect("in test");
+        assert_eq!(update.inst_id.as_str(), "BTC-USDT");
+        assert_eq!(update.algo_id, "12345");
+        assert_eq!(update.state, "effective");
+        assert_eq!(update.actual_side, "tp");
+    }
+
+    #[test]
+    fn parse_order_update_triggered_by_algo_order() {
+        let msg = r#"{"instType":"SPOT","instId":"BTC-USDT","ordId":"312269865356374016","clOrdId":"","algoId":"12345","algoClOrdId":"1","ordType":"market","side":"sell","state":"filled","sz":"0.001","tradeId":"1","fillPx":"19000","fillSz":"0.001","accFillSz":"0.001","fillFee":"-0.01","fillFeeCcy":"USDT","execType":"T","fillTime":"1597026383085"}"#;
+
+        let update: OkxOrderUpdate = serde_json::from_str(msg).expect("in test");
+        assert_eq!(update.algo_id, "12345");
+        assert_eq!(update.ord_type, "market");
+        assert!(update.cl_ord_id.as_str().is_empty());
+    }
+
+    #[test]
+    fn parse_login_event() {
+        let msg = r#"{"event":"login","code":"0","msg":""}"#;
+
+        let message: WebsocketMessage = serde_json::from_str(msg).expect("in test");
+        let WebsocketMessage::Event(event) = message else {
+            panic!("Unexpected message {message:?}");
+        };
+        assert_eq!(event.event, "login");
+        assert_eq!(event.code.as_deref(), Some("0"));
+    }
+}
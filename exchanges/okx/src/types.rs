@@ -0,0 +1,295 @@
+use mmb_domain::market::SpecificCurrencyPair;
+use mmb_domain::order::snapshot::{Amount, ClientOrderId, ExchangeOrderId, OrderSide, Price};
+use mmb_utils::time::u64_to_date_time;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+
+/// Common envelope of OKX REST responses
+/// {
+/// "code": "0", // "0" for success, otherwise error code
+/// "msg": "",   // error message
+/// "data": []   // payload
+/// }
+#[derive(Deserialize, Debug)]
+pub struct OkxResponse<T> {
+    pub code: String,
+    pub msg: String,
+    pub data: Vec<T>,
+}
+
+/// Result of single order operation. OKX returns `code` "0" for whole request only if all
+/// operations succeeded, so error of every operation is described by `sCode` and `sMsg`
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxOrderOperationResult {
+    pub ord_id: ExchangeOrderId,
+    #[serde(rename = "sCode")]
+    pub s_code: String,
+    #[serde(rename = "sMsg")]
+    pub s_msg: String,
+}
+
+/// Instrument description from `/api/v5/public/instruments`. Instrument ids look like
+/// `BTC-USDT` for spot and `BTC-USDT-SWAP` for perpetual swaps
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxInstrument {
+    pub inst_id: SpecificCurrencyPair,
+    /// `live`, `suspend`, `preopen` or `test`
+    pub state: String,
+    /// Contract value, empty for spot
+    pub ct_val: String,
+    pub tick_sz: Price,
+    pub lot_sz: Amount,
+    pub min_sz: Amount,
+    #[serde(deserialize_with = "optional_decimal")]
+    pub max_lmt_sz: Option<Amount>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxOrderInfo {
+    pub inst_id: SpecificCurrencyPair,
+    pub ord_id: ExchangeOrderId,
+    pub cl_ord_id: ClientOrderId,
+    #[serde(deserialize_with = "order_side")]
+    pub side: OrderSide,
+    pub state: String,
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub px: Price,
+    pub sz: Amount,
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub avg_px: Price,
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub acc_fill_sz: Amount,
+    /// Fee of order, negative if it's charged
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub fee: Amount,
+    pub fee_ccy: String,
+}
+
+/// Item of `orders` websocket channel. Fill fields are set only if the update is caused by a trade
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxOrderUpdate {
+    pub inst_id: SpecificCurrencyPair,
+    pub ord_id: ExchangeOrderId,
+    pub cl_ord_id: ClientOrderId,
+    #[serde(deserialize_with = "order_side")]
+    pub side: OrderSide,
+    pub state: String,
+    pub sz: Amount,
+    pub trade_id: String,
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub fill_px: Price,
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub fill_sz: Amount,
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub acc_fill_sz: Amount,
+    /// Fee of the fill, negative if it's charged
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub fill_fee: Amount,
+    pub fill_fee_ccy: String,
+    /// `T` for taker, `M` for maker
+    pub exec_type: String,
+    #[serde(deserialize_with = "optional_timestamp")]
+    pub fill_time: Option<DateTime>,
+    /// Id of algo order which triggered the order, empty for ordinary orders
+    #[serde(default)]
+    pub algo_id: String,
+    #[serde(default)]
+    pub ord_type: String,
+}
+
+/// Item of `orders-algo` websocket channel
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxAlgoOrderUpdate {
+    pub inst_id: SpecificCurrencyPair,
+    pub algo_id: String,
+    /// `live`, `effective` when order is triggered, `canceled` or `order_failed`
+    pub state: String,
+    /// `tp` or `sl` for triggered leg of OCO order, empty otherwise
+    #[serde(default)]
+    pub actual_side: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxFill {
+    pub ord_id: ExchangeOrderId,
+    pub trade_id: String,
+    pub fill_px: Price,
+    pub fill_sz: Amount,
+    /// Fee of the fill, negative if it's charged
+    pub fee: Amount,
+    pub fee_ccy: String,
+    /// `T` for taker, `M` for maker
+    pub exec_type: String,
+    #[serde(deserialize_with = "timestamp")]
+    pub ts: DateTime,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxTrade {
+    pub inst_id: SpecificCurrencyPair,
+    pub trade_id: String,
+    pub px: Price,
+    pub sz: Amount,
+    #[serde(deserialize_with = "order_side")]
+    pub side: OrderSide,
+    #[serde(deserialize_with = "timestamp")]
+    pub ts: DateTime,
+}
+
+/// Levels are arrays of price, amount, deprecated field and count of orders
+#[derive(Deserialize, Debug)]
+pub struct OkxOrderBook {
+    pub asks: Vec<(Price, Amount, String, String)>,
+    pub bids: Vec<(Price, Amount, String, String)>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxBalanceInfo {
+    pub details: Vec<OkxBalanceDetails>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxBalanceDetails {
+    pub ccy: String,
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub avail_bal: Amount,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxPosition {
+    pub inst_id: SpecificCurrencyPair,
+    /// Amount of contracts, negative for short position in net mode
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub pos: Amount,
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub avg_px: Price,
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub liq_px: Price,
+    #[serde(deserialize_with = "decimal_or_zero")]
+    pub lever: Decimal,
+    #[serde(deserialize_with = "timestamp")]
+    pub u_time: DateTime,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OkxServerTime {
+    pub ts: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxCreateOrderRequest<'a> {
+    pub inst_id: SpecificCurrencyPair,
+    /// `cash` for spot, `cross` or `isolated` for derivatives
+    pub td_mode: &'a str,
+    pub cl_ord_id: &'a str,
+    pub side: &'a str,
+    pub ord_type: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub px: Option<Price>,
+    pub sz: Amount,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub reduce_only: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxCancelOrderRequest<'a> {
+    pub inst_id: SpecificCurrencyPair,
+    pub ord_id: &'a str,
+}
+
+/// OCO algo order: take profit leg places limit order by `tpOrdPx` and stop loss leg places
+/// market order if `slOrdPx` is `-1`
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxCreateOcoOrderRequest<'a> {
+    pub inst_id: SpecificCurrencyPair,
+    pub td_mode: &'a str,
+    pub algo_cl_ord_id: String,
+    pub side: &'a str,
+    pub ord_type: &'a str,
+    pub sz: Amount,
+    pub tp_trigger_px: Price,
+    pub tp_ord_px: Price,
+    pub sl_trigger_px: Price,
+    pub sl_ord_px: &'a str,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxAlgoOperationResult {
+    pub algo_id: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OkxCancelAlgoRequest<'a> {
+    pub inst_id: SpecificCurrencyPair,
+    pub algo_id: &'a str,
+}
+
+fn decimal_or_zero<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(optional_decimal(deserializer)?.unwrap_or_default())
+}
+
+/// OKX sends empty string instead of number if value isn't set
+fn optional_decimal<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Cow::<str>::deserialize(deserializer)?;
+    match value.is_empty() {
+        true => Ok(None),
+        false => value.parse().map(Some).map_err(de::Error::custom),
+    }
+}
+
+fn timestamp<'de, D>(deserializer: D) -> Result<DateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    optional_timestamp(deserializer)?.ok_or_else(|| de::Error::custom("timestamp is empty"))
+}
+
+/// Timestamps are sent as strings with unix time in milliseconds
+fn optional_timestamp<'de, D>(deserializer: D) -> Result<Option<DateTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Cow::<str>::deserialize(deserializer)?;
+    match value.is_empty() {
+        true => Ok(None),
+        false => value
+            .parse()
+            .map(|x| Some(u64_to_date_time(x)))
+            .map_err(de::Error::custom),
+    }
+}
+
+fn order_side<'de, D>(deserializer: D) -> Result<OrderSide, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Cow::<str>::deserialize(deserializer)?;
+    match value.as_ref() {
+        "buy" => Ok(OrderSide::Buy),
+        "sell" => Ok(OrderSide::Sell),
+        _ => Err(de::Error::custom(format!("unknown order side {value}"))),
+    }
+}
@@ -0,0 +1,528 @@
+pub mod report;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context, Result};
+use dashmap::DashMap;
+use mmb_core::accounting::performance_fee::{calculate_performance_fee, PerformanceFeeSettings};
+use mmb_core::balance::manager::balance_manager::BalanceManager;
+use mmb_core::database::events::recorder::EventRecorder;
+use mmb_core::exchanges::exchange_blocker::ExchangeBlocker;
+use mmb_core::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::exchanges::general::exchange_creation::{create_exchange, create_timeout_manager};
+use mmb_core::exchanges::internal_events_loop::InternalEventsLoop;
+use mmb_core::infrastructure::{init_lifetime_manager, spawn_future};
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::lifecycle::cold_start::ColdStartGuard;
+use mmb_core::lifecycle::launcher::EngineBuildConfig;
+use mmb_core::lifecycle::trading_engine::EngineContext;
+use mmb_core::misc::clock::{Clock, VirtualClock};
+use mmb_core::risk::kill_switch::KillSwitch;
+use mmb_core::risk::loss_limit::LossLimitGuard;
+use mmb_core::settings::{
+    CoreSettings, CurrencyPairSetting, ExchangeSettings, StrategyInstanceSettings,
+};
+use mmb_core::strategy_registry::Strategy;
+use mmb_domain::events::{ExchangeEvent, ExchangeEvents, CHANNEL_MAX_EVENTS_COUNT};
+use mmb_domain::market::{CurrencyCode, ExchangeAccountId};
+use mmb_domain::order::event::OrderEventType;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use mmb_utils::{hashmap, nothing_to_do, DateTime};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use tokio::sync::oneshot;
+
+use crate::backtest::report::{BacktestReport, ReportBuilder};
+use crate::replay::MarketDataReplay;
+use crate::simulated_exchange::{
+    SimulatedExchange, SimulatedExchangeBuilder, SimulationSettings, SIMULATED_EXCHANGE_ID,
+};
+
+/// Count of yields after which engine tasks are considered to have handled the last step of
+/// virtual time
+const SETTLE_YIELDS_COUNT: usize = 100;
+
+pub struct BacktestSettings {
+    /// Markets, initial balances, fee and slippage models. Latency is applied to requests of
+    /// strategy on the virtual clock
+    pub simulation: SimulationSettings,
+    /// Name, timer interval and risk budget of the strategy under test
+    pub strategy: StrategyInstanceSettings,
+    /// Start of replayed data range (inclusive)
+    pub from: DateTime,
+    /// End of replayed data range (exclusive)
+    pub to: DateTime,
+    /// Currency in which portfolio is valued for PnL curve
+    pub valuation_currency_code: CurrencyCode,
+    /// Minimal interval between points of PnL curve
+    pub sampling_interval: Duration,
+    /// Performance fee charged over PnL curve. Statement isn't produced if it isn't set
+    pub performance_fee: Option<PerformanceFeeSettings>,
+}
+
+/// Engine with single simulated exchange account which time is driven by replayed market data
+struct BacktestEngine {
+    clock: Arc<VirtualClock>,
+    lifetime_manager: Arc<AppLifetimeManager>,
+    engine_context: Arc<EngineContext>,
+    exchange: Arc<Exchange>,
+    events: broadcast::Receiver<ExchangeEvent>,
+}
+
+impl BacktestEngine {
+    /// Assembles engine the same way as launcher does, but without database, control panel and
+    /// services which need connection to real exchanges
+    async fn start(settings: &BacktestSettings) -> Result<Self> {
+        let clock = VirtualClock::new(settings.from);
+        let lifetime_manager = init_lifetime_manager();
+
+        let exchange_account_id = ExchangeAccountId::new(SIMULATED_EXCHANGE_ID, 0);
+        let mut exchange_settings =
+            ExchangeSettings::new_short(exchange_account_id, String::new(), String::new(), false);
+        exchange_settings.currency_pairs = Some(
+            settings
+                .simulation
+                .symbols
+                .iter()
+                .map(|symbol| CurrencyPairSetting::Ordinary {
+                    base: symbol.base_currency_code(),
+                    quote: symbol.quote_currency_code(),
+                })
+                .collect(),
+        );
+        let core_settings = CoreSettings {
+            exchanges: vec![exchange_settings.clone()],
+            strategies: vec![settings.strategy.clone()],
+            ..Default::default()
+        };
+
+        let mut build_config = EngineBuildConfig::new(vec![Box::new(
+            SimulatedExchangeBuilder::new(settings.simulation.clone()),
+        )]);
+        build_config.clock = clock.clone();
+
+        let (events_sender, internal_events_receiver) =
+            broadcast::channel(CHANNEL_MAX_EVENTS_COUNT);
+        let events = events_sender.subscribe();
+        let timeout_manager = create_timeout_manager(&core_settings, &build_config);
+        let exchange_blocker = ExchangeBlocker::new(vec![exchange_account_id]);
+        let event_recorder = EventRecorder::start(None, None, Default::default())
+            .await
+            .context("Unable to start EventRecorder")?;
+
+        let exchange = create_exchange(
+            &exchange_settings,
+            &build_config,
+            events_sender.clone(),
+            lifetime_manager.clone(),
+            timeout_manager.clone(),
+            Arc::downgrade(&exchange_blocker),
+            event_recorder.clone(),
+        )
+        .await;
+
+        let balance_manager = BalanceManager::new(
+            CurrencyPairToSymbolConverter::new(hashmap![exchange_account_id => exchange.clone()]),
+            None,
+        );
+        BalanceManager::update_balances_for_exchanges(
+            balance_manager.clone(),
+            lifetime_manager.stop_token(),
+        )
+        .await;
+        exchange.setup_balance_manager(balance_manager.clone());
+
+        let kill_switch = KillSwitch::new();
+        exchange.setup_kill_switch(kill_switch.clone());
+
+        let exchanges = DashMap::new();
+        let _ = exchanges.insert(exchange_account_id, exchange.clone());
+        let (finish_graceful_shutdown_sender, _) = oneshot::channel();
+        let engine_context = EngineContext::new(
+            core_settings,
+            exchanges,
+            ExchangeEvents::new(events_sender),
+            finish_graceful_shutdown_sender,
+            exchange_blocker.clone(),
+            timeout_manager,
+            lifetime_manager.clone(),
+            balance_manager,
+            event_recorder,
+            ColdStartGuard::new(exchange_blocker.clone()),
+            LossLimitGuard::new(exchange_blocker),
+            kill_switch,
+            Vec::new(),
+        );
+
+        spawn_future(
+            "Backtest internal_events_loop start",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            InternalEventsLoop::new().start(
+                internal_events_receiver,
+                hashmap![exchange_account_id => exchange.clone()],
+                lifetime_manager.stop_token(),
+            ),
+        );
+
+        Ok(Self {
+            clock,
+            lifetime_manager,
+            engine_context,
+            exchange,
+            events,
+        })
+    }
+
+    fn simulated_exchange(&self) -> &SimulatedExchange {
+        self.exchange
+            .exchange_client
+            .as_any()
+            .downcast_ref::<SimulatedExchange>()
+            .expect("exchange client should be SimulatedExchange")
+    }
+
+    /// Moves virtual clock to the specified time. Sleeps of engine tasks are completed in order
+    /// of their deadlines, so requests and timers of strategy are handled in the right time
+    async fn advance_to(&self, time: DateTime) {
+        while let Some(deadline) = self.clock.next_deadline().filter(|x| *x <= time) {
+            self.clock.set(deadline);
+            settle().await;
+        }
+
+        self.clock.set(time);
+        settle().await;
+    }
+
+    fn stop(&self) {
+        self.lifetime_manager.stop_token().cancel();
+    }
+}
+
+/// Lets engine tasks handle everything caused by the last step of virtual time. Backtest should
+/// be run on a current-thread runtime, so tasks are polled in the same order in every run
+async fn settle() {
+    for _ in 0..SETTLE_YIELDS_COUNT {
+        tokio::task::yield_now().await;
+    }
+}
+
+/// Runs strategy of the engine over recorded market data. Strategy trades on a simulated exchange
+/// account through the usual order flow of the engine, and time of the engine is a virtual clock
+/// moved by replayed events
+pub struct Backtest {
+    settings: BacktestSettings,
+    report: ReportBuilder,
+    /// Count of fills of the simulated exchange which are already added to report
+    reported_fills_count: usize,
+}
+
+impl Backtest {
+    pub fn new(settings: BacktestSettings) -> Result<Self> {
+        ensure!(
+            settings.from < settings.to,
+            "Backtest range should not be empty: from {} to {}",
+            settings.from,
+            settings.to
+        );
+
+        let report = ReportBuilder::new(
+            settings.valuation_currency_code,
+            chrono::Duration::from_std(settings.sampling_interval)?,
+        );
+
+        Ok(Self {
+            settings,
+            report,
+            reported_fills_count: 0,
+        })
+    }
+
+    pub async fn run(
+        mut self,
+        replay: &MarketDataReplay,
+        strategy: Arc<dyn Strategy>,
+    ) -> Result<BacktestReport> {
+        let mut engine = BacktestEngine::start(&self.settings).await?;
+        let result = self.run_strategy(&mut engine, replay, strategy).await;
+        engine.stop();
+        result?;
+
+        let now = engine.clock.now();
+        let mut report = self
+            .report
+            .finish(now, &engine.simulated_exchange().engine.lock());
+        if let Some(performance_fee) = &self.settings.performance_fee {
+            let equity_series = report
+                .pnl_curve
+                .iter()
+                .map(|x| (x.time, x.equity))
+                .collect::<Vec<_>>();
+            report.performance_fee = Some(calculate_performance_fee(
+                "backtest",
+                performance_fee,
+                &equity_series,
+            ));
+        }
+
+        Ok(report)
+    }
+
+    async fn run_strategy(
+        &mut self,
+        engine: &mut BacktestEngine,
+        replay: &MarketDataReplay,
+        strategy: Arc<dyn Strategy>,
+    ) -> Result<()> {
+        engine.engine_context.strategy_registry.register(
+            engine.engine_context.clone(),
+            &self.settings.strategy.name,
+            strategy,
+        )?;
+        settle().await;
+
+        for event in replay.events(self.settings.from, self.settings.to) {
+            engine.advance_to(event.creation_time).await;
+            engine.simulated_exchange().apply_order_book_event(event);
+            settle().await;
+
+            self.collect(engine)?;
+            self.report.sample(
+                engine.clock.now(),
+                &engine.simulated_exchange().engine.lock(),
+            );
+        }
+
+        engine.advance_to(self.settings.to).await;
+        self.collect(engine)
+    }
+
+    /// Adds results of order requests and fills of the simulated exchange to report
+    fn collect(&mut self, engine: &mut BacktestEngine) -> Result<()> {
+        loop {
+            let order_event = match engine.events.try_recv() {
+                Ok(ExchangeEvent::OrderEvent(order_event)) => order_event,
+                Ok(_) => continue,
+                Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+                Err(TryRecvError::Lagged(skipped)) => {
+                    bail!("Backtest report skipped {skipped} events")
+                }
+            };
+
+            match order_event.event_type {
+                OrderEventType::CreateOrderSucceeded => {
+                    self.report.order_created(order_event.order.header())
+                }
+                OrderEventType::CreateOrderFailed
+                | OrderEventType::RejectedByRisk
+                | OrderEventType::RejectedByBalance
+                | OrderEventType::RejectedByValidation => self.report.order_rejected(),
+                _ => nothing_to_do(),
+            }
+        }
+
+        let matching_engine = engine.simulated_exchange().engine.lock();
+        let fills = &matching_engine.all_fills()[self.reported_fills_count..];
+        for fill in fills {
+            self.report.order_filled(fill);
+        }
+        self.reported_fills_count += fills.len();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use mmb_core::accounting::performance_fee::FeePeriod;
+    use mmb_core::strategy_registry::StrategyContext;
+    use mmb_domain::exchanges::commission::FeeSchedule;
+    use mmb_domain::exchanges::symbol::{Precision, Symbol};
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::snapshot::{OrderSide, Price, UserOrder};
+    use parking_lot::Mutex;
+    use rust_decimal_macros::dec;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::replay::{RecordedEventType, RecordedOrderBookEvent};
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new(SIMULATED_EXCHANGE_ID, 0)
+    }
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    fn time(seconds: u32) -> DateTime {
+        chrono::Utc
+            .with_ymd_and_hms(2022, 11, 17, 10, 0, seconds)
+            .single()
+            .expect("in test")
+    }
+
+    fn settings(latency: Duration) -> BacktestSettings {
+        let symbol = Arc::new(Symbol::new(
+            false,
+            "BTC".into(),
+            "btc".into(),
+            "USDT".into(),
+            "usdt".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "btc".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(0.001) },
+        ));
+
+        BacktestSettings {
+            simulation: SimulationSettings {
+                symbols: vec![symbol],
+                initial_balances: hashmap!["btc".into() => dec!(0), "usdt".into() => dec!(1000)],
+                fees: FeeSchedule::flat(dec!(0), dec!(0.1)),
+                traded_volume: dec!(0),
+                slippage: dec!(0),
+                latency,
+                market_data_source: None,
+            },
+            strategy: StrategyInstanceSettings {
+                name: "buy_once".to_owned(),
+                timer_interval_millis: 1000,
+                risk_budget: None,
+                event_queue: None,
+            },
+            from: time(0),
+            to: time(59),
+            valuation_currency_code: "usdt".into(),
+            sampling_interval: Duration::ZERO,
+            performance_fee: None,
+        }
+    }
+
+    fn replay() -> MarketDataReplay {
+        let event = |seconds, event_type, ask, bid| RecordedOrderBookEvent {
+            time: time(seconds),
+            currency_pair: currency_pair(),
+            event_type,
+            asks: vec![(ask, dec!(1))],
+            bids: vec![(bid, dec!(1))],
+        };
+
+        MarketDataReplay::new(
+            [
+                event(1, RecordedEventType::Snapshot, dec!(101), dec!(99)),
+                event(2, RecordedEventType::Snapshot, dec!(111), dec!(109)),
+                event(3, RecordedEventType::Snapshot, dec!(121), dec!(119)),
+            ]
+            .iter()
+            .map(|x| x.to_order_book_event(exchange_account_id()))
+            .collect(),
+        )
+    }
+
+    /// Buys 1 btc by market on the first order book
+    #[derive(Default)]
+    struct BuyOnce {
+        is_sent: AtomicBool,
+        /// Time and price of fills received by the strategy
+        fills: Mutex<Vec<(DateTime, Price)>>,
+    }
+
+    #[async_trait]
+    impl Strategy for BuyOnce {
+        async fn on_event(&self, ctx: &StrategyContext, event: &ExchangeEvent) -> Result<()> {
+            match event {
+                ExchangeEvent::OrderBookEvent(_) if !self.is_sent.swap(true, Ordering::SeqCst) => {
+                    let _ = ctx
+                        .create_order(
+                            exchange_account_id(),
+                            currency_pair(),
+                            OrderSide::Buy,
+                            dec!(1),
+                            UserOrder::Market,
+                        )
+                        .await?;
+                }
+                ExchangeEvent::OrderEvent(order_event) => {
+                    if let OrderEventType::OrderFilled { cloned_order } = &order_event.event_type {
+                        let fill = cloned_order.fills.fills.last().expect("in test");
+                        self.fills.lock().push((fill.receive_time(), fill.price()));
+                    }
+                }
+                _ => nothing_to_do(),
+            }
+
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn order_is_matched_after_latency() {
+        let strategy = Arc::new(BuyOnce::default());
+
+        let report = Backtest::new(settings(Duration::from_millis(1500)))
+            .expect("in test")
+            .run(&replay(), strategy.clone())
+            .await
+            .expect("in test");
+
+        // order sent at 1s arrives at 2.5s, so it is matched against the second order book
+        let fills = strategy.fills.lock();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(
+            fills[0],
+            (time(1) + chrono::Duration::milliseconds(1500), dec!(111))
+        );
+
+        // bought by 111 with 0.111 commission, valued by mid price 120 in the end
+        assert_eq!(report.summary.final_equity, dec!(1008.889));
+        assert_eq!(report.summary.pnl, dec!(8.889));
+        assert_eq!(report.summary.orders_created, 1);
+        assert_eq!(report.summary.fill_ratio, dec!(1));
+        assert_eq!(report.pairs[0].fills_count, 1);
+        assert_eq!(report.pairs[0].commission, dec!(0.111));
+    }
+
+    #[tokio::test]
+    async fn performance_fee_is_charged_over_pnl_curve() {
+        let mut settings = settings(Duration::from_millis(1500));
+        settings.performance_fee = Some(PerformanceFeeSettings {
+            fee_rate: dec!(0.5),
+            period: FeePeriod::Daily,
+            high_water_mark: None,
+        });
+
+        let report = Backtest::new(settings)
+            .expect("in test")
+            .run(&replay(), Arc::new(BuyOnce::default()))
+            .await
+            .expect("in test");
+
+        let performance_fee = report.performance_fee.expect("in test");
+        assert_eq!(performance_fee.periods.len(), 1);
+        assert_eq!(performance_fee.periods[0].fee_base, dec!(8.889));
+        assert_eq!(performance_fee.total_fee, dec!(4.4445));
+    }
+
+    #[tokio::test]
+    async fn run_is_deterministic() {
+        let run = || async {
+            Backtest::new(settings(Duration::ZERO))
+                .expect("in test")
+                .run(&replay(), Arc::new(BuyOnce::default()))
+                .await
+                .expect("in test")
+                .to_json()
+                .expect("in test")
+        };
+
+        assert_eq!(run().await, run().await);
+    }
+}
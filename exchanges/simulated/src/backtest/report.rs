@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use mmb_core::accounting::performance_fee::PerformanceFeeStatement;
+use mmb_domain::market::{CurrencyCode, CurrencyPair};
+use mmb_domain::order::snapshot::{Amount, ClientOrderId, OrderHeader, OrderSide, Price};
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::matching_engine::{MatchingEngine, SimulatedFill};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PnlPoint {
+    pub time: DateTime,
+    pub equity: Decimal,
+    pub pnl: Decimal,
+    pub drawdown: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PairReport {
+    pub currency_pair: CurrencyPair,
+    pub orders_count: u64,
+    pub fills_count: u64,
+    pub ordered_amount: Amount,
+    pub filled_amount: Amount,
+    /// Filled amount divided by amount of created orders
+    pub fill_ratio: Decimal,
+    pub bought_amount: Amount,
+    pub sold_amount: Amount,
+    /// Traded volume in quote currency
+    pub volume: Decimal,
+    /// Paid commission in quote currency
+    pub commission: Decimal,
+    /// Realized and unrealized PnL in quote currency. Open position is valued by the last mid price.
+    /// `None` if there is open position but no price to value it
+    pub pnl: Option<Decimal>,
+    #[serde(skip)]
+    quote_flow: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestSummary {
+    pub start: DateTime,
+    pub end: DateTime,
+    pub valuation_currency_code: CurrencyCode,
+    pub initial_equity: Decimal,
+    pub final_equity: Decimal,
+    pub pnl: Decimal,
+    pub max_drawdown: Decimal,
+    pub max_drawdown_percent: Decimal,
+    pub orders_created: u64,
+    pub orders_rejected: u64,
+    pub filled_orders: u64,
+    /// Share of created orders that got at least one fill
+    pub fill_ratio: Decimal,
+    pub fills_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    pub summary: BacktestSummary,
+    pub pnl_curve: Vec<PnlPoint>,
+    pub pairs: Vec<PairReport>,
+    pub performance_fee: Option<PerformanceFeeStatement>,
+}
+
+impl BacktestReport {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn pnl_curve_csv(&self) -> String {
+        let mut csv = "time,equity,pnl,drawdown\n".to_owned();
+        for point in &self.pnl_curve {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{}",
+                point.time.to_rfc3339(),
+                point.equity,
+                point.pnl,
+                point.drawdown
+            );
+        }
+        csv
+    }
+
+    pub fn pairs_csv(&self) -> String {
+        let mut csv = "currency_pair,orders_count,fills_count,ordered_amount,filled_amount,\
+            fill_ratio,bought_amount,sold_amount,volume,commission,pnl\n"
+            .to_owned();
+        for pair in &self.pairs {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                pair.currency_pair,
+                pair.orders_count,
+                pair.fills_count,
+                pair.ordered_amount,
+                pair.filled_amount,
+                pair.fill_ratio,
+                pair.bought_amount,
+                pair.sold_amount,
+                pair.volume,
+                pair.commission,
+                pair.pnl.map(|x| x.to_string()).unwrap_or_default()
+            );
+        }
+        csv
+    }
+
+    /// Writes `report.json`, `pnl_curve.csv`, `pairs.csv` and `performance_fee.csv` (if
+    /// performance fee is calculated) to the specified directory
+    pub fn save(&self, directory: &Path) -> Result<()> {
+        fs::create_dir_all(directory)
+            .with_context(|| format!("Unable to create directory {}", directory.display()))?;
+
+        let mut files = vec![
+            ("report.json", self.to_json()?),
+            ("pnl_curve.csv", self.pnl_curve_csv()),
+            ("pairs.csv", self.pairs_csv()),
+        ];
+        if let Some(performance_fee) = &self.performance_fee {
+            files.push(("performance_fee.csv", performance_fee.periods_csv()));
+        }
+        for (file_name, content) in files {
+            let path = directory.join(file_name);
+            fs::write(&path, content)
+                .with_context(|| format!("Unable to write {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects statistics of backtest run
+pub(crate) struct ReportBuilder {
+    valuation_currency_code: CurrencyCode,
+    sampling_interval: chrono::Duration,
+    start: Option<DateTime>,
+    initial_equity: Option<Decimal>,
+    peak_equity: Decimal,
+    max_drawdown: Decimal,
+    max_drawdown_percent: Decimal,
+    pnl_curve: Vec<PnlPoint>,
+    orders_created: u64,
+    orders_rejected: u64,
+    filled_orders: HashSet<ClientOrderId>,
+    pairs: HashMap<CurrencyPair, PairReport>,
+}
+
+impl ReportBuilder {
+    pub fn new(valuation_currency_code: CurrencyCode, sampling_interval: chrono::Duration) -> Self {
+        Self {
+            valuation_currency_code,
+            sampling_interval,
+            start: None,
+            initial_equity: None,
+            peak_equity: dec!(0),
+            max_drawdown: dec!(0),
+            max_drawdown_percent: dec!(0),
+            pnl_curve: Vec::new(),
+            orders_created: 0,
+            orders_rejected: 0,
+            filled_orders: HashSet::new(),
+            pairs: HashMap::new(),
+        }
+    }
+
+    pub fn order_created(&mut self, header: &OrderHeader) {
+        self.orders_created += 1;
+
+        let pair = self.pair(header.currency_pair);
+        pair.orders_count += 1;
+        pair.ordered_amount += header.amount;
+    }
+
+    pub fn order_rejected(&mut self) {
+        self.orders_rejected += 1;
+    }
+
+    pub fn order_filled(&mut self, fill: &SimulatedFill) {
+        let _ = self.filled_orders.insert(fill.client_order_id.clone());
+
+        let cost = fill.price * fill.amount;
+        let pair = self.pair(fill.currency_pair);
+        pair.fills_count += 1;
+        pair.filled_amount += fill.amount;
+        pair.volume += cost;
+        pair.commission += fill.commission_amount;
+        match fill.side {
+            OrderSide::Buy => {
+                pair.bought_amount += fill.amount;
+                pair.quote_flow -= cost;
+            }
+            OrderSide::Sell => {
+                pair.sold_amount += fill.amount;
+                pair.quote_flow += cost;
+            }
+        }
+    }
+
+    /// Adds point to PnL curve if sampling interval passed since the previous point
+    pub fn sample(&mut self, now: DateTime, engine: &MatchingEngine) {
+        let is_sampling_time = self
+            .pnl_curve
+            .last()
+            .is_none_or(|last| now - last.time >= self.sampling_interval);
+        if is_sampling_time {
+            self.add_point(now, engine);
+        }
+    }
+
+    pub fn finish(mut self, now: DateTime, engine: &MatchingEngine) -> BacktestReport {
+        if self.pnl_curve.last().map(|x| x.time) != Some(now) {
+            self.add_point(now, engine);
+        }
+
+        let initial_equity = self.initial_equity.unwrap_or_default();
+        let final_equity = self
+            .pnl_curve
+            .last()
+            .map(|x| x.equity)
+            .unwrap_or(initial_equity);
+
+        let mut pairs = self
+            .pairs
+            .into_values()
+            .map(|mut pair| {
+                if !pair.ordered_amount.is_zero() {
+                    pair.fill_ratio = pair.filled_amount / pair.ordered_amount;
+                }
+
+                let position = pair.bought_amount - pair.sold_amount;
+                let position_value = match position.is_zero() {
+                    true => Some(dec!(0)),
+                    false => mid_price(engine, pair.currency_pair).map(|price| position * price),
+                };
+                pair.pnl = position_value.map(|x| pair.quote_flow - pair.commission + x);
+
+                pair
+            })
+            .collect::<Vec<_>>();
+        pairs.sort_by(|a, b| a.currency_pair.as_str().cmp(b.currency_pair.as_str()));
+
+        let filled_orders = self.filled_orders.len() as u64;
+        let fill_ratio = match self.orders_created {
+            0 => dec!(0),
+            orders_created => Decimal::from(filled_orders) / Decimal::from(orders_created),
+        };
+
+        BacktestReport {
+            summary: BacktestSummary {
+                start: self.start.unwrap_or(now),
+                end: now,
+                valuation_currency_code: self.valuation_currency_code,
+                initial_equity,
+                final_equity,
+                pnl: final_equity - initial_equity,
+                max_drawdown: self.max_drawdown,
+                max_drawdown_percent: self.max_drawdown_percent,
+                orders_created: self.orders_created,
+                orders_rejected: self.orders_rejected,
+                filled_orders,
+                fill_ratio,
+                fills_count: pairs.iter().map(|x| x.fills_count).sum(),
+            },
+            pnl_curve: self.pnl_curve,
+            pairs,
+            performance_fee: None,
+        }
+    }
+
+    fn add_point(&mut self, now: DateTime, engine: &MatchingEngine) {
+        // curve starts when all balances can be valued
+        let equity = match self.equity(engine) {
+            Some(equity) => equity,
+            None => return,
+        };
+
+        let _ = self.start.get_or_insert(now);
+        let initial_equity = *self.initial_equity.get_or_insert(equity);
+        self.peak_equity = self.peak_equity.max(equity);
+
+        let drawdown = self.peak_equity - equity;
+        self.max_drawdown = self.max_drawdown.max(drawdown);
+        if self.peak_equity > dec!(0) {
+            let drawdown_percent = drawdown / self.peak_equity * dec!(100);
+            self.max_drawdown_percent = self.max_drawdown_percent.max(drawdown_percent);
+        }
+
+        self.pnl_curve.push(PnlPoint {
+            time: now,
+            equity,
+            pnl: equity - initial_equity,
+            drawdown,
+        });
+    }
+
+    /// Sum of balances in valuation currency. `None` if some balance can't be valued yet
+    fn equity(&self, engine: &MatchingEngine) -> Option<Decimal> {
+        engine
+            .balances()
+            .iter()
+            .filter(|(_, balance)| !balance.is_zero())
+            .map(|(&currency_code, &balance)| {
+                value_in(engine, currency_code, self.valuation_currency_code)
+                    .map(|price| balance * price)
+            })
+            .sum()
+    }
+
+    fn pair(&mut self, currency_pair: CurrencyPair) -> &mut PairReport {
+        self.pairs
+            .entry(currency_pair)
+            .or_insert_with(|| PairReport {
+                currency_pair,
+                orders_count: 0,
+                fills_count: 0,
+                ordered_amount: dec!(0),
+                filled_amount: dec!(0),
+                fill_ratio: dec!(0),
+                bought_amount: dec!(0),
+                sold_amount: dec!(0),
+                volume: dec!(0),
+                commission: dec!(0),
+                pnl: None,
+                quote_flow: dec!(0),
+            })
+    }
+}
+
+fn mid_price(engine: &MatchingEngine, currency_pair: CurrencyPair) -> Option<Price> {
+    let order_book = engine.order_book(currency_pair)?;
+    match (order_book.get_top_ask(), order_book.get_top_bid()) {
+        (Some((ask, _)), Some((bid, _))) => Some((ask + bid) / dec!(2)),
+        (Some((price, _)), None) | (None, Some((price, _))) => Some(price),
+        (None, None) => None,
+    }
+}
+
+/// Price of currency in valuation currency by mid price of direct or inverse market
+fn value_in(
+    engine: &MatchingEngine,
+    currency_code: CurrencyCode,
+    valuation_currency_code: CurrencyCode,
+) -> Option<Price> {
+    if currency_code == valuation_currency_code {
+        return Some(dec!(1));
+    }
+
+    engine.symbols().find_map(|symbol| {
+        let (base, quote) = (symbol.base_currency_code(), symbol.quote_currency_code());
+        if base == currency_code && quote == valuation_currency_code {
+            mid_price(engine, symbol.currency_pair())
+        } else if base == valuation_currency_code && quote == currency_code {
+            mid_price(engine, symbol.currency_pair())
+                .filter(|price| !price.is_zero())
+                .map(|price| dec!(1) / price)
+        } else {
+            None
+        }
+    })
+}
@@ -0,0 +1,214 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use mmb_core::exchanges::general::exchange::RequestResult;
+use mmb_core::exchanges::general::order::cancel::CancelOrderResult;
+use mmb_core::exchanges::general::order::create::CreateOrderResult;
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::traits::{ExchangeClient, ExchangeError};
+use mmb_domain::events::{EventSourceType, ExchangeBalance, ExchangeBalancesAndPositions, TradeId};
+use mmb_domain::exchanges::symbol::Symbol;
+use mmb_domain::market::{CurrencyPair, ExchangeErrorType};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{ExchangeOrderId, OrderInfo, Price};
+use mmb_domain::position::{ActivePosition, ClosedPosition};
+use mmb_utils::DateTime;
+
+use crate::matching_engine::{MatchingEngine, SimulatedOrder};
+use crate::simulated_exchange::SimulatedExchange;
+
+#[async_trait]
+impl ExchangeClient for SimulatedExchange {
+    async fn create_order(&self, order: &OrderRef) -> CreateOrderResult {
+        self.clock.sleep(self.simulation_settings.latency).await;
+
+        let result = self
+            .engine
+            .lock()
+            .create_order(order.header(), self.clock.now());
+
+        match result {
+            Ok((exchange_order_id, fills)) => {
+                // acknowledgement is delivered before fills like it is done by exchanges streams
+                (self.order_created_callback)(
+                    order.client_order_id(),
+                    exchange_order_id.clone(),
+                    EventSourceType::WebSocket,
+                );
+                self.report_fills(fills);
+
+                CreateOrderResult::succeed(&exchange_order_id, EventSourceType::Rest)
+            }
+            Err(error) => CreateOrderResult::failed(error, EventSourceType::Rest),
+        }
+    }
+
+    async fn cancel_order(
+        &self,
+        order: &OrderRef,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> CancelOrderResult {
+        self.clock.sleep(self.simulation_settings.latency).await;
+
+        let client_order_id = order.client_order_id();
+        let result = self
+            .engine
+            .lock()
+            .cancel_order(&client_order_id)
+            .map(|x| x.filled_amount);
+
+        match result {
+            Ok(filled_amount) => {
+                (self.order_cancelled_callback)(
+                    client_order_id.clone(),
+                    exchange_order_id.clone(),
+                    EventSourceType::WebSocket,
+                );
+
+                CancelOrderResult::succeed(
+                    client_order_id,
+                    EventSourceType::Rest,
+                    Some(filled_amount),
+                )
+            }
+            Err(error) => CancelOrderResult::failed(error, EventSourceType::Rest),
+        }
+    }
+
+    async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        self.clock.sleep(self.simulation_settings.latency).await;
+
+        let canceled_orders = {
+            let mut engine = self.engine.lock();
+            let client_order_ids = engine
+                .open_orders()
+                .filter(|x| x.currency_pair == currency_pair)
+                .map(|x| x.client_order_id.clone())
+                .collect::<Vec<_>>();
+
+            client_order_ids
+                .iter()
+                .filter_map(|client_order_id| engine.cancel_order(client_order_id).ok().cloned())
+                .collect::<Vec<_>>()
+        };
+
+        for order in canceled_orders {
+            (self.order_cancelled_callback)(
+                order.client_order_id,
+                order.exchange_order_id,
+                EventSourceType::WebSocket,
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn get_open_orders(&self) -> Result<Vec<OrderInfo>> {
+        let engine = self.engine.lock();
+        Ok(engine
+            .open_orders()
+            .map(|order| order_info(&engine, order))
+            .collect())
+    }
+
+    async fn get_open_orders_by_currency_pair(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<Vec<OrderInfo>> {
+        let engine = self.engine.lock();
+        Ok(engine
+            .open_orders()
+            .filter(|x| x.currency_pair == currency_pair)
+            .map(|order| order_info(&engine, order))
+            .collect())
+    }
+
+    async fn get_order_info(&self, order: &OrderRef) -> Result<OrderInfo, ExchangeError> {
+        let client_order_id = order.client_order_id();
+        let engine = self.engine.lock();
+        match engine.get_order(&client_order_id) {
+            Some(order) => Ok(order_info(&engine, order)),
+            None => Err(ExchangeError::new(
+                ExchangeErrorType::OrderNotFound,
+                format!("Order {client_order_id} not found"),
+                None,
+            )),
+        }
+    }
+
+    async fn close_position(
+        &self,
+        _position: &ActivePosition,
+        _price: Option<Price>,
+    ) -> Result<ClosedPosition> {
+        Err(anyhow!("Positions aren't supported by simulated exchange"))
+    }
+
+    async fn get_active_positions(&self) -> Result<Vec<ActivePosition>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_balance_and_positions(&self) -> Result<ExchangeBalancesAndPositions> {
+        let balances = self
+            .engine
+            .lock()
+            .balances()
+            .iter()
+            .map(|(&currency_code, &balance)| ExchangeBalance {
+                currency_code,
+                balance,
+            })
+            .collect();
+
+        Ok(ExchangeBalancesAndPositions {
+            balances,
+            positions: None,
+        })
+    }
+
+    async fn get_my_trades(
+        &self,
+        symbol: &Symbol,
+        from_datetime: Option<DateTime>,
+    ) -> RequestResult<Vec<OrderTrade>> {
+        let engine = self.engine.lock();
+        let trades = engine
+            .fills(symbol.currency_pair())
+            .filter(|fill| from_datetime.is_none_or(|from| fill.datetime >= from))
+            .map(|fill| {
+                OrderTrade::new(
+                    fill.exchange_order_id.clone(),
+                    TradeId::Number(fill.trade_id),
+                    fill.datetime,
+                    fill.price,
+                    fill.amount,
+                    fill.role,
+                    fill.commission_currency_code,
+                    Some(fill.commission_rate),
+                    Some(fill.commission_amount),
+                    OrderFillType::UserTrade,
+                )
+            })
+            .collect();
+
+        RequestResult::Success(trades)
+    }
+
+    async fn build_all_symbols(&self) -> Result<Vec<Arc<Symbol>>> {
+        Ok(self.simulation_settings.symbols.clone())
+    }
+
+    async fn get_server_time(&self) -> Option<Result<i64>> {
+        None
+    }
+}
+
+fn order_info(engine: &MatchingEngine, order: &SimulatedOrder) -> OrderInfo {
+    let commission_currency_code = engine
+        .commission_currency_code(order.currency_pair)
+        .expect("symbol should exist for simulated order");
+
+    order.to_order_info(commission_currency_code)
+}
@@ -0,0 +1,862 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use mmb_core::exchanges::traits::ExchangeError;
+use mmb_core::math::ConvertPercentToRate;
+use mmb_domain::exchanges::commission::FeeSchedule;
+use mmb_domain::exchanges::symbol::{BeforeAfter, Symbol};
+use mmb_domain::market::{CurrencyCode, CurrencyPair, ExchangeErrorType};
+use mmb_domain::order::snapshot::{
+    Amount, ClientOrderId, ExchangeOrderId, OrderExecutionType, OrderHeader, OrderInfo, OrderRole,
+    OrderSide, OrderStatus, OrderType, Price, SortedOrderData,
+};
+use mmb_domain::order_book::event::{EventType, OrderBookEvent};
+use mmb_domain::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// State of order accepted by the simulated exchange
+#[derive(Debug, Clone)]
+pub(crate) struct SimulatedOrder {
+    pub client_order_id: ClientOrderId,
+    pub exchange_order_id: ExchangeOrderId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    /// `None` for market orders
+    pub price: Option<Price>,
+    pub amount: Amount,
+    pub filled_amount: Amount,
+    pub filled_cost: Decimal,
+    pub commission_amount: Amount,
+    pub status: OrderStatus,
+    /// Orders created earlier are matched first
+    sequence: u64,
+}
+
+impl SimulatedOrder {
+    fn remaining_amount(&self) -> Amount {
+        self.amount - self.filled_amount
+    }
+
+    pub fn to_order_info(&self, commission_currency_code: CurrencyCode) -> OrderInfo {
+        let average_fill_price = match self.filled_amount.is_zero() {
+            true => dec!(0),
+            false => self.filled_cost / self.filled_amount,
+        };
+
+        OrderInfo::new(
+            self.currency_pair,
+            self.exchange_order_id.clone(),
+            self.client_order_id.clone(),
+            self.side,
+            self.status,
+            self.price.unwrap_or_default(),
+            self.amount,
+            average_fill_price,
+            self.filled_amount,
+            Some(commission_currency_code.to_string()),
+            None,
+            Some(self.commission_amount),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulatedFill {
+    pub trade_id: u64,
+    pub client_order_id: ClientOrderId,
+    pub exchange_order_id: ExchangeOrderId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub price: Price,
+    pub amount: Amount,
+    pub total_filled_amount: Amount,
+    pub role: OrderRole,
+    pub commission_currency_code: CurrencyCode,
+    pub commission_rate: Decimal,
+    pub commission_amount: Amount,
+    pub datetime: DateTime,
+}
+
+/// Matches orders against the order book of a real market.
+/// Liquidity consumed by simulated orders is removed from local copy of the order book until
+/// the next update of the affected price levels, so the same liquidity isn't used twice.
+/// Only spot markets are supported: fills are settled in base and quote currencies balances
+pub(crate) struct MatchingEngine {
+    symbols: HashMap<CurrencyPair, Arc<Symbol>>,
+    fees: FeeSchedule,
+    /// Trading volume which determines fee tier. It grows by cost of every fill
+    traded_volume: Decimal,
+    /// Rate by which taker fill prices are worsened
+    slippage_rate: Decimal,
+    balances: HashMap<CurrencyCode, Amount>,
+    order_books: HashMap<CurrencyPair, LocalOrderBookSnapshot>,
+    orders: HashMap<ClientOrderId, SimulatedOrder>,
+    fills: Vec<SimulatedFill>,
+    last_order_id: u64,
+    last_trade_id: u64,
+}
+
+impl MatchingEngine {
+    pub fn new(
+        symbols: &[Arc<Symbol>],
+        fees: FeeSchedule,
+        traded_volume: Decimal,
+        slippage: Decimal,
+        balances: HashMap<CurrencyCode, Amount>,
+    ) -> Self {
+        Self {
+            symbols: symbols
+                .iter()
+                .map(|symbol| (symbol.currency_pair(), symbol.clone()))
+                .collect(),
+            fees,
+            traded_volume,
+            slippage_rate: slippage.percent_to_rate(),
+            balances,
+            order_books: HashMap::new(),
+            orders: HashMap::new(),
+            fills: Vec::new(),
+            last_order_id: 0,
+            last_trade_id: 0,
+        }
+    }
+
+    pub fn balances(&self) -> &HashMap<CurrencyCode, Amount> {
+        &self.balances
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &Arc<Symbol>> {
+        self.symbols.values()
+    }
+
+    pub fn order_book(&self, currency_pair: CurrencyPair) -> Option<&LocalOrderBookSnapshot> {
+        self.order_books.get(&currency_pair)
+    }
+
+    pub fn get_order(&self, client_order_id: &ClientOrderId) -> Option<&SimulatedOrder> {
+        self.orders.get(client_order_id)
+    }
+
+    pub fn open_orders(&self) -> impl Iterator<Item = &SimulatedOrder> {
+        self.orders.values().filter(|x| !x.status.is_finished())
+    }
+
+    /// Fills of all markets in order of execution
+    pub fn all_fills(&self) -> &[SimulatedFill] {
+        &self.fills
+    }
+
+    pub fn fills(&self, currency_pair: CurrencyPair) -> impl Iterator<Item = &SimulatedFill> {
+        self.fills
+            .iter()
+            .filter(move |x| x.currency_pair == currency_pair)
+    }
+
+    pub fn commission_currency_code(&self, currency_pair: CurrencyPair) -> Option<CurrencyCode> {
+        self.symbols
+            .get(&currency_pair)
+            .map(|symbol| symbol.quote_currency_code())
+    }
+
+    /// Applies snapshot or update of order book. Returns `None` if the event was skipped because
+    /// currency pair isn't simulated or there is no snapshot to apply update to
+    pub fn apply_order_book_event(
+        &mut self,
+        event: &OrderBookEvent,
+        now: DateTime,
+    ) -> Option<Vec<SimulatedFill>> {
+        let currency_pair = event.currency_pair;
+        if !self.symbols.contains_key(&currency_pair) {
+            return None;
+        }
+
+        let order_book = match (event.event_type, self.order_books.get(&currency_pair)) {
+            (EventType::Snapshot, _) => event.to_orderbook_snapshot(),
+            (EventType::Update, Some(order_book)) => {
+                let mut order_book = order_book.clone();
+                order_book.apply_update(&event.data, event.creation_time);
+                order_book
+            }
+            (EventType::Update, None) => return None,
+        };
+
+        Some(self.apply_order_book(currency_pair, order_book, now))
+    }
+
+    /// Replaces order book of market and fills resting orders crossed by it
+    pub fn apply_order_book(
+        &mut self,
+        currency_pair: CurrencyPair,
+        order_book: LocalOrderBookSnapshot,
+        now: DateTime,
+    ) -> Vec<SimulatedFill> {
+        let _ = self.order_books.insert(currency_pair, order_book);
+        self.match_resting_orders(currency_pair, now)
+    }
+
+    /// Accepts order and immediately matches its crossing part as taker
+    pub fn create_order(
+        &mut self,
+        header: &OrderHeader,
+        now: DateTime,
+    ) -> Result<(ExchangeOrderId, Vec<SimulatedFill>), ExchangeError> {
+        let currency_pair = header.currency_pair;
+        let symbol = self.symbols.get(&currency_pair).cloned().ok_or_else(|| {
+            invalid_order(format!(
+                "Unknown currency pair {currency_pair} in simulation"
+            ))
+        })?;
+
+        if self.orders.contains_key(&header.client_order_id) {
+            return Err(invalid_order(format!(
+                "Order {} already exists",
+                header.client_order_id
+            )));
+        }
+
+        let price = match header.order_type {
+            OrderType::Market => None,
+            OrderType::Limit => Some(header.source_price.ok_or_else(|| {
+                invalid_order(format!(
+                    "Limit order {} without price",
+                    header.client_order_id
+                ))
+            })?),
+            order_type => {
+                return Err(invalid_order(format!(
+                    "Order type {order_type:?} isn't supported in simulation"
+                )))
+            }
+        };
+
+        let crosses_book = self
+            .best_opposite_price(currency_pair, header.side)
+            .is_some_and(|best_price| match price {
+                None => true,
+                Some(price) => is_crossed(header.side, price, best_price),
+            });
+
+        let execution_type = header.options.execution_type();
+        let is_maker_only = execution_type == Some(OrderExecutionType::MakerOnly);
+        if is_maker_only && crosses_book {
+            return Err(invalid_order(format!(
+                "Maker only order {} would immediately match",
+                header.client_order_id
+            )));
+        }
+
+        let required = self.required_balance(&symbol, header.side, header.amount, price)?;
+        let currency_code = symbol.get_trade_code(header.side, BeforeAfter::Before);
+        if self.available_balance(currency_code) < required {
+            return Err(ExchangeError::new(
+                ExchangeErrorType::InsufficientFunds,
+                format!("Not enough {currency_code} to create order {required}"),
+                None,
+            ));
+        }
+
+        self.last_order_id += 1;
+        let exchange_order_id = ExchangeOrderId::from(self.last_order_id);
+        let order = SimulatedOrder {
+            client_order_id: header.client_order_id.clone(),
+            exchange_order_id: exchange_order_id.clone(),
+            currency_pair,
+            side: header.side,
+            price,
+            amount: header.amount,
+            filled_amount: dec!(0),
+            filled_cost: dec!(0),
+            commission_amount: dec!(0),
+            status: OrderStatus::Created,
+            sequence: self.last_order_id,
+        };
+        let _ = self.orders.insert(header.client_order_id.clone(), order);
+
+        let fills = self.match_order(&header.client_order_id, OrderRole::Taker, now);
+
+        if price.is_none() || execution_type == Some(OrderExecutionType::ImmediateOrCancel) {
+            // rest of market or immediate or cancel order can't be placed in the order book
+            if let Some(order) = self.orders.get_mut(&header.client_order_id) {
+                if !order.status.is_finished() {
+                    order.status = OrderStatus::Canceled;
+                }
+            }
+        }
+
+        Ok((exchange_order_id, fills))
+    }
+
+    pub fn cancel_order(
+        &mut self,
+        client_order_id: &ClientOrderId,
+    ) -> Result<&SimulatedOrder, ExchangeError> {
+        let order = self.orders.get_mut(client_order_id).ok_or_else(|| {
+            ExchangeError::new(
+                ExchangeErrorType::OrderNotFound,
+                format!("Order {client_order_id} not found"),
+                None,
+            )
+        })?;
+
+        match order.status {
+            OrderStatus::Completed => Err(ExchangeError::new(
+                ExchangeErrorType::OrderCompleted,
+                format!("Order {client_order_id} is already completed"),
+                None,
+            )),
+            OrderStatus::Canceled => Err(ExchangeError::new(
+                ExchangeErrorType::OrderNotFound,
+                format!("Order {client_order_id} is already canceled"),
+                None,
+            )),
+            _ => {
+                order.status = OrderStatus::Canceled;
+                Ok(order)
+            }
+        }
+    }
+
+    fn best_opposite_price(&self, currency_pair: CurrencyPair, side: OrderSide) -> Option<Price> {
+        let order_book = self.order_books.get(&currency_pair)?;
+        order_book
+            .get_top(side.change_side())
+            .map(|(price, _)| price)
+    }
+
+    /// Balance required to create order. Market buy order is charged by price levels it would
+    /// take and taker commission, because its cost isn't limited by price
+    fn required_balance(
+        &self,
+        symbol: &Symbol,
+        side: OrderSide,
+        amount: Amount,
+        price: Option<Price>,
+    ) -> Result<Amount, ExchangeError> {
+        match (side, price) {
+            (OrderSide::Sell, _) => Ok(amount),
+            (OrderSide::Buy, Some(price)) => Ok(amount * price * (dec!(1) + self.slippage_rate)),
+            (OrderSide::Buy, None) => {
+                let cost = self.market_buy_cost(symbol.currency_pair(), amount)?;
+                let commission_rate = self
+                    .fees
+                    .commission(self.traded_volume)
+                    .get_commission(OrderRole::Taker)
+                    .fee
+                    .percent_to_rate();
+                Ok(cost * (dec!(1) + commission_rate))
+            }
+        }
+    }
+
+    /// Cost of the part of market buy order which can be filled by current asks
+    fn market_buy_cost(
+        &self,
+        currency_pair: CurrencyPair,
+        mut amount: Amount,
+    ) -> Result<Decimal, ExchangeError> {
+        let asks = self
+            .order_books
+            .get(&currency_pair)
+            .map(|x| &x.asks)
+            .filter(|x| !x.is_empty())
+            .ok_or_else(|| {
+                invalid_order(format!(
+                    "There is no liquidity for market order on {currency_pair}"
+                ))
+            })?;
+
+        let mut cost = dec!(0);
+        for (&level_price, &level_amount) in asks.iter() {
+            if amount <= dec!(0) {
+                break;
+            }
+
+            let matched_amount = amount.min(level_amount);
+            cost += matched_amount * level_price * (dec!(1) + self.slippage_rate);
+            amount -= matched_amount;
+        }
+
+        Ok(cost)
+    }
+
+    /// Balance that isn't locked by open orders
+    fn available_balance(&self, currency_code: CurrencyCode) -> Amount {
+        let balance = self
+            .balances
+            .get(&currency_code)
+            .copied()
+            .unwrap_or_default();
+
+        let locked: Amount = self
+            .open_orders()
+            .filter_map(|order| {
+                let symbol = self.symbols.get(&order.currency_pair)?;
+                let order_currency_code = symbol.get_trade_code(order.side, BeforeAfter::Before);
+                if order_currency_code != currency_code {
+                    return None;
+                }
+
+                match order.side {
+                    OrderSide::Sell => Some(order.remaining_amount()),
+                    OrderSide::Buy => order.price.map(|price| order.remaining_amount() * price),
+                }
+            })
+            .sum();
+
+        balance - locked
+    }
+
+    fn match_resting_orders(
+        &mut self,
+        currency_pair: CurrencyPair,
+        now: DateTime,
+    ) -> Vec<SimulatedFill> {
+        let mut resting_orders = self
+            .open_orders()
+            .filter(|x| x.currency_pair == currency_pair)
+            .map(|x| (x.sequence, x.client_order_id.clone()))
+            .collect::<Vec<_>>();
+        resting_orders.sort_by_key(|(sequence, _)| *sequence);
+
+        resting_orders
+            .into_iter()
+            .flat_map(|(_, client_order_id)| {
+                self.match_order(&client_order_id, OrderRole::Maker, now)
+            })
+            .collect()
+    }
+
+    /// Matches order against opposite side of the order book.
+    /// Taker is filled by prices of price levels worsened by slippage, maker is filled by its own price
+    fn match_order(
+        &mut self,
+        client_order_id: &ClientOrderId,
+        role: OrderRole,
+        now: DateTime,
+    ) -> Vec<SimulatedFill> {
+        let order = match self.orders.get(client_order_id) {
+            Some(order) if !order.status.is_finished() => order.clone(),
+            _ => return Vec::new(),
+        };
+
+        let order_book = match self.order_books.get_mut(&order.currency_pair) {
+            Some(order_book) => order_book,
+            None => return Vec::new(),
+        };
+
+        let opposite_levels = match order.side {
+            OrderSide::Buy => &mut order_book.asks,
+            OrderSide::Sell => &mut order_book.bids,
+        };
+
+        let matched_levels = take_liquidity(
+            opposite_levels,
+            order.side,
+            order.price,
+            order.remaining_amount(),
+        );
+
+        let slippage_rate = self.slippage_rate;
+        matched_levels
+            .into_iter()
+            .map(|(level_price, amount)| {
+                let price = match (role, order.side) {
+                    (OrderRole::Taker, OrderSide::Buy) => level_price * (dec!(1) + slippage_rate),
+                    (OrderRole::Taker, OrderSide::Sell) => level_price * (dec!(1) - slippage_rate),
+                    (OrderRole::Maker, _) => order.price.unwrap_or(level_price),
+                };
+                self.fill_order(client_order_id, price, amount, role, now)
+            })
+            .collect()
+    }
+
+    fn fill_order(
+        &mut self,
+        client_order_id: &ClientOrderId,
+        price: Price,
+        amount: Amount,
+        role: OrderRole,
+        now: DateTime,
+    ) -> SimulatedFill {
+        let order = self
+            .orders
+            .get_mut(client_order_id)
+            .expect("order should exist during matching");
+        let symbol = self
+            .symbols
+            .get(&order.currency_pair)
+            .expect("symbol should exist for order");
+
+        let commission_rate = self
+            .fees
+            .commission(self.traded_volume)
+            .get_commission(role)
+            .fee
+            .percent_to_rate();
+        let cost = amount * price;
+        let commission_amount = cost * commission_rate;
+        self.traded_volume += cost;
+        let commission_currency_code = symbol.quote_currency_code();
+
+        order.filled_amount += amount;
+        order.filled_cost += cost;
+        order.commission_amount += commission_amount;
+        if order.remaining_amount().is_zero() {
+            order.status = OrderStatus::Completed;
+        }
+
+        let (base_diff, quote_diff) = match order.side {
+            OrderSide::Buy => (amount, -cost),
+            OrderSide::Sell => (-amount, cost),
+        };
+        *self
+            .balances
+            .entry(symbol.base_currency_code())
+            .or_default() += base_diff;
+        *self
+            .balances
+            .entry(symbol.quote_currency_code())
+            .or_default() += quote_diff - commission_amount;
+
+        self.last_trade_id += 1;
+        let fill = SimulatedFill {
+            trade_id: self.last_trade_id,
+            client_order_id: order.client_order_id.clone(),
+            exchange_order_id: order.exchange_order_id.clone(),
+            currency_pair: order.currency_pair,
+            side: order.side,
+            price,
+            amount,
+            total_filled_amount: order.filled_amount,
+            role,
+            commission_currency_code,
+            commission_rate,
+            commission_amount,
+            datetime: now,
+        };
+        self.fills.push(fill.clone());
+
+        fill
+    }
+}
+
+fn is_crossed(side: OrderSide, price: Price, opposite_price: Price) -> bool {
+    match side {
+        OrderSide::Buy => price >= opposite_price,
+        OrderSide::Sell => price <= opposite_price,
+    }
+}
+
+/// Removes liquidity from the best price levels up to the specified amount and limit price.
+/// Returns matched price levels with matched amounts
+fn take_liquidity(
+    levels: &mut SortedOrderData,
+    side: OrderSide,
+    limit_price: Option<Price>,
+    mut amount: Amount,
+) -> Vec<(Price, Amount)> {
+    let mut matched = Vec::new();
+
+    while amount > dec!(0) {
+        let best_level = match side {
+            OrderSide::Buy => levels.iter().next(),
+            OrderSide::Sell => levels.iter().next_back(),
+        };
+        let (level_price, level_amount) = match best_level {
+            Some((&price, &amount)) => (price, amount),
+            None => break,
+        };
+
+        if let Some(limit_price) = limit_price {
+            if !is_crossed(side, limit_price, level_price) {
+                break;
+            }
+        }
+
+        let matched_amount = amount.min(level_amount);
+        amount -= matched_amount;
+        matched.push((level_price, matched_amount));
+
+        if matched_amount == level_amount {
+            let _ = levels.remove(&level_price);
+        } else {
+            let _ = levels.insert(level_price, level_amount - matched_amount);
+        }
+    }
+
+    matched
+}
+
+fn invalid_order(message: String) -> ExchangeError {
+    ExchangeError::new(ExchangeErrorType::InvalidOrder, message, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::exchanges::commission::FeeTier;
+    use mmb_domain::exchanges::symbol::Precision;
+    use mmb_domain::market::ExchangeAccountId;
+    use mmb_domain::order::snapshot::{OrderOptions, UserOrder};
+    use mmb_utils::hashmap;
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("btc".into(), "usdt".into())
+    }
+
+    fn engine() -> MatchingEngine {
+        engine_with_fees(FeeSchedule::flat(dec!(0.1), dec!(0.2)))
+    }
+
+    fn engine_with_fees(fees: FeeSchedule) -> MatchingEngine {
+        let symbol = Arc::new(Symbol::new(
+            false,
+            "BTC".into(),
+            "btc".into(),
+            "USDT".into(),
+            "usdt".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "btc".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(0.001) },
+        ));
+        let balances = hashmap!["btc".into() => dec!(10), "usdt".into() => dec!(100000)];
+
+        let mut engine = MatchingEngine::new(&[symbol], fees, dec!(0), dec!(0), balances);
+        let _ = engine.apply_order_book(
+            currency_pair(),
+            order_book(
+                &[(dec!(101), dec!(1)), (dec!(102), dec!(2))],
+                &[(dec!(99), dec!(3))],
+            ),
+            DateTime::default(),
+        );
+        engine
+    }
+
+    fn order_book(asks: &[(Price, Amount)], bids: &[(Price, Amount)]) -> LocalOrderBookSnapshot {
+        LocalOrderBookSnapshot::new(
+            asks.iter().copied().collect(),
+            bids.iter().copied().collect(),
+            DateTime::default(),
+        )
+    }
+
+    fn header(id: &str, side: OrderSide, amount: Amount, options: OrderOptions) -> OrderHeader {
+        OrderHeader::with_options(
+            ClientOrderId::from(id),
+            ExchangeAccountId::new("Simulated", 0),
+            currency_pair(),
+            side,
+            amount,
+            options,
+            None,
+            None,
+            "test".to_owned(),
+        )
+    }
+
+    #[test]
+    fn taker_order_sweeps_price_levels() {
+        let mut engine = engine();
+
+        let (_, fills) = engine
+            .create_order(
+                &header("1", OrderSide::Buy, dec!(2), OrderOptions::limit(dec!(102))),
+                DateTime::default(),
+            )
+            .expect("in test");
+
+        let matched = fills
+            .iter()
+            .map(|x| (x.price, x.amount))
+            .collect::<Vec<_>>();
+        assert_eq!(matched, vec![(dec!(101), dec!(1)), (dec!(102), dec!(1))]);
+        assert!(fills.iter().all(|x| x.role == OrderRole::Taker));
+
+        let order = engine
+            .get_order(&ClientOrderId::from("1"))
+            .expect("in test");
+        assert_eq!(order.status, OrderStatus::Completed);
+
+        // 203 usdt cost and 0.2% taker commission
+        assert_eq!(engine.balances()[&"btc".into()], dec!(12));
+        assert_eq!(engine.balances()[&"usdt".into()], dec!(99796.594));
+    }
+
+    #[test]
+    fn fee_tier_is_upgraded_by_traded_volume() {
+        let tier = |min_volume, taker_fee| FeeTier {
+            min_volume,
+            maker_fee: dec!(0),
+            taker_fee,
+        };
+        let mut engine = engine_with_fees(FeeSchedule {
+            tiers: vec![tier(dec!(0), dec!(0.2)), tier(dec!(100), dec!(0.1))],
+            fee_token_discount: None,
+            referral_reward: dec!(0),
+        });
+
+        let (_, fills) = engine
+            .create_order(
+                &header("1", OrderSide::Buy, dec!(2), OrderOptions::limit(dec!(102))),
+                DateTime::default(),
+            )
+            .expect("in test");
+
+        let rates = fills.iter().map(|x| x.commission_rate).collect::<Vec<_>>();
+        assert_eq!(rates, vec![dec!(0.002), dec!(0.001)]);
+    }
+
+    #[test]
+    fn resting_order_is_filled_by_crossing_order_book() {
+        let mut engine = engine();
+
+        let (_, fills) = engine
+            .create_order(
+                &header(
+                    "1",
+                    OrderSide::Sell,
+                    dec!(2),
+                    OrderOptions::limit(dec!(100)),
+                ),
+                DateTime::default(),
+            )
+            .expect("in test");
+        assert!(fills.is_empty());
+
+        let fills = engine.apply_order_book(
+            currency_pair(),
+            order_book(&[(dec!(101), dec!(1))], &[(dec!(100.5), dec!(1.5))]),
+            DateTime::default(),
+        );
+
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].price, dec!(100));
+        assert_eq!(fills[0].amount, dec!(1.5));
+        assert_eq!(fills[0].role, OrderRole::Maker);
+
+        let order = engine
+            .get_order(&ClientOrderId::from("1"))
+            .expect("in test");
+        assert_eq!(order.status, OrderStatus::Created);
+        assert_eq!(order.filled_amount, dec!(1.5));
+    }
+
+    #[test]
+    fn reject_crossing_maker_only_order() {
+        let mut engine = engine();
+
+        let result = engine.create_order(
+            &header(
+                "1",
+                OrderSide::Buy,
+                dec!(1),
+                OrderOptions::maker_only(dec!(101)),
+            ),
+            DateTime::default(),
+        );
+
+        let error = result.expect_err("in test");
+        assert_eq!(error.error_type, ExchangeErrorType::InvalidOrder);
+    }
+
+    #[test]
+    fn rest_of_immediate_or_cancel_order_is_canceled() {
+        let mut engine = engine();
+
+        let (_, fills) = engine
+            .create_order(
+                &header(
+                    "1",
+                    OrderSide::Buy,
+                    dec!(2),
+                    OrderOptions::immediate_or_cancel(dec!(101)),
+                ),
+                DateTime::default(),
+            )
+            .expect("in test");
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].amount, dec!(1));
+
+        let order = engine
+            .get_order(&ClientOrderId::from("1"))
+            .expect("in test");
+        assert_eq!(order.status, OrderStatus::Canceled);
+        assert_eq!(order.filled_amount, dec!(1));
+        assert_eq!(engine.open_orders().count(), 0);
+    }
+
+    #[test]
+    fn reject_order_without_enough_balance() {
+        let mut engine = engine();
+
+        let result = engine.create_order(
+            &header(
+                "1",
+                OrderSide::Sell,
+                dec!(11),
+                OrderOptions::limit(dec!(200)),
+            ),
+            DateTime::default(),
+        );
+
+        let error = result.expect_err("in test");
+        assert_eq!(error.error_type, ExchangeErrorType::InsufficientFunds);
+    }
+
+    #[test]
+    fn market_buy_requires_balance_for_walked_order_book() {
+        // 101 + 2 * 102 = 305 usdt cost and 0.2% taker commission
+        let create_market_buy = |balance: Amount| {
+            let mut engine = engine();
+            let _ = engine.balances.insert("usdt".into(), balance);
+            engine.create_order(
+                &header(
+                    "1",
+                    OrderSide::Buy,
+                    dec!(3),
+                    OrderOptions::User(UserOrder::Market),
+                ),
+                DateTime::default(),
+            )
+        };
+
+        let error = create_market_buy(dec!(305)).expect_err("in test");
+        assert_eq!(error.error_type, ExchangeErrorType::InsufficientFunds);
+
+        let (_, fills) = create_market_buy(dec!(305.61)).expect("in test");
+        let filled_amount: Amount = fills.iter().map(|x| x.amount).sum();
+        assert_eq!(filled_amount, dec!(3));
+    }
+
+    #[test]
+    fn cancel_resting_order() {
+        let mut engine = engine();
+        let client_order_id = ClientOrderId::from("1");
+
+        let _ = engine
+            .create_order(
+                &header("1", OrderSide::Buy, dec!(1), OrderOptions::limit(dec!(90))),
+                DateTime::default(),
+            )
+            .expect("in test");
+        let order = engine.cancel_order(&client_order_id).expect("in test");
+        assert_eq!(order.status, OrderStatus::Canceled);
+
+        let fills = engine.apply_order_book(
+            currency_pair(),
+            order_book(&[(dec!(89), dec!(1))], &[]),
+            DateTime::default(),
+        );
+        assert!(fills.is_empty());
+        assert_eq!(engine.open_orders().count(), 0);
+    }
+}
@@ -0,0 +1,510 @@
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use mmb_domain::order::history::OrderStateTransition;
+use mmb_domain::order::snapshot::Amount;
+use mmb_utils::DateTime;
+use parquet::basic::Compression;
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use parquet::schema::types::Type;
+use rust_decimal::prelude::ToPrimitive;
+use serde::de::DeserializeOwned;
+
+use crate::replay::{RecordedEventType, RecordedOrderBookEvent, RecordedTrade};
+
+pub const ORDER_BOOKS_FILE: &str = "order_books.jsonl";
+pub const TRADES_FILE: &str = "trades.jsonl";
+pub const ORDERS_FILE: &str = "orders.jsonl";
+
+/// Rows are written by row groups, so memory usage doesn't depend on size of recorded session
+const ROW_GROUP_SIZE: usize = 100_000;
+
+const ORDER_BOOKS_SCHEMA: &str = "
+message order_books {
+    required int64 time (TIMESTAMP(MICROS, true));
+    required binary currency_pair (UTF8);
+    required int64 event_index;
+    required boolean is_snapshot;
+    required binary side (UTF8);
+    required double price;
+    required double amount;
+}";
+
+const TRADES_SCHEMA: &str = "
+message trades {
+    required int64 time (TIMESTAMP(MICROS, true));
+    required binary currency_pair (UTF8);
+    required binary trade_id (UTF8);
+    required double price;
+    required double amount;
+    required binary side (UTF8);
+}";
+
+const ORDERS_SCHEMA: &str = "
+message orders {
+    required int64 time (TIMESTAMP(MICROS, true));
+    required binary client_order_id (UTF8);
+    optional binary exchange_order_id (UTF8);
+    required binary exchange_account_id (UTF8);
+    required binary currency_pair (UTF8);
+    required binary side (UTF8);
+    required binary order_type (UTF8);
+    optional double price;
+    required double amount;
+    required double filled_amount;
+    required binary change_type (UTF8);
+    required binary status (UTF8);
+    required binary strategy_name (UTF8);
+}";
+
+/// Rows written to parquet dataset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedDataset {
+    pub name: &'static str,
+    pub rows_count: usize,
+    pub files: Vec<PathBuf>,
+}
+
+/// Converts recorded session into parquet datasets `order_books`, `trades` and `orders` in
+/// `output_dir`. Session directory contains JSON line files `order_books.jsonl` with
+/// `RecordedOrderBookEvent`, `trades.jsonl` with `RecordedTrade` and `orders.jsonl` with
+/// `OrderStateTransition`. Missing files are skipped.
+///
+/// Datasets are partitioned by date of rows, e.g. `trades/date=2022-11-17/part-0.parquet`.
+/// Prices and amounts are written as doubles and times as UTC timestamps in microseconds.
+/// Order book events are written as a row per price level with index of event in session
+pub fn export_session_to_parquet(
+    session_dir: &Path,
+    output_dir: &Path,
+) -> Result<Vec<ExportedDataset>> {
+    let mut datasets = Vec::new();
+
+    let path = session_dir.join(ORDER_BOOKS_FILE);
+    if path.exists() {
+        let mut dataset = DatasetWriter::new(output_dir, "order_books", ORDER_BOOKS_SCHEMA)?;
+        let mut event_index = 0;
+        for_each_record(&path, |event: RecordedOrderBookEvent| {
+            add_order_book_rows(&mut dataset, &event, event_index)?;
+            event_index += 1;
+            Ok(())
+        })?;
+        datasets.push(dataset.finish()?);
+    }
+
+    let path = session_dir.join(TRADES_FILE);
+    if path.exists() {
+        let mut dataset = DatasetWriter::new(output_dir, "trades", TRADES_SCHEMA)?;
+        for_each_record(&path, |trade: RecordedTrade| {
+            let mut row = dataset.row(trade.time)?;
+            row.timestamp(trade.time);
+            row.text(&trade.currency_pair.to_string());
+            row.text(&trade.trade_id);
+            row.decimal(trade.price);
+            row.decimal(trade.amount);
+            row.text(trade.side.as_str());
+            dataset.finish_row(trade.time)
+        })?;
+        datasets.push(dataset.finish()?);
+    }
+
+    let path = session_dir.join(ORDERS_FILE);
+    if path.exists() {
+        let mut dataset = DatasetWriter::new(output_dir, "orders", ORDERS_SCHEMA)?;
+        for_each_record(&path, |order: OrderStateTransition| {
+            let mut row = dataset.row(order.time)?;
+            row.timestamp(order.time);
+            row.text(order.client_order_id.as_str());
+            row.optional_text(order.exchange_order_id.as_ref().map(|x| x.as_str()));
+            row.text(&order.exchange_account_id.to_string());
+            row.text(&order.currency_pair.to_string());
+            row.text(order.side.as_str());
+            row.text(&format!("{:?}", order.order_type));
+            row.optional_decimal(order.price);
+            row.decimal(order.amount);
+            row.decimal(order.filled_amount);
+            row.text(&format!("{:?}", order.change_type));
+            row.text(&format!("{:?}", order.status));
+            row.text(&order.strategy_name);
+            dataset.finish_row(order.time)
+        })?;
+        datasets.push(dataset.finish()?);
+    }
+
+    Ok(datasets)
+}
+
+fn add_order_book_rows(
+    dataset: &mut DatasetWriter,
+    event: &RecordedOrderBookEvent,
+    event_index: i64,
+) -> Result<()> {
+    let currency_pair = event.currency_pair.to_string();
+    let is_snapshot = event.event_type == RecordedEventType::Snapshot;
+    let levels = event
+        .asks
+        .iter()
+        .map(|x| ("ask", x))
+        .chain(event.bids.iter().map(|x| ("bid", x)));
+
+    for (side, (price, amount)) in levels {
+        let mut row = dataset.row(event.time)?;
+        row.timestamp(event.time);
+        row.text(&currency_pair);
+        row.int64(event_index);
+        row.boolean(is_snapshot);
+        row.text(side);
+        row.decimal(*price);
+        row.decimal(*amount);
+        dataset.finish_row(event.time)?;
+    }
+
+    Ok(())
+}
+
+fn for_each_record<T: DeserializeOwned>(
+    path: &Path,
+    mut action: impl FnMut(T) -> Result<()>,
+) -> Result<()> {
+    let file = File::open(path).with_context(|| format!("Unable to open {}", path.display()))?;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("Unable to read {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record = serde_json::from_str(&line)
+            .with_context(|| format!("Unable to parse line {} of {}", index + 1, path.display()))?;
+        action(record)?;
+    }
+
+    Ok(())
+}
+
+/// Values of parquet column. Definition levels of optional column are 0 for missing values
+#[derive(Debug)]
+enum ColumnValues {
+    Boolean(Vec<bool>),
+    Int64(Vec<i64>),
+    Double(Vec<f64>, Option<Vec<i16>>),
+    Text(Vec<ByteArray>, Option<Vec<i16>>),
+}
+
+impl ColumnValues {
+    fn clear(&mut self) {
+        match self {
+            ColumnValues::Boolean(values) => values.clear(),
+            ColumnValues::Int64(values) => values.clear(),
+            ColumnValues::Double(values, def_levels) => {
+                values.clear();
+                def_levels.iter_mut().for_each(Vec::clear);
+            }
+            ColumnValues::Text(values, def_levels) => {
+                values.clear();
+                def_levels.iter_mut().for_each(Vec::clear);
+            }
+        }
+    }
+}
+
+/// Appends values of row to columns in order of schema
+struct RowWriter<'a> {
+    columns: &'a mut Vec<ColumnValues>,
+    index: usize,
+}
+
+impl RowWriter<'_> {
+    /// Column is created by the first written value
+    fn column(&mut self, create: impl FnOnce() -> ColumnValues) -> &mut ColumnValues {
+        if self.columns.len() == self.index {
+            self.columns.push(create());
+        }
+        self.index += 1;
+        &mut self.columns[self.index - 1]
+    }
+
+    fn boolean(&mut self, value: bool) {
+        match self.column(|| ColumnValues::Boolean(Vec::new())) {
+            ColumnValues::Boolean(values) => values.push(value),
+            column => panic!("Boolean value is written to column {column:?}"),
+        }
+    }
+
+    fn int64(&mut self, value: i64) {
+        match self.column(|| ColumnValues::Int64(Vec::new())) {
+            ColumnValues::Int64(values) => values.push(value),
+            column => panic!("Int64 value is written to column {column:?}"),
+        }
+    }
+
+    fn timestamp(&mut self, value: DateTime) {
+        self.int64(value.timestamp_micros())
+    }
+
+    fn decimal(&mut self, value: Amount) {
+        match self.column(|| ColumnValues::Double(Vec::new(), None)) {
+            ColumnValues::Double(values, None) => values.push(to_f64(value)),
+            column => panic!("Double value is written to column {column:?}"),
+        }
+    }
+
+    fn optional_decimal(&mut self, value: Option<Amount>) {
+        match self.column(|| ColumnValues::Double(Vec::new(), Some(Vec::new()))) {
+            ColumnValues::Double(values, Some(def_levels)) => {
+                def_levels.push(value.is_some().into());
+                values.extend(value.map(to_f64));
+            }
+            column => panic!("Optional double value is written to column {column:?}"),
+        }
+    }
+
+    fn text(&mut self, value: &str) {
+        match self.column(|| ColumnValues::Text(Vec::new(), None)) {
+            ColumnValues::Text(values, None) => values.push(value.into()),
+            column => panic!("Text value is written to column {column:?}"),
+        }
+    }
+
+    fn optional_text(&mut self, value: Option<&str>) {
+        match self.column(|| ColumnValues::Text(Vec::new(), Some(Vec::new()))) {
+            ColumnValues::Text(values, Some(def_levels)) => {
+                def_levels.push(value.is_some().into());
+                values.extend(value.map(ByteArray::from));
+            }
+            column => panic!("Optional text value is written to column {column:?}"),
+        }
+    }
+}
+
+fn to_f64(value: Amount) -> f64 {
+    value.to_f64().unwrap_or(f64::NAN)
+}
+
+/// Parquet file of partition with rows which aren't written yet
+struct PartitionWriter {
+    path: PathBuf,
+    writer: SerializedFileWriter<File>,
+    columns: Vec<ColumnValues>,
+    rows_count: usize,
+}
+
+impl PartitionWriter {
+    fn write_row_group(&mut self) -> Result<()> {
+        if self.rows_count == 0 {
+            return Ok(());
+        }
+
+        let mut row_group = self.writer.next_row_group()?;
+        for values in &self.columns {
+            let mut column = row_group
+                .next_column()?
+                .context("Count of written columns doesn't match schema")?;
+            let _ = match values {
+                ColumnValues::Boolean(values) => {
+                    column.typed::<BoolType>().write_batch(values, None, None)?
+                }
+                ColumnValues::Int64(values) => column
+                    .typed::<Int64Type>()
+                    .write_batch(values, None, None)?,
+                ColumnValues::Double(values, def_levels) => column
+                    .typed::<DoubleType>()
+                    .write_batch(values, def_levels.as_deref(), None)?,
+                ColumnValues::Text(values, def_levels) => column
+                    .typed::<ByteArrayType>()
+                    .write_batch(values, def_levels.as_deref(), None)?,
+            };
+            column.close()?;
+        }
+        let _ = row_group.close()?;
+
+        self.columns.iter_mut().for_each(ColumnValues::clear);
+        self.rows_count = 0;
+        Ok(())
+    }
+}
+
+/// Writes rows of dataset to files of date partitions
+struct DatasetWriter {
+    name: &'static str,
+    dir: PathBuf,
+    schema: Arc<Type>,
+    properties: Arc<WriterProperties>,
+    partitions: HashMap<NaiveDate, PartitionWriter>,
+    rows_count: usize,
+}
+
+impl DatasetWriter {
+    fn new(output_dir: &Path, name: &'static str, schema: &str) -> Result<Self> {
+        let schema = parse_message_type(schema)
+            .with_context(|| format!("Invalid parquet schema of {name}"))?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+
+        Ok(DatasetWriter {
+            name,
+            dir: output_dir.join(name),
+            schema: Arc::new(schema),
+            properties: Arc::new(properties),
+            partitions: HashMap::new(),
+            rows_count: 0,
+        })
+    }
+
+    /// Writer of row in partition of `time`. Row should be completed by `finish_row`
+    fn row(&mut self, time: DateTime) -> Result<RowWriter<'_>> {
+        let date = time.date_naive();
+        if !self.partitions.contains_key(&date) {
+            let partition_dir = self.dir.join(format!("date={date}"));
+            create_dir_all(&partition_dir)
+                .with_context(|| format!("Unable to create {}", partition_dir.display()))?;
+
+            let path = partition_dir.join("part-0.parquet");
+            let file = File::create(&path)
+                .with_context(|| format!("Unable to create {}", path.display()))?;
+            let writer =
+                SerializedFileWriter::new(file, self.schema.clone(), self.properties.clone())?;
+            let _ = self.partitions.insert(
+                date,
+                PartitionWriter {
+                    path,
+                    writer,
+                    columns: Vec::new(),
+                    rows_count: 0,
+                },
+            );
+        }
+
+        let partition = self
+            .partitions
+            .get_mut(&date)
+            .context("Partition is created above")?;
+        Ok(RowWriter {
+            columns: &mut partition.columns,
+            index: 0,
+        })
+    }
+
+    fn finish_row(&mut self, time: DateTime) -> Result<()> {
+        let partition = self
+            .partitions
+            .get_mut(&time.date_naive())
+            .context("Row is finished for partition which wasn't started")?;
+        partition.rows_count += 1;
+        self.rows_count += 1;
+
+        if partition.rows_count >= ROW_GROUP_SIZE {
+            partition.write_row_group()?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<ExportedDataset> {
+        let mut files = Vec::with_capacity(self.partitions.len());
+        for (_, mut partition) in self.partitions {
+            partition.write_row_group()?;
+            let _ = partition
+                .writer
+                .close()
+                .with_context(|| format!("Unable to write {}", partition.path.display()))?;
+            files.push(partition.path);
+        }
+        files.sort();
+
+        Ok(ExportedDataset {
+            name: self.name,
+            rows_count: self.rows_count,
+            files,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::{Row, RowAccessor};
+    use std::fs;
+
+    fn read_rows(path: &Path) -> Vec<Row> {
+        let reader =
+            SerializedFileReader::new(File::open(path).expect("in test")).expect("in test");
+        reader
+            .get_row_iter(None)
+            .expect("in test")
+            .map(|x| x.expect("in test"))
+            .collect()
+    }
+
+    #[test]
+    fn session_is_exported_to_date_partitions() {
+        let session_dir = std::env::temp_dir().join(format!(
+            "parquet_export_{}_{}",
+            std::process::id(),
+            Utc::now().timestamp_micros()
+        ));
+        let output_dir = session_dir.join("parquet");
+        create_dir_all(&session_dir).expect("in test");
+        fs::write(
+            session_dir.join(ORDER_BOOKS_FILE),
+            concat!(
+                r#"{"time":"2022-11-17T10:00:00Z","currency_pair":"btc/usdt","event_type":"snapshot","asks":[["101","1"],["102","2"]],"bids":[["99","1.5"]]}"#,
+                "\n",
+                r#"{"time":"2022-11-18T10:00:00Z","currency_pair":"btc/usdt","event_type":"update","asks":[["101","0"]],"bids":[]}"#,
+            ),
+        )
+        .expect("in test");
+        fs::write(
+            session_dir.join(TRADES_FILE),
+            r#"{"time":"2022-11-17T10:00:01Z","currency_pair":"btc/usdt","trade_id":"42","price":"100.5","amount":"0.5","side":"Buy"}"#,
+        )
+        .expect("in test");
+
+        let datasets = export_session_to_parquet(&session_dir, &output_dir).expect("in test");
+
+        // there is no orders file in session
+        assert_eq!(datasets.len(), 2);
+        let order_books = &datasets[0];
+        assert_eq!(order_books.rows_count, 4);
+        assert_eq!(
+            order_books.files,
+            vec![
+                output_dir.join("order_books/date=2022-11-17/part-0.parquet"),
+                output_dir.join("order_books/date=2022-11-18/part-0.parquet"),
+            ]
+        );
+        let rows = read_rows(&order_books.files[0]);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[2].get_string(4).expect("in test"), "bid");
+        assert_eq!(rows[2].get_double(5).expect("in test"), 99.);
+        assert_eq!(
+            read_rows(&order_books.files[1])[0]
+                .get_long(2)
+                .expect("in test"),
+            1
+        );
+
+        let trades = read_rows(&datasets[1].files[0]);
+        assert_eq!(trades.len(), 1);
+        let time = Utc
+            .with_ymd_and_hms(2022, 11, 17, 10, 0, 1)
+            .single()
+            .expect("in test");
+        assert_eq!(
+            trades[0].get_timestamp_micros(0).expect("in test"),
+            time.timestamp_micros()
+        );
+        assert_eq!(trades[0].get_string(2).expect("in test"), "42");
+        assert_eq!(trades[0].get_double(3).expect("in test"), 100.5);
+
+        fs::remove_dir_all(&session_dir).expect("in test");
+    }
+}
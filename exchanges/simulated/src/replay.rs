@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::snapshot::{Amount, OrderSide, Price};
+use mmb_domain::order_book::event::{EventType, OrderBookEvent};
+use mmb_domain::order_book::order_book_data::OrderBookData;
+use mmb_utils::DateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedEventType {
+    Snapshot,
+    Update,
+}
+
+/// Order book event in the format of recorded market data files. Each line of a file is a JSON
+/// object like `{"time":"2022-11-17T10:00:00Z","currency_pair":"btc/usdt","event_type":"update",
+/// "asks":[["101","0"]],"bids":[["99","1.5"]]}`. Zero amount removes price level
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedOrderBookEvent {
+    pub time: DateTime,
+    pub currency_pair: CurrencyPair,
+    pub event_type: RecordedEventType,
+    pub asks: Vec<(Price, Amount)>,
+    pub bids: Vec<(Price, Amount)>,
+}
+
+impl RecordedOrderBookEvent {
+    pub fn to_order_book_event(&self, exchange_account_id: ExchangeAccountId) -> OrderBookEvent {
+        let event_type = match self.event_type {
+            RecordedEventType::Snapshot => EventType::Snapshot,
+            RecordedEventType::Update => EventType::Update,
+        };
+
+        OrderBookEvent::new(
+            self.time,
+            exchange_account_id,
+            self.currency_pair,
+            String::new(),
+            event_type,
+            Arc::new(OrderBookData::new(
+                self.asks.iter().copied().collect(),
+                self.bids.iter().copied().collect(),
+            )),
+        )
+    }
+}
+
+/// Public trade in the format of recorded trades files, a JSON object per line like
+/// `{"time":"2022-11-17T10:00:00Z","currency_pair":"btc/usdt","trade_id":"42","price":"100",
+/// "amount":"0.5","side":"Buy"}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedTrade {
+    pub time: DateTime,
+    pub currency_pair: CurrencyPair,
+    pub trade_id: String,
+    pub price: Price,
+    pub amount: Amount,
+    pub side: OrderSide,
+}
+
+/// Recorded market data ordered by time of events
+pub struct MarketDataReplay {
+    events: Vec<OrderBookEvent>,
+}
+
+impl MarketDataReplay {
+    pub fn new(mut events: Vec<OrderBookEvent>) -> Self {
+        // stable sort keeps order of events recorded at the same time
+        events.sort_by_key(|x| x.creation_time);
+        Self { events }
+    }
+
+    /// Loads order book events from file with JSON line per `RecordedOrderBookEvent`
+    pub fn load(path: &Path, exchange_account_id: ExchangeAccountId) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Unable to open market data file {}", path.display()))?;
+
+        let mut events = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.with_context(|| format!("Unable to read {}", path.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let event: RecordedOrderBookEvent = serde_json::from_str(&line).with_context(|| {
+                format!("Unable to parse line {} of {}", index + 1, path.display())
+            })?;
+            events.push(event.to_order_book_event(exchange_account_id));
+        }
+
+        Ok(Self::new(events))
+    }
+
+    /// Events in range `[from, to)`
+    pub fn events(&self, from: DateTime, to: DateTime) -> impl Iterator<Item = &OrderBookEvent> {
+        self.events
+            .iter()
+            .skip_while(move |x| x.creation_time < from)
+            .take_while(move |x| x.creation_time < to)
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    RestFillsType, WebSocketOptions,
+};
+use mmb_core::exchanges::general::handlers::handle_order_filled::{FillAmount, FillEvent};
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use mmb_core::exchanges::timeouts::timeout_manager::TimeoutManager;
+use mmb_core::exchanges::traits::{
+    ExchangeClientBuilder, ExchangeClientBuilderResult, HandleMetricsCb, HandleOrderFilledCb,
+    HandleTradeCb, OrderCancelledCb, OrderCreatedCb, SendWebsocketMessageCb,
+};
+use mmb_core::lifecycle::app_lifetime_manager::AppLifetimeManager;
+use mmb_core::misc::clock::Clock;
+use mmb_core::settings::ExchangeSettings;
+use mmb_domain::events::{AllowedEventSourceType, EventSourceType, ExchangeEvent, TradeId};
+use mmb_domain::exchanges::commission::FeeSchedule;
+use mmb_domain::exchanges::symbol::Symbol;
+use mmb_domain::market::{CurrencyCode, CurrencyId, ExchangeAccountId, ExchangeId};
+use mmb_domain::order::fill::OrderFillType;
+use mmb_domain::order::pool::OrdersPool;
+use mmb_domain::order::snapshot::Amount;
+use mmb_domain::order_book::event::OrderBookEvent;
+use mmb_utils::send_expected::SendExpectedByRef;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::matching_engine::{MatchingEngine, SimulatedFill};
+
+pub const SIMULATED_EXCHANGE_ID: &str = "Simulated";
+
+/// Settings of paper trading. They are shared by all simulated exchange accounts
+#[derive(Clone)]
+pub struct SimulationSettings {
+    /// Markets available for trading
+    pub symbols: Vec<Arc<Symbol>>,
+    /// Balances of simulated account at start
+    pub initial_balances: HashMap<CurrencyCode, Amount>,
+    /// Fees applied to fills of simulated orders
+    pub fees: FeeSchedule,
+    /// Trading volume of simulated account at start which determines fee tier together with
+    /// volume traded during simulation
+    pub traded_volume: Decimal,
+    /// Price slippage of taker fills in percents
+    pub slippage: Decimal,
+    /// Delay of order creation and cancellation requests. It is measured by clock of the engine, so
+    /// in backtests it passes in virtual time
+    pub latency: Duration,
+    /// Exchange account that supplies live order books. Orders are matched against order books of
+    /// the same currency pairs. If it isn't specified, order books should be supplied by
+    /// `SimulatedExchange::apply_order_book_event`, e.g. from recorded market data
+    pub market_data_source: Option<ExchangeAccountId>,
+}
+
+/// Exchange client for paper trading. Orders aren't sent anywhere but matched against real order
+/// books with configured latency and fees, and results are reported by usual order events
+pub struct SimulatedExchange {
+    pub(crate) exchange_account_id: ExchangeAccountId,
+    pub(crate) settings: ExchangeSettings,
+    pub(crate) simulation_settings: SimulationSettings,
+    pub(crate) engine: Mutex<MatchingEngine>,
+    pub(crate) events_channel: broadcast::Sender<ExchangeEvent>,
+    pub(crate) supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    /// Clock of the engine. Orders are matched and filled at its time
+    pub(crate) clock: Arc<dyn Clock>,
+
+    pub(crate) order_created_callback: OrderCreatedCb,
+    pub(crate) order_cancelled_callback: OrderCancelledCb,
+    pub(crate) handle_order_filled_callback: HandleOrderFilledCb,
+    pub(crate) handle_trade_callback: HandleTradeCb,
+    pub(crate) websocket_message_callback: SendWebsocketMessageCb,
+    pub(crate) metrics_callback: HandleMetricsCb,
+}
+
+impl SimulatedExchange {
+    pub fn new(
+        settings: ExchangeSettings,
+        simulation_settings: SimulationSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let engine = MatchingEngine::new(
+            &simulation_settings.symbols,
+            simulation_settings.fees.clone(),
+            simulation_settings.traded_volume,
+            simulation_settings.slippage,
+            simulation_settings.initial_balances.clone(),
+        );
+
+        Self {
+            exchange_account_id: settings.exchange_account_id,
+            settings,
+            simulation_settings,
+            engine: Mutex::new(engine),
+            events_channel,
+            supported_currencies: Default::default(),
+            clock,
+            order_created_callback: Box::new(|_, _, _| {}),
+            order_cancelled_callback: Box::new(|_, _, _| {}),
+            handle_order_filled_callback: Box::new(|_| {}),
+            handle_trade_callback: Box::new(|_, _| {}),
+            websocket_message_callback: Box::new(|_, _| Ok(())),
+            metrics_callback: Box::new(|_| {}),
+        }
+    }
+
+    /// Updates order book of simulated market, reports fills of crossed orders and publishes order
+    /// book as own market data of simulated exchange account
+    pub fn apply_order_book_event(&self, event: &OrderBookEvent) {
+        let fills = match self
+            .engine
+            .lock()
+            .apply_order_book_event(event, self.clock.now())
+        {
+            Some(fills) => fills,
+            None => return,
+        };
+
+        self.events_channel
+            .send_expected(ExchangeEvent::OrderBookEvent(OrderBookEvent::new(
+                event.creation_time,
+                self.exchange_account_id,
+                event.currency_pair,
+                String::new(),
+                event.event_type,
+                event.data.clone(),
+            )));
+
+        self.report_fills(fills);
+    }
+
+    pub(crate) fn report_fills(&self, fills: Vec<SimulatedFill>) {
+        for fill in fills {
+            (self.handle_order_filled_callback)(FillEvent {
+                source_type: EventSourceType::WebSocket,
+                trade_id: Some(TradeId::Number(fill.trade_id)),
+                client_order_id: Some(fill.client_order_id),
+                exchange_order_id: fill.exchange_order_id,
+                fill_price: fill.price,
+                fill_amount: FillAmount::Incremental {
+                    fill_amount: fill.amount,
+                    total_filled_amount: Some(fill.total_filled_amount),
+                },
+                order_role: Some(fill.role),
+                commission_currency_code: Some(fill.commission_currency_code),
+                commission_rate: Some(fill.commission_rate),
+                commission_amount: Some(fill.commission_amount),
+                fill_type: OrderFillType::UserTrade,
+                special_order_data: None,
+                fill_date: Some(fill.datetime),
+            });
+        }
+    }
+
+    /// Feeds order books of `market_data_source` exchange account to the simulated exchange
+    pub(crate) async fn listen_market_data(
+        exchange: Weak<Exchange>,
+        mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+        market_data_source: ExchangeAccountId,
+    ) -> Result<()> {
+        loop {
+            let event = match events_receiver.recv().await {
+                Ok(ExchangeEvent::OrderBookEvent(event))
+                    if event.exchange_account_id == market_data_source =>
+                {
+                    event
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("SimulatedExchange skipped {skipped} market data events");
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            let exchange = match exchange.upgrade() {
+                Some(exchange) => exchange,
+                None => return Ok(()),
+            };
+
+            exchange
+                .exchange_client
+                .as_any()
+                .downcast_ref::<SimulatedExchange>()
+                .expect("exchange client should be SimulatedExchange")
+                .apply_order_book_event(&event);
+        }
+    }
+}
+
+pub struct SimulatedExchangeBuilder {
+    settings: SimulationSettings,
+}
+
+impl SimulatedExchangeBuilder {
+    pub fn new(settings: SimulationSettings) -> Self {
+        Self { settings }
+    }
+}
+
+impl ExchangeClientBuilder for SimulatedExchangeBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        _lifetime_manager: Arc<AppLifetimeManager>,
+        timeout_manager: Arc<TimeoutManager>,
+        _orders: Arc<OrdersPool>,
+    ) -> ExchangeClientBuilderResult {
+        let empty_response_is_ok = false;
+
+        ExchangeClientBuilderResult {
+            client: Box::new(SimulatedExchange::new(
+                exchange_settings,
+                self.settings.clone(),
+                events_channel,
+                timeout_manager.clock().clone(),
+            )),
+            features: ExchangeFeatures::new(
+                OpenOrdersType::AllCurrencyPair,
+                RestFillsFeatures::new(RestFillsType::None),
+                OrderFeatures {
+                    supports_get_order_info_by_client_order_id: true,
+                    ..OrderFeatures::default()
+                },
+                OrderTradeOption::default(),
+                WebSocketOptions::default(),
+                empty_response_is_ok,
+                AllowedEventSourceType::All,
+                AllowedEventSourceType::All,
+                AllowedEventSourceType::All,
+            ),
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        // there is no real exchange behind, so requests aren't limited
+        RequestTimeoutArguments::from_requests_per_minute(100_000)
+    }
+
+    fn get_exchange_id(&self) -> ExchangeId {
+        SIMULATED_EXCHANGE_ID.into()
+    }
+}
@@ -0,0 +1,151 @@
+use mmb_core::lifecycle::trading_engine::EngineContext;
+use mmb_core::statistic_service::MarketAccountIdStatistic;
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId, MarketAccountId};
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{Amount, OrderSide};
+
+use crate::proto;
+
+pub(crate) fn order(order: &OrderRef) -> proto::Order {
+    let side = match order.side() {
+        OrderSide::Buy => proto::OrderSide::Buy,
+        OrderSide::Sell => proto::OrderSide::Sell,
+    };
+
+    proto::Order {
+        client_order_id: order.client_order_id().as_str().to_owned(),
+        exchange_order_id: order
+            .exchange_order_id()
+            .map(|x| x.as_str().to_owned())
+            .unwrap_or_default(),
+        exchange_account_id: order.exchange_account_id().to_string(),
+        currency_pair: order.currency_pair().as_str().to_owned(),
+        side: side.into(),
+        price: order.price().to_string(),
+        amount: order.amount().to_string(),
+        filled_amount: order.filled_amount().to_string(),
+        status: format!("{:?}", order.status()),
+    }
+}
+
+pub(crate) fn position(
+    exchange_account_id: ExchangeAccountId,
+    currency_pair: CurrencyPair,
+    position: Amount,
+) -> proto::Position {
+    proto::Position {
+        exchange_account_id: exchange_account_id.to_string(),
+        currency_pair: currency_pair.as_str().to_owned(),
+        position: position.to_string(),
+    }
+}
+
+pub(crate) fn market_stats(
+    market_account_id: MarketAccountId,
+    stats: MarketAccountIdStatistic,
+) -> proto::MarketStats {
+    proto::MarketStats {
+        exchange_account_id: market_account_id.exchange_account_id.to_string(),
+        currency_pair: market_account_id.currency_pair.as_str().to_owned(),
+        opened_orders_count: stats.opened_orders_count,
+        canceled_orders_count: stats.canceled_orders_count,
+        partially_filled_orders_count: stats.partially_filled_orders_count,
+        fully_filled_orders_count: stats.fully_filled_orders_count,
+        summary_filled_amount: stats.summary_filled_amount.to_string(),
+        summary_commission: stats.summary_commission.to_string(),
+        active_trading_time_secs: stats.active_trading_time_secs,
+    }
+}
+
+pub(crate) fn orders(engine_context: &EngineContext) -> proto::OrdersReply {
+    let orders = engine_context
+        .exchanges
+        .iter()
+        .flat_map(|exchange| {
+            exchange
+                .orders
+                .not_finished
+                .iter()
+                .map(|x| order(x.value()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    proto::OrdersReply { orders }
+}
+
+pub(crate) fn positions(engine_context: &EngineContext) -> proto::PositionsReply {
+    let markets = engine_context
+        .exchanges
+        .iter()
+        .flat_map(|exchange| {
+            let exchange_account_id = exchange.exchange_account_id;
+            exchange
+                .symbols
+                .iter()
+                .map(|symbol| (exchange_account_id, symbol.currency_pair()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let balance_manager = engine_context.balance_manager.lock();
+    let positions = markets
+        .into_iter()
+        .filter_map(|(exchange_account_id, currency_pair)| {
+            let amount = balance_manager.net_position(exchange_account_id, currency_pair);
+            (!amount.is_zero()).then(|| position(exchange_account_id, currency_pair, amount))
+        })
+        .collect();
+
+    proto::PositionsReply { positions }
+}
+
+pub(crate) fn stats(engine_context: &EngineContext) -> proto::StatsReply {
+    let mut markets = engine_context
+        .statistic_service
+        .market_account_id_stats()
+        .into_iter()
+        .map(|(market_account_id, stats)| market_stats(market_account_id, stats))
+        .collect::<Vec<_>>();
+    markets.sort_by(|a, b| {
+        (&a.exchange_account_id, &a.currency_pair).cmp(&(&b.exchange_account_id, &b.currency_pair))
+    });
+
+    proto::StatsReply { markets }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn decimals_are_passed_as_strings() {
+        let market_account_id = MarketAccountId::new(
+            "Binance_0".parse().expect("valid id"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        );
+        let stats = MarketAccountIdStatistic {
+            fully_filled_orders_count: 2,
+            summary_filled_amount: dec!(1.50),
+            ..Default::default()
+        };
+
+        let message = market_stats(market_account_id, stats);
+        assert_eq!(message.exchange_account_id, "Binance_0");
+        assert_eq!(
+            message.currency_pair,
+            market_account_id.currency_pair.as_str()
+        );
+        assert_eq!(message.fully_filled_orders_count, 2);
+        assert_eq!(message.summary_filled_amount, "1.50");
+        assert_eq!(message.summary_commission, "0");
+
+        let message = position(
+            market_account_id.exchange_account_id,
+            market_account_id.currency_pair,
+            dec!(-0.25),
+        );
+        assert_eq!(message.position, "-0.25");
+    }
+}
@@ -0,0 +1,31 @@
+#![deny(
+    non_ascii_idents,
+    non_shorthand_field_patterns,
+    no_mangle_generic_items,
+    overflowing_literals,
+    path_statements,
+    unused_allocation,
+    unused_comparisons,
+    unused_parens,
+    while_true,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_must_use,
+    clippy::unwrap_used
+)]
+// every gRPC method returns `tonic::Status` as error
+#![allow(clippy::result_large_err)]
+
+//! gRPC server mirroring control surface of the trading engine: orders, positions, streaming of
+//! statistics and control commands. Service is described in `proto/mmb.proto`.
+
+mod convert;
+pub mod server;
+
+/// Messages and service generated from `proto/mmb.proto`
+#[allow(unused_qualifications, clippy::all, clippy::unwrap_used)]
+pub mod proto {
+    tonic::include_proto!("mmb");
+}
@@ -0,0 +1,220 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures::{Stream, StreamExt};
+use mmb_core::infrastructure::spawn_future;
+use mmb_core::lifecycle::trading_engine::{EngineContext, Service};
+use mmb_domain::order::snapshot::ClientOrderId;
+use mmb_utils::infrastructure::SpawnFutureFlags;
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+use tokio_stream::wrappers::IntervalStream;
+use tonic::{Request, Response, Status};
+
+use crate::convert;
+use crate::proto::control_server::{Control, ControlServer};
+use crate::proto::{
+    CancelOrderRequest, CommandReply, Empty, KillSwitchRequest, OrdersReply, PositionsReply,
+    StatsReply, StrategyRequest, StreamStatsRequest,
+};
+
+const DEFAULT_STATS_INTERVAL: Duration = Duration::from_secs(1);
+const MIN_STATS_INTERVAL: Duration = Duration::from_millis(100);
+
+type StatsStream = Pin<Box<dyn Stream<Item = Result<StatsReply, Status>> + Send>>;
+
+fn reply(message: impl Into<String>) -> Result<Response<CommandReply>, Status> {
+    Ok(Response::new(CommandReply {
+        message: message.into(),
+    }))
+}
+
+struct ControlService {
+    engine_context: Arc<EngineContext>,
+}
+
+#[tonic::async_trait]
+impl Control for ControlService {
+    async fn health(&self, _: Request<Empty>) -> Result<Response<CommandReply>, Status> {
+        reply("Engine is working")
+    }
+
+    async fn get_orders(&self, _: Request<Empty>) -> Result<Response<OrdersReply>, Status> {
+        Ok(Response::new(convert::orders(&self.engine_context)))
+    }
+
+    async fn get_positions(&self, _: Request<Empty>) -> Result<Response<PositionsReply>, Status> {
+        Ok(Response::new(convert::positions(&self.engine_context)))
+    }
+
+    async fn get_stats(&self, _: Request<Empty>) -> Result<Response<StatsReply>, Status> {
+        Ok(Response::new(convert::stats(&self.engine_context)))
+    }
+
+    type StreamStatsStream = StatsStream;
+
+    async fn stream_stats(
+        &self,
+        request: Request<StreamStatsRequest>,
+    ) -> Result<Response<Self::StreamStatsStream>, Status> {
+        let interval = match request.into_inner().interval_millis {
+            0 => DEFAULT_STATS_INTERVAL,
+            millis => Duration::from_millis(millis).max(MIN_STATS_INTERVAL),
+        };
+
+        let engine_context = self.engine_context.clone();
+        let stop_token = engine_context.lifetime_manager.stop_token();
+        let stream = IntervalStream::new(tokio::time::interval(interval))
+            .map(move |_| Ok(convert::stats(&engine_context)))
+            .take_until(async move { stop_token.when_cancelled().await });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn cancel_order(
+        &self,
+        request: Request<CancelOrderRequest>,
+    ) -> Result<Response<CommandReply>, Status> {
+        let client_order_id = ClientOrderId::from(request.into_inner().client_order_id.as_str());
+        let found = self.engine_context.exchanges.iter().find_map(|exchange| {
+            let order = exchange.orders.not_finished.get(&client_order_id)?.clone();
+            Some((exchange.clone(), order))
+        });
+        let (exchange, order) = found
+            .ok_or_else(|| Status::not_found(format!("Order {client_order_id} isn't open")))?;
+
+        let cancellation_token = self.engine_context.lifetime_manager.stop_token();
+        spawn_future(
+            &format!("Cancel order {client_order_id} requested via gRPC"),
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            async move {
+                let _ = exchange.cancel_order(&order, cancellation_token).await;
+                Ok(())
+            },
+        );
+
+        reply(format!(
+            "Cancellation of order {client_order_id} is requested"
+        ))
+    }
+
+    async fn pause_strategy(
+        &self,
+        request: Request<StrategyRequest>,
+    ) -> Result<Response<CommandReply>, Status> {
+        let name = request.into_inner().name;
+        self.engine_context
+            .strategy_registry
+            .pause(&name)
+            .map_err(|err| Status::failed_precondition(format!("{err:#}")))?;
+        reply(format!("Strategy '{name}' is paused"))
+    }
+
+    async fn resume_strategy(
+        &self,
+        request: Request<StrategyRequest>,
+    ) -> Result<Response<CommandReply>, Status> {
+        let name = request.into_inner().name;
+        self.engine_context
+            .strategy_registry
+            .start(&name)
+            .map_err(|err| Status::failed_precondition(format!("{err:#}")))?;
+        reply(format!("Strategy '{name}' is resumed"))
+    }
+
+    async fn kill_switch(
+        &self,
+        request: Request<KillSwitchRequest>,
+    ) -> Result<Response<CommandReply>, Status> {
+        let flatten_positions = request.into_inner().flatten_positions;
+        match self
+            .engine_context
+            .lifetime_manager
+            .spawn_kill_switch("requested via gRPC", flatten_positions)
+        {
+            Some(_) => reply("Kill switch is triggered. Trading is halted until restart"),
+            None => Err(Status::unavailable("Trading engine is stopping")),
+        }
+    }
+
+    async fn stop(&self, _: Request<Empty>) -> Result<Response<CommandReply>, Status> {
+        match self
+            .engine_context
+            .lifetime_manager
+            .spawn_graceful_shutdown("requested via gRPC")
+        {
+            Some(_) => reply("Trading engine is being stopped"),
+            None => Err(Status::unavailable("Trading engine is stopping already")),
+        }
+    }
+}
+
+/// gRPC server running inside the trading engine. It's stopped on graceful shutdown of the engine
+pub struct GrpcApi {
+    address: SocketAddr,
+    server_stopper_tx: Mutex<Option<oneshot::Sender<()>>>,
+    work_finished_receiver: Mutex<Option<oneshot::Receiver<Result<()>>>>,
+}
+
+impl GrpcApi {
+    pub fn start(engine_context: Arc<EngineContext>, address: SocketAddr) -> Arc<Self> {
+        let (server_stopper_tx, server_stopper_rx) = oneshot::channel();
+        let (work_finished_sender, work_finished_receiver) = oneshot::channel();
+
+        let service = ControlServer::new(ControlService {
+            engine_context: engine_context.clone(),
+        });
+        spawn_future(
+            "Run gRPC API server",
+            SpawnFutureFlags::STOP_BY_TOKEN | SpawnFutureFlags::DENY_CANCELLATION,
+            async move {
+                let result = tonic::transport::Server::builder()
+                    .add_service(service)
+                    .serve_with_shutdown(address, async {
+                        let _ = server_stopper_rx.await;
+                    })
+                    .await
+                    .with_context(|| format!("running gRPC API server on {address}"));
+                if let Err(err) = &result {
+                    log::error!("{err:?}");
+                }
+
+                let _ = work_finished_sender.send(result);
+                Ok(())
+            },
+        );
+        log::info!("gRPC API is started on {address}");
+
+        let grpc_api = Arc::new(GrpcApi {
+            address,
+            server_stopper_tx: Mutex::new(Some(server_stopper_tx)),
+            work_finished_receiver: Mutex::new(Some(work_finished_receiver)),
+        });
+        engine_context
+            .shutdown_service
+            .register_user_service(grpc_api.clone());
+
+        grpc_api
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Service for GrpcApi {
+    fn name(&self) -> &str {
+        "GrpcApi"
+    }
+
+    fn graceful_shutdown(self: Arc<Self>) -> Option<oneshot::Receiver<Result<()>>> {
+        if let Some(server_stopper_tx) = self.server_stopper_tx.lock().take() {
+            let _ = server_stopper_tx.send(());
+        }
+
+        self.work_finished_receiver.lock().take()
+    }
+}
@@ -16,5 +16,8 @@
     clippy::unwrap_used
 )]
 
+pub mod order_history;
 #[allow(dead_code)] // TODO: delete it after start using
 pub mod postgres_db;
+pub mod sqlite_db;
+pub mod storage;
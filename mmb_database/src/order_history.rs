@@ -0,0 +1,112 @@
+use crate::storage::{EventStorage, EventsFilter};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+
+const ORDER_STATE_TRANSITIONS_TABLE: &str = "order_state_transitions";
+const ORDER_FILLS_TABLE: &str = "order_fills";
+
+/// Loads all saved state transitions of order ordered by saving time
+pub async fn load_order_state_transitions(
+    storage: &dyn EventStorage,
+    client_order_id: &str,
+) -> Result<Vec<JsonValue>> {
+    let filter = EventsFilter {
+        json_fields: &[("client_order_id", client_order_id)],
+        ..Default::default()
+    };
+
+    storage
+        .load_events(ORDER_STATE_TRANSITIONS_TABLE, &filter)
+        .await
+}
+
+/// Loads fills of exchange account saved within `[from, to)` ordered by saving time
+pub async fn load_order_fills(
+    storage: &dyn EventStorage,
+    exchange_account_id: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<JsonValue>> {
+    let filter = EventsFilter {
+        json_fields: &[("exchange_account_id", exchange_account_id)],
+        from: Some(from),
+        to: Some(to),
+        ..Default::default()
+    };
+
+    storage.load_events(ORDER_FILLS_TABLE, &filter).await
+}
+
+/// Conditions of order history query. Unset conditions aren't checked
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    pub exchange_account_id: Option<String>,
+    /// Currency pair in format of `CurrencyPair`, e.g. `btc/usdt`
+    pub currency_pair: Option<String>,
+    pub strategy_name: Option<String>,
+    /// Records saved at this time or later
+    pub from: Option<DateTime<Utc>>,
+    /// Records saved before this time
+    pub to: Option<DateTime<Utc>>,
+    /// `HistoryPage::next_cursor` of the previous page. The first page is loaded if it isn't set
+    pub cursor: Option<i64>,
+    pub page_size: u32,
+}
+
+/// Records ordered by saving time
+#[derive(Debug, Clone)]
+pub struct HistoryPage {
+    pub records: Vec<JsonValue>,
+    /// Cursor of the next page, it isn't set for the last page
+    pub next_cursor: Option<i64>,
+}
+
+/// Loads page of saved order state transitions
+pub async fn query_order_state_transitions(
+    storage: &dyn EventStorage,
+    query: &HistoryQuery,
+) -> Result<HistoryPage> {
+    query_history(storage, ORDER_STATE_TRANSITIONS_TABLE, query).await
+}
+
+/// Loads page of saved order fills
+pub async fn query_order_fills(
+    storage: &dyn EventStorage,
+    query: &HistoryQuery,
+) -> Result<HistoryPage> {
+    query_history(storage, ORDER_FILLS_TABLE, query).await
+}
+
+async fn query_history(
+    storage: &dyn EventStorage,
+    table_name: &str,
+    query: &HistoryQuery,
+) -> Result<HistoryPage> {
+    let json_fields: Vec<_> = [
+        ("exchange_account_id", &query.exchange_account_id),
+        ("currency_pair", &query.currency_pair),
+        ("strategy_name", &query.strategy_name),
+    ]
+    .into_iter()
+    .filter_map(|(field, value)| Some((field, value.as_deref()?)))
+    .collect();
+
+    let filter = EventsFilter {
+        json_fields: &json_fields,
+        from: query.from,
+        to: query.to,
+        after_id: query.cursor,
+        limit: Some(query.page_size),
+    };
+    let events = storage.load_stored_events(table_name, &filter).await?;
+
+    let next_cursor = match events.len() == query.page_size as usize {
+        true => events.last().map(|x| x.id),
+        false => None,
+    };
+    Ok(HistoryPage {
+        records: events.into_iter().map(|x| x.json).collect(),
+        next_cursor,
+    })
+}
@@ -1,5 +1,7 @@
 use crate::postgres_db::PgPool;
+use crate::storage::{EventStorage, EventsFilter, StoredEvent};
 use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
 use bb8_postgres::bb8::PooledConnection;
 use bb8_postgres::PostgresConnectionManager;
 use chrono::{DateTime, Utc};
@@ -8,7 +10,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::fmt::{Display, Formatter};
 use tokio_postgres::binary_copy::BinaryCopyInWriter;
-use tokio_postgres::types::Type;
+use tokio_postgres::types::{ToSql, Type};
 use tokio_postgres::{NoTls, Statement};
 pub type TableName = &'static str;
 pub type TableNameRef<'a> = &'a str;
@@ -159,6 +161,71 @@ pub async fn save_events_one_by_one(
     (Ok(()), failed_events)
 }
 
+#[async_trait]
+impl EventStorage for PgPool {
+    async fn save_events_batch(&self, table_name: &str, events: &[InsertEvent]) -> Result<()> {
+        save_events_batch(self, table_name, events).await
+    }
+
+    async fn save_events_one_by_one(
+        &self,
+        table_name: &str,
+        events: Vec<InsertEvent>,
+    ) -> (Result<()>, Vec<InsertEvent>) {
+        save_events_one_by_one(self, table_name, events).await
+    }
+
+    async fn is_connection_health(&self) -> bool {
+        PgPool::is_connection_health(self).await
+    }
+
+    async fn load_stored_events(
+        &self,
+        table_name: &str,
+        filter: &EventsFilter<'_>,
+    ) -> Result<Vec<StoredEvent>> {
+        let mut sql = format!("SELECT id, json FROM {table_name} WHERE true");
+        let mut params: Vec<&(dyn ToSql + Sync)> = Vec::new();
+        for (field, value) in filter.json_fields {
+            params.push(value);
+            sql += &format!(" AND json ->> '{field}' = ${}", params.len());
+        }
+        if let Some(from) = &filter.from {
+            params.push(from);
+            sql += &format!(" AND insert_time >= ${}", params.len());
+        }
+        if let Some(to) = &filter.to {
+            params.push(to);
+            sql += &format!(" AND insert_time < ${}", params.len());
+        }
+        if let Some(after_id) = &filter.after_id {
+            params.push(after_id);
+            sql += &format!(" AND id > ${}", params.len());
+        }
+        sql += " ORDER BY id";
+        if let Some(limit) = filter.limit {
+            sql += &format!(" LIMIT {limit}");
+        }
+
+        let rows = self
+            .0
+            .get()
+            .await
+            .context("getting db connection from pool")?
+            .query(&sql, &params)
+            .await
+            .with_context(|| format!("loading events from {table_name}"))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| StoredEvent {
+                id: row.get("id"),
+                json: row.get("json"),
+            })
+            .collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::postgres_db::events::{save_events_batch, save_events_one_by_one, InsertEvent};
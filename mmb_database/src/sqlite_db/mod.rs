@@ -0,0 +1,280 @@
+use crate::postgres_db::events::InsertEvent;
+use crate::storage::{EventStorage, EventsFilter, StoredEvent};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+const INSERT_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+/// Embedded storage of events in SQLite database file for deployments without Postgres.
+/// Tables of events are created on first saving, so migrations aren't needed
+pub struct SqliteStorage {
+    pool: SqlitePool,
+    created_tables: Mutex<HashSet<String>>,
+}
+
+impl SqliteStorage {
+    pub async fn create(database_url: &str, max_size: u32) -> Result<SqliteStorage> {
+        let options = SqliteConnectOptions::from_str(database_url)
+            .context("building sqlite connection options")?
+            .create_if_missing(true);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_size)
+            .connect_with(options)
+            .await
+            .context("building sqlite connection pool")?;
+
+        Ok(SqliteStorage {
+            pool,
+            created_tables: Mutex::new(HashSet::new()),
+        })
+    }
+
+    async fn ensure_table_created(&self, table_name: &str) -> Result<()> {
+        if self.created_tables.lock().contains(table_name) {
+            return Ok(());
+        }
+
+        // insert time is saved in the same format as `INSERT_TIME_FORMAT`, so it can be compared as string
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {table_name} (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                insert_time TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+                version INTEGER,
+                json TEXT NOT NULL
+            )"
+        );
+        let _ = sqlx::query(&sql)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("creating sqlite table {table_name}"))?;
+
+        let _ = self.created_tables.lock().insert(table_name.to_owned());
+        Ok(())
+    }
+}
+
+fn insert_sql(table_name: &str) -> String {
+    format!("INSERT INTO {table_name} (version, json) VALUES (?, ?)")
+}
+
+#[async_trait]
+impl EventStorage for SqliteStorage {
+    async fn save_events_batch(&self, table_name: &str, events: &[InsertEvent]) -> Result<()> {
+        self.ensure_table_created(table_name).await?;
+
+        let sql = insert_sql(table_name);
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .context("from `save_events_batch` on begin transaction")?;
+        for event in events {
+            let _ = sqlx::query(&sql)
+                .bind(event.version)
+                .bind(event.json.to_string())
+                .execute(&mut transaction)
+                .await
+                .context("from `save_events_batch` on insert")?;
+        }
+
+        transaction
+            .commit()
+            .await
+            .context("from `save_events_batch` on commit transaction")
+    }
+
+    async fn save_events_one_by_one(
+        &self,
+        table_name: &str,
+        events: Vec<InsertEvent>,
+    ) -> (Result<()>, Vec<InsertEvent>) {
+        if let Err(err) = self.ensure_table_created(table_name).await {
+            return (Err(err), events);
+        }
+
+        let sql = insert_sql(table_name);
+        let mut failed_events = vec![];
+        for event in events {
+            let insert_result = sqlx::query(&sql)
+                .bind(event.version)
+                .bind(event.json.to_string())
+                .execute(&self.pool)
+                .await;
+
+            if let Err(err) = insert_result {
+                log::error!(
+                    "in `save_events_one_by_one` with error {err} failed saving event: {event}"
+                );
+                failed_events.push(event);
+            }
+        }
+
+        (Ok(()), failed_events)
+    }
+
+    async fn is_connection_health(&self) -> bool {
+        self.pool.acquire().await.is_ok()
+    }
+
+    async fn load_stored_events(
+        &self,
+        table_name: &str,
+        filter: &EventsFilter<'_>,
+    ) -> Result<Vec<StoredEvent>> {
+        self.ensure_table_created(table_name).await?;
+
+        let mut sql = format!("SELECT id, json FROM {table_name} WHERE 1 = 1");
+        for (field, _) in filter.json_fields {
+            sql += &format!(" AND json_extract(json, '$.{field}') = ?");
+        }
+        if filter.from.is_some() {
+            sql += " AND insert_time >= ?";
+        }
+        if filter.to.is_some() {
+            sql += " AND insert_time < ?";
+        }
+        if filter.after_id.is_some() {
+            sql += " AND id > ?";
+        }
+        sql += " ORDER BY id";
+        if let Some(limit) = filter.limit {
+            sql += &format!(" LIMIT {limit}");
+        }
+
+        let mut query = sqlx::query(&sql);
+        for (_, value) in filter.json_fields {
+            query = query.bind(*value);
+        }
+        for time in [filter.from, filter.to].into_iter().flatten() {
+            query = query.bind(time.format(INSERT_TIME_FORMAT).to_string());
+        }
+        if let Some(after_id) = filter.after_id {
+            query = query.bind(after_id);
+        }
+
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| format!("loading events from {table_name}"))?;
+
+        rows.iter()
+            .map(|row| {
+                let json: String = row.try_get("json")?;
+                Ok(StoredEvent {
+                    id: row.try_get("id")?,
+                    json: serde_json::from_str(&json).context("parsing json of saved event")?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    const TABLE_NAME: &str = "persons";
+
+    async fn storage() -> SqliteStorage {
+        // every connection to in-memory database opens its own database
+        SqliteStorage::create("sqlite::memory:", 1)
+            .await
+            .expect("in test")
+    }
+
+    fn event(first_name: &str) -> InsertEvent {
+        InsertEvent {
+            version: 1,
+            json: json!({ "first_name": first_name }),
+        }
+    }
+
+    #[tokio::test]
+    async fn save_and_load_events() {
+        let storage = storage().await;
+
+        storage
+            .save_events_batch(TABLE_NAME, &[event("Ivan"), event("Petr")])
+            .await
+            .expect("in test");
+        let (result, failed_events) = storage
+            .save_events_one_by_one(TABLE_NAME, vec![event("Ivan")])
+            .await;
+        result.expect("in test");
+        assert!(failed_events.is_empty());
+
+        let all_events = storage
+            .load_events(TABLE_NAME, &EventsFilter::default())
+            .await
+            .expect("in test");
+        assert_eq!(
+            all_events,
+            vec![event("Ivan").json, event("Petr").json, event("Ivan").json]
+        );
+
+        let filtered_events = storage
+            .load_events(
+                TABLE_NAME,
+                &EventsFilter {
+                    json_fields: &[("first_name", "Ivan")],
+                    from: Some(Utc::now() - Duration::minutes(1)),
+                    to: Some(Utc::now() + Duration::minutes(1)),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("in test");
+        assert_eq!(filtered_events.len(), 2);
+
+        let future_events = storage
+            .load_events(
+                TABLE_NAME,
+                &EventsFilter {
+                    from: Some(Utc::now() + Duration::minutes(1)),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("in test");
+        assert!(future_events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn load_events_by_pages() {
+        let storage = storage().await;
+        let names = ["Ivan", "Petr", "Ivan", "Ivan"];
+        let events: Vec<_> = names.into_iter().map(event).collect();
+        storage
+            .save_events_batch(TABLE_NAME, &events)
+            .await
+            .expect("in test");
+
+        let mut filter = EventsFilter {
+            json_fields: &[("first_name", "Ivan")],
+            limit: Some(2),
+            ..Default::default()
+        };
+        let first_page = storage
+            .load_stored_events(TABLE_NAME, &filter)
+            .await
+            .expect("in test");
+        let ids: Vec<_> = first_page.iter().map(|x| x.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+
+        filter.after_id = Some(3);
+        let second_page = storage
+            .load_stored_events(TABLE_NAME, &filter)
+            .await
+            .expect("in test");
+        assert_eq!(second_page.len(), 1);
+        assert_eq!(second_page[0].id, 4);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::postgres_db::events::InsertEvent;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+
+/// Conditions for loading of saved events. Unset conditions aren't checked
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EventsFilter<'a> {
+    /// Names of top level json fields and their expected string values
+    pub json_fields: &'a [(&'a str, &'a str)],
+    /// Events saved at this time or later
+    pub from: Option<DateTime<Utc>>,
+    /// Events saved before this time
+    pub to: Option<DateTime<Utc>>,
+    /// Events with greater id, i.e. saved after event with this id
+    pub after_id: Option<i64>,
+    /// Max count of loaded events
+    pub limit: Option<u32>,
+}
+
+/// Saved event with its auto incremented id
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredEvent {
+    pub id: i64,
+    pub json: JsonValue,
+}
+
+/// Backend for persistence of events. Every event table consists of
+/// auto incremented `id`, `insert_time`, `version` and `json` columns
+#[async_trait]
+pub trait EventStorage: Send + Sync {
+    async fn save_events_batch(&self, table_name: &str, events: &[InsertEvent]) -> Result<()>;
+
+    /// Saves events separately, so single invalid event doesn't prevent saving of others.
+    /// Returns events which weren't saved
+    async fn save_events_one_by_one(
+        &self,
+        table_name: &str,
+        events: Vec<InsertEvent>,
+    ) -> (Result<()>, Vec<InsertEvent>);
+
+    async fn is_connection_health(&self) -> bool;
+
+    /// Loads events ordered by saving
+    async fn load_stored_events(
+        &self,
+        table_name: &str,
+        filter: &EventsFilter<'_>,
+    ) -> Result<Vec<StoredEvent>>;
+
+    /// Loads json of events ordered by saving
+    async fn load_events(
+        &self,
+        table_name: &str,
+        filter: &EventsFilter<'_>,
+    ) -> Result<Vec<JsonValue>> {
+        let events = self.load_stored_events(table_name, filter).await?;
+        Ok(events.into_iter().map(|x| x.json).collect())
+    }
+}
@@ -11,6 +11,12 @@ pub trait MmbRpc {
     #[rpc(name = "health")]
     fn health(&self) -> Result<String>;
 
+    #[rpc(name = "health_live")]
+    fn health_live(&self) -> Result<String>;
+
+    #[rpc(name = "health_ready")]
+    fn health_ready(&self) -> Result<String>;
+
     #[rpc(name = "stop")]
     fn stop(&self) -> Result<String>;
 
@@ -22,12 +28,73 @@ pub trait MmbRpc {
 
     #[rpc(name = "stats")]
     fn stats(&self) -> Result<String>;
+
+    #[rpc(name = "open_orders")]
+    fn open_orders(&self) -> Result<String>;
+
+    #[rpc(name = "portfolio")]
+    fn portfolio(&self) -> Result<String>;
+
+    #[rpc(name = "positions")]
+    fn positions(&self) -> Result<String>;
+
+    #[rpc(name = "cancel_order")]
+    fn cancel_order(&self, client_order_id: String) -> Result<String>;
+
+    #[rpc(name = "unmatched_events")]
+    fn unmatched_events(&self) -> Result<String>;
+
+    #[rpc(name = "cold_start")]
+    fn cold_start(&self) -> Result<String>;
+
+    #[rpc(name = "confirm_cold_start")]
+    fn confirm_cold_start(&self) -> Result<String>;
+
+    #[rpc(name = "loss_limit")]
+    fn loss_limit(&self) -> Result<String>;
+
+    #[rpc(name = "acknowledge_loss_limit")]
+    fn acknowledge_loss_limit(&self) -> Result<String>;
+
+    #[rpc(name = "rebalancing")]
+    fn rebalancing(&self) -> Result<String>;
+
+    #[rpc(name = "approve_rebalancing")]
+    fn approve_rebalancing(&self, decision_id: u64) -> Result<String>;
+
+    #[rpc(name = "reject_rebalancing")]
+    fn reject_rebalancing(&self, decision_id: u64) -> Result<String>;
+
+    #[rpc(name = "strategies")]
+    fn strategies(&self) -> Result<String>;
+
+    #[rpc(name = "start_strategy")]
+    fn start_strategy(&self, name: String) -> Result<String>;
+
+    #[rpc(name = "stop_strategy")]
+    fn stop_strategy(&self, name: String) -> Result<String>;
+
+    #[rpc(name = "pause_strategy")]
+    fn pause_strategy(&self, name: String) -> Result<String>;
+
+    #[rpc(name = "resume_strategy")]
+    fn resume_strategy(&self, name: String) -> Result<String>;
+
+    #[rpc(name = "signals")]
+    fn signals(&self) -> Result<String>;
+
+    #[rpc(name = "push_signals")]
+    fn push_signals(&self, signals: String) -> Result<String>;
+
+    #[rpc(name = "kill_switch")]
+    fn kill_switch(&self, flatten_positions: bool) -> Result<String>;
 }
 
 pub enum ErrorCode {
     StopperIsNone = 1,
     UnableToSendSignal = 2,
     FailedToSaveNewConfig = 3,
+    FailedToSerializeResponse = 4,
 }
 
 pub fn server_side_error(code: ErrorCode) -> Error {
@@ -35,6 +102,7 @@ pub fn server_side_error(code: ErrorCode) -> Error {
         ErrorCode::StopperIsNone => "Server stopper is none",
         ErrorCode::UnableToSendSignal => "Unable to send signal",
         ErrorCode::FailedToSaveNewConfig => "Failed to save new config",
+        ErrorCode::FailedToSerializeResponse => "Failed to serialize response",
     };
     log::error!("Rest API error: {}", reason);
     Error::new(jsonrpc_core::ErrorCode::ServerError(code as i64))
@@ -23,9 +23,11 @@ pub mod impl_mocks;
 pub mod impl_table_types;
 pub mod infrastructure;
 pub mod logger;
+pub mod object_pool;
 pub mod panic;
 pub mod send_expected;
 pub mod time;
+pub mod tracing_bridge;
 pub mod value_to_decimal;
 
 use chrono::Utc;
@@ -1,3 +1,4 @@
+use crate::tracing_bridge::init_tracing;
 use anyhow::{Context, Result};
 use log4rs::config::Deserializers;
 use log4rs::init_file;
@@ -9,6 +10,8 @@ use std::sync::Once;
 use std::{env, fs};
 
 pub fn init_logger() {
+    init_tracing();
+
     if env::var("MMB_NO_LOGS").is_ok() {
         return;
     }
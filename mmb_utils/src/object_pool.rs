@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+/// Object which can be cleared for reuse keeping its allocated memory
+pub trait Recycle {
+    fn recycle(&mut self);
+}
+
+impl<T> Recycle for Vec<T> {
+    fn recycle(&mut self) {
+        self.clear();
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Objects created because there was no free object in the pool
+    pub created: u64,
+    pub reused: u64,
+}
+
+#[derive(Debug, Default)]
+struct PoolCounters {
+    created: AtomicU64,
+    reused: AtomicU64,
+}
+
+impl PoolCounters {
+    fn register(&self, is_reused: bool) {
+        let counter = match is_reused {
+            true => &self.reused,
+            false => &self.created,
+        };
+        let _ = counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> PoolStats {
+        PoolStats {
+            created: self.created.load(Ordering::Relaxed),
+            reused: self.reused.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Pool of objects which are taken for a short time, e.g. buffers. Taken object is returned to
+/// the pool when its guard is dropped. Pool with zero capacity doesn't keep objects
+#[derive(Debug)]
+pub struct ObjectPool<T> {
+    objects: Mutex<Vec<T>>,
+    capacity: AtomicUsize,
+    counters: PoolCounters,
+}
+
+impl<T: Default + Recycle> ObjectPool<T> {
+    pub fn new(capacity: usize) -> Self {
+        ObjectPool {
+            objects: Mutex::new(Vec::with_capacity(capacity)),
+            capacity: AtomicUsize::new(capacity),
+            counters: Default::default(),
+        }
+    }
+
+    pub fn take(&self) -> Pooled<'_, T> {
+        let object = self.objects.lock().pop();
+        self.counters.register(object.is_some());
+        Pooled {
+            pool: self,
+            object: Some(object.unwrap_or_default()),
+        }
+    }
+
+    fn put_back(&self, mut object: T) {
+        let mut objects = self.objects.lock();
+        if objects.len() < self.capacity.load(Ordering::Relaxed) {
+            object.recycle();
+            objects.push(object);
+        }
+    }
+
+    /// Free objects above new capacity are dropped
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity, Ordering::Relaxed);
+        self.objects.lock().truncate(capacity);
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.counters.stats()
+    }
+}
+
+/// Object taken from `ObjectPool`
+pub struct Pooled<'a, T: Default + Recycle> {
+    pool: &'a ObjectPool<T>,
+    object: Option<T>,
+}
+
+impl<T: Default + Recycle> Deref for Pooled<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.object.as_ref().expect("object is taken only on drop")
+    }
+}
+
+impl<T: Default + Recycle> DerefMut for Pooled<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.object.as_mut().expect("object is taken only on drop")
+    }
+}
+
+impl<T: Default + Recycle> Drop for Pooled<'_, T> {
+    fn drop(&mut self) {
+        if let Some(object) = self.object.take() {
+            self.pool.put_back(object);
+        }
+    }
+}
+
+/// Pool of objects which are shared with consumers through `Arc`, e.g. snapshots sent in events.
+/// Pool keeps references to given objects, and object is reused once all consumers dropped their
+/// references. Objects are given in order, so the oldest one is checked for reuse
+#[derive(Debug)]
+pub struct SharedPool<T> {
+    objects: Mutex<VecDeque<Arc<T>>>,
+    capacity: AtomicUsize,
+    counters: PoolCounters,
+}
+
+impl<T> SharedPool<T> {
+    pub fn new(capacity: usize) -> Self {
+        SharedPool {
+            objects: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity: AtomicUsize::new(capacity),
+            counters: Default::default(),
+        }
+    }
+
+    /// Object created by `create` or free object of the pool overwritten by `reuse`
+    pub fn take(&self, create: impl FnOnce() -> T, reuse: impl FnOnce(&mut T)) -> Arc<T> {
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        if capacity == 0 {
+            self.counters.register(false);
+            return Arc::new(create());
+        }
+
+        let mut objects = self.objects.lock();
+        let free = match objects.front_mut().and_then(Arc::get_mut) {
+            Some(object) => {
+                reuse(object);
+                objects.pop_front()
+            }
+            None => {
+                // the oldest object is still used by consumers, so it's left to them
+                if objects.len() >= capacity {
+                    let _ = objects.pop_front();
+                }
+                None
+            }
+        };
+        self.counters.register(free.is_some());
+
+        let object = free.unwrap_or_else(|| Arc::new(create()));
+        objects.push_back(object.clone());
+        object
+    }
+
+    /// Objects above new capacity are dropped by the pool
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity, Ordering::Relaxed);
+        let mut objects = self.objects.lock();
+        while objects.len() > capacity {
+            let _ = objects.pop_front();
+        }
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.counters.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn object_is_reused_after_guard_is_dropped() {
+        let pool = ObjectPool::<Vec<u8>>::new(2);
+        {
+            let mut buffer = pool.take();
+            buffer.extend_from_slice(&[1, 2, 3]);
+            // pool is empty, so one more buffer is created
+            let _other = pool.take();
+        }
+
+        let buffer = pool.take();
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= 3);
+        assert_eq!(
+            pool.stats(),
+            PoolStats {
+                created: 2,
+                reused: 1
+            }
+        );
+    }
+
+    #[test]
+    fn shared_object_is_reused_after_consumers_dropped_it() {
+        let pool = SharedPool::new(2);
+        let first = pool.take(|| vec![1], |_| panic!("pool is empty"));
+        let second = pool.take(|| vec![2], |_| panic!("first object is used"));
+        drop(first);
+
+        let third = pool.take(|| vec![3], |x| x[0] = 3);
+        assert_eq!(third[0], 3);
+        assert_eq!(
+            pool.stats(),
+            PoolStats {
+                created: 2,
+                reused: 1
+            }
+        );
+
+        // second object is still used and pool is full, so second is left to its consumers
+        let fourth = pool.take(|| vec![4], |_| panic!("second object is used"));
+        assert_eq!((second[0], fourth[0]), (2, 4));
+        assert_eq!(pool.objects.lock().len(), 2);
+    }
+}
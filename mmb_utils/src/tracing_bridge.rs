@@ -0,0 +1,309 @@
+use std::fmt::{Debug, Write};
+use std::sync::Once;
+
+use anyhow::{bail, Result};
+use once_cell::sync::OnceCell;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layered, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{registry, Layer, Registry};
+
+/// Subscriber which extra layer, e.g. exporter of spans, is attached to
+pub type LogSubscriber = Layered<LogLayer, Registry>;
+pub type BoxedLayer = Box<dyn Layer<LogSubscriber> + Send + Sync>;
+
+struct ExtraLayer {
+    layer: BoxedLayer,
+    target_prefixes: Vec<String>,
+}
+
+static EXTRA_LAYER: OnceCell<ExtraLayer> = OnceCell::new();
+
+/// Sets global `tracing` subscriber writing events to log. Extra layer can be attached to it later
+/// by [`set_extra_layer`] because settings of exporters are loaded after logger is initialized
+pub fn init_tracing() {
+    static INIT_TRACING: Once = Once::new();
+    INIT_TRACING.call_once(|| {
+        let subscriber = registry().with(LogLayer::default()).with(ExtraLayerSlot);
+        tracing::subscriber::set_global_default(subscriber)
+            .expect("Unable to set up tracing subscriber");
+    });
+}
+
+/// Attaches layer to global `tracing` subscriber. Layer gets only spans and events with target
+/// starting with one of `target_prefixes`, so e.g. exporter doesn't get spans of its own requests
+pub fn set_extra_layer(layer: BoxedLayer, target_prefixes: Vec<String>) -> Result<()> {
+    init_tracing();
+    if EXTRA_LAYER
+        .set(ExtraLayer {
+            layer,
+            target_prefixes,
+        })
+        .is_err()
+    {
+        bail!("Extra layer is already attached to tracing subscriber");
+    }
+    Ok(())
+}
+
+fn extra_layer(metadata: Option<&Metadata<'_>>) -> Option<&'static BoxedLayer> {
+    let extra_layer = EXTRA_LAYER.get()?;
+    let target = metadata?.target();
+    extra_layer
+        .target_prefixes
+        .iter()
+        .any(|x| target.starts_with(x.as_str()))
+        .then_some(&extra_layer.layer)
+}
+
+/// Forwards spans and events to layer attached by [`set_extra_layer`]
+struct ExtraLayerSlot;
+
+impl Layer<LogSubscriber> for ExtraLayerSlot {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, LogSubscriber>) {
+        if let Some(layer) = extra_layer(Some(attrs.metadata())) {
+            layer.on_new_span(attrs, id, ctx);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, LogSubscriber>) {
+        if let Some(layer) = extra_layer(ctx.metadata(id)) {
+            layer.on_record(id, values, ctx);
+        }
+    }
+
+    fn on_follows_from(&self, id: &Id, follows: &Id, ctx: Context<'_, LogSubscriber>) {
+        if let Some(layer) = extra_layer(ctx.metadata(id)) {
+            layer.on_follows_from(id, follows, ctx);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, LogSubscriber>) {
+        if let Some(layer) = extra_layer(Some(event.metadata())) {
+            layer.on_event(event, ctx);
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, LogSubscriber>) {
+        if let Some(layer) = extra_layer(ctx.metadata(id)) {
+            layer.on_enter(id, ctx);
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, LogSubscriber>) {
+        if let Some(layer) = extra_layer(ctx.metadata(id)) {
+            layer.on_exit(id, ctx);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, LogSubscriber>) {
+        if let Some(layer) = extra_layer(ctx.metadata(&id)) {
+            layer.on_close(id, ctx);
+        }
+    }
+}
+
+/// Writes `tracing` events as `log` records, so they are handled by the same log4rs appenders as
+/// other logs. Message is prefixed by entered spans with their fields, e.g.
+/// `wait_cancel_order{client_order_id=1 exchange_account_id=Binance_0}: Cancellation iteration is 2`.
+/// Fields already written by outer span are skipped for nested spans
+pub struct LogLayer {
+    logger: fn() -> &'static dyn log::Log,
+}
+
+impl Default for LogLayer {
+    fn default() -> Self {
+        LogLayer {
+            logger: log::logger,
+        }
+    }
+}
+
+/// Fields of span as `(name, value)` pairs in order of declaration
+struct SpanFields(Vec<(&'static str, String)>);
+
+#[derive(Default)]
+struct FieldsVisitor {
+    message: String,
+    fields: Vec<(&'static str, String)>,
+}
+
+impl FieldsVisitor {
+    fn push(&mut self, field: &Field, value: String) {
+        match field.name() {
+            "message" => self.message = value,
+            name => self.fields.push((name, value)),
+        }
+    }
+}
+
+impl Visit for FieldsVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.push(field, format!("{value:?}"));
+    }
+}
+
+fn log_level(level: &Level) -> log::Level {
+    match *level {
+        Level::ERROR => log::Level::Error,
+        Level::WARN => log::Level::Warn,
+        Level::INFO => log::Level::Info,
+        Level::DEBUG => log::Level::Debug,
+        Level::TRACE => log::Level::Trace,
+    }
+}
+
+impl<S> Layer<S> for LogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut visitor = FieldsVisitor::default();
+            attrs.record(&mut visitor);
+            span.extensions_mut().insert(SpanFields(visitor.fields));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return,
+        };
+
+        let mut visitor = FieldsVisitor::default();
+        values.record(&mut visitor);
+
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            for (name, value) in visitor.fields {
+                match fields.iter_mut().find(|(x, _)| *x == name) {
+                    Some(field) => field.1 = value,
+                    None => fields.push((name, value)),
+                }
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let log_metadata = log::Metadata::builder()
+            .level(log_level(metadata.level()))
+            .target(metadata.target())
+            .build();
+
+        let logger = (self.logger)();
+        if log_metadata.level() > log::max_level() || !logger.enabled(&log_metadata) {
+            return;
+        }
+
+        let mut text = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            let mut written: Vec<(&'static str, String)> = Vec::new();
+            for span in scope.from_root() {
+                text.push_str(span.name());
+
+                let extensions = span.extensions();
+                let fields = extensions
+                    .get::<SpanFields>()
+                    .map(|x| x.0.as_slice())
+                    .unwrap_or_default();
+                let new_fields: Vec<_> = fields.iter().filter(|x| !written.contains(x)).collect();
+                for (i, (name, value)) in new_fields.iter().enumerate() {
+                    let separator = if i == 0 { '{' } else { ' ' };
+                    let _ = write!(text, "{separator}{name}={value}");
+                }
+                if !new_fields.is_empty() {
+                    text.push('}');
+                }
+                written.extend(new_fields.into_iter().cloned());
+
+                text.push(':');
+            }
+            text.push(' ');
+        }
+
+        let mut visitor = FieldsVisitor::default();
+        event.record(&mut visitor);
+        text.push_str(&visitor.message);
+        for (name, value) in &visitor.fields {
+            let _ = write!(text, " {name}={value}");
+        }
+
+        logger.log(
+            &log::Record::builder()
+                .metadata(log_metadata)
+                .module_path(metadata.module_path())
+                .file(metadata.file())
+                .line(metadata.line())
+                .args(format_args!("{text}"))
+                .build(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    struct CapturingLogger(Mutex<Vec<String>>);
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            self.0
+                .lock()
+                .push(format!("{} {}", record.level(), record.args()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CapturingLogger = CapturingLogger(parking_lot::const_mutex(Vec::new()));
+
+    fn capturing_logger() -> &'static dyn log::Log {
+        &LOGGER
+    }
+
+    #[test]
+    fn events_contain_fields_of_entered_spans() {
+        log::set_max_level(log::LevelFilter::Trace);
+        let layer = LogLayer {
+            logger: capturing_logger,
+        };
+
+        tracing::subscriber::with_default(registry().with(layer), || {
+            let span = tracing::info_span!(
+                "wait_cancel_order",
+                client_order_id = "1",
+                exchange_order_id = tracing::field::Empty,
+                exchange_account_id = "Binance_0"
+            );
+            let _entered = span.enter();
+            tracing::info!("Started");
+
+            span.record("exchange_order_id", "100");
+            let nested = tracing::info_span!("cancel_order", client_order_id = "1", attempt = 2);
+            let _entered = nested.enter();
+            tracing::warn!(timeout_secs = 10, "Re-cancelling order");
+        });
+
+        assert_eq!(
+            *LOGGER.0.lock(),
+            vec![
+                "INFO wait_cancel_order{client_order_id=1 exchange_account_id=Binance_0}: Started",
+                "WARN wait_cancel_order{client_order_id=1 exchange_account_id=Binance_0 exchange_order_id=100}:cancel_order{attempt=2}: Re-cancelling order timeout_secs=10",
+            ]
+        );
+    }
+}
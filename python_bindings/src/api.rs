@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId, MarketAccountId};
+use mmb_domain::order::snapshot::{Amount, ClientOrderId, OrderSide, Price};
+use parking_lot::Mutex;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rust_decimal::Decimal;
+
+/// Request of Python strategy executed by the engine after return from strategy callback
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderIntent {
+    Create {
+        client_order_id: ClientOrderId,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+    },
+    Cancel {
+        client_order_id: ClientOrderId,
+    },
+}
+
+#[derive(Default)]
+pub(crate) struct ApiState {
+    pub intents: Vec<OrderIntent>,
+    pub order_book_subscriptions: HashSet<MarketAccountId>,
+    pub orders_subscription: bool,
+}
+
+/// Object passed to constructor of Python strategy. Order requests are collected while
+/// strategy callback is executed and then performed by the engine through risk checks
+/// of the strategy
+#[pyclass(name = "Engine")]
+pub struct EngineApi {
+    pub(crate) state: Arc<Mutex<ApiState>>,
+}
+
+#[pymethods]
+impl EngineApi {
+    /// Delivers order book top of market to `on_order_book` callback
+    fn subscribe_order_book(&self, exchange_account_id: &str, currency_pair: &str) -> PyResult<()> {
+        let market_account_id = MarketAccountId::new(
+            parse_exchange_account_id(exchange_account_id)?,
+            parse_currency_pair(currency_pair)?,
+        );
+        let _ = self
+            .state
+            .lock()
+            .order_book_subscriptions
+            .insert(market_account_id);
+        Ok(())
+    }
+
+    /// Delivers updates of orders of the strategy to `on_order` callback
+    fn subscribe_orders(&self) {
+        self.state.lock().orders_subscription = true;
+    }
+
+    /// Requests limit order and returns its client order id. Price and amount can be
+    /// `decimal.Decimal`, `str`, `int` or `float`
+    fn create_order(
+        &self,
+        exchange_account_id: &str,
+        currency_pair: &str,
+        side: &str,
+        price: &PyAny,
+        amount: &PyAny,
+    ) -> PyResult<String> {
+        let client_order_id = ClientOrderId::unique_id();
+        let intent = OrderIntent::Create {
+            client_order_id: client_order_id.clone(),
+            exchange_account_id: parse_exchange_account_id(exchange_account_id)?,
+            currency_pair: parse_currency_pair(currency_pair)?,
+            side: parse_side(side)?,
+            price: to_decimal(price)?,
+            amount: to_decimal(amount)?,
+        };
+        self.state.lock().intents.push(intent);
+
+        Ok(client_order_id.as_str().to_owned())
+    }
+
+    fn cancel_order(&self, client_order_id: &str) {
+        self.state.lock().intents.push(OrderIntent::Cancel {
+            client_order_id: client_order_id.into(),
+        });
+    }
+}
+
+fn value_error(err: anyhow::Error) -> PyErr {
+    PyValueError::new_err(format!("{err:#}"))
+}
+
+fn parse_exchange_account_id(value: &str) -> PyResult<ExchangeAccountId> {
+    value
+        .parse()
+        .map_err(|err| PyValueError::new_err(format!("{err:?}")))
+}
+
+fn parse_currency_pair(value: &str) -> PyResult<CurrencyPair> {
+    let (base, quote) = value
+        .split_once('/')
+        .with_context(|| format!("Currency pair '{value}' should be in format 'base/quote'"))
+        .map_err(value_error)?;
+    Ok(CurrencyPair::from_codes(base.into(), quote.into()))
+}
+
+fn parse_side(value: &str) -> PyResult<OrderSide> {
+    let side = match value.to_lowercase().as_str() {
+        "buy" => OrderSide::Buy,
+        "sell" => OrderSide::Sell,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "Unknown order side '{value}'"
+            )))
+        }
+    };
+    Ok(side)
+}
+
+fn to_decimal(value: &PyAny) -> PyResult<Decimal> {
+    let text = value.str()?.to_str()?.to_owned();
+    parse_decimal(&text).map_err(value_error)
+}
+
+fn parse_decimal(text: &str) -> Result<Decimal> {
+    match Decimal::from_str(text) {
+        Ok(value) => Ok(value),
+        Err(_) => match Decimal::from_scientific(text) {
+            Ok(value) => Ok(value),
+            Err(err) => bail!("Can't convert '{text}' to decimal: {err}"),
+        },
+    }
+}
@@ -0,0 +1,26 @@
+#![deny(
+    non_ascii_idents,
+    non_shorthand_field_patterns,
+    no_mangle_generic_items,
+    overflowing_literals,
+    path_statements,
+    unused_allocation,
+    unused_comparisons,
+    unused_parens,
+    while_true,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_must_use,
+    clippy::unwrap_used
+)]
+
+//! Python bindings for strategies. Strategy is written as Python class which receives engine
+//! API object to subscribe to market data and order events and to create or cancel orders,
+//! while connectivity, order lifecycle and risk checks are handled by the engine.
+
+// pyo3 macros generate fully qualified paths
+#[allow(unused_qualifications)]
+pub mod api;
+pub mod strategy;
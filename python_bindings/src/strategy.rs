@@ -0,0 +1,356 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use mmb_core::strategy_registry::{Strategy, StrategyContext};
+use mmb_domain::events::ExchangeEvent;
+use mmb_domain::market::MarketAccountId;
+use mmb_domain::order::pool::OrderRef;
+use mmb_domain::order::snapshot::{Amount, Price, UserOrder};
+use mmb_utils::time::get_current_milliseconds;
+use parking_lot::Mutex;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyModule, PyTuple};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::api::{ApiState, EngineApi, OrderIntent};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PythonStrategySettings {
+    /// Path to Python file with strategy class
+    pub path: PathBuf,
+    /// Name of strategy class. Its constructor receives engine API object
+    pub class_name: String,
+}
+
+/// Strategy implemented by Python class. Optional methods of the class are called by the engine:
+/// * `on_order_book(top)` - order book top of subscribed market was changed;
+/// * `on_order(order)` - subscribed order of the strategy was changed;
+/// * `on_timer(now_millis)` - periodical call with interval from strategy settings;
+/// * `on_stop()` - strategy is stopped.
+///
+/// Decimal values are passed as `decimal.Decimal`. Orders requested by Python code are created
+/// after return from callback, so they pass risk checks of the strategy as ordinary orders
+pub struct PythonStrategy {
+    name: String,
+    instance: Py<PyAny>,
+    api: Arc<Mutex<ApiState>>,
+}
+
+impl PythonStrategy {
+    pub fn load(settings: &PythonStrategySettings) -> Result<Self> {
+        let code = fs::read_to_string(&settings.path)
+            .with_context(|| format!("reading Python strategy {}", settings.path.display()))?;
+        let file_name = settings.path.to_string_lossy();
+        Self::from_code(&code, &file_name, &settings.class_name)
+    }
+
+    pub fn from_code(code: &str, file_name: &str, class_name: &str) -> Result<Self> {
+        let api = Arc::new(Mutex::new(ApiState::default()));
+        let module_name = file_name
+            .rsplit('/')
+            .next()
+            .unwrap_or(file_name)
+            .trim_end_matches(".py");
+
+        let instance = Python::with_gil(|py| -> PyResult<Py<PyAny>> {
+            let module = PyModule::from_code(py, code, file_name, module_name)?;
+            let engine = Py::new(py, EngineApi { state: api.clone() })?;
+            Ok(module.getattr(class_name)?.call1((engine,))?.into())
+        })
+        .with_context(|| format!("creating Python strategy {class_name} from {file_name}"))?;
+
+        Ok(PythonStrategy {
+            name: format!("{file_name}:{class_name}"),
+            instance,
+            api,
+        })
+    }
+
+    pub fn on_order_book_top(
+        &self,
+        market_account_id: MarketAccountId,
+        bid: Option<(Price, Amount)>,
+        ask: Option<(Price, Amount)>,
+    ) -> Result<Vec<OrderIntent>> {
+        if !self
+            .api
+            .lock()
+            .order_book_subscriptions
+            .contains(&market_account_id)
+        {
+            return Ok(Vec::new());
+        }
+
+        self.call("on_order_book", |py| {
+            let level = |level: Option<(Price, Amount)>| -> PyResult<PyObject> {
+                match level {
+                    Some((price, amount)) => {
+                        let level = (to_py_decimal(py, price)?, to_py_decimal(py, amount)?);
+                        Ok(level.into_py(py))
+                    }
+                    None => Ok(py.None()),
+                }
+            };
+
+            let top = PyDict::new(py);
+            top.set_item(
+                "exchange_account_id",
+                market_account_id.exchange_account_id.to_string(),
+            )?;
+            top.set_item("currency_pair", market_account_id.currency_pair.as_str())?;
+            top.set_item("bid", level(bid)?)?;
+            top.set_item("ask", level(ask)?)?;
+            Ok(PyTuple::new(py, [top]).into())
+        })
+    }
+
+    pub fn on_order(&self, order: &OrderRef) -> Result<Vec<OrderIntent>> {
+        if !self.api.lock().orders_subscription {
+            return Ok(Vec::new());
+        }
+
+        self.call("on_order", |py| {
+            let dict = PyDict::new(py);
+            dict.set_item("client_order_id", order.client_order_id().as_str())?;
+            dict.set_item(
+                "exchange_account_id",
+                order.exchange_account_id().to_string(),
+            )?;
+            dict.set_item("currency_pair", order.currency_pair().as_str())?;
+            dict.set_item("side", order.side().as_str())?;
+            dict.set_item("price", to_py_decimal(py, order.price())?)?;
+            dict.set_item("amount", to_py_decimal(py, order.amount())?)?;
+            dict.set_item("filled_amount", to_py_decimal(py, order.filled_amount())?)?;
+            dict.set_item("status", format!("{:?}", order.status()))?;
+            Ok(PyTuple::new(py, [dict]).into())
+        })
+    }
+
+    pub fn on_timer(&self, now_millis: i64) -> Result<Vec<OrderIntent>> {
+        self.call("on_timer", |py| Ok(PyTuple::new(py, [now_millis]).into()))
+    }
+
+    /// Calls method of strategy object if it exists and returns order intents requested by it.
+    /// Intents of failed call are discarded
+    fn call(
+        &self,
+        method: &str,
+        args: impl FnOnce(Python) -> PyResult<Py<PyTuple>>,
+    ) -> Result<Vec<OrderIntent>> {
+        Python::with_gil(|py| -> PyResult<()> {
+            let instance = self.instance.as_ref(py);
+            if instance.hasattr(method)? {
+                let _ = instance.call_method1(method, args(py)?.as_ref(py))?;
+            }
+            Ok(())
+        })
+        .map_err(|err| {
+            self.api.lock().intents.clear();
+            anyhow::Error::from(err)
+        })
+        .with_context(|| format!("calling {method} of Python strategy {}", self.name))?;
+
+        Ok(std::mem::take(&mut self.api.lock().intents))
+    }
+
+    fn intents_by_event(
+        &self,
+        ctx: &StrategyContext,
+        event: &ExchangeEvent,
+    ) -> Result<Vec<OrderIntent>> {
+        match event {
+            ExchangeEvent::OrderBookEvent(event) => {
+                let exchange = match ctx
+                    .engine_context()
+                    .exchanges
+                    .get(&event.exchange_account_id)
+                {
+                    Some(exchange) => exchange.clone(),
+                    None => return Ok(Vec::new()),
+                };
+                let (bid, ask) = match exchange.order_book_top.get(&event.currency_pair) {
+                    Some(top) => (
+                        top.bid.as_ref().map(|x| (x.price, x.amount)),
+                        top.ask.as_ref().map(|x| (x.price, x.amount)),
+                    ),
+                    None => return Ok(Vec::new()),
+                };
+
+                self.on_order_book_top(
+                    MarketAccountId::new(event.exchange_account_id, event.currency_pair),
+                    bid,
+                    ask,
+                )
+            }
+            ExchangeEvent::OrderEvent(event) => self.on_order(&event.order),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    async fn execute_intents(&self, ctx: &StrategyContext, intents: Vec<OrderIntent>) {
+        for intent in intents {
+            if let Err(err) = execute_intent(ctx, intent).await {
+                log::error!(
+                    "Failed to execute intent of Python strategy {}: {err:?}",
+                    self.name
+                );
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Strategy for PythonStrategy {
+    async fn on_event(&self, ctx: &StrategyContext, event: &ExchangeEvent) -> Result<()> {
+        let intents = self.intents_by_event(ctx, event)?;
+        self.execute_intents(ctx, intents).await;
+        Ok(())
+    }
+
+    async fn on_timer(&self, ctx: &StrategyContext) -> Result<()> {
+        let intents = PythonStrategy::on_timer(self, get_current_milliseconds())?;
+        self.execute_intents(ctx, intents).await;
+        Ok(())
+    }
+
+    async fn on_stop(&self, _ctx: &StrategyContext) -> Result<()> {
+        // orders can't be created by stopped strategy
+        let _ = self.call("on_stop", |py| Ok(PyTuple::empty(py).into()))?;
+        Ok(())
+    }
+}
+
+async fn execute_intent(ctx: &StrategyContext, intent: OrderIntent) -> Result<()> {
+    match intent {
+        OrderIntent::Create {
+            client_order_id,
+            exchange_account_id,
+            currency_pair,
+            side,
+            price,
+            amount,
+        } => {
+            let _ = ctx
+                .create_order_with_id(
+                    client_order_id,
+                    exchange_account_id,
+                    currency_pair,
+                    side,
+                    amount,
+                    UserOrder::limit(price),
+                )
+                .await?;
+        }
+        OrderIntent::Cancel { client_order_id } => {
+            let order = ctx
+                .open_orders()
+                .into_iter()
+                .find(|x| x.client_order_id() == client_order_id)
+                .with_context(|| format!("Order {client_order_id} of strategy isn't open"))?;
+            ctx.cancel_order(&order).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn to_py_decimal(py: Python, value: Decimal) -> PyResult<PyObject> {
+    let decimal = py.import("decimal")?.getattr("Decimal")?;
+    Ok(decimal.call1((value.to_string(),))?.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_domain::market::CurrencyPair;
+    use mmb_domain::order::snapshot::OrderSide;
+    use rust_decimal_macros::dec;
+
+    const STRATEGY: &str = r#"
+class Quoter:
+    def __init__(self, engine):
+        self.engine = engine
+        engine.subscribe_order_book("Binance_0", "btc/usdt")
+
+    def on_order_book(self, top):
+        bid_price, _ = top["bid"]
+        self.engine.create_order(
+            top["exchange_account_id"], top["currency_pair"], "buy", bid_price - 1, "0.5")
+
+    def on_timer(self, now_millis):
+        self.engine.cancel_order("42")
+        raise RuntimeError("timer failed")
+"#;
+
+    fn market_account_id() -> MarketAccountId {
+        MarketAccountId::new(
+            "Binance_0".parse().expect("valid id"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    fn strategy() -> PythonStrategy {
+        PythonStrategy::from_code(STRATEGY, "quoter.py", "Quoter").expect("strategy is created")
+    }
+
+    #[test]
+    fn order_book_top_produces_orders() {
+        let intents = strategy()
+            .on_order_book_top(
+                market_account_id(),
+                Some((dec!(100.5), dec!(1))),
+                Some((dec!(101), dec!(2))),
+            )
+            .expect("callback is called");
+
+        assert_eq!(intents.len(), 1);
+        match &intents[0] {
+            OrderIntent::Create {
+                exchange_account_id,
+                currency_pair,
+                side,
+                price,
+                amount,
+                ..
+            } => {
+                assert_eq!(
+                    *exchange_account_id,
+                    market_account_id().exchange_account_id
+                );
+                assert_eq!(*currency_pair, market_account_id().currency_pair);
+                assert_eq!(*side, OrderSide::Buy);
+                assert_eq!(*price, dec!(99.5));
+                assert_eq!(*amount, dec!(0.5));
+            }
+            intent => panic!("Unexpected intent {intent:?}"),
+        }
+    }
+
+    #[test]
+    fn not_subscribed_market_is_skipped() {
+        let other_market = MarketAccountId::new(
+            "Binance_1".parse().expect("valid id"),
+            market_account_id().currency_pair,
+        );
+        let intents = strategy()
+            .on_order_book_top(other_market, Some((dec!(1), dec!(1))), None)
+            .expect("callback is skipped");
+        assert!(intents.is_empty());
+    }
+
+    #[test]
+    fn intents_of_failed_callback_are_discarded() {
+        let strategy = strategy();
+        let error = strategy.on_timer(1).expect_err("callback fails");
+        assert!(format!("{error:#}").contains("timer failed"), "{error:#}");
+
+        // strategy fails on order book without bids
+        let intents = strategy.on_order_book_top(market_account_id(), None, None);
+        assert!(intents.is_err(), "bid is required by strategy");
+        assert!(strategy.api.lock().intents.is_empty());
+    }
+}
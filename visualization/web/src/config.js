@@ -9,4 +9,6 @@ export default {
       ? "ws://localhost:53938"
       : window.location.origin.replace("http", "ws")
   }/hub/`,
+  // event stream of trading engine, see `event_stream` section of core settings
+  baseEventsURL: "ws://localhost:8090",
 };
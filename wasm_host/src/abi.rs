@@ -0,0 +1,113 @@
+//! Stable ABI between the engine and WASM strategy plugins.
+//!
+//! Plugin is a WASM module without imports which exports:
+//! * `memory` - linear memory used to pass messages;
+//! * `mmb_abi_version() -> i32` - version of ABI implemented by plugin, should be equal to
+//!   [`ABI_VERSION`];
+//! * `mmb_alloc(len: i32) -> i32` - allocates buffer of `len` bytes for input message and
+//!   returns pointer to it. Buffer is owned by plugin and can be reused by next calls;
+//! * `mmb_on_input(ptr: i32, len: i32) -> i64` - handles JSON encoded [`PluginInput`] written
+//!   to buffer returned by `mmb_alloc`. Returns `(ptr << 32) | len` of JSON encoded list of
+//!   [`OrderIntent`] in plugin memory or 0 if there are no intents.
+
+use mmb_domain::market::{CurrencyPair, ExchangeAccountId};
+use mmb_domain::order::snapshot::{Amount, ClientOrderId, OrderSide, OrderStatus, Price};
+use serde::{Deserialize, Serialize};
+
+pub const ABI_VERSION: i32 = 1;
+
+pub(crate) const ABI_VERSION_EXPORT: &str = "mmb_abi_version";
+pub(crate) const ALLOC_EXPORT: &str = "mmb_alloc";
+pub(crate) const ON_INPUT_EXPORT: &str = "mmb_on_input";
+pub(crate) const MEMORY_EXPORT: &str = "memory";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceLevel {
+    pub price: Price,
+    pub amount: Amount,
+}
+
+/// Message passed from the engine to plugin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PluginInput {
+    /// Top of order book after its update
+    OrderBookTop {
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        bid: Option<PriceLevel>,
+        ask: Option<PriceLevel>,
+    },
+    /// State of order created by plugin after its change
+    OrderUpdate {
+        client_order_id: ClientOrderId,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        filled_amount: Amount,
+        status: OrderStatus,
+    },
+    /// Periodical call with current time in milliseconds since Unix epoch
+    Timer { now_millis: i64 },
+}
+
+/// Action requested by plugin in response to input message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum OrderIntent {
+    /// Create limit order
+    Create {
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+    },
+    /// Cancel not finished order created by plugin
+    Cancel { client_order_id: ClientOrderId },
+}
+
+/// Splits value returned by `mmb_on_input` into pointer and length of output message
+pub(crate) fn unpack_output(packed: i64) -> (u32, u32) {
+    let packed = packed as u64;
+    ((packed >> 32) as u32, packed as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn order_intents_from_json() {
+        let json = r#"[
+            {"type":"Create","exchange_account_id":"Binance_0","currency_pair":"btc/usdt","side":"Buy","price":"100.5","amount":"0.1"},
+            {"type":"Cancel","client_order_id":"42"}
+        ]"#;
+
+        let intents: Vec<OrderIntent> = serde_json::from_str(json).expect("valid intents");
+        assert_eq!(
+            intents,
+            vec![
+                OrderIntent::Create {
+                    exchange_account_id: "Binance_0".parse().expect("valid id"),
+                    currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+                    side: OrderSide::Buy,
+                    price: dec!(100.5),
+                    amount: dec!(0.1),
+                },
+                OrderIntent::Cancel {
+                    client_order_id: "42".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unpack_output_pointer_and_length() {
+        assert_eq!(unpack_output((1024 << 32) | 17), (1024, 17));
+        assert_eq!(unpack_output(0), (0, 0));
+    }
+}
@@ -0,0 +1,25 @@
+#![deny(
+    non_ascii_idents,
+    non_shorthand_field_patterns,
+    no_mangle_generic_items,
+    overflowing_literals,
+    path_statements,
+    unused_allocation,
+    unused_comparisons,
+    unused_parens,
+    while_true,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_must_use,
+    clippy::unwrap_used
+)]
+
+//! Host of strategy plugins compiled to WASM. Plugins are sandboxed and communicate with the
+//! engine through stable ABI described in [`abi`] module: market data and order updates are
+//! passed in, order intents are returned back.
+
+pub mod abi;
+pub mod plugin;
+pub mod strategy;
@@ -0,0 +1,214 @@
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use wasmi::{
+    Config, Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc,
+};
+
+use crate::abi::{
+    unpack_output, OrderIntent, PluginInput, ABI_VERSION, ABI_VERSION_EXPORT, ALLOC_EXPORT,
+    MEMORY_EXPORT, ON_INPUT_EXPORT,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxSettings {
+    /// Max count of WASM instructions executed by plugin for one input message
+    pub fuel_per_call: u64,
+    /// Max size of plugin linear memory in bytes
+    pub max_memory_bytes: usize,
+    /// Max size of output message of plugin in bytes
+    pub max_output_bytes: u32,
+}
+
+impl Default for SandboxSettings {
+    fn default() -> Self {
+        SandboxSettings {
+            fuel_per_call: 10_000_000,
+            max_memory_bytes: 16 * 1024 * 1024,
+            max_output_bytes: 1024 * 1024,
+        }
+    }
+}
+
+struct HostState {
+    limits: StoreLimits,
+}
+
+/// Instance of plugin module isolated in its own store. Plugin can't import any host
+/// functions, its memory is limited and execution of every call is limited by fuel
+pub struct WasmPlugin {
+    settings: SandboxSettings,
+    store: Store<HostState>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    on_input: TypedFunc<(i32, i32), i64>,
+}
+
+impl WasmPlugin {
+    pub fn load(wasm: &[u8], settings: SandboxSettings) -> Result<Self> {
+        let mut config = Config::default();
+        let _ = config.consume_fuel(true);
+        let engine = Engine::new(&config);
+
+        let module = Module::new(&engine, wasm).context("compiling plugin module")?;
+        ensure!(
+            module.imports().len() == 0,
+            "Plugin module shouldn't import anything"
+        );
+
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(settings.max_memory_bytes)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&engine, HostState { limits });
+        store.limiter(|state| &mut state.limits);
+
+        let instance = Linker::<HostState>::new(&engine)
+            .instantiate(&mut store, &module)
+            .context("instantiating plugin module")?
+            .ensure_no_start(&mut store)
+            .context("plugin module shouldn't have start function")?;
+
+        let memory = instance
+            .get_memory(&store, MEMORY_EXPORT)
+            .with_context(|| format!("Plugin module doesn't export '{MEMORY_EXPORT}'"))?;
+        let abi_version = instance
+            .get_typed_func::<(), i32>(&store, ABI_VERSION_EXPORT)
+            .with_context(|| format!("getting '{ABI_VERSION_EXPORT}' export"))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, ALLOC_EXPORT)
+            .with_context(|| format!("getting '{ALLOC_EXPORT}' export"))?;
+        let on_input = instance
+            .get_typed_func::<(i32, i32), i64>(&store, ON_INPUT_EXPORT)
+            .with_context(|| format!("getting '{ON_INPUT_EXPORT}' export"))?;
+
+        let mut plugin = WasmPlugin {
+            settings,
+            store,
+            memory,
+            alloc,
+            on_input,
+        };
+
+        plugin.refuel()?;
+        let version = abi_version
+            .call(&mut plugin.store, ())
+            .context("calling plugin ABI version")?;
+        ensure!(
+            version == ABI_VERSION,
+            "Plugin ABI version {version} isn't supported, expected {ABI_VERSION}"
+        );
+
+        Ok(plugin)
+    }
+
+    /// Passes input message to plugin and returns order intents produced by it
+    pub fn handle(&mut self, input: &PluginInput) -> Result<Vec<OrderIntent>> {
+        let input = serde_json::to_vec(input).context("serializing plugin input")?;
+        let len = i32::try_from(input.len()).context("plugin input is too long")?;
+
+        self.refuel()?;
+        let ptr = self
+            .alloc
+            .call(&mut self.store, len)
+            .context("allocating plugin input")?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, &input)
+            .map_err(|err| anyhow!("writing plugin input: {err}"))?;
+
+        let packed = self
+            .on_input
+            .call(&mut self.store, (ptr, len))
+            .context("handling plugin input")?;
+        let (ptr, len) = unpack_output(packed);
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        if len > self.settings.max_output_bytes {
+            bail!(
+                "Plugin output size {len} exceeds limit {}",
+                self.settings.max_output_bytes
+            );
+        }
+
+        let mut output = vec![0; len as usize];
+        self.memory
+            .read(&self.store, ptr as usize, &mut output)
+            .map_err(|err| anyhow!("reading plugin output: {err}"))?;
+        serde_json::from_slice(&output).context("parsing plugin output")
+    }
+
+    /// Resets remaining fuel to limit of one call, so unused fuel isn't accumulated
+    fn refuel(&mut self) -> Result<()> {
+        let remaining = self
+            .store
+            .consume_fuel(0)
+            .map_err(|err| anyhow!("getting plugin fuel: {err}"))?;
+        self.store
+            .add_fuel(self.settings.fuel_per_call.saturating_sub(remaining))
+            .map_err(|err| anyhow!("adding plugin fuel: {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CANCEL_INTENT: &str = r#"[{"type":"Cancel","client_order_id":"1"}]"#;
+
+    fn plugin_wat(abi_version: i32, on_input_body: &str) -> Vec<u8> {
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 1024) "{data}")
+                (func (export "mmb_abi_version") (result i32) i32.const {abi_version})
+                (func (export "mmb_alloc") (param i32) (result i32) i32.const 0)
+                (func (export "mmb_on_input") (param i32 i32) (result i64) {on_input_body}))"#,
+            data = CANCEL_INTENT.replace('"', "\\\""),
+        );
+        wat::parse_str(wat).expect("valid wat")
+    }
+
+    fn timer() -> PluginInput {
+        PluginInput::Timer { now_millis: 1 }
+    }
+
+    #[test]
+    fn plugin_returns_intents() {
+        let return_cancel = format!(
+            "i64.const 1024 i64.const 32 i64.shl i64.const {} i64.or",
+            CANCEL_INTENT.len()
+        );
+        let mut plugin = WasmPlugin::load(&plugin_wat(1, &return_cancel), Default::default())
+            .expect("plugin is loaded");
+
+        let intents = plugin.handle(&timer()).expect("input is handled");
+        assert_eq!(
+            intents,
+            vec![OrderIntent::Cancel {
+                client_order_id: "1".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn plugin_with_other_abi_version_is_rejected() {
+        let error = WasmPlugin::load(&plugin_wat(2, "i64.const 0"), Default::default())
+            .err()
+            .expect("plugin isn't loaded");
+        assert!(error.to_string().contains("ABI version 2"), "{error:#}");
+    }
+
+    #[test]
+    fn endless_plugin_is_stopped_by_fuel() {
+        let settings = SandboxSettings {
+            fuel_per_call: 10_000,
+            ..Default::default()
+        };
+        let mut plugin = WasmPlugin::load(&plugin_wat(1, "(loop br 0) i64.const 0"), settings)
+            .expect("plugin is loaded");
+
+        assert!(plugin.handle(&timer()).is_err());
+        // fuel is restored for the next call
+        assert!(plugin.handle(&timer()).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use mmb_core::exchanges::general::exchange::PriceLevel as ExchangePriceLevel;
+use mmb_core::strategy_registry::{Strategy, StrategyContext};
+use mmb_domain::events::ExchangeEvent;
+use mmb_domain::market::MarketAccountId;
+use mmb_domain::order::snapshot::UserOrder;
+use mmb_utils::time::get_current_milliseconds;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::abi::{OrderIntent, PluginInput, PriceLevel};
+use crate::plugin::{SandboxSettings, WasmPlugin};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmStrategySettings {
+    /// Path to compiled plugin module. Plugin is reloaded when the file is changed
+    pub path: PathBuf,
+    /// Markets which order book tops are passed to plugin. All markets if empty
+    #[serde(default)]
+    pub markets: Vec<MarketAccountId>,
+    #[serde(default)]
+    pub sandbox: SandboxSettings,
+}
+
+struct LoadedPlugin {
+    plugin: WasmPlugin,
+    modified: SystemTime,
+}
+
+/// Strategy which trading logic is implemented by WASM plugin. Plugin module is hot-swapped
+/// on timer when its file is changed, failed reload keeps previous version of plugin running
+pub struct WasmStrategy {
+    settings: WasmStrategySettings,
+    loaded: Mutex<LoadedPlugin>,
+}
+
+impl WasmStrategy {
+    pub fn load(settings: WasmStrategySettings) -> Result<Self> {
+        let loaded = load_plugin(&settings.path, &settings.sandbox)?;
+        log::info!("WASM plugin {} is loaded", settings.path.display());
+
+        Ok(WasmStrategy {
+            settings,
+            loaded: Mutex::new(loaded),
+        })
+    }
+
+    /// Replaces running plugin if its file was changed since the last load
+    pub fn reload_if_changed(&self) -> Result<bool> {
+        let modified = modified_time(&self.settings.path)?;
+        if modified == self.loaded.lock().modified {
+            return Ok(false);
+        }
+
+        let loaded = load_plugin(&self.settings.path, &self.settings.sandbox)?;
+        *self.loaded.lock() = loaded;
+        log::info!("WASM plugin {} is reloaded", self.settings.path.display());
+        Ok(true)
+    }
+
+    fn input_by_event(&self, ctx: &StrategyContext, event: &ExchangeEvent) -> Option<PluginInput> {
+        match event {
+            ExchangeEvent::OrderBookEvent(event) => {
+                let market_account_id =
+                    MarketAccountId::new(event.exchange_account_id, event.currency_pair);
+                if !self.settings.markets.is_empty()
+                    && !self.settings.markets.contains(&market_account_id)
+                {
+                    return None;
+                }
+
+                let exchange = ctx
+                    .engine_context()
+                    .exchanges
+                    .get(&event.exchange_account_id)?;
+                let top = exchange.order_book_top.get(&event.currency_pair)?;
+                let level = |x: &ExchangePriceLevel| PriceLevel {
+                    price: x.price,
+                    amount: x.amount,
+                };
+
+                Some(PluginInput::OrderBookTop {
+                    exchange_account_id: event.exchange_account_id,
+                    currency_pair: event.currency_pair,
+                    bid: top.bid.as_ref().map(level),
+                    ask: top.ask.as_ref().map(level),
+                })
+            }
+            ExchangeEvent::OrderEvent(event) => {
+                let order = &event.order;
+                Some(PluginInput::OrderUpdate {
+                    client_order_id: order.client_order_id(),
+                    exchange_account_id: order.exchange_account_id(),
+                    currency_pair: order.currency_pair(),
+                    side: order.side(),
+                    price: order.price(),
+                    amount: order.amount(),
+                    filled_amount: order.filled_amount(),
+                    status: order.status(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    async fn handle_input(&self, ctx: &StrategyContext, input: &PluginInput) -> Result<()> {
+        // plugin is called synchronously, so lock isn't held while intents are executed
+        let intents = self.loaded.lock().plugin.handle(input)?;
+        for intent in intents {
+            if let Err(err) = execute_intent(ctx, intent).await {
+                log::error!(
+                    "Failed to execute intent of WASM plugin {}: {err:?}",
+                    self.settings.path.display()
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Strategy for WasmStrategy {
+    async fn on_event(&self, ctx: &StrategyContext, event: &ExchangeEvent) -> Result<()> {
+        match self.input_by_event(ctx, event) {
+            Some(input) => self.handle_input(ctx, &input).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn on_timer(&self, ctx: &StrategyContext) -> Result<()> {
+        if let Err(err) = self.reload_if_changed() {
+            log::error!(
+                "Failed to reload WASM plugin {}, previous version is kept: {err:?}",
+                self.settings.path.display()
+            );
+        }
+
+        let input = PluginInput::Timer {
+            now_millis: get_current_milliseconds(),
+        };
+        self.handle_input(ctx, &input).await
+    }
+}
+
+async fn execute_intent(ctx: &StrategyContext, intent: OrderIntent) -> Result<()> {
+    match intent {
+        OrderIntent::Create {
+            exchange_account_id,
+            currency_pair,
+            side,
+            price,
+            amount,
+        } => {
+            let _ = ctx
+                .create_order(
+                    exchange_account_id,
+                    currency_pair,
+                    side,
+                    amount,
+                    UserOrder::limit(price),
+                )
+                .await?;
+        }
+        OrderIntent::Cancel { client_order_id } => {
+            let order = ctx
+                .open_orders()
+                .into_iter()
+                .find(|x| x.client_order_id() == client_order_id)
+                .with_context(|| format!("Order {client_order_id} of plugin isn't open"))?;
+            ctx.cancel_order(&order).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn load_plugin(path: &Path, sandbox: &SandboxSettings) -> Result<LoadedPlugin> {
+    let modified = modified_time(path)?;
+    let wasm = fs::read(path).with_context(|| format!("reading WASM plugin {}", path.display()))?;
+    let plugin = WasmPlugin::load(&wasm, sandbox.clone())
+        .with_context(|| format!("loading WASM plugin {}", path.display()))?;
+
+    Ok(LoadedPlugin { plugin, modified })
+}
+
+fn modified_time(path: &Path) -> Result<SystemTime> {
+    fs::metadata(path)
+        .and_then(|x| x.modified())
+        .with_context(|| format!("getting modification time of {}", path.display()))
+}